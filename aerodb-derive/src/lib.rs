@@ -0,0 +1,170 @@
+//! `#[derive(AeroDocument)]` for AeroDB's embedded API
+//!
+//! Maps a struct with named fields to a schema-validated document:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize, AeroDocument)]
+//! #[aerodb(schema = "users", version = "v1")]
+//! struct User {
+//!     #[serde(rename = "_id")]
+//!     id: String,
+//!     #[aerodb(unique)]
+//!     email: String,
+//!     age: Option<i64>,
+//! }
+//! ```
+//!
+//! Field names follow `#[serde(rename = "...")]`, so the schema matches the
+//! serialized document. Each field's definition comes from its type's
+//! `FieldValue` impl; `#[aerodb(unique)]` marks it unique. A struct without
+//! an `_id` field is rejected at compile time.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitStr};
+
+#[proc_macro_derive(AeroDocument, attributes(aerodb))]
+pub fn derive_aero_document(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+struct Field {
+    name: String,
+    ty: syn::Type,
+    unique: bool,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let (schema_id, schema_version) = container_attrs(input)?;
+    let fields = fields(input)?;
+    if !fields.iter().any(|f| f.name == "_id") {
+        return Err(Error::new(
+            Span::call_site(),
+            "AeroDocument requires an `_id` field (use #[serde(rename = \"_id\")])",
+        ));
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let names = fields.iter().map(|f| &f.name);
+    let defs = fields.iter().map(|f| {
+        let name = &f.name;
+        let ty = &f.ty;
+        let def = quote! {
+            <#ty as ::aerodb::embedded::FieldValue>::field_def()
+        };
+        let def = if f.unique {
+            quote! { #def.unique() }
+        } else {
+            def
+        };
+        quote! { fields.insert(#name.to_string(), #def); }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::aerodb::embedded::AeroDocument for #ident #ty_generics #where_clause {
+            const SCHEMA_ID: &'static str = #schema_id;
+            const SCHEMA_VERSION: &'static str = #schema_version;
+            const FIELDS: &'static [&'static str] = &[#(#names),*];
+
+            fn field_defs() -> ::std::collections::HashMap<String, ::aerodb::schema::FieldDef> {
+                let mut fields = ::std::collections::HashMap::new();
+                #(#defs)*
+                fields
+            }
+        }
+    })
+}
+
+/// `#[aerodb(schema = "...", version = "...")]`
+fn container_attrs(input: &DeriveInput) -> syn::Result<(String, String)> {
+    let mut schema = None;
+    let mut version = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("aerodb")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("schema") {
+                schema = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("version") {
+                version = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else {
+                return Err(meta.error("expected `schema` or `version`"));
+            }
+            Ok(())
+        })?;
+    }
+    match (schema, version) {
+        (Some(schema), Some(version)) => Ok((schema, version)),
+        _ => Err(Error::new(
+            Span::call_site(),
+            "AeroDocument requires #[aerodb(schema = \"...\", version = \"...\")]",
+        )),
+    }
+}
+
+fn fields(input: &DeriveInput) -> syn::Result<Vec<Field>> {
+    let named = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(named) => &named.named,
+            _ => {
+                return Err(Error::new(
+                    Span::call_site(),
+                    "AeroDocument requires a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                Span::call_site(),
+                "AeroDocument can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut fields = Vec::new();
+    for field in named {
+        let mut name = field
+            .ident
+            .as_ref()
+            .map(|i| i.to_string())
+            .unwrap_or_default();
+        let mut unique = false;
+        let mut skip = false;
+        for attr in &field.attrs {
+            if attr.path().is_ident("aerodb") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("unique") {
+                        unique = true;
+                        Ok(())
+                    } else {
+                        Err(meta.error("expected `unique`"))
+                    }
+                })?;
+            } else if attr.path().is_ident("serde") {
+                // Only the attributes that change the document's fields;
+                // serde itself validates the rest
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("rename") {
+                        name = meta.value()?.parse::<LitStr>()?.value();
+                    } else if meta.path.is_ident("skip") {
+                        skip = true;
+                    } else if meta.input.peek(syn::Token![=]) {
+                        meta.value()?.parse::<syn::Expr>()?;
+                    }
+                    Ok(())
+                })?;
+            }
+        }
+        if !skip {
+            fields.push(Field {
+                name,
+                ty: field.ty.clone(),
+                unique,
+            });
+        }
+    }
+    Ok(fields)
+}
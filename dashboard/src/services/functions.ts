@@ -89,6 +89,7 @@ export const functionsService = {
         const params = new URLSearchParams()
         if (options?.limit) params.append('limit', options.limit.toString())
         if (options?.offset) params.append('offset', options.offset.toString())
+        if (options?.since) params.append('since', options.since)
         if (options?.level) params.append('level', options.level)
         if (options?.since) params.append('since', options.since)
 
@@ -104,6 +105,7 @@ export const functionsService = {
         options?: {
             limit?: number
             offset?: number
+            since?: string
         }
     ): Promise<{
         invocations: FunctionInvocation[]
@@ -121,7 +123,7 @@ export const functionsService = {
      * Get function statistics
      */
     async getFunctionStats(functionId: string): Promise<{
-        total_invocations: number
+        invocation_count: number
         success_count: number
         error_count: number
         avg_duration_ms: number
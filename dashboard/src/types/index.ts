@@ -130,12 +130,15 @@ export interface FunctionLog {
 export interface FunctionInvocation {
     id: string
     function_id: string
-    payload: Record<string, unknown>
+    payload?: Record<string, unknown>
     result?: unknown
     error?: string
     duration_ms: number
     status: 'success' | 'error' | 'timeout'
     invoked_at: string
+    fuel_consumed?: number
+    logs?: string[]
+    logs_truncated?: boolean
 }
 
 // ========== Backup & Restore Types ==========
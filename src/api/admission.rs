@@ -0,0 +1,329 @@
+//! Priority-class admission and load shedding
+//!
+//! Every request passes the admission queue before it waits for the global
+//! execution lock. Queue depth counts requests admitted and not yet
+//! finished (waiting for the lock or executing).
+//!
+//! Under overload the lowest priority classes are shed first:
+//! - `System` is never shed
+//! - `Operator`, `Authenticated` and `Anonymous` are shed once queue depth
+//!   reaches their threshold
+//!
+//! Shedding is deterministic: the decision depends only on the class and
+//! the queue depth at admission. A shed request is rejected with
+//! `AERO_OVERLOADED` before any work is done, and counted per class.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use super::errors::{ApiError, ApiResult};
+use crate::observability::MetricsRegistry;
+
+/// Request priority class, highest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PriorityClass {
+    /// Internal work (replication, checkpoint, recovery tooling)
+    System,
+    /// Operator and control-plane requests
+    Operator,
+    /// Authenticated clients
+    Authenticated,
+    /// Anonymous clients
+    Anonymous,
+}
+
+impl PriorityClass {
+    /// All classes, highest priority first
+    pub const ALL: [PriorityClass; 4] = [
+        PriorityClass::System,
+        PriorityClass::Operator,
+        PriorityClass::Authenticated,
+        PriorityClass::Anonymous,
+    ];
+
+    /// Returns the class name
+    pub fn name(self) -> &'static str {
+        match self {
+            PriorityClass::System => "system",
+            PriorityClass::Operator => "operator",
+            PriorityClass::Authenticated => "authenticated",
+            PriorityClass::Anonymous => "anonymous",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl fmt::Display for PriorityClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Queue depth at which each class starts being shed
+///
+/// `System` has no threshold and is never shed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SheddingThresholds {
+    /// Depth at which operator requests are shed
+    pub operator: usize,
+    /// Depth at which authenticated requests are shed
+    pub authenticated: usize,
+    /// Depth at which anonymous requests are shed
+    pub anonymous: usize,
+}
+
+impl SheddingThresholds {
+    /// Returns the shedding threshold for a class (`None` = never shed)
+    pub fn threshold(&self, class: PriorityClass) -> Option<usize> {
+        match class {
+            PriorityClass::System => None,
+            PriorityClass::Operator => Some(self.operator),
+            PriorityClass::Authenticated => Some(self.authenticated),
+            PriorityClass::Anonymous => Some(self.anonymous),
+        }
+    }
+
+    /// Validate that lower classes are never admitted past higher ones.
+    ///
+    /// # Errors
+    ///
+    /// `AERO_INVALID_REQUEST` if thresholds are zero or not ordered
+    /// `anonymous <= authenticated <= operator`.
+    pub fn validate(&self) -> ApiResult<()> {
+        if self.anonymous == 0 {
+            return Err(ApiError::invalid_request(
+                "Shedding thresholds must be greater than zero",
+            ));
+        }
+        if self.anonymous > self.authenticated || self.authenticated > self.operator {
+            return Err(ApiError::invalid_request(format!(
+                "Shedding thresholds must satisfy anonymous ({}) <= authenticated ({}) <= operator ({})",
+                self.anonymous, self.authenticated, self.operator
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Default for SheddingThresholds {
+    fn default() -> Self {
+        Self {
+            operator: 256,
+            authenticated: 128,
+            anonymous: 64,
+        }
+    }
+}
+
+/// Per-class admission counters
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SheddingStats {
+    /// Current queue depth
+    pub depth: usize,
+    /// Requests admitted, indexed like `PriorityClass::ALL`
+    pub admitted: [u64; 4],
+    /// Requests shed, indexed like `PriorityClass::ALL`
+    pub shed: [u64; 4],
+}
+
+impl SheddingStats {
+    /// Requests admitted for a class
+    pub fn admitted(&self, class: PriorityClass) -> u64 {
+        self.admitted[class.index()]
+    }
+
+    /// Requests shed for a class
+    pub fn shed(&self, class: PriorityClass) -> u64 {
+        self.shed[class.index()]
+    }
+}
+
+/// Admission queue shared by all request entry points
+#[derive(Debug)]
+pub struct AdmissionQueue {
+    thresholds: SheddingThresholds,
+    depth: AtomicUsize,
+    admitted: [AtomicU64; 4],
+    shed: [AtomicU64; 4],
+    metrics: Option<Arc<MetricsRegistry>>,
+}
+
+impl AdmissionQueue {
+    /// Create a queue with the given thresholds.
+    ///
+    /// # Errors
+    ///
+    /// `AERO_INVALID_REQUEST` if the thresholds are invalid.
+    pub fn new(thresholds: SheddingThresholds) -> ApiResult<Self> {
+        thresholds.validate()?;
+        Ok(Self {
+            thresholds,
+            depth: AtomicUsize::new(0),
+            admitted: Default::default(),
+            shed: Default::default(),
+            metrics: None,
+        })
+    }
+
+    /// Create a shareable queue with default thresholds.
+    pub fn shared() -> Arc<Self> {
+        Arc::new(Self::new(SheddingThresholds::default()).expect("Default thresholds are valid"))
+    }
+
+    /// Also count shedding decisions in a metrics registry.
+    pub fn with_metrics(mut self, metrics: Arc<MetricsRegistry>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Returns the configured thresholds.
+    pub fn thresholds(&self) -> SheddingThresholds {
+        self.thresholds
+    }
+
+    /// Current queue depth.
+    pub fn depth(&self) -> usize {
+        self.depth.load(Ordering::SeqCst)
+    }
+
+    /// Admit a request of the given class, returning a slot held until the
+    /// request finishes.
+    ///
+    /// # Errors
+    ///
+    /// `AERO_OVERLOADED` if queue depth has reached the class threshold.
+    pub fn admit(&self, class: PriorityClass) -> ApiResult<QueueSlot<'_>> {
+        let threshold = self.thresholds.threshold(class);
+        let admitted =
+            self.depth.fetch_update(
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+                |depth| match threshold {
+                    Some(limit) if depth >= limit => None,
+                    _ => Some(depth + 1),
+                },
+            );
+
+        match admitted {
+            Ok(_) => {
+                self.admitted[class.index()].fetch_add(1, Ordering::Relaxed);
+                Ok(QueueSlot { queue: self })
+            }
+            Err(depth) => {
+                self.shed[class.index()].fetch_add(1, Ordering::Relaxed);
+                if let Some(metrics) = &self.metrics {
+                    metrics.increment_requests_shed();
+                }
+                Err(ApiError::overloaded(format!(
+                    "Request shed: {} queue depth {} reached threshold {}",
+                    class,
+                    depth,
+                    threshold.unwrap_or(depth)
+                )))
+            }
+        }
+    }
+
+    /// Snapshot the admission counters.
+    pub fn stats(&self) -> SheddingStats {
+        let mut stats = SheddingStats {
+            depth: self.depth(),
+            ..Default::default()
+        };
+        for class in PriorityClass::ALL {
+            stats.admitted[class.index()] = self.admitted[class.index()].load(Ordering::Relaxed);
+            stats.shed[class.index()] = self.shed[class.index()].load(Ordering::Relaxed);
+        }
+        stats
+    }
+}
+
+/// Admission queue slot, released when dropped
+#[derive(Debug)]
+pub struct QueueSlot<'a> {
+    queue: &'a AdmissionQueue,
+}
+
+impl Drop for QueueSlot<'_> {
+    fn drop(&mut self) {
+        self.queue.depth.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small_queue() -> AdmissionQueue {
+        AdmissionQueue::new(SheddingThresholds {
+            operator: 3,
+            authenticated: 2,
+            anonymous: 1,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_lower_classes_shed_first() {
+        let queue = small_queue();
+
+        let _a = queue.admit(PriorityClass::Anonymous).unwrap();
+        let err = queue.admit(PriorityClass::Anonymous).unwrap_err();
+        assert_eq!(err.code(), "AERO_OVERLOADED");
+        assert_eq!(err.http_status(), 503);
+
+        let _b = queue.admit(PriorityClass::Authenticated).unwrap();
+        assert!(queue.admit(PriorityClass::Authenticated).is_err());
+
+        let _c = queue.admit(PriorityClass::Operator).unwrap();
+        assert!(queue.admit(PriorityClass::Operator).is_err());
+
+        // System is never shed
+        let _d = queue.admit(PriorityClass::System).unwrap();
+        assert_eq!(queue.depth(), 4);
+    }
+
+    #[test]
+    fn test_slot_release_reopens_admission() {
+        let queue = small_queue();
+        let slot = queue.admit(PriorityClass::Anonymous).unwrap();
+        assert!(queue.admit(PriorityClass::Anonymous).is_err());
+        drop(slot);
+        assert_eq!(queue.depth(), 0);
+        assert!(queue.admit(PriorityClass::Anonymous).is_ok());
+    }
+
+    #[test]
+    fn test_shedding_counted_per_class() {
+        let metrics = Arc::new(MetricsRegistry::new());
+        let queue = small_queue().with_metrics(Arc::clone(&metrics));
+
+        let _slot = queue.admit(PriorityClass::Authenticated).unwrap();
+        let _slot2 = queue.admit(PriorityClass::Authenticated).unwrap();
+        assert!(queue.admit(PriorityClass::Anonymous).is_err());
+        assert!(queue.admit(PriorityClass::Authenticated).is_err());
+        assert!(queue.admit(PriorityClass::Anonymous).is_err());
+
+        let stats = queue.stats();
+        assert_eq!(stats.admitted(PriorityClass::Authenticated), 2);
+        assert_eq!(stats.shed(PriorityClass::Anonymous), 2);
+        assert_eq!(stats.shed(PriorityClass::Authenticated), 1);
+        assert_eq!(stats.shed(PriorityClass::System), 0);
+        assert_eq!(metrics.snapshot().requests_shed, 3);
+    }
+
+    #[test]
+    fn test_thresholds_must_be_ordered() {
+        let thresholds = SheddingThresholds {
+            operator: 10,
+            authenticated: 20,
+            anonymous: 5,
+        };
+        assert!(AdmissionQueue::new(thresholds).is_err());
+        assert!(SheddingThresholds::default().validate().is_ok());
+    }
+}
@@ -0,0 +1,101 @@
+//! Committed document changes
+//!
+//! A `ChangeSink` given to `ApiHandler::with_change_sink` is told of each
+//! successful write's document changes once the write has committed. It is
+//! called under the handler's global lock, so in commit order, once per
+//! write, with the changes in the order the request made them. Rejected
+//! and failed writes report nothing.
+//!
+//! Documents are as the client sent them: encrypted fields are in the
+//! clear. Deletes carry no document.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::request::{Request, TxnOp};
+
+/// What a write did to a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// One committed change to one document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentChange {
+    pub collection: String,
+    pub kind: ChangeKind,
+    pub document_id: String,
+    /// The written document; `None` for deletes
+    pub document: Option<Value>,
+}
+
+/// Receives the document changes of committed writes
+pub trait ChangeSink: Send + Sync {
+    /// `changes` committed; the write has already been acknowledged to
+    /// storage, so a sink cannot fail it
+    fn committed(&self, changes: &[DocumentChange]);
+}
+
+/// Changes `request` makes in `collection` if it commits
+///
+/// `exists` tells whether a document id is stored, deciding what an upsert
+/// does. Non-document requests make none.
+pub(super) fn document_changes(
+    request: &Request,
+    collection: &str,
+    exists: impl Fn(&str) -> bool,
+) -> Vec<DocumentChange> {
+    let written = |kind, document: &Value| {
+        document
+            .get("_id")
+            .and_then(Value::as_str)
+            .map(|id| DocumentChange {
+                collection: collection.to_string(),
+                kind,
+                document_id: id.to_string(),
+                document: Some(document.clone()),
+            })
+    };
+    let deleted = |document_id: &str| DocumentChange {
+        collection: collection.to_string(),
+        kind: ChangeKind::Delete,
+        document_id: document_id.to_string(),
+        document: None,
+    };
+
+    match request {
+        Request::Insert(r) => written(ChangeKind::Insert, &r.document)
+            .into_iter()
+            .collect(),
+        Request::InsertMany(r) => r
+            .documents
+            .iter()
+            .filter_map(|document| written(ChangeKind::Insert, document))
+            .collect(),
+        Request::Update(r) => written(ChangeKind::Update, &r.document)
+            .into_iter()
+            .collect(),
+        Request::Upsert(r) => {
+            let kind = match r.document.get("_id").and_then(Value::as_str) {
+                Some(id) if exists(id) => ChangeKind::Update,
+                _ => ChangeKind::Insert,
+            };
+            written(kind, &r.document).into_iter().collect()
+        }
+        Request::Delete(r) => vec![deleted(&r.document_id)],
+        Request::Transaction(r) => r
+            .ops
+            .iter()
+            .filter_map(|op| match op {
+                TxnOp::Insert(r) => written(ChangeKind::Insert, &r.document),
+                TxnOp::Update(r) => written(ChangeKind::Update, &r.document),
+                TxnOp::Delete(r) => Some(deleted(&r.document_id)),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
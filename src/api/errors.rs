@@ -5,6 +5,8 @@
 
 use std::fmt;
 
+use crate::core::CoreError;
+
 /// API error severity
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Severity {
@@ -30,6 +32,24 @@ pub enum ApiErrorCode {
     AeroInvalidRequest,
     /// Unknown operation
     AeroUnknownOperation,
+    /// Operation rejected because the node is in maintenance mode
+    AeroMaintenanceMode,
+    /// Write rejected because the node is serving read-only (storage full)
+    AeroReadOnly,
+    /// Request shed at admission because the node is overloaded
+    AeroOverloaded,
+    /// Requested document does not exist
+    AeroNotFound,
+    /// Write precondition failed (document changed since it was read)
+    AeroConflict,
+    /// Insert of an `_id` that names a live document
+    AeroDuplicateId,
+    /// Write would duplicate a value of a unique field
+    AeroConstraintUnique,
+    /// Transaction aborted: a concurrent transaction committed first
+    AeroSerializationFailure,
+    /// Historical read older than the versions still retained
+    AeroSnapshotTooOld,
     /// Pass-through error from subsystem
     PassThrough,
 }
@@ -40,6 +60,15 @@ impl ApiErrorCode {
         match self {
             ApiErrorCode::AeroInvalidRequest => "AERO_INVALID_REQUEST",
             ApiErrorCode::AeroUnknownOperation => "AERO_UNKNOWN_OPERATION",
+            ApiErrorCode::AeroMaintenanceMode => "AERO_MAINTENANCE_MODE",
+            ApiErrorCode::AeroReadOnly => "AERO_READ_ONLY",
+            ApiErrorCode::AeroOverloaded => "AERO_OVERLOADED",
+            ApiErrorCode::AeroNotFound => "AERO_NOT_FOUND",
+            ApiErrorCode::AeroConflict => "AERO_CONFLICT",
+            ApiErrorCode::AeroDuplicateId => "AERO_DUPLICATE_ID",
+            ApiErrorCode::AeroConstraintUnique => "AERO_CONSTRAINT_UNIQUE",
+            ApiErrorCode::AeroSerializationFailure => "AERO_SERIALIZATION_FAILURE",
+            ApiErrorCode::AeroSnapshotTooOld => "AERO_SNAPSHOT_TOO_OLD",
             ApiErrorCode::PassThrough => "PASS_THROUGH",
         }
     }
@@ -49,6 +78,15 @@ impl ApiErrorCode {
         match self {
             ApiErrorCode::AeroInvalidRequest => Severity::Error,
             ApiErrorCode::AeroUnknownOperation => Severity::Error,
+            ApiErrorCode::AeroMaintenanceMode => Severity::Error,
+            ApiErrorCode::AeroReadOnly => Severity::Error,
+            ApiErrorCode::AeroOverloaded => Severity::Error,
+            ApiErrorCode::AeroNotFound => Severity::Error,
+            ApiErrorCode::AeroConflict => Severity::Error,
+            ApiErrorCode::AeroDuplicateId => Severity::Error,
+            ApiErrorCode::AeroConstraintUnique => Severity::Error,
+            ApiErrorCode::AeroSerializationFailure => Severity::Error,
+            ApiErrorCode::AeroSnapshotTooOld => Severity::Error,
             ApiErrorCode::PassThrough => Severity::Error, // Can be overridden
         }
     }
@@ -90,6 +128,96 @@ impl ApiError {
         }
     }
 
+    /// Create a maintenance mode rejection
+    pub fn maintenance_mode(reason: impl Into<String>) -> Self {
+        Self {
+            code: ApiErrorCode::AeroMaintenanceMode.code().to_string(),
+            message: reason.into(),
+            severity: Severity::Error,
+        }
+    }
+
+    /// Create a read-only mode rejection
+    pub fn read_only(reason: impl Into<String>) -> Self {
+        Self {
+            code: ApiErrorCode::AeroReadOnly.code().to_string(),
+            message: reason.into(),
+            severity: Severity::Error,
+        }
+    }
+
+    /// Create a load-shedding rejection
+    pub fn overloaded(reason: impl Into<String>) -> Self {
+        Self {
+            code: ApiErrorCode::AeroOverloaded.code().to_string(),
+            message: reason.into(),
+            severity: Severity::Error,
+        }
+    }
+
+    /// Create a document-not-found error
+    pub fn not_found(document_id: impl Into<String>) -> Self {
+        Self {
+            code: ApiErrorCode::AeroNotFound.code().to_string(),
+            message: format!("Document not found: {}", document_id.into()),
+            severity: Severity::Error,
+        }
+    }
+
+    /// Create a precondition conflict error
+    pub fn conflict(reason: impl Into<String>) -> Self {
+        Self {
+            code: ApiErrorCode::AeroConflict.code().to_string(),
+            message: reason.into(),
+            severity: Severity::Error,
+        }
+    }
+
+    /// Create a duplicate `_id` error for an insert over a live document
+    pub fn duplicate_id(document_id: impl Into<String>) -> Self {
+        Self {
+            code: ApiErrorCode::AeroDuplicateId.code().to_string(),
+            message: format!(
+                "Document {} already exists; use update or upsert to replace it",
+                document_id.into()
+            ),
+            severity: Severity::Error,
+        }
+    }
+
+    /// Create a serialization failure (write-write conflict) error
+    pub fn serialization_failure(err: crate::mvcc::CommitAuthorityError) -> Self {
+        Self {
+            code: ApiErrorCode::AeroSerializationFailure.code().to_string(),
+            message: err.to_string(),
+            severity: Severity::Error,
+        }
+    }
+
+    /// Create a snapshot-too-old error for a boundary below the GC horizon
+    pub fn snapshot_too_old(as_of: u64, horizon: u64) -> Self {
+        Self {
+            code: ApiErrorCode::AeroSnapshotTooOld.code().to_string(),
+            message: format!(
+                "Commit {} is older than the GC horizon {}; its versions may be collected",
+                as_of, horizon
+            ),
+            severity: Severity::Error,
+        }
+    }
+
+    /// Create a unique constraint violation error
+    pub fn unique_violation(violation: &crate::index::UniqueViolation) -> Self {
+        Self {
+            code: ApiErrorCode::AeroConstraintUnique.code().to_string(),
+            message: format!(
+                "Unique field '{}' value {} is already used by document {}",
+                violation.field, violation.value, violation.document_id
+            ),
+            severity: Severity::Error,
+        }
+    }
+
     /// Create from a schema error (pass-through)
     pub fn from_schema_error(err: crate::schema::SchemaError) -> Self {
         Self {
@@ -138,6 +266,15 @@ impl ApiError {
         }
     }
 
+    /// Create from a pipeline middleware error (pass-through)
+    pub fn from_core_error(err: CoreError) -> Self {
+        Self {
+            code: err.code().to_string(),
+            message: err.to_string(),
+            severity: Severity::Error,
+        }
+    }
+
     /// Create from a storage error (pass-through)
     pub fn from_storage_error(err: crate::storage::StorageError) -> Self {
         Self {
@@ -170,6 +307,43 @@ impl ApiError {
     pub fn is_fatal(&self) -> bool {
         matches!(self.severity, Severity::Fatal)
     }
+
+    /// Returns the HTTP status code for transports that need one
+    ///
+    /// Maintenance and load-shedding rejections are 503 so clients and load
+    /// balancers retry elsewhere; writes refused in read-only mode are 507;
+    /// requests refused by pipeline middleware
+    /// are 401 or 403; missing documents are 404; failed write
+    /// preconditions, duplicate ids, unique violations and serialization
+    /// failures are 409;
+    /// fatal errors are 500; everything else is a client error.
+    pub fn http_status(&self) -> u16 {
+        if self.code == ApiErrorCode::AeroMaintenanceMode.code()
+            || self.code == ApiErrorCode::AeroOverloaded.code()
+        {
+            503
+        } else if self.code == ApiErrorCode::AeroReadOnly.code() {
+            507
+        } else if self.code == CoreError::AuthRequired.code() {
+            401
+        } else if self.code == CoreError::access_denied("").code() {
+            403
+        } else if self.code == ApiErrorCode::AeroNotFound.code() {
+            404
+        } else if self.code == CoreError::rate_limited("").code() {
+            429
+        } else if self.code == ApiErrorCode::AeroConflict.code()
+            || self.code == ApiErrorCode::AeroDuplicateId.code()
+            || self.code == ApiErrorCode::AeroConstraintUnique.code()
+            || self.code == ApiErrorCode::AeroSerializationFailure.code()
+        {
+            409
+        } else if self.is_fatal() {
+            500
+        } else {
+            400
+        }
+    }
 }
 
 impl fmt::Display for ApiError {
@@ -200,4 +374,44 @@ mod tests {
         assert_eq!(err.code(), "AERO_UNKNOWN_OPERATION");
         assert!(err.message().contains("foo"));
     }
+
+    #[test]
+    fn test_not_found_error() {
+        let err = ApiError::not_found("user_1");
+        assert_eq!(err.code(), "AERO_NOT_FOUND");
+        assert_eq!(err.http_status(), 404);
+        assert!(err.message().contains("user_1"));
+    }
+
+    #[test]
+    fn test_conflict_error() {
+        let err = ApiError::conflict("changed");
+        assert_eq!(err.code(), "AERO_CONFLICT");
+        assert_eq!(err.http_status(), 409);
+    }
+
+    #[test]
+    fn test_unique_violation_error() {
+        let err = ApiError::unique_violation(&crate::index::UniqueViolation {
+            field: "email".into(),
+            value: serde_json::json!("a@x.com"),
+            document_id: "user_1".into(),
+        });
+        assert_eq!(err.code(), "AERO_CONSTRAINT_UNIQUE");
+        assert_eq!(err.http_status(), 409);
+        assert!(err.message().contains("\"a@x.com\""));
+        assert!(err.message().contains("user_1"));
+    }
+
+    #[test]
+    fn test_core_errors_pass_through() {
+        let err = ApiError::from_core_error(CoreError::AuthRequired);
+        assert_eq!(err.code(), "AUTH_REQUIRED");
+        assert_eq!(err.http_status(), 401);
+
+        let err = ApiError::from_core_error(CoreError::access_denied("not yours"));
+        assert_eq!(err.code(), "ACCESS_DENIED");
+        assert_eq!(err.http_status(), 403);
+        assert!(err.message().contains("not yours"));
+    }
 }
@@ -2,72 +2,337 @@
 //!
 //! Orchestrates all subsystems behind a single global mutex.
 //! Enforces strict request handling flow.
+//!
+//! Each request runs through the handler's core middleware as a
+//! `core::Operation`; the pipeline's final stage dispatches it here.
 
-use std::sync::Mutex;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use serde_json::{json, Value};
 
+use crate::core::{AuthContext, CoreError, Middleware, Next, RequestContext};
+use crate::executor::{Aggregator, ExecutionStats, PredicateFilter};
 use crate::index::{DocumentInfo, IndexManager};
+use crate::mvcc::{CommitAuthority, ReadView, Version, VersionChain};
+use crate::observability::{
+    AuditAction, AuditLog, AuditOutcome, AuditRecord, MetricsRegistry, SlowQuery, SlowQueryLog,
+    SlowQueryPlan, SlowQueryTiming,
+};
 use crate::planner::{
     FilterOp, IndexMetadata, Predicate, Query, QueryPlan, QueryPlanner, ScanType, SortSpec,
 };
-use crate::schema::{SchemaLoader, SchemaValidator};
-use crate::storage::{StoragePayload, StorageReader, StorageWriter};
-use crate::wal::{RecordType, WalPayload, WalWriter};
+use crate::schema::{Schema, SchemaDdl, SchemaError, SchemaLoader, SchemaValidator};
+use crate::storage::{
+    compute_checksum, decrypt_fields, encrypt_fields, DocumentRecord, FieldKeyring, KeyProvider,
+    StoragePayload, StorageReader, StorageWriter,
+};
+use crate::wal::{RecordType, WalError, WalPayload, WalWriter};
 
-use super::errors::{ApiError, ApiResult};
-use super::request::{DeleteRequest, InsertRequest, QueryRequest, Request, UpdateRequest};
+use super::admission::{AdmissionQueue, PriorityClass};
+use super::changes::{document_changes, ChangeSink};
+use super::errors::{ApiError, ApiErrorCode, ApiResult};
+use super::maintenance::MaintenanceGate;
+use super::pipeline::{block_on, operation_of, rls_predicates, RequestExecutor};
+use super::read_view::{storage_commit_id, storage_offset, ReadViewHandle, ReadViewRegistry};
+use super::request::{
+    AggregateRequest, CollectionRequest, CreateCollectionRequest, CreateIndexRequest,
+    DeleteRequest, DeprecateSchemaRequest, GetRequest, InsertManyRequest, InsertRequest,
+    ListSchemasRequest, QueryRequest, Request, RoutedRequest, TransactionRequest, TxnOp,
+    UpdateRequest, UpsertRequest,
+};
 use super::response::Response;
 
+/// Documents read per batch while backfilling a new index
+const BACKFILL_BATCH: usize = 256;
+
 /// Subsystem references for API handler
 pub struct Subsystems<'a> {
-    pub schema_loader: &'a SchemaLoader,
+    pub schema_loader: &'a mut SchemaLoader,
     pub wal_writer: &'a mut WalWriter,
     pub storage_writer: &'a mut StorageWriter,
     pub storage_reader: &'a mut StorageReader,
     pub index_manager: &'a mut IndexManager,
 }
 
+/// What a mutation's audit record names, captured before it runs
+struct AuditSubject {
+    command: &'static str,
+    resource: String,
+    document_ids: Vec<String>,
+}
+
+impl AuditSubject {
+    /// Audit record of the mutation run by `actor` with `result`
+    ///
+    /// Fatal errors are failures; every other error is a rejection.
+    fn record(self, actor: &AuthContext, result: &ApiResult<Value>) -> AuditRecord {
+        let record = match result {
+            Ok(_) => AuditRecord::new(AuditAction::CommandExecuted, AuditOutcome::Success),
+            Err(e) if e.is_fatal() => {
+                AuditRecord::new(AuditAction::CommandFailed, AuditOutcome::Failed)
+                    .with_error(format!("{}: {}", e.code(), e.message()))
+            }
+            Err(e) => AuditRecord::new(AuditAction::CommandRejected, AuditOutcome::Rejected)
+                .with_error(format!("{}: {}", e.code(), e.message())),
+        };
+        let authority = if actor.is_service_role {
+            "SERVICE_ROLE"
+        } else if actor.is_authenticated {
+            "AUTHENTICATED"
+        } else {
+            "ANONYMOUS"
+        };
+        let record = record
+            .with_command(self.command)
+            .with_authority(authority)
+            .with_resource(self.resource)
+            .with_documents(self.document_ids);
+        match actor.user_id() {
+            Some(user_id) => record.with_operator(user_id.to_string()),
+            None => record,
+        }
+    }
+}
+
 /// API Handler with global execution lock
 pub struct ApiHandler {
     /// Global mutex for serialized execution
     lock: Mutex<()>,
 
-    /// Collection name (single collection in Phase 0)
+    /// Default collection for requests that name none
     collection: String,
+
+    /// Maintenance admission gate (shared with the control plane)
+    maintenance: Arc<MaintenanceGate>,
+
+    /// Priority-class admission queue (load shedding)
+    admission: Arc<AdmissionQueue>,
+
+    /// Open read views for repeatable queries
+    read_views: ReadViewRegistry,
+
+    /// Registry counting queries and writes, if any
+    metrics: Option<Arc<MetricsRegistry>>,
+
+    /// Log of queries over the slow query threshold, if any
+    slow_queries: Option<Arc<SlowQueryLog>>,
+
+    /// Audit log every mutation is recorded in, if any
+    audit: Option<Arc<dyn AuditLog>>,
+
+    /// Keys for schema fields marked encrypted
+    field_keys: Arc<dyn KeyProvider>,
+
+    /// Sink told of committed document changes, if any
+    changes: Option<Arc<dyn ChangeSink>>,
+
+    /// Core middleware every request runs through, in order
+    middleware: Vec<Arc<dyn Middleware>>,
 }
 
 impl ApiHandler {
     /// Create a new API handler
     pub fn new(collection: impl Into<String>) -> Self {
+        Self::with_maintenance(collection, MaintenanceGate::shared())
+    }
+
+    /// Create a new API handler using a shared maintenance gate
+    pub fn with_maintenance(
+        collection: impl Into<String>,
+        maintenance: Arc<MaintenanceGate>,
+    ) -> Self {
         Self {
             lock: Mutex::new(()),
             collection: collection.into(),
+            maintenance,
+            admission: AdmissionQueue::shared(),
+            read_views: ReadViewRegistry::default(),
+            metrics: None,
+            slow_queries: None,
+            audit: None,
+            field_keys: Arc::new(FieldKeyring::new()),
+            changes: None,
+            middleware: Vec::new(),
+        }
+    }
+
+    /// Use a shared admission queue for load shedding
+    pub fn with_admission_queue(mut self, admission: Arc<AdmissionQueue>) -> Self {
+        self.admission = admission;
+        self
+    }
+
+    /// Count queries, query latency and writes in `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<MetricsRegistry>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Record queries over the slow query threshold in `slow_queries`
+    pub fn with_slow_query_log(mut self, slow_queries: Arc<SlowQueryLog>) -> Self {
+        self.slow_queries = Some(slow_queries);
+        self
+    }
+
+    /// Record every mutation, with its actor and outcome, in `audit`
+    pub fn with_audit_log(mut self, audit: Arc<dyn AuditLog>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Encrypt and decrypt fields marked encrypted with keys from `keys`
+    ///
+    /// Without keys, writes of documents with encrypted fields are rejected
+    /// with `AERO_ENCRYPTION_KEY_UNAVAILABLE`, as are reads of them.
+    pub fn with_field_keys(mut self, keys: Arc<dyn KeyProvider>) -> Self {
+        self.field_keys = keys;
+        self
+    }
+
+    /// Tell `sink` of each committed write's document changes
+    pub fn with_change_sink(mut self, sink: Arc<dyn ChangeSink>) -> Self {
+        self.changes = Some(sink);
+        self
+    }
+
+    /// Run every request through `middleware`, after any added before
+    ///
+    /// Middleware sees the request as a `core::Operation` and the actor
+    /// as the request context's auth; RLS filters it injects are enforced
+    /// on the documents the request reads or changes.
+    pub fn with_middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Expire read views left unused for `timeout`
+    pub fn with_read_view_timeout(mut self, timeout: Duration) -> Self {
+        self.read_views = ReadViewRegistry::with_timeout(timeout);
+        self
+    }
+
+    /// Returns the maintenance gate
+    pub fn maintenance(&self) -> &Arc<MaintenanceGate> {
+        &self.maintenance
+    }
+
+    /// Returns the admission queue
+    pub fn admission(&self) -> &Arc<AdmissionQueue> {
+        &self.admission
+    }
+
+    /// Map a failed WAL append to the error the request reports
+    ///
+    /// A full WAL device puts the node in read-only mode instead of
+    /// failing every later write; reads keep being served until the
+    /// control plane exits the mode.
+    fn wal_failure(&self, err: WalError) -> ApiError {
+        if err.is_storage_full() {
+            self.maintenance.enter_read_only(err.to_string())
+        } else {
+            ApiError::from_wal_error(err)
         }
     }
 
-    /// Handle a raw JSON request string
+    /// Open a read view over everything acknowledged so far
+    ///
+    /// Queries carrying the returned handle's id see this snapshot until
+    /// `end_read` is called, regardless of later writes.
+    pub fn begin_read(&self, subsystems: &Subsystems<'_>) -> ReadViewHandle {
+        let _guard = self.lock.lock().expect("Lock poisoned");
+        self.read_views
+            .open(subsystems.storage_writer.current_offset())
+    }
+
+    /// Record that versions older than `horizon` may have been collected
+    ///
+    /// Queries `as_of_commit_id` an older boundary are then rejected with
+    /// `AERO_SNAPSHOT_TOO_OLD`. The horizon never moves back and never
+    /// passes an open read view; returns the horizon in effect.
+    pub fn advance_gc_horizon(&self, horizon: u64) -> u64 {
+        self.read_views.advance_gc_horizon(horizon)
+    }
+
+    /// Oldest commit boundary `as_of_commit_id` queries may use
+    pub fn gc_horizon(&self) -> u64 {
+        self.read_views.gc_horizon()
+    }
+
+    /// End a read view. Returns `false` if it was not open.
+    pub fn end_read(&self, handle: ReadViewHandle) -> bool {
+        self.read_views.close(handle.id())
+    }
+
+    /// Number of open read views
+    pub fn open_read_views(&self) -> usize {
+        self.read_views.open_count()
+    }
+
+    /// Handle a raw JSON request string from an authenticated client
     ///
     /// Acquires global lock at entry, releases on return.
     pub fn handle(&self, json_request: &str, subsystems: &mut Subsystems<'_>) -> Response {
+        self.handle_with_priority(json_request, PriorityClass::Authenticated, subsystems)
+    }
+
+    /// Handle a raw JSON request string with an explicit priority class
+    ///
+    /// The request is admitted (or shed) before it waits for the global lock.
+    pub fn handle_with_priority(
+        &self,
+        json_request: &str,
+        priority: PriorityClass,
+        subsystems: &mut Subsystems<'_>,
+    ) -> Response {
+        self.handle_as(
+            json_request,
+            priority,
+            &AuthContext::anonymous(),
+            subsystems,
+        )
+    }
+
+    /// Handle a raw JSON request string on behalf of `actor`
+    ///
+    /// Every parsed mutation, including rejected ones, is recorded in the
+    /// audit log (if any) with `actor`, its outcome and the documents it
+    /// names.
+    pub fn handle_as(
+        &self,
+        json_request: &str,
+        priority: PriorityClass,
+        actor: &AuthContext,
+        subsystems: &mut Subsystems<'_>,
+    ) -> Response {
+        // Admission queue: shed lower classes first under overload
+        let _slot = match self.admission.admit(priority) {
+            Ok(slot) => slot,
+            Err(e) => return Response::error(&e),
+        };
+
         // Acquire global lock at request entry
         let _guard = self.lock.lock().expect("Lock poisoned");
 
         // Parse request
-        let request = match Request::parse(json_request) {
+        let routed = match Request::parse_routed(json_request) {
             Ok(r) => r,
             Err(e) => return Response::error(&e),
         };
 
-        // Dispatch to appropriate handler
-        let result = match request {
-            Request::Insert(r) => self.handle_insert(r, subsystems),
-            Request::Update(r) => self.handle_update(r, subsystems),
-            Request::Delete(r) => self.handle_delete(r, subsystems),
-            Request::Query(r) => self.handle_query(r, subsystems),
-            Request::Explain(r) => self.handle_explain(r, subsystems),
+        let audit = match &self.audit {
+            Some(log) if routed.request.is_write() => Some((log, self.audit_subject(&routed))),
+            _ => None,
         };
 
+        let result = self.execute(routed, actor, subsystems);
+
+        if let Some((log, subject)) = audit {
+            // Best-effort: an audit failure never fails the request
+            log.append(&subject.record(actor, &result)).ok();
+        }
+
         // Lock released when _guard drops
         match result {
             Ok(data) => Response::success(data),
@@ -75,20 +340,302 @@ impl ApiHandler {
         }
     }
 
+    /// Admit and route a parsed request, and run it through the pipeline
+    fn execute(
+        &self,
+        routed: RoutedRequest,
+        actor: &AuthContext,
+        subsystems: &mut Subsystems<'_>,
+    ) -> ApiResult<Value> {
+        let RoutedRequest {
+            collection,
+            request,
+        } = routed;
+
+        // Admission: rejected during maintenance, tracked for draining
+        let _in_flight = self.maintenance.admit(request.is_write())?;
+
+        // Route to the collection and its index partition
+        let collection = self.route(collection, &request, subsystems.schema_loader)?;
+        let index_manager = if collection == self.collection {
+            &mut *subsystems.index_manager
+        } else {
+            subsystems.index_manager.collection_mut(&collection)
+        };
+        let sys = &mut Subsystems {
+            schema_loader: &mut *subsystems.schema_loader,
+            wal_writer: &mut *subsystems.wal_writer,
+            storage_writer: &mut *subsystems.storage_writer,
+            storage_reader: &mut *subsystems.storage_reader,
+            index_manager,
+        };
+        let collection = collection.as_str();
+        let is_query = request.is_query();
+        let is_write = request.is_write();
+        let started = Instant::now();
+
+        // What a successful write must be remembered as, for conflict checks
+        let storage_end = sys.storage_writer.current_offset();
+        let written = request.written_document_ids();
+        let removed_collection = match &request {
+            Request::DropCollection(r) | Request::TruncateCollection(r) => {
+                Some(r.collection.clone())
+            }
+            _ => None,
+        };
+        let changes = match &self.changes {
+            Some(sink) if is_write => {
+                let index_manager = &*sys.index_manager;
+                let changes = document_changes(&request, collection, |id| {
+                    !index_manager.lookup_pk(id).is_empty()
+                });
+                Some((sink, changes))
+            }
+            _ => None,
+        };
+
+        // Run through the core pipeline; its final stage dispatches
+        let operation = operation_of(&request, collection);
+        let mut ctx = RequestContext::new(actor.clone());
+        let executor = RequestExecutor::new(|ctx: &RequestContext| {
+            self.dispatch(request, collection, ctx, sys)
+        });
+        let result = block_on(Next::new(&self.middleware, &executor).run(&operation, &mut ctx));
+        let result = executor.finish(result);
+
+        if is_write && result.is_ok() {
+            // Every record the write appended lies past the old storage end
+            let commit = storage_commit_id(storage_end);
+            self.read_views.record_writes(collection, &written, commit);
+            if let Some(removed) = removed_collection {
+                self.read_views.record_collection_write(&removed, commit);
+            }
+            if let Some((sink, changes)) = changes.filter(|(_, c)| !c.is_empty()) {
+                sink.committed(&changes);
+            }
+        }
+
+        if let Some(metrics) = &self.metrics {
+            match (is_query, result.is_ok()) {
+                (true, true) => {
+                    metrics.increment_queries_executed();
+                    metrics.observe_query_latency(started.elapsed());
+                }
+                (true, false) => metrics.increment_queries_rejected(),
+                (false, true) if is_write => metrics.increment_writes(),
+                _ => {}
+            }
+        }
+
+        result
+    }
+
+    /// Dispatch a request that passed the pipeline's middleware
+    ///
+    /// RLS filters in `ctx` are enforced here: queries gain them as
+    /// predicates (so, like any filter, they must name indexed fields), a
+    /// fetched document they exclude is not found, and a mutation of a
+    /// stored document they exclude is denied.
+    fn dispatch(
+        &self,
+        mut request: Request,
+        collection: &str,
+        ctx: &RequestContext,
+        sys: &mut Subsystems<'_>,
+    ) -> ApiResult<Value> {
+        let predicates = rls_predicates(&ctx.rls_filters)?;
+        if !predicates.is_empty() {
+            match &mut request {
+                Request::Query(r) | Request::Count(r) | Request::Explain(r) => {
+                    r.rls_predicates = predicates.clone();
+                }
+                Request::Aggregate(r) => r.query.rls_predicates = predicates.clone(),
+                Request::Update(UpdateRequest {
+                    schema_id,
+                    document,
+                    ..
+                })
+                | Request::Upsert(UpsertRequest {
+                    schema_id,
+                    document,
+                    ..
+                }) => {
+                    let id = document.get("_id").and_then(Value::as_str).unwrap_or("");
+                    self.guard_stored(schema_id, id, &predicates, sys)?;
+                }
+                Request::Delete(r) => {
+                    self.guard_stored(&r.schema_id, &r.document_id, &predicates, sys)?
+                }
+                _ => {}
+            }
+        }
+
+        match request {
+            Request::Insert(r) => self.handle_insert(r, collection, sys),
+            Request::InsertMany(r) => self.handle_insert_many(r, collection, sys),
+            Request::Update(r) => self.handle_update(r, collection, sys),
+            Request::Upsert(r) => self.handle_upsert(r, collection, sys),
+            Request::Delete(r) => self.handle_delete(r, collection, sys),
+            Request::Transaction(r) => self.handle_transaction(r, collection, sys),
+            Request::Get(r) => {
+                let document_id = r.document_id.clone();
+                self.handle_get(r, sys).and_then(|document| {
+                    if PredicateFilter::matches(&document, &predicates) {
+                        Ok(document)
+                    } else {
+                        Err(ApiError::not_found(document_id))
+                    }
+                })
+            }
+            Request::Query(r) => self.handle_query(r, collection, sys),
+            Request::Count(r) => self.handle_count(r, collection, sys),
+            Request::Aggregate(r) => self.handle_aggregate(r, collection, sys),
+            Request::Explain(r) => self.handle_explain(r, collection, sys),
+            Request::CreateSchema(r) => self.handle_create_schema(r, false, sys),
+            Request::AlterSchema(r) => self.handle_create_schema(r, true, sys),
+            Request::DeprecateSchema(r) => self.handle_deprecate_schema(r, sys),
+            Request::ListSchemas(r) => self.handle_list_schemas(r, sys),
+            Request::CreateCollection(r) => self.handle_create_collection(r, sys),
+            Request::ListCollections => Ok(self.list_collections(sys.schema_loader)),
+            Request::DropCollection(r) => self.handle_remove_collection(r, true, sys),
+            Request::TruncateCollection(r) => self.handle_remove_collection(r, false, sys),
+            Request::CreateIndex(r) => self.handle_create_index(r, sys),
+            Request::BeginRead => Ok(self.open_read_view(sys)),
+            Request::EndRead(id) => Ok(json!({"ended": self.read_views.close(id)})),
+        }
+    }
+
+    /// Deny a mutation of stored document `document_id` that `predicates`
+    /// exclude; a document not stored yet is left to the handler
+    fn guard_stored(
+        &self,
+        schema_id: &str,
+        document_id: &str,
+        predicates: &[Predicate],
+        sys: &mut Subsystems<'_>,
+    ) -> ApiResult<()> {
+        let get = GetRequest {
+            schema_id: schema_id.to_string(),
+            document_id: document_id.to_string(),
+        };
+        match self.handle_get(get, sys) {
+            Ok(stored) if !PredicateFilter::matches(&stored, predicates) => {
+                Err(ApiError::from_core_error(CoreError::access_denied(
+                    format!("Document {} is outside the rows RLS allows", document_id),
+                )))
+            }
+            Err(e) if e.code() != ApiErrorCode::AeroNotFound.code() => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Stored form of `document`: the schema's encrypted fields sealed
+    /// under the active field key
+    fn seal_document(
+        &self,
+        schema_loader: &SchemaLoader,
+        schema_id: &str,
+        schema_version: &str,
+        document: &Value,
+    ) -> ApiResult<Value> {
+        let mut stored = document.clone();
+        let paths = schema_loader
+            .get(schema_id, schema_version)
+            .map(Schema::encrypted_fields)
+            .unwrap_or_default();
+        if !paths.is_empty() {
+            encrypt_fields(&*self.field_keys, &mut stored, &paths)
+                .map_err(ApiError::from_storage_error)?;
+        }
+        Ok(stored)
+    }
+
+    /// A stored document with its encrypted fields opened
+    fn open_document(&self, mut document: Value) -> ApiResult<Value> {
+        decrypt_fields(&*self.field_keys, &mut document).map_err(ApiError::from_storage_error)?;
+        Ok(document)
+    }
+
+    /// What the audit record of a mutation names
+    fn audit_subject(&self, routed: &RoutedRequest) -> AuditSubject {
+        let request = &routed.request;
+        let resource = match request {
+            Request::CreateSchema(s) | Request::AlterSchema(s) => {
+                format!("schema:{}@{}", s.schema_id, s.schema_version)
+            }
+            Request::DeprecateSchema(r) => format!("schema:{}@{}", r.schema_id, r.schema_version),
+            Request::CreateCollection(r) => r.collection.clone(),
+            Request::DropCollection(r) | Request::TruncateCollection(r) => r.collection.clone(),
+            Request::CreateIndex(r) => format!("index:{}", r.field),
+            _ => routed
+                .collection
+                .clone()
+                .unwrap_or_else(|| self.collection.clone()),
+        };
+        AuditSubject {
+            command: request.op_name(),
+            resource,
+            document_ids: request.written_document_ids(),
+        }
+    }
+
+    /// Resolve the collection a request targets
+    ///
+    /// Requests naming no collection (or the default one) go to the
+    /// default collection, which accepts any schema. Any other collection
+    /// must be registered, and the request's documents must be of the
+    /// schema it was created for.
+    fn route(
+        &self,
+        collection: Option<String>,
+        request: &Request,
+        schema_loader: &SchemaLoader,
+    ) -> ApiResult<String> {
+        let collection = match collection {
+            Some(c) if c != self.collection => c,
+            _ => return Ok(self.collection.clone()),
+        };
+        let bound = schema_loader
+            .collection_schema(&collection)
+            .ok_or_else(|| {
+                ApiError::from_schema_error(SchemaError::unknown_collection(&collection))
+            })?;
+        if let Some(other) = request
+            .document_schema_ids()
+            .into_iter()
+            .find(|schema_id| *schema_id != bound)
+        {
+            return Err(ApiError::invalid_request(format!(
+                "Collection '{}' stores schema '{}', not '{}'",
+                collection, bound, other
+            )));
+        }
+        Ok(collection)
+    }
+
     /// Handle insert operation
     ///
+    /// An insert never replaces a live document: an `_id` already stored
+    /// is rejected with `AERO_DUPLICATE_ID` before the WAL is touched, so
+    /// a caller allowed only to insert cannot overwrite someone else's row.
+    ///
     /// Flow:
     /// 1. Validate schema
     /// 2. Build write intent
     /// 3. Append WAL record
     /// 4. Apply to Storage
     /// 5. Update Index
-    fn handle_insert(&self, req: InsertRequest, sys: &mut Subsystems<'_>) -> ApiResult<Value> {
+    fn handle_insert(
+        &self,
+        mut req: InsertRequest,
+        collection: &str,
+        sys: &mut Subsystems<'_>,
+    ) -> ApiResult<Value> {
         let validator = SchemaValidator::new(sys.schema_loader);
 
-        // 1. Validate schema
+        // 1. Materialize defaults and validate schema
         validator
-            .validate_document(&req.schema_id, &req.schema_version, &req.document)
+            .prepare_document(&req.schema_id, &req.schema_version, &mut req.document)
             .map_err(ApiError::from_schema_error)?;
 
         // Extract document ID
@@ -98,14 +645,28 @@ impl ApiHandler {
             .and_then(|v| v.as_str())
             .ok_or_else(|| ApiError::invalid_request("Document missing _id"))?
             .to_string();
+        if !sys.index_manager.lookup_pk(&doc_id).is_empty() {
+            return Err(ApiError::duplicate_id(doc_id));
+        }
 
-        // 2. Build write intent
-        let body_bytes = serde_json::to_vec(&req.document).map_err(|e| {
+        // Unique constraints hold before anything reaches the WAL
+        sys.index_manager
+            .check_unique(&doc_id, &req.document)
+            .map_err(|v| ApiError::unique_violation(&v))?;
+
+        // 2. Build write intent, encrypted fields sealed
+        let stored = self.seal_document(
+            sys.schema_loader,
+            &req.schema_id,
+            &req.schema_version,
+            &req.document,
+        )?;
+        let body_bytes = serde_json::to_vec(&stored).map_err(|e| {
             ApiError::invalid_request(format!("Failed to serialize document: {}", e))
         })?;
 
         let wal_payload = WalPayload::new(
-            &self.collection,
+            collection,
             &doc_id,
             &req.schema_id,
             &req.schema_version,
@@ -115,11 +676,11 @@ impl ApiHandler {
         // 3. Append WAL record
         sys.wal_writer
             .append(RecordType::Insert, wal_payload)
-            .map_err(ApiError::from_wal_error)?;
+            .map_err(|e| self.wal_failure(e))?;
 
         // 4. Apply to Storage
         let storage_payload = StoragePayload::new(
-            &self.collection,
+            collection,
             &doc_id,
             &req.schema_id,
             &req.schema_version,
@@ -136,12 +697,129 @@ impl ApiHandler {
             schema_id: req.schema_id,
             schema_version: req.schema_version,
             is_tombstone: false,
-            body: req.document,
+            body: stored,
             offset,
         };
         sys.index_manager.apply_write(&doc_info);
 
-        Ok(json!({"inserted": doc_id}))
+        Ok(json!({"inserted": doc_id, "commit_id": storage_commit_id(offset).value()}))
+    }
+
+    /// Handle batch insert operation (all-or-nothing)
+    ///
+    /// Flow:
+    /// 1. Validate every document (schema, `_id`, no duplicate IDs within
+    ///    the batch or among live documents)
+    /// 2. Append all WAL records with a single fsync
+    /// 3. Apply all documents to Storage with a single fsync
+    /// 4. Update Index for every document
+    ///
+    /// Any validation failure rejects the whole batch before the WAL is
+    /// touched. A failed WAL append leaves no record of the batch behind.
+    fn handle_insert_many(
+        &self,
+        req: InsertManyRequest,
+        collection: &str,
+        sys: &mut Subsystems<'_>,
+    ) -> ApiResult<Value> {
+        let validator = SchemaValidator::new(sys.schema_loader);
+
+        // 1. Validate every document before any write
+        let mut seen = std::collections::HashSet::new();
+        let mut prepared = Vec::with_capacity(req.documents.len());
+        for (i, mut document) in req.documents.into_iter().enumerate() {
+            validator
+                .prepare_document(&req.schema_id, &req.schema_version, &mut document)
+                .map_err(ApiError::from_schema_error)?;
+
+            let doc_id = document
+                .get("_id")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ApiError::invalid_request(format!("Document {} missing _id", i)))?
+                .to_string();
+            if !seen.insert(doc_id.clone()) {
+                return Err(ApiError::invalid_request(format!(
+                    "Duplicate _id '{}' in batch",
+                    doc_id
+                )));
+            }
+            if !sys.index_manager.lookup_pk(&doc_id).is_empty() {
+                return Err(ApiError::duplicate_id(doc_id));
+            }
+
+            let stored = self.seal_document(
+                sys.schema_loader,
+                &req.schema_id,
+                &req.schema_version,
+                &document,
+            )?;
+            let body_bytes = serde_json::to_vec(&stored).map_err(|e| {
+                ApiError::invalid_request(format!("Failed to serialize document: {}", e))
+            })?;
+            prepared.push((doc_id, stored, body_bytes));
+        }
+
+        // Unique constraints hold across the batch and existing documents
+        let mut unique = sys.index_manager.stage_unique();
+        for (doc_id, document, _) in &prepared {
+            unique
+                .write(doc_id, document)
+                .map_err(|v| ApiError::unique_violation(&v))?;
+        }
+
+        // 2. Append all WAL records, one fsync
+        let wal_records = prepared
+            .iter()
+            .map(|(doc_id, _, body_bytes)| {
+                (
+                    RecordType::Insert,
+                    WalPayload::new(
+                        collection,
+                        doc_id,
+                        &req.schema_id,
+                        &req.schema_version,
+                        body_bytes.clone(),
+                    ),
+                )
+            })
+            .collect();
+        sys.wal_writer
+            .append_batch(wal_records)
+            .map_err(|e| self.wal_failure(e))?;
+
+        // 3. Apply to Storage, one fsync
+        let storage_payloads: Vec<StoragePayload> = prepared
+            .iter()
+            .map(|(doc_id, _, body_bytes)| {
+                StoragePayload::new(
+                    collection,
+                    doc_id,
+                    &req.schema_id,
+                    &req.schema_version,
+                    body_bytes.clone(),
+                )
+            })
+            .collect();
+        let offsets = sys
+            .storage_writer
+            .write_batch(&storage_payloads)
+            .map_err(ApiError::from_storage_error)?;
+
+        // 4. Update Index
+        let mut inserted = Vec::with_capacity(prepared.len());
+        for ((doc_id, document, _), offset) in prepared.into_iter().zip(offsets) {
+            sys.index_manager.apply_write(&DocumentInfo {
+                document_id: doc_id.clone(),
+                schema_id: req.schema_id.clone(),
+                schema_version: req.schema_version.clone(),
+                is_tombstone: false,
+                body: document,
+                offset,
+            });
+            inserted.push(doc_id);
+        }
+
+        Ok(json!({"inserted": inserted, "count": inserted.len()}))
     }
 
     /// Handle update operation
@@ -153,7 +831,12 @@ impl ApiHandler {
     /// 4. Append WAL record
     /// 5. Apply to Storage
     /// 6. Update Index
-    fn handle_update(&self, req: UpdateRequest, sys: &mut Subsystems<'_>) -> ApiResult<Value> {
+    fn handle_update(
+        &self,
+        mut req: UpdateRequest,
+        collection: &str,
+        sys: &mut Subsystems<'_>,
+    ) -> ApiResult<Value> {
         let validator = SchemaValidator::new(sys.schema_loader);
 
         // Extract document ID
@@ -164,27 +847,44 @@ impl ApiHandler {
             .ok_or_else(|| ApiError::invalid_request("Document missing _id"))?
             .to_string();
 
-        // 1. Validate schema (update mode)
+        // 1. Materialize defaults and validate schema (update mode)
         validator
-            .validate_update(&req.schema_id, &req.schema_version, &doc_id, &req.document)
+            .prepare_update(
+                &req.schema_id,
+                &req.schema_version,
+                &doc_id,
+                &mut req.document,
+            )
             .map_err(ApiError::from_schema_error)?;
 
-        // 2. Check document exists (via index)
-        let offsets = sys.index_manager.lookup_pk(&doc_id);
-        if offsets.is_empty() {
-            return Err(ApiError::invalid_request(format!(
-                "Document not found: {}",
-                doc_id
-            )));
-        }
+        // 2. Check document exists (via index) and preconditions hold
+        let current = match sys.index_manager.lookup_pk(&doc_id).last() {
+            Some(offset) => *offset,
+            None => {
+                return Err(ApiError::invalid_request(format!(
+                    "Document not found: {}",
+                    doc_id
+                )))
+            }
+        };
+        check_update_precondition(&req, &doc_id, current, sys.storage_reader)?;
+        sys.index_manager
+            .check_unique(&doc_id, &req.document)
+            .map_err(|v| ApiError::unique_violation(&v))?;
 
-        // 3. Build write intent
-        let body_bytes = serde_json::to_vec(&req.document).map_err(|e| {
+        // 3. Build write intent, encrypted fields sealed
+        let stored = self.seal_document(
+            sys.schema_loader,
+            &req.schema_id,
+            &req.schema_version,
+            &req.document,
+        )?;
+        let body_bytes = serde_json::to_vec(&stored).map_err(|e| {
             ApiError::invalid_request(format!("Failed to serialize document: {}", e))
         })?;
 
         let wal_payload = WalPayload::new(
-            &self.collection,
+            collection,
             &doc_id,
             &req.schema_id,
             &req.schema_version,
@@ -194,11 +894,11 @@ impl ApiHandler {
         // 4. Append WAL record
         sys.wal_writer
             .append(RecordType::Update, wal_payload)
-            .map_err(ApiError::from_wal_error)?;
+            .map_err(|e| self.wal_failure(e))?;
 
         // 5. Apply to Storage (overwrite)
         let storage_payload = StoragePayload::new(
-            &self.collection,
+            collection,
             &doc_id,
             &req.schema_id,
             &req.schema_version,
@@ -215,12 +915,59 @@ impl ApiHandler {
             schema_id: req.schema_id,
             schema_version: req.schema_version,
             is_tombstone: false,
-            body: req.document,
+            body: stored,
             offset,
         };
         sys.index_manager.apply_write(&doc_info);
 
-        Ok(json!({"updated": doc_id}))
+        Ok(json!({"updated": doc_id, "commit_id": storage_commit_id(offset).value()}))
+    }
+
+    /// Handle upsert operation
+    ///
+    /// Chooses insert or update from a primary key index lookup. The lookup
+    /// and the write run under the same global lock, so no other request
+    /// can create or delete the document in between. The WAL records a
+    /// plain Insert or Update.
+    fn handle_upsert(
+        &self,
+        req: UpsertRequest,
+        collection: &str,
+        sys: &mut Subsystems<'_>,
+    ) -> ApiResult<Value> {
+        let doc_id = req
+            .document
+            .get("_id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ApiError::invalid_request("Document missing _id"))?
+            .to_string();
+
+        let exists = !sys.index_manager.lookup_pk(&doc_id).is_empty();
+        if exists {
+            self.handle_update(
+                UpdateRequest {
+                    schema_id: req.schema_id,
+                    schema_version: req.schema_version,
+                    document: req.document,
+                    if_commit_id: None,
+                    if_checksum: None,
+                },
+                collection,
+                sys,
+            )?;
+        } else {
+            self.handle_insert(
+                InsertRequest {
+                    schema_id: req.schema_id,
+                    schema_version: req.schema_version,
+                    document: req.document,
+                },
+                collection,
+                sys,
+            )?;
+        }
+
+        Ok(json!({"upserted": doc_id, "created": !exists}))
     }
 
     /// Handle delete operation
@@ -230,7 +977,12 @@ impl ApiHandler {
     /// 2. Append WAL record
     /// 3. Apply tombstone to Storage
     /// 4. Update Index
-    fn handle_delete(&self, req: DeleteRequest, sys: &mut Subsystems<'_>) -> ApiResult<Value> {
+    fn handle_delete(
+        &self,
+        req: DeleteRequest,
+        collection: &str,
+        sys: &mut Subsystems<'_>,
+    ) -> ApiResult<Value> {
         // 1. Check document exists (via index)
         let offsets = sys.index_manager.lookup_pk(&req.document_id);
         if offsets.is_empty() {
@@ -251,7 +1003,7 @@ impl ApiHandler {
 
         // 2. Append WAL record
         let wal_payload = WalPayload::tombstone(
-            &self.collection,
+            collection,
             &req.document_id,
             &req.schema_id,
             "", // version empty for delete
@@ -259,11 +1011,11 @@ impl ApiHandler {
 
         sys.wal_writer
             .append(RecordType::Delete, wal_payload)
-            .map_err(ApiError::from_wal_error)?;
+            .map_err(|e| self.wal_failure(e))?;
 
         // 3. Apply tombstone to Storage
         sys.storage_writer
-            .write_tombstone(&self.collection, &req.document_id, &req.schema_id, "")
+            .write_tombstone(collection, &req.document_id, &req.schema_id, "")
             .map_err(ApiError::from_storage_error)?;
 
         // 4. Update Index
@@ -272,83 +1024,960 @@ impl ApiHandler {
         Ok(json!({"deleted": req.document_id}))
     }
 
-    /// Handle query operation
+    /// Handle multi-operation transaction (all-or-nothing)
     ///
     /// Flow:
-    /// 1. Parse query
-    /// 2. Call Planner
-    /// 3. Call Executor (simplified: use index + storage)
-    /// 4. Return results
-    fn handle_query(&self, req: QueryRequest, sys: &mut Subsystems<'_>) -> ApiResult<Value> {
-        // Build index metadata
-        let index_metadata =
-            IndexMetadata::with_indexes(sys.index_manager.indexed_fields().iter().cloned());
-
-        let planner = QueryPlanner::new(sys.schema_loader, &index_metadata);
+    /// 1. Validate every op in order against a transaction-local view
+    ///    (so later ops see earlier inserts, updates and deletes)
+    /// 2. Assign the next CommitId
+    /// 3. Append TxnBegin, all ops and TxnCommit with a single fsync
+    /// 4. Apply all ops to Storage with a single fsync
+    /// 5. Update Index in op order
+    ///
+    /// Any validation failure rejects the whole transaction before the WAL
+    /// is touched. Recovery discards a transaction without its TxnCommit.
+    ///
+    /// A transaction with a read view is also rejected, before the WAL, if
+    /// another request wrote any of its documents after the view was
+    /// opened (first committer wins).
+    fn handle_transaction(
+        &self,
+        req: TransactionRequest,
+        collection: &str,
+        sys: &mut Subsystems<'_>,
+    ) -> ApiResult<Value> {
+        let validator = SchemaValidator::new(sys.schema_loader);
+        let view = req
+            .read_view
+            .map(|id| self.read_views.resolve(id))
+            .transpose()?;
 
-        // 1. Build query AST
-        let query = self.build_query(&req)?;
+        // 1. Validate every op before any write. The overlay holds the
+        // latest body per document touched so far (None = deleted).
+        let mut overlay: HashMap<String, Option<Value>> = HashMap::new();
+        let mut prepared = Vec::with_capacity(req.ops.len());
+        for (i, op) in req.ops.into_iter().enumerate() {
+            let prepared_op = match op {
+                TxnOp::Insert(mut r) => {
+                    validator
+                        .prepare_document(&r.schema_id, &r.schema_version, &mut r.document)
+                        .map_err(ApiError::from_schema_error)?;
+                    let doc_id = txn_document_id(i, &r.document)?;
+                    let stored = self.seal_document(
+                        sys.schema_loader,
+                        &r.schema_id,
+                        &r.schema_version,
+                        &r.document,
+                    )?;
+                    overlay.insert(doc_id.clone(), Some(stored.clone()));
+                    PreparedTxnOp::write(
+                        RecordType::Insert,
+                        doc_id,
+                        r.schema_id,
+                        r.schema_version,
+                        stored,
+                    )?
+                }
+                TxnOp::Update(mut r) => {
+                    let doc_id = txn_document_id(i, &r.document)?;
+                    validator
+                        .prepare_update(&r.schema_id, &r.schema_version, &doc_id, &mut r.document)
+                        .map_err(ApiError::from_schema_error)?;
+                    let exists = match overlay.get(&doc_id) {
+                        Some(body) => {
+                            if body.is_some() && has_precondition(&r) {
+                                return Err(ApiError::conflict(format!(
+                                    "Op {}: document {} was already written earlier in this transaction",
+                                    i, doc_id
+                                )));
+                            }
+                            body.is_some()
+                        }
+                        None => match sys.index_manager.lookup_pk(&doc_id).last() {
+                            Some(offset) => {
+                                check_update_precondition(
+                                    &r,
+                                    &doc_id,
+                                    *offset,
+                                    sys.storage_reader,
+                                )?;
+                                true
+                            }
+                            None => false,
+                        },
+                    };
+                    if !exists {
+                        return Err(ApiError::invalid_request(format!(
+                            "Op {}: document not found: {}",
+                            i, doc_id
+                        )));
+                    }
+                    let stored = self.seal_document(
+                        sys.schema_loader,
+                        &r.schema_id,
+                        &r.schema_version,
+                        &r.document,
+                    )?;
+                    overlay.insert(doc_id.clone(), Some(stored.clone()));
+                    PreparedTxnOp::write(
+                        RecordType::Update,
+                        doc_id,
+                        r.schema_id,
+                        r.schema_version,
+                        stored,
+                    )?
+                }
+                TxnOp::Delete(r) => {
+                    let old_body = match overlay.get(&r.document_id) {
+                        Some(Some(body)) => body.clone(),
+                        Some(None) => {
+                            return Err(ApiError::invalid_request(format!(
+                                "Op {}: document not found: {}",
+                                i, r.document_id
+                            )))
+                        }
+                        None => {
+                            let offsets = sys.index_manager.lookup_pk(&r.document_id);
+                            let Some(&old_offset) = offsets.last() else {
+                                return Err(ApiError::invalid_request(format!(
+                                    "Op {}: document not found: {}",
+                                    i, r.document_id
+                                )));
+                            };
+                            let old_doc = sys
+                                .storage_reader
+                                .read_at(old_offset)
+                                .map_err(ApiError::from_storage_error)?;
+                            serde_json::from_slice(&old_doc.document_body).unwrap_or(json!({}))
+                        }
+                    };
+                    overlay.insert(r.document_id.clone(), None);
+                    PreparedTxnOp {
+                        record_type: RecordType::Delete,
+                        doc_id: r.document_id,
+                        schema_id: r.schema_id,
+                        schema_version: String::new(),
+                        body: old_body,
+                        body_bytes: Vec::new(),
+                    }
+                }
+            };
+            prepared.push(prepared_op);
+        }
 
-        // 2. Call Planner
-        let plan = planner.plan(&query).map_err(ApiError::from_planner_error)?;
+        // Unique constraints hold after every op, in order
+        let mut unique = sys.index_manager.stage_unique();
+        for op in &prepared {
+            if op.record_type == RecordType::Delete {
+                unique.delete(&op.doc_id);
+            } else {
+                unique
+                    .write(&op.doc_id, &op.body)
+                    .map_err(|v| ApiError::unique_violation(&v))?;
+            }
+        }
 
-        // 3. Execute query (simplified execution)
-        let mut results = Vec::new();
+        if let Some(view) = view {
+            let latest = prepared.iter().map(|op| {
+                let written = self.read_views.last_write(collection, &op.doc_id);
+                (op.doc_id.as_str(), written)
+            });
+            CommitAuthority::check_write_set(&view, latest)
+                .map_err(ApiError::serialization_failure)?;
+        }
 
-        // Get offsets from index based on plan
-        let offsets = self.get_offsets_for_plan(&plan, &query, sys.index_manager);
+        // 2. Assign the CommitId at the commit boundary
+        let mut authority = CommitAuthority::from_replayed_commit(sys.wal_writer.last_commit_id());
+        let commit_id = authority.next_commit_id();
 
-        // Read documents at offsets
-        for offset in offsets.iter().take(req.limit) {
-            if let Ok(record) = sys.storage_reader.read_at(*offset) {
-                // Skip tombstones
-                if record.is_tombstone {
-                    continue;
-                }
+        // 3. Append TxnBegin + ops + TxnCommit, one fsync
+        let wal_ops = prepared
+            .iter()
+            .map(|op| {
+                let payload = if op.record_type == RecordType::Delete {
+                    WalPayload::tombstone(collection, &op.doc_id, &op.schema_id, "")
+                } else {
+                    WalPayload::new(
+                        collection,
+                        &op.doc_id,
+                        &op.schema_id,
+                        &op.schema_version,
+                        op.body_bytes.clone(),
+                    )
+                };
+                (op.record_type, payload)
+            })
+            .collect();
+        sys.wal_writer
+            .append_transaction(wal_ops, commit_id.value())
+            .map_err(|e| self.wal_failure(e))?;
+        authority
+            .mark_committed(commit_id)
+            .expect("CommitId was taken from next_commit_id");
 
-                // Check schema match
-                if record.schema_id != req.schema_id || record.schema_version != req.schema_version
-                {
-                    continue;
+        // 4. Apply to Storage, one fsync
+        let storage_payloads: Vec<StoragePayload> = prepared
+            .iter()
+            .map(|op| {
+                if op.record_type == RecordType::Delete {
+                    StoragePayload::tombstone(collection, &op.doc_id, &op.schema_id, "")
+                } else {
+                    StoragePayload::new(
+                        collection,
+                        &op.doc_id,
+                        &op.schema_id,
+                        &op.schema_version,
+                        op.body_bytes.clone(),
+                    )
                 }
+            })
+            .collect();
+        let offsets = sys
+            .storage_writer
+            .write_batch(&storage_payloads)
+            .map_err(ApiError::from_storage_error)?;
 
-                // Parse body
-                if let Ok(doc) = serde_json::from_slice::<Value>(&record.document_body) {
-                    results.push(doc);
-                }
+        // 5. Update Index in op order
+        let committed = prepared.len();
+        for (op, offset) in prepared.into_iter().zip(offsets) {
+            if op.record_type == RecordType::Delete {
+                sys.index_manager.apply_delete(&op.doc_id, &op.body);
+            } else {
+                sys.index_manager.apply_write(&DocumentInfo {
+                    document_id: op.doc_id,
+                    schema_id: op.schema_id,
+                    schema_version: op.schema_version,
+                    is_tombstone: false,
+                    body: op.body,
+                    offset,
+                });
             }
         }
 
-        Ok(json!(results))
+        Ok(json!({"committed": committed, "commit_id": commit_id.value()}))
     }
 
-    /// Handle explain operation
-    fn handle_explain(&self, req: QueryRequest, sys: &mut Subsystems<'_>) -> ApiResult<Value> {
-        // Build index metadata
-        let index_metadata =
-            IndexMetadata::with_indexes(sys.index_manager.indexed_fields().iter().cloned());
+    /// Handle get-by-id operation
+    ///
+    /// Fast path for a single document by primary key: no query AST, no
+    /// plan. The latest offset comes from the primary key index and the
+    /// record is read with checksum validation.
+    fn handle_get(&self, req: GetRequest, sys: &mut Subsystems<'_>) -> ApiResult<Value> {
+        let offset = match sys.index_manager.lookup_pk(&req.document_id).last() {
+            Some(offset) => *offset,
+            None => return Err(ApiError::not_found(req.document_id)),
+        };
 
-        let planner = QueryPlanner::new(sys.schema_loader, &index_metadata);
+        let record = sys
+            .storage_reader
+            .read_at(offset)
+            .map_err(ApiError::from_storage_error)?;
+        if record.is_tombstone || record.schema_id != req.schema_id {
+            return Err(ApiError::not_found(req.document_id));
+        }
 
-        // Build query AST
-        let query = self.build_query(&req)?;
+        let document = serde_json::from_slice(&record.document_body).map_err(|e| {
+            ApiError::invalid_request(format!(
+                "Stored document {} is not valid JSON: {}",
+                req.document_id, e
+            ))
+        })?;
+        self.open_document(document)
+    }
 
-        // Call Planner
-        let plan = planner.plan(&query).map_err(ApiError::from_planner_error)?;
+    /// Handle query operation
+    ///
+    /// Flow:
+    /// 1. Parse query
+    /// 2. Call Planner
+    /// 3. Call Executor (simplified: use index + storage)
+    /// 4. Return results
+    fn handle_query(
+        &self,
+        req: QueryRequest,
+        collection: &str,
+        sys: &mut Subsystems<'_>,
+    ) -> ApiResult<Value> {
+        let mut results = Vec::new();
+        let stats = self.scan_matches(&req, collection, sys, |doc| results.push(doc))?;
+        if req.stats {
+            return Ok(json!({ "documents": results, "stats": stats }));
+        }
+        Ok(json!(results))
+    }
+
+    /// Handle count operation
+    ///
+    /// Same planning, bounds and read-view rules as a query; matching
+    /// documents are counted, not returned.
+    fn handle_count(
+        &self,
+        req: QueryRequest,
+        collection: &str,
+        sys: &mut Subsystems<'_>,
+    ) -> ApiResult<Value> {
+        let mut count: u64 = 0;
+        let stats = self.scan_matches(&req, collection, sys, |_| count += 1)?;
+        if req.stats {
+            return Ok(json!({ "count": count, "stats": stats }));
+        }
+        Ok(json!({ "count": count }))
+    }
+
+    /// Handle aggregate operation (min/max/sum over one field)
+    ///
+    /// Matching documents are folded into the aggregate as they are read.
+    fn handle_aggregate(
+        &self,
+        req: AggregateRequest,
+        collection: &str,
+        sys: &mut Subsystems<'_>,
+    ) -> ApiResult<Value> {
+        let mut aggregator = Aggregator::new(req.function, &req.field);
+        let stats = self.scan_matches(&req.query, collection, sys, |doc| {
+            aggregator.accumulate(&doc)
+        })?;
+        let mut result = json!({
+            "function": req.function.as_str(),
+            "field": req.field,
+            "value": aggregator.value(),
+            "count": aggregator.count()
+        });
+        if req.query.stats {
+            result["stats"] = json!(stats);
+        }
+        Ok(result)
+    }
+
+    /// Handle create_schema (`alter` false) and alter_schema (`alter` true)
+    ///
+    /// create_schema registers the first version of a new schema id;
+    /// alter_schema adds a version to an existing one. Unique fields must
+    /// already be enforced by the running index.
+    fn handle_create_schema(
+        &self,
+        schema: Schema,
+        alter: bool,
+        sys: &mut Subsystems<'_>,
+    ) -> ApiResult<Value> {
+        schema.validate_structure().map_err(|e| {
+            ApiError::invalid_request(format!("Invalid schema '{}': {}", schema.schema_id, e))
+        })?;
+
+        let exists = sys.schema_loader.schema_id_exists(&schema.schema_id);
+        if alter && !exists {
+            return Err(ApiError::from_schema_error(SchemaError::unknown_schema(
+                &schema.schema_id,
+            )));
+        }
+        if !alter && exists {
+            return Err(ApiError::conflict(format!(
+                "Schema '{}' already exists; use alter_schema to add a version",
+                schema.schema_id
+            )));
+        }
+
+        if let Some(field) = schema
+            .encrypted_fields()
+            .into_iter()
+            .find(|field| sys.index_manager.indexed_fields().contains(field))
+        {
+            return Err(ApiError::invalid_request(format!(
+                "Encrypted field '{}' is indexed; indexes hold stored values, \
+                 which are ciphertext",
+                field
+            )));
+        }
+
+        for field in schema.unique_fields() {
+            if !sys.index_manager.unique_fields().any(|f| f == field) {
+                return Err(ApiError::invalid_request(format!(
+                    "Unique field '{}' is not enforced by the running index; \
+                     declare it in a schema file and restart",
+                    field
+                )));
+            }
+        }
+
+        self.apply_schema_ddl(SchemaDdl::Create(schema), sys)
+    }
+
+    /// Handle deprecate_schema operation
+    fn handle_deprecate_schema(
+        &self,
+        req: DeprecateSchemaRequest,
+        sys: &mut Subsystems<'_>,
+    ) -> ApiResult<Value> {
+        self.apply_schema_ddl(
+            SchemaDdl::Deprecate {
+                schema_id: req.schema_id,
+                schema_version: req.schema_version,
+            },
+            sys,
+        )
+    }
+
+    /// Apply a schema change durably
+    ///
+    /// Flow:
+    /// 1. Check the change against the catalog
+    /// 2. Append SCHEMA_DDL WAL record (fsync)
+    /// 3. Write the schema file and update the registry
+    ///
+    /// A crash after step 2 is repaired at boot, which re-applies the WAL
+    /// record before recovery.
+    fn apply_schema_ddl(&self, ddl: SchemaDdl, sys: &mut Subsystems<'_>) -> ApiResult<Value> {
+        // 1. Check
+        sys.schema_loader
+            .check_ddl(&ddl)
+            .map_err(ApiError::from_schema_error)?;
+
+        // 2. WAL append
+        sys.wal_writer
+            .append(RecordType::SchemaDdl, ddl.to_payload())
+            .map_err(|e| self.wal_failure(e))?;
+
+        // 3. Schema catalog
+        sys.schema_loader
+            .apply_ddl(&ddl)
+            .map_err(ApiError::from_schema_error)?;
+
+        let deprecated = matches!(ddl, SchemaDdl::Deprecate { .. });
+        Ok(json!({
+            "schema_id": ddl.schema_id(),
+            "schema_version": ddl.schema_version(),
+            "deprecated": deprecated
+        }))
+    }
+
+    /// Handle create_collection operation
+    ///
+    /// The collection is registered through a SCHEMA_DDL record, like any
+    /// other catalog change. Its index partition is created on first use.
+    fn handle_create_collection(
+        &self,
+        req: CreateCollectionRequest,
+        sys: &mut Subsystems<'_>,
+    ) -> ApiResult<Value> {
+        if req.collection.is_empty() || req.collection.contains(':') {
+            return Err(ApiError::invalid_request(format!(
+                "Invalid collection name '{}': must be non-empty and contain no ':'",
+                req.collection
+            )));
+        }
+        if req.collection == self.collection
+            || sys
+                .schema_loader
+                .collection_schema(&req.collection)
+                .is_some()
+        {
+            return Err(ApiError::conflict(format!(
+                "Collection '{}' already exists",
+                req.collection
+            )));
+        }
+
+        self.apply_schema_ddl(
+            SchemaDdl::CreateCollection {
+                collection: req.collection.clone(),
+                schema_id: req.schema_id,
+            },
+            sys,
+        )?;
+        Ok(json!({
+            "collection": req.collection,
+            "schema_id": sys.schema_loader.collection_schema(&req.collection)
+        }))
+    }
+
+    /// Handle drop_collection (`drop` true) and truncate_collection
+    ///
+    /// Flow:
+    /// 1. Collect the live documents from the collection's index partition
+    /// 2. Append a tombstone per document, then the SCHEMA_DDL record, with
+    ///    a single fsync
+    /// 3. Apply the tombstones to Storage with a single fsync
+    /// 4. Update the catalog (drop unregisters the collection)
+    /// 5. Update Index (drop discards the partition)
+    ///
+    /// Recovery replays the tombstones like any deletes and the DDL record
+    /// in the catalog pass. A crash before the DDL record is durable leaves
+    /// the collection registered with some documents removed; retrying
+    /// completes it. The default collection can be truncated, not dropped.
+    fn handle_remove_collection(
+        &self,
+        req: CollectionRequest,
+        drop: bool,
+        sys: &mut Subsystems<'_>,
+    ) -> ApiResult<Value> {
+        let name = req.collection;
+        let is_default = name == self.collection;
+        if drop && is_default {
+            return Err(ApiError::invalid_request(format!(
+                "The default collection '{}' cannot be dropped",
+                name
+            )));
+        }
+        if !is_default && sys.schema_loader.collection_schema(&name).is_none() {
+            return Err(ApiError::from_schema_error(
+                SchemaError::unknown_collection(&name),
+            ));
+        }
+        let ddl = if drop {
+            SchemaDdl::DropCollection {
+                collection: name.clone(),
+            }
+        } else {
+            SchemaDdl::TruncateCollection {
+                collection: name.clone(),
+            }
+        };
+        sys.schema_loader
+            .check_ddl(&ddl)
+            .map_err(ApiError::from_schema_error)?;
+
+        let index = if is_default {
+            &mut *sys.index_manager
+        } else {
+            sys.index_manager.collection_mut(&name)
+        };
+
+        // 1. Live documents: (id, schema_id, body)
+        let prefix = format!("{}:", name);
+        let mut removed = Vec::new();
+        for offset in index.all_offsets_pk_order() {
+            let record = sys
+                .storage_reader
+                .read_at(offset)
+                .map_err(ApiError::from_storage_error)?;
+            let Some(doc_id) = record.document_id.strip_prefix(&prefix) else {
+                continue;
+            };
+            let body: Value = serde_json::from_slice(&record.document_body).unwrap_or(json!({}));
+            removed.push((doc_id.to_string(), record.schema_id, body));
+        }
+
+        // 2. WAL: tombstones, then the DDL record
+        let mut wal_records: Vec<(RecordType, WalPayload)> = removed
+            .iter()
+            .map(|(doc_id, schema_id, _)| {
+                (
+                    RecordType::Delete,
+                    WalPayload::tombstone(&name, doc_id, schema_id, ""),
+                )
+            })
+            .collect();
+        wal_records.push((RecordType::SchemaDdl, ddl.to_payload()));
+        sys.wal_writer
+            .append_batch(wal_records)
+            .map_err(|e| self.wal_failure(e))?;
+
+        // 3. Storage
+        if !removed.is_empty() {
+            let tombstones: Vec<StoragePayload> = removed
+                .iter()
+                .map(|(doc_id, schema_id, _)| {
+                    StoragePayload::tombstone(&name, doc_id, schema_id, "")
+                })
+                .collect();
+            sys.storage_writer
+                .write_batch(&tombstones)
+                .map_err(ApiError::from_storage_error)?;
+        }
+
+        // 4. Catalog
+        sys.schema_loader
+            .apply_ddl(&ddl)
+            .map_err(ApiError::from_schema_error)?;
+
+        // 5. Index
+        if drop {
+            sys.index_manager.drop_collection(&name);
+        } else {
+            for (doc_id, _, body) in &removed {
+                index.apply_delete(doc_id, body);
+            }
+        }
+
+        Ok(json!({
+            "collection": name,
+            "dropped": drop,
+            "deleted": removed.len()
+        }))
+    }
+
+    /// Handle create_index operation
+    ///
+    /// Flow:
+    /// 1. Append SCHEMA_DDL record (fsync) and record the index in the
+    ///    catalog
+    /// 2. Declare the field on the running index (new writes maintain it)
+    /// 3. Backfill every collection's index from storage, reading
+    ///    `BACKFILL_BATCH` documents at a time
+    ///
+    /// The global lock is held throughout, so no write interleaves with the
+    /// backfill. Boot declares every catalogued index before rebuilding, so
+    /// a crash after step 1 loses nothing.
+    fn handle_create_index(
+        &self,
+        req: CreateIndexRequest,
+        sys: &mut Subsystems<'_>,
+    ) -> ApiResult<Value> {
+        let field = req.field;
+        if field.is_empty() || field == "_id" {
+            return Err(ApiError::invalid_request(format!(
+                "Cannot create an index on '{}'",
+                field
+            )));
+        }
+        if sys.index_manager.indexed_fields().contains(&field) {
+            return Err(ApiError::conflict(format!(
+                "Field '{}' is already indexed",
+                field
+            )));
+        }
+        if let Some(schema) = sys
+            .schema_loader
+            .all_schemas()
+            .find(|schema| schema.encrypted_fields().contains(&field))
+        {
+            return Err(ApiError::invalid_request(format!(
+                "Cannot index '{}': schema '{}' encrypts it",
+                field, schema.schema_id
+            )));
+        }
+
+        // 1. Durable definition
+        let ddl = SchemaDdl::CreateIndex {
+            field: field.clone(),
+        };
+        sys.wal_writer
+            .append(RecordType::SchemaDdl, ddl.to_payload())
+            .map_err(|e| self.wal_failure(e))?;
+        sys.schema_loader
+            .apply_ddl(&ddl)
+            .map_err(ApiError::from_schema_error)?;
+
+        // 2. Declare
+        sys.index_manager.add_field_index(&field);
+
+        // 3. Backfill the default collection, then each partition
+        let mut backfilled = 0;
+        let partitions = sys.index_manager.collection_names();
+        for partition in std::iter::once(None).chain(partitions.iter().map(Some)) {
+            let index = match partition {
+                None => &mut *sys.index_manager,
+                Some(name) => sys.index_manager.collection_mut(name),
+            };
+            for batch in index.all_offsets_pk_order().chunks(BACKFILL_BATCH) {
+                for &offset in batch {
+                    let record = sys
+                        .storage_reader
+                        .read_at(offset)
+                        .map_err(ApiError::from_storage_error)?;
+                    if record.is_tombstone {
+                        continue;
+                    }
+                    if let Ok(body) = serde_json::from_slice::<Value>(&record.document_body) {
+                        index.backfill_field(&field, offset, &body);
+                        backfilled += 1;
+                    }
+                }
+            }
+        }
+
+        Ok(json!({"field": field, "backfilled": backfilled}))
+    }
+
+    /// List the default collection and every registered one, in name order
+    /// Handle begin_read: open a read view for a read-only transaction
+    fn open_read_view(&self, sys: &Subsystems<'_>) -> Value {
+        let handle = self.read_views.open(sys.storage_writer.current_offset());
+        json!({
+            "read_view": handle.id(),
+            "timeout_ms": self.read_views.timeout().as_millis() as u64,
+        })
+    }
+
+    fn list_collections(&self, schema_loader: &SchemaLoader) -> Value {
+        let mut collections: Vec<Value> = schema_loader
+            .collections()
+            .map(|(name, schema_id)| json!({"collection": name, "schema_id": schema_id}))
+            .collect();
+        collections.push(json!({"collection": self.collection, "schema_id": null}));
+        collections.sort_by(|a, b| a["collection"].as_str().cmp(&b["collection"].as_str()));
+        json!(collections)
+    }
+
+    /// Handle list_schemas operation
+    ///
+    /// Returns every registered version in (schema_id, schema_version) order.
+    fn handle_list_schemas(
+        &self,
+        req: ListSchemasRequest,
+        sys: &mut Subsystems<'_>,
+    ) -> ApiResult<Value> {
+        let mut schemas: Vec<&Schema> = sys
+            .schema_loader
+            .all_schemas()
+            .filter(|s| req.schema_id.as_ref().is_none_or(|id| *id == s.schema_id))
+            .collect();
+        schemas.sort_by(|a, b| a.key().cmp(&b.key()));
+
+        let schemas: Vec<Value> = schemas
+            .into_iter()
+            .map(|s| {
+                let mut fields: Vec<&str> = s.fields.keys().map(String::as_str).collect();
+                fields.sort_unstable();
+                json!({
+                    "schema_id": s.schema_id,
+                    "schema_version": s.schema_version,
+                    "description": s.description,
+                    "deprecated": s.deprecated,
+                    "fields": fields
+                })
+            })
+            .collect();
+        Ok(json!(schemas))
+    }
+
+    /// Plan a query request and pass each matching document to `visit`
+    ///
+    /// Returns the work done, which is also added to the metrics registry.
+    /// Queries over the slow query threshold are recorded in the slow
+    /// query log.
+    fn scan_matches(
+        &self,
+        req: &QueryRequest,
+        collection: &str,
+        sys: &mut Subsystems<'_>,
+        mut visit: impl FnMut(Value),
+    ) -> ApiResult<ExecutionStats> {
+        let started = Instant::now();
+
+        // Build index metadata
+        let index_metadata = index_metadata(sys.index_manager);
+
+        let planner = QueryPlanner::new(sys.schema_loader, &index_metadata);
+
+        // 1. Build query AST
+        let query = self.build_query(req, collection)?;
+
+        // 2. Call Planner
+        let plan = planner.plan(&query).map_err(ApiError::from_planner_error)?;
+        let planned = Instant::now();
+
+        // 3. Execute query, as of a read view or historical commit if given
+        let mut stats = ExecutionStats::default();
+        let mut rows: u64 = 0;
+        let counted = |doc| {
+            rows += 1;
+            visit(doc)
+        };
+        let view = match (req.read_view, req.as_of_commit_id) {
+            (Some(id), _) => Some(self.read_views.resolve(id)?),
+            (None, Some(commit)) => Some(
+                self.read_views
+                    .as_of(commit, sys.storage_writer.current_offset())?,
+            ),
+            (None, None) => None,
+        };
+        match view {
+            Some(view) => self.scan_read_view(req, &query, view, sys, &mut stats, counted)?,
+            None => self.scan_plan(req, &query, &plan, sys, &mut stats, counted)?,
+        }
+        stats.elapsed_micros = planned.elapsed().as_micros() as u64;
+
+        if let Some(metrics) = &self.metrics {
+            metrics.add_documents_scanned(stats.documents_scanned);
+            metrics.add_index_candidates(stats.index_candidates);
+            metrics.add_checksum_validations(stats.checksum_validations);
+            metrics.add_filter_evaluations(stats.filter_evaluations);
+        }
+
+        if let Some(slow_queries) = &self.slow_queries {
+            let total = started.elapsed();
+            if slow_queries.is_slow(total) {
+                slow_queries.record(SlowQuery {
+                    collection: collection.to_string(),
+                    schema_id: req.schema_id.clone(),
+                    schema_version: req.schema_version.clone(),
+                    plan: SlowQueryPlan {
+                        scan_type: format!("{:?}", plan.scan_type),
+                        chosen_index: plan.chosen_index.clone(),
+                    },
+                    predicates: query
+                        .predicates
+                        .iter()
+                        .map(|p| {
+                            json!({
+                                "field": p.field,
+                                "op": p.op.op_name(),
+                                "value": p.op.operand()
+                            })
+                        })
+                        .collect(),
+                    limit: req.limit,
+                    rows,
+                    timing: SlowQueryTiming {
+                        planning_micros: (planned - started).as_micros() as u64,
+                        execution_micros: stats.elapsed_micros,
+                        total_micros: total.as_micros() as u64,
+                    },
+                    stats,
+                });
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Visit the matches of `plan` from the index, counting the work in
+    /// `stats`
+    fn scan_plan(
+        &self,
+        req: &QueryRequest,
+        query: &Query,
+        plan: &QueryPlan,
+        sys: &mut Subsystems<'_>,
+        stats: &mut ExecutionStats,
+        mut visit: impl FnMut(Value),
+    ) -> ApiResult<()> {
+        // Get offsets from index based on plan
+        let offsets = self.get_offsets_for_plan(plan, query, sys.index_manager);
+        stats.index_candidates = offsets.len() as u64;
+
+        // Read documents at offsets
+        for offset in offsets.iter().take(req.limit) {
+            stats.documents_scanned += 1;
+            if let Ok(record) = sys.storage_reader.read_at(*offset) {
+                stats.checksum_validations += 1;
+
+                // Skip tombstones
+                if record.is_tombstone {
+                    continue;
+                }
+
+                // Check schema match, then apply residual predicates
+                let served = served_body(&record, req, sys.schema_loader, &*self.field_keys)?;
+                if let Some(doc) = served {
+                    stats.filter_evaluations += 1;
+                    if PredicateFilter::matches(&doc, &query.predicates) {
+                        visit(doc);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Scan matching documents as of a read view
+    ///
+    /// Builds a version chain per document from the storage records inside
+    /// the view, picks the visible version with `Visibility`, then reads it
+    /// with checksum validation and applies the predicates. Documents are
+    /// visited in document key order, at most `limit` of them.
+    fn scan_read_view(
+        &self,
+        req: &QueryRequest,
+        query: &Query,
+        view: ReadView,
+        sys: &mut Subsystems<'_>,
+        stats: &mut ExecutionStats,
+        mut visit: impl FnMut(Value),
+    ) -> ApiResult<()> {
+        let reader = &mut *sys.storage_reader;
+        reader.reset().map_err(ApiError::from_storage_error)?;
+
+        let prefix = format!("{}:", query.collection);
+        let mut chains: BTreeMap<String, VersionChain> = BTreeMap::new();
+        while storage_commit_id(reader.current_offset()) <= view.upper_bound() {
+            let commit_id = storage_commit_id(reader.current_offset());
+            let record = match reader.read_next().map_err(ApiError::from_storage_error)? {
+                Some(record) => record,
+                None => break,
+            };
+            stats.documents_scanned += 1;
+            stats.checksum_validations += 1;
+            if !record.document_id.starts_with(&prefix) {
+                continue;
+            }
+            let key = record.document_id;
+            let version = if record.is_tombstone {
+                Version::with_tombstone(key.clone(), commit_id)
+            } else {
+                Version::with_document(key.clone(), Vec::new(), commit_id)
+            };
+            chains
+                .entry(key.clone())
+                .or_insert_with(|| VersionChain::new(key))
+                .push(version);
+        }
+
+        let mut visited = 0;
+        for chain in chains.values() {
+            if visited >= req.limit {
+                break;
+            }
+            let commit_id = match chain.visible_version(view).version() {
+                Some(version) => version.commit_id(),
+                None => continue,
+            };
+
+            let record = reader
+                .read_at(storage_offset(commit_id))
+                .map_err(ApiError::from_storage_error)?;
+            stats.checksum_validations += 1;
+            if let Some(doc) = served_body(&record, req, sys.schema_loader, &*self.field_keys)? {
+                stats.filter_evaluations += 1;
+                if PredicateFilter::matches(&doc, &query.predicates) {
+                    visited += 1;
+                    visit(doc);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle explain operation
+    fn handle_explain(
+        &self,
+        req: QueryRequest,
+        collection: &str,
+        sys: &mut Subsystems<'_>,
+    ) -> ApiResult<Value> {
+        // Build index metadata
+        let index_metadata = index_metadata(sys.index_manager);
+
+        let planner = QueryPlanner::new(sys.schema_loader, &index_metadata);
+
+        // Build query AST
+        let query = self.build_query(&req, collection)?;
+
+        // Call Planner
+        let plan = planner.plan(&query).map_err(ApiError::from_planner_error)?;
 
         // Return explain output
         Ok(json!({
             "scan_type": format!("{:?}", plan.scan_type),
             "chosen_index": plan.chosen_index,
+            "composite_index": plan.composite.as_ref().map(|c| json!({
+                "fields": c.fields,
+                "bound_fields": c.bound_fields
+            })),
+            "index_costs": plan.index_costs.iter().map(|c| json!({
+                "field": c.field,
+                "estimated_rows": c.estimated_rows
+            })).collect::<Vec<_>>(),
             "predicates": plan.predicates.len(),
-            "sort": plan.sort.as_ref().map(|s| &s.field),
+            "sort": plan.sort.iter().map(|s| &s.field).collect::<Vec<_>>(),
             "limit": plan.limit
         }))
     }
 
     /// Build a Query AST from a QueryRequest
-    fn build_query(&self, req: &QueryRequest) -> ApiResult<Query> {
-        let mut query = Query::new(&self.collection, &req.schema_id)
+    fn build_query(&self, req: &QueryRequest, collection: &str) -> ApiResult<Query> {
+        let mut query = Query::new(collection, &req.schema_id)
             .with_schema_version(&req.schema_version)
             .with_limit(req.limit as u64);
 
@@ -364,6 +1993,34 @@ impl ApiHandler {
                                 "$gt" => Predicate::gt(field, value.clone()),
                                 "$lte" => Predicate::lte(field, value.clone()),
                                 "$lt" => Predicate::lt(field, value.clone()),
+                                "$in" => match value.as_array() {
+                                    Some(values) => Predicate::in_values(field, values.clone()),
+                                    None => {
+                                        return Err(ApiError::invalid_request(format!(
+                                            "$in on {} requires an array",
+                                            field
+                                        )))
+                                    }
+                                },
+                                "$ne" => Predicate::ne(field, value.clone()),
+                                "$exists" => match value.as_bool() {
+                                    Some(present) => Predicate::exists(field, present),
+                                    None => {
+                                        return Err(ApiError::invalid_request(format!(
+                                            "$exists on {} requires a boolean",
+                                            field
+                                        )))
+                                    }
+                                },
+                                "$prefix" => match value.as_str() {
+                                    Some(prefix) => Predicate::prefix(field, prefix),
+                                    None => {
+                                        return Err(ApiError::invalid_request(format!(
+                                            "$prefix on {} requires a string",
+                                            field
+                                        )))
+                                    }
+                                },
                                 other => {
                                     return Err(ApiError::invalid_request(format!(
                                         "Unknown filter operator: {}",
@@ -378,14 +2035,26 @@ impl ApiHandler {
             }
         }
 
-        // Parse sort
+        // Row filters from the pipeline narrow whatever the client asked for
+        for predicate in &req.rls_predicates {
+            query = query.with_predicate(predicate.clone());
+        }
+
+        // Parse sort: comma-separated keys in priority order, `-` = desc
         if let Some(sort_str) = &req.sort {
-            let sort = if sort_str.starts_with('-') {
-                SortSpec::desc(&sort_str[1..])
-            } else {
-                SortSpec::asc(sort_str)
-            };
-            query = query.with_sort(sort);
+            for key in sort_str.split(',').map(str::trim) {
+                let sort = match key.strip_prefix('-') {
+                    Some(field) => SortSpec::desc(field),
+                    None => SortSpec::asc(key),
+                };
+                if sort.field.is_empty() {
+                    return Err(ApiError::invalid_request(format!(
+                        "Invalid sort: {}",
+                        sort_str
+                    )));
+                }
+                query = query.with_sort(sort);
+            }
         }
 
         Ok(query)
@@ -412,6 +2081,12 @@ impl ApiHandler {
                 }
                 Vec::new()
             }
+            ScanType::CompositeEquality => match &plan.composite {
+                Some(composite) => {
+                    index_manager.lookup_composite(&composite.fields, &plan.composite_prefix())
+                }
+                None => Vec::new(),
+            },
             ScanType::IndexedEquality => {
                 let field = &plan.chosen_index;
                 for pred in &query.predicates {
@@ -423,31 +2098,185 @@ impl ApiHandler {
                 }
                 Vec::new()
             }
+            ScanType::IndexedIn => {
+                let field = &plan.chosen_index;
+                for pred in &query.predicates {
+                    if &pred.field == field {
+                        if let FilterOp::In(ref values) = pred.op {
+                            let mut offsets: Vec<u64> = values
+                                .iter()
+                                .flat_map(|val| match (field.as_str(), val.as_str()) {
+                                    ("_id", Some(pk)) => index_manager.lookup_pk(pk),
+                                    ("_id", None) => Vec::new(),
+                                    _ => index_manager.lookup_eq(field, val),
+                                })
+                                .collect();
+                            offsets.sort_unstable();
+                            offsets.dedup();
+                            return offsets;
+                        }
+                    }
+                }
+                Vec::new()
+            }
             ScanType::IndexedRange => {
                 let field = &plan.chosen_index;
-                let mut min: Option<&Value> = None;
-                let mut max: Option<&Value> = None;
+                let mut min: Option<Value> = None;
+                let mut max: Option<Value> = None;
 
                 for pred in &query.predicates {
                     if &pred.field == field {
-                        match &pred.op {
-                            FilterOp::Gte(v) | FilterOp::Gt(v) => min = Some(v),
-                            FilterOp::Lte(v) | FilterOp::Lt(v) => max = Some(v),
-                            _ => {}
+                        if let Some(v) = pred.op.lower_bound() {
+                            min = Some(v);
+                        }
+                        if let Some(v) = pred.op.upper_bound() {
+                            max = Some(v);
                         }
                     }
                 }
 
-                index_manager.lookup_range(field, min, max, Some(plan.limit as usize))
+                index_manager.lookup_range(
+                    field,
+                    min.as_ref(),
+                    max.as_ref(),
+                    Some(plan.limit as usize),
+                )
             }
         }
     }
 }
 
+/// Planner view of the single-field and composite indexes being
+/// maintained, with their current cardinality
+fn index_metadata(index_manager: &IndexManager) -> IndexMetadata {
+    let metadata = index_manager.composite_indexes().fold(
+        IndexMetadata::with_indexes(index_manager.indexed_fields().iter().cloned()),
+        |metadata, fields| metadata.with_composite_index(fields.iter().cloned()),
+    );
+    index_manager
+        .field_stats()
+        .into_iter()
+        .fold(metadata, |metadata, (field, stats)| {
+            metadata.with_statistics(field, stats)
+        })
+}
+
+/// Body of a stored record as served under the requested schema version
+///
+/// Encrypted fields are opened with `keys`, then records of an older
+/// version are upgraded through registered migrations; records of other
+/// schemas, versions without a migration path, or invalid JSON yield
+/// `None`. Index lookups still see stored field names and sealed values.
+fn served_body(
+    record: &DocumentRecord,
+    req: &QueryRequest,
+    schema_loader: &SchemaLoader,
+    keys: &dyn KeyProvider,
+) -> ApiResult<Option<Value>> {
+    if record.schema_id != req.schema_id {
+        return Ok(None);
+    }
+    let Ok(mut doc) = serde_json::from_slice::<Value>(&record.document_body) else {
+        return Ok(None);
+    };
+    decrypt_fields(keys, &mut doc).map_err(ApiError::from_storage_error)?;
+    Ok(schema_loader.upgrade_document(
+        &record.schema_id,
+        &record.schema_version,
+        &req.schema_version,
+        doc,
+    ))
+}
+
+/// A validated transaction op ready to be written
+struct PreparedTxnOp {
+    record_type: RecordType,
+    doc_id: String,
+    schema_id: String,
+    schema_version: String,
+    /// New body for writes, previous body for deletes (index removal)
+    body: Value,
+    /// Serialized body (empty for deletes)
+    body_bytes: Vec<u8>,
+}
+
+impl PreparedTxnOp {
+    fn write(
+        record_type: RecordType,
+        doc_id: String,
+        schema_id: String,
+        schema_version: String,
+        body: Value,
+    ) -> ApiResult<Self> {
+        let body_bytes = serde_json::to_vec(&body).map_err(|e| {
+            ApiError::invalid_request(format!("Failed to serialize document: {}", e))
+        })?;
+        Ok(Self {
+            record_type,
+            doc_id,
+            schema_id,
+            schema_version,
+            body,
+            body_bytes,
+        })
+    }
+}
+
+/// Whether an update carries a precondition
+fn has_precondition(req: &UpdateRequest) -> bool {
+    req.if_commit_id.is_some() || req.if_checksum.is_some()
+}
+
+/// Check an update's preconditions against the current stored version
+///
+/// The current version's commit identity is derived from its storage
+/// offset (see `read_view`); its checksum is CRC32 of the stored body.
+fn check_update_precondition(
+    req: &UpdateRequest,
+    doc_id: &str,
+    current_offset: u64,
+    storage_reader: &mut StorageReader,
+) -> ApiResult<()> {
+    if let Some(expected) = req.if_commit_id {
+        let current = storage_commit_id(current_offset).value();
+        if current != expected {
+            return Err(ApiError::conflict(format!(
+                "Document {} changed: commit_id is {}, expected {}",
+                doc_id, current, expected
+            )));
+        }
+    }
+
+    if let Some(expected) = req.if_checksum {
+        let record = storage_reader
+            .read_at(current_offset)
+            .map_err(ApiError::from_storage_error)?;
+        let current = compute_checksum(&record.document_body);
+        if current != expected {
+            return Err(ApiError::conflict(format!(
+                "Document {} changed: checksum is {}, expected {}",
+                doc_id, current, expected
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Extract `_id` from a transaction op document
+fn txn_document_id(op_index: usize, document: &Value) -> ApiResult<String> {
+    document
+        .get("_id")
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| ApiError::invalid_request(format!("Op {}: document missing _id", op_index)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::schema::{FieldDef, Schema};
+    use crate::observability::{MemoryAuditLog, SlowQueryConfig};
+    use crate::schema::{FieldDef, Schema, SchemaMigration};
     use serde_json::json;
     use std::collections::{HashMap, HashSet};
     use tempfile::TempDir;
@@ -498,11 +2327,12 @@ mod tests {
 
     #[test]
     fn test_insert_and_query_roundtrip() {
-        let (_temp, loader, mut wal, mut storage_w, mut storage_r, mut index) = setup_test_env();
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
 
         let handler = ApiHandler::new("users");
         let mut subsystems = Subsystems {
-            schema_loader: &loader,
+            schema_loader: &mut loader,
             wal_writer: &mut wal,
             storage_writer: &mut storage_w,
             storage_reader: &mut storage_r,
@@ -535,11 +2365,12 @@ mod tests {
 
     #[test]
     fn test_invalid_schema_rejected() {
-        let (_temp, loader, mut wal, mut storage_w, mut storage_r, mut index) = setup_test_env();
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
 
         let handler = ApiHandler::new("users");
         let mut subsystems = Subsystems {
-            schema_loader: &loader,
+            schema_loader: &mut loader,
             wal_writer: &mut wal,
             storage_writer: &mut storage_w,
             storage_reader: &mut storage_r,
@@ -560,11 +2391,12 @@ mod tests {
 
     #[test]
     fn test_unbounded_query_rejected() {
-        let (_temp, loader, mut wal, mut storage_w, mut storage_r, mut index) = setup_test_env();
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
 
         let handler = ApiHandler::new("users");
         let mut subsystems = Subsystems {
-            schema_loader: &loader,
+            schema_loader: &mut loader,
             wal_writer: &mut wal,
             storage_writer: &mut storage_w,
             storage_reader: &mut storage_r,
@@ -587,11 +2419,12 @@ mod tests {
 
     #[test]
     fn test_explain_returns_deterministic_plan() {
-        let (_temp, loader, mut wal, mut storage_w, mut storage_r, mut index) = setup_test_env();
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
 
         let handler = ApiHandler::new("users");
         let mut subsystems = Subsystems {
-            schema_loader: &loader,
+            schema_loader: &mut loader,
             wal_writer: &mut wal,
             storage_writer: &mut storage_w,
             storage_reader: &mut storage_r,
@@ -616,11 +2449,12 @@ mod tests {
     #[test]
     fn test_serialization_enforced() {
         // This test verifies the lock exists; actual blocking tested differently
-        let (_temp, loader, mut wal, mut storage_w, mut storage_r, mut index) = setup_test_env();
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
 
         let handler = ApiHandler::new("users");
         let mut subsystems = Subsystems {
-            schema_loader: &loader,
+            schema_loader: &mut loader,
             wal_writer: &mut wal,
             storage_writer: &mut storage_w,
             storage_reader: &mut storage_r,
@@ -654,11 +2488,12 @@ mod tests {
         // Corruption is surfaced when storage/WAL returns error
         // This is implicitly tested via pass-through errors
 
-        let (_temp, loader, mut wal, mut storage_w, mut storage_r, mut index) = setup_test_env();
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
 
         let handler = ApiHandler::new("users");
         let mut subsystems = Subsystems {
-            schema_loader: &loader,
+            schema_loader: &mut loader,
             wal_writer: &mut wal,
             storage_writer: &mut storage_w,
             storage_reader: &mut storage_r,
@@ -676,4 +2511,1821 @@ mod tests {
         let resp = handler.handle(insert_req, &mut subsystems);
         assert!(resp.is_success());
     }
+
+    #[test]
+    fn test_maintenance_mode_rejects_writes() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        handler.maintenance().enter(true, None);
+
+        let insert_req = r#"{
+            "op": "insert",
+            "schema_id": "users",
+            "schema_version": "v1",
+            "document": {"_id": "user_1", "name": "Alice", "age": 25}
+        }"#;
+        let resp = handler.handle(insert_req, &mut subsystems);
+        assert!(!resp.is_success());
+        assert!(resp.to_json().contains("AERO_MAINTENANCE_MODE"));
+        assert_eq!(subsystems.wal_writer.last_sequence_number(), 0);
+
+        // Reads still served
+        let query_req = r#"{
+            "op": "query",
+            "schema_id": "users",
+            "schema_version": "v1",
+            "filter": {"_id": {"$eq": "user_1"}},
+            "limit": 10
+        }"#;
+        assert!(handler.handle(query_req, &mut subsystems).is_success());
+
+        handler.maintenance().exit();
+        assert!(handler.handle(insert_req, &mut subsystems).is_success());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_full_wal_device_enters_read_only_mode() {
+        let (temp, mut loader, _wal, mut storage_w, mut storage_r, mut index) = setup_test_env();
+
+        // Writes to /dev/full fail with ENOSPC
+        let full_dir = temp.path().join("full");
+        std::fs::create_dir_all(full_dir.join("wal")).unwrap();
+        std::os::unix::fs::symlink("/dev/full", full_dir.join("wal").join("wal.log")).unwrap();
+        let mut wal = WalWriter::open(&full_dir).unwrap();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        let insert_req = r#"{
+            "op": "insert",
+            "schema_id": "users",
+            "schema_version": "v1",
+            "document": {"_id": "user_1", "name": "Alice", "age": 25}
+        }"#;
+        let resp = handler.handle(insert_req, &mut subsystems);
+        assert!(resp.to_json().contains("AERO_READ_ONLY"));
+        assert!(handler.maintenance().is_read_only());
+
+        // Later writes are refused at admission; reads are still served
+        let resp = handler.handle(insert_req, &mut subsystems);
+        assert!(resp.to_json().contains("AERO_READ_ONLY"));
+        let query_req = r#"{
+            "op": "query",
+            "schema_id": "users",
+            "schema_version": "v1",
+            "filter": {"_id": {"$eq": "user_1"}},
+            "limit": 10
+        }"#;
+        assert!(handler.handle(query_req, &mut subsystems).is_success());
+
+        // Exiting with the device still full trips the gate again
+        assert!(handler.maintenance().exit_read_only());
+        let resp = handler.handle(insert_req, &mut subsystems);
+        assert!(resp.to_json().contains("AERO_READ_ONLY"));
+    }
+
+    #[test]
+    fn test_insert_many_single_fsync_batch() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        let batch_req = r#"{
+            "op": "insert_many",
+            "schema_id": "users",
+            "schema_version": "v1",
+            "documents": [
+                {"_id": "user_1", "name": "Alice", "age": 25},
+                {"_id": "user_2", "name": "Bob", "age": 30},
+                {"_id": "user_3", "name": "Carol"}
+            ]
+        }"#;
+        let resp = handler.handle(batch_req, &mut subsystems);
+        assert!(resp.is_success(), "Batch insert should succeed");
+        assert_eq!(subsystems.wal_writer.last_sequence_number(), 3);
+
+        let offsets = subsystems.index_manager.lookup_pk("user_2");
+        assert_eq!(offsets.len(), 1);
+
+        let mut reader = StorageReader::open(subsystems.storage_writer.path()).unwrap();
+        let record = reader.read_at(offsets[0]).unwrap();
+        assert_eq!(record.document_id, "users:user_2");
+    }
+
+    #[test]
+    fn test_insert_many_all_or_nothing() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        // Second document violates the schema (missing name)
+        let invalid_req = r#"{
+            "op": "insert_many",
+            "schema_id": "users",
+            "schema_version": "v1",
+            "documents": [
+                {"_id": "user_1", "name": "Alice"},
+                {"_id": "user_2"}
+            ]
+        }"#;
+        assert!(!handler.handle(invalid_req, &mut subsystems).is_success());
+
+        // Duplicate IDs within the batch
+        let duplicate_req = r#"{
+            "op": "insert_many",
+            "schema_id": "users",
+            "schema_version": "v1",
+            "documents": [
+                {"_id": "user_1", "name": "Alice"},
+                {"_id": "user_1", "name": "Alicia"}
+            ]
+        }"#;
+        assert!(!handler.handle(duplicate_req, &mut subsystems).is_success());
+
+        assert_eq!(subsystems.wal_writer.last_sequence_number(), 0);
+        assert_eq!(subsystems.storage_writer.document_count(), 0);
+    }
+
+    #[test]
+    fn test_transaction_commits_all_ops() {
+        let (temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) = setup_test_env();
+
+        let handler = ApiHandler::new("users");
+        {
+            let mut subsystems = Subsystems {
+                schema_loader: &mut loader,
+                wal_writer: &mut wal,
+                storage_writer: &mut storage_w,
+                storage_reader: &mut storage_r,
+                index_manager: &mut index,
+            };
+            let setup = r#"{"op": "insert", "schema_id": "users", "schema_version": "v1", "document": {"_id": "user_0", "name": "Zed"}}"#;
+            assert!(handler.handle(setup, &mut subsystems).is_success());
+        }
+
+        // Reopen the reader so it sees the document written above
+        let mut storage_r = StorageReader::open_from_data_dir(temp.path()).unwrap();
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        // Insert then update the same document, and delete an existing one
+        let txn = r#"{
+            "op": "transaction",
+            "ops": [
+                {"op": "insert", "schema_id": "users", "schema_version": "v1", "document": {"_id": "user_1", "name": "Alice"}},
+                {"op": "update", "schema_id": "users", "schema_version": "v1", "document": {"_id": "user_1", "name": "Alicia"}},
+                {"op": "delete", "schema_id": "users", "document_id": "user_0"}
+            ]
+        }"#;
+        let response = handler.handle(txn, &mut subsystems);
+        assert!(response.is_success());
+        let json = response.to_json();
+        assert!(json.contains(r#""committed":3"#));
+        assert!(json.contains(r#""commit_id":1"#));
+
+        // Begin + 3 ops + Commit after the setup insert
+        assert_eq!(subsystems.wal_writer.last_sequence_number(), 6);
+        assert_eq!(subsystems.wal_writer.last_commit_id(), 1);
+        assert!(subsystems.index_manager.lookup_pk("user_0").is_empty());
+        assert!(!subsystems.index_manager.lookup_pk("user_1").is_empty());
+    }
+
+    #[test]
+    fn test_transaction_invalid_op_writes_nothing() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        // Last op deletes a document that does not exist
+        let txn = r#"{
+            "op": "transaction",
+            "ops": [
+                {"op": "insert", "schema_id": "users", "schema_version": "v1", "document": {"_id": "user_1", "name": "Alice"}},
+                {"op": "delete", "schema_id": "users", "document_id": "user_1"},
+                {"op": "delete", "schema_id": "users", "document_id": "user_1"}
+            ]
+        }"#;
+        assert!(!handler.handle(txn, &mut subsystems).is_success());
+
+        assert_eq!(subsystems.wal_writer.last_sequence_number(), 0);
+        assert_eq!(subsystems.wal_writer.last_commit_id(), 0);
+        assert_eq!(subsystems.storage_writer.document_count(), 0);
+        assert!(subsystems.index_manager.lookup_pk("user_1").is_empty());
+    }
+
+    #[test]
+    fn test_overload_sheds_low_priority_requests() {
+        use crate::api::admission::SheddingThresholds;
+
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let queue = Arc::new(
+            AdmissionQueue::new(SheddingThresholds {
+                operator: 2,
+                authenticated: 1,
+                anonymous: 1,
+            })
+            .unwrap(),
+        );
+        let handler = ApiHandler::new("users").with_admission_queue(Arc::clone(&queue));
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        // Simulate one request already queued
+        let _queued = queue.admit(PriorityClass::System).unwrap();
+
+        let insert_req = r#"{
+            "op": "insert",
+            "schema_id": "users",
+            "schema_version": "v1",
+            "document": {"_id": "user_1", "name": "Alice"}
+        }"#;
+        let resp =
+            handler.handle_with_priority(insert_req, PriorityClass::Anonymous, &mut subsystems);
+        assert!(resp.to_json().contains("AERO_OVERLOADED"));
+        assert_eq!(subsystems.wal_writer.last_sequence_number(), 0);
+
+        let resp =
+            handler.handle_with_priority(insert_req, PriorityClass::Operator, &mut subsystems);
+        assert!(resp.is_success());
+
+        let stats = queue.stats();
+        assert_eq!(stats.shed(PriorityClass::Anonymous), 1);
+        assert_eq!(stats.admitted(PriorityClass::Operator), 1);
+        assert_eq!(stats.depth, 1);
+    }
+
+    #[test]
+    fn test_read_view_sees_stable_snapshot() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        for (id, name) in [("user_1", "Alice"), ("user_2", "Bob")] {
+            let req = json!({
+                "op": "insert", "schema_id": "users", "schema_version": "v1",
+                "document": {"_id": id, "name": name, "age": 30}
+            });
+            assert!(handler
+                .handle(&req.to_string(), &mut subsystems)
+                .is_success());
+        }
+
+        // Read-your-writes: both inserts are inside the view
+        let handle = handler.begin_read(&subsystems);
+        let query = json!({
+            "op": "query", "schema_id": "users", "schema_version": "v1",
+            "filter": {"age": {"$gte": 18}}, "limit": 10, "read_view": handle.id()
+        })
+        .to_string();
+        let before = handler.handle(&query, &mut subsystems).to_json();
+        assert!(before.contains("Alice") && before.contains("Bob"));
+
+        // Writes after the view are not visible through it
+        let writes = [
+            json!({"op": "update", "schema_id": "users", "schema_version": "v1",
+                   "document": {"_id": "user_1", "name": "Alicia", "age": 31}}),
+            json!({"op": "delete", "schema_id": "users", "document_id": "user_2"}),
+            json!({"op": "insert", "schema_id": "users", "schema_version": "v1",
+                   "document": {"_id": "user_3", "name": "Carol", "age": 40}}),
+        ];
+        for req in writes {
+            assert!(handler
+                .handle(&req.to_string(), &mut subsystems)
+                .is_success());
+        }
+        assert_eq!(handler.handle(&query, &mut subsystems).to_json(), before);
+
+        // A new view sees the latest state
+        let latest = handler.begin_read(&subsystems);
+        let query_latest = query.replace(
+            &format!(r#""read_view":{}"#, handle.id()),
+            &format!(r#""read_view":{}"#, latest.id()),
+        );
+        let after = handler.handle(&query_latest, &mut subsystems).to_json();
+        assert!(after.contains("Alicia") && after.contains("Carol"));
+        assert!(!after.contains("Bob"));
+
+        // Ended views are rejected
+        assert!(handler.end_read(handle));
+        assert_eq!(handler.open_read_views(), 1);
+        let resp = handler.handle(&query, &mut subsystems).to_json();
+        assert!(resp.contains("AERO_INVALID_REQUEST"));
+    }
+
+    #[test]
+    fn test_read_only_transaction_spans_checkpoint() {
+        use crate::checkpoint::CheckpointManager;
+        use crate::snapshot::{GlobalExecutionLock, SnapshotManager};
+
+        let (temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) = setup_test_env();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+        let mut run = |req: Value, subsystems: &mut Subsystems<'_>| {
+            let response = handler.handle(&req.to_string(), subsystems);
+            assert!(response.is_success(), "{}", response.to_json());
+            serde_json::from_str::<Value>(&response.to_json()).unwrap()["data"].clone()
+        };
+
+        run(
+            json!({"op": "insert", "schema_id": "users", "schema_version": "v1",
+                   "document": {"_id": "user_1", "name": "Alice", "age": 30}}),
+            &mut subsystems,
+        );
+        let begun = run(json!({"op": "begin_read"}), &mut subsystems);
+        assert_eq!(begun["timeout_ms"], json!(300_000));
+        let txn = begun["read_view"].as_u64().unwrap();
+
+        let query = json!({
+            "op": "query", "schema_id": "users", "schema_version": "v1",
+            "filter": {"age": {"$gte": 18}}, "limit": 10, "read_view": txn
+        });
+        let before = run(query.clone(), &mut subsystems);
+        assert_eq!(before.as_array().unwrap().len(), 1);
+
+        // Later writes and a checkpoint leave the transaction's snapshot intact
+        run(
+            json!({"op": "insert", "schema_id": "users", "schema_version": "v1",
+                   "document": {"_id": "user_2", "name": "Bob", "age": 40}}),
+            &mut subsystems,
+        );
+        let data_dir = temp.path();
+        CheckpointManager::create_checkpoint(
+            data_dir,
+            &data_dir.join("data").join("documents.dat"),
+            subsystems.schema_loader.schema_dir(),
+            &SnapshotManager,
+            subsystems.wal_writer,
+            &GlobalExecutionLock::new(),
+        )
+        .unwrap();
+        assert_eq!(run(query.clone(), &mut subsystems), before);
+
+        let ended = run(json!({"op": "end_read", "read_view": txn}), &mut subsystems);
+        assert_eq!(ended, json!({"ended": true}));
+        assert_eq!(handler.open_read_views(), 0);
+        let resp = handler
+            .handle(&query.to_string(), &mut subsystems)
+            .to_json();
+        assert!(resp.contains("AERO_INVALID_REQUEST"));
+    }
+
+    #[test]
+    fn test_concurrent_transactions_first_committer_wins() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+        for id in ["user_1", "user_2"] {
+            let req = json!({"op": "insert", "schema_id": "users", "schema_version": "v1",
+                             "document": {"_id": id, "name": "Alice"}});
+            assert!(handler
+                .handle(&req.to_string(), &mut subsystems)
+                .is_success());
+        }
+        let txn = |view: &ReadViewHandle, id: &str, name: &str| {
+            json!({"op": "transaction", "read_view": view.id(), "ops": [
+                {"op": "update", "schema_id": "users", "schema_version": "v1",
+                 "document": {"_id": id, "name": name}}
+            ]})
+            .to_string()
+        };
+
+        // Both transactions read user_1 through their own view
+        let first = handler.begin_read(&subsystems);
+        let second = handler.begin_read(&subsystems);
+        let resp = handler.handle(&txn(&first, "user_1", "Alicia"), &mut subsystems);
+        assert!(resp.is_success(), "{}", resp.to_json());
+
+        let wal_before = subsystems.wal_writer.last_sequence_number();
+        let resp = handler
+            .handle(&txn(&second, "user_1", "Alina"), &mut subsystems)
+            .to_json();
+        assert!(resp.contains("AERO_SERIALIZATION_FAILURE"), "{}", resp);
+        assert_eq!(subsystems.wal_writer.last_sequence_number(), wal_before);
+
+        // Disjoint writes still commit; a retry from a fresh view succeeds
+        let resp = handler.handle(&txn(&second, "user_2", "Bob"), &mut subsystems);
+        assert!(resp.is_success(), "{}", resp.to_json());
+        let retry = handler.begin_read(&subsystems);
+        let resp = handler.handle(&txn(&retry, "user_1", "Alina"), &mut subsystems);
+        assert!(resp.is_success(), "{}", resp.to_json());
+    }
+
+    #[test]
+    fn test_query_as_of_commit_id() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+        let mut commit_of = |req: Value, subsystems: &mut Subsystems<'_>| {
+            let response = handler.handle(&req.to_string(), subsystems).to_json();
+            serde_json::from_str::<Value>(&response).unwrap()["data"]["commit_id"]
+                .as_u64()
+                .unwrap()
+        };
+        let write = |op: &str, name: &str| {
+            json!({"op": op, "schema_id": "users", "schema_version": "v1",
+                   "document": {"_id": "user_1", "name": name, "age": 30}})
+        };
+        let inserted = commit_of(write("insert", "Alice"), &mut subsystems);
+        let updated = commit_of(write("update", "Alicia"), &mut subsystems);
+        let delete = json!({"op": "delete", "schema_id": "users", "document_id": "user_1"});
+        assert!(handler
+            .handle(&delete.to_string(), &mut subsystems)
+            .is_success());
+
+        let as_of = |commit: u64| {
+            json!({"op": "query", "schema_id": "users", "schema_version": "v1",
+                   "filter": {"age": {"$gte": 18}}, "limit": 10, "as_of_commit_id": commit})
+            .to_string()
+        };
+        let resp = handler.handle(&as_of(inserted), &mut subsystems).to_json();
+        assert!(resp.contains("Alice\""), "{}", resp);
+        let resp = handler.handle(&as_of(updated), &mut subsystems).to_json();
+        assert!(resp.contains("Alicia"), "{}", resp);
+        let resp = handler
+            .handle(&as_of(updated - 1), &mut subsystems)
+            .to_json();
+        assert!(
+            resp.contains("Alice\"") && !resp.contains("Alicia"),
+            "{}",
+            resp
+        );
+        let latest = subsystems.storage_writer.current_offset();
+        let resp = handler.handle(&as_of(latest), &mut subsystems).to_json();
+        assert!(!resp.contains("Alic"), "{}", resp);
+
+        let resp = handler
+            .handle(&as_of(latest + 1), &mut subsystems)
+            .to_json();
+        assert!(resp.contains("AERO_INVALID_REQUEST"));
+        assert_eq!(handler.advance_gc_horizon(updated), updated);
+        let resp = handler.handle(&as_of(inserted), &mut subsystems).to_json();
+        assert!(resp.contains("AERO_SNAPSHOT_TOO_OLD"), "{}", resp);
+        let resp = handler.handle(&as_of(updated), &mut subsystems).to_json();
+        assert!(resp.contains("Alicia"), "{}", resp);
+    }
+
+    #[test]
+    fn test_idle_read_only_transaction_expires() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let handler = ApiHandler::new("users").with_read_view_timeout(Duration::ZERO);
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        let handle = handler.begin_read(&subsystems);
+        let query = json!({
+            "op": "count", "schema_id": "users", "schema_version": "v1",
+            "filter": {"age": {"$gte": 18}}, "limit": 10, "read_view": handle.id()
+        });
+        let resp = handler
+            .handle(&query.to_string(), &mut subsystems)
+            .to_json();
+        assert!(resp.contains("AERO_INVALID_REQUEST") && resp.contains("expired"));
+        assert!(!handler.end_read(handle));
+    }
+
+    #[test]
+    fn test_get_by_id() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        let insert_req = r#"{
+            "op": "insert",
+            "schema_id": "users",
+            "schema_version": "v1",
+            "document": {"_id": "user_1", "name": "Alice", "age": 25}
+        }"#;
+        assert!(handler.handle(insert_req, &mut subsystems).is_success());
+
+        let get_req = r#"{"op": "get", "schema_id": "users", "document_id": "user_1"}"#;
+        let resp = handler.handle(get_req, &mut subsystems);
+        assert!(resp.is_success());
+        assert!(resp.to_json().contains("Alice"));
+
+        let missing = r#"{"op": "get", "schema_id": "users", "document_id": "user_2"}"#;
+        let resp = handler.handle(missing, &mut subsystems);
+        assert!(resp.to_json().contains("AERO_NOT_FOUND"));
+
+        let delete_req = r#"{"op": "delete", "schema_id": "users", "document_id": "user_1"}"#;
+        assert!(handler.handle(delete_req, &mut subsystems).is_success());
+        let resp = handler.handle(get_req, &mut subsystems);
+        assert!(resp.to_json().contains("AERO_NOT_FOUND"));
+    }
+
+    #[test]
+    fn test_upsert_inserts_then_updates() {
+        let (temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) = setup_test_env();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        let upsert = |name: &str| {
+            json!({
+                "op": "upsert",
+                "schema_id": "users",
+                "schema_version": "v1",
+                "document": {"_id": "user_1", "name": name, "age": 25}
+            })
+            .to_string()
+        };
+
+        let resp = handler.handle(&upsert("Alice"), &mut subsystems).to_json();
+        assert!(resp.contains(r#""created":true"#));
+
+        let resp = handler.handle(&upsert("Alicia"), &mut subsystems).to_json();
+        assert!(resp.contains(r#""created":false"#));
+
+        let get_req = r#"{"op": "get", "schema_id": "users", "document_id": "user_1"}"#;
+        let resp = handler.handle(get_req, &mut subsystems).to_json();
+        assert!(resp.contains("Alicia"));
+
+        // One Insert and one Update record
+        let records = crate::wal::WalReader::open_from_data_dir(temp.path())
+            .unwrap()
+            .read_all()
+            .unwrap();
+        let types: Vec<_> = records.iter().map(|r| r.record_type).collect();
+        assert_eq!(types, vec![RecordType::Insert, RecordType::Update]);
+    }
+
+    #[test]
+    fn test_conditional_update_rejects_stale_version() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        let insert_req = r#"{
+            "op": "insert",
+            "schema_id": "users",
+            "schema_version": "v1",
+            "document": {"_id": "user_1", "name": "Alice", "age": 25}
+        }"#;
+        let resp: Value =
+            serde_json::from_str(&handler.handle(insert_req, &mut subsystems).to_json()).unwrap();
+        let commit_id = resp["data"]["commit_id"].as_u64().unwrap();
+
+        let update = |name: &str, precondition: Value| {
+            let mut req = json!({
+                "op": "update",
+                "schema_id": "users",
+                "schema_version": "v1",
+                "document": {"_id": "user_1", "name": name, "age": 25}
+            });
+            req.as_object_mut()
+                .unwrap()
+                .extend(precondition.as_object().unwrap().clone());
+            req.to_string()
+        };
+
+        // Matching version: accepted
+        let resp = handler.handle(
+            &update("Alicia", json!({"if_commit_id": commit_id})),
+            &mut subsystems,
+        );
+        assert!(resp.is_success());
+
+        // Same precondition again: document has changed since
+        let resp = handler
+            .handle(
+                &update("Alina", json!({"if_commit_id": commit_id})),
+                &mut subsystems,
+            )
+            .to_json();
+        assert!(resp.contains("AERO_CONFLICT"));
+
+        // Checksum precondition against the stored body
+        let stored =
+            serde_json::to_vec(&json!({"_id": "user_1", "name": "Alicia", "age": 25})).unwrap();
+        let resp = handler.handle(
+            &update("Alina", json!({"if_checksum": compute_checksum(&stored)})),
+            &mut subsystems,
+        );
+        assert!(resp.is_success());
+        let resp = handler
+            .handle(
+                &update("Aline", json!({"if_checksum": compute_checksum(&stored)})),
+                &mut subsystems,
+            )
+            .to_json();
+        assert!(resp.contains("AERO_CONFLICT"));
+    }
+
+    #[test]
+    fn test_query_in_prefix_and_residual_filters() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        for (id, name, age) in [("u1", "Alice", 20), ("u2", "Bob", 30), ("u3", "Carol", 40)] {
+            let req = json!({
+                "op": "insert", "schema_id": "users", "schema_version": "v1",
+                "document": {"_id": id, "name": name, "age": age}
+            });
+            assert!(handler
+                .handle(&req.to_string(), &mut subsystems)
+                .is_success());
+        }
+
+        let mut query = |filter: Value| {
+            let req = json!({
+                "op": "query", "schema_id": "users", "schema_version": "v1",
+                "filter": filter, "limit": 10
+            });
+            handler.handle(&req.to_string(), &mut subsystems).to_json()
+        };
+
+        let resp = query(json!({"age": {"$in": [20, 40], "$ne": 40}}));
+        assert!(resp.contains("Alice"));
+        assert!(!resp.contains("Bob") && !resp.contains("Carol"));
+
+        let resp = query(json!({"_id": {"$in": ["u2", "u3"]}}));
+        assert!(resp.contains("Bob") && resp.contains("Carol"));
+
+        let resp = query(json!({"age": {"$in": 20}}));
+        assert!(resp.contains("AERO_INVALID_REQUEST"));
+    }
+
+    #[test]
+    fn test_query_uses_composite_index() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, index) = setup_test_env();
+        let mut index = index.with_composite_index(["name", "age"]);
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        for (id, name, age) in [("u1", "Alice", 20), ("u2", "Alice", 30), ("u3", "Bob", 30)] {
+            let req = json!({
+                "op": "insert", "schema_id": "users", "schema_version": "v1",
+                "document": {"_id": id, "name": name, "age": age}
+            });
+            assert!(handler
+                .handle(&req.to_string(), &mut subsystems)
+                .is_success());
+        }
+
+        let req = json!({
+            "op": "query", "schema_id": "users", "schema_version": "v1",
+            "filter": {"name": {"$eq": "Alice"}, "age": {"$eq": 30}}, "limit": 10
+        });
+        let resp = handler.handle(&req.to_string(), &mut subsystems).to_json();
+        assert!(resp.contains("u2"));
+        assert!(!resp.contains("u1") && !resp.contains("u3"));
+
+        let req = json!({
+            "op": "explain", "schema_id": "users", "schema_version": "v1",
+            "filter": {"name": {"$eq": "Alice"}, "age": {"$eq": 30}}, "limit": 10
+        });
+        let resp = handler.handle(&req.to_string(), &mut subsystems).to_json();
+        assert!(resp.contains("CompositeEquality"));
+        assert!(resp.contains("name,age"));
+        assert!(resp.contains("\"bound_fields\":2"));
+    }
+
+    #[test]
+    fn test_explain_prefers_selective_index() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+        index.add_field_index("name");
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        for (id, name) in [
+            ("u1", "Alice"),
+            ("u2", "Bob"),
+            ("u3", "Carol"),
+            ("u4", "Dan"),
+        ] {
+            let req = json!({
+                "op": "insert", "schema_id": "users", "schema_version": "v1",
+                "document": {"_id": id, "name": name, "age": 30}
+            });
+            assert!(handler
+                .handle(&req.to_string(), &mut subsystems)
+                .is_success());
+        }
+
+        // "age" sorts first, but every document shares its value
+        let req = json!({
+            "op": "explain", "schema_id": "users", "schema_version": "v1",
+            "filter": {"age": {"$eq": 30}, "name": {"$eq": "Carol"}}, "limit": 10
+        });
+        let resp: Value =
+            serde_json::from_str(&handler.handle(&req.to_string(), &mut subsystems).to_json())
+                .unwrap();
+        assert_eq!(resp["data"]["chosen_index"], "name");
+        assert_eq!(
+            resp["data"]["index_costs"],
+            json!([
+                {"field": "name", "estimated_rows": 1},
+                {"field": "age", "estimated_rows": 4}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_query_stats_opt_in() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let metrics = Arc::new(MetricsRegistry::new());
+        let handler = ApiHandler::new("users").with_metrics(Arc::clone(&metrics));
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        for id in ["u1", "u2"] {
+            let req = json!({
+                "op": "insert", "schema_id": "users", "schema_version": "v1",
+                "document": {"_id": id, "name": "User", "age": 30}
+            });
+            assert!(handler
+                .handle(&req.to_string(), &mut subsystems)
+                .is_success());
+        }
+
+        let mut req = json!({
+            "op": "query", "schema_id": "users", "schema_version": "v1",
+            "filter": {"_id": {"$eq": "u2"}}, "limit": 10
+        });
+        let resp = handler.handle(&req.to_string(), &mut subsystems).to_json();
+        assert!(!resp.contains("\"stats\""));
+
+        req["stats"] = json!(true);
+        let resp: Value =
+            serde_json::from_str(&handler.handle(&req.to_string(), &mut subsystems).to_json())
+                .unwrap();
+        assert_eq!(resp["data"]["documents"][0]["_id"], "u2");
+        let stats = &resp["data"]["stats"];
+        assert_eq!(stats["index_candidates"], 1);
+        assert_eq!(stats["documents_scanned"], 1);
+        assert_eq!(stats["checksum_validations"], 1);
+        assert_eq!(stats["filter_evaluations"], 1);
+        assert!(stats["elapsed_micros"].is_u64());
+
+        // Both queries were counted, with or without the flag
+        assert_eq!(metrics.snapshot().documents_scanned, 2);
+    }
+
+    #[test]
+    fn test_slow_queries_recorded() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let slow_queries = Arc::new(SlowQueryLog::new(&SlowQueryConfig::new(
+            std::time::Duration::ZERO,
+        )));
+        let handler = ApiHandler::new("users").with_slow_query_log(Arc::clone(&slow_queries));
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        let req = json!({
+            "op": "insert", "schema_id": "users", "schema_version": "v1",
+            "document": {"_id": "u1", "name": "User", "age": 30}
+        });
+        assert!(handler
+            .handle(&req.to_string(), &mut subsystems)
+            .is_success());
+        assert!(slow_queries.recent(10).is_empty());
+
+        let req = json!({
+            "op": "query", "schema_id": "users", "schema_version": "v1",
+            "filter": {"age": {"$gte": 18}}, "limit": 10
+        });
+        assert!(handler
+            .handle(&req.to_string(), &mut subsystems)
+            .is_success());
+
+        let entries = slow_queries.recent(10);
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0].query;
+        assert_eq!(entry.plan.chosen_index, "age");
+        assert_eq!(entry.predicates[0]["op"], "gte");
+        assert_eq!(entry.rows, 1);
+        assert!(entry.timing.total_micros >= entry.timing.execution_micros);
+    }
+
+    #[test]
+    fn test_mutations_audited_with_actor() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let audit = Arc::new(MemoryAuditLog::new());
+        let handler = ApiHandler::new("users").with_audit_log(audit.clone());
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+        let user_id = uuid::Uuid::new_v4();
+        let actor = AuthContext::authenticated(user_id);
+
+        for schema_id in ["users", "unknown"] {
+            let insert = json!({
+                "op": "insert", "schema_id": schema_id, "schema_version": "v1",
+                "document": {"_id": "u1", "name": "User", "age": 30}
+            });
+            handler.handle_as(
+                &insert.to_string(),
+                PriorityClass::Authenticated,
+                &actor,
+                &mut subsystems,
+            );
+        }
+        let query = json!({
+            "op": "query", "schema_id": "users", "schema_version": "v1",
+            "filter": {"_id": {"$eq": "u1"}}, "limit": 1
+        });
+        assert!(handler
+            .handle(&query.to_string(), &mut subsystems)
+            .is_success());
+
+        // Reads are not audited; the unknown schema is a rejection
+        let records = audit.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].outcome, AuditOutcome::Success);
+        assert_eq!(records[0].command_name.as_deref(), Some("insert"));
+        assert_eq!(records[0].operator_id, Some(user_id.to_string()));
+        assert_eq!(records[0].resource.as_deref(), Some("users"));
+        assert_eq!(records[0].document_ids, vec!["u1".to_string()]);
+        assert_eq!(records[1].action, AuditAction::CommandRejected);
+        assert!(records[1].error_message.is_some());
+    }
+
+    use crate::api::{ChangeKind, DocumentChange};
+
+    #[derive(Default)]
+    struct RecordingSink(Mutex<Vec<Vec<DocumentChange>>>);
+
+    impl ChangeSink for RecordingSink {
+        fn committed(&self, changes: &[DocumentChange]) {
+            self.0.lock().unwrap().push(changes.to_vec());
+        }
+    }
+
+    #[test]
+    fn test_committed_changes_reported() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let sink = Arc::new(RecordingSink::default());
+        let handler = ApiHandler::new("users").with_change_sink(sink.clone());
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+        let doc = json!({"_id": "u1", "name": "User", "age": 30});
+        for request in [
+            json!({"op": "insert", "schema_id": "users", "schema_version": "v1", "document": doc}),
+            // Rejected: unknown schema
+            json!({"op": "insert", "schema_id": "unknown", "schema_version": "v1", "document": doc}),
+            json!({"op": "upsert", "schema_id": "users", "schema_version": "v1", "document": doc}),
+            json!({"op": "delete", "schema_id": "users", "document_id": "u1"}),
+        ] {
+            handler.handle(&request.to_string(), &mut subsystems);
+        }
+
+        let writes = sink.0.lock().unwrap();
+        let kinds: Vec<_> = writes.iter().map(|changes| changes[0].kind).collect();
+        assert_eq!(
+            kinds,
+            vec![ChangeKind::Insert, ChangeKind::Update, ChangeKind::Delete]
+        );
+        assert_eq!(writes[0][0].document, Some(doc));
+        assert_eq!(writes[2][0].document_id, "u1");
+        assert_eq!(writes[2][0].document, None);
+    }
+
+    #[test]
+    fn test_encrypted_fields() {
+        let (temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) = setup_test_env();
+        let mut fields = HashMap::new();
+        fields.insert("_id".to_string(), FieldDef::required_string());
+        fields.insert("name".to_string(), FieldDef::required_string());
+        fields.insert("ssn".to_string(), FieldDef::required_string().encrypted());
+        loader
+            .register(Schema::new("patients", "v1", fields))
+            .unwrap();
+
+        let keys = Arc::new(FieldKeyring::new());
+        keys.rotate("k1").unwrap();
+        let handler = ApiHandler::new("users").with_field_keys(keys.clone());
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+        let mut run = |handler: &ApiHandler, req: Value| -> Value {
+            serde_json::from_str(&handler.handle(&req.to_string(), &mut subsystems).to_json())
+                .unwrap()
+        };
+
+        let insert = |id: &str, ssn: &str| {
+            json!({
+                "op": "insert", "schema_id": "patients", "schema_version": "v1",
+                "document": {"_id": id, "name": "Ada", "ssn": ssn}
+            })
+        };
+        assert_eq!(run(&handler, insert("p1", "111-11-1111"))["status"], "ok");
+        // Values sealed under a retired key stay readable
+        keys.rotate("k2").unwrap();
+        assert_eq!(run(&handler, insert("p2", "222-22-2222"))["status"], "ok");
+
+        let get = json!({"op": "get", "schema_id": "patients", "document_id": "p1"});
+        assert_eq!(run(&handler, get.clone())["data"]["ssn"], "111-11-1111");
+        let query = json!({
+            "op": "query", "schema_id": "patients", "schema_version": "v1",
+            "filter": {"_id": {"$eq": "p2"}}, "limit": 10
+        });
+        let resp = run(&handler, query);
+        assert_eq!(resp["data"][0]["ssn"], "222-22-2222");
+
+        // Neither the WAL nor storage holds the plaintext
+        for file in ["wal/wal.log", "data/documents.dat"] {
+            let bytes = std::fs::read(temp.path().join(file)).unwrap();
+            let text = String::from_utf8_lossy(&bytes);
+            assert!(text.contains("\"kid\":\"k2\""), "{}", file);
+            assert!(!text.contains("111-11-1111") && !text.contains("222-22-2222"));
+        }
+
+        // Without keys, encrypted fields are neither written nor read
+        let keyless = ApiHandler::new("users");
+        let resp = run(&keyless, insert("p3", "333-33-3333"));
+        assert_eq!(resp["code"], "AERO_ENCRYPTION_KEY_UNAVAILABLE");
+        let resp = run(&keyless, get);
+        assert_eq!(resp["code"], "AERO_ENCRYPTION_KEY_UNAVAILABLE");
+
+        // Indexes would hold ciphertext
+        let resp = run(&handler, json!({"op": "create_index", "field": "ssn"}));
+        assert_eq!(resp["status"], "error");
+    }
+
+    #[test]
+    fn test_count_and_aggregate() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        for (id, age) in [("u1", 20), ("u2", 30), ("u3", 40)] {
+            let req = json!({
+                "op": "insert", "schema_id": "users", "schema_version": "v1",
+                "document": {"_id": id, "name": "User", "age": age}
+            });
+            assert!(handler
+                .handle(&req.to_string(), &mut subsystems)
+                .is_success());
+        }
+
+        let filter = json!({"age": {"$gte": 25}});
+        let req = json!({
+            "op": "count", "schema_id": "users", "schema_version": "v1",
+            "filter": filter, "limit": 100
+        });
+        let resp = handler.handle(&req.to_string(), &mut subsystems).to_json();
+        assert!(resp.contains("\"count\":2"));
+
+        let req = json!({
+            "op": "aggregate", "schema_id": "users", "schema_version": "v1",
+            "function": "sum", "field": "age", "filter": filter, "limit": 100
+        });
+        let resp = handler.handle(&req.to_string(), &mut subsystems).to_json();
+        assert!(resp.contains("\"value\":70"));
+
+        let req = json!({
+            "op": "aggregate", "schema_id": "users", "schema_version": "v1",
+            "function": "min", "field": "age", "filter": filter, "limit": 100
+        });
+        let resp = handler.handle(&req.to_string(), &mut subsystems).to_json();
+        assert!(resp.contains("\"value\":30"));
+
+        // Same boundedness rules as queries
+        let req = json!({
+            "op": "count", "schema_id": "users", "schema_version": "v1",
+            "filter": {"name": {"$eq": "User"}}, "limit": 100
+        });
+        let resp = handler.handle(&req.to_string(), &mut subsystems);
+        assert!(!resp.is_success());
+    }
+
+    #[test]
+    fn test_insert_materializes_field_defaults() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let mut fields = HashMap::new();
+        fields.insert("_id".to_string(), FieldDef::required_string());
+        fields.insert("name".to_string(), FieldDef::optional_string().nullable());
+        fields.insert("age".to_string(), FieldDef::optional_int());
+        fields.insert(
+            "active".to_string(),
+            FieldDef::required_bool().with_default(json!(true)),
+        );
+        loader
+            .register(Schema::new("users", "v2", fields).non_strict())
+            .unwrap();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        let req = json!({
+            "op": "insert", "schema_id": "users", "schema_version": "v2",
+            "document": {"_id": "u1", "name": null, "age": 30}
+        });
+        assert!(handler
+            .handle(&req.to_string(), &mut subsystems)
+            .is_success());
+
+        // The default is part of the stored document
+        let offset = *subsystems.index_manager.lookup_pk("u1").last().unwrap();
+        let record = subsystems.storage_reader.read_at(offset).unwrap();
+        let stored: Value = serde_json::from_slice(&record.document_body).unwrap();
+        assert_eq!(
+            stored,
+            json!({"_id": "u1", "name": null, "age": 30, "active": true})
+        );
+
+        // Strict schemas still reject nulls
+        let req = json!({
+            "op": "insert", "schema_id": "users", "schema_version": "v1",
+            "document": {"_id": "u2", "name": null}
+        });
+        assert!(!handler
+            .handle(&req.to_string(), &mut subsystems)
+            .is_success());
+    }
+
+    #[test]
+    fn test_query_upgrades_older_versions_at_read_time() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let mut fields = HashMap::new();
+        fields.insert("_id".to_string(), FieldDef::required_string());
+        fields.insert("full_name".to_string(), FieldDef::required_string());
+        fields.insert("age".to_string(), FieldDef::optional_int());
+        fields.insert("active".to_string(), FieldDef::required_bool());
+        loader.register(Schema::new("users", "v2", fields)).unwrap();
+        loader
+            .register_migration(
+                SchemaMigration::new("users", "v1", "v2")
+                    .rename_field("name", "full_name")
+                    .add_field_with_default("active", json!(true)),
+            )
+            .unwrap();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        let req = json!({
+            "op": "insert", "schema_id": "users", "schema_version": "v1",
+            "document": {"_id": "u1", "name": "Alice", "age": 30}
+        });
+        assert!(handler
+            .handle(&req.to_string(), &mut subsystems)
+            .is_success());
+        let req = json!({
+            "op": "insert", "schema_id": "users", "schema_version": "v2",
+            "document": {"_id": "u2", "full_name": "Bob", "age": 40, "active": false}
+        });
+        assert!(handler
+            .handle(&req.to_string(), &mut subsystems)
+            .is_success());
+
+        let mut query = |version: &str| {
+            let req = json!({
+                "op": "query", "schema_id": "users", "schema_version": version,
+                "filter": {"age": {"$gte": 0}}, "limit": 10
+            });
+            handler.handle(&req.to_string(), &mut subsystems).to_json()
+        };
+
+        // v1 document is served under v2 with the rename and default applied
+        let resp = query("v2");
+        assert!(resp.contains("\"full_name\":\"Alice\""));
+        assert!(resp.contains("\"active\":true"));
+        assert!(resp.contains("Bob"));
+
+        // Newer documents are never downgraded
+        let resp = query("v1");
+        assert!(resp.contains("Alice"));
+        assert!(!resp.contains("Bob"));
+    }
+
+    #[test]
+    fn test_schema_catalog_operations() {
+        let (temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) = setup_test_env();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        let schema = |version: &str, extra: Option<&str>| {
+            let mut fields = json!({"_id": {"type": "string", "required": true}});
+            if let Some(field) = extra {
+                fields[field] = json!({"type": "int", "required": false});
+            }
+            json!({"schema_id": "orders", "schema_version": version, "fields": fields})
+        };
+        let mut run = |req: Value| handler.handle(&req.to_string(), &mut subsystems).to_json();
+
+        let resp = run(json!({"op": "create_schema", "schema": schema("v1", None)}));
+        assert!(resp.contains("\"status\":\"ok\""), "{}", resp);
+        let resp = run(json!({"op": "create_schema", "schema": schema("v2", None)}));
+        assert!(resp.contains("AERO_CONFLICT"));
+        let resp = run(json!({"op": "alter_schema", "schema": schema("v2", Some("total"))}));
+        assert!(resp.contains("\"status\":\"ok\""), "{}", resp);
+        let resp = run(json!({"op": "alter_schema", "schema": {
+            "schema_id": "missing", "schema_version": "v1",
+            "fields": {"_id": {"type": "string", "required": true}}
+        }}));
+        assert!(resp.contains("AERO_UNKNOWN_SCHEMA"));
+
+        let resp = run(json!({
+            "op": "insert", "schema_id": "orders", "schema_version": "v1",
+            "document": {"_id": "o1"}
+        }));
+        assert!(resp.contains("\"status\":\"ok\""), "{}", resp);
+
+        let resp = run(json!({
+            "op": "deprecate_schema", "schema_id": "orders", "schema_version": "v1"
+        }));
+        assert!(resp.contains("\"deprecated\":true"));
+        let resp = run(json!({
+            "op": "insert", "schema_id": "orders", "schema_version": "v1",
+            "document": {"_id": "o2"}
+        }));
+        assert!(resp.contains("AERO_SCHEMA_DEPRECATED"));
+
+        let resp = run(json!({"op": "list_schemas", "schema_id": "orders"}));
+        let listed: Value = serde_json::from_str(&resp).unwrap();
+        let versions: Vec<_> = listed["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| (s["schema_version"].clone(), s["deprecated"].clone()))
+            .collect();
+        assert_eq!(
+            versions,
+            vec![(json!("v1"), json!(true)), (json!("v2"), json!(false))]
+        );
+
+        // Schema changes are WAL records, ahead of the document they govern
+        let records = crate::wal::WalReader::open_from_data_dir(temp.path())
+            .unwrap()
+            .read_all()
+            .unwrap();
+        let types: Vec<_> = records.iter().map(|r| r.record_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                RecordType::SchemaDdl,
+                RecordType::SchemaDdl,
+                RecordType::Insert,
+                RecordType::SchemaDdl
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unique_field_rejects_duplicates_before_wal() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, index) = setup_test_env();
+        let mut index = index.with_unique_field("name");
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        let insert = |id: &str, name: &str| {
+            json!({
+                "op": "insert", "schema_id": "users", "schema_version": "v1",
+                "document": {"_id": id, "name": name}
+            })
+            .to_string()
+        };
+        assert!(handler
+            .handle(&insert("u1", "Alice"), &mut subsystems)
+            .is_success());
+        assert!(handler
+            .handle(&insert("u2", "Bob"), &mut subsystems)
+            .is_success());
+        let seq = subsystems.wal_writer.last_sequence_number();
+
+        let resp = handler.handle(&insert("u3", "Alice"), &mut subsystems);
+        assert!(resp.to_json().contains("AERO_CONSTRAINT_UNIQUE"));
+
+        let update = json!({
+            "op": "update", "schema_id": "users", "schema_version": "v1",
+            "document": {"_id": "u2", "name": "Alice"}
+        });
+        let resp = handler.handle(&update.to_string(), &mut subsystems);
+        assert!(resp.to_json().contains("AERO_CONSTRAINT_UNIQUE"));
+
+        let batch = json!({
+            "op": "insert_many", "schema_id": "users", "schema_version": "v1",
+            "documents": [{"_id": "u4", "name": "Carol"}, {"_id": "u5", "name": "Carol"}]
+        });
+        let resp = handler.handle(&batch.to_string(), &mut subsystems);
+        assert!(resp.to_json().contains("AERO_CONSTRAINT_UNIQUE"));
+        assert_eq!(subsystems.wal_writer.last_sequence_number(), seq);
+
+        // A transaction may move a value from one document to another
+        let txn = json!({
+            "op": "transaction",
+            "ops": [
+                {"op": "update", "schema_id": "users", "schema_version": "v1",
+                 "document": {"_id": "u1", "name": "Alicia"}},
+                {"op": "insert", "schema_id": "users", "schema_version": "v1",
+                 "document": {"_id": "u3", "name": "Alice"}}
+            ]
+        });
+        assert!(handler
+            .handle(&txn.to_string(), &mut subsystems)
+            .is_success());
+        assert!(handler
+            .handle(&insert("u6", "Alicia"), &mut subsystems)
+            .to_json()
+            .contains("AERO_CONSTRAINT_UNIQUE"));
+    }
+
+    #[test]
+    fn test_collections_are_routed_and_isolated() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        let unknown = json!({"op": "get", "collection": "admins",
+                             "schema_id": "users", "document_id": "user_1"});
+        let resp = handler.handle(&unknown.to_string(), &mut subsystems);
+        assert!(resp.to_json().contains("AERO_UNKNOWN_COLLECTION"));
+
+        let create = r#"{"op": "create_collection", "collection": "admins", "schema_id": "users"}"#;
+        assert!(handler.handle(create, &mut subsystems).is_success());
+        assert!(handler
+            .handle(create, &mut subsystems)
+            .to_json()
+            .contains("AERO_CONFLICT"));
+
+        // The same _id lives independently in each collection
+        for (collection, name) in [("users", "Alice"), ("admins", "Root")] {
+            let req = json!({
+                "op": "insert", "collection": collection, "schema_id": "users",
+                "schema_version": "v1", "document": {"_id": "user_1", "name": name, "age": 30}
+            });
+            assert!(handler
+                .handle(&req.to_string(), &mut subsystems)
+                .is_success());
+        }
+
+        let get = json!({"op": "get", "collection": "admins",
+                         "schema_id": "users", "document_id": "user_1"});
+        let resp = handler.handle(&get.to_string(), &mut subsystems).to_json();
+        assert!(resp.contains("Root") && !resp.contains("Alice"));
+
+        let query = json!({"op": "query", "schema_id": "users", "schema_version": "v1",
+                           "filter": {"age": {"$gte": 0}}, "limit": 10});
+        let resp = handler
+            .handle(&query.to_string(), &mut subsystems)
+            .to_json();
+        assert!(resp.contains("Alice") && !resp.contains("Root"));
+
+        let handle = handler.begin_read(&subsystems);
+        let mut scoped = query.clone();
+        scoped["collection"] = json!("admins");
+        scoped["read_view"] = json!(handle.id());
+        let resp = handler
+            .handle(&scoped.to_string(), &mut subsystems)
+            .to_json();
+        assert!(resp.contains("Root") && !resp.contains("Alice"));
+
+        // A collection only stores the schema it was created for
+        let wrong = json!({"op": "get", "collection": "admins",
+                           "schema_id": "orders", "document_id": "o1"});
+        let resp = handler.handle(&wrong.to_string(), &mut subsystems);
+        assert!(resp.to_json().contains("AERO_INVALID_REQUEST"));
+
+        let resp = handler
+            .handle(r#"{"op": "list_collections"}"#, &mut subsystems)
+            .to_json();
+        assert!(resp.contains(r#""collection":"admins","schema_id":"users""#));
+        assert!(resp.contains(r#""collection":"users""#));
+    }
+
+    #[test]
+    fn test_truncate_and_drop_collection() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        let create = r#"{"op": "create_collection", "collection": "admins", "schema_id": "users"}"#;
+        assert!(handler.handle(create, &mut subsystems).is_success());
+        for (collection, id) in [("users", "u1"), ("admins", "a1"), ("admins", "a2")] {
+            let req = json!({
+                "op": "insert", "collection": collection, "schema_id": "users",
+                "schema_version": "v1", "document": {"_id": id, "name": id, "age": 30}
+            });
+            assert!(handler
+                .handle(&req.to_string(), &mut subsystems)
+                .is_success());
+        }
+
+        // Unconfirmed requests are rejected before anything is written
+        let wal_before = subsystems.wal_writer.next_sequence_number();
+        let unconfirmed = r#"{"op": "truncate_collection", "collection": "admins"}"#;
+        assert!(!handler.handle(unconfirmed, &mut subsystems).is_success());
+        assert_eq!(subsystems.wal_writer.next_sequence_number(), wal_before);
+
+        let truncate = r#"{"op": "truncate_collection", "collection": "admins", "confirm": true}"#;
+        let resp = handler.handle(truncate, &mut subsystems).to_json();
+        assert!(resp.contains(r#""deleted":2"#), "{}", resp);
+
+        let query = json!({"op": "count", "collection": "admins", "schema_id": "users",
+                           "schema_version": "v1", "filter": {"age": {"$gte": 0}}, "limit": 10});
+        let resp = handler
+            .handle(&query.to_string(), &mut subsystems)
+            .to_json();
+        assert!(resp.contains(r#""count":0"#));
+
+        // Other collections are untouched
+        let get = r#"{"op": "get", "schema_id": "users", "document_id": "u1"}"#;
+        assert!(handler.handle(get, &mut subsystems).is_success());
+
+        let drop_default = r#"{"op": "drop_collection", "collection": "users", "confirm": true}"#;
+        assert!(!handler.handle(drop_default, &mut subsystems).is_success());
+
+        let drop = r#"{"op": "drop_collection", "collection": "admins", "confirm": true}"#;
+        let resp = handler.handle(drop, &mut subsystems).to_json();
+        assert!(resp.contains(r#""dropped":true"#), "{}", resp);
+        assert!(subsystems
+            .schema_loader
+            .collection_schema("admins")
+            .is_none());
+        let resp = handler
+            .handle(&query.to_string(), &mut subsystems)
+            .to_json();
+        assert!(resp.contains("AERO_UNKNOWN_COLLECTION"));
+    }
+
+    #[test]
+    fn test_create_index_backfills_existing_documents() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let handler = ApiHandler::new("users");
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+
+        let create = r#"{"op": "create_collection", "collection": "admins", "schema_id": "users"}"#;
+        assert!(handler.handle(create, &mut subsystems).is_success());
+        for (collection, id, name) in [
+            ("users", "u1", "alice"),
+            ("users", "u2", "bob"),
+            ("admins", "a1", "alice"),
+        ] {
+            let req = json!({
+                "op": "insert", "collection": collection, "schema_id": "users",
+                "schema_version": "v1", "document": {"_id": id, "name": name, "age": 30}
+            });
+            assert!(handler
+                .handle(&req.to_string(), &mut subsystems)
+                .is_success());
+        }
+        let delete = r#"{"op": "delete", "schema_id": "users", "document_id": "u2"}"#;
+        assert!(handler.handle(delete, &mut subsystems).is_success());
+
+        let resp = handler
+            .handle(
+                r#"{"op": "create_index", "field": "name"}"#,
+                &mut subsystems,
+            )
+            .to_json();
+        assert!(resp.contains(r#""backfilled":2"#), "{}", resp);
+        assert!(subsystems.schema_loader.index_fields().any(|f| f == "name"));
+
+        // Already indexed
+        let resp = handler
+            .handle(
+                r#"{"op": "create_index", "field": "name"}"#,
+                &mut subsystems,
+            )
+            .to_json();
+        assert!(resp.contains("AERO_CONFLICT"), "{}", resp);
+
+        let explain = json!({
+            "op": "explain", "schema_id": "users", "schema_version": "v1",
+            "filter": {"name": {"$eq": "alice"}}, "limit": 10
+        });
+        let resp = handler
+            .handle(&explain.to_string(), &mut subsystems)
+            .to_json();
+        assert!(resp.contains(r#""chosen_index":"name""#), "{}", resp);
+
+        for collection in ["users", "admins"] {
+            let query = json!({
+                "op": "count", "collection": collection, "schema_id": "users",
+                "schema_version": "v1", "filter": {"name": {"$eq": "alice"}}, "limit": 10
+            });
+            let resp = handler
+                .handle(&query.to_string(), &mut subsystems)
+                .to_json();
+            assert!(resp.contains(r#""count":1"#), "{}", resp);
+        }
+    }
+
+    /// Response to `request` made by `actor`
+    fn respond_as(
+        handler: &ApiHandler,
+        actor: &AuthContext,
+        request: Value,
+        sys: &mut Subsystems<'_>,
+    ) -> Response {
+        handler.handle_as(
+            &request.to_string(),
+            PriorityClass::Authenticated,
+            actor,
+            sys,
+        )
+    }
+
+    fn error_code(response: &Response) -> Option<&str> {
+        match response {
+            Response::Error(e) => Some(e.code.as_str()),
+            Response::Success(_) => None,
+        }
+    }
+
+    /// Records the operations that reach it
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Middleware for Recorder {
+        fn process<'a>(
+            &'a self,
+            op: &'a crate::core::Operation,
+            ctx: &'a mut RequestContext,
+            next: Next<'a>,
+        ) -> std::pin::Pin<
+            Box<
+                dyn std::future::Future<Output = crate::core::pipeline::OperationResult>
+                    + Send
+                    + 'a,
+            >,
+        > {
+            Box::pin(async move {
+                self.0.lock().unwrap().push(format!(
+                    "{} {}",
+                    op.name(),
+                    op.collection().unwrap_or("-")
+                ));
+                next.run(op, ctx).await
+            })
+        }
+    }
+
+    #[test]
+    fn test_requests_run_through_core_middleware() {
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, mut index) =
+            setup_test_env();
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let handler = ApiHandler::new("users")
+            .with_middleware(crate::core::middleware::auth::AuthMiddleware::new())
+            .with_middleware(Recorder(seen.clone()));
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+        let insert = json!({
+            "op": "insert", "schema_id": "users", "schema_version": "v1",
+            "document": {"_id": "u1", "name": "User", "age": 30}
+        });
+
+        // Auth middleware refuses the anonymous write before it runs
+        let resp = handler.handle(&insert.to_string(), &mut subsystems);
+        assert_eq!(error_code(&resp), Some("AUTH_REQUIRED"));
+        assert!(seen.lock().unwrap().is_empty());
+
+        let service = AuthContext::service_role();
+        let resp = respond_as(&handler, &service, insert, &mut subsystems);
+        assert!(resp.is_success(), "{}", resp.to_json());
+        let query = json!({
+            "op": "query", "schema_id": "users", "schema_version": "v1",
+            "filter": {"age": {"$gte": 0}}, "limit": 10
+        });
+        let resp = respond_as(&handler, &service, query, &mut subsystems);
+        assert!(resp.to_json().contains("\"u1\""), "{}", resp.to_json());
+        let schemas = json!({"op": "list_schemas"});
+        assert!(respond_as(&handler, &service, schemas, &mut subsystems).is_success());
+
+        // Handler errors keep their own codes through the pipeline
+        let missing = json!({"op": "get", "schema_id": "users", "document_id": "u9"});
+        let resp = respond_as(&handler, &service, missing, &mut subsystems);
+        assert_eq!(error_code(&resp), Some("AERO_NOT_FOUND"));
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec!["write users", "query users", "command -", "read users"]
+        );
+    }
+
+    #[test]
+    fn test_rls_middleware_scopes_rows() {
+        use crate::core::middleware::rls::{OwnershipPolicy, RlsMiddleware};
+
+        let (_temp, mut loader, mut wal, mut storage_w, mut storage_r, _) = setup_test_env();
+        let mut index = IndexManager::new(["age", "name"].map(String::from).into());
+
+        // Rows belong to the user whose id is their `name`, which is
+        // indexed like any other filtered field
+        let handler = ApiHandler::new("users")
+            .with_middleware(RlsMiddleware::new(OwnershipPolicy::new("name")));
+        let mut subsystems = Subsystems {
+            schema_loader: &mut loader,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_w,
+            storage_reader: &mut storage_r,
+            index_manager: &mut index,
+        };
+        let (alice_id, bob_id) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+        let alice = AuthContext::authenticated(alice_id);
+        let service = AuthContext::service_role();
+        for (id, owner) in [("a1", alice_id), ("b1", bob_id)] {
+            let insert = json!({
+                "op": "insert", "schema_id": "users", "schema_version": "v1",
+                "document": {"_id": id, "name": owner.to_string(), "age": 30}
+            });
+            assert!(respond_as(&handler, &service, insert, &mut subsystems).is_success());
+        }
+
+        let query = json!({
+            "op": "query", "schema_id": "users", "schema_version": "v1",
+            "filter": {"age": {"$gte": 0}}, "limit": 10
+        });
+        let resp = respond_as(&handler, &alice, query, &mut subsystems).to_json();
+        assert!(
+            resp.contains("\"a1\"") && !resp.contains("\"b1\""),
+            "{}",
+            resp
+        );
+        let count = json!({
+            "op": "count", "schema_id": "users", "schema_version": "v1",
+            "filter": {"age": {"$gte": 0}}, "limit": 10
+        });
+        let resp = respond_as(&handler, &alice, count, &mut subsystems).to_json();
+        assert!(resp.contains(r#""count":1"#), "{}", resp);
+
+        let get = |id: &str| json!({"op": "get", "schema_id": "users", "document_id": id});
+        assert!(respond_as(&handler, &alice, get("a1"), &mut subsystems).is_success());
+        let resp = respond_as(&handler, &alice, get("b1"), &mut subsystems);
+        assert_eq!(error_code(&resp), Some("AERO_NOT_FOUND"));
+
+        // Bob's stored row is out of reach even when the new body is not
+        let update = json!({
+            "op": "update", "schema_id": "users", "schema_version": "v1",
+            "document": {"_id": "b1", "name": "taken", "age": 31}
+        });
+        let resp = respond_as(&handler, &alice, update, &mut subsystems);
+        assert_eq!(error_code(&resp), Some("ACCESS_DENIED"));
+        let delete = |id: &str| json!({"op": "delete", "schema_id": "users", "document_id": id});
+        let resp = respond_as(&handler, &alice, delete("b1"), &mut subsystems);
+        assert_eq!(error_code(&resp), Some("ACCESS_DENIED"));
+        assert!(respond_as(&handler, &alice, delete("a1"), &mut subsystems).is_success());
+
+        // An upsert cannot take over Bob's row by claiming it in the body
+        let upsert = |id: &str| {
+            json!({
+                "op": "upsert", "schema_id": "users", "schema_version": "v1",
+                "document": {"_id": id, "name": alice_id.to_string(), "age": 32}
+            })
+        };
+        let resp = respond_as(&handler, &alice, upsert("b1"), &mut subsystems);
+        assert_eq!(error_code(&resp), Some("ACCESS_DENIED"));
+        assert!(respond_as(&handler, &alice, upsert("a3"), &mut subsystems).is_success());
+        assert!(respond_as(&handler, &alice, upsert("a3"), &mut subsystems).is_success());
+
+        // Nor can a plain insert, which never replaces a live document
+        let insert = json!({
+            "op": "insert", "schema_id": "users", "schema_version": "v1",
+            "document": {"_id": "b1", "name": alice_id.to_string(), "age": 33}
+        });
+        let resp = respond_as(&handler, &alice, insert, &mut subsystems);
+        assert_eq!(error_code(&resp), Some("AERO_DUPLICATE_ID"));
+        let resp = respond_as(&handler, &service, get("b1"), &mut subsystems).to_json();
+        assert!(resp.contains(&bob_id.to_string()), "{}", resp);
+
+        // Neither are catalog changes
+        let create_index = json!({"op": "create_index", "field": "age"});
+        let resp = respond_as(&handler, &alice, create_index, &mut subsystems);
+        assert_eq!(error_code(&resp), Some("ACCESS_DENIED"));
+
+        // Batches are not row-scoped
+        let insert_many = json!({
+            "op": "insert_many", "schema_id": "users", "schema_version": "v1",
+            "documents": [{"_id": "a2", "name": alice_id.to_string()}]
+        });
+        let resp = respond_as(&handler, &alice, insert_many, &mut subsystems);
+        assert_eq!(error_code(&resp), Some("ACCESS_DENIED"));
+        assert!(respond_as(&handler, &service, get("b1"), &mut subsystems).is_success());
+    }
 }
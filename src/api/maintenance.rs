@@ -0,0 +1,354 @@
+//! Maintenance mode and connection draining
+//!
+//! Before compaction, restore rehearsal, or a planned switchover the operator
+//! places the node in maintenance mode:
+//! - New write operations are rejected with `AERO_MAINTENANCE_MODE` (HTTP 503)
+//! - Reads are rejected too unless explicitly allowed
+//! - Work admitted before entry is allowed to finish (draining)
+//! - Health reports "maintenance" until the mode is exited
+//!
+//! The gate never aborts in-flight work. Draining only waits for admitted
+//! operations to release their guard.
+//!
+//! Independently of maintenance, the gate degrades to read-only serving
+//! when a WAL append fails because the device is full:
+//! - Writes are rejected with `AERO_READ_ONLY` (HTTP 507); reads continue
+//! - Health reports "read_only" until an operator exits the mode after
+//!   freeing space (`exit_read_only_mode` on the control plane)
+//!
+//! Exiting does not check free space; if the device is still full the
+//! next append trips the gate again.
+
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use super::errors::{ApiError, ApiResult};
+
+/// Health status reported while the node serves normally
+pub const HEALTH_OK: &str = "ok";
+
+/// Health status reported while the node is in maintenance mode
+pub const HEALTH_MAINTENANCE: &str = "maintenance";
+
+/// Health status reported while the node serves reads only (storage full)
+pub const HEALTH_READ_ONLY: &str = "read_only";
+
+/// Snapshot of the maintenance gate state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaintenanceStatus {
+    /// Whether maintenance mode is active
+    pub active: bool,
+    /// Whether reads are admitted during maintenance
+    pub allow_reads: bool,
+    /// Operations currently admitted and not yet finished
+    pub in_flight: usize,
+    /// Operator-supplied reason (for audit)
+    pub reason: Option<String>,
+    /// Whether writes are refused because storage is full
+    pub read_only: bool,
+    /// Failure that put the node in read-only mode
+    pub read_only_reason: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct GateState {
+    active: bool,
+    allow_reads: bool,
+    in_flight: usize,
+    reason: Option<String>,
+    /// Set while serving read-only, holding the triggering failure
+    read_only: Option<String>,
+}
+
+/// Admission gate shared between the API layer and the control plane
+#[derive(Debug, Default)]
+pub struct MaintenanceGate {
+    state: Mutex<GateState>,
+    drained: Condvar,
+}
+
+impl MaintenanceGate {
+    /// Create a gate in normal (serving) mode.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a shareable gate.
+    pub fn shared() -> Arc<Self> {
+        Arc::new(Self::new())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, GateState> {
+        self.state.lock().expect("Maintenance gate lock poisoned")
+    }
+
+    /// Admit an operation, returning a guard that marks it in flight.
+    ///
+    /// # Errors
+    ///
+    /// `AERO_MAINTENANCE_MODE` if maintenance is active and the operation is
+    /// a write (or a read while reads are not allowed); `AERO_READ_ONLY` if
+    /// the node is read-only and the operation is a write.
+    pub fn admit(&self, is_write: bool) -> ApiResult<InFlightGuard<'_>> {
+        let mut state = self.lock();
+        if state.active && (is_write || !state.allow_reads) {
+            let kind = if is_write { "writes" } else { "reads" };
+            return Err(ApiError::maintenance_mode(format!(
+                "Node is in maintenance mode; {} are not accepted{}",
+                kind,
+                state
+                    .reason
+                    .as_deref()
+                    .map(|r| format!(" ({})", r))
+                    .unwrap_or_default()
+            )));
+        }
+        if is_write {
+            if let Some(cause) = &state.read_only {
+                return Err(Self::read_only_error(cause));
+            }
+        }
+        state.in_flight += 1;
+        Ok(InFlightGuard { gate: self })
+    }
+
+    /// Enter maintenance mode.
+    ///
+    /// New operations are rejected immediately. Call `wait_for_drain` to
+    /// wait for admitted work to complete.
+    pub fn enter(&self, allow_reads: bool, reason: Option<String>) {
+        let mut state = self.lock();
+        state.active = true;
+        state.allow_reads = allow_reads;
+        state.reason = reason;
+    }
+
+    /// Exit maintenance mode and resume normal admission.
+    ///
+    /// Returns `false` if maintenance mode was not active.
+    pub fn exit(&self) -> bool {
+        let mut state = self.lock();
+        let was_active = state.active;
+        state.active = false;
+        state.allow_reads = false;
+        state.reason = None;
+        was_active
+    }
+
+    /// Enter read-only mode because storage is full.
+    ///
+    /// Called by the API layer when a WAL append fails with the device
+    /// full. The first failure is kept as the cause; returns the
+    /// `AERO_READ_ONLY` error the failing write should report.
+    pub fn enter_read_only(&self, cause: impl Into<String>) -> ApiError {
+        let mut state = self.lock();
+        let cause = state.read_only.get_or_insert_with(|| cause.into());
+        Self::read_only_error(cause)
+    }
+
+    /// Exit read-only mode after space has been freed.
+    ///
+    /// Returns `false` if the node was not read-only.
+    pub fn exit_read_only(&self) -> bool {
+        self.lock().read_only.take().is_some()
+    }
+
+    /// Whether writes are refused because storage is full.
+    pub fn is_read_only(&self) -> bool {
+        self.lock().read_only.is_some()
+    }
+
+    fn read_only_error(cause: &str) -> ApiError {
+        ApiError::read_only(format!(
+            "Node is read-only because storage is full; writes are not accepted ({})",
+            cause
+        ))
+    }
+
+    /// Wait until no admitted operations remain in flight.
+    ///
+    /// Returns `true` if the gate drained within `timeout`.
+    pub fn wait_for_drain(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.lock();
+        while state.in_flight > 0 {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            state = self
+                .drained
+                .wait_timeout(state, deadline - now)
+                .expect("Maintenance gate lock poisoned")
+                .0;
+        }
+        true
+    }
+
+    /// Whether maintenance mode is active.
+    pub fn is_active(&self) -> bool {
+        self.lock().active
+    }
+
+    /// Number of operations currently in flight.
+    pub fn in_flight(&self) -> usize {
+        self.lock().in_flight
+    }
+
+    /// Health status string for health endpoints.
+    ///
+    /// Maintenance takes precedence over read-only mode.
+    pub fn health_status(&self) -> &'static str {
+        let state = self.lock();
+        if state.active {
+            HEALTH_MAINTENANCE
+        } else if state.read_only.is_some() {
+            HEALTH_READ_ONLY
+        } else {
+            HEALTH_OK
+        }
+    }
+
+    /// Snapshot the current gate state.
+    pub fn status(&self) -> MaintenanceStatus {
+        let state = self.lock();
+        MaintenanceStatus {
+            active: state.active,
+            allow_reads: state.allow_reads,
+            in_flight: state.in_flight,
+            reason: state.reason.clone(),
+            read_only: state.read_only.is_some(),
+            read_only_reason: state.read_only.clone(),
+        }
+    }
+
+    fn release(&self) {
+        let mut state = self.lock();
+        state.in_flight = state.in_flight.saturating_sub(1);
+        if state.in_flight == 0 {
+            self.drained.notify_all();
+        }
+    }
+}
+
+/// Marks an admitted operation as in flight until dropped
+#[derive(Debug)]
+pub struct InFlightGuard<'a> {
+    gate: &'a MaintenanceGate,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.gate.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_normal_mode_admits_everything() {
+        let gate = MaintenanceGate::new();
+        assert!(gate.admit(true).is_ok());
+        assert!(gate.admit(false).is_ok());
+        assert_eq!(gate.health_status(), HEALTH_OK);
+    }
+
+    #[test]
+    fn test_maintenance_rejects_writes() {
+        let gate = MaintenanceGate::new();
+        gate.enter(true, Some("compaction".to_string()));
+
+        let err = gate.admit(true).unwrap_err();
+        assert_eq!(err.code(), "AERO_MAINTENANCE_MODE");
+        assert_eq!(err.http_status(), 503);
+        assert!(err.message().contains("compaction"));
+
+        // Reads allowed
+        assert!(gate.admit(false).is_ok());
+        assert_eq!(gate.health_status(), HEALTH_MAINTENANCE);
+    }
+
+    #[test]
+    fn test_maintenance_rejects_reads_unless_allowed() {
+        let gate = MaintenanceGate::new();
+        gate.enter(false, None);
+        assert!(gate.admit(false).is_err());
+    }
+
+    #[test]
+    fn test_exit_resumes_admission() {
+        let gate = MaintenanceGate::new();
+        gate.enter(false, None);
+        assert!(gate.exit());
+        assert!(!gate.exit());
+        assert!(gate.admit(true).is_ok());
+    }
+
+    #[test]
+    fn test_read_only_rejects_writes_and_serves_reads() {
+        let gate = MaintenanceGate::new();
+        let err = gate.enter_read_only("WAL device full");
+        assert_eq!(err.code(), "AERO_READ_ONLY");
+
+        let err = gate.admit(true).unwrap_err();
+        assert_eq!(err.code(), "AERO_READ_ONLY");
+        assert_eq!(err.http_status(), 507);
+        assert!(err.message().contains("WAL device full"));
+        assert!(gate.admit(false).is_ok());
+        assert_eq!(gate.health_status(), HEALTH_READ_ONLY);
+
+        // The first failure stays the cause
+        gate.enter_read_only("second failure");
+        assert_eq!(
+            gate.status().read_only_reason.as_deref(),
+            Some("WAL device full")
+        );
+
+        assert!(gate.exit_read_only());
+        assert!(!gate.exit_read_only());
+        assert!(gate.admit(true).is_ok());
+        assert_eq!(gate.health_status(), HEALTH_OK);
+    }
+
+    #[test]
+    fn test_maintenance_exit_keeps_read_only() {
+        let gate = MaintenanceGate::new();
+        gate.enter_read_only("WAL device full");
+        gate.enter(true, None);
+        assert_eq!(gate.health_status(), HEALTH_MAINTENANCE);
+        gate.exit();
+        assert_eq!(gate.admit(true).unwrap_err().code(), "AERO_READ_ONLY");
+    }
+
+    #[test]
+    fn test_in_flight_tracking() {
+        let gate = MaintenanceGate::new();
+        let guard = gate.admit(true).unwrap();
+        assert_eq!(gate.in_flight(), 1);
+        drop(guard);
+        assert_eq!(gate.in_flight(), 0);
+    }
+
+    #[test]
+    fn test_drain_waits_for_in_flight_work() {
+        let gate = MaintenanceGate::shared();
+        let (admitted_tx, admitted_rx) = std::sync::mpsc::channel();
+
+        let worker_gate = Arc::clone(&gate);
+        let worker = thread::spawn(move || {
+            let _guard = worker_gate.admit(true).unwrap();
+            admitted_tx.send(()).unwrap();
+            thread::sleep(Duration::from_millis(50));
+        });
+
+        admitted_rx.recv().unwrap();
+        gate.enter(false, None);
+        assert!(!gate.wait_for_drain(Duration::from_millis(0)));
+        assert!(gate.wait_for_drain(Duration::from_secs(5)));
+        worker.join().unwrap();
+        assert_eq!(gate.status().in_flight, 0);
+    }
+}
@@ -12,17 +12,56 @@
 //! # Supported Operations
 //!
 //! - insert
-//! - update
+//! - insert_many (all-or-nothing batch, single WAL fsync)
+//! - update (optionally conditional on `if_commit_id` / `if_checksum`)
+//! - upsert (insert if absent, update otherwise, decided under the lock)
 //! - delete
-//! - query
+//! - get (single document by `_id`, no query planning)
+//! - transaction (ordered insert/update/delete ops, all-or-nothing)
+//! - query (optionally bound to a read view for a stable snapshot)
 //! - explain
+//! - create_collection / list_collections
+//! - drop_collection / truncate_collection (require `"confirm": true`)
+//! - create_index (declares a single-field index and backfills it)
+//!
+//! Document requests may name a `collection`; those that don't go to the
+//! handler's default collection. Each collection stores one schema and
+//! has its own index partition.
+//!
+//! Writes (and optionally reads) are rejected while the node is in
+//! maintenance mode, and writes alone while it is read-only after the WAL
+//! device filled up; see `MaintenanceGate`.
+//!
+//! Under overload, requests are shed by priority class at admission; see
+//! `AdmissionQueue`.
+//!
+//! Committed document changes can be observed through a `ChangeSink`.
+//!
+//! Every request runs through the handler's core middleware (auth, RLS,
+//! observability) as a `core::Operation`; see `ApiHandler::with_middleware`.
 
+mod admission;
+mod changes;
 mod errors;
 mod handler;
+mod maintenance;
+mod pipeline;
+mod read_view;
 mod request;
 mod response;
 
+pub use admission::{AdmissionQueue, PriorityClass, QueueSlot, SheddingStats, SheddingThresholds};
+pub use changes::{ChangeKind, ChangeSink, DocumentChange};
 pub use errors::{ApiError, ApiErrorCode, ApiResult};
 pub use handler::{ApiHandler, Subsystems};
-pub use request::{DeleteRequest, InsertRequest, QueryRequest, Request, UpdateRequest};
+pub use maintenance::{
+    InFlightGuard, MaintenanceGate, MaintenanceStatus, HEALTH_MAINTENANCE, HEALTH_OK,
+    HEALTH_READ_ONLY,
+};
+pub use read_view::ReadViewHandle;
+pub use request::{
+    query_request, AggregateRequest, CollectionRequest, CreateCollectionRequest,
+    CreateIndexRequest, DeleteRequest, GetRequest, InsertManyRequest, InsertRequest, QueryRequest,
+    Request, RoutedRequest, TransactionRequest, TxnOp, UpdateRequest, UpsertRequest,
+};
 pub use response::{ErrorResponse, Response, SuccessResponse};
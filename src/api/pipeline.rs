@@ -0,0 +1,209 @@
+//! Core pipeline integration
+//!
+//! Every routed request runs through the handler's core middleware as a
+//! `core::Operation`, so auth, RLS and observability middleware see API
+//! traffic exactly as they see HTTP traffic. The pipeline's final stage
+//! dispatches the request itself to the handler under the global lock.
+//!
+//! Middleware futures are driven to completion on the calling thread:
+//! the handler is synchronous and holds the global lock throughout.
+
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+use serde_json::Value;
+
+use crate::core::operation::{CommandOp, DeleteOp, Operation, QueryOp, ReadOp, UpdateOp, WriteOp};
+use crate::core::pipeline::{OperationExecutor, OperationResult};
+use crate::core::{CoreError, RequestContext, RlsFilter};
+use crate::planner::Predicate;
+
+use super::errors::{ApiError, ApiResult};
+use super::request::{QueryRequest, Request};
+
+/// Core operation describing `request` against `collection`
+///
+/// Single-document requests and queries map onto their data operations;
+/// everything else becomes a `Command`, naming the collection only when
+/// the command touches that collection's documents.
+///
+/// An upsert may replace a stored document, so it maps onto a row-scoped
+/// `Update` rather than a `Write`: RLS then filters the stored document
+/// it replaces as well as checking the fields it writes.
+pub(crate) fn operation_of(request: &Request, collection: &str) -> Operation {
+    let collection_name = collection.to_string();
+    let document_id = |document: &Value| {
+        document
+            .get("_id")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    match request {
+        Request::Insert(r) => Operation::Write(WriteOp {
+            collection: collection_name,
+            document: r.document.clone(),
+            schema_id: r.schema_id.clone(),
+            schema_version: r.schema_version.clone(),
+        }),
+        Request::Upsert(r) => Operation::Update(UpdateOp {
+            collection: collection_name,
+            id: document_id(&r.document),
+            updates: r.document.clone(),
+            schema_id: Some(r.schema_id.clone()),
+            schema_version: Some(r.schema_version.clone()),
+        }),
+        Request::Update(r) => Operation::Update(UpdateOp {
+            collection: collection_name,
+            id: document_id(&r.document),
+            updates: r.document.clone(),
+            schema_id: Some(r.schema_id.clone()),
+            schema_version: Some(r.schema_version.clone()),
+        }),
+        Request::Delete(r) => Operation::Delete(DeleteOp {
+            collection: collection_name,
+            id: r.document_id.clone(),
+            schema_id: Some(r.schema_id.clone()),
+        }),
+        Request::Get(r) => Operation::Read(ReadOp {
+            collection: collection_name,
+            id: r.document_id.clone(),
+            select: None,
+        }),
+        Request::Query(r) | Request::Count(r) => Operation::Query(query_op(r, collection_name)),
+        Request::Aggregate(r) => Operation::Query(query_op(&r.query, collection_name)),
+        Request::Explain(r) => Operation::Explain(query_op(r, collection_name)),
+        Request::InsertMany(_) | Request::Transaction(_) => command(request, Some(collection)),
+        Request::DropCollection(r) | Request::TruncateCollection(r) => {
+            command(request, Some(&r.collection))
+        }
+        _ => command(request, None),
+    }
+}
+
+fn query_op(req: &QueryRequest, collection: String) -> QueryOp {
+    QueryOp {
+        collection,
+        filter: req.filter.clone(),
+        select: None,
+        order: None,
+        limit: req.limit,
+        offset: 0,
+        schema_id: Some(req.schema_id.clone()),
+        schema_version: Some(req.schema_version.clone()),
+    }
+}
+
+fn command(request: &Request, collection: Option<&str>) -> Operation {
+    Operation::Command(CommandOp {
+        command: request.op_name().to_string(),
+        collection: collection.map(str::to_string),
+        writes: request.is_write(),
+    })
+}
+
+/// Planner predicates enforcing the RLS filters middleware injected
+///
+/// # Errors
+///
+/// `ACCESS_DENIED` for a filter with no planner equivalent: a row filter
+/// that cannot be enforced must not be dropped.
+pub(crate) fn rls_predicates(filters: &[RlsFilter]) -> ApiResult<Vec<Predicate>> {
+    filters
+        .iter()
+        .map(|filter| {
+            filter.to_predicate().ok_or_else(|| {
+                ApiError::from_core_error(CoreError::access_denied(format!(
+                    "RLS filter on {} cannot be enforced by the query planner",
+                    filter.field
+                )))
+            })
+        })
+        .collect()
+}
+
+/// Final pipeline stage: runs one request through `dispatch`
+///
+/// `dispatch` receives the request context as the middleware left it.
+/// The handler's own error is kept in full, since a `CoreError` cannot
+/// carry its code; `finish` recovers it.
+pub(crate) struct RequestExecutor<F> {
+    dispatch: Mutex<Option<F>>,
+    failure: Mutex<Option<ApiError>>,
+}
+
+impl<F> RequestExecutor<F>
+where
+    F: FnOnce(&RequestContext) -> ApiResult<Value> + Send,
+{
+    pub(crate) fn new(dispatch: F) -> Self {
+        Self {
+            dispatch: Mutex::new(Some(dispatch)),
+            failure: Mutex::new(None),
+        }
+    }
+
+    /// The request's result, given the pipeline's
+    ///
+    /// Errors raised by the handler keep their code; errors raised by
+    /// middleware pass through as core errors.
+    pub(crate) fn finish(self, result: OperationResult) -> ApiResult<Value> {
+        result.map_err(|e| {
+            self.failure
+                .into_inner()
+                .unwrap_or_else(|e| e.into_inner())
+                .unwrap_or_else(|| ApiError::from_core_error(e))
+        })
+    }
+}
+
+impl<F> OperationExecutor for RequestExecutor<F>
+where
+    F: FnOnce(&RequestContext) -> ApiResult<Value> + Send,
+{
+    fn execute(
+        &self,
+        _op: &Operation,
+        ctx: &RequestContext,
+    ) -> Pin<Box<dyn Future<Output = OperationResult> + Send + '_>> {
+        let dispatch = self
+            .dispatch
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        let result = match dispatch {
+            Some(dispatch) => dispatch(ctx).map_err(|e| {
+                let mirrored = CoreError::execution(format!("{}: {}", e.code(), e.message()));
+                *self.failure.lock().unwrap_or_else(|e| e.into_inner()) = Some(e);
+                mirrored
+            }),
+            None => Err(CoreError::internal("request dispatched twice")),
+        };
+        Box::pin(async move { result })
+    }
+}
+
+/// Wakes the thread blocked in `block_on`
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Drive `future` to completion on the calling thread
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
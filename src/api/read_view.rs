@@ -0,0 +1,402 @@
+//! Read views for repeatable queries
+//!
+//! A caller opens a read view with `ApiHandler::begin_read` and passes its id
+//! in `QueryRequest::read_view`. Every query bound to the view sees the same
+//! snapshot, including all writes acknowledged before the view was opened
+//! (read-your-writes), and nothing acknowledged after.
+//!
+//! Storage is append-only and every write is acknowledged after its storage
+//! append, under the global execution lock. Storage order is therefore
+//! commit order, and a storage record at offset `o` is treated as a version
+//! with commit identity `o + 1`. A view opened when the storage file ends at
+//! offset `b` has `read_upper_bound = b`; visibility is then decided by the
+//! standard MVCC rule (`Visibility::visible_version`).
+//!
+//! Over the API a view is a read-only transaction: `{"op": "begin_read"}`
+//! opens one and returns its id, every query carrying that `read_view` runs
+//! against the same snapshot, and `{"op": "end_read", "read_view": id}`
+//! releases it.
+//!
+//! Views are held until `ApiHandler::end_read`, or until they go unused for
+//! the registry's timeout (`DEFAULT_READ_VIEW_TIMEOUT` unless configured);
+//! each query through a view restarts its timeout. Queries naming an
+//! unknown, ended or expired view are rejected with `AERO_INVALID_REQUEST`.
+//!
+//! # Read-write transactions
+//!
+//! A `transaction` request carrying a `read_view` commits only if none of
+//! the documents it writes were written by anyone else after the view was
+//! opened (first committer wins); otherwise it is aborted with
+//! `AERO_SERIALIZATION_FAILURE` and nothing is written. To detect this the
+//! registry remembers, while any view is open, the commit identity of the
+//! latest write to each document (and of each drop or truncate of a whole
+//! collection), forgetting writes every open view already sees.
+//!
+//! # Time travel
+//!
+//! A query with `as_of_commit_id = c` runs against the one-off view with
+//! `read_upper_bound = c`: it sees exactly the writes whose acknowledged
+//! `commit_id` is at most `c`. Boundaries below the GC horizon are rejected
+//! with `AERO_SNAPSHOT_TOO_OLD`, since versions older than the horizon may
+//! have been collected, and boundaries past the end of storage (not yet
+//! committed) with `AERO_INVALID_REQUEST`. The horizon only moves forward
+//! and never passes an open view.
+//!
+//! # Checkpoints
+//!
+//! A checkpoint neither waits for nor ends open views. It snapshots the
+//! storage file and truncates the WAL, but never rewrites or compacts
+//! storage, so every version a view can see stays at its offset and the
+//! view returns the same documents before and after. Views are in-memory
+//! only: none survive a restart, and a client holding one after a restart
+//! gets `AERO_INVALID_REQUEST` and must begin a new one.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use super::errors::{ApiError, ApiResult};
+use crate::mvcc::{CommitId, ReadView};
+
+/// Idle time after which an unused read view expires (default)
+pub const DEFAULT_READ_VIEW_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Handle for an open read view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadViewHandle {
+    id: u64,
+    view: ReadView,
+}
+
+impl ReadViewHandle {
+    /// Id to pass as `read_view` in query requests
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The pinned snapshot boundary
+    pub fn view(&self) -> ReadView {
+        self.view
+    }
+}
+
+/// Commit identity of the storage record at `offset`
+pub(crate) fn storage_commit_id(offset: u64) -> CommitId {
+    CommitId::new(offset + 1)
+}
+
+/// Storage offset of the version with the given commit identity
+pub(crate) fn storage_offset(commit_id: CommitId) -> u64 {
+    commit_id.value() - 1
+}
+
+#[derive(Debug, Clone, Copy)]
+struct OpenView {
+    view: ReadView,
+    last_used: Instant,
+}
+
+#[derive(Debug, Default)]
+struct RegistryState {
+    next_id: u64,
+    open: HashMap<u64, OpenView>,
+    /// Latest write per (collection, document id) newer than some open view
+    document_writes: HashMap<(String, String), CommitId>,
+    /// Latest drop or truncate per collection newer than some open view
+    collection_writes: HashMap<String, CommitId>,
+    /// Oldest boundary whose versions are all retained
+    gc_horizon: u64,
+}
+
+impl RegistryState {
+    /// Drop every view idle for at least `timeout`
+    fn expire(&mut self, timeout: Duration) {
+        self.open
+            .retain(|_, view| view.last_used.elapsed() < timeout);
+        self.forget_seen_writes();
+    }
+
+    /// Forget writes that every open view already sees
+    fn forget_seen_writes(&mut self) {
+        match self.open.values().map(|open| open.view.upper_bound()).min() {
+            Some(oldest) => {
+                self.document_writes.retain(|_, commit| *commit > oldest);
+                self.collection_writes.retain(|_, commit| *commit > oldest);
+            }
+            None => {
+                self.document_writes.clear();
+                self.collection_writes.clear();
+            }
+        }
+    }
+}
+
+/// Open read views, keyed by handle id
+#[derive(Debug)]
+pub(crate) struct ReadViewRegistry {
+    state: Mutex<RegistryState>,
+    timeout: Duration,
+}
+
+impl Default for ReadViewRegistry {
+    fn default() -> Self {
+        Self::with_timeout(DEFAULT_READ_VIEW_TIMEOUT)
+    }
+}
+
+impl ReadViewRegistry {
+    /// Registry expiring views idle for `timeout`
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            state: Mutex::new(RegistryState::default()),
+            timeout,
+        }
+    }
+
+    /// Idle time after which a view expires
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RegistryState> {
+        self.state.lock().expect("Read view registry lock poisoned")
+    }
+
+    /// Open a view covering every storage record before `storage_end`.
+    pub fn open(&self, storage_end: u64) -> ReadViewHandle {
+        let mut state = self.lock();
+        state.expire(self.timeout);
+        state.next_id += 1;
+        let handle = ReadViewHandle {
+            id: state.next_id,
+            view: ReadView::new(CommitId::new(storage_end)),
+        };
+        state.open.insert(
+            handle.id,
+            OpenView {
+                view: handle.view,
+                last_used: Instant::now(),
+            },
+        );
+        handle
+    }
+
+    /// Resolve an open view by handle id, restarting its timeout.
+    ///
+    /// # Errors
+    ///
+    /// `AERO_INVALID_REQUEST` if the view was never opened, has ended or
+    /// has expired.
+    pub fn resolve(&self, id: u64) -> ApiResult<ReadView> {
+        let mut state = self.lock();
+        let open = state
+            .open
+            .get_mut(&id)
+            .ok_or_else(|| ApiError::invalid_request(format!("Unknown read view: {}", id)))?;
+        if open.last_used.elapsed() >= self.timeout {
+            state.open.remove(&id);
+            state.forget_seen_writes();
+            return Err(ApiError::invalid_request(format!(
+                "Read view {} expired",
+                id
+            )));
+        }
+        open.last_used = Instant::now();
+        Ok(open.view)
+    }
+
+    /// End a view. Returns `false` if it was not open (or had expired).
+    pub fn close(&self, id: u64) -> bool {
+        let mut state = self.lock();
+        let closed = state.open.remove(&id).is_some();
+        state.expire(self.timeout);
+        closed
+    }
+
+    /// View of history as of `commit`, with storage ending at `storage_end`.
+    ///
+    /// # Errors
+    ///
+    /// `AERO_SNAPSHOT_TOO_OLD` below the GC horizon; `AERO_INVALID_REQUEST`
+    /// past the end of storage.
+    pub fn as_of(&self, commit: u64, storage_end: u64) -> ApiResult<ReadView> {
+        let horizon = self.lock().gc_horizon;
+        if commit < horizon {
+            return Err(ApiError::snapshot_too_old(commit, horizon));
+        }
+        if commit > storage_end {
+            return Err(ApiError::invalid_request(format!(
+                "Commit {} is not committed yet (latest boundary is {})",
+                commit, storage_end
+            )));
+        }
+        Ok(ReadView::new(CommitId::new(commit)))
+    }
+
+    /// Move the GC horizon forward to at most `horizon`.
+    ///
+    /// The horizon never moves back and never passes the oldest open view.
+    /// Returns the horizon in effect.
+    pub fn advance_gc_horizon(&self, horizon: u64) -> u64 {
+        let mut state = self.lock();
+        state.expire(self.timeout);
+        let oldest_open = state
+            .open
+            .values()
+            .map(|open| open.view.upper_bound().value())
+            .min()
+            .unwrap_or(u64::MAX);
+        state.gc_horizon = state.gc_horizon.max(horizon.min(oldest_open));
+        state.gc_horizon
+    }
+
+    /// Current GC horizon.
+    pub fn gc_horizon(&self) -> u64 {
+        self.lock().gc_horizon
+    }
+
+    /// Remember that `documents` of `collection` were written at `commit`.
+    ///
+    /// A no-op while no view is open: a view opened later sees the write.
+    pub fn record_writes(&self, collection: &str, documents: &[String], commit: CommitId) {
+        let mut state = self.lock();
+        if state.open.is_empty() {
+            return;
+        }
+        for document in documents {
+            state
+                .document_writes
+                .insert((collection.to_string(), document.clone()), commit);
+        }
+    }
+
+    /// Remember that every document of `collection` was written at `commit`.
+    pub fn record_collection_write(&self, collection: &str, commit: CommitId) {
+        let mut state = self.lock();
+        if !state.open.is_empty() {
+            state
+                .collection_writes
+                .insert(collection.to_string(), commit);
+        }
+    }
+
+    /// Commit identity of the latest remembered write to a document.
+    pub fn last_write(&self, collection: &str, document: &str) -> Option<CommitId> {
+        let state = self.lock();
+        let document_write = state
+            .document_writes
+            .get(&(collection.to_string(), document.to_string()));
+        document_write
+            .max(state.collection_writes.get(collection))
+            .copied()
+    }
+
+    /// Number of open (unexpired) views.
+    pub fn open_count(&self) -> usize {
+        let mut state = self.lock();
+        state.expire(self.timeout);
+        state.open.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_views_resolve_until_closed() {
+        let registry = ReadViewRegistry::default();
+        let a = registry.open(100);
+        let b = registry.open(100);
+        assert_ne!(a.id(), b.id());
+        assert_eq!(registry.resolve(a.id()).unwrap(), a.view());
+
+        assert!(registry.close(a.id()));
+        assert!(!registry.close(a.id()));
+        let err = registry.resolve(a.id()).unwrap_err();
+        assert_eq!(err.code(), "AERO_INVALID_REQUEST");
+        assert_eq!(registry.resolve(b.id()).unwrap(), b.view());
+        assert_eq!(registry.open_count(), 1);
+    }
+
+    #[test]
+    fn test_idle_views_expire() {
+        let registry = ReadViewRegistry::with_timeout(Duration::ZERO);
+        let handle = registry.open(100);
+        let err = registry.resolve(handle.id()).unwrap_err();
+        assert_eq!(err.code(), "AERO_INVALID_REQUEST");
+        assert!(err.message().contains("expired"));
+        assert_eq!(registry.open_count(), 0);
+
+        // Use restarts the timeout
+        let registry = ReadViewRegistry::with_timeout(Duration::from_secs(3600));
+        let handle = registry.open(100);
+        assert_eq!(registry.resolve(handle.id()).unwrap(), handle.view());
+        assert_eq!(registry.open_count(), 1);
+        assert!(registry.close(handle.id()));
+    }
+
+    #[test]
+    fn test_writes_remembered_while_views_open() {
+        let registry = ReadViewRegistry::default();
+        let docs = ["user_1".to_string()];
+
+        // Nobody can be concurrent with a write while no view is open
+        registry.record_writes("users", &docs, CommitId::new(5));
+        assert_eq!(registry.last_write("users", "user_1"), None);
+
+        let old = registry.open(10);
+        let new = registry.open(20);
+        registry.record_writes("users", &docs, CommitId::new(15));
+        registry.record_collection_write("admins", CommitId::new(25));
+        assert_eq!(
+            registry.last_write("users", "user_1"),
+            Some(CommitId::new(15))
+        );
+        assert_eq!(
+            registry.last_write("admins", "user_1"),
+            Some(CommitId::new(25))
+        );
+
+        // Once only `new` is open, writes it sees are forgotten
+        registry.close(old.id());
+        assert_eq!(registry.last_write("users", "user_1"), None);
+        assert_eq!(
+            registry.last_write("admins", "user_1"),
+            Some(CommitId::new(25))
+        );
+        registry.close(new.id());
+        assert_eq!(registry.last_write("admins", "user_1"), None);
+    }
+
+    #[test]
+    fn test_as_of_bounded_by_horizon_and_storage_end() {
+        let registry = ReadViewRegistry::default();
+        assert_eq!(
+            registry.as_of(0, 100).unwrap().upper_bound(),
+            CommitId::new(0)
+        );
+        let err = registry.as_of(101, 100).unwrap_err();
+        assert_eq!(err.code(), "AERO_INVALID_REQUEST");
+
+        // The horizon stops at the oldest open view and never moves back
+        let view = registry.open(40);
+        assert_eq!(registry.advance_gc_horizon(60), 40);
+        registry.close(view.id());
+        assert_eq!(registry.advance_gc_horizon(60), 60);
+        assert_eq!(registry.advance_gc_horizon(10), 60);
+
+        let err = registry.as_of(59, 100).unwrap_err();
+        assert_eq!(err.code(), "AERO_SNAPSHOT_TOO_OLD");
+        assert!(registry.as_of(60, 100).is_ok());
+    }
+
+    #[test]
+    fn test_storage_commit_id_mapping() {
+        // A view at storage end `b` sees exactly the records before `b`
+        let view = ReadView::new(CommitId::new(64));
+        assert!(storage_commit_id(63) <= view.upper_bound());
+        assert!(storage_commit_id(64) > view.upper_bound());
+        assert_eq!(storage_offset(storage_commit_id(0)), 0);
+    }
+}
@@ -3,19 +3,51 @@
 //! JSON request parsing for all supported operations.
 
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Map, Value};
 
 use super::errors::{ApiError, ApiResult};
+use crate::executor::AggregateFunction;
+use crate::planner::{FilterOp, Predicate, Query, SortDirection};
+use crate::schema::Schema;
 
 /// Operation type
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Operation {
     Insert,
+    #[serde(rename = "insert_many")]
+    InsertMany,
+    Transaction,
     Update,
+    Upsert,
     Delete,
+    Get,
     Query,
+    Count,
+    Aggregate,
     Explain,
+    #[serde(rename = "create_schema")]
+    CreateSchema,
+    #[serde(rename = "alter_schema")]
+    AlterSchema,
+    #[serde(rename = "deprecate_schema")]
+    DeprecateSchema,
+    #[serde(rename = "list_schemas")]
+    ListSchemas,
+    #[serde(rename = "create_collection")]
+    CreateCollection,
+    #[serde(rename = "list_collections")]
+    ListCollections,
+    #[serde(rename = "drop_collection")]
+    DropCollection,
+    #[serde(rename = "truncate_collection")]
+    TruncateCollection,
+    #[serde(rename = "create_index")]
+    CreateIndex,
+    #[serde(rename = "begin_read")]
+    BeginRead,
+    #[serde(rename = "end_read")]
+    EndRead,
 }
 
 /// Insert request
@@ -26,12 +58,38 @@ pub struct InsertRequest {
     pub document: Value,
 }
 
+/// Batch insert request (all-or-nothing)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InsertManyRequest {
+    pub schema_id: String,
+    pub schema_version: String,
+    pub documents: Vec<Value>,
+}
+
 /// Update request
+///
+/// `if_commit_id` and `if_checksum` are optional preconditions on the
+/// current stored version; the update is rejected with `AERO_CONFLICT` if
+/// either no longer matches.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateRequest {
     pub schema_id: String,
     pub schema_version: String,
     pub document: Value,
+    /// Expected commit identity of the current version
+    #[serde(default)]
+    pub if_commit_id: Option<u64>,
+    /// Expected CRC32 of the current stored document body
+    #[serde(default)]
+    pub if_checksum: Option<u32>,
+}
+
+/// Upsert request (insert if absent, update otherwise)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpsertRequest {
+    pub schema_id: String,
+    pub schema_version: String,
+    pub document: Value,
 }
 
 /// Delete request
@@ -41,6 +99,13 @@ pub struct DeleteRequest {
     pub document_id: String,
 }
 
+/// Get-by-id request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetRequest {
+    pub schema_id: String,
+    pub document_id: String,
+}
+
 /// Query request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryRequest {
@@ -48,19 +113,131 @@ pub struct QueryRequest {
     pub schema_version: String,
     #[serde(default)]
     pub filter: Option<Value>,
+    /// Comma-separated sort keys in priority order, `-` prefix for descending
     #[serde(default)]
     pub sort: Option<String>,
     pub limit: usize,
+    /// Read view handle id; when set, the query sees that stable snapshot
+    #[serde(default)]
+    pub read_view: Option<u64>,
+    /// Historical boundary; when set, the query sees every write with a
+    /// commit id up to and including it (exclusive with `read_view`)
+    #[serde(default)]
+    pub as_of_commit_id: Option<u64>,
+    /// Return execution statistics alongside the results
+    #[serde(default)]
+    pub stats: bool,
+    /// Row-level security predicates added to `filter` by the pipeline;
+    /// never read from the wire
+    #[serde(skip)]
+    pub rls_predicates: Vec<Predicate>,
+}
+
+/// Aggregate request: a scalar min/max/sum over the documents a query matches
+#[derive(Debug, Clone)]
+pub struct AggregateRequest {
+    pub query: QueryRequest,
+    pub function: AggregateFunction,
+    pub field: String,
+}
+
+/// Deprecate-schema request: closes one version to new writes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeprecateSchemaRequest {
+    pub schema_id: String,
+    pub schema_version: String,
+}
+
+/// List-schemas request, optionally restricted to one schema id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListSchemasRequest {
+    #[serde(default)]
+    pub schema_id: Option<String>,
+}
+
+/// Create-collection request: registers a collection storing one schema
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateCollectionRequest {
+    pub collection: String,
+    pub schema_id: String,
+}
+
+/// Drop or truncate request for one collection
+///
+/// Parsing fails unless the request carries `"confirm": true`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionRequest {
+    pub collection: String,
+}
+
+/// Create-index request: declares and backfills a single-field index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateIndexRequest {
+    pub field: String,
+}
+
+/// Single operation within a transaction
+#[derive(Debug, Clone)]
+pub enum TxnOp {
+    Insert(InsertRequest),
+    Update(UpdateRequest),
+    Delete(DeleteRequest),
+}
+
+/// Multi-operation transaction request (ordered, all-or-nothing)
+///
+/// With `read_view`, the transaction is aborted with
+/// `AERO_SERIALIZATION_FAILURE` if any document it writes was written by
+/// another request after that view was opened.
+#[derive(Debug, Clone)]
+pub struct TransactionRequest {
+    pub ops: Vec<TxnOp>,
+    /// Read view the transaction read its inputs through
+    pub read_view: Option<u64>,
 }
 
 /// Unified request envelope
 #[derive(Debug, Clone)]
 pub enum Request {
     Insert(InsertRequest),
+    InsertMany(InsertManyRequest),
     Update(UpdateRequest),
+    Upsert(UpsertRequest),
     Delete(DeleteRequest),
+    Get(GetRequest),
     Query(QueryRequest),
+    Count(QueryRequest),
+    Aggregate(AggregateRequest),
     Explain(QueryRequest),
+    Transaction(TransactionRequest),
+    /// New schema id (first version)
+    CreateSchema(Schema),
+    /// New version of an existing schema id
+    AlterSchema(Schema),
+    DeprecateSchema(DeprecateSchemaRequest),
+    ListSchemas(ListSchemasRequest),
+    CreateCollection(CreateCollectionRequest),
+    ListCollections,
+    /// Tombstone every document and unregister the collection
+    DropCollection(CollectionRequest),
+    /// Tombstone every document, keeping the collection
+    TruncateCollection(CollectionRequest),
+    /// Index a field of every collection, backfilling existing documents
+    CreateIndex(CreateIndexRequest),
+    /// Open a read-only transaction (read view) over everything acknowledged
+    BeginRead,
+    /// End the read-only transaction with this read view id
+    EndRead(u64),
+}
+
+/// A request and the collection it is routed to
+///
+/// `collection` is `None` when the request names none; the handler then
+/// uses its default collection.
+#[derive(Debug, Clone)]
+pub struct RoutedRequest {
+    pub collection: Option<String>,
+    pub request: Request,
 }
 
 /// Raw request for parsing
@@ -74,6 +251,8 @@ struct RawRequest {
     #[serde(default)]
     document: Option<Value>,
     #[serde(default)]
+    documents: Option<Vec<Value>>,
+    #[serde(default)]
     document_id: Option<String>,
     #[serde(default)]
     filter: Option<Value>,
@@ -81,14 +260,226 @@ struct RawRequest {
     sort: Option<String>,
     #[serde(default)]
     limit: Option<usize>,
+    #[serde(default)]
+    read_view: Option<u64>,
+    #[serde(default)]
+    as_of_commit_id: Option<u64>,
+    #[serde(default)]
+    stats: bool,
+    #[serde(default)]
+    if_commit_id: Option<u64>,
+    #[serde(default)]
+    if_checksum: Option<u32>,
+    #[serde(default)]
+    function: Option<String>,
+    #[serde(default)]
+    field: Option<String>,
+    #[serde(default)]
+    ops: Option<Vec<Value>>,
+    #[serde(default)]
+    schema: Option<Value>,
+    #[serde(default)]
+    collection: Option<String>,
+    #[serde(default)]
+    confirm: bool,
 }
 
 impl Request {
+    /// Returns whether this request mutates state
+    pub fn is_write(&self) -> bool {
+        matches!(
+            self,
+            Request::Insert(_)
+                | Request::InsertMany(_)
+                | Request::Update(_)
+                | Request::Upsert(_)
+                | Request::Delete(_)
+                | Request::Transaction(_)
+                | Request::CreateSchema(_)
+                | Request::AlterSchema(_)
+                | Request::DeprecateSchema(_)
+                | Request::CreateCollection(_)
+                | Request::DropCollection(_)
+                | Request::TruncateCollection(_)
+                | Request::CreateIndex(_)
+        )
+    }
+
+    /// Returns whether this request reads documents
+    pub fn is_query(&self) -> bool {
+        matches!(
+            self,
+            Request::Get(_) | Request::Query(_) | Request::Count(_) | Request::Aggregate(_)
+        )
+    }
+
+    /// Operation name, as given in the request's `op` field
+    pub fn op_name(&self) -> &'static str {
+        match self {
+            Request::Insert(_) => "insert",
+            Request::InsertMany(_) => "insert_many",
+            Request::Update(_) => "update",
+            Request::Upsert(_) => "upsert",
+            Request::Delete(_) => "delete",
+            Request::Get(_) => "get",
+            Request::Query(_) => "query",
+            Request::Count(_) => "count",
+            Request::Aggregate(_) => "aggregate",
+            Request::Explain(_) => "explain",
+            Request::Transaction(_) => "transaction",
+            Request::CreateSchema(_) => "create_schema",
+            Request::AlterSchema(_) => "alter_schema",
+            Request::DeprecateSchema(_) => "deprecate_schema",
+            Request::ListSchemas(_) => "list_schemas",
+            Request::CreateCollection(_) => "create_collection",
+            Request::ListCollections => "list_collections",
+            Request::DropCollection(_) => "drop_collection",
+            Request::TruncateCollection(_) => "truncate_collection",
+            Request::CreateIndex(_) => "create_index",
+            Request::BeginRead => "begin_read",
+            Request::EndRead(_) => "end_read",
+        }
+    }
+
+    /// Ids of the documents this request writes, in request order
+    ///
+    /// Empty for reads and catalog operations, and for documents whose
+    /// `_id` is missing (those are rejected anyway).
+    pub fn written_document_ids(&self) -> Vec<String> {
+        fn id_of(document: &Value) -> Option<String> {
+            document
+                .get("_id")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        }
+        match self {
+            Request::Insert(r) => id_of(&r.document).into_iter().collect(),
+            Request::InsertMany(r) => r.documents.iter().filter_map(id_of).collect(),
+            Request::Update(r) => id_of(&r.document).into_iter().collect(),
+            Request::Upsert(r) => id_of(&r.document).into_iter().collect(),
+            Request::Delete(r) => vec![r.document_id.clone()],
+            Request::Transaction(r) => r
+                .ops
+                .iter()
+                .filter_map(|op| match op {
+                    TxnOp::Insert(r) => id_of(&r.document),
+                    TxnOp::Update(r) => id_of(&r.document),
+                    TxnOp::Delete(r) => Some(r.document_id.clone()),
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Schema ids of the documents this request reads or writes
+    ///
+    /// Empty for catalog operations.
+    pub fn document_schema_ids(&self) -> Vec<&str> {
+        match self {
+            Request::Insert(r) => vec![r.schema_id.as_str()],
+            Request::InsertMany(r) => vec![r.schema_id.as_str()],
+            Request::Update(r) => vec![r.schema_id.as_str()],
+            Request::Upsert(r) => vec![r.schema_id.as_str()],
+            Request::Delete(r) => vec![r.schema_id.as_str()],
+            Request::Get(r) => vec![r.schema_id.as_str()],
+            Request::Query(r) | Request::Count(r) | Request::Explain(r) => {
+                vec![r.schema_id.as_str()]
+            }
+            Request::Aggregate(r) => vec![r.query.schema_id.as_str()],
+            Request::Transaction(r) => r
+                .ops
+                .iter()
+                .map(|op| match op {
+                    TxnOp::Insert(r) => r.schema_id.as_str(),
+                    TxnOp::Update(r) => r.schema_id.as_str(),
+                    TxnOp::Delete(r) => r.schema_id.as_str(),
+                })
+                .collect(),
+            Request::CreateSchema(_)
+            | Request::AlterSchema(_)
+            | Request::DeprecateSchema(_)
+            | Request::ListSchemas(_)
+            | Request::CreateCollection(_)
+            | Request::ListCollections
+            | Request::DropCollection(_)
+            | Request::TruncateCollection(_)
+            | Request::CreateIndex(_)
+            | Request::BeginRead
+            | Request::EndRead(_) => Vec::new(),
+        }
+    }
+
     /// Parse a request from JSON string
     pub fn parse(json: &str) -> ApiResult<Self> {
-        let raw: RawRequest = serde_json::from_str(json)
+        Self::parse_routed(json).map(|routed| routed.request)
+    }
+
+    /// Parse a request and the collection it targets from JSON string
+    ///
+    /// For create, drop and truncate collection requests, `collection`
+    /// names the collection operated on and the request is not routed.
+    /// Index creation applies to every collection, and read views to the
+    /// whole store; neither is routed.
+    pub fn parse_routed(json: &str) -> ApiResult<RoutedRequest> {
+        let mut raw: RawRequest = serde_json::from_str(json)
             .map_err(|e| ApiError::invalid_request(format!("Invalid JSON: {}", e)))?;
 
+        let collection = match raw.op.as_str() {
+            "create_collection"
+            | "drop_collection"
+            | "truncate_collection"
+            | "create_index"
+            | "begin_read"
+            | "end_read" => None,
+            _ => raw.collection.take(),
+        };
+        Ok(RoutedRequest {
+            collection,
+            request: Self::from_raw(raw)?,
+        })
+    }
+
+    /// Build the query part shared by query, count, aggregate and explain
+    fn query_from_raw(raw: RawRequest) -> ApiResult<QueryRequest> {
+        let schema_id = raw
+            .schema_id
+            .ok_or_else(|| ApiError::invalid_request("Missing schema_id"))?;
+        let schema_version = raw
+            .schema_version
+            .ok_or_else(|| ApiError::invalid_request("Missing schema_version"))?;
+        let limit = raw
+            .limit
+            .ok_or_else(|| ApiError::invalid_request("Missing limit"))?;
+        if raw.read_view.is_some() && raw.as_of_commit_id.is_some() {
+            return Err(ApiError::invalid_request(
+                "read_view and as_of_commit_id are mutually exclusive",
+            ));
+        }
+
+        Ok(QueryRequest {
+            schema_id,
+            schema_version,
+            filter: raw.filter,
+            sort: raw.sort,
+            limit,
+            read_view: raw.read_view,
+            as_of_commit_id: raw.as_of_commit_id,
+            stats: raw.stats,
+            rls_predicates: Vec::new(),
+        })
+    }
+
+    /// Parse the schema definition of a create/alter schema request
+    fn schema_from_raw(raw: RawRequest) -> ApiResult<Schema> {
+        let schema = raw
+            .schema
+            .ok_or_else(|| ApiError::invalid_request("Missing schema"))?;
+        serde_json::from_value(schema)
+            .map_err(|e| ApiError::invalid_request(format!("Invalid schema: {}", e)))
+    }
+
+    /// Build a request from its raw (deserialized) form
+    fn from_raw(mut raw: RawRequest) -> ApiResult<Self> {
         match raw.op.as_str() {
             "insert" => {
                 let schema_id = raw
@@ -107,6 +498,26 @@ impl Request {
                     document,
                 }))
             }
+            "insert_many" => {
+                let schema_id = raw
+                    .schema_id
+                    .ok_or_else(|| ApiError::invalid_request("Missing schema_id"))?;
+                let schema_version = raw
+                    .schema_version
+                    .ok_or_else(|| ApiError::invalid_request("Missing schema_version"))?;
+                let documents = raw
+                    .documents
+                    .ok_or_else(|| ApiError::invalid_request("Missing documents"))?;
+                if documents.is_empty() {
+                    return Err(ApiError::invalid_request("documents must not be empty"));
+                }
+
+                Ok(Request::InsertMany(InsertManyRequest {
+                    schema_id,
+                    schema_version,
+                    documents,
+                }))
+            }
             "update" => {
                 let schema_id = raw
                     .schema_id
@@ -122,6 +533,25 @@ impl Request {
                     schema_id,
                     schema_version,
                     document,
+                    if_commit_id: raw.if_commit_id,
+                    if_checksum: raw.if_checksum,
+                }))
+            }
+            "upsert" => {
+                let schema_id = raw
+                    .schema_id
+                    .ok_or_else(|| ApiError::invalid_request("Missing schema_id"))?;
+                let schema_version = raw
+                    .schema_version
+                    .ok_or_else(|| ApiError::invalid_request("Missing schema_version"))?;
+                let document = raw
+                    .document
+                    .ok_or_else(|| ApiError::invalid_request("Missing document"))?;
+
+                Ok(Request::Upsert(UpsertRequest {
+                    schema_id,
+                    schema_version,
+                    document,
                 }))
             }
             "delete" => {
@@ -137,52 +567,211 @@ impl Request {
                     document_id,
                 }))
             }
-            "query" => {
+            "get" => {
                 let schema_id = raw
                     .schema_id
                     .ok_or_else(|| ApiError::invalid_request("Missing schema_id"))?;
-                let schema_version = raw
-                    .schema_version
-                    .ok_or_else(|| ApiError::invalid_request("Missing schema_version"))?;
-                let limit = raw
-                    .limit
-                    .ok_or_else(|| ApiError::invalid_request("Missing limit"))?;
+                let document_id = raw
+                    .document_id
+                    .ok_or_else(|| ApiError::invalid_request("Missing document_id"))?;
 
-                Ok(Request::Query(QueryRequest {
+                Ok(Request::Get(GetRequest {
                     schema_id,
-                    schema_version,
-                    filter: raw.filter,
-                    sort: raw.sort,
-                    limit,
+                    document_id,
+                }))
+            }
+            "query" => Ok(Request::Query(Self::query_from_raw(raw)?)),
+            "count" => Ok(Request::Count(Self::query_from_raw(raw)?)),
+            "aggregate" => {
+                let function = raw
+                    .function
+                    .take()
+                    .ok_or_else(|| ApiError::invalid_request("Missing function"))?;
+                let function = AggregateFunction::parse(&function).ok_or_else(|| {
+                    ApiError::invalid_request(format!(
+                        "Unknown aggregate function: {} (min, max, sum)",
+                        function
+                    ))
+                })?;
+                let field = raw
+                    .field
+                    .take()
+                    .ok_or_else(|| ApiError::invalid_request("Missing field"))?;
+
+                Ok(Request::Aggregate(AggregateRequest {
+                    query: Self::query_from_raw(raw)?,
+                    function,
+                    field,
+                }))
+            }
+            "explain" => Ok(Request::Explain(Self::query_from_raw(raw)?)),
+            "transaction" => {
+                let ops = raw
+                    .ops
+                    .ok_or_else(|| ApiError::invalid_request("Missing ops"))?;
+                if ops.is_empty() {
+                    return Err(ApiError::invalid_request("ops must not be empty"));
+                }
+
+                let ops = ops
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, value)| {
+                        let raw: RawRequest = serde_json::from_value(value).map_err(|e| {
+                            ApiError::invalid_request(format!("Invalid op {}: {}", i, e))
+                        })?;
+                        if raw.collection.is_some() {
+                            return Err(ApiError::invalid_request(format!(
+                                "Op {} names a collection; a transaction targets the \
+                                 request's collection",
+                                i
+                            )));
+                        }
+                        match Self::from_raw(raw)? {
+                            Request::Insert(r) => Ok(TxnOp::Insert(r)),
+                            Request::Update(r) => Ok(TxnOp::Update(r)),
+                            Request::Delete(r) => Ok(TxnOp::Delete(r)),
+                            _ => Err(ApiError::invalid_request(format!(
+                                "Op {} is not allowed in a transaction (insert, update, delete only)",
+                                i
+                            ))),
+                        }
+                    })
+                    .collect::<ApiResult<Vec<_>>>()?;
+
+                Ok(Request::Transaction(TransactionRequest {
+                    ops,
+                    read_view: raw.read_view,
                 }))
             }
-            "explain" => {
+            "create_schema" => Ok(Request::CreateSchema(Self::schema_from_raw(raw)?)),
+            "alter_schema" => Ok(Request::AlterSchema(Self::schema_from_raw(raw)?)),
+            "deprecate_schema" => {
                 let schema_id = raw
                     .schema_id
                     .ok_or_else(|| ApiError::invalid_request("Missing schema_id"))?;
                 let schema_version = raw
                     .schema_version
                     .ok_or_else(|| ApiError::invalid_request("Missing schema_version"))?;
-                let limit = raw
-                    .limit
-                    .ok_or_else(|| ApiError::invalid_request("Missing limit"))?;
 
-                Ok(Request::Explain(QueryRequest {
+                Ok(Request::DeprecateSchema(DeprecateSchemaRequest {
                     schema_id,
                     schema_version,
-                    filter: raw.filter,
-                    sort: raw.sort,
-                    limit,
                 }))
             }
+            "list_schemas" => Ok(Request::ListSchemas(ListSchemasRequest {
+                schema_id: raw.schema_id,
+            })),
+            "create_collection" => {
+                let collection = raw
+                    .collection
+                    .ok_or_else(|| ApiError::invalid_request("Missing collection"))?;
+                let schema_id = raw
+                    .schema_id
+                    .ok_or_else(|| ApiError::invalid_request("Missing schema_id"))?;
+
+                Ok(Request::CreateCollection(CreateCollectionRequest {
+                    collection,
+                    schema_id,
+                }))
+            }
+            "list_collections" => Ok(Request::ListCollections),
+            "create_index" => {
+                let field = raw
+                    .field
+                    .ok_or_else(|| ApiError::invalid_request("Missing field"))?;
+
+                Ok(Request::CreateIndex(CreateIndexRequest { field }))
+            }
+            "begin_read" => Ok(Request::BeginRead),
+            "end_read" => {
+                let id = raw
+                    .read_view
+                    .ok_or_else(|| ApiError::invalid_request("Missing read_view"))?;
+
+                Ok(Request::EndRead(id))
+            }
+            "drop_collection" | "truncate_collection" => {
+                let collection = raw
+                    .collection
+                    .ok_or_else(|| ApiError::invalid_request("Missing collection"))?;
+                if !raw.confirm {
+                    return Err(ApiError::invalid_request(format!(
+                        "{} removes every document of '{}'; resend with \"confirm\": true",
+                        raw.op, collection
+                    )));
+                }
+
+                let req = CollectionRequest { collection };
+                Ok(if raw.op == "drop_collection" {
+                    Request::DropCollection(req)
+                } else {
+                    Request::TruncateCollection(req)
+                })
+            }
             other => Err(ApiError::unknown_operation(other)),
         }
     }
 }
 
+/// JSON `query` request running a planner `query`
+///
+/// # Errors
+///
+/// Two predicates with the same operator on one field, which the request
+/// filter cannot express.
+pub fn query_request(query: &Query) -> ApiResult<Value> {
+    let mut filter = Map::new();
+    for predicate in &query.predicates {
+        let (op, operand) = match &predicate.op {
+            FilterOp::Eq(v) => ("$eq", v.clone()),
+            FilterOp::Gte(v) => ("$gte", v.clone()),
+            FilterOp::Gt(v) => ("$gt", v.clone()),
+            FilterOp::Lte(v) => ("$lte", v.clone()),
+            FilterOp::Lt(v) => ("$lt", v.clone()),
+            FilterOp::In(values) => ("$in", Value::Array(values.clone())),
+            FilterOp::Ne(v) => ("$ne", v.clone()),
+            FilterOp::Exists(present) => ("$exists", Value::Bool(*present)),
+            FilterOp::Prefix(prefix) => ("$prefix", Value::String(prefix.clone())),
+        };
+        let conditions = filter
+            .entry(predicate.field.clone())
+            .or_insert_with(|| json!({}));
+        if conditions.get(op).is_some() {
+            return Err(ApiError::invalid_request(format!(
+                "{} is constrained twice by {}",
+                predicate.field, op
+            )));
+        }
+        conditions[op] = operand;
+    }
+    let sort: Vec<String> = query
+        .sort
+        .iter()
+        .map(|s| match s.direction {
+            SortDirection::Asc => s.field.clone(),
+            SortDirection::Desc => format!("-{}", s.field),
+        })
+        .collect();
+
+    let mut request = json!({
+        "op": "query",
+        "collection": query.collection,
+        "schema_id": query.schema_id,
+        "schema_version": query.schema_version,
+        "filter": filter,
+        "limit": query.limit,
+    });
+    if !sort.is_empty() {
+        request["sort"] = Value::String(sort.join(","));
+    }
+    Ok(request)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::planner::{Predicate, SortSpec};
 
     #[test]
     fn test_parse_insert() {
@@ -203,6 +792,106 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_conditional_update() {
+        let json = r#"{
+            "op": "update",
+            "schema_id": "users",
+            "schema_version": "v1",
+            "document": {"_id": "user_1", "name": "Alice"},
+            "if_commit_id": 42
+        }"#;
+
+        match Request::parse(json).unwrap() {
+            Request::Update(r) => {
+                assert_eq!(r.if_commit_id, Some(42));
+                assert_eq!(r.if_checksum, None);
+            }
+            _ => panic!("Expected Update"),
+        }
+    }
+
+    #[test]
+    fn test_parse_upsert() {
+        let json = r#"{
+            "op": "upsert",
+            "schema_id": "users",
+            "schema_version": "v1",
+            "document": {"_id": "user_1", "name": "Alice"}
+        }"#;
+
+        let req = Request::parse(json).unwrap();
+        assert!(req.is_write());
+        assert!(matches!(req, Request::Upsert(_)));
+    }
+
+    #[test]
+    fn test_parse_get() {
+        let json = r#"{"op": "get", "schema_id": "users", "document_id": "user_1"}"#;
+
+        let req = Request::parse(json).unwrap();
+        assert!(!req.is_write());
+        match req {
+            Request::Get(r) => {
+                assert_eq!(r.schema_id, "users");
+                assert_eq!(r.document_id, "user_1");
+            }
+            _ => panic!("Expected Get"),
+        }
+
+        let missing = r#"{"op": "get", "schema_id": "users"}"#;
+        assert!(Request::parse(missing).is_err());
+    }
+
+    #[test]
+    fn test_parse_insert_many() {
+        let json = r#"{
+            "op": "insert_many",
+            "schema_id": "users",
+            "schema_version": "v1",
+            "documents": [{"_id": "user_1"}, {"_id": "user_2"}]
+        }"#;
+
+        let req = Request::parse(json).unwrap();
+        assert!(req.is_write());
+        match req {
+            Request::InsertMany(r) => assert_eq!(r.documents.len(), 2),
+            _ => panic!("Expected InsertMany request"),
+        }
+
+        let empty = r#"{"op": "insert_many", "schema_id": "users", "schema_version": "v1", "documents": []}"#;
+        assert!(Request::parse(empty).is_err());
+    }
+
+    #[test]
+    fn test_parse_transaction() {
+        let json = r#"{
+            "op": "transaction",
+            "ops": [
+                {"op": "insert", "schema_id": "users", "schema_version": "v1", "document": {"_id": "a"}},
+                {"op": "update", "schema_id": "users", "schema_version": "v1", "document": {"_id": "b"}},
+                {"op": "delete", "schema_id": "users", "document_id": "c"}
+            ]
+        }"#;
+
+        let req = Request::parse(json).unwrap();
+        assert!(req.is_write());
+        assert_eq!(req.op_name(), "transaction");
+        assert_eq!(req.written_document_ids(), vec!["a", "b", "c"]);
+        match req {
+            Request::Transaction(t) => {
+                assert_eq!(t.ops.len(), 3);
+                assert!(matches!(t.ops[0], TxnOp::Insert(_)));
+                assert!(matches!(t.ops[1], TxnOp::Update(_)));
+                assert!(matches!(t.ops[2], TxnOp::Delete(_)));
+            }
+            _ => panic!("Expected Transaction request"),
+        }
+
+        let nested = r#"{"op": "transaction", "ops": [{"op": "query", "schema_id": "users", "schema_version": "v1", "limit": 1}]}"#;
+        assert!(Request::parse(nested).is_err());
+    }
+
     #[test]
     fn test_parse_query() {
         let json = r#"{
@@ -218,9 +907,51 @@ mod tests {
             Request::Query(r) => {
                 assert_eq!(r.schema_id, "users");
                 assert_eq!(r.limit, 10);
+                assert!(!r.stats);
             }
             _ => panic!("Expected Query"),
         }
+
+        let json = r#"{"op": "query", "schema_id": "users", "schema_version": "v1", "limit": 1, "stats": true}"#;
+        assert!(matches!(Request::parse(json).unwrap(), Request::Query(r) if r.stats));
+
+        let json = r#"{"op": "count", "schema_id": "users", "schema_version": "v1", "limit": 1, "as_of_commit_id": 42}"#;
+        assert!(
+            matches!(Request::parse(json).unwrap(), Request::Count(r) if r.as_of_commit_id == Some(42))
+        );
+        let both = r#"{"op": "query", "schema_id": "users", "schema_version": "v1", "limit": 1, "as_of_commit_id": 42, "read_view": 1}"#;
+        assert!(Request::parse(both).is_err());
+    }
+
+    #[test]
+    fn test_parse_count_and_aggregate() {
+        let json = r#"{"op": "count", "schema_id": "users", "schema_version": "v1", "limit": 100}"#;
+        let req = Request::parse(json).unwrap();
+        assert!(!req.is_write());
+        assert!(matches!(req, Request::Count(ref r) if r.limit == 100));
+
+        let json = r#"{
+            "op": "aggregate",
+            "schema_id": "users",
+            "schema_version": "v1",
+            "function": "sum",
+            "field": "age",
+            "limit": 100
+        }"#;
+        match Request::parse(json).unwrap() {
+            Request::Aggregate(r) => {
+                assert_eq!(r.function, AggregateFunction::Sum);
+                assert_eq!(r.field, "age");
+                assert_eq!(r.query.schema_id, "users");
+            }
+            _ => panic!("Expected Aggregate"),
+        }
+
+        let bad = r#"{"op": "aggregate", "schema_id": "users", "schema_version": "v1", "function": "avg", "field": "age", "limit": 1}"#;
+        assert!(Request::parse(bad)
+            .unwrap_err()
+            .message()
+            .contains("Unknown aggregate function"));
     }
 
     #[test]
@@ -238,4 +969,129 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().message().contains("Missing"));
     }
+
+    #[test]
+    fn test_parse_schema_catalog_ops() {
+        let json = r#"{
+            "op": "create_schema",
+            "schema": {
+                "schema_id": "orders",
+                "schema_version": "v1",
+                "fields": {"_id": {"type": "string", "required": true}}
+            }
+        }"#;
+        match Request::parse(json).unwrap() {
+            Request::CreateSchema(schema) => {
+                assert_eq!(schema.key(), ("orders", "v1"));
+                assert!(!schema.deprecated);
+            }
+            _ => panic!("Expected CreateSchema"),
+        }
+
+        let req = Request::parse(
+            r#"{"op": "deprecate_schema", "schema_id": "orders", "schema_version": "v1"}"#,
+        )
+        .unwrap();
+        assert!(req.is_write());
+
+        let req = Request::parse(r#"{"op": "list_schemas"}"#).unwrap();
+        assert!(!req.is_write());
+
+        assert!(Request::parse(r#"{"op": "alter_schema"}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_collection_routing() {
+        let routed = Request::parse_routed(
+            r#"{"op": "get", "collection": "admins", "schema_id": "users", "document_id": "u1"}"#,
+        )
+        .unwrap();
+        assert_eq!(routed.collection.as_deref(), Some("admins"));
+        assert_eq!(routed.request.document_schema_ids(), vec!["users"]);
+
+        let routed = Request::parse_routed(
+            r#"{"op": "create_collection", "collection": "admins", "schema_id": "users"}"#,
+        )
+        .unwrap();
+        assert!(routed.collection.is_none());
+        match routed.request {
+            Request::CreateCollection(r) => assert_eq!(r.collection, "admins"),
+            _ => panic!("Expected CreateCollection"),
+        }
+
+        let json = r#"{
+            "op": "transaction",
+            "ops": [{"op": "delete", "collection": "admins", "schema_id": "users", "document_id": "u1"}]
+        }"#;
+        assert!(Request::parse(json).is_err());
+    }
+
+    #[test]
+    fn test_parse_drop_requires_confirmation() {
+        let json = r#"{"op": "drop_collection", "collection": "admins"}"#;
+        let err = Request::parse(json).unwrap_err();
+        assert!(err.message().contains("confirm"));
+
+        let json = r#"{"op": "truncate_collection", "collection": "admins", "confirm": true}"#;
+        let routed = Request::parse_routed(json).unwrap();
+        assert!(routed.collection.is_none());
+        assert!(routed.request.is_write());
+        match routed.request {
+            Request::TruncateCollection(r) => assert_eq!(r.collection, "admins"),
+            _ => panic!("Expected TruncateCollection"),
+        }
+    }
+
+    #[test]
+    fn test_parse_create_index() {
+        let routed = Request::parse_routed(r#"{"op": "create_index", "field": "name"}"#).unwrap();
+        assert!(routed.collection.is_none());
+        assert!(routed.request.is_write());
+        match routed.request {
+            Request::CreateIndex(r) => assert_eq!(r.field, "name"),
+            _ => panic!("Expected CreateIndex"),
+        }
+
+        assert!(Request::parse(r#"{"op": "create_index"}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_read_only_transaction() {
+        let routed =
+            Request::parse_routed(r#"{"op": "begin_read", "collection": "admins"}"#).unwrap();
+        assert!(routed.collection.is_none());
+        assert!(matches!(routed.request, Request::BeginRead));
+        assert!(!routed.request.is_write());
+
+        let req = Request::parse(r#"{"op": "end_read", "read_view": 7}"#).unwrap();
+        assert!(matches!(req, Request::EndRead(7)));
+        assert!(Request::parse(r#"{"op": "end_read"}"#).is_err());
+    }
+
+    #[test]
+    fn test_query_request() {
+        let query = Query::new("default", "users")
+            .with_schema_version("v1")
+            .with_predicate(Predicate::gte("age", json!(18)))
+            .with_predicate(Predicate::lt("age", json!(65)))
+            .with_predicate(Predicate::prefix("name", "A"))
+            .with_sort(SortSpec::desc("age"))
+            .with_sort(SortSpec::asc("name"))
+            .with_limit(10);
+        assert_eq!(
+            query_request(&query).unwrap(),
+            json!({
+                "op": "query",
+                "collection": "default",
+                "schema_id": "users",
+                "schema_version": "v1",
+                "filter": {"age": {"$gte": 18, "$lt": 65}, "name": {"$prefix": "A"}},
+                "sort": "-age,name",
+                "limit": 10,
+            })
+        );
+
+        let twice = query.with_predicate(Predicate::gte("age", json!(21)));
+        assert!(query_request(&twice).is_err());
+    }
 }
@@ -13,12 +13,15 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
 
-use super::crypto::PasswordPolicy;
+use super::crypto::{generate_token, PasswordPolicy};
 use super::email::{EmailSender, EmailTemplate};
 use super::errors::{AuthError, AuthResult};
 use super::jwt::{JwtConfig, JwtManager, TokenResponse};
+use super::mfa::{unix_now, MfaPolicy, TotpConfig, TotpFactor};
+use super::oidc::ExternalIdentity;
+use super::revocation::RevocationStore;
 use super::rls::RlsContext;
-use super::session::{SessionConfig, SessionManager, SessionRepository};
+use super::session::{Session, SessionConfig, SessionManager, SessionRepository};
 use super::user::{LoginRequest, SignupRequest, User, UserRepository};
 
 use chrono::{DateTime, Duration, Utc};
@@ -100,6 +103,17 @@ pub struct AuthService<U: UserRepository, S: SessionRepository> {
     password_policy: PasswordPolicy,
     reset_tokens: ResetTokenStore,
     email_sender: Arc<dyn EmailSender>,
+    mfa_policy: MfaPolicy,
+    totp_config: TotpConfig,
+}
+
+/// A started TOTP enrollment
+#[derive(Debug, Clone, Serialize)]
+pub struct TotpEnrollment {
+    /// Base32 secret, for manual entry
+    pub secret: String,
+    /// `otpauth://` URI, usually shown as a QR code
+    pub provisioning_uri: String,
 }
 
 impl<U: UserRepository, S: SessionRepository> AuthService<U, S> {
@@ -135,9 +149,41 @@ impl<U: UserRepository, S: SessionRepository> AuthService<U, S> {
             password_policy,
             reset_tokens: ResetTokenStore::default(),
             email_sender,
+            mfa_policy: MfaPolicy::default(),
+            totp_config: TotpConfig::default(),
         }
     }
 
+    /// Require a second factor at login as `policy` says
+    pub fn with_mfa_policy(mut self, policy: MfaPolicy) -> Self {
+        self.mfa_policy = policy;
+        self
+    }
+
+    /// Reject access tokens revoked in `revocations`, and record
+    /// revocations there
+    pub fn with_revocations(mut self, revocations: Arc<RevocationStore>) -> Self {
+        self.jwt_manager = self.jwt_manager.with_revocations(revocations);
+        self
+    }
+
+    /// Access token for `session` alongside its refresh token
+    fn issue_tokens(
+        &self,
+        user: &User,
+        session: &Session,
+        refresh_token: String,
+    ) -> AuthResult<TokenResponse> {
+        let access_token = self
+            .jwt_manager
+            .generate_session_access_token(user, session.family_id)?;
+        Ok(TokenResponse::new(
+            access_token,
+            refresh_token,
+            self.jwt_manager.get_expiration(),
+        ))
+    }
+
     /// Register a new user
     pub fn signup(&self, request: SignupRequest) -> AuthResult<(User, TokenResponse)> {
         // Check if email already exists
@@ -145,9 +191,12 @@ impl<U: UserRepository, S: SessionRepository> AuthService<U, S> {
             return Err(AuthError::EmailAlreadyExists);
         }
 
-        // Create user
+        // Create user; roles are assigned by operators, not chosen at signup
         let mut user = User::new(request.email, &request.password, &self.password_policy)?;
-        if let Some(metadata) = request.metadata {
+        if let Some(mut metadata) = request.metadata {
+            if let Some(fields) = metadata.as_object_mut() {
+                fields.remove("role");
+            }
             user.metadata = Some(metadata);
         }
 
@@ -155,55 +204,219 @@ impl<U: UserRepository, S: SessionRepository> AuthService<U, S> {
         self.user_repo.create(&user)?;
 
         // Create session
-        let (_, refresh_token) = self.session_manager.create_session(user.id, None, None)?;
+        let (session, refresh_token) = self.session_manager.create_session(user.id, None, None)?;
 
         // Generate tokens
-        let access_token = self.jwt_manager.generate_access_token(&user)?;
-        let token_response = TokenResponse::new(
-            access_token,
-            refresh_token,
-            self.jwt_manager.get_expiration(),
-        );
+        let token_response = self.issue_tokens(&user, &session, refresh_token)?;
 
         Ok((user, token_response))
     }
 
     /// Authenticate a user
+    ///
+    /// A user with a confirmed TOTP factor must send a current code; one
+    /// whose role the MFA policy covers must have enrolled a factor.
     pub fn login(&self, request: LoginRequest) -> AuthResult<(User, TokenResponse)> {
+        let mut user = self.authenticate_password(&request.email, &request.password)?;
+
+        // Second factor
+        if let Some(factor) = user.mfa.as_mut().filter(|factor| factor.confirmed) {
+            let code = request.totp_code.as_deref().ok_or(AuthError::MfaRequired)?;
+            // Failures are persisted too: they count towards the lockout
+            let verified = factor.verify(&self.totp_config, code, unix_now());
+            self.user_repo.update(&user)?;
+            verified?;
+        } else if self.mfa_policy.requires_mfa(&user) {
+            return Err(AuthError::MfaEnrollmentRequired);
+        }
+
+        // Create session
+        let (session, refresh_token) = self.session_manager.create_session(user.id, None, None)?;
+
+        // Generate tokens
+        let token_response = self.issue_tokens(&user, &session, refresh_token)?;
+
+        Ok((user, token_response))
+    }
+
+    /// User with `email` and `password`, without any second factor check
+    pub fn authenticate_password(&self, email: &str, password: &str) -> AuthResult<User> {
         // Find user by email
         let user = self
             .user_repo
-            .find_by_email(&request.email)?
+            .find_by_email(email)?
             .ok_or(AuthError::InvalidCredentials)?;
 
         // Verify password
-        if !user.verify_password(&request.password)? {
+        if !user.verify_password(password)? {
             return Err(AuthError::InvalidCredentials);
         }
+        Ok(user)
+    }
+
+    /// Start TOTP enrollment, replacing any unconfirmed factor
+    pub fn enroll_totp(&self, user_id: Uuid) -> AuthResult<TotpEnrollment> {
+        let mut user = self.get_user(user_id)?;
+        if user.has_confirmed_mfa() {
+            return Err(AuthError::MfaAlreadyEnrolled);
+        }
+        let factor = TotpFactor::generate();
+        let enrollment = TotpEnrollment {
+            secret: factor.secret.clone(),
+            provisioning_uri: factor.provisioning_uri(&self.totp_config, &user.email),
+        };
+        user.mfa = Some(factor);
+        user.updated_at = Utc::now();
+        self.user_repo.update(&user)?;
+        Ok(enrollment)
+    }
+
+    /// Confirm TOTP enrollment with a first code; later logins need codes
+    pub fn confirm_totp(&self, user_id: Uuid, code: &str) -> AuthResult<()> {
+        let mut user = self.get_user(user_id)?;
+        let factor = user.mfa.as_mut().ok_or(AuthError::InvalidMfaCode)?;
+        if factor.confirmed {
+            return Err(AuthError::MfaAlreadyEnrolled);
+        }
+        if let Err(e) = factor.verify(&self.totp_config, code, unix_now()) {
+            self.user_repo.update(&user)?;
+            return Err(e);
+        }
+        factor.confirmed = true;
+        user.updated_at = Utc::now();
+        self.user_repo.update(&user)
+    }
+
+    /// Remove a user's TOTP factor, proven with a current code
+    ///
+    /// Refused for roles the MFA policy covers.
+    pub fn disable_totp(&self, user_id: Uuid, code: &str) -> AuthResult<()> {
+        let mut user = self.get_user(user_id)?;
+        if self.mfa_policy.requires_mfa(&user) {
+            return Err(AuthError::Unauthorized);
+        }
+        let factor = user.mfa.as_mut().ok_or(AuthError::InvalidMfaCode)?;
+        if let Err(e) = factor.verify(&self.totp_config, code, unix_now()) {
+            self.user_repo.update(&user)?;
+            return Err(e);
+        }
+        user.mfa = None;
+        user.updated_at = Utc::now();
+        self.user_repo.update(&user)
+    }
+
+    /// Assign a user's role (operator action)
+    pub fn set_role(&self, user_id: Uuid, role: &str) -> AuthResult<User> {
+        let mut user = self.get_user(user_id)?;
+        let metadata = user.metadata.get_or_insert_with(|| serde_json::json!({}));
+        if !metadata.is_object() {
+            *metadata = serde_json::json!({});
+        }
+        metadata["role"] = serde_json::json!(role);
+        user.updated_at = Utc::now();
+        self.user_repo.update(&user)?;
+        Ok(user)
+    }
+
+    /// Sign in with an identity asserted by an OIDC provider
+    ///
+    /// The provider authenticates the user with its own factors. There is
+    /// no TOTP step in this flow, so a user with a confirmed TOTP factor or
+    /// a role the MFA policy covers is refused (`OidcMfaRequired`) unless
+    /// the provider asserted multi-factor authentication.
+    ///
+    /// The identity maps to the local user with its email. A user without
+    /// the identity linked in `metadata.identities` is only linked when the
+    /// provider verified the email; a new user is provisioned when none
+    /// has the email, with an unusable random password.
+    pub fn login_external(&self, identity: &ExternalIdentity) -> AuthResult<(User, TokenResponse)> {
+        let link = serde_json::json!({
+            "provider": identity.provider,
+            "subject": identity.subject,
+        });
+
+        let user = match self.user_repo.find_by_email(&identity.email)? {
+            Some(mut user) => {
+                let linked = user
+                    .metadata
+                    .as_ref()
+                    .and_then(|m| m.get("identities"))
+                    .and_then(|i| i.as_array())
+                    .is_some_and(|identities| identities.contains(&link));
+                if !linked {
+                    if !identity.email_verified {
+                        return Err(AuthError::EmailAlreadyExists);
+                    }
+                    let metadata = user.metadata.get_or_insert_with(|| serde_json::json!({}));
+                    if !metadata.is_object() {
+                        *metadata = serde_json::json!({});
+                    }
+                    let identities = metadata
+                        .as_object_mut()
+                        .expect("metadata is an object")
+                        .entry("identities")
+                        .or_insert_with(|| serde_json::json!([]));
+                    if !identities.is_array() {
+                        *identities = serde_json::json!([]);
+                    }
+                    identities
+                        .as_array_mut()
+                        .expect("identities is an array")
+                        .push(link);
+                    user.email_verified = true;
+                    user.updated_at = Utc::now();
+                    self.user_repo.update(&user)?;
+                }
+                user
+            }
+            None => {
+                let mut user = User::new(
+                    identity.email.clone(),
+                    &generate_token(),
+                    &PasswordPolicy::default(),
+                )?;
+                user.email_verified = identity.email_verified;
+                let mut metadata = serde_json::json!({ "identities": [link] });
+                if let Some(name) = &identity.name {
+                    metadata["name"] = serde_json::json!(name);
+                }
+                user.metadata = Some(metadata);
+                self.user_repo.create(&user)?;
+                user
+            }
+        };
+
+        if !identity.mfa_asserted
+            && (user.has_confirmed_mfa() || self.mfa_policy.requires_mfa(&user))
+        {
+            return Err(AuthError::OidcMfaRequired);
+        }
 
         // Create session
-        let (_, refresh_token) = self.session_manager.create_session(user.id, None, None)?;
+        let (session, refresh_token) = self.session_manager.create_session(user.id, None, None)?;
 
         // Generate tokens
-        let access_token = self.jwt_manager.generate_access_token(&user)?;
-        let token_response = TokenResponse::new(
-            access_token,
-            refresh_token,
-            self.jwt_manager.get_expiration(),
-        );
+        let token_response = self.issue_tokens(&user, &session, refresh_token)?;
 
         Ok((user, token_response))
     }
 
     /// Refresh access token
+    ///
+    /// Rotates the refresh token. Presenting a rotated token again revokes
+    /// the session, access tokens included (`RefreshTokenReused`).
     pub fn refresh(&self, refresh_token: &str) -> AuthResult<TokenResponse> {
         // Refresh session (invalidates old token)
-        let (_, new_refresh_token) = self.session_manager.refresh_session(refresh_token)?;
-
-        // Get session to find user
-        let session = self
-            .session_manager
-            .validate_refresh_token(&new_refresh_token)?;
+        let (session, new_refresh_token) = match self.session_manager.refresh_session(refresh_token)
+        {
+            Err(AuthError::RefreshTokenReused) => {
+                if let Some(session) = self.session_manager.find_session(refresh_token)? {
+                    self.jwt_manager.revoke_session_tokens(session.family_id)?;
+                }
+                return Err(AuthError::RefreshTokenReused);
+            }
+            result => result?,
+        };
 
         // Get user
         let user = self
@@ -212,19 +425,56 @@ impl<U: UserRepository, S: SessionRepository> AuthService<U, S> {
             .ok_or(AuthError::InvalidCredentials)?;
 
         // Generate new access token
-        let access_token = self.jwt_manager.generate_access_token(&user)?;
-
-        Ok(TokenResponse::new(
-            access_token,
-            new_refresh_token,
-            self.jwt_manager.get_expiration(),
-        ))
+        self.issue_tokens(&user, &session, new_refresh_token)
     }
 
-    /// Logout (invalidate session)
+    /// Logout (invalidate session, access tokens included)
     pub fn logout(&self, refresh_token: &str) -> AuthResult<()> {
         let session = self.session_manager.validate_refresh_token(refresh_token)?;
-        self.session_manager.revoke_session(session.id)
+        self.session_manager.revoke_family(session.family_id)?;
+        self.jwt_manager.revoke_session_tokens(session.family_id)
+    }
+
+    /// Revoke a refresh or access token (RFC 7009)
+    ///
+    /// A refresh token revokes its whole session; an access token only
+    /// itself. `token_type_hint` (`refresh_token` or `access_token`) says
+    /// which to try first. Unknown, expired or already revoked tokens are
+    /// not an error, so callers cannot probe which tokens exist.
+    pub fn revoke_token(&self, token: &str, token_type_hint: Option<&str>) -> AuthResult<()> {
+        let revoke_refresh = || -> AuthResult<bool> {
+            match self.session_manager.find_session(token)? {
+                Some(session) => {
+                    self.session_manager.revoke_family(session.family_id)?;
+                    self.jwt_manager.revoke_session_tokens(session.family_id)?;
+                    Ok(true)
+                }
+                None => Ok(false),
+            }
+        };
+        let revoke_access = || -> AuthResult<bool> {
+            match self.jwt_manager.validate_token(token) {
+                Ok(claims) => self.jwt_manager.revoke_token(&claims).map(|_| true),
+                Err(_) => Ok(false),
+            }
+        };
+
+        if token_type_hint == Some("access_token") {
+            if !revoke_access()? {
+                revoke_refresh()?;
+            }
+        } else if !revoke_refresh()? {
+            revoke_access()?;
+        }
+        Ok(())
+    }
+
+    /// Revoke every session of a user, access tokens included
+    fn revoke_user_sessions(&self, user_id: Uuid) -> AuthResult<()> {
+        for session in self.session_manager.get_user_sessions(user_id)? {
+            self.jwt_manager.revoke_session_tokens(session.family_id)?;
+        }
+        self.session_manager.revoke_all_user_sessions(user_id)
     }
 
     /// Get user by ID
@@ -241,8 +491,15 @@ impl<U: UserRepository, S: SessionRepository> AuthService<U, S> {
             .find_by_id(user_id)?
             .ok_or(AuthError::InvalidCredentials)?;
 
-        // Update metadata if provided
-        if let Some(metadata) = update.metadata {
+        // Update metadata if provided, keeping the operator-assigned role
+        if let Some(mut metadata) = update.metadata {
+            let role = user.metadata.as_ref().and_then(|m| m.get("role")).cloned();
+            if let Some(fields) = metadata.as_object_mut() {
+                fields.remove("role");
+                if let Some(role) = role {
+                    fields.insert("role".to_string(), role);
+                }
+            }
             user.metadata = Some(metadata);
         }
 
@@ -322,7 +579,7 @@ impl<U: UserRepository, S: SessionRepository> AuthService<U, S> {
         self.user_repo.update(&user)?;
 
         // Revoke all existing sessions for security
-        self.session_manager.revoke_all_user_sessions(user_id)?;
+        self.revoke_user_sessions(user_id)?;
 
         // Send password changed notification
         let _ = self.email_sender.send(EmailTemplate::PasswordChanged {
@@ -507,6 +764,7 @@ mod tests {
         let login = LoginRequest {
             email: "test@example.com".to_string(),
             password: "password123".to_string(),
+            totp_code: None,
         };
         let (user, tokens) = service.login(login).unwrap();
 
@@ -530,6 +788,7 @@ mod tests {
         let login = LoginRequest {
             email: "test@example.com".to_string(),
             password: "wrong_password".to_string(),
+            totp_code: None,
         };
         let result = service.login(login);
 
@@ -553,6 +812,49 @@ mod tests {
 
         assert!(!new_tokens.access_token.is_empty());
         assert_ne!(new_tokens.refresh_token, tokens.refresh_token);
+
+        // Reusing the rotated token revokes the session's access tokens
+        assert!(matches!(
+            service.refresh(&tokens.refresh_token),
+            Err(AuthError::RefreshTokenReused)
+        ));
+        assert!(matches!(
+            service.validate_access_token(&new_tokens.access_token),
+            Err(AuthError::TokenRevoked)
+        ));
+        assert!(service.refresh(&new_tokens.refresh_token).is_err());
+    }
+
+    #[test]
+    fn test_revoke_token() {
+        let service = create_test_service();
+        let signup = SignupRequest {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+            metadata: None,
+        };
+        let (_, tokens) = service.signup(signup).unwrap();
+        let login = LoginRequest {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+            totp_code: None,
+        };
+        let (_, other) = service.login(login).unwrap();
+
+        // An access token alone
+        service
+            .revoke_token(&tokens.access_token, Some("access_token"))
+            .unwrap();
+        assert!(service.validate_access_token(&tokens.access_token).is_err());
+        assert!(service.refresh(&tokens.refresh_token).is_ok());
+
+        // A refresh token's whole session, other logins untouched
+        service.revoke_token(&other.refresh_token, None).unwrap();
+        assert!(service.validate_access_token(&other.access_token).is_err());
+        assert!(service.refresh(&other.refresh_token).is_err());
+
+        // Unknown tokens are not an error
+        service.revoke_token("garbage", None).unwrap();
     }
 
     #[test]
@@ -593,4 +895,234 @@ mod tests {
         assert!(ctx.is_authenticated);
         assert_eq!(ctx.user_id, Some(user.id));
     }
+
+    #[test]
+    fn test_login_external_provisions_and_links() {
+        let service = create_test_service();
+        let identity = ExternalIdentity {
+            provider: "google".to_string(),
+            subject: "google-123".to_string(),
+            email: "ada@example.com".to_string(),
+            email_verified: true,
+            name: Some("Ada".to_string()),
+            mfa_asserted: false,
+        };
+
+        // First login provisions the user
+        let (user, _) = service.login_external(&identity).unwrap();
+        assert!(user.email_verified);
+        let metadata = user.metadata.clone().unwrap();
+        assert_eq!(metadata["identities"][0]["subject"], "google-123");
+        assert_eq!(metadata["name"], "Ada");
+
+        // Later logins find the same user
+        let (again, tokens) = service.login_external(&identity).unwrap();
+        assert_eq!(again.id, user.id);
+        assert!(service.validate_access_token(&tokens.access_token).is_ok());
+
+        // A password account is linked only with a provider-verified email
+        service
+            .signup(SignupRequest {
+                email: "grace@example.com".to_string(),
+                password: "password123".to_string(),
+                metadata: None,
+            })
+            .unwrap();
+        let unverified = ExternalIdentity {
+            email: "grace@example.com".to_string(),
+            email_verified: false,
+            ..identity.clone()
+        };
+        assert!(matches!(
+            service.login_external(&unverified),
+            Err(AuthError::EmailAlreadyExists)
+        ));
+        let (grace, _) = service
+            .login_external(&ExternalIdentity {
+                email_verified: true,
+                ..unverified
+            })
+            .unwrap();
+        assert_eq!(
+            grace.metadata.unwrap()["identities"]
+                .as_array()
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    fn current_code(secret: &str) -> String {
+        let secret = data_encoding::BASE32_NOPAD
+            .decode(secret.as_bytes())
+            .unwrap();
+        crate::auth::mfa::totp_code(&secret, unix_now() / 30, 6)
+    }
+
+    fn login_request(totp_code: Option<String>) -> LoginRequest {
+        LoginRequest {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+            totp_code,
+        }
+    }
+
+    #[test]
+    fn test_totp_login() {
+        let service = create_test_service();
+        let signup = SignupRequest {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+            metadata: None,
+        };
+        let (user, _) = service.signup(signup).unwrap();
+
+        // Unconfirmed enrollment does not affect login
+        let enrollment = service.enroll_totp(user.id).unwrap();
+        assert!(enrollment
+            .provisioning_uri
+            .starts_with("otpauth://totp/AeroDB:"));
+        assert!(service.login(login_request(None)).is_ok());
+        assert!(matches!(
+            service.confirm_totp(user.id, "000000x"),
+            Err(AuthError::InvalidMfaCode)
+        ));
+        service
+            .confirm_totp(user.id, &current_code(&enrollment.secret))
+            .unwrap();
+        assert!(matches!(
+            service.enroll_totp(user.id),
+            Err(AuthError::MfaAlreadyEnrolled)
+        ));
+
+        assert!(matches!(
+            service.login(login_request(None)),
+            Err(AuthError::MfaRequired)
+        ));
+        assert!(matches!(
+            service.login(login_request(Some("123456x".to_string()))),
+            Err(AuthError::InvalidMfaCode)
+        ));
+    }
+
+    #[test]
+    fn test_totp_lockout_rejects_valid_codes() {
+        let service = create_test_service();
+        let signup = SignupRequest {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+            metadata: None,
+        };
+        let (user, _) = service.signup(signup).unwrap();
+        let enrollment = service.enroll_totp(user.id).unwrap();
+        service
+            .confirm_totp(user.id, &current_code(&enrollment.secret))
+            .unwrap();
+
+        // Failed logins are persisted and lock the factor
+        let attempts = TotpConfig::default().max_failed_attempts;
+        for _ in 1..attempts {
+            assert!(matches!(
+                service.login(login_request(Some("123456x".to_string()))),
+                Err(AuthError::InvalidMfaCode)
+            ));
+        }
+        assert!(matches!(
+            service.login(login_request(Some("123456x".to_string()))),
+            Err(AuthError::MfaLocked)
+        ));
+
+        // A code the factor would otherwise accept (next step, within the
+        // drift window and not yet used) is rejected while locked
+        let secret = data_encoding::BASE32_NOPAD
+            .decode(enrollment.secret.as_bytes())
+            .unwrap();
+        let valid = crate::auth::mfa::totp_code(&secret, unix_now() / 30 + 1, 6);
+        assert!(matches!(
+            service.login(login_request(Some(valid.clone()))),
+            Err(AuthError::MfaLocked)
+        ));
+        assert!(matches!(
+            service.disable_totp(user.id, &valid),
+            Err(AuthError::MfaLocked)
+        ));
+        assert_eq!(AuthError::MfaLocked.status_code(), 429);
+    }
+
+    #[test]
+    fn test_login_external_applies_mfa_policy() {
+        let service = create_test_service().with_mfa_policy(MfaPolicy::require_for(["admin"]));
+        let identity = ExternalIdentity {
+            provider: "google".to_string(),
+            subject: "google-123".to_string(),
+            email: "ada@example.com".to_string(),
+            email_verified: true,
+            name: None,
+            mfa_asserted: false,
+        };
+        let (user, _) = service.login_external(&identity).unwrap();
+        service.set_role(user.id, "admin").unwrap();
+
+        // The role needs MFA and the provider did not assert it
+        assert!(matches!(
+            service.login_external(&identity),
+            Err(AuthError::OidcMfaRequired)
+        ));
+        let (admin, _) = service
+            .login_external(&ExternalIdentity {
+                mfa_asserted: true,
+                ..identity.clone()
+            })
+            .unwrap();
+        assert_eq!(admin.id, user.id);
+
+        // A confirmed TOTP factor is not skipped either
+        service.set_role(user.id, "authenticated").unwrap();
+        let enrollment = service.enroll_totp(user.id).unwrap();
+        service
+            .confirm_totp(user.id, &current_code(&enrollment.secret))
+            .unwrap();
+        assert!(matches!(
+            service.login_external(&identity),
+            Err(AuthError::OidcMfaRequired)
+        ));
+    }
+
+    #[test]
+    fn test_mfa_policy_by_role() {
+        let service = create_test_service().with_mfa_policy(MfaPolicy::require_for(["admin"]));
+        let signup = SignupRequest {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+            metadata: Some(serde_json::json!({"role": "admin", "team": "ops"})),
+        };
+
+        // Roles cannot be chosen at signup or changed by profile updates
+        let (user, _) = service.signup(signup).unwrap();
+        assert_eq!(user.role(), "authenticated");
+        service.set_role(user.id, "admin").unwrap();
+        let updated = service
+            .update_user(
+                user.id,
+                UpdateUserRequest {
+                    metadata: Some(serde_json::json!({"role": "authenticated"})),
+                },
+            )
+            .unwrap();
+        assert_eq!(updated.role(), "admin");
+
+        assert!(matches!(
+            service.login(login_request(None)),
+            Err(AuthError::MfaEnrollmentRequired)
+        ));
+        let enrollment = service.enroll_totp(user.id).unwrap();
+        let code = current_code(&enrollment.secret);
+        service.confirm_totp(user.id, &code).unwrap();
+        // AUTH-MFA1: the confirmation code cannot be replayed at login
+        assert!(service.login(login_request(Some(code.clone()))).is_err());
+        assert!(matches!(
+            service.disable_totp(user.id, &code),
+            Err(AuthError::Unauthorized)
+        ));
+    }
 }
@@ -44,6 +44,10 @@ pub enum AuthError {
     #[error("Session has been revoked")]
     SessionRevoked,
 
+    /// A rotated refresh token was presented again; its session is revoked
+    #[error("Refresh token reuse detected; session revoked")]
+    RefreshTokenReused,
+
     // ==================
     // JWT Errors
     // ==================
@@ -59,6 +63,45 @@ pub enum AuthError {
     #[error("Invalid token signature")]
     InvalidSignature,
 
+    /// Access token (or its session) has been revoked
+    #[error("Token has been revoked")]
+    TokenRevoked,
+
+    // ==================
+    // MFA Errors
+    // ==================
+    /// Login needs a TOTP code
+    #[error("MFA code required")]
+    MfaRequired,
+
+    /// TOTP code is wrong, expired or already used
+    #[error("Invalid MFA code")]
+    InvalidMfaCode,
+
+    /// The user's role requires MFA but no factor is enrolled
+    #[error("MFA enrollment required")]
+    MfaEnrollmentRequired,
+
+    /// A confirmed factor is already enrolled
+    #[error("MFA already enrolled")]
+    MfaAlreadyEnrolled,
+
+    /// Too many wrong TOTP codes; the factor is locked for a while
+    #[error("Too many failed MFA attempts; try again later")]
+    MfaLocked,
+
+    /// API key has been revoked
+    #[error("API key has been revoked")]
+    ApiKeyRevoked,
+
+    /// No API key with this ID is registered
+    #[error("API key not found")]
+    ApiKeyNotFound,
+
+    /// API key name or scopes are unusable
+    #[error("Invalid API key scope: {0}")]
+    InvalidKeyScope(String),
+
     // ==================
     // RLS Errors
     // ==================
@@ -78,6 +121,10 @@ pub enum AuthError {
     #[error("Invalid RLS policy: {0}")]
     InvalidPolicy(String),
 
+    /// An RLS policy rejected the operation
+    #[error("Denied by RLS policy: {0}")]
+    PolicyDenied(String),
+
     // ==================
     // Internal Errors
     // ==================
@@ -100,6 +147,25 @@ pub enum AuthError {
     /// Email sending failed
     #[error("Email error: {0}")]
     EmailError(String),
+
+    // ==================
+    // External Identity Errors
+    // ==================
+    /// No OIDC provider with this name is configured
+    #[error("Unknown identity provider: {0}")]
+    OidcProviderNotFound(String),
+
+    /// The provider's callback, token exchange or ID token was rejected
+    #[error("External login failed: {0}")]
+    OidcLoginFailed(String),
+
+    /// The user needs MFA but the provider did not assert it was used
+    #[error("Identity provider did not assert multi-factor authentication")]
+    OidcMfaRequired,
+
+    /// The provider could not be reached or answered unusably
+    #[error("Identity provider unavailable: {0}")]
+    OidcProviderUnavailable(String),
 }
 
 impl AuthError {
@@ -110,6 +176,7 @@ impl AuthError {
             AuthError::WeakPassword(_) => 400,
             AuthError::MalformedToken => 400,
             AuthError::InvalidPolicy(_) => 400,
+            AuthError::InvalidKeyScope(_) => 400,
 
             // 401 Unauthorized
             AuthError::InvalidCredentials => 401,
@@ -118,22 +185,42 @@ impl AuthError {
             AuthError::SessionRevoked => 401,
             AuthError::TokenExpired => 401,
             AuthError::InvalidSignature => 401,
+            AuthError::ApiKeyRevoked => 401,
             AuthError::AuthenticationRequired => 401,
             AuthError::InvalidToken => 401,
+            AuthError::TokenRevoked => 401,
+            AuthError::RefreshTokenReused => 401,
+            AuthError::MfaRequired => 401,
+            AuthError::InvalidMfaCode => 401,
+            AuthError::OidcLoginFailed(_) => 401,
 
             // 403 Forbidden
             AuthError::EmailNotVerified => 403,
             AuthError::Unauthorized => 403,
             AuthError::MissingOwnerField(_) => 403,
+            AuthError::PolicyDenied(_) => 403,
+            AuthError::MfaEnrollmentRequired => 403,
+            AuthError::OidcMfaRequired => 403,
+
+            // 404 Not Found
+            AuthError::ApiKeyNotFound => 404,
+            AuthError::OidcProviderNotFound(_) => 404,
 
             // 409 Conflict
             AuthError::EmailAlreadyExists => 409,
+            AuthError::MfaAlreadyEnrolled => 409,
+
+            // 429 Too Many Requests
+            AuthError::MfaLocked => 429,
 
             // 500 Internal Server Error
             AuthError::HashingFailed => 500,
             AuthError::TokenGenerationFailed => 500,
             AuthError::StorageError(_) => 500,
             AuthError::EmailError(_) => 500,
+
+            // 502 Bad Gateway
+            AuthError::OidcProviderUnavailable(_) => 502,
         }
     }
 
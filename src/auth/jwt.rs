@@ -3,16 +3,20 @@
 //! JSON Web Token generation and validation.
 //!
 //! ## Invariants
-//! - AUTH-JWT1: Stateless validation (no DB lookup)
+//! - AUTH-JWT1: Stateless validation (no DB lookup; revocations are held
+//!   in memory)
 //! - AUTH-JWT2: Short expiration (15 minutes)
 //! - AUTH-JWT3: No secrets in token
 
-use chrono::{Duration, Utc};
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
 use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use super::errors::{AuthError, AuthResult};
+use super::revocation::RevocationStore;
 use super::user::User;
 
 /// JWT claims for access tokens
@@ -38,6 +42,14 @@ pub struct JwtClaims {
 
     /// Whether email is verified
     pub email_verified: bool,
+
+    /// Token ID, for revoking this token alone
+    #[serde(default)]
+    pub jti: String,
+
+    /// Session (refresh token family) the token was issued for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
 }
 
 /// JWT configuration
@@ -73,6 +85,7 @@ pub struct JwtManager {
     config: JwtConfig,
     encoding_key: EncodingKey,
     decoding_key: DecodingKey,
+    revocations: Arc<RevocationStore>,
 }
 
 impl JwtManager {
@@ -85,15 +98,36 @@ impl JwtManager {
             config,
             encoding_key,
             decoding_key,
+            revocations: Arc::new(RevocationStore::new()),
         }
     }
 
+    /// Reject tokens revoked in `revocations`
+    pub fn with_revocations(mut self, revocations: Arc<RevocationStore>) -> Self {
+        self.revocations = revocations;
+        self
+    }
+
     /// Generate an access token for a user
     ///
     /// # Invariants
     /// - AUTH-JWT2: Token expires in 15 minutes
     /// - AUTH-JWT3: No secrets in token (only user ID, email, verification status)
     pub fn generate_access_token(&self, user: &User) -> AuthResult<String> {
+        self.issue(user, None)
+    }
+
+    /// Generate an access token for a user's session, revoked along with
+    /// the session's refresh token family
+    pub fn generate_session_access_token(
+        &self,
+        user: &User,
+        family_id: Uuid,
+    ) -> AuthResult<String> {
+        self.issue(user, Some(family_id))
+    }
+
+    fn issue(&self, user: &User, family_id: Option<Uuid>) -> AuthResult<String> {
         let now = Utc::now();
         let exp = now + self.config.access_token_ttl;
 
@@ -105,6 +139,8 @@ impl JwtManager {
             aud: self.config.audience.clone(),
             iss: self.config.issuer.clone(),
             email_verified: user.email_verified,
+            jti: Uuid::new_v4().to_string(),
+            sid: family_id.map(|id| id.to_string()),
         };
 
         encode(&Header::default(), &claims, &self.encoding_key)
@@ -131,7 +167,37 @@ impl JwtManager {
                 }
             })?;
 
-        Ok(token_data.claims)
+        let claims = token_data.claims;
+        if self.revocations.is_revoked(&claims.jti)
+            || claims
+                .sid
+                .as_deref()
+                .is_some_and(|sid| self.revocations.is_revoked(sid))
+        {
+            return Err(AuthError::TokenRevoked);
+        }
+        Ok(claims)
+    }
+
+    /// Revoke one access token until it expires
+    ///
+    /// Tokens without a `jti` cannot be revoked individually.
+    pub fn revoke_token(&self, claims: &JwtClaims) -> AuthResult<()> {
+        if claims.jti.is_empty() {
+            return Err(AuthError::MalformedToken);
+        }
+        let until = DateTime::from_timestamp(claims.exp, 0).unwrap_or_else(Utc::now);
+        self.revocations.revoke(&claims.jti, until)
+    }
+
+    /// Revoke every access token issued for session `family_id`
+    pub fn revoke_session_tokens(&self, family_id: Uuid) -> AuthResult<()> {
+        // Tokens issued from now on are not for this session, so none
+        // outlives one lifetime from now
+        self.revocations.revoke(
+            &family_id.to_string(),
+            Utc::now() + self.config.access_token_ttl,
+        )
     }
 
     /// Extract user ID from validated claims
@@ -270,6 +336,8 @@ mod tests {
             aud: "test".to_string(),
             iss: "test".to_string(),
             email_verified: false,
+            jti: Uuid::new_v4().to_string(),
+            sid: None,
         };
 
         let token = encode(&Header::default(), &claims, &encoding_key).unwrap();
@@ -310,4 +378,33 @@ mod tests {
         assert!(!token.contains("password"));
         assert!(!token.contains(&user.password_hash));
     }
+
+    #[test]
+    fn test_revoked_tokens_rejected() {
+        let manager = create_test_manager();
+        let user = create_test_user();
+        let family = Uuid::new_v4();
+
+        let token = manager.generate_access_token(&user).unwrap();
+        let claims = manager.validate_token(&token).unwrap();
+        manager.revoke_token(&claims).unwrap();
+        assert!(matches!(
+            manager.validate_token(&token),
+            Err(AuthError::TokenRevoked)
+        ));
+
+        let first = manager
+            .generate_session_access_token(&user, family)
+            .unwrap();
+        let second = manager
+            .generate_session_access_token(&user, family)
+            .unwrap();
+        let other = manager
+            .generate_session_access_token(&user, Uuid::new_v4())
+            .unwrap();
+        manager.revoke_session_tokens(family).unwrap();
+        assert!(manager.validate_token(&first).is_err());
+        assert!(manager.validate_token(&second).is_err());
+        assert!(manager.validate_token(&other).is_ok());
+    }
 }
@@ -0,0 +1,311 @@
+//! # Multi-Factor Authentication
+//!
+//! Time-based one-time passwords (TOTP, RFC 6238) as a second login factor.
+//!
+//! A user enrolls by generating a secret, adding it to an authenticator
+//! app through the `otpauth://` provisioning URI (usually shown as a QR
+//! code), and confirming with a first code. From then on login needs a
+//! current code as well as the password. `MfaPolicy` can require a factor
+//! for users of given roles, who cannot log in until they enroll.
+//!
+//! ## Invariants
+//! - AUTH-MFA1: A code is accepted at most once (no replay within its window)
+//! - AUTH-MFA2: Secrets are never serialized with the user
+//! - AUTH-MFA3: After `max_failed_attempts` wrong codes in a row the factor
+//!   rejects every code, valid or not, until the lockout expires
+
+use std::collections::BTreeSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use data_encoding::BASE32_NOPAD;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+
+use super::crypto::constant_time_str_eq;
+use super::errors::{AuthError, AuthResult};
+use super::user::User;
+
+/// Secret length (RFC 4226 §4 recommends 160 bits)
+const SECRET_LEN: usize = 20;
+
+/// TOTP parameters
+///
+/// Authenticator apps widely support only SHA-1, so that is the only
+/// algorithm offered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TotpConfig {
+    /// Issuer shown in authenticator apps
+    pub issuer: String,
+    /// Code length
+    pub digits: u32,
+    /// Seconds per time step
+    pub period: u64,
+    /// Steps of clock drift accepted either side of the current one
+    pub skew: u64,
+    /// Wrong codes in a row before the factor locks
+    pub max_failed_attempts: u32,
+    /// Seconds a locked factor rejects every code
+    pub lockout_secs: u64,
+}
+
+impl Default for TotpConfig {
+    fn default() -> Self {
+        Self {
+            issuer: "AeroDB".to_string(),
+            digits: 6,
+            period: 30,
+            skew: 1,
+            max_failed_attempts: 5,
+            lockout_secs: 300,
+        }
+    }
+}
+
+/// A user's TOTP factor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TotpFactor {
+    /// Base32 secret shared with the authenticator
+    pub secret: String,
+    /// Whether a first code confirmed enrollment
+    pub confirmed: bool,
+    /// Time step of the last accepted code
+    pub last_used_step: Option<u64>,
+    /// Wrong codes since the last accepted one or lockout
+    #[serde(default)]
+    pub failed_attempts: u32,
+    /// Unix time until which every code is rejected
+    #[serde(default)]
+    pub locked_until: Option<u64>,
+}
+
+impl TotpFactor {
+    /// Unconfirmed factor with a fresh random secret
+    pub fn generate() -> Self {
+        let mut secret = [0u8; SECRET_LEN];
+        OsRng.fill_bytes(&mut secret);
+        Self {
+            secret: BASE32_NOPAD.encode(&secret),
+            confirmed: false,
+            last_used_step: None,
+            failed_attempts: 0,
+            locked_until: None,
+        }
+    }
+
+    /// `otpauth://` URI provisioning this factor for `account`
+    pub fn provisioning_uri(&self, config: &TotpConfig, account: &str) -> String {
+        format!(
+            "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+            uri_encode(&config.issuer),
+            uri_encode(account),
+            self.secret,
+            uri_encode(&config.issuer),
+            config.digits,
+            config.period
+        )
+    }
+
+    /// Check `code` at `unix_time`, recording its step if accepted
+    ///
+    /// A rejected code counts towards the lockout, so callers persist the
+    /// factor whether or not the code is accepted.
+    ///
+    /// # Invariants
+    /// AUTH-MFA1: A step at or before the last accepted one is rejected
+    /// AUTH-MFA3: While locked every code is rejected with `MfaLocked`
+    pub fn verify(&mut self, config: &TotpConfig, code: &str, unix_time: u64) -> AuthResult<()> {
+        if self.locked_until.is_some_and(|until| unix_time < until) {
+            return Err(AuthError::MfaLocked);
+        }
+        let secret = BASE32_NOPAD
+            .decode(self.secret.as_bytes())
+            .map_err(|_| AuthError::StorageError("Invalid TOTP secret".to_string()))?;
+        let current = unix_time / config.period;
+        let code = code.trim();
+
+        let accepted = (current.saturating_sub(config.skew)..=current + config.skew)
+            .filter(|step| self.last_used_step.is_none_or(|last| *step > last))
+            .find(|step| constant_time_str_eq(&totp_code(&secret, *step, config.digits), code));
+        match accepted {
+            Some(step) => {
+                self.last_used_step = Some(step);
+                self.failed_attempts = 0;
+                self.locked_until = None;
+                Ok(())
+            }
+            None => {
+                self.failed_attempts += 1;
+                if self.failed_attempts >= config.max_failed_attempts {
+                    self.failed_attempts = 0;
+                    self.locked_until = Some(unix_time + config.lockout_secs);
+                    return Err(AuthError::MfaLocked);
+                }
+                Err(AuthError::InvalidMfaCode)
+            }
+        }
+    }
+}
+
+/// HOTP value of `secret` at `counter` (RFC 4226 §5.3), `digits` long
+pub fn totp_code(secret: &[u8], counter: u64, digits: u32) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, secret);
+    let digest = hmac::sign(&key, &counter.to_be_bytes());
+    let digest = digest.as_ref();
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(digits),
+        width = digits as usize
+    )
+}
+
+/// Seconds since the Unix epoch
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters
+fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Which users need a second factor
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MfaPolicy {
+    /// Roles that must log in with a confirmed TOTP factor
+    pub required_roles: BTreeSet<String>,
+}
+
+impl MfaPolicy {
+    /// Policy requiring MFA for `roles`
+    pub fn require_for<I, S>(roles: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            required_roles: roles.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Whether `user` must present a second factor
+    pub fn requires_mfa(&self, user: &User) -> bool {
+        self.required_roles.contains(user.role())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 6238 Appendix B secret for SHA-1
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        for (time, code) in [
+            (59u64, "94287082"),
+            (1111111109, "07081804"),
+            (1234567890, "89005924"),
+            (20000000000, "65353130"),
+        ] {
+            assert_eq!(totp_code(RFC_SECRET, time / 30, 8), code);
+        }
+    }
+
+    fn factor() -> TotpFactor {
+        TotpFactor {
+            secret: BASE32_NOPAD.encode(RFC_SECRET),
+            confirmed: true,
+            last_used_step: None,
+            failed_attempts: 0,
+            locked_until: None,
+        }
+    }
+
+    #[test]
+    fn test_drift_window_and_replay() {
+        let config = TotpConfig::default();
+        let now = 1_700_000_000;
+        let code_at = |time: u64| totp_code(RFC_SECRET, time / 30, 6);
+
+        // One step of drift either way
+        assert!(factor().verify(&config, &code_at(now - 30), now).is_ok());
+        assert!(factor().verify(&config, &code_at(now + 30), now).is_ok());
+        assert!(matches!(
+            factor().verify(&config, &code_at(now - 90), now),
+            Err(AuthError::InvalidMfaCode)
+        ));
+
+        // AUTH-MFA1: a code, or an earlier one, is not accepted twice
+        let mut factor = factor();
+        factor.verify(&config, &code_at(now), now).unwrap();
+        assert!(factor.verify(&config, &code_at(now), now).is_err());
+        assert!(factor.verify(&config, &code_at(now - 30), now).is_err());
+        assert!(factor.verify(&config, &code_at(now + 30), now).is_ok());
+    }
+
+    #[test]
+    fn test_lockout_after_failed_attempts() {
+        let config = TotpConfig::default();
+        let now = 1_700_000_000;
+        let code_at = |time: u64| totp_code(RFC_SECRET, time / 30, 6);
+        let wrong = "000000x";
+
+        let mut factor = factor();
+        for _ in 1..config.max_failed_attempts {
+            assert!(matches!(
+                factor.verify(&config, wrong, now),
+                Err(AuthError::InvalidMfaCode)
+            ));
+        }
+        assert!(matches!(
+            factor.verify(&config, wrong, now),
+            Err(AuthError::MfaLocked)
+        ));
+
+        // AUTH-MFA3: even a valid code is rejected while locked
+        assert!(matches!(
+            factor.verify(&config, &code_at(now), now),
+            Err(AuthError::MfaLocked)
+        ));
+        let later = now + config.lockout_secs;
+        assert!(factor.verify(&config, &code_at(later), later).is_ok());
+        assert_eq!(factor.locked_until, None);
+    }
+
+    #[test]
+    fn test_provisioning_uri() {
+        let factor = TotpFactor::generate();
+        assert_eq!(
+            BASE32_NOPAD.decode(factor.secret.as_bytes()).unwrap().len(),
+            20
+        );
+        let uri = factor.provisioning_uri(&TotpConfig::default(), "ada+db@example.com");
+        assert_eq!(
+            uri,
+            format!(
+                "otpauth://totp/AeroDB:ada%2Bdb%40example.com?secret={}&issuer=AeroDB&algorithm=SHA1&digits=6&period=30",
+                factor.secret
+            )
+        );
+    }
+}
@@ -10,12 +10,26 @@ pub mod crypto;
 pub mod email;
 pub mod errors;
 pub mod jwt;
+pub mod mfa;
+pub mod oidc;
+pub mod oidc_transport;
+pub mod revocation;
 pub mod rls;
+pub mod rls_policy;
+pub mod rls_store;
+pub mod service_keys;
 pub mod session;
 pub mod user;
 
 pub use errors::{AuthError, AuthResult};
 pub use jwt::{JwtClaims, JwtManager};
+pub use mfa::{MfaPolicy, TotpConfig, TotpFactor};
+pub use oidc::{ExternalIdentity, OidcManager, OidcProviderConfig, OidcTransport};
+pub use oidc_transport::HttpsTransport;
+pub use revocation::RevocationStore;
 pub use rls::{RlsContext, RlsEnforcer, RlsPolicy};
+pub use rls_policy::{RlsAction, RlsDecision, RlsPolicySet};
+pub use rls_store::{PublishedPolicies, RlsPolicyStore};
+pub use service_keys::{KeyScope, ServiceKey, ServiceKeyGrant, ServiceKeyManager};
 pub use session::{Session, SessionManager};
 pub use user::{User, UserRepository};
@@ -0,0 +1,697 @@
+//! # OpenID Connect Login
+//!
+//! Sign-in through external identity providers with the authorization
+//! code flow and PKCE.
+//!
+//! 1. `authorize` discovers the provider, records a pending login (state,
+//!    nonce, PKCE verifier) and returns the provider's authorization URL.
+//! 2. The provider redirects back with `code` and `state`; `callback`
+//!    consumes the pending login, exchanges the code at the token
+//!    endpoint and validates the returned ID token against the
+//!    provider's JWKS (signature, issuer, audience, expiry, nonce).
+//! 3. The resulting `ExternalIdentity` is mapped to a local user by
+//!    `AuthService::login_external`.
+//!
+//! ## Invariants
+//! - AUTH-OIDC1: A state value is accepted once, and only before it expires
+//! - AUTH-OIDC2: ID tokens are only accepted with an asymmetric signature
+//!   from a key in the provider's JWKS
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::crypto::generate_token;
+use super::errors::{AuthError, AuthResult};
+
+/// An external OpenID Connect provider (`[[http.oidc]]`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OidcProviderConfig {
+    /// Name used in routes, e.g. `/auth/oidc/{name}/authorize`
+    pub name: String,
+    /// Issuer URL; discovery reads `{issuer}/.well-known/openid-configuration`
+    pub issuer: String,
+    /// Client ID registered with the provider
+    pub client_id: String,
+    /// Client secret, for confidential clients (PKCE is always used)
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    /// Callback URL registered with the provider
+    pub redirect_uri: String,
+    /// Scopes requested (default: openid, email, profile)
+    #[serde(default = "default_scopes")]
+    pub scopes: Vec<String>,
+}
+
+fn default_scopes() -> Vec<String> {
+    vec![
+        "openid".to_string(),
+        "email".to_string(),
+        "profile".to_string(),
+    ]
+}
+
+impl OidcProviderConfig {
+    /// Check the provider can be used for login
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty()
+            || !self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!(
+                "provider name '{}' must be non-empty ASCII letters, digits, '-' or '_'",
+                self.name
+            ));
+        }
+        if self.client_id.is_empty() {
+            return Err(format!("provider '{}' has no client_id", self.name));
+        }
+        if !is_secure_url(&self.issuer) {
+            return Err(format!(
+                "provider '{}' issuer '{}' must be an https:// URL (http:// only on loopback)",
+                self.name, self.issuer
+            ));
+        }
+        if !self.redirect_uri.starts_with("https://") && !self.redirect_uri.starts_with("http://") {
+            return Err(format!(
+                "provider '{}' redirect_uri '{}' must be an absolute http(s) URL",
+                self.name, self.redirect_uri
+            ));
+        }
+        if !self.scopes.iter().any(|s| s == "openid") {
+            return Err(format!(
+                "provider '{}' scopes must include 'openid'",
+                self.name
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Whether `url` is https, or http to a loopback host
+pub(crate) fn is_secure_url(url: &str) -> bool {
+    if url.starts_with("https://") {
+        return true;
+    }
+    let Some(rest) = url.strip_prefix("http://") else {
+        return false;
+    };
+    let host = rest.split(['/', '?']).next().unwrap_or_default();
+    let host = host
+        .rsplit_once(':')
+        .filter(|(_, port)| port.chars().all(|c| c.is_ascii_digit()))
+        .map_or(host, |(host, _)| host);
+    matches!(host, "localhost" | "127.0.0.1" | "[::1]")
+}
+
+/// HTTP access to identity providers
+///
+/// Responses are `(status, JSON body)`; failing to reach the provider or
+/// to parse its response is `AuthError::OidcProviderUnavailable`.
+pub trait OidcTransport: Send + Sync {
+    /// GET `url`
+    fn get(&self, url: &str) -> AuthResult<(u16, Value)>;
+
+    /// POST `form` to `url` as `application/x-www-form-urlencoded`
+    fn post_form(&self, url: &str, form: &[(&str, &str)]) -> AuthResult<(u16, Value)>;
+}
+
+/// Provider metadata from discovery (OpenID Connect Discovery 1.0)
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+/// Where to send the user to sign in
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthorizationRequest {
+    /// Provider authorization URL, with state, nonce and PKCE challenge
+    pub url: String,
+    /// State value the provider echoes back to the callback
+    pub state: String,
+}
+
+/// An identity asserted by a provider's validated ID token
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalIdentity {
+    /// Provider name
+    pub provider: String,
+    /// Subject: the provider's stable user identifier
+    pub subject: String,
+    /// Email address
+    pub email: String,
+    /// Whether the provider verified the email address
+    pub email_verified: bool,
+    /// Display name, if the provider sent one
+    pub name: Option<String>,
+    /// Whether the provider asserted multi-factor authentication
+    /// (`amr` holds `mfa`, RFC 8176)
+    pub mfa_asserted: bool,
+}
+
+/// A login between `authorize` and `callback`
+#[derive(Debug, Clone)]
+struct PendingLogin {
+    provider: String,
+    code_verifier: String,
+    nonce: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// Discovery results of one provider
+#[derive(Debug, Clone)]
+struct Discovered {
+    metadata: ProviderMetadata,
+    jwks: JwkSet,
+}
+
+/// ID token claims read during validation
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    #[serde(default)]
+    email: Option<String>,
+    /// Some providers send `"true"` rather than `true`
+    #[serde(default)]
+    email_verified: Option<Value>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    nonce: Option<String>,
+    /// Authentication methods references
+    #[serde(default)]
+    amr: Vec<String>,
+}
+
+/// PKCE S256 code challenge of `verifier` (RFC 7636)
+pub fn pkce_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// OpenID Connect login across the configured providers
+pub struct OidcManager {
+    providers: HashMap<String, OidcProviderConfig>,
+    transport: Arc<dyn OidcTransport>,
+    discovered: RwLock<HashMap<String, Discovered>>,
+    pending: RwLock<HashMap<String, PendingLogin>>,
+    login_ttl: Duration,
+}
+
+impl OidcManager {
+    /// Manager of `providers`, reached through `transport`
+    pub fn new(providers: Vec<OidcProviderConfig>, transport: Arc<dyn OidcTransport>) -> Self {
+        Self {
+            providers: providers.into_iter().map(|p| (p.name.clone(), p)).collect(),
+            transport,
+            discovered: RwLock::new(HashMap::new()),
+            pending: RwLock::new(HashMap::new()),
+            login_ttl: Duration::minutes(10),
+        }
+    }
+
+    /// Names of the configured providers, sorted
+    pub fn provider_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.providers.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    fn provider(&self, name: &str) -> AuthResult<&OidcProviderConfig> {
+        self.providers
+            .get(name)
+            .ok_or_else(|| AuthError::OidcProviderNotFound(name.to_string()))
+    }
+
+    /// Start a login at `provider`
+    pub fn authorize(&self, provider: &str) -> AuthResult<AuthorizationRequest> {
+        let config = self.provider(provider)?;
+        let discovered = self.discover(config, false)?;
+
+        let state = generate_token();
+        let nonce = generate_token();
+        let code_verifier = generate_token();
+        let challenge = pkce_challenge(&code_verifier);
+
+        let query = form_urlencoded::Serializer::new(String::new())
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &config.client_id)
+            .append_pair("redirect_uri", &config.redirect_uri)
+            .append_pair("scope", &config.scopes.join(" "))
+            .append_pair("state", &state)
+            .append_pair("nonce", &nonce)
+            .append_pair("code_challenge", &challenge)
+            .append_pair("code_challenge_method", "S256")
+            .finish();
+        let endpoint = &discovered.metadata.authorization_endpoint;
+        let separator = if endpoint.contains('?') { '&' } else { '?' };
+
+        let mut pending = self.pending.write().unwrap_or_else(|e| e.into_inner());
+        let now = Utc::now();
+        pending.retain(|_, login| login.expires_at > now);
+        pending.insert(
+            state.clone(),
+            PendingLogin {
+                provider: provider.to_string(),
+                code_verifier,
+                nonce,
+                expires_at: now + self.login_ttl,
+            },
+        );
+
+        Ok(AuthorizationRequest {
+            url: format!("{}{}{}", endpoint, separator, query),
+            state,
+        })
+    }
+
+    /// Finish a login at `provider` with the callback's `state` and `code`
+    pub fn callback(
+        &self,
+        provider: &str,
+        state: &str,
+        code: &str,
+    ) -> AuthResult<ExternalIdentity> {
+        let config = self.provider(provider)?;
+        let login = self
+            .pending
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(state)
+            .filter(|login| login.expires_at > Utc::now() && login.provider == provider)
+            .ok_or_else(|| AuthError::OidcLoginFailed("unknown or expired state".to_string()))?;
+
+        let discovered = self.discover(config, false)?;
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", config.redirect_uri.as_str()),
+            ("client_id", config.client_id.as_str()),
+            ("code_verifier", login.code_verifier.as_str()),
+        ];
+        if let Some(secret) = &config.client_secret {
+            form.push(("client_secret", secret.as_str()));
+        }
+        let (status, body) = self
+            .transport
+            .post_form(&discovered.metadata.token_endpoint, &form)?;
+        if !(200..300).contains(&status) {
+            let reason = body
+                .get("error")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown error");
+            return Err(AuthError::OidcLoginFailed(format!(
+                "token exchange rejected: {}",
+                reason
+            )));
+        }
+        let id_token = body
+            .get("id_token")
+            .and_then(Value::as_str)
+            .ok_or_else(|| {
+                AuthError::OidcLoginFailed("token response has no id_token".to_string())
+            })?;
+
+        self.validate_id_token(config, id_token, &login.nonce)
+    }
+
+    /// Validate `id_token` from `config`'s provider, issued for `nonce`
+    fn validate_id_token(
+        &self,
+        config: &OidcProviderConfig,
+        id_token: &str,
+        nonce: &str,
+    ) -> AuthResult<ExternalIdentity> {
+        let failed = |reason: String| AuthError::OidcLoginFailed(reason);
+        let header =
+            decode_header(id_token).map_err(|e| failed(format!("malformed ID token: {}", e)))?;
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            return Err(failed(format!(
+                "ID token algorithm {:?} is not accepted",
+                header.alg
+            )));
+        }
+
+        // An unknown key ID may be a rotated key: refetch the JWKS once
+        let mut discovered = self.discover(config, false)?;
+        let mut jwk = find_key(&discovered.jwks, header.kid.as_deref());
+        if jwk.is_none() {
+            discovered = self.discover(config, true)?;
+            jwk = find_key(&discovered.jwks, header.kid.as_deref());
+        }
+        let jwk = jwk.ok_or_else(|| failed("ID token signed with an unknown key".to_string()))?;
+        let key = DecodingKey::from_jwk(&jwk)
+            .map_err(|e| failed(format!("unusable provider key: {}", e)))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[&config.client_id]);
+        validation.set_issuer(&[&discovered.metadata.issuer]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        let claims = decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map_err(|e| failed(format!("invalid ID token: {}", e)))?
+            .claims;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(failed("ID token nonce does not match".to_string()));
+        }
+        let email = claims
+            .email
+            .ok_or_else(|| failed("ID token has no email claim".to_string()))?;
+        let email_verified = match claims.email_verified {
+            Some(Value::Bool(verified)) => verified,
+            Some(Value::String(verified)) => verified == "true",
+            _ => false,
+        };
+        Ok(ExternalIdentity {
+            provider: config.name.clone(),
+            subject: claims.sub,
+            email,
+            email_verified,
+            name: claims.name,
+            mfa_asserted: claims.amr.iter().any(|method| method == "mfa"),
+        })
+    }
+
+    /// Discovery document and JWKS of a provider, cached after the first
+    /// fetch unless `refresh`
+    fn discover(&self, config: &OidcProviderConfig, refresh: bool) -> AuthResult<Discovered> {
+        if !refresh {
+            let discovered = self.discovered.read().unwrap_or_else(|e| e.into_inner());
+            if let Some(found) = discovered.get(&config.name) {
+                return Ok(found.clone());
+            }
+        }
+
+        let unavailable = |reason: String| {
+            AuthError::OidcProviderUnavailable(format!("{}: {}", config.name, reason))
+        };
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            config.issuer.trim_end_matches('/')
+        );
+        let metadata: ProviderMetadata = self.fetch(&url, &unavailable)?;
+        // OIDC Discovery §4.3: the issuer must be the one the URL was built from
+        if metadata.issuer.trim_end_matches('/') != config.issuer.trim_end_matches('/') {
+            return Err(unavailable(format!(
+                "discovery issuer '{}' does not match '{}'",
+                metadata.issuer, config.issuer
+            )));
+        }
+        for endpoint in [
+            &metadata.authorization_endpoint,
+            &metadata.token_endpoint,
+            &metadata.jwks_uri,
+        ] {
+            if !is_secure_url(endpoint) {
+                return Err(unavailable(format!("endpoint '{}' is not https", endpoint)));
+            }
+        }
+        let jwks: JwkSet = self.fetch(&metadata.jwks_uri, &unavailable)?;
+
+        let found = Discovered { metadata, jwks };
+        self.discovered
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(config.name.clone(), found.clone());
+        Ok(found)
+    }
+
+    fn fetch<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+        unavailable: &dyn Fn(String) -> AuthError,
+    ) -> AuthResult<T> {
+        let (status, body) = self.transport.get(url)?;
+        if !(200..300).contains(&status) {
+            return Err(unavailable(format!("GET {} returned {}", url, status)));
+        }
+        serde_json::from_value(body).map_err(|e| unavailable(format!("{}: {}", url, e)))
+    }
+}
+
+/// The key `kid` names, or the only key when the token names none
+fn find_key(jwks: &JwkSet, kid: Option<&str>) -> Option<jsonwebtoken::jwk::Jwk> {
+    match kid {
+        Some(kid) => jwks.find(kid).cloned(),
+        None if jwks.keys.len() == 1 => jwks.keys.first().cloned(),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use ring::rand::SystemRandom;
+    use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    pub(crate) const ISSUER: &str = "https://idp.example.com";
+
+    /// A provider that signs ID tokens with a fresh P-256 key
+    pub(crate) struct FakeProvider {
+        key: EncodingKey,
+        jwk: Value,
+        /// Claims of the next ID token (nonce is filled in from the request)
+        pub claims: Mutex<Value>,
+        /// Forms posted to the token endpoint
+        pub token_requests: Mutex<Vec<HashMap<String, String>>>,
+        /// Nonce of the last authorization URL handed out
+        pub nonce: Mutex<String>,
+    }
+
+    impl FakeProvider {
+        pub fn new() -> Self {
+            let rng = SystemRandom::new();
+            let pkcs8 =
+                EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+            let pair =
+                EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng)
+                    .unwrap();
+            let point = pair.public_key().as_ref();
+            let jwk = json!({
+                "kty": "EC",
+                "crv": "P-256",
+                "kid": "k1",
+                "alg": "ES256",
+                "x": URL_SAFE_NO_PAD.encode(&point[1..33]),
+                "y": URL_SAFE_NO_PAD.encode(&point[33..65]),
+            });
+            Self {
+                key: EncodingKey::from_ec_der(pkcs8.as_ref()),
+                jwk,
+                claims: Mutex::new(json!({
+                    "iss": ISSUER,
+                    "aud": "aerodb-client",
+                    "sub": "google-123",
+                    "email": "ada@example.com",
+                    "email_verified": true,
+                    "name": "Ada",
+                    "exp": Utc::now().timestamp() + 300,
+                })),
+                token_requests: Mutex::new(Vec::new()),
+                nonce: Mutex::new(String::new()),
+            }
+        }
+
+        pub fn id_token(&self, claims: &Value) -> String {
+            let mut header = Header::new(Algorithm::ES256);
+            header.kid = Some("k1".to_string());
+            encode(&header, claims, &self.key).unwrap()
+        }
+    }
+
+    impl OidcTransport for FakeProvider {
+        fn get(&self, url: &str) -> AuthResult<(u16, Value)> {
+            match url {
+                "https://idp.example.com/.well-known/openid-configuration" => Ok((
+                    200,
+                    json!({
+                        "issuer": ISSUER,
+                        "authorization_endpoint": "https://idp.example.com/authorize",
+                        "token_endpoint": "https://idp.example.com/token",
+                        "jwks_uri": "https://idp.example.com/jwks",
+                    }),
+                )),
+                "https://idp.example.com/jwks" => Ok((200, json!({"keys": [self.jwk]}))),
+                _ => Ok((404, json!({}))),
+            }
+        }
+
+        fn post_form(&self, url: &str, form: &[(&str, &str)]) -> AuthResult<(u16, Value)> {
+            assert_eq!(url, "https://idp.example.com/token");
+            let form: HashMap<String, String> = form
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            let accepted = form["code"] == "good-code";
+            self.token_requests.lock().unwrap().push(form);
+            if !accepted {
+                return Ok((400, json!({"error": "invalid_grant"})));
+            }
+            let mut claims = self.claims.lock().unwrap().clone();
+            claims["nonce"] = json!(self.nonce.lock().unwrap().clone());
+            Ok((200, json!({"id_token": self.id_token(&claims)})))
+        }
+    }
+
+    pub(crate) fn provider_config() -> OidcProviderConfig {
+        OidcProviderConfig {
+            name: "google".to_string(),
+            issuer: ISSUER.to_string(),
+            client_id: "aerodb-client".to_string(),
+            client_secret: Some("s3cret".to_string()),
+            redirect_uri: "https://db.example.com/auth/oidc/google/callback".to_string(),
+            scopes: default_scopes(),
+        }
+    }
+
+    /// Start a login, recording its nonce with the fake provider
+    pub(crate) fn start_login(manager: &OidcManager, provider: &FakeProvider) -> String {
+        let request = manager.authorize("google").unwrap();
+        let query: HashMap<String, String> =
+            form_urlencoded::parse(request.url.split_once('?').unwrap().1.as_bytes())
+                .into_owned()
+                .collect();
+        *provider.nonce.lock().unwrap() = query["nonce"].clone();
+        request.state
+    }
+
+    fn manager() -> (OidcManager, Arc<FakeProvider>) {
+        let provider = Arc::new(FakeProvider::new());
+        let manager = OidcManager::new(
+            vec![provider_config()],
+            Arc::clone(&provider) as Arc<dyn OidcTransport>,
+        );
+        (manager, provider)
+    }
+
+    #[test]
+    fn test_pkce_challenge() {
+        // RFC 7636 Appendix B
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn test_authorization_url() {
+        let (manager, _) = manager();
+        let request = manager.authorize("google").unwrap();
+        let (endpoint, query) = request.url.split_once('?').unwrap();
+        assert_eq!(endpoint, "https://idp.example.com/authorize");
+        let query: HashMap<String, String> = form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect();
+        assert_eq!(query["response_type"], "code");
+        assert_eq!(query["scope"], "openid email profile");
+        assert_eq!(query["state"], request.state);
+        assert_eq!(query["code_challenge_method"], "S256");
+
+        assert!(matches!(
+            manager.authorize("github"),
+            Err(AuthError::OidcProviderNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_callback_validates_id_token() {
+        let (manager, provider) = manager();
+        let state = start_login(&manager, &provider);
+        let identity = manager.callback("google", &state, "good-code").unwrap();
+        assert_eq!(identity.subject, "google-123");
+        assert_eq!(identity.email, "ada@example.com");
+        assert!(identity.email_verified);
+        assert!(!identity.mfa_asserted);
+
+        // The PKCE verifier sent matches the challenge handed out
+        let form = provider.token_requests.lock().unwrap()[0].clone();
+        assert_eq!(form["client_secret"], "s3cret");
+        assert_eq!(form["code_verifier"].len(), 43);
+
+        // AUTH-OIDC1: state is single-use
+        assert!(matches!(
+            manager.callback("google", &state, "good-code"),
+            Err(AuthError::OidcLoginFailed(_))
+        ));
+
+        provider.claims.lock().unwrap()["amr"] = json!(["pwd", "mfa"]);
+        let state = start_login(&manager, &provider);
+        let identity = manager.callback("google", &state, "good-code").unwrap();
+        assert!(identity.mfa_asserted);
+    }
+
+    #[test]
+    fn test_callback_rejects_bad_tokens() {
+        let (manager, provider) = manager();
+        let state = start_login(&manager, &provider);
+        assert!(matches!(
+            manager.callback("google", &state, "bad-code"),
+            Err(AuthError::OidcLoginFailed(reason)) if reason.contains("invalid_grant")
+        ));
+
+        let good_claims = provider.claims.lock().unwrap().clone();
+        for (field, value) in [
+            ("aud", json!("someone-else")),
+            ("iss", json!("https://evil.example.com")),
+            ("exp", json!(Utc::now().timestamp() - 3600)),
+        ] {
+            let mut claims = good_claims.clone();
+            claims[field] = value;
+            *provider.claims.lock().unwrap() = claims;
+            let state = start_login(&manager, &provider);
+            assert!(
+                manager.callback("google", &state, "good-code").is_err(),
+                "accepted bad {}",
+                field
+            );
+        }
+
+        // Nonce of another login
+        *provider.claims.lock().unwrap() = good_claims;
+        let state = start_login(&manager, &provider);
+        *provider.nonce.lock().unwrap() = "other".to_string();
+        assert!(manager.callback("google", &state, "good-code").is_err());
+    }
+
+    #[test]
+    fn test_provider_config_validation() {
+        assert!(provider_config().validate().is_ok());
+        let invalid = [
+            OidcProviderConfig {
+                issuer: "http://idp.example.com".to_string(),
+                ..provider_config()
+            },
+            OidcProviderConfig {
+                scopes: vec!["email".to_string()],
+                ..provider_config()
+            },
+            OidcProviderConfig {
+                name: "a/b".to_string(),
+                ..provider_config()
+            },
+        ];
+        for config in invalid {
+            assert!(config.validate().is_err(), "{:?}", config);
+        }
+        assert!(is_secure_url("http://localhost:8080/realms/dev"));
+        assert!(!is_secure_url("http://localhost.evil.com"));
+    }
+}
@@ -0,0 +1,307 @@
+//! # OIDC HTTPS Transport
+//!
+//! Blocking HTTPS requests to identity providers: one request per
+//! connection (`Connection: close`), server certificates checked against
+//! the Mozilla root store, JSON responses of at most 1 MiB.
+//!
+//! Plain HTTP is only used for loopback issuers, such as a local
+//! development provider.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio_rustls::rustls::{self, pki_types::ServerName, ClientConfig, RootCertStore};
+
+use super::errors::{AuthError, AuthResult};
+use super::oidc::{is_secure_url, OidcTransport};
+
+/// Largest response accepted, head and body
+const MAX_RESPONSE_LEN: usize = 1024 * 1024;
+/// Most response headers accepted
+const MAX_HEADERS: usize = 64;
+
+/// `OidcTransport` over HTTPS
+pub struct HttpsTransport {
+    tls: Arc<ClientConfig>,
+    timeout: Duration,
+}
+
+impl HttpsTransport {
+    /// Transport with `timeout` for connecting and for each read/write
+    pub fn new(timeout: Duration) -> Self {
+        let mut roots = RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        let tls =
+            ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .expect("ring provider supports the default protocol versions")
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+        Self {
+            tls: Arc::new(tls),
+            timeout,
+        }
+    }
+
+    fn request(&self, url: &str, method: &str, body: Option<&str>) -> AuthResult<(u16, Value)> {
+        let unavailable =
+            |reason: String| AuthError::OidcProviderUnavailable(format!("{}: {}", url, reason));
+        if !is_secure_url(url) {
+            return Err(unavailable("only https URLs are allowed".to_string()));
+        }
+        let target = Target::parse(url).ok_or_else(|| unavailable("invalid URL".to_string()))?;
+
+        let mut request = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\nAccept: application/json\r\nConnection: close\r\n",
+            method, target.path, target.host_header
+        );
+        if let Some(body) = body {
+            request.push_str("Content-Type: application/x-www-form-urlencoded\r\n");
+            request.push_str(&format!("Content-Length: {}\r\n", body.len()));
+        }
+        request.push_str("\r\n");
+        request.push_str(body.unwrap_or_default());
+
+        let raw = self
+            .exchange(&target, request.as_bytes())
+            .map_err(|e| unavailable(e.to_string()))?;
+        let (status, body) = parse_response(&raw).map_err(|e| unavailable(e.to_string()))?;
+        let json = if body.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&body)
+                .map_err(|e| unavailable(format!("invalid JSON response: {}", e)))?
+        };
+        Ok((status, json))
+    }
+
+    /// Send `request` and read the whole response
+    fn exchange(&self, target: &Target, request: &[u8]) -> io::Result<Vec<u8>> {
+        let addr = (target.host.as_str(), target.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host did not resolve"))?;
+        let tcp = TcpStream::connect_timeout(&addr, self.timeout)?;
+        tcp.set_read_timeout(Some(self.timeout))?;
+        tcp.set_write_timeout(Some(self.timeout))?;
+
+        let mut raw = Vec::new();
+        if target.tls {
+            let name = ServerName::try_from(target.host.clone())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            let connection = rustls::ClientConnection::new(Arc::clone(&self.tls), name)
+                .map_err(io::Error::other)?;
+            let mut stream = rustls::StreamOwned::new(connection, tcp);
+            stream.write_all(request)?;
+            read_capped(&mut stream, &mut raw)?;
+        } else {
+            let mut stream = tcp;
+            stream.write_all(request)?;
+            read_capped(&mut stream, &mut raw)?;
+        }
+        Ok(raw)
+    }
+}
+
+impl Default for HttpsTransport {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(10))
+    }
+}
+
+impl OidcTransport for HttpsTransport {
+    fn get(&self, url: &str) -> AuthResult<(u16, Value)> {
+        self.request(url, "GET", None)
+    }
+
+    fn post_form(&self, url: &str, form: &[(&str, &str)]) -> AuthResult<(u16, Value)> {
+        let body = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(form)
+            .finish();
+        self.request(url, "POST", Some(&body))
+    }
+}
+
+/// Read to the end of `stream`, up to `MAX_RESPONSE_LEN` bytes
+fn read_capped(stream: &mut impl Read, raw: &mut Vec<u8>) -> io::Result<()> {
+    let read = stream.take(MAX_RESPONSE_LEN as u64 + 1).read_to_end(raw);
+    match read {
+        // Providers commonly skip TLS close_notify after `Connection: close`
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof && !raw.is_empty() => {}
+        other => {
+            other?;
+        }
+    }
+    if raw.len() > MAX_RESPONSE_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "response too large",
+        ));
+    }
+    Ok(())
+}
+
+/// Request target of a URL
+#[derive(Debug, PartialEq)]
+struct Target {
+    tls: bool,
+    host: String,
+    port: u16,
+    host_header: String,
+    path: String,
+}
+
+impl Target {
+    fn parse(url: &str) -> Option<Self> {
+        let (tls, rest) = match url.split_once("://")? {
+            ("https", rest) => (true, rest),
+            ("http", rest) => (false, rest),
+            _ => return None,
+        };
+        let split = rest.find(['/', '?']).unwrap_or(rest.len());
+        let (authority, path) = rest.split_at(split);
+        let path = match path {
+            "" => "/".to_string(),
+            p if p.starts_with('?') => format!("/{}", p),
+            p => p.to_string(),
+        };
+        if authority.is_empty() || authority.contains('@') {
+            return None;
+        }
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.contains(']') => (host, port.parse().ok()?),
+            _ => (authority, if tls { 443 } else { 80 }),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        Some(Self {
+            tls,
+            host: host.to_string(),
+            port,
+            host_header: authority.to_string(),
+            path,
+        })
+    }
+}
+
+/// Status and body of a complete response
+fn parse_response(raw: &[u8]) -> io::Result<(u16, Vec<u8>)> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+    let mut response = httparse::Response::new(&mut headers);
+    let head_len = match response.parse(raw) {
+        Ok(httparse::Status::Complete(len)) => len,
+        Ok(httparse::Status::Partial) => return Err(invalid("incomplete response".to_string())),
+        Err(e) => return Err(invalid(format!("invalid response: {}", e))),
+    };
+    let status = response.code.unwrap_or_default();
+    let header = |name: &str| {
+        response
+            .headers
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case(name))
+            .map(|h| String::from_utf8_lossy(h.value).to_ascii_lowercase())
+    };
+    let rest = &raw[head_len..];
+
+    let body = if header("transfer-encoding").is_some_and(|v| v.contains("chunked")) {
+        let mut body = Vec::new();
+        let mut reader = BufReader::new(rest);
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            let size = line.trim().split(';').next().unwrap_or_default();
+            let size = usize::from_str_radix(size, 16)
+                .map_err(|_| invalid(format!("invalid chunk size '{}'", line.trim())))?;
+            if size == 0 {
+                break body;
+            }
+            let start = body.len();
+            body.resize(start + size, 0);
+            reader.read_exact(&mut body[start..])?;
+            let mut crlf = [0; 2];
+            reader.read_exact(&mut crlf)?;
+        }
+    } else if let Some(len) = header("content-length") {
+        let len: usize = len
+            .trim()
+            .parse()
+            .map_err(|_| invalid("invalid Content-Length".to_string()))?;
+        rest.get(..len)
+            .ok_or_else(|| invalid("truncated response body".to_string()))?
+            .to_vec()
+    } else {
+        rest.to_vec()
+    };
+    Ok((status, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_target_parse() {
+        let target =
+            Target::parse("https://idp.example.com/.well-known/openid-configuration").unwrap();
+        assert!(target.tls);
+        assert_eq!(target.port, 443);
+        assert_eq!(target.path, "/.well-known/openid-configuration");
+
+        let target = Target::parse("http://[::1]:8080?x=1").unwrap();
+        assert_eq!(target.host, "::1");
+        assert_eq!(target.port, 8080);
+        assert_eq!(target.host_header, "[::1]:8080");
+        assert_eq!(target.path, "/?x=1");
+
+        assert!(Target::parse("https://user@idp.example.com/").is_none());
+        assert!(Target::parse("ftp://idp.example.com/").is_none());
+    }
+
+    #[test]
+    fn test_parse_chunked_response() {
+        let raw = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\n{\"a\"\r\n3\r\n:1}\r\n0\r\n\r\n";
+        let (status, body) = parse_response(raw).unwrap();
+        assert_eq!(status, 200);
+        assert_eq!(body, b"{\"a\":1}");
+    }
+
+    #[test]
+    fn test_loopback_http_request() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![0; 4096];
+            let len = stream.read(&mut request).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 25\r\n\r\n{\"error\":\"invalid_grant\"}")
+                .unwrap();
+            String::from_utf8_lossy(&request[..len]).into_owned()
+        });
+
+        let transport = HttpsTransport::default();
+        let url = format!("http://localhost:{}/token", addr.port());
+        let (status, body) = transport
+            .post_form(
+                &url,
+                &[("code", "a b"), ("grant_type", "authorization_code")],
+            )
+            .unwrap();
+        assert_eq!(status, 400);
+        assert_eq!(body["error"], "invalid_grant");
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /token HTTP/1.1\r\n"));
+        assert!(request.ends_with("code=a+b&grant_type=authorization_code"));
+
+        // Plain HTTP elsewhere is refused before connecting
+        assert!(matches!(
+            transport.get("http://idp.example.com/jwks"),
+            Err(AuthError::OidcProviderUnavailable(_))
+        ));
+    }
+}
@@ -0,0 +1,158 @@
+//! # Token Revocation
+//!
+//! Access tokens are validated without a session lookup, so a revoked
+//! session's access tokens would stay usable until they expire. The
+//! revocation store lists what `JwtManager` must reject before then:
+//! single access tokens (by `jti`) and whole sessions (by `sid`, the
+//! refresh token family).
+//!
+//! An entry is kept only until every token it covers has expired. With a
+//! data directory the store is written through to `auth_revocations.json`
+//! and loaded again on open, so revocations survive a restart.
+//!
+//! ## Invariants
+//! - AUTH-RV1: A revoked token or session is rejected until it would have
+//!   expired anyway
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::errors::{AuthError, AuthResult};
+
+/// Revocation list, relative to the data directory
+pub const REVOCATIONS_FILE: &str = "auth_revocations.json";
+
+/// One revoked token or session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Revocation {
+    /// Token ID (`jti`) or session ID (`sid`)
+    id: String,
+    /// When the last token covered expires (Unix epoch seconds)
+    until: i64,
+}
+
+/// Revoked access tokens and sessions
+#[derive(Debug, Default)]
+pub struct RevocationStore {
+    /// List file, or `None` to keep the list in memory
+    path: Option<PathBuf>,
+    entries: RwLock<BTreeMap<String, i64>>,
+}
+
+impl RevocationStore {
+    /// Store kept in memory
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store persisted in `data_dir`
+    pub fn open(data_dir: &Path) -> AuthResult<Self> {
+        let path = data_dir.join(REVOCATIONS_FILE);
+        let storage_error =
+            |e: String| AuthError::StorageError(format!("{}: {}", path.display(), e));
+        let revocations: Vec<Revocation> = match fs::read(&path) {
+            Ok(bytes) => {
+                serde_json::from_slice(&bytes).map_err(|e| storage_error(e.to_string()))?
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(storage_error(e.to_string())),
+        };
+        let now = Utc::now().timestamp();
+        let entries = revocations
+            .into_iter()
+            .filter(|r| r.until > now)
+            .map(|r| (r.id, r.until))
+            .collect();
+        Ok(Self {
+            path: Some(path),
+            entries: RwLock::new(entries),
+        })
+    }
+
+    /// Revoke `id` until `until`, when its tokens expire anyway
+    pub fn revoke(&self, id: &str, until: DateTime<Utc>) -> AuthResult<()> {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        let now = Utc::now().timestamp();
+        entries.retain(|_, until| *until > now);
+        let until = until.timestamp();
+        let entry = entries.entry(id.to_string()).or_insert(until);
+        *entry = (*entry).max(until);
+
+        if let Some(path) = &self.path {
+            let revocations: Vec<Revocation> = entries
+                .iter()
+                .map(|(id, until)| Revocation {
+                    id: id.clone(),
+                    until: *until,
+                })
+                .collect();
+            write_revocations(path, &revocations)
+                .map_err(|e| AuthError::StorageError(format!("{}: {}", path.display(), e)))?;
+        }
+        Ok(())
+    }
+
+    /// Whether `id` is revoked
+    pub fn is_revoked(&self, id: &str) -> bool {
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(id)
+            .is_some_and(|until| *until > Utc::now().timestamp())
+    }
+}
+
+/// Write `revocations` to a temporary file, fsync it, and rename it over `path`
+fn write_revocations(path: &Path, revocations: &[Revocation]) -> std::io::Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(&serde_json::to_vec_pretty(revocations)?)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_revocations_expire() {
+        let store = RevocationStore::new();
+        store
+            .revoke("a", Utc::now() + Duration::minutes(5))
+            .unwrap();
+        store
+            .revoke("b", Utc::now() - Duration::minutes(5))
+            .unwrap();
+        assert!(store.is_revoked("a"));
+        assert!(!store.is_revoked("b"));
+        assert!(!store.is_revoked("c"));
+
+        // A later revocation of the same ID extends it, an earlier one does not
+        store
+            .revoke("a", Utc::now() - Duration::minutes(1))
+            .unwrap();
+        assert!(store.is_revoked("a"));
+    }
+
+    #[test]
+    fn test_revocations_survive_reopen() {
+        let dir = TempDir::new().unwrap();
+        let store = RevocationStore::open(dir.path()).unwrap();
+        store
+            .revoke("a", Utc::now() + Duration::minutes(5))
+            .unwrap();
+        drop(store);
+
+        let store = RevocationStore::open(dir.path()).unwrap();
+        assert!(store.is_revoked("a"));
+        assert!(!store.is_revoked("b"));
+    }
+}
@@ -0,0 +1,855 @@
+//! # RLS Policy Language
+//!
+//! Row-level security policies declared per collection in a small text
+//! language and compiled to planner predicates.
+//!
+//! ```text
+//! -- Signed-in users see and change only their own rows
+//! policy own_rows on read, update, delete
+//!     when authenticated
+//!     using owner_id = auth.uid;
+//!
+//! -- Anyone may read published rows
+//! policy published on read when anonymous using status = 'published';
+//!
+//! -- New rows belong to whoever inserts them
+//! policy insert_own on insert when authenticated using owner_id = auth.uid;
+//! ```
+//!
+//! A policy names the actions it covers (`read`, `insert`, `update`,
+//! `delete`, or `all`), optional `when` conditions on the caller, and an
+//! effect: `using` predicates on documents, `allow`, or `deny`.
+//! Conditions test `authenticated` / `anonymous`, or compare `auth.uid`,
+//! `auth.role` or any other JWT claim (`auth.<claim>`) with a literal.
+//! Predicates compare a document field using `=`, `!=`, `<`, `<=`, `>`,
+//! `>=`, `prefix` or `in (...)`, or test it with `exists` / `missing`;
+//! operands are string, number or boolean literals, or `auth.*`
+//! references. Keywords are lowercase and `--` starts a comment.
+//!
+//! For an operation, every policy covering its action whose conditions
+//! all hold *fires*. A firing `deny` rejects the operation, and so does
+//! no policy firing at all. Otherwise the predicates of all fired policies
+//! are AND-ed: reads, updates and deletes only see matching documents,
+//! inserted documents must match, and an update may only set a
+//! constrained field to a matching value.
+//!
+//! ## Invariants
+//! - AUTH-RLS5: An operation no policy covers is denied
+
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::errors::{AuthError, AuthResult};
+use super::rls::RlsContext;
+use super::user::DEFAULT_ROLE;
+use crate::executor::PredicateFilter;
+use crate::planner::Predicate;
+
+/// `auth.role` of anonymous callers without a role claim
+pub const ANONYMOUS_ROLE: &str = "anon";
+
+/// What an operation does to a collection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RlsAction {
+    Read,
+    Insert,
+    Update,
+    Delete,
+}
+
+impl RlsAction {
+    /// Every action, as covered by `all`
+    pub const ALL: [RlsAction; 4] = [Self::Read, Self::Insert, Self::Update, Self::Delete];
+
+    /// Keyword naming this action in policy text
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Insert => "insert",
+            Self::Update => "update",
+            Self::Delete => "delete",
+        }
+    }
+}
+
+impl fmt::Display for RlsAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Value a comparison is made against
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Literal(Value),
+    /// Attribute of the caller (`auth.<name>`)
+    Auth(String),
+}
+
+impl Operand {
+    /// The operand's value for the caller, or the missing attribute
+    fn resolve(&self, auth: &Map<String, Value>) -> Result<Value, String> {
+        match self {
+            Self::Literal(value) => Ok(value.clone()),
+            Self::Auth(attribute) => auth
+                .get(attribute)
+                .filter(|value| !value.is_null())
+                .cloned()
+                .ok_or_else(|| attribute.clone()),
+        }
+    }
+}
+
+/// Comparison operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+    Prefix,
+}
+
+/// Predicate with `auth.*` operands still unresolved
+#[derive(Debug, Clone, PartialEq)]
+enum PredicateTemplate {
+    Compare {
+        field: String,
+        op: CompareOp,
+        operand: Operand,
+    },
+    In {
+        field: String,
+        values: Vec<Value>,
+    },
+    Exists {
+        field: String,
+        present: bool,
+    },
+}
+
+impl PredicateTemplate {
+    /// Planner predicate for the caller, or the `auth.*` attribute it lacks
+    fn instantiate(&self, auth: &Map<String, Value>) -> Result<Predicate, String> {
+        match self {
+            Self::Compare { field, op, operand } => {
+                let value = operand.resolve(auth)?;
+                Ok(match op {
+                    CompareOp::Eq => Predicate::eq(field, value),
+                    CompareOp::Ne => Predicate::ne(field, value),
+                    CompareOp::Lt => Predicate::lt(field, value),
+                    CompareOp::Lte => Predicate::lte(field, value),
+                    CompareOp::Gt => Predicate::gt(field, value),
+                    CompareOp::Gte => Predicate::gte(field, value),
+                    CompareOp::Prefix => match (value, operand) {
+                        (Value::String(prefix), _) => Predicate::prefix(field, prefix),
+                        // Only claims can be non-strings; treat them as absent
+                        (_, Operand::Auth(attribute)) => return Err(attribute.clone()),
+                        (_, Operand::Literal(_)) => unreachable!("checked by the parser"),
+                    },
+                })
+            }
+            Self::In { field, values } => Ok(Predicate::in_values(field, values.clone())),
+            Self::Exists { field, present } => Ok(Predicate::exists(field, *present)),
+        }
+    }
+
+    fn uses_auth(&self) -> bool {
+        matches!(
+            self,
+            Self::Compare {
+                operand: Operand::Auth(_),
+                ..
+            }
+        )
+    }
+}
+
+/// What a firing policy does
+#[derive(Debug, Clone, PartialEq)]
+enum Effect {
+    Using(Vec<PredicateTemplate>),
+    Allow,
+    Deny,
+}
+
+/// One compiled policy
+#[derive(Debug, Clone, PartialEq)]
+struct PolicyRule {
+    name: String,
+    actions: BTreeSet<RlsAction>,
+    /// Predicates over the caller's attributes
+    conditions: Vec<Predicate>,
+    effect: Effect,
+}
+
+/// Outcome of evaluating a policy set for one operation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RlsDecision {
+    /// Names of the policies that fired, in declaration order
+    pub fired: Vec<String>,
+    /// Predicates every affected document must match (AND)
+    pub predicates: Vec<Predicate>,
+}
+
+impl RlsDecision {
+    /// Whether `document` matches every predicate
+    pub fn allows(&self, document: &Value) -> bool {
+        PredicateFilter::matches(document, &self.predicates)
+    }
+
+    /// Whether the fields `updates` sets keep matching their predicates
+    pub fn allows_changes(&self, updates: &Value) -> bool {
+        let Some(fields) = updates.as_object() else {
+            return true;
+        };
+        let touched: Vec<Predicate> = self
+            .predicates
+            .iter()
+            .filter(|predicate| fields.contains_key(&predicate.field))
+            .cloned()
+            .collect();
+        PredicateFilter::matches(updates, &touched)
+    }
+}
+
+/// A collection's compiled policies
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RlsPolicySet {
+    rules: Vec<PolicyRule>,
+}
+
+impl RlsPolicySet {
+    /// Compile policy text
+    pub fn parse(source: &str) -> AuthResult<Self> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let mut rules = Vec::new();
+        let mut names = HashSet::new();
+        while !parser.at_end() {
+            let rule = parser.policy()?;
+            if !names.insert(rule.name.clone()) {
+                return Err(AuthError::InvalidPolicy(format!(
+                    "duplicate policy {}",
+                    rule.name
+                )));
+            }
+            rules.push(rule);
+        }
+        Ok(Self { rules })
+    }
+
+    /// Policy names in declaration order
+    pub fn policy_names(&self) -> impl Iterator<Item = &str> {
+        self.rules.iter().map(|rule| rule.name.as_str())
+    }
+
+    /// Decide `action` for the caller in `ctx`
+    ///
+    /// The service role bypasses the policies (AUTH-RLS1) and gets an
+    /// empty decision.
+    ///
+    /// # Invariant
+    /// AUTH-RLS5: Fails unless some policy fires
+    pub fn evaluate(&self, action: RlsAction, ctx: &RlsContext) -> AuthResult<RlsDecision> {
+        let mut decision = RlsDecision::default();
+        if ctx.can_bypass_rls() {
+            return Ok(decision);
+        }
+
+        let auth = auth_attributes(ctx);
+        let auth_doc = Value::Object(auth.clone());
+        for rule in &self.rules {
+            if !rule.actions.contains(&action)
+                || !PredicateFilter::matches(&auth_doc, &rule.conditions)
+            {
+                continue;
+            }
+            match &rule.effect {
+                Effect::Deny => {
+                    return Err(AuthError::PolicyDenied(format!(
+                        "policy {} denies {}",
+                        rule.name, action
+                    )))
+                }
+                Effect::Allow => {}
+                Effect::Using(templates) => {
+                    for template in templates {
+                        let predicate = template.instantiate(&auth).map_err(|attribute| {
+                            if attribute == "uid" {
+                                AuthError::AuthenticationRequired
+                            } else {
+                                AuthError::PolicyDenied(format!(
+                                    "policy {} needs auth.{}",
+                                    rule.name, attribute
+                                ))
+                            }
+                        })?;
+                        decision.predicates.push(predicate);
+                    }
+                }
+            }
+            decision.fired.push(rule.name.clone());
+        }
+
+        if decision.fired.is_empty() {
+            return Err(AuthError::PolicyDenied(format!(
+                "no policy allows {}",
+                action
+            )));
+        }
+        Ok(decision)
+    }
+}
+
+/// The caller's `auth.*` attributes: JWT claims plus `uid` and `role`
+fn auth_attributes(ctx: &RlsContext) -> Map<String, Value> {
+    let mut auth: Map<String, Value> = ctx
+        .claims
+        .iter()
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    match ctx.user_id {
+        Some(id) => auth.insert("uid".to_string(), Value::String(id.to_string())),
+        None => auth.remove("uid"),
+    };
+    if !auth.get("role").is_some_and(Value::is_string) {
+        let role = if ctx.is_authenticated {
+            DEFAULT_ROLE
+        } else {
+            ANONYMOUS_ROLE
+        };
+        auth.insert("role".to_string(), Value::String(role.to_string()));
+    }
+    auth
+}
+
+// ==================
+// Lexer
+// ==================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(serde_json::Number),
+    Sym(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ident(ident) => write!(f, "'{}'", ident),
+            Self::Str(s) => write!(f, "string '{}'", s),
+            Self::Num(n) => write!(f, "number {}", n),
+            Self::Sym(sym) => write!(f, "'{}'", sym),
+        }
+    }
+}
+
+/// Token with its 1-based line and column
+type Spanned = (Token, usize, usize);
+
+const SYMBOLS: [&str; 11] = ["<=", ">=", "!=", "=", "<", ">", ",", "(", ")", ";", "."];
+
+fn tokenize(source: &str) -> AuthResult<Vec<Spanned>> {
+    let mut tokens = Vec::new();
+    for (line_index, line) in source.lines().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let (line_no, col) = (line_index + 1, i + 1);
+            let error = |msg: String| {
+                AuthError::InvalidPolicy(format!("line {}:{}: {}", line_no, col, msg))
+            };
+
+            if c.is_whitespace() {
+                i += 1;
+            } else if c == '-' && chars.get(i + 1) == Some(&'-') {
+                break;
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let ident = chars[start..i].iter().collect();
+                tokens.push((Token::Ident(ident), line_no, col));
+            } else if c == '\'' {
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(error("unterminated string".to_string())),
+                        Some('\'') if chars.get(i + 1) == Some(&'\'') => {
+                            value.push('\'');
+                            i += 2;
+                        }
+                        Some('\'') => {
+                            i += 1;
+                            break;
+                        }
+                        Some(&other) => {
+                            value.push(other);
+                            i += 1;
+                        }
+                    }
+                }
+                tokens.push((Token::Str(value), line_no, col));
+            } else if c.is_ascii_digit()
+                || (c == '-' && chars.get(i + 1).is_some_and(char::is_ascii_digit))
+            {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let number = match text.parse::<i64>() {
+                    Ok(n) => serde_json::Number::from(n),
+                    Err(_) => text
+                        .parse::<f64>()
+                        .ok()
+                        .and_then(serde_json::Number::from_f64)
+                        .ok_or_else(|| error(format!("invalid number {}", text)))?,
+                };
+                tokens.push((Token::Num(number), line_no, col));
+            } else {
+                let rest: String = chars[i..].iter().take(2).collect();
+                let symbol = SYMBOLS
+                    .iter()
+                    .find(|sym| rest.starts_with(**sym))
+                    .ok_or_else(|| error(format!("unexpected character '{}'", c)))?;
+                i += symbol.len();
+                tokens.push((Token::Sym(symbol), line_no, col));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+// ==================
+// Parser
+// ==================
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+}
+
+impl Parser {
+    fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _, _)| token)
+    }
+
+    fn error(&self, msg: impl fmt::Display) -> AuthError {
+        match self.tokens.get(self.pos) {
+            Some((token, line, col)) => {
+                AuthError::InvalidPolicy(format!("line {}:{}: {}, found {}", line, col, msg, token))
+            }
+            None => AuthError::InvalidPolicy(format!("{} at end of input", msg)),
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(ident)) if ident == keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_sym(&mut self, sym: &'static str) -> bool {
+        if self.peek() == Some(&Token::Sym(sym)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> AuthResult<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{}'", keyword)))
+        }
+    }
+
+    fn expect_sym(&mut self, sym: &'static str) -> AuthResult<()> {
+        if self.eat_sym(sym) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{}'", sym)))
+        }
+    }
+
+    fn ident(&mut self, what: &str) -> AuthResult<String> {
+        match self.peek() {
+            Some(Token::Ident(ident)) => {
+                let ident = ident.clone();
+                self.pos += 1;
+                Ok(ident)
+            }
+            _ => Err(self.error(format!("expected {}", what))),
+        }
+    }
+
+    /// `policy <name> on <actions> [when <conditions>] <effect> ;`
+    fn policy(&mut self) -> AuthResult<PolicyRule> {
+        self.expect_keyword("policy")?;
+        let name = self.ident("policy name")?;
+        self.expect_keyword("on")?;
+        let actions = self.actions()?;
+
+        let mut conditions = Vec::new();
+        if self.eat_keyword("when") {
+            loop {
+                conditions.push(self.condition()?);
+                if !self.eat_keyword("and") {
+                    break;
+                }
+            }
+        }
+
+        let effect = if self.eat_keyword("allow") {
+            Effect::Allow
+        } else if self.eat_keyword("deny") {
+            Effect::Deny
+        } else if self.eat_keyword("using") {
+            let mut predicates = Vec::new();
+            loop {
+                let field = self.ident("field name")?;
+                predicates.push(self.comparison(field)?);
+                if !self.eat_keyword("and") {
+                    break;
+                }
+            }
+            Effect::Using(predicates)
+        } else {
+            return Err(self.error("expected 'when', 'using', 'allow' or 'deny'"));
+        };
+        self.expect_sym(";")?;
+
+        Ok(PolicyRule {
+            name,
+            actions,
+            conditions,
+            effect,
+        })
+    }
+
+    /// `all` or a comma-separated list of actions
+    fn actions(&mut self) -> AuthResult<BTreeSet<RlsAction>> {
+        if self.eat_keyword("all") {
+            return Ok(RlsAction::ALL.into_iter().collect());
+        }
+        let mut actions = BTreeSet::new();
+        loop {
+            let action = RlsAction::ALL
+                .into_iter()
+                .find(|action| self.eat_keyword(action.as_str()))
+                .ok_or_else(|| self.error("expected an action"))?;
+            actions.insert(action);
+            if !self.eat_sym(",") {
+                return Ok(actions);
+            }
+        }
+    }
+
+    /// `authenticated`, `anonymous`, or a comparison of `auth.<attribute>`
+    fn condition(&mut self) -> AuthResult<Predicate> {
+        if self.eat_keyword("authenticated") {
+            return Ok(Predicate::exists("uid", true));
+        }
+        if self.eat_keyword("anonymous") {
+            return Ok(Predicate::exists("uid", false));
+        }
+        let attribute = self
+            .auth_ref()?
+            .ok_or_else(|| self.error("expected a condition on auth"))?;
+        let template = self.comparison(attribute)?;
+        if template.uses_auth() {
+            return Err(self.error_before("conditions compare auth with literals"));
+        }
+        Ok(template
+            .instantiate(&Map::new())
+            .expect("literal operands always resolve"))
+    }
+
+    /// Error pointing at the previous token
+    fn error_before(&mut self, msg: &str) -> AuthError {
+        self.pos -= 1;
+        self.error(msg)
+    }
+
+    /// `auth.<attribute>`, if next
+    fn auth_ref(&mut self) -> AuthResult<Option<String>> {
+        if !self.eat_keyword("auth") {
+            return Ok(None);
+        }
+        self.expect_sym(".")?;
+        self.ident("auth attribute").map(Some)
+    }
+
+    /// The comparison following `field`
+    fn comparison(&mut self, field: String) -> AuthResult<PredicateTemplate> {
+        if self.eat_keyword("exists") {
+            return Ok(PredicateTemplate::Exists {
+                field,
+                present: true,
+            });
+        }
+        if self.eat_keyword("missing") {
+            return Ok(PredicateTemplate::Exists {
+                field,
+                present: false,
+            });
+        }
+        if self.eat_keyword("in") {
+            self.expect_sym("(")?;
+            let mut values = vec![self.literal()?];
+            while self.eat_sym(",") {
+                values.push(self.literal()?);
+            }
+            self.expect_sym(")")?;
+            return Ok(PredicateTemplate::In { field, values });
+        }
+
+        let op = if self.eat_keyword("prefix") {
+            CompareOp::Prefix
+        } else {
+            match self.peek() {
+                Some(Token::Sym("=")) => CompareOp::Eq,
+                Some(Token::Sym("!=")) => CompareOp::Ne,
+                Some(Token::Sym("<")) => CompareOp::Lt,
+                Some(Token::Sym("<=")) => CompareOp::Lte,
+                Some(Token::Sym(">")) => CompareOp::Gt,
+                Some(Token::Sym(">=")) => CompareOp::Gte,
+                _ => return Err(self.error("expected a comparison")),
+            }
+        };
+        if op != CompareOp::Prefix {
+            self.pos += 1;
+        }
+
+        let operand = match self.auth_ref()? {
+            Some(attribute) => Operand::Auth(attribute),
+            None => Operand::Literal(self.literal()?),
+        };
+        if op == CompareOp::Prefix
+            && matches!(&operand, Operand::Literal(value) if !value.is_string())
+        {
+            return Err(self.error_before("prefix needs a string"));
+        }
+        Ok(PredicateTemplate::Compare { field, op, operand })
+    }
+
+    /// String, number or boolean
+    fn literal(&mut self) -> AuthResult<Value> {
+        let value = match self.peek() {
+            Some(Token::Str(s)) => Value::String(s.clone()),
+            Some(Token::Num(n)) => Value::Number(n.clone()),
+            Some(Token::Ident(ident)) if ident == "true" => Value::Bool(true),
+            Some(Token::Ident(ident)) if ident == "false" => Value::Bool(false),
+            _ => return Err(self.error("expected a string, number or boolean")),
+        };
+        self.next();
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    const POLICIES: &str = "
+        -- Signed-in users see and change only their own rows
+        policy own_rows on read, update, delete
+            when authenticated
+            using owner_id = auth.uid;
+
+        policy published on read when anonymous using status = 'published';
+        policy insert_own on insert when authenticated using owner_id = auth.uid;
+    ";
+
+    #[test]
+    fn test_owner_policies_compile_to_predicates() {
+        let set = RlsPolicySet::parse(POLICIES).unwrap();
+        assert_eq!(
+            set.policy_names().collect::<Vec<_>>(),
+            ["own_rows", "published", "insert_own"]
+        );
+
+        let user_id = Uuid::new_v4();
+        let ctx = RlsContext::authenticated(user_id);
+        let decision = set.evaluate(RlsAction::Read, &ctx).unwrap();
+        assert_eq!(decision.fired, ["own_rows"]);
+        assert_eq!(
+            decision.predicates,
+            [Predicate::eq("owner_id", json!(user_id.to_string()))]
+        );
+
+        let anonymous = set
+            .evaluate(RlsAction::Read, &RlsContext::anonymous())
+            .unwrap();
+        assert_eq!(anonymous.fired, ["published"]);
+        assert_eq!(
+            anonymous.predicates,
+            [Predicate::eq("status", json!("published"))]
+        );
+
+        // AUTH-RLS1: the service role bypasses every policy
+        let bypass = set
+            .evaluate(RlsAction::Delete, &RlsContext::service_role())
+            .unwrap();
+        assert_eq!(bypass, RlsDecision::default());
+    }
+
+    #[test]
+    fn test_uncovered_action_denied() {
+        let set = RlsPolicySet::parse(POLICIES).unwrap();
+        // AUTH-RLS5: nothing covers anonymous inserts
+        assert!(matches!(
+            set.evaluate(RlsAction::Insert, &RlsContext::anonymous()),
+            Err(AuthError::PolicyDenied(_))
+        ));
+        let empty = RlsPolicySet::parse("-- nothing yet").unwrap();
+        assert!(matches!(
+            empty.evaluate(RlsAction::Read, &RlsContext::authenticated(Uuid::new_v4())),
+            Err(AuthError::PolicyDenied(_))
+        ));
+    }
+
+    #[test]
+    fn test_deny_and_role_conditions() {
+        let set = RlsPolicySet::parse(
+            "policy members on all when auth.role != 'admin' using tenant = auth.tenant_id;
+             policy admins on all when auth.role = 'admin' allow;
+             policy frozen on delete when auth.plan in ('free', 'trial') deny;",
+        )
+        .unwrap();
+
+        let mut admin = RlsContext::authenticated(Uuid::new_v4());
+        admin.claims.insert("role".to_string(), json!("admin"));
+        let decision = set.evaluate(RlsAction::Delete, &admin).unwrap();
+        assert_eq!(decision.fired, ["admins"]);
+        assert!(decision.predicates.is_empty());
+
+        let mut member = RlsContext::authenticated(Uuid::new_v4());
+        member.claims.insert("tenant_id".to_string(), json!(7));
+        let decision = set.evaluate(RlsAction::Update, &member).unwrap();
+        assert_eq!(decision.predicates, [Predicate::eq("tenant", json!(7))]);
+
+        member.claims.insert("plan".to_string(), json!("trial"));
+        let err = set.evaluate(RlsAction::Delete, &member).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Denied by RLS policy: policy frozen denies delete"
+        );
+
+        // A missing claim leaves the policy unsatisfiable
+        let stranger = RlsContext::authenticated(Uuid::new_v4());
+        assert!(matches!(
+            set.evaluate(RlsAction::Read, &stranger),
+            Err(AuthError::PolicyDenied(_))
+        ));
+        // A missing uid asks for a login instead
+        let owner = RlsPolicySet::parse("policy own on read using owner = auth.uid;").unwrap();
+        assert!(matches!(
+            owner.evaluate(RlsAction::Read, &RlsContext::anonymous()),
+            Err(AuthError::AuthenticationRequired)
+        ));
+    }
+
+    #[test]
+    fn test_writes_checked_against_predicates() {
+        let set = RlsPolicySet::parse(
+            "policy own on insert, update using owner = auth.uid and archived missing
+                 and path prefix 'docs/' and rank >= 1;",
+        )
+        .unwrap();
+        let user_id = Uuid::new_v4();
+        let decision = set
+            .evaluate(RlsAction::Insert, &RlsContext::authenticated(user_id))
+            .unwrap();
+        let owner = user_id.to_string();
+
+        assert!(decision.allows(&json!({"owner": owner, "path": "docs/a", "rank": 2})));
+        assert!(!decision.allows(&json!({"owner": owner, "path": "tmp/a", "rank": 2})));
+        assert!(!decision.allows(&json!({"owner": owner, "path": "docs/a", "rank": 0.5})));
+        assert!(!decision.allows(&json!({"path": "docs/a", "rank": 2})));
+
+        // Updates are checked only on the fields they set
+        assert!(decision.allows_changes(&json!({"title": "renamed"})));
+        assert!(decision.allows_changes(&json!({"rank": 3})));
+        assert!(!decision.allows_changes(&json!({"owner": Uuid::new_v4().to_string()})));
+        assert!(!decision.allows_changes(&json!({"archived": true})));
+    }
+
+    #[test]
+    fn test_parse_errors_report_position() {
+        for (source, message) in [
+            (
+                "policy a on read using x = ;",
+                "line 1:28: expected a string, number or boolean, found ';'",
+            ),
+            (
+                "policy a on write allow;",
+                "line 1:13: expected an action, found 'write'",
+            ),
+            (
+                "policy a on read\n  using x == 1;",
+                "line 2:12: expected a string, number or boolean, found '='",
+            ),
+            ("policy a on read allow", "expected ';' at end of input"),
+            (
+                "policy a on read when auth.uid = auth.sub allow;",
+                "line 1:39: conditions compare auth with literals, found 'sub'",
+            ),
+            (
+                "policy a on read using x prefix 1;",
+                "line 1:33: prefix needs a string, found number 1",
+            ),
+            (
+                "policy a on read using x = 'open;",
+                "line 1:28: unterminated string",
+            ),
+            (
+                "policy a on read using x = #;",
+                "line 1:28: unexpected character '#'",
+            ),
+            (
+                "policy a on read allow; policy a on insert deny;",
+                "duplicate policy a",
+            ),
+        ] {
+            let err = RlsPolicySet::parse(source).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("Invalid RLS policy: {}", message),
+                "{}",
+                source
+            );
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! # RLS Policy Store
+//!
+//! Published RLS policy sets, versioned per collection like schemas.
+//!
+//! Publishing compiles the policy text and stores it as the collection's
+//! next version, starting at 1; the highest version is the one enforced.
+//! With a data directory each version is written to
+//! `metadata/policies/policy_<collection>_<version>.json` and all of them
+//! are loaded again on open.
+//!
+//! ## Invariants
+//! - AUTH-RLS6: Only policy text that compiles is published, and a
+//!   published version never changes
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::errors::{AuthError, AuthResult};
+use super::rls_policy::RlsPolicySet;
+
+/// Policy directory, relative to the data directory
+pub const POLICY_DIR: &str = "metadata/policies";
+
+/// On-disk form of one policy version
+#[derive(Debug, Serialize, Deserialize)]
+struct PolicyFile {
+    collection: String,
+    version: u32,
+    source: String,
+    published_at: DateTime<Utc>,
+}
+
+/// One published version of a collection's policies
+#[derive(Debug, Clone)]
+pub struct PublishedPolicies {
+    /// Collection the policies guard
+    pub collection: String,
+    /// Version, counting from 1
+    pub version: u32,
+    /// Policy text as published
+    pub source: String,
+    /// When this version was published
+    pub published_at: DateTime<Utc>,
+    /// Compiled policies
+    pub policies: RlsPolicySet,
+}
+
+/// Versioned RLS policies per collection
+#[derive(Debug, Default)]
+pub struct RlsPolicyStore {
+    /// Policy directory, or `None` to keep policies in memory
+    dir: Option<PathBuf>,
+    /// Versions per collection, oldest first
+    collections: RwLock<BTreeMap<String, Vec<Arc<PublishedPolicies>>>>,
+}
+
+impl RlsPolicyStore {
+    /// Store kept in memory
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store persisted under `data_dir`
+    ///
+    /// A policy file that cannot be read or no longer compiles fails the
+    /// open, like a malformed schema file.
+    pub fn open(data_dir: &Path) -> AuthResult<Self> {
+        let dir = data_dir.join(POLICY_DIR);
+        let storage_error =
+            |path: &Path, e: String| AuthError::StorageError(format!("{}: {}", path.display(), e));
+
+        let mut collections: BTreeMap<String, Vec<Arc<PublishedPolicies>>> = BTreeMap::new();
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self {
+                    dir: Some(dir),
+                    collections: RwLock::new(collections),
+                })
+            }
+            Err(e) => return Err(storage_error(&dir, e.to_string())),
+        };
+        for entry in entries {
+            let path = entry
+                .map_err(|e| storage_error(&dir, e.to_string()))?
+                .path();
+            let is_policy_file = path.extension().is_some_and(|ext| ext == "json")
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("policy_"));
+            if !is_policy_file {
+                continue;
+            }
+            let bytes = fs::read(&path).map_err(|e| storage_error(&path, e.to_string()))?;
+            let file: PolicyFile =
+                serde_json::from_slice(&bytes).map_err(|e| storage_error(&path, e.to_string()))?;
+            let policies = RlsPolicySet::parse(&file.source)
+                .map_err(|e| storage_error(&path, e.to_string()))?;
+            collections
+                .entry(file.collection.clone())
+                .or_default()
+                .push(Arc::new(PublishedPolicies {
+                    collection: file.collection,
+                    version: file.version,
+                    source: file.source,
+                    published_at: file.published_at,
+                    policies,
+                }));
+        }
+        for versions in collections.values_mut() {
+            versions.sort_by_key(|published| published.version);
+        }
+
+        Ok(Self {
+            dir: Some(dir),
+            collections: RwLock::new(collections),
+        })
+    }
+
+    /// Compile `source` and publish it as `collection`'s next version
+    ///
+    /// # Invariant
+    /// AUTH-RLS6: Nothing is stored unless `source` compiles
+    pub fn publish(&self, collection: &str, source: &str) -> AuthResult<Arc<PublishedPolicies>> {
+        validate_collection_name(collection)?;
+        let policies = RlsPolicySet::parse(source)?;
+
+        let mut collections = self.collections.write().unwrap_or_else(|e| e.into_inner());
+        let versions = collections.entry(collection.to_string()).or_default();
+        let published = PublishedPolicies {
+            collection: collection.to_string(),
+            version: versions.last().map_or(1, |latest| latest.version + 1),
+            source: source.to_string(),
+            published_at: Utc::now(),
+            policies,
+        };
+
+        if let Some(dir) = &self.dir {
+            let path = dir.join(format!(
+                "policy_{}_{}.json",
+                published.collection, published.version
+            ));
+            let file = PolicyFile {
+                collection: published.collection.clone(),
+                version: published.version,
+                source: published.source.clone(),
+                published_at: published.published_at,
+            };
+            write_policy_file(dir, &path, &file)
+                .map_err(|e| AuthError::StorageError(format!("{}: {}", path.display(), e)))?;
+        }
+
+        let published = Arc::new(published);
+        versions.push(Arc::clone(&published));
+        Ok(published)
+    }
+
+    /// The enforced (latest) version of `collection`'s policies
+    pub fn active(&self, collection: &str) -> Option<Arc<PublishedPolicies>> {
+        let collections = self.collections.read().unwrap_or_else(|e| e.into_inner());
+        collections.get(collection)?.last().cloned()
+    }
+
+    /// A specific version of `collection`'s policies
+    pub fn version(&self, collection: &str, version: u32) -> Option<Arc<PublishedPolicies>> {
+        let collections = self.collections.read().unwrap_or_else(|e| e.into_inner());
+        collections
+            .get(collection)?
+            .iter()
+            .find(|published| published.version == version)
+            .cloned()
+    }
+
+    /// Published versions of `collection`, oldest first
+    pub fn versions(&self, collection: &str) -> Vec<u32> {
+        let collections = self.collections.read().unwrap_or_else(|e| e.into_inner());
+        collections
+            .get(collection)
+            .map(|versions| versions.iter().map(|published| published.version).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Collection names end up in file names, so keep them to `[A-Za-z0-9_-]`
+fn validate_collection_name(collection: &str) -> AuthResult<()> {
+    let valid = !collection.is_empty()
+        && collection
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(AuthError::InvalidPolicy(format!(
+            "invalid collection name {:?}",
+            collection
+        )))
+    }
+}
+
+/// Write `file` to a temporary file, fsync it, and rename it to `path`
+fn write_policy_file(dir: &Path, path: &Path, file: &PolicyFile) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let tmp = path.with_extension("json.tmp");
+    let mut out = File::create(&tmp)?;
+    out.write_all(&serde_json::to_vec_pretty(file)?)?;
+    out.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const OWN_ROWS: &str = "policy own_rows on all using owner_id = auth.uid;";
+    const PUBLIC: &str = "policy public on read allow;";
+
+    #[test]
+    fn test_publish_versions() {
+        let store = RlsPolicyStore::new();
+        assert!(store.active("posts").is_none());
+
+        assert_eq!(store.publish("posts", OWN_ROWS).unwrap().version, 1);
+        assert_eq!(store.publish("posts", PUBLIC).unwrap().version, 2);
+        assert_eq!(store.publish("notes", PUBLIC).unwrap().version, 1);
+
+        assert_eq!(store.versions("posts"), [1, 2]);
+        assert_eq!(store.active("posts").unwrap().source, PUBLIC);
+        assert_eq!(store.version("posts", 1).unwrap().source, OWN_ROWS);
+        assert!(store.version("posts", 3).is_none());
+    }
+
+    #[test]
+    fn test_invalid_policies_not_published() {
+        let store = RlsPolicyStore::new();
+        // AUTH-RLS6: a policy that does not compile leaves no version behind
+        assert!(matches!(
+            store.publish("posts", "policy broken on read"),
+            Err(AuthError::InvalidPolicy(_))
+        ));
+        assert!(store.versions("posts").is_empty());
+        assert!(matches!(
+            store.publish("../posts", PUBLIC),
+            Err(AuthError::InvalidPolicy(_))
+        ));
+    }
+
+    #[test]
+    fn test_policies_survive_reopen() {
+        let dir = TempDir::new().unwrap();
+        let store = RlsPolicyStore::open(dir.path()).unwrap();
+        store.publish("posts", OWN_ROWS).unwrap();
+        store.publish("posts", PUBLIC).unwrap();
+        drop(store);
+
+        assert!(dir
+            .path()
+            .join(POLICY_DIR)
+            .join("policy_posts_2.json")
+            .exists());
+        let store = RlsPolicyStore::open(dir.path()).unwrap();
+        assert_eq!(store.versions("posts"), [1, 2]);
+        let active = store.active("posts").unwrap();
+        assert_eq!(active.source, PUBLIC);
+        assert_eq!(active.policies, RlsPolicySet::parse(PUBLIC).unwrap());
+        assert_eq!(store.publish("posts", OWN_ROWS).unwrap().version, 3);
+    }
+}
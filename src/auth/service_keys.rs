@@ -0,0 +1,401 @@
+//! # Service API Keys
+//!
+//! API keys for server-to-server integrations, held by no user.
+//!
+//! A service key acts with the service role, so RLS policies do not apply
+//! to it, but only within its scopes: each scope names a collection (or
+//! `*` for all) and the operations (`read`, `insert`, `update`, `delete`)
+//! allowed on it. The core pipeline rejects anything else the key tries.
+//!
+//! A key is the string `sk_<key id>_<secret>`. Only a SHA-256 hash of the
+//! secret is kept, so a key is shown once, when it is created or rotated.
+//! Rotating replaces the secret and the old key stops working at once.
+//! With a data directory the keys are written through to
+//! `auth_service_keys.json` and loaded again on open.
+//!
+//! ## Invariants
+//! - AUTH-SK1: A service key is never granted more than its scopes
+//! - AUTH-SK2: Key secrets are never stored, only their hashes
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::crypto::{constant_time_str_eq, generate_token, hash_token};
+use super::errors::{AuthError, AuthResult};
+use super::rls_policy::RlsAction;
+
+/// Key registry, relative to the data directory
+pub const SERVICE_KEYS_FILE: &str = "auth_service_keys.json";
+
+/// Prefix of every service key
+const KEY_PREFIX: &str = "sk_";
+
+/// Collection name matching every collection in a scope
+pub const ANY_COLLECTION: &str = "*";
+
+/// Operations a key may perform on a collection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyScope {
+    /// Collection name, or `*` for every collection
+    pub collection: String,
+    /// Operations allowed
+    pub operations: BTreeSet<RlsAction>,
+}
+
+impl KeyScope {
+    /// Scope allowing `operations` on `collection`
+    pub fn new(
+        collection: impl Into<String>,
+        operations: impl IntoIterator<Item = RlsAction>,
+    ) -> Self {
+        Self {
+            collection: collection.into(),
+            operations: operations.into_iter().collect(),
+        }
+    }
+}
+
+/// What an authenticated service key may do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceKeyGrant {
+    /// Key ID
+    pub key_id: Uuid,
+    /// Key name, for audit and error messages
+    pub name: String,
+    /// Scopes of the key
+    pub scopes: Vec<KeyScope>,
+}
+
+impl ServiceKeyGrant {
+    /// Whether some scope allows `action` on `collection`
+    ///
+    /// # Invariant
+    /// AUTH-SK1: Anything no scope names is refused
+    pub fn allows(&self, collection: &str, action: RlsAction) -> bool {
+        self.scopes.iter().any(|scope| {
+            (scope.collection == ANY_COLLECTION || scope.collection == collection)
+                && scope.operations.contains(&action)
+        })
+    }
+}
+
+/// A registered service key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceKey {
+    /// Key ID
+    pub id: Uuid,
+    /// Integration the key is for
+    pub name: String,
+    /// What the key may do
+    pub scopes: Vec<KeyScope>,
+    /// SHA-256 of the current secret
+    secret_hash: String,
+    /// When the key was created
+    pub created_at: DateTime<Utc>,
+    /// When the secret was last rotated
+    pub rotated_at: Option<DateTime<Utc>>,
+    /// When the key stops working, if ever
+    pub expires_at: Option<DateTime<Utc>>,
+    /// When the key was revoked
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ServiceKey {
+    /// Whether the key has been revoked
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+
+    /// What the key may do
+    pub fn grant(&self) -> ServiceKeyGrant {
+        ServiceKeyGrant {
+            key_id: self.id,
+            name: self.name.clone(),
+            scopes: self.scopes.clone(),
+        }
+    }
+}
+
+/// Creates, rotates, revokes and authenticates service keys
+#[derive(Debug, Default)]
+pub struct ServiceKeyManager {
+    /// Registry file, or `None` to keep keys in memory
+    path: Option<PathBuf>,
+    keys: RwLock<BTreeMap<Uuid, ServiceKey>>,
+}
+
+impl ServiceKeyManager {
+    /// Manager keeping keys in memory
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Manager persisting keys in `data_dir`
+    pub fn open(data_dir: &Path) -> AuthResult<Self> {
+        let path = data_dir.join(SERVICE_KEYS_FILE);
+        let storage_error =
+            |e: String| AuthError::StorageError(format!("{}: {}", path.display(), e));
+        let keys: Vec<ServiceKey> = match fs::read(&path) {
+            Ok(bytes) => {
+                serde_json::from_slice(&bytes).map_err(|e| storage_error(e.to_string()))?
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(storage_error(e.to_string())),
+        };
+        Ok(Self {
+            path: Some(path),
+            keys: RwLock::new(keys.into_iter().map(|key| (key.id, key)).collect()),
+        })
+    }
+
+    /// Create a key named `name` with `scopes`, valid for `ttl` or forever
+    ///
+    /// Returns the registered key and the key string to hand over; the
+    /// string cannot be recovered later.
+    pub fn create(
+        &self,
+        name: &str,
+        scopes: Vec<KeyScope>,
+        ttl: Option<Duration>,
+    ) -> AuthResult<(ServiceKey, String)> {
+        validate_scopes(name, &scopes)?;
+        let now = Utc::now();
+        let id = Uuid::new_v4();
+        let (secret_hash, token) = new_secret(id);
+        let key = ServiceKey {
+            id,
+            name: name.to_string(),
+            scopes,
+            secret_hash,
+            created_at: now,
+            rotated_at: None,
+            expires_at: ttl.map(|ttl| now + ttl),
+            revoked_at: None,
+        };
+        self.update(|keys| {
+            keys.insert(id, key.clone());
+            Ok(())
+        })?;
+        Ok((key, token))
+    }
+
+    /// Replace key `id`'s secret; the previous key string stops working
+    pub fn rotate(&self, id: Uuid) -> AuthResult<(ServiceKey, String)> {
+        self.update(|keys| {
+            let key = keys.get_mut(&id).ok_or(AuthError::ApiKeyNotFound)?;
+            if key.is_revoked() {
+                return Err(AuthError::ApiKeyRevoked);
+            }
+            let (secret_hash, token) = new_secret(id);
+            key.secret_hash = secret_hash;
+            key.rotated_at = Some(Utc::now());
+            Ok((key.clone(), token))
+        })
+    }
+
+    /// Revoke key `id`; it authenticates nothing from now on
+    pub fn revoke(&self, id: Uuid) -> AuthResult<ServiceKey> {
+        self.update(|keys| {
+            let key = keys.get_mut(&id).ok_or(AuthError::ApiKeyNotFound)?;
+            if key.revoked_at.is_none() {
+                key.revoked_at = Some(Utc::now());
+            }
+            Ok(key.clone())
+        })
+    }
+
+    /// All registered keys, revoked ones included
+    pub fn list(&self) -> Vec<ServiceKey> {
+        let keys = self.keys.read().unwrap_or_else(|e| e.into_inner());
+        keys.values().cloned().collect()
+    }
+
+    /// The key `token` belongs to
+    ///
+    /// # Errors
+    ///
+    /// An unknown, rotated-out, revoked or expired key is an error.
+    pub fn authenticate(&self, token: &str) -> AuthResult<ServiceKey> {
+        let (id, secret) = token
+            .strip_prefix(KEY_PREFIX)
+            .and_then(|rest| rest.split_once('_'))
+            .ok_or(AuthError::InvalidToken)?;
+        let id = Uuid::parse_str(id).map_err(|_| AuthError::InvalidToken)?;
+
+        let keys = self.keys.read().unwrap_or_else(|e| e.into_inner());
+        let key = keys.get(&id).ok_or(AuthError::InvalidToken)?;
+        if !constant_time_str_eq(&hash_token(secret), &key.secret_hash) {
+            return Err(AuthError::InvalidToken);
+        }
+        if key.is_revoked() {
+            return Err(AuthError::ApiKeyRevoked);
+        }
+        if key
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= Utc::now())
+        {
+            return Err(AuthError::TokenExpired);
+        }
+        Ok(key.clone())
+    }
+
+    /// Apply `change` to the keys and write them through
+    fn update<T>(
+        &self,
+        change: impl FnOnce(&mut BTreeMap<Uuid, ServiceKey>) -> AuthResult<T>,
+    ) -> AuthResult<T> {
+        let mut keys = self.keys.write().unwrap_or_else(|e| e.into_inner());
+        let mut updated = keys.clone();
+        let result = change(&mut updated)?;
+        if let Some(path) = &self.path {
+            let records: Vec<&ServiceKey> = updated.values().collect();
+            write_keys(path, &records)
+                .map_err(|e| AuthError::StorageError(format!("{}: {}", path.display(), e)))?;
+        }
+        *keys = updated;
+        Ok(result)
+    }
+}
+
+/// Key names and scopes must say something
+fn validate_scopes(name: &str, scopes: &[KeyScope]) -> AuthResult<()> {
+    if name.trim().is_empty() {
+        return Err(AuthError::InvalidKeyScope("key name is empty".to_string()));
+    }
+    if scopes.is_empty() {
+        return Err(AuthError::InvalidKeyScope("key has no scopes".to_string()));
+    }
+    for scope in scopes {
+        if scope.collection.is_empty() || scope.operations.is_empty() {
+            return Err(AuthError::InvalidKeyScope(format!(
+                "scope {:?} needs a collection and operations",
+                scope.collection
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Fresh secret for key `id`: its hash and the key string
+///
+/// # Invariant
+/// AUTH-SK2: Only the hash is kept
+fn new_secret(id: Uuid) -> (String, String) {
+    let secret = generate_token();
+    let token = format!("{}{}_{}", KEY_PREFIX, id.simple(), secret);
+    (hash_token(&secret), token)
+}
+
+/// Write `keys` to a temporary file, fsync it, and rename it over `path`
+fn write_keys(path: &Path, keys: &[&ServiceKey]) -> std::io::Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(&serde_json::to_vec_pretty(keys)?)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn reader_scopes() -> Vec<KeyScope> {
+        vec![
+            KeyScope::new("orders", [RlsAction::Read, RlsAction::Insert]),
+            KeyScope::new(ANY_COLLECTION, [RlsAction::Read]),
+        ]
+    }
+
+    #[test]
+    fn test_scopes_limit_grant() {
+        let keys = ServiceKeyManager::new();
+        let (key, token) = keys.create("billing", reader_scopes(), None).unwrap();
+        assert!(token.starts_with("sk_"));
+
+        let grant = keys.authenticate(&token).unwrap().grant();
+        assert_eq!(grant.key_id, key.id);
+        assert!(grant.allows("orders", RlsAction::Insert));
+        assert!(grant.allows("customers", RlsAction::Read));
+        // AUTH-SK1
+        assert!(!grant.allows("customers", RlsAction::Insert));
+        assert!(!grant.allows("orders", RlsAction::Delete));
+
+        assert!(matches!(
+            keys.create("empty", Vec::new(), None),
+            Err(AuthError::InvalidKeyScope(_))
+        ));
+        assert!(matches!(
+            keys.create("none", vec![KeyScope::new("orders", [])], None),
+            Err(AuthError::InvalidKeyScope(_))
+        ));
+    }
+
+    #[test]
+    fn test_rotate_and_revoke() {
+        let keys = ServiceKeyManager::new();
+        let (key, old) = keys.create("billing", reader_scopes(), None).unwrap();
+
+        let (rotated, new) = keys.rotate(key.id).unwrap();
+        assert!(rotated.rotated_at.is_some());
+        assert!(matches!(
+            keys.authenticate(&old),
+            Err(AuthError::InvalidToken)
+        ));
+        assert_eq!(keys.authenticate(&new).unwrap().id, key.id);
+
+        assert!(keys.revoke(key.id).unwrap().is_revoked());
+        assert!(matches!(
+            keys.authenticate(&new),
+            Err(AuthError::ApiKeyRevoked)
+        ));
+        assert!(matches!(keys.rotate(key.id), Err(AuthError::ApiKeyRevoked)));
+        assert!(matches!(
+            keys.revoke(Uuid::new_v4()),
+            Err(AuthError::ApiKeyNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_bad_and_expired_keys_rejected() {
+        let keys = ServiceKeyManager::new();
+        let (key, token) = keys
+            .create("billing", reader_scopes(), Some(Duration::seconds(-1)))
+            .unwrap();
+        assert!(matches!(
+            keys.authenticate(&token),
+            Err(AuthError::TokenExpired)
+        ));
+
+        let forged = format!("sk_{}_{}", key.id.simple(), "A".repeat(43));
+        for bad in ["", "sk_", "sk_nope_secret", forged.as_str()] {
+            assert!(matches!(
+                keys.authenticate(bad),
+                Err(AuthError::InvalidToken)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_keys_survive_reopen() {
+        let dir = TempDir::new().unwrap();
+        let keys = ServiceKeyManager::open(dir.path()).unwrap();
+        let (key, token) = keys.create("billing", reader_scopes(), None).unwrap();
+        drop(keys);
+
+        // AUTH-SK2: the key string is not on disk
+        let stored = fs::read_to_string(dir.path().join(SERVICE_KEYS_FILE)).unwrap();
+        assert!(!stored.contains(token.rsplit('_').next().unwrap()));
+
+        let keys = ServiceKeyManager::open(dir.path()).unwrap();
+        assert_eq!(keys.authenticate(&token).unwrap(), key);
+        assert_eq!(keys.list(), [key]);
+    }
+}
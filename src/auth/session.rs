@@ -3,10 +3,16 @@
 //! Session and refresh token management.
 //! Sessions are stored in the `_sessions` collection.
 //!
+//! Refreshing rotates the refresh token: the presented token's session is
+//! replaced by a new one in the same family. Presenting a rotated token
+//! again means it leaked (or the rotation reply was lost), so the whole
+//! family is revoked and both holders must log in again.
+//!
 //! ## Invariants
 //! - AUTH-SS1: Refresh tokens are single-use
 //! - AUTH-SS2: Sessions expire at stated time
 //! - AUTH-SS3: Logout invalidates immediately
+//! - AUTH-SS4: Reuse of a rotated refresh token revokes its family
 
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
@@ -37,6 +43,14 @@ pub struct Session {
     /// Whether the session has been revoked
     pub revoked: bool,
 
+    /// Refresh token family: the login this session was rotated from
+    #[serde(default)]
+    pub family_id: Uuid,
+
+    /// Session that replaced this one when its refresh token was rotated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaced_by: Option<Uuid>,
+
     /// User agent from the request
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user_agent: Option<String>,
@@ -94,6 +108,21 @@ impl<R: SessionRepository> SessionManager<R> {
         user_agent: Option<String>,
         ip_address: Option<String>,
     ) -> AuthResult<(Session, String)> {
+        let (session, refresh_token) =
+            self.new_session(user_id, Uuid::new_v4(), user_agent, ip_address);
+
+        self.repository.create(&session)?;
+
+        Ok((session, refresh_token))
+    }
+
+    fn new_session(
+        &self,
+        user_id: Uuid,
+        family_id: Uuid,
+        user_agent: Option<String>,
+        ip_address: Option<String>,
+    ) -> (Session, String) {
         let refresh_token = generate_token();
         let refresh_token_hash = hash_token(&refresh_token);
 
@@ -105,19 +134,23 @@ impl<R: SessionRepository> SessionManager<R> {
             created_at: now,
             expires_at: now + self.config.refresh_token_ttl,
             revoked: false,
+            family_id,
+            replaced_by: None,
             user_agent,
             ip_address,
         };
-
-        self.repository.create(&session)?;
-
-        Ok((session, refresh_token))
+        (session, refresh_token)
     }
 
     /// Refresh a session using the refresh token
     ///
-    /// # Invariant
-    /// AUTH-SS1: Refresh tokens are single-use (old session revoked)
+    /// The new session belongs to the old one's family and expires with
+    /// the same refresh token lifetime from now.
+    ///
+    /// # Invariants
+    /// - AUTH-SS1: Refresh tokens are single-use (old session rotated)
+    /// - AUTH-SS4: A rotated token presented again revokes the family
+    ///   (`RefreshTokenReused`)
     pub fn refresh_session(&self, refresh_token: &str) -> AuthResult<(Session, String)> {
         let token_hash = hash_token(refresh_token);
 
@@ -127,6 +160,11 @@ impl<R: SessionRepository> SessionManager<R> {
             .find_by_refresh_token_hash(&token_hash)?
             .ok_or(AuthError::InvalidRefreshToken)?;
 
+        if old_session.replaced_by.is_some() {
+            self.repository.revoke_family(old_session.family_id)?;
+            return Err(AuthError::RefreshTokenReused);
+        }
+
         // Check if revoked
         if old_session.revoked {
             return Err(AuthError::SessionRevoked);
@@ -137,15 +175,33 @@ impl<R: SessionRepository> SessionManager<R> {
             return Err(AuthError::SessionInvalid);
         }
 
-        // Revoke old session (single-use token)
-        self.repository.revoke(old_session.id)?;
-
-        // Create new session
-        self.create_session(
+        // Replace the old session (single-use token); a concurrent
+        // refresh with the same token loses and counts as reuse
+        let (session, new_token) = self.new_session(
             old_session.user_id,
+            old_session.family_id,
             old_session.user_agent,
             old_session.ip_address,
-        )
+        );
+        if let Err(e) = self.repository.rotate(old_session.id, &session) {
+            if matches!(e, AuthError::RefreshTokenReused) {
+                self.repository.revoke_family(old_session.family_id)?;
+            }
+            return Err(e);
+        }
+
+        Ok((session, new_token))
+    }
+
+    /// Session of `refresh_token` in any state (rotated, revoked, expired)
+    pub fn find_session(&self, refresh_token: &str) -> AuthResult<Option<Session>> {
+        self.repository
+            .find_by_refresh_token_hash(&hash_token(refresh_token))
+    }
+
+    /// Revoke every session of a refresh token family
+    pub fn revoke_family(&self, family_id: Uuid) -> AuthResult<()> {
+        self.repository.revoke_family(family_id)
     }
 
     /// Revoke a session (logout)
@@ -204,6 +260,16 @@ pub trait SessionRepository: Send + Sync {
     /// Revoke a session
     fn revoke(&self, id: Uuid) -> AuthResult<()>;
 
+    /// Atomically revoke session `old_id` as replaced by `new`, and store
+    /// `new`
+    ///
+    /// Fails with `RefreshTokenReused` if `old_id` was already replaced,
+    /// or `SessionRevoked` if it was revoked.
+    fn rotate(&self, old_id: Uuid, new: &Session) -> AuthResult<()>;
+
+    /// Revoke all sessions of a refresh token family
+    fn revoke_family(&self, family_id: Uuid) -> AuthResult<()>;
+
     /// Revoke all sessions for a user
     fn revoke_all_for_user(&self, user_id: Uuid) -> AuthResult<()>;
 
@@ -278,6 +344,41 @@ impl SessionRepository for InMemorySessionRepository {
         }
     }
 
+    fn rotate(&self, old_id: Uuid, new: &Session) -> AuthResult<()> {
+        let mut sessions = self
+            .sessions
+            .write()
+            .map_err(|_| AuthError::StorageError("Lock poisoned".to_string()))?;
+
+        let old = sessions
+            .iter_mut()
+            .find(|s| s.id == old_id)
+            .ok_or(AuthError::SessionInvalid)?;
+        if old.replaced_by.is_some() {
+            return Err(AuthError::RefreshTokenReused);
+        }
+        if old.revoked {
+            return Err(AuthError::SessionRevoked);
+        }
+        old.revoked = true;
+        old.replaced_by = Some(new.id);
+        sessions.push(new.clone());
+        Ok(())
+    }
+
+    fn revoke_family(&self, family_id: Uuid) -> AuthResult<()> {
+        let mut sessions = self
+            .sessions
+            .write()
+            .map_err(|_| AuthError::StorageError("Lock poisoned".to_string()))?;
+
+        for session in sessions.iter_mut().filter(|s| s.family_id == family_id) {
+            session.revoked = true;
+        }
+
+        Ok(())
+    }
+
     fn revoke_all_for_user(&self, user_id: Uuid) -> AuthResult<()> {
         let mut sessions = self
             .sessions
@@ -357,12 +458,34 @@ mod tests {
         let (new_session, new_token) = manager.refresh_session(&refresh_token).unwrap();
         assert_eq!(new_session.user_id, user_id);
 
-        // Using old token again should fail (single-use)
+        // New token should work
+        let (newest, newest_token) = manager.refresh_session(&new_token).unwrap();
+        assert_eq!(newest.family_id, new_session.family_id);
+
+        // Using an old token again should fail (single-use)...
         let result = manager.refresh_session(&refresh_token);
-        assert!(matches!(result, Err(AuthError::SessionRevoked)));
+        assert!(matches!(result, Err(AuthError::RefreshTokenReused)));
 
-        // New token should work
-        let _ = manager.refresh_session(&new_token).unwrap();
+        // ...and revoke the whole family (AUTH-SS4)
+        assert!(matches!(
+            manager.refresh_session(&newest_token),
+            Err(AuthError::SessionRevoked)
+        ));
+    }
+
+    #[test]
+    fn test_logins_are_separate_families() {
+        let manager = create_manager();
+        let user_id = Uuid::new_v4();
+
+        let (first, first_token) = manager.create_session(user_id, None, None).unwrap();
+        let (second, second_token) = manager.create_session(user_id, None, None).unwrap();
+        assert_ne!(first.family_id, second.family_id);
+
+        let (_, rotated) = manager.refresh_session(&first_token).unwrap();
+        manager.revoke_family(first.family_id).unwrap();
+        assert!(manager.validate_refresh_token(&rotated).is_err());
+        assert!(manager.validate_refresh_token(&second_token).is_ok());
     }
 
     #[test]
@@ -9,6 +9,13 @@ use uuid::Uuid;
 
 use super::crypto::{hash_password, validate_password, verify_password, PasswordPolicy};
 use super::errors::{AuthError, AuthResult};
+use super::mfa::TotpFactor;
+
+/// Role of users without one in their metadata
+pub const DEFAULT_ROLE: &str = "authenticated";
+
+/// Role of operators, who may manage service API keys
+pub const ADMIN_ROLE: &str = "admin";
 
 /// User model
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +42,10 @@ pub struct User {
     /// Optional user metadata
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<serde_json::Value>,
+
+    /// TOTP second factor, if enrolled (never serialized)
+    #[serde(skip)]
+    pub mfa: Option<TotpFactor>,
 }
 
 impl User {
@@ -56,9 +67,26 @@ impl User {
             created_at: now,
             updated_at: now,
             metadata: None,
+            mfa: None,
         })
     }
 
+    /// Role of the user: `metadata.role`, else `"authenticated"`
+    ///
+    /// The role is assigned by operators; profile updates keep it.
+    pub fn role(&self) -> &str {
+        self.metadata
+            .as_ref()
+            .and_then(|m| m.get("role"))
+            .and_then(|r| r.as_str())
+            .unwrap_or(DEFAULT_ROLE)
+    }
+
+    /// Whether login needs a TOTP code
+    pub fn has_confirmed_mfa(&self) -> bool {
+        self.mfa.as_ref().is_some_and(|factor| factor.confirmed)
+    }
+
     /// Verify a password against this user's stored hash
     pub fn verify_password(&self, password: &str) -> AuthResult<bool> {
         verify_password(password, &self.password_hash)
@@ -104,6 +132,9 @@ pub struct SignupRequest {
 pub struct LoginRequest {
     pub email: String,
     pub password: String,
+    /// Current TOTP code, for users with a second factor
+    #[serde(default)]
+    pub totp_code: Option<String>,
 }
 
 /// User profile update request
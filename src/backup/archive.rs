@@ -7,12 +7,13 @@
 //! - fsync archive after creation
 
 use std::fs::{self, File, OpenOptions};
-use std::io::{BufWriter, Write};
+use std::io::{BufWriter, Read, Write};
 use std::path::Path;
 
-use tar::Builder;
+use tar::{Archive, Builder};
 
 use super::errors::{BackupError, BackupResult};
+use super::manifest::BackupManifest;
 
 /// Create a tar archive from a source directory
 ///
@@ -160,6 +161,42 @@ pub fn fsync_archive(archive_path: &Path) -> BackupResult<()> {
     Ok(())
 }
 
+/// Read backup_manifest.json from an archive without extracting it
+pub fn read_archive_manifest(archive_path: &Path) -> BackupResult<BackupManifest> {
+    let file =
+        File::open(archive_path).map_err(|e| BackupError::io_error_at_path(archive_path, e))?;
+    let mut archive = Archive::new(file);
+    let entries = archive
+        .entries()
+        .map_err(|e| BackupError::io_error_at_path(archive_path, e))?;
+
+    for entry in entries {
+        let mut entry = entry.map_err(|e| BackupError::io_error_at_path(archive_path, e))?;
+        let is_manifest = entry
+            .path()
+            .map(|path| path.as_os_str() == "backup_manifest.json")
+            .unwrap_or(false);
+        if is_manifest {
+            let mut contents = String::new();
+            entry.read_to_string(&mut contents).map_err(|e| {
+                BackupError::manifest_failed_with_source(
+                    format!(
+                        "Failed to read backup manifest from {}",
+                        archive_path.display()
+                    ),
+                    e,
+                )
+            })?;
+            return BackupManifest::from_json(&contents);
+        }
+    }
+
+    Err(BackupError::manifest_failed(format!(
+        "Archive has no backup_manifest.json: {}",
+        archive_path.display()
+    )))
+}
+
 /// Delete a partial archive if it exists
 ///
 /// Per BACKUP.md §5: Partial backups must be deleted
@@ -41,29 +41,56 @@
 //! Backup does NOT create snapshots.
 //! Backup does NOT modify WAL.
 //! Backup does NOT truncate WAL.
+//!
+//! # Protection
+//!
+//! `create_protected_backup` may encrypt the archive and sign it (see
+//! `protection`). Signing happens last, over the archive as written, with
+//! the signature beside it in `backup.tar.sig`.
 
 mod archive;
 mod errors;
 mod manifest;
 mod packer;
+mod protection;
 
 pub use errors::{BackupError, BackupErrorCode, BackupResult, Severity};
 pub use manifest::BackupManifest;
+pub use protection::{
+    is_encrypted_archive, signature_path, verify_archive_signature, ArchiveDecryptor,
+    BackupProtection, BackupSigningKey, BackupVerifyKey, ENCRYPTED_ARCHIVE_MAGIC,
+    SIGNATURE_EXTENSION,
+};
 
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use crate::snapshot::GlobalExecutionLock;
 use crate::wal::WalWriter;
 
-use archive::{cleanup_partial_archive, create_tar_archive};
+use archive::{cleanup_partial_archive, create_tar_archive, read_archive_manifest};
 use packer::{
     cleanup_temp_dir, copy_snapshot_to_temp, copy_wal_to_temp, create_temp_backup_dir,
     find_latest_snapshot, fsync_recursive, get_snapshot_id,
 };
+use protection::{cleanup_signature, encrypt_archive, sign_archive};
 
 /// Backup ID type (equals SnapshotId per spec)
 pub type BackupId = String;
 
+/// A backup archive found on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupArchive {
+    /// Path to the backup.tar
+    pub path: PathBuf,
+
+    /// Archive size in bytes
+    pub size_bytes: u64,
+
+    /// The archive's backup_manifest.json
+    pub manifest: BackupManifest,
+}
+
 /// Backup manager for creating backup archives.
 ///
 /// This struct provides the public API for backup operations.
@@ -129,6 +156,34 @@ impl BackupManager {
         data_dir: &Path,
         output_path: &Path,
         wal: &WalWriter,
+        lock: &GlobalExecutionLock,
+    ) -> Result<BackupId, BackupError> {
+        Self::create_protected_backup(
+            data_dir,
+            output_path,
+            wal,
+            &BackupProtection::default(),
+            lock,
+        )
+    }
+
+    /// Create a backup archive, encrypted and signed per `protection`.
+    ///
+    /// Identical to `create_backup`, except that the packaged tar is
+    /// encrypted under `protection.encryption_key` into `output_path`, and
+    /// `output_path` is then signed with `protection.signing_key` into
+    /// `backup.tar.sig`. The unencrypted tar is only ever written inside
+    /// the data directory and removed afterwards.
+    ///
+    /// # Errors
+    ///
+    /// As `create_backup`; a failed backup leaves neither archive nor
+    /// signature behind.
+    pub fn create_protected_backup(
+        data_dir: &Path,
+        output_path: &Path,
+        wal: &WalWriter,
+        protection: &BackupProtection,
         _lock: &GlobalExecutionLock,
     ) -> Result<BackupId, BackupError> {
         // Step 2: fsync WAL to ensure all pending writes are durable
@@ -159,10 +214,24 @@ impl BackupManager {
             // Step 7: fsync temp directory
             fsync_recursive(&temp_dir)?;
 
-            // Step 8: Package temp directory into tar
-            create_tar_archive(&temp_dir, output_path)?;
+            // Step 8: Package temp directory into tar, encrypting it if
+            // asked to
+            match &protection.encryption_key {
+                Some(key) => {
+                    let plain_path = temp_dir.with_extension("tar");
+                    let packed = create_tar_archive(&temp_dir, &plain_path)
+                        .and_then(|()| encrypt_archive(&plain_path, output_path, key));
+                    let _ = fs::remove_file(&plain_path);
+                    packed?;
+                }
+                None => create_tar_archive(&temp_dir, output_path)?,
+            }
+
+            // Step 9: fsync backup.tar (already done when it was written)
 
-            // Step 9: fsync backup.tar (already done in create_tar_archive)
+            if let Some(key) = &protection.signing_key {
+                sign_archive(output_path, key)?;
+            }
 
             Ok(snapshot_id.clone())
         })();
@@ -173,10 +242,44 @@ impl BackupManager {
         // On error, also cleanup partial archive
         if result.is_err() {
             cleanup_partial_archive(output_path);
+            cleanup_signature(output_path);
         }
 
         result
     }
+
+    /// List the backup archives (`*.tar`) in a directory, oldest first.
+    ///
+    /// Only the backup manifest is read; archive contents are not
+    /// verified. Archives without a readable manifest, including
+    /// encrypted ones, are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns `BackupError` if the directory cannot be read.
+    pub fn list_backups(directory: &Path) -> BackupResult<Vec<BackupArchive>> {
+        let entries =
+            fs::read_dir(directory).map_err(|e| BackupError::io_error_at_path(directory, e))?;
+
+        let mut backups: Vec<BackupArchive> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "tar"))
+            .filter_map(|path| {
+                let manifest = read_archive_manifest(&path).ok()?;
+                let size_bytes = fs::metadata(&path).ok()?.len();
+                Some(BackupArchive {
+                    path,
+                    size_bytes,
+                    manifest,
+                })
+            })
+            .collect();
+        backups.sort_by(|a, b| {
+            (&a.manifest.created_at, &a.path).cmp(&(&b.manifest.created_at, &b.path))
+        });
+        Ok(backups)
+    }
 }
 
 #[cfg(test)]
@@ -425,6 +528,30 @@ mod tests {
         assert!(!data_dir.join(".backup_temp").exists());
     }
 
+    #[test]
+    fn test_list_backups_reads_manifests() {
+        let (temp_dir, _) = setup_test_environment();
+        let data_dir = temp_dir.path();
+        create_test_snapshot(data_dir, "20260204T163000Z");
+
+        let wal = WalWriter::open(data_dir).unwrap();
+        let backups_dir = data_dir.join("backups");
+        fs::create_dir_all(&backups_dir).unwrap();
+        let output_path = backups_dir.join("nightly.tar");
+        BackupManager::create_backup(data_dir, &output_path, &wal, &GlobalExecutionLock::new())
+            .unwrap();
+        // Not a backup: no manifest
+        fs::write(backups_dir.join("other.tar"), b"not a tar").unwrap();
+
+        let backups = BackupManager::list_backups(&backups_dir).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].path, output_path);
+        assert_eq!(backups[0].manifest.backup_id, "20260204T163000Z");
+        assert!(backups[0].size_bytes > 0);
+
+        assert!(BackupManager::list_backups(&data_dir.join("missing")).is_err());
+    }
+
     #[test]
     fn test_backup_error_not_fatal() {
         let err = BackupError::failed("test");
@@ -449,4 +576,62 @@ mod tests {
 
         // If this compiles, the test passes
     }
+
+    #[test]
+    fn test_protected_backup_verifies_only_under_its_policy() {
+        use crate::restore::{RestoreErrorCode, RestoreManager, RestorePolicy};
+        use crate::storage::EncryptionKey;
+
+        let (temp_dir, _) = setup_test_environment();
+        let data_dir = temp_dir.path();
+        create_test_snapshot(data_dir, "20260204T163000Z");
+        let wal = WalWriter::open(data_dir).unwrap();
+        let output_path = data_dir.join("backup.tar");
+
+        let key = EncryptionKey::generate();
+        let signing = BackupSigningKey::generate();
+        let trusted = signing.verify_key();
+        let protection = BackupProtection::default()
+            .with_encryption(key.clone())
+            .with_signing(signing);
+        BackupManager::create_protected_backup(
+            data_dir,
+            &output_path,
+            &wal,
+            &protection,
+            &GlobalExecutionLock::new(),
+        )
+        .unwrap();
+        assert!(is_encrypted_archive(&output_path).unwrap());
+        assert!(signature_path(&output_path).exists());
+        assert!(!data_dir.join(".backup_temp.tar").exists());
+        let bytes = fs::read(&output_path).unwrap();
+        assert!(!bytes.windows(17).any(|w| w == b"test storage data"));
+
+        let policy = RestorePolicy::default()
+            .with_decryption_key(key)
+            .with_verify_key(trusted)
+            .requiring_signature();
+        let manifest = RestoreManager::verify_backup_with_policy(&output_path, &policy).unwrap();
+        assert_eq!(manifest.backup_id, "20260204T163000Z");
+
+        let err = RestoreManager::verify_backup(&output_path).unwrap_err();
+        assert_eq!(err.code(), RestoreErrorCode::AeroRestoreInvalidBackup);
+        let wrong_key = RestorePolicy {
+            decryption_key: Some(EncryptionKey::generate()),
+            ..policy.clone()
+        };
+        let err = RestoreManager::verify_backup_with_policy(&output_path, &wrong_key).unwrap_err();
+        assert_eq!(err.code(), RestoreErrorCode::AeroRestoreCorruption);
+        let untrusted = RestorePolicy {
+            verify_key: Some(BackupSigningKey::generate().verify_key()),
+            ..policy.clone()
+        };
+        let err = RestoreManager::verify_backup_with_policy(&output_path, &untrusted).unwrap_err();
+        assert_eq!(err.code(), RestoreErrorCode::AeroRestoreSignatureInvalid);
+
+        fs::remove_file(signature_path(&output_path)).unwrap();
+        let err = RestoreManager::verify_backup_with_policy(&output_path, &policy).unwrap_err();
+        assert_eq!(err.code(), RestoreErrorCode::AeroRestoreSignatureInvalid);
+    }
 }
@@ -0,0 +1,260 @@
+//! WAL archiving before truncation
+//!
+//! Checkpoint is the only mechanism that truncates WAL. Without archiving,
+//! truncated records are lost forever. A `WalArchiver` is invoked by the
+//! checkpoint coordinator after the marker is written and BEFORE the WAL is
+//! truncated, so archived segments can feed PITR and external audit
+//! pipelines.
+//!
+//! # Crash Safety
+//!
+//! - Archive failure aborts the checkpoint → WAL intact, marker shows
+//!   `wal_truncated = false`
+//! - Crash during archiving → partial `.wal.tmp` file is ignored; the next
+//!   checkpoint archives the full WAL again
+//! - An archived segment is durable (file + directory fsync) before
+//!   truncation begins
+//! - An archived segment is never replaced. Checkpoint ids have
+//!   one-second resolution, so a second checkpoint in the same second
+//!   archives to `<checkpoint_id>_<n>.wal`, the first free `n` from 1;
+//!   segment names sort in archive order
+
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::errors::{CheckpointError, CheckpointResult};
+
+/// Archive directory name within the data directory
+pub const WAL_ARCHIVE_DIR: &str = "archive";
+
+/// Hook invoked by checkpoint before WAL truncation.
+///
+/// Implementations MUST make the archived copy durable before returning
+/// `Ok`. Returning an error aborts the checkpoint with the WAL intact.
+pub trait WalArchiver {
+    /// Archive the WAL file about to be truncated by `checkpoint_id`.
+    fn archive(&self, checkpoint_id: &str, wal_path: &Path) -> CheckpointResult<()>;
+}
+
+/// Archiver that discards truncated WAL (the default behavior).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopWalArchiver;
+
+impl WalArchiver for NoopWalArchiver {
+    fn archive(&self, _checkpoint_id: &str, _wal_path: &Path) -> CheckpointResult<()> {
+        Ok(())
+    }
+}
+
+/// Archiver that copies the WAL to `<archive_dir>/<checkpoint_id>.wal`
+/// (`<checkpoint_id>_<n>.wal` for later checkpoints with the same id).
+#[derive(Debug, Clone)]
+pub struct FileWalArchiver {
+    archive_dir: PathBuf,
+}
+
+impl FileWalArchiver {
+    /// Create an archiver writing to an explicit directory.
+    pub fn new(archive_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            archive_dir: archive_dir.into(),
+        }
+    }
+
+    /// Create an archiver writing to `<data_dir>/archive`.
+    pub fn in_data_dir(data_dir: &Path) -> Self {
+        Self::new(data_dir.join(WAL_ARCHIVE_DIR))
+    }
+
+    /// Returns the archive directory.
+    pub fn archive_dir(&self) -> &Path {
+        &self.archive_dir
+    }
+
+    /// Returns the archive path for a checkpoint.
+    ///
+    /// This is the first segment archived under `checkpoint_id`; later
+    /// ones are at `segment_path(checkpoint_id, n)`.
+    pub fn archive_path(&self, checkpoint_id: &str) -> PathBuf {
+        self.segment_path(checkpoint_id, 0)
+    }
+
+    /// Returns the path of the `n`th segment archived under `checkpoint_id`.
+    pub fn segment_path(&self, checkpoint_id: &str, n: u32) -> PathBuf {
+        if n == 0 {
+            self.archive_dir.join(format!("{}.wal", checkpoint_id))
+        } else {
+            self.archive_dir
+                .join(format!("{}_{:04}.wal", checkpoint_id, n))
+        }
+    }
+}
+
+impl WalArchiver for FileWalArchiver {
+    fn archive(&self, checkpoint_id: &str, wal_path: &Path) -> CheckpointResult<()> {
+        fs::create_dir_all(&self.archive_dir).map_err(|e| {
+            CheckpointError::wal_archive_failed(
+                format!(
+                    "Failed to create archive directory: {}",
+                    self.archive_dir.display()
+                ),
+                e,
+            )
+        })?;
+
+        let tmp_path = self.archive_dir.join(format!("{}.wal.tmp", checkpoint_id));
+
+        // Copy to a temporary file first so a crash never leaves a
+        // truncated segment under the final name
+        copy_with_fsync(wal_path, &tmp_path).map_err(|e| {
+            CheckpointError::wal_archive_failed(
+                format!(
+                    "Failed to copy WAL {} to {}",
+                    wal_path.display(),
+                    tmp_path.display()
+                ),
+                e,
+            )
+        })?;
+
+        // Linking fails instead of replacing an existing segment
+        let mut n = 0;
+        loop {
+            let final_path = self.segment_path(checkpoint_id, n);
+            match fs::hard_link(&tmp_path, &final_path) {
+                Ok(()) => break,
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => n += 1,
+                Err(e) => {
+                    let _ = fs::remove_file(&tmp_path);
+                    return Err(CheckpointError::wal_archive_failed(
+                        format!("Failed to finalize archive: {}", final_path.display()),
+                        e,
+                    ));
+                }
+            }
+        }
+        fs::remove_file(&tmp_path).map_err(|e| {
+            CheckpointError::wal_archive_failed(
+                format!("Failed to remove {}", tmp_path.display()),
+                e,
+            )
+        })?;
+
+        fsync_dir(&self.archive_dir).map_err(|e| {
+            CheckpointError::wal_archive_failed(
+                format!(
+                    "fsync archive directory failed: {}",
+                    self.archive_dir.display()
+                ),
+                e,
+            )
+        })
+    }
+}
+
+/// Checkpoint id an archived segment was written for, if `path` names one.
+pub fn segment_checkpoint_id(path: &Path) -> Option<&str> {
+    if path.extension()? != "wal" {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?;
+    Some(
+        stem.split_once('_')
+            .map_or(stem, |(checkpoint_id, _)| checkpoint_id),
+    )
+}
+
+/// Copy a file byte-for-byte and fsync the destination.
+fn copy_with_fsync(src: &Path, dst: &Path) -> io::Result<()> {
+    let mut src_file = File::open(src)?;
+    let mut dst_file = File::create(dst)?;
+    io::copy(&mut src_file, &mut dst_file)?;
+    dst_file.sync_all()
+}
+
+/// fsync a directory to ensure durability of renames.
+fn fsync_dir(path: &Path) -> io::Result<()> {
+    OpenOptions::new().read(true).open(path)?.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkpoint::CheckpointErrorCode;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_file_archiver_copies_wal() {
+        let temp_dir = TempDir::new().unwrap();
+        let wal_path = temp_dir.path().join("wal.log");
+        let mut file = File::create(&wal_path).unwrap();
+        file.write_all(b"wal bytes").unwrap();
+
+        let archiver = FileWalArchiver::in_data_dir(temp_dir.path());
+        archiver.archive("20260204T113000Z", &wal_path).unwrap();
+
+        let archived = archiver.archive_path("20260204T113000Z");
+        assert_eq!(fs::read(&archived).unwrap(), b"wal bytes");
+        assert!(!archiver
+            .archive_dir()
+            .join("20260204T113000Z.wal.tmp")
+            .exists());
+    }
+
+    #[test]
+    fn test_file_archiver_keeps_same_second_segments() {
+        let temp_dir = TempDir::new().unwrap();
+        let wal_path = temp_dir.path().join("wal.log");
+        let archiver = FileWalArchiver::in_data_dir(temp_dir.path());
+
+        // Three checkpoints resolving to the same id
+        for contents in ["first", "second", "third"] {
+            fs::write(&wal_path, contents).unwrap();
+            archiver.archive("20260204T113000Z", &wal_path).unwrap();
+        }
+
+        assert_eq!(
+            fs::read(archiver.archive_path("20260204T113000Z")).unwrap(),
+            b"first"
+        );
+        assert_eq!(
+            fs::read(archiver.segment_path("20260204T113000Z", 1)).unwrap(),
+            b"second"
+        );
+        assert_eq!(
+            fs::read(archiver.segment_path("20260204T113000Z", 2)).unwrap(),
+            b"third"
+        );
+
+        // Names sort in archive order, before the next second's segment
+        fs::write(&wal_path, "fourth").unwrap();
+        archiver.archive("20260204T113001Z", &wal_path).unwrap();
+        let mut paths: Vec<_> = fs::read_dir(archiver.archive_dir())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        paths.sort();
+        let contents: Vec<_> = paths.iter().map(|path| fs::read(path).unwrap()).collect();
+        assert_eq!(contents, [&b"first"[..], b"second", b"third", b"fourth"]);
+        assert_eq!(
+            segment_checkpoint_id(&archiver.segment_path("20260204T113000Z", 2)),
+            Some("20260204T113000Z")
+        );
+    }
+
+    #[test]
+    fn test_file_archiver_missing_wal_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let archiver = FileWalArchiver::in_data_dir(temp_dir.path());
+
+        let err = archiver
+            .archive("cp1", &temp_dir.path().join("missing.log"))
+            .unwrap_err();
+        assert_eq!(
+            err.code(),
+            CheckpointErrorCode::AeroCheckpointWalArchiveFailed
+        );
+    }
+}
@@ -7,24 +7,44 @@
 //! 3. Create snapshot (per SNAPSHOT.md)
 //! 4. fsync snapshot (handled by snapshot module)
 //! 5. Write checkpoint manifest (checkpoint.json)
+//!    (then archive WAL via the configured `WalArchiver`)
 //! 6. Truncate WAL to zero
 //! 7. fsync WAL directory
 //! 8. Release global execution lock (caller responsibility)
 //!
 //! Any failure aborts checkpoint.
 //!
-//! Checkpoint is NOT recovery. This code does NOT rebuild indexes.
+//! Checkpoint is NOT recovery. This code does NOT rebuild indexes; it may
+//! capture an index checkpoint into the snapshot before the manifest is
+//! written. A re-encrypting checkpoint rewrites storage under a fresh data
+//! key before step 3.
 
+use std::fs;
 use std::path::Path;
 
 use chrono::Utc;
 
+use super::archive::WalArchiver;
 use super::errors::{CheckpointError, CheckpointResult};
 use super::marker::{marker_path, CheckpointMarker};
 use super::CheckpointId;
-use crate::snapshot::{GlobalExecutionLock, SnapshotManager};
+use crate::index::{IndexCheckpoint, IndexManager, StorageSeek, INDEX_CHECKPOINT_FILE};
+use crate::snapshot::{snapshot_path, GlobalExecutionLock, SnapshotManager};
+use crate::storage::{MasterKeyring, StorageWriter};
 use crate::wal::WalWriter;
 
+/// Index state to capture into an index checkpoint alongside the snapshot
+pub struct IndexCapture<'a> {
+    /// Live indexes, consistent with storage
+    pub index: &'a IndexManager,
+    /// Collection the manager's own entries belong to
+    pub collection: &'a str,
+    /// Storage the indexes point into
+    pub storage: &'a mut dyn StorageSeek,
+    /// Whether a collection is encrypted (its values are not persisted)
+    pub sealed: &'a dyn Fn(&str) -> bool,
+}
+
 /// Generate timestamp in RFC3339 format for created_at field
 fn generate_created_at() -> String {
     Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string()
@@ -40,6 +60,7 @@ fn generate_created_at() -> String {
 /// * `storage_path` - Path to storage.dat file
 /// * `schema_dir` - Path to schema directory
 /// * `wal` - Mutable reference to WAL writer
+/// * `archiver` - Hook invoked with the WAL before truncation
 ///
 /// # Returns
 ///
@@ -50,6 +71,7 @@ fn generate_created_at() -> String {
 /// Returns `CheckpointError` on any failure. Failure scenarios:
 /// - Snapshot creation fails → WAL intact
 /// - Marker write fails → snapshot exists, WAL intact
+/// - WAL archiving fails → snapshot exists, marker exists, WAL intact
 /// - WAL truncation fails → snapshot exists, marker exists, WAL intact
 ///
 /// # Crash Safety
@@ -65,6 +87,107 @@ pub fn create_checkpoint_impl(
     storage_path: &Path,
     schema_dir: &Path,
     wal: &mut WalWriter,
+    archiver: &dyn WalArchiver,
+    lock: &GlobalExecutionLock,
+) -> CheckpointResult<CheckpointId> {
+    run_checkpoint(
+        data_dir,
+        storage_path,
+        schema_dir,
+        wal,
+        archiver,
+        None,
+        lock,
+    )
+}
+
+/// Create a checkpoint that also writes an index checkpoint.
+///
+/// The index checkpoint is captured under the same lock after the snapshot
+/// is created and written into the snapshot directory before the manifest,
+/// so a manifest never names a snapshot whose index checkpoint is partly
+/// written. Capture failure aborts the checkpoint with the WAL intact.
+pub fn create_checkpoint_with_indexes_impl(
+    data_dir: &Path,
+    storage_path: &Path,
+    schema_dir: &Path,
+    wal: &mut WalWriter,
+    archiver: &dyn WalArchiver,
+    indexes: IndexCapture<'_>,
+    lock: &GlobalExecutionLock,
+) -> CheckpointResult<CheckpointId> {
+    run_checkpoint(
+        data_dir,
+        storage_path,
+        schema_dir,
+        wal,
+        archiver,
+        Some(indexes),
+        lock,
+    )
+}
+
+/// Create a checkpoint that first rewrites storage under a fresh data key.
+///
+/// The index checkpoint of the latest snapshot is removed before storage
+/// is rewritten, since its offsets point into the old file; startup then
+/// rebuilds indexes from storage. The new snapshot copies the rewritten
+/// file, and truncation starts the WAL under a fresh data key if it was
+/// opened with master keys.
+///
+/// Failure before the rewrite leaves storage as it was; a crash after it
+/// leaves the rewritten storage and an intact WAL, which startup replays.
+pub fn create_reencrypting_checkpoint_impl(
+    data_dir: &Path,
+    storage_path: &Path,
+    schema_dir: &Path,
+    wal: &mut WalWriter,
+    keys: &MasterKeyring,
+    archiver: &dyn WalArchiver,
+    lock: &GlobalExecutionLock,
+) -> CheckpointResult<CheckpointId> {
+    if !keys.is_enabled() {
+        return Err(CheckpointError::failed(
+            "No active master key to re-encrypt storage under",
+        ));
+    }
+    wal.fsync()?;
+
+    let marker_file = marker_path(data_dir);
+    if marker_file.exists() {
+        let marker = CheckpointMarker::read_from_file(&marker_file)?;
+        let stale = snapshot_path(data_dir, &marker.snapshot_id).join(INDEX_CHECKPOINT_FILE);
+        if stale.exists() {
+            fs::remove_file(&stale).map_err(|e| {
+                CheckpointError::failed_with_source(
+                    format!("Failed to remove index checkpoint {}", stale.display()),
+                    e,
+                )
+            })?;
+        }
+    }
+
+    StorageWriter::reencrypt(storage_path, keys)
+        .map_err(|e| CheckpointError::failed(format!("Failed to re-encrypt storage: {}", e)))?;
+
+    run_checkpoint(
+        data_dir,
+        storage_path,
+        schema_dir,
+        wal,
+        archiver,
+        None,
+        lock,
+    )
+}
+
+fn run_checkpoint(
+    data_dir: &Path,
+    storage_path: &Path,
+    schema_dir: &Path,
+    wal: &mut WalWriter,
+    archiver: &dyn WalArchiver,
+    indexes: Option<IndexCapture<'_>>,
     lock: &GlobalExecutionLock,
 ) -> CheckpointResult<CheckpointId> {
     // Step 2: fsync WAL to ensure all pending writes are durable
@@ -74,6 +197,11 @@ pub fn create_checkpoint_impl(
     let snapshot_id =
         SnapshotManager::create_snapshot(data_dir, storage_path, schema_dir, wal, lock)?;
 
+    // Step 4a: Capture the index checkpoint into the snapshot
+    if let Some(indexes) = indexes {
+        write_index_checkpoint(data_dir, &snapshot_id, indexes)?;
+    }
+
     // Checkpoint ID equals snapshot ID
     let checkpoint_id = snapshot_id.clone();
     let created_at = generate_created_at();
@@ -84,6 +212,10 @@ pub fn create_checkpoint_impl(
     let mp = marker_path(data_dir);
     marker.write_to_file(&mp)?;
 
+    // Step 5a: Archive WAL before it is truncated
+    // Failure aborts the checkpoint with the WAL intact
+    archiver.archive(&checkpoint_id, wal.path())?;
+
     // Step 6: Truncate WAL to zero
     // Per CHECKPOINT.md §6:
     // - WAL file deleted or truncated
@@ -101,6 +233,26 @@ pub fn create_checkpoint_impl(
     Ok(checkpoint_id)
 }
 
+/// Capture and write the index checkpoint of `snapshot_id`
+fn write_index_checkpoint(
+    data_dir: &Path,
+    snapshot_id: &str,
+    indexes: IndexCapture<'_>,
+) -> CheckpointResult<()> {
+    let checkpoint = IndexCheckpoint::capture(
+        indexes.index,
+        indexes.collection,
+        snapshot_id,
+        indexes.storage,
+        indexes.sealed,
+    )
+    .and_then(|checkpoint| {
+        checkpoint.write_to(&snapshot_path(data_dir, snapshot_id).join(INDEX_CHECKPOINT_FILE))
+    });
+    checkpoint
+        .map_err(|e| CheckpointError::failed(format!("Failed to write index checkpoint: {}", e)))
+}
+
 /// Create an MVCC-aware checkpoint.
 ///
 /// Per MVCC_SNAPSHOT_INTEGRATION.md §5:
@@ -113,6 +265,7 @@ pub fn create_mvcc_checkpoint_impl(
     schema_dir: &Path,
     wal: &mut WalWriter,
     commit_authority: &crate::mvcc::CommitAuthority,
+    archiver: &dyn WalArchiver,
     lock: &GlobalExecutionLock,
 ) -> CheckpointResult<CheckpointId> {
     // Step 2: fsync WAL to ensure all pending writes are durable
@@ -138,6 +291,10 @@ pub fn create_mvcc_checkpoint_impl(
     let mp = marker_path(data_dir);
     marker.write_to_file(&mp)?;
 
+    // Step 5a: Archive WAL before it is truncated
+    // Failure aborts the checkpoint with the WAL intact
+    archiver.archive(&checkpoint_id, wal.path())?;
+
     // Step 6: Truncate WAL to zero
     // Per CHECKPOINT.md §6:
     // - WAL file deleted or truncated
@@ -158,6 +315,7 @@ pub fn create_mvcc_checkpoint_impl(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::checkpoint::archive::{FileWalArchiver, NoopWalArchiver};
     use crate::wal::{RecordType, WalPayload, WalReader};
     use std::fs::{self, File};
     use std::io::Write;
@@ -206,8 +364,15 @@ mod tests {
         let data_dir = temp_dir.path();
         let lock = GlobalExecutionLock::new();
 
-        let checkpoint_id =
-            create_checkpoint_impl(data_dir, &storage_path, &schema_dir, &mut wal, &lock).unwrap();
+        let checkpoint_id = create_checkpoint_impl(
+            data_dir,
+            &storage_path,
+            &schema_dir,
+            &mut wal,
+            &NoopWalArchiver,
+            &lock,
+        )
+        .unwrap();
 
         // Verify snapshot exists
         let snapshot_dir = data_dir.join("snapshots").join(&checkpoint_id);
@@ -222,8 +387,15 @@ mod tests {
         let data_dir = temp_dir.path();
         let lock = GlobalExecutionLock::new();
 
-        let checkpoint_id =
-            create_checkpoint_impl(data_dir, &storage_path, &schema_dir, &mut wal, &lock).unwrap();
+        let checkpoint_id = create_checkpoint_impl(
+            data_dir,
+            &storage_path,
+            &schema_dir,
+            &mut wal,
+            &NoopWalArchiver,
+            &lock,
+        )
+        .unwrap();
 
         // Verify marker exists
         let mp = marker_path(data_dir);
@@ -250,7 +422,15 @@ mod tests {
         assert_eq!(wal.next_sequence_number(), 3);
 
         // Create checkpoint
-        create_checkpoint_impl(data_dir, &storage_path, &schema_dir, &mut wal, &lock).unwrap();
+        create_checkpoint_impl(
+            data_dir,
+            &storage_path,
+            &schema_dir,
+            &mut wal,
+            &NoopWalArchiver,
+            &lock,
+        )
+        .unwrap();
 
         // Verify WAL is truncated (sequence reset to 1)
         assert_eq!(wal.next_sequence_number(), 1);
@@ -267,8 +447,15 @@ mod tests {
         let data_dir = temp_dir.path();
         let lock = GlobalExecutionLock::new();
 
-        let checkpoint_id =
-            create_checkpoint_impl(data_dir, &storage_path, &schema_dir, &mut wal, &lock).unwrap();
+        let checkpoint_id = create_checkpoint_impl(
+            data_dir,
+            &storage_path,
+            &schema_dir,
+            &mut wal,
+            &NoopWalArchiver,
+            &lock,
+        )
+        .unwrap();
 
         // Verify checkpoint_id format matches snapshot format
         assert_eq!(checkpoint_id.len(), 16); // YYYYMMDDTHHMMSSZ
@@ -292,7 +479,15 @@ mod tests {
             .unwrap();
 
         // Checkpoint
-        create_checkpoint_impl(data_dir, &storage_path, &schema_dir, &mut wal, &lock).unwrap();
+        create_checkpoint_impl(
+            data_dir,
+            &storage_path,
+            &schema_dir,
+            &mut wal,
+            &NoopWalArchiver,
+            &lock,
+        )
+        .unwrap();
 
         // New writes should work, starting at sequence 1
         let seq = wal
@@ -313,7 +508,14 @@ mod tests {
         let schema_dir = data_dir.join("schemas");
         fs::create_dir_all(&schema_dir).unwrap();
 
-        let result = create_checkpoint_impl(data_dir, &storage_path, &schema_dir, &mut wal, &lock);
+        let result = create_checkpoint_impl(
+            data_dir,
+            &storage_path,
+            &schema_dir,
+            &mut wal,
+            &NoopWalArchiver,
+            &lock,
+        );
 
         // Should fail
         assert!(result.is_err());
@@ -332,7 +534,15 @@ mod tests {
         let data_dir = temp_dir.path();
         let lock = GlobalExecutionLock::new();
 
-        create_checkpoint_impl(data_dir, &storage_path, &schema_dir, &mut wal, &lock).unwrap();
+        create_checkpoint_impl(
+            data_dir,
+            &storage_path,
+            &schema_dir,
+            &mut wal,
+            &NoopWalArchiver,
+            &lock,
+        )
+        .unwrap();
 
         let mp = marker_path(data_dir);
         let marker = CheckpointMarker::read_from_file(&mp).unwrap();
@@ -350,8 +560,15 @@ mod tests {
         let lock = GlobalExecutionLock::new();
 
         // First checkpoint
-        let cp1 =
-            create_checkpoint_impl(data_dir, &storage_path, &schema_dir, &mut wal, &lock).unwrap();
+        let cp1 = create_checkpoint_impl(
+            data_dir,
+            &storage_path,
+            &schema_dir,
+            &mut wal,
+            &NoopWalArchiver,
+            &lock,
+        )
+        .unwrap();
 
         // Write more data
         wal.append(RecordType::Insert, create_test_payload("doc_after_cp1"))
@@ -361,8 +578,15 @@ mod tests {
         std::thread::sleep(std::time::Duration::from_millis(1100));
 
         // Second checkpoint
-        let cp2 =
-            create_checkpoint_impl(data_dir, &storage_path, &schema_dir, &mut wal, &lock).unwrap();
+        let cp2 = create_checkpoint_impl(
+            data_dir,
+            &storage_path,
+            &schema_dir,
+            &mut wal,
+            &NoopWalArchiver,
+            &lock,
+        )
+        .unwrap();
 
         // Both snapshots should exist
         assert!(data_dir.join("snapshots").join(&cp1).exists());
@@ -373,4 +597,106 @@ mod tests {
         let marker = CheckpointMarker::read_from_file(&mp).unwrap();
         assert_eq!(marker.snapshot_id, cp2);
     }
+
+    #[test]
+    fn test_checkpoint_archives_wal_before_truncation() {
+        let (temp_dir, storage_path, schema_dir, mut wal) = setup_test_environment();
+        let data_dir = temp_dir.path();
+        let lock = GlobalExecutionLock::new();
+
+        wal.append(RecordType::Insert, create_test_payload("archived_doc"))
+            .unwrap();
+
+        let archiver = FileWalArchiver::in_data_dir(data_dir);
+        let checkpoint_id = create_checkpoint_impl(
+            data_dir,
+            &storage_path,
+            &schema_dir,
+            &mut wal,
+            &archiver,
+            &lock,
+        )
+        .unwrap();
+
+        // Archived segment holds the pre-truncation records
+        let mut reader = WalReader::open(&archiver.archive_path(&checkpoint_id)).unwrap();
+        let record = reader.read_next().unwrap().unwrap();
+        assert_eq!(record.payload.document_id, "archived_doc");
+
+        // Live WAL is truncated
+        assert_eq!(wal.next_sequence_number(), 1);
+    }
+
+    #[test]
+    fn test_back_to_back_checkpoints_archive_both_segments() {
+        let (temp_dir, storage_path, schema_dir, mut wal) = setup_test_environment();
+        let data_dir = temp_dir.path();
+        let lock = GlobalExecutionLock::new();
+        let archiver = FileWalArchiver::in_data_dir(data_dir);
+
+        // Checkpoint ids have one-second resolution: these usually share one
+        for doc_id in ["first_doc", "second_doc"] {
+            wal.append(RecordType::Insert, create_test_payload(doc_id))
+                .unwrap();
+            create_checkpoint_impl(
+                data_dir,
+                &storage_path,
+                &schema_dir,
+                &mut wal,
+                &archiver,
+                &lock,
+            )
+            .unwrap();
+        }
+
+        let mut segments: Vec<_> = fs::read_dir(archiver.archive_dir())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        segments.sort();
+        let archived: Vec<_> = segments
+            .iter()
+            .map(|path| {
+                let mut reader = WalReader::open(path).unwrap();
+                reader.read_next().unwrap().unwrap().payload.document_id
+            })
+            .collect();
+        assert_eq!(archived, ["first_doc", "second_doc"]);
+    }
+
+    #[test]
+    fn test_archive_failure_leaves_wal_intact() {
+        struct FailingArchiver;
+
+        impl WalArchiver for FailingArchiver {
+            fn archive(&self, _checkpoint_id: &str, _wal_path: &Path) -> CheckpointResult<()> {
+                Err(CheckpointError::wal_archive_failed(
+                    "archive unavailable",
+                    std::io::Error::new(std::io::ErrorKind::Other, "offline"),
+                ))
+            }
+        }
+
+        let (temp_dir, storage_path, schema_dir, mut wal) = setup_test_environment();
+        let data_dir = temp_dir.path();
+        let lock = GlobalExecutionLock::new();
+
+        wal.append(RecordType::Insert, create_test_payload("kept_doc"))
+            .unwrap();
+
+        let result = create_checkpoint_impl(
+            data_dir,
+            &storage_path,
+            &schema_dir,
+            &mut wal,
+            &FailingArchiver,
+            &lock,
+        );
+        assert!(result.is_err());
+
+        // WAL not truncated, marker records it
+        assert_eq!(wal.next_sequence_number(), 2);
+        let marker = CheckpointMarker::read_from_file(&marker_path(data_dir)).unwrap();
+        assert!(!marker.wal_truncated);
+    }
 }
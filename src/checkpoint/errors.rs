@@ -35,6 +35,8 @@ pub enum CheckpointErrorCode {
     AeroCheckpointMarkerFailed,
     /// WAL truncation failure
     AeroCheckpointWalTruncateFailed,
+    /// WAL archiving failure (before truncation)
+    AeroCheckpointWalArchiveFailed,
 }
 
 impl CheckpointErrorCode {
@@ -46,6 +48,9 @@ impl CheckpointErrorCode {
             CheckpointErrorCode::AeroCheckpointWalTruncateFailed => {
                 "AERO_CHECKPOINT_WAL_TRUNCATE_FAILED"
             }
+            CheckpointErrorCode::AeroCheckpointWalArchiveFailed => {
+                "AERO_CHECKPOINT_WAL_ARCHIVE_FAILED"
+            }
         }
     }
 
@@ -128,6 +133,15 @@ impl CheckpointError {
         )
     }
 
+    /// Creates a WAL archive failure error
+    pub fn wal_archive_failed(message: impl Into<String>, source: io::Error) -> Self {
+        Self::new(
+            CheckpointErrorCode::AeroCheckpointWalArchiveFailed,
+            message,
+            Some(source),
+        )
+    }
+
     /// Returns the error code
     pub fn code(&self) -> CheckpointErrorCode {
         self.code
@@ -209,6 +223,10 @@ mod tests {
             CheckpointErrorCode::AeroCheckpointWalTruncateFailed.as_str(),
             "AERO_CHECKPOINT_WAL_TRUNCATE_FAILED"
         );
+        assert_eq!(
+            CheckpointErrorCode::AeroCheckpointWalArchiveFailed.as_str(),
+            "AERO_CHECKPOINT_WAL_ARCHIVE_FAILED"
+        );
     }
 
     #[test]
@@ -217,6 +235,7 @@ mod tests {
             CheckpointErrorCode::AeroCheckpointFailed,
             CheckpointErrorCode::AeroCheckpointMarkerFailed,
             CheckpointErrorCode::AeroCheckpointWalTruncateFailed,
+            CheckpointErrorCode::AeroCheckpointWalArchiveFailed,
         ];
 
         for code in codes {
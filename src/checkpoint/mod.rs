@@ -22,6 +22,7 @@
 //! 3. Create snapshot (per SNAPSHOT.md)
 //! 4. fsync snapshot
 //! 5. Write checkpoint manifest (checkpoint.json)
+//!    (then archive WAL via the configured `WalArchiver`, no-op by default)
 //! 6. Truncate WAL to zero
 //! 7. fsync WAL directory
 //! 8. Release global execution lock
@@ -44,12 +45,30 @@
 //! # Phase 3 Optimizations
 //!
 //! - Pipelining: Overlap Phase A (prep) work with normal operation (optional, disabled by default)
+//!
+//! # Index Checkpoints
+//!
+//! `create_checkpoint_with_indexes` also captures the live indexes into
+//! `snapshots/<snapshot_id>/indexes.ckpt`, which startup restores instead of
+//! rebuilding indexes from all of storage.
+//!
+//! # Key Rotation
+//!
+//! `create_reencrypting_checkpoint` rewrites storage under a fresh data key
+//! wrapped by the active master key before the snapshot, and truncation
+//! starts the WAL under a fresh data key. It is the one checkpoint that
+//! modifies storage.
 
+mod archive;
 mod coordinator;
 mod errors;
 mod marker;
 mod pipeline;
 
+pub use archive::{
+    segment_checkpoint_id, FileWalArchiver, NoopWalArchiver, WalArchiver, WAL_ARCHIVE_DIR,
+};
+pub use coordinator::IndexCapture;
 pub use errors::{CheckpointError, CheckpointErrorCode, CheckpointResult, Severity};
 pub use marker::{marker_path, CheckpointMarker};
 pub use pipeline::{
@@ -60,6 +79,7 @@ pub use pipeline::{
 use std::path::Path;
 
 use crate::snapshot::{GlobalExecutionLock, SnapshotManager};
+use crate::storage::MasterKeyring;
 use crate::wal::WalWriter;
 
 /// Checkpoint ID type (equals SnapshotId per spec)
@@ -134,7 +154,88 @@ impl CheckpointManager {
         wal: &mut WalWriter,
         lock: &GlobalExecutionLock,
     ) -> Result<CheckpointId, CheckpointError> {
-        coordinator::create_checkpoint_impl(data_dir, storage_path, schema_dir, wal, lock)
+        coordinator::create_checkpoint_impl(
+            data_dir,
+            storage_path,
+            schema_dir,
+            wal,
+            &NoopWalArchiver,
+            lock,
+        )
+    }
+
+    /// Create a checkpoint, archiving the WAL before truncation.
+    ///
+    /// Identical to `create_checkpoint`, except `archiver` is invoked after
+    /// the marker is written and before the WAL is truncated. Archive
+    /// failure aborts the checkpoint with the WAL intact.
+    pub fn create_checkpoint_with_archiver(
+        data_dir: &Path,
+        storage_path: &Path,
+        schema_dir: &Path,
+        _snapshot_mgr: &SnapshotManager,
+        wal: &mut WalWriter,
+        archiver: &dyn WalArchiver,
+        lock: &GlobalExecutionLock,
+    ) -> Result<CheckpointId, CheckpointError> {
+        coordinator::create_checkpoint_impl(data_dir, storage_path, schema_dir, wal, archiver, lock)
+    }
+
+    /// Create a checkpoint that also writes an index checkpoint.
+    ///
+    /// Identical to `create_checkpoint`, except `indexes` is captured into
+    /// the snapshot directory before the manifest is written. Startup
+    /// restores it instead of rebuilding indexes from all of storage.
+    pub fn create_checkpoint_with_indexes(
+        data_dir: &Path,
+        storage_path: &Path,
+        schema_dir: &Path,
+        _snapshot_mgr: &SnapshotManager,
+        wal: &mut WalWriter,
+        indexes: IndexCapture<'_>,
+        lock: &GlobalExecutionLock,
+    ) -> Result<CheckpointId, CheckpointError> {
+        coordinator::create_checkpoint_with_indexes_impl(
+            data_dir,
+            storage_path,
+            schema_dir,
+            wal,
+            &NoopWalArchiver,
+            indexes,
+            lock,
+        )
+    }
+
+    /// Create a checkpoint that re-encrypts storage under a fresh data key.
+    ///
+    /// Identical to `create_checkpoint`, except storage is first rewritten
+    /// under a data key wrapped by the active master key of `keys` (rotate
+    /// the master key beforehand to retire the old one). Record offsets
+    /// change: storage writers and readers must be reopened and indexes
+    /// rebuilt afterwards, and the previous index checkpoint is discarded.
+    ///
+    /// # Errors
+    ///
+    /// `AERO_CHECKPOINT_FAILED` if no master key is active or storage cannot
+    /// be rewritten; storage and the WAL are then left as they were.
+    pub fn create_reencrypting_checkpoint(
+        data_dir: &Path,
+        storage_path: &Path,
+        schema_dir: &Path,
+        _snapshot_mgr: &SnapshotManager,
+        wal: &mut WalWriter,
+        keys: &MasterKeyring,
+        lock: &GlobalExecutionLock,
+    ) -> Result<CheckpointId, CheckpointError> {
+        coordinator::create_reencrypting_checkpoint_impl(
+            data_dir,
+            storage_path,
+            schema_dir,
+            wal,
+            keys,
+            &NoopWalArchiver,
+            lock,
+        )
     }
 
     /// Create an MVCC-aware checkpoint with commit boundary.
@@ -168,6 +269,30 @@ impl CheckpointManager {
             schema_dir,
             wal,
             commit_authority,
+            &NoopWalArchiver,
+            lock,
+        )
+    }
+
+    /// Create an MVCC-aware checkpoint, archiving the WAL before truncation.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_mvcc_checkpoint_with_archiver(
+        data_dir: &Path,
+        storage_path: &Path,
+        schema_dir: &Path,
+        _snapshot_mgr: &SnapshotManager,
+        wal: &mut WalWriter,
+        commit_authority: &crate::mvcc::CommitAuthority,
+        archiver: &dyn WalArchiver,
+        lock: &GlobalExecutionLock,
+    ) -> Result<CheckpointId, CheckpointError> {
+        coordinator::create_mvcc_checkpoint_impl(
+            data_dir,
+            storage_path,
+            schema_dir,
+            wal,
+            commit_authority,
+            archiver,
             lock,
         )
     }
@@ -2,9 +2,17 @@
 //!
 //! Commands:
 //! - aerodb init --config <path>
-//! - aerodb start --config <path>
+//! - aerodb start --config <path> [--daemon] [--checkpoint-on-shutdown]
+//! - aerodb shell --config <path>
 //! - aerodb query --config <path>
 //! - aerodb explain --config <path>
+//! - aerodb sandbox --config <path> [--scratch-dir <path>]
+//! - aerodb selftest [--scratch-dir <path>] [--keep]
+//! - aerodb doctor --config <path>
+//! - aerodb config validate --config <path>
+//! - aerodb config dump --config <path>
+//! - aerodb wal dump --config <path> [--collection <name>] [--from-seq <n>] [--to-seq <n>]
+//! - aerodb storage dump --config <path> [--collection <name>]
 //!
 //! # Phase 7 Control Plane Commands
 //!
@@ -12,6 +20,11 @@
 //! - aerodb control inspect <cluster|node|replication|promotion>
 //! - aerodb control diag <diagnostics|wal|snapshots>
 //! - aerodb control <promote|demote|force-promote>
+//!
+//! Every command accepts `--format <json|ndjson|table>`.
+//! - aerodb control maintenance <enter|exit|exit-read-only>
+//! - aerodb control reload-observability --node-id <uuid>
+//! - aerodb control switchover --from <uuid> --to <uuid>
 
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
@@ -21,6 +34,11 @@ use std::path::PathBuf;
 #[command(name = "aerodb")]
 #[command(version, about, long_about = None)]
 pub struct Cli {
+    /// Output format: json, ndjson or table. Record dumps always print
+    /// JSON lines.
+    #[arg(long, global = true, default_value = "json")]
+    pub format: String,
+
     #[command(subcommand)]
     pub command: Command,
 }
@@ -39,6 +57,33 @@ pub enum Command {
         /// Path to configuration file
         #[arg(long, default_value = "./aerodb.json")]
         config: PathBuf,
+
+        /// Storage verification after recovery, overriding the config:
+        /// none, manifest-only, sampled or full
+        #[arg(long)]
+        verify: Option<String>,
+
+        /// Run in the background, detached from the terminal
+        #[arg(long)]
+        daemon: bool,
+
+        /// Checkpoint before exiting on shutdown, overriding the config
+        #[arg(long)]
+        checkpoint_on_shutdown: bool,
+
+        /// Internal: this process is the detached child of --daemon
+        #[arg(long, hide = true)]
+        detached: bool,
+    },
+
+    /// Start an interactive shell over the data directory
+    ///
+    /// Boots once, then evaluates JSON requests, `<op> [json]` shorthand
+    /// and dot commands (`.help`, `.use`, `.quit`) one line at a time.
+    Shell {
+        /// Path to configuration file
+        #[arg(long, default_value = "./aerodb.json")]
+        config: PathBuf,
     },
 
     /// Execute a single query and exit
@@ -55,6 +100,72 @@ pub enum Command {
         config: PathBuf,
     },
 
+    /// Index a field, backfilling existing documents, and exit
+    CreateIndex {
+        /// Path to configuration file
+        #[arg(long, default_value = "./aerodb.json")]
+        config: PathBuf,
+
+        /// Field to index
+        #[arg(long)]
+        field: String,
+    },
+
+    /// Start a disposable schema sandbox seeded from the latest snapshot
+    ///
+    /// Reads JSON requests from stdin like `start`, but all writes go to a
+    /// scratch directory that is removed on exit. The data directory is
+    /// never written.
+    Sandbox {
+        /// Path to configuration file
+        #[arg(long, default_value = "./aerodb.json")]
+        config: PathBuf,
+
+        /// Directory to create the scratch sandbox under (default: system temp)
+        #[arg(long)]
+        scratch_dir: Option<PathBuf>,
+    },
+
+    /// Run the end-to-end acceptance self-test against a scratch directory
+    Selftest {
+        /// Directory to create the scratch data directory under (default: system temp)
+        #[arg(long)]
+        scratch_dir: Option<PathBuf>,
+
+        /// Keep the scratch data directory after the run
+        #[arg(long)]
+        keep: bool,
+    },
+
+    /// Inspect the data directory without starting the server
+    ///
+    /// Checks the WAL, schemas, snapshots, checkpoint marker and document
+    /// storage, and writes a JSON health report. Nothing is written to the
+    /// data directory.
+    Doctor {
+        /// Path to configuration file
+        #[arg(long, default_value = "./aerodb.json")]
+        config: PathBuf,
+    },
+
+    /// Validate or print the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Inspect WAL records
+    Wal {
+        #[command(subcommand)]
+        action: WalAction,
+    },
+
+    /// Inspect storage records
+    Storage {
+        #[command(subcommand)]
+        action: StorageAction,
+    },
+
     /// Start HTTP server for dashboard (Phase 13.5)
     ///
     /// Starts an HTTP server exposing REST API for the dashboard.
@@ -64,9 +175,9 @@ pub enum Command {
         #[arg(long, default_value = "./aerodb.json")]
         config: PathBuf,
 
-        /// Port to bind to (default: 54321)
-        #[arg(long, default_value = "54321")]
-        port: u16,
+        /// Port to bind to, overriding the config (default: 54321)
+        #[arg(long)]
+        port: Option<u16>,
     },
 
     /// Control plane commands (Phase 7)
@@ -83,6 +194,62 @@ pub enum Command {
     },
 }
 
+/// Configuration actions.
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Check a config file without starting
+    Validate {
+        /// Path to configuration file
+        #[arg(long, default_value = "./aerodb.json")]
+        config: PathBuf,
+    },
+
+    /// Print the effective configuration (defaults + file + env) as JSON
+    Dump {
+        /// Path to configuration file
+        #[arg(long, default_value = "./aerodb.json")]
+        config: PathBuf,
+    },
+}
+
+/// WAL inspection actions.
+#[derive(Subcommand, Debug)]
+pub enum WalAction {
+    /// Print WAL records as JSON lines
+    Dump {
+        /// Path to configuration file
+        #[arg(long, default_value = "./aerodb.json")]
+        config: PathBuf,
+
+        /// Only records of this collection
+        #[arg(long)]
+        collection: Option<String>,
+
+        /// Only records with a sequence number at or above this
+        #[arg(long)]
+        from_seq: Option<u64>,
+
+        /// Only records with a sequence number at or below this
+        #[arg(long)]
+        to_seq: Option<u64>,
+    },
+}
+
+/// Storage inspection actions.
+#[derive(Subcommand, Debug)]
+pub enum StorageAction {
+    /// Print storage records as JSON lines
+    Dump {
+        /// Path to configuration file
+        #[arg(long, default_value = "./aerodb.json")]
+        config: PathBuf,
+
+        /// Only records of this collection
+        #[arg(long)]
+        collection: Option<String>,
+    },
+}
+
 /// Control plane actions.
 ///
 /// Per PHASE7_COMMAND_MODEL.md:
@@ -158,6 +325,259 @@ pub enum ControlAction {
         #[arg(long)]
         confirm: Option<String>,
     },
+
+    /// Enter or exit maintenance mode, or exit read-only mode
+    ///
+    /// Requires confirmation.
+    Maintenance {
+        #[command(subcommand)]
+        action: MaintenanceAction,
+    },
+
+    /// Re-read the log level and metrics settings of the running server
+    ///
+    /// Requires confirmation. Equivalent to sending the server SIGHUP.
+    ReloadObservability {
+        /// Node UUID to reload
+        #[arg(long)]
+        node_id: String,
+
+        /// Reason for the reload (for audit)
+        #[arg(long)]
+        reason: Option<String>,
+
+        /// Confirmation token (from previous request)
+        #[arg(long)]
+        confirm: Option<String>,
+    },
+
+    /// Planned switchover from the current primary to a caught-up replica
+    ///
+    /// Requires confirmation. Blocks writes, drains WAL shipping, verifies
+    /// the replica has caught up, transfers authority, then unblocks
+    /// writes; any failed step aborts and unblocks writes.
+    Switchover {
+        /// Primary UUID giving up authority
+        #[arg(long)]
+        from: String,
+
+        /// Replica UUID gaining authority
+        #[arg(long)]
+        to: String,
+
+        /// Maximum time to wait for writes and WAL shipping (milliseconds)
+        #[arg(long, default_value = "30000")]
+        drain_timeout_ms: u64,
+
+        /// Reason for the switchover (for audit)
+        #[arg(long)]
+        reason: Option<String>,
+
+        /// Confirmation token (from previous request)
+        #[arg(long)]
+        confirm: Option<String>,
+    },
+
+    /// Take a checkpoint: snapshot the data and truncate the WAL
+    ///
+    /// Requires confirmation.
+    Checkpoint {
+        /// Node UUID to checkpoint
+        #[arg(long)]
+        node_id: String,
+
+        /// Reason for the checkpoint (for audit)
+        #[arg(long)]
+        reason: Option<String>,
+
+        /// Confirmation token (from previous request)
+        #[arg(long)]
+        confirm: Option<String>,
+    },
+
+    /// Create, list, or verify backup archives
+    Backup {
+        #[command(subcommand)]
+        action: BackupAction,
+    },
+
+    /// Issue, revoke, or list API keys for the HTTP control routes
+    Keys {
+        #[command(subcommand)]
+        action: KeysAction,
+    },
+
+    /// Set, read, list, or delete secrets bound into functions
+    Secrets {
+        #[command(subcommand)]
+        action: SecretsAction,
+    },
+}
+
+/// API key actions.
+#[derive(Subcommand, Debug)]
+pub enum KeysAction {
+    /// Issue a key; the key is printed once and cannot be recovered
+    Issue {
+        /// Key holder, recorded as the operator in audit records
+        #[arg(long)]
+        name: String,
+
+        /// Authority level: observer, operator, or auditor
+        #[arg(long)]
+        authority: String,
+
+        /// Days until the key expires
+        #[arg(long, default_value = "90")]
+        ttl_days: i64,
+    },
+
+    /// Revoke a key; requests presenting it are rejected from then on
+    Revoke {
+        /// Key ID (from issue or list)
+        #[arg(long)]
+        key_id: String,
+    },
+
+    /// List issued keys, revoked ones included
+    List,
+}
+
+/// Function secret actions.
+#[derive(Subcommand, Debug)]
+pub enum SecretsAction {
+    /// Store a new version of a secret, reading its value from stdin
+    Set {
+        /// Secret name ([A-Za-z0-9_-])
+        #[arg(long)]
+        name: String,
+    },
+
+    /// Print a secret's value
+    Get {
+        /// Secret name
+        #[arg(long)]
+        name: String,
+
+        /// Version to print (default: latest)
+        #[arg(long)]
+        version: Option<u32>,
+    },
+
+    /// List secrets and their versions; values are not shown
+    List,
+
+    /// Delete a secret and all its versions
+    Delete {
+        /// Secret name
+        #[arg(long)]
+        name: String,
+    },
+
+    /// Seal new versions under a fresh key; older versions stay readable
+    RotateKey,
+}
+
+/// Backup actions.
+#[derive(Subcommand, Debug)]
+pub enum BackupAction {
+    /// Write a backup archive of the latest snapshot and WAL tail
+    ///
+    /// Requires confirmation.
+    Create {
+        /// Node UUID to back up
+        #[arg(long)]
+        node_id: String,
+
+        /// Path of the archive to write
+        #[arg(long)]
+        output: PathBuf,
+
+        /// Reason for the backup (for audit)
+        #[arg(long)]
+        reason: Option<String>,
+
+        /// Confirmation token (from previous request)
+        #[arg(long)]
+        confirm: Option<String>,
+    },
+
+    /// List backup archives in a directory (read-only)
+    List {
+        /// Directory containing backup archives
+        #[arg(long)]
+        directory: PathBuf,
+    },
+
+    /// Verify a backup archive without restoring it
+    ///
+    /// Requires confirmation due to cost.
+    Verify {
+        /// Path of the archive to verify
+        #[arg(long)]
+        path: PathBuf,
+
+        /// Confirmation token (from previous request)
+        #[arg(long)]
+        confirm: Option<String>,
+    },
+}
+
+/// Maintenance mode actions.
+#[derive(Subcommand, Debug)]
+pub enum MaintenanceAction {
+    /// Reject new writes and drain in-flight work
+    Enter {
+        /// Node UUID to place in maintenance
+        #[arg(long)]
+        node_id: String,
+
+        /// Continue serving reads during maintenance
+        #[arg(long)]
+        allow_reads: bool,
+
+        /// Maximum time to wait for in-flight work (milliseconds)
+        #[arg(long, default_value = "30000")]
+        drain_timeout_ms: u64,
+
+        /// Reason for maintenance (for audit)
+        #[arg(long)]
+        reason: Option<String>,
+
+        /// Confirmation token (from previous request)
+        #[arg(long)]
+        confirm: Option<String>,
+    },
+
+    /// Resume accepting writes
+    Exit {
+        /// Node UUID to take out of maintenance
+        #[arg(long)]
+        node_id: String,
+
+        /// Reason for exiting maintenance (for audit)
+        #[arg(long)]
+        reason: Option<String>,
+
+        /// Confirmation token (from previous request)
+        #[arg(long)]
+        confirm: Option<String>,
+    },
+
+    /// Resume accepting writes after freeing space on a full WAL device
+    ExitReadOnly {
+        /// Node UUID to take out of read-only mode
+        #[arg(long)]
+        node_id: String,
+
+        /// Reason for exiting read-only mode (for audit)
+        #[arg(long)]
+        reason: Option<String>,
+
+        /// Confirmation token (from previous request)
+        #[arg(long)]
+        confirm: Option<String>,
+    },
 }
 
 /// Inspection targets.
@@ -8,30 +8,71 @@
 //! Control plane commands are thin clients with no authority.
 //! Safety is enforced server-side.
 
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::fs;
+use std::io::IsTerminal;
 use std::path::Path;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
 
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use uuid::Uuid;
 
-use crate::api::{ApiHandler, Subsystems};
+use crate::api::{query_request, ApiHandler, PriorityClass, Response, Subsystems};
+use crate::auth::{RevocationStore, ServiceKeyManager};
+use crate::checkpoint::{CheckpointManager, IndexCapture};
+use crate::config::{
+    collect_overrides, AeroConfig, CheckpointSection, DxSection, HttpSection, IndexSection,
+    ObservabilitySection, PgWireSection, RecoverySection, ReplicationSection, StorageSection,
+    SubsystemConfigs, WalSection, DEFAULT_MAX_MEMORY_BYTES, DEFAULT_MAX_WAL_SIZE_BYTES, SECTIONS,
+};
+use crate::core::{AuthContext, DurableStorage};
 use crate::dx::api::control_plane::{
-    AuthorityContext, CommandRequest, ControlCommand, ControlPlaneCommand, ControlPlaneHandler,
-    DiagnosticCommand, InspectionCommand,
+    ApiKeyManager, ApiKeyRecord, AuthorityContext, AuthorityLevel, CommandRequest, ControlCommand,
+    ControlPlaneCommand, ControlPlaneHandler, DiagnosticCommand, InspectionCommand,
+    LiveKernelAdapter,
 };
+use crate::file_storage::UploadSessionStore;
+use crate::functions::{InvocationHistory, SecretStore};
 use crate::index::IndexManager;
-use crate::observability::{AuditAction, AuditLog, AuditOutcome, AuditRecord, MemoryAuditLog};
-use crate::recovery::RecoveryManager;
+use crate::observability::{
+    install_sinks, AuditLogConfig, Event, FileAuditLog, MetricsRegistry, Severity, SlowQueryLog,
+};
+use crate::pgwire::{BackendError, PgWireServer, QueryBackend};
+use crate::planner::Query;
+use crate::promotion::PromotionController;
+use crate::recovery::{
+    IndexStorage, RecoveryManager, VerificationLevel, WalReplayer, DEFAULT_SAMPLE_PERCENT,
+};
 use crate::replication::{ReplicationConfig, ReplicationRole, ReplicationState};
 use crate::schema::SchemaLoader;
-use crate::storage::{StorageReader, StorageWriter};
-use crate::wal::{WalReader, WalWriter};
+use crate::snapshot::{GlobalExecutionLock, SnapshotManager};
+use crate::storage::{
+    BlockCache, CollectionKeyring, FieldKeyring, KeyProvider, MasterKeyring, StorageFormat,
+    StorageReader, StorageWriter,
+};
+use crate::wal::{TailRecovery, WalReader, WalWriter};
 
-use super::args::{Command, ControlAction, DiagTarget, InspectTarget};
+use super::args::{
+    BackupAction, Command, ConfigAction, ControlAction, DiagTarget, InspectTarget, KeysAction,
+    MaintenanceAction, SecretsAction, StorageAction, WalAction,
+};
+use super::doctor::diagnose;
+use super::dump::{dump_storage, dump_wal, WalDumpFilter};
 use super::errors::{CliError, CliResult};
-use super::io::{read_request, read_requests, write_error, write_json, write_response};
+use super::io::{
+    read_request, read_requests, write_error, write_json, write_response, OutputFormat,
+};
+use super::lifecycle::{
+    daemonize, emit, send_reload, serve_inputs, ProcessLock, ServeInput, PID_FILE,
+};
+use super::selftest::SelfTest;
+use super::shell::Shell;
+
+/// Collection that requests naming no collection are served from
+pub(crate) const DEFAULT_COLLECTION: &str = "default";
 
 /// Configuration file structure per CONFIG.md
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,6 +92,39 @@ pub struct Config {
     #[serde(default = "default_wal_sync_mode")]
     pub wal_sync_mode: String,
 
+    /// Torn final WAL record policy: "strict" or "truncate" (default "strict")
+    #[serde(default = "default_wal_tail_recovery")]
+    pub wal_tail_recovery: String,
+
+    /// Storage file format for new data directories: 1 or 2 (default 1)
+    #[serde(default = "default_storage_format_version")]
+    pub storage_format_version: u16,
+
+    /// Records held in the storage block cache (optional, default 0 = off)
+    #[serde(default)]
+    pub storage_cache_entries: usize,
+
+    /// Worker threads for a full index rebuild at boot (optional, default 1)
+    #[serde(default = "default_index_rebuild_threads")]
+    pub index_rebuild_threads: usize,
+
+    /// Storage verification after recovery: "none", "manifest-only",
+    /// "sampled" or "full" (default "full")
+    #[serde(default = "default_recovery_verification")]
+    pub recovery_verification: String,
+
+    /// Percentage of records checked by "sampled" verification (default 10)
+    #[serde(default = "default_verification_sample_percent")]
+    pub verification_sample_percent: u8,
+
+    /// Seed choosing the records "sampled" verification checks (default 0)
+    #[serde(default)]
+    pub verification_seed: u64,
+
+    /// Checkpoint before exiting on a graceful shutdown (default false)
+    #[serde(default)]
+    pub checkpoint_on_shutdown: bool,
+
     // --- Replication Configuration (Phase 5 Stage 1) ---
     // Per P5-I16: All fields default to disabled.
     /// Whether replication is enabled (default: false per P5-I16)
@@ -68,24 +142,54 @@ pub struct Config {
     /// Primary node address (required for replicas, forbidden for primaries)
     #[serde(default)]
     pub primary_address: Option<String>,
+
+    /// Typed subsystem configs. Only `aerodb.toml` sets these; JSON
+    /// configs run every subsystem with its defaults.
+    #[serde(skip)]
+    pub subsystems: SubsystemConfigs,
 }
 
 fn default_max_wal_size() -> u64 {
-    1073741824
-} // 1GB
+    DEFAULT_MAX_WAL_SIZE_BYTES
+}
 fn default_max_memory() -> u64 {
-    536870912
-} // 512MB
+    DEFAULT_MAX_MEMORY_BYTES
+}
 fn default_wal_sync_mode() -> String {
     "fsync".to_string()
 }
+fn default_wal_tail_recovery() -> String {
+    TailRecovery::default().name().to_string()
+}
+fn default_storage_format_version() -> u16 {
+    StorageFormat::default().version()
+}
+fn default_index_rebuild_threads() -> usize {
+    1
+}
+fn default_recovery_verification() -> String {
+    VerificationLevel::default().name().to_string()
+}
+fn default_verification_sample_percent() -> u8 {
+    DEFAULT_SAMPLE_PERCENT
+}
 fn default_replication_role() -> String {
     "primary".to_string()
 }
 
 impl Config {
     /// Load configuration from file
+    ///
+    /// A `.toml` file is read as the unified `aerodb.toml` config, with
+    /// `AERODB_*` environment overrides; anything else as `aerodb.json`.
     pub fn load(path: &Path) -> CliResult<Self> {
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            let aero = AeroConfig::load(path).map_err(|e| CliError::config_error(e.to_string()))?;
+            let config = Self::from_aero(&aero)?;
+            config.validate()?;
+            return Ok(config);
+        }
+
         let content = fs::read_to_string(path)
             .map_err(|e| CliError::config_error(format!("Failed to read config: {}", e)))?;
 
@@ -97,6 +201,105 @@ impl Config {
         Ok(config)
     }
 
+    /// Build the boot configuration from a loaded `aerodb.toml`
+    pub fn from_aero(aero: &AeroConfig) -> CliResult<Self> {
+        Ok(Self {
+            data_dir: aero.data_dir.clone(),
+            max_wal_size_bytes: aero.wal.max_size_bytes,
+            max_memory_bytes: aero.max_memory_bytes,
+            wal_sync_mode: aero.wal.sync_mode.clone(),
+            wal_tail_recovery: aero.wal.tail_recovery.clone(),
+            storage_format_version: aero.storage.format_version,
+            storage_cache_entries: aero.storage.cache_entries,
+            index_rebuild_threads: aero.recovery.index_rebuild_threads,
+            recovery_verification: aero.recovery.verification.clone(),
+            verification_sample_percent: aero.recovery.sample_percent,
+            verification_seed: aero.recovery.seed,
+            checkpoint_on_shutdown: aero.checkpoint.on_shutdown,
+            replication_enabled: aero.replication.enabled,
+            replication_role: aero.replication.role.clone(),
+            replica_id: aero.replication.replica_id.clone(),
+            primary_address: aero.replication.primary_address.clone(),
+            subsystems: aero
+                .subsystems()
+                .map_err(|e| CliError::config_error(e.to_string()))?,
+        })
+    }
+
+    /// The effective configuration in `aerodb.toml` form
+    ///
+    /// Inverse of `from_aero`; JSON configs report their subsystem
+    /// defaults.
+    pub fn to_aero(&self) -> AeroConfig {
+        let subsystems = &self.subsystems;
+        AeroConfig {
+            data_dir: self.data_dir.clone(),
+            max_memory_bytes: self.max_memory_bytes,
+            wal: WalSection {
+                max_size_bytes: self.max_wal_size_bytes,
+                sync_mode: self.wal_sync_mode.clone(),
+                tail_recovery: self.wal_tail_recovery.clone(),
+                group_commit: subsystems.group_commit.enabled,
+            },
+            storage: StorageSection {
+                format_version: self.storage_format_version,
+                cache_entries: self.storage_cache_entries,
+            },
+            recovery: RecoverySection {
+                verification: self.recovery_verification.clone(),
+                sample_percent: self.verification_sample_percent,
+                seed: self.verification_seed,
+                index_rebuild_threads: self.index_rebuild_threads,
+            },
+            checkpoint: CheckpointSection {
+                pipelining: subsystems.pipeline.enabled,
+                on_shutdown: self.checkpoint_on_shutdown,
+            },
+            index: IndexSection {
+                accelerated_structures: subsystems.index_accel.accelerated_structures_enabled,
+                predicate_prefilter: subsystems.index_accel.predicate_prefilter_enabled,
+                multi_attribute: subsystems.index_accel.multi_attribute_enabled,
+            },
+            replication: ReplicationSection {
+                enabled: self.replication_enabled,
+                role: self.replication_role.clone(),
+                replica_id: self.replica_id.clone(),
+                primary_address: self.primary_address.clone(),
+            },
+            http: HttpSection {
+                host: subsystems.http_server.host.clone(),
+                port: subsystems.http_server.port,
+                cors_origins: subsystems.http_server.cors_origins.clone(),
+                cors_methods: subsystems.http_server.cors_methods.clone(),
+                cors_headers: subsystems.http_server.cors_headers.clone(),
+                security_headers: subsystems.http_server.security_headers,
+                control_signing_key: subsystems.http_server.control_signing_key.clone(),
+                dual_control: subsystems.http_server.dual_control.clone(),
+                tls: subsystems.http_server.tls.clone(),
+                oidc: subsystems.http_server.oidc.clone(),
+                mfa_required_roles: subsystems.http_server.mfa_required_roles.clone(),
+                pipeline: subsystems.http_server.pipeline.clone(),
+            },
+            dx: DxSection {
+                enabled: subsystems.dx.enabled,
+                port: subsystems.dx.port,
+                bind_address: subsystems.dx.bind_address.clone(),
+            },
+            pgwire: PgWireSection {
+                enabled: subsystems.pgwire.enabled,
+                port: subsystems.pgwire.port,
+                bind_address: subsystems.pgwire.bind_address.clone(),
+                max_connections: subsystems.pgwire.max_connections,
+            },
+            observability: ObservabilitySection::new(
+                &subsystems.observability,
+                &subsystems.log_sinks,
+                subsystems.slow_queries.as_ref(),
+                subsystems.audit_log.as_ref(),
+            ),
+        }
+    }
+
     /// Validate configuration per CONFIG.md
     fn validate(&self) -> CliResult<()> {
         // Validate wal_sync_mode
@@ -107,6 +310,22 @@ impl Config {
             )));
         }
 
+        // Validate wal_tail_recovery
+        if TailRecovery::from_name(&self.wal_tail_recovery).is_none() {
+            return Err(CliError::config_error(format!(
+                "Invalid wal_tail_recovery: '{}'. Expected 'strict' or 'truncate'.",
+                self.wal_tail_recovery
+            )));
+        }
+
+        // Validate storage_format_version
+        if StorageFormat::from_version(self.storage_format_version).is_none() {
+            return Err(CliError::config_error(format!(
+                "Invalid storage_format_version: {}. Expected 1 or 2.",
+                self.storage_format_version
+            )));
+        }
+
         // Validate max_wal_size_bytes
         if self.max_wal_size_bytes == 0 {
             return Err(CliError::config_error("max_wal_size_bytes must be > 0"));
@@ -117,6 +336,27 @@ impl Config {
             return Err(CliError::config_error("max_memory_bytes must be > 0"));
         }
 
+        // Validate recovery_verification
+        if VerificationLevel::from_name(&self.recovery_verification).is_none() {
+            return Err(CliError::config_error(format!(
+                "Invalid recovery_verification: '{}'. Expected 'none', 'manifest-only', \
+                 'sampled' or 'full'.",
+                self.recovery_verification
+            )));
+        }
+
+        // Validate verification_sample_percent
+        if !(1..=100).contains(&self.verification_sample_percent) {
+            return Err(CliError::config_error(
+                "verification_sample_percent must be between 1 and 100",
+            ));
+        }
+
+        // Validate index_rebuild_threads
+        if self.index_rebuild_threads == 0 {
+            return Err(CliError::config_error("index_rebuild_threads must be > 0"));
+        }
+
         // Validate replication config (Phase 5 Stage 1)
         self.to_replication_config()?.validate().map_err(|e| {
             CliError::config_error(format!("Replication config error: {}", e.message))
@@ -130,6 +370,37 @@ impl Config {
         Path::new(&self.data_dir)
     }
 
+    /// Get the torn-tail recovery policy (validated on load)
+    pub fn tail_recovery(&self) -> TailRecovery {
+        TailRecovery::from_name(&self.wal_tail_recovery).unwrap_or_default()
+    }
+
+    /// Get the storage verification level (validated on load)
+    pub fn verification(&self) -> VerificationLevel {
+        match VerificationLevel::from_name(&self.recovery_verification) {
+            Some(VerificationLevel::Sampled { .. }) => VerificationLevel::Sampled {
+                percent: self.verification_sample_percent,
+                seed: self.verification_seed,
+            },
+            level => level.unwrap_or_default(),
+        }
+    }
+
+    /// Get the boot settings for the default collection
+    pub(super) fn boot_options(&self) -> BootOptions<'static> {
+        BootOptions {
+            tail_recovery: self.tail_recovery(),
+            index_rebuild_threads: self.index_rebuild_threads,
+            verification: self.verification(),
+            ..BootOptions::default()
+        }
+    }
+
+    /// Get the storage format for new data directories (validated on load)
+    pub fn storage_format(&self) -> StorageFormat {
+        StorageFormat::from_version(self.storage_format_version).unwrap_or_default()
+    }
+
     /// Convert to ReplicationConfig for use during boot.
     ///
     /// Per PHASE5_IMPLEMENTATION_ORDER.md §Stage 1:
@@ -210,6 +481,14 @@ impl Config {
 /// This is the only function that main.rs should call.
 pub fn run() -> CliResult<()> {
     let cli = super::args::Cli::parse_args();
+    OutputFormat::from_name(&cli.format)
+        .ok_or_else(|| {
+            CliError::config_error(format!(
+                "Unknown output format '{}': expected json, ndjson or table",
+                cli.format
+            ))
+        })?
+        .select();
     run_command(cli.command)
 }
 
@@ -217,9 +496,56 @@ pub fn run() -> CliResult<()> {
 pub fn run_command(cmd: Command) -> CliResult<()> {
     match cmd {
         Command::Init { config } => init(&config),
-        Command::Start { config } => start(&config),
+        Command::Start {
+            config,
+            verify,
+            daemon,
+            checkpoint_on_shutdown,
+            detached,
+        } => start(
+            &config,
+            &StartOptions {
+                verify,
+                daemon,
+                checkpoint_on_shutdown,
+                detached,
+            },
+        ),
+        Command::Shell { config } => shell(&config),
         Command::Query { config } => query(&config),
         Command::Explain { config } => explain(&config),
+        Command::CreateIndex { config, field } => create_index(&config, &field),
+        Command::Sandbox {
+            config,
+            scratch_dir,
+        } => sandbox(&config, scratch_dir.as_deref()),
+        Command::Selftest { scratch_dir, keep } => selftest(scratch_dir.as_deref(), keep),
+        Command::Doctor { config } => doctor(&config),
+        Command::Wal {
+            action:
+                WalAction::Dump {
+                    config,
+                    collection,
+                    from_seq,
+                    to_seq,
+                },
+        } => wal_dump(
+            &config,
+            &WalDumpFilter {
+                collection,
+                from_sequence: from_seq,
+                to_sequence: to_seq,
+            },
+        ),
+        Command::Storage {
+            action: StorageAction::Dump { config, collection },
+        } => storage_dump(&config, collection.as_deref()),
+        Command::Config {
+            action: ConfigAction::Validate { config },
+        } => config_validate(&config),
+        Command::Config {
+            action: ConfigAction::Dump { config },
+        } => config_dump(&config),
         Command::Serve { config, port } => serve(&config, port),
         Command::Control { config, action } => control(&config, action),
     }
@@ -231,6 +557,7 @@ pub fn run_command(cmd: Command) -> CliResult<()> {
 /// - Creates directory structure
 /// - Does NOT start server
 /// - Writes no WAL records
+/// - Creates the storage file in the configured format
 /// - Does not create clean_shutdown marker
 pub fn init(config_path: &Path) -> CliResult<()> {
     let config = Config::load(config_path)?;
@@ -241,24 +568,33 @@ pub fn init(config_path: &Path) -> CliResult<()> {
         return Err(CliError::already_initialized());
     }
 
-    // Create directory structure per CONFIG.md §4
-    let dirs = [
-        data_dir.join("wal"),
-        data_dir.join("data"),
-        data_dir.join("metadata").join("schemas"),
-    ];
+    create_data_dirs(data_dir)?;
 
-    for dir in &dirs {
-        fs::create_dir_all(dir).map_err(|e| {
-            CliError::config_error(format!("Failed to create directory {:?}: {}", dir, e))
-        })?;
-    }
+    // Later opens keep whichever format the file was created with; a
+    // master key installed beforehand encrypts it from the first byte
+    let master_keys = MasterKeyring::open(data_dir)
+        .map_err(|e| CliError::config_error(format!("Master keyring open failed: {}", e)))?;
+    StorageWriter::open_with_keys(data_dir, config.storage_format(), &master_keys)
+        .map_err(|e| CliError::config_error(format!("Failed to create storage file: {}", e)))?;
 
     write_response(json!({"initialized": true}))?;
 
     Ok(())
 }
 
+/// Settings for `aerodb start` beyond the config file
+#[derive(Debug, Clone, Default)]
+pub struct StartOptions {
+    /// Storage verification level overriding the config
+    pub verify: Option<String>,
+    /// Relaunch detached from the terminal and return
+    pub daemon: bool,
+    /// Checkpoint on shutdown even if the config does not ask for it
+    pub checkpoint_on_shutdown: bool,
+    /// This process is the detached child of `--daemon`
+    pub detached: bool,
+}
+
 /// Start the AeroDB server
 ///
 /// Per BOOT.md §3, startup sequence:
@@ -269,9 +605,20 @@ pub fn init(config_path: &Path) -> CliResult<()> {
 /// 5. Verification
 /// 6. API Activation
 ///
-/// Then enters SERVING loop reading JSON from stdin.
-pub fn start(config_path: &Path) -> CliResult<()> {
-    let config = Config::load(config_path)?;
+/// The data directory lock and pid file are taken before recovery. Then
+/// enters SERVING loop reading JSON from stdin until end of input, or
+/// until SIGTERM/SIGINT. A detached server reads no stdin and runs until
+/// signalled. SIGHUP re-reads `[observability]` from the config file.
+///
+/// Shutdown stops accepting requests, fsyncs the WAL, optionally
+/// checkpoints, writes the clean_shutdown marker and releases the lock.
+pub fn start(config_path: &Path, options: &StartOptions) -> CliResult<()> {
+    let mut config = Config::load(config_path)?;
+    if let Some(level) = &options.verify {
+        config.recovery_verification = level.clone();
+        config.validate()?;
+    }
+    config.checkpoint_on_shutdown |= options.checkpoint_on_shutdown;
     let data_dir = config.data_path();
 
     // Check if initialized
@@ -279,36 +626,153 @@ pub fn start(config_path: &Path) -> CliResult<()> {
         return Err(CliError::not_initialized());
     }
 
-    // Boot the system
-    let (mut wal_writer, mut storage_writer, mut storage_reader, schema_loader, mut index_manager) =
-        boot_system(data_dir)?;
+    if options.daemon {
+        let mut start_args = vec![
+            "--config".to_string(),
+            config_path.to_string_lossy().into_owned(),
+        ];
+        if let Some(level) = &options.verify {
+            start_args.extend(["--verify".to_string(), level.clone()]);
+        }
+        if options.checkpoint_on_shutdown {
+            start_args.push("--checkpoint-on-shutdown".to_string());
+        }
+        let pid = daemonize(data_dir, &start_args)?;
+        return write_response(json!({
+            "daemon": true,
+            "pid": pid,
+            "pid_file": data_dir.join(PID_FILE).to_string_lossy(),
+        }));
+    }
 
-    // Initialize API handler
-    let handler = ApiHandler::new("default");
+    install_sinks(&config.subsystems.log_sinks);
+    let lock = ProcessLock::acquire(data_dir)?;
 
-    // Enter SERVING loop
-    // Read JSON from stdin line-by-line, write response to stdout
-    for request_result in read_requests() {
-        match request_result {
-            Ok(request) => {
-                let request_str = request.to_string();
+    let metrics = Arc::new(MetricsRegistry::new());
+    config.subsystems.observability.apply(&metrics);
 
-                let mut subsystems = Subsystems {
-                    schema_loader: &schema_loader,
-                    wal_writer: &mut wal_writer,
-                    storage_writer: &mut storage_writer,
-                    storage_reader: &mut storage_reader,
-                    index_manager: &mut index_manager,
-                };
+    // Boot the system
+    let (
+        mut wal_writer,
+        mut storage_writer,
+        mut storage_reader,
+        mut schema_loader,
+        mut index_manager,
+    ) = boot_serving(&config, &metrics)?;
+
+    // Initialize API handler; fields marked encrypted use keys from the
+    // data directory
+    let field_keys = FieldKeyring::open(data_dir)
+        .map_err(|e| CliError::boot_failed(format!("Field keyring open failed: {}", e)))?;
+    let mut handler = ApiHandler::new(DEFAULT_COLLECTION)
+        .with_metrics(Arc::clone(&metrics))
+        .with_field_keys(Arc::new(field_keys));
+    if let Some(slow_queries) = &config.subsystems.slow_queries {
+        handler = handler.with_slow_query_log(Arc::new(SlowQueryLog::new(slow_queries)));
+    }
+    if let Some(audit) = &config.subsystems.audit_log {
+        handler = handler.with_audit_log(Arc::new(open_audit_log(audit)?));
+    }
 
-                let response = handler.handle(&request_str, &mut subsystems);
-                write_json(&response.to_json())?;
+    // Enter SERVING loop
+    // Requests from stdin and SQL clients, and shutdown signals, arrive on
+    // one channel
+    let (submit, inputs) = serve_inputs(!options.detached)?;
+    if config.subsystems.pgwire.enabled {
+        let backend = Arc::new(SqlBackend { submit });
+        let addr = PgWireServer::new(config.subsystems.pgwire.clone(), backend).spawn()?;
+        emit(
+            Severity::Info,
+            Event::PgWireListening,
+            &[("addr", &addr.to_string())],
+        );
+    }
+    let mut handle = |request: &Value| {
+        let mut subsystems = Subsystems {
+            schema_loader: &mut schema_loader,
+            wal_writer: &mut wal_writer,
+            storage_writer: &mut storage_writer,
+            storage_reader: &mut storage_reader,
+            index_manager: &mut index_manager,
+        };
+
+        // Local clients act with the operator's full authority
+        handler.handle_as(
+            &request.to_string(),
+            PriorityClass::Authenticated,
+            &AuthContext::service_role(),
+            &mut subsystems,
+        )
+    };
+    emit(Severity::Info, Event::Serving, &[]);
+    let reason = loop {
+        match inputs.recv() {
+            Ok(ServeInput::Request(Ok(request))) => {
+                write_json(&handle(&request).to_json())?;
             }
-            Err(e) => {
+            Ok(ServeInput::Query(request, reply)) => {
+                // The client may have disconnected; nothing to report then
+                let _ = reply.send(handle(&request));
+            }
+            Ok(ServeInput::Request(Err(e))) => {
                 // I/O error reading - this is fatal
                 write_error(e.code_str(), e.message())?;
-                break;
+                break "input error";
             }
+            Ok(ServeInput::Reload) => reload_observability(config_path, &metrics),
+            Ok(ServeInput::Shutdown(signal)) => break signal,
+            Ok(ServeInput::EndOfInput) | Err(_) => break "end of input",
+        }
+    };
+
+    // Shutdown sequence
+    emit(Severity::Info, Event::ShutdownStart, &[("reason", reason)]);
+    drop(inputs);
+    emit(Severity::Info, Event::ShutdownStopAccepting, &[]);
+
+    wal_writer
+        .fsync()
+        .map_err(|e| CliError::io_error(format!("WAL fsync on shutdown failed: {}", e)))?;
+    emit(Severity::Info, Event::WalFsync, &[]);
+
+    if config.checkpoint_on_shutdown {
+        emit(Severity::Info, Event::CheckpointStart, &[]);
+        let keyring = CollectionKeyring::open(data_dir)
+            .map_err(|e| CliError::io_error(format!("Collection keyring open failed: {}", e)))?;
+        // Index checkpoints hold plaintext keys; none are kept for
+        // storage encrypted at rest
+        let at_rest = storage_writer.is_encrypted();
+        let started = Instant::now();
+        let checkpoint = CheckpointManager::create_checkpoint_with_indexes(
+            data_dir,
+            &data_dir.join("data").join("documents.dat"),
+            schema_loader.schema_dir(),
+            &SnapshotManager,
+            &mut wal_writer,
+            IndexCapture {
+                index: &index_manager,
+                collection: DEFAULT_COLLECTION,
+                storage: &mut IndexStorage::new(&mut storage_reader),
+                sealed: &|collection| at_rest || keyring.is_encrypted(collection),
+            },
+            &GlobalExecutionLock::new(),
+        );
+        match checkpoint {
+            Ok(checkpoint_id) => {
+                metrics.increment_checkpoints();
+                metrics.observe_checkpoint_duration(started.elapsed());
+                emit(
+                    Severity::Info,
+                    Event::CheckpointComplete,
+                    &[("checkpoint_id", &checkpoint_id)],
+                )
+            }
+            // The WAL is intact and fsynced; the next boot replays it
+            Err(e) => emit(
+                Severity::Error,
+                Event::CheckpointFailed,
+                &[("reason", &e.to_string())],
+            ),
         }
     }
 
@@ -316,6 +780,116 @@ pub fn start(config_path: &Path) -> CliResult<()> {
     let shutdown_marker = data_dir.join("clean_shutdown");
     let _ = fs::write(&shutdown_marker, "");
 
+    lock.release()?;
+    emit(
+        Severity::Info,
+        Event::ShutdownComplete,
+        &[("reason", reason)],
+    );
+
+    Ok(())
+}
+
+/// Runs SQL client queries on the serving loop
+struct SqlBackend {
+    submit: Sender<ServeInput>,
+}
+
+impl QueryBackend for SqlBackend {
+    fn query(&self, query: &Query) -> Result<Vec<Value>, BackendError> {
+        let shutting_down = || BackendError::new("AERO_SHUTTING_DOWN", "server is shutting down");
+        let request = query_request(query).map_err(|e| BackendError::new(e.code(), e.message()))?;
+        let (reply, response) = mpsc::channel();
+        self.submit
+            .send(ServeInput::Query(request, reply))
+            .map_err(|_| shutting_down())?;
+        match response.recv().map_err(|_| shutting_down())? {
+            Response::Success(success) => match success.data {
+                Value::Array(documents) => Ok(documents),
+                other => Err(BackendError::new(
+                    "AERO_INTERNAL",
+                    format!("query returned {} instead of documents", other),
+                )),
+            },
+            Response::Error(error) => Err(BackendError::new(error.code, error.message)),
+        }
+    }
+}
+
+/// Re-read `[observability]` from the config file and apply it.
+///
+/// A config that no longer loads leaves the current settings in place.
+fn reload_observability(config_path: &Path, metrics: &MetricsRegistry) {
+    match Config::load(config_path) {
+        Ok(config) => {
+            let observability = config.subsystems.observability;
+            observability.apply(metrics);
+            emit(
+                Severity::Info,
+                Event::ObservabilityReloaded,
+                &[
+                    ("log_level", observability.log_level.as_str()),
+                    (
+                        "metrics_enabled",
+                        &observability.metrics_enabled.to_string(),
+                    ),
+                ],
+            );
+        }
+        Err(e) => emit(
+            Severity::Error,
+            Event::ObservabilityReloadFailed,
+            &[("reason", e.message())],
+        ),
+    }
+}
+
+/// Start an interactive shell over the booted data directory
+///
+/// Reads one request or shell command per line until end of input or
+/// `.quit`, prompting on stderr when stdin is a terminal.
+pub fn shell(config_path: &Path) -> CliResult<()> {
+    let config = Config::load(config_path)?;
+    let data_dir = config.data_path();
+
+    if !is_initialized(data_dir) {
+        return Err(CliError::not_initialized());
+    }
+
+    let (
+        mut wal_writer,
+        mut storage_writer,
+        mut storage_reader,
+        mut schema_loader,
+        mut index_manager,
+    ) = boot_serving(&config, &Arc::new(MetricsRegistry::new()))?;
+    let mut subsystems = Subsystems {
+        schema_loader: &mut schema_loader,
+        wal_writer: &mut wal_writer,
+        storage_writer: &mut storage_writer,
+        storage_reader: &mut storage_reader,
+        index_manager: &mut index_manager,
+    };
+
+    let stdin = std::io::stdin();
+    let mut stderr = std::io::stderr();
+    let prompt: Option<&mut dyn std::io::Write> = if stdin.is_terminal() {
+        Some(&mut stderr)
+    } else {
+        None
+    };
+    Shell::new(ApiHandler::new(DEFAULT_COLLECTION))
+        .with_format(OutputFormat::current())
+        .run(
+            stdin.lock(),
+            &mut std::io::stdout(),
+            prompt,
+            &mut subsystems,
+        )?;
+
+    // Clean shutdown - write marker
+    let _ = fs::write(data_dir.join("clean_shutdown"), "");
+
     Ok(())
 }
 
@@ -332,8 +906,13 @@ pub fn query(config_path: &Path) -> CliResult<()> {
     }
 
     // Boot the system
-    let (mut wal_writer, mut storage_writer, mut storage_reader, schema_loader, mut index_manager) =
-        boot_system(data_dir)?;
+    let (
+        mut wal_writer,
+        mut storage_writer,
+        mut storage_reader,
+        mut schema_loader,
+        mut index_manager,
+    ) = boot_system(data_dir, &config.boot_options())?;
 
     // Read single request from stdin
     let request = read_request()?;
@@ -353,10 +932,51 @@ pub fn query(config_path: &Path) -> CliResult<()> {
     let request_str = request_obj.to_string();
 
     // Initialize API handler
-    let handler = ApiHandler::new("default");
+    let handler = ApiHandler::new(DEFAULT_COLLECTION);
 
     let mut subsystems = Subsystems {
-        schema_loader: &schema_loader,
+        schema_loader: &mut schema_loader,
+        wal_writer: &mut wal_writer,
+        storage_writer: &mut storage_writer,
+        storage_reader: &mut storage_reader,
+        index_manager: &mut index_manager,
+    };
+
+    let response = handler.handle(&request_str, &mut subsystems);
+    write_json(&response.to_json())?;
+
+    Ok(())
+}
+
+/// Declare an index on a field, backfill it, and exit
+///
+/// The index definition is recorded durably, so every later boot rebuilds
+/// it.
+pub fn create_index(config_path: &Path, field: &str) -> CliResult<()> {
+    let config = Config::load(config_path)?;
+    let data_dir = config.data_path();
+
+    // Check if initialized
+    if !is_initialized(data_dir) {
+        return Err(CliError::not_initialized());
+    }
+
+    // Boot the system
+    let (
+        mut wal_writer,
+        mut storage_writer,
+        mut storage_reader,
+        mut schema_loader,
+        mut index_manager,
+    ) = boot_system(data_dir, &config.boot_options())?;
+
+    let request_str = json!({"op": "create_index", "field": field}).to_string();
+
+    // Initialize API handler
+    let handler = ApiHandler::new(DEFAULT_COLLECTION);
+
+    let mut subsystems = Subsystems {
+        schema_loader: &mut schema_loader,
         wal_writer: &mut wal_writer,
         storage_writer: &mut storage_writer,
         storage_reader: &mut storage_reader,
@@ -382,8 +1002,13 @@ pub fn explain(config_path: &Path) -> CliResult<()> {
     }
 
     // Boot the system
-    let (mut wal_writer, mut storage_writer, mut storage_reader, schema_loader, mut index_manager) =
-        boot_system(data_dir)?;
+    let (
+        mut wal_writer,
+        mut storage_writer,
+        mut storage_reader,
+        mut schema_loader,
+        mut index_manager,
+    ) = boot_system(data_dir, &config.boot_options())?;
 
     // Read single request from stdin
     let request = read_request()?;
@@ -397,10 +1022,10 @@ pub fn explain(config_path: &Path) -> CliResult<()> {
     let request_str = request_obj.to_string();
 
     // Initialize API handler
-    let handler = ApiHandler::new("default");
+    let handler = ApiHandler::new(DEFAULT_COLLECTION);
 
     let mut subsystems = Subsystems {
-        schema_loader: &schema_loader,
+        schema_loader: &mut schema_loader,
         wal_writer: &mut wal_writer,
         storage_writer: &mut storage_writer,
         storage_reader: &mut storage_reader,
@@ -413,6 +1038,160 @@ pub fn explain(config_path: &Path) -> CliResult<()> {
     Ok(())
 }
 
+/// Run a disposable schema sandbox
+///
+/// Seeds a scratch environment from the latest snapshot and serves JSON
+/// requests from stdin against it. No WAL record is ever written to the
+/// configured data directory. The scratch directory is removed on exit.
+pub fn sandbox(config_path: &Path, scratch_dir: Option<&Path>) -> CliResult<()> {
+    use crate::dx::sandbox::{Sandbox, SandboxConfig};
+
+    let config = Config::load(config_path)?;
+    let data_dir = config.data_path();
+
+    if !is_initialized(data_dir) {
+        return Err(CliError::not_initialized());
+    }
+
+    let mut sandbox_config = SandboxConfig::default();
+    if let Some(dir) = scratch_dir {
+        sandbox_config.scratch_root = dir.to_path_buf();
+    }
+
+    let mut sandbox =
+        Sandbox::open(data_dir, sandbox_config).map_err(|e| CliError::boot_failed(e.message()))?;
+
+    write_response(json!({
+        "sandbox": true,
+        "snapshot_id": sandbox.snapshot_id(),
+    }))?;
+
+    for request_result in read_requests() {
+        match request_result {
+            Ok(request) => {
+                let response = sandbox.handle(&request.to_string());
+                write_json(&response.to_json())?;
+            }
+            Err(e) => {
+                write_error(e.code_str(), e.message())?;
+                break;
+            }
+        }
+    }
+
+    sandbox
+        .teardown()
+        .map_err(|e| CliError::io_error(e.message()))
+}
+
+/// Run the end-to-end acceptance self-test
+///
+/// Exercises the full vertical against a scratch data directory and writes
+/// a per-stage report. The configured data directory is never touched.
+pub fn selftest(scratch_dir: Option<&Path>, keep: bool) -> CliResult<()> {
+    let scratch_root = scratch_dir
+        .map(Path::to_path_buf)
+        .unwrap_or_else(std::env::temp_dir);
+
+    let report = SelfTest::new(&scratch_root).run(keep);
+    write_response(report.to_json())?;
+
+    match report.first_failure() {
+        None => Ok(()),
+        Some(stage) => Err(CliError::selftest_failed(format!(
+            "Stage '{}' failed: {}",
+            stage.stage, stage.detail
+        ))),
+    }
+}
+
+/// Inspect the configured data directory without booting it
+///
+/// Writes a per-subsystem health report and fails if any check failed.
+pub fn doctor(config_path: &Path) -> CliResult<()> {
+    let config = Config::load(config_path)?;
+    let data_dir = config.data_path();
+
+    if !is_initialized(data_dir) {
+        return Err(CliError::not_initialized());
+    }
+
+    let report = diagnose(data_dir);
+    write_response(report.to_json())?;
+
+    let failed: Vec<&str> = report.failures().map(|c| c.subsystem).collect();
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(CliError::doctor_failed(format!(
+            "Unhealthy subsystems: {}",
+            failed.join(", ")
+        )))
+    }
+}
+
+/// Print the WAL records of the configured data directory as JSON lines
+pub fn wal_dump(config_path: &Path, filter: &WalDumpFilter) -> CliResult<()> {
+    let config = Config::load(config_path)?;
+    let data_dir = config.data_path();
+
+    if !is_initialized(data_dir) {
+        return Err(CliError::not_initialized());
+    }
+
+    dump_wal(data_dir, filter, &mut std::io::stdout().lock())?;
+    Ok(())
+}
+
+/// Print the storage records of the configured data directory as JSON lines
+pub fn storage_dump(config_path: &Path, collection: Option<&str>) -> CliResult<()> {
+    let config = Config::load(config_path)?;
+    let data_dir = config.data_path();
+
+    if !is_initialized(data_dir) {
+        return Err(CliError::not_initialized());
+    }
+
+    dump_storage(data_dir, collection, &mut std::io::stdout().lock())?;
+    Ok(())
+}
+
+/// Validate a config file without starting
+///
+/// Loads and validates the config exactly as `start` would, without
+/// touching the data directory. Reports the `AERODB_*` variables applied
+/// to a `.toml` config.
+pub fn config_validate(config_path: &Path) -> CliResult<()> {
+    Config::load(config_path)?;
+
+    let is_toml = config_path.extension().is_some_and(|ext| ext == "toml");
+    let env_overrides: Vec<String> = if is_toml {
+        collect_overrides(std::env::vars(), SECTIONS)
+            .into_iter()
+            .map(|o| o.var)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    write_response(json!({
+        "valid": true,
+        "path": config_path.to_string_lossy(),
+        "format": if is_toml { "toml" } else { "json" },
+        "env_overrides": env_overrides,
+    }))
+}
+
+/// Print the effective configuration
+///
+/// Defaults, the file and `AERODB_*` overrides resolved into one JSON
+/// object in `aerodb.toml` layout, with keys in a fixed order so configs
+/// from different environments diff cleanly.
+pub fn config_dump(config_path: &Path) -> CliResult<()> {
+    let config = Config::load(config_path)?;
+    write_response(serde_json::to_value(config.to_aero())?)
+}
+
 /// Start the HTTP server for dashboard (Phase 13.5)
 ///
 /// Boots the database and starts an HTTP server. This is the recommended
@@ -422,7 +1201,7 @@ pub fn explain(config_path: &Path) -> CliResult<()> {
 /// 1. Boot database (same as start command)
 /// 2. Initialize HTTP server with all subsystems
 /// 3. Start Axum server on specified port
-pub fn serve(config_path: &Path, port: u16) -> CliResult<()> {
+pub fn serve(config_path: &Path, port: Option<u16>) -> CliResult<()> {
     let config = Config::load(config_path)?;
     let data_dir = config.data_path();
 
@@ -431,15 +1210,104 @@ pub fn serve(config_path: &Path, port: u16) -> CliResult<()> {
         return Err(CliError::not_initialized());
     }
 
-    // Boot the system (same as start command)
-    let (_wal_writer, _storage_writer, _storage_reader, _schema_loader, _index_manager) =
-        boot_system(data_dir)?;
+    let metrics = Arc::new(MetricsRegistry::new());
+    config.subsystems.observability.apply(&metrics);
 
-    // Create HTTP server with configured port
-    use crate::http_server::{HttpServer, HttpServerConfig};
-
-    let http_config = HttpServerConfig::with_port(port);
-    let server = HttpServer::with_config(http_config);
+    // Boot the system (same as start command)
+    metrics.increment_recovery_runs();
+    let recovery_started = Instant::now();
+    let (mut wal_writer, storage_writer, storage_reader, schema_loader, index_manager) =
+        boot_system(data_dir, &config.boot_options())
+            .inspect_err(|_| metrics.increment_recovery_failures())?;
+    metrics.observe_recovery_duration(recovery_started.elapsed());
+    wal_writer.set_metrics(Arc::clone(&metrics));
+
+    let wal_writer = Arc::new(Mutex::new(wal_writer));
+
+    // Database routes serve the booted stack, sharing its WAL with the
+    // control plane; fields marked encrypted use keys from the data directory
+    let field_keys = FieldKeyring::open(data_dir)
+        .map_err(|e| CliError::boot_failed(format!("Field keyring open failed: {}", e)))?;
+    let database = DurableStorage::new(
+        DEFAULT_COLLECTION,
+        Arc::clone(&wal_writer),
+        storage_writer,
+        storage_reader,
+        schema_loader,
+        index_manager,
+    )
+    .with_field_keys(Arc::new(field_keys));
+
+    // Control plane commands over HTTP run against this node's WAL,
+    // snapshots and authority markers
+    let state = config.init_replication_state()?;
+    let kernel = LiveKernelAdapter::new(
+        state.replica_id().unwrap_or_else(Uuid::nil),
+        data_dir,
+        wal_writer,
+        Arc::new(RwLock::new(state)),
+        Arc::new(Mutex::new(PromotionController::new())),
+    );
+    kernel.recover_authority().map_err(CliError::boot_failed)?;
+
+    // Create HTTP server from the [http] config, --port taking precedence
+    use crate::http_server::control_routes::ControlState;
+    use crate::http_server::{AuthStores, HttpServer};
+
+    let mut http_config = config.subsystems.http_server.clone();
+    if let Some(port) = port {
+        http_config.port = port;
+    }
+    let keys = match &http_config.control_signing_key {
+        Some(secret) => Some(Arc::new(
+            ApiKeyManager::open(secret, data_dir)
+                .map_err(|e| CliError::boot_failed(e.to_string()))?,
+        )),
+        None => None,
+    };
+    let mut handler = ControlPlaneHandler::with_kernel(Arc::new(kernel))
+        .with_dual_control(http_config.dual_control.iter().cloned());
+    if let Some(audit) = &config.subsystems.audit_log {
+        handler = handler.with_audit_log(Arc::new(open_audit_log(audit)?));
+    }
+    // Client certificates mapped in [http.tls] act without an API key
+    let client_identities = http_config
+        .tls
+        .iter()
+        .flat_map(|tls| &tls.client_identities)
+        .filter_map(|(name, level)| Some((name.clone(), AuthorityLevel::parse(level)?)))
+        .collect::<Vec<_>>();
+    let control =
+        ControlState::with_handler(handler, keys).with_client_identities(client_identities);
+    // Revoked auth tokens stay revoked, service keys and function secrets
+    // valid, and function invocation history and file upload sessions kept,
+    // across restarts
+    let stores = AuthStores {
+        revocations: Arc::new(
+            RevocationStore::open(data_dir).map_err(|e| CliError::boot_failed(e.to_string()))?,
+        ),
+        service_keys: Arc::new(
+            ServiceKeyManager::open(data_dir).map_err(|e| CliError::boot_failed(e.to_string()))?,
+        ),
+        function_secrets: Arc::new(
+            SecretStore::open(
+                data_dir,
+                Arc::new(
+                    FieldKeyring::open_secrets(data_dir)
+                        .map_err(|e| CliError::boot_failed(e.to_string()))?,
+                ),
+            )
+            .map_err(|e| CliError::boot_failed(e.to_string()))?,
+        ),
+        function_history: Arc::new(
+            InvocationHistory::open(data_dir).map_err(|e| CliError::boot_failed(e.to_string()))?,
+        ),
+        upload_sessions: Arc::new(
+            UploadSessionStore::open(data_dir).map_err(|e| CliError::boot_failed(e.to_string()))?,
+        ),
+        database: Some(Arc::new(database)),
+    };
+    let server = HttpServer::with_auth_stores(http_config, metrics, Arc::new(control), stores);
 
     // Start the async runtime and run the server
     let rt = tokio::runtime::Runtime::new()
@@ -462,35 +1330,56 @@ pub fn serve(config_path: &Path, port: u16) -> CliResult<()> {
 /// - No retries, no defaults
 /// - Safety enforced server-side
 pub fn control(config_path: &Path, action: ControlAction) -> CliResult<()> {
-    let _config = Config::load(config_path)?;
-
-    // Create in-memory audit log for this session
-    let audit_log = MemoryAuditLog::new();
+    let config = Config::load(config_path)?;
+    let action = match action {
+        ControlAction::Keys { action } => return control_keys(&config, action),
+        ControlAction::Secrets { action } => return control_secrets(&config, action),
+        action => action,
+    };
 
-    // Create control plane handler
-    let mut handler = ControlPlaneHandler::new();
+    // Create control plane handler over this node's WAL, snapshots and
+    // authority markers; observability reloads are forwarded to the
+    // server running on this data directory
+    let data_dir = config.data_path().to_path_buf();
+    let master_keys = MasterKeyring::open(&data_dir)
+        .map_err(|e| CliError::boot_failed(format!("Master keyring open failed: {}", e)))?;
+    let wal = WalWriter::open_with_keys(&data_dir, Arc::new(master_keys))
+        .map_err(|e| CliError::boot_failed(format!("Failed to open WAL: {}", e)))?;
+    let state = config.init_replication_state()?;
+    let node_id = state.replica_id().unwrap_or_else(Uuid::nil);
+    let kernel = LiveKernelAdapter::new(
+        node_id,
+        &data_dir,
+        Arc::new(Mutex::new(wal)),
+        Arc::new(RwLock::new(state)),
+        Arc::new(Mutex::new(PromotionController::new())),
+    )
+    .with_observability_reload(Arc::new(move |_reason: &str| {
+        send_reload(&data_dir)
+            .map(|pid| format!("Sent SIGHUP to server process {}", pid))
+            .map_err(|e| e.message().to_string())
+    }));
+    kernel.recover_authority().map_err(CliError::boot_failed)?;
+
+    // The handler audits every command to the configured file, else to
+    // memory for this session
+    let mut handler = ControlPlaneHandler::with_kernel(Arc::new(kernel));
+    if let Some(audit) = &config.subsystems.audit_log {
+        handler = handler.with_audit_log(Arc::new(open_audit_log(audit)?));
+    }
 
-    // Convert CLI action to control plane command
+    // Convert CLI action to control plane command, on behalf of the
+    // local user
     let (command, authority) = build_command(action)?;
-
-    // Log command request
-    let request_audit = AuditRecord::new(AuditAction::CommandRequested, AuditOutcome::Pending)
-        .with_command(command.command_name())
-        .with_authority(&authority.level.to_string());
-    audit_log.append(&request_audit).ok();
-
-    // Create request
-    let request = CommandRequest::new(command.clone(), authority);
+    let authority = match std::env::var("USER") {
+        Ok(user) => authority.with_operator_id(user),
+        Err(_) => authority,
+    };
+    let request = CommandRequest::new(command, authority);
 
     // Handle command
     match handler.handle_command(request) {
         Ok(response) => {
-            // Log success
-            let outcome_audit =
-                AuditRecord::new(AuditAction::CommandExecuted, AuditOutcome::Success)
-                    .with_command(response.command_name.clone());
-            audit_log.append(&outcome_audit).ok();
-
             // Output response
             write_response(json!({
                 "request_id": response.request_id.to_string(),
@@ -500,13 +1389,6 @@ pub fn control(config_path: &Path, action: ControlAction) -> CliResult<()> {
             }))?;
         }
         Err(e) => {
-            // Log rejection
-            let outcome_audit =
-                AuditRecord::new(AuditAction::CommandRejected, AuditOutcome::Rejected)
-                    .with_command(command.command_name())
-                    .with_error(e.message());
-            audit_log.append(&outcome_audit).ok();
-
             // Output error
             write_error(e.code(), e.message())?;
         }
@@ -515,6 +1397,123 @@ pub fn control(config_path: &Path, action: ControlAction) -> CliResult<()> {
     Ok(())
 }
 
+/// Issue, revoke or list control plane API keys.
+///
+/// Keys are signed with `http.control_signing_key` and registered in the
+/// data directory, where a running server sees revocations immediately.
+fn control_keys(config: &Config, action: KeysAction) -> CliResult<()> {
+    let secret = config
+        .subsystems
+        .http_server
+        .control_signing_key
+        .as_deref()
+        .ok_or_else(|| {
+            CliError::config_error("http.control_signing_key is required to manage API keys")
+        })?;
+    let keys = ApiKeyManager::open(secret, config.data_path())
+        .map_err(|e| CliError::io_error(e.to_string()))?;
+
+    let key_json = |record: &ApiKeyRecord| {
+        json!({
+            "key_id": record.id.to_string(),
+            "name": record.name,
+            "authority": record.authority,
+            "issued_at": record.issued_at,
+            "expires_at": record.expires_at,
+            "revoked_at": record.revoked_at,
+        })
+    };
+
+    match action {
+        KeysAction::Issue {
+            name,
+            authority,
+            ttl_days,
+        } => {
+            let level = AuthorityLevel::parse(&authority).ok_or_else(|| {
+                CliError::config_error(format!(
+                    "unknown authority level '{}': expected observer, operator or auditor",
+                    authority
+                ))
+            })?;
+            let (record, token) = keys
+                .issue(&name, level, chrono::Duration::days(ttl_days))
+                .map_err(|e| CliError::io_error(e.to_string()))?;
+            let mut response = key_json(&record);
+            response["api_key"] = json!(token);
+            write_response(response)
+        }
+        KeysAction::Revoke { key_id } => {
+            let record = keys
+                .revoke(parse_uuid(&key_id)?)
+                .map_err(|e| CliError::io_error(e.to_string()))?;
+            write_response(key_json(&record))
+        }
+        KeysAction::List => {
+            let records = keys.list().map_err(|e| CliError::io_error(e.to_string()))?;
+            write_response(json!({
+                "keys": records.iter().map(key_json).collect::<Vec<_>>(),
+            }))
+        }
+    }
+}
+
+/// Manage function secrets in the data directory
+///
+/// Values are sealed under the keyring in `keys/secrets/`; the first secret
+/// set creates its key. A running server reads secrets at boot.
+fn control_secrets(config: &Config, action: SecretsAction) -> CliResult<()> {
+    let data_dir = config.data_path();
+    let keys = Arc::new(
+        FieldKeyring::open_secrets(data_dir).map_err(|e| CliError::io_error(e.to_string()))?,
+    );
+    let rotate = |keys: &FieldKeyring| {
+        let key_id = format!("s{}", chrono::Utc::now().timestamp_millis());
+        keys.rotate(&key_id)
+            .map(|()| key_id)
+            .map_err(|e| CliError::io_error(e.to_string()))
+    };
+    let secrets =
+        SecretStore::open(data_dir, keys.clone()).map_err(|e| CliError::io_error(e.to_string()))?;
+
+    match action {
+        SecretsAction::Set { name } => {
+            if keys.active_key_id().is_none() {
+                rotate(&keys)?;
+            }
+            // From stdin, so the value stays out of shell history
+            let mut value = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut value)?;
+            let value = value.strip_suffix('\n').unwrap_or(&value);
+            let value = value.strip_suffix('\r').unwrap_or(value);
+            if value.is_empty() {
+                return Err(CliError::io_error("Empty secret value"));
+            }
+            let info = secrets
+                .set(&name, value)
+                .map_err(|e| CliError::io_error(e.to_string()))?;
+            write_response(json!(info))
+        }
+        SecretsAction::Get { name, version } => {
+            let value = secrets
+                .get(&name, version)
+                .map_err(|e| CliError::io_error(e.to_string()))?;
+            write_response(json!({ "name": name, "value": value }))
+        }
+        SecretsAction::List => write_response(json!({ "secrets": secrets.list() })),
+        SecretsAction::Delete { name } => {
+            let info = secrets
+                .delete(&name)
+                .map_err(|e| CliError::io_error(e.to_string()))?;
+            write_response(json!(info))
+        }
+        SecretsAction::RotateKey => {
+            let key_id = rotate(&keys)?;
+            write_response(json!({ "active_key_id": key_id }))
+        }
+    }
+}
+
 /// Build a control plane command from CLI action.
 fn build_command(action: ControlAction) -> CliResult<(ControlPlaneCommand, AuthorityContext)> {
     let authority = AuthorityContext::operator();
@@ -575,6 +1574,100 @@ fn build_command(action: ControlAction) -> CliResult<(ControlPlaneCommand, Autho
                 acknowledged_risks: risks,
             })
         }
+        ControlAction::Maintenance { action } => match action {
+            MaintenanceAction::Enter {
+                node_id,
+                allow_reads,
+                drain_timeout_ms,
+                reason,
+                ..
+            } => {
+                let uuid = parse_uuid(&node_id)?;
+                ControlPlaneCommand::Control(ControlCommand::EnterMaintenanceMode {
+                    node_id: uuid,
+                    allow_reads,
+                    drain_timeout_ms,
+                    reason,
+                })
+            }
+            MaintenanceAction::Exit {
+                node_id, reason, ..
+            } => {
+                let uuid = parse_uuid(&node_id)?;
+                ControlPlaneCommand::Control(ControlCommand::ExitMaintenanceMode {
+                    node_id: uuid,
+                    reason,
+                })
+            }
+            MaintenanceAction::ExitReadOnly {
+                node_id, reason, ..
+            } => {
+                let uuid = parse_uuid(&node_id)?;
+                ControlPlaneCommand::Control(ControlCommand::ExitReadOnlyMode {
+                    node_id: uuid,
+                    reason,
+                })
+            }
+        },
+        ControlAction::ReloadObservability {
+            node_id, reason, ..
+        } => {
+            let uuid = parse_uuid(&node_id)?;
+            ControlPlaneCommand::Control(ControlCommand::ReloadObservability {
+                node_id: uuid,
+                reason,
+            })
+        }
+        ControlAction::Switchover {
+            from,
+            to,
+            drain_timeout_ms,
+            reason,
+            ..
+        } => ControlPlaneCommand::Control(ControlCommand::Switchover {
+            from: parse_uuid(&from)?,
+            to: parse_uuid(&to)?,
+            drain_timeout_ms,
+            reason,
+        }),
+        ControlAction::Checkpoint {
+            node_id, reason, ..
+        } => ControlPlaneCommand::Control(ControlCommand::CreateCheckpoint {
+            node_id: parse_uuid(&node_id)?,
+            reason,
+        }),
+        ControlAction::Keys { .. } => {
+            return Err(CliError::config_error(
+                "API keys are managed locally, not through the control plane",
+            ))
+        }
+        ControlAction::Secrets { .. } => {
+            return Err(CliError::config_error(
+                "Function secrets are managed locally, not through the control plane",
+            ))
+        }
+        ControlAction::Backup { action } => match action {
+            BackupAction::Create {
+                node_id,
+                output,
+                reason,
+                ..
+            } => ControlPlaneCommand::Control(ControlCommand::CreateBackup {
+                node_id: parse_uuid(&node_id)?,
+                output_path: output.display().to_string(),
+                reason,
+            }),
+            BackupAction::List { directory } => {
+                ControlPlaneCommand::Diagnostic(DiagnosticCommand::ListBackups {
+                    directory: directory.display().to_string(),
+                })
+            }
+            BackupAction::Verify { path, .. } => {
+                ControlPlaneCommand::Diagnostic(DiagnosticCommand::VerifyBackup {
+                    path: path.display().to_string(),
+                })
+            }
+        },
     };
 
     Ok((command, authority))
@@ -585,31 +1678,120 @@ fn parse_uuid(s: &str) -> CliResult<Uuid> {
     Uuid::parse_str(s).map_err(|e| CliError::config_error(format!("Invalid UUID '{}': {}", s, e)))
 }
 
+/// Open the configured audit log, failing boot if it cannot be written
+fn open_audit_log(config: &AuditLogConfig) -> CliResult<FileAuditLog> {
+    config.open().map_err(|e| {
+        CliError::boot_failed(format!("Failed to open audit log {:?}: {}", config.path, e))
+    })
+}
+
+/// Create the data directory structure per CONFIG.md §4
+pub(crate) fn create_data_dirs(data_dir: &Path) -> CliResult<()> {
+    let dirs = [
+        data_dir.join("wal"),
+        data_dir.join("data"),
+        data_dir.join("metadata").join("schemas"),
+    ];
+
+    for dir in &dirs {
+        fs::create_dir_all(dir).map_err(|e| {
+            CliError::config_error(format!("Failed to create directory {:?}: {}", dir, e))
+        })?;
+    }
+    Ok(())
+}
+
 /// Check if a data directory is initialized
-fn is_initialized(data_dir: &Path) -> bool {
+pub(crate) fn is_initialized(data_dir: &Path) -> bool {
     data_dir.join("wal").exists()
         && data_dir.join("data").exists()
         && data_dir.join("metadata").join("schemas").exists()
 }
 
+/// Settings that shape how `boot_system` recovers
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BootOptions<'a> {
+    /// Policy for a torn final WAL record
+    pub tail_recovery: TailRecovery,
+    /// Collection the returned index manager's own entries belong to
+    pub collection: &'a str,
+    /// Worker threads for a full index rebuild
+    pub index_rebuild_threads: usize,
+    /// Storage verification after WAL replay
+    pub verification: VerificationLevel,
+}
+
+impl Default for BootOptions<'_> {
+    fn default() -> Self {
+        Self {
+            tail_recovery: TailRecovery::default(),
+            collection: DEFAULT_COLLECTION,
+            index_rebuild_threads: 1,
+            verification: VerificationLevel::Full,
+        }
+    }
+}
+
 /// Boot the system per BOOT.md with mandatory recovery
 ///
 /// Steps (strict order, all mandatory):
-/// 1. Load schemas
-/// 2. Open WAL reader for replay
+/// 1. Load schemas, then apply schema DDL records from the WAL
+/// 2. Open WAL reader for replay (with the configured torn-tail policy)
 /// 3. Open recovery storage (combined writer + scanner)
 /// 4. Execute RecoveryManager::recover() which:
 ///    - Replays WAL from offset 0
 ///    - Applies all records to storage
-///    - Rebuilds indexes from storage
 ///    - Verifies consistency
 ///    - Removes clean_shutdown marker
-/// 5. Return initialized subsystems
+/// 5. Restore indexes from the latest index checkpoint, or rebuild them
+///    from storage on `index_rebuild_threads` workers
+/// 6. Return initialized subsystems
 ///
 /// FATAL: Any failure at any step halts startup immediately.
 /// No partial startup. No serving without complete recovery.
-fn boot_system(
+/// Boot for serving requests, with the configured block cache
+///
+/// The recovery, the WAL writer and the block cache report to `metrics`.
+fn boot_serving(
+    config: &Config,
+    metrics: &Arc<MetricsRegistry>,
+) -> CliResult<(
+    WalWriter,
+    StorageWriter,
+    StorageReader,
+    SchemaLoader,
+    IndexManager,
+)> {
+    metrics.increment_recovery_runs();
+    let started = Instant::now();
+    let (mut wal_writer, mut storage_writer, mut storage_reader, schema_loader, index_manager) =
+        boot_system(config.data_path(), &config.boot_options())
+            .inspect_err(|_| metrics.increment_recovery_failures())?;
+    metrics.observe_recovery_duration(started.elapsed());
+    wal_writer.set_metrics(Arc::clone(metrics));
+
+    // Point lookups go through the block cache when configured; the writer
+    // shares it so every write invalidates stale entries
+    if config.storage_cache_entries > 0 {
+        let cache = Arc::new(
+            BlockCache::new(config.storage_cache_entries).with_metrics(Arc::clone(metrics)),
+        );
+        storage_writer.set_cache(Arc::clone(&cache));
+        storage_reader.set_cache(cache);
+    }
+
+    Ok((
+        wal_writer,
+        storage_writer,
+        storage_reader,
+        schema_loader,
+        index_manager,
+    ))
+}
+
+pub(crate) fn boot_system(
     data_dir: &Path,
+    options: &BootOptions<'_>,
 ) -> CliResult<(
     WalWriter,
     StorageWriter,
@@ -617,6 +1799,7 @@ fn boot_system(
     SchemaLoader,
     IndexManager,
 )> {
+    let tail_recovery = options.tail_recovery;
     use crate::recovery::RecoveryStorage;
 
     // Step 1: Load schemas (required for schema validation during recovery)
@@ -625,26 +1808,64 @@ fn boot_system(
         .load_all()
         .map_err(|e| CliError::boot_failed(format!("Schema load failed: {}", e)))?;
 
+    // Step 1b: Unwrap per-collection data keys. Every collection marked
+    // encrypted must have its tenant key present, or boot halts.
+    let keyring =
+        Arc::new(CollectionKeyring::open(data_dir).map_err(|e| {
+            CliError::boot_failed(format!("Collection keyring open failed: {}", e))
+        })?);
+
+    // Step 1c: Load master keys. A WAL or storage file encrypted at rest
+    // whose master key is missing refuses to open.
+    let master_keys = Arc::new(
+        MasterKeyring::open(data_dir)
+            .map_err(|e| CliError::boot_failed(format!("Master keyring open failed: {}", e)))?,
+    );
+
     // Step 2: Open WAL reader for replay
     let wal_path = data_dir.join("wal").join("wal.log");
     let wal_exists = wal_path.exists();
 
-    // Step 3: Create index manager
-    let indexed_fields: HashSet<String> = HashSet::new();
-    let mut index_manager = IndexManager::new(indexed_fields);
+    // Step 2b: Schemas created or deprecated at runtime are durable in the
+    // WAL; apply them before recovery verifies documents against schemas
+    if wal_exists {
+        let mut ddl_reader = WalReader::open_with_keys(&wal_path, &master_keys)
+            .map_err(|e| CliError::boot_failed(format!("WAL reader open failed: {}", e)))?
+            .with_tail_recovery(tail_recovery);
+        WalReplayer::replay_schema_ddl(&mut ddl_reader, &mut schema_loader)
+            .map_err(|e| CliError::boot_failed(format!("Schema DDL replay failed: {}", e)))?;
+    }
+
+    // Step 3: Create index manager, tracking catalogued indexes and fields
+    // schemas declare unique
+    let indexed_fields: HashSet<String> =
+        schema_loader.index_fields().map(str::to_string).collect();
+    let unique_fields: BTreeSet<String> = schema_loader
+        .all_schemas()
+        .flat_map(|schema| schema.unique_fields())
+        .map(str::to_string)
+        .collect();
+    let mut index_manager = unique_fields.into_iter().fold(
+        IndexManager::new(indexed_fields),
+        IndexManager::with_unique_field,
+    );
 
     // Step 4: Execute RecoveryManager::recover() - MANDATORY
     // This performs: WAL replay -> Index rebuild -> Consistency verification
-    let recovery_manager = RecoveryManager::new(data_dir);
+    let recovery_manager = RecoveryManager::new(data_dir)
+        .with_index_rebuild_threads(options.index_rebuild_threads)
+        .with_verification(options.verification);
 
-    let (storage_writer, storage_reader) = if wal_exists {
+    let (storage_writer, mut storage_reader) = if wal_exists {
         // Open WAL reader
-        let mut wal_reader = WalReader::open(&wal_path)
-            .map_err(|e| CliError::boot_failed(format!("WAL reader open failed: {}", e)))?;
+        let mut wal_reader = WalReader::open_with_keys(&wal_path, &master_keys)
+            .map_err(|e| CliError::boot_failed(format!("WAL reader open failed: {}", e)))?
+            .with_tail_recovery(tail_recovery);
 
         // Open recovery storage (implements both StorageApply + StorageScan)
-        let mut recovery_storage = RecoveryStorage::open(data_dir)
-            .map_err(|e| CliError::boot_failed(format!("Recovery storage open failed: {}", e)))?;
+        let mut recovery_storage = RecoveryStorage::open_with_keys(data_dir, &master_keys)
+            .map_err(|e| CliError::boot_failed(format!("Recovery storage open failed: {}", e)))?
+            .with_keyring(Arc::clone(&keyring));
 
         // Execute full recovery sequence
         // This MUST succeed before we can serve any requests
@@ -676,16 +1897,40 @@ fn boot_system(
         }
 
         // Open storage directly
-        let storage_writer = StorageWriter::open(data_dir)
-            .map_err(|e| CliError::boot_failed(format!("Storage writer open failed: {}", e)))?;
-        let storage_reader = StorageReader::open_from_data_dir(data_dir)
+        let mut storage_writer =
+            StorageWriter::open_with_keys(data_dir, StorageFormat::V1, &master_keys)
+                .map_err(|e| CliError::boot_failed(format!("Storage writer open failed: {}", e)))?;
+        let mut storage_reader = StorageReader::open_with_keys(storage_writer.path(), &master_keys)
             .map_err(|e| CliError::boot_failed(format!("Storage reader open failed: {}", e)))?;
+        storage_writer.set_keyring(Arc::clone(&keyring));
+        storage_reader.set_keyring(Arc::clone(&keyring));
 
         (storage_writer, storage_reader)
     };
 
+    // Step 4b: Restore indexes from the latest index checkpoint, patched
+    // with the records stored after it, or rebuild them from storage
+    recovery_manager
+        .recover_indexes(
+            &mut index_manager,
+            options.collection,
+            &mut IndexStorage::new(&mut storage_reader).with_keyring(keyring),
+        )
+        .map_err(|e| {
+            CliError::boot_failed(format!(
+                "Index recovery failed (FATAL): {}. System cannot serve requests.",
+                e
+            ))
+        })?;
+    // Partitions of dropped collections hold no live documents
+    for name in index_manager.collection_names() {
+        if schema_loader.collection_schema(&name).is_none() {
+            index_manager.drop_collection(&name);
+        }
+    }
+
     // Step 5: Open WAL writer for new writes
-    let wal_writer = WalWriter::open(data_dir)
+    let wal_writer = WalWriter::open_with_keys(data_dir, master_keys)
         .map_err(|e| CliError::boot_failed(format!("WAL writer open failed: {}", e)))?;
 
     // Recovery complete - system may now enter SERVING state
@@ -755,7 +2000,7 @@ mod tests {
         let config_path = create_config(&temp_dir);
 
         // Start without init fails
-        let result = start(&config_path);
+        let result = start(&config_path, &StartOptions::default());
         assert!(result.is_err());
         assert_eq!(result.unwrap_err().code(), &CliErrorCode::NotInitialized);
     }
@@ -777,6 +2022,53 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_config_verification_level() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("aerodb.json");
+        let data_dir = temp_dir.path().join("data");
+        let write = |extra: Value| {
+            let mut config = json!({"data_dir": data_dir.to_string_lossy()});
+            config
+                .as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            fs::write(&config_path, config.to_string()).unwrap();
+        };
+
+        write(json!({}));
+        let config = Config::load(&config_path).unwrap();
+        assert_eq!(config.verification(), VerificationLevel::Full);
+
+        write(json!({"recovery_verification": "sampled", "verification_seed": 9}));
+        let config = Config::load(&config_path).unwrap();
+        assert_eq!(
+            config.verification(),
+            VerificationLevel::Sampled {
+                percent: DEFAULT_SAMPLE_PERCENT,
+                seed: 9
+            }
+        );
+        assert_eq!(config.boot_options().verification, config.verification());
+
+        write(json!({"recovery_verification": "partial"}));
+        assert!(Config::load(&config_path).is_err());
+        write(json!({"recovery_verification": "sampled", "verification_sample_percent": 0}));
+        assert!(Config::load(&config_path).is_err());
+
+        // `start --verify` is validated like the config
+        write(json!({}));
+        let err = start(
+            &config_path,
+            &StartOptions {
+                verify: Some("partial".to_string()),
+                ..StartOptions::default()
+            },
+        )
+        .unwrap_err();
+        assert!(err.message().contains("recovery_verification"));
+    }
+
     #[test]
     fn test_config_defaults() {
         let temp_dir = TempDir::new().unwrap();
@@ -793,5 +2085,322 @@ mod tests {
         assert_eq!(config.max_wal_size_bytes, 1073741824);
         assert_eq!(config.max_memory_bytes, 536870912);
         assert_eq!(config.wal_sync_mode, "fsync");
+        assert_eq!(config.storage_format(), StorageFormat::V1);
+        assert_eq!(config.storage_cache_entries, 0);
+        assert_eq!(config.index_rebuild_threads, 1);
+    }
+
+    #[test]
+    fn test_config_loads_toml() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("aerodb.toml");
+        let data_dir = temp_dir.path().join("data");
+
+        fs::write(
+            &config_path,
+            format!(
+                "data_dir = {:?}\n\n[storage]\nformat_version = 2\n\n[http]\nport = 8080\n",
+                data_dir.to_string_lossy()
+            ),
+        )
+        .unwrap();
+
+        let config = Config::load(&config_path).unwrap();
+        assert_eq!(config.data_path(), data_dir.as_path());
+        assert_eq!(config.storage_format(), StorageFormat::V2);
+        assert_eq!(config.wal_sync_mode, "fsync");
+        assert_eq!(config.subsystems.http_server.port, 8080);
+
+        fs::write(
+            &config_path,
+            "data_dir = \"d\"\nwal_sync_mode = \"fsync\"\n",
+        )
+        .unwrap();
+        let err = Config::load(&config_path).unwrap_err();
+        assert!(err.message().contains("AERO_CONFIG_UNKNOWN_KEY"));
+    }
+
+    #[test]
+    fn test_effective_config_round_trips() {
+        let aero = AeroConfig::parse(
+            "data_dir = \"./data\"\n[wal]\ngroup_commit = true\n[http]\nport = 8080\n",
+            Vec::new(),
+        )
+        .unwrap();
+        let config = Config::from_aero(&aero).unwrap();
+        assert_eq!(config.to_aero(), aero);
+
+        // JSON configs report the same layout, with subsystem defaults
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = create_config(&temp_dir);
+        let effective = Config::load(&config_path).unwrap().to_aero();
+        assert_eq!(effective.wal.sync_mode, "fsync");
+        assert_eq!(effective.http.port, 54321);
+        assert!(config_validate(&config_path).is_ok());
+    }
+
+    #[test]
+    fn test_init_uses_configured_storage_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("aerodb.json");
+        let data_dir = temp_dir.path().join("data");
+
+        let config = json!({
+            "data_dir": data_dir.to_string_lossy(),
+            "storage_format_version": 3
+        });
+        fs::write(&config_path, config.to_string()).unwrap();
+        assert!(Config::load(&config_path).is_err());
+
+        let config = json!({
+            "data_dir": data_dir.to_string_lossy(),
+            "storage_format_version": 2
+        });
+        fs::write(&config_path, config.to_string()).unwrap();
+        init(&config_path).unwrap();
+
+        let (_wal, _writer, reader, _loader, _index) =
+            boot_system(&data_dir, &BootOptions::default()).unwrap();
+        assert_eq!(reader.format(), StorageFormat::V2);
+    }
+
+    #[test]
+    fn test_boot_applies_schema_ddl_from_wal() {
+        use crate::schema::{FieldDef, Schema, SchemaDdl};
+        use crate::wal::{RecordType, WalPayload};
+        use std::collections::HashMap;
+
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = create_config(&temp_dir);
+        let data_dir = temp_dir.path().join("data");
+        init(&config_path).unwrap();
+
+        // A crash after the WAL append leaves no schema file behind
+        let mut fields = HashMap::new();
+        fields.insert("_id".to_string(), FieldDef::required_string());
+        let ddl = SchemaDdl::Create(Schema::new("orders", "v1", fields));
+        {
+            let mut wal = WalWriter::open(&data_dir).unwrap();
+            wal.append(RecordType::SchemaDdl, ddl.to_payload()).unwrap();
+            let payload =
+                WalPayload::new("orders", "o1", "orders", "v1", br#"{"_id":"o1"}"#.to_vec());
+            wal.append(RecordType::Insert, payload).unwrap();
+        }
+
+        // Recovery verifies the document against the replayed schema
+        let (_, _, mut storage_reader, schema_loader, _) =
+            boot_system(&data_dir, &BootOptions::default()).unwrap();
+        assert!(schema_loader.exists("orders", "v1"));
+        storage_reader.reset().unwrap();
+        let record = storage_reader.read_next().unwrap().unwrap();
+        assert_eq!(record.schema_id, "orders");
+        assert!(schema_loader
+            .schema_dir()
+            .join("schema_orders_v1.json")
+            .exists());
+    }
+
+    #[test]
+    fn test_boot_replays_collection_drop() {
+        use crate::schema::{FieldDef, Schema, SchemaDdl};
+        use crate::wal::{RecordType, WalPayload};
+        use std::collections::HashMap;
+
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = create_config(&temp_dir);
+        let data_dir = temp_dir.path().join("data");
+        init(&config_path).unwrap();
+
+        // Only the WAL survives: create, insert, then drop
+        let mut fields = HashMap::new();
+        fields.insert("_id".to_string(), FieldDef::required_string());
+        let create_collection = SchemaDdl::CreateCollection {
+            collection: "archive".to_string(),
+            schema_id: "orders".to_string(),
+        };
+        let drop = SchemaDdl::DropCollection {
+            collection: "archive".to_string(),
+        };
+        {
+            let mut wal = WalWriter::open(&data_dir).unwrap();
+            let schema = SchemaDdl::Create(Schema::new("orders", "v1", fields));
+            wal.append(RecordType::SchemaDdl, schema.to_payload())
+                .unwrap();
+            wal.append(RecordType::SchemaDdl, create_collection.to_payload())
+                .unwrap();
+            let payload =
+                WalPayload::new("archive", "o1", "orders", "v1", br#"{"_id":"o1"}"#.to_vec());
+            wal.append(RecordType::Insert, payload).unwrap();
+            wal.append_batch(vec![
+                (
+                    RecordType::Delete,
+                    WalPayload::tombstone("archive", "o1", "orders", ""),
+                ),
+                (RecordType::SchemaDdl, drop.to_payload()),
+            ])
+            .unwrap();
+        }
+
+        let (_, _, mut storage_reader, schema_loader, _) =
+            boot_system(&data_dir, &BootOptions::default()).unwrap();
+        assert!(schema_loader.collection_schema("archive").is_none());
+        storage_reader.reset().unwrap();
+        let mut last = None;
+        while let Some(record) = storage_reader.read_next().unwrap() {
+            last = Some(record);
+        }
+        let last = last.unwrap();
+        assert_eq!(last.document_id, "archive:o1");
+        assert!(last.is_tombstone);
+    }
+
+    #[test]
+    fn test_boot_declares_catalogued_indexes() {
+        use crate::schema::SchemaDdl;
+        use crate::wal::RecordType;
+
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = create_config(&temp_dir);
+        let data_dir = temp_dir.path().join("data");
+        init(&config_path).unwrap();
+
+        {
+            let mut wal = WalWriter::open(&data_dir).unwrap();
+            let ddl = SchemaDdl::CreateIndex {
+                field: "email".to_string(),
+            };
+            wal.append(RecordType::SchemaDdl, ddl.to_payload()).unwrap();
+        }
+
+        let (_, _, _, schema_loader, index_manager) =
+            boot_system(&data_dir, &BootOptions::default()).unwrap();
+        assert!(schema_loader.index_fields().any(|f| f == "email"));
+        assert!(index_manager.indexed_fields().contains("email"));
+    }
+
+    #[test]
+    fn test_boot_restores_index_checkpoint() {
+        use crate::checkpoint::{CheckpointManager, IndexCapture};
+        use crate::index::INDEX_CHECKPOINT_FILE;
+        use crate::recovery::{IndexRecoveryPath, IndexStorage, RecoveryManager};
+        use crate::schema::{FieldDef, Schema, SchemaDdl};
+        use crate::snapshot::{snapshot_path, GlobalExecutionLock, SnapshotManager};
+        use crate::wal::RecordType;
+        use std::collections::HashMap;
+
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = create_config(&temp_dir);
+        let data_dir = temp_dir.path().join("data");
+        init(&config_path).unwrap();
+        {
+            let mut fields = HashMap::new();
+            fields.insert("_id".to_string(), FieldDef::required_string());
+            let ddl = SchemaDdl::Create(Schema::new("items", "v1", fields));
+            let mut wal = WalWriter::open(&data_dir).unwrap();
+            wal.append(RecordType::SchemaDdl, ddl.to_payload()).unwrap();
+        }
+
+        let handler = ApiHandler::new(DEFAULT_COLLECTION);
+        let run = |booted: &mut (_, _, _, _, _), request: Value| {
+            let (wal_writer, storage_writer, storage_reader, schema_loader, index_manager) = booted;
+            let mut subsystems = Subsystems {
+                schema_loader,
+                wal_writer,
+                storage_writer,
+                storage_reader,
+                index_manager,
+            };
+            let response = handler.handle(&request.to_string(), &mut subsystems);
+            assert!(response.is_success(), "{}", response.to_json());
+        };
+        let insert = |id: &str| {
+            json!({"op": "insert", "schema_id": "items", "schema_version": "v1",
+                   "document": {"_id": id}})
+        };
+
+        let mut booted = boot_system(&data_dir, &BootOptions::default()).unwrap();
+        run(&mut booted, insert("d1"));
+        run(&mut booted, insert("d2"));
+
+        let snapshot_id = {
+            let (wal_writer, _, storage_reader, schema_loader, index_manager) = &mut booted;
+            CheckpointManager::create_checkpoint_with_indexes(
+                &data_dir,
+                &data_dir.join("data").join("documents.dat"),
+                schema_loader.schema_dir(),
+                &SnapshotManager,
+                wal_writer,
+                IndexCapture {
+                    index: index_manager,
+                    collection: DEFAULT_COLLECTION,
+                    storage: &mut IndexStorage::new(storage_reader),
+                    sealed: &|_| false,
+                },
+                &GlobalExecutionLock::new(),
+            )
+            .unwrap()
+        };
+
+        // Written after the checkpoint: patched in from the storage tail
+        run(&mut booted, insert("d3"));
+        let delete = json!({"op": "delete", "schema_id": "items", "document_id": "d1"});
+        run(&mut booted, delete);
+        drop(booted);
+
+        let (_, _, mut storage_reader, _, index_manager) =
+            boot_system(&data_dir, &BootOptions::default()).unwrap();
+        assert!(index_manager.lookup_pk("d1").is_empty());
+        assert_eq!(index_manager.lookup_pk("d2").len(), 1);
+        assert_eq!(index_manager.lookup_pk("d3").len(), 1);
+
+        let recovery = RecoveryManager::new(&data_dir);
+        let mut index = IndexManager::pk_only();
+        let path = recovery
+            .recover_indexes(
+                &mut index,
+                DEFAULT_COLLECTION,
+                &mut IndexStorage::new(&mut storage_reader),
+            )
+            .unwrap();
+        assert!(
+            matches!(path, IndexRecoveryPath::Checkpoint { .. }),
+            "{:?}",
+            path
+        );
+
+        // A corrupt checkpoint falls back to a full rebuild
+        let file = snapshot_path(&data_dir, &snapshot_id).join(INDEX_CHECKPOINT_FILE);
+        let mut bytes = fs::read(&file).unwrap();
+        let last = bytes.len() - 2;
+        bytes[last] ^= 0x01;
+        fs::write(&file, bytes).unwrap();
+
+        let mut index = IndexManager::pk_only();
+        let path = recovery
+            .recover_indexes(
+                &mut index,
+                DEFAULT_COLLECTION,
+                &mut IndexStorage::new(&mut storage_reader),
+            )
+            .unwrap();
+        assert!(
+            matches!(path, IndexRecoveryPath::FullRebuild { .. }),
+            "{:?}",
+            path
+        );
+        assert!(index.lookup_pk("d1").is_empty());
+        assert_eq!(index.lookup_pk("d3").len(), 1);
+
+        // A parallel rebuild over storage ranges matches the sequential one
+        let mut parallel = IndexManager::pk_only();
+        RecoveryManager::new(&data_dir)
+            .with_index_rebuild_threads(3)
+            .recover_indexes(
+                &mut parallel,
+                DEFAULT_COLLECTION,
+                &mut IndexStorage::new(&mut storage_reader),
+            )
+            .unwrap();
+        assert_eq!(parallel, index);
     }
 }
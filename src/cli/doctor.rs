@@ -0,0 +1,336 @@
+//! Offline data directory diagnostics (`aerodb doctor`)
+//!
+//! Inspects a data directory without booting it and reports pass/fail per
+//! subsystem:
+//!
+//! 1. layout — the directories `aerodb init` creates
+//! 2. wal — every record's checksum and the sequence chain
+//! 3. schemas — schema files, catalogs and the schema DDL in the WAL
+//! 4. snapshots — each snapshot's layout, manifest checksums and index
+//!    checkpoint
+//! 5. checkpoint — the checkpoint marker names an existing snapshot
+//! 6. storage — the recovery consistency verifier over document storage
+//!
+//! Every check runs even if an earlier one failed, except storage, which
+//! needs the schemas. Nothing in the data directory is written.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::checkpoint::{marker_path, CheckpointMarker};
+use crate::index::{IndexCheckpoint, INDEX_CHECKPOINT_FILE};
+use crate::recovery::{ConsistencyVerifier, WalReplayer};
+use crate::restore::{validate_snapshot_dir, validate_wal, verify_snapshot_checksums};
+use crate::schema::SchemaLoader;
+use crate::snapshot::{snapshot_path, snapshots_dir};
+use crate::storage::StorageReader;
+use crate::wal::WalReader;
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Passed,
+    Failed,
+    /// Nothing to check, or a prerequisite failed
+    Skipped,
+}
+
+impl CheckStatus {
+    /// Returns the status name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckStatus::Passed => "pass",
+            CheckStatus::Failed => "fail",
+            CheckStatus::Skipped => "skipped",
+        }
+    }
+}
+
+/// Result of a single check
+#[derive(Debug, Clone)]
+pub struct CheckReport {
+    /// Subsystem checked
+    pub subsystem: &'static str,
+    /// Outcome
+    pub status: CheckStatus,
+    /// What was verified, or why the check failed
+    pub detail: String,
+}
+
+/// Health report for a data directory
+#[derive(Debug, Clone)]
+pub struct DoctorReport {
+    /// Data directory inspected
+    pub data_dir: PathBuf,
+    /// Per-subsystem results, in check order
+    pub checks: Vec<CheckReport>,
+}
+
+impl DoctorReport {
+    /// Whether no check failed
+    pub fn healthy(&self) -> bool {
+        self.checks.iter().all(|c| c.status != CheckStatus::Failed)
+    }
+
+    /// Failed checks, in check order
+    pub fn failures(&self) -> impl Iterator<Item = &CheckReport> {
+        self.checks
+            .iter()
+            .filter(|c| c.status == CheckStatus::Failed)
+    }
+
+    /// JSON form for CLI output
+    pub fn to_json(&self) -> Value {
+        json!({
+            "healthy": self.healthy(),
+            "data_dir": self.data_dir.display().to_string(),
+            "checks": self.checks.iter().map(|c| json!({
+                "subsystem": c.subsystem,
+                "status": c.status.as_str(),
+                "detail": c.detail,
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// `Ok(None)` means there was nothing to check
+type CheckResult = Result<Option<String>, String>;
+
+/// Inspect `data_dir` and report its health
+pub fn diagnose(data_dir: &Path) -> DoctorReport {
+    let mut checks = Vec::new();
+    let mut record = |subsystem, result: CheckResult| {
+        let (status, detail) = match result {
+            Ok(Some(detail)) => (CheckStatus::Passed, detail),
+            Ok(None) => (CheckStatus::Skipped, "nothing to check".to_string()),
+            Err(reason) => (CheckStatus::Failed, reason),
+        };
+        checks.push(CheckReport {
+            subsystem,
+            status,
+            detail,
+        });
+    };
+
+    record("layout", check_layout(data_dir));
+    record("wal", check_wal(data_dir));
+    let schemas = load_schemas(data_dir);
+    record(
+        "schemas",
+        schemas
+            .as_ref()
+            .map(|loader| Some(format!("{} schema(s) loaded", loader.all_schemas().count())))
+            .map_err(Clone::clone),
+    );
+    record("snapshots", check_snapshots(data_dir));
+    record("checkpoint", check_checkpoint(data_dir));
+    match &schemas {
+        Ok(loader) => record("storage", check_storage(data_dir, loader)),
+        Err(_) => checks.push(CheckReport {
+            subsystem: "storage",
+            status: CheckStatus::Skipped,
+            detail: "schemas failed to load".to_string(),
+        }),
+    }
+
+    DoctorReport {
+        data_dir: data_dir.to_path_buf(),
+        checks,
+    }
+}
+
+/// The directories `aerodb init` creates
+fn check_layout(data_dir: &Path) -> CheckResult {
+    let required = [
+        data_dir.join("wal"),
+        data_dir.join("data"),
+        data_dir.join("metadata").join("schemas"),
+    ];
+    let missing: Vec<String> = required
+        .iter()
+        .filter(|dir| !dir.is_dir())
+        .map(|dir| dir.display().to_string())
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("missing directories: {}", missing.join(", ")));
+    }
+    Ok(Some("data directory initialized".to_string()))
+}
+
+/// Read every WAL record, validating checksums and the sequence chain
+fn check_wal(data_dir: &Path) -> CheckResult {
+    let wal_path = data_dir.join("wal").join("wal.log");
+    if !wal_path.exists() {
+        return Ok(None);
+    }
+    validate_wal(data_dir).map_err(|e| e.to_string())?;
+
+    let mut reader = WalReader::open(&wal_path).map_err(|e| e.to_string())?;
+    let mut records = 0u64;
+    while reader.read_next().map_err(|e| e.to_string())?.is_some() {
+        records += 1;
+    }
+    Ok(Some(format!(
+        "{} record(s), last sequence {}, {} bytes",
+        records,
+        reader.last_sequence_number(),
+        reader.current_offset()
+    )))
+}
+
+/// Load schema files and catalogs, then apply the schema DDL in the WAL
+fn load_schemas(data_dir: &Path) -> Result<SchemaLoader, String> {
+    let mut loader = SchemaLoader::new(data_dir);
+    loader.load_all().map_err(|e| e.to_string())?;
+
+    let wal_path = data_dir.join("wal").join("wal.log");
+    if wal_path.exists() {
+        let mut reader = WalReader::open(&wal_path).map_err(|e| e.to_string())?;
+        WalReplayer::replay_schema_ddl(&mut reader, &mut loader)
+            .map_err(|e| format!("schema DDL in WAL: {}", e))?;
+    }
+    Ok(loader)
+}
+
+/// Each snapshot's layout, manifest checksums and index checkpoint
+fn check_snapshots(data_dir: &Path) -> CheckResult {
+    let dir = snapshots_dir(data_dir);
+    if !dir.is_dir() {
+        return Ok(None);
+    }
+    let mut snapshots: Vec<PathBuf> = fs::read_dir(&dir)
+        .map_err(|e| format!("{}: {}", dir.display(), e))?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    if snapshots.is_empty() {
+        return Ok(None);
+    }
+    snapshots.sort();
+
+    let mut index_checkpoints = 0;
+    for snapshot in &snapshots {
+        validate_snapshot_dir(snapshot).map_err(|e| e.to_string())?;
+        verify_snapshot_checksums(snapshot).map_err(|e| e.to_string())?;
+
+        let index_checkpoint = snapshot.join(INDEX_CHECKPOINT_FILE);
+        if index_checkpoint.exists() {
+            IndexCheckpoint::read_from(&index_checkpoint).map_err(|e| e.to_string())?;
+            index_checkpoints += 1;
+        }
+    }
+    Ok(Some(format!(
+        "{} snapshot(s) verified, {} with index checkpoint",
+        snapshots.len(),
+        index_checkpoints
+    )))
+}
+
+/// The checkpoint marker parses and names an existing snapshot
+fn check_checkpoint(data_dir: &Path) -> CheckResult {
+    let path = marker_path(data_dir);
+    if !CheckpointMarker::exists(&path) {
+        return Ok(None);
+    }
+    let marker = CheckpointMarker::read_from_file(&path).map_err(|e| e.to_string())?;
+    if !snapshot_path(data_dir, &marker.snapshot_id).is_dir() {
+        return Err(format!(
+            "checkpoint marker names missing snapshot {}",
+            marker.snapshot_id
+        ));
+    }
+    Ok(Some(format!(
+        "snapshot {}, WAL truncated: {}",
+        marker.snapshot_id, marker.wal_truncated
+    )))
+}
+
+/// Run the recovery consistency verifier over document storage
+fn check_storage(data_dir: &Path, schemas: &SchemaLoader) -> CheckResult {
+    if !data_dir.join("data").join("documents.dat").exists() {
+        return Ok(None);
+    }
+    let mut reader = StorageReader::open_from_data_dir(data_dir).map_err(|e| e.to_string())?;
+    let stats = ConsistencyVerifier::verify(&mut reader, schemas).map_err(|e| e.to_string())?;
+    Ok(Some(format!(
+        "{} record(s) verified, {} live, {} tombstone(s)",
+        stats.records_verified, stats.live_documents, stats.tombstones
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkpoint::CheckpointManager;
+    use crate::snapshot::{GlobalExecutionLock, SnapshotManager};
+    use crate::wal::{WalPayload, WalWriter};
+    use tempfile::TempDir;
+
+    fn status(report: &DoctorReport, subsystem: &str) -> CheckStatus {
+        report
+            .checks
+            .iter()
+            .find(|c| c.subsystem == subsystem)
+            .unwrap()
+            .status
+    }
+
+    #[test]
+    fn test_doctor_reports_healthy_and_corrupt_data_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        super::super::commands::create_data_dirs(data_dir).unwrap();
+
+        let report = diagnose(data_dir);
+        assert!(report.healthy(), "{}", report.to_json());
+        assert_eq!(status(&report, "wal"), CheckStatus::Skipped);
+
+        {
+            let mut wal = WalWriter::open(data_dir).unwrap();
+            wal.append_insert(WalPayload::new("c", "d1", "s", "v1", b"{}".to_vec()))
+                .unwrap();
+            wal.append_insert(WalPayload::new("c", "d2", "s", "v1", b"{}".to_vec()))
+                .unwrap();
+            fs::write(data_dir.join("data").join("documents.dat"), b"").unwrap();
+            CheckpointManager::create_checkpoint(
+                data_dir,
+                &data_dir.join("data").join("documents.dat"),
+                &data_dir.join("metadata").join("schemas"),
+                &SnapshotManager,
+                &mut wal,
+                &GlobalExecutionLock::new(),
+            )
+            .unwrap();
+            wal.append_insert(WalPayload::new("c", "d3", "s", "v1", b"{}".to_vec()))
+                .unwrap();
+        }
+
+        let report = diagnose(data_dir);
+        assert!(report.healthy(), "{}", report.to_json());
+        for subsystem in ["wal", "snapshots", "checkpoint"] {
+            assert_eq!(status(&report, subsystem), CheckStatus::Passed);
+        }
+
+        // A flipped WAL byte and a tampered snapshot both fail
+        let wal_path = data_dir.join("wal").join("wal.log");
+        let mut bytes = fs::read(&wal_path).unwrap();
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 0xFF;
+        fs::write(&wal_path, bytes).unwrap();
+        let snapshot = fs::read_dir(snapshots_dir(data_dir))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        fs::write(snapshot.join("storage.dat"), b"tampered").unwrap();
+
+        let report = diagnose(data_dir);
+        assert!(!report.healthy());
+        let failed: Vec<&str> = report.failures().map(|c| c.subsystem).collect();
+        assert_eq!(failed, vec!["wal", "snapshots"]);
+        assert_eq!(report.to_json()["healthy"], false);
+    }
+}
@@ -18,6 +18,16 @@ pub enum CliErrorCode {
     NotInitialized,
     /// Boot failed
     BootFailed,
+    /// Data directory locked by a running server
+    AlreadyRunning,
+    /// No server is running on the data directory
+    NotRunning,
+    /// Self-test stage failed
+    SelftestFailed,
+    /// Doctor found an unhealthy subsystem
+    DoctorFailed,
+    /// Dump stopped at an unreadable record
+    DumpFailed,
 }
 
 impl CliErrorCode {
@@ -29,6 +39,11 @@ impl CliErrorCode {
             Self::AlreadyInitialized => "AERO_CLI_ALREADY_INITIALIZED",
             Self::NotInitialized => "AERO_CLI_NOT_INITIALIZED",
             Self::BootFailed => "AERO_CLI_BOOT_FAILED",
+            Self::AlreadyRunning => "AERO_CLI_ALREADY_RUNNING",
+            Self::NotRunning => "AERO_CLI_NOT_RUNNING",
+            Self::SelftestFailed => "AERO_CLI_SELFTEST_FAILED",
+            Self::DoctorFailed => "AERO_CLI_DOCTOR_FAILED",
+            Self::DumpFailed => "AERO_CLI_DUMP_FAILED",
         }
     }
 }
@@ -80,6 +95,31 @@ impl CliError {
         Self::new(CliErrorCode::BootFailed, msg)
     }
 
+    /// Data directory locked by a running server
+    pub fn already_running(msg: impl Into<String>) -> Self {
+        Self::new(CliErrorCode::AlreadyRunning, msg)
+    }
+
+    /// No server is running on the data directory
+    pub fn not_running(msg: impl Into<String>) -> Self {
+        Self::new(CliErrorCode::NotRunning, msg)
+    }
+
+    /// Self-test failed
+    pub fn selftest_failed(msg: impl Into<String>) -> Self {
+        Self::new(CliErrorCode::SelftestFailed, msg)
+    }
+
+    /// Doctor found an unhealthy subsystem
+    pub fn doctor_failed(msg: impl Into<String>) -> Self {
+        Self::new(CliErrorCode::DoctorFailed, msg)
+    }
+
+    /// Dump stopped at an unreadable record
+    pub fn dump_failed(msg: impl Into<String>) -> Self {
+        Self::new(CliErrorCode::DumpFailed, msg)
+    }
+
     /// Get the error code
     pub fn code(&self) -> &CliErrorCode {
         &self.code
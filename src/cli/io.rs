@@ -4,13 +4,68 @@
 //! - Input: single JSON object via stdin
 //! - Output: single JSON object via stdout
 //! - UTF-8 only
+//!
+//! Responses are rendered in the output format chosen with `--format`:
+//! - json: the response object on one line (the default)
+//! - ndjson: the response data, one line per element of an array result
+//! - table: the response data as an aligned text table
 
+use std::collections::BTreeSet;
 use std::io::{self, BufRead, Write};
+use std::sync::OnceLock;
 
-use serde_json::Value;
+use serde_json::{json, Map, Value};
 
 use super::errors::{CliError, CliResult};
 
+/// Output format selected for this process
+static OUTPUT_FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// How responses are written to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// The response object, one per line
+    #[default]
+    Json,
+    /// The response data, one JSON value per line
+    Ndjson,
+    /// The response data as an aligned text table
+    Table,
+}
+
+impl OutputFormat {
+    /// Parse a `--format` value
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(OutputFormat::Json),
+            "ndjson" => Some(OutputFormat::Ndjson),
+            "table" => Some(OutputFormat::Table),
+            _ => None,
+        }
+    }
+
+    /// `--format` value of the format
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Ndjson => "ndjson",
+            OutputFormat::Table => "table",
+        }
+    }
+
+    /// The format selected for this process, `Json` if none was
+    pub fn current() -> Self {
+        OUTPUT_FORMAT.get().copied().unwrap_or_default()
+    }
+
+    /// Select the format for this process.
+    ///
+    /// Only the first call has an effect.
+    pub fn select(self) {
+        let _ = OUTPUT_FORMAT.set(self);
+    }
+}
+
 /// Read a JSON request from stdin
 pub fn read_request() -> CliResult<Value> {
     let stdin = io::stdin();
@@ -40,40 +95,215 @@ pub fn read_requests() -> impl Iterator<Item = CliResult<Value>> {
 
 /// Write a success response to stdout
 pub fn write_response(data: Value) -> CliResult<()> {
-    let response = serde_json::json!({
+    write_rendered(&json!({
         "status": "ok",
         "data": data
-    });
-
-    let mut stdout = io::stdout();
-    serde_json::to_writer(&mut stdout, &response)?;
-    writeln!(stdout)?;
-    stdout.flush()?;
-
-    Ok(())
+    }))
 }
 
 /// Write an error response to stdout
 pub fn write_error(code: &str, message: &str) -> CliResult<()> {
-    let response = serde_json::json!({
+    write_rendered(&json!({
         "status": "error",
         "code": code,
         "message": message
-    });
+    }))
+}
 
-    let mut stdout = io::stdout();
-    serde_json::to_writer(&mut stdout, &response)?;
-    writeln!(stdout)?;
-    stdout.flush()?;
+/// Write a JSON response string to stdout
+pub fn write_json(json_str: &str) -> CliResult<()> {
+    match serde_json::from_str(json_str) {
+        Ok(response) => write_rendered(&response),
+        Err(_) => write_line(json_str),
+    }
+}
 
-    Ok(())
+fn write_rendered(response: &Value) -> CliResult<()> {
+    write_line(&render(response, OutputFormat::current()))
 }
 
-/// Write a raw JSON string to stdout
-pub fn write_json(json_str: &str) -> CliResult<()> {
+fn write_line(text: &str) -> CliResult<()> {
     let mut stdout = io::stdout();
-    writeln!(stdout, "{}", json_str)?;
+    writeln!(stdout, "{}", text)?;
     stdout.flush()?;
 
     Ok(())
 }
+
+/// Render a response object in `format`, without a trailing newline
+///
+/// Error responses render as an error line in the table format and as the
+/// response object otherwise.
+pub fn render(response: &Value, format: OutputFormat) -> String {
+    let data = match response.get("status").and_then(Value::as_str) {
+        Some("ok") => response.get("data").unwrap_or(&Value::Null),
+        Some("error") if format == OutputFormat::Table => {
+            return format!(
+                "error {}: {}",
+                response["code"].as_str().unwrap_or_default(),
+                response["message"].as_str().unwrap_or_default()
+            );
+        }
+        _ => return response.to_string(),
+    };
+
+    match format {
+        OutputFormat::Json => response.to_string(),
+        OutputFormat::Ndjson => match data {
+            Value::Array(items) => items
+                .iter()
+                .map(Value::to_string)
+                .collect::<Vec<_>>()
+                .join("\n"),
+            data => data.to_string(),
+        },
+        OutputFormat::Table => render_table(data),
+    }
+}
+
+/// Arrays of objects render one row per element with a column per field;
+/// objects render one row per field; scalars render as they are.
+fn render_table(data: &Value) -> String {
+    match data {
+        Value::Array(items) => {
+            let mut header = Vec::new();
+            let mut seen = BTreeSet::new();
+            for item in items {
+                match item {
+                    Value::Object(fields) => {
+                        for field in fields.keys() {
+                            if seen.insert(field.as_str()) {
+                                header.push(field.clone());
+                            }
+                        }
+                    }
+                    _ => {
+                        if seen.insert("value") {
+                            header.push("value".to_string());
+                        }
+                    }
+                }
+            }
+            let rows: Vec<Vec<String>> = items
+                .iter()
+                .map(|item| {
+                    header
+                        .iter()
+                        .map(|column| match item {
+                            Value::Object(fields) => fields.get(column).map(cell),
+                            item => (column == "value").then(|| cell(item)),
+                        })
+                        .map(Option::unwrap_or_default)
+                        .collect()
+                })
+                .collect();
+            let footer = match rows.len() {
+                1 => "(1 row)".to_string(),
+                n => format!("({} rows)", n),
+            };
+            if rows.is_empty() {
+                return footer;
+            }
+            format!("{}\n{}", aligned(&header, &rows), footer)
+        }
+        Value::Object(fields) => aligned(
+            &["field".to_string(), "value".to_string()],
+            &fields_rows(fields),
+        ),
+        scalar => cell(scalar),
+    }
+}
+
+fn fields_rows(fields: &Map<String, Value>) -> Vec<Vec<String>> {
+    fields
+        .iter()
+        .map(|(field, value)| vec![field.clone(), cell(value)])
+        .collect()
+}
+
+/// Strings without quotes, null as empty, anything else as compact JSON
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Columns padded to their widest cell, with a rule under the header
+fn aligned(header: &[String], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+
+    let line = |cells: &[String]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(value, &width)| format!("{:<width$}", value, width = width))
+            .collect();
+        padded.join(" | ").trim_end().to_string()
+    };
+    let rule: Vec<String> = widths.iter().map(|&width| "-".repeat(width)).collect();
+
+    let mut lines = vec![line(header), rule.join("-+-")];
+    lines.extend(rows.iter().map(|row| line(row)));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_formats() {
+        let response = json!({
+            "status": "ok",
+            "data": [
+                {"_id": "u1", "name": "Alice", "age": 30},
+                {"_id": "u2", "name": "Bob", "tags": ["a"]}
+            ]
+        });
+        assert_eq!(render(&response, OutputFormat::Json), response.to_string());
+        assert_eq!(
+            render(&response, OutputFormat::Ndjson),
+            format!("{}\n{}", response["data"][0], response["data"][1])
+        );
+        assert_eq!(
+            render(&response, OutputFormat::Table),
+            [
+                "_id | age | name  | tags",
+                "----+-----+-------+------",
+                "u1  | 30  | Alice |",
+                "u2  |     | Bob   | [\"a\"]",
+                "(2 rows)",
+            ]
+            .join("\n")
+        );
+
+        let plan = json!({"status": "ok", "data": {"scan_type": "FullScan", "limit": 10}});
+        assert_eq!(
+            render(&plan, OutputFormat::Table),
+            "field     | value\n----------+---------\nlimit     | 10\nscan_type | FullScan"
+        );
+
+        let error = json!({"status": "error", "code": "AERO_X", "message": "bad"});
+        assert_eq!(render(&error, OutputFormat::Table), "error AERO_X: bad");
+        assert_eq!(render(&error, OutputFormat::Ndjson), error.to_string());
+    }
+
+    #[test]
+    fn test_output_format_names() {
+        for format in [
+            OutputFormat::Json,
+            OutputFormat::Ndjson,
+            OutputFormat::Table,
+        ] {
+            assert_eq!(OutputFormat::from_name(format.name()), Some(format));
+        }
+        assert_eq!(OutputFormat::from_name("yaml"), None);
+    }
+}
@@ -0,0 +1,463 @@
+//! Server process lifecycle for `aerodb start`
+//!
+//! - The data directory lock (`.lock`) and pid file (`aerodb.pid`) are held
+//!   for as long as the server runs; ownership is an OS advisory lock on
+//!   `.lock`, which the OS drops when the owner exits
+//! - SIGTERM and SIGINT reach the serving loop as a shutdown request
+//! - SIGHUP reaches it as a request to reload the observability settings
+//! - `--daemon` relaunches the server detached from the terminal
+//!
+//! Every shutdown step is emitted as an observability event.
+
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::api::Response;
+use crate::observability::{Event, Logger, Severity};
+
+use super::errors::{CliError, CliResult};
+use super::io::read_requests;
+
+/// Lock file marking a data directory as in use by a running server
+pub const LOCK_FILE: &str = ".lock";
+
+/// File holding the pid of the server that owns the lock
+pub const PID_FILE: &str = "aerodb.pid";
+
+/// File a daemonized server writes its stdout and stderr to
+pub const DAEMON_LOG_FILE: &str = "aerodb.out";
+
+/// How often `daemonize` checks whether the child has taken the lock
+const DAEMON_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Emit a lifecycle event on stderr, keeping stdout for responses
+pub fn emit(severity: Severity, event: Event, fields: &[(&str, &str)]) {
+    Logger::log_stderr(severity, event.as_str(), fields);
+}
+
+/// Exclusive ownership of a data directory by this process
+///
+/// The lock and pid files are removed by `release`, or on drop when the
+/// server stops without a clean shutdown. The advisory lock on `.lock` is
+/// held until then.
+#[derive(Debug)]
+pub struct ProcessLock {
+    lock_path: PathBuf,
+    pid_path: PathBuf,
+    /// Open `.lock`, holding the advisory lock
+    _lock_file: File,
+    released: bool,
+}
+
+impl ProcessLock {
+    /// Take the lock on `data_dir` and write this process's pid file.
+    ///
+    /// Ownership is an exclusive advisory lock on `.lock`, taken without
+    /// blocking, so two servers can never both succeed. A `.lock` left
+    /// behind by a process that died holds no advisory lock and is taken
+    /// over. A lock held by another process fails with
+    /// `AERO_CLI_ALREADY_RUNNING`, whether or not its pid can be read.
+    pub fn acquire(data_dir: &Path) -> CliResult<Self> {
+        let lock_path = data_dir.join(LOCK_FILE);
+        let pid_path = data_dir.join(PID_FILE);
+
+        let mut lock_file = loop {
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&lock_path)
+                .map_err(|e| {
+                    CliError::io_error(format!(
+                        "Failed to open lock file {}: {}",
+                        lock_path.display(),
+                        e
+                    ))
+                })?;
+            match file.try_lock() {
+                Ok(()) => {}
+                Err(TryLockError::WouldBlock) => {
+                    let owner = read_pid(&lock_path)
+                        .or_else(|| read_pid(&pid_path))
+                        .map_or_else(|| "unknown".to_string(), |p| p.to_string());
+                    return Err(CliError::already_running(format!(
+                        "Data directory is locked by running process {} ({})",
+                        owner,
+                        lock_path.display()
+                    )));
+                }
+                Err(TryLockError::Error(e)) => {
+                    return Err(CliError::io_error(format!(
+                        "Failed to lock {}: {}",
+                        lock_path.display(),
+                        e
+                    )))
+                }
+            }
+            // The previous owner removes `.lock` on release; a lock taken on
+            // the file it removed does not own the data directory
+            if is_same_file(&file, &lock_path) {
+                break file;
+            }
+        };
+
+        if let Some(stale) = read_pid(&lock_path) {
+            emit(
+                Severity::Warn,
+                Event::LockStale,
+                &[("pid", &stale.to_string())],
+            );
+        }
+
+        let pid = std::process::id().to_string();
+        let written = lock_file
+            .set_len(0)
+            .and_then(|()| lock_file.write_all(pid.as_bytes()))
+            .and_then(|()| fs::write(&pid_path, &pid));
+        if let Err(e) = written {
+            let _ = fs::remove_file(&lock_path);
+            return Err(CliError::io_error(format!(
+                "Failed to write pid file {}: {}",
+                pid_path.display(),
+                e
+            )));
+        }
+        emit(Severity::Info, Event::LockAcquired, &[("pid", &pid)]);
+
+        Ok(Self {
+            lock_path,
+            pid_path,
+            _lock_file: lock_file,
+            released: false,
+        })
+    }
+
+    /// Path of the pid file
+    pub fn pid_path(&self) -> &Path {
+        &self.pid_path
+    }
+
+    /// Remove the pid file, then the lock file
+    pub fn release(mut self) -> CliResult<()> {
+        self.released = true;
+        fs::remove_file(&self.pid_path)?;
+        fs::remove_file(&self.lock_path)?;
+        emit(
+            Severity::Info,
+            Event::LockReleased,
+            &[("pid", &std::process::id().to_string())],
+        );
+        Ok(())
+    }
+}
+
+impl Drop for ProcessLock {
+    fn drop(&mut self) {
+        if !self.released {
+            let _ = fs::remove_file(&self.pid_path);
+            let _ = fs::remove_file(&self.lock_path);
+        }
+    }
+}
+
+/// Read the pid recorded in a pid file
+pub fn read_pid(pid_path: &Path) -> Option<u32> {
+    fs::read_to_string(pid_path).ok()?.trim().parse().ok()
+}
+
+/// Whether `file` is still the file at `path`
+#[cfg(unix)]
+fn is_same_file(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(open), Ok(current)) => open.dev() == current.dev() && open.ino() == current.ino(),
+        _ => false,
+    }
+}
+
+/// Whether `file` is still the file at `path`
+///
+/// Off unix an open file cannot be removed, so it always is.
+#[cfg(not(unix))]
+fn is_same_file(_file: &File, _path: &Path) -> bool {
+    true
+}
+
+/// Whether a process with `pid` exists.
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // Pid 0 and negative pids name process groups, not a process
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    if pid <= 0 {
+        return false;
+    }
+    // SAFETY: signal 0 performs only the existence and permission checks
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+    std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Whether a process with `pid` exists.
+///
+/// Off unix there is no portable check, so every pid counts as alive.
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+/// Input to the serving loop
+#[derive(Debug)]
+pub enum ServeInput {
+    /// A request line read from stdin
+    Request(CliResult<Value>),
+    /// A request from a SQL client; the response is sent back on the sender
+    Query(Value, Sender<Response>),
+    /// Stdin reached end of input
+    EndOfInput,
+    /// SIGHUP arrived: reload the observability settings
+    Reload,
+    /// A shutdown signal arrived
+    Shutdown(&'static str),
+}
+
+/// Channel carrying requests and shutdown signals to the serving loop.
+///
+/// Requests are read from stdin on a reader thread unless `read_stdin` is
+/// false; a detached server has no stdin and runs until signalled. The
+/// returned sender lets other listeners submit requests.
+pub fn serve_inputs(read_stdin: bool) -> CliResult<(Sender<ServeInput>, Receiver<ServeInput>)> {
+    let (tx, rx) = mpsc::channel();
+    install_signal_handlers(tx.clone())?;
+    let submit = tx.clone();
+
+    if read_stdin {
+        thread::Builder::new()
+            .name("aerodb-stdin".to_string())
+            .spawn(move || {
+                for request in read_requests() {
+                    if tx.send(ServeInput::Request(request)).is_err() {
+                        return;
+                    }
+                }
+                let _ = tx.send(ServeInput::EndOfInput);
+            })?;
+    }
+
+    Ok((submit, rx))
+}
+
+/// Deliver every SIGHUP to `tx` as `ServeInput::Reload`, and the first
+/// SIGTERM or SIGINT as `ServeInput::Shutdown`
+fn install_signal_handlers(tx: Sender<ServeInput>) -> CliResult<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+
+    #[cfg(unix)]
+    let (mut terminate, mut hangup) = runtime.block_on(async {
+        use tokio::signal::unix::{signal, SignalKind};
+        Ok::<_, std::io::Error>((
+            signal(SignalKind::terminate())?,
+            signal(SignalKind::hangup())?,
+        ))
+    })?;
+
+    thread::Builder::new()
+        .name("aerodb-signals".to_string())
+        .spawn(move || loop {
+            let signal = runtime.block_on(async {
+                #[cfg(unix)]
+                {
+                    tokio::select! {
+                        _ = tokio::signal::ctrl_c() => "SIGINT",
+                        _ = terminate.recv() => "SIGTERM",
+                        _ = hangup.recv() => "SIGHUP",
+                    }
+                }
+                #[cfg(not(unix))]
+                {
+                    let _ = tokio::signal::ctrl_c().await;
+                    "SIGINT"
+                }
+            });
+            if signal == "SIGHUP" {
+                if tx.send(ServeInput::Reload).is_err() {
+                    return;
+                }
+                continue;
+            }
+            let _ = tx.send(ServeInput::Shutdown(signal));
+            return;
+        })?;
+
+    Ok(())
+}
+
+/// Ask the server holding `data_dir` to reload its observability
+/// settings by sending it SIGHUP. Returns the server's pid.
+pub fn send_reload(data_dir: &Path) -> CliResult<u32> {
+    let pid_path = data_dir.join(PID_FILE);
+    let pid = read_pid(&pid_path)
+        .filter(|pid| process_alive(*pid))
+        .ok_or_else(|| {
+            CliError::not_running(format!(
+                "No server is running on {} ({} missing or stale)",
+                data_dir.display(),
+                pid_path.display()
+            ))
+        })?;
+
+    if !cfg!(unix) {
+        return Err(CliError::io_error(
+            "Signalling the server is only supported on unix",
+        ));
+    }
+    let status = Command::new("kill")
+        .args(["-HUP", &pid.to_string()])
+        .status()?;
+    if !status.success() {
+        return Err(CliError::not_running(format!(
+            "Failed to signal server process {} ({})",
+            pid, status
+        )));
+    }
+    Ok(pid)
+}
+
+/// Relaunch `aerodb start` detached from the terminal.
+///
+/// The child runs with `--detached`: no stdin, stdout and stderr appended
+/// to `aerodb.out` in the data directory, and its own process group so
+/// terminal signals do not reach it. Returns the child's pid once it has
+/// taken the data directory lock.
+pub fn daemonize(data_dir: &Path, start_args: &[String]) -> CliResult<u32> {
+    let exe = std::env::current_exe()?;
+    let log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(data_dir.join(DAEMON_LOG_FILE))?;
+
+    let mut command = Command::new(exe);
+    command
+        .arg("start")
+        .args(start_args)
+        .arg("--detached")
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+
+    let mut child = command.spawn()?;
+    let pid = child.id();
+    let pid_path = data_dir.join(PID_FILE);
+    loop {
+        if read_pid(&pid_path) == Some(pid) {
+            return Ok(pid);
+        }
+        if let Some(status) = child.try_wait()? {
+            return Err(CliError::boot_failed(format!(
+                "Daemon exited before taking the data directory lock ({}). See {}.",
+                status,
+                data_dir.join(DAEMON_LOG_FILE).display()
+            )));
+        }
+        thread::sleep(DAEMON_POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::errors::CliErrorCode;
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lock_writes_and_removes_pid_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let lock = ProcessLock::acquire(temp_dir.path()).unwrap();
+
+        assert!(temp_dir.path().join(LOCK_FILE).exists());
+        assert_eq!(read_pid(lock.pid_path()), Some(std::process::id()));
+
+        lock.release().unwrap();
+        assert!(!temp_dir.path().join(LOCK_FILE).exists());
+        assert!(!temp_dir.path().join(PID_FILE).exists());
+    }
+
+    #[test]
+    fn test_lock_rejects_live_owner() {
+        let temp_dir = TempDir::new().unwrap();
+        let _lock = ProcessLock::acquire(temp_dir.path()).unwrap();
+
+        let err = ProcessLock::acquire(temp_dir.path()).unwrap_err();
+        assert_eq!(err.code(), &CliErrorCode::AlreadyRunning);
+    }
+
+    #[test]
+    fn test_lock_takes_over_stale_lock() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join(LOCK_FILE), u32::MAX.to_string()).unwrap();
+        fs::write(temp_dir.path().join(PID_FILE), u32::MAX.to_string()).unwrap();
+
+        let lock = ProcessLock::acquire(temp_dir.path()).unwrap();
+        assert_eq!(read_pid(lock.pid_path()), Some(std::process::id()));
+        assert_eq!(
+            read_pid(&temp_dir.path().join(LOCK_FILE)),
+            Some(std::process::id())
+        );
+    }
+
+    #[test]
+    fn test_lock_held_by_unknown_owner_is_not_taken_over() {
+        let temp_dir = TempDir::new().unwrap();
+        let lock_path = temp_dir.path().join(LOCK_FILE);
+        let held = File::create(&lock_path).unwrap();
+        held.try_lock().unwrap();
+
+        // No pid anywhere: the owner is unknown but still holds the lock
+        let err = ProcessLock::acquire(temp_dir.path()).unwrap_err();
+        assert_eq!(err.code(), &CliErrorCode::AlreadyRunning);
+        assert!(!temp_dir.path().join(PID_FILE).exists());
+
+        drop(held);
+        assert!(ProcessLock::acquire(temp_dir.path()).is_ok());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_process_alive() {
+        assert!(process_alive(std::process::id()));
+        assert!(!process_alive(0));
+        assert!(!process_alive(u32::MAX));
+    }
+
+    #[test]
+    fn test_reload_without_server_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let err = send_reload(temp_dir.path()).unwrap_err();
+        assert_eq!(err.code(), &CliErrorCode::NotRunning);
+    }
+
+    #[test]
+    fn test_dropped_lock_is_removed() {
+        let temp_dir = TempDir::new().unwrap();
+        drop(ProcessLock::acquire(temp_dir.path()).unwrap());
+        assert!(!temp_dir.path().join(LOCK_FILE).exists());
+    }
+}
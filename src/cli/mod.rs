@@ -2,16 +2,38 @@
 //!
 //! Provides command-line interface for:
 //! - init: Create directory structure
-//! - start: Boot system and enter serving loop
+//! - start: Boot system and enter serving loop, optionally as a daemon
+//! - shell: Interactive request loop over a warm data directory
 //! - query: One-shot query execution
 //! - explain: One-shot explain execution
+//! - sandbox: Disposable schema sandbox seeded from the latest snapshot
+//! - selftest: End-to-end acceptance run against a scratch data directory
+//! - doctor: Offline health report for a data directory
+//! - config validate / config dump: Check a config file or print the effective config
+//! - wal dump / storage dump: Print WAL or storage records as JSON lines
 
 mod args;
 mod commands;
+mod doctor;
+mod dump;
 mod errors;
 mod io;
+mod lifecycle;
+mod selftest;
+mod shell;
 
 pub use args::{Cli, Command};
-pub use commands::{explain, init, query, run, run_command, start};
-pub use errors::{CliError, CliResult};
-pub use io::{read_request, write_error, write_response};
+pub(crate) use commands::{
+    boot_system, create_data_dirs, is_initialized, BootOptions, DEFAULT_COLLECTION,
+};
+pub use commands::{
+    config_dump, config_validate, doctor, explain, init, query, run, run_command, sandbox,
+    selftest, shell, start, storage_dump, wal_dump, StartOptions,
+};
+pub use doctor::{diagnose, CheckReport, CheckStatus, DoctorReport};
+pub use dump::{dump_storage, dump_wal, WalDumpFilter};
+pub use errors::{CliError, CliErrorCode, CliResult};
+pub use io::{read_request, render, write_error, write_response, OutputFormat};
+pub use lifecycle::{ProcessLock, DAEMON_LOG_FILE, LOCK_FILE, PID_FILE};
+pub use selftest::{SelfTestReport, StageReport, StageStatus};
+pub use shell::{Shell, PROMPT};
@@ -0,0 +1,596 @@
+//! End-to-end acceptance self-test (`aerodb selftest`)
+//!
+//! Runs the full vertical against a temporary data directory and reports
+//! pass/fail per stage:
+//!
+//! 1. init — create the data directory layout
+//! 2. schema_registration — write a schema file and load it at boot
+//! 3. writes — insert, insert_many, update, delete and a transaction
+//! 4. queries — primary-key query, explain and get through the API handler
+//! 5. checkpoint — snapshot + WAL truncation
+//! 6. backup — archive the snapshot and the WAL tail written after it
+//! 7. crash_restart — append to the WAL without applying to storage, then
+//!    drop every handle without a clean shutdown marker
+//! 8. recovery — boot again; the WAL-only write must be replayed
+//! 9. restore — restore the backup over the data directory and verify it
+//!
+//! Stages run in order. After a failure the remaining stages are skipped.
+//! The scratch directory is removed afterwards unless `keep` is set. The
+//! configured data directory is never touched.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::api::{ApiHandler, Subsystems};
+use crate::backup::BackupManager;
+use crate::checkpoint::{CheckpointManager, IndexCapture};
+use crate::index::IndexManager;
+use crate::recovery::IndexStorage;
+use crate::restore::RestoreManager;
+use crate::schema::{FieldDef, Schema, SchemaLoader};
+use crate::snapshot::{GlobalExecutionLock, SnapshotManager};
+use crate::storage::{CollectionKeyring, StorageReader, StorageResult, StorageWriter};
+use crate::wal::{RecordType, TailRecovery, WalPayload, WalReader, WalWriter};
+
+use super::commands::{boot_system, create_data_dirs, BootOptions};
+
+/// Collection and schema used by the self-test
+const SELFTEST_SCHEMA: &str = "selftest";
+const SELFTEST_VERSION: &str = "v1";
+
+/// Outcome of a single stage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageStatus {
+    Passed,
+    Failed,
+    /// Not run because an earlier stage failed
+    Skipped,
+}
+
+impl StageStatus {
+    /// Returns the status name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StageStatus::Passed => "pass",
+            StageStatus::Failed => "fail",
+            StageStatus::Skipped => "skipped",
+        }
+    }
+}
+
+/// Result of a single stage
+#[derive(Debug, Clone)]
+pub struct StageReport {
+    /// Stage name
+    pub stage: &'static str,
+    /// Outcome
+    pub status: StageStatus,
+    /// What was verified, or why the stage failed
+    pub detail: String,
+    /// Wall-clock duration in milliseconds
+    pub duration_ms: u64,
+}
+
+/// Self-test report
+#[derive(Debug, Clone)]
+pub struct SelfTestReport {
+    /// Scratch data directory used by the run
+    pub data_dir: PathBuf,
+    /// Whether the scratch directory was kept
+    pub kept: bool,
+    /// Per-stage results, in run order
+    pub stages: Vec<StageReport>,
+}
+
+impl SelfTestReport {
+    /// Whether every stage passed
+    pub fn passed(&self) -> bool {
+        self.stages.iter().all(|s| s.status == StageStatus::Passed)
+    }
+
+    /// First failed stage, if any
+    pub fn first_failure(&self) -> Option<&StageReport> {
+        self.stages.iter().find(|s| s.status == StageStatus::Failed)
+    }
+
+    /// JSON form for CLI output
+    pub fn to_json(&self) -> Value {
+        json!({
+            "passed": self.passed(),
+            "data_dir": self.data_dir.display().to_string(),
+            "kept": self.kept,
+            "stages": self.stages.iter().map(|s| json!({
+                "stage": s.stage,
+                "status": s.status.as_str(),
+                "detail": s.detail,
+                "duration_ms": s.duration_ms,
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+type StageResult = Result<String, String>;
+type Stage = fn(&mut SelfTest) -> StageResult;
+
+/// Stages in run order
+const STAGES: [(&str, Stage); 9] = [
+    ("init", SelfTest::init),
+    ("schema_registration", SelfTest::schema_registration),
+    ("writes", SelfTest::writes),
+    ("queries", SelfTest::queries),
+    ("checkpoint", SelfTest::checkpoint),
+    ("backup", SelfTest::backup),
+    ("crash_restart", SelfTest::crash_restart),
+    ("recovery", SelfTest::recovery),
+    ("restore", SelfTest::restore),
+];
+
+/// Subsystems returned by `boot_system`
+struct Booted {
+    wal_writer: WalWriter,
+    storage_writer: StorageWriter,
+    storage_reader: StorageReader,
+    schema_loader: SchemaLoader,
+    index_manager: IndexManager,
+}
+
+/// Self-test run state shared between stages
+pub struct SelfTest {
+    scratch_dir: PathBuf,
+    data_dir: PathBuf,
+    handler: ApiHandler,
+    booted: Option<Booted>,
+    /// Live documents expected in storage after the writes stage
+    live_documents: usize,
+    /// WAL records written after the checkpoint and included in the backup
+    wal_tail_records: usize,
+    backup_path: PathBuf,
+}
+
+impl SelfTest {
+    /// Create a run with a fresh scratch directory under `scratch_root`.
+    pub fn new(scratch_root: &Path) -> Self {
+        let scratch_dir = scratch_root.join(format!("aerodb-selftest-{}", Uuid::new_v4()));
+        Self {
+            data_dir: scratch_dir.join("data"),
+            backup_path: scratch_dir.join("backup.tar"),
+            scratch_dir,
+            handler: ApiHandler::new(SELFTEST_SCHEMA),
+            booted: None,
+            live_documents: 0,
+            wal_tail_records: 0,
+        }
+    }
+
+    /// Run every stage and remove the scratch directory unless `keep`.
+    pub fn run(mut self, keep: bool) -> SelfTestReport {
+        let mut stages = Vec::with_capacity(STAGES.len());
+        let mut failed = false;
+
+        for (name, stage) in STAGES {
+            if failed {
+                stages.push(StageReport {
+                    stage: name,
+                    status: StageStatus::Skipped,
+                    detail: "earlier stage failed".to_string(),
+                    duration_ms: 0,
+                });
+                continue;
+            }
+
+            let started = Instant::now();
+            let (status, detail) = match stage(&mut self) {
+                Ok(detail) => (StageStatus::Passed, detail),
+                Err(reason) => {
+                    failed = true;
+                    (StageStatus::Failed, reason)
+                }
+            };
+            stages.push(StageReport {
+                stage: name,
+                status,
+                detail,
+                duration_ms: started.elapsed().as_millis() as u64,
+            });
+        }
+
+        // Release file handles before removing the scratch directory
+        self.booted = None;
+        if !keep {
+            let _ = fs::remove_dir_all(&self.scratch_dir);
+        }
+
+        SelfTestReport {
+            data_dir: self.data_dir,
+            kept: keep,
+            stages,
+        }
+    }
+
+    fn init(&mut self) -> StageResult {
+        create_data_dirs(&self.data_dir).map_err(|e| e.message().to_string())?;
+        Ok(format!("created {}", self.data_dir.display()))
+    }
+
+    fn schema_registration(&mut self) -> StageResult {
+        let mut fields = HashMap::new();
+        fields.insert("_id".to_string(), FieldDef::required_string());
+        fields.insert("name".to_string(), FieldDef::required_string());
+        fields.insert("n".to_string(), FieldDef::optional_int());
+        let schema = Schema::new(SELFTEST_SCHEMA, SELFTEST_VERSION, fields);
+
+        let schema_path = SchemaLoader::new(&self.data_dir)
+            .schema_dir()
+            .join(format!("{}_{}.json", SELFTEST_SCHEMA, SELFTEST_VERSION));
+        let bytes = serde_json::to_vec_pretty(&schema).map_err(|e| e.to_string())?;
+        fs::write(&schema_path, bytes).map_err(|e| e.to_string())?;
+
+        self.boot()?;
+        let loader = &self.booted()?.schema_loader;
+        if !loader.exists(SELFTEST_SCHEMA, SELFTEST_VERSION) {
+            return Err("schema file was not loaded at boot".to_string());
+        }
+        Ok(format!(
+            "{} {} loaded at boot",
+            SELFTEST_SCHEMA, SELFTEST_VERSION
+        ))
+    }
+
+    fn writes(&mut self) -> StageResult {
+        for i in 1..=3 {
+            self.request(json!({
+                "op": "insert",
+                "schema_id": SELFTEST_SCHEMA,
+                "schema_version": SELFTEST_VERSION,
+                "document": {"_id": format!("doc_{}", i), "name": format!("doc {}", i), "n": i},
+            }))?;
+        }
+        self.request(json!({
+            "op": "insert_many",
+            "schema_id": SELFTEST_SCHEMA,
+            "schema_version": SELFTEST_VERSION,
+            "documents": [
+                {"_id": "doc_4", "name": "doc 4", "n": 4},
+                {"_id": "doc_5", "name": "doc 5", "n": 5},
+            ],
+        }))?;
+        self.request(json!({
+            "op": "update",
+            "schema_id": SELFTEST_SCHEMA,
+            "schema_version": SELFTEST_VERSION,
+            "document": {"_id": "doc_2", "name": "doc 2 updated", "n": 20},
+        }))?;
+        self.request(json!({
+            "op": "delete",
+            "schema_id": SELFTEST_SCHEMA,
+            "document_id": "doc_3",
+        }))?;
+        self.request(json!({
+            "op": "transaction",
+            "ops": [
+                {"op": "insert", "schema_id": SELFTEST_SCHEMA, "schema_version": SELFTEST_VERSION,
+                 "document": {"_id": "doc_6", "name": "doc 6", "n": 6}},
+                {"op": "update", "schema_id": SELFTEST_SCHEMA, "schema_version": SELFTEST_VERSION,
+                 "document": {"_id": "doc_6", "name": "doc 6 updated", "n": 60}},
+            ],
+        }))?;
+
+        // doc_1, doc_2, doc_4, doc_5, doc_6
+        self.live_documents = 5;
+        Ok("5 inserts, 2 updates, 1 delete, 1 transaction acknowledged".to_string())
+    }
+
+    fn queries(&mut self) -> StageResult {
+        let result = self.request(json!({
+            "op": "query",
+            "schema_id": SELFTEST_SCHEMA,
+            "schema_version": SELFTEST_VERSION,
+            "filter": {"_id": {"$eq": "doc_2"}},
+            "limit": 1,
+        }))?;
+        if result.pointer("/0/name") != Some(&json!("doc 2 updated")) {
+            return Err(format!("query for doc_2 returned {}", result));
+        }
+
+        let deleted = self.request(json!({
+            "op": "query",
+            "schema_id": SELFTEST_SCHEMA,
+            "schema_version": SELFTEST_VERSION,
+            "filter": {"_id": {"$eq": "doc_3"}},
+            "limit": 1,
+        }))?;
+        if deleted.as_array().is_none_or(|docs| !docs.is_empty()) {
+            return Err(format!("deleted doc_3 still returned: {}", deleted));
+        }
+
+        let plan = self.request(json!({
+            "op": "explain",
+            "schema_id": SELFTEST_SCHEMA,
+            "schema_version": SELFTEST_VERSION,
+            "filter": {"_id": {"$eq": "doc_2"}},
+            "limit": 1,
+        }))?;
+        if plan.is_null() {
+            return Err("explain returned no plan".to_string());
+        }
+
+        let doc = self.request(json!({
+            "op": "get",
+            "schema_id": SELFTEST_SCHEMA,
+            "document_id": "doc_6",
+        }))?;
+        if doc["name"] != json!("doc 6 updated") {
+            return Err(format!("get for doc_6 returned {}", doc));
+        }
+        Ok("primary-key query, deleted-document query, explain and get".to_string())
+    }
+
+    fn checkpoint(&mut self) -> StageResult {
+        let data_dir = self.data_dir.clone();
+        let keyring = CollectionKeyring::open(&data_dir).map_err(|e| e.to_string())?;
+        let booted = self.booted_mut()?;
+        let lock = GlobalExecutionLock::new();
+        let indexes = IndexCapture {
+            index: &booted.index_manager,
+            collection: SELFTEST_SCHEMA,
+            storage: &mut IndexStorage::new(&mut booted.storage_reader),
+            sealed: &|collection| keyring.is_encrypted(collection),
+        };
+        let checkpoint_id = CheckpointManager::create_checkpoint_with_indexes(
+            &data_dir,
+            &data_dir.join("data").join("documents.dat"),
+            booted.schema_loader.schema_dir(),
+            &SnapshotManager,
+            &mut booted.wal_writer,
+            indexes,
+            &lock,
+        )
+        .map_err(|e| e.to_string())?;
+
+        if booted.wal_writer.next_sequence_number() != 1 {
+            return Err("WAL was not truncated by checkpoint".to_string());
+        }
+        Ok(format!(
+            "checkpoint {} created, WAL truncated",
+            checkpoint_id
+        ))
+    }
+
+    fn backup(&mut self) -> StageResult {
+        // WAL tail after the checkpoint, included in the backup
+        self.request(json!({
+            "op": "insert",
+            "schema_id": SELFTEST_SCHEMA,
+            "schema_version": SELFTEST_VERSION,
+            "document": {"_id": "doc_7", "name": "doc 7", "n": 7},
+        }))?;
+        self.live_documents += 1;
+        self.wal_tail_records = 1;
+
+        let data_dir = self.data_dir.clone();
+        let backup_path = self.backup_path.clone();
+        let booted = self.booted()?;
+        let lock = GlobalExecutionLock::new();
+        let backup_id =
+            BackupManager::create_backup(&data_dir, &backup_path, &booted.wal_writer, &lock)
+                .map_err(|e| e.to_string())?;
+        Ok(format!(
+            "backup {} written to {}",
+            backup_id,
+            backup_path.display()
+        ))
+    }
+
+    fn crash_restart(&mut self) -> StageResult {
+        // Crash window: WAL fsync done, storage write never happened
+        let body = serde_json::to_vec(&json!({"_id": "doc_8", "name": "doc 8", "n": 8}))
+            .map_err(|e| e.to_string())?;
+        self.booted_mut()?
+            .wal_writer
+            .append(
+                RecordType::Insert,
+                WalPayload::new(
+                    SELFTEST_SCHEMA,
+                    "doc_8",
+                    SELFTEST_SCHEMA,
+                    SELFTEST_VERSION,
+                    body,
+                ),
+            )
+            .map_err(|e| e.to_string())?;
+        self.live_documents += 1;
+
+        // Drop every handle without writing the clean shutdown marker
+        self.booted = None;
+        if self.data_dir.join("clean_shutdown").exists() {
+            return Err("clean shutdown marker present after crash".to_string());
+        }
+        Ok("WAL-only write appended, process state dropped without clean shutdown".to_string())
+    }
+
+    fn recovery(&mut self) -> StageResult {
+        self.boot()?;
+
+        let live = live_documents(&mut StorageReader::open_from_data_dir(&self.data_dir))?;
+        if !live.contains(&format!("{}:doc_8", SELFTEST_SCHEMA)) {
+            return Err("WAL-only write was not replayed into storage".to_string());
+        }
+        if live.len() != self.live_documents {
+            return Err(format!(
+                "expected {} live documents after recovery, found {}",
+                self.live_documents,
+                live.len()
+            ));
+        }
+
+        // Indexes were restored from the index checkpoint and patched
+        let docs = self.request(json!({
+            "op": "query",
+            "schema_id": SELFTEST_SCHEMA,
+            "schema_version": SELFTEST_VERSION,
+            "filter": {"_id": {"$eq": "doc_8"}},
+            "limit": 1,
+        }))?;
+        if docs.as_array().is_none_or(|docs| docs.len() != 1) {
+            return Err(format!("recovered doc_8 not found by index: {}", docs));
+        }
+
+        // Serve again after recovery
+        self.request(json!({
+            "op": "insert",
+            "schema_id": SELFTEST_SCHEMA,
+            "schema_version": SELFTEST_VERSION,
+            "document": {"_id": "doc_9", "name": "doc 9", "n": 9},
+        }))?;
+        Ok(format!(
+            "{} live documents recovered, writes accepted",
+            live.len()
+        ))
+    }
+
+    fn restore(&mut self) -> StageResult {
+        // Restore requires the node to be stopped
+        self.booted = None;
+        RestoreManager::restore_from_backup(&self.data_dir, &self.backup_path)
+            .map_err(|e| e.to_string())?;
+
+        // Snapshot storage holds the documents as of the checkpoint
+        let storage_path = self.data_dir.join("data").join("storage.dat");
+        let live = live_documents(&mut StorageReader::open(&storage_path))?;
+        let expected = self.live_documents - self.wal_tail_records - 1;
+        if live.len() != expected {
+            return Err(format!(
+                "expected {} documents in restored snapshot, found {}",
+                expected,
+                live.len()
+            ));
+        }
+
+        let wal_records = WalReader::open_from_data_dir(&self.data_dir)
+            .and_then(|mut reader| reader.read_all())
+            .map_err(|e| e.to_string())?;
+        if wal_records.len() != self.wal_tail_records {
+            return Err(format!(
+                "expected {} WAL tail records in restored data, found {}",
+                self.wal_tail_records,
+                wal_records.len()
+            ));
+        }
+
+        let mut loader = SchemaLoader::new(&self.data_dir);
+        loader.load_all().map_err(|e| e.to_string())?;
+        if !loader.exists(SELFTEST_SCHEMA, SELFTEST_VERSION) {
+            return Err("schema missing from restored data".to_string());
+        }
+        Ok(format!(
+            "{} snapshot documents, {} WAL tail record(s) and schema restored",
+            live.len(),
+            wal_records.len()
+        ))
+    }
+
+    fn boot(&mut self) -> Result<(), String> {
+        let (wal_writer, storage_writer, storage_reader, schema_loader, index_manager) =
+            boot_system(
+                &self.data_dir,
+                &BootOptions {
+                    tail_recovery: TailRecovery::Strict,
+                    collection: SELFTEST_SCHEMA,
+                    ..BootOptions::default()
+                },
+            )
+            .map_err(|e| e.message().to_string())?;
+        self.booted = Some(Booted {
+            wal_writer,
+            storage_writer,
+            storage_reader,
+            schema_loader,
+            index_manager,
+        });
+        Ok(())
+    }
+
+    fn booted(&self) -> Result<&Booted, String> {
+        self.booted
+            .as_ref()
+            .ok_or_else(|| "system is not booted".to_string())
+    }
+
+    fn booted_mut(&mut self) -> Result<&mut Booted, String> {
+        self.booted
+            .as_mut()
+            .ok_or_else(|| "system is not booted".to_string())
+    }
+
+    /// Send a request through the API handler and return its data.
+    fn request(&mut self, request: Value) -> Result<Value, String> {
+        let booted = self
+            .booted
+            .as_mut()
+            .ok_or_else(|| "system is not booted".to_string())?;
+        let mut subsystems = Subsystems {
+            schema_loader: &mut booted.schema_loader,
+            wal_writer: &mut booted.wal_writer,
+            storage_writer: &mut booted.storage_writer,
+            storage_reader: &mut booted.storage_reader,
+            index_manager: &mut booted.index_manager,
+        };
+
+        let response = self.handler.handle(&request.to_string(), &mut subsystems);
+        let body: Value = serde_json::from_str(&response.to_json()).map_err(|e| e.to_string())?;
+        if !response.is_success() {
+            return Err(format!("{} rejected: {}", request["op"], body));
+        }
+        Ok(body["data"].clone())
+    }
+}
+
+/// Composite ids of live (non-tombstone) documents in a storage file.
+fn live_documents(reader: &mut StorageResult<StorageReader>) -> Result<HashSet<String>, String> {
+    let reader = reader.as_mut().map_err(|e| e.to_string())?;
+    let map = reader.build_document_map().map_err(|e| e.to_string())?;
+    Ok(map
+        .into_iter()
+        .filter(|(_, record)| !record.is_tombstone)
+        .map(|(id, _)| id)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_selftest_all_stages_pass() {
+        let temp_dir = TempDir::new().unwrap();
+        let report = SelfTest::new(temp_dir.path()).run(false);
+
+        for stage in &report.stages {
+            assert_eq!(
+                stage.status,
+                StageStatus::Passed,
+                "stage {} failed: {}",
+                stage.stage,
+                stage.detail
+            );
+        }
+        assert_eq!(report.stages.len(), STAGES.len());
+        assert!(report.passed());
+        assert!(!report.data_dir.exists());
+    }
+
+    #[test]
+    fn test_keep_retains_scratch_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let report = SelfTest::new(temp_dir.path()).run(true);
+        assert!(report.passed());
+        assert!(report.data_dir.exists());
+        assert_eq!(report.to_json()["kept"], true);
+    }
+}
@@ -0,0 +1,269 @@
+//! Interactive shell (`aerodb shell`)
+//!
+//! Boots the data directory once and evaluates one line at a time against
+//! the warm subsystems. Responses are written to stdout in the selected
+//! output format, the same as `start`; unlike `start`, a malformed line
+//! yields an error response and the shell keeps reading.
+//!
+//! A line is one of:
+//! - a JSON request, as accepted by `start`
+//! - `<op> [json]` — the op followed by the rest of the request, e.g.
+//!   `query {"schema_id": "users", "schema_version": "v1", "limit": 10}`
+//! - a dot command: `.help`, `.use [collection]`, `.quit` / `.exit`
+//!
+//! Blank lines and lines starting with `#` are ignored.
+
+use std::io::{BufRead, Write};
+
+use serde_json::{json, Map, Value};
+
+use crate::api::{ApiError, ApiHandler, Response, Subsystems};
+
+use super::errors::CliResult;
+use super::io::{render, OutputFormat};
+
+/// Prompt written before each line when stdin is a terminal
+pub const PROMPT: &str = "aerodb> ";
+
+/// Dot commands and what they do
+const COMMANDS: [(&str, &str); 4] = [
+    (".help", "list shell commands"),
+    (
+        ".use [collection]",
+        "route requests that name no collection to this one; no argument resets",
+    ),
+    (".quit", "leave the shell"),
+    (".exit", "leave the shell"),
+];
+
+/// What to do with one input line
+#[derive(Debug, Clone, PartialEq)]
+enum Line {
+    /// Nothing to evaluate
+    Skip,
+    /// Request object for the API handler
+    Request(Map<String, Value>),
+    Help,
+    Use(Option<String>),
+    Quit,
+}
+
+/// Interactive request loop over booted subsystems
+pub struct Shell {
+    handler: ApiHandler,
+    /// Collection set by `.use`
+    collection: Option<String>,
+    format: OutputFormat,
+}
+
+impl Shell {
+    /// Evaluate requests with `handler`
+    pub fn new(handler: ApiHandler) -> Self {
+        Self {
+            handler,
+            collection: None,
+            format: OutputFormat::Json,
+        }
+    }
+
+    /// Render responses in `format`
+    pub fn with_format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Read lines from `input` until end of input or `.quit`, writing one
+    /// response per evaluated line to `out`.
+    ///
+    /// `prompt` receives `PROMPT` before each line. Returns the number of
+    /// lines evaluated.
+    pub fn run(
+        &mut self,
+        input: impl BufRead,
+        out: &mut impl Write,
+        mut prompt: Option<&mut dyn Write>,
+        subsystems: &mut Subsystems<'_>,
+    ) -> CliResult<u64> {
+        let mut evaluated = 0;
+        let mut lines = input.lines();
+        loop {
+            if let Some(prompt) = prompt.as_deref_mut() {
+                write!(prompt, "{}", PROMPT)?;
+                prompt.flush()?;
+            }
+            let Some(line) = lines.next() else {
+                return Ok(evaluated);
+            };
+            let response = match parse_line(&line?) {
+                Ok(Line::Skip) => continue,
+                Ok(Line::Quit) => return Ok(evaluated),
+                Ok(line) => self.eval(line, subsystems),
+                Err(e) => Response::error(&e).to_json(),
+            };
+            let response: Value = serde_json::from_str(&response)?;
+            writeln!(out, "{}", render(&response, self.format))?;
+            out.flush()?;
+            evaluated += 1;
+        }
+    }
+
+    /// Evaluate a parsed line, returning the JSON response
+    fn eval(&mut self, line: Line, subsystems: &mut Subsystems<'_>) -> String {
+        match line {
+            Line::Request(mut request) => {
+                if let Some(collection) = &self.collection {
+                    request
+                        .entry("collection")
+                        .or_insert_with(|| json!(collection));
+                }
+                self.handler
+                    .handle(&Value::Object(request).to_string(), subsystems)
+                    .to_json()
+            }
+            Line::Help => {
+                let commands: Map<String, Value> = COMMANDS
+                    .iter()
+                    .map(|(name, help)| (name.to_string(), json!(help)))
+                    .collect();
+                Response::success(json!({ "commands": commands })).to_json()
+            }
+            Line::Use(collection) => {
+                self.collection = collection;
+                Response::success(json!({ "collection": self.collection })).to_json()
+            }
+            Line::Skip | Line::Quit => unreachable!("not evaluated"),
+        }
+    }
+}
+
+/// Parse one input line
+fn parse_line(line: &str) -> Result<Line, ApiError> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(Line::Skip);
+    }
+
+    if let Some(command) = line.strip_prefix('.') {
+        let mut words = command.split_whitespace();
+        return match (words.next(), words.next(), words.next()) {
+            (Some("help"), None, _) => Ok(Line::Help),
+            (Some("quit" | "exit"), None, _) => Ok(Line::Quit),
+            (Some("use"), collection, None) => Ok(Line::Use(collection.map(str::to_string))),
+            _ => Err(ApiError::invalid_request(format!(
+                "Unknown shell command: {} (try .help)",
+                line
+            ))),
+        };
+    }
+
+    if line.starts_with('{') {
+        return match serde_json::from_str(line) {
+            Ok(Value::Object(request)) => Ok(Line::Request(request)),
+            Ok(_) => Err(ApiError::invalid_request("Request must be a JSON object")),
+            Err(e) => Err(ApiError::invalid_request(format!("Invalid JSON: {}", e))),
+        };
+    }
+
+    let (op, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let mut request = match rest.trim() {
+        "" => Map::new(),
+        rest => match serde_json::from_str(rest) {
+            Ok(Value::Object(request)) => request,
+            Ok(_) => {
+                return Err(ApiError::invalid_request(format!(
+                    "Arguments to {} must be a JSON object",
+                    op
+                )))
+            }
+            Err(e) => return Err(ApiError::invalid_request(format!("Invalid JSON: {}", e))),
+        },
+    };
+    request.insert("op".to_string(), json!(op));
+    Ok(Line::Request(request))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::commands::{boot_system, create_data_dirs, BootOptions};
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(parse_line("  ").unwrap(), Line::Skip);
+        assert_eq!(parse_line("# comment").unwrap(), Line::Skip);
+        assert_eq!(parse_line(".exit").unwrap(), Line::Quit);
+        assert_eq!(
+            parse_line(".use orders").unwrap(),
+            Line::Use(Some("orders".to_string()))
+        );
+        assert_eq!(parse_line(".use").unwrap(), Line::Use(None));
+        assert!(parse_line(".drop everything").is_err());
+
+        let Line::Request(request) = parse_line(r#"get {"document_id": "u1"}"#).unwrap() else {
+            panic!("expected a request");
+        };
+        assert_eq!(request["op"], "get");
+        assert_eq!(request["document_id"], "u1");
+        assert_eq!(
+            parse_line("list_schemas").unwrap(),
+            Line::Request(Map::from_iter([("op".to_string(), json!("list_schemas"))]))
+        );
+        assert!(parse_line("query [1]").is_err());
+        assert!(parse_line("{not json").is_err());
+    }
+
+    #[test]
+    fn test_shell_keeps_reading_after_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        create_data_dirs(temp_dir.path()).unwrap();
+        let (mut wal, mut storage_writer, mut storage_reader, mut schemas, mut indexes) =
+            boot_system(temp_dir.path(), &BootOptions::default()).unwrap();
+        let mut subsystems = Subsystems {
+            schema_loader: &mut schemas,
+            wal_writer: &mut wal,
+            storage_writer: &mut storage_writer,
+            storage_reader: &mut storage_reader,
+            index_manager: &mut indexes,
+        };
+
+        let input = [
+            "{broken",
+            "",
+            ".use orders",
+            "list_schemas",
+            ".use",
+            "list_schemas",
+            ".help",
+            ".quit",
+            "list_schemas",
+        ]
+        .join("\n");
+        let mut out = Vec::new();
+        let mut prompt = Vec::new();
+        let evaluated = Shell::new(ApiHandler::new("documents"))
+            .run(
+                input.as_bytes(),
+                &mut out,
+                Some(&mut prompt),
+                &mut subsystems,
+            )
+            .unwrap();
+
+        assert_eq!(evaluated, 6);
+        let responses: Vec<Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses[0]["status"], "error");
+        assert_eq!(responses[1]["data"]["collection"], "orders");
+        // Routed to the missing collection, then back to the default
+        assert_eq!(responses[2]["status"], "error");
+        assert!(responses[2]["message"].as_str().unwrap().contains("orders"));
+        assert!(responses[3]["data"]["collection"].is_null());
+        assert_eq!(responses[4]["status"], "ok");
+        assert!(responses[5]["data"]["commands"][".use [collection]"].is_string());
+        assert_eq!(String::from_utf8(prompt).unwrap(), PROMPT.repeat(8));
+    }
+}
@@ -0,0 +1,80 @@
+//! Client Configuration
+
+use std::time::Duration;
+
+/// Configuration of an `AeroClient`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientConfig {
+    /// Server base URL, e.g. `http://127.0.0.1:54321` (plain HTTP only)
+    pub base_url: String,
+    /// Sent as the `apikey` header
+    pub api_key: Option<String>,
+    /// Sent as `Authorization: Bearer <token>`
+    pub access_token: Option<String>,
+    /// Deadline of one attempt, response body included
+    pub timeout: Duration,
+    /// Attempts after the first for retryable failures
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each further one
+    pub retry_backoff: Duration,
+    /// Idle connections kept open per host
+    pub pool_max_idle_per_host: usize,
+    /// How long an idle connection is kept open
+    pub pool_idle_timeout: Duration,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "http://127.0.0.1:54321".to_string(),
+            api_key: None,
+            access_token: None,
+            timeout: Duration::from_secs(30),
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(100),
+            pool_max_idle_per_host: 8,
+            pool_idle_timeout: Duration::from_secs(90),
+        }
+    }
+}
+
+impl ClientConfig {
+    /// Default configuration for the server at `base_url`
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Authenticate with an API key
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Authenticate with a JWT access token
+    pub fn with_access_token(mut self, token: impl Into<String>) -> Self {
+        self.access_token = Some(token.into());
+        self
+    }
+
+    /// Delay before retry number `retry` (0-based)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.retry_backoff
+            .saturating_mul(2u32.saturating_pow(retry.min(16)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles() {
+        let config = ClientConfig::new("http://db:54321");
+        assert_eq!(config.backoff(0), Duration::from_millis(100));
+        assert_eq!(config.backoff(2), Duration::from_millis(400));
+        assert!(config.backoff(u32::MAX) > Duration::from_secs(1));
+    }
+}
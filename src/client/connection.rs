@@ -0,0 +1,287 @@
+//! HTTP/1.1 connections
+//!
+//! Just enough of HTTP/1.1 for the REST API: one request at a time per
+//! connection, `Content-Length` or chunked response bodies, and a pool
+//! of idle keep-alive connections per host.
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+/// Largest response head accepted
+const MAX_HEAD_LEN: usize = 64 * 1024;
+/// Most response headers accepted
+const MAX_HEADERS: usize = 64;
+
+/// A response read off a connection
+#[derive(Debug)]
+pub(crate) struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// Value of header `name` (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Failure of one exchange
+#[derive(Debug)]
+pub(crate) enum ExchangeError {
+    /// The connection was closed before any of the response arrived. On a
+    /// reused connection this is the server dropping it while idle.
+    Closed,
+    /// The exchange failed after (part of) the request was sent
+    Io(io::Error),
+}
+
+impl From<io::Error> for ExchangeError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// One keep-alive connection
+pub(crate) struct Connection {
+    stream: BufReader<TcpStream>,
+}
+
+impl Connection {
+    /// Connect to `authority` (`host:port`)
+    pub async fn connect(authority: &str) -> io::Result<Self> {
+        let stream = TcpStream::connect(authority).await?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            stream: BufReader::new(stream),
+        })
+    }
+
+    /// Send `request` (head and body) and read the response; the
+    /// connection may be reused only if `keep_alive` is returned true
+    pub async fn exchange(&mut self, request: &[u8]) -> Result<(Response, bool), ExchangeError> {
+        self.stream.get_mut().write_all(request).await?;
+
+        let mut head = Vec::new();
+        loop {
+            let read = self.stream.read_until(b'\n', &mut head).await?;
+            if read == 0 {
+                return Err(if head.is_empty() {
+                    ExchangeError::Closed
+                } else {
+                    invalid("connection closed in response head").into()
+                });
+            }
+            if head.ends_with(b"\r\n\r\n") {
+                break;
+            }
+            if head.len() > MAX_HEAD_LEN {
+                return Err(invalid("response head too large").into());
+            }
+        }
+
+        let mut parsed_headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+        let mut parsed = httparse::Response::new(&mut parsed_headers);
+        match parsed.parse(&head) {
+            Ok(httparse::Status::Complete(_)) => {}
+            Ok(httparse::Status::Partial) => return Err(invalid("incomplete response head").into()),
+            Err(e) => return Err(invalid(format!("invalid response head: {}", e)).into()),
+        }
+        let status = parsed.code.unwrap_or_default();
+        let headers: Vec<(String, String)> = parsed
+            .headers
+            .iter()
+            .map(|h| {
+                (
+                    h.name.to_string(),
+                    String::from_utf8_lossy(h.value).into_owned(),
+                )
+            })
+            .collect();
+        let mut response = Response {
+            status,
+            headers,
+            body: Vec::new(),
+        };
+
+        let mut keep_alive = parsed.version == Some(1)
+            && !response
+                .header("connection")
+                .is_some_and(|v| v.eq_ignore_ascii_case("close"));
+        if status == 204 || status == 304 || (100..200).contains(&status) {
+            // No body
+        } else if response
+            .header("transfer-encoding")
+            .is_some_and(|v| v.to_ascii_lowercase().contains("chunked"))
+        {
+            response.body = self.read_chunked().await?;
+        } else if let Some(len) = response.header("content-length") {
+            let len: usize = len
+                .trim()
+                .parse()
+                .map_err(|_| invalid("invalid Content-Length"))?;
+            response.body = vec![0; len];
+            self.stream.read_exact(&mut response.body).await?;
+        } else {
+            // Delimited by the end of the connection
+            self.stream.read_to_end(&mut response.body).await?;
+            keep_alive = false;
+        }
+        Ok((response, keep_alive))
+    }
+
+    async fn read_chunked(&mut self) -> io::Result<Vec<u8>> {
+        let mut body = Vec::new();
+        loop {
+            let mut line = String::new();
+            self.stream.read_line(&mut line).await?;
+            let size = line.trim().split(';').next().unwrap_or_default();
+            let size = usize::from_str_radix(size, 16)
+                .map_err(|_| invalid(format!("invalid chunk size '{}'", line.trim())))?;
+            if size == 0 {
+                // Trailers, up to the empty line
+                loop {
+                    line.clear();
+                    if self.stream.read_line(&mut line).await? == 0 || line == "\r\n" {
+                        return Ok(body);
+                    }
+                }
+            }
+            let start = body.len();
+            body.resize(start + size + 2, 0);
+            self.stream.read_exact(&mut body[start..]).await?;
+            if !body.ends_with(b"\r\n") {
+                return Err(invalid("chunk not terminated by CRLF"));
+            }
+            body.truncate(start + size);
+        }
+    }
+}
+
+/// Idle connections, by authority
+pub(crate) struct Pool {
+    idle: Mutex<HashMap<String, Vec<(Connection, Instant)>>>,
+    max_idle_per_host: usize,
+    idle_timeout: Duration,
+}
+
+impl Pool {
+    pub fn new(max_idle_per_host: usize, idle_timeout: Duration) -> Self {
+        Self {
+            idle: Mutex::new(HashMap::new()),
+            max_idle_per_host,
+            idle_timeout,
+        }
+    }
+
+    /// Most recently used idle connection to `authority`
+    pub fn checkout(&self, authority: &str) -> Option<Connection> {
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        let connections = idle.get_mut(authority)?;
+        connections.retain(|(_, since)| since.elapsed() < self.idle_timeout);
+        connections.pop().map(|(connection, _)| connection)
+    }
+
+    /// Return a connection after a complete keep-alive exchange
+    pub fn checkin(&self, authority: &str, connection: Connection) {
+        if self.max_idle_per_host == 0 {
+            return;
+        }
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        let connections = idle.entry(authority.to_string()).or_default();
+        if connections.len() >= self.max_idle_per_host {
+            connections.remove(0);
+        }
+        connections.push((connection, Instant::now()));
+    }
+
+    /// Idle connections to `authority`
+    #[cfg(test)]
+    pub fn idle_count(&self, authority: &str) -> usize {
+        let idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        idle.get(authority).map_or(0, Vec::len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Serve `responses` in order on one connection, returning its address
+    async fn serve(responses: Vec<&'static [u8]>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+            for response in responses {
+                let mut line = String::new();
+                while line != "\r\n" {
+                    line.clear();
+                    stream.read_line(&mut line).await.unwrap();
+                }
+                stream.get_mut().write_all(response).await.unwrap();
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_content_length_and_chunked_bodies() {
+        let addr = serve(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nETag: \"v1\"\r\n\r\nok",
+            b"HTTP/1.1 404 Not Found\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2;x=y\r\nde\r\n0\r\n\r\n",
+        ])
+        .await;
+        let mut connection = Connection::connect(&addr).await.unwrap();
+        let request = b"GET / HTTP/1.1\r\nHost: test\r\n\r\n";
+
+        let (response, keep_alive) = connection.exchange(request).await.unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"ok");
+        assert_eq!(response.header("etag"), Some("\"v1\""));
+        assert!(keep_alive);
+
+        let (response, _) = connection.exchange(request).await.unwrap();
+        assert_eq!(response.status, 404);
+        assert_eq!(response.body, b"abcde");
+
+        // The server is done with the connection
+        assert!(matches!(
+            connection.exchange(request).await,
+            Err(ExchangeError::Closed) | Err(ExchangeError::Io(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_pool_keeps_recent_idle_connections() {
+        // Connections wait in the backlog; none is ever accepted
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let pool = Pool::new(1, Duration::from_secs(60));
+        assert!(pool.checkout(&addr).is_none());
+
+        pool.checkin(&addr, Connection::connect(&addr).await.unwrap());
+        assert_eq!(pool.idle_count(&addr), 1);
+        assert!(pool.checkout(&addr).is_some());
+        assert_eq!(pool.idle_count(&addr), 0);
+
+        let expired = Pool::new(1, Duration::ZERO);
+        expired.checkin(&addr, Connection::connect(&addr).await.unwrap());
+        assert!(expired.checkout(&addr).is_none());
+    }
+}
@@ -0,0 +1,244 @@
+//! Client error types
+//!
+//! Errors the server reported carry the code of their problem type (the
+//! `urn:aerodb:problem:<slug>` of `RestError::problem_type`); failures on
+//! the way to the server carry an `AERO_CLIENT_*` code.
+
+use std::fmt;
+
+use crate::rest_api::ProblemDetails;
+
+/// Client error codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientErrorCode {
+    // Reported by the server, one per REST problem type
+    /// Invalid query parameter
+    InvalidQueryParam,
+    /// Invalid filter expression
+    InvalidFilter,
+    /// Missing required parameter
+    MissingParam,
+    /// Invalid request body
+    InvalidBody,
+    /// Record not found
+    NotFound,
+    /// Collection not found
+    CollectionNotFound,
+    /// List query without a limit
+    UnboundedQuery,
+    /// Limit above the server maximum
+    LimitExceeded,
+    /// `If-Match` did not match the record's current version
+    PreconditionFailed,
+    /// Authentication or authorization failed
+    Auth,
+    /// Write sent to a node that is not the Primary
+    NotPrimary,
+    /// Replica cannot serve the read
+    ReplicaUnavailable,
+    /// Server-side failure
+    Internal,
+    /// Server-side schema failure
+    Schema,
+    /// Operation not supported by the server
+    Unsupported,
+
+    // Raised by the client
+    /// Client configuration is invalid
+    InvalidConfig,
+    /// Connection to the server failed; the request was not sent
+    Connect,
+    /// No response within the request timeout
+    Timeout,
+    /// Request failed in transit
+    Transport,
+    /// Response body is not what the endpoint returns
+    InvalidResponse,
+    /// Error status without a problem type this client knows
+    UnexpectedStatus,
+}
+
+impl ClientErrorCode {
+    /// Get the error code string
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidQueryParam => "AERO_INVALID_QUERY_PARAM",
+            Self::InvalidFilter => "AERO_INVALID_FILTER",
+            Self::MissingParam => "AERO_MISSING_PARAM",
+            Self::InvalidBody => "AERO_INVALID_BODY",
+            Self::NotFound => "AERO_NOT_FOUND",
+            Self::CollectionNotFound => "AERO_UNKNOWN_COLLECTION",
+            Self::UnboundedQuery => "AERO_QUERY_UNBOUNDED",
+            Self::LimitExceeded => "AERO_QUERY_LIMIT_EXCEEDED",
+            Self::PreconditionFailed => "AERO_CONFLICT",
+            Self::Auth => "AERO_AUTH",
+            Self::NotPrimary => "AERO_NOT_PRIMARY",
+            Self::ReplicaUnavailable => "AERO_REPLICA_UNAVAILABLE",
+            Self::Internal => "AERO_INTERNAL",
+            Self::Schema => "AERO_SCHEMA",
+            Self::Unsupported => "AERO_UNSUPPORTED",
+            Self::InvalidConfig => "AERO_CLIENT_INVALID_CONFIG",
+            Self::Connect => "AERO_CLIENT_CONNECT",
+            Self::Timeout => "AERO_CLIENT_TIMEOUT",
+            Self::Transport => "AERO_CLIENT_TRANSPORT",
+            Self::InvalidResponse => "AERO_CLIENT_INVALID_RESPONSE",
+            Self::UnexpectedStatus => "AERO_CLIENT_UNEXPECTED_STATUS",
+        }
+    }
+
+    /// Code of a REST problem type slug, if this client knows it
+    pub fn from_problem_type(slug: &str) -> Option<Self> {
+        Some(match slug {
+            "invalid-query-param" => Self::InvalidQueryParam,
+            "invalid-filter" => Self::InvalidFilter,
+            "missing-param" => Self::MissingParam,
+            "invalid-body" => Self::InvalidBody,
+            "not-found" => Self::NotFound,
+            "collection-not-found" => Self::CollectionNotFound,
+            "unbounded-query" => Self::UnboundedQuery,
+            "limit-exceeded" => Self::LimitExceeded,
+            "precondition-failed" => Self::PreconditionFailed,
+            "auth" => Self::Auth,
+            "not-primary" => Self::NotPrimary,
+            "replica-unavailable" => Self::ReplicaUnavailable,
+            "internal" => Self::Internal,
+            "schema" => Self::Schema,
+            "unsupported" => Self::Unsupported,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for ClientErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+/// Client error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientError {
+    code: ClientErrorCode,
+    message: String,
+    /// HTTP status, if the server responded
+    status: Option<u16>,
+    /// Where to retry the request (replication errors only)
+    primary: Option<String>,
+}
+
+impl ClientError {
+    /// Create an error raised by the client
+    pub fn new(code: ClientErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            status: None,
+            primary: None,
+        }
+    }
+
+    /// Error of a response with a problem details body
+    pub fn from_problem(problem: ProblemDetails) -> Self {
+        let slug = problem
+            .problem_type
+            .strip_prefix("urn:aerodb:problem:")
+            .unwrap_or(&problem.problem_type);
+        Self {
+            code: ClientErrorCode::from_problem_type(slug)
+                .unwrap_or(ClientErrorCode::UnexpectedStatus),
+            message: problem.detail,
+            status: Some(problem.status),
+            primary: problem.primary,
+        }
+    }
+
+    /// Error of a response with an error status and no problem details
+    pub fn from_status(status: u16, body: &[u8]) -> Self {
+        Self {
+            code: ClientErrorCode::UnexpectedStatus,
+            message: format!("HTTP {}: {}", status, String::from_utf8_lossy(body)),
+            status: Some(status),
+            primary: None,
+        }
+    }
+
+    /// Get the error code
+    pub fn code(&self) -> ClientErrorCode {
+        self.code
+    }
+
+    /// Get the error message
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// HTTP status of the response, if the server responded
+    pub fn status(&self) -> Option<u16> {
+        self.status
+    }
+
+    /// Primary URL to retry the request at, if known
+    pub fn primary(&self) -> Option<&str> {
+        self.primary.as_deref()
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+/// Result type for client operations
+pub type ClientResult<T> = Result<T, ClientError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::AuthError;
+    use crate::rest_api::RestError;
+
+    #[test]
+    fn test_every_problem_type_has_a_code() {
+        let errors = [
+            RestError::InvalidQueryParam(String::new()),
+            RestError::InvalidFilter(String::new()),
+            RestError::MissingParam(String::new()),
+            RestError::InvalidBody(String::new()),
+            RestError::NotFound,
+            RestError::CollectionNotFound(String::new()),
+            RestError::UnboundedQuery(0),
+            RestError::LimitExceeded(0, 0),
+            RestError::PreconditionFailed(String::new()),
+            RestError::Auth(AuthError::InvalidCredentials),
+            RestError::NotPrimary(String::new(), None),
+            RestError::ReplicaUnavailable(String::new(), None),
+            RestError::Internal(String::new()),
+            RestError::SchemaError(String::new()),
+            RestError::Unsupported(String::new()),
+        ];
+        for err in errors {
+            let slug = err.problem_type();
+            assert!(
+                ClientErrorCode::from_problem_type(slug).is_some(),
+                "no client code for problem type {}",
+                slug
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_problem() {
+        let problem = ProblemDetails::from(RestError::NotPrimary(
+            "ReplicaActive".to_string(),
+            Some("http://primary:54321".to_string()),
+        ));
+        let err = ClientError::from_problem(problem);
+        assert_eq!(err.code(), ClientErrorCode::NotPrimary);
+        assert_eq!(err.status(), Some(307));
+        assert_eq!(err.primary(), Some("http://primary:54321"));
+        assert!(err.to_string().starts_with("AERO_NOT_PRIMARY: "));
+    }
+}
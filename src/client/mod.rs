@@ -0,0 +1,29 @@
+//! # Async REST Client
+//!
+//! Rust client of the REST API (`/rest/v1`), enabled by the `client`
+//! feature. It reuses the server's request and response types, so the two
+//! cannot drift apart.
+//!
+//! ```ignore
+//! let client = AeroClient::new(ClientConfig::new("http://127.0.0.1:54321").with_api_key(key))?;
+//! let user: Value = client.insert("users", &json!({"name": "Ada"})).await?;
+//! let adults = client.stream::<Value>("users", ListQuery::new().filter("age", "gte", "18"));
+//! ```
+//!
+//! ## Module Structure
+//!
+//! - `config` - Client configuration (pool, timeouts, retries)
+//! - `errors` - Client error types, one code per REST problem type
+//! - `query` - List query builder
+//! - `rest` - `AeroClient`
+
+mod config;
+mod connection;
+mod errors;
+mod query;
+mod rest;
+
+pub use config::ClientConfig;
+pub use errors::{ClientError, ClientErrorCode, ClientResult};
+pub use query::ListQuery;
+pub use rest::{AeroClient, Record, IDEMPOTENCY_KEY};
@@ -0,0 +1,113 @@
+//! List queries
+//!
+//! Builds the query string `QueryParams` parses on the server:
+//! `select`, `order`, `limit`, `offset`, `cursor` and `field=op.value`
+//! filters.
+
+/// Query of a list request
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListQuery {
+    pairs: Vec<(String, String)>,
+}
+
+impl ListQuery {
+    /// Query with the server's default limit and no filters
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn set(mut self, key: &str, value: String) -> Self {
+        self.pairs.retain(|(k, _)| k != key);
+        self.pairs.push((key.to_string(), value));
+        self
+    }
+
+    /// Return only `fields`
+    pub fn select(self, fields: &[&str]) -> Self {
+        self.set("select", fields.join(","))
+    }
+
+    /// Filter `field` with `op` (`eq`, `gte`, `in`, ...), e.g.
+    /// `filter("age", "gte", "18")`
+    ///
+    /// Filters on one field combine with AND.
+    pub fn filter(mut self, field: &str, op: &str, value: &str) -> Self {
+        self.pairs
+            .push((field.to_string(), format!("{}.{}", op, value)));
+        self
+    }
+
+    /// Equality filter
+    pub fn eq(self, field: &str, value: &str) -> Self {
+        self.filter(field, "eq", value)
+    }
+
+    /// Sort by `field`; later calls add lower-priority keys
+    pub fn order(self, field: &str, ascending: bool) -> Self {
+        let key = format!("{}.{}", field, if ascending { "asc" } else { "desc" });
+        let order = match self.get("order") {
+            Some(existing) => format!("{},{}", existing, key),
+            None => key,
+        };
+        self.set("order", order)
+    }
+
+    /// Page size
+    pub fn limit(self, limit: usize) -> Self {
+        self.set("limit", limit.to_string())
+    }
+
+    /// Rows to skip (not combinable with a cursor)
+    pub fn offset(self, offset: usize) -> Self {
+        self.set("offset", offset.to_string())
+    }
+
+    /// Resume after the page that returned `cursor` as `next_cursor`
+    pub fn cursor(self, cursor: &str) -> Self {
+        self.set("cursor", cursor.to_string())
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// URL-encoded query string, without the leading `?`
+    pub fn to_query_string(&self) -> String {
+        form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(&self.pairs)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rest_api::QueryParams;
+
+    #[test]
+    fn test_query_string_parses_on_the_server() {
+        let query = ListQuery::new()
+            .select(&["id", "name"])
+            .filter("age", "gte", "18")
+            .filter("age", "lt", "65")
+            .eq("name", "Ada & Grace")
+            .order("age", false)
+            .order("name", true)
+            .limit(10)
+            .limit(20);
+        let string = query.to_query_string();
+        assert!(string.contains("name=eq.Ada+%26+Grace"));
+
+        let pairs: Vec<(String, String)> = form_urlencoded::parse(string.as_bytes())
+            .into_owned()
+            .collect();
+        let params = QueryParams::from_pairs(pairs).unwrap();
+        assert_eq!(params.limit, 20);
+        assert_eq!(params.filters.len(), 3);
+        assert_eq!(params.order.len(), 2);
+        assert!(!params.order[0].ascending);
+    }
+}
@@ -0,0 +1,663 @@
+//! REST API client
+//!
+//! One `AeroClient` holds a pool of keep-alive connections and is cheap to
+//! clone; clones share the pool.
+//!
+//! Retries: a failed attempt is retried, with exponential backoff, when
+//! the request cannot have taken effect (the connection failed, or the
+//! server answered 429 or 503) or when it is a read. A write whose
+//! outcome is unknown (timeout, connection lost mid-request) is not
+//! retried. Every write carries an `Idempotency-Key` header that stays the
+//! same across its attempts, so a deduplicating proxy can recognize them.
+//!
+//! A write sent to a replica is redirected (307) to the Primary once.
+
+use std::io;
+use std::sync::Arc;
+
+use futures_util::stream::{self, Stream, TryStreamExt};
+use hyper::http::uri::InvalidUri;
+use hyper::Uri;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::config::ClientConfig;
+use super::connection::{Connection, ExchangeError, Pool, Response};
+use super::errors::{ClientError, ClientErrorCode, ClientResult};
+use super::query::ListQuery;
+use crate::rest_api::response::{
+    DeleteResponse, InsertResponse, ListResponse, SingleResponse, UpdateResponse,
+};
+use crate::rest_api::{BatchOp, BatchResponse, ProblemDetails};
+
+/// Header carrying the idempotency key of a write
+pub const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+/// A record with the ETag it was read or written at
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record<T> {
+    pub data: T,
+    /// Pass to `update` / `delete` as `if_match` to guard against lost updates
+    pub etag: Option<String>,
+}
+
+/// Response of one request: status, ETag and body
+struct Reply {
+    status: u16,
+    etag: Option<String>,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// Outcome of one attempt
+enum Attempt {
+    Reply(Reply),
+    /// Failed before the request was sent
+    NotSent(ClientError),
+    /// Failed with the request possibly applied
+    Unknown(ClientError),
+}
+
+/// Async client of the REST API
+#[derive(Clone)]
+pub struct AeroClient {
+    config: Arc<ClientConfig>,
+    base: Target,
+    pool: Arc<Pool>,
+}
+
+impl AeroClient {
+    /// Client of the server at `config.base_url`
+    pub fn new(config: ClientConfig) -> ClientResult<Self> {
+        let base = Target::parse(&config.base_url)?;
+        let pool = Pool::new(config.pool_max_idle_per_host, config.pool_idle_timeout);
+        Ok(Self {
+            config: Arc::new(config),
+            base,
+            pool: Arc::new(pool),
+        })
+    }
+
+    /// Client configuration
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    /// One page of records of `collection`
+    pub async fn list<T: DeserializeOwned>(
+        &self,
+        collection: &str,
+        query: &ListQuery,
+    ) -> ClientResult<ListResponse<T>> {
+        let path = format!(
+            "{}?{}",
+            collection_path(collection),
+            query.to_query_string()
+        );
+        let reply = self.call("GET", &path, None, None).await?;
+        decode(&reply)
+    }
+
+    /// Every record matching `query`, fetched a page at a time
+    ///
+    /// Pages follow `next_cursor`, so the query must not set an offset;
+    /// its limit is the page size.
+    pub fn stream<'a, T: DeserializeOwned + 'a>(
+        &'a self,
+        collection: &'a str,
+        query: ListQuery,
+    ) -> impl Stream<Item = ClientResult<T>> + 'a {
+        stream::try_unfold(Some(query), move |next| async move {
+            let Some(query) = next else {
+                return Ok(None);
+            };
+            let page: ListResponse<T> = self.list(collection, &query).await?;
+            let next = page.next_cursor.as_deref().map(|c| query.cursor(c));
+            Ok(Some((stream::iter(page.data.into_iter().map(Ok)), next)))
+        })
+        .try_flatten()
+    }
+
+    /// Record `id` of `collection`, or `None` if there is none
+    pub async fn get<T: DeserializeOwned>(
+        &self,
+        collection: &str,
+        id: &str,
+    ) -> ClientResult<Option<Record<T>>> {
+        let path = record_path(collection, id);
+        match self.call("GET", &path, None, None).await {
+            Ok(reply) => {
+                let response: SingleResponse<T> = decode(&reply)?;
+                Ok(Some(Record {
+                    data: response.data,
+                    etag: reply.etag,
+                }))
+            }
+            Err(e) if e.code() == ClientErrorCode::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Insert `record`, returning it as written
+    pub async fn insert<T: Serialize, R: DeserializeOwned>(
+        &self,
+        collection: &str,
+        record: &T,
+    ) -> ClientResult<R> {
+        let body = encode(record)?;
+        let reply = self
+            .call("POST", &collection_path(collection), Some(body), None)
+            .await?;
+        let response: InsertResponse<R> = decode(&reply)?;
+        response.data.into_iter().next().ok_or_else(|| {
+            ClientError::new(
+                ClientErrorCode::InvalidResponse,
+                "insert response holds no record",
+            )
+        })
+    }
+
+    /// Merge `patch` into record `id`, only if it still has `if_match`
+    pub async fn update<P: Serialize, T: DeserializeOwned>(
+        &self,
+        collection: &str,
+        id: &str,
+        patch: &P,
+        if_match: Option<&str>,
+    ) -> ClientResult<Record<T>> {
+        let body = encode(patch)?;
+        let path = record_path(collection, id);
+        let reply = self.call("PATCH", &path, Some(body), if_match).await?;
+        let response: UpdateResponse<T> = decode(&reply)?;
+        Ok(Record {
+            data: response.data,
+            etag: reply.etag,
+        })
+    }
+
+    /// Delete record `id`, only if it still has `if_match`
+    pub async fn delete(
+        &self,
+        collection: &str,
+        id: &str,
+        if_match: Option<&str>,
+    ) -> ClientResult<()> {
+        let path = record_path(collection, id);
+        let reply = self.call("DELETE", &path, None, if_match).await?;
+        let _: DeleteResponse = decode(&reply)?;
+        Ok(())
+    }
+
+    /// Apply `ops` all-or-nothing
+    ///
+    /// A batch that was not committed is returned, not raised: its
+    /// results say which operation failed.
+    pub async fn batch(&self, collection: &str, ops: &[BatchOp]) -> ClientResult<BatchResponse> {
+        let body = encode(&ops)?;
+        let path = format!("{}/batch", collection_path(collection));
+        let reply = self.send("POST", &path, Some(body), None).await?;
+        if reply.is_success() {
+            return decode(&reply);
+        }
+        // Uncommitted batches answer with the status of the failed operation
+        serde_json::from_slice(&reply.body).map_err(|_| reply_error(&reply))
+    }
+
+    /// Send a request, retrying and following a redirect as described in
+    /// the module docs; error statuses become errors
+    async fn call(
+        &self,
+        method: &'static str,
+        path: &str,
+        body: Option<Vec<u8>>,
+        if_match: Option<&str>,
+    ) -> ClientResult<Reply> {
+        let reply = self.send(method, path, body, if_match).await?;
+        if reply.is_success() {
+            Ok(reply)
+        } else {
+            Err(reply_error(&reply))
+        }
+    }
+
+    /// Send a request, retrying and following a redirect as described in
+    /// the module docs; the final reply may have an error status
+    async fn send(
+        &self,
+        method: &'static str,
+        path: &str,
+        body: Option<Vec<u8>>,
+        if_match: Option<&str>,
+    ) -> ClientResult<Reply> {
+        let idempotency_key = (method != "GET").then(|| Uuid::new_v4().to_string());
+        let body = body.unwrap_or_default();
+        let mut authority = self.base.authority.clone();
+        let mut path = format!("{}{}", self.base.path, path);
+        let mut redirected = false;
+        let mut retry = 0;
+        loop {
+            let request = self.request(
+                method,
+                &authority,
+                &path,
+                &body,
+                if_match,
+                idempotency_key.as_deref(),
+            )?;
+            let (retryable, outcome) = match self.attempt(&authority, &request).await {
+                Attempt::Reply(reply) if reply.is_success() => return Ok(reply),
+                Attempt::Reply(reply) => {
+                    if reply.status == 307 && !redirected {
+                        // The Primary URL of the request, path included
+                        if let Some(primary) = reply_error(&reply).primary() {
+                            let target = Target::parse(primary)?;
+                            authority = target.authority;
+                            path = target.path;
+                            redirected = true;
+                            continue;
+                        }
+                    }
+                    // Shed or refused before the request was processed
+                    (matches!(reply.status, 429 | 503), Ok(reply))
+                }
+                Attempt::NotSent(err) => (true, Err(err)),
+                Attempt::Unknown(err) => (method == "GET", Err(err)),
+            };
+            if !retryable || retry >= self.config.max_retries {
+                return outcome;
+            }
+            tokio::time::sleep(self.config.backoff(retry)).await;
+            retry += 1;
+        }
+    }
+
+    /// Request head and body
+    fn request(
+        &self,
+        method: &str,
+        authority: &str,
+        path: &str,
+        body: &[u8],
+        if_match: Option<&str>,
+        idempotency_key: Option<&str>,
+    ) -> ClientResult<Vec<u8>> {
+        let mut head = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\n",
+            method,
+            path,
+            authority,
+            body.len()
+        );
+        let bearer = self
+            .config
+            .access_token
+            .as_ref()
+            .map(|token| format!("Bearer {}", token));
+        let headers = [
+            (
+                "Content-Type",
+                (!body.is_empty()).then_some("application/json"),
+            ),
+            ("apikey", self.config.api_key.as_deref()),
+            ("Authorization", bearer.as_deref()),
+            ("If-Match", if_match),
+            (IDEMPOTENCY_KEY, idempotency_key),
+        ];
+        for (name, value) in headers {
+            let Some(value) = value else { continue };
+            if value.contains(['\r', '\n']) {
+                return Err(ClientError::new(
+                    ClientErrorCode::InvalidConfig,
+                    format!("{} header value contains a line break", name),
+                ));
+            }
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str("\r\n");
+        let mut request = head.into_bytes();
+        request.extend_from_slice(body);
+        Ok(request)
+    }
+
+    async fn attempt(&self, authority: &str, request: &[u8]) -> Attempt {
+        let transport = |e: io::Error| {
+            Attempt::Unknown(ClientError::new(ClientErrorCode::Transport, e.to_string()))
+        };
+        let exchange = async {
+            // A pooled connection the server closed while idle fails before
+            // the request reaches it; move on to the next one
+            while let Some(mut connection) = self.pool.checkout(authority) {
+                match connection.exchange(request).await {
+                    Ok((response, keep_alive)) => {
+                        return Ok(self.reply(authority, connection, response, keep_alive))
+                    }
+                    Err(ExchangeError::Closed) => continue,
+                    Err(ExchangeError::Io(e)) => return Err(transport(e)),
+                }
+            }
+            let mut connection = Connection::connect(authority).await.map_err(|e| {
+                Attempt::NotSent(ClientError::new(
+                    ClientErrorCode::Connect,
+                    format!("connect to {} failed: {}", authority, e),
+                ))
+            })?;
+            match connection.exchange(request).await {
+                Ok((response, keep_alive)) => {
+                    Ok(self.reply(authority, connection, response, keep_alive))
+                }
+                Err(ExchangeError::Closed) => Err(transport(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "connection closed before the response",
+                ))),
+                Err(ExchangeError::Io(e)) => Err(transport(e)),
+            }
+        };
+        match tokio::time::timeout(self.config.timeout, exchange).await {
+            Ok(Ok(reply)) => Attempt::Reply(reply),
+            Ok(Err(attempt)) => attempt,
+            Err(_) => Attempt::Unknown(ClientError::new(
+                ClientErrorCode::Timeout,
+                format!("no response within {:?}", self.config.timeout),
+            )),
+        }
+    }
+
+    /// Reply of a complete exchange, returning the connection to the pool
+    fn reply(
+        &self,
+        authority: &str,
+        connection: Connection,
+        response: Response,
+        keep_alive: bool,
+    ) -> Reply {
+        if keep_alive {
+            self.pool.checkin(authority, connection);
+        }
+        Reply {
+            status: response.status,
+            etag: response.header("etag").map(str::to_string),
+            body: response.body,
+        }
+    }
+}
+
+/// Where requests go: `host:port`, and the path (and query) of the URL
+#[derive(Debug, Clone)]
+struct Target {
+    authority: String,
+    path: String,
+}
+
+impl Target {
+    fn parse(url: &str) -> ClientResult<Self> {
+        let invalid = |reason: String| {
+            ClientError::new(
+                ClientErrorCode::InvalidConfig,
+                format!("invalid server URL '{}': {}", url, reason),
+            )
+        };
+        let uri: Uri = url
+            .parse()
+            .map_err(|e: InvalidUri| invalid(e.to_string()))?;
+        if uri.scheme_str() != Some("http") {
+            return Err(invalid("only http:// URLs are supported".to_string()));
+        }
+        let host = uri.host().ok_or_else(|| invalid("no host".to_string()))?;
+        Ok(Self {
+            authority: format!("{}:{}", host, uri.port_u16().unwrap_or(80)),
+            path: uri
+                .path_and_query()
+                .map_or("", |p| p.as_str())
+                .trim_end_matches('/')
+                .to_string(),
+        })
+    }
+}
+
+fn collection_path(collection: &str) -> String {
+    format!("/rest/v1/{}", segment(collection))
+}
+
+fn record_path(collection: &str, id: &str) -> String {
+    format!("{}/{}", collection_path(collection), segment(id))
+}
+
+/// Percent-encode a path segment
+fn segment(value: &str) -> String {
+    form_urlencoded::byte_serialize(value.as_bytes())
+        .collect::<String>()
+        .replace('+', "%20")
+}
+
+fn encode<T: Serialize>(value: &T) -> ClientResult<Vec<u8>> {
+    serde_json::to_vec(value).map_err(|e| {
+        ClientError::new(
+            ClientErrorCode::InvalidBody,
+            format!("request body does not serialize: {}", e),
+        )
+    })
+}
+
+fn decode<T: DeserializeOwned>(reply: &Reply) -> ClientResult<T> {
+    serde_json::from_slice(&reply.body).map_err(|e| {
+        ClientError::new(
+            ClientErrorCode::InvalidResponse,
+            format!("unexpected response body: {}", e),
+        )
+    })
+}
+
+/// Error of an error-status reply
+fn reply_error(reply: &Reply) -> ClientError {
+    match serde_json::from_slice::<ProblemDetails>(&reply.body) {
+        Ok(problem) => ClientError::from_problem(problem),
+        Err(_) => ClientError::from_status(reply.status, &reply.body),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use futures_util::StreamExt;
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
+    use uuid::Uuid;
+
+    use crate::auth::jwt::JwtConfig;
+    use crate::auth::rls::DefaultRlsEnforcer;
+    use crate::replication::ReplicationState;
+    use crate::rest_api::handler::InMemoryRestHandler;
+    use crate::rest_api::{ReadRouter, RestServer};
+
+    async fn serve(router: Router) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        format!("http://{}", addr)
+    }
+
+    fn rest_server() -> RestServer<InMemoryRestHandler<DefaultRlsEnforcer>> {
+        let handler = InMemoryRestHandler::new(DefaultRlsEnforcer::new());
+        RestServer::new(handler, JwtConfig::default())
+    }
+
+    fn test_client(base_url: &str) -> AeroClient {
+        let config = ClientConfig {
+            retry_backoff: Duration::from_millis(1),
+            ..ClientConfig::new(base_url).with_api_key("service_test")
+        };
+        AeroClient::new(config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_crud_with_etags() {
+        let base = serve(rest_server().router()).await;
+        let client = test_client(&base);
+
+        let ada: Value = client
+            .insert("users", &json!({"id": "u1", "name": "Ada"}))
+            .await
+            .unwrap();
+        assert_eq!(ada["name"], "Ada");
+
+        let record = client.get::<Value>("users", "u1").await.unwrap().unwrap();
+        let etag = record.etag.unwrap();
+        let updated: Record<Value> = client
+            .update("users", "u1", &json!({"name": "Ada L."}), Some(&etag))
+            .await
+            .unwrap();
+        assert_eq!(updated.data["name"], "Ada L.");
+
+        let err = client
+            .update::<_, Value>("users", "u1", &json!({"name": "x"}), Some(&etag))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), ClientErrorCode::PreconditionFailed);
+        assert_eq!(err.status(), Some(412));
+
+        let batch = client
+            .batch(
+                "users",
+                &[BatchOp::Delete {
+                    id: "missing".to_string(),
+                    if_match: None,
+                }],
+            )
+            .await
+            .unwrap();
+        assert!(!batch.committed);
+
+        client.delete("users", "u1", None).await.unwrap();
+        assert!(client.get::<Value>("users", "u1").await.unwrap().is_none());
+        assert!(client.pool.idle_count(&client.base.authority) > 0);
+    }
+
+    #[tokio::test]
+    async fn test_stream_follows_cursors() {
+        let base = serve(rest_server().router()).await;
+        let client = test_client(&base);
+        for id in ["a", "b", "c", "d", "e"] {
+            let _: Value = client.insert("users", &json!({"id": id})).await.unwrap();
+        }
+
+        let ids: Vec<String> = client
+            .stream::<Value>("users", ListQuery::new().limit(2))
+            .map(|record| record.unwrap()["id"].as_str().unwrap().to_string())
+            .collect()
+            .await;
+        assert_eq!(ids, vec!["a", "b", "c", "d", "e"]);
+    }
+
+    #[tokio::test]
+    async fn test_shed_writes_retry_with_one_idempotency_key() {
+        let keys = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&keys);
+        let router = Router::new().route(
+            "/rest/v1/users",
+            post(move |headers: HeaderMap, Json(body): Json<Value>| {
+                let seen = Arc::clone(&seen);
+                async move {
+                    let mut keys = seen.lock().unwrap();
+                    keys.push(headers[IDEMPOTENCY_KEY].to_str().unwrap().to_string());
+                    if keys.len() < 3 {
+                        (StatusCode::SERVICE_UNAVAILABLE, Json(json!({})))
+                    } else {
+                        (
+                            StatusCode::CREATED,
+                            Json(json!({"data": [body], "count": 1})),
+                        )
+                    }
+                }
+            }),
+        );
+        let client = test_client(&serve(router).await);
+
+        let record: Value = client.insert("users", &json!({"id": "u1"})).await.unwrap();
+        assert_eq!(record["id"], "u1");
+        let keys = keys.lock().unwrap();
+        assert_eq!(keys.len(), 3);
+        assert!(keys.iter().all(|key| *key == keys[0]));
+    }
+
+    #[tokio::test]
+    async fn test_failed_writes_are_not_retried() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&calls);
+        let router = Router::new().route(
+            "/rest/v1/users",
+            post(move || {
+                counted.fetch_add(1, Ordering::SeqCst);
+                async { StatusCode::INTERNAL_SERVER_ERROR }
+            }),
+        );
+        let client = test_client(&serve(router).await);
+
+        let err = client
+            .insert::<_, Value>("users", &json!({}))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), ClientErrorCode::UnexpectedStatus);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Nothing listens here: every attempt fails to connect
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let closed = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let err = test_client(&closed)
+            .insert::<_, Value>("users", &json!({}))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), ClientErrorCode::Connect);
+    }
+
+    #[tokio::test]
+    async fn test_writes_follow_redirect_to_primary() {
+        let primary = serve(rest_server().router()).await;
+        let routing = Arc::new(
+            ReadRouter::new(ReplicationState::ReplicaActive {
+                replica_id: Uuid::new_v4(),
+            })
+            .with_primary_url(&primary),
+        );
+        let replica = serve(rest_server().with_read_routing(routing).router()).await;
+
+        let _: Value = test_client(&replica)
+            .insert("users", &json!({"id": "u1"}))
+            .await
+            .unwrap();
+        assert!(test_client(&primary)
+            .get::<Value>("users", "u1")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(test_client(&replica)
+            .get::<Value>("users", "u1")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_base_url_must_be_http() {
+        for url in ["https://db:54321", "db:54321", "not a url"] {
+            let err = AeroClient::new(ClientConfig::new(url)).err().unwrap();
+            assert_eq!(err.code(), ClientErrorCode::InvalidConfig);
+        }
+        let client = AeroClient::new(ClientConfig::new("http://db/api/")).unwrap();
+        assert_eq!(client.base.authority, "db:80");
+        assert_eq!(client.base.path, "/api");
+    }
+}
@@ -0,0 +1,152 @@
+//! `AERODB_*` environment overrides
+//!
+//! `AERODB_<SECTION>_<KEY>` sets `<key>` in `[<section>]`; any other
+//! `AERODB_<KEY>` sets a top-level key. Names are lowercased, so
+//! `AERODB_HTTP_PORT=8080` sets `port` in `[http]` and
+//! `AERODB_DATA_DIR=/srv/aerodb` sets `data_dir`.
+//!
+//! Values are read as TOML (`8080`, `true`, `["a", "b"]`), falling back
+//! to a plain string when they do not parse.
+
+use toml::{Table, Value};
+
+/// Prefix of every override variable
+pub const ENV_PREFIX: &str = "AERODB_";
+
+/// `AERODB_*` variables that are not configuration
+const RESERVED: &[&str] = &["AERODB_CRASH_POINT"];
+
+/// One environment variable applied to the config table
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvOverride {
+    /// Variable name, e.g. `AERODB_HTTP_PORT`
+    pub var: String,
+    /// Section the key belongs to, `None` for top-level keys
+    pub section: Option<String>,
+    /// Key within the section
+    pub key: String,
+    /// Parsed value
+    pub value: Value,
+}
+
+impl EnvOverride {
+    /// Dotted path of the key, e.g. `http.port`
+    pub fn path(&self) -> String {
+        match &self.section {
+            Some(section) => format!("{}.{}", section, self.key),
+            None => self.key.clone(),
+        }
+    }
+
+    /// Set the key in `table`
+    pub fn apply(&self, table: &mut Table) {
+        let target = match &self.section {
+            Some(section) => {
+                let entry = table
+                    .entry(section.clone())
+                    .or_insert_with(|| Value::Table(Table::new()));
+                if !entry.is_table() {
+                    *entry = Value::Table(Table::new());
+                }
+                entry.as_table_mut().expect("section is a table")
+            }
+            None => table,
+        };
+        target.insert(self.key.clone(), self.value.clone());
+    }
+}
+
+/// Collect overrides from `vars`, in variable-name order.
+///
+/// `sections` names the tables a variable may address.
+pub fn collect_overrides(
+    vars: impl IntoIterator<Item = (String, String)>,
+    sections: &[&str],
+) -> Vec<EnvOverride> {
+    let mut overrides: Vec<EnvOverride> = vars
+        .into_iter()
+        .filter(|(var, _)| var.starts_with(ENV_PREFIX) && !RESERVED.contains(&var.as_str()))
+        .filter_map(|(var, raw)| {
+            let name = var[ENV_PREFIX.len()..].to_lowercase();
+            if name.is_empty() {
+                return None;
+            }
+            let (section, key) = match name.split_once('_') {
+                Some((section, key)) if !key.is_empty() && sections.contains(&section) => {
+                    (Some(section.to_string()), key.to_string())
+                }
+                _ => (None, name),
+            };
+            Some(EnvOverride {
+                var,
+                section,
+                key,
+                value: parse_value(&raw),
+            })
+        })
+        .collect();
+    overrides.sort_by(|a, b| a.var.cmp(&b.var));
+    overrides
+}
+
+/// Read `raw` as a TOML value, or as a string if it is not one
+fn parse_value(raw: &str) -> Value {
+    format!("v = {}", raw)
+        .parse::<Table>()
+        .ok()
+        .and_then(|mut table| table.remove("v"))
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_section_and_top_level_keys() {
+        let overrides = collect_overrides(
+            vars(&[
+                ("AERODB_HTTP_PORT", "8080"),
+                ("AERODB_DATA_DIR", "/srv/aerodb"),
+                ("PATH", "/bin"),
+                ("AERODB_CRASH_POINT", "wal_after_fsync"),
+            ]),
+            &["http"],
+        );
+
+        assert_eq!(overrides.len(), 2);
+        assert_eq!(overrides[0].path(), "data_dir");
+        assert_eq!(overrides[0].value, Value::String("/srv/aerodb".into()));
+        assert_eq!(overrides[1].path(), "http.port");
+        assert_eq!(overrides[1].value, Value::Integer(8080));
+    }
+
+    #[test]
+    fn test_values_parse_as_toml() {
+        assert_eq!(parse_value("true"), Value::Boolean(true));
+        assert_eq!(parse_value("fsync"), Value::String("fsync".into()));
+        assert_eq!(
+            parse_value("[\"a\", \"b\"]"),
+            Value::Array(vec![Value::String("a".into()), Value::String("b".into())])
+        );
+    }
+
+    #[test]
+    fn test_apply_creates_missing_section() {
+        let mut table = Table::new();
+        for o in collect_overrides(vars(&[("AERODB_HTTP_HOST", "127.0.0.1")]), &["http"]) {
+            o.apply(&mut table);
+        }
+        assert_eq!(
+            table["http"]["host"],
+            Value::String("127.0.0.1".to_string())
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! Configuration error types
+//!
+//! Per ERRORS.md, configuration errors are FATAL: a server never starts
+//! with a file it could not fully understand.
+
+use std::fmt;
+
+/// Configuration error codes per ERRORS.md format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigErrorCode {
+    /// Config file could not be read
+    AeroConfigIo,
+    /// Config file is not valid TOML, or a value has the wrong type
+    AeroConfigParse,
+    /// Config file or environment names a key AeroDB does not know
+    AeroConfigUnknownKey,
+    /// A value is out of range or inconsistent with another
+    AeroConfigInvalid,
+}
+
+impl ConfigErrorCode {
+    /// Returns the string representation per ERRORS.md format
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConfigErrorCode::AeroConfigIo => "AERO_CONFIG_IO",
+            ConfigErrorCode::AeroConfigParse => "AERO_CONFIG_PARSE",
+            ConfigErrorCode::AeroConfigUnknownKey => "AERO_CONFIG_UNKNOWN_KEY",
+            ConfigErrorCode::AeroConfigInvalid => "AERO_CONFIG_INVALID",
+        }
+    }
+}
+
+impl fmt::Display for ConfigErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Configuration error with context
+#[derive(Debug, Clone)]
+pub struct ConfigError {
+    code: ConfigErrorCode,
+    message: String,
+}
+
+impl ConfigError {
+    fn new(code: ConfigErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// Config file could not be read
+    pub fn io(message: impl Into<String>) -> Self {
+        Self::new(ConfigErrorCode::AeroConfigIo, message)
+    }
+
+    /// Config file could not be parsed
+    pub fn parse(message: impl Into<String>) -> Self {
+        Self::new(ConfigErrorCode::AeroConfigParse, message)
+    }
+
+    /// Unknown key in the file or environment
+    pub fn unknown_key(message: impl Into<String>) -> Self {
+        Self::new(ConfigErrorCode::AeroConfigUnknownKey, message)
+    }
+
+    /// Invalid value
+    pub fn invalid(message: impl Into<String>) -> Self {
+        Self::new(ConfigErrorCode::AeroConfigInvalid, message)
+    }
+
+    /// Returns the error code
+    pub fn code(&self) -> ConfigErrorCode {
+        self.code
+    }
+
+    /// Returns the error message
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Result type for configuration operations
+pub type ConfigResult<T> = Result<T, ConfigError>;
@@ -0,0 +1,1135 @@
+//! Unified configuration file (`aerodb.toml`)
+//!
+//! Per CONFIG.md, configuration provides paths and resource bounds and is
+//! validated before anything is opened. This module:
+//! - Loads `aerodb.toml`, one table per subsystem
+//! - Rejects keys it does not know, in the file or the environment
+//! - Applies `AERODB_*` environment overrides over the file
+//! - Hands each subsystem its typed config at boot
+//!
+//! `observability.log_level` and `observability.metrics_enabled` are
+//! re-read on SIGHUP or `control reload-observability`; everything else,
+//! including the log sinks, takes effect only at boot.
+//!
+//! ```toml
+//! data_dir = "/var/lib/aerodb"
+//!
+//! [wal]
+//! sync_mode = "fsync"
+//! group_commit = true
+//!
+//! [http]
+//! port = 8080
+//! ```
+//!
+//! Omitted keys take the same defaults as `aerodb.json`.
+
+mod env;
+mod errors;
+
+pub use env::{collect_overrides, EnvOverride, ENV_PREFIX};
+pub use errors::{ConfigError, ConfigErrorCode, ConfigResult};
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use toml::Table;
+use uuid::Uuid;
+
+use crate::auth::oidc::OidcProviderConfig;
+use crate::checkpoint::PipelineConfig;
+use crate::core::middleware::registry::MiddlewareConfig;
+use crate::dx::api::control_plane::{AuthorityLevel, ControlCommand};
+use crate::dx::DxConfig;
+use crate::http_server::middleware::cors_layer;
+use crate::http_server::{HttpServerConfig, TlsConfig};
+use crate::index::IndexAccelConfig;
+use crate::observability::{
+    AuditLogConfig, AuditRetention, FileSinkConfig, LogSinkConfig, ObservabilityConfig, Severity,
+    SlowQueryConfig, DEFAULT_AUDIT_LOG_KEEP, DEFAULT_AUDIT_LOG_MAX_BYTES, DEFAULT_LOG_FILE_KEEP,
+    DEFAULT_LOG_FILE_MAX_BYTES,
+};
+use crate::pgwire::PgWireConfig;
+use crate::recovery::{VerificationLevel, DEFAULT_SAMPLE_PERCENT};
+use crate::replication::{ReplicationConfig, ReplicationRole};
+use crate::storage::StorageFormat;
+use crate::wal::{GroupCommitConfig, TailRecovery};
+
+/// Conventional file name of the unified config
+pub const CONFIG_FILE: &str = "aerodb.toml";
+
+/// Default max WAL size in bytes (1GB)
+pub const DEFAULT_MAX_WAL_SIZE_BYTES: u64 = 1073741824;
+
+/// Default max memory in bytes (512MB)
+pub const DEFAULT_MAX_MEMORY_BYTES: u64 = 536870912;
+
+/// Tables `AERODB_<SECTION>_<KEY>` may address
+pub const SECTIONS: &[&str] = &[
+    "wal",
+    "storage",
+    "recovery",
+    "checkpoint",
+    "index",
+    "replication",
+    "http",
+    "dx",
+    "pgwire",
+    "observability",
+];
+
+/// Contents of `aerodb.toml`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AeroConfig {
+    /// Data directory (required)
+    pub data_dir: String,
+
+    /// Max memory in bytes (default 512MB)
+    #[serde(default = "default_max_memory_bytes")]
+    pub max_memory_bytes: u64,
+
+    /// `[wal]`
+    #[serde(default)]
+    pub wal: WalSection,
+
+    /// `[storage]`
+    #[serde(default)]
+    pub storage: StorageSection,
+
+    /// `[recovery]`
+    #[serde(default)]
+    pub recovery: RecoverySection,
+
+    /// `[checkpoint]`
+    #[serde(default)]
+    pub checkpoint: CheckpointSection,
+
+    /// `[index]`
+    #[serde(default)]
+    pub index: IndexSection,
+
+    /// `[replication]`
+    #[serde(default)]
+    pub replication: ReplicationSection,
+
+    /// `[http]`
+    #[serde(default)]
+    pub http: HttpSection,
+
+    /// `[dx]`
+    #[serde(default)]
+    pub dx: DxSection,
+
+    /// `[pgwire]`
+    #[serde(default)]
+    pub pgwire: PgWireSection,
+
+    /// `[observability]`
+    #[serde(default)]
+    pub observability: ObservabilitySection,
+}
+
+fn default_max_memory_bytes() -> u64 {
+    DEFAULT_MAX_MEMORY_BYTES
+}
+
+/// `[wal]`: write-ahead log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WalSection {
+    /// Max WAL size in bytes (default 1GB)
+    pub max_size_bytes: u64,
+    /// Sync mode; only "fsync" is allowed
+    pub sync_mode: String,
+    /// Torn final record policy: "strict" or "truncate" (default "strict")
+    pub tail_recovery: String,
+    /// Share fsyncs between concurrent commits (default false)
+    pub group_commit: bool,
+}
+
+impl Default for WalSection {
+    fn default() -> Self {
+        Self {
+            max_size_bytes: DEFAULT_MAX_WAL_SIZE_BYTES,
+            sync_mode: "fsync".to_string(),
+            tail_recovery: TailRecovery::default().name().to_string(),
+            group_commit: GroupCommitConfig::default().enabled,
+        }
+    }
+}
+
+/// `[storage]`: document storage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageSection {
+    /// File format for new data directories: 1 or 2 (default 1)
+    pub format_version: u16,
+    /// Records held in the block cache (default 0 = off)
+    pub cache_entries: usize,
+}
+
+impl Default for StorageSection {
+    fn default() -> Self {
+        Self {
+            format_version: StorageFormat::default().version(),
+            cache_entries: 0,
+        }
+    }
+}
+
+/// `[recovery]`: startup recovery
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RecoverySection {
+    /// "none", "manifest-only", "sampled" or "full" (default "full")
+    pub verification: String,
+    /// Percentage of records "sampled" verification checks (default 10)
+    pub sample_percent: u8,
+    /// Seed choosing the sampled records (default 0)
+    pub seed: u64,
+    /// Worker threads for a full index rebuild (default 1)
+    pub index_rebuild_threads: usize,
+}
+
+impl Default for RecoverySection {
+    fn default() -> Self {
+        Self {
+            verification: VerificationLevel::default().name().to_string(),
+            sample_percent: DEFAULT_SAMPLE_PERCENT,
+            seed: 0,
+            index_rebuild_threads: 1,
+        }
+    }
+}
+
+/// `[checkpoint]`: checkpointing
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CheckpointSection {
+    /// Overlap checkpoint preparation with serving (default false)
+    pub pipelining: bool,
+    /// Checkpoint before exiting on a graceful shutdown (default false)
+    pub on_shutdown: bool,
+}
+
+/// `[index]`: index acceleration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IndexSection {
+    /// Accelerated in-memory index structures (default false)
+    pub accelerated_structures: bool,
+    /// Predicate pre-filtering (default false)
+    pub predicate_prefilter: bool,
+    /// Multi-attribute indexes (default false)
+    pub multi_attribute: bool,
+}
+
+/// `[replication]`: replication role
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReplicationSection {
+    /// Whether replication is enabled (default false per P5-I16)
+    pub enabled: bool,
+    /// "primary" or "replica" (default "primary")
+    pub role: String,
+    /// Replica UUID (generated if omitted)
+    pub replica_id: Option<String>,
+    /// Primary address (required for replicas, forbidden for primaries)
+    pub primary_address: Option<String>,
+}
+
+impl Default for ReplicationSection {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            role: "primary".to_string(),
+            replica_id: None,
+            primary_address: None,
+        }
+    }
+}
+
+/// `[http]`: dashboard HTTP server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpSection {
+    /// Host to bind to (default "0.0.0.0")
+    pub host: String,
+    /// Port to bind to (default 54321)
+    pub port: u16,
+    /// CORS allowed origins
+    pub cors_origins: Vec<String>,
+    /// CORS allowed methods
+    pub cors_methods: Vec<String>,
+    /// CORS allowed request headers
+    pub cors_headers: Vec<String>,
+    /// Whether responses carry security headers
+    pub security_headers: bool,
+    /// Secret signing control plane API keys
+    pub control_signing_key: Option<String>,
+    /// Control commands requiring two distinct operators' approval
+    pub dual_control: Vec<String>,
+    /// `[http.tls]`: serve HTTPS, optionally requiring client certificates
+    pub tls: Option<TlsConfig>,
+    /// `[[http.oidc]]`: external identity providers
+    pub oidc: Vec<OidcProviderConfig>,
+    /// User roles that must log in with a TOTP second factor
+    pub mfa_required_roles: Vec<String>,
+    /// `[http.pipeline]`: core pipeline stages of the database routes
+    pub pipeline: MiddlewareConfig,
+}
+
+impl Default for HttpSection {
+    fn default() -> Self {
+        let http = HttpServerConfig::default();
+        Self {
+            host: http.host,
+            port: http.port,
+            cors_origins: http.cors_origins,
+            cors_methods: http.cors_methods,
+            cors_headers: http.cors_headers,
+            security_headers: http.security_headers,
+            control_signing_key: http.control_signing_key,
+            dual_control: http.dual_control,
+            tls: http.tls,
+            oidc: http.oidc,
+            mfa_required_roles: http.mfa_required_roles,
+            pipeline: http.pipeline,
+        }
+    }
+}
+
+/// `[dx]`: local observability API
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DxSection {
+    /// Whether the observability API is enabled (default false)
+    pub enabled: bool,
+    /// Port of the observability API (default 9191)
+    pub port: u16,
+    /// Bind address; loopback only (default "127.0.0.1")
+    pub bind_address: String,
+}
+
+impl Default for DxSection {
+    fn default() -> Self {
+        let dx = DxConfig::default();
+        Self {
+            enabled: dx.enabled,
+            port: dx.port,
+            bind_address: dx.bind_address,
+        }
+    }
+}
+
+/// `[pgwire]`: PostgreSQL wire-protocol listener
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PgWireSection {
+    /// Whether `aerodb start` serves SQL clients (default false)
+    pub enabled: bool,
+    /// Port of the listener (default 5433)
+    pub port: u16,
+    /// Bind address; loopback only (default "127.0.0.1")
+    pub bind_address: String,
+    /// Connections served at once (default 16)
+    pub max_connections: usize,
+}
+
+impl Default for PgWireSection {
+    fn default() -> Self {
+        let pgwire = PgWireConfig::default();
+        Self {
+            enabled: pgwire.enabled,
+            port: pgwire.port,
+            bind_address: pgwire.bind_address,
+            max_connections: pgwire.max_connections,
+        }
+    }
+}
+
+/// `[observability]`: logging and metrics
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ObservabilitySection {
+    /// Minimum severity logged: "trace", "info", "warn", "error" or
+    /// "fatal" (default "trace"). Reloadable.
+    pub log_level: String,
+    /// Whether metrics counters are updated (default true). Reloadable.
+    pub metrics_enabled: bool,
+    /// Also write logs to this file, rotated (default: no file)
+    pub log_file: Option<String>,
+    /// Rotate the log file at this size (default 100MB)
+    pub log_file_max_bytes: u64,
+    /// Rotate the log file after this many seconds (default 0 = never)
+    pub log_file_max_age_secs: u64,
+    /// Rotated log files kept (default 5)
+    pub log_file_keep: usize,
+    /// Also send logs to the local syslog/journald socket (default false)
+    pub syslog: bool,
+    /// Record queries taking at least this many milliseconds
+    /// (default 0 = no slow query log)
+    pub slow_query_threshold_ms: u64,
+    /// Also write slow queries to this file, rotated like the log file
+    /// (default: memory only)
+    pub slow_query_log: Option<String>,
+    /// Record every mutation and control plane command in this file
+    /// (default: no audit log)
+    pub audit_log: Option<String>,
+    /// Rotate the audit log at this size (default 100MB, 0 = never)
+    pub audit_log_max_bytes: u64,
+    /// Rotated audit log files kept (default 10)
+    pub audit_log_keep: usize,
+    /// Delete rotated audit log files older than this many seconds
+    /// (default 0 = regardless of age)
+    pub audit_log_max_age_secs: u64,
+}
+
+impl Default for ObservabilitySection {
+    fn default() -> Self {
+        Self::new(
+            &ObservabilityConfig::default(),
+            &LogSinkConfig::default(),
+            None,
+            None,
+        )
+    }
+}
+
+impl ObservabilitySection {
+    /// Section producing `observability`, `sinks`, `slow_queries` and
+    /// `audit`
+    pub fn new(
+        observability: &ObservabilityConfig,
+        sinks: &LogSinkConfig,
+        slow_queries: Option<&SlowQueryConfig>,
+        audit: Option<&AuditLogConfig>,
+    ) -> Self {
+        let file = sinks.file.as_ref();
+        let retention = audit.map(|audit| audit.retention);
+        Self {
+            log_level: observability.log_level.as_str().to_ascii_lowercase(),
+            metrics_enabled: observability.metrics_enabled,
+            log_file: file.map(|file| file.path.to_string_lossy().into_owned()),
+            log_file_max_bytes: file.map_or(DEFAULT_LOG_FILE_MAX_BYTES, |file| file.max_bytes),
+            log_file_max_age_secs: file
+                .and_then(|file| file.max_age)
+                .map_or(0, |age| age.as_secs()),
+            log_file_keep: file.map_or(DEFAULT_LOG_FILE_KEEP, |file| file.keep),
+            syslog: sinks.syslog,
+            slow_query_threshold_ms: slow_queries
+                .map_or(0, |slow| slow.threshold.as_millis() as u64),
+            slow_query_log: slow_queries
+                .and_then(|slow| slow.file.as_ref())
+                .map(|file| file.path.to_string_lossy().into_owned()),
+            audit_log: audit.map(|audit| audit.path.to_string_lossy().into_owned()),
+            audit_log_max_bytes: retention
+                .map_or(DEFAULT_AUDIT_LOG_MAX_BYTES, |r| r.max_bytes.unwrap_or(0)),
+            audit_log_keep: retention.map_or(DEFAULT_AUDIT_LOG_KEEP, |r| r.keep),
+            audit_log_max_age_secs: retention
+                .and_then(|r| r.max_age)
+                .map_or(0, |age| age.as_secs()),
+        }
+    }
+}
+
+/// Typed configs handed to each subsystem at boot
+#[derive(Debug, Clone, Default)]
+pub struct SubsystemConfigs {
+    /// WAL group commit
+    pub group_commit: GroupCommitConfig,
+    /// Checkpoint pipelining
+    pub pipeline: PipelineConfig,
+    /// Index acceleration
+    pub index_accel: IndexAccelConfig,
+    /// Replication role
+    pub replication: ReplicationConfig,
+    /// Dashboard HTTP server
+    pub http_server: HttpServerConfig,
+    /// Local observability API
+    pub dx: DxConfig,
+    /// PostgreSQL wire-protocol listener
+    pub pgwire: PgWireConfig,
+    /// Log level and metrics enablement
+    pub observability: ObservabilityConfig,
+    /// Log sinks installed at boot
+    pub log_sinks: LogSinkConfig,
+    /// Slow query log, if enabled
+    pub slow_queries: Option<SlowQueryConfig>,
+    /// Audit log, if enabled
+    pub audit_log: Option<AuditLogConfig>,
+}
+
+impl AeroConfig {
+    /// Load `path`, applying `AERODB_*` variables from the process
+    /// environment, and validate the result
+    pub fn load(path: &Path) -> ConfigResult<Self> {
+        Self::load_with_env(path, std::env::vars())
+    }
+
+    /// Load `path`, applying `AERODB_*` variables from `vars`, and
+    /// validate the result
+    pub fn load_with_env(
+        path: &Path,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> ConfigResult<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| ConfigError::io(format!("Failed to read {}: {}", path.display(), e)))?;
+        Self::parse(&content, vars)
+    }
+
+    /// Parse TOML `content`, applying `AERODB_*` variables from `vars`,
+    /// and validate the result.
+    ///
+    /// The file is checked on its own first, then with each override in
+    /// turn, so an error names the key or variable that caused it.
+    pub fn parse(
+        content: &str,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> ConfigResult<Self> {
+        let mut table: Table = content
+            .parse()
+            .map_err(|e: toml::de::Error| ConfigError::parse(e.message().to_string()))?;
+
+        let mut config = Self::from_table(&table, CONFIG_FILE)?;
+        for o in collect_overrides(vars, SECTIONS) {
+            o.apply(&mut table);
+            config = Self::from_table(&table, &o.var)?;
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn from_table(table: &Table, origin: &str) -> ConfigResult<Self> {
+        toml::Value::Table(table.clone())
+            .try_into()
+            .map_err(|e: toml::de::Error| {
+                let message = format!("{}: {}", origin, e.message());
+                if e.message().starts_with("unknown field") {
+                    ConfigError::unknown_key(message)
+                } else {
+                    ConfigError::parse(message)
+                }
+            })
+    }
+
+    /// Validate values per CONFIG.md
+    pub fn validate(&self) -> ConfigResult<()> {
+        if self.data_dir.is_empty() {
+            return Err(ConfigError::invalid("data_dir must not be empty"));
+        }
+        if self.max_memory_bytes == 0 {
+            return Err(ConfigError::invalid("max_memory_bytes must be > 0"));
+        }
+        if self.wal.max_size_bytes == 0 {
+            return Err(ConfigError::invalid("wal.max_size_bytes must be > 0"));
+        }
+        if self.wal.sync_mode != "fsync" {
+            return Err(ConfigError::invalid(format!(
+                "Invalid wal.sync_mode: '{}'. Only 'fsync' is allowed.",
+                self.wal.sync_mode
+            )));
+        }
+        self.tail_recovery()?;
+        self.storage_format()?;
+        self.verification()?;
+        if !(1..=100).contains(&self.recovery.sample_percent) {
+            return Err(ConfigError::invalid(
+                "recovery.sample_percent must be between 1 and 100",
+            ));
+        }
+        if self.recovery.index_rebuild_threads == 0 {
+            return Err(ConfigError::invalid(
+                "recovery.index_rebuild_threads must be > 0",
+            ));
+        }
+        if self.http.port == 0 {
+            return Err(ConfigError::invalid("http.port must be > 0"));
+        }
+        // A misspelt name would silently leave a command single-operator
+        if let Some(name) = self
+            .http
+            .dual_control
+            .iter()
+            .find(|name| !ControlCommand::NAMES.contains(&name.as_str()))
+        {
+            return Err(ConfigError::invalid(format!(
+                "Invalid http.dual_control entry: '{}' is not a control command",
+                name
+            )));
+        }
+        if let Err(e) = self.http.pipeline.validate() {
+            return Err(ConfigError::invalid(format!(
+                "Invalid http.pipeline setting: {}",
+                e
+            )));
+        }
+        if let Err(e) = cors_layer(
+            &self.http.cors_origins,
+            &self.http.cors_methods,
+            &self.http.cors_headers,
+        ) {
+            return Err(ConfigError::invalid(format!(
+                "Invalid http CORS setting: {}",
+                e
+            )));
+        }
+        if let Some(tls) = &self.http.tls {
+            if !tls.client_identities.is_empty() && tls.client_ca_path.is_none() {
+                return Err(ConfigError::invalid(
+                    "http.tls.client_identities requires http.tls.client_ca_path",
+                ));
+            }
+            if let Some((name, level)) = tls
+                .client_identities
+                .iter()
+                .find(|(_, level)| AuthorityLevel::parse(level).is_none())
+            {
+                return Err(ConfigError::invalid(format!(
+                    "Invalid http.tls.client_identities entry: '{}' = '{}' is not an authority level",
+                    name, level
+                )));
+            }
+        }
+        let mut oidc_names = HashSet::new();
+        for provider in &self.http.oidc {
+            if let Err(e) = provider.validate() {
+                return Err(ConfigError::invalid(format!(
+                    "Invalid http.oidc entry: {}",
+                    e
+                )));
+            }
+            if !oidc_names.insert(provider.name.as_str()) {
+                return Err(ConfigError::invalid(format!(
+                    "Invalid http.oidc entry: provider '{}' is configured twice",
+                    provider.name
+                )));
+            }
+        }
+        // Per DX_OBSERVABILITY_API.md §3.1: local only
+        if !is_loopback(&self.dx.bind_address) {
+            return Err(ConfigError::invalid(format!(
+                "Invalid dx.bind_address: '{}'. Only loopback addresses are allowed.",
+                self.dx.bind_address
+            )));
+        }
+        // Clients authenticate by trust
+        if !is_loopback(&self.pgwire.bind_address) {
+            return Err(ConfigError::invalid(format!(
+                "Invalid pgwire.bind_address: '{}'. Only loopback addresses are allowed.",
+                self.pgwire.bind_address
+            )));
+        }
+        if self.pgwire.max_connections == 0 {
+            return Err(ConfigError::invalid("pgwire.max_connections must be > 0"));
+        }
+        self.observability_config()?;
+        if self.observability.log_file_max_bytes == 0 {
+            return Err(ConfigError::invalid(
+                "observability.log_file_max_bytes must be > 0",
+            ));
+        }
+        if self.observability.slow_query_log.is_some()
+            && self.observability.slow_query_threshold_ms == 0
+        {
+            return Err(ConfigError::invalid(
+                "observability.slow_query_log requires observability.slow_query_threshold_ms > 0",
+            ));
+        }
+        // Rotation must never delete the records it just moved aside
+        if self.observability.audit_log.is_some() && self.observability.audit_log_keep == 0 {
+            return Err(ConfigError::invalid(
+                "observability.audit_log_keep must be > 0",
+            ));
+        }
+        self.replication_config()?
+            .validate()
+            .map_err(|e| ConfigError::invalid(format!("[replication]: {}", e.message)))?;
+        Ok(())
+    }
+
+    /// Torn-tail recovery policy
+    pub fn tail_recovery(&self) -> ConfigResult<TailRecovery> {
+        TailRecovery::from_name(&self.wal.tail_recovery).ok_or_else(|| {
+            ConfigError::invalid(format!(
+                "Invalid wal.tail_recovery: '{}'. Expected 'strict' or 'truncate'.",
+                self.wal.tail_recovery
+            ))
+        })
+    }
+
+    /// Storage format for new data directories
+    pub fn storage_format(&self) -> ConfigResult<StorageFormat> {
+        StorageFormat::from_version(self.storage.format_version).ok_or_else(|| {
+            ConfigError::invalid(format!(
+                "Invalid storage.format_version: {}. Expected 1 or 2.",
+                self.storage.format_version
+            ))
+        })
+    }
+
+    /// Storage verification level after recovery
+    pub fn verification(&self) -> ConfigResult<VerificationLevel> {
+        match VerificationLevel::from_name(&self.recovery.verification) {
+            Some(VerificationLevel::Sampled { .. }) => Ok(VerificationLevel::Sampled {
+                percent: self.recovery.sample_percent,
+                seed: self.recovery.seed,
+            }),
+            Some(level) => Ok(level),
+            None => Err(ConfigError::invalid(format!(
+                "Invalid recovery.verification: '{}'. Expected 'none', 'manifest-only', \
+                 'sampled' or 'full'.",
+                self.recovery.verification
+            ))),
+        }
+    }
+
+    /// Log level and metrics enablement from `[observability]`
+    pub fn observability_config(&self) -> ConfigResult<ObservabilityConfig> {
+        let log_level = Severity::from_name(&self.observability.log_level).ok_or_else(|| {
+            ConfigError::invalid(format!(
+                "Invalid observability.log_level: '{}'. Expected 'trace', 'info', 'warn', \
+                 'error' or 'fatal'.",
+                self.observability.log_level
+            ))
+        })?;
+        Ok(ObservabilityConfig {
+            log_level,
+            metrics_enabled: self.observability.metrics_enabled,
+        })
+    }
+
+    /// Log sinks from `[observability]`
+    pub fn log_sinks(&self) -> LogSinkConfig {
+        let section = &self.observability;
+        LogSinkConfig {
+            file: section.log_file.as_ref().map(|path| FileSinkConfig {
+                path: path.into(),
+                max_bytes: section.log_file_max_bytes,
+                max_age: (section.log_file_max_age_secs > 0)
+                    .then(|| Duration::from_secs(section.log_file_max_age_secs)),
+                keep: section.log_file_keep,
+            }),
+            syslog: section.syslog,
+        }
+    }
+
+    /// Audit log from `[observability]`, if a file is set
+    pub fn audit_log_config(&self) -> Option<AuditLogConfig> {
+        let section = &self.observability;
+        section.audit_log.as_ref().map(|path| AuditLogConfig {
+            path: path.into(),
+            retention: AuditRetention {
+                max_bytes: (section.audit_log_max_bytes > 0).then_some(section.audit_log_max_bytes),
+                keep: section.audit_log_keep,
+                max_age: (section.audit_log_max_age_secs > 0)
+                    .then(|| Duration::from_secs(section.audit_log_max_age_secs)),
+            },
+        })
+    }
+
+    /// Slow query log from `[observability]`, if a threshold is set
+    pub fn slow_query_config(&self) -> Option<SlowQueryConfig> {
+        let section = &self.observability;
+        (section.slow_query_threshold_ms > 0).then(|| SlowQueryConfig {
+            threshold: Duration::from_millis(section.slow_query_threshold_ms),
+            file: section.slow_query_log.as_ref().map(FileSinkConfig::new),
+        })
+    }
+
+    /// Replication config from `[replication]`
+    pub fn replication_config(&self) -> ConfigResult<ReplicationConfig> {
+        let section = &self.replication;
+        if !section.enabled {
+            return Ok(ReplicationConfig::disabled());
+        }
+        let role = match section.role.as_str() {
+            "primary" => ReplicationRole::Primary,
+            "replica" => ReplicationRole::Replica,
+            other => {
+                return Err(ConfigError::invalid(format!(
+                    "Invalid replication.role: '{}'. Must be 'primary' or 'replica'.",
+                    other
+                )))
+            }
+        };
+        let replica_id = section
+            .replica_id
+            .as_deref()
+            .map(Uuid::parse_str)
+            .transpose()
+            .map_err(|e| {
+                ConfigError::invalid(format!("Invalid replication.replica_id UUID: {}", e))
+            })?;
+        Ok(match role {
+            ReplicationRole::Primary => {
+                ReplicationConfig::new(true, role, None, section.primary_address.clone())
+            }
+            ReplicationRole::Replica => ReplicationConfig::new(
+                true,
+                role,
+                Some(replica_id.unwrap_or_else(Uuid::new_v4)),
+                section.primary_address.clone(),
+            ),
+        })
+    }
+
+    /// Typed config for each subsystem
+    pub fn subsystems(&self) -> ConfigResult<SubsystemConfigs> {
+        Ok(SubsystemConfigs {
+            group_commit: GroupCommitConfig {
+                enabled: self.wal.group_commit,
+            },
+            pipeline: PipelineConfig {
+                enabled: self.checkpoint.pipelining,
+            },
+            index_accel: IndexAccelConfig {
+                accelerated_structures_enabled: self.index.accelerated_structures,
+                predicate_prefilter_enabled: self.index.predicate_prefilter,
+                multi_attribute_enabled: self.index.multi_attribute,
+            },
+            replication: self.replication_config()?,
+            http_server: HttpServerConfig {
+                host: self.http.host.clone(),
+                port: self.http.port,
+                cors_origins: self.http.cors_origins.clone(),
+                cors_methods: self.http.cors_methods.clone(),
+                cors_headers: self.http.cors_headers.clone(),
+                security_headers: self.http.security_headers,
+                control_signing_key: self.http.control_signing_key.clone(),
+                dual_control: self.http.dual_control.clone(),
+                tls: self.http.tls.clone(),
+                oidc: self.http.oidc.clone(),
+                mfa_required_roles: self.http.mfa_required_roles.clone(),
+                pipeline: self.http.pipeline.clone(),
+            },
+            dx: DxConfig {
+                enabled: self.dx.enabled,
+                port: self.dx.port,
+                bind_address: self.dx.bind_address.clone(),
+            },
+            pgwire: PgWireConfig {
+                enabled: self.pgwire.enabled,
+                port: self.pgwire.port,
+                bind_address: self.pgwire.bind_address.clone(),
+                max_connections: self.pgwire.max_connections,
+            },
+            observability: self.observability_config()?,
+            log_sinks: self.log_sinks(),
+            slow_queries: self.slow_query_config(),
+            audit_log: self.audit_log_config(),
+        })
+    }
+}
+
+/// Whether `address` only accepts local connections
+fn is_loopback(address: &str) -> bool {
+    address == "localhost"
+        || address
+            .parse::<std::net::IpAddr>()
+            .map(|ip| ip.is_loopback())
+            .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str, vars: &[(&str, &str)]) -> ConfigResult<AeroConfig> {
+        AeroConfig::parse(
+            content,
+            vars.iter().map(|(k, v)| (k.to_string(), v.to_string())),
+        )
+    }
+
+    #[test]
+    fn test_minimal_file_takes_defaults() {
+        let config = parse("data_dir = \"./data\"", &[]).unwrap();
+        assert_eq!(config.wal, WalSection::default());
+        assert_eq!(config.http.port, 54321);
+
+        let subsystems = config.subsystems().unwrap();
+        assert!(!subsystems.group_commit.enabled);
+        assert!(!subsystems.replication.is_enabled());
+        assert_eq!(subsystems.dx.bind_addr(), "127.0.0.1:9191");
+        assert!(!subsystems.pgwire.enabled);
+    }
+
+    #[test]
+    fn test_sections_reach_subsystems() {
+        let config = parse(
+            r#"
+            data_dir = "./data"
+
+            [wal]
+            group_commit = true
+
+            [checkpoint]
+            pipelining = true
+
+            [index]
+            predicate_prefilter = true
+
+            [http]
+            port = 8080
+            dual_control = ["force_promotion", "drop_collection"]
+            mfa_required_roles = ["admin"]
+
+            [http.pipeline]
+            stages = ["auth", "rate_limit", "rls", "observe"]
+            rate_limit_per_minute = 60
+
+            [http.tls]
+            cert_path = "/etc/aerodb/server.pem"
+            key_path = "/etc/aerodb/server.key"
+            client_ca_path = "/etc/aerodb/clients.pem"
+            client_identities = { "ops-1" = "OPERATOR" }
+
+            [[http.oidc]]
+            name = "google"
+            issuer = "https://accounts.google.com"
+            client_id = "aerodb"
+            redirect_uri = "https://db.example.com/auth/oidc/google/callback"
+            "#,
+            &[],
+        )
+        .unwrap();
+
+        let subsystems = config.subsystems().unwrap();
+        assert!(subsystems.group_commit.enabled);
+        assert!(subsystems.pipeline.enabled);
+        assert!(subsystems.index_accel.predicate_prefilter_enabled);
+        assert!(!subsystems.index_accel.multi_attribute_enabled);
+        assert_eq!(subsystems.http_server.socket_addr(), "0.0.0.0:8080");
+        assert_eq!(
+            subsystems.http_server.dual_control,
+            ["force_promotion", "drop_collection"]
+        );
+        let tls = subsystems.http_server.tls.unwrap();
+        assert_eq!(tls.key_path, Path::new("/etc/aerodb/server.key"));
+        assert_eq!(tls.client_identities["ops-1"], "OPERATOR");
+        assert_eq!(subsystems.http_server.mfa_required_roles, ["admin"]);
+        let pipeline = &subsystems.http_server.pipeline;
+        assert_eq!(pipeline.stages, ["auth", "rate_limit", "rls", "observe"]);
+        assert_eq!(pipeline.rate_limit_per_minute, 60);
+        assert_eq!(pipeline.idempotency_window_secs, 86400);
+        let oidc = &subsystems.http_server.oidc;
+        assert_eq!(oidc.len(), 1);
+        assert_eq!(oidc[0].scopes, ["openid", "email", "profile"]);
+    }
+
+    #[test]
+    fn test_unknown_keys_rejected() {
+        let err = parse("data_dir = \"d\"\nwal_sync = \"fsync\"", &[]).unwrap_err();
+        assert_eq!(err.code(), ConfigErrorCode::AeroConfigUnknownKey);
+
+        let err = parse("data_dir = \"d\"\n[wal]\nsync = \"fsync\"", &[]).unwrap_err();
+        assert_eq!(err.code(), ConfigErrorCode::AeroConfigUnknownKey);
+
+        let err = parse("data_dir = \"d\"\n[cache]\nsize = 1", &[]).unwrap_err();
+        assert_eq!(err.code(), ConfigErrorCode::AeroConfigUnknownKey);
+    }
+
+    #[test]
+    fn test_env_overrides_file() {
+        let config = parse(
+            "data_dir = \"./data\"\n[http]\nport = 8080",
+            &[
+                ("AERODB_HTTP_PORT", "9090"),
+                ("AERODB_DATA_DIR", "/srv/aerodb"),
+                ("AERODB_WAL_GROUP_COMMIT", "true"),
+                ("AERODB_HTTP_CONTROL_SIGNING_KEY", "s3cret"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(config.http.port, 9090);
+        assert_eq!(config.http.control_signing_key.as_deref(), Some("s3cret"));
+        assert_eq!(config.data_dir, "/srv/aerodb");
+        assert!(config.wal.group_commit);
+    }
+
+    #[test]
+    fn test_unknown_env_key_names_variable() {
+        let err = parse("data_dir = \"./data\"", &[("AERODB_HTTP_PROT", "9090")]).unwrap_err();
+        assert_eq!(err.code(), ConfigErrorCode::AeroConfigUnknownKey);
+        assert!(err.message().contains("AERODB_HTTP_PROT"));
+    }
+
+    #[test]
+    fn test_observability_section() {
+        let config = parse(
+            "data_dir = \"d\"\n[observability]\nlog_level = \"WARN\"",
+            &[("AERODB_OBSERVABILITY_METRICS_ENABLED", "false")],
+        )
+        .unwrap();
+        let observability = config.subsystems().unwrap().observability;
+        assert_eq!(observability.log_level, Severity::Warn);
+        assert!(!observability.metrics_enabled);
+
+        let err = parse(
+            "data_dir = \"d\"\n[observability]\nlog_level = \"loud\"",
+            &[],
+        )
+        .unwrap_err();
+        assert!(err.message().contains("observability.log_level"));
+    }
+
+    #[test]
+    fn test_log_sinks() {
+        let config = parse("data_dir = \"d\"", &[]).unwrap();
+        assert_eq!(config.log_sinks(), LogSinkConfig::default());
+
+        let config = parse(
+            r#"
+            data_dir = "d"
+
+            [observability]
+            log_file = "/var/log/aerodb/aerodb.log"
+            log_file_max_age_secs = 86400
+            syslog = true
+            "#,
+            &[],
+        )
+        .unwrap();
+        let sinks = config.subsystems().unwrap().log_sinks;
+        let file = sinks.file.unwrap();
+        assert_eq!(file.max_bytes, DEFAULT_LOG_FILE_MAX_BYTES);
+        assert_eq!(file.max_age, Some(Duration::from_secs(86400)));
+        assert!(sinks.syslog);
+    }
+
+    #[test]
+    fn test_audit_log_config() {
+        let config = parse("data_dir = \"d\"", &[]).unwrap();
+        assert_eq!(config.audit_log_config(), None);
+
+        let config = parse(
+            "data_dir = \"d\"\n[observability]\naudit_log = \"audit.log\"\naudit_log_max_bytes = 0",
+            &[],
+        )
+        .unwrap();
+        let audit = config.subsystems().unwrap().audit_log.unwrap();
+        assert_eq!(audit.retention.max_bytes, None);
+        assert_eq!(audit.retention.keep, DEFAULT_AUDIT_LOG_KEEP);
+
+        let err = parse(
+            "data_dir = \"d\"\n[observability]\naudit_log = \"audit.log\"\naudit_log_keep = 0",
+            &[],
+        )
+        .unwrap_err();
+        assert!(err.message().contains("audit_log_keep"));
+    }
+
+    #[test]
+    fn test_slow_query_config() {
+        let config = parse("data_dir = \"d\"", &[]).unwrap();
+        assert_eq!(config.slow_query_config(), None);
+
+        let config = parse(
+            "data_dir = \"d\"\n[observability]\nslow_query_log = \"slow.log\"",
+            &[("AERODB_OBSERVABILITY_SLOW_QUERY_THRESHOLD_MS", "250")],
+        )
+        .unwrap();
+        let slow = config.subsystems().unwrap().slow_queries.unwrap();
+        assert_eq!(slow.threshold, Duration::from_millis(250));
+        assert_eq!(slow.file.unwrap().path, Path::new("slow.log"));
+
+        let err = parse(
+            "data_dir = \"d\"\n[observability]\nslow_query_log = \"slow.log\"",
+            &[],
+        )
+        .unwrap_err();
+        assert!(err.message().contains("slow_query_threshold_ms"));
+    }
+
+    #[test]
+    fn test_invalid_values_rejected() {
+        let err = parse("data_dir = \"d\"\n[wal]\nsync_mode = \"none\"", &[]).unwrap_err();
+        assert_eq!(err.code(), ConfigErrorCode::AeroConfigInvalid);
+
+        let err = parse("data_dir = \"d\"\n[dx]\nbind_address = \"0.0.0.0\"", &[]).unwrap_err();
+        assert!(err.message().contains("dx.bind_address"));
+
+        let err = parse(
+            "data_dir = \"d\"\n[pgwire]\nbind_address = \"0.0.0.0\"",
+            &[],
+        )
+        .unwrap_err();
+        assert!(err.message().contains("pgwire.bind_address"));
+
+        let err = parse(
+            "data_dir = \"d\"\n[replication]\nenabled = true\nrole = \"replica\"",
+            &[],
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), ConfigErrorCode::AeroConfigInvalid);
+
+        let err = parse(
+            "data_dir = \"d\"\n[http]\ndual_control = [\"drop_colection\"]",
+            &[],
+        )
+        .unwrap_err();
+        assert!(err.message().contains("drop_colection"));
+
+        let err = parse(
+            "data_dir = \"d\"\n[http.pipeline]\nstages = [\"auth\", \"cache\"]",
+            &[],
+        )
+        .unwrap_err();
+        assert!(err.message().contains("http.pipeline") && err.message().contains("cache"));
+
+        let err = parse(
+            "data_dir = \"d\"\n[http]\ncors_origins = [\"localhost:5173\"]",
+            &[],
+        )
+        .unwrap_err();
+        assert!(err.message().contains("localhost:5173"));
+
+        let tls = "data_dir = \"d\"\n[http.tls]\ncert_path = \"c\"\nkey_path = \"k\"\n";
+        let err = parse(
+            &format!("{}client_identities = {{ a = \"OPERATOR\" }}", tls),
+            &[],
+        )
+        .unwrap_err();
+        assert!(err.message().contains("client_ca_path"));
+        let err = parse(
+            &format!(
+                "{}client_ca_path = \"ca\"\nclient_identities = {{ a = \"ROOT\" }}",
+                tls
+            ),
+            &[],
+        )
+        .unwrap_err();
+        assert!(err.message().contains("ROOT"));
+
+        let oidc =
+            "[[http.oidc]]\nname = \"idp\"\nclient_id = \"c\"\nredirect_uri = \"https://db/cb\"\n";
+        let err = parse(
+            &format!(
+                "data_dir = \"d\"\n{}issuer = \"http://idp.example.com\"",
+                oidc
+            ),
+            &[],
+        )
+        .unwrap_err();
+        assert!(err.message().contains("https://"));
+        let err = parse(
+            &format!(
+                "data_dir = \"d\"\n{0}issuer = \"https://idp\"\n{0}issuer = \"https://idp\"",
+                oidc
+            ),
+            &[],
+        )
+        .unwrap_err();
+        assert!(err.message().contains("twice"));
+
+        let err = parse("data_dir = \"d\"\n[http]\nport = \"x\"", &[]).unwrap_err();
+        assert_eq!(err.code(), ConfigErrorCode::AeroConfigParse);
+    }
+}
@@ -4,16 +4,23 @@
 //! existing API handlers for backward compatibility.
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use serde_json::Value;
 
+use crate::auth::RlsPolicyStore;
 use crate::core::context::{AuthContext, RequestContext};
-use crate::core::error::CoreError;
+use crate::core::error::{CoreError, CoreResult};
 use crate::core::middleware::auth::AuthMiddleware;
+use crate::core::middleware::idempotency::IdempotencyMiddleware;
 use crate::core::middleware::observe::{AuditLogger, MetricsRecorder, ObserveMiddleware};
+use crate::core::middleware::rate_limit::RateLimitMiddleware;
+use crate::core::middleware::registry::{MiddlewareConfig, MiddlewareRegistry, BUILTIN_STAGES};
 use crate::core::middleware::rls::{OwnershipPolicy, RlsMiddleware};
+use crate::core::middleware::validate::{SchemaSource, ValidationMiddleware};
+use crate::core::middleware::Middleware;
 use crate::core::operation::{DeleteOp, Operation, QueryOp, ReadOp, UpdateOp, WriteOp};
-use crate::core::pipeline::Pipeline;
+use crate::core::pipeline::{Pipeline, PipelineBuilder};
 use crate::core::StorageBackend;
 
 use super::executor::UnifiedExecutor;
@@ -29,6 +36,13 @@ pub struct BridgeConfig {
     pub enable_observe: bool,
     /// Allow anonymous reads
     pub allow_anonymous_reads: bool,
+    /// Declarative RLS policies, enforced where published
+    pub rls_policies: Option<Arc<RlsPolicyStore>>,
+    /// Schemas write payloads are validated against; unvalidated when unset
+    pub schemas: Option<Arc<dyn SchemaSource>>,
+    /// Stages to run, in order; when unset, auth, rls, validate and
+    /// observe run as the `enable_*` flags allow
+    pub middleware: Option<MiddlewareConfig>,
 }
 
 impl Default for BridgeConfig {
@@ -38,6 +52,9 @@ impl Default for BridgeConfig {
             enable_auth: true,
             enable_observe: true,
             allow_anonymous_reads: false,
+            rls_policies: None,
+            schemas: None,
+            middleware: None,
         }
     }
 }
@@ -55,28 +72,29 @@ impl PipelineBridge {
     }
 
     /// Create a pipeline bridge with custom storage
+    ///
+    /// # Panics
+    ///
+    /// If `config.middleware` names a stage that is not built in or names
+    /// one twice; `builder` reports that as an error instead.
     pub fn with_storage(storage: impl StorageBackend + 'static, config: BridgeConfig) -> Self {
-        let executor = UnifiedExecutor::new(storage);
-        let mut pipeline = Pipeline::new(executor);
-
-        if config.enable_auth {
-            let auth = if config.allow_anonymous_reads {
-                AuthMiddleware::new().with_anonymous_reads()
-            } else {
-                AuthMiddleware::new()
-            };
-            pipeline = pipeline.with_middleware(auth);
-        }
-
-        if config.enable_rls {
-            pipeline = pipeline.with_middleware(RlsMiddleware::ownership());
-        }
+        Self::builder(storage, config)
+            .build()
+            .unwrap_or_else(|e| panic!("invalid pipeline stages: {}", e))
+    }
 
-        if config.enable_observe {
-            pipeline = pipeline.with_middleware(ObserveMiddleware::noop());
+    /// Start a pipeline bridge over `storage` that custom stages can be
+    /// registered on
+    pub fn builder(
+        storage: impl StorageBackend + 'static,
+        config: BridgeConfig,
+    ) -> PipelineBridgeBuilder {
+        PipelineBridgeBuilder {
+            executor: UnifiedExecutor::new(storage),
+            registry: builtin_stages(&config),
+            config,
+            custom: Vec::new(),
         }
-
-        Self { pipeline }
     }
 
     /// Execute a read operation
@@ -172,6 +190,112 @@ impl PipelineBridge {
     }
 }
 
+/// Builder for a pipeline bridge with custom stages
+pub struct PipelineBridgeBuilder {
+    executor: UnifiedExecutor,
+    config: BridgeConfig,
+    registry: MiddlewareRegistry,
+    /// Custom stage names, in registration order
+    custom: Vec<String>,
+}
+
+impl PipelineBridgeBuilder {
+    /// Register `middleware` as stage `name`
+    ///
+    /// A stage the configured `stages` list runs in that position; one it
+    /// does not list runs after them, in registration order. Registering
+    /// a built-in name replaces that stage where it is enabled.
+    pub fn register(
+        mut self,
+        name: impl Into<String>,
+        middleware: impl Middleware + 'static,
+    ) -> Self {
+        let name = name.into();
+        self.registry.register(name.clone(), Arc::new(middleware));
+        if !BUILTIN_STAGES.contains(&name.as_str()) && !self.custom.contains(&name) {
+            self.custom.push(name);
+        }
+        self
+    }
+
+    /// Build the bridge
+    ///
+    /// # Errors
+    ///
+    /// `VALIDATION_ERROR` if the configured stages name an unregistered
+    /// stage or name one twice.
+    pub fn build(self) -> CoreResult<PipelineBridge> {
+        let mut stages = match &self.config.middleware {
+            Some(middleware) => middleware.stages.clone(),
+            None => {
+                let flags = [
+                    ("auth", self.config.enable_auth),
+                    ("rls", self.config.enable_rls),
+                    ("validate", true),
+                    ("observe", self.config.enable_observe),
+                ];
+                flags
+                    .iter()
+                    .filter(|(_, enabled)| *enabled)
+                    .map(|(name, _)| name.to_string())
+                    .collect()
+            }
+        };
+        for name in &self.custom {
+            if !stages.contains(name) {
+                stages.push(name.clone());
+            }
+        }
+        // Without schemas there is nothing to validate against
+        stages.retain(|stage| stage != "validate" || self.registry.contains(stage));
+
+        let pipeline = self
+            .registry
+            .resolve(&stages)?
+            .into_iter()
+            .fold(PipelineBuilder::new(), PipelineBuilder::with_shared)
+            .build(self.executor);
+        Ok(PipelineBridge { pipeline })
+    }
+}
+
+/// The built-in stages `config` sets up
+fn builtin_stages(config: &BridgeConfig) -> MiddlewareRegistry {
+    let settings = config.middleware.clone().unwrap_or_default();
+    let mut registry = MiddlewareRegistry::new();
+    let auth = if config.allow_anonymous_reads {
+        AuthMiddleware::new().with_anonymous_reads()
+    } else {
+        AuthMiddleware::new()
+    };
+    registry.register("auth", Arc::new(auth));
+    registry.register(
+        "rate_limit",
+        Arc::new(RateLimitMiddleware::per_minute(
+            settings.rate_limit_per_minute,
+        )),
+    );
+    let rls = match &config.rls_policies {
+        Some(store) => RlsMiddleware::ownership().with_policy_store(Arc::clone(store)),
+        None => RlsMiddleware::ownership(),
+    };
+    registry.register("rls", Arc::new(rls));
+    registry.register(
+        "idempotency",
+        Arc::new(IdempotencyMiddleware::new(Duration::from_secs(
+            settings.idempotency_window_secs,
+        ))),
+    );
+    if let Some(schemas) = &config.schemas {
+        registry.register(
+            "validate",
+            Arc::new(ValidationMiddleware::new(Arc::clone(schemas))),
+        );
+    }
+    registry.register("observe", Arc::new(ObserveMiddleware::noop()));
+    registry
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -245,4 +369,83 @@ mod tests {
             .await;
         assert!(result.is_ok());
     }
+
+    /// Refuses operations on the `secret` collection
+    struct DenySecret;
+
+    impl Middleware for DenySecret {
+        fn process<'a>(
+            &'a self,
+            op: &'a Operation,
+            ctx: &'a mut RequestContext,
+            next: crate::core::pipeline::Next<'a>,
+        ) -> std::pin::Pin<Box<dyn std::future::Future<Output = CoreResult<Value>> + Send + 'a>>
+        {
+            Box::pin(async move {
+                if op.collection() == Some("secret") {
+                    return Err(CoreError::access_denied("secret"));
+                }
+                next.run(op, ctx).await
+            })
+        }
+    }
+
+    fn staged(stages: &[&str]) -> BridgeConfig {
+        BridgeConfig {
+            middleware: Some(MiddlewareConfig {
+                stages: stages.iter().map(|s| s.to_string()).collect(),
+                rate_limit_per_minute: 2,
+                ..MiddlewareConfig::default()
+            }),
+            ..BridgeConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stages_from_config() {
+        // Configured stages replace the enable flags: no auth here
+        let bridge = PipelineBridge::new_in_memory(staged(&["rate_limit"]));
+        let doc = serde_json::json!({"name": "Alice"});
+        for _ in 0..2 {
+            let ctx = RequestContext::anonymous();
+            assert!(bridge
+                .write("users", doc.clone(), "users", ctx)
+                .await
+                .is_ok());
+        }
+        let ctx = RequestContext::anonymous();
+        let err = bridge.write("users", doc, "users", ctx).await.unwrap_err();
+        assert_eq!(err.code(), "RATE_LIMITED");
+    }
+
+    #[tokio::test]
+    async fn test_custom_stages_registered_on_builder() {
+        let storage = super::super::executor::InMemoryStorage::new();
+        let bridge = PipelineBridge::builder(storage, staged(&["deny_secret", "auth"]))
+            .register("deny_secret", DenySecret)
+            .build()
+            .unwrap();
+
+        // The custom stage runs before auth, as listed
+        let ctx = RequestContext::anonymous();
+        let err = bridge.read("secret", "1", ctx).await.unwrap_err();
+        assert_eq!(err.code(), "ACCESS_DENIED");
+        let ctx = RequestContext::anonymous();
+        let err = bridge.read("users", "1", ctx).await.unwrap_err();
+        assert_eq!(err.code(), "AUTH_REQUIRED");
+
+        // Unlisted custom stages run after the configured ones
+        let storage = super::super::executor::InMemoryStorage::new();
+        let bridge = PipelineBridge::builder(storage, BridgeConfig::default())
+            .register("deny_secret", DenySecret)
+            .build()
+            .unwrap();
+        let ctx = RequestContext::service_role();
+        let err = bridge.read("secret", "1", ctx).await.unwrap_err();
+        assert_eq!(err.code(), "ACCESS_DENIED");
+
+        let storage = super::super::executor::InMemoryStorage::new();
+        let result = PipelineBridge::builder(storage, staged(&["auth", "audit"])).build();
+        assert!(result.is_err());
+    }
 }
@@ -6,9 +6,12 @@
 use std::collections::HashMap;
 use std::time::Instant;
 
-use serde_json::Value;
+use serde_json::{json, Value};
 use uuid::Uuid;
 
+use crate::auth::ServiceKeyGrant;
+use crate::planner::{FilterOp, Predicate};
+
 /// Context carried through the execution pipeline
 #[derive(Debug, Clone)]
 pub struct RequestContext {
@@ -21,6 +24,9 @@ pub struct RequestContext {
     /// RLS filters to apply (injected by RLS middleware)
     pub rls_filters: Vec<RlsFilter>,
 
+    /// RLS policies that fired, as `<collection>.<policy>@v<version>`
+    pub rls_policies: Vec<String>,
+
     /// Metadata for observability
     pub metadata: HashMap<String, Value>,
 
@@ -35,6 +41,7 @@ impl RequestContext {
             request_id: Uuid::new_v4(),
             auth,
             rls_filters: Vec::new(),
+            rls_policies: Vec::new(),
             metadata: HashMap::new(),
             started_at: Instant::now(),
         }
@@ -87,6 +94,9 @@ pub struct AuthContext {
 
     /// Custom claims from JWT
     pub claims: HashMap<String, Value>,
+
+    /// Scopes limiting a service API key, if one authenticated the request
+    pub service_key: Option<ServiceKeyGrant>,
 }
 
 impl AuthContext {
@@ -97,6 +107,17 @@ impl AuthContext {
             is_authenticated: true,
             is_service_role: false,
             claims: HashMap::new(),
+            service_key: None,
+        }
+    }
+
+    /// Create context for a service API key
+    ///
+    /// The key acts with the service role, but only within its scopes.
+    pub fn service_key(grant: ServiceKeyGrant) -> Self {
+        Self {
+            service_key: Some(grant),
+            ..Self::service_role()
         }
     }
 
@@ -118,6 +139,7 @@ impl AuthContext {
             is_authenticated: true,
             is_service_role: true,
             claims: HashMap::new(),
+            service_key: None,
         }
     }
 
@@ -126,6 +148,19 @@ impl AuthContext {
         self.user_id
     }
 
+    /// Who is acting, for per-caller state such as rate limits
+    ///
+    /// Service keys are told apart by key; anonymous callers share one
+    /// principal.
+    pub fn principal(&self) -> String {
+        match (&self.service_key, self.user_id) {
+            (Some(grant), _) => format!("key:{}", grant.key_id),
+            (None, Some(user_id)) => format!("user:{}", user_id),
+            (None, None) if self.is_service_role => "service".to_string(),
+            (None, None) => "anonymous".to_string(),
+        }
+    }
+
     /// Require user ID, returning error description if missing
     pub fn require_user_id(&self) -> Result<Uuid, &'static str> {
         self.user_id.ok_or("Authentication required")
@@ -161,6 +196,59 @@ impl RlsFilter {
             value: Value::Array(values),
         }
     }
+
+    /// Planner predicate with the same meaning, if there is one
+    ///
+    /// `Contains` has no planner equivalent, nor does `Prefix` with a
+    /// non-string value.
+    pub fn to_predicate(&self) -> Option<Predicate> {
+        let field = self.field.clone();
+        let value = self.value.clone();
+        Some(match self.operator {
+            FilterOperator::Eq => Predicate::eq(field, value),
+            FilterOperator::Neq => Predicate::ne(field, value),
+            FilterOperator::In => {
+                Predicate::in_values(field, value.as_array().cloned().unwrap_or_default())
+            }
+            FilterOperator::Gt => Predicate::gt(field, value),
+            FilterOperator::Gte => Predicate::gte(field, value),
+            FilterOperator::Lt => Predicate::lt(field, value),
+            FilterOperator::Lte => Predicate::lte(field, value),
+            FilterOperator::Exists => Predicate::exists(field, value == Value::Bool(true)),
+            FilterOperator::Prefix => Predicate::prefix(field, value.as_str()?),
+            FilterOperator::Contains => return None,
+        })
+    }
+
+    /// Filter as shown in explain output
+    pub fn explain(&self) -> Value {
+        json!({
+            "field": self.field,
+            "op": self.operator.as_str(),
+            "value": self.value,
+        })
+    }
+}
+
+impl From<Predicate> for RlsFilter {
+    fn from(predicate: Predicate) -> Self {
+        let (operator, value) = match predicate.op {
+            FilterOp::Eq(v) => (FilterOperator::Eq, v),
+            FilterOp::Ne(v) => (FilterOperator::Neq, v),
+            FilterOp::In(values) => (FilterOperator::In, Value::Array(values)),
+            FilterOp::Gt(v) => (FilterOperator::Gt, v),
+            FilterOp::Gte(v) => (FilterOperator::Gte, v),
+            FilterOp::Lt(v) => (FilterOperator::Lt, v),
+            FilterOp::Lte(v) => (FilterOperator::Lte, v),
+            FilterOp::Exists(present) => (FilterOperator::Exists, Value::Bool(present)),
+            FilterOp::Prefix(prefix) => (FilterOperator::Prefix, Value::String(prefix)),
+        };
+        Self {
+            field: predicate.field,
+            operator,
+            value,
+        }
+    }
 }
 
 /// Filter operators for RLS
@@ -174,6 +262,28 @@ pub enum FilterOperator {
     Gte,
     Lt,
     Lte,
+    /// Field present (`true`) or absent/null (`false`)
+    Exists,
+    /// String field starting with the value
+    Prefix,
+}
+
+impl FilterOperator {
+    /// Operator name for explain output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Eq => "eq",
+            Self::Neq => "neq",
+            Self::In => "in",
+            Self::Contains => "contains",
+            Self::Gt => "gt",
+            Self::Gte => "gte",
+            Self::Lt => "lt",
+            Self::Lte => "lte",
+            Self::Exists => "exists",
+            Self::Prefix => "prefix",
+        }
+    }
 }
 
 #[cfg(test)]
@@ -202,4 +312,26 @@ mod tests {
         assert_eq!(filter.field, "owner_id");
         assert_eq!(filter.operator, FilterOperator::Eq);
     }
+
+    #[test]
+    fn test_rls_filter_predicate_round_trip() {
+        for predicate in [
+            Predicate::eq("owner_id", Value::from("u1")),
+            Predicate::ne("status", Value::from("draft")),
+            Predicate::in_values("tier", vec![Value::from(1), Value::from(2)]),
+            Predicate::gte("rank", Value::from(3)),
+            Predicate::exists("deleted_at", false),
+            Predicate::prefix("path", "public/"),
+        ] {
+            let filter = RlsFilter::from(predicate.clone());
+            assert_eq!(filter.to_predicate(), Some(predicate));
+        }
+
+        let contains = RlsFilter {
+            field: "title".to_string(),
+            operator: FilterOperator::Contains,
+            value: Value::from("rust"),
+        };
+        assert_eq!(contains.to_predicate(), None);
+    }
 }
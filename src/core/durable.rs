@@ -0,0 +1,508 @@
+//! Durable Storage Backend
+//!
+//! `DurableStorage` serves the unified executor from the booted WAL,
+//! storage and index stack. Each backend call runs as a request through
+//! an `ApiHandler` with the service role (the pipeline in front of the
+//! executor has already authorized it), so documents are validated
+//! against their schema, logged to the WAL before storage is written,
+//! indexed, and recovered on the next boot exactly as CLI writes are.
+//!
+//! A registered collection's documents are of the schema it was created
+//! for, written at that schema's one writable version. Documents in the
+//! default collection use the schema set by `with_default_schema`.
+//! Queries follow the API's rules: filters use the API filter syntax and
+//! must be bounded by an index.
+//!
+//! The backend is also the `SchemaSource` for validation middleware, so
+//! payloads are checked against the schema they will be written as.
+
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value};
+
+use crate::api::{ApiHandler, ErrorResponse, PriorityClass, Response, Subsystems};
+use crate::index::IndexManager;
+use crate::schema::{FieldViolation, SchemaLoader, SchemaValidator};
+use crate::storage::{KeyProvider, StorageReader, StorageWriter};
+use crate::wal::WalWriter;
+
+use super::context::AuthContext;
+use super::error::{CoreError, CoreResult};
+use super::executor::StorageBackend;
+use super::middleware::validate::SchemaSource;
+
+/// Error code the API reports for a missing document
+const NOT_FOUND: &str = "AERO_NOT_FOUND";
+
+/// Storage subsystems owned by the backend
+struct Stack {
+    storage_writer: StorageWriter,
+    storage_reader: StorageReader,
+    schema_loader: SchemaLoader,
+    index_manager: IndexManager,
+}
+
+/// Storage backend writing through the WAL, storage and indexes
+pub struct DurableStorage {
+    handler: ApiHandler,
+    /// Default collection, as named to the handler
+    collection: String,
+    /// Schema id and version of documents in the default collection
+    default_schema: Option<(String, String)>,
+    /// WAL writer, shared with the control plane
+    wal_writer: Arc<Mutex<WalWriter>>,
+    stack: Mutex<Stack>,
+}
+
+impl DurableStorage {
+    /// Create a backend over booted subsystems
+    ///
+    /// `collection` is the default collection the subsystems were booted
+    /// with; the WAL writer may be shared with other writers.
+    pub fn new(
+        collection: impl Into<String>,
+        wal_writer: Arc<Mutex<WalWriter>>,
+        storage_writer: StorageWriter,
+        storage_reader: StorageReader,
+        schema_loader: SchemaLoader,
+        index_manager: IndexManager,
+    ) -> Self {
+        let collection = collection.into();
+        Self {
+            handler: ApiHandler::new(collection.clone()),
+            collection,
+            default_schema: None,
+            wal_writer,
+            stack: Mutex::new(Stack {
+                storage_writer,
+                storage_reader,
+                schema_loader,
+                index_manager,
+            }),
+        }
+    }
+
+    /// Write documents in the default collection as `schema_id` at
+    /// `schema_version`
+    pub fn with_default_schema(mut self, schema_id: &str, schema_version: &str) -> Self {
+        self.default_schema = Some((schema_id.to_string(), schema_version.to_string()));
+        self
+    }
+
+    /// Encrypt and decrypt fields marked encrypted with keys from `keys`
+    pub fn with_field_keys(mut self, keys: Arc<dyn KeyProvider>) -> Self {
+        self.handler = self.handler.with_field_keys(keys);
+        self
+    }
+
+    /// Run an API request (any `op`) and return its `data`
+    ///
+    /// Schema and collection DDL reach the stack this way.
+    pub fn execute(&self, request: Value) -> Result<Value, String> {
+        self.call(request).map_err(Self::describe)
+    }
+
+    fn call(&self, request: Value) -> Result<Value, ErrorResponse> {
+        let mut stack = self.stack.lock().unwrap_or_else(|e| e.into_inner());
+        let mut wal_writer = self.wal_writer.lock().unwrap_or_else(|e| e.into_inner());
+        let stack = &mut *stack;
+        let mut subsystems = Subsystems {
+            schema_loader: &mut stack.schema_loader,
+            wal_writer: &mut wal_writer,
+            storage_writer: &mut stack.storage_writer,
+            storage_reader: &mut stack.storage_reader,
+            index_manager: &mut stack.index_manager,
+        };
+        let response = self.handler.handle_as(
+            &request.to_string(),
+            PriorityClass::Authenticated,
+            &AuthContext::service_role(),
+            &mut subsystems,
+        );
+        match response {
+            Response::Success(success) => Ok(success.data),
+            Response::Error(error) => Err(error),
+        }
+    }
+
+    /// Schema id and version documents in `collection` are written as
+    fn schema_of(&self, collection: &str) -> Result<(String, String), String> {
+        if collection == self.collection {
+            return self.default_schema.clone().ok_or_else(|| {
+                format!(
+                    "No schema is set for the default collection '{}'",
+                    collection
+                )
+            });
+        }
+        let stack = self.stack.lock().unwrap_or_else(|e| e.into_inner());
+        let schema_id = stack
+            .schema_loader
+            .collection_schema(collection)
+            .ok_or_else(|| format!("Collection '{}' is not registered", collection))?;
+        let mut writable = stack
+            .schema_loader
+            .all_schemas()
+            .filter(|s| s.schema_id == schema_id && !s.deprecated);
+        match (writable.next(), writable.next()) {
+            (Some(schema), None) => Ok((schema_id.to_string(), schema.schema_version.clone())),
+            (None, _) => Err(format!("Schema '{}' has no writable version", schema_id)),
+            (Some(_), Some(_)) => Err(format!(
+                "Schema '{}' has several writable versions; deprecate all but one",
+                schema_id
+            )),
+        }
+    }
+
+    fn describe(error: ErrorResponse) -> String {
+        format!("{}: {}", error.code, error.message)
+    }
+}
+
+impl SchemaSource for DurableStorage {
+    fn violations(
+        &self,
+        collection: &str,
+        document: &Value,
+        partial: bool,
+    ) -> CoreResult<Vec<FieldViolation>> {
+        let (schema_id, schema_version) =
+            self.schema_of(collection).map_err(CoreError::validation)?;
+        let stack = self.stack.lock().unwrap_or_else(|e| e.into_inner());
+        SchemaValidator::new(&stack.schema_loader)
+            .violations(&schema_id, &schema_version, document, partial)
+            .map_err(|e| CoreError::validation(e.message()))
+    }
+}
+
+impl StorageBackend for DurableStorage {
+    fn read(&self, collection: &str, id: &str) -> Result<Option<Value>, String> {
+        let (schema_id, _) = self.schema_of(collection)?;
+        let request = json!({
+            "op": "get",
+            "collection": collection,
+            "schema_id": schema_id,
+            "document_id": id,
+        });
+        match self.call(request) {
+            Ok(document) => Ok(Some(document)),
+            Err(e) if e.code == NOT_FOUND => Ok(None),
+            Err(e) => Err(Self::describe(e)),
+        }
+    }
+
+    fn write(&self, collection: &str, mut document: Value) -> Result<String, String> {
+        let (schema_id, schema_version) = self.schema_of(collection)?;
+        let id = match document.get("_id").and_then(Value::as_str) {
+            Some(id) => id.to_string(),
+            None => {
+                let id = uuid::Uuid::new_v4().to_string();
+                if let Some(obj) = document.as_object_mut() {
+                    obj.insert("_id".to_string(), Value::String(id.clone()));
+                }
+                id
+            }
+        };
+        let request = json!({
+            "op": "insert",
+            "collection": collection,
+            "schema_id": schema_id,
+            "schema_version": schema_version,
+            "document": document,
+        });
+        self.call(request).map_err(Self::describe)?;
+        Ok(id)
+    }
+
+    fn update(&self, collection: &str, id: &str, updates: Value) -> Result<Value, String> {
+        let mut document = self
+            .read(collection, id)?
+            .ok_or_else(|| format!("Document {} not found", id))?;
+        if let (Some(doc_obj), Some(updates_obj)) = (document.as_object_mut(), updates.as_object())
+        {
+            for (k, v) in updates_obj {
+                if k != "_id" {
+                    doc_obj.insert(k.clone(), v.clone());
+                }
+            }
+        }
+
+        let (schema_id, schema_version) = self.schema_of(collection)?;
+        let request = json!({
+            "op": "update",
+            "collection": collection,
+            "schema_id": schema_id,
+            "schema_version": schema_version,
+            "document": document,
+        });
+        self.call(request).map_err(Self::describe)?;
+        Ok(document)
+    }
+
+    fn delete(&self, collection: &str, id: &str) -> Result<bool, String> {
+        if self.read(collection, id)?.is_none() {
+            return Ok(false);
+        }
+        let (schema_id, _) = self.schema_of(collection)?;
+        let request = json!({
+            "op": "delete",
+            "collection": collection,
+            "schema_id": schema_id,
+            "document_id": id,
+        });
+        self.call(request).map_err(Self::describe)?;
+        Ok(true)
+    }
+
+    fn query(
+        &self,
+        collection: &str,
+        filter: Option<&Value>,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<Value>, String> {
+        let (schema_id, schema_version) = self.schema_of(collection)?;
+        // The API has no offset: read past it and skip
+        let request = json!({
+            "op": "query",
+            "collection": collection,
+            "schema_id": schema_id,
+            "schema_version": schema_version,
+            "filter": filter.cloned().unwrap_or_else(|| json!({})),
+            "limit": limit + offset,
+        });
+        let documents = self.call(request).map_err(Self::describe)?;
+        Ok(match documents {
+            Value::Array(documents) => documents.into_iter().skip(offset).collect(),
+            _ => Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::bridge::{BridgeConfig, PipelineBridge};
+    use crate::core::executor::UnifiedExecutor;
+    use crate::core::operation::{Operation, QueryOp, ReadOp, WriteOp};
+    use crate::core::pipeline::Pipeline;
+    use crate::core::RequestContext;
+    use crate::schema::{FieldDef, Schema};
+    use std::collections::{HashMap, HashSet};
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, Arc<Mutex<WalWriter>>, DurableStorage) {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+
+        let mut loader = SchemaLoader::new(data_dir);
+        let mut fields = HashMap::new();
+        fields.insert("_id".to_string(), FieldDef::required_string());
+        fields.insert("name".to_string(), FieldDef::required_string());
+        fields.insert("age".to_string(), FieldDef::optional_int());
+        loader.register(Schema::new("users", "v1", fields)).unwrap();
+
+        let wal_writer = Arc::new(Mutex::new(WalWriter::open(data_dir).unwrap()));
+        let storage = DurableStorage::new(
+            "users",
+            Arc::clone(&wal_writer),
+            StorageWriter::open(data_dir).unwrap(),
+            StorageReader::open_from_data_dir(data_dir).unwrap(),
+            loader,
+            IndexManager::new(HashSet::from(["age".to_string()])),
+        )
+        .with_default_schema("users", "v1");
+        (temp_dir, wal_writer, storage)
+    }
+
+    fn ages(documents: &[Value]) -> Vec<i64> {
+        let mut ages: Vec<i64> = documents.iter().filter_map(|d| d["age"].as_i64()).collect();
+        ages.sort();
+        ages
+    }
+
+    #[test]
+    fn test_document_lifecycle() {
+        let (_temp, _wal, storage) = setup();
+
+        let id = storage
+            .write("users", json!({"name": "Alice", "age": 30}))
+            .unwrap();
+        let document = storage.read("users", &id).unwrap().unwrap();
+        assert_eq!(document["name"], "Alice");
+
+        let updated = storage
+            .update("users", &id, json!({"_id": "other", "age": 31}))
+            .unwrap();
+        assert_eq!(updated["_id"], id.as_str());
+        assert_eq!(storage.read("users", &id).unwrap().unwrap()["age"], 31);
+
+        assert!(storage.delete("users", &id).unwrap());
+        assert!(storage.read("users", &id).unwrap().is_none());
+        assert!(!storage.delete("users", &id).unwrap());
+        assert!(storage.update("users", &id, json!({"age": 32})).is_err());
+    }
+
+    #[test]
+    fn test_writes_are_validated_and_logged() {
+        let (temp, wal, storage) = setup();
+
+        storage
+            .write("users", json!({"_id": "u1", "name": "Alice", "age": 30}))
+            .unwrap();
+        let err = storage.write("users", json!({"age": 40})).unwrap_err();
+        assert!(err.starts_with("AERO_SCHEMA_VALIDATION"), "{}", err);
+        assert_eq!(wal.lock().unwrap().last_sequence_number(), 1);
+
+        let mut reader = StorageReader::open_from_data_dir(temp.path()).unwrap();
+        let record = reader.read_next().unwrap().unwrap();
+        assert!(record.document_id.ends_with("u1"));
+        assert!(reader.read_next().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_query_pages_through_index() {
+        let (_temp, _wal, storage) = setup();
+
+        for age in [25, 30, 35, 40] {
+            storage
+                .write(
+                    "users",
+                    json!({"name": format!("User {}", age), "age": age}),
+                )
+                .unwrap();
+        }
+
+        let filter = json!({"age": {"$gte": 30}});
+        let all = storage.query("users", Some(&filter), 10, 0).unwrap();
+        assert_eq!(ages(&all), vec![30, 35, 40]);
+        let page = storage.query("users", Some(&filter), 2, 1).unwrap();
+        assert_eq!(page.len(), 2);
+
+        // The API refuses scans no index bounds
+        let err = storage.query("users", None, 10, 0).unwrap_err();
+        assert!(err.starts_with("AERO_QUERY_UNBOUNDED"), "{}", err);
+    }
+
+    #[test]
+    fn test_registered_collections_use_their_schema() {
+        let (_temp, _wal, storage) = setup();
+
+        assert!(storage
+            .write("admins", json!({"name": "Root", "age": 50}))
+            .unwrap_err()
+            .contains("not registered"));
+        storage
+            .execute(json!({"op": "create_collection", "collection": "admins",
+                            "schema_id": "users"}))
+            .unwrap();
+
+        let id = storage
+            .write("admins", json!({"name": "Root", "age": 50}))
+            .unwrap();
+        storage
+            .write("users", json!({"name": "Alice", "age": 30}))
+            .unwrap();
+        assert_eq!(
+            storage.read("admins", &id).unwrap().unwrap()["name"],
+            "Root"
+        );
+        assert!(storage.read("users", &id).unwrap().is_none());
+
+        let filter = json!({"age": {"$gte": 0}});
+        let admins = storage.query("admins", Some(&filter), 10, 0).unwrap();
+        assert_eq!(ages(&admins), vec![50]);
+    }
+
+    #[tokio::test]
+    async fn test_unified_pipeline_over_durable_storage() {
+        let (_temp, _wal, storage) = setup();
+        let pipeline = Pipeline::new(UnifiedExecutor::new(storage));
+        let ctx = RequestContext::service_role();
+
+        let write = Operation::Write(WriteOp {
+            collection: "users".to_string(),
+            document: json!({"name": "Alice", "age": 30}),
+            schema_id: "users".to_string(),
+            schema_version: "v1".to_string(),
+        });
+        let written = pipeline.execute(write, ctx.clone()).await.unwrap();
+        let id = written["id"].as_str().unwrap().to_string();
+
+        let read = Operation::Read(ReadOp {
+            collection: "users".to_string(),
+            id,
+            select: None,
+        });
+        let document = pipeline.execute(read, ctx.clone()).await.unwrap();
+        assert_eq!(document["name"], "Alice");
+
+        let query = Operation::Query(QueryOp {
+            collection: "users".to_string(),
+            filter: Some(json!({"age": {"$gte": 18}})),
+            select: None,
+            order: None,
+            limit: 10,
+            offset: 0,
+            schema_id: None,
+            schema_version: None,
+        });
+        let result = pipeline.execute(query, ctx).await.unwrap();
+        assert_eq!(result["count"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_bridge_validates_against_collection_schema() {
+        let (_temp, wal, storage) = setup();
+        let storage = Arc::new(storage);
+        let bridge = PipelineBridge::with_storage(
+            Arc::clone(&storage),
+            BridgeConfig {
+                enable_auth: false,
+                enable_rls: false,
+                schemas: Some(storage),
+                ..BridgeConfig::default()
+            },
+        );
+        let ctx = RequestContext::service_role();
+
+        let err = bridge
+            .write(
+                "users",
+                json!({"name": 1, "age": 2.5}),
+                "users",
+                ctx.clone(),
+            )
+            .await
+            .unwrap_err();
+        let paths: Vec<&str> = err
+            .field_violations()
+            .iter()
+            .map(|v| v.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["age", "name"]);
+        assert_eq!(wal.lock().unwrap().last_sequence_number(), 0);
+
+        let written = bridge
+            .write("users", json!({"name": "Alice"}), "users", ctx.clone())
+            .await
+            .unwrap();
+        let id = written["id"].as_str().unwrap();
+        let err = bridge
+            .update("users", id, json!({"age": "old"}), ctx)
+            .await
+            .unwrap_err();
+        assert_eq!(err.field_violations()[0].expected, "int");
+        assert!(bridge
+            .write(
+                "orders",
+                json!({}),
+                "orders",
+                RequestContext::service_role()
+            )
+            .await
+            .unwrap_err()
+            .to_string()
+            .contains("not registered"));
+    }
+}
@@ -4,6 +4,8 @@
 
 use std::fmt;
 
+use crate::schema::FieldViolation;
+
 /// Core module result type
 pub type CoreResult<T> = Result<T, CoreError>;
 
@@ -19,9 +21,15 @@ pub enum CoreError {
     /// Resource not found
     NotFound(String),
 
+    /// Caller exceeded its request rate
+    RateLimited(String),
+
     /// Validation error
     Validation(String),
 
+    /// Document fields violating their schema
+    InvalidFields(Vec<FieldViolation>),
+
     /// Execution error
     Execution(String),
 
@@ -35,7 +43,18 @@ impl fmt::Display for CoreError {
             Self::AuthRequired => write!(f, "Authentication required"),
             Self::AccessDenied(msg) => write!(f, "Access denied: {}", msg),
             Self::NotFound(msg) => write!(f, "Not found: {}", msg),
+            Self::RateLimited(msg) => write!(f, "Rate limited: {}", msg),
             Self::Validation(msg) => write!(f, "Validation error: {}", msg),
+            Self::InvalidFields(violations) => {
+                write!(f, "Validation error: ")?;
+                for (i, violation) in violations.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}", violation)?;
+                }
+                Ok(())
+            }
             Self::Execution(msg) => write!(f, "Execution error: {}", msg),
             Self::Internal(msg) => write!(f, "Internal error: {}", msg),
         }
@@ -55,6 +74,24 @@ impl CoreError {
         Self::Validation(msg.into())
     }
 
+    /// Create an error listing field violations
+    pub fn invalid_fields(violations: Vec<FieldViolation>) -> Self {
+        Self::InvalidFields(violations)
+    }
+
+    /// Field violations, for errors that list them
+    pub fn field_violations(&self) -> &[FieldViolation] {
+        match self {
+            Self::InvalidFields(violations) => violations,
+            _ => &[],
+        }
+    }
+
+    /// Create a rate limited error
+    pub fn rate_limited(msg: impl Into<String>) -> Self {
+        Self::RateLimited(msg.into())
+    }
+
     /// Create an access denied error
     pub fn access_denied(msg: impl Into<String>) -> Self {
         Self::AccessDenied(msg.into())
@@ -76,7 +113,8 @@ impl CoreError {
             Self::AuthRequired => "AUTH_REQUIRED",
             Self::AccessDenied(_) => "ACCESS_DENIED",
             Self::NotFound(_) => "NOT_FOUND",
-            Self::Validation(_) => "VALIDATION_ERROR",
+            Self::RateLimited(_) => "RATE_LIMITED",
+            Self::Validation(_) | Self::InvalidFields(_) => "VALIDATION_ERROR",
             Self::Execution(_) => "EXECUTION_ERROR",
             Self::Internal(_) => "INTERNAL_ERROR",
         }
@@ -88,7 +126,8 @@ impl CoreError {
             Self::AuthRequired => 401,
             Self::AccessDenied(_) => 403,
             Self::NotFound(_) => 404,
-            Self::Validation(_) => 400,
+            Self::RateLimited(_) => 429,
+            Self::Validation(_) | Self::InvalidFields(_) => 400,
             Self::Execution(_) => 500,
             Self::Internal(_) => 500,
         }
@@ -10,10 +10,11 @@ use std::sync::Arc;
 
 use serde_json::{json, Value};
 
-use crate::core::context::RequestContext;
+use crate::core::context::{FilterOperator, RequestContext, RlsFilter};
 use crate::core::error::CoreError;
 use crate::core::operation::{DeleteOp, Operation, QueryOp, ReadOp, UpdateOp, WriteOp};
 use crate::core::pipeline::{OperationExecutor, OperationResult};
+use crate::executor::PredicateFilter;
 
 /// Trait for the storage backend
 pub trait StorageBackend: Send + Sync {
@@ -39,6 +40,34 @@ pub trait StorageBackend: Send + Sync {
     ) -> Result<Vec<Value>, String>;
 }
 
+impl<T: StorageBackend + ?Sized> StorageBackend for Arc<T> {
+    fn read(&self, collection: &str, id: &str) -> Result<Option<Value>, String> {
+        (**self).read(collection, id)
+    }
+
+    fn write(&self, collection: &str, document: Value) -> Result<String, String> {
+        (**self).write(collection, document)
+    }
+
+    fn update(&self, collection: &str, id: &str, updates: Value) -> Result<Value, String> {
+        (**self).update(collection, id, updates)
+    }
+
+    fn delete(&self, collection: &str, id: &str) -> Result<bool, String> {
+        (**self).delete(collection, id)
+    }
+
+    fn query(
+        &self,
+        collection: &str,
+        filter: Option<&Value>,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<Value>, String> {
+        (**self).query(collection, filter, limit, offset)
+    }
+}
+
 /// Unified executor that routes operations through subsystems
 pub struct UnifiedExecutor {
     storage: Arc<dyn StorageBackend>,
@@ -62,6 +91,7 @@ impl OperationExecutor for UnifiedExecutor {
         // Clone what we need for the async block
         let storage = Arc::clone(&self.storage);
         let rls_filters = ctx.rls_filters.clone();
+        let rls_policies = ctx.rls_policies.clone();
 
         // Clone the operation data we need
         let op_clone = op.clone();
@@ -81,7 +111,8 @@ impl OperationExecutor for UnifiedExecutor {
                             "filter": query.filter,
                             "limit": query.limit,
                             "offset": query.offset,
-                            "rls_filters": rls_filters.len(),
+                            "rls_filters": rls_filters.iter().map(RlsFilter::explain).collect::<Vec<_>>(),
+                            "rls_policies": rls_policies,
                         }
                     }))
                 }
@@ -100,7 +131,7 @@ impl OperationExecutor for UnifiedExecutor {
 fn execute_read(
     storage: &Arc<dyn StorageBackend>,
     op: &ReadOp,
-    rls_filters: &[crate::core::context::RlsFilter],
+    rls_filters: &[RlsFilter],
 ) -> OperationResult {
     let result = storage
         .read(&op.collection, &op.id)
@@ -144,7 +175,7 @@ fn execute_write(storage: &Arc<dyn StorageBackend>, op: &WriteOp) -> OperationRe
 fn execute_update(
     storage: &Arc<dyn StorageBackend>,
     op: &UpdateOp,
-    rls_filters: &[crate::core::context::RlsFilter],
+    rls_filters: &[RlsFilter],
 ) -> OperationResult {
     // First read to check RLS
     let existing = storage
@@ -180,7 +211,7 @@ fn execute_update(
 fn execute_delete(
     storage: &Arc<dyn StorageBackend>,
     op: &DeleteOp,
-    rls_filters: &[crate::core::context::RlsFilter],
+    rls_filters: &[RlsFilter],
 ) -> OperationResult {
     // First read to check RLS
     let existing = storage
@@ -220,7 +251,7 @@ fn execute_delete(
 fn execute_query(
     storage: &Arc<dyn StorageBackend>,
     op: &QueryOp,
-    rls_filters: &[crate::core::context::RlsFilter],
+    rls_filters: &[RlsFilter],
 ) -> OperationResult {
     let results = storage
         .query(&op.collection, op.filter.as_ref(), op.limit, op.offset)
@@ -245,29 +276,20 @@ fn execute_query(
 }
 
 /// Check if a document passes an RLS filter
-fn check_rls_filter(doc: &Value, filter: &crate::core::context::RlsFilter) -> bool {
-    use crate::core::context::FilterOperator;
-
-    let field_value = doc.get(&filter.field);
-
-    match (&filter.operator, field_value) {
-        (FilterOperator::Eq, Some(v)) => v == &filter.value,
-        (FilterOperator::Neq, Some(v)) => v != &filter.value,
-        (FilterOperator::In, Some(v)) => {
-            if let Some(arr) = filter.value.as_array() {
-                arr.contains(v)
-            } else {
-                false
-            }
-        }
-        (FilterOperator::Contains, Some(v)) => {
-            if let (Some(haystack), Some(needle)) = (v.as_str(), filter.value.as_str()) {
-                haystack.contains(needle)
-            } else {
-                false
+fn check_rls_filter(doc: &Value, filter: &RlsFilter) -> bool {
+    match filter.operator {
+        FilterOperator::Contains => {
+            match (
+                doc.get(&filter.field).and_then(Value::as_str),
+                filter.value.as_str(),
+            ) {
+                (Some(haystack), Some(needle)) => haystack.contains(needle),
+                _ => false,
             }
         }
-        _ => false,
+        _ => filter
+            .to_predicate()
+            .is_some_and(|predicate| PredicateFilter::matches(doc, &[predicate])),
     }
 }
 
@@ -1,6 +1,7 @@
 //! Authentication Middleware
 //!
-//! Validates authentication and extracts user context.
+//! Validates authentication and extracts user context, and holds service
+//! API keys to their scopes.
 
 use std::future::Future;
 use std::pin::Pin;
@@ -47,6 +48,23 @@ impl Middleware for AuthMiddleware {
         next: Next<'a>,
     ) -> Pin<Box<dyn Future<Output = OperationResult> + Send + 'a>> {
         Box::pin(async move {
+            // Service keys only reach the collections and operations they
+            // are scoped for
+            if let Some(grant) = &ctx.auth.service_key {
+                let allowed = match (op.collection(), op.action()) {
+                    (Some(collection), Some(action)) => grant.allows(collection, action),
+                    _ => false,
+                };
+                if !allowed {
+                    return Err(CoreError::access_denied(format!(
+                        "API key {} is not scoped for {} on {}",
+                        grant.name,
+                        op.name(),
+                        op.collection().unwrap_or("this resource")
+                    )));
+                }
+            }
+
             // Service role always passes
             if ctx.auth.is_service_role {
                 return next.run(op, ctx).await;
@@ -71,8 +89,9 @@ impl Middleware for AuthMiddleware {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::auth::{KeyScope, RlsAction, ServiceKeyManager};
     use crate::core::context::AuthContext;
-    use crate::core::operation::ReadOp;
+    use crate::core::operation::{DeleteOp, InvokeOp, QueryOp, ReadOp};
     use crate::core::pipeline::{NoOpExecutor, Pipeline};
     use uuid::Uuid;
 
@@ -106,6 +125,57 @@ mod tests {
         assert!(matches!(result, Err(CoreError::AuthRequired)));
     }
 
+    #[tokio::test]
+    async fn test_service_key_held_to_scopes() {
+        let pipeline = Pipeline::new(NoOpExecutor).with_middleware(AuthMiddleware::new());
+        let keys = ServiceKeyManager::new();
+        let (key, _) = keys
+            .create(
+                "billing",
+                vec![KeyScope::new("orders", [RlsAction::Read])],
+                None,
+            )
+            .unwrap();
+        let ctx = RequestContext::new(AuthContext::service_key(key.grant()));
+
+        let query = |collection: &str| {
+            Operation::Query(QueryOp {
+                collection: collection.to_string(),
+                filter: None,
+                select: None,
+                order: None,
+                limit: 10,
+                offset: 0,
+                schema_id: None,
+                schema_version: None,
+            })
+        };
+        assert!(pipeline.execute(query("orders"), ctx.clone()).await.is_ok());
+        assert!(matches!(
+            pipeline.execute(query("users"), ctx.clone()).await,
+            Err(CoreError::AccessDenied(_))
+        ));
+        let delete = Operation::Delete(DeleteOp {
+            collection: "orders".to_string(),
+            id: "order_1".to_string(),
+            schema_id: None,
+        });
+        assert!(matches!(
+            pipeline.execute(delete, ctx.clone()).await,
+            Err(CoreError::AccessDenied(_))
+        ));
+        // Operations outside collections are never in scope
+        let invoke = Operation::Invoke(InvokeOp {
+            function_name: "notify".to_string(),
+            payload: serde_json::Value::Null,
+            async_mode: false,
+        });
+        assert!(matches!(
+            pipeline.execute(invoke, ctx).await,
+            Err(CoreError::AccessDenied(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_anonymous_reads_allowed() {
         let pipeline = Pipeline::new(NoOpExecutor)
@@ -0,0 +1,204 @@
+//! Idempotency Middleware
+//!
+//! A data write carrying an idempotency key in its request metadata runs
+//! once per caller and key: a retry within the window gets the first
+//! run's result instead of writing again. Reusing a key for a different
+//! operation is refused, as is a retry while the first run is still in
+//! flight. Failed runs are forgotten so they can be retried.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::core::context::RequestContext;
+use crate::core::error::CoreError;
+use crate::core::operation::Operation;
+use crate::core::pipeline::{Next, OperationResult};
+
+use super::Middleware;
+
+/// Request metadata key holding the idempotency key
+pub const IDEMPOTENCY_KEY: &str = "idempotency_key";
+
+/// A keyed write, and its result once it has one
+struct Entry {
+    operation: Value,
+    result: Option<Value>,
+    at: Instant,
+}
+
+/// Idempotent write middleware
+pub struct IdempotencyMiddleware {
+    window: Duration,
+    /// (principal, key) -> entry
+    entries: Mutex<HashMap<(String, String), Entry>>,
+}
+
+impl IdempotencyMiddleware {
+    /// Replay results of keyed writes for `window`
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The stored result for `slot`, or `None` after claiming it
+    fn claim(
+        &self,
+        slot: &(String, String),
+        operation: &Value,
+        now: Instant,
+    ) -> Result<Option<Value>, CoreError> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, entry| now.duration_since(entry.at) < self.window);
+        match entries.get(slot) {
+            Some(entry) if &entry.operation != operation => Err(CoreError::validation(format!(
+                "idempotency key {} was used for a different operation",
+                slot.1
+            ))),
+            Some(Entry { result: None, .. }) => Err(CoreError::execution(format!(
+                "operation with idempotency key {} is still in progress",
+                slot.1
+            ))),
+            Some(Entry {
+                result: Some(result),
+                ..
+            }) => Ok(Some(result.clone())),
+            None => {
+                let entry = Entry {
+                    operation: operation.clone(),
+                    result: None,
+                    at: now,
+                };
+                entries.insert(slot.clone(), entry);
+                Ok(None)
+            }
+        }
+    }
+
+    fn settle(&self, slot: (String, String), result: &OperationResult) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        match result {
+            Ok(value) => {
+                if let Some(entry) = entries.get_mut(&slot) {
+                    entry.result = Some(value.clone());
+                }
+            }
+            Err(_) => {
+                entries.remove(&slot);
+            }
+        }
+    }
+}
+
+impl Middleware for IdempotencyMiddleware {
+    fn process<'a>(
+        &'a self,
+        op: &'a Operation,
+        ctx: &'a mut RequestContext,
+        next: Next<'a>,
+    ) -> Pin<Box<dyn Future<Output = OperationResult> + Send + 'a>> {
+        Box::pin(async move {
+            let key = match ctx.metadata.get(IDEMPOTENCY_KEY).and_then(Value::as_str) {
+                Some(key) if op.is_data_write() => key.to_string(),
+                _ => return next.run(op, ctx).await,
+            };
+            let slot = (ctx.auth.principal(), key);
+            let operation = serde_json::to_value(op)?;
+            if let Some(result) = self.claim(&slot, &operation, Instant::now())? {
+                return Ok(result);
+            }
+            let result = next.run(op, ctx).await;
+            self.settle(slot, &result);
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::executor::{InMemoryStorage, UnifiedExecutor};
+    use crate::core::operation::{QueryOp, WriteOp};
+    use crate::core::pipeline::Pipeline;
+    use serde_json::json;
+
+    fn write(name: &str) -> Operation {
+        Operation::Write(WriteOp {
+            collection: "posts".to_string(),
+            document: json!({"title": name}),
+            schema_id: "posts".to_string(),
+            schema_version: "v1".to_string(),
+        })
+    }
+
+    fn keyed(key: &str) -> RequestContext {
+        RequestContext::service_role().with_metadata(IDEMPOTENCY_KEY, json!(key))
+    }
+
+    #[tokio::test]
+    async fn test_retried_write_runs_once() {
+        let pipeline = Pipeline::new(UnifiedExecutor::new(InMemoryStorage::new()))
+            .with_middleware(IdempotencyMiddleware::new(Duration::from_secs(60)));
+
+        let first = pipeline.execute(write("a"), keyed("k1")).await.unwrap();
+        let retry = pipeline.execute(write("a"), keyed("k1")).await.unwrap();
+        assert_eq!(first["id"], retry["id"]);
+
+        let err = pipeline.execute(write("b"), keyed("k1")).await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+
+        // Unkeyed writes are not deduplicated
+        let ctx = RequestContext::service_role();
+        pipeline.execute(write("a"), ctx.clone()).await.unwrap();
+        let query = Operation::Query(QueryOp {
+            collection: "posts".to_string(),
+            filter: None,
+            select: None,
+            order: None,
+            limit: 10,
+            offset: 0,
+            schema_id: None,
+            schema_version: None,
+        });
+        let result = pipeline.execute(query, ctx).await.unwrap();
+        assert_eq!(result["count"], 2);
+    }
+
+    #[test]
+    fn test_in_flight_and_expired_keys() {
+        let idempotency = IdempotencyMiddleware::new(Duration::from_secs(60));
+        let slot = ("service".to_string(), "k1".to_string());
+        let operation = json!({"op": "write"});
+        let start = Instant::now();
+
+        assert!(idempotency
+            .claim(&slot, &operation, start)
+            .unwrap()
+            .is_none());
+        assert!(idempotency.claim(&slot, &operation, start).is_err());
+
+        // A failed run frees the key
+        idempotency.settle(slot.clone(), &Err(CoreError::internal("disk")));
+        assert!(idempotency
+            .claim(&slot, &operation, start)
+            .unwrap()
+            .is_none());
+        idempotency.settle(slot.clone(), &Ok(json!({"id": "1"})));
+        assert_eq!(
+            idempotency.claim(&slot, &operation, start).unwrap(),
+            Some(json!({"id": "1"}))
+        );
+
+        let later = start + Duration::from_secs(60);
+        assert!(idempotency
+            .claim(&slot, &operation, later)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -22,5 +22,9 @@ pub trait Middleware: Send + Sync {
 
 /// Composable middleware implementations
 pub mod auth;
+pub mod idempotency;
 pub mod observe;
+pub mod rate_limit;
+pub mod registry;
 pub mod rls;
+pub mod validate;
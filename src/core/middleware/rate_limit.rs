@@ -0,0 +1,117 @@
+//! Rate Limit Middleware
+//!
+//! Caps how many operations each caller may run per minute, in fixed
+//! one-minute windows. The service role is not limited; service keys are
+//! limited per key and users per user, while anonymous callers share a
+//! single allowance.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::core::context::RequestContext;
+use crate::core::error::CoreError;
+use crate::core::operation::Operation;
+use crate::core::pipeline::{Next, OperationResult};
+
+use super::Middleware;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Per-caller rate limit middleware
+pub struct RateLimitMiddleware {
+    per_minute: u32,
+    /// Principal -> (window start, operations in the window)
+    windows: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimitMiddleware {
+    /// Allow each caller `per_minute` operations a minute
+    pub fn per_minute(per_minute: u32) -> Self {
+        Self {
+            per_minute,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn admit(&self, principal: String, now: Instant) -> Result<(), CoreError> {
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        windows.retain(|_, (start, _)| now.duration_since(*start) < WINDOW);
+        let (start, count) = windows.entry(principal).or_insert((now, 0));
+        if *count >= self.per_minute {
+            let retry = WINDOW.saturating_sub(now.duration_since(*start));
+            return Err(CoreError::rate_limited(format!(
+                "more than {} operations a minute; retry in {}s",
+                self.per_minute,
+                retry.as_secs().max(1)
+            )));
+        }
+        *count += 1;
+        Ok(())
+    }
+}
+
+impl Middleware for RateLimitMiddleware {
+    fn process<'a>(
+        &'a self,
+        op: &'a Operation,
+        ctx: &'a mut RequestContext,
+        next: Next<'a>,
+    ) -> Pin<Box<dyn Future<Output = OperationResult> + Send + 'a>> {
+        Box::pin(async move {
+            if !(ctx.auth.is_service_role && ctx.auth.service_key.is_none()) {
+                self.admit(ctx.auth.principal(), Instant::now())?;
+            }
+            next.run(op, ctx).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::context::AuthContext;
+    use crate::core::operation::ReadOp;
+    use crate::core::pipeline::{NoOpExecutor, Pipeline};
+    use uuid::Uuid;
+
+    fn read() -> Operation {
+        Operation::Read(ReadOp {
+            collection: "users".to_string(),
+            id: "user_1".to_string(),
+            select: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_callers_limited_separately() {
+        let pipeline =
+            Pipeline::new(NoOpExecutor).with_middleware(RateLimitMiddleware::per_minute(2));
+        let alice = RequestContext::new(AuthContext::authenticated(Uuid::new_v4()));
+        let bob = RequestContext::new(AuthContext::authenticated(Uuid::new_v4()));
+
+        for _ in 0..2 {
+            assert!(pipeline.execute(read(), alice.clone()).await.is_ok());
+        }
+        let err = pipeline.execute(read(), alice).await.unwrap_err();
+        assert_eq!(err.code(), "RATE_LIMITED");
+        assert_eq!(err.status_code(), 429);
+
+        assert!(pipeline.execute(read(), bob).await.is_ok());
+        for _ in 0..3 {
+            let ctx = RequestContext::service_role();
+            assert!(pipeline.execute(read(), ctx).await.is_ok());
+        }
+    }
+
+    #[test]
+    fn test_window_resets() {
+        let limit = RateLimitMiddleware::per_minute(1);
+        let start = Instant::now();
+        assert!(limit.admit("anonymous".to_string(), start).is_ok());
+        assert!(limit.admit("anonymous".to_string(), start).is_err());
+        assert!(limit.admit("anonymous".to_string(), start + WINDOW).is_ok());
+    }
+}
@@ -0,0 +1,197 @@
+//! Middleware Registry
+//!
+//! Pipeline stages by name, and the configuration choosing which of them
+//! run and in what order. The built-in stages are `auth`, `rate_limit`,
+//! `rls`, `idempotency`, `validate` and `observe`; embedders register
+//! their own under new names, or replace a built-in by registering its
+//! name.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::error::{CoreError, CoreResult};
+
+use super::Middleware;
+
+/// Names of the built-in stages
+pub const BUILTIN_STAGES: &[&str] = &[
+    "auth",
+    "rate_limit",
+    "rls",
+    "idempotency",
+    "validate",
+    "observe",
+];
+
+/// Which pipeline stages run, and their settings
+///
+/// ```toml
+/// [http.pipeline]
+/// stages = ["auth", "rate_limit", "rls", "idempotency", "validate", "observe"]
+/// rate_limit_per_minute = 600
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MiddlewareConfig {
+    /// Stages in the order operations pass through them (default: auth,
+    /// rls, validate, observe)
+    pub stages: Vec<String>,
+    /// Operations a caller may run per minute under `rate_limit`
+    /// (default: 600)
+    pub rate_limit_per_minute: u32,
+    /// Seconds `idempotency` replays a keyed write's result (default:
+    /// 86400)
+    pub idempotency_window_secs: u64,
+}
+
+impl Default for MiddlewareConfig {
+    fn default() -> Self {
+        Self {
+            stages: ["auth", "rls", "validate", "observe"]
+                .map(String::from)
+                .to_vec(),
+            rate_limit_per_minute: 600,
+            idempotency_window_secs: 86400,
+        }
+    }
+}
+
+impl MiddlewareConfig {
+    /// Check the stages are built in and listed once, and the limits
+    /// positive
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(stage) = self
+            .stages
+            .iter()
+            .find(|s| !BUILTIN_STAGES.contains(&s.as_str()))
+        {
+            return Err(format!(
+                "'{}' is not a pipeline stage; expected one of {}",
+                stage,
+                BUILTIN_STAGES.join(", ")
+            ));
+        }
+        if let Some(stage) = duplicate(&self.stages) {
+            return Err(format!("stage '{}' is listed twice", stage));
+        }
+        if self.rate_limit_per_minute == 0 {
+            return Err("rate_limit_per_minute must be > 0".to_string());
+        }
+        if self.idempotency_window_secs == 0 {
+            return Err("idempotency_window_secs must be > 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn duplicate(stages: &[String]) -> Option<&str> {
+    let mut seen = HashSet::new();
+    stages
+        .iter()
+        .find(|s| !seen.insert(s.as_str()))
+        .map(String::as_str)
+}
+
+/// Middleware by stage name
+#[derive(Default)]
+pub struct MiddlewareRegistry {
+    stages: HashMap<String, Arc<dyn Middleware>>,
+    /// Names in registration order
+    order: Vec<String>,
+}
+
+impl MiddlewareRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `middleware` as `name`, replacing any stage of that name
+    pub fn register(&mut self, name: impl Into<String>, middleware: Arc<dyn Middleware>) {
+        let name = name.into();
+        if self.stages.insert(name.clone(), middleware).is_none() {
+            self.order.push(name);
+        }
+    }
+
+    /// Whether a stage is registered as `name`
+    pub fn contains(&self, name: &str) -> bool {
+        self.stages.contains_key(name)
+    }
+
+    /// Names registered, in registration order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.order.iter().map(String::as_str)
+    }
+
+    /// The middleware for `stages`, in that order
+    ///
+    /// # Errors
+    ///
+    /// `VALIDATION_ERROR` for a stage listed twice or not registered.
+    pub fn resolve(&self, stages: &[String]) -> CoreResult<Vec<Arc<dyn Middleware>>> {
+        if let Some(stage) = duplicate(stages) {
+            return Err(CoreError::validation(format!(
+                "pipeline stage '{}' is listed twice",
+                stage
+            )));
+        }
+        stages
+            .iter()
+            .map(|stage| {
+                self.stages.get(stage).cloned().ok_or_else(|| {
+                    CoreError::validation(format!("unknown pipeline stage '{}'", stage))
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::middleware::auth::AuthMiddleware;
+
+    fn stages(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(MiddlewareConfig::default().validate().is_ok());
+
+        let config = MiddlewareConfig {
+            stages: stages(&["auth", "ratelimit"]),
+            ..MiddlewareConfig::default()
+        };
+        assert!(config.validate().unwrap_err().contains("'ratelimit'"));
+
+        let config = MiddlewareConfig {
+            stages: stages(&["auth", "rls", "auth"]),
+            ..MiddlewareConfig::default()
+        };
+        assert!(config.validate().unwrap_err().contains("twice"));
+
+        let config = MiddlewareConfig {
+            rate_limit_per_minute: 0,
+            ..MiddlewareConfig::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_resolve_in_listed_order() {
+        let mut registry = MiddlewareRegistry::new();
+        registry.register("auth", Arc::new(AuthMiddleware::new()));
+        registry.register("tenant", Arc::new(AuthMiddleware::new()));
+        registry.register("auth", Arc::new(AuthMiddleware::new()));
+        assert_eq!(registry.names().collect::<Vec<_>>(), ["auth", "tenant"]);
+
+        let resolved = registry.resolve(&stages(&["tenant", "auth"])).unwrap();
+        assert_eq!(resolved.len(), 2);
+        assert!(registry.resolve(&stages(&["audit"])).is_err());
+        assert!(registry.resolve(&stages(&["auth", "auth"])).is_err());
+    }
+}
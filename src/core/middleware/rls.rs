@@ -1,6 +1,16 @@
 //! RLS Middleware
 //!
 //! Evaluates Row-Level Security policies and injects filters.
+//!
+//! Collections with policies published in an `RlsPolicyStore` are guarded
+//! by those: their compiled predicates become RLS filters for reads,
+//! updates and deletes, and inserted or updated fields are checked
+//! against them. Other collections fall back to the policy provider.
+//!
+//! Commands touching a collection's documents (batches, transactions,
+//! truncation) are not row-scoped, so only the service role may run them;
+//! nor may anyone else change the catalog (create collections, indexes or
+//! schemas).
 
 use std::future::Future;
 use std::pin::Pin;
@@ -8,9 +18,11 @@ use std::sync::Arc;
 
 use serde_json::Value;
 
+use crate::auth::rls_policy::RlsAction;
+use crate::auth::{AuthError, PublishedPolicies, RlsContext, RlsPolicyStore};
 use crate::core::context::{FilterOperator, RequestContext, RlsFilter};
-use crate::core::error::CoreError;
-use crate::core::operation::Operation;
+use crate::core::error::{CoreError, CoreResult};
+use crate::core::operation::{CommandOp, Operation};
 use crate::core::pipeline::{Next, OperationResult};
 
 use super::Middleware;
@@ -92,18 +104,73 @@ impl RlsPolicyProvider for OwnershipPolicy {
 /// RLS middleware
 pub struct RlsMiddleware {
     policy: Arc<dyn RlsPolicyProvider>,
+    /// Declarative policies, which take precedence where published
+    policy_store: Option<Arc<RlsPolicyStore>>,
 }
 
 impl RlsMiddleware {
     pub fn new(policy: impl RlsPolicyProvider + 'static) -> Self {
         Self {
             policy: Arc::new(policy),
+            policy_store: None,
         }
     }
 
     pub fn ownership() -> Self {
         Self::new(OwnershipPolicy::default())
     }
+
+    /// Enforce the policies published in `store`
+    pub fn with_policy_store(mut self, store: Arc<RlsPolicyStore>) -> Self {
+        self.policy_store = Some(store);
+        self
+    }
+}
+
+/// Apply a collection's published policies to `op`
+fn apply_published(
+    published: &PublishedPolicies,
+    op: &Operation,
+    ctx: &mut RequestContext,
+) -> CoreResult<()> {
+    let action = op.action().unwrap_or(RlsAction::Read);
+    let rls_ctx = RlsContext {
+        user_id: ctx.auth.user_id,
+        is_authenticated: ctx.auth.is_authenticated,
+        is_service_role: ctx.auth.is_service_role,
+        claims: ctx.auth.claims.clone(),
+    };
+    let decision = published
+        .policies
+        .evaluate(action, &rls_ctx)
+        .map_err(|e| match e {
+            AuthError::AuthenticationRequired => CoreError::AuthRequired,
+            e => CoreError::access_denied(e.to_string()),
+        })?;
+
+    let allowed = match op {
+        Operation::Write(w) => decision.allows(&w.document),
+        Operation::Update(u) => decision.allows_changes(&u.updates),
+        _ => true,
+    };
+    if !allowed {
+        return Err(CoreError::access_denied(format!(
+            "Document violates RLS policies on {}",
+            published.collection
+        )));
+    }
+
+    if action != RlsAction::Insert {
+        ctx.rls_filters
+            .extend(decision.predicates.into_iter().map(RlsFilter::from));
+    }
+    ctx.rls_policies.extend(
+        decision
+            .fired
+            .iter()
+            .map(|name| format!("{}.{}@v{}", published.collection, name, published.version)),
+    );
+    Ok(())
 }
 
 impl Middleware for RlsMiddleware {
@@ -119,31 +186,70 @@ impl Middleware for RlsMiddleware {
                 return next.run(op, ctx).await;
             }
 
-            // Get collection if applicable
-            if let Some(collection) = op.collection() {
-                // Get and inject RLS filter for reads
-                match op {
-                    Operation::Read(_) | Operation::Query(_) => {
-                        let filter = self
-                            .policy
-                            .get_read_filter(collection, ctx.auth.user_id)
-                            .map_err(CoreError::access_denied)?;
-
-                        if let Some(f) = filter {
-                            ctx.rls_filters.push(f);
-                        }
+            if let Operation::Command(CommandOp {
+                command,
+                collection,
+                writes,
+            }) = op
+            {
+                match collection {
+                    Some(collection) => {
+                        return Err(CoreError::access_denied(format!(
+                            "{} on {} is not row-scoped and requires the service role",
+                            command, collection
+                        )))
                     }
-                    Operation::Write(w) => {
-                        self.policy
-                            .validate_write(collection, &w.document, ctx.auth.user_id)
-                            .map_err(CoreError::access_denied)?;
+                    None if *writes => {
+                        return Err(CoreError::access_denied(format!(
+                            "{} changes the catalog and requires the service role",
+                            command
+                        )))
                     }
-                    Operation::Update(u) => {
-                        self.policy
-                            .validate_write(collection, &u.updates, ctx.auth.user_id)
-                            .map_err(CoreError::access_denied)?;
+                    None => {}
+                }
+            }
+
+            // Published policies replace the provider for their collection
+            let published = op.collection().and_then(|collection| {
+                self.policy_store
+                    .as_ref()
+                    .and_then(|store| store.active(collection))
+            });
+            if let Some(published) = published {
+                apply_published(&published, op, ctx)?;
+                return next.run(op, ctx).await;
+            }
+
+            // Get collection if applicable
+            if let Some(collection) = op.collection() {
+                // Validate written fields
+                let written = match op {
+                    Operation::Write(w) => Some(&w.document),
+                    Operation::Update(u) => Some(&u.updates),
+                    _ => None,
+                };
+                if let Some(document) = written {
+                    self.policy
+                        .validate_write(collection, document, ctx.auth.user_id)
+                        .map_err(CoreError::access_denied)?;
+                }
+
+                // Inject the row filter for everything touching stored rows
+                if matches!(
+                    op,
+                    Operation::Read(_)
+                        | Operation::Query(_)
+                        | Operation::Update(_)
+                        | Operation::Delete(_)
+                ) {
+                    let filter = self
+                        .policy
+                        .get_read_filter(collection, ctx.auth.user_id)
+                        .map_err(CoreError::access_denied)?;
+
+                    if let Some(f) = filter {
+                        ctx.rls_filters.push(f);
                     }
-                    _ => {}
                 }
             }
 
@@ -156,10 +262,147 @@ impl Middleware for RlsMiddleware {
 mod tests {
     use super::*;
     use crate::core::context::AuthContext;
-    use crate::core::operation::{QueryOp, WriteOp};
+    use crate::core::executor::{InMemoryStorage, StorageBackend, UnifiedExecutor};
+    use crate::core::operation::{DeleteOp, QueryOp, UpdateOp, WriteOp};
     use crate::core::pipeline::{NoOpExecutor, Pipeline};
+    use serde_json::json;
     use uuid::Uuid;
 
+    fn query(collection: &str) -> QueryOp {
+        QueryOp {
+            collection: collection.to_string(),
+            filter: None,
+            select: None,
+            order: None,
+            limit: 10,
+            offset: 0,
+            schema_id: None,
+            schema_version: None,
+        }
+    }
+
+    fn insert(document: Value) -> Operation {
+        Operation::Write(WriteOp {
+            collection: "posts".to_string(),
+            document,
+            schema_id: "posts".to_string(),
+            schema_version: "v1".to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_published_policies_filter_and_explain() {
+        let store = Arc::new(RlsPolicyStore::new());
+        store
+            .publish(
+                "posts",
+                "policy own_rows on all when authenticated using owner_id = auth.uid;
+                 policy published on read when anonymous using status = 'published';",
+            )
+            .unwrap();
+        let pipeline = Pipeline::new(UnifiedExecutor::new(InMemoryStorage::new()))
+            .with_middleware(RlsMiddleware::ownership().with_policy_store(store));
+
+        let alice = Uuid::new_v4();
+        let bob = Uuid::new_v4();
+        let as_user = |id| RequestContext::new(AuthContext::authenticated(id));
+        for (owner, status) in [(alice, "published"), (alice, "draft"), (bob, "draft")] {
+            let doc = json!({"owner_id": owner.to_string(), "status": status});
+            pipeline.execute(insert(doc), as_user(owner)).await.unwrap();
+        }
+
+        // Inserting a row owned by someone else is rejected
+        let forged = json!({"owner_id": bob.to_string(), "status": "draft"});
+        assert!(matches!(
+            pipeline.execute(insert(forged), as_user(alice)).await,
+            Err(CoreError::AccessDenied(_))
+        ));
+
+        let result = pipeline
+            .execute(Operation::Query(query("posts")), as_user(alice))
+            .await
+            .unwrap();
+        assert_eq!(result["count"], 2);
+        let result = pipeline
+            .execute(
+                Operation::Query(query("posts")),
+                RequestContext::anonymous(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result["count"], 1);
+        assert_eq!(result["data"][0]["status"], "published");
+
+        // Explain shows the policies that fired and the filters they added
+        let plan = pipeline
+            .execute(Operation::Explain(query("posts")), as_user(alice))
+            .await
+            .unwrap();
+        assert_eq!(plan["plan"]["rls_policies"], json!(["posts.own_rows@v1"]));
+        assert_eq!(
+            plan["plan"]["rls_filters"],
+            json!([{"field": "owner_id", "op": "eq", "value": alice.to_string()}])
+        );
+
+        // Other collections keep the ownership provider
+        let plan = pipeline
+            .execute(Operation::Explain(query("notes")), as_user(alice))
+            .await
+            .unwrap();
+        assert_eq!(plan["plan"]["rls_policies"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_published_policies_guard_mutations() {
+        let storage = InMemoryStorage::new();
+        let bob = Uuid::new_v4();
+        let id = storage
+            .write("posts", json!({"owner_id": bob.to_string()}))
+            .unwrap();
+        let store = Arc::new(RlsPolicyStore::new());
+        store
+            .publish(
+                "posts",
+                "policy own_rows on update, delete using owner_id = auth.uid;",
+            )
+            .unwrap();
+        let pipeline = Pipeline::new(UnifiedExecutor::new(storage))
+            .with_middleware(RlsMiddleware::ownership().with_policy_store(store));
+
+        let alice = RequestContext::new(AuthContext::authenticated(Uuid::new_v4()));
+        let delete = Operation::Delete(DeleteOp {
+            collection: "posts".to_string(),
+            id: id.clone(),
+            schema_id: None,
+        });
+        assert!(matches!(
+            pipeline.execute(delete.clone(), alice.clone()).await,
+            Err(CoreError::AccessDenied(_))
+        ));
+        // No policy covers reads
+        assert!(matches!(
+            pipeline
+                .execute(Operation::Query(query("posts")), alice)
+                .await,
+            Err(CoreError::AccessDenied(_))
+        ));
+
+        // The owner cannot hand the row to someone else
+        let bob = RequestContext::new(AuthContext::authenticated(bob));
+        let update = Operation::Update(UpdateOp {
+            collection: "posts".to_string(),
+            id,
+            updates: json!({"owner_id": Uuid::new_v4().to_string()}),
+            schema_id: None,
+            schema_version: None,
+        });
+        assert!(matches!(
+            pipeline.execute(update, bob.clone()).await,
+            Err(CoreError::AccessDenied(_))
+        ));
+        assert!(pipeline.execute(delete, bob).await.is_ok());
+    }
+
     #[tokio::test]
     async fn test_rls_injects_filter() {
         let pipeline = Pipeline::new(NoOpExecutor).with_middleware(RlsMiddleware::ownership());
@@ -203,4 +446,44 @@ mod tests {
         let result = pipeline.execute(op, ctx).await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_collection_commands_require_service_role() {
+        let pipeline = Pipeline::new(NoOpExecutor).with_middleware(RlsMiddleware::ownership());
+        let command = |name: &str, collection: Option<&str>, writes: bool| {
+            Operation::Command(CommandOp {
+                command: name.to_string(),
+                collection: collection.map(str::to_string),
+                writes,
+            })
+        };
+
+        let user = RequestContext::new(AuthContext::authenticated(Uuid::new_v4()));
+        let denied = pipeline
+            .execute(command("insert_many", Some("posts"), true), user.clone())
+            .await;
+        assert!(matches!(denied, Err(CoreError::AccessDenied(_))));
+        assert!(pipeline
+            .execute(command("list_collections", None, false), user.clone())
+            .await
+            .is_ok());
+
+        // Catalog changes name no collection but are not row-scoped either
+        for name in ["create_collection", "create_index", "create_schema"] {
+            let denied = pipeline
+                .execute(command(name, None, true), user.clone())
+                .await;
+            assert!(matches!(denied, Err(CoreError::AccessDenied(_))));
+        }
+
+        let service = RequestContext::service_role();
+        assert!(pipeline
+            .execute(command("insert_many", Some("posts"), true), service.clone())
+            .await
+            .is_ok());
+        assert!(pipeline
+            .execute(command("create_index", None, true), service)
+            .await
+            .is_ok());
+    }
 }
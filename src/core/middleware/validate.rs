@@ -0,0 +1,198 @@
+//! Validation Middleware
+//!
+//! Checks write payloads against the schema of their collection before
+//! they reach the executor, reporting every field violation (path,
+//! expected type, constraint) at once instead of the first one storage
+//! would refuse.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::core::context::RequestContext;
+use crate::core::error::{CoreError, CoreResult};
+use crate::core::operation::Operation;
+use crate::core::pipeline::{Next, OperationResult};
+use crate::schema::FieldViolation;
+
+use super::Middleware;
+
+/// Registered schemas, by collection
+pub trait SchemaSource: Send + Sync {
+    /// Field violations of `document` against the schema `collection`
+    /// stores; with `partial`, `document` holds only the fields being set
+    ///
+    /// Fails for a collection with no schema to validate against.
+    fn violations(
+        &self,
+        collection: &str,
+        document: &Value,
+        partial: bool,
+    ) -> CoreResult<Vec<FieldViolation>>;
+}
+
+/// Schema validation middleware
+pub struct ValidationMiddleware {
+    schemas: Arc<dyn SchemaSource>,
+}
+
+impl ValidationMiddleware {
+    /// Validate against the schemas in `schemas`
+    pub fn new(schemas: Arc<dyn SchemaSource>) -> Self {
+        Self { schemas }
+    }
+
+    fn check(&self, op: &Operation) -> CoreResult<()> {
+        let violations = match op {
+            // The executor assigns ids to documents written without one
+            Operation::Write(write) => self
+                .schemas
+                .violations(&write.collection, &write.document, false)?
+                .into_iter()
+                .filter(|v| !(v.path == "_id" && v.constraint == "required"))
+                .collect(),
+            // Updates never change _id, so it is not validated
+            Operation::Update(update) => {
+                let mut updates = update.updates.clone();
+                if let Some(obj) = updates.as_object_mut() {
+                    obj.remove("_id");
+                }
+                self.schemas
+                    .violations(&update.collection, &updates, true)?
+            }
+            _ => Vec::new(),
+        };
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(CoreError::invalid_fields(violations))
+        }
+    }
+}
+
+impl Middleware for ValidationMiddleware {
+    fn process<'a>(
+        &'a self,
+        op: &'a Operation,
+        ctx: &'a mut RequestContext,
+        next: Next<'a>,
+    ) -> Pin<Box<dyn Future<Output = OperationResult> + Send + 'a>> {
+        Box::pin(async move {
+            self.check(op)?;
+            next.run(op, ctx).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::operation::{ReadOp, UpdateOp, WriteOp};
+    use crate::core::pipeline::{NoOpExecutor, Pipeline};
+    use crate::schema::{FieldDef, Schema, SchemaLoader, SchemaValidator};
+    use serde_json::json;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    /// Every collection stores users v1
+    struct Users(SchemaLoader);
+
+    impl SchemaSource for Users {
+        fn violations(
+            &self,
+            _collection: &str,
+            document: &Value,
+            partial: bool,
+        ) -> CoreResult<Vec<FieldViolation>> {
+            SchemaValidator::new(&self.0)
+                .violations("users", "v1", document, partial)
+                .map_err(|e| CoreError::validation(e.message()))
+        }
+    }
+
+    fn pipeline() -> (TempDir, Pipeline) {
+        let temp_dir = TempDir::new().unwrap();
+        let mut loader = SchemaLoader::new(temp_dir.path());
+        let mut fields = HashMap::new();
+        fields.insert("_id".to_string(), FieldDef::required_string());
+        fields.insert("name".to_string(), FieldDef::required_string());
+        fields.insert("age".to_string(), FieldDef::optional_int());
+        loader.register(Schema::new("users", "v1", fields)).unwrap();
+
+        let validation = ValidationMiddleware::new(Arc::new(Users(loader)));
+        let pipeline = Pipeline::new(NoOpExecutor).with_middleware(validation);
+        (temp_dir, pipeline)
+    }
+
+    fn write(document: Value) -> Operation {
+        Operation::Write(WriteOp {
+            collection: "users".to_string(),
+            document,
+            schema_id: "users".to_string(),
+            schema_version: "v1".to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_write_lists_field_violations() {
+        let (_temp, pipeline) = pipeline();
+        let ctx = RequestContext::service_role();
+
+        let result = pipeline
+            .execute(
+                write(json!({"age": "thirty", "role": "admin"})),
+                ctx.clone(),
+            )
+            .await;
+        let err = result.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION_ERROR");
+        let found: Vec<(&str, &str)> = err
+            .field_violations()
+            .iter()
+            .map(|v| (v.path.as_str(), v.constraint))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("age", "type"), ("name", "required"), ("role", "declared")]
+        );
+
+        // A missing _id is left to the executor to assign
+        let valid = write(json!({"name": "Alice", "age": 30}));
+        assert!(pipeline.execute(valid, ctx).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_update_checks_only_fields_set() {
+        let (_temp, pipeline) = pipeline();
+        let ctx = RequestContext::service_role();
+        let update = |updates: Value| {
+            Operation::Update(UpdateOp {
+                collection: "users".to_string(),
+                id: "user_1".to_string(),
+                updates,
+                schema_id: None,
+                schema_version: None,
+            })
+        };
+
+        let valid = update(json!({"_id": "user_1", "age": 31}));
+        assert!(pipeline.execute(valid, ctx.clone()).await.is_ok());
+
+        let err = pipeline
+            .execute(update(json!({"name": null})), ctx.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(err.field_violations().len(), 1);
+        assert_eq!(err.field_violations()[0].constraint, "not_null");
+
+        // Reads carry no payload to validate
+        let read = Operation::Read(ReadOp {
+            collection: "users".to_string(),
+            id: "user_1".to_string(),
+            select: None,
+        });
+        assert!(pipeline.execute(read, ctx).await.is_ok());
+    }
+}
@@ -13,6 +13,7 @@
 pub mod adapter;
 pub mod bridge;
 pub mod context;
+pub mod durable;
 pub mod error;
 pub mod executor;
 pub mod middleware;
@@ -23,6 +24,7 @@ pub mod write_through;
 pub use adapter::{AeroDbConfig, AeroDbStorageBackend, DurableAeroDbBackend};
 pub use bridge::{BridgeConfig, PipelineBridge};
 pub use context::{AuthContext, RequestContext, RlsFilter};
+pub use durable::DurableStorage;
 pub use error::{CoreError, CoreResult};
 pub use executor::{InMemoryStorage, StorageBackend, UnifiedExecutor};
 pub use middleware::Middleware;
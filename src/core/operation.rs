@@ -6,6 +6,8 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::auth::RlsAction;
+
 /// All operations in AeroDB route through this enum
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "op", rename_all = "snake_case")]
@@ -31,6 +33,10 @@ pub enum Operation {
     // File operations
     Upload(FileOp),
     Download(FileOp),
+
+    // Commands outside the single-document model: batches, transactions
+    // and catalog changes
+    Command(CommandOp),
 }
 
 impl Operation {
@@ -42,6 +48,7 @@ impl Operation {
             Self::Update(u) => Some(&u.collection),
             Self::Delete(d) => Some(&d.collection),
             Self::Query(q) | Self::Explain(q) => Some(&q.collection),
+            Self::Command(c) => c.collection.as_deref(),
             _ => None,
         }
     }
@@ -61,9 +68,31 @@ impl Operation {
             Self::Invoke(_) => "invoke",
             Self::Upload(_) => "upload",
             Self::Download(_) => "download",
+            Self::Command(_) => "command",
+        }
+    }
+
+    /// What this operation does to its collection, if it is a data operation
+    pub fn action(&self) -> Option<RlsAction> {
+        match self {
+            Self::Read(_) | Self::Query(_) | Self::Explain(_) => Some(RlsAction::Read),
+            Self::Write(_) => Some(RlsAction::Insert),
+            Self::Update(_) => Some(RlsAction::Update),
+            Self::Delete(_) => Some(RlsAction::Delete),
+            _ => None,
         }
     }
 
+    /// Check if this operation only reads documents
+    pub fn is_data_read(&self) -> bool {
+        matches!(self, Self::Read(_) | Self::Query(_) | Self::Explain(_))
+    }
+
+    /// Check if this operation modifies documents
+    pub fn is_data_write(&self) -> bool {
+        matches!(self, Self::Write(_) | Self::Update(_) | Self::Delete(_))
+    }
+
     /// Check if this operation requires authentication
     pub fn requires_auth(&self) -> bool {
         // Most operations require auth; public reads handled by RLS
@@ -181,6 +210,19 @@ pub struct FileOp {
     pub metadata: Option<Value>,
 }
 
+/// Command outside the single-document model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandOp {
+    /// Command name, e.g. `insert_many` or `create_schema`
+    pub command: String,
+    /// Collection whose documents the command touches, if any
+    #[serde(default)]
+    pub collection: Option<String>,
+    /// Whether the command changes documents or the catalog
+    #[serde(default)]
+    pub writes: bool,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
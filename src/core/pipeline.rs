@@ -24,6 +24,14 @@ pub struct Next<'a> {
 }
 
 impl<'a> Next<'a> {
+    /// Chain running `middleware` in order, then `executor`
+    pub fn new(middleware: &'a [Arc<dyn Middleware>], executor: &'a dyn OperationExecutor) -> Self {
+        Self {
+            middleware,
+            executor,
+        }
+    }
+
     /// Run the next middleware or executor
     pub fn run(
         self,
@@ -78,10 +86,7 @@ impl Pipeline {
 
     /// Execute an operation through the pipeline
     pub async fn execute(&self, op: Operation, mut ctx: RequestContext) -> OperationResult {
-        let next = Next {
-            middleware: &self.middleware,
-            executor: self.executor.as_ref(),
-        };
+        let next = Next::new(&self.middleware, self.executor.as_ref());
         next.run(&op, &mut ctx).await
     }
 
@@ -110,6 +115,12 @@ impl PipelineBuilder {
         self
     }
 
+    /// Add middleware shared with other pipelines
+    pub fn with_shared(mut self, m: Arc<dyn Middleware>) -> Self {
+        self.middleware.push(m);
+        self
+    }
+
     /// Build the pipeline with the given executor
     pub fn build(self, executor: impl OperationExecutor + 'static) -> Pipeline {
         Pipeline {
@@ -107,6 +107,8 @@ pub mod points {
     pub const RECOVERY_START: &str = "recovery_start";
     pub const RECOVERY_AFTER_WAL_REPLAY: &str = "recovery_after_wal_replay";
     pub const RECOVERY_AFTER_INDEX_REBUILD: &str = "recovery_after_index_rebuild";
+    pub const RECOVERY_BEFORE_TAIL_TRUNCATE: &str = "recovery_before_tail_truncate";
+    pub const RECOVERY_AFTER_TAIL_TRUNCATE: &str = "recovery_after_tail_truncate";
 
     // MVCC crash points per MVCC_FAILURE_MATRIX.md
     pub const MVCC_BEFORE_COMMIT_RECORD: &str = "mvcc_before_commit_record";
@@ -150,6 +152,8 @@ pub mod points {
             RECOVERY_START,
             RECOVERY_AFTER_WAL_REPLAY,
             RECOVERY_AFTER_INDEX_REBUILD,
+            RECOVERY_BEFORE_TAIL_TRUNCATE,
+            RECOVERY_AFTER_TAIL_TRUNCATE,
             MVCC_BEFORE_COMMIT_RECORD,
             MVCC_AFTER_COMMIT_RECORD,
             MVCC_AFTER_COMMIT_FSYNC,
@@ -173,7 +177,7 @@ mod tests {
     #[test]
     fn test_all_crash_points_defined() {
         let all = points::all();
-        assert_eq!(all.len(), 35);
+        assert_eq!(all.len(), 37);
 
         // Verify WAL points
         assert!(all.contains(&"wal_before_append"));
@@ -0,0 +1,315 @@
+//! Phase 7 API Keys
+//!
+//! Per PHASE7_AUTHORITY_MODEL.md §5:
+//! - Authority is never inferred; a remote request carries it explicitly
+//! - Any ambiguity in authority is treated as no authority
+//!
+//! An API key is an HS256-signed token naming its holder and authority
+//! level. Every issued key is registered in `control_api_keys.json` in the
+//! data directory; a key authenticates only while its signature is valid,
+//! it has not expired, and the registry lists it as not revoked.
+//!
+//! The registry is re-read on every lookup, so a key revoked from the CLI
+//! stops working on a running server immediately.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::{Duration, Utc};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::authority::{AuthorityContext, AuthorityLevel};
+use crate::auth::{AuthError, AuthResult};
+
+/// Registry of issued API keys, relative to the data directory
+pub const API_KEYS_FILE: &str = "control_api_keys.json";
+
+/// Issuer and audience of API keys, so auth access tokens are never
+/// accepted as API keys or vice versa
+const API_KEY_ISSUER: &str = "aerodb";
+const API_KEY_AUDIENCE: &str = "aerodb-control";
+
+/// Claims signed into an API key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ApiKeyClaims {
+    /// Key ID
+    sub: String,
+    /// Key holder
+    name: String,
+    /// Authority level name
+    authority: String,
+    iat: i64,
+    exp: i64,
+    aud: String,
+    iss: String,
+}
+
+/// A registered API key. The token itself is never stored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKeyRecord {
+    /// Key ID
+    pub id: Uuid,
+    /// Key holder, recorded as the operator in audit records
+    pub name: String,
+    /// Authority level granted ("OBSERVER", "OPERATOR", "AUDITOR")
+    pub authority: String,
+    /// Issue time (Unix epoch seconds)
+    pub issued_at: i64,
+    /// Expiration time (Unix epoch seconds)
+    pub expires_at: i64,
+    /// Revocation time (Unix epoch seconds), if revoked
+    pub revoked_at: Option<i64>,
+}
+
+impl ApiKeyRecord {
+    /// Whether the key has been revoked
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+}
+
+/// Issues, revokes and authenticates control plane API keys.
+pub struct ApiKeyManager {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    /// Registry file, or `None` to keep the registry in memory
+    registry_path: Option<PathBuf>,
+    keys: Mutex<BTreeMap<Uuid, ApiKeyRecord>>,
+}
+
+impl std::fmt::Debug for ApiKeyManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKeyManager")
+            .field("registry_path", &self.registry_path)
+            .finish()
+    }
+}
+
+impl ApiKeyManager {
+    /// Manager signing with `secret`, keeping its registry in memory
+    pub fn new(secret: &str) -> Self {
+        Self {
+            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
+            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+            registry_path: None,
+            keys: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Manager signing with `secret`, with its registry in `data_dir`
+    pub fn open(secret: &str, data_dir: &Path) -> AuthResult<Self> {
+        let manager = Self {
+            registry_path: Some(data_dir.join(API_KEYS_FILE)),
+            ..Self::new(secret)
+        };
+        manager.reload()?;
+        Ok(manager)
+    }
+
+    /// Issue a key granting `level` to `name`, valid for `ttl`
+    ///
+    /// Returns the registered record and the token to hand to the holder;
+    /// the token cannot be recovered later.
+    pub fn issue(
+        &self,
+        name: &str,
+        level: AuthorityLevel,
+        ttl: Duration,
+    ) -> AuthResult<(ApiKeyRecord, String)> {
+        let now = Utc::now();
+        let record = ApiKeyRecord {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            authority: level.to_string(),
+            issued_at: now.timestamp(),
+            expires_at: (now + ttl).timestamp(),
+            revoked_at: None,
+        };
+        let claims = ApiKeyClaims {
+            sub: record.id.to_string(),
+            name: record.name.clone(),
+            authority: record.authority.clone(),
+            iat: record.issued_at,
+            exp: record.expires_at,
+            aud: API_KEY_AUDIENCE.to_string(),
+            iss: API_KEY_ISSUER.to_string(),
+        };
+        let token = encode(&Header::default(), &claims, &self.encoding_key)
+            .map_err(|_| AuthError::TokenGenerationFailed)?;
+
+        self.update(|keys| {
+            keys.insert(record.id, record.clone());
+            Ok(())
+        })?;
+        Ok((record, token))
+    }
+
+    /// Revoke key `id`; it authenticates nobody from now on
+    pub fn revoke(&self, id: Uuid) -> AuthResult<ApiKeyRecord> {
+        self.update(|keys| {
+            let record = keys.get_mut(&id).ok_or(AuthError::InvalidToken)?;
+            if record.revoked_at.is_none() {
+                record.revoked_at = Some(Utc::now().timestamp());
+            }
+            Ok(record.clone())
+        })
+    }
+
+    /// All registered keys, revoked ones included
+    pub fn list(&self) -> AuthResult<Vec<ApiKeyRecord>> {
+        self.reload()?;
+        Ok(self.keys.lock().unwrap().values().cloned().collect())
+    }
+
+    /// Authority of the holder of `token`
+    ///
+    /// # Errors
+    ///
+    /// A bad signature, expired key, unregistered key or revoked key is an
+    /// error; there is no fallback authority.
+    pub fn authenticate(&self, token: &str) -> AuthResult<AuthorityContext> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_audience(&[API_KEY_AUDIENCE]);
+        validation.set_issuer(&[API_KEY_ISSUER]);
+        let claims = decode::<ApiKeyClaims>(token, &self.decoding_key, &validation)
+            .map_err(|e| match e.kind() {
+                jsonwebtoken::errors::ErrorKind::ExpiredSignature => AuthError::TokenExpired,
+                jsonwebtoken::errors::ErrorKind::InvalidSignature => AuthError::InvalidSignature,
+                _ => AuthError::MalformedToken,
+            })?
+            .claims;
+        let id = Uuid::parse_str(&claims.sub).map_err(|_| AuthError::MalformedToken)?;
+
+        self.reload()?;
+        let keys = self.keys.lock().unwrap();
+        let record = keys.get(&id).ok_or(AuthError::InvalidToken)?;
+        if record.is_revoked() {
+            return Err(AuthError::ApiKeyRevoked);
+        }
+        let level = AuthorityLevel::parse(&record.authority).ok_or(AuthError::MalformedToken)?;
+
+        Ok(AuthorityContext::new(level)
+            .with_operator_id(record.name.clone())
+            .with_session_id(record.id.to_string()))
+    }
+
+    /// Re-read the registry file, if there is one
+    fn reload(&self) -> AuthResult<()> {
+        let Some(path) = &self.registry_path else {
+            return Ok(());
+        };
+        let records: Vec<ApiKeyRecord> = match fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| AuthError::StorageError(format!("{}: {}", path.display(), e)))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                return Err(AuthError::StorageError(format!(
+                    "{}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+        *self.keys.lock().unwrap() = records.into_iter().map(|r| (r.id, r)).collect();
+        Ok(())
+    }
+
+    /// Apply `change` to the latest registry and write it back durably
+    fn update<T>(
+        &self,
+        change: impl FnOnce(&mut BTreeMap<Uuid, ApiKeyRecord>) -> AuthResult<T>,
+    ) -> AuthResult<T> {
+        self.reload()?;
+        let mut keys = self.keys.lock().unwrap();
+        let result = change(&mut keys)?;
+        if let Some(path) = &self.registry_path {
+            let records: Vec<&ApiKeyRecord> = keys.values().collect();
+            write_registry(path, &records)
+                .map_err(|e| AuthError::StorageError(format!("{}: {}", path.display(), e)))?;
+        }
+        Ok(result)
+    }
+}
+
+/// Write `records` to a temporary file, fsync it, and rename it over `path`
+fn write_registry(path: &Path, records: &[&ApiKeyRecord]) -> std::io::Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(&serde_json::to_vec_pretty(records)?)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_key_maps_to_authority_level() {
+        let keys = ApiKeyManager::new("secret");
+        let (record, token) = keys
+            .issue("alice", AuthorityLevel::Operator, Duration::days(1))
+            .unwrap();
+
+        let authority = keys.authenticate(&token).unwrap();
+        assert_eq!(authority.level, AuthorityLevel::Operator);
+        assert_eq!(authority.operator_id.as_deref(), Some("alice"));
+        assert_eq!(authority.session_id, Some(record.id.to_string()));
+
+        let (_, token) = keys
+            .issue("bob", AuthorityLevel::Observer, Duration::days(1))
+            .unwrap();
+        assert!(!keys.authenticate(&token).unwrap().can_mutate());
+    }
+
+    #[test]
+    fn test_forged_and_expired_keys_rejected() {
+        let keys = ApiKeyManager::new("secret");
+        let (_, forged) = ApiKeyManager::new("other")
+            .issue("mallory", AuthorityLevel::Operator, Duration::days(1))
+            .unwrap();
+        assert!(matches!(
+            keys.authenticate(&forged),
+            Err(AuthError::InvalidSignature)
+        ));
+
+        let (_, expired) = keys
+            .issue("alice", AuthorityLevel::Operator, Duration::days(-1))
+            .unwrap();
+        assert!(matches!(
+            keys.authenticate(&expired),
+            Err(AuthError::TokenExpired)
+        ));
+        assert!(keys.authenticate("not-a-key").is_err());
+    }
+
+    #[test]
+    fn test_revocation_is_seen_by_other_managers() {
+        let dir = TempDir::new().unwrap();
+        let server = ApiKeyManager::open("secret", dir.path()).unwrap();
+        let (record, token) = server
+            .issue("alice", AuthorityLevel::Operator, Duration::days(1))
+            .unwrap();
+        assert!(server.authenticate(&token).is_ok());
+
+        // Revoked from another process, e.g. the CLI
+        let cli = ApiKeyManager::open("secret", dir.path()).unwrap();
+        assert!(cli.revoke(record.id).unwrap().is_revoked());
+
+        assert!(matches!(
+            server.authenticate(&token),
+            Err(AuthError::ApiKeyRevoked)
+        ));
+        assert!(server.list().unwrap()[0].is_revoked());
+        assert!(matches!(
+            cli.revoke(Uuid::new_v4()),
+            Err(AuthError::InvalidToken)
+        ));
+    }
+}
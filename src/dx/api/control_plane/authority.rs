@@ -44,6 +44,25 @@ impl AuthorityLevel {
     pub fn can_audit(&self) -> bool {
         matches!(self, AuthorityLevel::Operator | AuthorityLevel::Auditor)
     }
+
+    /// Returns whether this authority level meets `required`.
+    pub fn satisfies(&self, required: AuthorityLevel) -> bool {
+        match required {
+            AuthorityLevel::Observer => self.can_observe(),
+            AuthorityLevel::Operator => self.can_mutate(),
+            AuthorityLevel::Auditor => self.can_audit(),
+        }
+    }
+
+    /// Parse a level name ("observer", "operator", "auditor"), ignoring case.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "observer" => Some(AuthorityLevel::Observer),
+            "operator" => Some(AuthorityLevel::Operator),
+            "auditor" => Some(AuthorityLevel::Auditor),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for AuthorityLevel {
@@ -135,6 +154,22 @@ mod tests {
         assert!(AuthorityLevel::Auditor.can_audit());
     }
 
+    #[test]
+    fn test_authority_requirements() {
+        use AuthorityLevel::*;
+
+        assert!(Observer.satisfies(Observer));
+        assert!(!Observer.satisfies(Operator));
+        assert!(!Observer.satisfies(Auditor));
+        assert!(Operator.satisfies(Operator));
+        assert!(Operator.satisfies(Auditor));
+        assert!(!Auditor.satisfies(Operator));
+        assert!(Auditor.satisfies(Auditor));
+
+        assert_eq!(AuthorityLevel::parse("OPERATOR"), Some(Operator));
+        assert_eq!(AuthorityLevel::parse("root"), None);
+    }
+
     #[test]
     fn test_authority_context() {
         let ctx = AuthorityContext::operator()
@@ -13,6 +13,8 @@
 use std::fmt;
 use uuid::Uuid;
 
+use super::authority::AuthorityLevel;
+
 /// All Phase 7 control plane commands.
 ///
 /// Per PHASE7_COMMAND_MODEL.md §3:
@@ -47,6 +49,19 @@ impl ControlPlaneCommand {
         matches!(self, ControlPlaneCommand::Control(_))
     }
 
+    /// Returns the authority level required to issue this command.
+    ///
+    /// Per PHASE7_AUTHORITY_MODEL.md §3:
+    /// Observers may only look; confirming an action, including an
+    /// expensive diagnostic, requires Operator authority.
+    pub fn required_authority(&self) -> AuthorityLevel {
+        if self.is_mutating() || self.requires_confirmation() {
+            AuthorityLevel::Operator
+        } else {
+            AuthorityLevel::Observer
+        }
+    }
+
     /// Returns the command name for audit logging.
     pub fn command_name(&self) -> &'static str {
         match self {
@@ -103,6 +118,13 @@ pub enum DiagnosticCommand {
 
     /// Inspect available snapshots and checkpoints.
     InspectSnapshots,
+
+    /// List the backup archives in a directory.
+    ListBackups { directory: String },
+
+    /// Verify a backup archive as a restore would, without restoring.
+    /// Requires confirmation due to potential cost.
+    VerifyBackup { path: String },
 }
 
 impl DiagnosticCommand {
@@ -112,15 +134,21 @@ impl DiagnosticCommand {
             DiagnosticCommand::RunDiagnostics => "run_diagnostics",
             DiagnosticCommand::InspectWal => "inspect_wal",
             DiagnosticCommand::InspectSnapshots => "inspect_snapshots",
+            DiagnosticCommand::ListBackups { .. } => "list_backups",
+            DiagnosticCommand::VerifyBackup { .. } => "verify_backup",
         }
     }
 
     /// Returns whether this diagnostic command requires confirmation.
     ///
     /// Per PHASE7_COMMAND_MODEL.md §5.1:
-    /// run_diagnostics requires confirmation due to potential cost.
+    /// run_diagnostics and verify_backup require confirmation due to
+    /// potential cost.
     pub fn requires_confirmation(&self) -> bool {
-        matches!(self, DiagnosticCommand::RunDiagnostics)
+        matches!(
+            self,
+            DiagnosticCommand::RunDiagnostics | DiagnosticCommand::VerifyBackup { .. }
+        )
     }
 }
 
@@ -152,15 +180,116 @@ pub enum ControlCommand {
         /// Acknowledgement of overridden invariants.
         acknowledged_risks: Vec<String>,
     },
+
+    /// Enter maintenance mode: reject new writes, drain in-flight work.
+    /// Confirmation required: Yes.
+    EnterMaintenanceMode {
+        node_id: Uuid,
+        /// Continue serving reads while in maintenance.
+        allow_reads: bool,
+        /// Maximum time to wait for in-flight work to drain.
+        drain_timeout_ms: u64,
+        reason: Option<String>,
+    },
+
+    /// Exit maintenance mode and resume accepting writes.
+    /// Confirmation required: Yes.
+    ExitMaintenanceMode {
+        node_id: Uuid,
+        reason: Option<String>,
+    },
+
+    /// Exit read-only mode after space is freed on the WAL device.
+    /// Confirmation required: Yes.
+    ExitReadOnlyMode {
+        node_id: Uuid,
+        reason: Option<String>,
+    },
+
+    /// Tombstone every document of a collection and unregister it.
+    /// Confirmation required: Yes.
+    DropCollection {
+        node_id: Uuid,
+        collection: String,
+        reason: Option<String>,
+    },
+
+    /// Tombstone every document of a collection, keeping it registered.
+    /// Confirmation required: Yes.
+    TruncateCollection {
+        node_id: Uuid,
+        collection: String,
+        reason: Option<String>,
+    },
+
+    /// Re-read the log level and metrics settings from the config.
+    /// Confirmation required: Yes.
+    ReloadObservability {
+        node_id: Uuid,
+        reason: Option<String>,
+    },
+
+    /// Planned switchover: demote `from` and promote `to` as one flow.
+    /// Blocks writes, drains WAL shipping, verifies `to` has caught up,
+    /// transfers authority, then unblocks writes.
+    /// Confirmation required: Yes (one token covers the whole flow).
+    Switchover {
+        from: Uuid,
+        to: Uuid,
+        /// Maximum time to wait for writes and WAL shipping to drain.
+        drain_timeout_ms: u64,
+        reason: Option<String>,
+    },
+
+    /// Take a checkpoint: snapshot storage, then truncate the WAL.
+    /// Confirmation required: Yes.
+    CreateCheckpoint {
+        node_id: Uuid,
+        reason: Option<String>,
+    },
+
+    /// Write a backup archive of the latest snapshot and WAL.
+    /// Confirmation required: Yes.
+    CreateBackup {
+        node_id: Uuid,
+        /// Path of the backup.tar to create on the node.
+        output_path: String,
+        reason: Option<String>,
+    },
 }
 
 impl ControlCommand {
+    /// Names of every control command, as returned by `command_name`.
+    pub const NAMES: &'static [&'static str] = &[
+        "request_promotion",
+        "request_demotion",
+        "force_promotion",
+        "enter_maintenance_mode",
+        "exit_maintenance_mode",
+        "exit_read_only_mode",
+        "drop_collection",
+        "truncate_collection",
+        "reload_observability",
+        "switchover",
+        "create_checkpoint",
+        "create_backup",
+    ];
+
     /// Returns the command name for audit logging.
     pub fn command_name(&self) -> &'static str {
         match self {
             ControlCommand::RequestPromotion { .. } => "request_promotion",
             ControlCommand::RequestDemotion { .. } => "request_demotion",
             ControlCommand::ForcePromotion { .. } => "force_promotion",
+            ControlCommand::EnterMaintenanceMode { .. } => "enter_maintenance_mode",
+            ControlCommand::ExitMaintenanceMode { .. } => "exit_maintenance_mode",
+            ControlCommand::ExitReadOnlyMode { .. } => "exit_read_only_mode",
+            ControlCommand::DropCollection { .. } => "drop_collection",
+            ControlCommand::TruncateCollection { .. } => "truncate_collection",
+            ControlCommand::ReloadObservability { .. } => "reload_observability",
+            ControlCommand::Switchover { .. } => "switchover",
+            ControlCommand::CreateCheckpoint { .. } => "create_checkpoint",
+            ControlCommand::CreateBackup { .. } => "create_backup",
         }
     }
 
@@ -173,11 +302,22 @@ impl ControlCommand {
     }
 
     /// Returns the target node/replica ID for this command.
+    ///
+    /// For a switchover this is the replica gaining authority.
     pub fn target_id(&self) -> Uuid {
         match self {
             ControlCommand::RequestPromotion { replica_id, .. } => *replica_id,
             ControlCommand::RequestDemotion { node_id, .. } => *node_id,
             ControlCommand::ForcePromotion { replica_id, .. } => *replica_id,
+            ControlCommand::EnterMaintenanceMode { node_id, .. } => *node_id,
+            ControlCommand::ExitMaintenanceMode { node_id, .. } => *node_id,
+            ControlCommand::ExitReadOnlyMode { node_id, .. } => *node_id,
+            ControlCommand::DropCollection { node_id, .. } => *node_id,
+            ControlCommand::TruncateCollection { node_id, .. } => *node_id,
+            ControlCommand::ReloadObservability { node_id, .. } => *node_id,
+            ControlCommand::Switchover { to, .. } => *to,
+            ControlCommand::CreateCheckpoint { node_id, .. } => *node_id,
+            ControlCommand::CreateBackup { node_id, .. } => *node_id,
         }
     }
 }
@@ -251,4 +391,80 @@ mod tests {
             "request_promotion"
         );
     }
+
+    #[test]
+    fn test_maintenance_commands_require_confirmation() {
+        let node_id = Uuid::new_v4();
+        let enter = ControlPlaneCommand::Control(ControlCommand::EnterMaintenanceMode {
+            node_id,
+            allow_reads: true,
+            drain_timeout_ms: 1000,
+            reason: None,
+        });
+        assert!(enter.requires_confirmation());
+        assert!(enter.is_mutating());
+        assert_eq!(enter.command_name(), "enter_maintenance_mode");
+
+        let exit = ControlCommand::ExitMaintenanceMode {
+            node_id,
+            reason: None,
+        };
+        assert_eq!(exit.target_id(), node_id);
+        assert!(!exit.requires_enhanced_confirmation());
+    }
+
+    #[test]
+    fn test_collection_commands_require_confirmation() {
+        let drop = ControlPlaneCommand::Control(ControlCommand::DropCollection {
+            node_id: Uuid::new_v4(),
+            collection: "archive".to_string(),
+            reason: None,
+        });
+        assert!(drop.requires_confirmation());
+        assert!(drop.is_mutating());
+        assert_eq!(drop.command_name(), "drop_collection");
+    }
+
+    #[test]
+    fn test_switchover_targets_promoted_replica() {
+        let (from, to) = (Uuid::new_v4(), Uuid::new_v4());
+        let cmd = ControlCommand::Switchover {
+            from,
+            to,
+            drain_timeout_ms: 1000,
+            reason: None,
+        };
+        assert_eq!(cmd.target_id(), to);
+        assert!(!cmd.requires_enhanced_confirmation());
+
+        let cmd = ControlPlaneCommand::Control(cmd);
+        assert!(cmd.requires_confirmation());
+        assert_eq!(cmd.command_name(), "switchover");
+    }
+
+    #[test]
+    fn test_backup_commands() {
+        let backup = ControlPlaneCommand::Control(ControlCommand::CreateBackup {
+            node_id: Uuid::new_v4(),
+            output_path: "/backups/nightly.tar".to_string(),
+            reason: None,
+        });
+        assert!(backup.requires_confirmation());
+        assert!(backup.is_mutating());
+
+        // Listing is cheap; verifying extracts the whole archive
+        let list = ControlPlaneCommand::Diagnostic(DiagnosticCommand::ListBackups {
+            directory: "/backups".to_string(),
+        });
+        assert!(!list.requires_confirmation());
+        let verify = ControlPlaneCommand::Diagnostic(DiagnosticCommand::VerifyBackup {
+            path: "/backups/nightly.tar".to_string(),
+        });
+        assert!(verify.requires_confirmation());
+        assert!(!verify.is_mutating());
+
+        assert_eq!(backup.required_authority(), AuthorityLevel::Operator);
+        assert_eq!(list.required_authority(), AuthorityLevel::Observer);
+        assert_eq!(verify.required_authority(), AuthorityLevel::Operator);
+    }
 }
@@ -4,9 +4,12 @@
 //! - Dangerous or irreversible actions require explicit confirmation
 //! - Confirmation is explicit, contemporaneous, specific, and not reusable
 //! - Override commands require enhanced confirmation
+//! - Commands under dual control require approvals from two distinct
+//!   operators before they execute
 //!
 //! Confirmation is a SAFETY BOUNDARY, not a usability feature.
 
+use std::collections::BTreeSet;
 use std::fmt;
 use std::time::{Duration, SystemTime};
 use uuid::Uuid;
@@ -32,6 +35,9 @@ pub struct ConfirmationToken {
 
     /// Whether this token has been consumed.
     consumed: bool,
+
+    /// Operators who have approved so far (dual control only).
+    approvals: Vec<String>,
 }
 
 impl ConfirmationToken {
@@ -43,6 +49,7 @@ impl ConfirmationToken {
             target_id,
             created_at: SystemTime::now(),
             consumed: false,
+            approvals: Vec::new(),
         }
     }
 
@@ -91,6 +98,11 @@ impl ConfirmationToken {
     pub fn is_consumed(&self) -> bool {
         self.consumed
     }
+
+    /// Operators who have approved this token so far.
+    pub fn approvals(&self) -> &[String] {
+        &self.approvals
+    }
 }
 
 /// Enhanced confirmation for override commands.
@@ -186,7 +198,18 @@ impl fmt::Display for ConfirmationStatus {
 #[derive(Debug, Clone)]
 pub enum ConfirmationResult {
     /// Confirmation successful, proceed with execution.
-    Proceed { token_id: Uuid },
+    ///
+    /// `approvals` names the approving operators under dual control.
+    Proceed {
+        token_id: Uuid,
+        approvals: Vec<String>,
+    },
+
+    /// Approval recorded; a second, distinct operator must still approve.
+    AwaitingApproval {
+        token_id: Uuid,
+        approvals: Vec<String>,
+    },
 
     /// Confirmation rejected or failed.
     Abort { reason: String },
@@ -202,7 +225,12 @@ pub struct ConfirmationFlow {
     pending_tokens: Vec<ConfirmationToken>,
 
     /// Maximum age for confirmation tokens.
+    ///
+    /// Under dual control both approvals must land within this window.
     max_token_age: Duration,
+
+    /// Commands requiring approvals from two distinct operators.
+    dual_control: BTreeSet<String>,
 }
 
 impl ConfirmationFlow {
@@ -211,9 +239,26 @@ impl ConfirmationFlow {
         Self {
             pending_tokens: Vec::new(),
             max_token_age: Duration::from_secs(300), // 5 minutes
+            dual_control: BTreeSet::new(),
         }
     }
 
+    /// Require approvals from two distinct operators for `command_names`.
+    pub fn with_dual_control<I, S>(mut self, command_names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.dual_control
+            .extend(command_names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Returns whether `command_name` is under dual control.
+    pub fn requires_dual_control(&self, command_name: &str) -> bool {
+        self.dual_control.contains(command_name)
+    }
+
     /// Create a confirmation token for a command.
     ///
     /// Per PHASE7_CONFIRMATION_MODEL.md §5:
@@ -239,6 +284,22 @@ impl ConfirmationFlow {
         token_id: Uuid,
         command_name: &str,
         target_id: Option<Uuid>,
+    ) -> ConfirmationResult {
+        self.confirm_as(token_id, command_name, target_id, None)
+    }
+
+    /// Confirm a pending command on behalf of `approver`.
+    ///
+    /// A command under dual control proceeds only on the approval of a
+    /// second operator distinct from the first; until then the token stays
+    /// pending. Approvals without an operator identity are refused, since
+    /// they cannot be told apart.
+    pub fn confirm_as(
+        &mut self,
+        token_id: Uuid,
+        command_name: &str,
+        target_id: Option<Uuid>,
+        approver: Option<&str>,
     ) -> ConfirmationResult {
         // Find and validate the token
         let token_idx = self.pending_tokens.iter().position(|t| t.id() == token_id);
@@ -265,6 +326,30 @@ impl ConfirmationFlow {
                     };
                 }
 
+                // Dual control: record the approval, proceed on the second
+                if self.dual_control.contains(command_name) {
+                    let Some(approver) = approver else {
+                        return ConfirmationResult::Abort {
+                            reason: "Dual control requires an operator identity".to_string(),
+                        };
+                    };
+                    if token.approvals.iter().any(|a| a == approver) {
+                        return ConfirmationResult::Abort {
+                            reason: format!(
+                                "{} has already approved; a second operator must approve",
+                                approver
+                            ),
+                        };
+                    }
+                    token.approvals.push(approver.to_string());
+                    if token.approvals.len() < 2 {
+                        return ConfirmationResult::AwaitingApproval {
+                            token_id,
+                            approvals: token.approvals.clone(),
+                        };
+                    }
+                }
+
                 // Consume the token
                 if !token.consume() {
                     return ConfirmationResult::Abort {
@@ -273,9 +358,13 @@ impl ConfirmationFlow {
                 }
 
                 let id = token.id();
+                let approvals = token.approvals.clone();
                 self.pending_tokens.remove(idx);
 
-                ConfirmationResult::Proceed { token_id: id }
+                ConfirmationResult::Proceed {
+                    token_id: id,
+                    approvals,
+                }
             }
         }
     }
@@ -357,6 +446,7 @@ mod tests {
             ConfirmationResult::Abort { reason } => {
                 panic!("Expected proceed, got abort: {}", reason);
             }
+            other => panic!("Expected proceed, got {:?}", other),
         }
     }
 
@@ -380,4 +470,59 @@ mod tests {
             ConfirmationResult::Abort { .. }
         ));
     }
+
+    #[test]
+    fn test_dual_control_needs_two_distinct_operators() {
+        let mut flow = ConfirmationFlow::new().with_dual_control(["force_promotion"]);
+        let target = Uuid::new_v4();
+        let token_id = flow
+            .request_confirmation("force_promotion", Some(target))
+            .id();
+
+        // Anonymous approvals cannot be told apart
+        assert!(matches!(
+            flow.confirm(token_id, "force_promotion", Some(target)),
+            ConfirmationResult::Abort { .. }
+        ));
+
+        assert!(matches!(
+            flow.confirm_as(token_id, "force_promotion", Some(target), Some("alice")),
+            ConfirmationResult::AwaitingApproval { .. }
+        ));
+        // The same operator cannot approve twice
+        assert!(matches!(
+            flow.confirm_as(token_id, "force_promotion", Some(target), Some("alice")),
+            ConfirmationResult::Abort { .. }
+        ));
+        match flow.confirm_as(token_id, "force_promotion", Some(target), Some("bob")) {
+            ConfirmationResult::Proceed { approvals, .. } => {
+                assert_eq!(approvals, vec!["alice".to_string(), "bob".to_string()]);
+            }
+            other => panic!("expected proceed, got {:?}", other),
+        }
+
+        // Commands not under dual control are unaffected
+        let token_id = flow
+            .request_confirmation("request_promotion", Some(target))
+            .id();
+        assert!(matches!(
+            flow.confirm(token_id, "request_promotion", Some(target)),
+            ConfirmationResult::Proceed { .. }
+        ));
+    }
+
+    #[test]
+    fn test_dual_control_approvals_expire() {
+        let mut flow = ConfirmationFlow {
+            max_token_age: Duration::ZERO,
+            ..ConfirmationFlow::new().with_dual_control(["drop_collection"])
+        };
+        let token_id = flow.request_confirmation("drop_collection", None).id();
+        std::thread::sleep(Duration::from_millis(2));
+
+        match flow.confirm_as(token_id, "drop_collection", None, Some("alice")) {
+            ConfirmationResult::Abort { reason } => assert!(reason.contains("expired")),
+            other => panic!("expected abort, got {:?}", other),
+        }
+    }
 }
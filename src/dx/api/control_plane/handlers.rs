@@ -8,8 +8,9 @@
 //!
 //! This is the ONLY component allowed to call kernel APIs.
 
+use std::path::Path;
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 use uuid::Uuid;
 
 use super::authority::AuthorityContext;
@@ -17,13 +18,20 @@ use super::commands::{ControlCommand, ControlPlaneCommand, DiagnosticCommand, In
 use super::confirmation::{ConfirmationFlow, ConfirmationResult, ConfirmationToken};
 use super::errors::{ControlPlaneError, ControlPlaneResult};
 use super::types::{
-    ClusterState, CommandOutcome, CommandRequest, CommandResponse, CommandResponseData,
-    DiagnosticResult, DiagnosticSection, NodeHealth, NodeRole, NodeState, PromotionResultData,
-    PromotionStateView, ReplicaState, ReplicationStatus, SnapshotInfo, WalInfo,
+    ApprovalPendingData, BackupListData, BackupMeta, BackupResultData, BackupVerificationData,
+    CheckpointResultData, ClusterState, CollectionResultData, CommandOutcome, CommandRequest,
+    CommandResponse, CommandResponseData, DiagnosticResult, DiagnosticSection,
+    MaintenanceResultData, NodeHealth, NodeRole, NodeState, PromotionResultData,
+    PromotionStateView, ReloadResultData, ReplicaState, ReplicationStatus, SnapshotInfo,
+    SwitchoverResultData, SwitchoverStep, SwitchoverStepResult, SwitchoverStepStatus, WalInfo,
 };
 
+use crate::api::{MaintenanceGate, MaintenanceStatus};
+use crate::backup::{BackupArchive, BackupManager, BackupManifest};
+use crate::observability::{AuditAction, AuditLog, AuditOutcome, AuditRecord, MemoryAuditLog};
 use crate::promotion::{PromotionController, PromotionState};
 use crate::replication::ReplicationState;
+use crate::restore::RestoreManager;
 
 /// Kernel Adapter trait for accessing kernel subsystems.
 ///
@@ -60,12 +68,68 @@ pub trait KernelAdapter: Send + Sync {
 
     /// Force promotion (with risk acknowledgment)
     fn force_promotion(&self, replica_id: Uuid, reason: &str) -> Result<String, String>;
+
+    /// Get maintenance mode state
+    fn get_maintenance_status(&self) -> MaintenanceStatus;
+
+    /// Enter maintenance mode and wait for in-flight work to drain.
+    ///
+    /// Returns whether the drain completed within `drain_timeout`.
+    fn enter_maintenance_mode(
+        &self,
+        allow_reads: bool,
+        drain_timeout: Duration,
+        reason: &str,
+    ) -> Result<bool, String>;
+
+    /// Exit maintenance mode
+    fn exit_maintenance_mode(&self, reason: &str) -> Result<String, String>;
+
+    /// Exit read-only mode entered when the WAL device filled up
+    fn exit_read_only_mode(&self, reason: &str) -> Result<String, String>;
+
+    /// Tombstone every document of a collection and unregister it
+    fn drop_collection(&self, collection: &str, reason: &str) -> Result<String, String>;
+
+    /// Tombstone every document of a collection, keeping it registered
+    fn truncate_collection(&self, collection: &str, reason: &str) -> Result<String, String>;
+
+    /// Re-read the log level and metrics settings from the config
+    fn reload_observability(&self, reason: &str) -> Result<String, String>;
+
+    /// Wait for WAL shipping to a replica to flush.
+    ///
+    /// Returns whether everything shipped within `timeout`.
+    fn drain_wal_shipping(&self, replica_id: Uuid, timeout: Duration) -> Result<bool, String>;
+
+    /// Get a replica's applied WAL position, if known
+    fn get_replica_wal_position(&self, replica_id: Uuid) -> Option<u64>;
+
+    /// Demote `from` and promote `to` in one atomic authority transition
+    fn switchover_authority(&self, from: Uuid, to: Uuid, reason: &str) -> Result<String, String>;
+
+    /// Take a checkpoint, returning its ID
+    fn create_checkpoint(&self, reason: &str) -> Result<String, String>;
+
+    /// Write a backup archive to `output_path`, returning the backup ID
+    fn create_backup(&self, output_path: &Path, reason: &str) -> Result<String, String>;
+
+    /// List the backup archives in `directory`
+    fn list_backups(&self, directory: &Path) -> Result<Vec<BackupArchive>, String>;
+
+    /// Verify a backup archive as a restore would, without restoring
+    fn verify_backup(&self, path: &Path) -> Result<BackupManifest, String>;
 }
 
+/// Hook performing an observability reload for `DefaultKernelAdapter`
+pub type ObservabilityReloadHook = Arc<dyn Fn(&str) -> Result<String, String> + Send + Sync>;
+
 /// Default kernel adapter using actual kernel modules
 pub struct DefaultKernelAdapter {
     replication_state: ReplicationState,
     promotion_state: PromotionState,
+    maintenance: Arc<MaintenanceGate>,
+    observability_reload: Option<ObservabilityReloadHook>,
 }
 
 impl Default for DefaultKernelAdapter {
@@ -73,6 +137,8 @@ impl Default for DefaultKernelAdapter {
         Self {
             replication_state: ReplicationState::default(),
             promotion_state: PromotionState::Steady,
+            maintenance: MaintenanceGate::shared(),
+            observability_reload: None,
         }
     }
 }
@@ -82,8 +148,22 @@ impl DefaultKernelAdapter {
         Self {
             replication_state,
             promotion_state,
+            maintenance: MaintenanceGate::shared(),
+            observability_reload: None,
         }
     }
+
+    /// Use the maintenance gate shared with the API layer.
+    pub fn with_maintenance(mut self, maintenance: Arc<MaintenanceGate>) -> Self {
+        self.maintenance = maintenance;
+        self
+    }
+
+    /// Perform observability reloads through `hook`.
+    pub fn with_observability_reload(mut self, hook: ObservabilityReloadHook) -> Self {
+        self.observability_reload = Some(hook);
+        self
+    }
 }
 
 impl KernelAdapter for DefaultKernelAdapter {
@@ -127,6 +207,85 @@ impl KernelAdapter for DefaultKernelAdapter {
     fn force_promotion(&self, _replica_id: Uuid, _reason: &str) -> Result<String, String> {
         Err("Promotion controller not connected".to_string())
     }
+
+    fn get_maintenance_status(&self) -> MaintenanceStatus {
+        self.maintenance.status()
+    }
+
+    fn enter_maintenance_mode(
+        &self,
+        allow_reads: bool,
+        drain_timeout: Duration,
+        reason: &str,
+    ) -> Result<bool, String> {
+        self.maintenance
+            .enter(allow_reads, Some(reason.to_string()));
+        Ok(self.maintenance.wait_for_drain(drain_timeout))
+    }
+
+    fn exit_maintenance_mode(&self, _reason: &str) -> Result<String, String> {
+        if self.maintenance.exit() {
+            Ok("Maintenance mode exited; writes resumed".to_string())
+        } else {
+            Err("Node is not in maintenance mode".to_string())
+        }
+    }
+
+    fn exit_read_only_mode(&self, _reason: &str) -> Result<String, String> {
+        if self.maintenance.exit_read_only() {
+            Ok("Read-only mode exited; writes resumed".to_string())
+        } else {
+            Err("Node is not in read-only mode".to_string())
+        }
+    }
+
+    fn drop_collection(&self, _collection: &str, _reason: &str) -> Result<String, String> {
+        Err("Collection catalog not connected".to_string())
+    }
+
+    fn truncate_collection(&self, _collection: &str, _reason: &str) -> Result<String, String> {
+        Err("Collection catalog not connected".to_string())
+    }
+
+    fn reload_observability(&self, reason: &str) -> Result<String, String> {
+        match &self.observability_reload {
+            Some(hook) => hook(reason),
+            None => Err("Observability reload not connected".to_string()),
+        }
+    }
+
+    fn drain_wal_shipping(&self, _replica_id: Uuid, _timeout: Duration) -> Result<bool, String> {
+        Err("WAL shipping not connected".to_string())
+    }
+
+    fn get_replica_wal_position(&self, _replica_id: Uuid) -> Option<u64> {
+        None
+    }
+
+    fn switchover_authority(
+        &self,
+        _from: Uuid,
+        _to: Uuid,
+        _reason: &str,
+    ) -> Result<String, String> {
+        Err("Promotion controller not connected".to_string())
+    }
+
+    fn create_checkpoint(&self, _reason: &str) -> Result<String, String> {
+        Err("Checkpoint manager not connected".to_string())
+    }
+
+    fn create_backup(&self, _output_path: &Path, _reason: &str) -> Result<String, String> {
+        Err("Backup manager not connected".to_string())
+    }
+
+    fn list_backups(&self, directory: &Path) -> Result<Vec<BackupArchive>, String> {
+        BackupManager::list_backups(directory).map_err(|e| e.to_string())
+    }
+
+    fn verify_backup(&self, path: &Path) -> Result<BackupManifest, String> {
+        RestoreManager::verify_backup(path).map_err(|e| e.to_string())
+    }
 }
 
 /// Phase 7 Control Plane Handler.
@@ -135,11 +294,14 @@ impl KernelAdapter for DefaultKernelAdapter {
 /// - Routes requests to kernel boundary adapter
 /// - Orchestrates confirmation flows
 /// - Validates request structure and authority
+/// - Records every command in the audit log
 pub struct ControlPlaneHandler {
     /// Confirmation flow manager (ephemeral state).
     confirmation: ConfirmationFlow,
     /// Kernel adapter for accessing kernel subsystems.
     kernel: Arc<dyn KernelAdapter>,
+    /// Audit log receiving a record per request and per outcome.
+    audit_log: Arc<dyn AuditLog>,
 }
 
 impl Default for ControlPlaneHandler {
@@ -159,10 +321,14 @@ impl std::fmt::Debug for ControlPlaneHandler {
 impl ControlPlaneHandler {
     /// Create a new control plane handler with default kernel adapter.
     pub fn new() -> Self {
-        Self {
-            confirmation: ConfirmationFlow::new(),
-            kernel: Arc::new(DefaultKernelAdapter::default()),
-        }
+        Self::with_kernel(Arc::new(DefaultKernelAdapter::default()))
+    }
+
+    /// Create with a maintenance gate shared with the API layer.
+    pub fn with_maintenance(maintenance: Arc<MaintenanceGate>) -> Self {
+        Self::with_kernel(Arc::new(
+            DefaultKernelAdapter::default().with_maintenance(maintenance),
+        ))
     }
 
     /// Create with a custom kernel adapter.
@@ -170,9 +336,26 @@ impl ControlPlaneHandler {
         Self {
             confirmation: ConfirmationFlow::new(),
             kernel,
+            audit_log: Arc::new(MemoryAuditLog::new()),
         }
     }
 
+    /// Require approvals from two distinct operators for `command_names`.
+    pub fn with_dual_control<I, S>(mut self, command_names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.confirmation = std::mem::take(&mut self.confirmation).with_dual_control(command_names);
+        self
+    }
+
+    /// Record commands in `audit_log` instead of in memory.
+    pub fn with_audit_log(mut self, audit_log: Arc<dyn AuditLog>) -> Self {
+        self.audit_log = audit_log;
+        self
+    }
+
     /// Handle a command request.
     ///
     /// Per PHASE7_CONTROL_PLANE_ARCHITECTURE.md §6:
@@ -181,10 +364,108 @@ impl ControlPlaneHandler {
     /// 3. Request confirmation (if required)
     /// 4. Forward to kernel boundary adapter
     /// 5. Return result
+    ///
+    /// Per PHASE7_AUDITABILITY.md §2, the request is audited before any of
+    /// this and its outcome after; a request that cannot be audited is not
+    /// executed.
     pub fn handle_command(
         &mut self,
         request: CommandRequest,
     ) -> ControlPlaneResult<CommandResponse> {
+        let audited = self.audit_context(&request);
+        self.audit_log
+            .append(&audited(AuditRecord::new(
+                AuditAction::CommandRequested,
+                AuditOutcome::Pending,
+            )))
+            .map_err(|e| {
+                ControlPlaneError::transport_failure(&format!("audit log unavailable: {}", e))
+            })?;
+
+        let request_confirmed = request.confirmation_token.is_some();
+        let result = self.dispatch_command(request);
+
+        let record = match &result {
+            Ok(response) => {
+                let record = match response.outcome {
+                    // A first dual control approval
+                    CommandOutcome::AwaitingConfirmation if request_confirmed => {
+                        AuditRecord::new(AuditAction::ConfirmationProvided, AuditOutcome::Pending)
+                    }
+                    CommandOutcome::AwaitingConfirmation => {
+                        AuditRecord::new(AuditAction::ConfirmationRequested, AuditOutcome::Pending)
+                    }
+                    CommandOutcome::Success => {
+                        AuditRecord::new(AuditAction::CommandExecuted, AuditOutcome::Success)
+                    }
+                    CommandOutcome::Rejected => {
+                        AuditRecord::new(AuditAction::CommandRejected, AuditOutcome::Rejected)
+                    }
+                    CommandOutcome::Failed => {
+                        AuditRecord::new(AuditAction::CommandFailed, AuditOutcome::Failed)
+                    }
+                };
+                let record = match response.confirmation_token {
+                    Some(token) => record.with_confirmation_token(token),
+                    None => record,
+                };
+                match &response.error_message {
+                    Some(message) => record.with_error(message.clone()),
+                    None => record,
+                }
+            }
+            Err(e) => {
+                let record = AuditRecord::new(AuditAction::CommandRejected, AuditOutcome::Rejected)
+                    .with_error(e.message());
+                match e.invariant() {
+                    Some(invariant) => record.with_invariant(invariant),
+                    None => record,
+                }
+            }
+        };
+        // The command has already run; a lost outcome record cannot undo it
+        self.audit_log.append(&audited(record)).ok();
+
+        result
+    }
+
+    /// Fill in the command, authority and target of `request` on a record.
+    fn audit_context(&self, request: &CommandRequest) -> impl Fn(AuditRecord) -> AuditRecord {
+        let command_name = request.command.command_name();
+        let request_id = request.request_id;
+        let target_id = self.extract_target_id(&request.command);
+        let confirmation_token = request.confirmation_token;
+        let authority = request.authority.level.to_string();
+        let operator = match (
+            &request.authority.operator_id,
+            &request.authority.session_id,
+        ) {
+            (Some(operator), Some(session)) => Some(format!("{} ({})", operator, session)),
+            (Some(operator), None) => Some(operator.clone()),
+            (None, _) => None,
+        };
+        move |record: AuditRecord| {
+            let record = record
+                .with_command(command_name)
+                .with_request_id(request_id)
+                .with_authority(authority.clone());
+            let record = match target_id {
+                Some(id) => record.with_target(id),
+                None => record,
+            };
+            let record = match (record.confirmation_token, confirmation_token) {
+                (None, Some(token)) => record.with_confirmation_token(token),
+                _ => record,
+            };
+            match &operator {
+                Some(operator) => record.with_operator(operator.clone()),
+                None => record,
+            }
+        }
+    }
+
+    /// Validate, confirm and execute a request.
+    fn dispatch_command(&mut self, request: CommandRequest) -> ControlPlaneResult<CommandResponse> {
         // Step 1: Validate authority
         self.validate_authority(&request)?;
 
@@ -201,9 +482,10 @@ impl ControlPlaneHandler {
     fn validate_authority(&self, request: &CommandRequest) -> ControlPlaneResult<()> {
         // Per PHASE7_AUTHORITY_MODEL.md §3.2:
         // Operator authority is required for all state mutation.
-        if request.command.is_mutating() && !request.authority.can_mutate() {
+        let required = request.command.required_authority();
+        if !request.authority.level.satisfies(required) {
             return Err(ControlPlaneError::insufficient_authority(
-                "OPERATOR",
+                &required.to_string(),
                 &request.authority.level.to_string(),
             ));
         }
@@ -233,11 +515,31 @@ impl ControlPlaneHandler {
             }
             Some(token_id) => {
                 // Validate and consume confirmation
-                match self.confirmation.confirm(token_id, command_name, target_id) {
+                let approver = request.authority.operator_id.as_deref();
+                match self
+                    .confirmation
+                    .confirm_as(token_id, command_name, target_id, approver)
+                {
                     ConfirmationResult::Proceed { .. } => {
                         // Execute the command
                         self.execute_command(&request)
                     }
+                    ConfirmationResult::AwaitingApproval {
+                        token_id,
+                        approvals,
+                    } => {
+                        let mut response = CommandResponse::awaiting_confirmation(
+                            request.request_id,
+                            command_name,
+                            token_id,
+                        );
+                        response.data =
+                            Some(CommandResponseData::ApprovalPending(ApprovalPendingData {
+                                approved_by: approvals,
+                                required: 2,
+                            }));
+                        Ok(response)
+                    }
                     ConfirmationResult::Abort { reason } => {
                         Err(ControlPlaneError::missing_confirmation(&reason))
                     }
@@ -316,6 +618,10 @@ impl ControlPlaneHandler {
                 };
                 let health = if repl_state.is_halted() {
                     NodeHealth::Unavailable
+                } else if self.kernel.get_maintenance_status().active {
+                    NodeHealth::Maintenance
+                } else if self.kernel.get_maintenance_status().read_only {
+                    NodeHealth::Degraded
                 } else if repl_state.can_read() {
                     NodeHealth::Healthy
                 } else {
@@ -415,6 +721,29 @@ impl ControlPlaneHandler {
                             ("state".to_string(), promo_state.state_name().to_string()),
                         ],
                     },
+                    {
+                        let maintenance = self.kernel.get_maintenance_status();
+                        DiagnosticSection {
+                            name: "maintenance".to_string(),
+                            entries: vec![
+                                (
+                                    "status".to_string(),
+                                    if maintenance.active {
+                                        "maintenance"
+                                    } else {
+                                        "ok"
+                                    }
+                                    .to_string(),
+                                ),
+                                (
+                                    "allow_reads".to_string(),
+                                    maintenance.allow_reads.to_string(),
+                                ),
+                                ("in_flight".to_string(), maintenance.in_flight.to_string()),
+                                ("read_only".to_string(), maintenance.read_only.to_string()),
+                            ],
+                        }
+                    },
                     DiagnosticSection {
                         name: "wal".to_string(),
                         entries: vec![
@@ -487,6 +816,55 @@ impl ControlPlaneHandler {
                     CommandResponseData::SnapshtoInfo(info),
                 ))
             }
+            DiagnosticCommand::ListBackups { directory } => {
+                let backups = self
+                    .kernel
+                    .list_backups(Path::new(directory))
+                    .map_err(|msg| ControlPlaneError::from_kernel_rejection("BACKUP_LIST", &msg))?;
+                let list = BackupListData {
+                    directory: directory.clone(),
+                    backups: backups
+                        .into_iter()
+                        .map(|backup| BackupMeta {
+                            path: backup.path.display().to_string(),
+                            backup_id: backup.manifest.backup_id,
+                            created_at: backup.manifest.created_at,
+                            size_bytes: backup.size_bytes,
+                            wal_present: backup.manifest.wal_present,
+                        })
+                        .collect(),
+                    snapshot_time: SystemTime::now(),
+                };
+                Ok(CommandResponse::success(
+                    request_id,
+                    cmd.command_name(),
+                    CommandResponseData::BackupList(list),
+                ))
+            }
+            DiagnosticCommand::VerifyBackup { path } => {
+                let result = match self.kernel.verify_backup(Path::new(path)) {
+                    Ok(manifest) => BackupVerificationData {
+                        path: path.clone(),
+                        valid: true,
+                        explanation: format!(
+                            "Backup {} passed restore validation",
+                            manifest.backup_id
+                        ),
+                        backup_id: Some(manifest.backup_id),
+                    },
+                    Err(msg) => BackupVerificationData {
+                        path: path.clone(),
+                        valid: false,
+                        backup_id: None,
+                        explanation: msg,
+                    },
+                };
+                Ok(CommandResponse::success(
+                    request_id,
+                    cmd.command_name(),
+                    CommandResponseData::BackupVerification(result),
+                ))
+            }
         }
     }
 
@@ -575,6 +953,309 @@ impl ControlPlaneHandler {
                     CommandResponseData::PromotionResult(result),
                 ))
             }
+            ControlCommand::EnterMaintenanceMode {
+                node_id,
+                allow_reads,
+                drain_timeout_ms,
+                reason,
+            } => {
+                let result = self.kernel.enter_maintenance_mode(
+                    *allow_reads,
+                    Duration::from_millis(*drain_timeout_ms),
+                    reason.as_deref().unwrap_or("operator request"),
+                );
+                let status = self.kernel.get_maintenance_status();
+                let (drained, explanation) = match result {
+                    Ok(true) => (true, "Maintenance mode entered; in-flight work drained".to_string()),
+                    Ok(false) => (
+                        false,
+                        format!(
+                            "Maintenance mode entered; drain timed out with {} operation(s) in flight",
+                            status.in_flight
+                        ),
+                    ),
+                    Err(msg) => (false, msg),
+                };
+                let result = MaintenanceResultData {
+                    node_id: *node_id,
+                    active: status.active,
+                    allow_reads: status.allow_reads,
+                    read_only: status.read_only,
+                    drained,
+                    in_flight: status.in_flight,
+                    explanation,
+                };
+                Ok(CommandResponse::success(
+                    request_id,
+                    cmd.command_name(),
+                    CommandResponseData::MaintenanceResult(result),
+                ))
+            }
+            ControlCommand::ExitMaintenanceMode { node_id, reason } => {
+                let result_msg = self
+                    .kernel
+                    .exit_maintenance_mode(reason.as_deref().unwrap_or("operator request"));
+                let status = self.kernel.get_maintenance_status();
+                let explanation = result_msg.unwrap_or_else(|msg| msg);
+                let result = MaintenanceResultData {
+                    node_id: *node_id,
+                    active: status.active,
+                    allow_reads: status.allow_reads,
+                    read_only: status.read_only,
+                    drained: status.in_flight == 0,
+                    in_flight: status.in_flight,
+                    explanation,
+                };
+                Ok(CommandResponse::success(
+                    request_id,
+                    cmd.command_name(),
+                    CommandResponseData::MaintenanceResult(result),
+                ))
+            }
+            ControlCommand::ExitReadOnlyMode { node_id, reason } => {
+                let result_msg = self
+                    .kernel
+                    .exit_read_only_mode(reason.as_deref().unwrap_or("operator request"));
+                let status = self.kernel.get_maintenance_status();
+                let explanation = result_msg.unwrap_or_else(|msg| msg);
+                let result = MaintenanceResultData {
+                    node_id: *node_id,
+                    active: status.active,
+                    allow_reads: status.allow_reads,
+                    read_only: status.read_only,
+                    drained: status.in_flight == 0,
+                    in_flight: status.in_flight,
+                    explanation,
+                };
+                Ok(CommandResponse::success(
+                    request_id,
+                    cmd.command_name(),
+                    CommandResponseData::MaintenanceResult(result),
+                ))
+            }
+            ControlCommand::DropCollection {
+                collection, reason, ..
+            }
+            | ControlCommand::TruncateCollection {
+                collection, reason, ..
+            } => {
+                let dropped = matches!(cmd, ControlCommand::DropCollection { .. });
+                let reason = reason.as_deref().unwrap_or("operator request");
+                let result_msg = if dropped {
+                    self.kernel.drop_collection(collection, reason)
+                } else {
+                    self.kernel.truncate_collection(collection, reason)
+                };
+                let (success, explanation) = match result_msg {
+                    Ok(msg) => (true, msg),
+                    Err(msg) => (false, msg),
+                };
+                let result = CollectionResultData {
+                    collection: collection.clone(),
+                    dropped: dropped && success,
+                    success,
+                    explanation,
+                };
+                Ok(CommandResponse::success(
+                    request_id,
+                    cmd.command_name(),
+                    CommandResponseData::CollectionResult(result),
+                ))
+            }
+            ControlCommand::ReloadObservability { node_id, reason } => {
+                let reason = reason.as_deref().unwrap_or("operator request");
+                let (applied, explanation) = match self.kernel.reload_observability(reason) {
+                    Ok(msg) => (true, msg),
+                    Err(msg) => (false, msg),
+                };
+                let result = ReloadResultData {
+                    node_id: *node_id,
+                    applied,
+                    explanation,
+                };
+                Ok(CommandResponse::success(
+                    request_id,
+                    cmd.command_name(),
+                    CommandResponseData::ReloadResult(result),
+                ))
+            }
+            ControlCommand::Switchover {
+                from,
+                to,
+                drain_timeout_ms,
+                reason,
+            } => {
+                if from == to {
+                    return Err(ControlPlaneError::malformed_request(
+                        "switchover source and target must be different nodes",
+                    ));
+                }
+                let result = self.execute_switchover(
+                    *from,
+                    *to,
+                    Duration::from_millis(*drain_timeout_ms),
+                    reason.as_deref().unwrap_or("planned switchover"),
+                );
+                Ok(CommandResponse::success(
+                    request_id,
+                    cmd.command_name(),
+                    CommandResponseData::SwitchoverResult(result),
+                ))
+            }
+            ControlCommand::CreateCheckpoint { node_id, reason } => {
+                let reason = reason.as_deref().unwrap_or("operator request");
+                let (checkpoint_id, explanation) = match self.kernel.create_checkpoint(reason) {
+                    Ok(id) => (Some(id.clone()), format!("Checkpoint {} taken", id)),
+                    Err(msg) => (None, msg),
+                };
+                let result = CheckpointResultData {
+                    node_id: *node_id,
+                    checkpoint_id,
+                    explanation,
+                };
+                Ok(CommandResponse::success(
+                    request_id,
+                    cmd.command_name(),
+                    CommandResponseData::CheckpointResult(result),
+                ))
+            }
+            ControlCommand::CreateBackup {
+                node_id,
+                output_path,
+                reason,
+            } => {
+                let reason = reason.as_deref().unwrap_or("operator request");
+                let (backup_id, explanation) =
+                    match self.kernel.create_backup(Path::new(output_path), reason) {
+                        Ok(id) => (
+                            Some(id.clone()),
+                            format!("Backup {} written to {}", id, output_path),
+                        ),
+                        Err(msg) => (None, msg),
+                    };
+                let result = BackupResultData {
+                    node_id: *node_id,
+                    output_path: output_path.clone(),
+                    backup_id,
+                    explanation,
+                };
+                Ok(CommandResponse::success(
+                    request_id,
+                    cmd.command_name(),
+                    CommandResponseData::BackupResult(result),
+                ))
+            }
+        }
+    }
+
+    /// Run a planned switchover, step by step.
+    ///
+    /// The first failing step aborts the flow: later steps are skipped,
+    /// except that writes are unblocked again if they were blocked.
+    /// Authority transfer is atomic, so an abort never leaves authority
+    /// split between `from` and `to`.
+    fn execute_switchover(
+        &self,
+        from: Uuid,
+        to: Uuid,
+        drain_timeout: Duration,
+        reason: &str,
+    ) -> SwitchoverResultData {
+        let mut steps = Vec::new();
+        let mut aborted_at = None;
+        let mut writes_blocked = false;
+
+        for step in SwitchoverStep::ALL {
+            let outcome = match step {
+                SwitchoverStep::UnblockWrites if writes_blocked => {
+                    Some(self.kernel.exit_maintenance_mode(reason))
+                }
+                SwitchoverStep::UnblockWrites => None,
+                _ if aborted_at.is_some() => None,
+                SwitchoverStep::BlockWrites => Some(
+                    match self
+                        .kernel
+                        .enter_maintenance_mode(true, drain_timeout, reason)
+                    {
+                        Ok(drained) => {
+                            writes_blocked = true;
+                            if drained {
+                                Ok("Writes blocked; in-flight work drained".to_string())
+                            } else {
+                                Err(format!(
+                                    "Drain timed out with {} operation(s) in flight",
+                                    self.kernel.get_maintenance_status().in_flight
+                                ))
+                            }
+                        }
+                        Err(msg) => Err(msg),
+                    },
+                ),
+                SwitchoverStep::DrainWalShipping => {
+                    Some(match self.kernel.drain_wal_shipping(to, drain_timeout) {
+                        Ok(true) => Ok(format!("WAL shipping to {} drained", to)),
+                        Ok(false) => Err(format!("WAL shipping to {} did not drain in time", to)),
+                        Err(msg) => Err(msg),
+                    })
+                }
+                SwitchoverStep::VerifyCaughtUp => {
+                    let primary = self.kernel.get_wal_position();
+                    Some(match self.kernel.get_replica_wal_position(to) {
+                        Some(applied) if applied >= primary => {
+                            Ok(format!("Replica has applied WAL up to {}", applied))
+                        }
+                        Some(applied) => Err(format!(
+                            "Replica has applied WAL up to {}, primary is at {}",
+                            applied, primary
+                        )),
+                        None => Err(format!("WAL position of {} is unknown", to)),
+                    })
+                }
+                SwitchoverStep::TransferAuthority => {
+                    Some(self.kernel.switchover_authority(from, to, reason))
+                }
+            };
+
+            let (status, detail) = match outcome {
+                Some(Ok(detail)) => (SwitchoverStepStatus::Completed, detail),
+                Some(Err(detail)) => {
+                    // Authority has already moved if only the unblock failed
+                    if step != SwitchoverStep::UnblockWrites {
+                        aborted_at = Some(step);
+                    }
+                    (SwitchoverStepStatus::Failed, detail)
+                }
+                None if step == SwitchoverStep::UnblockWrites => (
+                    SwitchoverStepStatus::Skipped,
+                    "Writes were not blocked".to_string(),
+                ),
+                None => (
+                    SwitchoverStepStatus::Skipped,
+                    "Switchover aborted".to_string(),
+                ),
+            };
+            steps.push(SwitchoverStepResult {
+                step,
+                status,
+                detail,
+            });
+        }
+
+        let explanation = match aborted_at {
+            None => format!("Authority transferred from {} to {}", from, to),
+            Some(step) => format!(
+                "Switchover aborted at {}; {} remains primary",
+                step.name(),
+                from
+            ),
+        };
+        SwitchoverResultData {
+            from,
+            to,
+            completed: aborted_at.is_none(),
+            aborted_at,
+            steps,
+            explanation,
         }
     }
 
@@ -596,6 +1277,7 @@ impl ControlPlaneHandler {
 mod tests {
     use super::*;
     use crate::dx::api::control_plane::authority::AuthorityContext;
+    use crate::dx::api::control_plane::types::CommandOutcome;
 
     #[test]
     fn test_inspection_no_confirmation() {
@@ -643,6 +1325,75 @@ mod tests {
         assert_eq!(response2.outcome, CommandOutcome::Success);
     }
 
+    #[test]
+    fn test_drop_collection_requires_confirmation() {
+        let mut handler = ControlPlaneHandler::new();
+        let cmd = ControlPlaneCommand::Control(ControlCommand::DropCollection {
+            node_id: Uuid::new_v4(),
+            collection: "archive".to_string(),
+            reason: None,
+        });
+
+        let response = handler
+            .handle_command(CommandRequest::new(
+                cmd.clone(),
+                AuthorityContext::operator(),
+            ))
+            .unwrap();
+        assert_eq!(response.outcome, CommandOutcome::AwaitingConfirmation);
+
+        let token_id = response.confirmation_token.unwrap();
+        let response = handler
+            .handle_command(
+                CommandRequest::new(cmd, AuthorityContext::operator()).with_confirmation(token_id),
+            )
+            .unwrap();
+        match response.data {
+            Some(CommandResponseData::CollectionResult(result)) => {
+                assert_eq!(result.collection, "archive");
+                // The default adapter has no catalog to drop from
+                assert!(!result.success);
+                assert!(!result.dropped);
+            }
+            other => panic!("unexpected response data: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reload_observability_runs_hook() {
+        let kernel =
+            DefaultKernelAdapter::default().with_observability_reload(Arc::new(|reason: &str| {
+                Ok(format!("reloaded: {}", reason))
+            }));
+        let mut handler = ControlPlaneHandler::with_kernel(Arc::new(kernel));
+        let cmd = ControlPlaneCommand::Control(ControlCommand::ReloadObservability {
+            node_id: Uuid::new_v4(),
+            reason: Some("raise log level".to_string()),
+        });
+
+        let response = handler
+            .handle_command(CommandRequest::new(
+                cmd.clone(),
+                AuthorityContext::operator(),
+            ))
+            .unwrap();
+        assert_eq!(response.outcome, CommandOutcome::AwaitingConfirmation);
+
+        let token_id = response.confirmation_token.unwrap();
+        let response = handler
+            .handle_command(
+                CommandRequest::new(cmd, AuthorityContext::operator()).with_confirmation(token_id),
+            )
+            .unwrap();
+        match response.data {
+            Some(CommandResponseData::ReloadResult(result)) => {
+                assert!(result.applied);
+                assert_eq!(result.explanation, "reloaded: raise log level");
+            }
+            other => panic!("unexpected response data: {:?}", other),
+        }
+    }
+
     #[test]
     fn test_insufficient_authority_rejected() {
         let mut handler = ControlPlaneHandler::new();
@@ -657,4 +1408,534 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_enter_and_exit_maintenance_mode() {
+        let gate = MaintenanceGate::shared();
+        let mut handler = ControlPlaneHandler::with_maintenance(Arc::clone(&gate));
+        let node_id = Uuid::new_v4();
+
+        let enter = ControlPlaneCommand::Control(ControlCommand::EnterMaintenanceMode {
+            node_id,
+            allow_reads: true,
+            drain_timeout_ms: 100,
+            reason: Some("compaction".to_string()),
+        });
+        let response = handler
+            .handle_command(CommandRequest::new(
+                enter.clone(),
+                AuthorityContext::operator(),
+            ))
+            .unwrap();
+        assert_eq!(response.outcome, CommandOutcome::AwaitingConfirmation);
+        assert!(!gate.is_active());
+
+        let token_id = response.confirmation_token.unwrap();
+        let response = handler
+            .handle_command(
+                CommandRequest::new(enter, AuthorityContext::operator())
+                    .with_confirmation(token_id),
+            )
+            .unwrap();
+        match response.data {
+            Some(CommandResponseData::MaintenanceResult(result)) => {
+                assert!(result.active);
+                assert!(result.drained);
+            }
+            other => panic!("unexpected response data: {:?}", other),
+        }
+        assert!(gate.is_active());
+        assert!(gate.admit(true).is_err());
+
+        // Node health reflects maintenance
+        let inspect = ControlPlaneCommand::Inspection(InspectionCommand::InspectNode { node_id });
+        let response = handler
+            .handle_command(CommandRequest::new(inspect, AuthorityContext::observer()))
+            .unwrap();
+        match response.data {
+            Some(CommandResponseData::NodeState(state)) => {
+                assert_eq!(state.health, NodeHealth::Maintenance)
+            }
+            other => panic!("unexpected response data: {:?}", other),
+        }
+
+        let exit = ControlPlaneCommand::Control(ControlCommand::ExitMaintenanceMode {
+            node_id,
+            reason: None,
+        });
+        let token = handler.request_confirmation(&exit);
+        handler
+            .handle_command(
+                CommandRequest::new(exit, AuthorityContext::operator())
+                    .with_confirmation(token.id()),
+            )
+            .unwrap();
+        assert!(!gate.is_active());
+        assert!(gate.admit(true).is_ok());
+    }
+
+    #[test]
+    fn test_exit_read_only_mode() {
+        let gate = MaintenanceGate::shared();
+        let mut handler = ControlPlaneHandler::with_maintenance(Arc::clone(&gate));
+        let node_id = Uuid::new_v4();
+        gate.enter_read_only("WAL device full");
+
+        // Node health reports the degraded serving state
+        let inspect = ControlPlaneCommand::Inspection(InspectionCommand::InspectNode { node_id });
+        let response = handler
+            .handle_command(CommandRequest::new(inspect, AuthorityContext::observer()))
+            .unwrap();
+        match response.data {
+            Some(CommandResponseData::NodeState(state)) => {
+                assert_eq!(state.health, NodeHealth::Degraded)
+            }
+            other => panic!("unexpected response data: {:?}", other),
+        }
+
+        let exit = ControlPlaneCommand::Control(ControlCommand::ExitReadOnlyMode {
+            node_id,
+            reason: Some("freed WAL space".to_string()),
+        });
+        assert_eq!(exit.command_name(), "exit_read_only_mode");
+        let token = handler.request_confirmation(&exit);
+        let response = handler
+            .handle_command(
+                CommandRequest::new(exit, AuthorityContext::operator())
+                    .with_confirmation(token.id()),
+            )
+            .unwrap();
+        match response.data {
+            Some(CommandResponseData::MaintenanceResult(result)) => assert!(!result.read_only),
+            other => panic!("unexpected response data: {:?}", other),
+        }
+        assert!(!gate.is_read_only());
+        assert!(gate.admit(true).is_ok());
+    }
+
+    #[test]
+    fn test_backup_list_and_verify() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let backup_path = temp.path().join("broken.tar");
+        std::fs::write(&backup_path, b"not a tar").unwrap();
+        let mut handler = ControlPlaneHandler::new();
+
+        // Listing is read-only and needs no confirmation
+        let list = ControlPlaneCommand::Diagnostic(DiagnosticCommand::ListBackups {
+            directory: temp.path().display().to_string(),
+        });
+        let response = handler
+            .handle_command(CommandRequest::new(list, AuthorityContext::observer()))
+            .unwrap();
+        match response.data {
+            Some(CommandResponseData::BackupList(list)) => assert!(list.backups.is_empty()),
+            other => panic!("unexpected response data: {:?}", other),
+        }
+
+        let verify = ControlPlaneCommand::Diagnostic(DiagnosticCommand::VerifyBackup {
+            path: backup_path.display().to_string(),
+        });
+        // Observers cannot confirm, so verifying needs an operator
+        assert!(handler
+            .handle_command(CommandRequest::new(
+                verify.clone(),
+                AuthorityContext::observer(),
+            ))
+            .is_err());
+        let response = handler
+            .handle_command(CommandRequest::new(
+                verify.clone(),
+                AuthorityContext::operator(),
+            ))
+            .unwrap();
+        assert_eq!(response.outcome, CommandOutcome::AwaitingConfirmation);
+
+        let token = handler.request_confirmation(&verify);
+        let response = handler
+            .handle_command(
+                CommandRequest::new(verify, AuthorityContext::operator())
+                    .with_confirmation(token.id()),
+            )
+            .unwrap();
+        match response.data {
+            Some(CommandResponseData::BackupVerification(result)) => {
+                assert!(!result.valid);
+                assert!(result.backup_id.is_none());
+            }
+            other => panic!("unexpected response data: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_without_kernel_reports_failure() {
+        let mut handler = ControlPlaneHandler::new();
+        let cmd = ControlPlaneCommand::Control(ControlCommand::CreateCheckpoint {
+            node_id: Uuid::new_v4(),
+            reason: None,
+        });
+        let token = handler.request_confirmation(&cmd);
+        let response = handler
+            .handle_command(
+                CommandRequest::new(cmd, AuthorityContext::operator())
+                    .with_confirmation(token.id()),
+            )
+            .unwrap();
+        match response.data {
+            Some(CommandResponseData::CheckpointResult(result)) => {
+                assert!(result.checkpoint_id.is_none());
+                assert!(result.explanation.contains("not connected"));
+            }
+            other => panic!("unexpected response data: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_every_command_is_audited() {
+        let audit = Arc::new(MemoryAuditLog::new());
+        let mut handler = ControlPlaneHandler::new().with_audit_log(audit.clone());
+        let node_id = Uuid::new_v4();
+        let cmd = ControlPlaneCommand::Control(ControlCommand::RequestDemotion {
+            node_id,
+            reason: None,
+        });
+
+        // Rejected for authority
+        assert!(handler
+            .handle_command(CommandRequest::new(
+                cmd.clone(),
+                AuthorityContext::observer().with_operator_id("bob"),
+            ))
+            .is_err());
+        // Awaiting confirmation
+        let response = handler
+            .handle_command(CommandRequest::new(
+                cmd,
+                AuthorityContext::operator()
+                    .with_operator_id("alice")
+                    .with_session_id("key-1"),
+            ))
+            .unwrap();
+
+        let records = audit.records();
+        let actions: Vec<AuditAction> = records.iter().map(|r| r.action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                AuditAction::CommandRequested,
+                AuditAction::CommandRejected,
+                AuditAction::CommandRequested,
+                AuditAction::ConfirmationRequested,
+            ]
+        );
+        assert_eq!(records[1].authority_level.as_deref(), Some("OBSERVER"));
+        assert!(records[1].error_message.is_some());
+        assert_eq!(records[3].operator_id.as_deref(), Some("alice (key-1)"));
+        assert_eq!(records[3].target_id, Some(node_id));
+        assert_eq!(records[3].confirmation_token, response.confirmation_token);
+    }
+
+    /// Audit log that cannot be written
+    struct BrokenAuditLog;
+
+    impl AuditLog for BrokenAuditLog {
+        fn append(&self, _record: &AuditRecord) -> std::io::Result<()> {
+            Err(std::io::Error::other("disk full"))
+        }
+
+        fn sync(&self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_unauditable_command_not_executed() {
+        let gate = MaintenanceGate::shared();
+        let mut handler = ControlPlaneHandler::with_maintenance(gate.clone())
+            .with_audit_log(Arc::new(BrokenAuditLog));
+        let cmd = ControlPlaneCommand::Control(ControlCommand::EnterMaintenanceMode {
+            node_id: Uuid::new_v4(),
+            allow_reads: true,
+            drain_timeout_ms: 0,
+            reason: None,
+        });
+        let token = handler.request_confirmation(&cmd);
+
+        let err = handler
+            .handle_command(
+                CommandRequest::new(cmd, AuthorityContext::operator())
+                    .with_confirmation(token.id()),
+            )
+            .unwrap_err();
+        assert!(err.message().contains("audit log unavailable"));
+        assert!(!gate.status().active);
+    }
+
+    #[test]
+    fn test_dual_control_needs_second_operator() {
+        let audit = Arc::new(MemoryAuditLog::new());
+        let mut handler = ControlPlaneHandler::new()
+            .with_audit_log(audit.clone())
+            .with_dual_control(["drop_collection"]);
+        let cmd = ControlPlaneCommand::Control(ControlCommand::DropCollection {
+            node_id: Uuid::new_v4(),
+            collection: "archive".to_string(),
+            reason: None,
+        });
+        let as_operator =
+            |name: &str| AuthorityContext::operator().with_operator_id(name.to_string());
+
+        let token_id = handler
+            .handle_command(CommandRequest::new(cmd.clone(), as_operator("alice")))
+            .unwrap()
+            .confirmation_token
+            .unwrap();
+
+        // First approval is recorded but does not execute
+        let response = handler
+            .handle_command(
+                CommandRequest::new(cmd.clone(), as_operator("alice")).with_confirmation(token_id),
+            )
+            .unwrap();
+        assert_eq!(response.outcome, CommandOutcome::AwaitingConfirmation);
+        match response.data {
+            Some(CommandResponseData::ApprovalPending(pending)) => {
+                assert_eq!(pending.approved_by, vec!["alice".to_string()]);
+                assert_eq!(pending.required, 2);
+            }
+            other => panic!("unexpected response data: {:?}", other),
+        }
+
+        // The same operator cannot approve twice
+        assert!(handler
+            .handle_command(
+                CommandRequest::new(cmd.clone(), as_operator("alice")).with_confirmation(token_id),
+            )
+            .is_err());
+
+        let response = handler
+            .handle_command(
+                CommandRequest::new(cmd, as_operator("bob")).with_confirmation(token_id),
+            )
+            .unwrap();
+        assert!(matches!(
+            response.data,
+            Some(CommandResponseData::CollectionResult(_))
+        ));
+
+        let records = audit.records();
+        let actions: Vec<AuditAction> = records.iter().map(|r| r.action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                AuditAction::CommandRequested,
+                AuditAction::ConfirmationRequested,
+                AuditAction::CommandRequested,
+                AuditAction::ConfirmationProvided,
+                AuditAction::CommandRequested,
+                AuditAction::CommandRejected,
+                AuditAction::CommandRequested,
+                AuditAction::CommandExecuted,
+            ]
+        );
+        assert_eq!(records[3].operator_id.as_deref(), Some("alice"));
+        assert_eq!(records[7].operator_id.as_deref(), Some("bob"));
+        assert!(records[2..]
+            .iter()
+            .all(|r| r.confirmation_token == Some(token_id)));
+    }
+
+    /// Kernel with scripted WAL shipping and authority transfer
+    struct SwitchoverKernel {
+        inner: DefaultKernelAdapter,
+        replica_position: Option<u64>,
+        transferred: std::sync::Mutex<Option<(Uuid, Uuid)>>,
+    }
+
+    impl SwitchoverKernel {
+        fn new(gate: Arc<MaintenanceGate>, replica_position: Option<u64>) -> Self {
+            Self {
+                inner: DefaultKernelAdapter::default().with_maintenance(gate),
+                replica_position,
+                transferred: std::sync::Mutex::new(None),
+            }
+        }
+    }
+
+    impl KernelAdapter for SwitchoverKernel {
+        fn get_replication_state(&self) -> ReplicationState {
+            ReplicationState::PrimaryActive
+        }
+        fn get_promotion_state(&self) -> PromotionState {
+            self.inner.get_promotion_state()
+        }
+        fn get_wal_position(&self) -> u64 {
+            10
+        }
+        fn get_wal_oldest_position(&self) -> u64 {
+            0
+        }
+        fn get_wal_size_bytes(&self) -> u64 {
+            0
+        }
+        fn get_snapshots(&self) -> Vec<(u64, SystemTime)> {
+            Vec::new()
+        }
+        fn get_checkpoints(&self) -> Vec<(u64, SystemTime)> {
+            Vec::new()
+        }
+        fn request_promotion(&self, replica_id: Uuid, reason: &str) -> Result<String, String> {
+            self.inner.request_promotion(replica_id, reason)
+        }
+        fn request_demotion(&self, node_id: Uuid, reason: &str) -> Result<String, String> {
+            self.inner.request_demotion(node_id, reason)
+        }
+        fn force_promotion(&self, replica_id: Uuid, reason: &str) -> Result<String, String> {
+            self.inner.force_promotion(replica_id, reason)
+        }
+        fn get_maintenance_status(&self) -> MaintenanceStatus {
+            self.inner.get_maintenance_status()
+        }
+        fn enter_maintenance_mode(
+            &self,
+            allow_reads: bool,
+            drain_timeout: Duration,
+            reason: &str,
+        ) -> Result<bool, String> {
+            self.inner
+                .enter_maintenance_mode(allow_reads, drain_timeout, reason)
+        }
+        fn exit_maintenance_mode(&self, reason: &str) -> Result<String, String> {
+            self.inner.exit_maintenance_mode(reason)
+        }
+        fn exit_read_only_mode(&self, reason: &str) -> Result<String, String> {
+            self.inner.exit_read_only_mode(reason)
+        }
+        fn drop_collection(&self, collection: &str, reason: &str) -> Result<String, String> {
+            self.inner.drop_collection(collection, reason)
+        }
+        fn truncate_collection(&self, collection: &str, reason: &str) -> Result<String, String> {
+            self.inner.truncate_collection(collection, reason)
+        }
+        fn reload_observability(&self, reason: &str) -> Result<String, String> {
+            self.inner.reload_observability(reason)
+        }
+        fn drain_wal_shipping(
+            &self,
+            _replica_id: Uuid,
+            _timeout: Duration,
+        ) -> Result<bool, String> {
+            Ok(true)
+        }
+        fn get_replica_wal_position(&self, _replica_id: Uuid) -> Option<u64> {
+            self.replica_position
+        }
+        fn switchover_authority(
+            &self,
+            from: Uuid,
+            to: Uuid,
+            _reason: &str,
+        ) -> Result<String, String> {
+            *self.transferred.lock().unwrap() = Some((from, to));
+            Ok("authority transferred".to_string())
+        }
+        fn create_checkpoint(&self, reason: &str) -> Result<String, String> {
+            self.inner.create_checkpoint(reason)
+        }
+        fn create_backup(&self, output_path: &Path, reason: &str) -> Result<String, String> {
+            self.inner.create_backup(output_path, reason)
+        }
+        fn list_backups(&self, directory: &Path) -> Result<Vec<BackupArchive>, String> {
+            self.inner.list_backups(directory)
+        }
+        fn verify_backup(&self, path: &Path) -> Result<BackupManifest, String> {
+            self.inner.verify_backup(path)
+        }
+    }
+
+    fn run_switchover(
+        handler: &mut ControlPlaneHandler,
+        from: Uuid,
+        to: Uuid,
+    ) -> ControlPlaneResult<SwitchoverResultData> {
+        let cmd = ControlPlaneCommand::Control(ControlCommand::Switchover {
+            from,
+            to,
+            drain_timeout_ms: 100,
+            reason: None,
+        });
+        let token = handler.request_confirmation(&cmd);
+        let response = handler.handle_command(
+            CommandRequest::new(cmd, AuthorityContext::operator()).with_confirmation(token.id()),
+        )?;
+        match response.data {
+            Some(CommandResponseData::SwitchoverResult(result)) => Ok(result),
+            other => panic!("unexpected response data: {:?}", other),
+        }
+    }
+
+    fn statuses(result: &SwitchoverResultData) -> Vec<SwitchoverStepStatus> {
+        result.steps.iter().map(|step| step.status).collect()
+    }
+
+    #[test]
+    fn test_switchover_runs_every_step() {
+        use SwitchoverStepStatus::*;
+        let gate = MaintenanceGate::shared();
+        let kernel = Arc::new(SwitchoverKernel::new(Arc::clone(&gate), Some(10)));
+        let mut handler = ControlPlaneHandler::with_kernel(kernel.clone());
+        let (from, to) = (Uuid::new_v4(), Uuid::new_v4());
+
+        // The whole flow waits on one confirmation
+        let cmd = ControlPlaneCommand::Control(ControlCommand::Switchover {
+            from,
+            to,
+            drain_timeout_ms: 100,
+            reason: None,
+        });
+        let response = handler
+            .handle_command(CommandRequest::new(cmd, AuthorityContext::operator()))
+            .unwrap();
+        assert_eq!(response.outcome, CommandOutcome::AwaitingConfirmation);
+        assert!(kernel.transferred.lock().unwrap().is_none());
+
+        let result = run_switchover(&mut handler, from, to).unwrap();
+        assert!(result.completed);
+        assert_eq!(result.aborted_at, None);
+        assert_eq!(statuses(&result), vec![Completed; 5]);
+        assert_eq!(*kernel.transferred.lock().unwrap(), Some((from, to)));
+        assert!(!gate.is_active());
+    }
+
+    #[test]
+    fn test_switchover_aborts_when_replica_lags() {
+        use SwitchoverStepStatus::*;
+        let gate = MaintenanceGate::shared();
+        let kernel = Arc::new(SwitchoverKernel::new(Arc::clone(&gate), Some(7)));
+        let mut handler = ControlPlaneHandler::with_kernel(kernel.clone());
+
+        let result = run_switchover(&mut handler, Uuid::new_v4(), Uuid::new_v4()).unwrap();
+        assert!(!result.completed);
+        assert_eq!(result.aborted_at, Some(SwitchoverStep::VerifyCaughtUp));
+        assert_eq!(
+            statuses(&result),
+            vec![Completed, Completed, Failed, Skipped, Completed]
+        );
+        assert!(result.steps[2].detail.contains("primary is at 10"));
+
+        // Authority never moved, and writes were unblocked again
+        assert!(kernel.transferred.lock().unwrap().is_none());
+        assert!(!gate.is_active());
+    }
+
+    #[test]
+    fn test_switchover_validation() {
+        let mut handler = ControlPlaneHandler::new();
+        let node = Uuid::new_v4();
+        assert!(run_switchover(&mut handler, node, node).is_err());
+
+        // Without WAL shipping there is nothing to drain
+        let result = run_switchover(&mut handler, node, Uuid::new_v4()).unwrap();
+        assert_eq!(result.aborted_at, Some(SwitchoverStep::DrainWalShipping));
+        assert_eq!(result.steps[4].status, SwitchoverStepStatus::Completed);
+    }
 }
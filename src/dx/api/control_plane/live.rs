@@ -0,0 +1,595 @@
+//! Phase 7 Live Kernel Adapter
+//!
+//! Per PHASE7_CONTROL_PLANE_ARCHITECTURE.md §4.4:
+//! - Kernel Boundary Adapter translates control-plane requests into kernel calls
+//! - Each adapter method maps to exactly one kernel operation
+//!
+//! `LiveKernelAdapter` is backed by the node's real subsystems: the
+//! `WalWriter`, the snapshot and checkpoint directories, the shared
+//! `ReplicationState` and the `PromotionController`. Inspection reads them
+//! directly; promotion and demotion drive the Phase 6 state machine and
+//! write the durable authority marker. Checkpoints and backups hold the
+//! WAL writer for their whole duration, so no append interleaves.
+//!
+//! Per PHASE6_INVARIANTS.md §P6-F1 the adapter fails closed: the Primary
+//! is only treated as unavailable once that has been explicitly observed.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, NaiveDateTime};
+use uuid::Uuid;
+
+use super::handlers::{KernelAdapter, ObservabilityReloadHook};
+
+use crate::api::{MaintenanceGate, MaintenanceStatus};
+use crate::backup::{BackupArchive, BackupManager, BackupManifest};
+use crate::checkpoint::{
+    marker_path, segment_checkpoint_id, CheckpointManager, CheckpointMarker, WAL_ARCHIVE_DIR,
+};
+use crate::promotion::{
+    AuthorityTransitionManager, PromotionController, PromotionRequest, PromotionState,
+    PromotionValidator, ValidationContext,
+};
+use crate::replication::{HaltReason, ReplicationState, WalPosition};
+use crate::restore::RestoreManager;
+use crate::schema::SchemaLoader;
+use crate::snapshot::{
+    snapshot_path, snapshots_dir, GlobalExecutionLock, SnapshotManager, SnapshotManifest,
+};
+use crate::wal::{WalReader, WalWriter};
+
+/// Kernel adapter backed by the node's live subsystems
+pub struct LiveKernelAdapter {
+    node_id: Uuid,
+    data_dir: PathBuf,
+    wal: Arc<Mutex<WalWriter>>,
+    replication: Arc<RwLock<ReplicationState>>,
+    promotion: Arc<Mutex<PromotionController>>,
+    transitions: Mutex<AuthorityTransitionManager>,
+    /// Explicitly observed Primary availability (fail closed: available)
+    primary_unavailable: AtomicBool,
+    /// Primary's last known committed WAL position, if observed
+    primary_committed: Mutex<Option<WalPosition>>,
+    maintenance: Arc<MaintenanceGate>,
+    observability_reload: Option<ObservabilityReloadHook>,
+}
+
+impl LiveKernelAdapter {
+    /// Adapter for node `node_id` whose data lives in `data_dir`
+    pub fn new(
+        node_id: Uuid,
+        data_dir: &Path,
+        wal: Arc<Mutex<WalWriter>>,
+        replication: Arc<RwLock<ReplicationState>>,
+        promotion: Arc<Mutex<PromotionController>>,
+    ) -> Self {
+        Self {
+            node_id,
+            data_dir: data_dir.to_path_buf(),
+            wal,
+            replication,
+            promotion,
+            transitions: Mutex::new(AuthorityTransitionManager::new(data_dir)),
+            primary_unavailable: AtomicBool::new(false),
+            primary_committed: Mutex::new(None),
+            maintenance: MaintenanceGate::shared(),
+            observability_reload: None,
+        }
+    }
+
+    /// Use the maintenance gate shared with the API layer.
+    pub fn with_maintenance(mut self, maintenance: Arc<MaintenanceGate>) -> Self {
+        self.maintenance = maintenance;
+        self
+    }
+
+    /// Perform observability reloads through `hook`.
+    pub fn with_observability_reload(mut self, hook: ObservabilityReloadHook) -> Self {
+        self.observability_reload = Some(hook);
+        self
+    }
+
+    /// Record what is known about the Primary.
+    ///
+    /// Promotion is only allowed once the Primary is known to be
+    /// unavailable (or the request is forced), and never past a replica
+    /// that is behind `committed`.
+    pub fn observe_primary(&self, unavailable: bool, committed: Option<WalPosition>) {
+        self.primary_unavailable
+            .store(unavailable, Ordering::SeqCst);
+        *self.primary_committed.lock().unwrap() = committed;
+    }
+
+    /// Apply a committed authority transition to the replication state.
+    ///
+    /// Per PHASE6_INVARIANTS.md §P6-D2 the durable marker is the sole
+    /// source of truth: a marker naming this node makes it the Primary.
+    /// Returns whether the state changed.
+    pub fn recover_authority(&self) -> Result<bool, String> {
+        let (committed, primary_id) = self
+            .transitions
+            .lock()
+            .unwrap()
+            .recover_after_crash()
+            .map_err(|e| e.to_string())?;
+        let mut state = self.replication.write().unwrap();
+        if !committed || primary_id != Some(self.node_id) || state.is_primary() {
+            return Ok(false);
+        }
+        *state = ReplicationState::PrimaryActive;
+        Ok(true)
+    }
+
+    /// Drive a promotion of this node through the Phase 6 state machine.
+    fn promote(&self, replica_id: Uuid, reason: &str, force: bool) -> Result<String, String> {
+        if replica_id != self.node_id {
+            return Err(format!(
+                "Replica {} is not this node ({}); promotion must run on the replica",
+                replica_id, self.node_id
+            ));
+        }
+
+        let mut controller = self.promotion.lock().unwrap();
+        let request = PromotionRequest::new(replica_id)
+            .with_reason(reason)
+            .with_force(force);
+        let requested = controller.request_promotion(request);
+        if !requested.is_accepted() {
+            return Err(format!("Promotion request not accepted: {:?}", requested));
+        }
+        controller.begin_validation().map_err(|e| e.to_string())?;
+
+        let replica_state = self.replication.read().unwrap().clone();
+        let context = ValidationContext {
+            replica_state: replica_state.clone(),
+            replica_wal_position: WalPosition::new(
+                self.get_wal_position(),
+                self.get_wal_size_bytes(),
+            ),
+            primary_committed_position: *self.primary_committed.lock().unwrap(),
+            primary_unavailable: self.primary_unavailable.load(Ordering::SeqCst),
+            force,
+        };
+        let validation = PromotionValidator::validate(replica_id, &context);
+        let explanation = PromotionValidator::explain(&validation);
+        if let Some(reason) = validation.denial_reason() {
+            controller
+                .deny_promotion(reason.clone())
+                .and_then(|_| controller.acknowledge_denial())
+                .map_err(|e| e.to_string())?;
+            return Err(explanation);
+        }
+
+        controller.approve_promotion().map_err(|e| e.to_string())?;
+        controller
+            .begin_authority_transition()
+            .map_err(|e| e.to_string())?;
+
+        let mut transitions = self.transitions.lock().unwrap();
+        let applied = transitions
+            .begin_transition(replica_id, &replica_state)
+            .and_then(|_| transitions.apply_transition());
+        let new_state = match applied {
+            Ok(new_state) => new_state,
+            Err(e) => {
+                // Nothing durable was written: the old authority remains
+                transitions.abort_transition().ok();
+                *controller = PromotionController::recover_after_crash(false, None);
+                return Err(format!("Authority transition failed: {}", e));
+            }
+        };
+        *self.replication.write().unwrap() = new_state;
+
+        transitions
+            .complete_transition()
+            .map_err(|e| e.to_string())?;
+        controller
+            .complete_transition()
+            .map_err(|e| e.to_string())?;
+        controller
+            .acknowledge_success()
+            .map_err(|e| e.to_string())?;
+
+        let epoch = transitions.fencing_epoch().map_err(|e| e.to_string())?;
+        Ok(format!(
+            "{}; {} is now Primary at fencing epoch {}",
+            explanation, replica_id, epoch
+        ))
+    }
+
+    /// Sequence number of the oldest record still in the WAL, or 0
+    fn oldest_wal_sequence(path: &Path) -> u64 {
+        WalReader::open(path)
+            .and_then(|mut reader| reader.read_next())
+            .ok()
+            .flatten()
+            .map_or(0, |record| record.sequence_number)
+    }
+
+    /// `(commit boundary, created_at)` of a snapshot's manifest
+    fn snapshot_entry(&self, snapshot_id: &str) -> Option<(u64, SystemTime)> {
+        let manifest_path = snapshot_path(&self.data_dir, snapshot_id).join("manifest.json");
+        let manifest = SnapshotManifest::read_from_file(&manifest_path).ok()?;
+        let created_at = DateTime::parse_from_rfc3339(&manifest.created_at)
+            .map(SystemTime::from)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        Some((manifest.commit_boundary().unwrap_or(0), created_at))
+    }
+
+    /// Checkpoint entry, falling back to the time encoded in its ID
+    /// (`YYYYMMDDTHHMMSSZ`) when the snapshot has been removed
+    fn checkpoint_entry(&self, checkpoint_id: &str) -> (u64, SystemTime) {
+        self.snapshot_entry(checkpoint_id).unwrap_or_else(|| {
+            let created_at = NaiveDateTime::parse_from_str(checkpoint_id, "%Y%m%dT%H%M%SZ")
+                .map(|time| SystemTime::from(time.and_utc()))
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            (0, created_at)
+        })
+    }
+}
+
+impl KernelAdapter for LiveKernelAdapter {
+    fn get_replication_state(&self) -> ReplicationState {
+        self.replication.read().unwrap().clone()
+    }
+
+    fn get_promotion_state(&self) -> PromotionState {
+        self.promotion.lock().unwrap().state().clone()
+    }
+
+    fn get_wal_position(&self) -> u64 {
+        self.wal.lock().unwrap().last_sequence_number()
+    }
+
+    fn get_wal_oldest_position(&self) -> u64 {
+        let path = self.wal.lock().unwrap().path().to_path_buf();
+        Self::oldest_wal_sequence(&path)
+    }
+
+    fn get_wal_size_bytes(&self) -> u64 {
+        let wal = self.wal.lock().unwrap();
+        fs::metadata(wal.path()).map_or(0, |metadata| metadata.len())
+    }
+
+    fn get_snapshots(&self) -> Vec<(u64, SystemTime)> {
+        let Ok(entries) = fs::read_dir(snapshots_dir(&self.data_dir)) else {
+            return Vec::new();
+        };
+        // Snapshot IDs sort chronologically; incomplete snapshots have
+        // no readable manifest and are skipped
+        let ids: BTreeSet<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect();
+        ids.iter()
+            .filter_map(|id| self.snapshot_entry(id))
+            .collect()
+    }
+
+    fn get_checkpoints(&self) -> Vec<(u64, SystemTime)> {
+        // Archived checkpoints keep their WAL as `archive/<id>.wal`
+        // (`<id>_<n>.wal` for later checkpoints in the same second)
+        let mut ids: BTreeSet<String> = fs::read_dir(self.data_dir.join(WAL_ARCHIVE_DIR))
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| segment_checkpoint_id(&entry.path()).map(str::to_string))
+            .collect();
+        if let Ok(marker) = CheckpointMarker::read_from_file(&marker_path(&self.data_dir)) {
+            ids.insert(marker.snapshot_id);
+        }
+        ids.iter().map(|id| self.checkpoint_entry(id)).collect()
+    }
+
+    fn request_promotion(&self, replica_id: Uuid, reason: &str) -> Result<String, String> {
+        self.promote(replica_id, reason, false)
+    }
+
+    fn request_demotion(&self, node_id: Uuid, _reason: &str) -> Result<String, String> {
+        if node_id != self.node_id {
+            return Err(format!(
+                "Node {} is not this node ({}); demotion must run on the node",
+                node_id, self.node_id
+            ));
+        }
+        let mut state = self.replication.write().unwrap();
+        if !state.is_primary() {
+            return Err(format!(
+                "Node is {}, not Primary; nothing to demote",
+                state.state_name()
+            ));
+        }
+        // Per PHASE6_INVARIANTS.md §P6-A1 a demoted Primary gives up write
+        // authority until it is explicitly re-joined as a replica
+        *state = state.clone().halt(HaltReason::AuthorityAmbiguity);
+        Ok(format!(
+            "Node {} demoted; writes refused until it re-joins as a replica",
+            node_id
+        ))
+    }
+
+    fn force_promotion(&self, replica_id: Uuid, reason: &str) -> Result<String, String> {
+        self.promote(replica_id, reason, true)
+    }
+
+    fn get_maintenance_status(&self) -> MaintenanceStatus {
+        self.maintenance.status()
+    }
+
+    fn enter_maintenance_mode(
+        &self,
+        allow_reads: bool,
+        drain_timeout: Duration,
+        reason: &str,
+    ) -> Result<bool, String> {
+        self.maintenance
+            .enter(allow_reads, Some(reason.to_string()));
+        Ok(self.maintenance.wait_for_drain(drain_timeout))
+    }
+
+    fn exit_maintenance_mode(&self, _reason: &str) -> Result<String, String> {
+        if self.maintenance.exit() {
+            Ok("Maintenance mode exited; writes resumed".to_string())
+        } else {
+            Err("Node is not in maintenance mode".to_string())
+        }
+    }
+
+    fn exit_read_only_mode(&self, _reason: &str) -> Result<String, String> {
+        if self.maintenance.exit_read_only() {
+            Ok("Read-only mode exited; writes resumed".to_string())
+        } else {
+            Err("Node is not in read-only mode".to_string())
+        }
+    }
+
+    fn drop_collection(&self, _collection: &str, _reason: &str) -> Result<String, String> {
+        Err("Collection catalog not connected".to_string())
+    }
+
+    fn truncate_collection(&self, _collection: &str, _reason: &str) -> Result<String, String> {
+        Err("Collection catalog not connected".to_string())
+    }
+
+    fn reload_observability(&self, reason: &str) -> Result<String, String> {
+        match &self.observability_reload {
+            Some(hook) => hook(reason),
+            None => Err("Observability reload not connected".to_string()),
+        }
+    }
+
+    fn drain_wal_shipping(&self, _replica_id: Uuid, _timeout: Duration) -> Result<bool, String> {
+        Err("WAL shipping not connected".to_string())
+    }
+
+    fn get_replica_wal_position(&self, _replica_id: Uuid) -> Option<u64> {
+        None
+    }
+
+    fn switchover_authority(
+        &self,
+        _from: Uuid,
+        _to: Uuid,
+        _reason: &str,
+    ) -> Result<String, String> {
+        Err("Authority switchover not connected".to_string())
+    }
+
+    fn create_checkpoint(&self, _reason: &str) -> Result<String, String> {
+        // Holding the WAL writer excludes appends for the whole checkpoint
+        let mut wal = self.wal.lock().unwrap();
+        CheckpointManager::create_checkpoint(
+            &self.data_dir,
+            &self.data_dir.join("data").join("documents.dat"),
+            SchemaLoader::new(&self.data_dir).schema_dir(),
+            &SnapshotManager,
+            &mut wal,
+            &GlobalExecutionLock::new(),
+        )
+        .map_err(|e| e.to_string())
+    }
+
+    fn create_backup(&self, output_path: &Path, _reason: &str) -> Result<String, String> {
+        let wal = self.wal.lock().unwrap();
+        BackupManager::create_backup(
+            &self.data_dir,
+            output_path,
+            &wal,
+            &GlobalExecutionLock::new(),
+        )
+        .map_err(|e| e.to_string())
+    }
+
+    fn list_backups(&self, directory: &Path) -> Result<Vec<BackupArchive>, String> {
+        BackupManager::list_backups(directory).map_err(|e| e.to_string())
+    }
+
+    fn verify_backup(&self, path: &Path) -> Result<BackupManifest, String> {
+        RestoreManager::verify_backup(path).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wal::{RecordType, WalPayload};
+    use tempfile::TempDir;
+
+    fn adapter(state: ReplicationState) -> (TempDir, Uuid, LiveKernelAdapter) {
+        let temp = TempDir::new().unwrap();
+        let node_id = state.replica_id().unwrap_or_else(Uuid::new_v4);
+        let wal = WalWriter::open(temp.path()).unwrap();
+        let adapter = LiveKernelAdapter::new(
+            node_id,
+            temp.path(),
+            Arc::new(Mutex::new(wal)),
+            Arc::new(RwLock::new(state)),
+            Arc::new(Mutex::new(PromotionController::new())),
+        );
+        (temp, node_id, adapter)
+    }
+
+    fn write_manifest(data_dir: &Path, id: &str, created_at: &str, boundary: u64) {
+        let dir = snapshot_path(data_dir, id);
+        fs::create_dir_all(&dir).unwrap();
+        SnapshotManifest::with_mvcc_boundary(
+            id,
+            created_at,
+            "crc32:00000000",
+            Default::default(),
+            boundary,
+        )
+        .write_to_file(&dir.join("manifest.json"))
+        .unwrap();
+    }
+
+    #[test]
+    fn test_wal_inspection_reads_writer() {
+        let (_temp, _, adapter) = adapter(ReplicationState::PrimaryActive);
+        assert_eq!(adapter.get_wal_position(), 0);
+        assert_eq!(adapter.get_wal_size_bytes(), 0);
+
+        for key in ["a", "b", "c"] {
+            adapter
+                .wal
+                .lock()
+                .unwrap()
+                .append(
+                    RecordType::Insert,
+                    WalPayload::new("users", key, "user", "v1", b"{}".to_vec()),
+                )
+                .unwrap();
+        }
+
+        assert_eq!(adapter.get_wal_position(), 3);
+        assert_eq!(adapter.get_wal_oldest_position(), 1);
+        assert!(adapter.get_wal_size_bytes() > 0);
+    }
+
+    #[test]
+    fn test_snapshot_and_checkpoint_inspection() {
+        let (temp, _, adapter) = adapter(ReplicationState::PrimaryActive);
+        write_manifest(temp.path(), "20260101T000000Z", "2026-01-01T00:00:00Z", 7);
+        write_manifest(temp.path(), "20260102T000000Z", "2026-01-02T00:00:00Z", 12);
+        // An incomplete snapshot has no manifest
+        fs::create_dir_all(snapshot_path(temp.path(), "20260103T000000Z")).unwrap();
+
+        let snapshots = adapter.get_snapshots();
+        assert_eq!(
+            snapshots.iter().map(|(pos, _)| *pos).collect::<Vec<_>>(),
+            vec![7, 12]
+        );
+        assert!(snapshots[0].1 < snapshots[1].1);
+
+        // One archived checkpoint whose snapshot is gone, one current
+        fs::create_dir_all(temp.path().join(WAL_ARCHIVE_DIR)).unwrap();
+        fs::write(
+            temp.path()
+                .join(WAL_ARCHIVE_DIR)
+                .join("20251231T000000Z.wal"),
+            b"",
+        )
+        .unwrap();
+        fs::write(
+            temp.path()
+                .join(WAL_ARCHIVE_DIR)
+                .join("20251231T000000Z_0001.wal"),
+            b"",
+        )
+        .unwrap();
+        CheckpointMarker::new("20260102T000000Z", "2026-01-02T00:00:00Z")
+            .write_to_file(&marker_path(temp.path()))
+            .unwrap();
+
+        let checkpoints = adapter.get_checkpoints();
+        assert_eq!(checkpoints.len(), 2);
+        assert_eq!(checkpoints[0].0, 0);
+        assert!(checkpoints[0].1 > SystemTime::UNIX_EPOCH);
+        assert_eq!(checkpoints[1].0, 12);
+    }
+
+    #[test]
+    fn test_checkpoint_then_backup_round_trip() {
+        let (temp, _, adapter) = adapter(ReplicationState::PrimaryActive);
+        fs::create_dir_all(temp.path().join("data")).unwrap();
+        fs::write(temp.path().join("data").join("documents.dat"), b"").unwrap();
+        fs::create_dir_all(temp.path().join("metadata").join("schemas")).unwrap();
+
+        let checkpoint_id = adapter.create_checkpoint("test").unwrap();
+        assert_eq!(adapter.get_checkpoints().len(), 1);
+        assert_eq!(adapter.get_snapshots().len(), 1);
+
+        let backups = temp.path().join("backups");
+        fs::create_dir_all(&backups).unwrap();
+        let backup_id = adapter
+            .create_backup(&backups.join("nightly.tar"), "test")
+            .unwrap();
+        assert_eq!(backup_id, checkpoint_id);
+
+        let listed = adapter.list_backups(&backups).unwrap();
+        assert_eq!(listed.len(), 1);
+        let manifest = adapter.verify_backup(&listed[0].path).unwrap();
+        assert_eq!(manifest.backup_id, backup_id);
+    }
+
+    #[test]
+    fn test_promotion_fails_closed_while_primary_available() {
+        let replica_id = Uuid::new_v4();
+        let (_temp, _, adapter) = adapter(ReplicationState::ReplicaActive { replica_id });
+
+        let err = adapter.request_promotion(replica_id, "test").unwrap_err();
+        assert!(err.contains("denied"));
+        assert_eq!(adapter.get_promotion_state(), PromotionState::Steady);
+        assert!(adapter.get_replication_state().is_replica());
+
+        // Another node's promotion is not ours to run
+        assert!(adapter.request_promotion(Uuid::new_v4(), "test").is_err());
+    }
+
+    #[test]
+    fn test_promotion_transfers_authority() {
+        let replica_id = Uuid::new_v4();
+        let (_temp, _, adapter) = adapter(ReplicationState::ReplicaActive { replica_id });
+        adapter.observe_primary(true, None);
+
+        let msg = adapter.request_promotion(replica_id, "test").unwrap();
+        assert!(msg.contains("fencing epoch 1"));
+        assert!(adapter.get_replication_state().is_primary());
+        assert_eq!(adapter.get_promotion_state(), PromotionState::Steady);
+        // Completed transitions leave nothing to recover
+        assert!(!adapter.recover_authority().unwrap());
+    }
+
+    #[test]
+    fn test_recover_authority_after_crash_mid_transition() {
+        let replica_id = Uuid::new_v4();
+        let (temp, _, adapter) = adapter(ReplicationState::ReplicaActive { replica_id });
+
+        // Crash after the durable marker, before completion
+        let mut manager = AuthorityTransitionManager::new(temp.path());
+        manager
+            .begin_transition(replica_id, &adapter.get_replication_state())
+            .unwrap();
+        manager.apply_transition().unwrap();
+
+        assert!(adapter.recover_authority().unwrap());
+        assert!(adapter.get_replication_state().is_primary());
+    }
+
+    #[test]
+    fn test_demotion_halts_primary() {
+        let (_temp, node_id, adapter) = adapter(ReplicationState::PrimaryActive);
+
+        adapter.request_demotion(node_id, "test").unwrap();
+        let state = adapter.get_replication_state();
+        assert!(state.is_halted());
+        assert!(!state.can_write());
+
+        assert!(adapter.request_demotion(node_id, "test").is_err());
+    }
+}
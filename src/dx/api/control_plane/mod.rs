@@ -10,13 +10,16 @@
 //! - Phase 7 MUST NOT alter kernel timing, execution order, or durability
 //! - Kernel behavior must be identical with or without Phase 7
 
+mod api_keys;
 mod authority;
 mod commands;
 mod confirmation;
 mod errors;
 mod handlers;
+mod live;
 mod types;
 
+pub use api_keys::{ApiKeyManager, ApiKeyRecord, API_KEYS_FILE};
 pub use authority::{AuthorityContext, AuthorityLevel};
 pub use commands::{ControlCommand, ControlPlaneCommand, DiagnosticCommand, InspectionCommand};
 pub use confirmation::{
@@ -24,8 +27,14 @@ pub use confirmation::{
     EnhancedConfirmation,
 };
 pub use errors::{ControlPlaneError, ControlPlaneErrorDomain, ControlPlaneResult};
-pub use handlers::ControlPlaneHandler;
+pub use handlers::{
+    ControlPlaneHandler, DefaultKernelAdapter, KernelAdapter, ObservabilityReloadHook,
+};
+pub use live::LiveKernelAdapter;
 pub use types::{
-    ClusterState, CommandOutcome, CommandRequest, CommandResponse, NodeState, PromotionStateView,
-    ReplicationStatus,
+    ApprovalPendingData, BackupListData, BackupMeta, BackupResultData, BackupVerificationData,
+    CheckpointResultData, ClusterState, CollectionResultData, CommandOutcome, CommandRequest,
+    CommandResponse, CommandResponseData, MaintenanceResultData, NodeHealth, NodeState,
+    PromotionStateView, ReloadResultData, ReplicationStatus, SwitchoverResultData, SwitchoverStep,
+    SwitchoverStepResult, SwitchoverStepStatus,
 };
@@ -162,6 +162,33 @@ pub enum CommandResponseData {
 
     /// Promotion request result.
     PromotionResult(PromotionResultData),
+
+    /// Maintenance mode transition result.
+    MaintenanceResult(MaintenanceResultData),
+
+    /// Collection drop/truncate result.
+    CollectionResult(CollectionResultData),
+
+    /// Observability reload result.
+    ReloadResult(ReloadResultData),
+
+    /// Planned switchover result.
+    SwitchoverResult(SwitchoverResultData),
+
+    /// Checkpoint result.
+    CheckpointResult(CheckpointResultData),
+
+    /// Backup creation result.
+    BackupResult(BackupResultData),
+
+    /// Backup listing.
+    BackupList(BackupListData),
+
+    /// Backup verification result.
+    BackupVerification(BackupVerificationData),
+
+    /// Dual control approval recorded; a second operator must approve.
+    ApprovalPending(ApprovalPendingData),
 }
 
 // ============================================================================
@@ -219,6 +246,8 @@ pub enum NodeRole {
 pub enum NodeHealth {
     Healthy,
     Degraded,
+    /// Serving is restricted by operator-initiated maintenance mode.
+    Maintenance,
     Unavailable,
     Unknown,
 }
@@ -360,6 +389,236 @@ pub struct PromotionResultData {
     pub explanation: String,
 }
 
+/// Maintenance mode transition result.
+#[derive(Debug, Clone)]
+pub struct MaintenanceResultData {
+    /// Node whose maintenance mode changed.
+    pub node_id: Uuid,
+
+    /// Whether maintenance mode is active after the command.
+    pub active: bool,
+
+    /// Whether reads are still served.
+    pub allow_reads: bool,
+
+    /// Whether writes are still refused because storage is full.
+    pub read_only: bool,
+
+    /// Whether all in-flight work drained before the timeout.
+    pub drained: bool,
+
+    /// Operations still in flight when the command returned.
+    pub in_flight: usize,
+
+    /// Explanation of result.
+    pub explanation: String,
+}
+
+/// Collection drop/truncate result.
+#[derive(Debug, Clone)]
+pub struct CollectionResultData {
+    /// Collection operated on.
+    pub collection: String,
+
+    /// Whether the collection was unregistered (drop) or kept (truncate).
+    pub dropped: bool,
+
+    /// Whether the operation succeeded.
+    pub success: bool,
+
+    /// Explanation of result.
+    pub explanation: String,
+}
+
+/// Observability reload result.
+#[derive(Debug, Clone)]
+pub struct ReloadResultData {
+    /// Node asked to reload.
+    pub node_id: Uuid,
+
+    /// Whether the reload reached the node.
+    pub applied: bool,
+
+    /// Explanation of result.
+    pub explanation: String,
+}
+
+/// Dual control approval state.
+#[derive(Debug, Clone)]
+pub struct ApprovalPendingData {
+    /// Operators who have approved so far.
+    pub approved_by: Vec<String>,
+
+    /// Approvals required before the command executes.
+    pub required: usize,
+}
+
+/// Checkpoint result.
+#[derive(Debug, Clone)]
+pub struct CheckpointResultData {
+    /// Node that took the checkpoint.
+    pub node_id: Uuid,
+
+    /// Checkpoint ID (equals its snapshot ID), if one was taken.
+    pub checkpoint_id: Option<String>,
+
+    /// Explanation of result.
+    pub explanation: String,
+}
+
+/// Backup creation result.
+#[derive(Debug, Clone)]
+pub struct BackupResultData {
+    /// Node that wrote the backup.
+    pub node_id: Uuid,
+
+    /// Path of the backup archive on the node.
+    pub output_path: String,
+
+    /// Backup ID (equals its snapshot ID), if the backup was written.
+    pub backup_id: Option<String>,
+
+    /// Explanation of result.
+    pub explanation: String,
+}
+
+/// Backup listing.
+#[derive(Debug, Clone)]
+pub struct BackupListData {
+    /// Directory listed.
+    pub directory: String,
+
+    /// Backups found, oldest first.
+    pub backups: Vec<BackupMeta>,
+
+    /// Snapshot timestamp.
+    pub snapshot_time: SystemTime,
+}
+
+/// Backup metadata, from the archive's backup manifest.
+#[derive(Debug, Clone)]
+pub struct BackupMeta {
+    /// Path of the backup archive.
+    pub path: String,
+
+    /// Backup ID.
+    pub backup_id: String,
+
+    /// Creation timestamp (RFC3339).
+    pub created_at: String,
+
+    /// Archive size in bytes.
+    pub size_bytes: u64,
+
+    /// Whether the WAL tail is included.
+    pub wal_present: bool,
+}
+
+/// Backup verification result.
+#[derive(Debug, Clone)]
+pub struct BackupVerificationData {
+    /// Path of the backup archive.
+    pub path: String,
+
+    /// Whether a restore from the archive would pass validation.
+    pub valid: bool,
+
+    /// Backup ID, if the manifest could be read.
+    pub backup_id: Option<String>,
+
+    /// Explanation of result.
+    pub explanation: String,
+}
+
+/// Step of a planned switchover, in execution order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwitchoverStep {
+    /// Reject new writes on the primary and drain in-flight work.
+    BlockWrites,
+
+    /// Wait for the primary to ship its remaining WAL to the replica.
+    DrainWalShipping,
+
+    /// Confirm the replica has applied the primary's entire WAL.
+    VerifyCaughtUp,
+
+    /// Demote the primary and promote the replica atomically.
+    TransferAuthority,
+
+    /// Lift the write block (also run when an earlier step aborts).
+    UnblockWrites,
+}
+
+impl SwitchoverStep {
+    /// All steps, in execution order.
+    pub const ALL: [SwitchoverStep; 5] = [
+        SwitchoverStep::BlockWrites,
+        SwitchoverStep::DrainWalShipping,
+        SwitchoverStep::VerifyCaughtUp,
+        SwitchoverStep::TransferAuthority,
+        SwitchoverStep::UnblockWrites,
+    ];
+
+    /// Step name for display and audit.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SwitchoverStep::BlockWrites => "block_writes",
+            SwitchoverStep::DrainWalShipping => "drain_wal_shipping",
+            SwitchoverStep::VerifyCaughtUp => "verify_caught_up",
+            SwitchoverStep::TransferAuthority => "transfer_authority",
+            SwitchoverStep::UnblockWrites => "unblock_writes",
+        }
+    }
+}
+
+/// What happened to one switchover step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwitchoverStepStatus {
+    /// Step ran and succeeded.
+    Completed,
+
+    /// Step ran and failed; the switchover aborted here.
+    Failed,
+
+    /// Step did not run because the switchover aborted earlier.
+    Skipped,
+}
+
+/// Outcome of one switchover step.
+#[derive(Debug, Clone)]
+pub struct SwitchoverStepResult {
+    /// Step this result is for.
+    pub step: SwitchoverStep,
+
+    /// Whether the step ran and succeeded.
+    pub status: SwitchoverStepStatus,
+
+    /// Explanation of the step's outcome.
+    pub detail: String,
+}
+
+/// Planned switchover result.
+#[derive(Debug, Clone)]
+pub struct SwitchoverResultData {
+    /// Primary giving up authority.
+    pub from: Uuid,
+
+    /// Replica gaining authority.
+    pub to: Uuid,
+
+    /// Whether authority was transferred.
+    pub completed: bool,
+
+    /// Step that failed, if the switchover aborted.
+    pub aborted_at: Option<SwitchoverStep>,
+
+    /// Every step, in execution order.
+    pub steps: Vec<SwitchoverStepResult>,
+
+    /// Explanation of result.
+    pub explanation: String,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -8,6 +8,7 @@
 //! Read-only, Phase 4, no semantic authority.
 
 use super::response::{ApiError, ApiResponse, ObservedAt};
+use crate::observability::SlowQueryEntry;
 use crate::replication::ReplicationState;
 use serde::{Deserialize, Serialize};
 
@@ -259,6 +260,19 @@ pub struct ReplicationData {
     pub snapshot_bootstrap_active: bool,
 }
 
+// ============================================================================
+// Slow Query Endpoint Data (§5.9)
+// ============================================================================
+
+/// Slow queries response per §5.9.
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowQueriesData {
+    /// Duration at which a query counts as slow, in microseconds.
+    pub threshold_micros: u64,
+    /// Most recent slow queries, newest first.
+    pub entries: Vec<SlowQueryEntry>,
+}
+
 // ============================================================================
 // Handler Placeholder Functions
 // ============================================================================
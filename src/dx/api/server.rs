@@ -13,6 +13,7 @@
 use super::handlers::*;
 use super::response::{ApiResponse, ObservedAt};
 use crate::dx::config::DxConfig;
+use crate::observability::{render_prometheus, MetricsSnapshot, SlowQueryLog};
 use serde::Serialize;
 
 /// Observability API server.
@@ -110,6 +111,40 @@ impl ObservabilityServer {
         ApiResponse::new(ObservedAt::live(commit_id), data)
     }
 
+    /// Generate metrics response.
+    ///
+    /// Read-only, Phase 4, no semantic authority.
+    pub fn get_metrics(
+        &self,
+        commit_id: u64,
+        snapshot: MetricsSnapshot,
+    ) -> ApiResponse<MetricsSnapshot> {
+        ApiResponse::new(ObservedAt::live(commit_id), snapshot)
+    }
+
+    /// Render metrics in the Prometheus text format.
+    ///
+    /// Per DX_OBSERVABILITY_API.md §5.8: the one non-JSON response.
+    pub fn get_metrics_prometheus(&self, snapshot: &MetricsSnapshot) -> String {
+        render_prometheus(snapshot)
+    }
+
+    /// Generate slow queries response, newest first.
+    ///
+    /// Read-only, Phase 4, no semantic authority.
+    pub fn get_slow_queries(
+        &self,
+        commit_id: u64,
+        log: &SlowQueryLog,
+        limit: usize,
+    ) -> ApiResponse<SlowQueriesData> {
+        let data = SlowQueriesData {
+            threshold_micros: log.threshold().as_micros() as u64,
+            entries: log.recent(limit),
+        };
+        ApiResponse::new(ObservedAt::live(commit_id), data)
+    }
+
     /// Serialize response to JSON.
     ///
     /// Per DX_OBSERVABILITY_API.md §3.1: JSON responses, UTF-8 encoding.
@@ -160,6 +195,32 @@ mod tests {
         assert!(json.contains("\"commit_id\": 100"));
     }
 
+    #[test]
+    fn test_metrics_response() {
+        let server = ObservabilityServer::new(DxConfig::enabled());
+        let registry = crate::observability::MetricsRegistry::new();
+        registry.increment_wal_fsyncs();
+
+        let resp = server.get_metrics(100, registry.snapshot());
+        assert_eq!(resp.data.wal_fsyncs, 1);
+
+        let text = server.get_metrics_prometheus(&registry.snapshot());
+        assert!(text.contains("aerodb_wal_fsyncs_total 1\n"));
+    }
+
+    #[test]
+    fn test_slow_queries_response() {
+        use crate::observability::SlowQueryConfig;
+        use std::time::Duration;
+
+        let server = ObservabilityServer::new(DxConfig::enabled());
+        let log = SlowQueryLog::new(&SlowQueryConfig::new(Duration::from_millis(100)));
+        let resp = server.get_slow_queries(100, &log, 10);
+
+        assert_eq!(resp.data.threshold_micros, 100_000);
+        assert!(resp.data.entries.is_empty());
+    }
+
     #[test]
     fn test_mvcc_response() {
         let server = ObservabilityServer::new(DxConfig::enabled());
@@ -135,6 +135,31 @@ impl PreExecutionExplanation {
 
         exp
     }
+
+    /// Generate pre-execution explanation for enter_maintenance_mode.
+    pub fn for_enter_maintenance_mode(node_id: Uuid, allow_reads: bool) -> Self {
+        let exp = Self::new("enter_maintenance_mode")
+            .with_target(node_id)
+            .with_consequence(Consequence {
+                description: "New write operations will be rejected with AERO_MAINTENANCE_MODE"
+                    .to_string(),
+                scope: ConsequenceScope::Node,
+            })
+            .with_consequence(Consequence {
+                description: "In-flight operations will be allowed to complete".to_string(),
+                scope: ConsequenceScope::Node,
+            })
+            .with_risk("Clients without retry logic will observe failed writes");
+
+        if allow_reads {
+            exp
+        } else {
+            exp.with_consequence(Consequence {
+                description: "Read operations will also be rejected".to_string(),
+                scope: ConsequenceScope::Node,
+            })
+        }
+    }
 }
 
 /// A consequence of executing a command.
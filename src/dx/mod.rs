@@ -12,9 +12,13 @@
 //! Per DX_INVARIANTS.md §P4-16:
 //! - Phase 4 MUST be fully disableable
 //! - Disabling requires no migration, data changes, or behavior changes
+//!
+//! The schema sandbox writes only to its own scratch directory and never
+//! to the source data directory.
 
 pub mod api;
 pub mod config;
 pub mod explain;
+pub mod sandbox;
 
 pub use config::DxConfig;
@@ -0,0 +1,536 @@
+//! Schema Sandbox
+//!
+//! A disposable environment for experimenting with schema changes and
+//! queries against production-shaped data.
+//!
+//! The sandbox is seeded from the latest snapshot of a data directory:
+//! - storage.dat is copied into a scratch directory
+//! - schemas are copied into a scratch catalog (copy-on-write overlay)
+//! - indexes are rebuilt from the scratch storage
+//!
+//! All writes (WAL, storage, schema registrations) go to the scratch
+//! directory only. The source data directory is never opened for writing,
+//! and a scratch directory inside the source data directory is rejected.
+//!
+//! The scratch directory is removed on `teardown()` or when the sandbox is
+//! dropped.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::api::{ApiError, ApiHandler, Response, Subsystems};
+use crate::index::{DocumentInfo, IndexError, IndexManager, IndexResult, StorageScan};
+use crate::schema::{Schema, SchemaLoader};
+use crate::storage::{StorageReader, StorageWriter};
+use crate::wal::WalWriter;
+
+/// Sandbox error
+#[derive(Debug, Clone)]
+pub struct SandboxError {
+    message: String,
+}
+
+impl SandboxError {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+
+    /// Returns the error message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for SandboxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SandboxError: {}", self.message)
+    }
+}
+
+impl std::error::Error for SandboxError {}
+
+/// Result type for sandbox operations
+pub type SandboxResult<T> = Result<T, SandboxError>;
+
+/// Sandbox configuration
+#[derive(Debug, Clone)]
+pub struct SandboxConfig {
+    /// Directory under which the scratch directory is created.
+    ///
+    /// Must not be inside the source data directory.
+    pub scratch_root: PathBuf,
+    /// Fields to build secondary indexes for in the sandbox.
+    pub indexed_fields: HashSet<String>,
+    /// Collection name used by the sandbox API handler.
+    pub collection: String,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            scratch_root: std::env::temp_dir(),
+            indexed_fields: HashSet::new(),
+            collection: "default".to_string(),
+        }
+    }
+}
+
+/// Disposable schema and query sandbox
+pub struct Sandbox {
+    /// Snapshot the sandbox was seeded from
+    snapshot_id: String,
+    /// Scratch directory holding all sandbox state
+    scratch_dir: PathBuf,
+    schema_loader: SchemaLoader,
+    wal_writer: WalWriter,
+    storage_writer: StorageWriter,
+    storage_reader: StorageReader,
+    index_manager: IndexManager,
+    handler: ApiHandler,
+    torn_down: bool,
+}
+
+impl Sandbox {
+    /// Open a sandbox seeded from the latest snapshot in `data_dir`.
+    pub fn open(data_dir: &Path, config: SandboxConfig) -> SandboxResult<Self> {
+        let snapshot_dir = find_latest_snapshot(data_dir)?;
+        let snapshot_id = snapshot_dir
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(|s| s.to_string())
+            .ok_or_else(|| SandboxError::new("Invalid snapshot directory name"))?;
+
+        let scratch_dir = config
+            .scratch_root
+            .join(format!("aerodb-sandbox-{}", Uuid::new_v4()));
+        ensure_isolated(data_dir, &config.scratch_root)?;
+
+        let sandbox = Self::seed(&snapshot_dir, &scratch_dir, &config);
+        if sandbox.is_err() {
+            let _ = fs::remove_dir_all(&scratch_dir);
+        }
+        let (schema_loader, wal_writer, storage_writer, storage_reader, index_manager) = sandbox?;
+
+        Ok(Self {
+            snapshot_id,
+            scratch_dir,
+            schema_loader,
+            wal_writer,
+            storage_writer,
+            storage_reader,
+            index_manager,
+            handler: ApiHandler::new(config.collection),
+            torn_down: false,
+        })
+    }
+
+    /// Copy snapshot contents into the scratch directory and open subsystems.
+    #[allow(clippy::type_complexity)]
+    fn seed(
+        snapshot_dir: &Path,
+        scratch_dir: &Path,
+        config: &SandboxConfig,
+    ) -> SandboxResult<(
+        SchemaLoader,
+        WalWriter,
+        StorageWriter,
+        StorageReader,
+        IndexManager,
+    )> {
+        let data_subdir = scratch_dir.join("data");
+        let schema_dir = scratch_dir.join("metadata").join("schemas");
+        for dir in [&data_subdir, &schema_dir] {
+            fs::create_dir_all(dir).map_err(|e| {
+                SandboxError::new(format!("Failed to create {}: {}", dir.display(), e))
+            })?;
+        }
+
+        // Scratch collection set: private copy of snapshot storage
+        let snapshot_storage = snapshot_dir.join("storage.dat");
+        if snapshot_storage.exists() {
+            fs::copy(&snapshot_storage, data_subdir.join("documents.dat")).map_err(|e| {
+                SandboxError::new(format!("Failed to copy snapshot storage: {}", e))
+            })?;
+        }
+
+        // Catalog overlay: private copy of snapshot schemas
+        let snapshot_schemas = snapshot_dir.join("schemas");
+        if snapshot_schemas.exists() {
+            let entries = fs::read_dir(&snapshot_schemas).map_err(|e| {
+                SandboxError::new(format!("Failed to read snapshot schemas: {}", e))
+            })?;
+            for entry in entries {
+                let entry = entry.map_err(|e| {
+                    SandboxError::new(format!("Failed to read snapshot schema entry: {}", e))
+                })?;
+                if entry.path().is_file() {
+                    fs::copy(entry.path(), schema_dir.join(entry.file_name()))
+                        .map_err(|e| SandboxError::new(format!("Failed to copy schema: {}", e)))?;
+                }
+            }
+        }
+
+        let mut schema_loader = SchemaLoader::new(scratch_dir);
+        schema_loader
+            .load_all()
+            .map_err(|e| SandboxError::new(format!("Schema load failed: {}", e)))?;
+
+        let storage_writer = StorageWriter::open(scratch_dir)
+            .map_err(|e| SandboxError::new(format!("Storage writer open failed: {}", e)))?;
+        let mut storage_reader = StorageReader::open_from_data_dir(scratch_dir)
+            .map_err(|e| SandboxError::new(format!("Storage reader open failed: {}", e)))?;
+
+        let mut index_manager = IndexManager::new(config.indexed_fields.clone());
+        index_manager
+            .rebuild_from_storage(&mut SandboxScan {
+                reader: &mut storage_reader,
+            })
+            .map_err(|e| SandboxError::new(format!("Index rebuild failed: {}", e)))?;
+
+        let wal_writer = WalWriter::open(scratch_dir)
+            .map_err(|e| SandboxError::new(format!("WAL writer open failed: {}", e)))?;
+
+        Ok((
+            schema_loader,
+            wal_writer,
+            storage_writer,
+            storage_reader,
+            index_manager,
+        ))
+    }
+
+    /// Snapshot ID the sandbox was seeded from.
+    pub fn snapshot_id(&self) -> &str {
+        &self.snapshot_id
+    }
+
+    /// Scratch directory holding all sandbox state.
+    pub fn scratch_dir(&self) -> &Path {
+        &self.scratch_dir
+    }
+
+    /// Register an experimental schema in the sandbox catalog.
+    ///
+    /// The schema is visible only inside this sandbox.
+    pub fn register_schema(&mut self, schema: Schema) -> SandboxResult<()> {
+        self.schema_loader
+            .register(schema)
+            .map_err(|e| SandboxError::new(format!("Schema registration failed: {}", e)))
+    }
+
+    /// Handle a raw JSON request against the sandbox.
+    ///
+    /// Supports all API operations plus `register_schema`, which takes a
+    /// `schema` object and registers it in the sandbox catalog.
+    pub fn handle(&mut self, json_request: &str) -> Response {
+        if let Ok(value) = serde_json::from_str::<Value>(json_request) {
+            if value.get("op").and_then(|v| v.as_str()) == Some("register_schema") {
+                return self.handle_register_schema(&value);
+            }
+        }
+
+        let mut subsystems = Subsystems {
+            schema_loader: &mut self.schema_loader,
+            wal_writer: &mut self.wal_writer,
+            storage_writer: &mut self.storage_writer,
+            storage_reader: &mut self.storage_reader,
+            index_manager: &mut self.index_manager,
+        };
+        self.handler.handle(json_request, &mut subsystems)
+    }
+
+    fn handle_register_schema(&mut self, request: &Value) -> Response {
+        let schema = match request
+            .get("schema")
+            .cloned()
+            .map(serde_json::from_value::<Schema>)
+        {
+            Some(Ok(schema)) => schema,
+            Some(Err(e)) => {
+                return Response::error(&ApiError::invalid_request(format!(
+                    "Invalid schema: {}",
+                    e
+                )))
+            }
+            None => return Response::error(&ApiError::invalid_request("Missing schema")),
+        };
+
+        let schema_id = schema.schema_id.clone();
+        let schema_version = schema.schema_version.clone();
+        match self.register_schema(schema) {
+            Ok(()) => Response::success(json!({
+                "registered": schema_id,
+                "schema_version": schema_version,
+            })),
+            Err(e) => Response::error(&ApiError::invalid_request(e.message())),
+        }
+    }
+
+    /// Tear down the sandbox, removing all scratch state.
+    pub fn teardown(mut self) -> SandboxResult<()> {
+        self.remove_scratch()
+    }
+
+    fn remove_scratch(&mut self) -> SandboxResult<()> {
+        if self.torn_down {
+            return Ok(());
+        }
+        self.torn_down = true;
+        fs::remove_dir_all(&self.scratch_dir).map_err(|e| {
+            SandboxError::new(format!(
+                "Failed to remove scratch directory {}: {}",
+                self.scratch_dir.display(),
+                e
+            ))
+        })
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = self.remove_scratch();
+    }
+}
+
+/// Reject scratch locations inside the source data directory.
+fn ensure_isolated(data_dir: &Path, scratch_root: &Path) -> SandboxResult<()> {
+    let data_dir = data_dir
+        .canonicalize()
+        .map_err(|e| SandboxError::new(format!("Invalid data directory: {}", e)))?;
+
+    fs::create_dir_all(scratch_root)
+        .map_err(|e| SandboxError::new(format!("Failed to create scratch root: {}", e)))?;
+    let scratch_root = scratch_root
+        .canonicalize()
+        .map_err(|e| SandboxError::new(format!("Invalid scratch root: {}", e)))?;
+
+    if scratch_root.starts_with(&data_dir) {
+        return Err(SandboxError::new(format!(
+            "Scratch root {} must not be inside data directory {}",
+            scratch_root.display(),
+            data_dir.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Locate the latest snapshot (by ID) that has a manifest.
+fn find_latest_snapshot(data_dir: &Path) -> SandboxResult<PathBuf> {
+    let snapshots_dir = crate::snapshot::snapshots_dir(data_dir);
+    let entries = fs::read_dir(&snapshots_dir).map_err(|e| {
+        SandboxError::new(format!(
+            "No snapshots available in {}: {}",
+            snapshots_dir.display(),
+            e
+        ))
+    })?;
+
+    let mut snapshots: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir() && path.join("manifest.json").exists())
+        .collect();
+    snapshots.sort();
+
+    snapshots
+        .pop()
+        .ok_or_else(|| SandboxError::new("No valid snapshots found"))
+}
+
+/// Index scan over sandbox storage.
+///
+/// Storage records are keyed `collection_id:document_id`; the API layer
+/// indexes by bare document ID, so the collection prefix is stripped.
+struct SandboxScan<'a> {
+    reader: &'a mut StorageReader,
+}
+
+impl StorageScan for SandboxScan<'_> {
+    fn scan_next(&mut self) -> IndexResult<Option<DocumentInfo>> {
+        let offset = self.reader.current_offset();
+        let record = match self.reader.read_next() {
+            Ok(Some(record)) => record,
+            Ok(None) => return Ok(None),
+            Err(e) => return Err(IndexError::data_corruption(offset, e.to_string())),
+        };
+
+        let document_id = match record.document_id.split_once(':') {
+            Some((_, id)) => id.to_string(),
+            None => record.document_id.clone(),
+        };
+        let body = if record.is_tombstone {
+            Value::Null
+        } else {
+            serde_json::from_slice(&record.document_body)
+                .map_err(|e| IndexError::data_corruption(offset, e.to_string()))?
+        };
+
+        Ok(Some(DocumentInfo {
+            document_id,
+            schema_id: record.schema_id,
+            schema_version: record.schema_version,
+            is_tombstone: record.is_tombstone,
+            body,
+            offset,
+        }))
+    }
+
+    fn reset(&mut self) -> IndexResult<()> {
+        self.reader
+            .reset()
+            .map_err(|e| IndexError::build_failed(e.to_string()))
+    }
+
+    fn current_offset(&self) -> u64 {
+        self.reader.current_offset()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::FieldDef;
+    use crate::storage::StoragePayload;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn users_schema(version: &str) -> Schema {
+        let mut fields = HashMap::new();
+        fields.insert("_id".to_string(), FieldDef::required_string());
+        fields.insert("name".to_string(), FieldDef::required_string());
+        Schema::new("users", version, fields)
+    }
+
+    /// Create a data directory with one snapshot containing one document.
+    fn setup_snapshot() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path().join("data_dir");
+        let snapshot_dir = data_dir.join("snapshots").join("20260101T000000Z");
+        fs::create_dir_all(snapshot_dir.join("schemas")).unwrap();
+
+        // Build a storage file with one document via a throwaway writer
+        let staging = temp.path().join("staging");
+        let mut writer = StorageWriter::open(&staging).unwrap();
+        let body = serde_json::to_vec(&json!({"_id": "u1", "name": "Alice"})).unwrap();
+        writer
+            .write(&StoragePayload::new("default", "u1", "users", "v1", body))
+            .unwrap();
+        fs::copy(
+            staging.join("data").join("documents.dat"),
+            snapshot_dir.join("storage.dat"),
+        )
+        .unwrap();
+
+        fs::write(
+            snapshot_dir.join("schemas").join("schema_users_v1.json"),
+            serde_json::to_string(&users_schema("v1")).unwrap(),
+        )
+        .unwrap();
+        fs::write(snapshot_dir.join("manifest.json"), "{}").unwrap();
+
+        temp
+    }
+
+    fn sandbox_config(temp: &TempDir) -> SandboxConfig {
+        SandboxConfig {
+            scratch_root: temp.path().join("scratch"),
+            ..SandboxConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_sandbox_reads_snapshot_data() {
+        let temp = setup_snapshot();
+        let data_dir = temp.path().join("data_dir");
+        let mut sandbox = Sandbox::open(&data_dir, sandbox_config(&temp)).unwrap();
+
+        assert_eq!(sandbox.snapshot_id(), "20260101T000000Z");
+
+        let resp = sandbox.handle(
+            r#"{"op": "query", "schema_id": "users", "schema_version": "v1",
+                "filter": {"_id": {"$eq": "u1"}}, "limit": 10}"#,
+        );
+        assert!(resp.is_success());
+        assert!(resp.to_json().contains("Alice"));
+    }
+
+    #[test]
+    fn test_sandbox_writes_never_touch_source() {
+        let temp = setup_snapshot();
+        let data_dir = temp.path().join("data_dir");
+        let mut sandbox = Sandbox::open(&data_dir, sandbox_config(&temp)).unwrap();
+
+        let resp = sandbox.handle(
+            r#"{"op": "insert", "schema_id": "users", "schema_version": "v1",
+                "document": {"_id": "u2", "name": "Bob"}}"#,
+        );
+        assert!(resp.is_success());
+
+        // No WAL or storage created in the source data directory
+        assert!(!data_dir.join("wal").exists());
+        assert!(!data_dir.join("data").exists());
+        assert!(sandbox.scratch_dir().join("wal").join("wal.log").exists());
+    }
+
+    #[test]
+    fn test_sandbox_schema_overlay() {
+        let temp = setup_snapshot();
+        let data_dir = temp.path().join("data_dir");
+        let mut sandbox = Sandbox::open(&data_dir, sandbox_config(&temp)).unwrap();
+
+        let schema = serde_json::to_string(&users_schema("v2")).unwrap();
+        let resp = sandbox.handle(&format!(
+            r#"{{"op": "register_schema", "schema": {}}}"#,
+            schema
+        ));
+        assert!(resp.is_success());
+
+        let resp = sandbox.handle(
+            r#"{"op": "insert", "schema_id": "users", "schema_version": "v2",
+                "document": {"_id": "u3", "name": "Carol"}}"#,
+        );
+        assert!(resp.is_success());
+
+        // Source catalog untouched
+        assert!(!data_dir.join("metadata").exists());
+    }
+
+    #[test]
+    fn test_teardown_removes_scratch() {
+        let temp = setup_snapshot();
+        let data_dir = temp.path().join("data_dir");
+        let sandbox = Sandbox::open(&data_dir, sandbox_config(&temp)).unwrap();
+        let scratch = sandbox.scratch_dir().to_path_buf();
+
+        assert!(scratch.exists());
+        sandbox.teardown().unwrap();
+        assert!(!scratch.exists());
+    }
+
+    #[test]
+    fn test_scratch_inside_data_dir_rejected() {
+        let temp = setup_snapshot();
+        let data_dir = temp.path().join("data_dir");
+        let config = SandboxConfig {
+            scratch_root: data_dir.join("scratch"),
+            ..SandboxConfig::default()
+        };
+
+        assert!(Sandbox::open(&data_dir, config).is_err());
+    }
+
+    #[test]
+    fn test_missing_snapshot_rejected() {
+        let temp = TempDir::new().unwrap();
+        let data_dir = temp.path().join("empty");
+        fs::create_dir_all(&data_dir).unwrap();
+
+        assert!(Sandbox::open(&data_dir, sandbox_config(&temp)).is_err());
+    }
+}
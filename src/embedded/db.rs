@@ -0,0 +1,614 @@
+//! Embedded Database
+//!
+//! `AeroDb` opens a data directory in-process: it takes the data directory
+//! lock, runs the same mandatory recovery as `aerodb start`, and owns the
+//! WAL, storage, schemas and indexes from then on. Requests run through
+//! the `ApiHandler` with the service role, as local stdin clients of the
+//! server do.
+//!
+//! Dropping an `AeroDb` shuts it down cleanly: the WAL is fsynced, a
+//! checkpoint is taken if `checkpoint_on_close` is set, the clean shutdown
+//! marker is written and the lock released. `close` does the same and
+//! reports failures.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+
+use super::document::{from_document, to_document, AeroDocument};
+use super::errors::{DbError, DbErrorCode, DbResult};
+use crate::api::{query_request, ApiHandler, PriorityClass, Response, Subsystems};
+use crate::backup::{BackupId, BackupManager, BackupProtection};
+use crate::checkpoint::{CheckpointId, CheckpointManager, IndexCapture};
+use crate::cli::{
+    boot_system, create_data_dirs, is_initialized, BootOptions, CliErrorCode, ProcessLock,
+    DEFAULT_COLLECTION,
+};
+use crate::core::AuthContext;
+use crate::index::IndexManager;
+use crate::planner::Query;
+use crate::recovery::{IndexStorage, RecoveryManager, VerificationLevel};
+use crate::schema::{Schema, SchemaLoader};
+use crate::snapshot::{GlobalExecutionLock, SnapshotManager};
+use crate::storage::{
+    CollectionKeyring, FieldKeyring, MasterKeyring, StorageFormat, StorageReader, StorageWriter,
+};
+use crate::wal::{TailRecovery, WalWriter};
+
+/// How `AeroDb::open` opens a data directory
+#[derive(Debug, Clone)]
+pub struct AeroDbOptions {
+    /// Initialize the data directory if it is not one yet (default true)
+    pub create: bool,
+    /// Storage format of a newly initialized data directory
+    pub storage_format: StorageFormat,
+    /// Policy for a torn final WAL record
+    pub tail_recovery: TailRecovery,
+    /// Storage verification after WAL replay (default full)
+    pub verification: VerificationLevel,
+    /// Worker threads for a full index rebuild (default 1)
+    pub index_rebuild_threads: usize,
+    /// Checkpoint when the database is closed (default false)
+    pub checkpoint_on_close: bool,
+}
+
+impl Default for AeroDbOptions {
+    fn default() -> Self {
+        let boot = BootOptions::default();
+        Self {
+            create: true,
+            storage_format: StorageFormat::default(),
+            tail_recovery: boot.tail_recovery,
+            verification: boot.verification,
+            index_rebuild_threads: boot.index_rebuild_threads,
+            checkpoint_on_close: false,
+        }
+    }
+}
+
+/// An AeroDB database open in this process
+pub struct AeroDb {
+    data_dir: PathBuf,
+    handler: ApiHandler,
+    wal_writer: WalWriter,
+    storage_writer: StorageWriter,
+    storage_reader: StorageReader,
+    schema_loader: SchemaLoader,
+    index_manager: IndexManager,
+    checkpoint_on_close: bool,
+    /// Data directory lock, held until the database is closed
+    lock: Option<ProcessLock>,
+}
+
+impl AeroDb {
+    /// Open the database in `path`, recovering it from its WAL
+    ///
+    /// # Errors
+    ///
+    /// `AERO_DB_NOT_INITIALIZED` if `path` is not a data directory and
+    /// `create` is off, `AERO_DB_LOCKED` if a server or another `AeroDb`
+    /// holds it, `AERO_DB_OPEN_FAILED` if recovery fails.
+    pub fn open(path: impl AsRef<Path>, options: AeroDbOptions) -> DbResult<Self> {
+        let data_dir = path.as_ref();
+        let open_failed = |message: &str| DbError::new(DbErrorCode::OpenFailed, message);
+
+        if !is_initialized(data_dir) {
+            if !options.create {
+                return Err(DbError::new(
+                    DbErrorCode::NotInitialized,
+                    format!("{} is not an AeroDB data directory", data_dir.display()),
+                ));
+            }
+            create_data_dirs(data_dir).map_err(|e| open_failed(e.message()))?;
+            let master_keys = MasterKeyring::open(data_dir)
+                .map_err(|e| open_failed(&format!("Master keyring open failed: {}", e)))?;
+            StorageWriter::open_with_keys(data_dir, options.storage_format, &master_keys)
+                .map_err(|e| open_failed(&format!("Failed to create storage file: {}", e)))?;
+        }
+
+        let lock = ProcessLock::acquire(data_dir).map_err(|e| match e.code() {
+            CliErrorCode::AlreadyRunning => DbError::new(DbErrorCode::Locked, e.message()),
+            _ => open_failed(e.message()),
+        })?;
+        let boot = BootOptions {
+            tail_recovery: options.tail_recovery,
+            collection: DEFAULT_COLLECTION,
+            index_rebuild_threads: options.index_rebuild_threads,
+            verification: options.verification,
+        };
+        let (wal_writer, storage_writer, storage_reader, schema_loader, index_manager) =
+            boot_system(data_dir, &boot).map_err(|e| open_failed(e.message()))?;
+        let field_keys = FieldKeyring::open(data_dir)
+            .map_err(|e| open_failed(&format!("Field keyring open failed: {}", e)))?;
+
+        Ok(Self {
+            data_dir: data_dir.to_path_buf(),
+            handler: ApiHandler::new(DEFAULT_COLLECTION).with_field_keys(Arc::new(field_keys)),
+            wal_writer,
+            storage_writer,
+            storage_reader,
+            schema_loader,
+            index_manager,
+            checkpoint_on_close: options.checkpoint_on_close,
+            lock: Some(lock),
+        })
+    }
+
+    /// Data directory of the database
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// Run an API request (any `op`) and return its `data`
+    pub fn execute(&mut self, request: Value) -> DbResult<Value> {
+        let mut subsystems = Subsystems {
+            schema_loader: &mut self.schema_loader,
+            wal_writer: &mut self.wal_writer,
+            storage_writer: &mut self.storage_writer,
+            storage_reader: &mut self.storage_reader,
+            index_manager: &mut self.index_manager,
+        };
+        let response = self.handler.handle_as(
+            &request.to_string(),
+            PriorityClass::Authenticated,
+            &AuthContext::service_role(),
+            &mut subsystems,
+        );
+        match response {
+            Response::Success(success) => Ok(success.data),
+            Response::Error(error) => Err(DbError::from_response(error)),
+        }
+    }
+
+    /// Register the first version of a new schema
+    pub fn create_schema(&mut self, schema: &Schema) -> DbResult<()> {
+        self.execute(json!({"op": "create_schema", "schema": schema}))?;
+        Ok(())
+    }
+
+    /// Insert `document` into the default collection, returning its id
+    pub fn insert(
+        &mut self,
+        schema_id: &str,
+        schema_version: &str,
+        document: Value,
+    ) -> DbResult<String> {
+        let data = self.execute(json!({
+            "op": "insert",
+            "schema_id": schema_id,
+            "schema_version": schema_version,
+            "document": document,
+        }))?;
+        Ok(data["inserted"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Document `id` of `schema_id`, or `None` if there is none
+    pub fn get(&mut self, schema_id: &str, id: &str) -> DbResult<Option<Value>> {
+        let result = self.execute(json!({
+            "op": "get",
+            "schema_id": schema_id,
+            "document_id": id,
+        }));
+        match result {
+            Ok(document) => Ok(Some(document)),
+            Err(e) if e.code() == "AERO_NOT_FOUND" => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Documents matching `query`, in result order
+    ///
+    /// The query goes through the planner's bounds and index checks, like
+    /// an API `query` request.
+    pub fn query(&mut self, query: &Query) -> DbResult<Vec<Value>> {
+        let request = query_request(query).map_err(|e| DbError::from_api_error(&e))?;
+        match self.execute(request)? {
+            Value::Array(documents) => Ok(documents),
+            other => Ok(vec![other]),
+        }
+    }
+
+    /// Register the schema of `T`
+    pub fn create_document_schema<T: AeroDocument>(&mut self) -> DbResult<()> {
+        self.create_schema(&T::schema())
+    }
+
+    /// Insert a typed document, returning its id
+    pub fn insert_document<T: AeroDocument>(&mut self, document: &T) -> DbResult<String> {
+        let document = to_document(document)?;
+        self.insert(T::SCHEMA_ID, T::SCHEMA_VERSION, document)
+    }
+
+    /// Typed document `id`, or `None` if there is none
+    pub fn get_document<T: AeroDocument>(&mut self, id: &str) -> DbResult<Option<T>> {
+        self.get(T::SCHEMA_ID, id)?.map(from_document).transpose()
+    }
+
+    /// Typed documents matching `query`, which must target `T`'s schema
+    ///
+    /// Start from `T::query()` to get the schema and version right.
+    pub fn query_documents<T: AeroDocument>(&mut self, query: &Query) -> DbResult<Vec<T>> {
+        if query.schema_id != T::SCHEMA_ID {
+            return Err(DbError::new(
+                DbErrorCode::MappingFailed,
+                format!(
+                    "query targets schema '{}', not '{}'",
+                    query.schema_id,
+                    T::SCHEMA_ID
+                ),
+            ));
+        }
+        self.query(query)?.into_iter().map(from_document).collect()
+    }
+
+    /// Checkpoint: snapshot storage and indexes, then truncate the WAL
+    pub fn checkpoint(&mut self) -> DbResult<CheckpointId> {
+        let failed = |message: String| DbError::new(DbErrorCode::CheckpointFailed, message);
+        let keyring = CollectionKeyring::open(&self.data_dir)
+            .map_err(|e| failed(format!("Collection keyring open failed: {}", e)))?;
+        // Index checkpoints hold plaintext keys; none are kept for
+        // storage encrypted at rest
+        let at_rest = self.storage_writer.is_encrypted();
+        CheckpointManager::create_checkpoint_with_indexes(
+            &self.data_dir,
+            &self.data_dir.join("data").join("documents.dat"),
+            self.schema_loader.schema_dir(),
+            &SnapshotManager,
+            &mut self.wal_writer,
+            IndexCapture {
+                index: &self.index_manager,
+                collection: DEFAULT_COLLECTION,
+                storage: &mut IndexStorage::new(&mut self.storage_reader),
+                sealed: &|collection| at_rest || keyring.is_encrypted(collection),
+            },
+            &GlobalExecutionLock::new(),
+        )
+        .map_err(|e| failed(e.to_string()))
+    }
+
+    /// Rotate the master key and re-encrypt storage and the WAL under it
+    ///
+    /// A new master key `key_id` is generated and activated, then a
+    /// checkpoint rewrites storage under a fresh data key and truncates the
+    /// WAL, which restarts under another. Storage is reopened and indexes
+    /// rebuilt over the new record offsets. Unencrypted storage becomes
+    /// encrypted the same way.
+    ///
+    /// # Errors
+    ///
+    /// `AERO_DB_CHECKPOINT_FAILED` if the key cannot be installed or the
+    /// checkpoint fails.
+    pub fn rotate_master_key(&mut self, key_id: &str) -> DbResult<CheckpointId> {
+        let failed = |message: String| DbError::new(DbErrorCode::CheckpointFailed, message);
+        let keys = match self.wal_writer.master_keys() {
+            Some(keys) => Arc::clone(keys),
+            None => return Err(failed("WAL was opened without a master keyring".into())),
+        };
+        keys.rotate(key_id)
+            .map_err(|e| failed(format!("Master key rotation failed: {}", e)))?;
+
+        let storage_path = self.storage_writer.path().to_path_buf();
+        let checkpoint_id = CheckpointManager::create_reencrypting_checkpoint(
+            &self.data_dir,
+            &storage_path,
+            self.schema_loader.schema_dir(),
+            &SnapshotManager,
+            &mut self.wal_writer,
+            &keys,
+            &GlobalExecutionLock::new(),
+        )
+        .map_err(|e| failed(e.to_string()))?;
+
+        // Record offsets moved: reopen storage and rebuild every index
+        let keyring = Arc::new(
+            CollectionKeyring::open(&self.data_dir)
+                .map_err(|e| failed(format!("Collection keyring open failed: {}", e)))?,
+        );
+        let mut storage_writer =
+            StorageWriter::open_with_keys(&self.data_dir, StorageFormat::V1, &keys)
+                .map_err(|e| failed(format!("Storage writer reopen failed: {}", e)))?;
+        let mut storage_reader = StorageReader::open_with_keys(&storage_path, &keys)
+            .map_err(|e| failed(format!("Storage reader reopen failed: {}", e)))?;
+        storage_writer.set_keyring(Arc::clone(&keyring));
+        storage_reader.set_keyring(Arc::clone(&keyring));
+        self.storage_writer = storage_writer;
+        self.storage_reader = storage_reader;
+
+        self.index_manager.clear();
+        RecoveryManager::new(&self.data_dir)
+            .recover_indexes(
+                &mut self.index_manager,
+                DEFAULT_COLLECTION,
+                &mut IndexStorage::new(&mut self.storage_reader).with_keyring(keyring),
+            )
+            .map_err(|e| failed(format!("Index rebuild failed: {}", e)))?;
+        for name in self.index_manager.collection_names() {
+            if self.schema_loader.collection_schema(&name).is_none() {
+                self.index_manager.drop_collection(&name);
+            }
+        }
+        Ok(checkpoint_id)
+    }
+
+    /// Write a backup archive to `output_path`
+    ///
+    /// The archive holds the latest checkpoint and the WAL written since,
+    /// so at least one checkpoint must have been taken.
+    pub fn backup(&mut self, output_path: &Path) -> DbResult<BackupId> {
+        self.protected_backup(output_path, &BackupProtection::default())
+    }
+
+    /// Write a backup archive to `output_path`, encrypted and signed per
+    /// `protection`
+    pub fn protected_backup(
+        &mut self,
+        output_path: &Path,
+        protection: &BackupProtection,
+    ) -> DbResult<BackupId> {
+        BackupManager::create_protected_backup(
+            &self.data_dir,
+            output_path,
+            &self.wal_writer,
+            protection,
+            &GlobalExecutionLock::new(),
+        )
+        .map_err(|e| DbError::new(DbErrorCode::BackupFailed, e.to_string()))
+    }
+
+    /// Shut down cleanly, reporting any failure
+    ///
+    /// Dropping the database does the same, ignoring failures.
+    pub fn close(mut self) -> DbResult<()> {
+        self.shutdown()
+    }
+
+    fn shutdown(&mut self) -> DbResult<()> {
+        let Some(lock) = self.lock.take() else {
+            return Ok(());
+        };
+        let failed = |message: String| DbError::new(DbErrorCode::CloseFailed, message);
+
+        self.wal_writer
+            .fsync()
+            .map_err(|e| failed(format!("WAL fsync on close failed: {}", e)))?;
+        // A failed checkpoint leaves the WAL intact; the next open replays it
+        if self.checkpoint_on_close {
+            self.checkpoint().map_err(|e| failed(e.to_string()))?;
+        }
+        fs::write(self.data_dir.join("clean_shutdown"), "")
+            .map_err(|e| failed(format!("Failed to write shutdown marker: {}", e)))?;
+        lock.release().map_err(|e| failed(e.message().to_string()))
+    }
+}
+
+impl Drop for AeroDb {
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use aerodb_derive::AeroDocument;
+    use serde::{Deserialize, Serialize};
+    use tempfile::TempDir;
+
+    use crate::planner::Predicate;
+    use crate::schema::FieldDef;
+    use crate::storage::MASTER_KEYS_DIR;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, AeroDocument)]
+    #[aerodb(schema = "users", version = "v1")]
+    struct User {
+        #[serde(rename = "_id")]
+        id: String,
+        name: String,
+        age: Option<i64>,
+    }
+
+    #[derive(Serialize, Deserialize, AeroDocument)]
+    #[aerodb(schema = "accounts", version = "v2")]
+    struct Account {
+        #[serde(rename = "_id")]
+        id: String,
+        #[serde(rename = "mail")]
+        #[aerodb(unique)]
+        email: String,
+        #[serde(skip)]
+        #[allow(dead_code)]
+        cached: bool,
+    }
+
+    fn users_schema() -> Schema {
+        let mut fields = HashMap::new();
+        fields.insert("_id".to_string(), FieldDef::required_string());
+        fields.insert("name".to_string(), FieldDef::required_string());
+        fields.insert("age".to_string(), FieldDef::optional_int());
+        Schema::new("users", "v1", fields)
+    }
+
+    #[test]
+    fn test_documents_survive_reopen() {
+        let dir = TempDir::new().unwrap();
+        let mut db = AeroDb::open(dir.path(), AeroDbOptions::default()).unwrap();
+        db.create_schema(&users_schema()).unwrap();
+        db.execute(json!({"op": "create_index", "field": "age"}))
+            .unwrap();
+        let id = db
+            .insert(
+                "users",
+                "v1",
+                json!({"_id": "u1", "name": "Ada", "age": 36}),
+            )
+            .unwrap();
+        assert_eq!(id, "u1");
+        db.insert(
+            "users",
+            "v1",
+            json!({"_id": "u2", "name": "Grace", "age": 45}),
+        )
+        .unwrap();
+        assert!(db
+            .insert("users", "v1", json!({"_id": "u3", "age": 1}))
+            .is_err());
+        db.close().unwrap();
+        assert!(dir.path().join("clean_shutdown").exists());
+
+        let mut db = AeroDb::open(dir.path(), AeroDbOptions::default()).unwrap();
+        assert_eq!(db.get("users", "u1").unwrap().unwrap()["name"], "Ada");
+        assert_eq!(db.get("users", "missing").unwrap(), None);
+
+        let query = Query::new(DEFAULT_COLLECTION, "users")
+            .with_schema_version("v1")
+            .with_predicate(Predicate::gte("age", json!(18)))
+            .with_limit(10);
+        let mut ids: Vec<Value> = db
+            .query(&query)
+            .unwrap()
+            .into_iter()
+            .map(|d| d["_id"].clone())
+            .collect();
+        ids.sort_by_key(|id| id.to_string());
+        assert_eq!(ids, vec![json!("u1"), json!("u2")]);
+    }
+
+    #[test]
+    fn test_typed_documents() {
+        let dir = TempDir::new().unwrap();
+        let mut db = AeroDb::open(dir.path(), AeroDbOptions::default()).unwrap();
+        assert_eq!(User::FIELDS, &["_id", "name", "age"]);
+        assert_eq!(Account::FIELDS, &["_id", "mail"]);
+        assert!(Account::field_defs()["mail"].unique);
+        assert_eq!(Account::schema().key(), ("accounts", "v2"));
+        db.create_document_schema::<User>().unwrap();
+        db.execute(json!({"op": "create_index", "field": "age"}))
+            .unwrap();
+
+        let ada = User {
+            id: "u1".to_string(),
+            name: "Ada".to_string(),
+            age: Some(36),
+        };
+        let kid = User {
+            id: "u2".to_string(),
+            name: "Kid".to_string(),
+            age: None,
+        };
+        assert_eq!(db.insert_document(&ada).unwrap(), "u1");
+        db.insert_document(&kid).unwrap();
+        assert_eq!(db.get_document::<User>("u2").unwrap(), Some(kid));
+        assert_eq!(db.get_document::<User>("missing").unwrap(), None);
+
+        let query = User::query()
+            .with_predicate(Predicate::gte("age", json!(18)))
+            .with_limit(10);
+        assert_eq!(db.query_documents::<User>(&query).unwrap(), vec![ada]);
+
+        let other = Query::new(DEFAULT_COLLECTION, "orders").with_limit(1);
+        let err = db.query_documents::<User>(&other).unwrap_err();
+        assert_eq!(err.code(), "AERO_DB_MAPPING_FAILED");
+    }
+
+    #[test]
+    fn test_open_takes_the_lock_until_drop() {
+        let dir = TempDir::new().unwrap();
+        let db = AeroDb::open(dir.path(), AeroDbOptions::default()).unwrap();
+        let err = AeroDb::open(dir.path(), AeroDbOptions::default())
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "AERO_DB_LOCKED");
+
+        drop(db);
+        assert!(dir.path().join("clean_shutdown").exists());
+        assert!(AeroDb::open(dir.path(), AeroDbOptions::default()).is_ok());
+
+        let empty = TempDir::new().unwrap();
+        let options = AeroDbOptions {
+            create: false,
+            ..AeroDbOptions::default()
+        };
+        let err = AeroDb::open(empty.path(), options).err().unwrap();
+        assert_eq!(err.code(), "AERO_DB_NOT_INITIALIZED");
+    }
+
+    #[test]
+    fn test_checkpoint_and_backup() {
+        let dir = TempDir::new().unwrap();
+        let backup = dir.path().join("backup.tar");
+        let data = dir.path().join("data");
+        let options = AeroDbOptions {
+            checkpoint_on_close: true,
+            ..AeroDbOptions::default()
+        };
+        let mut db = AeroDb::open(&data, options).unwrap();
+        db.create_schema(&users_schema()).unwrap();
+        db.insert("users", "v1", json!({"_id": "u1", "name": "Ada"}))
+            .unwrap();
+
+        assert_eq!(
+            db.backup(&backup).unwrap_err().code(),
+            "AERO_DB_BACKUP_FAILED"
+        );
+        db.checkpoint().unwrap();
+        db.insert("users", "v1", json!({"_id": "u2", "name": "Grace"}))
+            .unwrap();
+        db.backup(&backup).unwrap();
+        assert!(backup.exists());
+        db.close().unwrap();
+
+        let mut db = AeroDb::open(&data, AeroDbOptions::default()).unwrap();
+        assert!(db.get("users", "u2").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_rotate_master_key_encrypts_at_rest() {
+        let dir = TempDir::new().unwrap();
+        let mut db = AeroDb::open(dir.path(), AeroDbOptions::default()).unwrap();
+        db.create_schema(&users_schema()).unwrap();
+        db.execute(json!({"op": "create_index", "field": "age"}))
+            .unwrap();
+        db.insert(
+            "users",
+            "v1",
+            json!({"_id": "u1", "name": "Ada", "age": 36}),
+        )
+        .unwrap();
+
+        db.rotate_master_key("m1").unwrap();
+        db.insert(
+            "users",
+            "v1",
+            json!({"_id": "u2", "name": "Grace", "age": 45}),
+        )
+        .unwrap();
+        let query = Query::new(DEFAULT_COLLECTION, "users")
+            .with_schema_version("v1")
+            .with_predicate(Predicate::gte("age", json!(18)))
+            .with_limit(10);
+        assert_eq!(db.query(&query).unwrap().len(), 2);
+        db.rotate_master_key("m2").unwrap();
+        db.close().unwrap();
+
+        for file in [["data", "documents.dat"], ["wal", "wal.log"]] {
+            let bytes = fs::read(dir.path().join(file[0]).join(file[1])).unwrap();
+            assert!(!bytes.windows(5).any(|w| w == b"Grace"));
+        }
+        let mut db = AeroDb::open(dir.path(), AeroDbOptions::default()).unwrap();
+        assert_eq!(db.get("users", "u1").unwrap().unwrap()["name"], "Ada");
+        assert_eq!(db.query(&query).unwrap().len(), 2);
+        db.close().unwrap();
+
+        // Without its master keys the database refuses to open
+        fs::remove_dir_all(dir.path().join("keys").join(MASTER_KEYS_DIR)).unwrap();
+        let err = AeroDb::open(dir.path(), AeroDbOptions::default())
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "AERO_DB_OPEN_FAILED");
+        assert!(err.message().contains("AERO_ENCRYPTION_KEY_UNAVAILABLE"));
+    }
+}
@@ -0,0 +1,206 @@
+//! Typed documents
+//!
+//! `AeroDocument` maps a Rust struct to documents of one schema version.
+//! The schema is built from the struct's field list, so typed inserts are
+//! validated against the same definition the struct was written for.
+//! `#[derive(AeroDocument)]` (feature `derive`) generates the impl.
+//!
+//! `None` fields are left out of the written document rather than stored
+//! as null, since strict schemas reject explicit nulls.
+
+use std::collections::HashMap;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use super::errors::{DbError, DbErrorCode, DbResult};
+use crate::cli::DEFAULT_COLLECTION;
+use crate::planner::Query;
+use crate::schema::{FieldDef, Schema};
+
+/// A Rust type stored as documents of one schema version
+pub trait AeroDocument: Serialize + DeserializeOwned {
+    /// Schema the documents belong to
+    const SCHEMA_ID: &'static str;
+    /// Schema version documents are written with
+    const SCHEMA_VERSION: &'static str;
+    /// Document field names, `_id` included
+    const FIELDS: &'static [&'static str];
+
+    /// Definition of every field in `FIELDS`
+    fn field_defs() -> HashMap<String, FieldDef>;
+
+    /// Schema to register for this type
+    fn schema() -> Schema {
+        Schema::new(Self::SCHEMA_ID, Self::SCHEMA_VERSION, Self::field_defs())
+    }
+
+    /// Query of this type's schema version, without predicates or limit
+    fn query() -> Query {
+        Query::new(DEFAULT_COLLECTION, Self::SCHEMA_ID).with_schema_version(Self::SCHEMA_VERSION)
+    }
+}
+
+/// A Rust type usable as an `AeroDocument` field
+///
+/// Implement it for a nested struct with `FieldDef::required_object` to
+/// use the struct as an object field.
+pub trait FieldValue {
+    /// Schema definition of a field of this type
+    fn field_def() -> FieldDef;
+}
+
+impl FieldValue for String {
+    fn field_def() -> FieldDef {
+        FieldDef::required_string()
+    }
+}
+
+macro_rules! int_field_value {
+    ($($ty:ty),*) => {
+        $(impl FieldValue for $ty {
+            fn field_def() -> FieldDef {
+                FieldDef::required_int()
+            }
+        })*
+    };
+}
+
+// Types that always fit the 64-bit signed integer field type
+int_field_value!(i8, i16, i32, i64, u8, u16, u32);
+
+impl FieldValue for bool {
+    fn field_def() -> FieldDef {
+        FieldDef::required_bool()
+    }
+}
+
+impl FieldValue for f32 {
+    fn field_def() -> FieldDef {
+        FieldDef::required_float()
+    }
+}
+
+impl FieldValue for f64 {
+    fn field_def() -> FieldDef {
+        FieldDef::required_float()
+    }
+}
+
+impl<T: FieldValue> FieldValue for Option<T> {
+    fn field_def() -> FieldDef {
+        FieldDef {
+            required: false,
+            ..T::field_def()
+        }
+    }
+}
+
+impl<T: FieldValue> FieldValue for Vec<T> {
+    fn field_def() -> FieldDef {
+        FieldDef::required_array(T::field_def().field_type)
+    }
+}
+
+fn mapping_failed(type_name: &str, err: serde_json::Error) -> DbError {
+    DbError::new(
+        DbErrorCode::MappingFailed,
+        format!("{} mapping failed: {}", type_name, err),
+    )
+}
+
+/// JSON form of `document`, with `None` fields left out
+pub(crate) fn to_document<T: AeroDocument>(document: &T) -> DbResult<Value> {
+    let mut value = serde_json::to_value(document)
+        .map_err(|e| mapping_failed(std::any::type_name::<T>(), e))?;
+    match value.as_object_mut() {
+        Some(fields) => fields.retain(|_, v| !v.is_null()),
+        None => {
+            return Err(DbError::new(
+                DbErrorCode::MappingFailed,
+                format!(
+                    "{} does not serialize to an object",
+                    std::any::type_name::<T>()
+                ),
+            ))
+        }
+    }
+    Ok(value)
+}
+
+/// `T` from its JSON form
+pub(crate) fn from_document<T: AeroDocument>(document: Value) -> DbResult<T> {
+    serde_json::from_value(document).map_err(|e| mapping_failed(std::any::type_name::<T>(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::FieldType;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Note {
+        #[serde(rename = "_id")]
+        id: String,
+        body: String,
+        stars: Option<u32>,
+        tags: Vec<String>,
+    }
+
+    impl AeroDocument for Note {
+        const SCHEMA_ID: &'static str = "notes";
+        const SCHEMA_VERSION: &'static str = "v1";
+        const FIELDS: &'static [&'static str] = &["_id", "body", "stars", "tags"];
+
+        fn field_defs() -> HashMap<String, FieldDef> {
+            let mut fields = HashMap::new();
+            fields.insert("_id".to_string(), String::field_def());
+            fields.insert("body".to_string(), String::field_def());
+            fields.insert("stars".to_string(), Option::<u32>::field_def());
+            fields.insert("tags".to_string(), Vec::<String>::field_def());
+            fields
+        }
+    }
+
+    #[test]
+    fn test_field_defs_from_types() {
+        assert_eq!(Option::<i64>::field_def(), FieldDef::optional_int());
+        assert_eq!(
+            Vec::<f64>::field_def(),
+            FieldDef::required_array(FieldType::Float)
+        );
+        assert_eq!(
+            Option::<Vec<bool>>::field_def(),
+            FieldDef::optional_array(FieldType::Bool)
+        );
+
+        let schema = Note::schema();
+        assert_eq!(schema.key(), ("notes", "v1"));
+        assert!(schema.validate_structure().is_ok());
+        let mut names: Vec<&str> = schema.fields.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        assert_eq!(names, Note::FIELDS);
+    }
+
+    #[test]
+    fn test_none_fields_left_out() {
+        let note = Note {
+            id: "n1".to_string(),
+            body: "hello".to_string(),
+            stars: None,
+            tags: vec!["a".to_string()],
+        };
+        let document = to_document(&note).unwrap();
+        assert_eq!(
+            document,
+            json!({"_id": "n1", "body": "hello", "tags": ["a"]})
+        );
+        assert_eq!(from_document::<Note>(document).unwrap(), note);
+
+        let err = from_document::<Note>(json!({"_id": "n2"})).unwrap_err();
+        assert_eq!(err.code(), "AERO_DB_MAPPING_FAILED");
+    }
+}
@@ -0,0 +1,97 @@
+//! Embedded API error types
+//!
+//! Errors of opening, checkpointing, backing up and closing a database
+//! carry an `AERO_DB_*` code. Errors of individual requests keep the code
+//! the API layer reported (e.g. `AERO_NOT_FOUND`, `AERO_QUERY_UNBOUNDED`).
+
+use std::fmt;
+
+use crate::api::{ApiError, ErrorResponse};
+
+/// Embedded API error codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DbErrorCode {
+    /// The data directory is not initialized and `create` is off
+    NotInitialized,
+    /// Another process holds the data directory
+    Locked,
+    /// Recovery or subsystem startup failed
+    OpenFailed,
+    /// Checkpoint failed; the WAL is intact
+    CheckpointFailed,
+    /// Backup failed; serving state is untouched
+    BackupFailed,
+    /// Clean shutdown could not complete
+    CloseFailed,
+    /// A typed document did not convert to or from its JSON form
+    MappingFailed,
+}
+
+impl DbErrorCode {
+    /// Get the error code string
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotInitialized => "AERO_DB_NOT_INITIALIZED",
+            Self::Locked => "AERO_DB_LOCKED",
+            Self::OpenFailed => "AERO_DB_OPEN_FAILED",
+            Self::CheckpointFailed => "AERO_DB_CHECKPOINT_FAILED",
+            Self::BackupFailed => "AERO_DB_BACKUP_FAILED",
+            Self::CloseFailed => "AERO_DB_CLOSE_FAILED",
+            Self::MappingFailed => "AERO_DB_MAPPING_FAILED",
+        }
+    }
+}
+
+/// Embedded API error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbError {
+    code: String,
+    message: String,
+}
+
+impl DbError {
+    /// Create an error with a database-level code
+    pub fn new(code: DbErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code: code.code().to_string(),
+            message: message.into(),
+        }
+    }
+
+    /// Error of a request, keeping the API error code
+    pub fn from_response(response: ErrorResponse) -> Self {
+        Self {
+            code: response.code,
+            message: response.message,
+        }
+    }
+
+    /// Error of a request built by the embedded API itself
+    pub fn from_api_error(err: &ApiError) -> Self {
+        Self {
+            code: err.code().to_string(),
+            message: err.message().to_string(),
+        }
+    }
+
+    /// Get the error code string
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Get the error message
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for DbError {}
+
+/// Result type for embedded API operations
+pub type DbResult<T> = Result<T, DbError>;
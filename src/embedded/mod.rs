@@ -0,0 +1,35 @@
+//! # Embedded API
+//!
+//! In-process use of AeroDB, without the CLI or HTTP stack:
+//!
+//! ```ignore
+//! let mut db = AeroDb::open("./data", AeroDbOptions::default())?;
+//! db.create_schema(&schema)?;
+//! db.insert("users", "v1", json!({"_id": "u1", "name": "Ada"}))?;
+//! let user = db.get("users", "u1")?;
+//! db.checkpoint()?;
+//! ```
+//!
+//! Structs implementing `AeroDocument` (by hand or with
+//! `#[derive(AeroDocument)]` under the `derive` feature) get typed
+//! `insert_document`, `get_document` and `query_documents`.
+//!
+//! Opening runs the same recovery as `aerodb start` and holds the data
+//! directory lock, so a database is open in at most one process at a time.
+//!
+//! ## Module Structure
+//!
+//! - `db` - `AeroDb` and its open options
+//! - `document` - Typed document mapping
+//! - `errors` - Embedded API error types
+
+mod db;
+mod document;
+mod errors;
+
+pub use db::{AeroDb, AeroDbOptions};
+pub use document::{AeroDocument, FieldValue};
+pub use errors::{DbError, DbErrorCode, DbResult};
+
+#[cfg(feature = "derive")]
+pub use aerodb_derive::AeroDocument;
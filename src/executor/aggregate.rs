@@ -0,0 +1,174 @@
+//! Scalar aggregates over query results
+//!
+//! Aggregates fold matching documents one at a time, so a count or sum over
+//! a bounded query never materializes the result set.
+
+use std::cmp::Ordering;
+
+use serde_json::{json, Value};
+
+use super::sorter::ResultSorter;
+
+/// Aggregate function over a single field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateFunction {
+    /// Smallest value (sort order of `ResultSorter`)
+    Min,
+    /// Largest value (sort order of `ResultSorter`)
+    Max,
+    /// Sum of numeric values
+    Sum,
+}
+
+impl AggregateFunction {
+    /// Parses a function name (`min`, `max`, `sum`)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "min" => Some(AggregateFunction::Min),
+            "max" => Some(AggregateFunction::Max),
+            "sum" => Some(AggregateFunction::Sum),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AggregateFunction::Min => "min",
+            AggregateFunction::Max => "max",
+            AggregateFunction::Sum => "sum",
+        }
+    }
+}
+
+/// Running state of one aggregate
+///
+/// Documents missing the field, or holding null, are counted but do not
+/// contribute a value. `Sum` ignores non-numeric values and stays integral
+/// until a float is seen or the integer sum overflows.
+#[derive(Debug, Clone)]
+pub struct Aggregator {
+    function: AggregateFunction,
+    field: String,
+    count: u64,
+    extreme: Option<Value>,
+    int_sum: Option<i64>,
+    float_sum: f64,
+}
+
+impl Aggregator {
+    /// Creates an empty aggregator
+    pub fn new(function: AggregateFunction, field: impl Into<String>) -> Self {
+        Self {
+            function,
+            field: field.into(),
+            count: 0,
+            extreme: None,
+            int_sum: Some(0),
+            float_sum: 0.0,
+        }
+    }
+
+    /// Folds one matching document into the aggregate
+    pub fn accumulate(&mut self, doc: &Value) {
+        self.count += 1;
+
+        let value = match doc.get(&self.field) {
+            Some(Value::Null) | None => return,
+            Some(v) => v,
+        };
+
+        match self.function {
+            AggregateFunction::Min => self.keep_extreme(value, Ordering::Less),
+            AggregateFunction::Max => self.keep_extreme(value, Ordering::Greater),
+            AggregateFunction::Sum => {
+                let Some(n) = value.as_f64() else {
+                    return;
+                };
+                self.float_sum += n;
+                self.int_sum = match (self.int_sum, value.as_i64()) {
+                    (Some(sum), Some(i)) => sum.checked_add(i),
+                    _ => None,
+                };
+            }
+        }
+    }
+
+    fn keep_extreme(&mut self, value: &Value, wanted: Ordering) {
+        let replace = match &self.extreme {
+            None => true,
+            Some(current) => ResultSorter::compare_values(Some(value), Some(current)) == wanted,
+        };
+        if replace {
+            self.extreme = Some(value.clone());
+        }
+    }
+
+    /// Number of documents folded in
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Aggregate result (null for min/max over no values)
+    pub fn value(&self) -> Value {
+        match self.function {
+            AggregateFunction::Min | AggregateFunction::Max => {
+                self.extreme.clone().unwrap_or(Value::Null)
+            }
+            AggregateFunction::Sum => match self.int_sum {
+                Some(sum) => json!(sum),
+                None => json!(self.float_sum),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fold(function: AggregateFunction, docs: &[Value]) -> Aggregator {
+        let mut agg = Aggregator::new(function, "age");
+        for doc in docs {
+            agg.accumulate(doc);
+        }
+        agg
+    }
+
+    #[test]
+    fn test_min_max_sum() {
+        let docs = [
+            json!({"age": 30}),
+            json!({"age": 20}),
+            json!({"name": "no age"}),
+            json!({"age": 45}),
+        ];
+
+        assert_eq!(fold(AggregateFunction::Min, &docs).value(), json!(20));
+        assert_eq!(fold(AggregateFunction::Max, &docs).value(), json!(45));
+        let sum = fold(AggregateFunction::Sum, &docs);
+        assert_eq!(sum.value(), json!(95));
+        assert_eq!(sum.count(), 4);
+    }
+
+    #[test]
+    fn test_empty_and_mixed_inputs() {
+        assert_eq!(fold(AggregateFunction::Min, &[]).value(), Value::Null);
+        assert_eq!(fold(AggregateFunction::Sum, &[]).value(), json!(0));
+
+        let docs = [json!({"age": 1}), json!({"age": 1.5}), json!({"age": "x"})];
+        assert_eq!(fold(AggregateFunction::Sum, &docs).value(), json!(2.5));
+
+        let docs = [json!({"age": i64::MAX}), json!({"age": 1})];
+        assert!(fold(AggregateFunction::Sum, &docs).value().is_f64());
+    }
+
+    #[test]
+    fn test_parse_function() {
+        assert_eq!(
+            AggregateFunction::parse("max"),
+            Some(AggregateFunction::Max)
+        );
+        assert_eq!(AggregateFunction::parse("avg"), None);
+        assert_eq!(AggregateFunction::Sum.as_str(), "sum");
+    }
+}
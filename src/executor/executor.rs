@@ -7,11 +7,14 @@
 //! 2. Read documents from storage
 //! 3. Validate checksum on every read
 //! 4. Filter documents strictly according to predicates
-//! 5. Apply schema version filtering
+//! 5. Apply schema version filtering (older versions are upgraded when a
+//!    migration path to the plan's version exists)
 //! 6. Apply sort (if specified)
 //! 7. Apply limit
 //! 8. Return ordered results
 
+use std::time::Instant;
+
 use serde_json::Value;
 
 use crate::planner::{FilterOp, QueryPlan, ScanType};
@@ -19,8 +22,9 @@ use crate::storage::DocumentRecord;
 
 use super::errors::{ExecutorError, ExecutorResult};
 use super::filters::PredicateFilter;
-use super::result::{ExecutionResult, ResultDocument};
-use super::sorter::ResultSorter;
+use super::result::{ExecutionResult, ExecutionStats, ResultDocument};
+use super::spill::{SortSpillConfig, SpillingSorter};
+use super::stream::ResultStream;
 
 /// Trait for looking up document offsets by index
 pub trait IndexLookup {
@@ -30,6 +34,10 @@ pub trait IndexLookup {
     /// Get all document offsets for an indexed field equality
     fn lookup_eq(&self, field: &str, value: &Value) -> Vec<u64>;
 
+    /// Get all document offsets whose leading composite index fields equal
+    /// `prefix` (one value per bound field, in index order)
+    fn lookup_composite(&self, fields: &[String], prefix: &[Value]) -> Vec<u64>;
+
     /// Get all document offsets for an indexed field range
     fn lookup_range(&self, field: &str, min: Option<&Value>, max: Option<&Value>) -> Vec<u64>;
 
@@ -45,97 +53,181 @@ pub trait StorageRead {
     fn read_at(&mut self, offset: u64) -> ExecutorResult<Option<DocumentRecord>>;
 }
 
+/// Trait for upgrading documents stored under an older schema version
+pub trait DocumentUpgrade {
+    /// Upgrade `body` from `from_version` to `to_version`.
+    /// Returns None if no migration path exists
+    fn upgrade(
+        &self,
+        schema_id: &str,
+        from_version: &str,
+        to_version: &str,
+        body: Value,
+    ) -> Option<Value>;
+}
+
 /// Query executor that processes plans against storage
 pub struct QueryExecutor<'a, I: IndexLookup, S: StorageRead> {
     index: &'a I,
     storage: &'a mut S,
+    upgrader: Option<&'a dyn DocumentUpgrade>,
+    sort_spill: Option<SortSpillConfig>,
 }
 
 impl<'a, I: IndexLookup, S: StorageRead> QueryExecutor<'a, I, S> {
     /// Creates a new executor
     pub fn new(index: &'a I, storage: &'a mut S) -> Self {
-        Self { index, storage }
+        Self {
+            index,
+            storage,
+            upgrader: None,
+            sort_spill: None,
+        }
+    }
+
+    /// Serves documents stored under older schema versions by upgrading
+    /// them at read time. Without an upgrader they are excluded.
+    ///
+    /// Index lookups still see stored field names, so predicates on renamed
+    /// fields should be planned as scans.
+    pub fn with_upgrader(mut self, upgrader: &'a dyn DocumentUpgrade) -> Self {
+        self.upgrader = Some(upgrader);
+        self
+    }
+
+    /// Sorts more candidates than `config.threshold` by spilling sorted
+    /// runs to `config.dir` and merging them. Without it sorts stay in
+    /// memory.
+    pub fn with_sort_spill(mut self, config: SortSpillConfig) -> Self {
+        self.sort_spill = Some(config);
+        self
     }
 
     /// Executes a query plan and returns results.
     ///
     /// This method is deterministic: same plan + same data = same results.
     pub fn execute(&mut self, plan: &QueryPlan) -> ExecutorResult<ExecutionResult> {
+        let started = Instant::now();
+        let mut stats = ExecutionStats::default();
+
         // Step 1: Use chosen_index to obtain candidate document offsets
         let offsets = self.get_candidate_offsets(plan);
+        stats.index_candidates = offsets.len() as u64;
 
         // Steps 2-5: Read, validate, filter, and check schema
-        let mut candidates = Vec::new();
+        let spill = self.sort_spill.clone();
+        let mut sorter = SpillingSorter::new(&plan.sort, spill.as_ref());
         let mut scanned_count = 0;
 
         for offset in offsets {
             scanned_count += 1;
+            if let Some(doc) = self.read_candidate(plan, offset, &mut stats)? {
+                sorter.push(doc)?;
+            }
+        }
 
-            // Step 2-3: Read document with checksum validation
-            let record = match self.storage.read_at(offset)? {
-                Some(r) => r,
-                None => continue, // Invalid offset, skip
-            };
+        // Steps 6-7: Apply sort (if specified, spilling past the
+        // threshold) and limit
+        let limit = plan.limit as usize;
+        let limit_applied = sorter.len() > limit;
+        let (candidates, sort_runs_spilled) = sorter.finish(limit)?;
 
-            // Skip tombstones
-            if record.is_tombstone {
-                continue;
-            }
+        // Step 8: Return ordered results
+        stats.documents_scanned = scanned_count as u64;
+        stats.elapsed_micros = started.elapsed().as_micros() as u64;
+        Ok(ExecutionResult {
+            returned_count: candidates.len(),
+            scanned_count,
+            limit_applied,
+            documents: candidates,
+            stats,
+            sort_runs_spilled,
+        })
+    }
 
-            // Step 5: Schema version filtering
-            // Extract schema info from document_id (format: collection:id)
-            if record.schema_id != plan.schema_id || record.schema_version != plan.schema_version {
-                continue; // Schema mismatch, exclude (not error)
-            }
+    /// Executes a query plan, yielding results lazily.
+    ///
+    /// Unsorted plans read, validate and filter one candidate per document
+    /// yielded and stop at the limit. Sorted plans must see every match
+    /// before the first is yielded; only the best `limit` are buffered.
+    /// Results equal those of `execute`.
+    pub fn execute_streaming<'e>(&'e mut self, plan: &'e QueryPlan) -> ResultStream<'e, 'a, I, S> {
+        ResultStream::new(self, plan)
+    }
 
-            // Parse document body
-            let body: Value = match serde_json::from_slice(&record.document_body) {
-                Ok(v) => v,
-                Err(_) => continue, // Invalid JSON, skip
-            };
+    /// Steps 2-5 for one candidate offset: read with checksum validation,
+    /// check the schema version (upgrading if possible), then filter.
+    ///
+    /// Returns `None` for a candidate that is excluded from the result.
+    pub(super) fn read_candidate(
+        &mut self,
+        plan: &QueryPlan,
+        offset: u64,
+        stats: &mut ExecutionStats,
+    ) -> ExecutorResult<Option<ResultDocument>> {
+        // Step 2-3: Read document with checksum validation
+        let record = match self.storage.read_at(offset)? {
+            Some(r) => r,
+            None => return Ok(None), // Invalid offset, skip
+        };
+        stats.checksum_validations += 1;
 
-            // Step 4: Filter according to predicates
-            if !PredicateFilter::matches(&body, &plan.predicates) {
-                continue;
-            }
+        // Skip tombstones
+        if record.is_tombstone {
+            return Ok(None);
+        }
 
-            // Extract document ID from composite (collection:id -> id)
-            let doc_id = record
-                .document_id
-                .split(':')
-                .last()
-                .unwrap_or(&record.document_id);
+        // Step 5: Schema version filtering
+        // Extract schema info from document_id (format: collection:id)
+        let current = record.schema_version == plan.schema_version;
+        if record.schema_id != plan.schema_id || (!current && self.upgrader.is_none()) {
+            return Ok(None); // Schema mismatch, exclude (not error)
+        }
+
+        // Parse document body
+        let body: Value = match serde_json::from_slice(&record.document_body) {
+            Ok(v) => v,
+            Err(_) => return Ok(None), // Invalid JSON, skip
+        };
 
-            candidates.push(ResultDocument::new(
-                doc_id,
+        // Older versions are served under the plan's version if possible
+        let body = match self.upgrader {
+            Some(upgrader) if !current => match upgrader.upgrade(
                 &record.schema_id,
                 &record.schema_version,
+                &plan.schema_version,
                 body,
-                offset,
-            ));
-        }
+            ) {
+                Some(upgraded) => upgraded,
+                None => return Ok(None),
+            },
+            _ => body,
+        };
 
-        // Step 6: Apply sort (if specified)
-        if let Some(sort_spec) = &plan.sort {
-            ResultSorter::sort(&mut candidates, sort_spec);
+        // Step 4: Filter according to predicates
+        stats.filter_evaluations += 1;
+        if !PredicateFilter::matches(&body, &plan.predicates) {
+            return Ok(None);
         }
 
-        // Step 7: Apply limit
-        let limit = plan.limit as usize;
-        let limit_applied = candidates.len() > limit;
-        candidates.truncate(limit);
-
-        // Step 8: Return ordered results
-        Ok(ExecutionResult {
-            returned_count: candidates.len(),
-            scanned_count,
-            limit_applied,
-            documents: candidates,
-        })
+        // Extract document ID from composite (collection:id -> id)
+        let doc_id = record
+            .document_id
+            .split(':')
+            .last()
+            .unwrap_or(&record.document_id);
+
+        Ok(Some(ResultDocument::new(
+            doc_id,
+            &record.schema_id,
+            &plan.schema_version,
+            body,
+            offset,
+        )))
     }
 
     /// Gets candidate document offsets based on plan's chosen index and scan type.
-    fn get_candidate_offsets(&self, plan: &QueryPlan) -> Vec<u64> {
+    pub(super) fn get_candidate_offsets(&self, plan: &QueryPlan) -> Vec<u64> {
         match plan.scan_type {
             ScanType::PrimaryKey => {
                 // Find the _id predicate value
@@ -150,6 +242,12 @@ impl<'a, I: IndexLookup, S: StorageRead> QueryExecutor<'a, I, S> {
                 }
                 Vec::new()
             }
+            ScanType::CompositeEquality => match &plan.composite {
+                Some(composite) => self
+                    .index
+                    .lookup_composite(&composite.fields, &plan.composite_prefix()),
+                None => Vec::new(),
+            },
             ScanType::IndexedEquality => {
                 // Find the equality predicate for chosen index
                 for pred in &plan.predicates {
@@ -161,6 +259,27 @@ impl<'a, I: IndexLookup, S: StorageRead> QueryExecutor<'a, I, S> {
                 }
                 Vec::new()
             }
+            ScanType::IndexedIn => {
+                // One probe per value, merged in offset order
+                for pred in &plan.predicates {
+                    if pred.field == plan.chosen_index {
+                        if let FilterOp::In(ref values) = pred.op {
+                            let mut offsets: Vec<u64> = values
+                                .iter()
+                                .flat_map(|val| match (plan.chosen_index.as_str(), val.as_str()) {
+                                    ("_id", Some(pk)) => self.index.lookup_pk(pk),
+                                    ("_id", None) => Vec::new(),
+                                    _ => self.index.lookup_eq(&plan.chosen_index, val),
+                                })
+                                .collect();
+                            offsets.sort_unstable();
+                            offsets.dedup();
+                            return offsets;
+                        }
+                    }
+                }
+                Vec::new()
+            }
             ScanType::IndexedRange => {
                 // Find range bounds for chosen index
                 let mut min = None;
@@ -168,15 +287,17 @@ impl<'a, I: IndexLookup, S: StorageRead> QueryExecutor<'a, I, S> {
 
                 for pred in &plan.predicates {
                     if pred.field == plan.chosen_index {
-                        match &pred.op {
-                            FilterOp::Gte(v) | FilterOp::Gt(v) => min = Some(v),
-                            FilterOp::Lte(v) | FilterOp::Lt(v) => max = Some(v),
-                            _ => {}
+                        if let Some(v) = pred.op.lower_bound() {
+                            min = Some(v);
+                        }
+                        if let Some(v) = pred.op.upper_bound() {
+                            max = Some(v);
                         }
                     }
                 }
 
-                self.index.lookup_range(&plan.chosen_index, min, max)
+                self.index
+                    .lookup_range(&plan.chosen_index, min.as_ref(), max.as_ref())
             }
         }
     }
@@ -185,7 +306,7 @@ impl<'a, I: IndexLookup, S: StorageRead> QueryExecutor<'a, I, S> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::planner::{BoundednessProof, Predicate, SortSpec};
+    use crate::planner::{BoundednessProof, CompositeScan, Predicate, SortSpec};
     use crate::storage::DocumentRecord;
     use serde_json::json;
     use std::collections::HashMap;
@@ -194,6 +315,7 @@ mod tests {
     struct MockIndex {
         pk_index: HashMap<String, Vec<u64>>,
         field_indexes: HashMap<String, HashMap<String, Vec<u64>>>,
+        composite_entries: Vec<(Vec<String>, Vec<Value>, u64)>,
         all_offsets: Vec<u64>,
     }
 
@@ -202,6 +324,7 @@ mod tests {
             Self {
                 pk_index: HashMap::new(),
                 field_indexes: HashMap::new(),
+                composite_entries: Vec::new(),
                 all_offsets: Vec::new(),
             }
         }
@@ -226,6 +349,13 @@ mod tests {
         }
     }
 
+    impl MockIndex {
+        fn add_composite(&mut self, fields: &[&str], values: Vec<Value>, offset: u64) {
+            let fields = fields.iter().map(|f| f.to_string()).collect();
+            self.composite_entries.push((fields, values, offset));
+        }
+    }
+
     impl IndexLookup for MockIndex {
         fn lookup_pk(&self, pk: &str) -> Vec<u64> {
             self.pk_index.get(pk).cloned().unwrap_or_default()
@@ -245,6 +375,17 @@ mod tests {
                 .unwrap_or_default()
         }
 
+        fn lookup_composite(&self, fields: &[String], prefix: &[Value]) -> Vec<u64> {
+            let mut offsets: Vec<u64> = self
+                .composite_entries
+                .iter()
+                .filter(|(f, values, _)| f == fields && values.starts_with(prefix))
+                .map(|(_, _, offset)| *offset)
+                .collect();
+            offsets.sort_unstable();
+            offsets
+        }
+
         fn lookup_range(
             &self,
             _field: &str,
@@ -318,8 +459,10 @@ mod tests {
             schema_version: version.to_string(),
             chosen_index: index.to_string(),
             scan_type,
+            composite: None,
+            index_costs: Vec::new(),
             predicates,
-            sort: None,
+            sort: Vec::new(),
             limit,
             bounds_proof: BoundednessProof::pk_lookup(),
         }
@@ -399,6 +542,101 @@ mod tests {
 
         assert_eq!(result.len(), 1);
         assert_eq!(result.documents[0].id, "user_1");
+
+        // Only the index candidate is read, validated and filtered
+        assert_eq!(result.stats.index_candidates, 1);
+        assert_eq!(result.stats.documents_scanned, 1);
+        assert_eq!(result.stats.checksum_validations, 1);
+        assert_eq!(result.stats.filter_evaluations, 1);
+    }
+
+    #[test]
+    fn test_composite_prefix_lookup() {
+        let mut index = MockIndex::new();
+        let mut storage = MockStorage::new();
+        let rows = [
+            ("user_1", "Paris", "active", 30),
+            ("user_2", "Paris", "active", 40),
+            ("user_3", "Paris", "banned", 30),
+            ("user_4", "Rome", "active", 30),
+        ];
+        for (i, (id, city, status, age)) in rows.iter().enumerate() {
+            let offset = (i as u64 + 1) * 100;
+            index.add_pk(id, offset);
+            index.add_composite(
+                &["city", "status", "age"],
+                vec![json!(city), json!(status), json!(age)],
+                offset,
+            );
+            storage.add_record(
+                offset,
+                make_record(
+                    id,
+                    "users",
+                    "v1",
+                    json!({"_id": id, "city": city, "status": status, "age": age}),
+                ),
+            );
+        }
+
+        let mut plan = make_plan(
+            "users",
+            "v1",
+            "city,status,age",
+            ScanType::CompositeEquality,
+            vec![
+                Predicate::eq("city", json!("Paris")),
+                Predicate::eq("status", json!("active")),
+            ],
+            10,
+        );
+        plan.composite = Some(CompositeScan {
+            fields: vec!["city".into(), "status".into(), "age".into()],
+            bound_fields: 2,
+        });
+
+        let mut executor = QueryExecutor::new(&index, &mut storage);
+        let result = executor.execute(&plan).unwrap();
+
+        assert_eq!(result.scanned_count, 2);
+        let ids: Vec<_> = result.documents.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, vec!["user_1", "user_2"]);
+    }
+
+    #[test]
+    fn test_indexed_in_probes_each_value() {
+        let mut index = MockIndex::new();
+        let emails = ["alice@example.com", "bob@example.com", "carol@example.com"];
+        let mut storage = MockStorage::new();
+        for (i, email) in emails.iter().enumerate() {
+            let id = format!("user_{}", i + 1);
+            let offset = (i as u64 + 1) * 100;
+            index.add_pk(&id, offset);
+            index.add_field_index("email", email, offset);
+            storage.add_record(
+                offset,
+                make_record(&id, "users", "v1", json!({"_id": id, "email": email})),
+            );
+        }
+
+        let plan = make_plan(
+            "users",
+            "v1",
+            "email",
+            ScanType::IndexedIn,
+            vec![Predicate::in_values(
+                "email",
+                vec![json!("carol@example.com"), json!("alice@example.com")],
+            )],
+            10,
+        );
+
+        let mut executor = QueryExecutor::new(&index, &mut storage);
+        let result = executor.execute(&plan).unwrap();
+
+        assert_eq!(result.scanned_count, 2);
+        let ids: Vec<_> = result.documents.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, vec!["user_1", "user_3"]);
     }
 
     #[test]
@@ -486,6 +724,7 @@ mod tests {
                 m
             },
             field_indexes: HashMap::new(),
+            composite_entries: Vec::new(),
             all_offsets: vec![200],
         };
 
@@ -496,6 +735,80 @@ mod tests {
         assert_eq!(result.len(), 0);
     }
 
+    /// Upgrades users v1 -> v2 by renaming `name` to `full_name`
+    struct RenameUpgrade;
+
+    impl DocumentUpgrade for RenameUpgrade {
+        fn upgrade(&self, schema_id: &str, from: &str, to: &str, mut body: Value) -> Option<Value> {
+            if (schema_id, from, to) != ("users", "v1", "v2") {
+                return None;
+            }
+            let name = body.as_object_mut()?.remove("name")?;
+            body["full_name"] = name;
+            Some(body)
+        }
+    }
+
+    #[test]
+    fn test_older_version_upgraded_at_read_time() {
+        let mut index = MockIndex::new();
+        index.all_offsets = vec![100, 200, 300];
+
+        let mut storage = MockStorage::new();
+        storage.add_record(
+            100,
+            make_record(
+                "user_1",
+                "users",
+                "v1",
+                json!({"_id": "user_1", "name": "Alice"}),
+            ),
+        );
+        storage.add_record(
+            200,
+            make_record(
+                "user_2",
+                "users",
+                "v2",
+                json!({"_id": "user_2", "full_name": "Alice"}),
+            ),
+        );
+        storage.add_record(
+            300,
+            make_record(
+                "user_3",
+                "users",
+                "v0",
+                json!({"_id": "user_3", "name": "Alice"}),
+            ),
+        );
+
+        let plan = make_plan(
+            "users",
+            "v2",
+            "_id",
+            ScanType::IndexedRange,
+            vec![Predicate::eq("full_name", json!("Alice"))],
+            10,
+        );
+
+        // Without an upgrader only v2 documents are served
+        let result = QueryExecutor::new(&index, &mut storage)
+            .execute(&plan)
+            .unwrap();
+        assert_eq!(result.len(), 1);
+
+        // v1 is upgraded and filtered on its new field name; v0 has no path
+        let upgrader = RenameUpgrade;
+        let result = QueryExecutor::new(&index, &mut storage)
+            .with_upgrader(&upgrader)
+            .execute(&plan)
+            .unwrap();
+        let ids: Vec<_> = result.documents.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, vec!["user_1", "user_2"]);
+        assert_eq!(result.documents[0].schema_version, "v2");
+    }
+
     #[test]
     fn test_corruption_halts_execution() {
         let mut index = MockIndex::new();
@@ -559,7 +872,7 @@ mod tests {
             vec![Predicate::gte("age", json!(27))],
             10,
         );
-        plan.sort = Some(SortSpec::asc("age"));
+        plan.sort = vec![SortSpec::asc("age")];
 
         // Execute multiple times
         let mut executor = QueryExecutor::new(&index, &mut storage);
@@ -611,6 +924,128 @@ mod tests {
         assert!(result.limit_applied);
     }
 
+    #[test]
+    fn test_streaming_matches_execute() {
+        let mut index = MockIndex::new();
+        let mut storage = MockStorage::new();
+        for i in 1..=10 {
+            index.add_pk(&format!("user_{}", i), i as u64 * 100);
+            storage.add_record(
+                i as u64 * 100,
+                make_record(
+                    &format!("user_{}", i),
+                    "users",
+                    "v1",
+                    json!({"_id": format!("user_{}", i), "age": 20 + i}),
+                ),
+            );
+        }
+        let mut plan = make_plan(
+            "users",
+            "v1",
+            "age",
+            ScanType::IndexedRange,
+            vec![Predicate::gte("age", json!(21))],
+            3,
+        );
+
+        // Unsorted: each pull reads one candidate
+        let mut executor = QueryExecutor::new(&index, &mut storage);
+        let mut stream = executor.execute_streaming(&plan);
+        assert_eq!(stream.next().unwrap().unwrap().id, "user_1");
+        assert_eq!(stream.stats().documents_scanned, 1);
+        assert_eq!(stream.stats().index_candidates, 10);
+        let rest: Vec<String> = stream.map(|d| d.unwrap().id).collect();
+        assert_eq!(rest, vec!["user_2", "user_3"]);
+
+        // Sorted: every candidate is read before the first result
+        plan.sort = vec![SortSpec::desc("age")];
+        let mut executor = QueryExecutor::new(&index, &mut storage);
+        let expected: Vec<String> = executor
+            .execute(&plan)
+            .unwrap()
+            .documents
+            .into_iter()
+            .map(|d| d.id)
+            .collect();
+        let mut stream = executor.execute_streaming(&plan);
+        let first = stream.next().unwrap().unwrap();
+        assert_eq!(stream.stats().documents_scanned, 10);
+        let streamed: Vec<String> = std::iter::once(Ok(first))
+            .chain(stream)
+            .map(|d| d.unwrap().id)
+            .collect();
+        assert_eq!(streamed, expected);
+        assert_eq!(streamed, vec!["user_10", "user_9", "user_8"]);
+    }
+
+    #[test]
+    fn test_sort_spills_past_threshold() {
+        let mut index = MockIndex::new();
+        let mut storage = MockStorage::new();
+        for i in 1..=10 {
+            index.add_pk(&format!("user_{}", i), i as u64 * 100);
+            storage.add_record(
+                i as u64 * 100,
+                make_record(
+                    &format!("user_{}", i),
+                    "users",
+                    "v1",
+                    json!({"_id": format!("user_{}", i), "age": 20 + i % 4}),
+                ),
+            );
+        }
+        let mut plan = make_plan(
+            "users",
+            "v1",
+            "age",
+            ScanType::IndexedRange,
+            vec![Predicate::gte("age", json!(20))],
+            5,
+        );
+        plan.sort = vec![SortSpec::desc("age")];
+
+        let mut executor = QueryExecutor::new(&index, &mut storage);
+        let in_memory = executor.execute(&plan).unwrap();
+        assert_eq!(in_memory.sort_runs_spilled, 0);
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut executor = QueryExecutor::new(&index, &mut storage)
+            .with_sort_spill(SortSpillConfig::new(temp_dir.path()).with_threshold(3));
+        let spilled = executor.execute(&plan).unwrap();
+        assert_eq!(spilled.sort_runs_spilled, 3);
+        assert!(spilled.limit_applied);
+
+        let ids = |r: &ExecutionResult| r.iter().map(|d| d.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&spilled), ids(&in_memory));
+        assert_eq!(
+            ids(&spilled),
+            vec!["user_3", "user_7", "user_10", "user_2", "user_6"]
+        );
+    }
+
+    #[test]
+    fn test_streaming_ends_after_corruption() {
+        let mut index = MockIndex::new();
+        index.add_pk("user_1", 100);
+        let mut storage = MockStorage::new();
+        storage.mark_corrupt(100);
+
+        let plan = make_plan(
+            "users",
+            "v1",
+            "_id",
+            ScanType::PrimaryKey,
+            vec![Predicate::eq("_id", json!("user_1"))],
+            1,
+        );
+
+        let mut executor = QueryExecutor::new(&index, &mut storage);
+        let mut stream = executor.execute_streaming(&plan);
+        assert!(stream.next().unwrap().unwrap_err().is_fatal());
+        assert!(stream.next().is_none());
+    }
+
     #[test]
     fn test_replay_stability() {
         // Same setup as deterministic_ordering
@@ -21,14 +21,17 @@ impl PredicateFilter {
 
     /// Checks if a document matches a single predicate
     fn matches_predicate(document: &Value, predicate: &Predicate) -> bool {
+        // Missing and null fields only match `$exists: false`
+        let absent = matches!(predicate.op, FilterOp::Exists(false));
+
         let field_value = match document.get(&predicate.field) {
             Some(v) => v,
-            None => return false, // Missing field = no match
+            None => return absent,
         };
 
         // Null values never match
         if field_value.is_null() {
-            return false;
+            return absent;
         }
 
         match &predicate.op {
@@ -37,6 +40,12 @@ impl PredicateFilter {
             FilterOp::Gt(bound) => Self::gt_match(field_value, bound),
             FilterOp::Lte(bound) => Self::lte_match(field_value, bound),
             FilterOp::Lt(bound) => Self::lt_match(field_value, bound),
+            FilterOp::In(values) => values.iter().any(|v| Self::eq_match(field_value, v)),
+            FilterOp::Ne(value) => !Self::eq_match(field_value, value),
+            FilterOp::Prefix(prefix) => field_value
+                .as_str()
+                .is_some_and(|s| s.starts_with(prefix.as_str())),
+            FilterOp::Exists(present) => *present,
         }
     }
 
@@ -192,4 +201,61 @@ mod tests {
         let pred = Predicate::eq("name", json!("Alice"));
         assert!(!PredicateFilter::matches(&doc, &[pred]));
     }
+
+    #[test]
+    fn test_in_ne_exists_prefix() {
+        let doc = json!({"name": "Alice", "age": 30, "nickname": null});
+
+        assert!(PredicateFilter::matches(
+            &doc,
+            &[Predicate::in_values("age", vec![json!(20), json!(30)])]
+        ));
+        assert!(!PredicateFilter::matches(
+            &doc,
+            &[Predicate::in_values("age", vec![json!("30")])]
+        ));
+
+        assert!(PredicateFilter::matches(
+            &doc,
+            &[Predicate::ne("name", json!("Bob"))]
+        ));
+        assert!(!PredicateFilter::matches(
+            &doc,
+            &[Predicate::ne("name", json!("Alice"))]
+        ));
+        assert!(!PredicateFilter::matches(
+            &doc,
+            &[Predicate::ne("missing", json!(1))]
+        ));
+
+        assert!(PredicateFilter::matches(
+            &doc,
+            &[Predicate::exists("name", true)]
+        ));
+        assert!(PredicateFilter::matches(
+            &doc,
+            &[Predicate::exists("nickname", false)]
+        ));
+        assert!(PredicateFilter::matches(
+            &doc,
+            &[Predicate::exists("missing", false)]
+        ));
+        assert!(!PredicateFilter::matches(
+            &doc,
+            &[Predicate::exists("missing", true)]
+        ));
+
+        assert!(PredicateFilter::matches(
+            &doc,
+            &[Predicate::prefix("name", "Al")]
+        ));
+        assert!(!PredicateFilter::matches(
+            &doc,
+            &[Predicate::prefix("name", "al")]
+        ));
+        assert!(!PredicateFilter::matches(
+            &doc,
+            &[Predicate::prefix("age", "3")]
+        ));
+    }
 }
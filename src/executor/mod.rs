@@ -8,25 +8,35 @@
 //! 2. Read documents from storage
 //! 3. Validate checksum on every read
 //! 4. Filter documents strictly according to predicates
-//! 5. Apply schema version filtering
-//! 6. Apply sort (if specified)
+//! 5. Apply schema version filtering (upgrading older versions if possible)
+//! 6. Apply sort (if specified), spilling sorted runs to disk past a
+//!    configured threshold
 //! 7. Apply limit
 //! 8. Return ordered results
 //!
+//! `execute` returns every result at once; `execute_streaming` yields them
+//! lazily, in the same order.
+//!
 //! # Invariants
 //!
 //! - T2: Deterministic execution
 //! - D2: Checksum validation on every read
 //! - F1: Fail loudly on corruption
 
+mod aggregate;
 mod errors;
 mod executor;
 mod filters;
 mod result;
 mod sorter;
+mod spill;
+mod stream;
 
+pub use aggregate::{AggregateFunction, Aggregator};
 pub use errors::{ExecutorError, ExecutorErrorCode, ExecutorResult};
-pub use executor::{IndexLookup, QueryExecutor};
+pub use executor::{DocumentUpgrade, IndexLookup, QueryExecutor};
 pub use filters::PredicateFilter;
-pub use result::{ExecutionResult, ResultDocument};
+pub use result::{ExecutionResult, ExecutionStats, ResultDocument};
 pub use sorter::ResultSorter;
+pub use spill::{SortSpillConfig, DEFAULT_SORT_SPILL_THRESHOLD};
+pub use stream::ResultStream;
@@ -1,9 +1,10 @@
 //! Result types for query execution
 
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 /// A single document in the result set
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResultDocument {
     /// Document ID
     pub id: String,
@@ -46,6 +47,21 @@ impl ResultDocument {
     }
 }
 
+/// Work done while executing one query
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ExecutionStats {
+    /// Candidate offsets visited
+    pub documents_scanned: u64,
+    /// Offsets produced by the chosen index (0 for storage scans)
+    pub index_candidates: u64,
+    /// Records read and checksum-validated
+    pub checksum_validations: u64,
+    /// Predicate filter evaluations
+    pub filter_evaluations: u64,
+    /// Wall-clock execution time, in microseconds
+    pub elapsed_micros: u64,
+}
+
 /// Result of query execution
 #[derive(Debug, Clone)]
 pub struct ExecutionResult {
@@ -57,6 +73,10 @@ pub struct ExecutionResult {
     pub returned_count: usize,
     /// Whether limit was applied
     pub limit_applied: bool,
+    /// Work done producing this result
+    pub stats: ExecutionStats,
+    /// Sorted runs spilled to disk (0: sorted in memory)
+    pub sort_runs_spilled: usize,
 }
 
 impl ExecutionResult {
@@ -67,6 +87,8 @@ impl ExecutionResult {
             scanned_count: 0,
             returned_count: 0,
             limit_applied: false,
+            stats: ExecutionStats::default(),
+            sort_runs_spilled: 0,
         }
     }
 
@@ -103,5 +125,6 @@ mod tests {
         let result = ExecutionResult::empty();
         assert!(result.is_empty());
         assert_eq!(result.len(), 0);
+        assert_eq!(result.stats, ExecutionStats::default());
     }
 }
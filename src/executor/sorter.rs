@@ -9,21 +9,33 @@ use crate::planner::{SortDirection, SortSpec};
 pub struct ResultSorter;
 
 impl ResultSorter {
-    /// Sorts documents according to sort specification.
+    /// Sorts documents by the sort keys in priority order.
     ///
-    /// Sort is stable and deterministic.
-    pub fn sort(documents: &mut [ResultDocument], sort_spec: &SortSpec) {
-        documents.sort_by(|a, b| {
-            let a_val = a.body.get(&sort_spec.field);
-            let b_val = b.body.get(&sort_spec.field);
-
-            let ordering = Self::compare_values(a_val, b_val);
+    /// Documents equal on every key are ordered by `_id` ascending, so the
+    /// result does not depend on candidate order.
+    pub fn sort(documents: &mut [ResultDocument], sort_specs: &[SortSpec]) {
+        documents.sort_by(|a, b| Self::compare(a, b, sort_specs));
+    }
 
-            match sort_spec.direction {
-                SortDirection::Asc => ordering,
-                SortDirection::Desc => ordering.reverse(),
-            }
-        });
+    /// Compares two documents by the sort keys in priority order, then by
+    /// `_id` ascending.
+    pub fn compare(
+        a: &ResultDocument,
+        b: &ResultDocument,
+        sort_specs: &[SortSpec],
+    ) -> std::cmp::Ordering {
+        sort_specs
+            .iter()
+            .map(|spec| {
+                let ordering =
+                    Self::compare_values(a.body.get(&spec.field), b.body.get(&spec.field));
+                match spec.direction {
+                    SortDirection::Asc => ordering,
+                    SortDirection::Desc => ordering.reverse(),
+                }
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| a.id.cmp(&b.id))
     }
 
     /// Compares two JSON values for sorting.
@@ -31,7 +43,7 @@ impl ResultSorter {
     /// Ordering rules:
     /// - null < bool < number < string
     /// - For same types, natural ordering
-    fn compare_values(
+    pub(crate) fn compare_values(
         a: Option<&serde_json::Value>,
         b: Option<&serde_json::Value>,
     ) -> std::cmp::Ordering {
@@ -92,7 +104,7 @@ mod tests {
     fn test_sort_ascending() {
         let mut docs = vec![make_doc("c", 30), make_doc("a", 20), make_doc("b", 25)];
 
-        ResultSorter::sort(&mut docs, &SortSpec::asc("age"));
+        ResultSorter::sort(&mut docs, &[SortSpec::asc("age")]);
 
         assert_eq!(docs[0].id, "a");
         assert_eq!(docs[1].id, "b");
@@ -103,7 +115,7 @@ mod tests {
     fn test_sort_descending() {
         let mut docs = vec![make_doc("c", 30), make_doc("a", 20), make_doc("b", 25)];
 
-        ResultSorter::sort(&mut docs, &SortSpec::desc("age"));
+        ResultSorter::sort(&mut docs, &[SortSpec::desc("age")]);
 
         assert_eq!(docs[0].id, "c");
         assert_eq!(docs[1].id, "b");
@@ -111,13 +123,12 @@ mod tests {
     }
 
     #[test]
-    fn test_sort_stable() {
-        // Same age, original order preserved
-        let mut docs = vec![make_doc("a", 25), make_doc("b", 25), make_doc("c", 25)];
+    fn test_sort_ties_broken_by_id() {
+        // Same age, ordered by _id regardless of input order
+        let mut docs = vec![make_doc("c", 25), make_doc("a", 25), make_doc("b", 25)];
 
-        ResultSorter::sort(&mut docs, &SortSpec::asc("age"));
+        ResultSorter::sort(&mut docs, &[SortSpec::asc("age")]);
 
-        // Order should be stable
         assert_eq!(docs[0].id, "a");
         assert_eq!(docs[1].id, "b");
         assert_eq!(docs[2].id, "c");
@@ -135,10 +146,35 @@ mod tests {
             make_doc_with_name("3", "bob"),
         ];
 
-        ResultSorter::sort(&mut docs, &SortSpec::asc("name"));
+        ResultSorter::sort(&mut docs, &[SortSpec::asc("name")]);
 
         assert_eq!(docs[0].id, "2"); // alice
         assert_eq!(docs[1].id, "3"); // bob
         assert_eq!(docs[2].id, "1"); // charlie
     }
+
+    #[test]
+    fn test_sort_secondary_key() {
+        fn make(id: &str, age: i64, name: &str) -> ResultDocument {
+            ResultDocument::new(
+                id,
+                "users",
+                "v1",
+                json!({"_id": id, "age": age, "name": name}),
+                0,
+            )
+        }
+
+        let mut docs = vec![
+            make("1", 30, "bob"),
+            make("2", 25, "alice"),
+            make("3", 30, "alice"),
+            make("4", 25, "carol"),
+        ];
+
+        ResultSorter::sort(&mut docs, &[SortSpec::desc("age"), SortSpec::asc("name")]);
+
+        let ids: Vec<_> = docs.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, vec!["3", "1", "2", "4"]);
+    }
 }
@@ -0,0 +1,281 @@
+//! External merge sort for result sets larger than memory
+//!
+//! Sorted results are buffered in memory up to a threshold. Past it, each
+//! full buffer is sorted and written to a run file as JSON lines under a
+//! private directory, and the runs are merged when the result is read.
+//!
+//! The merge always takes the smallest head, the earliest run on ties, so
+//! the output equals a stable in-memory sort of the same candidates. Run
+//! files are removed when the sorter is dropped.
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Lines, Write};
+use std::path::{Path, PathBuf};
+
+use uuid::Uuid;
+
+use crate::planner::SortSpec;
+
+use super::errors::{ExecutorError, ExecutorResult};
+use super::result::ResultDocument;
+use super::sorter::ResultSorter;
+
+/// Documents sorted in memory before spilling (default)
+pub const DEFAULT_SORT_SPILL_THRESHOLD: usize = 100_000;
+
+/// Where and when sorts spill to disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortSpillConfig {
+    /// Documents held in memory before a run is spilled
+    pub threshold: usize,
+    /// Directory holding run files (created on first spill)
+    pub dir: PathBuf,
+}
+
+impl SortSpillConfig {
+    /// Spill to `dir` past the default threshold
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            threshold: DEFAULT_SORT_SPILL_THRESHOLD,
+            dir: dir.into(),
+        }
+    }
+
+    /// Spill past `threshold` documents (at least 1)
+    pub fn with_threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold.max(1);
+        self
+    }
+}
+
+/// Sorts documents in memory, spilling sorted runs past the threshold
+///
+/// With no sort keys documents keep their candidate order and never spill.
+pub(super) struct SpillingSorter<'s> {
+    sort_specs: &'s [SortSpec],
+    config: Option<&'s SortSpillConfig>,
+    buffer: Vec<ResultDocument>,
+    run_dir: Option<PathBuf>,
+    runs: Vec<PathBuf>,
+    count: usize,
+}
+
+impl<'s> SpillingSorter<'s> {
+    /// Sorter by `sort_specs`; without `config` everything stays in memory
+    pub(super) fn new(sort_specs: &'s [SortSpec], config: Option<&'s SortSpillConfig>) -> Self {
+        Self {
+            sort_specs,
+            config,
+            buffer: Vec::new(),
+            run_dir: None,
+            runs: Vec::new(),
+            count: 0,
+        }
+    }
+
+    /// Documents pushed so far
+    pub(super) fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Add a document, spilling the buffer if it reaches the threshold
+    pub(super) fn push(&mut self, doc: ResultDocument) -> ExecutorResult<()> {
+        self.count += 1;
+        self.buffer.push(doc);
+        match self.config {
+            Some(config)
+                if !self.sort_specs.is_empty() && self.buffer.len() >= config.threshold =>
+            {
+                self.spill(config)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The first `limit` documents in sort order, and the runs spilled
+    pub(super) fn finish(mut self, limit: usize) -> ExecutorResult<(Vec<ResultDocument>, usize)> {
+        if !self.sort_specs.is_empty() {
+            ResultSorter::sort(&mut self.buffer, self.sort_specs);
+        }
+        if self.runs.is_empty() {
+            self.buffer.truncate(limit);
+            return Ok((std::mem::take(&mut self.buffer), 0));
+        }
+
+        // The in-memory remainder merges last: it holds the latest candidates
+        let mut heads = Vec::with_capacity(self.runs.len() + 1);
+        for path in &self.runs {
+            let mut run = RunReader::open(path)?;
+            heads.push((run.next_doc()?, Some(run)));
+        }
+        let mut remainder = std::mem::take(&mut self.buffer).into_iter();
+        heads.push((remainder.next(), None));
+
+        let mut merged = Vec::with_capacity(limit.min(self.count));
+        while merged.len() < limit {
+            let smallest = heads
+                .iter()
+                .enumerate()
+                .filter_map(|(i, (head, _))| head.as_ref().map(|doc| (i, doc)))
+                .reduce(
+                    |best, next| match ResultSorter::compare(next.1, best.1, self.sort_specs) {
+                        std::cmp::Ordering::Less => next,
+                        _ => best,
+                    },
+                )
+                .map(|(i, _)| i);
+            let Some(i) = smallest else {
+                break;
+            };
+
+            let (head, run) = &mut heads[i];
+            let next = match run {
+                Some(run) => run.next_doc()?,
+                None => remainder.next(),
+            };
+            if let Some(doc) = std::mem::replace(head, next) {
+                merged.push(doc);
+            }
+        }
+        Ok((merged, self.runs.len()))
+    }
+
+    /// Sort the buffer and write it as a new run
+    fn spill(&mut self, config: &SortSpillConfig) -> ExecutorResult<()> {
+        let dir = match &self.run_dir {
+            Some(dir) => dir.clone(),
+            None => {
+                let dir = config.dir.join(format!("sort-{}", Uuid::new_v4()));
+                fs::create_dir_all(&dir).map_err(|e| spill_failed(&dir, e))?;
+                self.run_dir = Some(dir.clone());
+                dir
+            }
+        };
+
+        ResultSorter::sort(&mut self.buffer, self.sort_specs);
+        let path = dir.join(format!("run-{:06}.jsonl", self.runs.len()));
+        let file = File::create(&path).map_err(|e| spill_failed(&path, e))?;
+        let mut writer = BufWriter::new(file);
+        for doc in self.buffer.drain(..) {
+            serde_json::to_writer(&mut writer, &doc).map_err(|e| spill_failed(&path, e.into()))?;
+            writer
+                .write_all(b"\n")
+                .map_err(|e| spill_failed(&path, e))?;
+        }
+        writer.flush().map_err(|e| spill_failed(&path, e))?;
+        self.runs.push(path);
+        Ok(())
+    }
+}
+
+impl Drop for SpillingSorter<'_> {
+    fn drop(&mut self) {
+        if let Some(dir) = &self.run_dir {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
+/// Sequential reader of one spilled run
+struct RunReader {
+    path: PathBuf,
+    lines: Lines<BufReader<File>>,
+}
+
+impl RunReader {
+    fn open(path: &Path) -> ExecutorResult<Self> {
+        let file = File::open(path).map_err(|e| spill_failed(path, e))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            lines: BufReader::new(file).lines(),
+        })
+    }
+
+    fn next_doc(&mut self) -> ExecutorResult<Option<ResultDocument>> {
+        let Some(line) = self.lines.next() else {
+            return Ok(None);
+        };
+        let line = line.map_err(|e| spill_failed(&self.path, e))?;
+        serde_json::from_str(&line)
+            .map(Some)
+            .map_err(|e| spill_failed(&self.path, e.into()))
+    }
+}
+
+fn spill_failed(path: &Path, e: std::io::Error) -> ExecutorError {
+    ExecutorError::execution_failed(format!("Sort spill {:?} failed: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn make_doc(id: &str, age: i64) -> ResultDocument {
+        ResultDocument::new(id, "users", "v1", json!({"_id": id, "age": age}), 0)
+    }
+
+    fn docs() -> Vec<ResultDocument> {
+        (0..25)
+            .map(|i| make_doc(&format!("user_{:02}", i), (i * 7) % 5))
+            .collect()
+    }
+
+    #[test]
+    fn test_spilled_sort_matches_in_memory_sort() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = SortSpillConfig::new(temp_dir.path()).with_threshold(4);
+        let specs = [SortSpec::desc("age")];
+
+        let mut expected = docs();
+        ResultSorter::sort(&mut expected, &specs);
+
+        let mut sorter = SpillingSorter::new(&specs, Some(&config));
+        for doc in docs() {
+            sorter.push(doc).unwrap();
+        }
+        assert_eq!(sorter.len(), 25);
+        let (sorted, runs) = sorter.finish(usize::MAX).unwrap();
+
+        assert_eq!(runs, 6);
+        let ids: Vec<&str> = sorted.iter().map(|d| d.id.as_str()).collect();
+        let expected: Vec<&str> = expected.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, expected);
+    }
+
+    #[test]
+    fn test_limit_and_cleanup() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = SortSpillConfig::new(temp_dir.path()).with_threshold(10);
+        let specs = [SortSpec::asc("age")];
+
+        let mut sorter = SpillingSorter::new(&specs, Some(&config));
+        for doc in docs() {
+            sorter.push(doc).unwrap();
+        }
+        let (sorted, runs) = sorter.finish(3).unwrap();
+
+        assert_eq!(runs, 2);
+        let ids: Vec<&str> = sorted.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, vec!["user_00", "user_05", "user_10"]);
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_below_threshold_stays_in_memory() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = SortSpillConfig::new(temp_dir.path()).with_threshold(100);
+        let specs = [SortSpec::asc("age")];
+
+        let mut sorter = SpillingSorter::new(&specs, Some(&config));
+        for doc in docs() {
+            sorter.push(doc).unwrap();
+        }
+        let (sorted, runs) = sorter.finish(usize::MAX).unwrap();
+
+        assert_eq!(runs, 0);
+        assert_eq!(sorted.len(), 25);
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,129 @@
+//! Streaming query execution
+//!
+//! `ResultStream` yields the documents of a plan one at a time, reading,
+//! validating and filtering candidates only as they are pulled. An unsorted
+//! plan never holds more than one document; a sorted plan buffers at most
+//! twice its limit while it looks for the best matches.
+//!
+//! A stream yields exactly the documents `execute` would return, in the
+//! same order.
+
+use std::time::Instant;
+
+use crate::planner::QueryPlan;
+
+use super::errors::ExecutorResult;
+use super::executor::{IndexLookup, QueryExecutor, StorageRead};
+use super::result::{ExecutionStats, ResultDocument};
+use super::sorter::ResultSorter;
+
+/// Lazily executed query results
+///
+/// Yields `Err` once on corruption, then ends.
+pub struct ResultStream<'e, 'a, I: IndexLookup, S: StorageRead> {
+    executor: &'e mut QueryExecutor<'a, I, S>,
+    plan: &'e QueryPlan,
+    offsets: std::vec::IntoIter<u64>,
+    /// Sorted matches, filled on the first pull of a sorted plan
+    sorted: Option<std::vec::IntoIter<ResultDocument>>,
+    remaining: usize,
+    stats: ExecutionStats,
+    started: Instant,
+    done: bool,
+}
+
+impl<'e, 'a, I: IndexLookup, S: StorageRead> ResultStream<'e, 'a, I, S> {
+    pub(super) fn new(executor: &'e mut QueryExecutor<'a, I, S>, plan: &'e QueryPlan) -> Self {
+        let started = Instant::now();
+        let offsets = executor.get_candidate_offsets(plan);
+        let stats = ExecutionStats {
+            index_candidates: offsets.len() as u64,
+            ..ExecutionStats::default()
+        };
+        Self {
+            executor,
+            plan,
+            offsets: offsets.into_iter(),
+            sorted: None,
+            remaining: plan.limit as usize,
+            stats,
+            started,
+            done: false,
+        }
+    }
+
+    /// Work done so far
+    pub fn stats(&self) -> ExecutionStats {
+        ExecutionStats {
+            elapsed_micros: self.started.elapsed().as_micros() as u64,
+            ..self.stats
+        }
+    }
+
+    /// Next match in candidate order
+    fn next_match(&mut self) -> ExecutorResult<Option<ResultDocument>> {
+        for offset in self.offsets.by_ref() {
+            self.stats.documents_scanned += 1;
+            if let Some(doc) = self
+                .executor
+                .read_candidate(self.plan, offset, &mut self.stats)?
+            {
+                return Ok(Some(doc));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Every match, sorted, keeping only the first `limit`
+    ///
+    /// The buffer is trimmed to `limit` whenever it reaches twice that, so
+    /// memory stays bounded by the limit rather than the candidates.
+    fn sort_matches(&mut self) -> ExecutorResult<Vec<ResultDocument>> {
+        let limit = self.plan.limit as usize;
+        let mut buffer = Vec::new();
+        while let Some(doc) = self.next_match()? {
+            buffer.push(doc);
+            if buffer.len() >= limit.saturating_mul(2).max(1) {
+                ResultSorter::sort(&mut buffer, &self.plan.sort);
+                buffer.truncate(limit);
+            }
+        }
+        ResultSorter::sort(&mut buffer, &self.plan.sort);
+        buffer.truncate(limit);
+        Ok(buffer)
+    }
+
+    fn pull(&mut self) -> ExecutorResult<Option<ResultDocument>> {
+        if self.plan.sort.is_empty() {
+            return self.next_match();
+        }
+        if self.sorted.is_none() {
+            self.sorted = Some(self.sort_matches()?.into_iter());
+        }
+        Ok(self.sorted.as_mut().and_then(Iterator::next))
+    }
+}
+
+impl<I: IndexLookup, S: StorageRead> Iterator for ResultStream<'_, '_, I, S> {
+    type Item = ExecutorResult<ResultDocument>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.remaining == 0 {
+            return None;
+        }
+        match self.pull() {
+            Ok(Some(doc)) => {
+                self.remaining -= 1;
+                Some(Ok(doc))
+            }
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
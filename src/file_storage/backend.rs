@@ -1,5 +1,7 @@
 //! # Storage Backend Trait
 
+use sha2::{Digest, Sha256};
+
 use super::errors::StorageResult;
 
 /// Backend trait for file storage
@@ -18,4 +20,16 @@ pub trait StorageBackend: Send + Sync + std::fmt::Debug {
 
     /// List files with prefix
     fn list(&self, prefix: &str) -> StorageResult<Vec<String>>;
+
+    /// Write the concatenation of `parts`, in order, to `path` in one
+    /// step and return its SHA-256 as hex; the parts are left in place
+    fn compose(&self, path: &str, parts: &[String]) -> StorageResult<String> {
+        let mut data = Vec::new();
+        for part in parts {
+            data.extend_from_slice(&self.read(part)?);
+        }
+        let checksum = format!("{:x}", Sha256::digest(&data));
+        self.write(path, &data)?;
+        Ok(checksum)
+    }
 }
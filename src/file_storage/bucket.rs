@@ -40,6 +40,14 @@ pub struct BucketConfig {
     /// Access policy
     #[serde(default)]
     pub policy: BucketPolicy,
+
+    /// Maximum total size of all objects in bytes (0 = unlimited)
+    #[serde(default)]
+    pub max_total_size: u64,
+
+    /// Maximum number of objects (0 = unlimited)
+    #[serde(default)]
+    pub max_objects: u64,
 }
 
 fn default_max_size() -> u64 {
@@ -52,10 +60,19 @@ impl Default for BucketConfig {
             allowed_mime_types: Vec::new(),
             max_file_size: default_max_size(),
             policy: BucketPolicy::Private,
+            max_total_size: 0,
+            max_objects: 0,
         }
     }
 }
 
+/// Objects held by a bucket
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BucketUsage {
+    pub object_count: u64,
+    pub total_size: u64,
+}
+
 /// A storage bucket
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Bucket {
@@ -109,6 +126,24 @@ impl Bucket {
             Ok(())
         }
     }
+
+    /// Check that `usage` is within the bucket's quotas
+    pub fn check_quota(&self, usage: &BucketUsage) -> StorageResult<()> {
+        let config = &self.config;
+        if config.max_total_size > 0 && usage.total_size > config.max_total_size {
+            return Err(StorageError::QuotaExceeded(format!(
+                "{} bytes exceeds the {} byte quota",
+                usage.total_size, config.max_total_size
+            )));
+        }
+        if config.max_objects > 0 && usage.object_count > config.max_objects {
+            return Err(StorageError::QuotaExceeded(format!(
+                "{} objects exceeds the {} object limit",
+                usage.object_count, config.max_objects
+            )));
+        }
+        Ok(())
+    }
 }
 
 /// Bucket registry
@@ -213,6 +248,29 @@ mod tests {
         assert!(bucket.check_size(2048).is_err());
     }
 
+    #[test]
+    fn test_quota_validation() {
+        let mut config = BucketConfig::default();
+        config.max_total_size = 100;
+        config.max_objects = 2;
+
+        let bucket = Bucket::new("quota".to_string(), None, config);
+        let usage = |object_count, total_size| BucketUsage {
+            object_count,
+            total_size,
+        };
+
+        assert!(bucket.check_quota(&usage(2, 100)).is_ok());
+        assert!(bucket.check_quota(&usage(3, 10)).is_err());
+        assert!(matches!(
+            bucket.check_quota(&usage(1, 101)),
+            Err(StorageError::QuotaExceeded(_))
+        ));
+        assert!(Bucket::new("free".into(), None, BucketConfig::default())
+            .check_quota(&usage(u64::MAX, u64::MAX))
+            .is_ok());
+    }
+
     #[test]
     fn test_registry() {
         let registry = BucketRegistry::new();
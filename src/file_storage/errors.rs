@@ -29,6 +29,9 @@ pub enum StorageError {
     #[error("File too large: {0} bytes (max: {1})")]
     FileTooLarge(u64, u64),
 
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+
     #[error("Invalid MIME type: {0}")]
     InvalidMimeType(String),
 
@@ -56,6 +59,20 @@ pub enum StorageError {
     #[error("I/O error: {0}")]
     IoError(String),
 
+    // Image transform errors
+    #[error("Unsupported image: {0}")]
+    UnsupportedImage(String),
+
+    #[error("Invalid transform: {0}")]
+    InvalidTransform(String),
+
+    // Upload session errors
+    #[error("Upload not found: {0}")]
+    UploadNotFound(String),
+
+    #[error("Invalid upload: {0}")]
+    InvalidUpload(String),
+
     #[error("Checksum mismatch")]
     ChecksumMismatch,
 
@@ -74,6 +91,7 @@ impl StorageError {
             StorageError::ObjectNotFound(_) => 404,
             StorageError::ObjectAlreadyExists(_) => 409,
             StorageError::FileTooLarge(_, _) => 413,
+            StorageError::QuotaExceeded(_) => 413,
             StorageError::InvalidMimeType(_) => 415,
             StorageError::InvalidPath(_) => 400,
             StorageError::Unauthorized => 401,
@@ -82,6 +100,10 @@ impl StorageError {
             StorageError::InvalidSignature => 403,
             StorageError::StorageFull => 507,
             StorageError::IoError(_) => 500,
+            StorageError::UnsupportedImage(_) => 415,
+            StorageError::InvalidTransform(_) => 400,
+            StorageError::UploadNotFound(_) => 404,
+            StorageError::InvalidUpload(_) => 400,
             StorageError::ChecksumMismatch => 500,
             StorageError::Internal(_) => 500,
         }
@@ -5,13 +5,14 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 use uuid::Uuid;
 
 use super::backend::StorageBackend;
-use super::bucket::{Bucket, BucketRegistry};
+use super::bucket::{Bucket, BucketRegistry, BucketUsage};
 use super::errors::{StorageError, StorageResult};
 use super::permissions::StoragePermissions;
+use super::upload::{UploadPart, UploadSession, UploadSessionStore, MAX_PARTS, MAX_PART_SIZE};
 use crate::auth::rls::RlsContext;
 
 /// A storage object (file metadata)
@@ -67,7 +68,9 @@ pub struct FileService<B: StorageBackend> {
     backend: B,
     buckets: BucketRegistry,
     objects: RwLock<HashMap<String, StorageObject>>, // key: bucket_id/path
+    usage: RwLock<HashMap<Uuid, BucketUsage>>,
     permissions: StoragePermissions,
+    uploads: Arc<UploadSessionStore>,
 }
 
 impl<B: StorageBackend> FileService<B> {
@@ -77,10 +80,18 @@ impl<B: StorageBackend> FileService<B> {
             backend,
             buckets: BucketRegistry::new(),
             objects: RwLock::new(HashMap::new()),
+            usage: RwLock::new(HashMap::new()),
             permissions: StoragePermissions::new(),
+            uploads: Arc::new(UploadSessionStore::new()),
         }
     }
 
+    /// Keep resumable upload sessions in `uploads`
+    pub fn with_upload_sessions(mut self, uploads: Arc<UploadSessionStore>) -> Self {
+        self.uploads = uploads;
+        self
+    }
+
     /// Get bucket registry
     pub fn buckets(&self) -> &BucketRegistry {
         &self.buckets
@@ -90,6 +101,53 @@ impl<B: StorageBackend> FileService<B> {
         format!("{}/{}", bucket_id, path)
     }
 
+    /// Usage of a bucket, if `context` may read it
+    pub fn usage(&self, bucket_name: &str, context: &RlsContext) -> StorageResult<BucketUsage> {
+        let bucket = self.buckets.get(bucket_name)?;
+        self.permissions.check_read(&bucket, context)?;
+        Ok(self.bucket_usage(&bucket.id))
+    }
+
+    /// Usage of bucket `bucket_id`
+    pub fn bucket_usage(&self, bucket_id: &Uuid) -> BucketUsage {
+        let usage = self.usage.read().unwrap_or_else(|e| e.into_inner());
+        usage.get(bucket_id).copied().unwrap_or_default()
+    }
+
+    /// Count a `size` byte write to `path` against the bucket's usage,
+    /// replacing any object there. Fails if usage would grow past a quota.
+    /// Returns the replaced object's size, for `release` if the write fails.
+    fn reserve(&self, bucket: &Bucket, path: &str, size: u64) -> StorageResult<Option<u64>> {
+        let replaced = {
+            let objects = self.objects.read().unwrap_or_else(|e| e.into_inner());
+            objects
+                .get(&Self::object_key(&bucket.id, path))
+                .map(|o| o.size)
+        };
+        let mut usage = self.usage.write().unwrap_or_else(|e| e.into_inner());
+        let current = usage.entry(bucket.id).or_default();
+        let next = adjust_usage(*current, replaced, Some(size));
+        if next.total_size > current.total_size || next.object_count > current.object_count {
+            bucket.check_quota(&next)?;
+        }
+        *current = next;
+        Ok(replaced)
+    }
+
+    /// Undo a `reserve` whose write failed
+    fn release(&self, bucket_id: &Uuid, replaced: Option<u64>, size: u64) {
+        let mut usage = self.usage.write().unwrap_or_else(|e| e.into_inner());
+        let current = usage.entry(*bucket_id).or_default();
+        *current = adjust_usage(*current, Some(size), replaced);
+    }
+
+    /// Bucket `bucket_name`, if `context` may write to it
+    pub fn check_write(&self, bucket_name: &str, context: &RlsContext) -> StorageResult<Bucket> {
+        let bucket = self.buckets.get(bucket_name)?;
+        self.permissions.check_write(&bucket, context)?;
+        Ok(bucket)
+    }
+
     /// Upload a file
     pub fn upload(
         &self,
@@ -111,8 +169,12 @@ impl<B: StorageBackend> FileService<B> {
         }
 
         // Write to backend
+        let replaced = self.reserve(&bucket, path, data.len() as u64)?;
         let storage_path = format!("{}/{}", bucket.id, path);
-        self.backend.write(&storage_path, data)?;
+        if let Err(e) = self.backend.write(&storage_path, data) {
+            self.release(&bucket.id, replaced, data.len() as u64);
+            return Err(e);
+        }
 
         // Create metadata
         let mut object = StorageObject::new(
@@ -165,6 +227,26 @@ impl<B: StorageBackend> FileService<B> {
         Ok((object, data))
     }
 
+    /// Metadata of a file
+    pub fn get_object(
+        &self,
+        bucket_name: &str,
+        path: &str,
+        context: &RlsContext,
+    ) -> StorageResult<StorageObject> {
+        let bucket = self.buckets.get(bucket_name)?;
+        self.permissions.check_read(&bucket, context)?;
+
+        let objects = self
+            .objects
+            .read()
+            .map_err(|_| StorageError::Internal("Lock poisoned".into()))?;
+        objects
+            .get(&Self::object_key(&bucket.id, path))
+            .cloned()
+            .ok_or_else(|| StorageError::ObjectNotFound(path.to_string()))
+    }
+
     /// Delete a file
     pub fn delete(&self, bucket_name: &str, path: &str, context: &RlsContext) -> StorageResult<()> {
         let bucket = self.buckets.get(bucket_name)?;
@@ -185,6 +267,8 @@ impl<B: StorageBackend> FileService<B> {
                 .ok_or_else(|| StorageError::ObjectNotFound(path.to_string()))?
         };
 
+        self.release(&bucket.id, None, object.size);
+
         // Delete from backend
         let storage_path = format!("{}/{}", bucket.id, &object.path);
         self.backend.delete(&storage_path)?;
@@ -192,6 +276,230 @@ impl<B: StorageBackend> FileService<B> {
         Ok(())
     }
 
+    /// Start a resumable upload of `path`; `size`, if given, is the size
+    /// the completed object must have
+    pub fn initiate_upload(
+        &self,
+        bucket_name: &str,
+        path: &str,
+        content_type: &str,
+        size: Option<u64>,
+        context: &RlsContext,
+    ) -> StorageResult<UploadSession> {
+        let bucket = self.buckets.get(bucket_name)?;
+        self.permissions.check_write(&bucket, context)?;
+
+        if path.is_empty() || path.starts_with('/') {
+            return Err(StorageError::InvalidPath(path.to_string()));
+        }
+        if let Some(size) = size {
+            bucket.check_size(size)?;
+        }
+        if !bucket.is_mime_allowed(content_type) {
+            return Err(StorageError::InvalidMimeType(content_type.to_string()));
+        }
+
+        self.expire_uploads();
+        let session = UploadSession::new(
+            bucket.id,
+            path.to_string(),
+            content_type.to_string(),
+            context.user_id,
+            size,
+        );
+        self.uploads.put(session.clone())?;
+        Ok(session)
+    }
+
+    /// Store part `number` of upload `id`, replacing any earlier copy;
+    /// `checksum`, if given, is the part's SHA-256 as hex
+    pub fn append_part(
+        &self,
+        bucket_name: &str,
+        id: &Uuid,
+        number: u32,
+        data: &[u8],
+        checksum: Option<&str>,
+        context: &RlsContext,
+    ) -> StorageResult<UploadPart> {
+        let (bucket, session) = self.upload_session(bucket_name, id, context)?;
+
+        if number == 0 || number > MAX_PARTS {
+            return Err(StorageError::InvalidUpload(format!(
+                "part number must be between 1 and {}",
+                MAX_PARTS
+            )));
+        }
+        if data.is_empty() || data.len() > MAX_PART_SIZE {
+            return Err(StorageError::InvalidUpload(format!(
+                "part size must be between 1 and {} bytes",
+                MAX_PART_SIZE
+            )));
+        }
+        let part = UploadPart {
+            size: data.len() as u64,
+            checksum: StorageObject::calculate_checksum(data),
+        };
+        if checksum.is_some_and(|c| !c.eq_ignore_ascii_case(&part.checksum)) {
+            return Err(StorageError::InvalidUpload(format!(
+                "part {} checksum mismatch",
+                number
+            )));
+        }
+
+        let replaced = session.parts.get(&number).map_or(0, |p| p.size);
+        let total = session.uploaded_bytes() - replaced + part.size;
+        bucket.check_size(total)?;
+        if let Some(size) = session.declared_size.filter(|size| total > *size) {
+            return Err(StorageError::FileTooLarge(total, size));
+        }
+
+        self.backend.write(&session.part_path(number), data)?;
+        self.uploads.put_part(id, number, part.clone())?;
+        Ok(part)
+    }
+
+    /// Upload `id` and the parts received so far
+    pub fn get_upload(
+        &self,
+        bucket_name: &str,
+        id: &Uuid,
+        context: &RlsContext,
+    ) -> StorageResult<UploadSession> {
+        self.upload_session(bucket_name, id, context)
+            .map(|(_, session)| session)
+    }
+
+    /// Assemble the parts of upload `id`, numbered 1 to N without gaps,
+    /// into its object; `checksum`, if given, is the object's SHA-256 as
+    /// hex. The object is written in one step, and its metadata recorded
+    /// only once it is.
+    pub fn complete_upload(
+        &self,
+        bucket_name: &str,
+        id: &Uuid,
+        checksum: Option<&str>,
+        context: &RlsContext,
+    ) -> StorageResult<StorageObject> {
+        let (bucket, session) = self.upload_session(bucket_name, id, context)?;
+
+        if session.parts.is_empty() {
+            return Err(StorageError::InvalidUpload("no parts uploaded".into()));
+        }
+        if let Some(missing) =
+            (1..=session.parts.len() as u32).find(|n| !session.parts.contains_key(n))
+        {
+            return Err(StorageError::InvalidUpload(format!(
+                "part {} is missing",
+                missing
+            )));
+        }
+        let size = session.uploaded_bytes();
+        if session
+            .declared_size
+            .is_some_and(|declared| declared != size)
+        {
+            return Err(StorageError::InvalidUpload(format!(
+                "uploaded {} bytes of a declared {}",
+                size,
+                session.declared_size.unwrap_or_default()
+            )));
+        }
+        bucket.check_size(size)?;
+
+        let parts: Vec<String> = session
+            .parts
+            .keys()
+            .map(|n| session.part_path(*n))
+            .collect();
+        let storage_path = format!("{}/{}", bucket.id, session.path);
+        let replaced = self.reserve(&bucket, &session.path, size)?;
+        let compose = || match checksum {
+            // Assemble beside the parts first, so a mismatch leaves any
+            // existing object untouched
+            Some(expected) => {
+                let assembled = session.part_path(0);
+                let actual = self.backend.compose(&assembled, &parts)?;
+                if !expected.eq_ignore_ascii_case(&actual) {
+                    let _ = self.backend.delete(&assembled);
+                    return Err(StorageError::InvalidUpload(
+                        "object checksum mismatch".into(),
+                    ));
+                }
+                self.backend.compose(&storage_path, &[assembled])
+            }
+            None => self.backend.compose(&storage_path, &parts),
+        };
+        let checksum = compose().inspect_err(|_| self.release(&bucket.id, replaced, size))?;
+
+        let mut object = StorageObject::new(
+            bucket.id,
+            session.path.clone(),
+            size,
+            session.content_type.clone(),
+            session.owner_id,
+        );
+        object.checksum = checksum;
+        let key = Self::object_key(&bucket.id, &session.path);
+        if let Ok(mut objects) = self.objects.write() {
+            objects.insert(key, object.clone());
+        }
+
+        self.uploads.remove(id)?;
+        self.discard_parts(&session);
+        Ok(object)
+    }
+
+    /// Abandon upload `id` and discard its parts
+    pub fn abort_upload(
+        &self,
+        bucket_name: &str,
+        id: &Uuid,
+        context: &RlsContext,
+    ) -> StorageResult<()> {
+        let (_, session) = self.upload_session(bucket_name, id, context)?;
+        self.uploads.remove(id)?;
+        self.discard_parts(&session);
+        Ok(())
+    }
+
+    /// Bucket and session of upload `id`, if `context` may continue it
+    fn upload_session(
+        &self,
+        bucket_name: &str,
+        id: &Uuid,
+        context: &RlsContext,
+    ) -> StorageResult<(Bucket, UploadSession)> {
+        let bucket = self.buckets.get(bucket_name)?;
+        self.permissions.check_write(&bucket, context)?;
+
+        let session = self.uploads.get(id)?;
+        if session.bucket_id != bucket.id || session.expires_at <= Utc::now() {
+            return Err(StorageError::UploadNotFound(id.to_string()));
+        }
+        if !context.can_bypass_rls() && session.owner_id != context.user_id {
+            return Err(StorageError::Forbidden);
+        }
+        Ok((bucket, session))
+    }
+
+    /// Drop expired upload sessions and their parts
+    fn expire_uploads(&self) {
+        for session in self.uploads.expired(Utc::now()) {
+            if self.uploads.remove(&session.id).is_ok() {
+                self.discard_parts(&session);
+            }
+        }
+    }
+
+    /// Delete staged parts of `session`; parts left behind are unreachable,
+    /// so failures are ignored
+    fn discard_parts(&self, session: &UploadSession) {
+        for number in session.parts.keys().copied().chain([0]) {
+            let _ = self.backend.delete(&session.part_path(number));
+        }
+    }
+
     /// List objects in a bucket
     pub fn list(
         &self,
@@ -221,6 +529,20 @@ impl<B: StorageBackend> FileService<B> {
     }
 }
 
+/// `usage` with an object of size `removed` taken out and one of size
+/// `added` put in
+fn adjust_usage(mut usage: BucketUsage, removed: Option<u64>, added: Option<u64>) -> BucketUsage {
+    if let Some(size) = removed {
+        usage.object_count = usage.object_count.saturating_sub(1);
+        usage.total_size = usage.total_size.saturating_sub(size);
+    }
+    if let Some(size) = added {
+        usage.object_count += 1;
+        usage.total_size += size;
+    }
+    usage
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -285,6 +607,165 @@ mod tests {
         assert!(service.download("test", "file.txt", &context).is_err());
     }
 
+    #[test]
+    fn test_resumable_upload() {
+        let temp_dir = TempDir::new().unwrap();
+        let context = RlsContext::authenticated(Uuid::new_v4());
+        let open = || {
+            FileService::new(LocalBackend::new(temp_dir.path().to_path_buf()))
+                .with_upload_sessions(Arc::new(UploadSessionStore::open(temp_dir.path()).unwrap()))
+        };
+        let service = open();
+        service
+            .buckets()
+            .create("test".to_string(), None, public_bucket_config())
+            .unwrap();
+
+        let session = service
+            .initiate_upload(
+                "test",
+                "big.bin",
+                "application/octet-stream",
+                Some(6),
+                &context,
+            )
+            .unwrap();
+        service
+            .append_part("test", &session.id, 1, b"abc", None, &context)
+            .unwrap();
+        assert!(matches!(
+            service.append_part("test", &session.id, 3, b"def", Some("00"), &context),
+            Err(StorageError::InvalidUpload(_))
+        ));
+        assert!(matches!(
+            service.append_part("test", &session.id, 2, b"defg", None, &context),
+            Err(StorageError::FileTooLarge(7, 6))
+        ));
+        let other = RlsContext::authenticated(Uuid::new_v4());
+        assert!(matches!(
+            service.get_upload("test", &session.id, &other),
+            Err(StorageError::Forbidden)
+        ));
+
+        // The session and its parts outlive the service
+        let service = open();
+        service
+            .buckets()
+            .create("test".to_string(), None, public_bucket_config())
+            .unwrap();
+        assert!(matches!(
+            service.get_upload("test", &session.id, &context),
+            Err(StorageError::UploadNotFound(_))
+        ));
+        // Buckets are kept in memory: point the session at the new one
+        let bucket = service.buckets().get("test").unwrap();
+        let mut moved = service.uploads.get(&session.id).unwrap();
+        moved.bucket_id = bucket.id;
+        service.uploads.put(moved).unwrap();
+
+        service
+            .append_part("test", &session.id, 3, b"f", None, &context)
+            .unwrap();
+        assert!(matches!(
+            service.complete_upload("test", &session.id, None, &context),
+            Err(StorageError::InvalidUpload(_))
+        ));
+        service
+            .append_part("test", &session.id, 2, b"de", None, &context)
+            .unwrap();
+        let object = service
+            .complete_upload("test", &session.id, None, &context)
+            .unwrap();
+        assert_eq!(
+            object.checksum,
+            StorageObject::calculate_checksum(b"abcdef")
+        );
+        let (_, data) = service.download("test", "big.bin", &context).unwrap();
+        assert_eq!(data, b"abcdef");
+        assert!(service.get_upload("test", &session.id, &context).is_err());
+        assert!(!temp_dir.path().join(session.part_path(1)).exists());
+    }
+
+    #[test]
+    fn test_abort_upload() {
+        let (service, temp_dir) = create_test_service();
+        let context = RlsContext::authenticated(Uuid::new_v4());
+        service
+            .buckets()
+            .create("test".to_string(), None, public_bucket_config())
+            .unwrap();
+
+        let session = service
+            .initiate_upload("test", "big.bin", "text/plain", None, &context)
+            .unwrap();
+        service
+            .append_part("test", &session.id, 1, b"abc", None, &context)
+            .unwrap();
+        service.abort_upload("test", &session.id, &context).unwrap();
+
+        assert!(!temp_dir.path().join(session.part_path(1)).exists());
+        assert!(service.download("test", "big.bin", &context).is_err());
+        assert!(matches!(
+            service.complete_upload("test", &session.id, None, &context),
+            Err(StorageError::UploadNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_bucket_quota() {
+        let (service, _temp) = create_test_service();
+        let context = RlsContext::authenticated(Uuid::new_v4());
+        let mut config = public_bucket_config();
+        config.max_total_size = 10;
+        config.max_objects = 2;
+        let bucket = service
+            .buckets()
+            .create("test".to_string(), None, config)
+            .unwrap();
+
+        service
+            .upload("test", "a.txt", b"12345", "text/plain", &context)
+            .unwrap();
+        service
+            .upload("test", "b.txt", b"1234", "text/plain", &context)
+            .unwrap();
+        assert!(matches!(
+            service.upload("test", "c.txt", b"1", "text/plain", &context),
+            Err(StorageError::QuotaExceeded(_))
+        ));
+        assert!(matches!(
+            service.upload("test", "a.txt", b"1234567", "text/plain", &context),
+            Err(StorageError::QuotaExceeded(_))
+        ));
+        // Replacing an object counts only the difference
+        service
+            .upload("test", "a.txt", b"123456", "text/plain", &context)
+            .unwrap();
+        assert_eq!(
+            service.usage("test", &context).unwrap(),
+            BucketUsage {
+                object_count: 2,
+                total_size: 10
+            }
+        );
+
+        service.delete("test", "b.txt", &context).unwrap();
+        let session = service
+            .initiate_upload("test", "c.txt", "text/plain", None, &context)
+            .unwrap();
+        service
+            .append_part("test", &session.id, 1, b"12345", None, &context)
+            .unwrap();
+        assert!(matches!(
+            service.complete_upload("test", &session.id, None, &context),
+            Err(StorageError::QuotaExceeded(_))
+        ));
+        service
+            .upload("test", "c.txt", b"1234", "text/plain", &context)
+            .unwrap();
+        assert_eq!(service.bucket_usage(&bucket.id).total_size, 10);
+    }
+
     #[test]
     fn test_checksum() {
         let checksum = StorageObject::calculate_checksum(b"test");
@@ -0,0 +1,756 @@
+//! # Image Codecs
+//!
+//! Just enough decoding and encoding for download transforms: 8-bit PNG
+//! (any color type, not interlaced) and uncompressed 24/32-bit BMP, read
+//! into an RGBA raster that can be cropped and resized. PNGs are written
+//! with stored (uncompressed) deflate blocks; BMPs as 24-bit.
+
+use super::errors::{StorageError, StorageResult};
+use serde::{Deserialize, Serialize};
+
+/// Largest width or height decoded or produced
+pub const MAX_DIMENSION: u32 = 8192;
+
+/// Most pixels decoded or produced
+pub const MAX_PIXELS: u64 = 25_000_000;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// An encoded image format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Png,
+    Bmp,
+}
+
+impl ImageFormat {
+    /// Parse a format name
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "png" => Some(ImageFormat::Png),
+            "bmp" => Some(ImageFormat::Bmp),
+            _ => None,
+        }
+    }
+
+    /// Format of encoded `data`, from its signature
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(PNG_SIGNATURE) {
+            Some(ImageFormat::Png)
+        } else if data.starts_with(b"BM") {
+            Some(ImageFormat::Bmp)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Bmp => "bmp",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Bmp => "image/bmp",
+        }
+    }
+}
+
+/// An RGBA image, 8 bits per channel, rows top to bottom
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Image of the given size and pixels, which must be RGBA
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> StorageResult<Self> {
+        check_dimensions(width, height)?;
+        if pixels.len() as u64 != width as u64 * height as u64 * 4 {
+            return Err(StorageError::Internal("pixel buffer size mismatch".into()));
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Decode a PNG or BMP image, returning it and its format
+    pub fn decode(data: &[u8]) -> StorageResult<(Self, ImageFormat)> {
+        let format = ImageFormat::detect(data)
+            .ok_or_else(|| StorageError::UnsupportedImage("unrecognized image format".into()))?;
+        let image = match format {
+            ImageFormat::Png => decode_png(data)?,
+            ImageFormat::Bmp => decode_bmp(data)?,
+        };
+        Ok((image, format))
+    }
+
+    /// Encode as `format`
+    pub fn encode(&self, format: ImageFormat) -> Vec<u8> {
+        match format {
+            ImageFormat::Png => encode_png(self),
+            ImageFormat::Bmp => encode_bmp(self),
+        }
+    }
+
+    /// The `width` x `height` region with top left corner at `x`, `y`
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> StorageResult<Self> {
+        if width == 0
+            || height == 0
+            || x as u64 + width as u64 > self.width as u64
+            || y as u64 + height as u64 > self.height as u64
+        {
+            return Err(StorageError::InvalidTransform(format!(
+                "crop {}x{} at {},{} exceeds the {}x{} image",
+                width, height, x, y, self.width, self.height
+            )));
+        }
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+        let stride = self.width as usize * 4;
+        for row in y..y + height {
+            let start = row as usize * stride + x as usize * 4;
+            pixels.extend_from_slice(&self.pixels[start..start + width as usize * 4]);
+        }
+        Image::new(width, height, pixels)
+    }
+
+    /// Scale to `width` x `height` by bilinear interpolation
+    pub fn resize(&self, width: u32, height: u32) -> StorageResult<Self> {
+        check_dimensions(width, height)?;
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+        let scale_x = self.width as f64 / width as f64;
+        let scale_y = self.height as f64 / height as f64;
+        for y in 0..height {
+            let (y0, y1, fy) = sample(y, scale_y, self.height);
+            for x in 0..width {
+                let (x0, x1, fx) = sample(x, scale_x, self.width);
+                for channel in 0..4 {
+                    let at = |px: u32, py: u32| {
+                        self.pixels[(py as usize * self.width as usize + px as usize) * 4 + channel]
+                            as f64
+                    };
+                    let top = at(x0, y0) * (1.0 - fx) + at(x1, y0) * fx;
+                    let bottom = at(x0, y1) * (1.0 - fx) + at(x1, y1) * fx;
+                    pixels.push((top * (1.0 - fy) + bottom * fy).round() as u8);
+                }
+            }
+        }
+        Image::new(width, height, pixels)
+    }
+}
+
+/// Source pixels either side of output pixel `i`'s center, and the weight
+/// of the second
+fn sample(i: u32, scale: f64, len: u32) -> (u32, u32, f64) {
+    let center = ((i as f64 + 0.5) * scale - 0.5).max(0.0);
+    let low = (center.floor() as u32).min(len - 1);
+    let high = (low + 1).min(len - 1);
+    (low, high, center - low as f64)
+}
+
+fn check_dimensions(width: u32, height: u32) -> StorageResult<()> {
+    if width == 0
+        || height == 0
+        || width > MAX_DIMENSION
+        || height > MAX_DIMENSION
+        || width as u64 * height as u64 > MAX_PIXELS
+    {
+        return Err(StorageError::UnsupportedImage(format!(
+            "{}x{} is outside the supported size",
+            width, height
+        )));
+    }
+    Ok(())
+}
+
+fn malformed(what: &str) -> StorageError {
+    StorageError::UnsupportedImage(format!("malformed {}", what))
+}
+
+fn be_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn le_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn le_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+// ==================
+// PNG
+// ==================
+
+fn decode_png(data: &[u8]) -> StorageResult<Image> {
+    let mut at = PNG_SIGNATURE.len();
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut alpha: &[u8] = &[];
+    let mut compressed = Vec::new();
+    loop {
+        let len = be_u32(data, at).ok_or_else(|| malformed("PNG chunk"))? as usize;
+        let kind = data
+            .get(at + 4..at + 8)
+            .ok_or_else(|| malformed("PNG chunk"))?;
+        let body = data
+            .get(at + 8..at + 8 + len)
+            .ok_or_else(|| malformed("PNG chunk"))?;
+        let crc = be_u32(data, at + 8 + len).ok_or_else(|| malformed("PNG chunk"))?;
+        if crc32fast::hash(&data[at + 4..at + 8 + len]) != crc {
+            return Err(malformed("PNG chunk checksum"));
+        }
+        at += 12 + len;
+        match kind {
+            b"IHDR" if body.len() == 13 => header = Some(body),
+            b"PLTE" => palette = body,
+            b"tRNS" => alpha = body,
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+    }
+
+    let header = header.ok_or_else(|| malformed("PNG header"))?;
+    let width = be_u32(header, 0).unwrap_or_default();
+    let height = be_u32(header, 4).unwrap_or_default();
+    let (depth, color, interlace) = (header[8], header[9], header[12]);
+    check_dimensions(width, height)?;
+    if depth != 8 || interlace != 0 {
+        return Err(StorageError::UnsupportedImage(
+            "only 8-bit, non-interlaced PNGs are supported".into(),
+        ));
+    }
+    let channels = match color {
+        0 | 3 => 1,
+        4 => 2,
+        2 => 3,
+        6 => 4,
+        _ => return Err(malformed("PNG header")),
+    };
+
+    let stride = width as usize * channels;
+    let expected = (stride + 1) * height as usize;
+    let mut raw = zlib_decompress(&compressed, expected)?;
+    if raw.len() != expected {
+        return Err(malformed("PNG image data"));
+    }
+    unfilter(&mut raw, stride, channels)?;
+
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+    for row in raw.chunks_exact(stride + 1) {
+        for px in row[1..].chunks_exact(channels) {
+            match color {
+                0 => pixels.extend_from_slice(&[px[0], px[0], px[0], 255]),
+                4 => pixels.extend_from_slice(&[px[0], px[0], px[0], px[1]]),
+                2 => pixels.extend_from_slice(&[px[0], px[1], px[2], 255]),
+                3 => {
+                    let i = px[0] as usize;
+                    let rgb = palette
+                        .get(i * 3..i * 3 + 3)
+                        .ok_or_else(|| malformed("PNG palette"))?;
+                    pixels.extend_from_slice(rgb);
+                    pixels.push(alpha.get(i).copied().unwrap_or(255));
+                }
+                _ => pixels.extend_from_slice(px),
+            }
+        }
+    }
+    Image::new(width, height, pixels)
+}
+
+/// Undo per-row PNG filters in place; each row starts with its filter type
+fn unfilter(raw: &mut [u8], stride: usize, bpp: usize) -> StorageResult<()> {
+    let row_len = stride + 1;
+    for row in 0..raw.len() / row_len {
+        let start = row * row_len;
+        let filter = raw[start];
+        for i in 1..row_len {
+            let left = if i > bpp { raw[start + i - bpp] } else { 0 };
+            let up = if row > 0 { raw[start - row_len + i] } else { 0 };
+            let up_left = if row > 0 && i > bpp {
+                raw[start - row_len + i - bpp]
+            } else {
+                0
+            };
+            let predictor = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(malformed("PNG filter")),
+            };
+            raw[start + i] = raw[start + i].wrapping_add(predictor);
+        }
+    }
+    Ok(())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn encode_png(image: &Image) -> Vec<u8> {
+    let stride = image.width as usize * 4;
+    let mut raw = Vec::with_capacity((stride + 1) * image.height as usize);
+    for row in image.pixels.chunks_exact(stride) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&image.width.to_be_bytes());
+    header.extend_from_slice(&image.height.to_be_bytes());
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut out = PNG_SIGNATURE.to_vec();
+    png_chunk(&mut out, b"IHDR", &header);
+    png_chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+    png_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], body: &[u8]) {
+    out.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(body);
+    let crc = crc32fast::hash(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+// ==================
+// BMP
+// ==================
+
+fn decode_bmp(data: &[u8]) -> StorageResult<Image> {
+    let offset = le_u32(data, 10).ok_or_else(|| malformed("BMP header"))? as usize;
+    let width = le_u32(data, 18).ok_or_else(|| malformed("BMP header"))? as i32;
+    let height = le_u32(data, 22).ok_or_else(|| malformed("BMP header"))? as i32;
+    let bpp = le_u16(data, 28).ok_or_else(|| malformed("BMP header"))?;
+    let compression = le_u32(data, 30).ok_or_else(|| malformed("BMP header"))?;
+    if compression != 0 || !(bpp == 24 || bpp == 32) {
+        return Err(StorageError::UnsupportedImage(
+            "only uncompressed 24/32-bit BMPs are supported".into(),
+        ));
+    }
+    if width <= 0 || height == 0 {
+        return Err(malformed("BMP header"));
+    }
+    let (width, top_down) = (width as u32, height < 0);
+    let height = height.unsigned_abs();
+    check_dimensions(width, height)?;
+
+    let bytes_per_px = bpp as usize / 8;
+    let stride = (width as usize * bytes_per_px).div_ceil(4) * 4;
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+    for y in 0..height as usize {
+        let row = if top_down { y } else { height as usize - 1 - y };
+        let start = offset + row * stride;
+        let row = data
+            .get(start..start + width as usize * bytes_per_px)
+            .ok_or_else(|| malformed("BMP pixel data"))?;
+        for px in row.chunks_exact(bytes_per_px) {
+            // The fourth byte of BI_RGB pixels is unused, not alpha
+            pixels.extend_from_slice(&[px[2], px[1], px[0], 255]);
+        }
+    }
+    Image::new(width, height, pixels)
+}
+
+fn encode_bmp(image: &Image) -> Vec<u8> {
+    let stride = (image.width as usize * 3).div_ceil(4) * 4;
+    let size = 54 + stride * image.height as usize;
+    let mut out = Vec::with_capacity(size);
+    out.extend_from_slice(b"BM");
+    out.extend_from_slice(&(size as u32).to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&54u32.to_le_bytes());
+    out.extend_from_slice(&40u32.to_le_bytes());
+    out.extend_from_slice(&image.width.to_le_bytes());
+    out.extend_from_slice(&image.height.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&24u16.to_le_bytes());
+    out.extend_from_slice(&[0; 24]);
+    for row in image.pixels.chunks_exact(image.width as usize * 4).rev() {
+        for px in row.chunks_exact(4) {
+            out.extend_from_slice(&[px[2], px[1], px[0]]);
+        }
+        out.resize(out.len() + stride - image.width as usize * 3, 0);
+    }
+    out
+}
+
+// ==================
+// Deflate
+// ==================
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// Order code length code lengths are sent in
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Canonical Huffman code: symbol count per code length, and symbols in
+/// code order
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Self { counts, symbols }
+    }
+}
+
+/// Least significant bit first reader over a deflate stream
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn bits(&mut self, n: u32) -> StorageResult<u32> {
+        while self.count < n {
+            let byte = *self
+                .data
+                .get(self.pos)
+                .ok_or_else(|| malformed("deflate stream"))?;
+            self.pos += 1;
+            self.buffer |= (byte as u32) << self.count;
+            self.count += 8;
+        }
+        let value = self.buffer & ((1u32 << n) - 1);
+        self.buffer = self.buffer.checked_shr(n).unwrap_or(0);
+        self.count -= n;
+        Ok(value)
+    }
+
+    fn symbol(&mut self, code: &Huffman) -> StorageResult<u16> {
+        let (mut value, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            value |= self.bits(1)? as i32;
+            let count = code.counts[len] as i32;
+            if value - first < count {
+                return Ok(code.symbols[(index + value - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            value <<= 1;
+        }
+        Err(malformed("deflate code"))
+    }
+}
+
+/// Inflate a zlib stream, producing at most `limit` bytes
+fn zlib_decompress(data: &[u8], limit: usize) -> StorageResult<Vec<u8>> {
+    if data.len() < 6
+        || data[0] & 0x0f != 8
+        || data[1] & 0x20 != 0
+        || !(data[0] as u16 * 256 + data[1] as u16).is_multiple_of(31)
+    {
+        return Err(malformed("zlib header"));
+    }
+    let mut reader = BitReader {
+        data: &data[2..],
+        pos: 0,
+        buffer: 0,
+        count: 0,
+    };
+    let mut out = Vec::with_capacity(limit);
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.buffer = 0;
+                reader.count = 0;
+                let len = reader.bits(16)? as usize;
+                if reader.bits(16)? as usize != !len & 0xffff {
+                    return Err(malformed("deflate stored block"));
+                }
+                let block = reader
+                    .data
+                    .get(reader.pos..reader.pos + len)
+                    .ok_or_else(|| malformed("deflate stored block"))?;
+                out.extend_from_slice(block);
+                reader.pos += len;
+            }
+            1 => {
+                let mut lengths = [8u8; 288];
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                inflate_block(
+                    &mut reader,
+                    &mut out,
+                    &Huffman::new(&lengths),
+                    &Huffman::new(&[5; 30]),
+                    limit,
+                )?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_codes(&mut reader)?;
+                inflate_block(&mut reader, &mut out, &literals, &distances, limit)?;
+            }
+            _ => return Err(malformed("deflate block type")),
+        }
+        if out.len() > limit {
+            return Err(malformed("image data size"));
+        }
+        if last {
+            break;
+        }
+    }
+
+    let at = 2 + reader.pos;
+    let expected = data
+        .get(at..at + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+    if expected != Some(adler32(&out)) {
+        return Err(malformed("zlib checksum"));
+    }
+    Ok(out)
+}
+
+fn dynamic_codes(reader: &mut BitReader) -> StorageResult<(Huffman, Huffman)> {
+    let literal_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_count = reader.bits(4)? as usize + 4;
+    let mut code_lengths = [0u8; 19];
+    for &i in &CODE_LENGTH_ORDER[..code_count] {
+        code_lengths[i] = reader.bits(3)? as u8;
+    }
+    let code = Huffman::new(&code_lengths);
+
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let (value, repeat) = match reader.symbol(&code)? {
+            len @ 0..=15 => (len as u8, 1),
+            16 => (
+                *lengths.last().ok_or_else(|| malformed("deflate lengths"))?,
+                3 + reader.bits(2)?,
+            ),
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() != literal_count + distance_count {
+        return Err(malformed("deflate lengths"));
+    }
+    Ok((
+        Huffman::new(&lengths[..literal_count]),
+        Huffman::new(&lengths[literal_count..]),
+    ))
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+    limit: usize,
+) -> StorageResult<()> {
+    loop {
+        let symbol = reader.symbol(literals)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let i = symbol - 257;
+                let len = *LENGTH_BASE
+                    .get(i)
+                    .ok_or_else(|| malformed("deflate length"))? as usize
+                    + reader.bits(LENGTH_EXTRA[i] as u32)? as usize;
+                let d = reader.symbol(distances)? as usize;
+                let distance = *DIST_BASE
+                    .get(d)
+                    .ok_or_else(|| malformed("deflate distance"))?
+                    as usize
+                    + reader.bits(DIST_EXTRA[d] as u32)? as usize;
+                if distance > out.len() {
+                    return Err(malformed("deflate distance"));
+                }
+                let start = out.len() - distance;
+                for i in 0..len {
+                    out.push(out[start + i]);
+                }
+            }
+        }
+        if out.len() > limit {
+            return Err(malformed("image data size"));
+        }
+    }
+}
+
+/// A zlib stream of stored (uncompressed) deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(0xffff).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        out.push(blocks.peek().is_none() as u8);
+        out.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(width: u32, height: u32) -> Image {
+        let mut pixels = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                pixels.extend_from_slice(&[(x * 10) as u8, (y * 10) as u8, 128, 200]);
+            }
+        }
+        Image::new(width, height, pixels).unwrap()
+    }
+
+    #[test]
+    fn test_codec_round_trips() {
+        let image = gradient(7, 5);
+        let (png, format) = Image::decode(&image.encode(ImageFormat::Png)).unwrap();
+        assert_eq!(format, ImageFormat::Png);
+        assert_eq!(png, image);
+
+        // BMP drops alpha
+        let (bmp, format) = Image::decode(&image.encode(ImageFormat::Bmp)).unwrap();
+        assert_eq!(format, ImageFormat::Bmp);
+        assert_eq!(bmp.pixels[..3], image.pixels[..3]);
+        assert_eq!(bmp.pixels[3], 255);
+        assert_eq!((bmp.width, bmp.height), (7, 5));
+
+        assert!(matches!(
+            Image::decode(b"GIF89a"),
+            Err(StorageError::UnsupportedImage(_))
+        ));
+    }
+
+    #[test]
+    fn test_inflate_compressed_png() {
+        // 2x1 RGB, one white and one black pixel: a fixed Huffman block
+        let fixed: &[u8] = &[
+            0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48,
+            0x44, 0x52, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x08, 0x02, 0x00, 0x00,
+            0x00, 0x7b, 0x40, 0xe8, 0xdd, 0x00, 0x00, 0x00, 0x0f, 0x49, 0x44, 0x41, 0x54, 0x78,
+            0xda, 0x63, 0xf8, 0xff, 0xff, 0x3f, 0x03, 0x03, 0x03, 0x00, 0x0e, 0xf8, 0x02, 0xfe,
+            0x70, 0xf0, 0x3f, 0xb2, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42,
+            0x60, 0x82,
+        ];
+        let (image, _) = Image::decode(fixed).unwrap();
+        assert_eq!(image.pixels, [255, 255, 255, 255, 0, 0, 0, 255]);
+
+        // 16x16 grayscale, Sub filtered: a dynamic Huffman block
+        let dynamic: &[u8] = &[
+            0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48,
+            0x44, 0x52, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x10, 0x08, 0x00, 0x00, 0x00,
+            0x00, 0x3a, 0x98, 0xa0, 0xbd, 0x00, 0x00, 0x00, 0x46, 0x49, 0x44, 0x41, 0x54, 0x78,
+            0xda, 0x95, 0xcf, 0x3b, 0x0e, 0xc0, 0x20, 0x0c, 0x04, 0xd1, 0x0c, 0xbf, 0x40, 0xcc,
+            0xfd, 0xcf, 0x1b, 0x24, 0x8a, 0x88, 0xe9, 0xd2, 0x79, 0xad, 0x27, 0x6b, 0xcd, 0x75,
+            0xd3, 0xd3, 0xc8, 0x4f, 0x89, 0x3a, 0xdb, 0x0a, 0x7c, 0xe3, 0xde, 0xd3, 0x4c, 0x86,
+            0x08, 0x53, 0x84, 0x24, 0x42, 0x15, 0x39, 0x0f, 0xae, 0x40, 0x88, 0x60, 0x42, 0x11,
+            0x41, 0xb5, 0x3a, 0x6a, 0x1e, 0xfc, 0x7f, 0xee, 0x05, 0x30, 0x5c, 0x06, 0x01, 0x28,
+            0xd4, 0x2b, 0x98, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60,
+            0x82,
+        ];
+        let (image, _) = Image::decode(dynamic).unwrap();
+        for y in 0..16u32 {
+            let mut gray = 0u8;
+            for x in 0..16u32 {
+                gray = gray.wrapping_add(((x * 7 + y * 3) % 13) as u8);
+                let at = ((y * 16 + x) * 4) as usize;
+                assert_eq!(image.pixels[at..at + 4], [gray, gray, gray, 255]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_crop_and_resize() {
+        let image = gradient(8, 6);
+        let cropped = image.crop(2, 1, 3, 2).unwrap();
+        assert_eq!((cropped.width, cropped.height), (3, 2));
+        assert_eq!(cropped.pixels[..4], [20, 10, 128, 200]);
+        assert!(image.crop(6, 0, 3, 1).is_err());
+
+        let resized = image.resize(4, 3).unwrap();
+        assert_eq!((resized.width, resized.height), (4, 3));
+        assert_eq!(resized.pixels[2..4], [128, 200]);
+        assert_eq!(image.resize(8, 6).unwrap(), image);
+        assert!(image.resize(0, 3).is_err());
+    }
+}
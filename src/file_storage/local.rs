@@ -1,7 +1,10 @@
 //! # Local Filesystem Backend
 
-use std::fs;
-use std::path::PathBuf;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
 
 use super::backend::StorageBackend;
 use super::errors::{StorageError, StorageResult};
@@ -21,18 +24,37 @@ impl LocalBackend {
     fn full_path(&self, path: &str) -> PathBuf {
         self.root.join(path)
     }
-}
-
-impl StorageBackend for LocalBackend {
-    fn write(&self, path: &str, data: &[u8]) -> StorageResult<()> {
-        let full_path = self.full_path(path);
 
+    /// Write through `fill` to a temporary file beside `full_path`, fsync
+    /// it, and rename it into place, so readers never see a partial file
+    fn write_atomic(
+        full_path: &Path,
+        fill: impl FnOnce(&mut File) -> io::Result<()>,
+    ) -> StorageResult<()> {
         // Create parent directories
         if let Some(parent) = full_path.parent() {
             fs::create_dir_all(parent).map_err(|e| StorageError::IoError(e.to_string()))?;
         }
 
-        fs::write(&full_path, data).map_err(|e| StorageError::IoError(e.to_string()))
+        let mut tmp_name = full_path.file_name().unwrap_or_default().to_os_string();
+        tmp_name.push(format!(".{}.tmp", uuid::Uuid::new_v4()));
+        let tmp = full_path.with_file_name(tmp_name);
+        let result = File::create(&tmp)
+            .and_then(|mut file| {
+                fill(&mut file)?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&tmp, full_path));
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        result.map_err(|e| StorageError::IoError(e.to_string()))
+    }
+}
+
+impl StorageBackend for LocalBackend {
+    fn write(&self, path: &str, data: &[u8]) -> StorageResult<()> {
+        Self::write_atomic(&self.full_path(path), |file| file.write_all(data))
     }
 
     fn read(&self, path: &str) -> StorageResult<Vec<u8>> {
@@ -81,6 +103,31 @@ impl StorageBackend for LocalBackend {
 
         Ok(results)
     }
+
+    fn compose(&self, path: &str, parts: &[String]) -> StorageResult<String> {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            if !self.full_path(part).is_file() {
+                return Err(StorageError::ObjectNotFound(part.clone()));
+            }
+        }
+        Self::write_atomic(&self.full_path(path), |file| {
+            let mut buf = vec![0; 64 * 1024];
+            for part in parts {
+                let mut source = File::open(self.full_path(part))?;
+                loop {
+                    let n = io::Read::read(&mut source, &mut buf)?;
+                    if n == 0 {
+                        break;
+                    }
+                    hasher.update(&buf[..n]);
+                    file.write_all(&buf[..n])?;
+                }
+            }
+            Ok(())
+        })?;
+        Ok(format!("{:x}", hasher.finalize()))
+    }
 }
 
 #[cfg(test)]
@@ -120,6 +167,24 @@ mod tests {
         assert!(!backend.exists("delete-me.txt").unwrap());
     }
 
+    #[test]
+    fn test_compose() {
+        let temp = TempDir::new().unwrap();
+        let backend = LocalBackend::new(temp.path().to_path_buf());
+
+        backend.write("parts/1", b"hello, ").unwrap();
+        backend.write("parts/2", b"world").unwrap();
+        let parts = vec!["parts/1".to_string(), "parts/2".to_string()];
+        let checksum = backend.compose("out/file.txt", &parts).unwrap();
+        assert_eq!(backend.read("out/file.txt").unwrap(), b"hello, world");
+        assert_eq!(checksum, format!("{:x}", Sha256::digest(b"hello, world")));
+
+        let missing = vec!["parts/1".to_string(), "parts/3".to_string()];
+        assert!(backend.compose("out/other.txt", &missing).is_err());
+        assert!(!backend.exists("out/other.txt").unwrap());
+        assert_eq!(backend.list("out").unwrap().len(), 1);
+    }
+
     #[test]
     fn test_not_found() {
         let temp = TempDir::new().unwrap();
@@ -232,7 +232,7 @@ impl<H: crate::rest_api::RestHandler + Send + Sync> MetadataStore for DatabaseMe
         if self.get(&object.bucket_id, &object.path)?.is_some() {
             // Update existing
             self.handler
-                .update("storage_objects", &object.id.to_string(), data, &ctx)
+                .update("storage_objects", &object.id.to_string(), data, None, &ctx)
                 .map_err(|e| StorageError::Internal(e.to_string()))?;
         } else {
             // Insert new
@@ -254,7 +254,7 @@ impl<H: crate::rest_api::RestHandler + Send + Sync> MetadataStore for DatabaseMe
 
         let ctx = RlsContext::service_role();
         self.handler
-            .delete("storage_objects", &object.id.to_string(), &ctx)
+            .delete("storage_objects", &object.id.to_string(), None, &ctx)
             .map_err(|e| StorageError::Internal(e.to_string()))?;
 
         Ok(())
@@ -317,7 +317,7 @@ impl<H: crate::rest_api::RestHandler + Send + Sync> MetadataStore for DatabaseMe
             serde_json::to_value(&object).map_err(|e| StorageError::Internal(e.to_string()))?;
 
         self.handler
-            .update("storage_objects", &object.id.to_string(), data, &ctx)
+            .update("storage_objects", &object.id.to_string(), data, None, &ctx)
             .map_err(|e| StorageError::Internal(e.to_string()))?;
 
         Ok(())
@@ -8,16 +8,26 @@ pub mod backend;
 pub mod bucket;
 pub mod errors;
 pub mod file;
+pub mod image;
 pub mod local;
 pub mod metadata;
 pub mod permissions;
+pub mod s3;
+pub mod s3_transport;
 pub mod signed_url;
+pub mod transform;
+pub mod upload;
 
 pub use backend::StorageBackend;
-pub use bucket::{Bucket, BucketConfig};
+pub use bucket::{Bucket, BucketConfig, BucketUsage};
 pub use errors::{StorageError, StorageResult};
 pub use file::{FileService, StorageObject};
+pub use image::{Image, ImageFormat};
 pub use local::LocalBackend;
 pub use metadata::{InMemoryMetadataStore, MetadataStore};
 pub use permissions::StoragePermissions;
-pub use signed_url::SignedUrlGenerator;
+pub use s3::{S3Backend, S3Config};
+pub use s3_transport::{HttpS3Transport, S3Request, S3Response, S3Transport};
+pub use signed_url::{SignedUpload, SignedUrlGenerator};
+pub use transform::{ImageTransformer, TransformCache, TransformOptions};
+pub use upload::{UploadPart, UploadSession, UploadSessionStore};
@@ -0,0 +1,269 @@
+//! # S3 HTTP Transport
+//!
+//! Blocking HTTP/1.1 requests to S3-compatible endpoints: one request per
+//! connection (`Connection: close`), server certificates checked against
+//! the Mozilla root store. Plain `http://` endpoints are allowed for local
+//! object stores and test servers.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio_rustls::rustls::{self, pki_types::ServerName, ClientConfig, RootCertStore};
+
+/// Most response headers accepted
+const MAX_HEADERS: usize = 64;
+
+/// A request to an S3 endpoint
+#[derive(Debug, Clone, PartialEq)]
+pub struct S3Request {
+    pub method: String,
+    /// `http` or `https` URL, with its query
+    pub url: String,
+    /// Headers besides `Host`, `Content-Length` and `Connection`
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// An S3 endpoint's response
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct S3Response {
+    pub status: u16,
+    /// Header names lowercased
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl S3Response {
+    /// Value of header `name`, given in lowercase
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Sends requests to an S3 endpoint
+pub trait S3Transport: Send + Sync {
+    fn send(&self, request: &S3Request) -> io::Result<S3Response>;
+}
+
+/// `S3Transport` over HTTP(S)
+pub struct HttpS3Transport {
+    tls: Arc<ClientConfig>,
+    timeout: Duration,
+}
+
+impl HttpS3Transport {
+    /// Transport with `timeout` for connecting and for each read/write
+    pub fn new(timeout: Duration) -> Self {
+        let mut roots = RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        let tls =
+            ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .expect("ring provider supports the default protocol versions")
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+        Self {
+            tls: Arc::new(tls),
+            timeout,
+        }
+    }
+}
+
+impl Default for HttpS3Transport {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(30))
+    }
+}
+
+impl std::fmt::Debug for HttpS3Transport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpS3Transport")
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl S3Transport for HttpS3Transport {
+    fn send(&self, request: &S3Request) -> io::Result<S3Response> {
+        let target = Target::parse(&request.url)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid URL"))?;
+
+        let mut head = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nContent-Length: {}\r\n",
+            request.method,
+            target.path,
+            target.host_header,
+            request.body.len()
+        );
+        for (name, value) in &request.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str("\r\n");
+        let mut raw_request = head.into_bytes();
+        raw_request.extend_from_slice(&request.body);
+
+        let addr = (target.host.as_str(), target.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host did not resolve"))?;
+        let tcp = TcpStream::connect_timeout(&addr, self.timeout)?;
+        tcp.set_read_timeout(Some(self.timeout))?;
+        tcp.set_write_timeout(Some(self.timeout))?;
+
+        let mut raw = Vec::new();
+        if target.tls {
+            let name = ServerName::try_from(target.host.clone())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            let connection = rustls::ClientConnection::new(Arc::clone(&self.tls), name)
+                .map_err(io::Error::other)?;
+            let mut stream = rustls::StreamOwned::new(connection, tcp);
+            stream.write_all(&raw_request)?;
+            read_all(&mut stream, &mut raw)?;
+        } else {
+            let mut stream = tcp;
+            stream.write_all(&raw_request)?;
+            read_all(&mut stream, &mut raw)?;
+        }
+        parse_response(&raw, request.method == "HEAD")
+    }
+}
+
+/// Read to the end of `stream`
+fn read_all(stream: &mut impl Read, raw: &mut Vec<u8>) -> io::Result<()> {
+    match stream.read_to_end(raw) {
+        // Servers commonly skip TLS close_notify after `Connection: close`
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof && !raw.is_empty() => Ok(()),
+        other => other.map(|_| ()),
+    }
+}
+
+/// Request target of a URL
+#[derive(Debug, PartialEq)]
+struct Target {
+    tls: bool,
+    host: String,
+    port: u16,
+    host_header: String,
+    path: String,
+}
+
+impl Target {
+    fn parse(url: &str) -> Option<Self> {
+        let (tls, rest) = match url.split_once("://")? {
+            ("https", rest) => (true, rest),
+            ("http", rest) => (false, rest),
+            _ => return None,
+        };
+        let split = rest.find(['/', '?']).unwrap_or(rest.len());
+        let (authority, path) = rest.split_at(split);
+        let path = match path {
+            "" => "/".to_string(),
+            p if p.starts_with('?') => format!("/{}", p),
+            p => p.to_string(),
+        };
+        if authority.is_empty() || authority.contains('@') {
+            return None;
+        }
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.contains(']') => (host, port.parse().ok()?),
+            _ => (authority, if tls { 443 } else { 80 }),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        Some(Self {
+            tls,
+            host: host.to_string(),
+            port,
+            host_header: authority.to_string(),
+            path,
+        })
+    }
+}
+
+/// Status, headers and body of a complete response; responses to `HEAD`
+/// have no body whatever their `Content-Length`
+fn parse_response(raw: &[u8], head: bool) -> io::Result<S3Response> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+    let mut response = httparse::Response::new(&mut headers);
+    let head_len = match response.parse(raw) {
+        Ok(httparse::Status::Complete(len)) => len,
+        Ok(httparse::Status::Partial) => return Err(invalid("incomplete response".to_string())),
+        Err(e) => return Err(invalid(format!("invalid response: {}", e))),
+    };
+    let headers: Vec<(String, String)> = response
+        .headers
+        .iter()
+        .map(|h| {
+            (
+                h.name.to_ascii_lowercase(),
+                String::from_utf8_lossy(h.value).trim().to_string(),
+            )
+        })
+        .collect();
+    let mut parsed = S3Response {
+        status: response.code.unwrap_or_default(),
+        headers,
+        body: Vec::new(),
+    };
+    if head {
+        return Ok(parsed);
+    }
+    let rest = &raw[head_len..];
+
+    parsed.body = if parsed
+        .header("transfer-encoding")
+        .is_some_and(|v| v.to_ascii_lowercase().contains("chunked"))
+    {
+        let mut body = Vec::new();
+        let mut reader = BufReader::new(rest);
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            let size = line.trim().split(';').next().unwrap_or_default();
+            let size = usize::from_str_radix(size, 16)
+                .map_err(|_| invalid(format!("invalid chunk size '{}'", line.trim())))?;
+            if size == 0 {
+                break body;
+            }
+            let start = body.len();
+            body.resize(start + size, 0);
+            reader.read_exact(&mut body[start..])?;
+            let mut crlf = [0; 2];
+            reader.read_exact(&mut crlf)?;
+        }
+    } else if let Some(len) = parsed.header("content-length") {
+        let len: usize = len
+            .parse()
+            .map_err(|_| invalid("invalid Content-Length".to_string()))?;
+        rest.get(..len)
+            .ok_or_else(|| invalid("truncated response body".to_string()))?
+            .to_vec()
+    } else {
+        rest.to_vec()
+    };
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() {
+        let raw = b"HTTP/1.1 200 OK\r\nETag: \"abc\"\r\nContent-Length: 5\r\n\r\nhello";
+        let response = parse_response(raw, false).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.header("etag"), Some("\"abc\""));
+        assert_eq!(response.body, b"hello");
+
+        // HEAD responses advertise a length they do not send
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n";
+        assert!(parse_response(raw, false).is_err());
+        assert!(parse_response(raw, true).unwrap().body.is_empty());
+    }
+}
@@ -5,6 +5,7 @@ use chrono::{DateTime, Duration, Utc};
 use sha2::{Digest, Sha256};
 
 use super::errors::{StorageError, StorageResult};
+use super::transform::TransformOptions;
 
 /// Signed URL generator
 #[derive(Debug)]
@@ -29,18 +30,28 @@ impl SignedUrlGenerator {
         path: &str,
         expires_at: Option<DateTime<Utc>>,
     ) -> SignedUrl {
-        let expires = expires_at.unwrap_or_else(|| Utc::now() + self.default_expiry);
-        let expires_ts = expires.timestamp();
+        self.generate_transformed(bucket, path, expires_at, TransformOptions::default())
+    }
 
-        let message = format!("{}/{}/{}", bucket, path, expires_ts);
-        let signature = self.sign(&message);
+    /// Generate a signed URL serving the object transformed by `transform`
+    pub fn generate_transformed(
+        &self,
+        bucket: &str,
+        path: &str,
+        expires_at: Option<DateTime<Utc>>,
+        transform: TransformOptions,
+    ) -> SignedUrl {
+        let expires = expires_at.unwrap_or_else(|| Utc::now() + self.default_expiry);
 
-        SignedUrl {
+        let mut signed = SignedUrl {
             bucket: bucket.to_string(),
             path: path.to_string(),
             expires_at: expires,
-            signature,
-        }
+            signature: String::new(),
+            transform,
+        };
+        signed.signature = self.sign(&signed.message());
+        signed
     }
 
     /// Verify a signed URL
@@ -51,8 +62,7 @@ impl SignedUrlGenerator {
         }
 
         // Verify signature
-        let message = format!("{}/{}/{}", url.bucket, url.path, url.expires_at.timestamp());
-        let expected = self.sign(&message);
+        let expected = self.sign(&url.message());
 
         if url.signature != expected {
             return Err(StorageError::InvalidSignature);
@@ -61,6 +71,53 @@ impl SignedUrlGenerator {
         Ok(())
     }
 
+    /// Generate a signed upload URL for an object of at most `max_size`
+    /// bytes and, if given, of `content_type` only
+    pub fn generate_upload(
+        &self,
+        bucket: &str,
+        path: &str,
+        expires_at: Option<DateTime<Utc>>,
+        max_size: u64,
+        content_type: Option<String>,
+    ) -> SignedUpload {
+        let mut signed = SignedUpload {
+            bucket: bucket.to_string(),
+            path: path.to_string(),
+            expires_at: expires_at.unwrap_or_else(|| Utc::now() + self.default_expiry),
+            max_size,
+            content_type,
+            signature: String::new(),
+        };
+        signed.signature = self.sign(&signed.message());
+        signed
+    }
+
+    /// Verify a signed upload URL for an upload of `size` bytes of
+    /// `content_type`
+    pub fn verify_upload(
+        &self,
+        upload: &SignedUpload,
+        size: u64,
+        content_type: &str,
+    ) -> StorageResult<()> {
+        if Utc::now() > upload.expires_at {
+            return Err(StorageError::UrlExpired);
+        }
+        if upload.signature != self.sign(&upload.message()) {
+            return Err(StorageError::InvalidSignature);
+        }
+        if size > upload.max_size {
+            return Err(StorageError::FileTooLarge(size, upload.max_size));
+        }
+        match &upload.content_type {
+            Some(allowed) if !allowed.eq_ignore_ascii_case(content_type) => {
+                Err(StorageError::InvalidMimeType(content_type.to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+
     fn sign(&self, message: &str) -> String {
         // Simple SHA-256 based signature (secret + message)
         let mut hasher = Sha256::new();
@@ -78,18 +135,88 @@ pub struct SignedUrl {
     pub path: String,
     pub expires_at: DateTime<Utc>,
     pub signature: String,
+    /// Transform applied on download; covered by the signature
+    pub transform: TransformOptions,
 }
 
 impl SignedUrl {
     /// Generate the URL string
     pub fn to_url(&self, base_url: &str) -> String {
-        format!(
+        let mut url = format!(
             "{}/storage/v1/object/sign/{}/{}?token={}&expires={}",
             base_url,
             self.bucket,
             self.path,
             self.signature,
             self.expires_at.timestamp()
+        );
+        if !self.transform.is_empty() {
+            url.push('&');
+            url.push_str(&self.transform.canonical());
+        }
+        url
+    }
+
+    /// Signed message; URLs without a transform sign just the object and
+    /// expiry
+    fn message(&self) -> String {
+        let mut message = format!(
+            "{}/{}/{}",
+            self.bucket,
+            self.path,
+            self.expires_at.timestamp()
+        );
+        if !self.transform.is_empty() {
+            message.push('?');
+            message.push_str(&self.transform.canonical());
+        }
+        message
+    }
+}
+
+/// A signed upload URL
+#[derive(Debug, Clone)]
+pub struct SignedUpload {
+    pub bucket: String,
+    pub path: String,
+    pub expires_at: DateTime<Utc>,
+    /// Largest upload accepted, in bytes
+    pub max_size: u64,
+    /// Only content type accepted, if restricted
+    pub content_type: Option<String>,
+    pub signature: String,
+}
+
+impl SignedUpload {
+    /// Generate the URL string
+    pub fn to_url(&self, base_url: &str) -> String {
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        query
+            .append_pair("token", &self.signature)
+            .append_pair("expires", &self.expires_at.timestamp().to_string())
+            .append_pair("max_size", &self.max_size.to_string());
+        if let Some(content_type) = &self.content_type {
+            query.append_pair("content_type", content_type);
+        }
+        format!(
+            "{}/storage/v1/object/upload/sign/{}/{}?{}",
+            base_url,
+            self.bucket,
+            self.path,
+            query.finish()
+        )
+    }
+
+    /// Signed message; the `PUT` prefix keeps download signatures from
+    /// passing as upload signatures
+    fn message(&self) -> String {
+        format!(
+            "PUT\n{}/{}/{}/{}/{}",
+            self.bucket,
+            self.path,
+            self.expires_at.timestamp(),
+            self.max_size,
+            self.content_type.as_deref().unwrap_or_default()
         )
     }
 }
@@ -118,6 +245,7 @@ mod tests {
             path: "file.txt".to_string(),
             expires_at: Utc::now() - Duration::hours(1),
             signature: "fake".to_string(),
+            transform: TransformOptions::default(),
         };
 
         assert!(matches!(
@@ -135,6 +263,7 @@ mod tests {
             path: "file.txt".to_string(),
             expires_at: Utc::now() + Duration::hours(1),
             signature: "bad-signature".to_string(),
+            transform: TransformOptions::default(),
         };
 
         assert!(matches!(
@@ -153,4 +282,62 @@ mod tests {
         assert!(url.contains("bucket"));
         assert!(url.contains("token="));
     }
+
+    #[test]
+    fn test_signed_upload() {
+        let generator = SignedUrlGenerator::new(b"secret");
+        let mut upload = generator.generate_upload(
+            "avatars",
+            "u/1.png",
+            None,
+            1024,
+            Some("image/png".to_string()),
+        );
+        assert!(upload
+            .to_url("")
+            .contains("/object/upload/sign/avatars/u/1.png?token="));
+        assert!(upload.to_url("").ends_with("content_type=image%2Fpng"));
+
+        assert!(generator.verify_upload(&upload, 1024, "image/png").is_ok());
+        assert!(matches!(
+            generator.verify_upload(&upload, 1025, "image/png"),
+            Err(StorageError::FileTooLarge(1025, 1024))
+        ));
+        assert!(matches!(
+            generator.verify_upload(&upload, 10, "text/html"),
+            Err(StorageError::InvalidMimeType(_))
+        ));
+
+        upload.max_size = 1 << 30;
+        assert!(matches!(
+            generator.verify_upload(&upload, 10, "image/png"),
+            Err(StorageError::InvalidSignature)
+        ));
+
+        // A download signature does not authorize uploads
+        let download = generator.generate("avatars", "u/1.png", None);
+        let forged = SignedUpload {
+            signature: download.signature,
+            ..generator.generate_upload("avatars", "u/1.png", Some(download.expires_at), 0, None)
+        };
+        assert!(generator.verify_upload(&forged, 0, "image/png").is_err());
+    }
+
+    #[test]
+    fn test_transform_is_signed() {
+        let generator = SignedUrlGenerator::new(b"secret");
+        let transform = TransformOptions {
+            width: Some(100),
+            ..Default::default()
+        };
+        let mut signed = generator.generate_transformed("bucket", "a.png", None, transform);
+        assert!(signed.to_url("").ends_with("&width=100"));
+        assert!(generator.verify(&signed).is_ok());
+
+        signed.transform.width = Some(4000);
+        assert!(matches!(
+            generator.verify(&signed),
+            Err(StorageError::InvalidSignature)
+        ));
+    }
 }
@@ -0,0 +1,413 @@
+//! # Image Transforms
+//!
+//! Downloads through signed URLs may ask for an image to be cropped,
+//! resized and converted on the way out, e.g.
+//! `?crop=0,0,800,600&width=400&format=png`. The transform is part of the
+//! signed message, so a URL grants exactly one rendition.
+//!
+//! Results are cached on disk under a key derived from the source
+//! object's checksum and the canonical transform, so a changed object
+//! never serves a stale rendition. The cache evicts least recently used
+//! entries to stay within its byte budget.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::errors::{StorageError, StorageResult};
+use super::image::{Image, ImageFormat, MAX_DIMENSION};
+
+/// Default transform cache budget
+pub const DEFAULT_CACHE_BUDGET: u64 = 256 * 1024 * 1024;
+
+/// Region to crop, in source pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Transform applied to an image download: crop, then resize, then encode
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransformOptions {
+    /// Output width; with no height, the height keeps the aspect ratio
+    #[serde(default)]
+    pub width: Option<u32>,
+    /// Output height; with no width, the width keeps the aspect ratio
+    #[serde(default)]
+    pub height: Option<u32>,
+    #[serde(default)]
+    pub crop: Option<CropRect>,
+    /// Output format; the source format if unset
+    #[serde(default)]
+    pub format: Option<ImageFormat>,
+}
+
+impl TransformOptions {
+    /// Options from `TRANSFORM_PARAMS` in a query; other parameters are
+    /// ignored
+    pub fn from_query(query: &HashMap<String, String>) -> StorageResult<Self> {
+        let invalid =
+            |name: &str| StorageError::InvalidTransform(format!("invalid '{}' parameter", name));
+        let dimension = |name: &str| {
+            query
+                .get(name)
+                .map(|v| v.parse::<u32>().map_err(|_| invalid(name)))
+                .transpose()
+        };
+        let crop = match query.get("crop") {
+            Some(value) => {
+                let parts = value
+                    .split(',')
+                    .map(|v| v.trim().parse::<u32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| invalid("crop"))?;
+                let [x, y, width, height] = parts[..] else {
+                    return Err(invalid("crop"));
+                };
+                Some(CropRect {
+                    x,
+                    y,
+                    width,
+                    height,
+                })
+            }
+            None => None,
+        };
+        let format = query
+            .get("format")
+            .map(|v| ImageFormat::parse(v).ok_or_else(|| invalid("format")))
+            .transpose()?;
+        let options = Self {
+            width: dimension("width")?,
+            height: dimension("height")?,
+            crop,
+            format,
+        };
+        options.validate()?;
+        Ok(options)
+    }
+
+    /// Reject zero or oversized dimensions
+    pub fn validate(&self) -> StorageResult<()> {
+        let crop = self.crop.iter().flat_map(|c| [c.width, c.height]);
+        if self
+            .width
+            .iter()
+            .chain(&self.height)
+            .copied()
+            .chain(crop)
+            .any(|d| d == 0 || d > MAX_DIMENSION)
+        {
+            return Err(StorageError::InvalidTransform(format!(
+                "dimensions must be between 1 and {}",
+                MAX_DIMENSION
+            )));
+        }
+        Ok(())
+    }
+
+    /// Whether no transform is asked for
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The options as a query string, parameters in a fixed order
+    pub fn canonical(&self) -> String {
+        let mut params = Vec::new();
+        if let Some(c) = &self.crop {
+            params.push(format!("crop={},{},{},{}", c.x, c.y, c.width, c.height));
+        }
+        if let Some(format) = &self.format {
+            params.push(format!("format={}", format.as_str()));
+        }
+        if let Some(height) = self.height {
+            params.push(format!("height={}", height));
+        }
+        if let Some(width) = self.width {
+            params.push(format!("width={}", width));
+        }
+        params.join("&")
+    }
+
+    /// Cache key of these options applied to content with `checksum`
+    pub fn cache_key(&self, checksum: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(checksum.as_bytes());
+        hasher.update(b"\n");
+        hasher.update(self.canonical().as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Transform encoded image `data`
+    pub fn apply(&self, data: &[u8]) -> StorageResult<(Vec<u8>, ImageFormat)> {
+        let (mut image, source_format) = Image::decode(data)?;
+        if let Some(c) = &self.crop {
+            image = image.crop(c.x, c.y, c.width, c.height)?;
+        }
+        let scaled = |to: u32, from: u32, other: u32| {
+            ((other as u64 * to as u64 + from as u64 / 2) / from as u64).max(1) as u32
+        };
+        let size = match (self.width, self.height) {
+            (Some(width), Some(height)) => Some((width, height)),
+            (Some(width), None) => Some((width, scaled(width, image.width, image.height))),
+            (None, Some(height)) => Some((scaled(height, image.height, image.width), height)),
+            (None, None) => None,
+        };
+        if let Some((width, height)) = size {
+            if (width, height) != (image.width, image.height) {
+                image = image.resize(width, height)?;
+            }
+        }
+        let format = self.format.unwrap_or(source_format);
+        Ok((image.encode(format), format))
+    }
+}
+
+/// Cached renditions on disk, bounded by a byte budget
+#[derive(Debug)]
+pub struct TransformCache {
+    dir: PathBuf,
+    budget: u64,
+    index: Mutex<CacheIndex>,
+}
+
+#[derive(Debug, Default)]
+struct CacheIndex {
+    /// Size and last use of each entry
+    entries: HashMap<String, (u64, u64)>,
+    total: u64,
+    clock: u64,
+}
+
+impl TransformCache {
+    /// Cache in `dir` holding at most `budget` bytes; entries already in
+    /// `dir` are kept, oldest first in line for eviction
+    pub fn new(dir: PathBuf, budget: u64) -> Self {
+        let mut found = Vec::new();
+        if let Ok(entries) = fs::read_dir(&dir) {
+            for entry in entries.flatten() {
+                let (Some(name), Ok(meta)) = (
+                    entry.file_name().to_str().map(String::from),
+                    entry.metadata(),
+                ) else {
+                    continue;
+                };
+                if name.ends_with(".tmp") {
+                    let _ = fs::remove_file(entry.path());
+                } else if meta.is_file() {
+                    found.push((meta.modified().ok(), name, meta.len()));
+                }
+            }
+        }
+        found.sort();
+
+        let mut index = CacheIndex::default();
+        for (_, name, size) in found {
+            index.clock += 1;
+            index.total += size;
+            index.entries.insert(name, (size, index.clock));
+        }
+        let cache = Self {
+            dir,
+            budget,
+            index: Mutex::new(index),
+        };
+        cache.evict(&mut cache.index.lock().unwrap_or_else(|e| e.into_inner()));
+        cache
+    }
+
+    /// Bytes held
+    pub fn usage(&self) -> u64 {
+        self.index.lock().unwrap_or_else(|e| e.into_inner()).total
+    }
+
+    /// Entry `key`, if cached
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let mut index = self.index.lock().unwrap_or_else(|e| e.into_inner());
+        index.clock += 1;
+        let clock = index.clock;
+        index.entries.get_mut(key)?.1 = clock;
+        match fs::read(self.dir.join(key)) {
+            Ok(data) => Some(data),
+            Err(_) => {
+                if let Some((size, _)) = index.entries.remove(key) {
+                    index.total -= size;
+                }
+                None
+            }
+        }
+    }
+
+    /// Cache `data` as `key`, evicting least recently used entries to stay
+    /// within budget; entries larger than the budget are not kept
+    pub fn put(&self, key: &str, data: &[u8]) -> StorageResult<()> {
+        let size = data.len() as u64;
+        if size > self.budget {
+            return Ok(());
+        }
+        let mut index = self.index.lock().unwrap_or_else(|e| e.into_inner());
+        write_entry(&self.dir, key, data).map_err(|e| StorageError::IoError(e.to_string()))?;
+        index.clock += 1;
+        let clock = index.clock;
+        if let Some((old, _)) = index.entries.insert(key.to_string(), (size, clock)) {
+            index.total -= old;
+        }
+        index.total += size;
+        self.evict(&mut index);
+        Ok(())
+    }
+
+    fn evict(&self, index: &mut CacheIndex) {
+        while index.total > self.budget {
+            let Some(oldest) = index
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            let _ = fs::remove_file(self.dir.join(&oldest));
+            if let Some((size, _)) = index.entries.remove(&oldest) {
+                index.total -= size;
+            }
+        }
+    }
+}
+
+/// Write `data` to a temporary file in `dir`, fsync it, and rename it to `key`
+fn write_entry(dir: &Path, key: &str, data: &[u8]) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let tmp = dir.join(format!("{}.tmp", key));
+    let mut file = File::create(&tmp)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    fs::rename(&tmp, dir.join(key))
+}
+
+/// Applies transforms through a `TransformCache`
+#[derive(Debug)]
+pub struct ImageTransformer {
+    cache: TransformCache,
+}
+
+impl ImageTransformer {
+    pub fn new(cache: TransformCache) -> Self {
+        Self { cache }
+    }
+
+    pub fn cache(&self) -> &TransformCache {
+        &self.cache
+    }
+
+    /// Rendition of `data`, whose checksum is `checksum`, under `options`
+    pub fn transform(
+        &self,
+        checksum: &str,
+        data: &[u8],
+        options: &TransformOptions,
+    ) -> StorageResult<(Vec<u8>, ImageFormat)> {
+        let key = options.cache_key(checksum);
+        if let Some(cached) = self.cache.get(&key) {
+            if let Some(format) = ImageFormat::detect(&cached) {
+                return Ok((cached, format));
+            }
+        }
+        let (output, format) = options.apply(data)?;
+        self.cache.put(&key, &output)?;
+        Ok((output, format))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn query(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_options_from_query() {
+        let options = TransformOptions::from_query(&query(&[
+            ("width", "200"),
+            ("format", "BMP"),
+            ("crop", "10, 20, 300, 400"),
+            ("token", "ignored"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            options.canonical(),
+            "crop=10,20,300,400&format=bmp&width=200"
+        );
+        assert_ne!(options.cache_key("a"), options.cache_key("b"));
+        assert!(TransformOptions::from_query(&query(&[]))
+            .unwrap()
+            .is_empty());
+
+        for bad in [
+            ("width", "0"),
+            ("width", "x"),
+            ("crop", "1,2,3"),
+            ("format", "gif"),
+        ] {
+            assert!(matches!(
+                TransformOptions::from_query(&query(&[bad])),
+                Err(StorageError::InvalidTransform(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_transform_and_cache() {
+        let source = Image::new(40, 20, vec![90; 40 * 20 * 4])
+            .unwrap()
+            .encode(ImageFormat::Png);
+        let dir = TempDir::new().unwrap();
+        let transformer = ImageTransformer::new(TransformCache::new(dir.path().to_path_buf(), 700));
+
+        let options = TransformOptions {
+            width: Some(10),
+            format: Some(ImageFormat::Bmp),
+            ..Default::default()
+        };
+        let (output, format) = transformer.transform("sum", &source, &options).unwrap();
+        assert_eq!(format, ImageFormat::Bmp);
+        let (image, _) = Image::decode(&output).unwrap();
+        assert_eq!((image.width, image.height), (10, 5));
+
+        // A second request is served from the cache, also after a restart
+        let usage = transformer.cache().usage();
+        assert_eq!(usage, output.len() as u64);
+        assert_eq!(
+            transformer.transform("sum", b"", &options).unwrap().0,
+            output
+        );
+        let reopened = TransformCache::new(dir.path().to_path_buf(), 700);
+        assert_eq!(reopened.usage(), usage);
+
+        // Older entries make way within the budget
+        let other = TransformOptions {
+            width: Some(20),
+            format: Some(ImageFormat::Bmp),
+            ..Default::default()
+        };
+        transformer.transform("sum", &source, &other).unwrap();
+        let cache = transformer.cache();
+        assert!(cache.usage() <= 700);
+        assert!(cache.get(&options.cache_key("sum")).is_none());
+        assert!(cache.get(&other.cache_key("sum")).is_some());
+    }
+}
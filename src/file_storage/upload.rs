@@ -0,0 +1,247 @@
+//! # Resumable Uploads
+//!
+//! A large file is uploaded as numbered parts of a session: the client
+//! initiates a session, sends parts in any order (re-sending a part
+//! replaces it, so a part cut off by a dropped connection is simply sent
+//! again), and completes the session, which assembles the parts in number
+//! order into the object. Until completion the object is untouched.
+//!
+//! Sessions are recorded in `<data_dir>/upload_sessions.json` by
+//! `UploadSessionStore::open`, so an upload resumes across restarts; part
+//! bytes are staged in the storage backend under `STAGING_PREFIX`.
+//! Sessions not completed within `SESSION_TTL_HOURS` are expired.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::errors::{StorageError, StorageResult};
+
+/// Session file name within the data directory
+pub const SESSIONS_FILE: &str = "upload_sessions.json";
+
+/// Backend path prefix parts are staged under
+pub const STAGING_PREFIX: &str = ".uploads";
+
+/// Most parts in one upload
+pub const MAX_PARTS: u32 = 10_000;
+
+/// Largest part accepted
+pub const MAX_PART_SIZE: usize = 64 * 1024 * 1024;
+
+/// Hours a session may stay incomplete
+pub const SESSION_TTL_HOURS: i64 = 24;
+
+/// One received part
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadPart {
+    pub size: u64,
+    /// SHA-256 of the part, hex
+    pub checksum: String,
+}
+
+/// An upload in progress
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadSession {
+    pub id: Uuid,
+    pub bucket_id: Uuid,
+    /// Object path the upload completes to
+    pub path: String,
+    pub content_type: String,
+    pub owner_id: Option<Uuid>,
+    /// Size the client declared up front, if any
+    pub declared_size: Option<u64>,
+    /// Received parts by number
+    pub parts: BTreeMap<u32, UploadPart>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl UploadSession {
+    /// Start a session for `path` in bucket `bucket_id`
+    pub fn new(
+        bucket_id: Uuid,
+        path: String,
+        content_type: String,
+        owner_id: Option<Uuid>,
+        declared_size: Option<u64>,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            bucket_id,
+            path,
+            content_type,
+            owner_id,
+            declared_size,
+            parts: BTreeMap::new(),
+            created_at: now,
+            expires_at: now + Duration::hours(SESSION_TTL_HOURS),
+        }
+    }
+
+    /// Bytes received so far
+    pub fn uploaded_bytes(&self) -> u64 {
+        self.parts.values().map(|p| p.size).sum()
+    }
+
+    /// Backend path part `number` is staged at
+    pub fn part_path(&self, number: u32) -> String {
+        format!("{}/{}/{}", STAGING_PREFIX, self.id, number)
+    }
+}
+
+/// Upload sessions, optionally persisted
+#[derive(Debug, Default)]
+pub struct UploadSessionStore {
+    /// Session file, or `None` to keep sessions in memory
+    path: Option<PathBuf>,
+    sessions: RwLock<HashMap<Uuid, UploadSession>>,
+}
+
+impl UploadSessionStore {
+    /// Sessions kept in memory
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open the sessions in `<data_dir>/upload_sessions.json`
+    pub fn open(data_dir: &Path) -> StorageResult<Self> {
+        let path = data_dir.join(SESSIONS_FILE);
+        let sessions = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| {
+                StorageError::Internal(format!("{} is malformed: {}", path.display(), e))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(StorageError::IoError(e.to_string())),
+        };
+        Ok(Self {
+            path: Some(path),
+            sessions: RwLock::new(sessions),
+        })
+    }
+
+    /// Session `id`
+    pub fn get(&self, id: &Uuid) -> StorageResult<UploadSession> {
+        let sessions = self.sessions.read().unwrap_or_else(|e| e.into_inner());
+        sessions
+            .get(id)
+            .cloned()
+            .ok_or_else(|| StorageError::UploadNotFound(id.to_string()))
+    }
+
+    /// Record a new or changed session
+    pub fn put(&self, session: UploadSession) -> StorageResult<()> {
+        self.update(|sessions| {
+            sessions.insert(session.id, session);
+            Ok(())
+        })
+    }
+
+    /// Record part `number` of session `id`
+    pub fn put_part(&self, id: &Uuid, number: u32, part: UploadPart) -> StorageResult<()> {
+        self.update(|sessions| {
+            let session = sessions
+                .get_mut(id)
+                .ok_or_else(|| StorageError::UploadNotFound(id.to_string()))?;
+            session.parts.insert(number, part);
+            Ok(())
+        })
+    }
+
+    /// Forget session `id`, returning it
+    pub fn remove(&self, id: &Uuid) -> StorageResult<UploadSession> {
+        let mut removed = None;
+        self.update(|sessions| {
+            removed = sessions.remove(id);
+            Ok(())
+        })?;
+        removed.ok_or_else(|| StorageError::UploadNotFound(id.to_string()))
+    }
+
+    /// Sessions expired at `now`
+    pub fn expired(&self, now: DateTime<Utc>) -> Vec<UploadSession> {
+        let sessions = self.sessions.read().unwrap_or_else(|e| e.into_inner());
+        sessions
+            .values()
+            .filter(|s| s.expires_at <= now)
+            .cloned()
+            .collect()
+    }
+
+    /// Apply `change` to a copy of the sessions, persist it, then keep it
+    fn update(
+        &self,
+        change: impl FnOnce(&mut HashMap<Uuid, UploadSession>) -> StorageResult<()>,
+    ) -> StorageResult<()> {
+        let mut sessions = self.sessions.write().unwrap_or_else(|e| e.into_inner());
+        let mut updated = sessions.clone();
+        change(&mut updated)?;
+        if let Some(path) = &self.path {
+            write_sessions(path, &updated).map_err(|e| StorageError::IoError(e.to_string()))?;
+        }
+        *sessions = updated;
+        Ok(())
+    }
+}
+
+/// Write `sessions` to a temporary file, fsync it, and rename it over `path`
+fn write_sessions(path: &Path, sessions: &HashMap<Uuid, UploadSession>) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(&serde_json::to_vec(sessions)?)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_sessions_survive_restart() {
+        let dir = TempDir::new().unwrap();
+        let store = UploadSessionStore::open(dir.path()).unwrap();
+        let session = UploadSession::new(
+            Uuid::new_v4(),
+            "videos/big.mp4".into(),
+            "video/mp4".into(),
+            None,
+            Some(10),
+        );
+        let id = session.id;
+        store.put(session).unwrap();
+        let part = UploadPart {
+            size: 4,
+            checksum: "c".into(),
+        };
+        store.put_part(&id, 2, part.clone()).unwrap();
+        assert!(matches!(
+            store.put_part(&Uuid::new_v4(), 1, part.clone()),
+            Err(StorageError::UploadNotFound(_))
+        ));
+
+        let reopened = UploadSessionStore::open(dir.path()).unwrap();
+        let session = reopened.get(&id).unwrap();
+        assert_eq!(session.parts[&2], part);
+        assert_eq!(session.uploaded_bytes(), 4);
+        assert!(reopened.expired(Utc::now()).is_empty());
+        assert_eq!(reopened.expired(session.expires_at).len(), 1);
+
+        reopened.remove(&id).unwrap();
+        assert!(UploadSessionStore::open(dir.path())
+            .unwrap()
+            .get(&id)
+            .is_err());
+    }
+}
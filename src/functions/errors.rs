@@ -23,15 +23,30 @@ pub enum FunctionError {
     #[error("Memory limit exceeded: {0}MB")]
     MemoryExceeded(u32),
 
+    #[error("Fuel exhausted: {0}")]
+    FuelExhausted(u64),
+
+    #[error("Import not allowed: {0}")]
+    DisallowedImport(String),
+
     #[error("Runtime error: {0}")]
     RuntimeError(String),
 
     #[error("Invalid trigger: {0}")]
     InvalidTrigger(String),
 
+    #[error("Method not allowed: {0}")]
+    MethodNotAllowed(String),
+
     #[error("Invalid cron expression: {0}")]
     InvalidCron(String),
 
+    #[error("Secret not found: {0}")]
+    SecretNotFound(String),
+
+    #[error("Invalid secret: {0}")]
+    InvalidSecret(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
@@ -45,9 +60,14 @@ impl FunctionError {
             FunctionError::CompilationError(_) => 400,
             FunctionError::Timeout(_) => 504,
             FunctionError::MemoryExceeded(_) => 500,
+            FunctionError::FuelExhausted(_) => 500,
+            FunctionError::DisallowedImport(_) => 400,
             FunctionError::RuntimeError(_) => 500,
             FunctionError::InvalidTrigger(_) => 400,
+            FunctionError::MethodNotAllowed(_) => 405,
             FunctionError::InvalidCron(_) => 400,
+            FunctionError::SecretNotFound(_) => 404,
+            FunctionError::InvalidSecret(_) => 400,
             FunctionError::Internal(_) => 500,
         }
     }
@@ -21,6 +21,11 @@ pub struct FunctionConfig {
     #[serde(default)]
     pub env: HashMap<String, String>,
 
+    /// Secrets bound into the environment: variable name to `name` or
+    /// `name@version` in the secret store
+    #[serde(default)]
+    pub secrets: HashMap<String, String>,
+
     /// Maximum retries for failed invocations
     #[serde(default = "default_retries")]
     pub max_retries: u32,
@@ -36,6 +41,7 @@ impl Default for FunctionConfig {
             timeout_ms: 10_000, // 10 seconds
             memory_mb: 64,
             env: HashMap::new(),
+            secrets: HashMap::new(),
             max_retries: default_retries(),
         }
     }
@@ -0,0 +1,298 @@
+//! # Invocation History
+//!
+//! The last invocations of each function, kept in a bounded ring per
+//! function: status, duration, fuel used and logs. Logs are truncated to a
+//! byte budget per invocation; once a ring is full, each new invocation
+//! drops the oldest.
+//!
+//! `InvocationHistory::open` persists each function's ring in
+//! `<data_dir>/function_invocations/<sha256 of name>.json`, so history
+//! survives restarts. Recording is best-effort: a failed write keeps the
+//! record in memory and never fails the invocation.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use super::errors::{FunctionError, FunctionResult};
+
+/// History directory name within the data directory
+pub const HISTORY_DIR: &str = "function_invocations";
+
+/// Invocations kept per function by default
+pub const DEFAULT_MAX_RECORDS: usize = 100;
+
+/// Log bytes kept per invocation by default
+pub const DEFAULT_MAX_LOG_BYTES: usize = 4096;
+
+/// How an invocation ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InvocationStatus {
+    Success,
+    Error,
+    Timeout,
+}
+
+impl InvocationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InvocationStatus::Success => "success",
+            InvocationStatus::Error => "error",
+            InvocationStatus::Timeout => "timeout",
+        }
+    }
+}
+
+/// One invocation of a function
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvocationRecord {
+    /// Invocation ID
+    pub id: Uuid,
+    pub function_id: Uuid,
+    pub invoked_at: DateTime<Utc>,
+    pub status: InvocationStatus,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    pub fuel_consumed: u64,
+    /// Logs, up to the byte budget
+    pub logs: Vec<String>,
+    /// Whether logs were dropped to fit the budget
+    pub logs_truncated: bool,
+}
+
+/// Totals over a function's retained invocations
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct InvocationStats {
+    pub invocation_count: u64,
+    pub success_count: u64,
+    pub error_count: u64,
+    pub avg_duration_ms: f64,
+    pub last_invoked_at: Option<DateTime<Utc>>,
+}
+
+/// A function's ring, as persisted
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct FunctionHistory {
+    function_name: String,
+    /// Oldest first
+    records: VecDeque<InvocationRecord>,
+}
+
+/// Bounded per-function invocation history
+#[derive(Debug)]
+pub struct InvocationHistory {
+    /// History directory, or `None` to keep history in memory
+    dir: Option<PathBuf>,
+    max_records: usize,
+    max_log_bytes: usize,
+    functions: RwLock<HashMap<String, FunctionHistory>>,
+}
+
+impl Default for InvocationHistory {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            max_records: DEFAULT_MAX_RECORDS,
+            max_log_bytes: DEFAULT_MAX_LOG_BYTES,
+            functions: RwLock::default(),
+        }
+    }
+}
+
+impl InvocationHistory {
+    /// History kept in memory
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open the history in `<data_dir>/function_invocations`
+    pub fn open(data_dir: &Path) -> FunctionResult<Self> {
+        let dir = data_dir.join(HISTORY_DIR);
+        let mut functions = HashMap::new();
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => Some(entries),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(history_error(&dir, e)),
+        };
+        for entry in entries.into_iter().flatten() {
+            let path = entry.map_err(|e| history_error(&dir, e))?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let bytes = fs::read(&path).map_err(|e| history_error(&path, e))?;
+            let history: FunctionHistory = serde_json::from_slice(&bytes).map_err(|e| {
+                FunctionError::Internal(format!("{} is malformed: {}", path.display(), e))
+            })?;
+            functions.insert(history.function_name.clone(), history);
+        }
+        Ok(Self {
+            dir: Some(dir),
+            functions: RwLock::new(functions),
+            ..Self::default()
+        })
+    }
+
+    /// Keep at most `max_records` invocations per function, with at most
+    /// `max_log_bytes` of logs each
+    pub fn with_limits(mut self, max_records: usize, max_log_bytes: usize) -> Self {
+        self.max_records = max_records.max(1);
+        self.max_log_bytes = max_log_bytes;
+        self
+    }
+
+    /// Record an invocation of `function_name`, truncating its logs
+    pub fn record(&self, function_name: &str, mut record: InvocationRecord) {
+        let mut kept = 0;
+        let total = record.logs.len();
+        record.logs.retain(|line| {
+            kept += line.len();
+            kept <= self.max_log_bytes
+        });
+        record.logs_truncated |= record.logs.len() < total;
+
+        let mut functions = self.functions.write().unwrap_or_else(|e| e.into_inner());
+        let history = functions
+            .entry(function_name.to_string())
+            .or_insert_with(|| FunctionHistory {
+                function_name: function_name.to_string(),
+                records: VecDeque::new(),
+            });
+        history.records.push_back(record);
+        while history.records.len() > self.max_records {
+            history.records.pop_front();
+        }
+        if let Some(dir) = &self.dir {
+            // Best-effort: the record stays in memory
+            write_history(&dir.join(file_name(function_name)), history).ok();
+        }
+    }
+
+    /// Invocations of `function_name`, newest first
+    pub fn invocations(&self, function_name: &str) -> Vec<InvocationRecord> {
+        let functions = self.functions.read().unwrap_or_else(|e| e.into_inner());
+        functions
+            .get(function_name)
+            .map(|h| h.records.iter().rev().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Totals over the retained invocations of `function_name`
+    pub fn stats(&self, function_name: &str) -> InvocationStats {
+        let records = self.invocations(function_name);
+        let mut stats = InvocationStats {
+            invocation_count: records.len() as u64,
+            last_invoked_at: records.first().map(|r| r.invoked_at),
+            ..InvocationStats::default()
+        };
+        for record in &records {
+            if record.status == InvocationStatus::Success {
+                stats.success_count += 1;
+            } else {
+                stats.error_count += 1;
+            }
+        }
+        if !records.is_empty() {
+            let total: u64 = records.iter().map(|r| r.duration_ms).sum();
+            stats.avg_duration_ms = total as f64 / records.len() as f64;
+        }
+        stats
+    }
+
+    /// Drop the history of `function_name`
+    pub fn forget(&self, function_name: &str) {
+        let mut functions = self.functions.write().unwrap_or_else(|e| e.into_inner());
+        functions.remove(function_name);
+        if let Some(dir) = &self.dir {
+            fs::remove_file(dir.join(file_name(function_name))).ok();
+        }
+    }
+}
+
+/// Function names are free-form, so files are named by their hash
+fn file_name(function_name: &str) -> String {
+    format!("{:x}.json", Sha256::digest(function_name.as_bytes()))
+}
+
+fn history_error(path: &Path, e: std::io::Error) -> FunctionError {
+    FunctionError::Internal(format!("{}: {}", path.display(), e))
+}
+
+/// Write `history` to a temporary file, fsync it, and rename it over `path`
+fn write_history(path: &Path, history: &FunctionHistory) -> std::io::Result<()> {
+    fs::create_dir_all(path.parent().expect("History files have a directory"))?;
+    let tmp = path.with_extension("json.tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(&serde_json::to_vec(history)?)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn record(status: InvocationStatus, duration_ms: u64, logs: &[&str]) -> InvocationRecord {
+        InvocationRecord {
+            id: Uuid::new_v4(),
+            function_id: Uuid::nil(),
+            invoked_at: Utc::now(),
+            status,
+            error: None,
+            duration_ms,
+            fuel_consumed: 10,
+            logs: logs.iter().map(|l| l.to_string()).collect(),
+            logs_truncated: false,
+        }
+    }
+
+    #[test]
+    fn test_ring_bounded_and_logs_truncated() {
+        let history = InvocationHistory::new().with_limits(2, 8);
+        history.record(
+            "f",
+            record(InvocationStatus::Success, 10, &["1234", "5678", "9"]),
+        );
+        history.record("f", record(InvocationStatus::Error, 20, &[]));
+        history.record("f", record(InvocationStatus::Timeout, 30, &[]));
+
+        let invocations = history.invocations("f");
+        assert_eq!(invocations.len(), 2);
+        assert_eq!(invocations[0].status, InvocationStatus::Timeout);
+        assert_eq!(invocations[1].status, InvocationStatus::Error);
+
+        let stats = history.stats("f");
+        assert_eq!(stats.invocation_count, 2);
+        assert_eq!(stats.error_count, 2);
+        assert_eq!(stats.avg_duration_ms, 25.0);
+
+        history.record(
+            "g",
+            record(InvocationStatus::Success, 1, &["1234", "5678", "9"]),
+        );
+        let kept = &history.invocations("g")[0];
+        assert_eq!(kept.logs, vec!["1234", "5678"]);
+        assert!(kept.logs_truncated);
+    }
+
+    #[test]
+    fn test_history_survives_restart() {
+        let dir = TempDir::new().unwrap();
+        let history = InvocationHistory::open(dir.path()).unwrap();
+        history.record("a/b c", record(InvocationStatus::Success, 5, &["hi"]));
+        history.record("other", record(InvocationStatus::Error, 7, &[]));
+        history.forget("other");
+
+        let reopened = InvocationHistory::open(dir.path()).unwrap();
+        assert_eq!(reopened.invocations("a/b c")[0].logs, vec!["hi"]);
+        assert!(reopened.invocations("other").is_empty());
+    }
+}
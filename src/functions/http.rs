@@ -0,0 +1,298 @@
+//! # HTTP Triggers
+//!
+//! Functions with an HTTP trigger own a method and a path template, such as
+//! `/users/:id` or `/files/*path`. A request matching them is handed to the
+//! function as an `HttpRequest` envelope:
+//!
+//! ```json
+//! {"method": "GET", "path": "/users/42", "params": {"id": "42"},
+//!  "query": {"page": "2"}, "headers": {"accept": "application/json"},
+//!  "body": null}
+//! ```
+//!
+//! The function's result becomes the response. A result of the shape
+//! `{"status": 201, "headers": {...}, "body": ...}` sets the status and
+//! headers; any other result is the body of a `200`. String bodies are
+//! sent as text, `null` as no body, and anything else as JSON.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::errors::{FunctionError, FunctionResult};
+use super::trigger::HttpMethod;
+
+/// Request headers not passed to functions: callers are identified by the
+/// invocation's user instead
+pub const WITHHELD_HEADERS: &[&str] = &["authorization", "cookie"];
+
+impl HttpMethod {
+    /// Parse an HTTP method name, in any case
+    pub fn parse(method: &str) -> Option<Self> {
+        match method.to_ascii_uppercase().as_str() {
+            "GET" => Some(HttpMethod::Get),
+            "POST" => Some(HttpMethod::Post),
+            "PUT" => Some(HttpMethod::Put),
+            "PATCH" => Some(HttpMethod::Patch),
+            "DELETE" => Some(HttpMethod::Delete),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+            HttpMethod::Put => "PUT",
+            HttpMethod::Patch => "PATCH",
+            HttpMethod::Delete => "DELETE",
+        }
+    }
+}
+
+/// One segment of a path template
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    /// `:name`, matching one segment
+    Param(String),
+    /// `*name`, matching the rest of the path; last only
+    Rest(String),
+}
+
+/// A parsed path template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteTemplate {
+    segments: Vec<Segment>,
+}
+
+impl RouteTemplate {
+    /// Parse a template of `/`-separated literals, `:name` params and a
+    /// final `*name` catch-all
+    pub fn parse(template: &str) -> FunctionResult<Self> {
+        let invalid =
+            |reason: &str| FunctionError::InvalidTrigger(format!("path '{}' {}", template, reason));
+        if !template.starts_with('/') {
+            return Err(invalid("must start with '/'"));
+        }
+
+        let parts: Vec<&str> = split_path(template).collect();
+        let mut segments = Vec::with_capacity(parts.len());
+        let mut names = Vec::new();
+        for (i, part) in parts.iter().enumerate() {
+            let (name, segment) = if let Some(name) = part.strip_prefix(':') {
+                (name, Segment::Param(name.to_string()))
+            } else if let Some(name) = part.strip_prefix('*') {
+                if i + 1 != parts.len() {
+                    return Err(invalid("has a catch-all before its end"));
+                }
+                (name, Segment::Rest(name.to_string()))
+            } else {
+                segments.push(Segment::Literal(part.to_string()));
+                continue;
+            };
+            if name.is_empty() || names.contains(&name) {
+                return Err(invalid("has an empty or repeated parameter name"));
+            }
+            names.push(name);
+            segments.push(segment);
+        }
+        Ok(Self { segments })
+    }
+
+    /// Parameters bound by matching `path`, or `None` if it does not match
+    pub fn matches(&self, path: &str) -> Option<HashMap<String, String>> {
+        let mut parts = split_path(path);
+        let mut params = HashMap::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => {
+                    if parts.next()? != literal {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => {
+                    params.insert(name.clone(), parts.next()?.to_string());
+                }
+                Segment::Rest(name) => {
+                    params.insert(name.clone(), parts.by_ref().collect::<Vec<_>>().join("/"));
+                }
+            }
+        }
+        parts.next().is_none().then_some(params)
+    }
+
+    /// The template with parameter names erased: templates of the same
+    /// shape match the same paths
+    pub fn shape(&self) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(literal) => format!("/{}", literal),
+                Segment::Param(_) => "/:".to_string(),
+                Segment::Rest(_) => "/*".to_string(),
+            })
+            .collect()
+    }
+
+    /// Rank among templates matching the same path: literals before params
+    /// before catch-alls, from the first segment on
+    pub fn specificity(&self) -> Vec<u8> {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(_) => 0,
+                Segment::Param(_) => 1,
+                Segment::Rest(_) => 2,
+            })
+            .collect()
+    }
+}
+
+fn split_path(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|part| !part.is_empty())
+}
+
+/// A request routed to a function
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HttpRequest {
+    pub method: String,
+    /// Path below the functions HTTP mount
+    pub path: String,
+    /// Parameters bound by the route template
+    pub params: HashMap<String, String>,
+    pub query: HashMap<String, String>,
+    /// Header names lowercased, without `WITHHELD_HEADERS`
+    pub headers: HashMap<String, String>,
+    /// JSON bodies parsed, other bodies as text; `null` when empty
+    pub body: Value,
+}
+
+impl HttpRequest {
+    /// Request body as a JSON value: parsed if it is JSON, else text
+    pub fn parse_body(body: &[u8]) -> FunctionResult<Value> {
+        if body.is_empty() {
+            return Ok(Value::Null);
+        }
+        if let Ok(value) = serde_json::from_slice(body) {
+            return Ok(value);
+        }
+        std::str::from_utf8(body)
+            .map(|text| Value::String(text.to_string()))
+            .map_err(|_| FunctionError::InvalidTrigger("request body is not UTF-8".into()))
+    }
+
+    /// Keep `name` unless it is withheld from functions
+    pub fn add_header(&mut self, name: &str, value: &str) {
+        let name = name.to_ascii_lowercase();
+        if !WITHHELD_HEADERS.contains(&name.as_str()) {
+            self.headers.insert(name, value.to_string());
+        }
+    }
+}
+
+/// Response built from a function's result
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Value,
+}
+
+impl HttpResponse {
+    /// Map a function's result to a response
+    ///
+    /// An object with an integer `status`, and no keys but `status`,
+    /// `headers` and `body`, is a full response; anything else is the
+    /// body of a `200`.
+    pub fn from_result(result: Value) -> FunctionResult<Self> {
+        let is_response = result.as_object().is_some_and(|object| {
+            object.get("status").is_some_and(Value::is_u64)
+                && object
+                    .keys()
+                    .all(|key| matches!(key.as_str(), "status" | "headers" | "body"))
+        });
+        if !is_response {
+            return Ok(Self {
+                status: 200,
+                headers: Vec::new(),
+                body: result,
+            });
+        }
+
+        let invalid =
+            |reason: &str| FunctionError::RuntimeError(format!("invalid response: {}", reason));
+        let status = result["status"]
+            .as_u64()
+            .filter(|status| (100..=599).contains(status))
+            .ok_or_else(|| invalid("status out of range"))? as u16;
+        let headers = match result.get("headers") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Object(headers)) => headers
+                .iter()
+                .map(|(name, value)| {
+                    value
+                        .as_str()
+                        .map(|value| (name.clone(), value.to_string()))
+                        .ok_or_else(|| invalid("header values must be strings"))
+                })
+                .collect::<FunctionResult<_>>()?,
+            Some(_) => return Err(invalid("headers must be an object")),
+        };
+        Ok(Self {
+            status,
+            headers,
+            body: result.get("body").cloned().unwrap_or(Value::Null),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_route_template_matching() {
+        let template = RouteTemplate::parse("/users/:id/files/*path").unwrap();
+        let params = template.matches("/users/42/files/a/b.txt").unwrap();
+        assert_eq!(params["id"], "42");
+        assert_eq!(params["path"], "a/b.txt");
+        assert!(template.matches("/users/42").is_none());
+        assert!(template.matches("/groups/42/files/a").is_none());
+
+        let exact = RouteTemplate::parse("/users/me").unwrap();
+        assert!(exact.matches("/users/me/").is_some());
+        assert!(exact.matches("/users/me/x").is_none());
+        assert!(exact.specificity() < RouteTemplate::parse("/users/:id").unwrap().specificity());
+
+        assert!(RouteTemplate::parse("users").is_err());
+        assert!(RouteTemplate::parse("/*rest/more").is_err());
+        assert!(RouteTemplate::parse("/:id/:id").is_err());
+    }
+
+    #[test]
+    fn test_response_mapping() {
+        let response = HttpResponse::from_result(json!({
+            "status": 201,
+            "headers": {"location": "/users/42"},
+            "body": {"id": 42}
+        }))
+        .unwrap();
+        assert_eq!(response.status, 201);
+        assert_eq!(
+            response.headers,
+            vec![("location".into(), "/users/42".into())]
+        );
+        assert_eq!(response.body, json!({"id": 42}));
+
+        // Not a response shape: the whole result is the body
+        let plain = HttpResponse::from_result(json!({"status": "executed"})).unwrap();
+        assert_eq!(plain.status, 200);
+        assert_eq!(plain.body, json!({"status": "executed"}));
+
+        assert!(HttpResponse::from_result(json!({"status": 42})).is_err());
+        assert!(HttpResponse::from_result(json!({"status": 200, "headers": {"x": 1}})).is_err());
+    }
+}
@@ -5,11 +5,16 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use uuid::Uuid;
 
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 
 use super::errors::{FunctionError, FunctionResult};
 use super::function::Function;
+use super::history::{InvocationHistory, InvocationRecord, InvocationStatus};
+use super::http::HttpRequest;
 use super::runtime::{ExecutionContext, RuntimeConfig, WasmRuntime, WasmtimeRuntime};
+use super::secrets::SecretStore;
 use super::trigger::TriggerType;
 
 /// Invocation context passed to functions
@@ -32,6 +37,10 @@ pub struct InvocationContext {
 
     /// Invocation timestamp
     pub timestamp: DateTime<Utc>,
+
+    /// Request routed to the function's HTTP trigger, if any
+    #[serde(default)]
+    pub http: Option<HttpRequest>,
 }
 
 impl InvocationContext {
@@ -44,6 +53,17 @@ impl InvocationContext {
             payload,
             user_id,
             timestamp: Utc::now(),
+            http: None,
+        }
+    }
+
+    /// Context for an HTTP request routed to `function`; the request
+    /// envelope is the function's input
+    pub fn http(function: &Function, request: HttpRequest, user_id: Option<Uuid>) -> Self {
+        let payload = serde_json::to_value(&request).unwrap_or(Value::Null);
+        Self {
+            http: Some(request),
+            ..Self::new(function, payload, user_id)
         }
     }
 }
@@ -68,6 +88,10 @@ pub struct InvocationResult {
 
     /// Logs produced
     pub logs: Vec<String>,
+
+    /// Fuel consumed, when metering is enabled
+    #[serde(default)]
+    pub fuel_consumed: u64,
 }
 
 impl InvocationResult {
@@ -80,6 +104,7 @@ impl InvocationResult {
             error: None,
             duration_ms,
             logs: Vec::new(),
+            fuel_consumed: 0,
         }
     }
 
@@ -92,6 +117,7 @@ impl InvocationResult {
             error: Some(error),
             duration_ms,
             logs: Vec::new(),
+            fuel_consumed: 0,
         }
     }
 }
@@ -101,6 +127,8 @@ impl InvocationResult {
 pub struct Invoker {
     runtime: Arc<WasmtimeRuntime>,
     config: RuntimeConfig,
+    secrets: Arc<SecretStore>,
+    history: Arc<InvocationHistory>,
 }
 
 impl Default for Invoker {
@@ -115,17 +143,96 @@ impl Invoker {
         Self {
             runtime: Arc::new(WasmtimeRuntime::default()),
             config: RuntimeConfig::default(),
+            secrets: Arc::new(SecretStore::default()),
+            history: Arc::new(InvocationHistory::new()),
         }
     }
 
+    /// Resolve functions' secret bindings from `secrets`
+    pub fn with_secrets(mut self, secrets: Arc<SecretStore>) -> Self {
+        self.secrets = secrets;
+        self
+    }
+
+    /// Record invocations in `history`
+    pub fn with_history(mut self, history: Arc<InvocationHistory>) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// History of this invoker's invocations
+    pub fn history(&self) -> &Arc<InvocationHistory> {
+        &self.history
+    }
+
+    /// Environment `function` runs with: its variables, then its secrets
+    ///
+    /// A secret bound under a variable's name takes its place.
+    pub fn environment(&self, function: &Function) -> FunctionResult<HashMap<String, String>> {
+        let mut env = function.config.env.clone();
+        env.extend(self.secrets.resolve(&function.config.secrets)?);
+        Ok(env)
+    }
+
     /// Invoke a function
     ///
-    /// Note: Actual WASM execution is stubbed. This simulates
-    /// successful invocation for testing purposes.
+    /// Every invocation of an enabled function, failed or not, is recorded
+    /// in the invocation history.
     pub fn invoke(
         &self,
         function: &Function,
         context: InvocationContext,
+    ) -> FunctionResult<InvocationResult> {
+        let id = context.id;
+        let invoked_at = context.timestamp;
+        let started = Instant::now();
+        let outcome = self.run(function, context);
+        if !function.enabled {
+            return outcome;
+        }
+
+        let record = match &outcome {
+            Ok(result) => InvocationRecord {
+                id,
+                function_id: function.id,
+                invoked_at,
+                status: if result.success {
+                    InvocationStatus::Success
+                } else {
+                    InvocationStatus::Error
+                },
+                error: result.error.clone(),
+                duration_ms: result.duration_ms,
+                fuel_consumed: result.fuel_consumed,
+                logs: result.logs.clone(),
+                logs_truncated: false,
+            },
+            Err(e) => InvocationRecord {
+                id,
+                function_id: function.id,
+                invoked_at,
+                status: match e {
+                    FunctionError::Timeout(_) => InvocationStatus::Timeout,
+                    _ => InvocationStatus::Error,
+                },
+                error: Some(e.to_string()),
+                duration_ms: started.elapsed().as_millis() as u64,
+                fuel_consumed: match e {
+                    FunctionError::FuelExhausted(fuel) => *fuel,
+                    _ => 0,
+                },
+                logs: Vec::new(),
+                logs_truncated: false,
+            },
+        };
+        self.history.record(&function.name, record);
+        outcome
+    }
+
+    fn run(
+        &self,
+        function: &Function,
+        context: InvocationContext,
     ) -> FunctionResult<InvocationResult> {
         // Check if function is enabled
         if !function.enabled {
@@ -135,8 +242,9 @@ impl Invoker {
         // Create execution context
         let mut exec_context = ExecutionContext::new(function, context.user_id);
 
-        // Copy environment variables if any
-        // ... (env logic would go here)
+        // Bind environment variables and secrets; a missing secret fails
+        // the invocation rather than running without it
+        exec_context.env = self.environment(function)?;
 
         // Execute via runtime
         let result = self
@@ -144,19 +252,22 @@ impl Invoker {
             .execute(function, context.payload, exec_context, &self.config)?;
 
         // Map ExecutionResult to InvocationResult
-        if result.success {
-            Ok(InvocationResult::success(
+        let mut invocation = if result.success {
+            InvocationResult::success(
                 context.id,
                 result.result.unwrap_or(serde_json::Value::Null),
                 result.duration_ms,
-            ))
+            )
         } else {
-            Ok(InvocationResult::failure(
+            InvocationResult::failure(
                 context.id,
                 result.error.unwrap_or("Unknown error".to_string()),
                 result.duration_ms,
-            ))
-        }
+            )
+        };
+        invocation.logs = result.logs;
+        invocation.fuel_consumed = result.fuel_consumed;
+        Ok(invocation)
     }
 }
 
@@ -209,4 +320,61 @@ mod tests {
 
         assert!(invoker.invoke(&func, context).is_err());
     }
+
+    #[test]
+    fn test_invocations_recorded() {
+        let invoker = Invoker::new();
+
+        let mut func = Function::new(
+            "echo".to_string(),
+            TriggerType::http("/echo".to_string()),
+            vec![0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00],
+        );
+        func.config.secrets.insert("TOKEN".into(), "missing".into());
+
+        // Failed invocations are recorded too
+        let context = InvocationContext::new(&func, serde_json::json!({}), None);
+        assert!(invoker.invoke(&func, context).is_err());
+        func.config.secrets.clear();
+        let context = InvocationContext::new(&func, serde_json::json!({}), None);
+        let result = invoker.invoke(&func, context).unwrap();
+
+        let history = invoker.history().invocations("echo");
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].id, result.id);
+        assert_eq!(history[0].status, InvocationStatus::Success);
+        assert_eq!(history[1].status, InvocationStatus::Error);
+        assert!(history[1].error.is_some());
+    }
+
+    #[test]
+    fn test_secrets_bound_into_environment() {
+        let keys = crate::storage::FieldKeyring::new();
+        keys.rotate("s1").unwrap();
+        let secrets = Arc::new(SecretStore::new(Arc::new(keys)));
+        secrets.set("api_token", "t0ken").unwrap();
+        let invoker = Invoker::new().with_secrets(secrets.clone());
+
+        let mut func = Function::new(
+            "notify".to_string(),
+            TriggerType::http("/notify".to_string()),
+            vec![0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00],
+        );
+        func.config.env.insert("REGION".into(), "eu".into());
+        func.config
+            .secrets
+            .insert("API_TOKEN".into(), "api_token".into());
+
+        let env = invoker.environment(&func).unwrap();
+        assert_eq!(env["REGION"], "eu");
+        assert_eq!(env["API_TOKEN"], "t0ken");
+
+        // Without its secret the function does not run
+        secrets.delete("api_token").unwrap();
+        let context = InvocationContext::new(&func, serde_json::json!({}), None);
+        assert!(matches!(
+            invoker.invoke(&func, context),
+            Err(FunctionError::SecretNotFound(_))
+        ));
+    }
 }
@@ -3,21 +3,30 @@
 //! Phase 12: Serverless Functions
 //!
 //! WebAssembly-based serverless functions with HTTP, database,
-//! and scheduled triggers.
+//! and scheduled triggers. Database triggers run after writes commit,
+//! through a durable queue (`DbTriggers`).
 
 pub mod errors;
 pub mod function;
+pub mod history;
+pub mod http;
 pub mod invoker;
 pub mod registry;
 pub mod runtime;
 pub mod scheduler;
+pub mod secrets;
 pub mod store;
 pub mod trigger;
+pub mod trigger_queue;
 
 pub use errors::{FunctionError, FunctionResult};
 pub use function::{Function, FunctionConfig};
+pub use history::{InvocationHistory, InvocationRecord, InvocationStats, InvocationStatus};
+pub use http::{HttpRequest, HttpResponse, RouteTemplate};
 pub use invoker::{InvocationContext, InvocationResult, Invoker};
 pub use registry::FunctionRegistry;
 pub use runtime::{ExecutionContext, ExecutionResult, RuntimeConfig, WasmRuntime, WasmtimeRuntime};
-pub use scheduler::Scheduler;
+pub use scheduler::{MissedRunPolicy, RunOutcome, ScheduledJob, Scheduler};
+pub use secrets::{SecretInfo, SecretRef, SecretStore};
 pub use trigger::TriggerType;
+pub use trigger_queue::{DbTriggers, DeliveryReport, TriggerDelivery, TriggerQueue};
@@ -5,7 +5,8 @@ use std::sync::RwLock;
 
 use super::errors::{FunctionError, FunctionResult};
 use super::function::Function;
-use super::trigger::TriggerType;
+use super::http::RouteTemplate;
+use super::trigger::{HttpMethod, TriggerType};
 
 /// Registry of deployed functions
 #[derive(Debug, Default)]
@@ -31,6 +32,10 @@ impl FunctionRegistry {
         let id = function.id.to_string();
         let name = function.name.clone();
         let trigger_id = function.trigger.identifier();
+        if let TriggerType::Http { path, .. } = &function.trigger {
+            RouteTemplate::parse(path)?;
+        }
+        let route = http_route(&function);
 
         // Check for duplicate name
         {
@@ -43,6 +48,24 @@ impl FunctionRegistry {
             }
         }
 
+        // Check for a route taken by another function
+        if let Some((method, shape)) = &route {
+            let by_id = self
+                .by_id
+                .read()
+                .map_err(|_| FunctionError::Internal("Lock poisoned".into()))?;
+            if by_id
+                .values()
+                .any(|f| http_route(f).as_ref() == Some(&(*method, shape.clone())))
+            {
+                return Err(FunctionError::AlreadyExists(format!(
+                    "route {} {}",
+                    method.as_str(),
+                    shape
+                )));
+            }
+        }
+
         // Insert into all indexes
         {
             let mut by_id = self
@@ -120,6 +143,55 @@ impl FunctionRegistry {
         functions
     }
 
+    /// Enabled HTTP function routed `method` and `path`, with the parameters
+    /// its template binds
+    ///
+    /// Of several templates matching the path, the most specific wins. A
+    /// path routed only for other methods is `MethodNotAllowed`.
+    pub fn route(
+        &self,
+        method: HttpMethod,
+        path: &str,
+    ) -> FunctionResult<(Function, HashMap<String, String>)> {
+        let by_id = self
+            .by_id
+            .read()
+            .map_err(|_| FunctionError::Internal("Lock poisoned".into()))?;
+
+        let mut path_matched = false;
+        let mut best: Option<(Vec<u8>, &Function, HashMap<String, String>)> = None;
+        for function in by_id.values().filter(|f| f.enabled) {
+            let TriggerType::Http {
+                path: template,
+                method: routed,
+            } = &function.trigger
+            else {
+                continue;
+            };
+            let Ok(template) = RouteTemplate::parse(template) else {
+                continue;
+            };
+            let Some(params) = template.matches(path) else {
+                continue;
+            };
+            path_matched = true;
+            let specificity = template.specificity();
+            if *routed == method && best.as_ref().is_none_or(|(best, ..)| specificity < *best) {
+                best = Some((specificity, function, params));
+            }
+        }
+
+        match best {
+            Some((_, function, params)) => Ok((function.clone(), params)),
+            None if path_matched => Err(FunctionError::MethodNotAllowed(format!(
+                "{} {}",
+                method.as_str(),
+                path
+            ))),
+            None => Err(FunctionError::NotFound(path.to_string())),
+        }
+    }
+
     /// Unregister a function
     pub fn unregister(&self, name: &str) -> FunctionResult<()> {
         let function = self.get(name)?;
@@ -175,6 +247,16 @@ impl FunctionRegistry {
     }
 }
 
+/// Method and template shape of an HTTP function's route
+fn http_route(function: &Function) -> Option<(HttpMethod, String)> {
+    match &function.trigger {
+        TriggerType::Http { path, method } => {
+            Some((*method, RouteTemplate::parse(path).ok()?.shape()))
+        }
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,8 +2,39 @@
 //!
 //! WebAssembly runtime abstraction for serverless function execution.
 //! Supports both stubbed (testing) and real WASM runtime backends.
+//!
+//! ## Module interface
+//!
+//! Host functions are imported from module `env`:
+//! - `log(ptr: i32, len: i32)` — append a UTF-8 message to the logs
+//!
+//! Nothing else is linked: there is no WASI, so no files, sockets, clocks
+//! or processes. A module importing anything else is refused before it
+//! runs (`DisallowedImport`).
+//!
+//! The entry point is an exported `handle`, in one of two shapes:
+//! - `handle(ptr: i32, len: i32) -> i64`, with exports `memory` and
+//!   `alloc(len: i32) -> i32`: the input JSON is written to `alloc(len)`,
+//!   and the result is the JSON at `ptr = ret >> 32`, `len = ret & 0xffffffff`
+//! - `handle()`: runs for effect, with result `{"status": "executed"}`
+//!
+//! ## Limits
+//!
+//! The tighter of `RuntimeConfig` and the function's own config applies:
+//! - Memory: linear memory beyond the cap is refused (`memory.grow` returns
+//!   -1); a module that cannot start within it, or traps after a refused
+//!   growth, fails with `MemoryExceeded`
+//! - Time: a watchdog bumps the engine epoch at the deadline and the
+//!   store's epoch callback interrupts the module (`Timeout`)
+//! - Fuel: `RuntimeConfig::max_fuel` bounds the instructions executed
+//!   (`FuelExhausted`)
+//!
+//! Other traps give a failed `ExecutionResult` that keeps the logs written
+//! so far. Results carry the fuel consumed and peak memory.
 
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::Arc;
+use std::thread;
 use std::time::{Duration, Instant};
 
 use serde_json::{json, Value};
@@ -13,7 +44,21 @@ use super::errors::{FunctionError, FunctionResult};
 use super::function::Function;
 use crate::auth::rls::RlsContext;
 
-use wasmtime::{Config, Engine, Linker, Module, Store};
+use wasmtime::{
+    Caller, Config, Engine, Instance, Linker, Module, ResourceLimiter, Store, Trap, UpdateDeadline,
+};
+
+/// Module host functions are imported from
+pub const HOST_MODULE: &str = "env";
+
+/// Host functions a module may import from `HOST_MODULE`
+pub const HOST_FUNCTIONS: &[&str] = &["log"];
+
+/// Log entries kept per execution; later ones are dropped
+pub const MAX_LOG_ENTRIES: usize = 1_000;
+
+/// Table elements a module may grow its tables to
+const MAX_TABLE_ELEMENTS: usize = 10_000;
 
 /// Trait for database access from functions
 pub trait DbProvider: Send + Sync {
@@ -40,6 +85,9 @@ pub struct RuntimeConfig {
     /// Maximum memory in bytes
     pub max_memory_bytes: usize,
 
+    /// Maximum fuel (roughly, instructions) per execution, `None` for no cap
+    pub max_fuel: Option<u64>,
+
     /// Enable debug logging
     pub debug: bool,
 }
@@ -49,6 +97,7 @@ impl Default for RuntimeConfig {
         Self {
             timeout_ms: 30_000,                  // 30 seconds
             max_memory_bytes: 128 * 1024 * 1024, // 128 MB
+            max_fuel: None,
             debug: false,
         }
     }
@@ -113,9 +162,12 @@ pub struct ExecutionResult {
     /// Execution time in milliseconds
     pub duration_ms: u64,
 
-    /// Memory used in bytes
+    /// Peak linear memory in bytes
     pub memory_used: usize,
 
+    /// Fuel consumed
+    pub fuel_consumed: u64,
+
     /// Logs captured during execution
     pub logs: Vec<String>,
 }
@@ -129,6 +181,7 @@ impl ExecutionResult {
             error: None,
             duration_ms,
             memory_used: 0,
+            fuel_consumed: 0,
             logs: Vec::new(),
         }
     }
@@ -141,6 +194,7 @@ impl ExecutionResult {
             error: Some(error),
             duration_ms,
             memory_used: 0,
+            fuel_consumed: 0,
             logs: Vec::new(),
         }
     }
@@ -149,6 +203,12 @@ impl ExecutionResult {
         self.logs = logs;
         self
     }
+
+    pub fn with_metering(mut self, memory_used: usize, fuel_consumed: u64) -> Self {
+        self.memory_used = memory_used;
+        self.fuel_consumed = fuel_consumed;
+        self
+    }
 }
 
 /// Trait for WASM runtime implementations
@@ -195,7 +255,8 @@ impl WasmtimeRuntime {
     pub fn new(db_provider: Option<Arc<dyn DbProvider>>) -> FunctionResult<Self> {
         let mut config = Config::new();
         config.async_support(false); // Synchronous execution for now
-        config.consume_fuel(true); // Enable gas metering for timeouts
+        config.consume_fuel(true); // Instruction metering
+        config.epoch_interruption(true); // Wall-clock deadlines
 
         let engine = Engine::new(&config)
             .map_err(|e| FunctionError::RuntimeError(format!("Failed to create engine: {}", e)))?;
@@ -207,6 +268,125 @@ impl WasmtimeRuntime {
     }
 }
 
+/// Store state for one execution
+struct StoreData {
+    logs: Vec<String>,
+    context: ExecutionContext,
+    db_provider: Arc<dyn DbProvider>,
+    limiter: MemoryLimiter,
+}
+
+/// Caps the linear memory of one execution and records its peak
+struct MemoryLimiter {
+    max_bytes: usize,
+    used_bytes: usize,
+    peak_bytes: usize,
+    /// Whether a growth was refused
+    refused: bool,
+}
+
+impl ResourceLimiter for MemoryLimiter {
+    fn memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        let used = self.used_bytes - current + desired;
+        if used > self.max_bytes {
+            self.refused = true;
+            return Ok(false);
+        }
+        self.used_bytes = used;
+        self.peak_bytes = self.peak_bytes.max(used);
+        Ok(true)
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        Ok(desired <= MAX_TABLE_ELEMENTS)
+    }
+
+    fn instances(&self) -> usize {
+        1
+    }
+}
+
+impl WasmtimeRuntime {
+    /// Link the host functions of `HOST_FUNCTIONS`
+    fn linker(&self) -> FunctionResult<Linker<StoreData>> {
+        let mut linker = Linker::new(&self.engine);
+        linker
+            .func_wrap(
+                HOST_MODULE,
+                "log",
+                |mut caller: Caller<'_, StoreData>, ptr: i32, len: i32| -> wasmtime::Result<()> {
+                    let memory = caller
+                        .get_export("memory")
+                        .and_then(|export| export.into_memory())
+                        .ok_or_else(|| wasmtime::Error::msg("log needs an exported memory"))?;
+                    let mut bytes = vec![0; len as u32 as usize];
+                    memory.read(&caller, ptr as u32 as usize, &mut bytes)?;
+                    let logs = &mut caller.data_mut().logs;
+                    if logs.len() < MAX_LOG_ENTRIES {
+                        host::log(&String::from_utf8_lossy(&bytes), logs);
+                    }
+                    Ok(())
+                },
+            )
+            .map_err(|e| FunctionError::Internal(e.to_string()))?;
+        Ok(linker)
+    }
+}
+
+/// Refuse modules importing anything the host does not provide
+fn check_imports(module: &Module) -> FunctionResult<()> {
+    for import in module.imports() {
+        if import.module() != HOST_MODULE || !HOST_FUNCTIONS.contains(&import.name()) {
+            return Err(FunctionError::DisallowedImport(format!(
+                "{}.{}",
+                import.module(),
+                import.name()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Call the module's `handle` export, see the module docs
+fn call_handle(
+    store: &mut Store<StoreData>,
+    instance: &Instance,
+    input: &Value,
+) -> wasmtime::Result<Value> {
+    if let Ok(handle) = instance.get_typed_func::<(i32, i32), i64>(&mut *store, "handle") {
+        let memory = instance
+            .get_memory(&mut *store, "memory")
+            .ok_or_else(|| wasmtime::Error::msg("handle(ptr, len) needs an exported memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut *store, "alloc")?;
+
+        let input = serde_json::to_vec(input)?;
+        let len = i32::try_from(input.len())?;
+        let ptr = alloc.call(&mut *store, len)?;
+        memory.write(&mut *store, ptr as u32 as usize, &input)?;
+
+        let packed = handle.call(&mut *store, (ptr, len))? as u64;
+        let mut output = vec![0; (packed & 0xffff_ffff) as usize];
+        memory.read(&*store, (packed >> 32) as usize, &mut output)?;
+        Ok(serde_json::from_slice(&output)?)
+    } else if let Ok(handle) = instance.get_typed_func::<(), ()>(&mut *store, "handle") {
+        handle.call(&mut *store, ())?;
+        Ok(json!({"status": "executed"}))
+    } else {
+        // Modules without an entry point (e.g. empty ones) have nothing to run
+        Ok(json!({"status": "no_handle_exported"}))
+    }
+}
+
 impl WasmRuntime for WasmtimeRuntime {
     fn execute(
         &self,
@@ -222,73 +402,89 @@ impl WasmRuntime for WasmtimeRuntime {
             return Err(FunctionError::RuntimeError("Function is disabled".into()));
         }
 
-        // 1. Compile module
+        // The tighter of the runtime's and the function's limits
+        let timeout_ms = config.timeout_ms.min(function.config.timeout_ms);
+        let max_memory_bytes = config
+            .max_memory_bytes
+            .min(function.config.memory_mb as usize * 1024 * 1024);
+        let fuel = config.max_fuel.unwrap_or(u64::MAX);
+
+        // 1. Compile module and check what it imports
         let module = Module::new(&self.engine, &function.wasm_bytes).map_err(|e| {
             FunctionError::RuntimeError(format!("Failed to verify/compile module: {}", e))
         })?;
+        check_imports(&module)?;
 
-        // 2. Setup Store
-        struct StoreData {
-            logs: Vec<String>,
-            context: ExecutionContext,
-            db_provider: Arc<dyn DbProvider>,
-        }
-
+        // 2. Setup Store with its limits
         let data = StoreData {
             logs: Vec::new(),
             context,
             db_provider: self.db_provider.clone(),
+            limiter: MemoryLimiter {
+                max_bytes: max_memory_bytes,
+                used_bytes: 0,
+                peak_bytes: 0,
+                refused: false,
+            },
         };
-
         let mut store = Store::new(&self.engine, data);
+        store.limiter(|data| &mut data.limiter);
         store
-            .set_fuel(u64::MAX)
-            .map_err(|e| FunctionError::RuntimeError(e.to_string()))?; // TODO: Map milliseconds to fuel
-
-        // 3. Setup Linker (Host Functions)
-        let mut linker = Linker::new(&self.engine);
-
-        // Host logging: env.log(ptr, len)
-        // For simplicity in this iteration, we don't fully implement memory reading here
-        // as we haven't defined the memory export name.
-        // But we wire the linker to show intent.
-
-        // 4. Instantiate
-        let instance = linker
-            .instantiate(&mut store, &module)
-            .map_err(|e| FunctionError::RuntimeError(format!("Failed to instantiate: {}", e)))?;
-
-        // 5. Execute 'handle' function
-        // Note: This expects the WASM to export a function named "handle" or similar.
-        // For the minimal replacement, we check for a known entrypoint.
-        // If "handle" exists, call it. If not, maybe just "start".
-
-        let result_value =
-            if let Ok(handle) = instance.get_typed_func::<(), ()>(&mut store, "handle") {
-                handle
-                    .call(&mut store, ())
-                    .map_err(|e| FunctionError::RuntimeError(format!("Runtime error: {}", e)))?;
-                json!({"status": "executed"})
+            .set_fuel(fuel)
+            .map_err(|e| FunctionError::RuntimeError(e.to_string()))?;
+
+        // Other executions bump the shared epoch too, so the callback checks
+        // this execution's own deadline
+        let deadline = start + Duration::from_millis(timeout_ms);
+        store.set_epoch_deadline(1);
+        store.epoch_deadline_callback(move |_| {
+            if Instant::now() >= deadline {
+                Ok(UpdateDeadline::Interrupt)
             } else {
-                // For now, if no handle, we assume success for empty modules (like in verification)
-                // or fail for real ones.
-                // But to pass tests with minimal header, we might return success.
-                json!({"status": "no_handle_exported"})
-            };
+                Ok(UpdateDeadline::Continue(1))
+            }
+        });
+        let (done, finished) = mpsc::channel::<()>();
+        let engine = self.engine.clone();
+        let watchdog = thread::spawn(move || {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if let Err(RecvTimeoutError::Timeout) = finished.recv_timeout(remaining) {
+                engine.increment_epoch();
+            }
+        });
+
+        // 3. Instantiate against the host functions and run `handle`
+        let outcome = self
+            .linker()?
+            .instantiate(&mut store, &module)
+            .and_then(|instance| call_handle(&mut store, &instance, &input));
+        drop(done);
+        let _ = watchdog.join();
 
         let duration_ms = start.elapsed().as_millis() as u64;
-
-        // Check timeout
-        if duration_ms > config.timeout_ms {
-            return Err(FunctionError::Timeout(config.timeout_ms));
+        let fuel_consumed = fuel - store.get_fuel().unwrap_or(fuel);
+        let data = store.into_data();
+        let invocation_id = data.context.invocation_id;
+
+        match outcome {
+            Ok(value) => Ok(ExecutionResult::success(invocation_id, value, duration_ms)
+                .with_logs(data.logs)
+                .with_metering(data.limiter.peak_bytes, fuel_consumed)),
+            Err(e) => match e.downcast_ref::<Trap>() {
+                Some(Trap::Interrupt) => Err(FunctionError::Timeout(timeout_ms)),
+                Some(Trap::OutOfFuel) => Err(FunctionError::FuelExhausted(fuel)),
+                _ if data.limiter.refused => Err(FunctionError::MemoryExceeded(
+                    (max_memory_bytes / (1024 * 1024)) as u32,
+                )),
+                _ => Ok(ExecutionResult::failure(
+                    invocation_id,
+                    format!("Runtime error: {:#}", e),
+                    duration_ms,
+                )
+                .with_logs(data.logs)
+                .with_metering(data.limiter.peak_bytes, fuel_consumed)),
+            },
         }
-
-        Ok(ExecutionResult::success(
-            store.data().context.invocation_id,
-            result_value,
-            duration_ms,
-        )
-        .with_logs(store.data().logs.clone()))
     }
 
     fn is_available(&self) -> bool {
@@ -335,7 +531,7 @@ mod tests {
         Function::new(
             "test-func".to_string(),
             TriggerType::http("/test".to_string()),
-            vec![0, 97, 115, 109], // Minimal WASM header
+            b"\0asm\x01\0\0\0".to_vec(), // Empty module: magic and version only
         )
     }
 
@@ -347,13 +543,12 @@ mod tests {
         let config = RuntimeConfig::default();
 
         let input = json!({"message": "hello"});
-        // This will likely fail to compile standard invalid bytes or empty bytes if strict.
-        // The minimal header vec![0, 97, 115, 109] is a valid empty module.
-        // It won't have "handle", so it returns "no_handle_exported".
+        // The empty module compiles but exports no `handle` to run
         let result = runtime.execute(&function, input, context, &config).unwrap();
 
-        assert!(result.success);
-        // logs might be empty if no code ran
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.result, Some(json!({"status": "no_handle_exported"})));
+        assert!(result.logs.is_empty());
     }
 
     #[test]
@@ -382,6 +577,115 @@ mod tests {
         assert!(result.is_err());
     }
 
+    fn wat_function(wat: &str) -> Function {
+        let mut function = create_test_function();
+        function.wasm_bytes = wat.as_bytes().to_vec();
+        function
+    }
+
+    fn run(function: &Function, config: &RuntimeConfig) -> FunctionResult<ExecutionResult> {
+        let runtime = WasmtimeRuntime::new(None).unwrap();
+        let context = ExecutionContext::new(function, None);
+        runtime.execute(function, json!({"n": 1}), context, config)
+    }
+
+    #[test]
+    fn test_wasmtime_json_abi_and_logs() {
+        let function = wat_function(
+            r#"(module
+                (import "env" "log" (func $log (param i32 i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "hello")
+                (global $next (mut i32) (i32.const 1024))
+                (func (export "alloc") (param $len i32) (result i32)
+                    (local $ptr i32)
+                    (local.set $ptr (global.get $next))
+                    (global.set $next (i32.add (global.get $next) (local.get $len)))
+                    (local.get $ptr))
+                (func (export "handle") (param $ptr i32) (param $len i32) (result i64)
+                    (call $log (i32.const 0) (i32.const 5))
+                    (i64.or
+                        (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+                        (i64.extend_i32_u (local.get $len)))))"#,
+        );
+
+        let result = run(&function, &RuntimeConfig::default()).unwrap();
+        assert!(result.success);
+        assert_eq!(result.result, Some(json!({"n": 1})));
+        assert_eq!(result.logs, vec!["[log] hello".to_string()]);
+        assert_eq!(result.memory_used, 64 * 1024);
+        assert!(result.fuel_consumed > 0);
+    }
+
+    #[test]
+    fn test_wasmtime_limits() {
+        let spin = wat_function(r#"(module (func (export "handle") (loop $l (br $l))))"#);
+        let config = RuntimeConfig {
+            timeout_ms: 50,
+            ..RuntimeConfig::default()
+        };
+        assert!(matches!(
+            run(&spin, &config),
+            Err(FunctionError::Timeout(50))
+        ));
+
+        let config = RuntimeConfig {
+            max_fuel: Some(10_000),
+            ..RuntimeConfig::default()
+        };
+        assert!(matches!(
+            run(&spin, &config),
+            Err(FunctionError::FuelExhausted(10_000))
+        ));
+
+        let grow = wat_function(
+            r#"(module
+                (memory 1)
+                (func (export "handle")
+                    (if (i32.eq (memory.grow (i32.const 100)) (i32.const -1))
+                        (then unreachable))))"#,
+        );
+        let config = RuntimeConfig {
+            max_memory_bytes: 1024 * 1024,
+            ..RuntimeConfig::default()
+        };
+        assert!(matches!(
+            run(&grow, &config),
+            Err(FunctionError::MemoryExceeded(1))
+        ));
+    }
+
+    #[test]
+    fn test_wasmtime_refuses_other_imports() {
+        let function = wat_function(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write"
+                    (func (param i32 i32 i32 i32) (result i32))))"#,
+        );
+        assert!(matches!(
+            run(&function, &RuntimeConfig::default()),
+            Err(FunctionError::DisallowedImport(name)) if name == "wasi_snapshot_preview1.fd_write"
+        ));
+    }
+
+    #[test]
+    fn test_wasmtime_trap_keeps_logs() {
+        let function = wat_function(
+            r#"(module
+                (import "env" "log" (func $log (param i32 i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "before")
+                (func (export "handle")
+                    (call $log (i32.const 0) (i32.const 6))
+                    unreachable))"#,
+        );
+
+        let result = run(&function, &RuntimeConfig::default()).unwrap();
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("unreachable"));
+        assert_eq!(result.logs, vec!["[log] before".to_string()]);
+    }
+
     #[test]
     fn test_execution_context_with_env() {
         let function = create_test_function();
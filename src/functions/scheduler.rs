@@ -1,16 +1,63 @@
 //! # Function Scheduler
+//!
+//! Cron jobs for scheduled functions, persisted through a `JobStore`
+//! (`FileJobStore` keeps them in the data directory), so schedules and
+//! their last outcomes survive restarts.
+//!
+//! Next runs are computed from the cron expression alone: the occurrence
+//! after the run just made, or after the restart, never from when a run
+//! happened to finish. A job's `MissedRunPolicy` decides what happens to
+//! occurrences missed while the scheduler was down or a run overran:
+//! - `Skip`: they are dropped; the job next runs at its next occurrence
+//!   after now
+//! - `CatchUp`: each runs, oldest first, one per due check, until the job
+//!   is back on schedule
 
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
+use std::time::Instant;
 
 use chrono::{DateTime, Utc};
 use croner::Cron;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use uuid::Uuid;
 
 use super::errors::{FunctionError, FunctionResult};
+use super::invoker::{InvocationContext, Invoker};
+use super::registry::FunctionRegistry;
 use super::store::{JobStore, MemJobStore};
 
+/// What to do with runs missed while the scheduler was not running
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedRunPolicy {
+    /// Drop missed runs
+    #[default]
+    Skip,
+    /// Make every missed run, oldest first
+    CatchUp,
+}
+
+/// Outcome of one run of a job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunOutcome {
+    /// Occurrence the run was for
+    pub scheduled_for: DateTime<Utc>,
+
+    /// When the run started
+    pub started_at: DateTime<Utc>,
+
+    /// Whether the function succeeded
+    pub success: bool,
+
+    /// Error message (if failed)
+    pub error: Option<String>,
+
+    /// Run duration in milliseconds
+    pub duration_ms: u64,
+}
+
 /// A scheduled job
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScheduledJob {
@@ -31,49 +78,96 @@ pub struct ScheduledJob {
 
     /// Whether the job is enabled
     pub enabled: bool,
+
+    /// What to do with missed runs
+    #[serde(default)]
+    pub missed_runs: MissedRunPolicy,
+
+    /// Outcome of the last run
+    #[serde(default)]
+    pub last_outcome: Option<RunOutcome>,
 }
 
 impl ScheduledJob {
     /// Create a new scheduled job
     pub fn new(function_name: String, cron: String) -> FunctionResult<Self> {
-        // Validate cron expression
-        let cron_parser = Cron::new(&cron).parse().map_err(|e| {
-            FunctionError::InvalidCron(format!("Invalid cron expression '{}': {}", cron, e))
-        })?;
-
-        let next_run = cron_parser
-            .find_next_occurrence(&Utc::now(), false)
-            .map(|dt| dt.with_timezone(&Utc))
-            .map_err(|e| {
-                FunctionError::InvalidCron(format!("Error calculating next run: {}", e))
-            })?;
-
-        Ok(Self {
+        let mut job = Self {
             id: Uuid::new_v4(),
             function_name,
             cron,
             last_run: None,
-            next_run: Some(next_run),
+            next_run: None,
             enabled: true,
-        })
+            missed_runs: MissedRunPolicy::default(),
+            last_outcome: None,
+        };
+        // Validates the cron expression
+        job.next_run =
+            Some(job.next_after(Utc::now())?.ok_or_else(|| {
+                FunctionError::InvalidCron(format!("'{}' never occurs", job.cron))
+            })?);
+        Ok(job)
     }
 
-    /// Mark as run
-    pub fn mark_run(&mut self) -> FunctionResult<()> {
-        self.last_run = Some(Utc::now());
+    /// Use `policy` for missed runs
+    pub fn with_missed_runs(mut self, policy: MissedRunPolicy) -> Self {
+        self.missed_runs = policy;
+        self
+    }
 
-        // Calculate next run
+    /// First occurrence of the cron expression strictly after `after`
+    pub fn next_after(&self, after: DateTime<Utc>) -> FunctionResult<Option<DateTime<Utc>>> {
         let cron_parser = Cron::new(&self.cron).parse().map_err(|e| {
             FunctionError::InvalidCron(format!("Invalid cron expression '{}': {}", self.cron, e))
         })?;
-
-        self.next_run = cron_parser
-            .find_next_occurrence(&Utc::now(), false)
+        Ok(cron_parser
+            .find_next_occurrence(&after, false)
             .map(|dt| dt.with_timezone(&Utc))
-            .ok();
+            .ok())
+    }
 
+    /// Mark as run, successfully, for its current occurrence
+    pub fn mark_run(&mut self) -> FunctionResult<()> {
+        let now = Utc::now();
+        self.complete_run(
+            RunOutcome {
+                scheduled_for: self.next_run.unwrap_or(now),
+                started_at: now,
+                success: true,
+                error: None,
+                duration_ms: 0,
+            },
+            now,
+        )
+    }
+
+    /// Record `outcome` and schedule the next run, as of `now`
+    ///
+    /// The next run follows the occurrence the run was for; under `Skip`,
+    /// occurrences already past `now` are dropped.
+    pub fn complete_run(&mut self, outcome: RunOutcome, now: DateTime<Utc>) -> FunctionResult<()> {
+        let after = match self.missed_runs {
+            MissedRunPolicy::Skip => outcome.scheduled_for.max(now),
+            MissedRunPolicy::CatchUp => outcome.scheduled_for,
+        };
+        self.next_run = self.next_after(after)?;
+        self.last_run = Some(outcome.started_at);
+        self.last_outcome = Some(outcome);
         Ok(())
     }
+
+    /// Apply the missed-run policy to runs missed before `now`
+    ///
+    /// Returns whether the next run changed.
+    fn reschedule_missed(&mut self, now: DateTime<Utc>) -> FunctionResult<bool> {
+        match (self.missed_runs, self.next_run) {
+            (MissedRunPolicy::Skip, Some(next)) if next < now => {
+                self.next_run = self.next_after(now)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
 }
 
 /// Job scheduler
@@ -97,25 +191,40 @@ impl Default for Scheduler {
 
 impl Scheduler {
     /// Create a new scheduler with specific store
+    ///
+    /// A store that fails to load leaves the scheduler empty; use `load`
+    /// to see the error instead.
     pub fn new(store: Arc<dyn JobStore>) -> Self {
-        let jobs = store.load().unwrap_or_else(|e| {
+        Self::load(store.clone()).unwrap_or_else(|e| {
             eprintln!("Failed to load jobs from store: {}", e);
-            Vec::new()
-        });
+            Self {
+                jobs: RwLock::default(),
+                by_function: RwLock::default(),
+                store,
+            }
+        })
+    }
 
+    /// Load the jobs in `store`, applying their missed-run policies to runs
+    /// missed while the scheduler was down
+    pub fn load(store: Arc<dyn JobStore>) -> FunctionResult<Self> {
+        let now = Utc::now();
         let mut jobs_map = HashMap::new();
         let mut by_function = HashMap::new();
 
-        for job in jobs {
+        for mut job in store.load()? {
+            if job.reschedule_missed(now)? {
+                store.save(&job)?;
+            }
             by_function.insert(job.function_name.clone(), job.id);
             jobs_map.insert(job.id, job);
         }
 
-        Self {
+        Ok(Self {
             jobs: RwLock::new(jobs_map),
             by_function: RwLock::new(by_function),
             store,
-        }
+        })
     }
 
     /// Add a scheduled job
@@ -171,9 +280,13 @@ impl Scheduler {
 
     /// Get jobs that are due to run
     pub fn get_due_jobs(&self) -> Vec<ScheduledJob> {
-        let now = Utc::now();
+        self.due_jobs_at(Utc::now())
+    }
 
-        self.jobs
+    /// Jobs due at `now`, oldest occurrence first (ties by job ID)
+    pub fn due_jobs_at(&self, now: DateTime<Utc>) -> Vec<ScheduledJob> {
+        let mut due: Vec<ScheduledJob> = self
+            .jobs
             .read()
             .map(|jobs| {
                 jobs.values()
@@ -181,7 +294,70 @@ impl Scheduler {
                     .cloned()
                     .collect()
             })
-            .unwrap_or_default()
+            .unwrap_or_default();
+        due.sort_by_key(|j| (j.next_run, j.id));
+        due
+    }
+
+    /// Get a job
+    pub fn get(&self, job_id: Uuid) -> Option<ScheduledJob> {
+        self.jobs.read().ok()?.get(&job_id).cloned()
+    }
+
+    /// Record the outcome of a run of a job and schedule its next run
+    pub fn record_run(&self, job_id: Uuid, outcome: RunOutcome) -> FunctionResult<()> {
+        let mut jobs = self
+            .jobs
+            .write()
+            .map_err(|_| FunctionError::Internal("Lock poisoned".into()))?;
+        let job = jobs
+            .get_mut(&job_id)
+            .ok_or_else(|| FunctionError::NotFound(job_id.to_string()))?;
+        let mut updated = job.clone();
+        updated.complete_run(outcome, Utc::now())?;
+        // Persist before the run counts as made
+        self.store.save(&updated)?;
+        *job = updated;
+        Ok(())
+    }
+
+    /// Run the functions of the jobs due now, each once, and record their
+    /// outcomes
+    ///
+    /// A job whose function is not registered records a failed run.
+    pub fn run_due(
+        &self,
+        registry: &FunctionRegistry,
+        invoker: &Invoker,
+    ) -> FunctionResult<Vec<(Uuid, RunOutcome)>> {
+        let mut outcomes = Vec::new();
+        for job in self.get_due_jobs() {
+            let scheduled_for = job.next_run.expect("Due jobs have a next run");
+            let started_at = Utc::now();
+            let started = Instant::now();
+            let result = registry.get(&job.function_name).and_then(|function| {
+                let payload = json!({
+                    "job_id": job.id,
+                    "scheduled_for": scheduled_for,
+                });
+                invoker.invoke(&function, InvocationContext::new(&function, payload, None))
+            });
+            let error = match result {
+                Ok(result) if result.success => None,
+                Ok(result) => Some(result.error.unwrap_or_default()),
+                Err(e) => Some(e.to_string()),
+            };
+            let outcome = RunOutcome {
+                scheduled_for,
+                started_at,
+                success: error.is_none(),
+                error,
+                duration_ms: started.elapsed().as_millis() as u64,
+            };
+            self.record_run(job.id, outcome.clone())?;
+            outcomes.push((job.id, outcome));
+        }
+        Ok(outcomes)
     }
 
     /// Mark a job as run
@@ -221,6 +397,7 @@ impl Scheduler {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::functions::store::FileJobStore;
 
     #[test]
     fn test_scheduled_job_creation() {
@@ -263,4 +440,78 @@ mod tests {
         let due = scheduler.get_due_jobs();
         assert_eq!(due.len(), 1);
     }
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        chrono::TimeZone::with_ymd_and_hms(&Utc, 2024, 1, 1, hour, 0, 0).unwrap()
+    }
+
+    fn outcome(scheduled_for: DateTime<Utc>) -> RunOutcome {
+        RunOutcome {
+            scheduled_for,
+            started_at: scheduled_for,
+            success: true,
+            error: None,
+            duration_ms: 1,
+        }
+    }
+
+    #[test]
+    fn test_next_run_follows_missed_run_policy() {
+        let mut job = ScheduledJob::new("hourly".to_string(), "0 * * * *".to_string())
+            .unwrap()
+            .with_missed_runs(MissedRunPolicy::CatchUp);
+
+        // Caught up one occurrence at a time, whenever the run finished
+        job.complete_run(outcome(at(1)), at(5)).unwrap();
+        assert_eq!(job.next_run, Some(at(2)));
+
+        // Skipped straight to the occurrence after now
+        job.missed_runs = MissedRunPolicy::Skip;
+        job.complete_run(outcome(at(2)), at(5)).unwrap();
+        assert_eq!(job.next_run, Some(at(6)));
+        assert_eq!(job.last_outcome, Some(outcome(at(2))));
+    }
+
+    #[test]
+    fn test_schedules_and_outcomes_survive_restart() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = Arc::new(FileJobStore::open(dir.path()));
+        let scheduler = Scheduler::load(store.clone()).unwrap();
+
+        let mut skip = ScheduledJob::new("skip".to_string(), "0 * * * *".to_string()).unwrap();
+        skip.next_run = Some(at(1));
+        let mut catch_up = ScheduledJob::new("catch_up".to_string(), "0 * * * *".to_string())
+            .unwrap()
+            .with_missed_runs(MissedRunPolicy::CatchUp);
+        catch_up.next_run = Some(at(1));
+        let (skip, catch_up) = (
+            scheduler.schedule(skip).unwrap(),
+            scheduler.schedule(catch_up).unwrap(),
+        );
+
+        let registry = FunctionRegistry::new();
+        registry
+            .register(crate::functions::Function::new(
+                "catch_up".to_string(),
+                crate::functions::TriggerType::schedule("0 * * * *".to_string()),
+                vec![0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00],
+            ))
+            .unwrap();
+        // "skip" has no function, so its run fails
+        let outcomes = scheduler.run_due(&registry, &Invoker::new()).unwrap();
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(scheduler.get(catch_up).unwrap().next_run, Some(at(2)));
+
+        let scheduler = Scheduler::load(Arc::new(FileJobStore::open(dir.path()))).unwrap();
+        let skip = scheduler.get(skip).unwrap();
+        assert!(skip.next_run.unwrap() > Utc::now());
+        let outcome = skip.last_outcome.unwrap();
+        assert!(!outcome.success);
+        assert_eq!(outcome.scheduled_for, at(1));
+
+        let catch_up = scheduler.get(catch_up).unwrap();
+        assert!(catch_up.last_outcome.unwrap().success);
+        assert_eq!(catch_up.next_run, Some(at(2)));
+        assert_eq!(scheduler.due_jobs_at(at(3)).len(), 1);
+    }
 }
@@ -0,0 +1,454 @@
+//! # Function Secrets
+//!
+//! Named secrets, sealed at rest and versioned. Setting a secret adds a new
+//! version and leaves the older ones readable. Functions bind secrets into
+//! their environment through `FunctionConfig::secrets`, mapping a variable
+//! name to `name` (the latest version) or `name@version` (pinned).
+//!
+//! Each version is sealed with AES-256-GCM under the active key of a
+//! `KeyProvider`, normally the keyring in `keys/secrets/`
+//! (`FieldKeyring::open_secrets`). The secret name and version are bound in
+//! as associated data, so a sealed value copied to another secret or version
+//! fails to open. Rotating the key seals new versions under the new key;
+//! older versions need their key kept.
+//!
+//! `SecretStore::open` keeps the sealed versions in
+//! `<data_dir>/function_secrets.json`; values never reach disk in the clear.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use serde::{Deserialize, Serialize};
+
+use super::errors::{FunctionError, FunctionResult};
+use crate::storage::{EncryptionKey, FieldKeyring, KeyProvider};
+
+/// Secrets file name within the data directory
+pub const SECRETS_FILE: &str = "function_secrets.json";
+
+/// Longest secret name accepted
+pub const MAX_SECRET_NAME_LEN: usize = 64;
+
+const AAD_PREFIX: &[u8] = b"aerodb-secret:v1";
+
+/// One sealed version of a secret
+#[derive(Clone, Serialize, Deserialize)]
+struct SealedVersion {
+    version: u32,
+    /// Id of the key the value is sealed under
+    key_id: String,
+    /// base64 of nonce | ciphertext | tag
+    data: String,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct SecretRecord {
+    name: String,
+    /// Oldest first
+    versions: Vec<SealedVersion>,
+}
+
+/// What is known about a secret without opening it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SecretInfo {
+    pub name: String,
+    /// Version a binding without `@version` resolves to
+    pub latest_version: u32,
+    /// Every stored version, oldest first
+    pub versions: Vec<u32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<&SecretRecord> for SecretInfo {
+    fn from(record: &SecretRecord) -> Self {
+        let first = record.versions.first().expect("Secrets keep a version");
+        let last = record.versions.last().expect("Secrets keep a version");
+        Self {
+            name: record.name.clone(),
+            latest_version: last.version,
+            versions: record.versions.iter().map(|v| v.version).collect(),
+            created_at: first.created_at,
+            updated_at: last.created_at,
+        }
+    }
+}
+
+/// A binding's reference to a secret: `name` or `name@version`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretRef {
+    pub name: String,
+    /// `None` for the latest version
+    pub version: Option<u32>,
+}
+
+impl SecretRef {
+    /// Parse `name` or `name@version`
+    pub fn parse(reference: &str) -> FunctionResult<Self> {
+        let (name, version) = match reference.split_once('@') {
+            Some((name, version)) => {
+                let version = version.parse().map_err(|_| {
+                    FunctionError::InvalidSecret(format!(
+                        "bad version in secret reference {:?}",
+                        reference
+                    ))
+                })?;
+                (name, Some(version))
+            }
+            None => (reference, None),
+        };
+        validate_name(name)?;
+        Ok(Self {
+            name: name.to_string(),
+            version,
+        })
+    }
+}
+
+/// Versioned function secrets, sealed in memory and on disk
+pub struct SecretStore {
+    /// Secrets file, or `None` to keep secrets in memory
+    path: Option<PathBuf>,
+    keys: Arc<dyn KeyProvider>,
+    secrets: RwLock<BTreeMap<String, SecretRecord>>,
+}
+
+impl fmt::Debug for SecretStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretStore")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for SecretStore {
+    fn default() -> Self {
+        Self::new(Arc::new(FieldKeyring::new()))
+    }
+}
+
+impl SecretStore {
+    /// Store kept in memory, sealing under `keys`
+    pub fn new(keys: Arc<dyn KeyProvider>) -> Self {
+        Self {
+            path: None,
+            keys,
+            secrets: RwLock::default(),
+        }
+    }
+
+    /// Open the store in `<data_dir>/function_secrets.json`, sealing under
+    /// `keys`
+    ///
+    /// A data directory without the file has no secrets.
+    pub fn open(data_dir: &Path, keys: Arc<dyn KeyProvider>) -> FunctionResult<Self> {
+        let path = data_dir.join(SECRETS_FILE);
+        let records: Vec<SecretRecord> = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| {
+                FunctionError::Internal(format!("{} is malformed: {}", path.display(), e))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                return Err(FunctionError::Internal(format!(
+                    "{}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+        Ok(Self {
+            path: Some(path),
+            keys,
+            secrets: RwLock::new(
+                records
+                    .into_iter()
+                    .map(|record| (record.name.clone(), record))
+                    .collect(),
+            ),
+        })
+    }
+
+    /// Store `value` as the next version of `name`, creating the secret if
+    /// needed
+    pub fn set(&self, name: &str, value: &str) -> FunctionResult<SecretInfo> {
+        validate_name(name)?;
+        let key_id = self
+            .keys
+            .active_key_id()
+            .ok_or_else(|| FunctionError::Internal("No active secret key".to_string()))?;
+        let key = self.key(&key_id)?;
+
+        self.update(|secrets| {
+            let record = secrets
+                .entry(name.to_string())
+                .or_insert_with(|| SecretRecord {
+                    name: name.to_string(),
+                    versions: Vec::new(),
+                });
+            let version = record.versions.last().map_or(1, |v| v.version + 1);
+            record.versions.push(SealedVersion {
+                version,
+                data: seal(&key, name, version, value.as_bytes()),
+                key_id,
+                created_at: Utc::now(),
+            });
+            Ok(SecretInfo::from(&*record))
+        })
+    }
+
+    /// Value of `name` at `version`, or its latest version
+    pub fn get(&self, name: &str, version: Option<u32>) -> FunctionResult<String> {
+        let sealed = {
+            let secrets = self.secrets.read().unwrap_or_else(|e| e.into_inner());
+            let record = secrets
+                .get(name)
+                .ok_or_else(|| FunctionError::SecretNotFound(name.to_string()))?;
+            let sealed = match version {
+                Some(version) => record.versions.iter().find(|v| v.version == version),
+                None => record.versions.last(),
+            };
+            sealed.cloned().ok_or_else(|| {
+                FunctionError::SecretNotFound(
+                    version.map_or(name.to_string(), |v| format!("{}@{}", name, v)),
+                )
+            })?
+        };
+
+        let key = self.key(&sealed.key_id)?;
+        let plaintext = open(&key, name, &sealed)?;
+        String::from_utf8(plaintext).map_err(|_| {
+            FunctionError::Internal(format!("Secret {}@{} is not UTF-8", name, sealed.version))
+        })
+    }
+
+    /// Every secret, by name; values are not opened
+    pub fn list(&self) -> Vec<SecretInfo> {
+        let secrets = self.secrets.read().unwrap_or_else(|e| e.into_inner());
+        secrets.values().map(SecretInfo::from).collect()
+    }
+
+    /// Delete `name` and all its versions
+    pub fn delete(&self, name: &str) -> FunctionResult<SecretInfo> {
+        self.update(|secrets| {
+            secrets
+                .remove(name)
+                .map(|record| SecretInfo::from(&record))
+                .ok_or_else(|| FunctionError::SecretNotFound(name.to_string()))
+        })
+    }
+
+    /// Open the secrets `bindings` map variable names to
+    /// (`FunctionConfig::secrets`)
+    ///
+    /// # Errors
+    ///
+    /// `SecretNotFound` if a binding names a secret or version that is not
+    /// stored; the function should not run without it.
+    pub fn resolve(
+        &self,
+        bindings: &HashMap<String, String>,
+    ) -> FunctionResult<HashMap<String, String>> {
+        bindings
+            .iter()
+            .map(|(variable, reference)| {
+                let reference = SecretRef::parse(reference)?;
+                let value = self.get(&reference.name, reference.version)?;
+                Ok((variable.clone(), value))
+            })
+            .collect()
+    }
+
+    fn key(&self, key_id: &str) -> FunctionResult<EncryptionKey> {
+        self.keys
+            .key(key_id)
+            .ok_or_else(|| FunctionError::Internal(format!("Unknown secret key: {}", key_id)))
+    }
+
+    /// Apply `change` to a copy of the secrets, write it through, then keep it
+    fn update<T>(
+        &self,
+        change: impl FnOnce(&mut BTreeMap<String, SecretRecord>) -> FunctionResult<T>,
+    ) -> FunctionResult<T> {
+        let mut secrets = self.secrets.write().unwrap_or_else(|e| e.into_inner());
+        let mut updated = secrets.clone();
+        let result = change(&mut updated)?;
+        if let Some(path) = &self.path {
+            write_secrets(path, &updated.values().collect::<Vec<_>>())
+                .map_err(|e| FunctionError::Internal(format!("{}: {}", path.display(), e)))?;
+        }
+        *secrets = updated;
+        Ok(result)
+    }
+}
+
+/// Secret names are kept to `[A-Za-z0-9_-]`, so `@` can mark a version
+fn validate_name(name: &str) -> FunctionResult<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_SECRET_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(FunctionError::InvalidSecret(format!(
+            "invalid secret name {:?}",
+            name
+        )))
+    }
+}
+
+fn associated_data(name: &str, version: u32) -> Vec<u8> {
+    let mut aad = AAD_PREFIX.to_vec();
+    aad.push(0);
+    aad.extend_from_slice(name.as_bytes());
+    aad.push(0);
+    aad.extend_from_slice(&version.to_le_bytes());
+    aad
+}
+
+fn aead_key(key: &EncryptionKey) -> LessSafeKey {
+    LessSafeKey::new(UnboundKey::new(&AES_256_GCM, key.as_bytes()).expect("Keys are 256 bits"))
+}
+
+fn seal(key: &EncryptionKey, name: &str, version: u32, plaintext: &[u8]) -> String {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let mut in_out = plaintext.to_vec();
+    aead_key(key)
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(associated_data(name, version)),
+            &mut in_out,
+        )
+        .expect("Secret values fit in one AES-GCM message");
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&in_out);
+    STANDARD.encode(sealed)
+}
+
+fn open(key: &EncryptionKey, name: &str, sealed: &SealedVersion) -> FunctionResult<Vec<u8>> {
+    let failed =
+        || FunctionError::Internal(format!("Secret {}@{} failed to open", name, sealed.version));
+    let mut bytes = STANDARD.decode(&sealed.data).map_err(|_| failed())?;
+    if bytes.len() < NONCE_LEN {
+        return Err(failed());
+    }
+    let mut in_out = bytes.split_off(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(&bytes).map_err(|_| failed())?;
+    let plaintext = aead_key(key)
+        .open_in_place(
+            nonce,
+            Aad::from(associated_data(name, sealed.version)),
+            &mut in_out,
+        )
+        .map_err(|_| failed())?;
+    Ok(plaintext.to_vec())
+}
+
+/// Write `records` to a temporary file, fsync it, and rename it over `path`
+fn write_secrets(path: &Path, records: &[&SecretRecord]) -> std::io::Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(&serde_json::to_vec_pretty(records)?)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn keyring() -> Arc<FieldKeyring> {
+        let keys = FieldKeyring::new();
+        keys.rotate("s1").unwrap();
+        Arc::new(keys)
+    }
+
+    #[test]
+    fn test_versions_and_bindings() {
+        let store = SecretStore::new(keyring());
+        store.set("stripe", "sk_old").unwrap();
+        let info = store.set("stripe", "sk_new").unwrap();
+        assert_eq!(info.latest_version, 2);
+        assert_eq!(info.versions, vec![1, 2]);
+
+        assert_eq!(store.get("stripe", None).unwrap(), "sk_new");
+        assert_eq!(store.get("stripe", Some(1)).unwrap(), "sk_old");
+        assert!(matches!(
+            store.get("stripe", Some(3)),
+            Err(FunctionError::SecretNotFound(_))
+        ));
+
+        let bindings = HashMap::from([
+            ("STRIPE_KEY".to_string(), "stripe".to_string()),
+            ("STRIPE_KEY_V1".to_string(), "stripe@1".to_string()),
+        ]);
+        let env = store.resolve(&bindings).unwrap();
+        assert_eq!(env["STRIPE_KEY"], "sk_new");
+        assert_eq!(env["STRIPE_KEY_V1"], "sk_old");
+
+        store.delete("stripe").unwrap();
+        assert!(matches!(
+            store.resolve(&bindings),
+            Err(FunctionError::SecretNotFound(_))
+        ));
+        assert!(matches!(
+            store.set("bad@name", "x"),
+            Err(FunctionError::InvalidSecret(_))
+        ));
+    }
+
+    #[test]
+    fn test_sealed_on_disk_and_reopened() {
+        let dir = TempDir::new().unwrap();
+        let keys = Arc::new(FieldKeyring::open_secrets(dir.path()).unwrap());
+        keys.rotate("s1").unwrap();
+
+        let store = SecretStore::open(dir.path(), keys.clone()).unwrap();
+        store.set("db_password", "hunter2").unwrap();
+        keys.rotate("s2").unwrap();
+        store.set("db_password", "correct horse").unwrap();
+
+        let on_disk = fs::read_to_string(dir.path().join(SECRETS_FILE)).unwrap();
+        assert!(!on_disk.contains("hunter2"));
+        assert!(!on_disk.contains("correct horse"));
+
+        let keys = Arc::new(FieldKeyring::open_secrets(dir.path()).unwrap());
+        let reopened = SecretStore::open(dir.path(), keys).unwrap();
+        assert_eq!(reopened.get("db_password", Some(1)).unwrap(), "hunter2");
+        assert_eq!(reopened.get("db_password", None).unwrap(), "correct horse");
+        assert_eq!(reopened.list()[0].versions, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_sealed_value_bound_to_name_and_version() {
+        let store = SecretStore::new(keyring());
+        store.set("a", "alpha").unwrap();
+        store.set("b", "beta").unwrap();
+
+        // Move a's sealed value into b
+        {
+            let mut secrets = store.secrets.write().unwrap();
+            let sealed = secrets["a"].versions[0].data.clone();
+            secrets.get_mut("b").unwrap().versions[0].data = sealed;
+        }
+        assert!(matches!(
+            store.get("b", None),
+            Err(FunctionError::Internal(_))
+        ));
+    }
+}
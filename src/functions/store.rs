@@ -2,7 +2,8 @@
 //!
 //! Durable storage for scheduled jobs.
 
-use std::fs;
+use std::fs::{self, File};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
@@ -21,6 +22,9 @@ pub trait JobStore: Send + Sync + std::fmt::Debug {
     fn delete(&self, job_id: &Uuid) -> FunctionResult<()>;
 }
 
+/// Job store file name within the data directory
+pub const SCHEDULES_FILE: &str = "function_schedules.json";
+
 /// JSON file-based job store
 #[derive(Debug)]
 pub struct FileJobStore {
@@ -34,6 +38,11 @@ impl FileJobStore {
         }
     }
 
+    /// Job store in `<data_dir>/function_schedules.json`
+    pub fn open(data_dir: &Path) -> Self {
+        Self::new(data_dir.join(SCHEDULES_FILE))
+    }
+
     fn load_jobs(&self) -> FunctionResult<Vec<ScheduledJob>> {
         if !self.path.exists() {
             return Ok(Vec::new());
@@ -60,8 +69,15 @@ impl FileJobStore {
             })?;
         }
 
-        fs::write(&self.path, content)
-            .map_err(|e| FunctionError::Internal(format!("Failed to write job store: {}", e)))
+        // Write to a temporary file, fsync it, and rename it over the store,
+        // so a crash leaves either the old jobs or the new ones
+        let tmp = self.path.with_extension("json.tmp");
+        let written = File::create(&tmp).and_then(|mut file| {
+            file.write_all(content.as_bytes())?;
+            file.sync_all()?;
+            fs::rename(&tmp, &self.path)
+        });
+        written.map_err(|e| FunctionError::Internal(format!("Failed to write job store: {}", e)))
     }
 }
 
@@ -0,0 +1,351 @@
+//! # Database Trigger Queue
+//!
+//! Runs functions bound to database events after writes commit.
+//! `DbTriggers` is an `ApiHandler` change sink: each committed change is
+//! matched against the registered `TriggerType::Database` bindings for its
+//! collection and event, and one delivery per matching function is appended
+//! to a durable queue before the write returns. `DbTriggers::deliver`
+//! invokes queued deliveries in enqueue order, outside the write path.
+//!
+//! Delivery is at least once: a delivery leaves the queue only after its
+//! invocation succeeds, so a crash in between runs it again. A failed
+//! delivery is retried on later passes, up to its function's `max_retries`,
+//! then moved to the dead-letter list.
+//!
+//! `TriggerQueue::open` keeps the queue in
+//! `<data_dir>/function_triggers.json`. A crash between a write's commit
+//! and its enqueue loses that write's deliveries.
+
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::errors::{FunctionError, FunctionResult};
+use super::invoker::{InvocationContext, Invoker};
+use super::registry::FunctionRegistry;
+use super::trigger::{DbEventType, TriggerType};
+use crate::api::{ChangeKind, ChangeSink, DocumentChange};
+
+/// Queue file name within the data directory
+pub const TRIGGER_QUEUE_FILE: &str = "function_triggers.json";
+
+impl From<ChangeKind> for DbEventType {
+    fn from(kind: ChangeKind) -> Self {
+        match kind {
+            ChangeKind::Insert => DbEventType::Insert,
+            ChangeKind::Update => DbEventType::Update,
+            ChangeKind::Delete => DbEventType::Delete,
+        }
+    }
+}
+
+/// One function to run for one committed change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TriggerDelivery {
+    /// Position in enqueue order
+    pub seq: u64,
+    pub function_id: Uuid,
+    pub function_name: String,
+    /// The change, passed to the function as its payload
+    pub change: DocumentChange,
+    /// Failed invocations so far
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub enqueued_at: DateTime<Utc>,
+}
+
+/// Outcome of a delivery pass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeliveryReport {
+    /// Invoked successfully and removed from the queue
+    pub delivered: usize,
+    /// Failed, left queued for another attempt
+    pub retrying: usize,
+    /// Failed for the last time, moved to the dead-letter list
+    pub dead: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct QueueState {
+    next_seq: u64,
+    pending: VecDeque<TriggerDelivery>,
+    dead: Vec<TriggerDelivery>,
+}
+
+/// Durable queue of trigger deliveries
+#[derive(Debug, Default)]
+pub struct TriggerQueue {
+    /// Queue file, or `None` to keep the queue in memory
+    path: Option<PathBuf>,
+    state: Mutex<QueueState>,
+}
+
+impl TriggerQueue {
+    /// Queue kept in memory
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open the queue in `<data_dir>/function_triggers.json`
+    ///
+    /// Deliveries left pending by a previous run are delivered again.
+    pub fn open(data_dir: &Path) -> FunctionResult<Self> {
+        let path = data_dir.join(TRIGGER_QUEUE_FILE);
+        let state = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| {
+                FunctionError::Internal(format!("{} is malformed: {}", path.display(), e))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => QueueState::default(),
+            Err(e) => {
+                return Err(FunctionError::Internal(format!(
+                    "{}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+        Ok(Self {
+            path: Some(path),
+            state: Mutex::new(state),
+        })
+    }
+
+    /// Deliveries waiting to run, in enqueue order
+    pub fn pending(&self) -> Vec<TriggerDelivery> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.pending.iter().cloned().collect()
+    }
+
+    /// Deliveries that ran out of retries
+    pub fn dead(&self) -> Vec<TriggerDelivery> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.dead.clone()
+    }
+
+    /// Append deliveries, each a function's id and name and its change
+    fn enqueue(&self, deliveries: Vec<(Uuid, String, DocumentChange)>) -> FunctionResult<()> {
+        if deliveries.is_empty() {
+            return Ok(());
+        }
+        self.update(|state| {
+            for (function_id, function_name, change) in deliveries {
+                let seq = state.next_seq;
+                state.next_seq += 1;
+                state.pending.push_back(TriggerDelivery {
+                    seq,
+                    function_id,
+                    function_name,
+                    change,
+                    attempts: 0,
+                    last_error: None,
+                    enqueued_at: Utc::now(),
+                });
+            }
+        })
+    }
+
+    /// Apply `change` to a copy of the state, write it through, then keep it
+    fn update<T>(&self, change: impl FnOnce(&mut QueueState) -> T) -> FunctionResult<T> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let mut updated = state.clone();
+        let result = change(&mut updated);
+        if let Some(path) = &self.path {
+            write_queue(path, &updated)
+                .map_err(|e| FunctionError::Internal(format!("{}: {}", path.display(), e)))?;
+        }
+        *state = updated;
+        Ok(result)
+    }
+}
+
+/// Database triggers: enqueues committed changes for the functions bound
+/// to them and delivers them
+#[derive(Debug)]
+pub struct DbTriggers {
+    registry: Arc<FunctionRegistry>,
+    queue: Arc<TriggerQueue>,
+}
+
+impl DbTriggers {
+    /// Triggers of the functions in `registry`, queued in `queue`
+    pub fn new(registry: Arc<FunctionRegistry>, queue: Arc<TriggerQueue>) -> Self {
+        Self { registry, queue }
+    }
+
+    /// The delivery queue
+    pub fn queue(&self) -> &Arc<TriggerQueue> {
+        &self.queue
+    }
+
+    /// Queue a delivery of each change to every function bound to its
+    /// collection and event, in registration order
+    pub fn enqueue(&self, changes: &[DocumentChange]) -> FunctionResult<()> {
+        let deliveries = changes
+            .iter()
+            .flat_map(|change| {
+                let trigger = TriggerType::database(change.collection.clone(), change.kind.into());
+                self.registry
+                    .get_by_trigger(&trigger)
+                    .into_iter()
+                    .map(move |function| (function.id, function.name, change.clone()))
+            })
+            .collect();
+        self.queue.enqueue(deliveries)
+    }
+
+    /// Run every queued delivery once, in enqueue order
+    ///
+    /// Successful deliveries leave the queue. Failed ones stay queued for
+    /// the next pass until their function's retries are used up. A
+    /// delivery whose function is gone is dead at once.
+    pub fn deliver(&self, invoker: &Invoker) -> FunctionResult<DeliveryReport> {
+        let mut report = DeliveryReport::default();
+        for delivery in self.queue.pending() {
+            let outcome = match self.registry.get(&delivery.function_name) {
+                Ok(function) if function.id == delivery.function_id => {
+                    let payload = serde_json::to_value(&delivery.change)
+                        .map_err(|e| FunctionError::Internal(e.to_string()))?;
+                    let context = InvocationContext::new(&function, payload, None);
+                    let retries = function.config.max_retries;
+                    match invoker.invoke(&function, context) {
+                        Ok(result) if result.success => Ok(()),
+                        Ok(result) => Err((result.error.unwrap_or_default(), retries)),
+                        Err(e) => Err((e.to_string(), retries)),
+                    }
+                }
+                _ => Err((format!("Function removed: {}", delivery.function_name), 0)),
+            };
+
+            self.queue.update(|state| {
+                let Some(index) = state.pending.iter().position(|d| d.seq == delivery.seq) else {
+                    return;
+                };
+                match outcome {
+                    Ok(()) => {
+                        state.pending.remove(index);
+                        report.delivered += 1;
+                    }
+                    Err((error, retries)) => {
+                        let queued = &mut state.pending[index];
+                        queued.attempts += 1;
+                        queued.last_error = Some(error);
+                        if queued.attempts > retries {
+                            let dead = state.pending.remove(index).expect("Index was found");
+                            state.dead.push(dead);
+                            report.dead += 1;
+                        } else {
+                            report.retrying += 1;
+                        }
+                    }
+                }
+            })?;
+        }
+        Ok(report)
+    }
+}
+
+impl ChangeSink for DbTriggers {
+    fn committed(&self, changes: &[DocumentChange]) {
+        // Best-effort: the write has committed and cannot be failed
+        self.enqueue(changes).ok();
+    }
+}
+
+/// Write `state` to a temporary file, fsync it, and rename it over `path`
+fn write_queue(path: &Path, state: &QueueState) -> std::io::Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(&serde_json::to_vec_pretty(state)?)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::functions::function::Function;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    const EMPTY_MODULE: &[u8] = &[0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00];
+
+    fn change(kind: ChangeKind, id: &str) -> DocumentChange {
+        DocumentChange {
+            collection: "orders".to_string(),
+            kind,
+            document_id: id.to_string(),
+            document: (kind != ChangeKind::Delete).then(|| json!({"_id": id})),
+        }
+    }
+
+    fn register(registry: &FunctionRegistry, name: &str, event: DbEventType, wasm: &[u8]) {
+        let trigger = TriggerType::database("orders".to_string(), event);
+        registry
+            .register(Function::new(name.to_string(), trigger, wasm.to_vec()))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_changes_queued_for_bound_functions() {
+        let registry = Arc::new(FunctionRegistry::new());
+        register(&registry, "on_insert", DbEventType::Insert, EMPTY_MODULE);
+        register(&registry, "on_delete", DbEventType::Delete, EMPTY_MODULE);
+        let triggers = DbTriggers::new(registry, Arc::new(TriggerQueue::new()));
+
+        triggers.committed(&[
+            change(ChangeKind::Insert, "o1"),
+            change(ChangeKind::Update, "o1"),
+            change(ChangeKind::Delete, "o1"),
+        ]);
+        let pending = triggers.queue().pending();
+        let names: Vec<_> = pending.iter().map(|d| d.function_name.as_str()).collect();
+        assert_eq!(names, vec!["on_insert", "on_delete"]);
+        assert_eq!(pending[0].seq, 0);
+        assert_eq!(pending[1].seq, 1);
+
+        let report = triggers.deliver(&Invoker::new()).unwrap();
+        assert_eq!(report.delivered, 2);
+        assert!(triggers.queue().pending().is_empty());
+    }
+
+    #[test]
+    fn test_failed_delivery_survives_restart_then_dead_letters() {
+        let dir = TempDir::new().unwrap();
+        let registry = Arc::new(FunctionRegistry::new());
+        // Traps every time
+        let trap = br#"(module (func (export "handle") unreachable))"#;
+        register(&registry, "on_insert", DbEventType::Insert, trap);
+        let retries = registry.get("on_insert").unwrap().config.max_retries;
+
+        let triggers = DbTriggers::new(
+            registry.clone(),
+            Arc::new(TriggerQueue::open(dir.path()).unwrap()),
+        );
+        triggers.committed(&[change(ChangeKind::Insert, "o1")]);
+        let report = triggers.deliver(&Invoker::new()).unwrap();
+        assert_eq!(report.retrying, 1);
+
+        // Still queued after a restart, with its attempt recorded
+        let triggers = DbTriggers::new(registry, Arc::new(TriggerQueue::open(dir.path()).unwrap()));
+        let pending = triggers.queue().pending();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].attempts, 1);
+        assert!(pending[0]
+            .last_error
+            .as_deref()
+            .unwrap()
+            .contains("unreachable"));
+
+        for _ in 0..retries {
+            triggers.deliver(&Invoker::new()).unwrap();
+        }
+        assert!(triggers.queue().pending().is_empty());
+        assert_eq!(triggers.queue().dead()[0].attempts, retries + 1);
+    }
+}
@@ -21,8 +21,9 @@ use crate::auth::api::{
 use crate::auth::crypto::PasswordPolicy;
 use crate::auth::errors::AuthError;
 use crate::auth::rls::{DefaultRlsEnforcer, RlsPolicy};
+use crate::auth::service_keys::{KeyScope, ServiceKey};
 use crate::auth::session::InMemorySessionRepository;
-use crate::auth::user::InMemoryUserRepository;
+use crate::auth::user::{InMemoryUserRepository, ADMIN_ROLE};
 
 use super::auth_routes::AuthState;
 
@@ -71,6 +72,54 @@ pub struct CreateRlsPolicyRequest {
     pub policy: RlsPolicy,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct CreateServiceKeyRequest {
+    pub name: String,
+    pub scopes: Vec<KeyScope>,
+    /// Days until the key expires; it never does if absent
+    #[serde(default)]
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServiceKeyResponse {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<KeyScope>,
+    pub created_at: String,
+    pub rotated_at: Option<String>,
+    pub expires_at: Option<String>,
+    pub revoked_at: Option<String>,
+}
+
+impl From<&ServiceKey> for ServiceKeyResponse {
+    fn from(key: &ServiceKey) -> Self {
+        Self {
+            id: key.id.to_string(),
+            name: key.name.clone(),
+            scopes: key.scopes.clone(),
+            created_at: key.created_at.to_rfc3339(),
+            rotated_at: key.rotated_at.map(|t| t.to_rfc3339()),
+            expires_at: key.expires_at.map(|t| t.to_rfc3339()),
+            revoked_at: key.revoked_at.map(|t| t.to_rfc3339()),
+        }
+    }
+}
+
+/// A created or rotated key; `api_key` is shown only this once
+#[derive(Debug, Serialize)]
+pub struct IssuedServiceKeyResponse {
+    #[serde(flatten)]
+    pub key: ServiceKeyResponse,
+    pub api_key: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServiceKeysListResponse {
+    pub keys: Vec<ServiceKeyResponse>,
+    pub total: usize,
+}
+
 #[derive(Debug, Serialize)]
 pub struct PasswordPolicyResponse {
     pub min_length: usize,
@@ -127,6 +176,11 @@ pub fn auth_management_routes(state: Arc<AuthState>) -> Router {
         .route("/rls/{table}", get(get_rls_policy_handler))
         .route("/rls/{table}", post(create_rls_policy_handler))
         .route("/rls/{table}", delete(delete_rls_policy_handler))
+        // Service API keys (admin role only)
+        .route("/service-keys", get(list_service_keys_handler))
+        .route("/service-keys", post(create_service_key_handler))
+        .route("/service-keys/:id", delete(revoke_service_key_handler))
+        .route("/service-keys/:id/rotate", post(rotate_service_key_handler))
         // Email verification
         .route("/verify-email", post(verify_email_handler))
         .route(
@@ -181,6 +235,31 @@ fn validate_admin_access(
     })
 }
 
+/// Status and body for an auth error
+fn auth_error(e: AuthError) -> (StatusCode, Json<ErrorResponse>) {
+    let status = StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    (status, Json(ErrorResponse::from(e)))
+}
+
+/// Validate access by a user with the admin role
+fn require_admin_role(
+    state: &AuthState,
+    headers: &HeaderMap,
+) -> Result<Uuid, (StatusCode, Json<ErrorResponse>)> {
+    let user_id = validate_admin_access(state, headers)?;
+    let user = state.service.get_user(user_id).map_err(auth_error)?;
+    if user.role() != ADMIN_ROLE {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse {
+                error: "Admin role required".to_string(),
+                code: 403,
+            }),
+        ));
+    }
+    Ok(user_id)
+}
+
 // ==================
 // User Management Handlers
 // ==================
@@ -434,6 +513,78 @@ async fn delete_rls_policy_handler(
     Ok(StatusCode::NO_CONTENT)
 }
 
+// ==================
+// Service Key Handlers
+// ==================
+
+/// List service keys, revoked ones included
+async fn list_service_keys_handler(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+) -> Result<Json<ServiceKeysListResponse>, (StatusCode, Json<ErrorResponse>)> {
+    require_admin_role(&state, &headers)?;
+
+    let keys: Vec<ServiceKeyResponse> = state
+        .service_keys
+        .list()
+        .iter()
+        .map(ServiceKeyResponse::from)
+        .collect();
+    Ok(Json(ServiceKeysListResponse {
+        total: keys.len(),
+        keys,
+    }))
+}
+
+/// Create a service key
+async fn create_service_key_handler(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Json(request): Json<CreateServiceKeyRequest>,
+) -> Result<(StatusCode, Json<IssuedServiceKeyResponse>), (StatusCode, Json<ErrorResponse>)> {
+    require_admin_role(&state, &headers)?;
+
+    let ttl = request.expires_in_days.map(chrono::Duration::days);
+    let (key, api_key) = state
+        .service_keys
+        .create(&request.name, request.scopes, ttl)
+        .map_err(auth_error)?;
+    Ok((
+        StatusCode::CREATED,
+        Json(IssuedServiceKeyResponse {
+            key: ServiceKeyResponse::from(&key),
+            api_key,
+        }),
+    ))
+}
+
+/// Replace a service key's secret
+async fn rotate_service_key_handler(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<IssuedServiceKeyResponse>, (StatusCode, Json<ErrorResponse>)> {
+    require_admin_role(&state, &headers)?;
+
+    let (key, api_key) = state.service_keys.rotate(id).map_err(auth_error)?;
+    Ok(Json(IssuedServiceKeyResponse {
+        key: ServiceKeyResponse::from(&key),
+        api_key,
+    }))
+}
+
+/// Revoke a service key
+async fn revoke_service_key_handler(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ServiceKeyResponse>, (StatusCode, Json<ErrorResponse>)> {
+    require_admin_role(&state, &headers)?;
+
+    let key = state.service_keys.revoke(id).map_err(auth_error)?;
+    Ok(Json(ServiceKeyResponse::from(&key)))
+}
+
 // ==================
 // Email Verification Handlers
 // ==================
@@ -470,4 +621,104 @@ mod tests {
         let response = PasswordPolicyResponse::from(&policy);
         assert_eq!(response.min_length, policy.min_length);
     }
+
+    #[tokio::test]
+    async fn test_service_key_routes() {
+        use crate::auth::user::SignupRequest;
+        use axum::body::{to_bytes, Body};
+        use axum::http::{header, Request};
+        use serde_json::{json, Value};
+        use tower::Service;
+
+        let state = Arc::new(AuthState::new());
+        let signup = |email: &str| {
+            state
+                .service
+                .signup(SignupRequest {
+                    email: email.to_string(),
+                    password: "password123".to_string(),
+                    metadata: None,
+                })
+                .unwrap()
+        };
+        let (admin, admin_tokens) = signup("admin@example.com");
+        state.service.set_role(admin.id, ADMIN_ROLE).unwrap();
+        let (_, user_tokens) = signup("user@example.com");
+        let router = auth_management_routes(state.clone());
+        let call = |method: &str, uri: &str, token: &str, body: Value| {
+            // Router is always ready
+            router.clone().call(
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+        };
+        let create = json!({
+            "name": "billing",
+            "scopes": [{"collection": "orders", "operations": ["read", "insert"]}]
+        });
+
+        // Only admins manage keys
+        let response = call(
+            "POST",
+            "/service-keys",
+            &user_tokens.access_token,
+            create.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = call("POST", "/service-keys", &admin_tokens.access_token, create)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let created: Value = serde_json::from_slice(&body).unwrap();
+        let api_key = created["api_key"].as_str().unwrap();
+        let key = state.service_keys.authenticate(api_key).unwrap();
+        assert_eq!(created["id"], key.id.to_string());
+        assert_eq!(
+            created["scopes"][0]["operations"],
+            json!(["read", "insert"])
+        );
+
+        let response = call(
+            "POST",
+            &format!("/service-keys/{}/rotate", key.id),
+            &admin_tokens.access_token,
+            Value::Null,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(state.service_keys.authenticate(api_key).is_err());
+
+        let response = call(
+            "DELETE",
+            &format!("/service-keys/{}", key.id),
+            &admin_tokens.access_token,
+            Value::Null,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = call(
+            "GET",
+            "/service-keys",
+            &admin_tokens.access_token,
+            Value::Null,
+        )
+        .await
+        .unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let listed: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(listed["total"], 1);
+        assert!(listed["keys"][0]["revoked_at"].is_string());
+        assert!(listed["keys"][0].get("api_key").is_none());
+    }
 }
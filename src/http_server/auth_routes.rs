@@ -5,24 +5,32 @@
 use std::sync::Arc;
 
 use axum::{
-    extract::{Json, State},
-    http::{HeaderMap, StatusCode},
+    extract::{Json, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
     response::IntoResponse,
     routing::{get, post},
     Router,
 };
 use serde::{Deserialize, Serialize};
 
-use crate::auth::api::AuthService;
+use crate::auth::api::{AuthService, TotpEnrollment};
 use crate::auth::crypto::PasswordPolicy;
 use crate::auth::errors::AuthError;
 use crate::auth::jwt::{JwtConfig, JwtManager, TokenResponse};
+use crate::auth::mfa::MfaPolicy;
+use crate::auth::oidc::OidcManager;
+use crate::auth::revocation::RevocationStore;
+use crate::auth::service_keys::ServiceKeyManager;
 use crate::auth::session::{InMemorySessionRepository, SessionConfig};
 use crate::auth::user::{InMemoryUserRepository, LoginRequest, SignupRequest, User};
 
 /// Shared auth state
 pub struct AuthState {
     pub service: AuthService<InMemoryUserRepository, InMemorySessionRepository>,
+    /// External identity providers (`/auth/oidc/*`), if any are configured
+    pub oidc: Option<OidcManager>,
+    /// Service API keys (`/auth/service-keys`)
+    pub service_keys: Arc<ServiceKeyManager>,
 }
 
 impl AuthState {
@@ -36,8 +44,39 @@ impl AuthState {
                 SessionConfig::default(),
                 PasswordPolicy::default(),
             ),
+            oidc: None,
+            service_keys: Arc::new(ServiceKeyManager::new()),
         }
     }
+
+    /// Create auth state recording revoked tokens in `revocations`
+    pub fn with_revocations(revocations: Arc<RevocationStore>) -> Self {
+        let state = Self::new();
+        Self {
+            service: state.service.with_revocations(revocations),
+            ..state
+        }
+    }
+
+    /// Require a second factor at login as `policy` says
+    pub fn with_mfa_policy(self, policy: MfaPolicy) -> Self {
+        Self {
+            service: self.service.with_mfa_policy(policy),
+            ..self
+        }
+    }
+
+    /// Manage service API keys in `keys`
+    pub fn with_service_keys(mut self, keys: Arc<ServiceKeyManager>) -> Self {
+        self.service_keys = keys;
+        self
+    }
+
+    /// Enable login through the providers of `oidc`
+    pub fn with_oidc(mut self, oidc: OidcManager) -> Self {
+        self.oidc = Some(oidc);
+        self
+    }
 }
 
 impl Default for AuthState {
@@ -52,8 +91,16 @@ pub fn auth_routes(state: Arc<AuthState>) -> Router {
         .route("/signup", post(signup_handler))
         .route("/login", post(login_handler))
         .route("/refresh", post(refresh_handler))
+        .route("/token/refresh", post(refresh_handler))
+        .route("/token/revoke", post(revoke_handler))
+        .route("/mfa/totp/enroll", post(totp_enroll_handler))
+        .route("/mfa/totp/confirm", post(totp_confirm_handler))
+        .route("/mfa/totp/disable", post(totp_disable_handler))
         .route("/logout", post(logout_handler))
         .route("/user", get(get_user_handler))
+        .route("/oidc/providers", get(oidc_providers_handler))
+        .route("/oidc/:provider/authorize", get(oidc_authorize_handler))
+        .route("/oidc/:provider/callback", get(oidc_callback_handler))
         .with_state(state)
 }
 
@@ -98,11 +145,45 @@ pub struct RefreshResponse {
     pub expires_in: u64,
 }
 
+/// Credentials re-authenticating a TOTP factor change; the password
+/// alone suffices, so users whose role requires MFA can enroll
+#[derive(Debug, Deserialize)]
+pub struct TotpRequest {
+    pub email: String,
+    pub password: String,
+    /// Current code, to confirm or disable a factor
+    #[serde(default)]
+    pub code: Option<String>,
+}
+
+/// Token revocation request (RFC 7009)
+#[derive(Debug, Deserialize)]
+pub struct RevokeRequest {
+    pub token: String,
+    /// `refresh_token` or `access_token`
+    #[serde(default)]
+    pub token_type_hint: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct LogoutRequest {
     pub refresh_token: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct OidcProvidersResponse {
+    pub providers: Vec<String>,
+}
+
+/// Query of the provider's redirect back to the callback
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
     pub error: String,
@@ -188,6 +269,66 @@ async fn refresh_handler(
     }
 }
 
+/// Token revocation handler; unknown tokens also succeed
+async fn revoke_handler(
+    State(state): State<Arc<AuthState>>,
+    Json(request): Json<RevokeRequest>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    state
+        .service
+        .revoke_token(&request.token, request.token_type_hint.as_deref())
+        .map(|_| StatusCode::OK)
+        .map_err(error_response)
+}
+
+/// Start TOTP enrollment
+async fn totp_enroll_handler(
+    State(state): State<Arc<AuthState>>,
+    Json(request): Json<TotpRequest>,
+) -> Result<Json<TotpEnrollment>, (StatusCode, Json<ErrorResponse>)> {
+    let user = state
+        .service
+        .authenticate_password(&request.email, &request.password)
+        .map_err(error_response)?;
+    state
+        .service
+        .enroll_totp(user.id)
+        .map(Json)
+        .map_err(error_response)
+}
+
+/// Confirm TOTP enrollment with a first code
+async fn totp_confirm_handler(
+    State(state): State<Arc<AuthState>>,
+    Json(request): Json<TotpRequest>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let user = state
+        .service
+        .authenticate_password(&request.email, &request.password)
+        .map_err(error_response)?;
+    state
+        .service
+        .confirm_totp(user.id, request.code.as_deref().unwrap_or_default())
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(error_response)
+}
+
+/// Remove a TOTP factor, proven with a current code
+async fn totp_disable_handler(
+    State(state): State<Arc<AuthState>>,
+    Json(request): Json<TotpRequest>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let user = state
+        .service
+        .authenticate_password(&request.email, &request.password)
+        .map_err(error_response)?;
+    state
+        .service
+        .disable_totp(user.id, request.code.as_deref().unwrap_or_default())
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(error_response)
+}
+
 /// Logout handler
 async fn logout_handler(
     State(state): State<Arc<AuthState>>,
@@ -202,6 +343,90 @@ async fn logout_handler(
     }
 }
 
+fn error_response(e: AuthError) -> (StatusCode, Json<ErrorResponse>) {
+    let status = StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    (status, Json(ErrorResponse::from(e)))
+}
+
+/// Run `f` with the OIDC manager off the async runtime; providers are
+/// reached with blocking I/O
+async fn with_oidc<T: Send + 'static>(
+    state: Arc<AuthState>,
+    provider: String,
+    f: impl FnOnce(&AuthState, &OidcManager) -> Result<T, AuthError> + Send + 'static,
+) -> Result<T, (StatusCode, Json<ErrorResponse>)> {
+    tokio::task::spawn_blocking(move || match &state.oidc {
+        Some(oidc) => f(&state, oidc),
+        None => Err(AuthError::OidcProviderNotFound(provider)),
+    })
+    .await
+    .map_err(|e| error_response(AuthError::StorageError(e.to_string())))?
+    .map_err(error_response)
+}
+
+/// List configured OIDC providers
+async fn oidc_providers_handler(
+    State(state): State<Arc<AuthState>>,
+) -> Json<OidcProvidersResponse> {
+    let providers = state.oidc.as_ref().map_or_else(Vec::new, |oidc| {
+        oidc.provider_names()
+            .into_iter()
+            .map(str::to_string)
+            .collect()
+    });
+    Json(OidcProvidersResponse { providers })
+}
+
+/// Redirect to the provider's sign-in page
+async fn oidc_authorize_handler(
+    State(state): State<Arc<AuthState>>,
+    Path(provider): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let request = with_oidc(state, provider.clone(), move |_, oidc| {
+        oidc.authorize(&provider)
+    })
+    .await?;
+    Ok((
+        StatusCode::FOUND,
+        [
+            (header::LOCATION, request.url),
+            (header::CACHE_CONTROL, "no-store".to_string()),
+        ],
+    ))
+}
+
+/// Finish a provider sign-in and issue tokens
+async fn oidc_callback_handler(
+    State(state): State<Arc<AuthState>>,
+    Path(provider): Path<String>,
+    Query(query): Query<OidcCallbackQuery>,
+) -> Result<Json<AuthResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if let Some(error) = query.error {
+        let reason = match query.error_description {
+            Some(description) => format!("{}: {}", error, description),
+            None => error,
+        };
+        return Err(error_response(AuthError::OidcLoginFailed(reason)));
+    }
+    let (Some(code), Some(login_state)) = (query.code, query.state) else {
+        return Err(error_response(AuthError::OidcLoginFailed(
+            "callback is missing code or state".to_string(),
+        )));
+    };
+
+    let (user, tokens) = with_oidc(state, provider.clone(), move |state, oidc| {
+        let identity = oidc.callback(&provider, &login_state, &code)?;
+        state.service.login_external(&identity)
+    })
+    .await?;
+    Ok(Json(AuthResponse {
+        user: UserResponse::from(&user),
+        access_token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
+        expires_in: tokens.expires_in as u64,
+    }))
+}
+
 /// Get current user handler (requires Authorization header)
 async fn get_user_handler(
     State(state): State<Arc<AuthState>>,
@@ -266,4 +491,168 @@ mod tests {
         assert!(true);
         let _ = state;
     }
+
+    #[tokio::test]
+    async fn test_token_routes() {
+        use axum::body::{to_bytes, Body};
+        use axum::http::Request;
+        use tower::Service;
+
+        let state = Arc::new(AuthState::new());
+        let signup = SignupRequest {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+            metadata: None,
+        };
+        let (_, tokens) = state.service.signup(signup).unwrap();
+        let router = auth_routes(state);
+        let post = |uri: &str, body: serde_json::Value| {
+            // Router is always ready
+            router.clone().call(
+                Request::post(uri)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+        };
+
+        let response = post(
+            "/token/refresh",
+            serde_json::json!({"refresh_token": tokens.refresh_token}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let refreshed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        let response = post(
+            "/token/revoke",
+            serde_json::json!({"token": refreshed["refresh_token"]}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = post(
+            "/token/refresh",
+            serde_json::json!({"refresh_token": refreshed["refresh_token"]}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_totp_routes() {
+        use crate::auth::mfa::{totp_code, unix_now};
+        use axum::body::{to_bytes, Body};
+        use axum::http::Request;
+        use tower::Service;
+
+        let state = AuthState::new().with_mfa_policy(MfaPolicy::require_for(["admin"]));
+        let signup = SignupRequest {
+            email: "test@example.com".to_string(),
+            password: "password123".to_string(),
+            metadata: None,
+        };
+        let (user, _) = state.service.signup(signup).unwrap();
+        state.service.set_role(user.id, "admin").unwrap();
+        let router = auth_routes(Arc::new(state));
+        let post = |uri: &str, body: serde_json::Value| {
+            // Router is always ready
+            router.clone().call(
+                Request::post(uri)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+        };
+        let credentials = serde_json::json!({
+            "email": "test@example.com",
+            "password": "password123",
+        });
+
+        let response = post("/login", credentials.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = post("/mfa/totp/enroll", credentials.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let enrollment: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let secret = data_encoding::BASE32_NOPAD
+            .decode(enrollment["secret"].as_str().unwrap().as_bytes())
+            .unwrap();
+        let code_at = |step: u64| totp_code(&secret, step, 6);
+
+        // Codes for the current and next step stay within the drift window
+        // even if the clock moves to the next step mid-test
+        let step = unix_now() / 30;
+        let mut confirm = credentials.clone();
+        confirm["code"] = serde_json::json!(code_at(step));
+        let response = post("/mfa/totp/confirm", confirm).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let response = post("/login", credentials.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let mut login = credentials.clone();
+        login["totp_code"] = serde_json::json!(code_at(step + 1));
+        let response = post("/login", login).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_oidc_login_routes() {
+        use crate::auth::oidc::tests::{provider_config, FakeProvider};
+        use crate::auth::oidc::OidcTransport;
+        use axum::body::{to_bytes, Body};
+        use axum::http::Request;
+        use std::collections::HashMap;
+        use tower::Service;
+
+        let provider = Arc::new(FakeProvider::new());
+        let oidc = OidcManager::new(
+            vec![provider_config()],
+            Arc::clone(&provider) as Arc<dyn OidcTransport>,
+        );
+        let router = auth_routes(Arc::new(AuthState::new().with_oidc(oidc)));
+        let get = |uri: String| {
+            // Router is always ready
+            router
+                .clone()
+                .call(Request::get(uri).body(Body::empty()).unwrap())
+        };
+
+        let response = get("/oidc/providers".to_string()).await.unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], br#"{"providers":["google"]}"#);
+
+        let response = get("/oidc/google/authorize".to_string()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FOUND);
+        let location = response.headers()[header::LOCATION].to_str().unwrap();
+        let query: HashMap<String, String> =
+            form_urlencoded::parse(location.split_once('?').unwrap().1.as_bytes())
+                .into_owned()
+                .collect();
+        *provider.nonce.lock().unwrap() = query["nonce"].clone();
+
+        let callback = format!(
+            "/oidc/google/callback?code=good-code&state={}",
+            query["state"]
+        );
+        let response = get(callback.clone()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let auth: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(auth["user"]["email"], "ada@example.com");
+
+        // Replayed callback and provider-reported errors
+        let response = get(callback).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = get("/oidc/google/callback?error=access_denied".to_string())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = get("/oidc/github/authorize".to_string()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
 }
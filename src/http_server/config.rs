@@ -1,9 +1,16 @@
 //! HTTP Server Configuration
 //!
-//! Configuration for the HTTP server including host, port, and CORS settings.
+//! Configuration for the HTTP server including host, port, CORS and TLS
+//! settings.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
+use crate::auth::oidc::OidcProviderConfig;
+use crate::core::middleware::registry::MiddlewareConfig;
+
 /// HTTP server configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HttpServerConfig {
@@ -15,9 +22,73 @@ pub struct HttpServerConfig {
     #[serde(default = "default_port")]
     pub port: u16,
 
-    /// CORS allowed origins (default: ["http://localhost:5173"])
+    /// CORS allowed origins, `"*"` for any (default: local dev servers)
     #[serde(default = "default_cors_origins")]
     pub cors_origins: Vec<String>,
+
+    /// CORS allowed methods (default: GET, POST, PUT, PATCH, DELETE)
+    #[serde(default = "default_cors_methods")]
+    pub cors_methods: Vec<String>,
+
+    /// CORS allowed request headers (default: authorization, content-type,
+    /// apikey, if-match)
+    #[serde(default = "default_cors_headers")]
+    pub cors_headers: Vec<String>,
+
+    /// Add security headers to every response (default: true)
+    #[serde(default = "default_security_headers")]
+    pub security_headers: bool,
+
+    /// Secret signing control plane API keys accepted on `/control/*`
+    /// (default: none, so control routes reject every request)
+    #[serde(default)]
+    pub control_signing_key: Option<String>,
+
+    /// Control commands that need approvals from two distinct operators
+    /// before they execute (default: none)
+    #[serde(default)]
+    pub dual_control: Vec<String>,
+
+    /// Serve HTTPS instead of plain HTTP (default: none)
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+
+    /// External identity providers for `/auth/oidc/*` (default: none)
+    #[serde(default)]
+    pub oidc: Vec<OidcProviderConfig>,
+
+    /// User roles that must log in with a TOTP second factor (default: none)
+    #[serde(default)]
+    pub mfa_required_roles: Vec<String>,
+
+    /// Core pipeline stages database routes run (default: auth, rls,
+    /// validate, observe)
+    #[serde(default)]
+    pub pipeline: MiddlewareConfig,
+}
+
+/// HTTPS settings
+///
+/// Certificates and keys are re-read from disk on SIGHUP, so a renewed
+/// certificate is picked up without a restart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// PEM certificate chain presented to clients, leaf first
+    pub cert_path: PathBuf,
+
+    /// PEM private key of the leaf certificate
+    pub key_path: PathBuf,
+
+    /// PEM CA certificates that client certificates must chain to. When
+    /// set, every connection must present a valid client certificate.
+    #[serde(default)]
+    pub client_ca_path: Option<PathBuf>,
+
+    /// Control plane authority level ("OBSERVER", "OPERATOR", "AUDITOR")
+    /// granted to a client certificate, keyed by its subject common name
+    #[serde(default)]
+    pub client_identities: BTreeMap<String, String>,
 }
 
 fn default_host() -> String {
@@ -36,12 +107,37 @@ fn default_cors_origins() -> Vec<String> {
     ]
 }
 
+fn default_cors_methods() -> Vec<String> {
+    ["GET", "POST", "PUT", "PATCH", "DELETE"]
+        .map(String::from)
+        .to_vec()
+}
+
+fn default_cors_headers() -> Vec<String> {
+    ["authorization", "content-type", "apikey", "if-match"]
+        .map(String::from)
+        .to_vec()
+}
+
+fn default_security_headers() -> bool {
+    true
+}
+
 impl Default for HttpServerConfig {
     fn default() -> Self {
         Self {
             host: default_host(),
             port: default_port(),
             cors_origins: default_cors_origins(),
+            cors_methods: default_cors_methods(),
+            cors_headers: default_cors_headers(),
+            security_headers: default_security_headers(),
+            control_signing_key: None,
+            dual_control: Vec::new(),
+            tls: None,
+            oidc: Vec::new(),
+            mfa_required_roles: Vec::new(),
+            pipeline: MiddlewareConfig::default(),
         }
     }
 }
@@ -59,6 +155,15 @@ impl HttpServerConfig {
     pub fn socket_addr(&self) -> String {
         format!("{}:{}", self.host, self.port)
     }
+
+    /// URL scheme the server is reached with
+    pub fn scheme(&self) -> &'static str {
+        if self.tls.is_some() {
+            "https"
+        } else {
+            "http"
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,573 @@
+//! Control Plane HTTP Routes
+//!
+//! Day-2 operations (checkpoints and backups) through the Phase 7 control
+//! plane, so they do not require shell access to the host.
+//!
+//! Requests go through the same `ControlPlaneHandler` as the CLI:
+//! authority is checked, confirmation-gated commands first return a
+//! confirmation token, and the command executes only when that token is
+//! sent back. Every request presents a control plane API key as a Bearer
+//! token and acts with the authority level the key was issued with; a
+//! missing, invalid or revoked key is rejected before the command is
+//! considered.
+//!
+//! Over mutual TLS, a client certificate listed in
+//! `http.tls.client_identities` stands in for an API key: a request
+//! without a Bearer token acts with the level mapped to the
+//! certificate's common name.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    extract::{Extension, Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::dx::api::control_plane::{
+    ApiKeyManager, AuthorityContext, AuthorityLevel, CommandRequest, CommandResponse,
+    CommandResponseData, ControlCommand, ControlPlaneCommand, ControlPlaneError,
+    ControlPlaneErrorDomain, ControlPlaneHandler, DiagnosticCommand, KernelAdapter,
+};
+
+use super::tls::ClientIdentity;
+
+// ==================
+// Shared State
+// ==================
+
+/// Control plane state shared across handlers
+pub struct ControlState {
+    /// Holds pending confirmation tokens between requests
+    handler: Mutex<ControlPlaneHandler>,
+    /// Authenticates API keys; `None` rejects every request
+    keys: Option<Arc<ApiKeyManager>>,
+    /// Authority of client certificates, by subject common name
+    client_identities: BTreeMap<String, AuthorityLevel>,
+}
+
+impl ControlState {
+    /// Control state with no kernel connected and no API keys
+    pub fn new() -> Self {
+        Self {
+            handler: Mutex::new(ControlPlaneHandler::new()),
+            keys: None,
+            client_identities: BTreeMap::new(),
+        }
+    }
+
+    /// Control state running `handler`, authenticating callers with `keys`
+    pub fn with_handler(handler: ControlPlaneHandler, keys: Option<Arc<ApiKeyManager>>) -> Self {
+        Self {
+            handler: Mutex::new(handler),
+            keys,
+            client_identities: BTreeMap::new(),
+        }
+    }
+
+    /// Control state over `kernel`, authenticating callers with `keys`
+    pub fn with_kernel(kernel: Arc<dyn KernelAdapter>, keys: Option<Arc<ApiKeyManager>>) -> Self {
+        Self::with_handler(ControlPlaneHandler::with_kernel(kernel), keys)
+    }
+
+    /// Also accept client certificates with these common names, at the
+    /// mapped authority levels, from requests without an API key
+    pub fn with_client_identities(
+        mut self,
+        identities: impl IntoIterator<Item = (String, AuthorityLevel)>,
+    ) -> Self {
+        self.client_identities = identities.into_iter().collect();
+        self
+    }
+
+    /// Authority of the caller presenting `headers` over a connection
+    /// authenticated as `identity`
+    fn authority(
+        &self,
+        headers: &HeaderMap,
+        identity: Option<&ClientIdentity>,
+    ) -> Result<AuthorityContext, (StatusCode, Json<ErrorResponse>)> {
+        let unauthorized = |message: String| {
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(ErrorResponse {
+                    error: message,
+                    code: "UNAUTHORIZED".to_string(),
+                }),
+            )
+        };
+        let token = headers
+            .get("authorization")
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "));
+        let Some(token) = token else {
+            // An API key, when sent, always decides
+            return identity
+                .and_then(|identity| {
+                    let level = self.client_identities.get(&identity.common_name)?;
+                    Some(
+                        AuthorityContext::new(*level)
+                            .with_operator_id(identity.common_name.clone()),
+                    )
+                })
+                .ok_or_else(|| unauthorized("Missing API key".to_string()));
+        };
+        let keys = self
+            .keys
+            .as_ref()
+            .ok_or_else(|| unauthorized("No control plane signing key configured".to_string()))?;
+        keys.authenticate(token)
+            .map_err(|e| unauthorized(e.to_string()))
+    }
+
+    /// Run `command` on behalf of the caller presenting `headers`
+    fn handle(
+        &self,
+        headers: &HeaderMap,
+        identity: Option<Extension<ClientIdentity>>,
+        command: ControlPlaneCommand,
+        confirmation_token: Option<Uuid>,
+    ) -> Result<Json<Value>, (StatusCode, Json<ErrorResponse>)> {
+        let authority = self.authority(headers, identity.as_ref().map(|e| &e.0))?;
+        let mut request = CommandRequest::new(command, authority);
+        if let Some(token) = confirmation_token {
+            request = request.with_confirmation(token);
+        }
+        self.handler
+            .lock()
+            .unwrap()
+            .handle_command(request)
+            .map(|response| Json(response_json(response)))
+            .map_err(error_response)
+    }
+}
+
+impl Default for ControlState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ==================
+// Request/Response Types
+// ==================
+
+#[derive(Debug, Deserialize)]
+pub struct CheckpointRequest {
+    pub node_id: Uuid,
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub confirmation_token: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateBackupRequest {
+    pub node_id: Uuid,
+    pub path: PathBuf,
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub confirmation_token: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListBackupsQuery {
+    pub directory: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyBackupRequest {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub confirmation_token: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    pub error: String,
+    pub code: String,
+}
+
+// ==================
+// Control Routes
+// ==================
+
+/// Create control plane routes
+pub fn control_routes(state: Arc<ControlState>) -> Router {
+    Router::new()
+        .route("/checkpoint", post(checkpoint_handler))
+        .route("/backups", get(list_backups_handler))
+        .route("/backups", post(create_backup_handler))
+        .route("/backups/verify", post(verify_backup_handler))
+        .with_state(state)
+}
+
+// ==================
+// Handlers
+// ==================
+
+async fn checkpoint_handler(
+    State(state): State<Arc<ControlState>>,
+    identity: Option<Extension<ClientIdentity>>,
+    headers: HeaderMap,
+    Json(request): Json<CheckpointRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<ErrorResponse>)> {
+    state.handle(
+        &headers,
+        identity,
+        ControlPlaneCommand::Control(ControlCommand::CreateCheckpoint {
+            node_id: request.node_id,
+            reason: request.reason,
+        }),
+        request.confirmation_token,
+    )
+}
+
+async fn create_backup_handler(
+    State(state): State<Arc<ControlState>>,
+    identity: Option<Extension<ClientIdentity>>,
+    headers: HeaderMap,
+    Json(request): Json<CreateBackupRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<ErrorResponse>)> {
+    state.handle(
+        &headers,
+        identity,
+        ControlPlaneCommand::Control(ControlCommand::CreateBackup {
+            node_id: request.node_id,
+            output_path: request.path.display().to_string(),
+            reason: request.reason,
+        }),
+        request.confirmation_token,
+    )
+}
+
+async fn list_backups_handler(
+    State(state): State<Arc<ControlState>>,
+    identity: Option<Extension<ClientIdentity>>,
+    headers: HeaderMap,
+    Query(query): Query<ListBackupsQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<ErrorResponse>)> {
+    state.handle(
+        &headers,
+        identity,
+        ControlPlaneCommand::Diagnostic(DiagnosticCommand::ListBackups {
+            directory: query.directory.display().to_string(),
+        }),
+        None,
+    )
+}
+
+async fn verify_backup_handler(
+    State(state): State<Arc<ControlState>>,
+    identity: Option<Extension<ClientIdentity>>,
+    headers: HeaderMap,
+    Json(request): Json<VerifyBackupRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<ErrorResponse>)> {
+    state.handle(
+        &headers,
+        identity,
+        ControlPlaneCommand::Diagnostic(DiagnosticCommand::VerifyBackup {
+            path: request.path.display().to_string(),
+        }),
+        request.confirmation_token,
+    )
+}
+
+// ==================
+// Rendering
+// ==================
+
+fn response_json(response: CommandResponse) -> Value {
+    json!({
+        "request_id": response.request_id.to_string(),
+        "command": response.command_name,
+        "outcome": format!("{:?}", response.outcome),
+        "confirmation_token": response.confirmation_token.map(|t| t.to_string()),
+        "data": response.data.map(data_json),
+        "error": response.error_message,
+    })
+}
+
+fn data_json(data: CommandResponseData) -> Value {
+    match data {
+        CommandResponseData::CheckpointResult(result) => json!({
+            "node_id": result.node_id.to_string(),
+            "checkpoint_id": result.checkpoint_id,
+            "explanation": result.explanation,
+        }),
+        CommandResponseData::BackupResult(result) => json!({
+            "node_id": result.node_id.to_string(),
+            "path": result.output_path,
+            "backup_id": result.backup_id,
+            "explanation": result.explanation,
+        }),
+        CommandResponseData::BackupList(list) => json!({
+            "directory": list.directory,
+            "backups": list.backups.iter().map(|backup| json!({
+                "path": backup.path,
+                "backup_id": backup.backup_id,
+                "created_at": backup.created_at,
+                "size_bytes": backup.size_bytes,
+                "wal_present": backup.wal_present,
+            })).collect::<Vec<_>>(),
+        }),
+        CommandResponseData::BackupVerification(verification) => json!({
+            "path": verification.path,
+            "valid": verification.valid,
+            "backup_id": verification.backup_id,
+            "explanation": verification.explanation,
+        }),
+        CommandResponseData::ApprovalPending(pending) => json!({
+            "approved_by": pending.approved_by,
+            "required": pending.required,
+        }),
+        // Only the commands above are routed here
+        _ => Value::Null,
+    }
+}
+
+fn error_response(error: ControlPlaneError) -> (StatusCode, Json<ErrorResponse>) {
+    let status = match error.domain() {
+        ControlPlaneErrorDomain::OperatorInput => StatusCode::BAD_REQUEST,
+        ControlPlaneErrorDomain::ValidationError
+            if error.code() == "PHASE7_INSUFFICIENT_AUTHORITY" =>
+        {
+            StatusCode::FORBIDDEN
+        }
+        ControlPlaneErrorDomain::ValidationError => StatusCode::CONFLICT,
+        ControlPlaneErrorDomain::KernelRejection => StatusCode::UNPROCESSABLE_ENTITY,
+        ControlPlaneErrorDomain::TransportError => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.message().to_string(),
+            code: error.code().to_string(),
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dx::api::control_plane::{AuthorityLevel, DefaultKernelAdapter};
+    use crate::observability::{AuditAction, MemoryAuditLog};
+    use chrono::Duration;
+    use tempfile::TempDir;
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
+    }
+
+    fn state(keys: &Arc<ApiKeyManager>, audit: &Arc<MemoryAuditLog>) -> Arc<ControlState> {
+        let handler = ControlPlaneHandler::with_kernel(Arc::new(DefaultKernelAdapter::default()))
+            .with_audit_log(Arc::clone(audit) as _);
+        Arc::new(ControlState::with_handler(handler, Some(Arc::clone(keys))))
+    }
+
+    fn checkpoint(node_id: Uuid, confirmation_token: Option<Uuid>) -> Json<CheckpointRequest> {
+        Json(CheckpointRequest {
+            node_id,
+            reason: Some("pre-upgrade".to_string()),
+            confirmation_token,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_requests_without_valid_key_rejected() {
+        let keys = Arc::new(ApiKeyManager::new("secret"));
+        let audit = Arc::new(MemoryAuditLog::new());
+        let state = state(&keys, &audit);
+
+        let (status, _) = checkpoint_handler(
+            State(Arc::clone(&state)),
+            None,
+            HeaderMap::new(),
+            checkpoint(Uuid::new_v4(), None),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (record, token) = keys
+            .issue("alice", AuthorityLevel::Operator, Duration::days(1))
+            .unwrap();
+        keys.revoke(record.id).unwrap();
+        let (status, Json(error)) = checkpoint_handler(
+            State(state),
+            None,
+            bearer(&token),
+            checkpoint(Uuid::new_v4(), None),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(error.error.contains("revoked"));
+
+        // Never reached the control plane, so nothing to audit
+        assert!(audit.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_observer_key_cannot_checkpoint() {
+        let keys = Arc::new(ApiKeyManager::new("secret"));
+        let audit = Arc::new(MemoryAuditLog::new());
+        let (_, token) = keys
+            .issue("bob", AuthorityLevel::Observer, Duration::days(1))
+            .unwrap();
+
+        let (status, Json(error)) = checkpoint_handler(
+            State(state(&keys, &audit)),
+            None,
+            bearer(&token),
+            checkpoint(Uuid::new_v4(), None),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(error.code, "PHASE7_INSUFFICIENT_AUTHORITY");
+
+        let records = audit.records();
+        assert_eq!(records.last().unwrap().action, AuditAction::CommandRejected);
+        assert_eq!(
+            records.last().unwrap().authority_level.as_deref(),
+            Some("OBSERVER")
+        );
+
+        // Observers may still list backups
+        let dir = TempDir::new().unwrap();
+        let query = ListBackupsQuery {
+            directory: dir.path().to_path_buf(),
+        };
+        let Json(list) = list_backups_handler(
+            State(state(&keys, &audit)),
+            None,
+            bearer(&token),
+            Query(query),
+        )
+        .await
+        .unwrap();
+        assert_eq!(list["outcome"], "Success");
+        assert_eq!(list["data"]["backups"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_operator_key_checkpoint_is_confirmed_and_audited() {
+        let keys = Arc::new(ApiKeyManager::new("secret"));
+        let audit = Arc::new(MemoryAuditLog::new());
+        let state = state(&keys, &audit);
+        let (record, token) = keys
+            .issue("alice", AuthorityLevel::Operator, Duration::days(1))
+            .unwrap();
+        let node_id = Uuid::new_v4();
+
+        let Json(pending) = checkpoint_handler(
+            State(Arc::clone(&state)),
+            None,
+            bearer(&token),
+            checkpoint(node_id, None),
+        )
+        .await
+        .unwrap();
+        assert_eq!(pending["outcome"], "AwaitingConfirmation");
+        let confirmation: Uuid = pending["confirmation_token"]
+            .as_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+
+        let Json(done) = checkpoint_handler(
+            State(state),
+            None,
+            bearer(&token),
+            checkpoint(node_id, Some(confirmation)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(done["outcome"], "Success");
+        assert_eq!(done["data"]["checkpoint_id"], Value::Null);
+        assert!(done["data"]["explanation"]
+            .as_str()
+            .unwrap()
+            .contains("not connected"));
+
+        let actions: Vec<AuditAction> = audit.records().iter().map(|r| r.action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                AuditAction::CommandRequested,
+                AuditAction::ConfirmationRequested,
+                AuditAction::CommandRequested,
+                AuditAction::CommandExecuted,
+            ]
+        );
+        let executed = audit.records().pop().unwrap();
+        assert_eq!(executed.operator_id, Some(format!("alice ({})", record.id)));
+        assert_eq!(executed.confirmation_token, Some(confirmation));
+        assert_eq!(executed.target_id, Some(node_id));
+    }
+
+    #[tokio::test]
+    async fn test_mapped_client_certificate_acts_as_operator() {
+        let keys = Arc::new(ApiKeyManager::new("secret"));
+        let audit = Arc::new(MemoryAuditLog::new());
+        let handler = ControlPlaneHandler::with_kernel(Arc::new(DefaultKernelAdapter::default()))
+            .with_audit_log(Arc::clone(&audit) as _);
+        let state = Arc::new(
+            ControlState::with_handler(handler, Some(keys))
+                .with_client_identities([("ops-1".to_string(), AuthorityLevel::Operator)]),
+        );
+        let identity = |name: &str| {
+            Some(Extension(ClientIdentity {
+                common_name: name.to_string(),
+            }))
+        };
+
+        let Json(pending) = checkpoint_handler(
+            State(Arc::clone(&state)),
+            identity("ops-1"),
+            HeaderMap::new(),
+            checkpoint(Uuid::new_v4(), None),
+        )
+        .await
+        .unwrap();
+        assert_eq!(pending["outcome"], "AwaitingConfirmation");
+        let requested = &audit.records()[0];
+        assert_eq!(requested.operator_id.as_deref(), Some("ops-1"));
+        assert_eq!(requested.authority_level.as_deref(), Some("OPERATOR"));
+
+        // Unmapped certificates carry no authority
+        let (status, _) = checkpoint_handler(
+            State(Arc::clone(&state)),
+            identity("guest"),
+            HeaderMap::new(),
+            checkpoint(Uuid::new_v4(), None),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // An API key, even an invalid one, takes precedence
+        let (status, _) = checkpoint_handler(
+            State(state),
+            identity("ops-1"),
+            bearer("not-a-key"),
+            checkpoint(Uuid::new_v4(), None),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
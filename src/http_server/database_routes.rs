@@ -13,8 +13,14 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::auth::errors::AuthError;
 use crate::auth::rls::RlsContext;
+use crate::auth::service_keys::ServiceKeyManager;
+use crate::core::context::AuthContext;
+use crate::core::executor::StorageBackend;
+use crate::core::middleware::idempotency::IDEMPOTENCY_KEY;
 use crate::core::{BridgeConfig, PipelineBridge, RequestContext};
+use crate::schema::FieldViolation;
 
 // ==================
 // Shared State
@@ -23,14 +29,31 @@ use crate::core::{BridgeConfig, PipelineBridge, RequestContext};
 /// Database state shared across handlers
 pub struct DatabaseState {
     pub bridge: Arc<PipelineBridge>,
+    /// Service API keys accepted in the `apikey` header
+    pub service_keys: Option<Arc<ServiceKeyManager>>,
 }
 
 impl DatabaseState {
     pub fn new() -> Self {
         Self {
             bridge: Arc::new(PipelineBridge::new_in_memory(BridgeConfig::default())),
+            service_keys: None,
         }
     }
+
+    /// Serve operations from `storage` instead of memory
+    pub fn with_storage(storage: Arc<dyn StorageBackend>, config: BridgeConfig) -> Self {
+        Self {
+            bridge: Arc::new(PipelineBridge::with_storage(storage, config)),
+            service_keys: None,
+        }
+    }
+
+    /// Accept service API keys issued by `keys`
+    pub fn with_service_keys(mut self, keys: Arc<ServiceKeyManager>) -> Self {
+        self.service_keys = Some(keys);
+        self
+    }
 }
 
 impl Default for DatabaseState {
@@ -184,6 +207,9 @@ pub struct CreateIndexRequest {
 pub struct ErrorResponse {
     pub error: String,
     pub code: u16,
+    /// Field violations, when the payload failed schema validation
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldViolation>,
 }
 
 #[derive(Debug, Serialize)]
@@ -235,7 +261,45 @@ pub fn database_routes(state: Arc<DatabaseState>) -> Router {
 // Helper Functions
 // ==================
 
-fn get_request_context(headers: &HeaderMap) -> RequestContext {
+/// Request context for the caller
+///
+/// A service API key in the `apikey` header acts with the service role,
+/// held by the pipeline to the key's scopes. An `Idempotency-Key` header
+/// is passed on to the pipeline's idempotency stage.
+fn get_request_context(
+    state: &DatabaseState,
+    headers: &HeaderMap,
+) -> Result<RequestContext, (StatusCode, Json<ErrorResponse>)> {
+    let ctx = caller_context(state, headers)?;
+    Ok(
+        match headers.get("idempotency-key").and_then(|v| v.to_str().ok()) {
+            Some(key) => ctx.with_metadata(IDEMPOTENCY_KEY, Value::String(key.to_string())),
+            None => ctx,
+        },
+    )
+}
+
+fn caller_context(
+    state: &DatabaseState,
+    headers: &HeaderMap,
+) -> Result<RequestContext, (StatusCode, Json<ErrorResponse>)> {
+    if let (Some(keys), Some(api_key)) = (&state.service_keys, headers.get("apikey")) {
+        let key = api_key
+            .to_str()
+            .map_err(|_| AuthError::InvalidToken)
+            .and_then(|token| keys.authenticate(token))
+            .map_err(|e| {
+                (
+                    StatusCode::UNAUTHORIZED,
+                    Json(ErrorResponse {
+                        error: e.to_string(),
+                        code: 401,
+                        errors: Vec::new(),
+                    }),
+                )
+            })?;
+        return Ok(RequestContext::new(AuthContext::service_key(key.grant())));
+    }
     if let Some(auth) = headers.get("authorization") {
         if auth
             .to_str()
@@ -243,10 +307,10 @@ fn get_request_context(headers: &HeaderMap) -> RequestContext {
             .map(|s| s.starts_with("Bearer "))
             .unwrap_or(false)
         {
-            return RequestContext::new(crate::core::context::AuthContext::service_role());
+            return Ok(RequestContext::new(AuthContext::service_role()));
         }
     }
-    RequestContext::anonymous()
+    Ok(RequestContext::anonymous())
 }
 
 // ==================
@@ -303,7 +367,7 @@ async fn get_table_data_handler(
     Path(name): Path<String>,
     Query(query): Query<TableDataQuery>,
 ) -> Result<Json<TableDataResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let ctx = get_request_context(&headers);
+    let ctx = get_request_context(&state, &headers)?;
     let limit = query.limit.unwrap_or(50);
     let offset = query.offset.unwrap_or(0);
 
@@ -318,6 +382,7 @@ async fn get_table_data_handler(
                 Json(ErrorResponse {
                     error: e.to_string(),
                     code: 500,
+                    errors: Vec::new(),
                 }),
             )
         })?;
@@ -338,7 +403,7 @@ async fn insert_row_handler(
     Path(name): Path<String>,
     Json(request): Json<InsertRowRequest>,
 ) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<ErrorResponse>)> {
-    let ctx = get_request_context(&headers);
+    let ctx = get_request_context(&state, &headers)?;
 
     let result = state
         .bridge
@@ -350,6 +415,7 @@ async fn insert_row_handler(
                 Json(ErrorResponse {
                     error: e.to_string(),
                     code: 400,
+                    errors: e.field_violations().to_vec(),
                 }),
             )
         })?;
@@ -362,7 +428,7 @@ async fn get_row_handler(
     headers: HeaderMap,
     Path((name, id)): Path<(String, String)>,
 ) -> Result<Json<Value>, (StatusCode, Json<ErrorResponse>)> {
-    let ctx = get_request_context(&headers);
+    let ctx = get_request_context(&state, &headers)?;
 
     let result = state.bridge.read(&name, &id, ctx).await.map_err(|e| {
         (
@@ -370,6 +436,7 @@ async fn get_row_handler(
             Json(ErrorResponse {
                 error: e.to_string(),
                 code: 404,
+                errors: Vec::new(),
             }),
         )
     })?;
@@ -382,7 +449,7 @@ async fn delete_row_handler(
     headers: HeaderMap,
     Path((name, id)): Path<(String, String)>,
 ) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
-    let ctx = get_request_context(&headers);
+    let ctx = get_request_context(&state, &headers)?;
 
     state.bridge.delete(&name, &id, ctx).await.map_err(|e| {
         (
@@ -390,6 +457,7 @@ async fn delete_row_handler(
             Json(ErrorResponse {
                 error: e.to_string(),
                 code: 404,
+                errors: Vec::new(),
             }),
         )
     })?;
@@ -2,22 +2,28 @@
 //!
 //! Endpoints for serverless function management and invocation.
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use axum::{
+    body::{Body, Bytes},
     extract::{Path, Query, State},
-    http::{HeaderMap, StatusCode},
-    routing::{delete, get, patch, post},
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{any, delete, get, patch, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use uuid::Uuid;
 
+use crate::functions::errors::FunctionError;
 use crate::functions::function::Function;
+use crate::functions::history::InvocationRecord;
+use crate::functions::http::{HttpRequest, HttpResponse};
 use crate::functions::invoker::{InvocationContext, InvocationResult, Invoker};
 use crate::functions::registry::FunctionRegistry;
-use crate::functions::trigger::TriggerType;
+use crate::functions::trigger::{HttpMethod, TriggerType};
 
 // ==================
 // Shared State
@@ -36,6 +42,14 @@ impl FunctionsState {
             invoker: Invoker::new(),
         }
     }
+
+    /// Functions state invoking functions through `invoker`
+    pub fn with_invoker(invoker: Invoker) -> Self {
+        Self {
+            registry: Arc::new(FunctionRegistry::new()),
+            invoker,
+        }
+    }
 }
 
 impl Default for FunctionsState {
@@ -162,10 +176,29 @@ pub struct FunctionLogsResponse {
 pub struct InvocationHistoryEntry {
     pub id: String,
     pub function_id: String,
-    pub timestamp: String,
+    pub invoked_at: String,
+    pub status: String,
     pub duration_ms: u64,
-    pub success: bool,
+    pub fuel_consumed: u64,
     pub error: Option<String>,
+    pub logs: Vec<String>,
+    pub logs_truncated: bool,
+}
+
+impl From<InvocationRecord> for InvocationHistoryEntry {
+    fn from(record: InvocationRecord) -> Self {
+        Self {
+            id: record.id.to_string(),
+            function_id: record.function_id.to_string(),
+            invoked_at: record.invoked_at.to_rfc3339(),
+            status: record.status.as_str().to_string(),
+            duration_ms: record.duration_ms,
+            fuel_consumed: record.fuel_consumed,
+            error: record.error,
+            logs: record.logs,
+            logs_truncated: record.logs_truncated,
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -216,6 +249,16 @@ pub struct LogsQuery {
     pub since: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct InvocationsQuery {
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: Option<usize>,
+    #[serde(default)]
+    pub since: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
     pub error: String,
@@ -237,19 +280,21 @@ pub fn functions_routes(state: Arc<FunctionsState>) -> Router {
         // CRUD operations
         .route("/", get(list_functions_handler))
         .route("/", post(create_function_handler))
-        .route("/{id}", get(get_function_handler))
-        .route("/{id}", patch(update_function_handler))
-        .route("/{id}", delete(delete_function_handler))
+        .route("/:id", get(get_function_handler))
+        .route("/:id", patch(update_function_handler))
+        .route("/:id", delete(delete_function_handler))
         // Invocation
-        .route("/{id}/invoke", post(invoke_function_handler))
+        .route("/:id/invoke", post(invoke_function_handler))
         // Logs and history
-        .route("/{id}/logs", get(get_function_logs_handler))
-        .route("/{id}/invocations", get(get_invocations_handler))
-        .route("/{id}/stats", get(get_function_stats_handler))
+        .route("/:id/logs", get(get_function_logs_handler))
+        .route("/:id/invocations", get(get_invocations_handler))
+        .route("/:id/stats", get(get_function_stats_handler))
         // Versioning
-        .route("/{id}/versions", get(list_versions_handler))
+        .route("/:id/versions", get(list_versions_handler))
         // Templates
         .route("/templates", get(list_templates_handler))
+        // HTTP triggers
+        .route("/http/*path", any(http_trigger_handler))
         .with_state(state)
 }
 
@@ -261,7 +306,14 @@ fn parse_trigger(trigger_type: &str, config: &Value) -> Option<TriggerType> {
     match trigger_type.to_lowercase().as_str() {
         "http" => {
             let path = config.get("path").and_then(|v| v.as_str()).unwrap_or("/");
-            Some(TriggerType::http(path.to_string()))
+            let method = match config.get("method").and_then(|v| v.as_str()) {
+                Some(method) => HttpMethod::parse(method)?,
+                None => HttpMethod::default(),
+            };
+            Some(TriggerType::Http {
+                path: path.to_string(),
+                method,
+            })
         }
         "database" => {
             let collection = config.get("collection").and_then(|v| v.as_str())?;
@@ -281,6 +333,57 @@ fn parse_trigger(trigger_type: &str, config: &Value) -> Option<TriggerType> {
     }
 }
 
+/// Retained invocations of function `name`, newest first, at or after
+/// `since` when given
+///
+/// A function that is neither registered nor has history is not found.
+fn function_invocations(
+    state: &FunctionsState,
+    name: &str,
+    since: Option<&str>,
+) -> Result<Vec<InvocationRecord>, (StatusCode, Json<ErrorResponse>)> {
+    let since = since
+        .map(chrono::DateTime::parse_from_rfc3339)
+        .transpose()
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: format!("Invalid since timestamp: {}", e),
+                    code: 400,
+                }),
+            )
+        })?;
+
+    let mut invocations = state.invoker.history().invocations(name);
+    if invocations.is_empty() {
+        state.registry.get(name).map_err(|e| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                    code: 404,
+                }),
+            )
+        })?;
+    }
+    if let Some(since) = since {
+        invocations.retain(|r| r.invoked_at >= since);
+    }
+    Ok(invocations)
+}
+
+fn function_error(e: FunctionError) -> (StatusCode, Json<ErrorResponse>) {
+    let code = e.status_code();
+    (
+        StatusCode::from_u16(code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+        Json(ErrorResponse {
+            error: e.to_string(),
+            code,
+        }),
+    )
+}
+
 fn get_user_id_from_headers(headers: &HeaderMap) -> Option<Uuid> {
     // Would extract from JWT token
     None
@@ -343,15 +446,10 @@ async fn create_function_handler(
 
     let function = Function::new(request.name, trigger, wasm_bytes);
 
-    state.registry.register(function.clone()).map_err(|e| {
-        (
-            StatusCode::CONFLICT,
-            Json(ErrorResponse {
-                error: e.to_string(),
-                code: 409,
-            }),
-        )
-    })?;
+    state
+        .registry
+        .register(function.clone())
+        .map_err(function_error)?;
 
     Ok((StatusCode::CREATED, Json(FunctionResponse::from(&function))))
 }
@@ -384,6 +482,7 @@ async fn delete_function_handler(
             }),
         )
     })?;
+    state.invoker.history().forget(&id);
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -424,43 +523,146 @@ async fn invoke_function_handler(
     Ok(Json(InvokeResponse::from(result)))
 }
 
+/// Run the HTTP-triggered function routed `method` and `path`, mapping its
+/// result to the response
+async fn http_trigger_handler(
+    State(state): State<Arc<FunctionsState>>,
+    method: Method,
+    Path(path): Path<String>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let path = format!("/{}", path.trim_start_matches('/'));
+    let routed = HttpMethod::parse(method.as_str())
+        .ok_or_else(|| FunctionError::MethodNotAllowed(format!("{} {}", method, path)))
+        .map_err(function_error)?;
+    let (function, params) = state
+        .registry
+        .route(routed, &path)
+        .map_err(function_error)?;
+
+    let mut request = HttpRequest {
+        method: routed.as_str().to_string(),
+        path,
+        params,
+        query,
+        body: HttpRequest::parse_body(&body).map_err(function_error)?,
+        ..HttpRequest::default()
+    };
+    for (name, value) in &headers {
+        if let Ok(value) = value.to_str() {
+            request.add_header(name.as_str(), value);
+        }
+    }
+
+    let user_id = get_user_id_from_headers(&headers);
+    let context = InvocationContext::http(&function, request, user_id);
+    let result = state
+        .invoker
+        .invoke(&function, context)
+        .map_err(function_error)?;
+    if !result.success {
+        return Err(function_error(FunctionError::RuntimeError(
+            result.error.unwrap_or_else(|| "Unknown error".to_string()),
+        )));
+    }
+
+    let invalid = |reason: String| {
+        (
+            StatusCode::BAD_GATEWAY,
+            Json(ErrorResponse {
+                error: format!("Function returned an invalid response: {}", reason),
+                code: 502,
+            }),
+        )
+    };
+    let mapped = HttpResponse::from_result(result.result.unwrap_or(Value::Null))
+        .map_err(|e| invalid(e.to_string()))?;
+    let mut response = match mapped.body {
+        Value::Null => Response::new(Body::empty()),
+        Value::String(text) => text.into_response(),
+        body => Json(body).into_response(),
+    };
+    *response.status_mut() =
+        StatusCode::from_u16(mapped.status).map_err(|e| invalid(e.to_string()))?;
+    for (name, value) in mapped.headers {
+        let name = HeaderName::try_from(name).map_err(|e| invalid(e.to_string()))?;
+        let value = HeaderValue::try_from(value).map_err(|e| invalid(e.to_string()))?;
+        response.headers_mut().insert(name, value);
+    }
+    Ok(response)
+}
+
 // ==================
 // Logs and History Handlers
 // ==================
 
 async fn get_function_logs_handler(
-    State(_state): State<Arc<FunctionsState>>,
+    State(state): State<Arc<FunctionsState>>,
     Path(id): Path<String>,
     Query(query): Query<LogsQuery>,
 ) -> Result<Json<FunctionLogsResponse>, (StatusCode, Json<ErrorResponse>)> {
-    // Would retrieve from log storage
+    // Logs of retained invocations, newest invocation first
+    let logs: Vec<FunctionLogEntry> = function_invocations(&state, &id, query.since.as_deref())?
+        .into_iter()
+        .flat_map(|record| {
+            let timestamp = record.invoked_at.to_rfc3339();
+            let level = if record.error.is_some() {
+                "error"
+            } else {
+                "info"
+            };
+            record
+                .logs
+                .into_iter()
+                .map(move |message| FunctionLogEntry {
+                    timestamp: timestamp.clone(),
+                    level: level.to_string(),
+                    message,
+                })
+        })
+        .collect();
+    let total = logs.len();
+
     Ok(Json(FunctionLogsResponse {
-        logs: vec![],
-        total: 0,
+        logs: logs.into_iter().take(query.limit.unwrap_or(100)).collect(),
+        total,
     }))
 }
 
 async fn get_invocations_handler(
-    State(_state): State<Arc<FunctionsState>>,
+    State(state): State<Arc<FunctionsState>>,
     Path(id): Path<String>,
+    Query(query): Query<InvocationsQuery>,
 ) -> Result<Json<InvocationsResponse>, (StatusCode, Json<ErrorResponse>)> {
-    // Would retrieve from invocation history store
+    let invocations = function_invocations(&state, &id, query.since.as_deref())?;
+    let total = invocations.len();
+
     Ok(Json(InvocationsResponse {
-        invocations: vec![],
-        total: 0,
+        invocations: invocations
+            .into_iter()
+            .skip(query.offset.unwrap_or(0))
+            .take(query.limit.unwrap_or(50))
+            .map(InvocationHistoryEntry::from)
+            .collect(),
+        total,
     }))
 }
 
 async fn get_function_stats_handler(
-    State(_state): State<Arc<FunctionsState>>,
+    State(state): State<Arc<FunctionsState>>,
     Path(id): Path<String>,
 ) -> Result<Json<FunctionStatsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    function_invocations(&state, &id, None)?;
+    let stats = state.invoker.history().stats(&id);
+
     Ok(Json(FunctionStatsResponse {
-        invocation_count: 0,
-        success_count: 0,
-        error_count: 0,
-        avg_duration_ms: 0.0,
-        last_invoked_at: None,
+        invocation_count: stats.invocation_count,
+        success_count: stats.success_count,
+        error_count: stats.error_count,
+        avg_duration_ms: stats.avg_duration_ms,
+        last_invoked_at: stats.last_invoked_at.map(|t| t.to_rfc3339()),
     }))
 }
 
@@ -516,6 +718,142 @@ mod tests {
         assert!(state.registry.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_invocation_history_routes() {
+        use axum::body::{to_bytes, Body};
+        use axum::http::Request;
+        use tower::Service;
+
+        let state = Arc::new(FunctionsState::new());
+        state
+            .registry
+            .register(Function::new(
+                "echo".to_string(),
+                TriggerType::http("/echo".to_string()),
+                vec![0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00],
+            ))
+            .unwrap();
+        let router = functions_routes(state);
+        let call = |request: Request<Body>| router.clone().call(request);
+        let get_json = |uri: &str| {
+            let response = call(Request::get(uri).body(Body::empty()).unwrap());
+            async move {
+                let response = response.await.unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (
+                    status,
+                    serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null),
+                )
+            }
+        };
+
+        for _ in 0..2 {
+            let response = call(
+                Request::post("/echo/invoke")
+                    .header(axum::http::header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(r#"{"payload":{}}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let (status, body) = get_json("/echo/invocations?limit=1").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 2);
+        assert_eq!(body["invocations"].as_array().unwrap().len(), 1);
+        assert_eq!(body["invocations"][0]["status"], "success");
+
+        let (_, body) = get_json("/echo/stats").await;
+        assert_eq!(body["invocation_count"], 2);
+
+        let (status, _) = get_json("/missing/invocations").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = get_json("/echo/invocations?since=yesterday").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_http_trigger_routing() {
+        use axum::body::to_bytes;
+        use axum::http::Request;
+        use tower::Service;
+
+        // Responds 201 with the request envelope it was given as its body
+        let prefix = r#"{"status":201,"headers":{"x-fn":"echo"},"body":"#;
+        let echo = format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 0) "{}")
+                (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+                (func (export "handle") (param $ptr i32) (param $len i32) (result i64)
+                    (memory.copy (i32.const 8192) (i32.const 0) (i32.const {p}))
+                    (memory.copy (i32.const {out}) (local.get $ptr) (local.get $len))
+                    (i32.store8 (i32.add (i32.const {out}) (local.get $len)) (i32.const 125))
+                    (i64.or
+                        (i64.const 35184372088832)
+                        (i64.extend_i32_u
+                            (i32.add (local.get $len) (i32.const {tail}))))))"#,
+            prefix.replace('"', "\\\""),
+            p = prefix.len(),
+            out = 8192 + prefix.len(),
+            tail = prefix.len() + 1,
+        );
+        let state = Arc::new(FunctionsState::new());
+        let mut function = Function::new(
+            "get_user".to_string(),
+            TriggerType::Http {
+                path: "/users/:id".to_string(),
+                method: HttpMethod::Get,
+            },
+            echo.into_bytes(),
+        );
+        state.registry.register(function.clone()).unwrap();
+        function.id = Uuid::new_v4();
+        function.name = "get_user_by_uid".to_string();
+        function.trigger = TriggerType::Http {
+            path: "/users/:uid".to_string(),
+            method: HttpMethod::Get,
+        };
+        assert!(matches!(
+            state.registry.register(function),
+            Err(FunctionError::AlreadyExists(_))
+        ));
+
+        let router = functions_routes(state);
+        let call = |request: Request<Body>| router.clone().call(request);
+        let response = call(
+            Request::get("/http/users/42?fields=name")
+                .header("x-trace", "t1")
+                .header("authorization", "Bearer secret")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()["x-fn"], "echo");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let request: HttpRequest = serde_json::from_slice(&body).unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/users/42");
+        assert_eq!(request.params["id"], "42");
+        assert_eq!(request.query["fields"], "name");
+        assert_eq!(request.headers["x-trace"], "t1");
+        assert!(!request.headers.contains_key("authorization"));
+
+        let response = call(Request::post("/http/users/42").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        let response = call(Request::get("/http/teams/42").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
     #[test]
     fn test_parse_http_trigger() {
         let config = serde_json::json!({ "path": "/api/test" });
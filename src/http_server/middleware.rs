@@ -0,0 +1,189 @@
+//! HTTP Middleware
+//!
+//! CORS and security headers applied to every route, driven by
+//! `HttpServerConfig`.
+//!
+//! Cross-origin access is granted only to the configured origins, methods
+//! and request headers; `"*"` as an origin allows any origin. Security
+//! headers stop browsers from sniffing, framing or leaking the referrer of
+//! API responses, and pin HTTPS when the server terminates TLS.
+
+use axum::extract::State;
+use axum::http::{header, HeaderName, HeaderValue, Method};
+use axum::response::Response;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+/// `Strict-Transport-Security` max-age: one year
+const HSTS_MAX_AGE_SECS: u64 = 365 * 24 * 60 * 60;
+
+/// CORS layer allowing `origins`, `methods` and request `headers`
+///
+/// # Errors
+///
+/// An entry that is not a valid origin, method or header name.
+pub fn cors_layer(
+    origins: &[String],
+    methods: &[String],
+    headers: &[String],
+) -> Result<CorsLayer, String> {
+    let allow_origin = if origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            origins
+                .iter()
+                .map(|o| parse_origin(o))
+                .collect::<Result<Vec<_>, _>>()?,
+        )
+    };
+    let methods = methods
+        .iter()
+        .map(|m| {
+            Method::from_bytes(m.to_ascii_uppercase().as_bytes())
+                .map_err(|_| format!("'{}' is not an HTTP method", m))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let headers = headers
+        .iter()
+        .map(|h| {
+            HeaderName::try_from(h.as_str()).map_err(|_| format!("'{}' is not a header name", h))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(methods)
+        .allow_headers(headers))
+}
+
+/// `scheme://host[:port]`, as browsers send it in `Origin`
+fn parse_origin(origin: &str) -> Result<HeaderValue, String> {
+    let invalid = || format!("'{}' is not an origin (scheme://host[:port])", origin);
+    let (scheme, host) = origin.split_once("://").ok_or_else(invalid)?;
+    if !matches!(scheme, "http" | "https") || host.is_empty() || host.contains('/') {
+        return Err(invalid());
+    }
+    HeaderValue::from_str(origin).map_err(|_| invalid())
+}
+
+/// Add security headers to `response`, keeping any a handler already set
+///
+/// The state is whether the server terminates TLS, which adds
+/// `Strict-Transport-Security`.
+pub async fn security_headers(State(https): State<bool>, mut response: Response) -> Response {
+    let headers = response.headers_mut();
+    let mut set = |name: HeaderName, value: &str| {
+        if let Ok(value) = HeaderValue::from_str(value) {
+            headers.entry(name).or_insert(value);
+        }
+    };
+    set(header::X_CONTENT_TYPE_OPTIONS, "nosniff");
+    set(header::X_FRAME_OPTIONS, "DENY");
+    set(header::REFERRER_POLICY, "no-referrer");
+    set(
+        header::CONTENT_SECURITY_POLICY,
+        "default-src 'none'; frame-ancestors 'none'",
+    );
+    if https {
+        set(
+            header::STRICT_TRANSPORT_SECURITY,
+            &format!("max-age={}", HSTS_MAX_AGE_SECS),
+        );
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use axum::middleware::map_response_with_state;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::Service;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    fn router(cors: CorsLayer, https: bool) -> Router {
+        Router::new()
+            .route("/health", get(|| async { "ok" }))
+            .layer(cors)
+            .layer(map_response_with_state(https, security_headers))
+    }
+
+    #[tokio::test]
+    async fn test_cors_allows_only_configured_origins() {
+        let cors = cors_layer(
+            &strings(&["https://dash.example.com"]),
+            &strings(&["get", "POST"]),
+            &strings(&["authorization"]),
+        )
+        .unwrap();
+        let mut router = router(cors, false);
+
+        let preflight = |origin: &str| {
+            Request::builder()
+                .method(Method::OPTIONS)
+                .uri("/health")
+                .header(header::ORIGIN, origin)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let allowed = router
+            .call(preflight("https://dash.example.com"))
+            .await
+            .unwrap();
+        assert_eq!(
+            allowed.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://dash.example.com"
+        );
+        assert_eq!(
+            allowed.headers()[header::ACCESS_CONTROL_ALLOW_METHODS],
+            "GET,POST"
+        );
+
+        let denied = router
+            .call(preflight("https://evil.example"))
+            .await
+            .unwrap();
+        assert!(!denied
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[tokio::test]
+    async fn test_security_headers_added() {
+        let cors = cors_layer(&[], &[], &[]).unwrap();
+        let request = || Request::get("/health").body(Body::empty()).unwrap();
+
+        let response = router(cors.clone(), false).call(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::X_CONTENT_TYPE_OPTIONS],
+            "nosniff"
+        );
+        assert_eq!(response.headers()[header::X_FRAME_OPTIONS], "DENY");
+        assert!(!response
+            .headers()
+            .contains_key(header::STRICT_TRANSPORT_SECURITY));
+
+        let response = router(cors, true).call(request()).await.unwrap();
+        assert_eq!(
+            response.headers()[header::STRICT_TRANSPORT_SECURITY],
+            "max-age=31536000"
+        );
+    }
+
+    #[test]
+    fn test_invalid_entries_rejected() {
+        assert!(cors_layer(&strings(&["*"]), &[], &[]).is_ok());
+        assert!(cors_layer(&strings(&["localhost:5173"]), &[], &[]).is_err());
+        assert!(cors_layer(&strings(&["http://a.com/path"]), &[], &[]).is_err());
+        assert!(cors_layer(&[], &strings(&["GE T"]), &[]).is_err());
+        assert!(cors_layer(&[], &[], &strings(&["bad header"])).is_err());
+    }
+}
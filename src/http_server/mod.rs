@@ -17,19 +17,23 @@
 //! - `/realtime/*` - Real-time subscriptions and WebSocket
 //! - `/backup/*` - Backup and restore endpoints
 //! - `/cluster/*` - Cluster management endpoints
+//! - `/control/*` - Control plane checkpoint and backup commands
 
 pub mod auth_management_routes;
 pub mod auth_routes;
 pub mod backup_routes;
 pub mod cluster_routes;
 pub mod config;
+pub mod control_routes;
 pub mod database_routes;
 pub mod functions_routes;
+pub mod middleware;
 pub mod observability_routes;
 pub mod realtime_routes;
 pub mod server;
 pub mod setup_routes;
 pub mod storage_routes;
+pub mod tls;
 
-pub use config::HttpServerConfig;
-pub use server::HttpServer;
+pub use config::{HttpServerConfig, TlsConfig};
+pub use server::{AuthStores, HttpServer};
@@ -1,12 +1,24 @@
 //! Observability HTTP Routes
 //!
 //! HTTP endpoints for system observability including health checks and metrics.
+//!
+//! `/metrics` serves the Prometheus text format, or JSON to clients whose
+//! `Accept` header lists `application/json` first.
+
+use std::sync::Arc;
 
-use axum::{http::StatusCode, response::IntoResponse, routing::get, Json, Router};
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
 use serde::Serialize;
 use serde_json::Value;
 
-use crate::observability::MetricsRegistry;
+use crate::api::MaintenanceGate;
+use crate::observability::{render_prometheus, MetricsRegistry, PROMETHEUS_CONTENT_TYPE};
 
 /// Health check response
 #[derive(Debug, Serialize)]
@@ -15,11 +27,12 @@ pub struct HealthResponse {
     pub version: String,
 }
 
-/// Create observability routes
-pub fn observability_routes() -> Router {
+/// Create observability routes serving `metrics`
+pub fn observability_routes(metrics: Arc<MetricsRegistry>) -> Router {
     Router::new()
         .route("/health", get(health_handler))
         .route("/metrics", get(metrics_handler))
+        .with_state(metrics)
 }
 
 /// Health check route (also available at root /health)
@@ -37,16 +50,63 @@ async fn health_handler() -> impl IntoResponse {
     (StatusCode::OK, Json(response))
 }
 
-/// Metrics handler - returns metrics as JSON
-async fn metrics_handler() -> impl IntoResponse {
-    let registry = MetricsRegistry::new();
-    let json_str = registry.to_json();
+/// Health check route that reports maintenance mode
+///
+/// Returns 503 with status "maintenance" while the gate is active so load
+/// balancers stop routing new work to the node. A read-only node (storage
+/// full) still serves reads, so it answers 200 with status "read_only".
+pub fn maintenance_health_routes(gate: Arc<MaintenanceGate>) -> Router {
+    Router::new()
+        .route("/health", get(maintenance_health_handler))
+        .with_state(gate)
+}
 
-    // Parse the JSON string to a Value for proper JSON response
-    let metrics: Value = serde_json::from_str(&json_str)
-        .unwrap_or_else(|_| serde_json::json!({"error": "Failed to serialize metrics"}));
+/// Health check handler backed by the maintenance gate
+async fn maintenance_health_handler(State(gate): State<Arc<MaintenanceGate>>) -> impl IntoResponse {
+    let status = if gate.is_active() {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    let response = HealthResponse {
+        status: gate.health_status().to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+    };
 
-    (StatusCode::OK, Json(metrics))
+    (status, Json(response))
+}
+
+/// Metrics handler - Prometheus text, or JSON when asked for first
+async fn metrics_handler(
+    State(registry): State<Arc<MetricsRegistry>>,
+    headers: HeaderMap,
+) -> Response {
+    if prefers_json(&headers) {
+        let json_str = registry.to_json();
+
+        // Parse the JSON string to a Value for proper JSON response
+        let metrics: Value = serde_json::from_str(&json_str)
+            .unwrap_or_else(|_| serde_json::json!({"error": "Failed to serialize metrics"}));
+
+        return (StatusCode::OK, Json(metrics)).into_response();
+    }
+
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)],
+        render_prometheus(&registry.snapshot()),
+    )
+        .into_response()
+}
+
+/// Whether the first media type in `Accept` is `application/json`
+fn prefers_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .and_then(|accept| accept.split(',').next())
+        .map(|first| first.split(';').next().unwrap_or("").trim() == "application/json")
+        .unwrap_or(false)
 }
 
 #[cfg(test)]
@@ -63,4 +123,35 @@ mod tests {
         let json = serde_json::to_string(&response).unwrap();
         assert!(json.contains("ok"));
     }
+
+    #[tokio::test]
+    async fn test_metrics_negotiates_format() {
+        let registry = Arc::new(MetricsRegistry::new());
+        registry.increment_wal_fsyncs();
+
+        let response = metrics_handler(State(Arc::clone(&registry)), HeaderMap::new()).await;
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            PROMETHEUS_CONTENT_TYPE
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::ACCEPT,
+            "application/json, text/plain, */*".parse().unwrap(),
+        );
+        let response = metrics_handler(State(registry), headers).await;
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_health_reports_maintenance() {
+        let gate = MaintenanceGate::shared();
+        gate.enter(true, None);
+
+        let response = maintenance_health_handler(State(gate))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
 }
@@ -20,6 +20,8 @@ use serde_json::Value;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
+use crate::realtime::PresenceChannels;
+
 // ==================
 // Shared State
 // ==================
@@ -28,6 +30,8 @@ use uuid::Uuid;
 pub struct RealtimeState {
     pub active_connections: Arc<RwLock<usize>>,
     pub subscriptions: Arc<RwLock<Vec<SubscriptionInfo>>>,
+    /// Presence channels, shared with the WebSocket server
+    pub presence: Arc<PresenceChannels>,
 }
 
 #[derive(Debug, Clone)]
@@ -43,8 +47,15 @@ impl RealtimeState {
         Self {
             active_connections: Arc::new(RwLock::new(0)),
             subscriptions: Arc::new(RwLock::new(Vec::new())),
+            presence: Arc::new(PresenceChannels::new()),
         }
     }
+
+    /// Serve presence from channels shared with a WebSocket server
+    pub fn with_presence(mut self, presence: Arc<PresenceChannels>) -> Self {
+        self.presence = presence;
+        self
+    }
 }
 
 impl Default for RealtimeState {
@@ -98,6 +109,14 @@ pub struct RealtimeStatsResponse {
     pub messages_per_minute: u64,
 }
 
+#[derive(Debug, Serialize)]
+pub struct PresenceResponse {
+    pub channel: String,
+    pub count: usize,
+    /// Members by user ID: metadata, joined_at, last_seen
+    pub members: Value,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
     pub error: String,
@@ -172,6 +191,8 @@ pub fn realtime_routes(state: Arc<RealtimeState>) -> Router {
         .route("/broadcast", post(broadcast_handler))
         // Stats
         .route("/stats", get(get_stats_handler))
+        // Current presence state per channel
+        .route("/presence/{channel}", get(get_presence_handler))
         // WebSocket endpoint
         .route("/ws", get(websocket_handler))
         .with_state(state)
@@ -398,9 +419,42 @@ async fn get_stats_handler(
     }))
 }
 
+/// Get the current presence state of a channel
+async fn get_presence_handler(
+    State(state): State<Arc<RealtimeState>>,
+    Path(channel): Path<String>,
+) -> Result<Json<PresenceResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let internal = |e: crate::realtime::RealtimeError| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+                code: 500,
+            }),
+        )
+    };
+    let Some(tracker) = state.presence.tracker(&channel) else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "Presence channel not found".to_string(),
+                code: 404,
+            }),
+        ));
+    };
+    let sync = tracker.sync().map_err(internal)?;
+
+    Ok(Json(PresenceResponse {
+        channel,
+        count: tracker.count(),
+        members: sync.state,
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::auth::rls::RlsContext;
 
     #[test]
     fn test_websocket_message_creation() {
@@ -409,6 +463,34 @@ mod tests {
         assert_eq!(msg.channel, Some("test-channel".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_presence_endpoint() {
+        let state = Arc::new(RealtimeState::new());
+        let user_id = Uuid::new_v4();
+        state
+            .presence
+            .join(
+                "lobby",
+                &RlsContext::authenticated(user_id),
+                "conn-1",
+                serde_json::json!({"status": "online"}),
+            )
+            .unwrap();
+
+        let Json(response) =
+            get_presence_handler(State(Arc::clone(&state)), Path("lobby".to_string()))
+                .await
+                .unwrap();
+        assert_eq!(response.count, 1);
+        assert_eq!(
+            response.members[user_id.to_string()]["metadata"]["status"],
+            "online"
+        );
+
+        let missing = get_presence_handler(State(state), Path("nowhere".to_string())).await;
+        assert_eq!(missing.unwrap_err().0, StatusCode::NOT_FOUND);
+    }
+
     #[test]
     fn test_realtime_state_creation() {
         let state = RealtimeState::new();
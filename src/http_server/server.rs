@@ -7,21 +7,47 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+use axum::middleware::map_response_with_state;
 use axum::Router;
 use tokio::net::TcpListener;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::cors::CorsLayer;
 
 use super::auth_management_routes::auth_management_routes;
 use super::auth_routes::{auth_routes, AuthState};
 use super::backup_routes::{backup_routes, BackupState};
 use super::cluster_routes::{cluster_routes, ClusterState};
 use super::config::HttpServerConfig;
+use super::control_routes::{control_routes, ControlState};
 use super::database_routes::{database_routes, DatabaseState};
 use super::functions_routes::{functions_routes, FunctionsState};
+use super::middleware::{cors_layer, security_headers};
 use super::observability_routes::{health_routes, observability_routes};
 use super::realtime_routes::{realtime_routes, RealtimeState};
 use super::setup_routes::{setup_routes, SetupState};
 use super::storage_routes::{storage_routes, StorageState};
+use super::tls::{self, TlsReloader};
+use crate::auth::{HttpsTransport, MfaPolicy, OidcManager, RevocationStore, ServiceKeyManager};
+use crate::core::{BridgeConfig, DurableStorage, InMemoryStorage};
+use crate::file_storage::UploadSessionStore;
+use crate::functions::{InvocationHistory, Invoker, SecretStore};
+use crate::observability::MetricsRegistry;
+
+/// Auth state that outlives the server, opened from the data directory
+#[derive(Clone, Default)]
+pub struct AuthStores {
+    /// Revoked auth tokens
+    pub revocations: Arc<RevocationStore>,
+    /// Service API keys
+    pub service_keys: Arc<ServiceKeyManager>,
+    /// Secrets bound into function invocations
+    pub function_secrets: Arc<SecretStore>,
+    /// Recent function invocations
+    pub function_history: Arc<InvocationHistory>,
+    /// Resumable file upload sessions
+    pub upload_sessions: Arc<UploadSessionStore>,
+    /// Durable storage behind the database routes; in memory when unset
+    pub database: Option<Arc<DurableStorage>>,
+}
 
 /// HTTP Server for AeroDB Dashboard
 pub struct HttpServer {
@@ -37,46 +63,108 @@ impl HttpServer {
 
     /// Create a new HTTP server with custom configuration
     pub fn with_config(config: HttpServerConfig) -> Self {
-        let router = Self::build_router(&config);
+        Self::with_metrics(config, Arc::new(MetricsRegistry::new()))
+    }
+
+    /// Create a new HTTP server exporting `metrics` at `/observability/metrics`
+    pub fn with_metrics(config: HttpServerConfig, metrics: Arc<MetricsRegistry>) -> Self {
+        Self::with_control(config, metrics, Arc::new(ControlState::new()))
+    }
+
+    /// Create a new HTTP server running `/control/*` commands through `control`
+    pub fn with_control(
+        config: HttpServerConfig,
+        metrics: Arc<MetricsRegistry>,
+        control: Arc<ControlState>,
+    ) -> Self {
+        Self::with_revocations(config, metrics, control, Arc::new(RevocationStore::new()))
+    }
+
+    /// Create a new HTTP server recording revoked auth tokens in
+    /// `revocations`
+    pub fn with_revocations(
+        config: HttpServerConfig,
+        metrics: Arc<MetricsRegistry>,
+        control: Arc<ControlState>,
+        revocations: Arc<RevocationStore>,
+    ) -> Self {
+        let stores = AuthStores {
+            revocations,
+            ..AuthStores::default()
+        };
+        Self::with_auth_stores(config, metrics, control, stores)
+    }
+
+    /// Create a new HTTP server keeping auth state in `stores`
+    pub fn with_auth_stores(
+        config: HttpServerConfig,
+        metrics: Arc<MetricsRegistry>,
+        control: Arc<ControlState>,
+        stores: AuthStores,
+    ) -> Self {
+        let router = Self::build_router(&config, metrics, control, stores);
         Self { config, router }
     }
 
     /// Build the combined router with all endpoints
-    fn build_router(config: &HttpServerConfig) -> Router {
+    fn build_router(
+        config: &HttpServerConfig,
+        metrics: Arc<MetricsRegistry>,
+        control_state: Arc<ControlState>,
+        stores: AuthStores,
+    ) -> Router {
         // Create shared states for each module
         let setup_state = Arc::new(SetupState::new());
-        let auth_state = Arc::new(AuthState::new());
-        let storage_state = Arc::new(StorageState::with_default_path());
-        let database_state = Arc::new(DatabaseState::new());
-        let functions_state = Arc::new(FunctionsState::new());
+        let mut auth_state = AuthState::with_revocations(stores.revocations)
+            .with_mfa_policy(MfaPolicy::require_for(
+                config.mfa_required_roles.iter().cloned(),
+            ))
+            .with_service_keys(Arc::clone(&stores.service_keys));
+        if !config.oidc.is_empty() {
+            auth_state = auth_state.with_oidc(OidcManager::new(
+                config.oidc.clone(),
+                Arc::new(HttpsTransport::default()),
+            ));
+        }
+        let auth_state = Arc::new(auth_state);
+        let storage_state = Arc::new(
+            StorageState::with_default_path().with_upload_sessions(stores.upload_sessions),
+        );
+        let bridge_config = BridgeConfig {
+            middleware: Some(config.pipeline.clone()),
+            ..BridgeConfig::default()
+        };
+        let database_state = match stores.database {
+            Some(storage) => DatabaseState::with_storage(
+                storage.clone(),
+                BridgeConfig {
+                    schemas: Some(storage),
+                    ..bridge_config
+                },
+            ),
+            None => DatabaseState::with_storage(Arc::new(InMemoryStorage::new()), bridge_config),
+        };
+        let database_state = Arc::new(database_state.with_service_keys(stores.service_keys));
+        let functions_state = Arc::new(FunctionsState::with_invoker(
+            Invoker::new()
+                .with_secrets(stores.function_secrets)
+                .with_history(stores.function_history),
+        ));
         let realtime_state = Arc::new(RealtimeState::new());
         let backup_state = Arc::new(BackupState::new());
         let cluster_state = Arc::new(ClusterState::new());
 
-        // Configure CORS from config
-        let cors = if config.cors_origins.is_empty() {
-            // If no origins configured, use permissive for development
-            CorsLayer::new()
-                .allow_origin(Any)
-                .allow_methods(Any)
-                .allow_headers(Any)
-        } else {
-            // Use configured origins for production
-            use tower_http::cors::AllowOrigin;
-            let origins: Vec<_> = config
-                .cors_origins
-                .iter()
-                .filter_map(|s| s.parse().ok())
-                .collect();
-
-            CorsLayer::new()
-                .allow_origin(AllowOrigin::list(origins))
-                .allow_methods(Any)
-                .allow_headers(Any)
-        };
+        // Configure CORS from config; config validation rejects invalid
+        // entries, so one slipping through here fails closed
+        let cors = cors_layer(
+            &config.cors_origins,
+            &config.cors_methods,
+            &config.cors_headers,
+        )
+        .unwrap_or_else(|_| CorsLayer::new());
 
         // Combine all routes
-        Router::new()
+        let router = Router::new()
             // Health check at root level
             .merge(health_routes())
             // Setup routes under /setup (first-run wizard, locked after complete)
@@ -86,7 +174,7 @@ impl HttpServer {
             // Auth management routes (extends /auth with user management, sessions, RLS, etc.)
             .nest("/auth", auth_management_routes(auth_state))
             // Observability routes under /observability
-            .nest("/observability", observability_routes())
+            .nest("/observability", observability_routes(metrics))
             // Storage routes under /storage
             .nest("/storage", storage_routes(storage_state))
             // Database routes under /api
@@ -99,8 +187,19 @@ impl HttpServer {
             .nest("/backup", backup_routes(backup_state))
             // Cluster routes under /cluster
             .nest("/cluster", cluster_routes(cluster_state))
+            // Control plane routes under /control (checkpoints and backups)
+            .nest("/control", control_routes(control_state))
             // Apply CORS middleware
-            .layer(cors)
+            .layer(cors);
+
+        if config.security_headers {
+            router.layer(map_response_with_state(
+                config.tls.is_some(),
+                security_headers,
+            ))
+        } else {
+            router
+        }
     }
 
     /// Get the socket address
@@ -114,16 +213,25 @@ impl HttpServer {
     }
 
     /// Start the HTTP server (async)
+    ///
+    /// With `tls` configured the server speaks only HTTPS, and re-reads its
+    /// certificates on SIGHUP.
     pub async fn start(self) -> Result<(), std::io::Error> {
         let addr: SocketAddr = self
             .config
             .socket_addr()
             .parse()
             .expect("Invalid socket address");
+        // Certificate errors surface before the port is bound
+        let tls = match &self.config.tls {
+            Some(config) => Some(Arc::new(TlsReloader::load(config)?)),
+            None => None,
+        };
+        let scheme = self.config.scheme();
 
         println!("Starting AeroDB HTTP server on {}", addr);
-        println!("Dashboard API available at http://{}", addr);
-        println!("Health check: http://{}/health", addr);
+        println!("Dashboard API available at {}://{}", scheme, addr);
+        println!("Health check: {}://{}/health", scheme, addr);
         println!("API endpoints:");
         println!("  - /auth/* - Authentication & user management");
         println!("  - /api/* - Database operations");
@@ -135,7 +243,13 @@ impl HttpServer {
         println!("  - /observability/* - Metrics & monitoring");
 
         let listener = TcpListener::bind(addr).await?;
-        axum::serve(listener, self.router).await?;
+        match tls {
+            Some(reloader) => {
+                reloader.reload_on_sighup()?;
+                tls::serve(listener, reloader, self.router).await?;
+            }
+            None => axum::serve(listener, self.router).await?,
+        }
 
         Ok(())
     }
@@ -2,22 +2,31 @@
 //!
 //! Endpoints for bucket and file management.
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use axum::{
-    body::Bytes,
-    extract::{Multipart, Path, Query, State},
-    http::{HeaderMap, StatusCode},
-    routing::{delete, get, patch, post},
+    body::{Body, Bytes},
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    routing::{delete, get, patch, post, put},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::auth::rls::RlsContext;
-use crate::file_storage::bucket::{Bucket, BucketConfig, BucketPolicy, BucketRegistry};
+use crate::file_storage::bucket::{
+    Bucket, BucketConfig, BucketPolicy, BucketRegistry, BucketUsage,
+};
+use crate::file_storage::errors::StorageError;
 use crate::file_storage::file::{FileService, StorageObject};
 use crate::file_storage::local::LocalBackend;
+use crate::file_storage::signed_url::{SignedUpload, SignedUrl, SignedUrlGenerator};
+use crate::file_storage::transform::{
+    ImageTransformer, TransformCache, TransformOptions, DEFAULT_CACHE_BUDGET,
+};
+use crate::file_storage::upload::{UploadSession, UploadSessionStore, MAX_PART_SIZE};
 
 // ==================
 // Shared State
@@ -26,13 +35,28 @@ use crate::file_storage::local::LocalBackend;
 /// Storage state shared across handlers
 pub struct StorageState {
     pub file_service: FileService<LocalBackend>,
+    /// Signs download URLs; the key is generated per process, so URLs
+    /// lapse on restart
+    pub signer: SignedUrlGenerator,
+    /// Transforms images downloaded through signed URLs, caching results
+    /// under `<storage_path>/.transforms`
+    pub transformer: ImageTransformer,
 }
 
 impl StorageState {
     pub fn new(storage_path: &std::path::Path) -> Self {
+        use rand::RngCore;
+
         let backend = LocalBackend::new(storage_path.to_path_buf());
+        let mut secret = [0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut secret);
         Self {
             file_service: FileService::new(backend),
+            signer: SignedUrlGenerator::new(&secret),
+            transformer: ImageTransformer::new(TransformCache::new(
+                storage_path.join(".transforms"),
+                DEFAULT_CACHE_BUDGET,
+            )),
         }
     }
 
@@ -40,6 +64,12 @@ impl StorageState {
         let storage_path = std::env::temp_dir().join("aerodb_storage");
         Self::new(&storage_path)
     }
+
+    /// Keep resumable upload sessions in `uploads`
+    pub fn with_upload_sessions(mut self, uploads: Arc<UploadSessionStore>) -> Self {
+        self.file_service = self.file_service.with_upload_sessions(uploads);
+        self
+    }
 }
 
 // ==================
@@ -56,15 +86,15 @@ pub struct BucketResponse {
     pub total_size: u64,
 }
 
-impl From<&Bucket> for BucketResponse {
-    fn from(bucket: &Bucket) -> Self {
+impl BucketResponse {
+    fn new(bucket: &Bucket, usage: BucketUsage) -> Self {
         Self {
             id: bucket.id.to_string(),
             name: bucket.name.clone(),
             policy: format!("{:?}", bucket.config.policy).to_lowercase(),
             created_at: bucket.created_at.to_rfc3339(),
-            file_count: 0, // Would need to query file service
-            total_size: 0, // Would need to query file service
+            file_count: usage.object_count as usize,
+            total_size: usage.total_size,
         }
     }
 }
@@ -84,6 +114,12 @@ pub struct CreateBucketRequest {
     pub allowed_mime_types: Vec<String>,
     #[serde(default)]
     pub max_file_size: Option<u64>,
+    /// Quota on the total size of the bucket's objects
+    #[serde(default)]
+    pub max_total_size: Option<u64>,
+    /// Quota on the number of objects in the bucket
+    #[serde(default)]
+    pub max_objects: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -142,6 +178,9 @@ pub struct MoveFileRequest {
 #[derive(Debug, Deserialize)]
 pub struct CreateSignedUrlRequest {
     pub expires_in: Option<u64>,
+    /// Image transform the URL serves
+    #[serde(default)]
+    pub transform: TransformOptions,
 }
 
 #[derive(Debug, Serialize)]
@@ -150,6 +189,25 @@ pub struct SignedUrlResponse {
     pub expires_at: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct CreateSignedUploadRequest {
+    pub expires_in: Option<u64>,
+    /// Largest upload accepted; the bucket's file size limit if unset
+    #[serde(default)]
+    pub max_size: Option<u64>,
+    /// Only content type accepted; any the bucket allows if unset
+    #[serde(default)]
+    pub content_type: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SignedUploadResponse {
+    pub url: String,
+    pub expires_at: String,
+    pub max_size: u64,
+    pub content_type: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct UploadResponse {
     pub id: String,
@@ -158,6 +216,67 @@ pub struct UploadResponse {
     pub content_type: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct InitiateUploadRequest {
+    pub path: String,
+    #[serde(default)]
+    pub content_type: Option<String>,
+    /// Size of the complete file, if known
+    #[serde(default)]
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CompleteUploadRequest {
+    /// SHA-256 of the complete file, hex
+    #[serde(default)]
+    pub checksum: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UploadPartResponse {
+    pub number: u32,
+    pub size: u64,
+    pub checksum: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UploadSessionResponse {
+    pub id: String,
+    pub bucket: String,
+    pub path: String,
+    pub content_type: String,
+    pub size: Option<u64>,
+    pub uploaded_bytes: u64,
+    pub parts: Vec<UploadPartResponse>,
+    pub created_at: String,
+    pub expires_at: String,
+}
+
+impl UploadSessionResponse {
+    fn from_session(session: &UploadSession, bucket_name: &str) -> Self {
+        Self {
+            id: session.id.to_string(),
+            bucket: bucket_name.to_string(),
+            path: session.path.clone(),
+            content_type: session.content_type.clone(),
+            size: session.declared_size,
+            uploaded_bytes: session.uploaded_bytes(),
+            parts: session
+                .parts
+                .iter()
+                .map(|(number, part)| UploadPartResponse {
+                    number: *number,
+                    size: part.size,
+                    checksum: part.checksum.clone(),
+                })
+                .collect(),
+            created_at: session.created_at.to_rfc3339(),
+            expires_at: session.expires_at.to_rfc3339(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
     pub error: String,
@@ -171,6 +290,17 @@ pub struct BucketStatsResponse {
     pub largest_file: u64,
 }
 
+#[derive(Debug, Serialize)]
+pub struct BucketUsageResponse {
+    pub bucket: String,
+    pub object_count: u64,
+    pub total_size: u64,
+    /// `None` when unlimited
+    pub max_objects: Option<u64>,
+    /// `None` when unlimited
+    pub max_total_size: Option<u64>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct MessageResponse {
     pub message: String,
@@ -186,24 +316,44 @@ pub fn storage_routes(state: Arc<StorageState>) -> Router {
         // Bucket management
         .route("/buckets", get(list_buckets_handler))
         .route("/buckets", post(create_bucket_handler))
-        .route("/buckets/{name}", get(get_bucket_handler))
-        .route("/buckets/{name}", patch(update_bucket_handler))
-        .route("/buckets/{name}", delete(delete_bucket_handler))
-        .route("/buckets/{name}/stats", get(get_bucket_stats_handler))
+        .route("/buckets/:name", get(get_bucket_handler))
+        .route("/buckets/:name", patch(update_bucket_handler))
+        .route("/buckets/:name", delete(delete_bucket_handler))
+        .route("/buckets/:name/stats", get(get_bucket_stats_handler))
+        .route("/buckets/:name/usage", get(get_bucket_usage_handler))
         // File operations (non-wildcard routes first)
-        .route("/buckets/{name}/files", get(list_files_handler))
-        .route("/buckets/{name}/files", post(upload_file_handler))
-        .route("/buckets/{name}/files/move", post(move_file_handler))
+        .route("/buckets/:name/files", get(list_files_handler))
+        .route("/buckets/:name/files", post(upload_file_handler))
+        .route("/buckets/:name/files/move", post(move_file_handler))
         // Signed URLs - use separate path prefix to avoid wildcard conflict
+        .route("/buckets/:name/sign/*path", post(create_signed_url_handler))
+        .route(
+            "/buckets/:name/upload/sign/*path",
+            post(create_signed_upload_handler),
+        )
+        // Downloads and uploads through signed URLs
+        .route("/v1/object/sign/:name/*path", get(signed_download_handler))
         .route(
-            "/buckets/{name}/sign/*path",
-            post(create_signed_url_handler),
+            "/v1/object/upload/sign/:name/*path",
+            put(signed_upload_handler).layer(DefaultBodyLimit::disable()),
         )
         // Folders
-        .route("/buckets/{name}/folders", post(create_folder_handler))
+        .route("/buckets/:name/folders", post(create_folder_handler))
+        // Resumable uploads
+        .route("/buckets/:name/uploads", post(initiate_upload_handler))
+        .route("/buckets/:name/uploads/:id", get(get_upload_handler))
+        .route("/buckets/:name/uploads/:id", delete(abort_upload_handler))
+        .route(
+            "/buckets/:name/uploads/:id/parts/:number",
+            put(append_part_handler).layer(DefaultBodyLimit::max(MAX_PART_SIZE)),
+        )
+        .route(
+            "/buckets/:name/uploads/:id/complete",
+            post(complete_upload_handler),
+        )
         // Wildcard file routes (must come last)
-        .route("/buckets/{name}/files/*path", get(download_file_handler))
-        .route("/buckets/{name}/files/*path", delete(delete_file_handler))
+        .route("/buckets/:name/files/*path", get(download_file_handler))
+        .route("/buckets/:name/files/*path", delete(delete_file_handler))
         .with_state(state)
 }
 
@@ -227,6 +377,18 @@ fn get_rls_context_from_headers(headers: &HeaderMap) -> RlsContext {
     RlsContext::anonymous()
 }
 
+/// Error response carrying `error`'s own status
+fn storage_error(error: StorageError) -> (StatusCode, Json<ErrorResponse>) {
+    let code = error.status_code();
+    (
+        StatusCode::from_u16(code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+        Json(ErrorResponse {
+            error: error.to_string(),
+            code,
+        }),
+    )
+}
+
 fn parse_bucket_policy(policy_str: &str) -> BucketPolicy {
     match policy_str.to_lowercase().as_str() {
         "public" => BucketPolicy::Public,
@@ -245,7 +407,10 @@ async fn list_buckets_handler(
     headers: HeaderMap,
 ) -> Result<Json<BucketsListResponse>, (StatusCode, Json<ErrorResponse>)> {
     let buckets = state.file_service.buckets().list();
-    let response: Vec<BucketResponse> = buckets.iter().map(BucketResponse::from).collect();
+    let response: Vec<BucketResponse> = buckets
+        .iter()
+        .map(|b| BucketResponse::new(b, state.file_service.bucket_usage(&b.id)))
+        .collect();
 
     Ok(Json(BucketsListResponse {
         total: response.len(),
@@ -267,7 +432,8 @@ async fn get_bucket_handler(
         )
     })?;
 
-    Ok(Json(BucketResponse::from(&bucket)))
+    let usage = state.file_service.bucket_usage(&bucket.id);
+    Ok(Json(BucketResponse::new(&bucket, usage)))
 }
 
 async fn create_bucket_handler(
@@ -285,6 +451,8 @@ async fn create_bucket_handler(
             .unwrap_or_default(),
         allowed_mime_types: request.allowed_mime_types,
         max_file_size: request.max_file_size.unwrap_or(100 * 1024 * 1024),
+        max_total_size: request.max_total_size.unwrap_or(0),
+        max_objects: request.max_objects.unwrap_or(0),
     };
 
     let bucket = state
@@ -301,7 +469,10 @@ async fn create_bucket_handler(
             )
         })?;
 
-    Ok((StatusCode::CREATED, Json(BucketResponse::from(&bucket))))
+    Ok((
+        StatusCode::CREATED,
+        Json(BucketResponse::new(&bucket, BucketUsage::default())),
+    ))
 }
 
 async fn update_bucket_handler(
@@ -348,6 +519,33 @@ async fn get_bucket_stats_handler(
     }))
 }
 
+async fn get_bucket_usage_handler(
+    State(state): State<Arc<StorageState>>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> Result<Json<BucketUsageResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let ctx = get_rls_context_from_headers(&headers);
+
+    let usage = state
+        .file_service
+        .usage(&name, &ctx)
+        .map_err(storage_error)?;
+    let bucket = state
+        .file_service
+        .buckets()
+        .get(&name)
+        .map_err(storage_error)?;
+    let limit = |limit: u64| (limit > 0).then_some(limit);
+
+    Ok(Json(BucketUsageResponse {
+        bucket: name,
+        object_count: usage.object_count,
+        total_size: usage.total_size,
+        max_objects: limit(bucket.config.max_objects),
+        max_total_size: limit(bucket.config.max_total_size),
+    }))
+}
+
 // ==================
 // File Handlers
 // ==================
@@ -421,15 +619,7 @@ async fn upload_file_handler(
         let obj = state
             .file_service
             .upload(&bucket_name, &file_name, &data, &content_type, &ctx)
-            .map_err(|e| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse {
-                        error: e.to_string(),
-                        code: 500,
-                    }),
-                )
-            })?;
+            .map_err(storage_error)?;
 
         return Ok((
             StatusCode::CREATED,
@@ -522,23 +712,197 @@ async fn move_file_handler(
 }
 
 async fn create_signed_url_handler(
-    State(_state): State<Arc<StorageState>>,
+    State(state): State<Arc<StorageState>>,
+    headers: HeaderMap,
     Path((bucket_name, path)): Path<(String, String)>,
     Json(request): Json<CreateSignedUrlRequest>,
 ) -> Result<Json<SignedUrlResponse>, (StatusCode, Json<ErrorResponse>)> {
-    // Would use SignedUrlGenerator from file_storage module
+    let ctx = get_rls_context_from_headers(&headers);
+
+    // Only callers who may read the object can hand out access to it
+    state
+        .file_service
+        .get_object(&bucket_name, &path, &ctx)
+        .map_err(storage_error)?;
+    request.transform.validate().map_err(storage_error)?;
+
     let expires_in = request.expires_in.unwrap_or(3600);
     let expires_at = chrono::Utc::now() + chrono::Duration::seconds(expires_in as i64);
+    let signed =
+        state
+            .signer
+            .generate_transformed(&bucket_name, &path, Some(expires_at), request.transform);
 
     Ok(Json(SignedUrlResponse {
-        url: format!(
-            "/storage/v1/{}/{}?token=signed_placeholder",
-            bucket_name, path
-        ),
+        url: signed.to_url(""),
         expires_at: expires_at.to_rfc3339(),
     }))
 }
 
+/// Serve a file through a signed URL, transformed if the URL asks for it
+async fn signed_download_handler(
+    State(state): State<Arc<StorageState>>,
+    Path((bucket_name, path)): Path<(String, String)>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Result<(StatusCode, HeaderMap, Bytes), (StatusCode, Json<ErrorResponse>)> {
+    let expires_at = query
+        .get("expires")
+        .and_then(|e| e.parse().ok())
+        .and_then(|e| chrono::DateTime::from_timestamp(e, 0))
+        .ok_or_else(|| storage_error(StorageError::InvalidSignature))?;
+    let signed = SignedUrl {
+        bucket: bucket_name,
+        path,
+        expires_at,
+        signature: query.get("token").cloned().unwrap_or_default(),
+        transform: TransformOptions::from_query(&query).map_err(storage_error)?,
+    };
+    state.signer.verify(&signed).map_err(storage_error)?;
+
+    // The signature grants access
+    let (obj, data) = state
+        .file_service
+        .download(&signed.bucket, &signed.path, &RlsContext::service_role())
+        .map_err(storage_error)?;
+    let (data, content_type) = if signed.transform.is_empty() {
+        (data, obj.content_type)
+    } else {
+        let (data, format) = state
+            .transformer
+            .transform(&obj.checksum, &data, &signed.transform)
+            .map_err(storage_error)?;
+        (data, format.content_type().to_string())
+    };
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(
+        header::CONTENT_TYPE,
+        content_type
+            .parse()
+            .unwrap_or_else(|_| "application/octet-stream".parse().unwrap()),
+    );
+    Ok((StatusCode::OK, response_headers, Bytes::from(data)))
+}
+
+async fn create_signed_upload_handler(
+    State(state): State<Arc<StorageState>>,
+    headers: HeaderMap,
+    Path((bucket_name, path)): Path<(String, String)>,
+    Json(request): Json<CreateSignedUploadRequest>,
+) -> Result<Json<SignedUploadResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let ctx = get_rls_context_from_headers(&headers);
+
+    // Only callers who may write to the bucket can hand out uploads to it
+    let bucket = state
+        .file_service
+        .check_write(&bucket_name, &ctx)
+        .map_err(storage_error)?;
+    let max_size = request.max_size.unwrap_or(bucket.config.max_file_size);
+    if max_size == 0 {
+        return Err(storage_error(StorageError::InvalidUpload(
+            "max_size is required for buckets without a file size limit".into(),
+        )));
+    }
+    bucket.check_size(max_size).map_err(storage_error)?;
+    if let Some(content_type) = &request.content_type {
+        if !bucket.is_mime_allowed(content_type) {
+            return Err(storage_error(StorageError::InvalidMimeType(
+                content_type.clone(),
+            )));
+        }
+    }
+
+    let expires_in = request.expires_in.unwrap_or(3600);
+    let expires_at = chrono::Utc::now() + chrono::Duration::seconds(expires_in as i64);
+    let signed = state.signer.generate_upload(
+        &bucket_name,
+        &path,
+        Some(expires_at),
+        max_size,
+        request.content_type,
+    );
+
+    Ok(Json(SignedUploadResponse {
+        url: signed.to_url(""),
+        expires_at: expires_at.to_rfc3339(),
+        max_size,
+        content_type: signed.content_type,
+    }))
+}
+
+/// Accept an upload through a signed URL. The URL is checked against the
+/// declared `Content-Length` before the body is read, and the body is
+/// read no further than the signed size limit.
+async fn signed_upload_handler(
+    State(state): State<Arc<StorageState>>,
+    headers: HeaderMap,
+    Path((bucket_name, path)): Path<(String, String)>,
+    Query(query): Query<HashMap<String, String>>,
+    body: Body,
+) -> Result<(StatusCode, Json<UploadResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let invalid = || storage_error(StorageError::InvalidSignature);
+    let signed = SignedUpload {
+        bucket: bucket_name,
+        path,
+        expires_at: query
+            .get("expires")
+            .and_then(|e| e.parse().ok())
+            .and_then(|e| chrono::DateTime::from_timestamp(e, 0))
+            .ok_or_else(invalid)?,
+        max_size: query
+            .get("max_size")
+            .and_then(|m| m.parse().ok())
+            .ok_or_else(invalid)?,
+        content_type: query.get("content_type").cloned(),
+        signature: query.get("token").cloned().unwrap_or_default(),
+    };
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .or(signed.content_type.as_deref())
+        .unwrap_or("application/octet-stream")
+        .to_string();
+    let declared = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok()?.parse().ok())
+        .unwrap_or(0);
+    state
+        .signer
+        .verify_upload(&signed, declared, &content_type)
+        .map_err(storage_error)?;
+
+    let data = axum::body::to_bytes(body, signed.max_size as usize)
+        .await
+        .map_err(|_| {
+            storage_error(StorageError::FileTooLarge(
+                signed.max_size + 1,
+                signed.max_size,
+            ))
+        })?;
+
+    // The signature grants access; bucket limits and quotas still apply
+    let obj = state
+        .file_service
+        .upload(
+            &signed.bucket,
+            &signed.path,
+            &data,
+            &content_type,
+            &RlsContext::service_role(),
+        )
+        .map_err(storage_error)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(UploadResponse {
+            id: obj.id.to_string(),
+            path: obj.path,
+            size: obj.size,
+            content_type: obj.content_type,
+        }),
+    ))
+}
+
 async fn create_folder_handler(
     State(_state): State<Arc<StorageState>>,
     Path(bucket_name): Path<String>,
@@ -552,6 +916,122 @@ async fn create_folder_handler(
     ))
 }
 
+// ==================
+// Resumable Upload Handlers
+// ==================
+
+async fn initiate_upload_handler(
+    State(state): State<Arc<StorageState>>,
+    headers: HeaderMap,
+    Path(bucket_name): Path<String>,
+    Json(request): Json<InitiateUploadRequest>,
+) -> Result<(StatusCode, Json<UploadSessionResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let ctx = get_rls_context_from_headers(&headers);
+    let content_type = request
+        .content_type
+        .as_deref()
+        .unwrap_or("application/octet-stream");
+
+    let session = state
+        .file_service
+        .initiate_upload(
+            &bucket_name,
+            &request.path,
+            content_type,
+            request.size,
+            &ctx,
+        )
+        .map_err(storage_error)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(UploadSessionResponse::from_session(&session, &bucket_name)),
+    ))
+}
+
+async fn get_upload_handler(
+    State(state): State<Arc<StorageState>>,
+    headers: HeaderMap,
+    Path((bucket_name, id)): Path<(String, Uuid)>,
+) -> Result<Json<UploadSessionResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let ctx = get_rls_context_from_headers(&headers);
+
+    let session = state
+        .file_service
+        .get_upload(&bucket_name, &id, &ctx)
+        .map_err(storage_error)?;
+
+    Ok(Json(UploadSessionResponse::from_session(
+        &session,
+        &bucket_name,
+    )))
+}
+
+/// Store one part; an `x-checksum-sha256` header, if sent, must match the
+/// body's SHA-256
+async fn append_part_handler(
+    State(state): State<Arc<StorageState>>,
+    headers: HeaderMap,
+    Path((bucket_name, id, number)): Path<(String, Uuid, u32)>,
+    body: Bytes,
+) -> Result<Json<UploadPartResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let ctx = get_rls_context_from_headers(&headers);
+    let checksum = headers
+        .get("x-checksum-sha256")
+        .and_then(|v| v.to_str().ok());
+
+    let part = state
+        .file_service
+        .append_part(&bucket_name, &id, number, &body, checksum, &ctx)
+        .map_err(storage_error)?;
+
+    Ok(Json(UploadPartResponse {
+        number,
+        size: part.size,
+        checksum: part.checksum,
+    }))
+}
+
+async fn complete_upload_handler(
+    State(state): State<Arc<StorageState>>,
+    headers: HeaderMap,
+    Path((bucket_name, id)): Path<(String, Uuid)>,
+    request: Option<Json<CompleteUploadRequest>>,
+) -> Result<(StatusCode, Json<UploadResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let ctx = get_rls_context_from_headers(&headers);
+    let Json(request) = request.unwrap_or_default();
+
+    let obj = state
+        .file_service
+        .complete_upload(&bucket_name, &id, request.checksum.as_deref(), &ctx)
+        .map_err(storage_error)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(UploadResponse {
+            id: obj.id.to_string(),
+            path: obj.path,
+            size: obj.size,
+            content_type: obj.content_type,
+        }),
+    ))
+}
+
+async fn abort_upload_handler(
+    State(state): State<Arc<StorageState>>,
+    headers: HeaderMap,
+    Path((bucket_name, id)): Path<(String, Uuid)>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    let ctx = get_rls_context_from_headers(&headers);
+
+    state
+        .file_service
+        .abort_upload(&bucket_name, &id, &ctx)
+        .map_err(storage_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -575,4 +1055,310 @@ mod tests {
             BucketPolicy::Private
         ));
     }
+
+    #[tokio::test]
+    async fn test_resumable_upload_routes() {
+        use axum::body::{to_bytes, Body};
+        use axum::http::Request;
+        use serde_json::{json, Value};
+        use tower::Service;
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(StorageState::new(temp.path()));
+        let router = storage_routes(state);
+        let call = |method: &str, uri: &str, body: Body| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("authorization", "Bearer token")
+                .header("content-type", "application/json")
+                .body(body)
+                .unwrap();
+            let response = router.clone().call(request);
+            async move {
+                let response = response.await.unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (
+                    status,
+                    serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null),
+                )
+            }
+        };
+
+        let (status, _) = call("POST", "/buckets", Body::from(r#"{"name":"media"}"#)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, session) = call(
+            "POST",
+            "/buckets/media/uploads",
+            Body::from(json!({"path": "videos/clip.bin", "size": 11}).to_string()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let uploads = format!("/buckets/media/uploads/{}", session["id"].as_str().unwrap());
+
+        // Parts arrive out of order, and a retried part replaces the first
+        for (number, data) in [(2, "world"), (1, "hello "), (2, "world")] {
+            let (status, part) = call(
+                "PUT",
+                &format!("{}/parts/{}", uploads, number),
+                Body::from(data),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(part["size"], data.len());
+        }
+        let (_, session) = call("GET", &uploads, Body::empty()).await;
+        assert_eq!(session["uploaded_bytes"], 11);
+        assert_eq!(session["parts"].as_array().unwrap().len(), 2);
+
+        let (status, _) = call(
+            "POST",
+            &format!("{}/complete", uploads),
+            Body::from(r#"{"checksum":"00"}"#),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, object) = call("POST", &format!("{}/complete", uploads), Body::empty()).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(object["size"], 11);
+
+        let (status, _) = call("GET", &uploads, Body::empty()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let response = router
+            .clone()
+            .call(
+                Request::get("/buckets/media/files/videos/clip.bin")
+                    .header("authorization", "Bearer token")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"hello world");
+    }
+
+    #[tokio::test]
+    async fn test_bucket_usage_route() {
+        use axum::body::{to_bytes, Body};
+        use axum::http::Request;
+        use serde_json::{json, Value};
+        use tower::Service;
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(StorageState::new(temp.path()));
+        let router = storage_routes(Arc::clone(&state));
+        let call = |request: Request<Body>| {
+            let response = router.clone().call(request);
+            async move {
+                let response = response.await.unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (
+                    status,
+                    serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null),
+                )
+            }
+        };
+
+        let (status, _) = call(
+            Request::post("/buckets")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({"name": "docs", "max_total_size": 8, "max_objects": 4}).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        state
+            .file_service
+            .upload(
+                "docs",
+                "a.txt",
+                b"hello",
+                "text/plain",
+                &RlsContext::service_role(),
+            )
+            .unwrap();
+
+        let (status, usage) = call(
+            Request::get("/buckets/docs/usage")
+                .header("authorization", "Bearer token")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(usage["object_count"], 1);
+        assert_eq!(usage["total_size"], 5);
+        assert_eq!(usage["max_total_size"], 8);
+        assert_eq!(usage["max_objects"], 4);
+
+        let (_, bucket) = call(Request::get("/buckets/docs").body(Body::empty()).unwrap()).await;
+        assert_eq!(bucket["file_count"], 1);
+
+        // Writes past the quota fail with a quota error
+        let err = state
+            .file_service
+            .upload(
+                "docs",
+                "b.txt",
+                b"world",
+                "text/plain",
+                &RlsContext::service_role(),
+            )
+            .unwrap_err();
+        let (status, Json(body)) = storage_error(err);
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(body.error.starts_with("Quota exceeded"));
+    }
+
+    #[tokio::test]
+    async fn test_signed_upload_routes() {
+        use axum::body::{to_bytes, Body};
+        use axum::http::Request;
+        use serde_json::{json, Value};
+        use tower::Service;
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(StorageState::new(temp.path()));
+        state
+            .file_service
+            .buckets()
+            .create("inbox".into(), None, BucketConfig::default())
+            .unwrap();
+        let router = storage_routes(Arc::clone(&state));
+        let call = |request: Request<Body>| {
+            let response = router.clone().call(request);
+            async move {
+                let response = response.await.unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (
+                    status,
+                    serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null),
+                )
+            }
+        };
+        let put = |url: &str, content_type: &str, body: &'static str| {
+            Request::put(url)
+                .header("content-type", content_type)
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        // Anonymous callers cannot hand out uploads
+        let sign = |authorized: bool| {
+            let mut request = Request::post("/buckets/inbox/upload/sign/docs/note.txt")
+                .header("content-type", "application/json");
+            if authorized {
+                request = request.header("authorization", "Bearer token");
+            }
+            request
+                .body(Body::from(
+                    json!({"max_size": 8, "content_type": "text/plain"}).to_string(),
+                ))
+                .unwrap()
+        };
+        let (status, _) = call(sign(false)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, signed) = call(sign(true)).await;
+        assert_eq!(status, StatusCode::OK);
+        let url = signed["url"]
+            .as_str()
+            .unwrap()
+            .trim_start_matches("/storage")
+            .to_string();
+
+        let (status, _) = call(put(&url, "text/plain", "too large!")).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        let (status, _) = call(put(&url, "text/html", "<p>")).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let tampered = url.replace("max_size=8", "max_size=800");
+        let (status, _) = call(put(&tampered, "text/plain", "hi")).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, object) = call(put(&url, "text/plain", "hello")).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(object["size"], 5);
+        let (_, data) = state
+            .file_service
+            .download("inbox", "docs/note.txt", &RlsContext::service_role())
+            .unwrap();
+        assert_eq!(data, b"hello");
+    }
+
+    #[tokio::test]
+    async fn test_signed_image_transform() {
+        use crate::file_storage::image::{Image, ImageFormat};
+        use axum::body::{to_bytes, Body};
+        use axum::http::Request;
+        use serde_json::{json, Value};
+        use tower::Service;
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let state = Arc::new(StorageState::new(temp.path()));
+        let ctx = RlsContext::service_role();
+        state
+            .file_service
+            .buckets()
+            .create("photos".into(), None, BucketConfig::default())
+            .unwrap();
+        let png = Image::new(8, 4, vec![200; 8 * 4 * 4])
+            .unwrap()
+            .encode(ImageFormat::Png);
+        state
+            .file_service
+            .upload("photos", "cat.png", &png, "image/png", &ctx)
+            .unwrap();
+
+        let router = storage_routes(Arc::clone(&state));
+        let call = |request: Request<Body>| {
+            let response = router.clone().call(request);
+            async move {
+                let response = response.await.unwrap();
+                let status = response.status();
+                let content_type = response
+                    .headers()
+                    .get(header::CONTENT_TYPE)
+                    .map(|v| v.to_str().unwrap().to_string());
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (status, content_type, body)
+            }
+        };
+
+        let (status, _, body) = call(
+            Request::post("/buckets/photos/sign/cat.png")
+                .header("authorization", "Bearer token")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    json!({"transform": {"width": 4, "format": "bmp"}}).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let url = serde_json::from_slice::<Value>(&body).unwrap()["url"]
+            .as_str()
+            .unwrap()
+            .trim_start_matches("/storage")
+            .to_string();
+
+        for _ in 0..2 {
+            let (status, content_type, body) =
+                call(Request::get(&url).body(Body::empty()).unwrap()).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(content_type.as_deref(), Some("image/bmp"));
+            let (image, _) = Image::decode(&body).unwrap();
+            assert_eq!((image.width, image.height), (4, 2));
+        }
+        assert!(state.transformer.cache().usage() > 0);
+
+        // The transform is signed: asking for another rendition fails
+        let tampered = url.replace("width=4", "width=8");
+        let (status, _, _) = call(Request::get(&tampered).body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
 }
@@ -75,11 +75,43 @@ impl IndexKey {
 /// Storage offset type
 pub type StorageOffset = u64;
 
+/// Cardinality of an index: how many entries it holds and how many
+/// distinct keys they fall under
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexStats {
+    /// Offsets indexed
+    pub entries: u64,
+    /// Distinct keys
+    pub distinct_keys: u64,
+}
+
+impl IndexStats {
+    /// Expected offsets returned by an equality lookup: entries per
+    /// distinct key, rounded up (0 for an empty index)
+    pub fn estimated_eq_rows(&self) -> u64 {
+        match self.distinct_keys {
+            0 => 0,
+            keys => self.entries.div_ceil(keys),
+        }
+    }
+
+    /// Fraction of entries an equality lookup is expected to return
+    /// (0 for an empty index)
+    pub fn selectivity(&self) -> f64 {
+        match self.entries {
+            0 => 0.0,
+            entries => self.estimated_eq_rows() as f64 / entries as f64,
+        }
+    }
+}
+
 /// A single field index using BTreeMap for deterministic ordering.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq)]
 pub struct IndexTree {
     /// Maps key values to sorted lists of offsets
     tree: BTreeMap<IndexKey, Vec<StorageOffset>>,
+    /// Offsets across all keys, kept in step with `tree`
+    entries: usize,
 }
 
 impl IndexTree {
@@ -87,6 +119,7 @@ impl IndexTree {
     pub fn new() -> Self {
         Self {
             tree: BTreeMap::new(),
+            entries: 0,
         }
     }
 
@@ -99,7 +132,10 @@ impl IndexTree {
         // Insert maintaining sorted order
         match offsets.binary_search(&offset) {
             Ok(_) => {} // Already exists
-            Err(pos) => offsets.insert(pos, offset),
+            Err(pos) => {
+                offsets.insert(pos, offset);
+                self.entries += 1;
+            }
         }
     }
 
@@ -110,6 +146,7 @@ impl IndexTree {
         if let Some(offsets) = self.tree.get_mut(key) {
             if let Ok(pos) = offsets.binary_search(&offset) {
                 offsets.remove(pos);
+                self.entries -= 1;
             }
             if offsets.is_empty() {
                 self.tree.remove(key);
@@ -158,6 +195,7 @@ impl IndexTree {
     /// Clear all entries
     pub fn clear(&mut self) {
         self.tree.clear();
+        self.entries = 0;
     }
 
     /// Returns the number of distinct keys
@@ -167,7 +205,15 @@ impl IndexTree {
 
     /// Returns the total number of offsets
     pub fn offset_count(&self) -> usize {
-        self.tree.values().map(|v| v.len()).sum()
+        self.entries
+    }
+
+    /// Returns the cardinality of this index
+    pub fn stats(&self) -> IndexStats {
+        IndexStats {
+            entries: self.entries as u64,
+            distinct_keys: self.tree.len() as u64,
+        }
     }
 }
 
@@ -238,6 +284,31 @@ mod tests {
         assert_eq!(tree.key_count(), 0);
     }
 
+    #[test]
+    fn test_stats_track_inserts_and_removes() {
+        let mut tree = IndexTree::new();
+        assert_eq!(tree.stats().estimated_eq_rows(), 0);
+
+        tree.insert(IndexKey::from_string("active"), 100);
+        tree.insert(IndexKey::from_string("active"), 200);
+        tree.insert(IndexKey::from_string("active"), 200);
+        tree.insert(IndexKey::from_string("banned"), 300);
+        assert_eq!(
+            tree.stats(),
+            IndexStats {
+                entries: 3,
+                distinct_keys: 2
+            }
+        );
+        assert_eq!(tree.stats().estimated_eq_rows(), 2);
+
+        tree.remove(&IndexKey::from_string("banned"), 300);
+        tree.remove(&IndexKey::from_string("banned"), 300);
+        assert_eq!(tree.offset_count(), 2);
+        assert_eq!(tree.key_count(), 1);
+        assert_eq!(tree.stats().selectivity(), 1.0);
+    }
+
     #[test]
     fn test_lookup_range() {
         let mut tree = IndexTree::new();
@@ -0,0 +1,457 @@
+//! Index checkpoints
+//!
+//! Rebuilding indexes reads and parses every stored record. An index
+//! checkpoint records the live entries of an `IndexManager` when a storage
+//! checkpoint is taken, keyed to its snapshot ID. Startup restores the
+//! entries, then indexes only the records stored after `storage_end`:
+//! writes made since the checkpoint and the records WAL replay re-applied.
+//!
+//! # File format
+//!
+//! `<snapshot_dir>/indexes.ckpt` holds a `crc32:XXXXXXXX` line followed by
+//! the JSON checkpoint; the checksum covers the JSON bytes.
+//!
+//! Entries keep only the values indexes read. Entries of encrypted
+//! collections keep none and their bodies are re-read from storage on
+//! restore, so sealed values are never written out in plaintext.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::btree::StorageOffset;
+use super::errors::{IndexError, IndexResult};
+use super::manager::{DocumentInfo, IndexManager, StorageScan};
+
+/// Index checkpoint file name inside a snapshot directory
+pub const INDEX_CHECKPOINT_FILE: &str = "indexes.ckpt";
+
+/// Current index checkpoint format version
+const FORMAT_VERSION: u8 = 1;
+
+/// Random access to stored records, needed to capture and restore
+/// checkpoints
+pub trait StorageSeek: StorageScan {
+    /// Position the scan at the first record at or after `offset`
+    fn seek(&mut self, offset: u64) -> IndexResult<()>;
+
+    /// Parsed body of the document stored at `offset`
+    fn read_body(&mut self, offset: u64) -> IndexResult<Value>;
+
+    /// Length of storage in bytes
+    fn storage_len(&mut self) -> IndexResult<u64>;
+}
+
+/// Indexes a manager declares
+///
+/// A checkpoint taken under other declarations is stale.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexDeclarations {
+    /// Single-field indexes
+    pub fields: BTreeSet<String>,
+    /// Unique fields
+    pub unique: BTreeSet<String>,
+    /// Composite indexes
+    pub composite: BTreeSet<Vec<String>>,
+}
+
+impl IndexDeclarations {
+    /// Declarations of `index`
+    pub fn of(index: &IndexManager) -> Self {
+        Self {
+            fields: index.indexed_fields().iter().cloned().collect(),
+            unique: index.unique_fields().cloned().collect(),
+            composite: index.composite_indexes().cloned().collect(),
+        }
+    }
+}
+
+/// A live document recorded in a checkpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    /// Stored document ID (`collection:document_id`)
+    pub document_id: String,
+    /// Storage offset of the live version
+    pub offset: StorageOffset,
+    /// Indexed values, or `None` to re-read the body on restore
+    pub values: Option<Value>,
+}
+
+/// Persisted index entries, keyed to a snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexCheckpoint {
+    /// Format version
+    pub format_version: u8,
+    /// Snapshot the checkpoint was taken with
+    pub snapshot_id: String,
+    /// Collection the manager's own entries belong to
+    pub collection: String,
+    /// Storage length the entries cover
+    pub storage_end: u64,
+    /// Declarations the entries were indexed under
+    pub declarations: IndexDeclarations,
+    /// Live documents of every collection, in stored ID order
+    pub entries: Vec<IndexEntry>,
+}
+
+impl IndexCheckpoint {
+    /// Capture the entries of `index` and its partitions.
+    ///
+    /// `collection` names the manager's own collection. Bodies are read
+    /// from `storage` to project indexed values, except for collections
+    /// `sealed` reports as encrypted. Storage must not change during the
+    /// capture.
+    pub fn capture<S: StorageSeek + ?Sized>(
+        index: &IndexManager,
+        collection: &str,
+        snapshot_id: &str,
+        storage: &mut S,
+        sealed: &dyn Fn(&str) -> bool,
+    ) -> IndexResult<Self> {
+        let storage_end = storage.storage_len()?;
+
+        let partitions = index.collection_names().into_iter().filter_map(|name| {
+            let partition = index.collection(&name)?;
+            Some((name, partition))
+        });
+        let mut entries = Vec::new();
+        for (name, partition) in std::iter::once((collection.to_string(), index)).chain(partitions)
+        {
+            let encrypted = sealed(&name);
+            for (doc_id, offset) in partition.documents() {
+                let values = if encrypted {
+                    None
+                } else {
+                    Some(partition.projection(&storage.read_body(offset)?))
+                };
+                entries.push(IndexEntry {
+                    document_id: format!("{}:{}", name, doc_id),
+                    offset,
+                    values,
+                });
+            }
+        }
+        entries.sort_by(|a, b| a.document_id.cmp(&b.document_id));
+
+        Ok(Self {
+            format_version: FORMAT_VERSION,
+            snapshot_id: snapshot_id.to_string(),
+            collection: collection.to_string(),
+            storage_end,
+            declarations: IndexDeclarations::of(index),
+            entries,
+        })
+    }
+
+    /// Write the checkpoint to `path` atomically, with fsync
+    pub fn write_to(&self, path: &Path) -> IndexResult<()> {
+        let body = serde_json::to_vec(self)
+            .map_err(|e| IndexError::build_failed(format!("Serialize index checkpoint: {}", e)))?;
+        let io_err =
+            |e: std::io::Error| IndexError::build_failed(format!("Write index checkpoint: {}", e));
+
+        let tmp_path = path.with_extension("ckpt.tmp");
+        let mut file = File::create(&tmp_path).map_err(io_err)?;
+        writeln!(file, "crc32:{:08x}", crc32fast::hash(&body)).map_err(io_err)?;
+        file.write_all(&body).map_err(io_err)?;
+        file.sync_all().map_err(io_err)?;
+        fs::rename(&tmp_path, path).map_err(io_err)?;
+        Ok(())
+    }
+
+    /// Read a checkpoint from `path`, validating its checksum and version
+    pub fn read_from(path: &Path) -> IndexResult<Self> {
+        let bytes = fs::read(path)
+            .map_err(|e| IndexError::build_failed(format!("Read index checkpoint: {}", e)))?;
+        let invalid =
+            |reason: &str| IndexError::build_failed(format!("{}: {}", reason, path.display()));
+
+        let split = bytes
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or_else(|| invalid("Index checkpoint has no checksum line"))?;
+        let (line, body) = (&bytes[..split], &bytes[split + 1..]);
+        let expected = std::str::from_utf8(line)
+            .ok()
+            .and_then(|line| line.strip_prefix("crc32:"))
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| invalid("Index checkpoint has a malformed checksum line"))?;
+        if crc32fast::hash(body) != expected {
+            return Err(invalid("Index checkpoint checksum mismatch"));
+        }
+
+        let checkpoint: Self = serde_json::from_slice(body)
+            .map_err(|e| invalid(&format!("Malformed index checkpoint ({})", e)))?;
+        if checkpoint.format_version != FORMAT_VERSION {
+            return Err(invalid(&format!(
+                "Unsupported index checkpoint format {}",
+                checkpoint.format_version
+            )));
+        }
+        Ok(checkpoint)
+    }
+}
+
+/// Applies stored records to an `IndexManager`
+///
+/// Each record is routed by its stored ID (`collection:document_id`) to the
+/// manager's own entries or to its collection's partition. The indexed
+/// values of each live document are kept, so a later version or tombstone
+/// removes every entry of the one it replaces.
+pub struct StoredRecordIndexer<'a> {
+    index: &'a mut IndexManager,
+    collection: String,
+    live: HashMap<String, Value>,
+}
+
+impl<'a> StoredRecordIndexer<'a> {
+    /// Start indexing into `index`, whose own entries belong to
+    /// `collection`. Existing entries and partitions are cleared.
+    pub fn new(index: &'a mut IndexManager, collection: &str) -> Self {
+        index.clear();
+        Self {
+            index,
+            collection: collection.to_string(),
+            live: HashMap::new(),
+        }
+    }
+
+    /// Entries of `collection`: the manager itself or a partition, created
+    /// on first use
+    pub fn partition(&mut self, collection: &str) -> &mut IndexManager {
+        if collection == self.collection {
+            self.index
+        } else {
+            self.index.collection_mut(collection)
+        }
+    }
+
+    /// Apply one stored record
+    pub fn apply(&mut self, doc: &DocumentInfo) {
+        let (collection, doc_id) = split_stored_id(&doc.document_id);
+        let old = self.live.remove(&doc.document_id);
+        let partition = self.partition(collection);
+
+        if let Some(old) = old {
+            partition.apply_delete(doc_id, &old);
+        }
+        if doc.is_tombstone {
+            return;
+        }
+
+        let values = partition.projection(&doc.body);
+        partition.apply_write(&DocumentInfo {
+            document_id: doc_id.to_string(),
+            schema_id: doc.schema_id.clone(),
+            schema_version: doc.schema_version.clone(),
+            is_tombstone: false,
+            body: values.clone(),
+            offset: doc.offset,
+        });
+        self.live.insert(doc.document_id.clone(), values);
+    }
+
+    /// Restore the entries of `checkpoint`
+    pub fn restore<S: StorageSeek + ?Sized>(
+        &mut self,
+        checkpoint: &IndexCheckpoint,
+        storage: &mut S,
+    ) -> IndexResult<()> {
+        for entry in &checkpoint.entries {
+            let body = match &entry.values {
+                Some(values) => values.clone(),
+                None => storage.read_body(entry.offset)?,
+            };
+            self.apply(&DocumentInfo {
+                document_id: entry.document_id.clone(),
+                schema_id: String::new(),
+                schema_version: String::new(),
+                is_tombstone: false,
+                body,
+                offset: entry.offset,
+            });
+        }
+        Ok(())
+    }
+
+    /// Apply every record from the scan position to the end of storage
+    ///
+    /// Returns the number of records applied.
+    pub fn scan<S: StorageScan + ?Sized>(&mut self, storage: &mut S) -> IndexResult<u64> {
+        let mut applied = 0;
+        loop {
+            let doc = match storage.scan_next() {
+                Ok(Some(doc)) => doc,
+                Ok(None) => return Ok(applied),
+                Err(e) => {
+                    return Err(IndexError::data_corruption(
+                        storage.current_offset(),
+                        e.message(),
+                    ))
+                }
+            };
+            self.apply(&doc);
+            applied += 1;
+        }
+    }
+}
+
+/// Split a stored ID into its collection and document ID
+pub(super) fn split_stored_id(stored_id: &str) -> (&str, &str) {
+    stored_id.split_once(':').unwrap_or(("", stored_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashSet;
+    use tempfile::TempDir;
+
+    /// In-memory storage of stored records at offsets 100, 200, ...
+    struct MockStorage {
+        records: Vec<DocumentInfo>,
+        position: usize,
+    }
+
+    impl MockStorage {
+        fn new() -> Self {
+            Self {
+                records: Vec::new(),
+                position: 0,
+            }
+        }
+
+        fn append(&mut self, document_id: &str, body: Option<Value>) -> u64 {
+            let offset = (self.records.len() as u64 + 1) * 100;
+            self.records.push(DocumentInfo {
+                document_id: document_id.to_string(),
+                schema_id: "users".to_string(),
+                schema_version: "v1".to_string(),
+                is_tombstone: body.is_none(),
+                body: body.unwrap_or(Value::Null),
+                offset,
+            });
+            offset
+        }
+    }
+
+    impl StorageScan for MockStorage {
+        fn scan_next(&mut self) -> IndexResult<Option<DocumentInfo>> {
+            let doc = self.records.get(self.position).cloned();
+            self.position += 1;
+            Ok(doc)
+        }
+
+        fn reset(&mut self) -> IndexResult<()> {
+            self.position = 0;
+            Ok(())
+        }
+
+        fn current_offset(&self) -> u64 {
+            (self.position as u64 + 1) * 100
+        }
+    }
+
+    impl StorageSeek for MockStorage {
+        fn seek(&mut self, offset: u64) -> IndexResult<()> {
+            self.position = (offset / 100).saturating_sub(1) as usize;
+            Ok(())
+        }
+
+        fn read_body(&mut self, offset: u64) -> IndexResult<Value> {
+            let doc = &self.records[(offset / 100 - 1) as usize];
+            Ok(doc.body.clone())
+        }
+
+        fn storage_len(&mut self) -> IndexResult<u64> {
+            Ok((self.records.len() as u64 + 1) * 100)
+        }
+    }
+
+    fn index() -> IndexManager {
+        IndexManager::new(HashSet::from(["age".to_string()])).with_unique_field("email")
+    }
+
+    #[test]
+    fn test_checkpoint_restore_matches_rebuild() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = MockStorage::new();
+        storage.append("users:u1", Some(json!({"age": 30, "email": "a@x"})));
+        storage.append("users:u2", Some(json!({"age": 40, "email": "b@x"})));
+        storage.append("admins:a1", Some(json!({"age": 30, "email": "a@x"})));
+        storage.append("users:u2", None);
+
+        let mut live = index();
+        let mut indexer = StoredRecordIndexer::new(&mut live, "users");
+        indexer.scan(&mut storage).unwrap();
+
+        let checkpoint = IndexCheckpoint::capture(
+            &live,
+            "users",
+            "20260101T000000Z",
+            &mut storage,
+            &|collection| collection == "admins",
+        )
+        .unwrap();
+        assert_eq!(checkpoint.entries.len(), 2);
+        assert!(checkpoint.entries[0].values.is_none());
+        assert_eq!(
+            checkpoint.entries[1].values,
+            Some(json!({"age": 30, "email": "a@x"}))
+        );
+
+        let path = temp_dir.path().join(INDEX_CHECKPOINT_FILE);
+        checkpoint.write_to(&path).unwrap();
+        let read = IndexCheckpoint::read_from(&path).unwrap();
+        assert_eq!(read, checkpoint);
+
+        // Records stored after the checkpoint are patched in
+        storage.append("users:u1", Some(json!({"age": 31, "email": "a@x"})));
+        storage.append("users:u3", Some(json!({"age": 30, "email": "c@x"})));
+
+        let mut restored = index();
+        let mut indexer = StoredRecordIndexer::new(&mut restored, "users");
+        indexer.restore(&read, &mut storage).unwrap();
+        storage.seek(read.storage_end).unwrap();
+        assert_eq!(indexer.scan(&mut storage).unwrap(), 2);
+
+        assert_eq!(restored.lookup_eq("age", &json!(30)), vec![600]);
+        assert_eq!(restored.lookup_eq("age", &json!(31)), vec![500]);
+        assert!(restored.lookup_pk("u2").is_empty());
+        assert!(restored
+            .check_unique("u9", &json!({"email": "a@x"}))
+            .is_err());
+        let admins = restored.collection("admins").unwrap();
+        assert_eq!(admins.lookup_eq("age", &json!(30)), vec![300]);
+    }
+
+    #[test]
+    fn test_corrupt_checkpoint_is_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut storage = MockStorage::new();
+        storage.append("users:u1", Some(json!({"age": 30})));
+        let mut live = index();
+        StoredRecordIndexer::new(&mut live, "users")
+            .scan(&mut storage)
+            .unwrap();
+
+        let path = temp_dir.path().join(INDEX_CHECKPOINT_FILE);
+        IndexCheckpoint::capture(&live, "users", "s1", &mut storage, &|_| false)
+            .unwrap()
+            .write_to(&path)
+            .unwrap();
+
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 2;
+        bytes[last] ^= 0x01;
+        fs::write(&path, bytes).unwrap();
+
+        let err = IndexCheckpoint::read_from(&path).unwrap_err();
+        assert!(err.message().contains("checksum mismatch"));
+    }
+}
@@ -9,13 +9,23 @@
 //! - `apply_delete(doc_id)` - Update index after delete
 //! - `lookup_eq(field, value)` - Exact match lookup
 //! - `lookup_range(field, min, max, limit)` - Range lookup
-
-use std::collections::{HashMap, HashSet};
+//! - `lookup_composite(fields, prefix)` - Composite index prefix lookup
+//! - `check_unique(doc_id, body)` - Unique constraint check before a write
+//! - `collection_mut(name)` - Index partition of another collection
+//! - `add_field_index(field)` / `backfill_field(field, offset, body)` -
+//!   Declare an index at runtime and fill it from existing documents
+//! - `documents()` / `projection(body)` - Live entries and the values
+//!   indexes read, captured by index checkpoints
+//! - `field_stats()` - Cardinality of each single-field index, for the
+//!   planner's cost model
+
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use serde_json::Value;
 
-use super::btree::{IndexKey, IndexTree, StorageOffset};
+use super::btree::{IndexKey, IndexStats, IndexTree, StorageOffset};
 use super::errors::{IndexError, IndexResult};
+use super::unique::{UniqueMap, UniqueStage, UniqueViolation};
 
 /// Document info extracted from storage for indexing
 #[derive(Debug, Clone)]
@@ -49,6 +59,9 @@ pub trait StorageScan {
 }
 
 /// Index Manager that maintains in-memory indexes
+///
+/// Two managers compare equal when every entry and declaration matches.
+#[derive(Debug, PartialEq)]
 pub struct IndexManager {
     /// Primary key index (_id -> offset)
     pk_index: IndexTree,
@@ -59,8 +72,17 @@ pub struct IndexManager {
     /// Indexed field names
     indexed_fields: HashSet<String>,
 
+    /// Composite indexes (ordered field list -> IndexTree of encoded keys)
+    composite_indexes: HashMap<Vec<String>, IndexTree>,
+
+    /// Unique field values -> owning document
+    unique: UniqueMap,
+
     /// Document ID to offset mapping (for delete)
     doc_offsets: HashMap<String, StorageOffset>,
+
+    /// Partitions of collections other than the one this manager indexes
+    collections: BTreeMap<String, IndexManager>,
 }
 
 impl IndexManager {
@@ -75,8 +97,143 @@ impl IndexManager {
             pk_index: IndexTree::new(),
             field_indexes,
             indexed_fields,
+            composite_indexes: HashMap::new(),
+            unique: UniqueMap::default(),
             doc_offsets: HashMap::new(),
+            collections: BTreeMap::new(),
+        }
+    }
+
+    /// Declare a unique indexed field.
+    ///
+    /// The field is also indexed for lookups. Must be declared before
+    /// `rebuild_from_storage` so existing values are tracked.
+    pub fn with_unique_field(mut self, field: impl Into<String>) -> Self {
+        let field = field.into();
+        self.field_indexes.entry(field.clone()).or_default();
+        self.indexed_fields.insert(field.clone());
+        self.unique.add_field(field);
+        self
+    }
+
+    /// Declare a composite index over the given fields, in order.
+    ///
+    /// Must be declared before `rebuild_from_storage` so existing documents
+    /// are indexed. Declarations with fewer than two fields are ignored.
+    pub fn with_composite_index(
+        mut self,
+        fields: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        let fields: Vec<String> = fields.into_iter().map(Into::into).collect();
+        if fields.len() >= 2 {
+            self.composite_indexes.entry(fields).or_default();
+        }
+        self
+    }
+
+    /// Index partition of another collection, created on first use
+    ///
+    /// A partition declares the same single-field, unique and composite
+    /// indexes as this manager, so document ids and unique values are
+    /// scoped to their collection.
+    pub fn collection_mut(&mut self, collection: &str) -> &mut IndexManager {
+        if !self.collections.contains_key(collection) {
+            let partition = self.unique.fields().fold(
+                self.composite_indexes.keys().fold(
+                    IndexManager::new(self.indexed_fields.clone()),
+                    |partition, fields| partition.with_composite_index(fields.iter().cloned()),
+                ),
+                |partition, field| partition.with_unique_field(field.clone()),
+            );
+            self.collections.insert(collection.to_string(), partition);
         }
+        self.collections
+            .get_mut(collection)
+            .expect("partition inserted above")
+    }
+
+    /// Declare a single-field index at runtime.
+    ///
+    /// The field is declared here and in every collection partition, so
+    /// later writes maintain it. Its trees start empty; existing documents
+    /// are added with `backfill_field`. Returns `false` if the field is
+    /// already indexed.
+    pub fn add_field_index(&mut self, field: &str) -> bool {
+        if !self.indexed_fields.insert(field.to_string()) {
+            return false;
+        }
+        self.field_indexes.entry(field.to_string()).or_default();
+        for partition in self.collections.values_mut() {
+            partition.add_field_index(field);
+        }
+        true
+    }
+
+    /// Index the value of `field` in a document body stored at `offset`.
+    ///
+    /// Used to backfill a runtime-declared index from storage. Bodies
+    /// without an indexable value for the field are skipped.
+    pub fn backfill_field(&mut self, field: &str, offset: StorageOffset, body: &Value) {
+        let Some(key) = body.get(field).and_then(IndexKey::from_json) else {
+            return;
+        };
+        if let Some(tree) = self.field_indexes.get_mut(field) {
+            tree.insert(key, offset);
+        }
+    }
+
+    /// Remove every entry and collection partition, keeping declarations
+    pub fn clear(&mut self) {
+        self.pk_index.clear();
+        for tree in self.field_indexes.values_mut() {
+            tree.clear();
+        }
+        for tree in self.composite_indexes.values_mut() {
+            tree.clear();
+        }
+        self.unique.clear();
+        self.doc_offsets.clear();
+        self.collections.clear();
+    }
+
+    /// Index partition of another collection, if it exists
+    pub fn collection(&self, collection: &str) -> Option<&IndexManager> {
+        self.collections.get(collection)
+    }
+
+    /// Live documents and their storage offsets, in no particular order
+    pub fn documents(&self) -> impl Iterator<Item = (&str, StorageOffset)> {
+        self.doc_offsets
+            .iter()
+            .map(|(doc_id, &offset)| (doc_id.as_str(), offset))
+    }
+
+    /// The values of `body` that single-field, unique and composite
+    /// indexes read.
+    ///
+    /// Indexing the projection yields the same entries as indexing `body`.
+    pub fn projection(&self, body: &Value) -> Value {
+        let composite_fields = self.composite_indexes.keys().flatten();
+        let projected = self
+            .indexed_fields
+            .iter()
+            .chain(self.unique.fields())
+            .chain(composite_fields)
+            .filter_map(|field| Some((field.clone(), body.get(field)?.clone())))
+            .collect();
+        Value::Object(projected)
+    }
+
+    /// Names of the collections with an index partition, sorted
+    pub fn collection_names(&self) -> Vec<String> {
+        self.collections.keys().cloned().collect()
+    }
+
+    /// Discard the index partition of a collection
+    ///
+    /// Returns whether the partition existed.
+    pub fn drop_collection(&mut self, collection: &str) -> bool {
+        self.collections.remove(collection).is_some()
     }
 
     /// Create with no secondary indexes (PK only)
@@ -94,12 +251,7 @@ impl IndexManager {
     ///
     /// On checksum failure: returns AERO_DATA_CORRUPTION (FATAL)
     pub fn rebuild_from_storage<S: StorageScan>(&mut self, storage: &mut S) -> IndexResult<()> {
-        // Clear existing indexes
-        self.pk_index.clear();
-        for tree in self.field_indexes.values_mut() {
-            tree.clear();
-        }
-        self.doc_offsets.clear();
+        self.clear();
 
         // Reset storage to beginning
         storage.reset()?;
@@ -117,8 +269,9 @@ impl IndexManager {
                 }
             };
 
-            // Skip tombstones
+            // Skip tombstones (a deleted document's unique values are free)
             if doc.is_tombstone {
+                self.unique.release(&doc.document_id);
                 continue;
             }
 
@@ -148,6 +301,14 @@ impl IndexManager {
                 }
             }
         }
+
+        // Composite indexes (every document, missing fields encoded as null)
+        for (fields, tree) in &mut self.composite_indexes {
+            tree.insert(composite_key(fields, &doc.body), doc.offset);
+        }
+
+        // Unique values (replaces values held by an earlier version)
+        self.unique.claim(&doc.document_id, &doc.body);
     }
 
     /// Remove a document from indexes
@@ -169,6 +330,14 @@ impl IndexManager {
                 }
             }
         }
+
+        // Remove from composite indexes
+        for (fields, tree) in &mut self.composite_indexes {
+            tree.remove(&composite_key(fields, body), offset);
+        }
+
+        // Release unique values
+        self.unique.release(doc_id);
     }
 
     /// Apply a write (insert or update) to indexes.
@@ -255,6 +424,59 @@ impl IndexManager {
         offsets
     }
 
+    /// Lookup offsets whose leading composite fields equal `prefix`.
+    ///
+    /// `prefix` holds one value per bound field, in index order; trailing
+    /// fields are unconstrained. Returns offsets sorted ascending, or none if
+    /// no such composite index is declared.
+    pub fn lookup_composite(&self, fields: &[String], prefix: &[Value]) -> Vec<StorageOffset> {
+        let Some(tree) = self.composite_indexes.get(fields) else {
+            return Vec::new();
+        };
+        if prefix.len() > fields.len() {
+            return Vec::new();
+        }
+
+        let encoded: String = prefix
+            .iter()
+            .map(|v| composite_component(Some(v)))
+            .collect();
+        if prefix.len() == fields.len() {
+            return tree.lookup_eq(&IndexKey::from_string(encoded));
+        }
+
+        // Keys extending the prefix sort between it and the prefix with its
+        // final '\0' terminator raised to '\u{1}'
+        let mut upper = encoded.clone();
+        upper.pop();
+        upper.push('\u{1}');
+        let min = IndexKey::from_string(encoded);
+        let max = IndexKey::from_string(upper);
+        tree.lookup_range(Some(&min), Some(&max))
+    }
+
+    /// Check that writing `body` as `doc_id` keeps unique fields unique.
+    ///
+    /// Must be called before the write reaches the WAL.
+    pub fn check_unique(&self, doc_id: &str, body: &Value) -> Result<(), UniqueViolation> {
+        self.unique.stage().write(doc_id, body)
+    }
+
+    /// Start a uniqueness check covering several writes of one request
+    pub fn stage_unique(&self) -> UniqueStage<'_> {
+        self.unique.stage()
+    }
+
+    /// Returns the unique fields, sorted
+    pub fn unique_fields(&self) -> impl Iterator<Item = &String> {
+        self.unique.fields()
+    }
+
+    /// Returns the declared composite indexes
+    pub fn composite_indexes(&self) -> impl Iterator<Item = &Vec<String>> {
+        self.composite_indexes.keys()
+    }
+
     /// Get all offsets in primary key order.
     ///
     /// Returns offsets sorted ascending.
@@ -266,6 +488,36 @@ impl IndexManager {
     pub fn indexed_fields(&self) -> &HashSet<String> {
         &self.indexed_fields
     }
+
+    /// Cardinality of each single-field index, by field, sorted
+    ///
+    /// The primary key is reported as `_id`, one entry per document.
+    pub fn field_stats(&self) -> BTreeMap<String, IndexStats> {
+        self.field_indexes
+            .iter()
+            .map(|(field, tree)| (field.clone(), tree.stats()))
+            .chain([("_id".to_string(), self.pk_index.stats())])
+            .collect()
+    }
+}
+
+/// Encoded composite key for a document body
+fn composite_key(fields: &[String], body: &Value) -> IndexKey {
+    IndexKey::from_string(
+        fields
+            .iter()
+            .map(|f| composite_component(body.get(f)))
+            .collect::<String>(),
+    )
+}
+
+/// One composite key component: the value's JSON text, '\0'-terminated.
+///
+/// JSON text never contains a raw '\0', so components cannot run together.
+fn composite_component(value: Option<&Value>) -> String {
+    let mut component = value.map_or_else(|| "null".to_string(), Value::to_string);
+    component.push('\0');
+    component
 }
 
 #[cfg(test)]
@@ -461,6 +713,140 @@ mod tests {
         assert_eq!(result.unwrap_err().code().code(), "AERO_DATA_CORRUPTION");
     }
 
+    #[test]
+    fn test_composite_index_prefix_lookup() {
+        let fields = vec!["age".to_string(), "name".to_string()];
+        let mut manager = IndexManager::pk_only().with_composite_index(fields.clone());
+
+        let mut storage = MockStorage::new(vec![
+            make_doc("a", 30, 100),
+            make_doc("b", 30, 200),
+            make_doc("c", 40, 300),
+            make_doc("d", 3, 400),
+        ]);
+        manager.rebuild_from_storage(&mut storage).unwrap();
+
+        // age 30 must not match the "3" prefix and vice versa
+        assert_eq!(
+            manager.lookup_composite(&fields, &[json!(30)]),
+            vec![100, 200]
+        );
+        assert_eq!(manager.lookup_composite(&fields, &[json!(3)]), vec![400]);
+        assert_eq!(
+            manager.lookup_composite(&fields, &[json!(30), json!("User_b")]),
+            vec![200]
+        );
+        assert!(manager
+            .lookup_composite(&["name".to_string(), "age".to_string()], &[json!(30)])
+            .is_empty());
+
+        manager.apply_delete("b", &json!({"_id": "b", "age": 30, "name": "User_b"}));
+        assert_eq!(manager.lookup_composite(&fields, &[json!(30)]), vec![100]);
+    }
+
+    #[test]
+    fn test_unique_values_rebuilt_from_storage() {
+        let mut manager = IndexManager::pk_only().with_unique_field("name");
+        assert!(manager.indexed_fields().contains("name"));
+
+        // "a" is rewritten with a new name, "b" is deleted
+        let mut renamed = make_doc("a", 30, 300);
+        renamed.body = json!({"_id": "a", "age": 30, "name": "Renamed"});
+        let mut storage = MockStorage::new(vec![
+            make_doc("a", 30, 100),
+            make_doc("b", 40, 200),
+            renamed,
+            make_tombstone("b", 400),
+        ]);
+        manager.rebuild_from_storage(&mut storage).unwrap();
+
+        assert!(manager
+            .check_unique("c", &json!({"name": "User_a"}))
+            .is_ok());
+        assert!(manager
+            .check_unique("c", &json!({"name": "User_b"}))
+            .is_ok());
+        let err = manager
+            .check_unique("c", &json!({"name": "Renamed"}))
+            .unwrap_err();
+        assert_eq!(err.document_id, "a");
+        assert!(manager
+            .check_unique("a", &json!({"name": "Renamed"}))
+            .is_ok());
+
+        manager.apply_delete("a", &json!({"_id": "a", "name": "Renamed"}));
+        assert!(manager
+            .check_unique("c", &json!({"name": "Renamed"}))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_collection_partitions_are_independent() {
+        let mut indexed = HashSet::new();
+        indexed.insert("age".to_string());
+        let mut manager = IndexManager::new(indexed)
+            .with_unique_field("email")
+            .with_composite_index(["age", "email"]);
+
+        manager.apply_write(&make_doc("user_1", 30, 100));
+        let partition = manager.collection_mut("admins");
+        assert!(partition.lookup_pk("user_1").is_empty());
+        assert!(partition.indexed_fields().contains("email"));
+        assert_eq!(partition.composite_indexes().count(), 1);
+
+        partition.apply_write(&make_doc("user_1", 40, 200));
+        assert_eq!(manager.lookup_pk("user_1"), vec![100]);
+        assert_eq!(
+            manager.collection_mut("admins").lookup_pk("user_1"),
+            vec![200]
+        );
+    }
+
+    #[test]
+    fn test_add_field_index_and_backfill() {
+        let mut manager = IndexManager::pk_only();
+        manager.apply_write(&make_doc("user_1", 30, 100));
+        manager.collection_mut("admins");
+
+        assert!(manager.add_field_index("age"));
+        assert!(!manager.add_field_index("age"));
+        assert!(manager.lookup_eq("age", &json!(30)).is_empty());
+        assert!(manager
+            .collection_mut("admins")
+            .indexed_fields()
+            .contains("age"));
+
+        manager.backfill_field("age", 100, &json!({"_id": "user_1", "age": 30}));
+        manager.backfill_field("age", 200, &json!({"_id": "user_2"}));
+        assert_eq!(manager.lookup_eq("age", &json!(30)), vec![100]);
+
+        // Later writes maintain the new index
+        manager.apply_write(&make_doc("user_3", 41, 300));
+        assert_eq!(manager.lookup_eq("age", &json!(41)), vec![300]);
+    }
+
+    #[test]
+    fn test_field_stats_follow_writes_and_deletes() {
+        let mut indexed = HashSet::new();
+        indexed.insert("age".to_string());
+        let mut manager = IndexManager::new(indexed);
+
+        manager.apply_write(&make_doc("user_1", 25, 100));
+        manager.apply_write(&make_doc("user_2", 25, 200));
+        let user_3 = make_doc("user_3", 30, 300);
+        manager.apply_write(&user_3);
+
+        let stats = manager.field_stats();
+        assert_eq!(stats["age"].entries, 3);
+        assert_eq!(stats["age"].distinct_keys, 2);
+        assert_eq!(stats["_id"].distinct_keys, 3);
+
+        manager.apply_delete("user_3", &user_3.body);
+        let stats = manager.field_stats();
+        assert_eq!(stats["age"].distinct_keys, 1);
+        assert_eq!(stats["age"].estimated_eq_rows(), 2);
+    }
+
     #[test]
     fn test_tombstones_ignored() {
         let docs = vec![
@@ -5,7 +5,8 @@
 //! # Design Principles
 //!
 //! - Derived state: Indexes mirror storage, never the source of truth
-//! - In-memory only: No persistence
+//! - In-memory: Optional checkpoints only shorten startup; a stale or
+//!   corrupt checkpoint is discarded in favour of a full rebuild
 //! - Deterministic: BTreeMap iteration order, sorted offsets
 //!
 //! # Invariants
@@ -17,16 +18,26 @@
 //! # Phase 3 Optimizations
 //!
 //! - Acceleration: Improved structures and predicate pre-filtering (optional, disabled by default)
+//! - Parallel rebuild: Storage ranges scanned on worker threads (optional, single-threaded by default)
 
 mod acceleration;
 mod btree;
+mod checkpoint;
 mod errors;
 mod manager;
+mod parallel;
+mod unique;
 
 pub use acceleration::{
     AcceleratorStats, AttributeIndex, CompositeIndex, IndexAccelConfig, IndexAccelerator,
     IndexPath, PrefilterResult, PrefilterStats,
 };
-pub use btree::{IndexKey, IndexTree};
+pub use btree::{IndexKey, IndexStats, IndexTree};
+pub use checkpoint::{
+    IndexCheckpoint, IndexDeclarations, IndexEntry, StorageSeek, StoredRecordIndexer,
+    INDEX_CHECKPOINT_FILE,
+};
 pub use errors::{IndexError, IndexErrorCode, IndexResult};
-pub use manager::{DocumentInfo, IndexManager};
+pub use manager::{DocumentInfo, IndexManager, StorageScan};
+pub use parallel::{rebuild_parallel, RangeScan};
+pub use unique::{UniqueStage, UniqueViolation};
@@ -0,0 +1,327 @@
+//! Parallel index rebuild
+//!
+//! Reading, checksumming and parsing stored records dominates a rebuild.
+//! The parallel path splits storage into contiguous record ranges and scans
+//! each on its own worker thread into a `BTreeMap` holding the last version
+//! of every document stored in the range. The maps are merged in range
+//! order, later ranges winning, and the surviving documents are applied in
+//! storage order.
+//!
+//! Applying only the last version of each document yields the same entries
+//! as the sequential `StoredRecordIndexer` build, which unindexes every
+//! version it replaces. This holds as long as stored documents respect
+//! their unique fields, which writes enforce.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::Value;
+
+use super::btree::StorageOffset;
+use super::checkpoint::{split_stored_id, StoredRecordIndexer};
+use super::errors::{IndexError, IndexResult};
+use super::manager::{DocumentInfo, IndexManager, StorageScan};
+
+/// Storage that can be scanned in independent record ranges
+pub trait RangeScan: StorageScan {
+    /// Scanner over one range, moved to a worker thread
+    type Range: StorageScan + Send;
+
+    /// Offsets of every stored record, in storage order
+    fn record_offsets(&mut self) -> IndexResult<Vec<u64>>;
+
+    /// Open a scanner over the records in `[start, end)`
+    fn open_range(&self, start: u64, end: u64) -> IndexResult<Self::Range>;
+}
+
+/// Last version of a document within a range
+struct LastVersion {
+    offset: StorageOffset,
+    schema_id: String,
+    schema_version: String,
+    /// Indexed values, or `None` for a tombstone
+    values: Option<Value>,
+}
+
+/// What one worker found in its range
+#[derive(Default)]
+struct RangeState {
+    /// Stored ID -> last version
+    last: BTreeMap<String, LastVersion>,
+    /// Collections with at least one record
+    collections: BTreeSet<String>,
+    /// Records scanned
+    records: u64,
+}
+
+/// Rebuild `index` from `storage` using up to `threads` worker threads.
+///
+/// Existing entries and partitions are cleared first; `collection` names
+/// the manager's own collection, as for `StoredRecordIndexer`. With fewer
+/// than two threads or records the rebuild runs sequentially. Returns the
+/// number of records scanned.
+pub fn rebuild_parallel<R: RangeScan>(
+    index: &mut IndexManager,
+    collection: &str,
+    storage: &mut R,
+    threads: usize,
+) -> IndexResult<u64> {
+    let offsets = if threads < 2 {
+        Vec::new()
+    } else {
+        storage.record_offsets()?
+    };
+    if offsets.len() < 2 {
+        let mut indexer = StoredRecordIndexer::new(index, collection);
+        storage.reset()?;
+        return indexer.scan(storage);
+    }
+
+    // Contiguous ranges of roughly equal record counts
+    let per_range = offsets.len().div_ceil(threads);
+    let starts: Vec<u64> = offsets.iter().step_by(per_range).copied().collect();
+    let mut ranges = Vec::with_capacity(starts.len());
+    for (i, &start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).copied().unwrap_or(u64::MAX);
+        ranges.push(storage.open_range(start, end)?);
+    }
+
+    // Workers only read the declarations, to project indexed values
+    let declared: &IndexManager = index;
+    let states = std::thread::scope(|scope| {
+        let workers: Vec<_> = ranges
+            .into_iter()
+            .map(|mut range| scope.spawn(move || scan_range(declared, &mut range)))
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| {
+                worker.join().unwrap_or_else(|_| {
+                    Err(IndexError::build_failed("Index rebuild worker panicked"))
+                })
+            })
+            .collect::<IndexResult<Vec<_>>>()
+    })?;
+
+    // Merge in range order: a later range holds later versions
+    let mut merged = RangeState::default();
+    for state in states {
+        merged.last.extend(state.last);
+        merged.collections.extend(state.collections);
+        merged.records += state.records;
+    }
+
+    let mut indexer = StoredRecordIndexer::new(index, collection);
+    for name in &merged.collections {
+        indexer.partition(name);
+    }
+    let mut live: Vec<_> = merged
+        .last
+        .into_iter()
+        .filter_map(|(stored_id, last)| Some((stored_id, last.values.clone()?, last)))
+        .collect();
+    live.sort_by_key(|(_, _, last)| last.offset);
+    for (document_id, body, last) in live {
+        indexer.apply(&DocumentInfo {
+            document_id,
+            schema_id: last.schema_id,
+            schema_version: last.schema_version,
+            is_tombstone: false,
+            body,
+            offset: last.offset,
+        });
+    }
+
+    Ok(merged.records)
+}
+
+/// Scan one range, keeping the last version of each document
+fn scan_range<S: StorageScan>(declared: &IndexManager, range: &mut S) -> IndexResult<RangeState> {
+    let mut state = RangeState::default();
+    loop {
+        let doc = match range.scan_next() {
+            Ok(Some(doc)) => doc,
+            Ok(None) => return Ok(state),
+            Err(e) => {
+                return Err(IndexError::data_corruption(
+                    range.current_offset(),
+                    e.message(),
+                ))
+            }
+        };
+
+        let (collection, _) = split_stored_id(&doc.document_id);
+        if !state.collections.contains(collection) {
+            state.collections.insert(collection.to_string());
+        }
+        let values = (!doc.is_tombstone).then(|| declared.projection(&doc.body));
+        state.last.insert(
+            doc.document_id,
+            LastVersion {
+                offset: doc.offset,
+                schema_id: doc.schema_id,
+                schema_version: doc.schema_version,
+                values,
+            },
+        );
+        state.records += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashSet;
+
+    /// In-memory storage of stored records at offsets 100, 200, ...
+    #[derive(Clone)]
+    struct MockStorage {
+        records: Vec<DocumentInfo>,
+        position: usize,
+        end: u64,
+    }
+
+    impl MockStorage {
+        fn new(records: Vec<(&str, Option<Value>)>) -> Self {
+            let records = records
+                .into_iter()
+                .enumerate()
+                .map(|(i, (id, body))| DocumentInfo {
+                    document_id: id.to_string(),
+                    schema_id: "users".to_string(),
+                    schema_version: "v1".to_string(),
+                    is_tombstone: body.is_none(),
+                    body: body.unwrap_or(Value::Null),
+                    offset: (i as u64 + 1) * 100,
+                })
+                .collect();
+            Self {
+                records,
+                position: 0,
+                end: u64::MAX,
+            }
+        }
+    }
+
+    impl StorageScan for MockStorage {
+        fn scan_next(&mut self) -> IndexResult<Option<DocumentInfo>> {
+            match self.records.get(self.position) {
+                Some(doc) if doc.offset < self.end => {
+                    self.position += 1;
+                    Ok(Some(doc.clone()))
+                }
+                _ => Ok(None),
+            }
+        }
+
+        fn reset(&mut self) -> IndexResult<()> {
+            self.position = 0;
+            Ok(())
+        }
+
+        fn current_offset(&self) -> u64 {
+            (self.position as u64 + 1) * 100
+        }
+    }
+
+    impl RangeScan for MockStorage {
+        type Range = MockStorage;
+
+        fn record_offsets(&mut self) -> IndexResult<Vec<u64>> {
+            Ok(self.records.iter().map(|doc| doc.offset).collect())
+        }
+
+        fn open_range(&self, start: u64, end: u64) -> IndexResult<Self::Range> {
+            let mut range = self.clone();
+            range.position = (start / 100 - 1) as usize;
+            range.end = end;
+            Ok(range)
+        }
+    }
+
+    fn index() -> IndexManager {
+        IndexManager::new(HashSet::from(["age".to_string()]))
+            .with_unique_field("email")
+            .with_composite_index(["age", "email"])
+    }
+
+    #[test]
+    fn test_parallel_rebuild_matches_sequential() {
+        let mut records = Vec::new();
+        for i in 0..40 {
+            let body = json!({"age": i % 7, "email": format!("u{}@x", i)});
+            records.push((format!("users:u{}", i % 13), Some(body)));
+            if i % 5 == 0 {
+                records.push((format!("users:u{}", (i + 3) % 13), None));
+            }
+            if i % 4 == 0 {
+                records.push((format!("admins:a{}", i % 3), Some(json!({"age": i}))));
+            }
+        }
+        records.push(("archive:x1".to_string(), Some(json!({"age": 1}))));
+        records.push(("archive:x1".to_string(), None));
+        let records: Vec<(&str, Option<Value>)> = records
+            .iter()
+            .map(|(id, body)| (id.as_str(), body.clone()))
+            .collect();
+
+        let mut sequential = index();
+        let mut storage = MockStorage::new(records.clone());
+        StoredRecordIndexer::new(&mut sequential, "users")
+            .scan(&mut storage)
+            .unwrap();
+
+        for threads in [2, 3, 8] {
+            let mut parallel = index();
+            let mut storage = MockStorage::new(records.clone());
+            let scanned = rebuild_parallel(&mut parallel, "users", &mut storage, threads).unwrap();
+            assert_eq!(scanned, records.len() as u64);
+            assert_eq!(parallel, sequential, "threads = {}", threads);
+        }
+        assert!(sequential.collection("archive").is_some());
+    }
+
+    #[test]
+    fn test_parallel_rebuild_reports_corruption() {
+        struct Corrupt(MockStorage);
+
+        impl StorageScan for Corrupt {
+            fn scan_next(&mut self) -> IndexResult<Option<DocumentInfo>> {
+                match self.0.scan_next()? {
+                    Some(doc) if doc.offset == 300 => {
+                        Err(IndexError::data_corruption(300, "checksum mismatch"))
+                    }
+                    other => Ok(other),
+                }
+            }
+
+            fn reset(&mut self) -> IndexResult<()> {
+                self.0.reset()
+            }
+
+            fn current_offset(&self) -> u64 {
+                self.0.current_offset()
+            }
+        }
+
+        impl RangeScan for Corrupt {
+            type Range = Corrupt;
+
+            fn record_offsets(&mut self) -> IndexResult<Vec<u64>> {
+                self.0.record_offsets()
+            }
+
+            fn open_range(&self, start: u64, end: u64) -> IndexResult<Self::Range> {
+                Ok(Corrupt(self.0.open_range(start, end)?))
+            }
+        }
+
+        let records = (0..4)
+            .map(|_| ("users:u1", Some(json!({"age": 1}))))
+            .collect();
+        let mut storage = Corrupt(MockStorage::new(records));
+        let err = rebuild_parallel(&mut index(), "users", &mut storage, 2).unwrap_err();
+        assert!(err.is_fatal());
+        assert!(err.message().contains("checksum mismatch"));
+    }
+}
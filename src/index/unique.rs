@@ -0,0 +1,227 @@
+//! Unique secondary index constraints
+//!
+//! Maps each value of a unique field to the single document holding it.
+//! Null, missing and non-scalar values are not constrained.
+//!
+//! Writes are checked through a `UniqueStage` before anything reaches the
+//! WAL. A stage layers the writes of one request over the committed map, so
+//! a batch or transaction may move a value between its own documents.
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde_json::Value;
+
+use super::btree::IndexKey;
+
+/// (field, value) slot of a unique field
+type UniqueKey = (String, IndexKey);
+
+/// A write that would duplicate a unique field value
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniqueViolation {
+    /// Unique field
+    pub field: String,
+    /// Duplicated value
+    pub value: Value,
+    /// Document already holding the value
+    pub document_id: String,
+}
+
+/// Committed uniqueness map
+#[derive(Debug, Default, PartialEq)]
+pub struct UniqueMap {
+    /// Unique field names (ordered for deterministic checks)
+    fields: BTreeSet<String>,
+    /// (field, value) -> owning document
+    owners: HashMap<UniqueKey, String>,
+    /// Document -> slots it owns
+    held: HashMap<String, Vec<UniqueKey>>,
+}
+
+impl UniqueMap {
+    /// Declare a unique field
+    pub fn add_field(&mut self, field: impl Into<String>) {
+        self.fields.insert(field.into());
+    }
+
+    /// Unique field names, sorted
+    pub fn fields(&self) -> impl Iterator<Item = &String> {
+        self.fields.iter()
+    }
+
+    /// Slots a document body would occupy
+    fn keys_of(&self, body: &Value) -> Vec<UniqueKey> {
+        self.fields
+            .iter()
+            .filter_map(|field| {
+                let key = IndexKey::from_json(body.get(field)?)?;
+                Some((field.clone(), key))
+            })
+            .collect()
+    }
+
+    /// Record `doc_id` as the owner of its body's values, replacing the
+    /// values it held before.
+    pub fn claim(&mut self, doc_id: &str, body: &Value) {
+        self.release(doc_id);
+        let keys = self.keys_of(body);
+        for key in &keys {
+            self.owners.insert(key.clone(), doc_id.to_string());
+        }
+        self.held.insert(doc_id.to_string(), keys);
+    }
+
+    /// Release every value held by `doc_id`
+    pub fn release(&mut self, doc_id: &str) {
+        for key in self.held.remove(doc_id).unwrap_or_default() {
+            if self.owners.get(&key).map(String::as_str) == Some(doc_id) {
+                self.owners.remove(&key);
+            }
+        }
+    }
+
+    /// Remove all entries (declared fields are kept)
+    pub fn clear(&mut self) {
+        self.owners.clear();
+        self.held.clear();
+    }
+
+    /// Start staging the writes of one request
+    pub fn stage(&self) -> UniqueStage<'_> {
+        UniqueStage {
+            map: self,
+            owners: HashMap::new(),
+            held: HashMap::new(),
+        }
+    }
+}
+
+/// Uniqueness check for the writes of one request, applied in order
+#[derive(Debug)]
+pub struct UniqueStage<'a> {
+    map: &'a UniqueMap,
+    /// Staged slot owners (None = released by this request)
+    owners: HashMap<UniqueKey, Option<String>>,
+    /// Staged slots per document written by this request
+    held: HashMap<String, Vec<UniqueKey>>,
+}
+
+impl UniqueStage<'_> {
+    fn owner(&self, key: &UniqueKey) -> Option<&str> {
+        match self.owners.get(key) {
+            Some(owner) => owner.as_deref(),
+            None => self.map.owners.get(key).map(String::as_str),
+        }
+    }
+
+    fn held_by(&self, doc_id: &str) -> Vec<UniqueKey> {
+        self.held
+            .get(doc_id)
+            .or_else(|| self.map.held.get(doc_id))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Stage an insert or update of `doc_id` with `body`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first unique field (in name order) whose value is held by
+    /// another document. Nothing is staged on error.
+    pub fn write(&mut self, doc_id: &str, body: &Value) -> Result<(), UniqueViolation> {
+        let keys = self.map.keys_of(body);
+        for key in &keys {
+            if let Some(owner) = self.owner(key).filter(|owner| *owner != doc_id) {
+                return Err(UniqueViolation {
+                    field: key.0.clone(),
+                    value: body.get(&key.0).cloned().unwrap_or(Value::Null),
+                    document_id: owner.to_string(),
+                });
+            }
+        }
+
+        self.delete(doc_id);
+        for key in &keys {
+            self.owners.insert(key.clone(), Some(doc_id.to_string()));
+        }
+        self.held.insert(doc_id.to_string(), keys);
+        Ok(())
+    }
+
+    /// Stage a delete of `doc_id`, releasing its values
+    pub fn delete(&mut self, doc_id: &str) {
+        for key in self.held_by(doc_id) {
+            if self.owner(&key) == Some(doc_id) {
+                self.owners.insert(key, None);
+            }
+        }
+        self.held.insert(doc_id.to_string(), Vec::new());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn email_map() -> UniqueMap {
+        let mut map = UniqueMap::default();
+        map.add_field("email");
+        map.claim("a", &json!({"_id": "a", "email": "a@x.com"}));
+        map
+    }
+
+    fn is_free(map: &UniqueMap, email: &str) -> bool {
+        map.stage().write("other", &json!({"email": email})).is_ok()
+    }
+
+    #[test]
+    fn test_claim_and_release() {
+        let mut map = email_map();
+        assert!(!is_free(&map, "a@x.com"));
+
+        // Update moves the value
+        map.claim("a", &json!({"_id": "a", "email": "new@x.com"}));
+        assert!(is_free(&map, "a@x.com"));
+        assert!(!is_free(&map, "new@x.com"));
+
+        map.release("a");
+        assert!(is_free(&map, "new@x.com"));
+    }
+
+    #[test]
+    fn test_stage_rejects_duplicate() {
+        let map = email_map();
+        let mut stage = map.stage();
+
+        // Same document may keep its own value; null is unconstrained
+        assert!(stage.write("a", &json!({"email": "a@x.com"})).is_ok());
+        assert!(stage.write("b", &json!({"email": null})).is_ok());
+        assert!(stage.write("c", &json!({})).is_ok());
+
+        let err = stage.write("b", &json!({"email": "a@x.com"})).unwrap_err();
+        assert_eq!(err.field, "email");
+        assert_eq!(err.value, json!("a@x.com"));
+        assert_eq!(err.document_id, "a");
+    }
+
+    #[test]
+    fn test_stage_tracks_earlier_writes() {
+        let map = email_map();
+        let mut stage = map.stage();
+
+        // Two new documents in one request collide
+        stage.write("b", &json!({"email": "b@x.com"})).unwrap();
+        assert!(stage.write("c", &json!({"email": "b@x.com"})).is_err());
+
+        // A value released earlier in the request can be reused
+        stage.write("a", &json!({"email": "other@x.com"})).unwrap();
+        assert!(stage.write("d", &json!({"email": "a@x.com"})).is_ok());
+
+        stage.delete("b");
+        assert!(stage.write("e", &json!({"email": "b@x.com"})).is_ok());
+
+        // The committed map is untouched
+        assert!(!is_free(&map, "a@x.com"));
+    }
+}
@@ -2,14 +2,22 @@
 //!
 //! Core abstractions provide unified operation model and execution pipeline.
 
+// Lets `#[derive(AeroDocument)]` output (`::aerodb::...` paths) compile
+// inside this crate too
+extern crate self as aerodb;
+
 pub mod api;
 pub mod auth;
 pub mod backup;
 pub mod checkpoint;
 pub mod cli;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod config;
 pub mod core;
 pub mod crash_point;
 pub mod dx;
+pub mod embedded;
 pub mod executor;
 pub mod file_storage;
 pub mod functions;
@@ -18,6 +26,7 @@ pub mod index;
 pub mod mvcc;
 pub mod observability;
 pub mod performance;
+pub mod pgwire;
 pub mod planner;
 pub mod promotion;
 pub mod realtime;
@@ -29,3 +38,5 @@ pub mod schema;
 pub mod snapshot;
 pub mod storage;
 pub mod wal;
+
+pub use embedded::{AeroDb, AeroDbOptions};
@@ -15,9 +15,10 @@
 //! This module provides the CommitAuthority which:
 //! - Tracks the highest observed commit identity (from WAL replay)
 //! - Provides the next commit identity for new commits
+//! - Enforces first-committer-wins between transactions writing the same key
 //! - Does NOT store state outside the WAL
 
-use crate::mvcc::CommitId;
+use crate::mvcc::{CommitId, ReadView};
 
 /// Commit authority for WAL-based commit identity assignment.
 ///
@@ -130,6 +131,31 @@ impl CommitAuthority {
     pub fn current_snapshot(&self) -> crate::mvcc::ReadView {
         crate::mvcc::ReadView::new(CommitId::new(self.highest_commit_id))
     }
+
+    /// Validate a transaction's write set against its read view.
+    ///
+    /// `latest` yields each key the transaction writes with the commit
+    /// identity of the newest committed write to that key, if any. A key
+    /// committed after `view` was taken was written by a concurrent
+    /// transaction that committed first; the transaction must abort rather
+    /// than overwrite it (first committer wins).
+    ///
+    /// Keys are checked in the order given; the first conflict is returned.
+    pub fn check_write_set<'k>(
+        view: &ReadView,
+        latest: impl IntoIterator<Item = (&'k str, Option<CommitId>)>,
+    ) -> Result<(), CommitAuthorityError> {
+        for (key, committed) in latest {
+            if let Some(committed) = committed.filter(|c| *c > view.upper_bound()) {
+                return Err(CommitAuthorityError::WriteConflict {
+                    key: key.to_string(),
+                    committed: committed.value(),
+                    read_upper_bound: view.upper_bound().value(),
+                });
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Default for CommitAuthority {
@@ -145,6 +171,12 @@ pub enum CommitAuthorityError {
     NonMonotonic { observed: u64, highest: u64 },
     /// Attempted to commit out of order.
     OutOfOrder { attempted: u64, expected: u64 },
+    /// A key in the write set was committed after the transaction's read view.
+    WriteConflict {
+        key: String,
+        committed: u64,
+        read_upper_bound: u64,
+    },
 }
 
 impl std::fmt::Display for CommitAuthorityError {
@@ -167,6 +199,17 @@ impl std::fmt::Display for CommitAuthorityError {
                     attempted, expected
                 )
             }
+            CommitAuthorityError::WriteConflict {
+                key,
+                committed,
+                read_upper_bound,
+            } => {
+                write!(
+                    f,
+                    "Write conflict on '{}': committed at {} after read view {}",
+                    key, committed, read_upper_bound
+                )
+            }
         }
     }
 }
@@ -243,6 +286,31 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_first_committer_wins() {
+        let view = ReadView::new(CommitId::new(10));
+
+        // Unwritten keys and writes inside the view do not conflict
+        let clean = [("a", None), ("b", Some(CommitId::new(10)))];
+        assert!(CommitAuthority::check_write_set(&view, clean).is_ok());
+
+        let result = CommitAuthority::check_write_set(
+            &view,
+            [
+                ("a", Some(CommitId::new(3))),
+                ("b", Some(CommitId::new(11))),
+            ],
+        );
+        assert_eq!(
+            result,
+            Err(CommitAuthorityError::WriteConflict {
+                key: "b".to_string(),
+                committed: 11,
+                read_upper_bound: 10,
+            })
+        );
+    }
+
     #[test]
     fn test_from_replayed_commit() {
         let authority = CommitAuthority::from_replayed_commit(100);
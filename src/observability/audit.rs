@@ -8,16 +8,25 @@
 //!
 //! Per PHASE7_INVARIANTS.md §P7-O3:
 //! - States and action history are written to persistent append-only audit logs.
-//! - No background purging or retention policies (those are external concerns).
+//! - No background purging: a `FileAuditLog` rotates and applies its
+//!   retention policy only while appending or opening.
 
 use std::fmt;
-use std::fs::{File, OpenOptions};
+use std::fs::{self, File, OpenOptions};
 use std::io::{self, BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 use uuid::Uuid;
 
+use super::sinks::{remove_if_exists, rename_if_exists};
+
+/// Default size at which a file audit log rotates (100MB)
+pub const DEFAULT_AUDIT_LOG_MAX_BYTES: u64 = 104857600;
+
+/// Default number of rotated audit log files kept
+pub const DEFAULT_AUDIT_LOG_KEEP: usize = 10;
+
 /// Audit action type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AuditAction {
@@ -147,6 +156,12 @@ pub struct AuditRecord {
 
     /// Referenced invariant (if applicable).
     pub invariant: Option<String>,
+
+    /// Resource acted on, e.g. a collection or schema (if applicable).
+    pub resource: Option<String>,
+
+    /// Documents affected (if applicable).
+    pub document_ids: Vec<String>,
 }
 
 impl AuditRecord {
@@ -165,6 +180,8 @@ impl AuditRecord {
             outcome,
             error_message: None,
             invariant: None,
+            resource: None,
+            document_ids: Vec::new(),
         }
     }
 
@@ -216,6 +233,18 @@ impl AuditRecord {
         self
     }
 
+    /// Set the resource acted on.
+    pub fn with_resource(mut self, resource: impl Into<String>) -> Self {
+        self.resource = Some(resource.into());
+        self
+    }
+
+    /// Set the affected document IDs.
+    pub fn with_documents(mut self, ids: Vec<String>) -> Self {
+        self.document_ids = ids;
+        self
+    }
+
     /// Serialize to JSON line (for append-only logging).
     pub fn to_json(&self) -> String {
         // Manual JSON to avoid dependency; simple and deterministic
@@ -254,6 +283,17 @@ impl AuditRecord {
         if let Some(ref inv) = self.invariant {
             json.push_str(&format!(r#","invariant":"{}""#, escape_json(inv)));
         }
+        if let Some(ref res) = self.resource {
+            json.push_str(&format!(r#","resource":"{}""#, escape_json(res)));
+        }
+        if !self.document_ids.is_empty() {
+            let ids: Vec<String> = self
+                .document_ids
+                .iter()
+                .map(|id| format!(r#""{}""#, escape_json(id)))
+                .collect();
+            json.push_str(&format!(r#","docs":[{}]"#, ids.join(",")));
+        }
 
         json.push('}');
         json
@@ -284,48 +324,174 @@ pub trait AuditLog: Send + Sync {
     fn sync(&self) -> io::Result<()>;
 }
 
+/// Rotation and retention policy of a file audit log.
+///
+/// The default never rotates, so nothing is ever deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditRetention {
+    /// Rotate once the active file would pass this size (`None`: never).
+    pub max_bytes: Option<u64>,
+    /// Rotated files kept; older ones are deleted.
+    pub keep: usize,
+    /// Delete rotated files last written longer ago than this
+    /// (`None`: regardless of age).
+    pub max_age: Option<Duration>,
+}
+
+impl Default for AuditRetention {
+    fn default() -> Self {
+        Self {
+            max_bytes: None,
+            keep: DEFAULT_AUDIT_LOG_KEEP,
+            max_age: None,
+        }
+    }
+}
+
+/// A file audit log and its retention policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditLogConfig {
+    /// Active audit log file
+    pub path: PathBuf,
+    /// Rotation and retention
+    pub retention: AuditRetention,
+}
+
+impl AuditLogConfig {
+    /// Open the configured log
+    pub fn open(&self) -> io::Result<FileAuditLog> {
+        FileAuditLog::open_with_retention(&self.path, self.retention)
+    }
+}
+
 /// File-based audit log implementation.
 ///
 /// Per PHASE7_AUDITABILITY.md §5:
 /// - Append-only file format
 /// - fsync after each write for durability
 /// - One JSON record per line
+///
+/// On rotation `<path>.N` becomes `<path>.N+1` and `<path>` becomes
+/// `<path>.1`; the active file is synced first, so no record is lost.
 pub struct FileAuditLog {
     path: PathBuf,
-    writer: Arc<Mutex<BufWriter<File>>>,
+    retention: AuditRetention,
+    file: Arc<Mutex<AuditFile>>,
+}
+
+/// Active audit file and its size
+struct AuditFile {
+    writer: BufWriter<File>,
+    size: u64,
+}
+
+impl AuditFile {
+    fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            writer: BufWriter::new(file),
+            size,
+        })
+    }
 }
 
 impl FileAuditLog {
-    /// Open or create an audit log file.
+    /// Open or create an audit log file that is never rotated.
     pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::open_with_retention(path, AuditRetention::default())
+    }
+
+    /// Open or create an audit log file rotated and pruned per `retention`.
+    pub fn open_with_retention(
+        path: impl AsRef<Path>,
+        retention: AuditRetention,
+    ) -> io::Result<Self> {
         let path = path.as_ref().to_path_buf();
-        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let file = AuditFile::open(&path)?;
 
-        Ok(Self {
+        let log = Self {
             path,
-            writer: Arc::new(Mutex::new(BufWriter::new(file))),
-        })
+            retention,
+            file: Arc::new(Mutex::new(file)),
+        };
+        log.prune()?;
+        Ok(log)
     }
 
     /// Get the audit log path.
     pub fn path(&self) -> &Path {
         &self.path
     }
+
+    /// Path of the `n`th most recent rotated file.
+    pub fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&self, file: &mut AuditFile) -> io::Result<()> {
+        file.writer.flush()?;
+        file.writer.get_ref().sync_all()?;
+
+        let keep = self.retention.keep;
+        if keep == 0 {
+            remove_if_exists(&self.path)?;
+        } else {
+            remove_if_exists(&self.rotated_path(keep))?;
+            for n in (1..keep).rev() {
+                rename_if_exists(&self.rotated_path(n), &self.rotated_path(n + 1))?;
+            }
+            rename_if_exists(&self.path, &self.rotated_path(1))?;
+        }
+        *file = AuditFile::open(&self.path)?;
+        self.prune()
+    }
+
+    /// Delete rotated files past `max_age`.
+    fn prune(&self) -> io::Result<()> {
+        let max_age = match self.retention.max_age {
+            Some(max_age) => max_age,
+            None => return Ok(()),
+        };
+        for n in 1..=self.retention.keep {
+            let path = self.rotated_path(n);
+            let modified = match fs::metadata(&path).and_then(|m| m.modified()) {
+                Ok(modified) => modified,
+                Err(_) => continue,
+            };
+            let age = SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or(Duration::ZERO);
+            if age > max_age {
+                remove_if_exists(&path)?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl AuditLog for FileAuditLog {
     fn append(&self, record: &AuditRecord) -> io::Result<()> {
         let json = record.to_json();
-        let mut writer = self.writer.lock().unwrap();
-        writeln!(writer, "{}", json)?;
-        writer.flush()?;
+        let incoming = json.len() as u64 + 1;
+        let mut file = self.file.lock().unwrap();
+        if let Some(max_bytes) = self.retention.max_bytes {
+            if file.size > 0 && file.size + incoming > max_bytes {
+                self.rotate(&mut file)?;
+            }
+        }
+        writeln!(file.writer, "{}", json)?;
+        file.writer.flush()?;
+        file.size += incoming;
         // Sync to disk for durability
-        writer.get_ref().sync_all()
+        file.writer.get_ref().sync_all()
     }
 
     fn sync(&self) -> io::Result<()> {
-        let writer = self.writer.lock().unwrap();
-        writer.get_ref().sync_all()
+        let file = self.file.lock().unwrap();
+        file.writer.get_ref().sync_all()
     }
 }
 
@@ -431,6 +597,62 @@ mod tests {
         assert!(contents.contains("inspect_cluster_state"));
     }
 
+    #[test]
+    fn test_audit_record_documents_json() {
+        let record = AuditRecord::new(AuditAction::CommandExecuted, AuditOutcome::Success)
+            .with_command("insert_many")
+            .with_resource("users")
+            .with_documents(vec!["u1".to_string(), "u\"2".to_string()]);
+
+        let json = record.to_json();
+        assert!(json.contains(r#""resource":"users""#));
+        assert!(json.contains(r#""docs":["u1","u\"2"]"#));
+    }
+
+    #[test]
+    fn test_file_audit_log_rotates_and_keeps() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let retention = AuditRetention {
+            max_bytes: Some(1),
+            keep: 2,
+            max_age: None,
+        };
+        let log = FileAuditLog::open_with_retention(&path, retention).unwrap();
+
+        for name in ["first", "second", "third", "fourth"] {
+            let record = AuditRecord::new(AuditAction::CommandExecuted, AuditOutcome::Success)
+                .with_command(name);
+            log.append(&record).unwrap();
+        }
+
+        assert!(fs::read_to_string(&path).unwrap().contains("fourth"));
+        assert!(fs::read_to_string(log.rotated_path(1))
+            .unwrap()
+            .contains("third"));
+        assert!(fs::read_to_string(log.rotated_path(2))
+            .unwrap()
+            .contains("second"));
+        assert!(!log.rotated_path(3).exists());
+    }
+
+    #[test]
+    fn test_file_audit_log_prunes_by_age() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let stale = dir.path().join("audit.log.1");
+        fs::write(&stale, "old\n").unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+
+        let retention = AuditRetention {
+            max_bytes: None,
+            keep: 2,
+            max_age: Some(Duration::from_millis(10)),
+        };
+        FileAuditLog::open_with_retention(&path, retention).unwrap();
+        assert!(!stale.exists());
+    }
+
     #[test]
     fn test_escape_json() {
         assert_eq!(escape_json("hello"), "hello");
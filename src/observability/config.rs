@@ -0,0 +1,34 @@
+//! Runtime-adjustable observability settings
+//!
+//! The log level and metrics enablement are the only settings that may
+//! change while the database runs; `apply` swaps them in place without
+//! touching any other subsystem.
+
+use super::logger::{Logger, Severity};
+use super::metrics::MetricsRegistry;
+
+/// Log level and metrics enablement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObservabilityConfig {
+    /// Minimum severity logged (default TRACE: everything)
+    pub log_level: Severity,
+    /// Whether metrics counters are updated (default true)
+    pub metrics_enabled: bool,
+}
+
+impl Default for ObservabilityConfig {
+    fn default() -> Self {
+        Self {
+            log_level: Severity::Trace,
+            metrics_enabled: true,
+        }
+    }
+}
+
+impl ObservabilityConfig {
+    /// Make these settings current for the logger and `metrics`
+    pub fn apply(&self, metrics: &MetricsRegistry) {
+        Logger::set_min_severity(self.log_level);
+        metrics.set_enabled(self.metrics_enabled);
+    }
+}
@@ -27,12 +27,32 @@ pub enum Event {
     ShutdownStart,
     /// Shutdown complete
     ShutdownComplete,
+    /// Serving loop stopped accepting requests during shutdown
+    ShutdownStopAccepting,
+    /// Data directory lock taken
+    LockAcquired,
+    /// Data directory lock released
+    LockReleased,
+    /// Lock left by a process that no longer exists taken over
+    LockStale,
 
     // Configuration
     /// Configuration loaded
     ConfigLoaded,
     /// Schemas loaded
     SchemasLoaded,
+    /// Log level and metrics enablement re-read from the config
+    ObservabilityReloaded,
+    /// Observability reload rejected; previous settings kept
+    ObservabilityReloadFailed,
+    /// HTTPS certificates re-read from disk
+    TlsReloaded,
+    /// HTTPS certificate reload rejected; previous certificates kept
+    TlsReloadFailed,
+    /// A log sink failed to write and is being skipped
+    LogSinkDegraded,
+    /// A degraded log sink wrote successfully again
+    LogSinkRecovered,
 
     // WAL operations
     /// WAL record appended
@@ -43,6 +63,10 @@ pub enum Event {
     WalTruncate,
     /// WAL corruption detected (FATAL)
     WalCorruption,
+    /// Torn final WAL record truncated during recovery
+    WalTailTruncated,
+    /// Uncommitted transaction discarded at end of WAL during recovery
+    WalTxnDiscarded,
 
     // Snapshot operations
     /// Snapshot creation started
@@ -81,6 +105,10 @@ pub enum Event {
     RecoveryReplayBegin,
     /// WAL replay complete
     RecoveryReplayComplete,
+    /// Periodic WAL replay progress
+    RecoveryReplayProgress,
+    /// WAL replay resumed from a recorded position
+    RecoveryReplayResumed,
     /// Index rebuild begins
     RecoveryIndexRebuildBegin,
     /// Index rebuild complete
@@ -117,6 +145,10 @@ pub enum Event {
     // Server operations
     /// Server serving (ready for requests)
     Serving,
+    /// PostgreSQL wire-protocol listener accepting connections
+    PgWireListening,
+    /// A PostgreSQL wire-protocol connection ended with an error
+    PgWireConnectionFailed,
 }
 
 impl Event {
@@ -128,16 +160,28 @@ impl Event {
             Event::BootComplete => "AERODB_STARTUP_COMPLETE",
             Event::ShutdownStart => "SHUTDOWN_START",
             Event::ShutdownComplete => "SHUTDOWN_COMPLETE",
+            Event::ShutdownStopAccepting => "SHUTDOWN_STOP_ACCEPTING",
+            Event::LockAcquired => "DATA_DIR_LOCK_ACQUIRED",
+            Event::LockReleased => "DATA_DIR_LOCK_RELEASED",
+            Event::LockStale => "DATA_DIR_LOCK_STALE",
 
             // Configuration
             Event::ConfigLoaded => "CONFIG_LOADED",
             Event::SchemasLoaded => "SCHEMAS_LOADED",
+            Event::ObservabilityReloaded => "OBSERVABILITY_RELOADED",
+            Event::ObservabilityReloadFailed => "OBSERVABILITY_RELOAD_FAILED",
+            Event::TlsReloaded => "TLS_RELOADED",
+            Event::TlsReloadFailed => "TLS_RELOAD_FAILED",
+            Event::LogSinkDegraded => "LOG_SINK_DEGRADED",
+            Event::LogSinkRecovered => "LOG_SINK_RECOVERED",
 
             // WAL
             Event::WalAppend => "WAL_APPEND",
             Event::WalFsync => "WAL_FSYNC",
             Event::WalTruncate => "WAL_TRUNCATED",
             Event::WalCorruption => "WAL_CORRUPTION",
+            Event::WalTailTruncated => "WAL_TAIL_TRUNCATED",
+            Event::WalTxnDiscarded => "WAL_TXN_DISCARDED",
 
             // Snapshot
             Event::SnapshotStart => "SNAPSHOT_START",
@@ -162,6 +206,8 @@ impl Event {
             Event::RecoveryStart => "RECOVERY_BEGIN",
             Event::RecoveryReplayBegin => "WAL_REPLAY_BEGIN",
             Event::RecoveryReplayComplete => "WAL_REPLAY_COMPLETE",
+            Event::RecoveryReplayProgress => "WAL_REPLAY_PROGRESS",
+            Event::RecoveryReplayResumed => "WAL_REPLAY_RESUMED",
             Event::RecoveryIndexRebuildBegin => "INDEX_REBUILD_BEGIN",
             Event::RecoveryIndexRebuildComplete => "INDEX_REBUILD_COMPLETE",
             Event::RecoveryVerifyBegin => "VERIFICATION_BEGIN",
@@ -184,6 +230,8 @@ impl Event {
 
             // Server
             Event::Serving => "AERODB_SERVING",
+            Event::PgWireListening => "PGWIRE_LISTENING",
+            Event::PgWireConnectionFailed => "PGWIRE_CONNECTION_FAILED",
         }
     }
 
@@ -210,12 +258,24 @@ mod tests {
             Event::BootComplete,
             Event::ShutdownStart,
             Event::ShutdownComplete,
+            Event::ShutdownStopAccepting,
+            Event::LockAcquired,
+            Event::LockReleased,
+            Event::LockStale,
             Event::ConfigLoaded,
             Event::SchemasLoaded,
+            Event::ObservabilityReloaded,
+            Event::ObservabilityReloadFailed,
+            Event::TlsReloaded,
+            Event::TlsReloadFailed,
+            Event::LogSinkDegraded,
+            Event::LogSinkRecovered,
             Event::WalAppend,
             Event::WalFsync,
             Event::WalTruncate,
             Event::WalCorruption,
+            Event::WalTailTruncated,
+            Event::WalTxnDiscarded,
             Event::SnapshotStart,
             Event::SnapshotComplete,
             Event::CheckpointStart,
@@ -230,6 +290,8 @@ mod tests {
             Event::RecoveryStart,
             Event::RecoveryReplayBegin,
             Event::RecoveryReplayComplete,
+            Event::RecoveryReplayProgress,
+            Event::RecoveryReplayResumed,
             Event::RecoveryIndexRebuildBegin,
             Event::RecoveryIndexRebuildComplete,
             Event::RecoveryVerifyBegin,
@@ -244,6 +306,8 @@ mod tests {
             Event::ExplainBegin,
             Event::ExplainComplete,
             Event::Serving,
+            Event::PgWireListening,
+            Event::PgWireConnectionFailed,
         ];
 
         for event in events {
@@ -6,9 +6,18 @@
 //! - Explicit severity levels
 //! - One log line = one event
 //! - Synchronous, no buffering
+//! - Events below the minimum severity are dropped (default: none)
+//! - Lines also go to the sinks installed at boot (see `sinks`)
 
 use std::fmt;
 use std::io::{self, Write};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use super::events::Event;
+use super::sinks;
+
+/// Minimum severity `Logger::log` and `Logger::log_stderr` emit
+static MIN_SEVERITY: AtomicU8 = AtomicU8::new(Severity::Trace as u8);
 
 /// Log severity levels per OBSERVABILITY.md
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -36,6 +45,28 @@ impl Severity {
             Severity::Fatal => "FATAL",
         }
     }
+
+    /// Parse a severity name, case-insensitively
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "TRACE" => Some(Severity::Trace),
+            "INFO" => Some(Severity::Info),
+            "WARN" => Some(Severity::Warn),
+            "ERROR" => Some(Severity::Error),
+            "FATAL" => Some(Severity::Fatal),
+            _ => None,
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Severity::Trace,
+            1 => Severity::Info,
+            2 => Severity::Warn,
+            3 => Severity::Error,
+            _ => Severity::Fatal,
+        }
+    }
 }
 
 impl fmt::Display for Severity {
@@ -53,16 +84,47 @@ impl fmt::Display for Severity {
 pub struct Logger;
 
 impl Logger {
+    /// Set the minimum severity logged from now on
+    pub fn set_min_severity(severity: Severity) {
+        MIN_SEVERITY.store(severity as u8, Ordering::Relaxed);
+    }
+
+    /// Minimum severity currently logged
+    pub fn min_severity() -> Severity {
+        Severity::from_u8(MIN_SEVERITY.load(Ordering::Relaxed))
+    }
+
+    /// Whether events at `severity` are logged
+    pub fn is_enabled(severity: Severity) -> bool {
+        severity >= Self::min_severity()
+    }
+
     /// Log an event with the given severity and fields
     ///
     /// Fields are output in deterministic order (alphabetical by key)
     pub fn log(severity: Severity, event: &str, fields: &[(&str, &str)]) {
-        Self::log_to_writer(severity, event, fields, &mut io::stdout());
+        if Self::is_enabled(severity) {
+            let line = Self::format_line(severity, event, fields);
+            Self::write_line(&line, &mut io::stdout());
+            sinks::dispatch(severity, &line);
+        }
     }
 
     /// Log to stderr (for errors and fatal messages)
     pub fn log_stderr(severity: Severity, event: &str, fields: &[(&str, &str)]) {
-        Self::log_to_writer(severity, event, fields, &mut io::stderr());
+        if Self::is_enabled(severity) {
+            let line = Self::format_line(severity, event, fields);
+            Self::write_line(&line, &mut io::stderr());
+            sinks::dispatch(severity, &line);
+        }
+    }
+
+    /// Report a problem with logging itself on stderr only, so a failing
+    /// sink is never asked to log its own failure
+    pub(super) fn report(severity: Severity, event: Event, fields: &[(&str, &str)]) {
+        if Self::is_enabled(severity) {
+            Self::log_to_writer(severity, event.as_str(), fields, &mut io::stderr());
+        }
     }
 
     /// Internal log implementation that writes to a given writer
@@ -72,6 +134,22 @@ impl Logger {
         fields: &[(&str, &str)],
         writer: &mut W,
     ) {
+        Self::write_line(&Self::format_line(severity, event, fields), writer);
+    }
+
+    /// Write one line, ignoring failures per OBSERVABILITY.md
+    fn write_line<W: Write>(line: &str, writer: &mut W) {
+        let mut output = String::with_capacity(line.len() + 1);
+        output.push_str(line);
+        output.push('\n');
+
+        // Write atomically (one syscall)
+        let _ = writer.write_all(output.as_bytes());
+        let _ = writer.flush();
+    }
+
+    /// Format one JSON log line, without the trailing newline
+    fn format_line(severity: Severity, event: &str, fields: &[(&str, &str)]) -> String {
         // Build JSON manually to avoid allocations and ensure deterministic ordering
         let mut output = String::with_capacity(256);
 
@@ -100,11 +178,7 @@ impl Logger {
         }
 
         output.push('}');
-        output.push('\n');
-
-        // Write atomically (one syscall)
-        let _ = writer.write_all(output.as_bytes());
-        let _ = writer.flush();
+        output
     }
 
     /// Escape special characters for JSON strings
@@ -179,6 +253,16 @@ mod tests {
         assert_eq!(Severity::Fatal.as_str(), "FATAL");
     }
 
+    #[test]
+    fn test_severity_from_name() {
+        assert_eq!(Severity::from_name("warn"), Some(Severity::Warn));
+        assert_eq!(Severity::from_name("FATAL"), Some(Severity::Fatal));
+        assert_eq!(Severity::from_name("verbose"), None);
+        for severity in [Severity::Trace, Severity::Info, Severity::Error] {
+            assert_eq!(Severity::from_u8(severity as u8), severity);
+        }
+    }
+
     #[test]
     fn test_log_json_format() {
         let output = capture_log(Severity::Info, "TEST_EVENT", &[]);
@@ -1,12 +1,63 @@
 //! Metrics registry for AeroDB
 //!
 //! Per OBSERVABILITY.md:
-//! - Counters only (no gauges, no histograms)
+//! - Counters only; latency histograms are fixed buckets of counters
 //! - Monotonic increase
 //! - Reset only on process start
 //! - Thread-safe but lock-minimal
+//! - Counters hold still while metrics are disabled
 
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Buckets per histogram
+pub const HISTOGRAM_BUCKETS: usize = 8;
+
+/// Upper bounds of the query latency buckets, in microseconds
+pub const QUERY_LATENCY_BUCKETS_MICROS: [u64; HISTOGRAM_BUCKETS] =
+    [100, 500, 1_000, 5_000, 10_000, 50_000, 100_000, 1_000_000];
+
+/// Upper bounds of the checkpoint and recovery duration buckets, in
+/// microseconds
+pub const DURATION_BUCKETS_MICROS: [u64; HISTOGRAM_BUCKETS] = [
+    10_000, 100_000, 500_000, 1_000_000, 5_000_000, 10_000_000, 30_000_000, 60_000_000,
+];
+
+/// Fixed-bucket histogram of durations
+///
+/// Observations above the last bound are only reflected in `count`.
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: [AtomicU64; HISTOGRAM_BUCKETS],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, bounds: &[u64; HISTOGRAM_BUCKETS], duration: Duration) {
+        let micros = duration.as_micros().min(u64::MAX as u128) as u64;
+        if let Some(bucket) = bounds.iter().position(|bound| micros <= *bound) {
+            self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros.fetch_add(micros, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self, bounds: &[u64; HISTOGRAM_BUCKETS]) -> HistogramSnapshot {
+        HistogramSnapshot {
+            bounds_micros: bounds.to_vec(),
+            counts: self
+                .buckets
+                .iter()
+                .map(|bucket| bucket.load(Ordering::Relaxed))
+                .collect(),
+            count: self.count.load(Ordering::Relaxed),
+            sum_micros: self.sum_micros.load(Ordering::Relaxed),
+        }
+    }
+}
 
 /// Metrics registry containing all operational counters
 ///
@@ -22,6 +73,8 @@ pub struct MetricsRegistry {
     wal_bytes_written: AtomicU64,
     /// Total WAL records written
     wal_records_written: AtomicU64,
+    /// WAL fsyncs completed
+    wal_fsyncs: AtomicU64,
     /// WAL truncation count
     wal_truncations: AtomicU64,
     /// Snapshot count
@@ -36,6 +89,14 @@ pub struct MetricsRegistry {
     queries_executed: AtomicU64,
     /// Rejected query count
     queries_rejected: AtomicU64,
+    /// Documents visited by queries
+    documents_scanned: AtomicU64,
+    /// Offsets produced by index lookups for queries
+    index_candidates: AtomicU64,
+    /// Records checksum-validated by queries
+    checksum_validations: AtomicU64,
+    /// Predicate filter evaluations by queries
+    filter_evaluations: AtomicU64,
     /// Recovery run count
     recovery_runs: AtomicU64,
     /// Recovery failure count
@@ -44,6 +105,20 @@ pub struct MetricsRegistry {
     documents: AtomicU64,
     /// Write operation count
     writes: AtomicU64,
+    /// Requests shed at admission under overload
+    requests_shed: AtomicU64,
+    /// Storage point lookups served from the block cache
+    storage_cache_hits: AtomicU64,
+    /// Storage point lookups that went to disk
+    storage_cache_misses: AtomicU64,
+    /// Query latency
+    query_latency: Histogram,
+    /// Checkpoint duration
+    checkpoint_duration: Histogram,
+    /// Recovery duration
+    recovery_duration: Histogram,
+    /// Set while metrics collection is switched off
+    disabled: AtomicBool,
 }
 
 impl MetricsRegistry {
@@ -52,21 +127,49 @@ impl MetricsRegistry {
         Self::default()
     }
 
+    /// Switch metrics collection on or off.
+    ///
+    /// Counters keep their values while off.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.disabled.store(!enabled, Ordering::Relaxed);
+    }
+
+    /// Whether counters are currently updated
+    pub fn is_enabled(&self) -> bool {
+        !self.disabled.load(Ordering::Relaxed)
+    }
+
+    fn add(&self, counter: &AtomicU64, value: u64) {
+        if self.is_enabled() {
+            counter.fetch_add(value, Ordering::Relaxed);
+        }
+    }
+
     // WAL metrics
 
     /// Increment WAL bytes written
     pub fn add_wal_bytes(&self, bytes: u64) {
-        self.wal_bytes_written.fetch_add(bytes, Ordering::Relaxed);
+        self.add(&self.wal_bytes_written, bytes);
     }
 
     /// Increment WAL records written
     pub fn increment_wal_records(&self) {
-        self.wal_records_written.fetch_add(1, Ordering::Relaxed);
+        self.add(&self.wal_records_written, 1);
+    }
+
+    /// Add WAL records written
+    pub fn add_wal_records(&self, records: u64) {
+        self.add(&self.wal_records_written, records);
+    }
+
+    /// Increment WAL fsyncs
+    pub fn increment_wal_fsyncs(&self) {
+        self.add(&self.wal_fsyncs, 1);
     }
 
     /// Increment WAL truncations
     pub fn increment_wal_truncations(&self) {
-        self.wal_truncations.fetch_add(1, Ordering::Relaxed);
+        self.add(&self.wal_truncations, 1);
     }
 
     /// Get WAL bytes written
@@ -78,70 +181,135 @@ impl MetricsRegistry {
 
     /// Increment snapshots created
     pub fn increment_snapshots(&self) {
-        self.snapshots_created.fetch_add(1, Ordering::Relaxed);
+        self.add(&self.snapshots_created, 1);
     }
 
     /// Increment checkpoints created
     pub fn increment_checkpoints(&self) {
-        self.checkpoints_created.fetch_add(1, Ordering::Relaxed);
+        self.add(&self.checkpoints_created, 1);
+    }
+
+    /// Record how long a checkpoint took
+    pub fn observe_checkpoint_duration(&self, duration: Duration) {
+        if self.is_enabled() {
+            self.checkpoint_duration
+                .observe(&DURATION_BUCKETS_MICROS, duration);
+        }
     }
 
     // Backup/Restore metrics
 
     /// Increment backups created
     pub fn increment_backups(&self) {
-        self.backups_created.fetch_add(1, Ordering::Relaxed);
+        self.add(&self.backups_created, 1);
     }
 
     /// Increment restores performed
     pub fn increment_restores(&self) {
-        self.restores_performed.fetch_add(1, Ordering::Relaxed);
+        self.add(&self.restores_performed, 1);
     }
 
     // Query metrics
 
     /// Increment queries executed
     pub fn increment_queries_executed(&self) {
-        self.queries_executed.fetch_add(1, Ordering::Relaxed);
+        self.add(&self.queries_executed, 1);
     }
 
     /// Increment queries rejected
     pub fn increment_queries_rejected(&self) {
-        self.queries_rejected.fetch_add(1, Ordering::Relaxed);
+        self.add(&self.queries_rejected, 1);
+    }
+
+    /// Add documents visited by a query
+    pub fn add_documents_scanned(&self, count: u64) {
+        self.add(&self.documents_scanned, count);
+    }
+
+    /// Add offsets produced by a query's index lookup
+    pub fn add_index_candidates(&self, count: u64) {
+        self.add(&self.index_candidates, count);
+    }
+
+    /// Add records checksum-validated by a query
+    pub fn add_checksum_validations(&self, count: u64) {
+        self.add(&self.checksum_validations, count);
+    }
+
+    /// Add predicate filter evaluations by a query
+    pub fn add_filter_evaluations(&self, count: u64) {
+        self.add(&self.filter_evaluations, count);
+    }
+
+    /// Record how long a query took
+    pub fn observe_query_latency(&self, duration: Duration) {
+        if self.is_enabled() {
+            self.query_latency
+                .observe(&QUERY_LATENCY_BUCKETS_MICROS, duration);
+        }
     }
 
     // Recovery metrics
 
     /// Increment recovery runs
     pub fn increment_recovery_runs(&self) {
-        self.recovery_runs.fetch_add(1, Ordering::Relaxed);
+        self.add(&self.recovery_runs, 1);
     }
 
     /// Increment recovery failures
     pub fn increment_recovery_failures(&self) {
-        self.recovery_failures.fetch_add(1, Ordering::Relaxed);
+        self.add(&self.recovery_failures, 1);
+    }
+
+    /// Record how long a recovery took
+    pub fn observe_recovery_duration(&self, duration: Duration) {
+        if self.is_enabled() {
+            self.recovery_duration
+                .observe(&DURATION_BUCKETS_MICROS, duration);
+        }
     }
 
     // Document metrics
 
     /// Set document count
     pub fn set_documents(&self, count: u64) {
-        self.documents.store(count, Ordering::Relaxed);
+        if self.is_enabled() {
+            self.documents.store(count, Ordering::Relaxed);
+        }
     }
 
     /// Increment document count
     pub fn increment_documents(&self) {
-        self.documents.fetch_add(1, Ordering::Relaxed);
+        self.add(&self.documents, 1);
     }
 
     /// Decrement document count
     pub fn decrement_documents(&self) {
-        self.documents.fetch_sub(1, Ordering::Relaxed);
+        if self.is_enabled() {
+            self.documents.fetch_sub(1, Ordering::Relaxed);
+        }
     }
 
     /// Increment writes
     pub fn increment_writes(&self) {
-        self.writes.fetch_add(1, Ordering::Relaxed);
+        self.add(&self.writes, 1);
+    }
+
+    /// Increment requests shed at admission
+    pub fn increment_requests_shed(&self) {
+        self.add(&self.requests_shed, 1);
+    }
+
+    // Storage cache metrics
+
+    /// Increment storage block cache hits
+    pub fn increment_storage_cache_hits(&self) {
+        self.add(&self.storage_cache_hits, 1);
+    }
+
+    /// Increment storage block cache misses
+    pub fn increment_storage_cache_misses(&self) {
+        self.add(&self.storage_cache_misses, 1);
     }
 
     /// Get current snapshot of all metrics as JSON
@@ -149,9 +317,10 @@ impl MetricsRegistry {
     /// Per OBSERVABILITY.md §5, returns exact values.
     pub fn to_json(&self) -> String {
         format!(
-            r#"{{"wal_bytes":{},"wal_records":{},"wal_truncations":{},"snapshots":{},"checkpoints":{},"backups":{},"restores":{},"queries_executed":{},"queries_rejected":{},"recovery_runs":{},"recovery_failures":{},"documents":{},"writes":{}}}"#,
+            r#"{{"wal_bytes":{},"wal_records":{},"wal_fsyncs":{},"wal_truncations":{},"snapshots":{},"checkpoints":{},"backups":{},"restores":{},"queries_executed":{},"queries_rejected":{},"documents_scanned":{},"index_candidates":{},"checksum_validations":{},"filter_evaluations":{},"recovery_runs":{},"recovery_failures":{},"documents":{},"writes":{},"requests_shed":{},"storage_cache_hits":{},"storage_cache_misses":{}}}"#,
             self.wal_bytes_written.load(Ordering::Relaxed),
             self.wal_records_written.load(Ordering::Relaxed),
+            self.wal_fsyncs.load(Ordering::Relaxed),
             self.wal_truncations.load(Ordering::Relaxed),
             self.snapshots_created.load(Ordering::Relaxed),
             self.checkpoints_created.load(Ordering::Relaxed),
@@ -159,10 +328,17 @@ impl MetricsRegistry {
             self.restores_performed.load(Ordering::Relaxed),
             self.queries_executed.load(Ordering::Relaxed),
             self.queries_rejected.load(Ordering::Relaxed),
+            self.documents_scanned.load(Ordering::Relaxed),
+            self.index_candidates.load(Ordering::Relaxed),
+            self.checksum_validations.load(Ordering::Relaxed),
+            self.filter_evaluations.load(Ordering::Relaxed),
             self.recovery_runs.load(Ordering::Relaxed),
             self.recovery_failures.load(Ordering::Relaxed),
             self.documents.load(Ordering::Relaxed),
             self.writes.load(Ordering::Relaxed),
+            self.requests_shed.load(Ordering::Relaxed),
+            self.storage_cache_hits.load(Ordering::Relaxed),
+            self.storage_cache_misses.load(Ordering::Relaxed),
         )
     }
 
@@ -171,6 +347,7 @@ impl MetricsRegistry {
         MetricsSnapshot {
             wal_bytes: self.wal_bytes_written.load(Ordering::Relaxed),
             wal_records: self.wal_records_written.load(Ordering::Relaxed),
+            wal_fsyncs: self.wal_fsyncs.load(Ordering::Relaxed),
             wal_truncations: self.wal_truncations.load(Ordering::Relaxed),
             snapshots: self.snapshots_created.load(Ordering::Relaxed),
             checkpoints: self.checkpoints_created.load(Ordering::Relaxed),
@@ -178,19 +355,43 @@ impl MetricsRegistry {
             restores: self.restores_performed.load(Ordering::Relaxed),
             queries_executed: self.queries_executed.load(Ordering::Relaxed),
             queries_rejected: self.queries_rejected.load(Ordering::Relaxed),
+            documents_scanned: self.documents_scanned.load(Ordering::Relaxed),
+            index_candidates: self.index_candidates.load(Ordering::Relaxed),
+            checksum_validations: self.checksum_validations.load(Ordering::Relaxed),
+            filter_evaluations: self.filter_evaluations.load(Ordering::Relaxed),
             recovery_runs: self.recovery_runs.load(Ordering::Relaxed),
             recovery_failures: self.recovery_failures.load(Ordering::Relaxed),
             documents: self.documents.load(Ordering::Relaxed),
             writes: self.writes.load(Ordering::Relaxed),
+            requests_shed: self.requests_shed.load(Ordering::Relaxed),
+            storage_cache_hits: self.storage_cache_hits.load(Ordering::Relaxed),
+            storage_cache_misses: self.storage_cache_misses.load(Ordering::Relaxed),
+            query_latency: self.query_latency.snapshot(&QUERY_LATENCY_BUCKETS_MICROS),
+            checkpoint_duration: self.checkpoint_duration.snapshot(&DURATION_BUCKETS_MICROS),
+            recovery_duration: self.recovery_duration.snapshot(&DURATION_BUCKETS_MICROS),
         }
     }
 }
 
+/// A point-in-time copy of one histogram
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HistogramSnapshot {
+    /// Upper bound of each bucket, in microseconds
+    pub bounds_micros: Vec<u64>,
+    /// Observations in each bucket (not cumulative)
+    pub counts: Vec<u64>,
+    /// All observations, including those above the last bound
+    pub count: u64,
+    /// Sum of all observations, in microseconds
+    pub sum_micros: u64,
+}
+
 /// A point-in-time snapshot of all metrics
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct MetricsSnapshot {
     pub wal_bytes: u64,
     pub wal_records: u64,
+    pub wal_fsyncs: u64,
     pub wal_truncations: u64,
     pub snapshots: u64,
     pub checkpoints: u64,
@@ -198,10 +399,20 @@ pub struct MetricsSnapshot {
     pub restores: u64,
     pub queries_executed: u64,
     pub queries_rejected: u64,
+    pub documents_scanned: u64,
+    pub index_candidates: u64,
+    pub checksum_validations: u64,
+    pub filter_evaluations: u64,
     pub recovery_runs: u64,
     pub recovery_failures: u64,
     pub documents: u64,
     pub writes: u64,
+    pub requests_shed: u64,
+    pub storage_cache_hits: u64,
+    pub storage_cache_misses: u64,
+    pub query_latency: HistogramSnapshot,
+    pub checkpoint_duration: HistogramSnapshot,
+    pub recovery_duration: HistogramSnapshot,
 }
 
 #[cfg(test)]
@@ -242,6 +453,8 @@ mod tests {
         registry.increment_restores();
         registry.increment_queries_executed();
         registry.increment_queries_rejected();
+        registry.add_documents_scanned(5);
+        registry.add_filter_evaluations(4);
         registry.increment_recovery_runs();
         registry.increment_recovery_failures();
 
@@ -253,6 +466,8 @@ mod tests {
         assert_eq!(snapshot.restores, 1);
         assert_eq!(snapshot.queries_executed, 1);
         assert_eq!(snapshot.queries_rejected, 1);
+        assert_eq!(snapshot.documents_scanned, 5);
+        assert_eq!(snapshot.filter_evaluations, 4);
         assert_eq!(snapshot.recovery_runs, 1);
         assert_eq!(snapshot.recovery_failures, 1);
     }
@@ -271,6 +486,39 @@ mod tests {
         assert_eq!(registry.snapshot().documents, 100);
     }
 
+    #[test]
+    fn test_disabled_counters_hold_still() {
+        let registry = MetricsRegistry::new();
+        assert!(registry.is_enabled());
+        registry.increment_writes();
+
+        registry.set_enabled(false);
+        registry.increment_writes();
+        registry.add_wal_bytes(10);
+        assert_eq!(registry.snapshot().writes, 1);
+        assert_eq!(registry.wal_bytes(), 0);
+
+        registry.set_enabled(true);
+        registry.increment_writes();
+        assert_eq!(registry.snapshot().writes, 2);
+    }
+
+    #[test]
+    fn test_histogram_buckets() {
+        let registry = MetricsRegistry::new();
+        registry.observe_query_latency(Duration::from_micros(50));
+        registry.observe_query_latency(Duration::from_micros(100));
+        registry.observe_query_latency(Duration::from_millis(3));
+        registry.observe_query_latency(Duration::from_secs(10));
+
+        let latency = registry.snapshot().query_latency;
+        assert_eq!(latency.counts[0], 2);
+        assert_eq!(latency.counts[3], 1);
+        assert_eq!(latency.counts.iter().sum::<u64>(), 3);
+        assert_eq!(latency.count, 4);
+        assert_eq!(latency.sum_micros, 50 + 100 + 3_000 + 10_000_000);
+    }
+
     #[test]
     fn test_to_json() {
         let registry = MetricsRegistry::new();
@@ -2,8 +2,11 @@
 //!
 //! Per OBSERVABILITY.md, this module provides:
 //! - Structured logging (JSON)
-//! - Deterministic metrics
+//! - Deterministic metrics, exported in the Prometheus text format
 //! - Lifecycle event tracing
+//! - Log level and metrics enablement adjustable at runtime
+//! - Rotating file and syslog/journald log sinks
+//! - A slow query log with a configurable threshold
 //!
 //! # Principles
 //!
@@ -32,16 +35,36 @@
 //! ```
 
 pub mod audit;
+mod config;
 mod events;
 mod logger;
 mod metrics;
+mod prometheus;
 mod scope;
-
-pub use audit::{AuditAction, AuditLog, AuditOutcome, AuditRecord, FileAuditLog, MemoryAuditLog};
+mod sinks;
+mod slow_query;
+
+pub use audit::{
+    AuditAction, AuditLog, AuditLogConfig, AuditOutcome, AuditRecord, AuditRetention, FileAuditLog,
+    MemoryAuditLog, DEFAULT_AUDIT_LOG_KEEP, DEFAULT_AUDIT_LOG_MAX_BYTES,
+};
+pub use config::ObservabilityConfig;
 pub use events::Event;
 pub use logger::{Logger, Severity};
-pub use metrics::{MetricsRegistry, MetricsSnapshot};
+pub use metrics::{
+    HistogramSnapshot, MetricsRegistry, MetricsSnapshot, DURATION_BUCKETS_MICROS,
+    HISTOGRAM_BUCKETS, QUERY_LATENCY_BUCKETS_MICROS,
+};
+pub use prometheus::{render_prometheus, PROMETHEUS_CONTENT_TYPE};
 pub use scope::{ObservationScope, Timer};
+pub use sinks::{
+    install_sinks, FileSinkConfig, LogSink, LogSinkConfig, RotatingFileSink, SyslogSink,
+    DEFAULT_LOG_FILE_KEEP, DEFAULT_LOG_FILE_MAX_BYTES, SYSLOG_SOCKET,
+};
+pub use slow_query::{
+    SlowQuery, SlowQueryConfig, SlowQueryEntry, SlowQueryLog, SlowQueryPlan, SlowQueryTiming,
+    SLOW_QUERIES_RETAINED,
+};
 
 use std::fmt;
 use std::io;
@@ -0,0 +1,211 @@
+//! Prometheus text exposition of a metrics snapshot
+//!
+//! Renders format version 0.0.4: every metric is prefixed `aerodb_`,
+//! counters end in `_total`, and histograms report cumulative buckets in
+//! seconds. Output order is fixed, so equal snapshots render identically.
+
+use std::fmt::Write;
+
+use super::metrics::{HistogramSnapshot, MetricsSnapshot};
+
+/// Content type of the rendered text
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Render `snapshot` in the Prometheus text format
+pub fn render_prometheus(snapshot: &MetricsSnapshot) -> String {
+    let mut out = String::with_capacity(4096);
+
+    let counters = [
+        (
+            "wal_bytes_written",
+            "Bytes written to the WAL",
+            snapshot.wal_bytes,
+        ),
+        (
+            "wal_records_written",
+            "WAL records written",
+            snapshot.wal_records,
+        ),
+        ("wal_fsyncs", "WAL fsyncs completed", snapshot.wal_fsyncs),
+        (
+            "wal_truncations",
+            "WAL truncations",
+            snapshot.wal_truncations,
+        ),
+        ("snapshots_created", "Snapshots created", snapshot.snapshots),
+        (
+            "checkpoints_created",
+            "Checkpoints created",
+            snapshot.checkpoints,
+        ),
+        ("backups_created", "Backups created", snapshot.backups),
+        (
+            "restores_performed",
+            "Restores performed",
+            snapshot.restores,
+        ),
+        (
+            "queries_executed",
+            "Queries executed",
+            snapshot.queries_executed,
+        ),
+        (
+            "queries_rejected",
+            "Queries rejected",
+            snapshot.queries_rejected,
+        ),
+        (
+            "query_documents_scanned",
+            "Documents visited by queries",
+            snapshot.documents_scanned,
+        ),
+        (
+            "query_index_candidates",
+            "Offsets produced by query index lookups",
+            snapshot.index_candidates,
+        ),
+        (
+            "query_checksum_validations",
+            "Records checksum-validated by queries",
+            snapshot.checksum_validations,
+        ),
+        (
+            "query_filter_evaluations",
+            "Predicate evaluations by queries",
+            snapshot.filter_evaluations,
+        ),
+        ("recovery_runs", "Recovery runs", snapshot.recovery_runs),
+        (
+            "recovery_failures",
+            "Recovery failures",
+            snapshot.recovery_failures,
+        ),
+        ("writes", "Write operations", snapshot.writes),
+        (
+            "requests_shed",
+            "Requests shed at admission",
+            snapshot.requests_shed,
+        ),
+        (
+            "storage_cache_hits",
+            "Storage lookups served from the block cache",
+            snapshot.storage_cache_hits,
+        ),
+        (
+            "storage_cache_misses",
+            "Storage lookups that went to disk",
+            snapshot.storage_cache_misses,
+        ),
+    ];
+    for (name, help, value) in counters {
+        let _ = writeln!(out, "# HELP aerodb_{}_total {}", name, help);
+        let _ = writeln!(out, "# TYPE aerodb_{}_total counter", name);
+        let _ = writeln!(out, "aerodb_{}_total {}", name, value);
+    }
+
+    let _ = writeln!(out, "# HELP aerodb_documents Documents stored");
+    let _ = writeln!(out, "# TYPE aerodb_documents gauge");
+    let _ = writeln!(out, "aerodb_documents {}", snapshot.documents);
+
+    let histograms = [
+        (
+            "query_latency_seconds",
+            "Query latency",
+            &snapshot.query_latency,
+        ),
+        (
+            "checkpoint_duration_seconds",
+            "Checkpoint duration",
+            &snapshot.checkpoint_duration,
+        ),
+        (
+            "recovery_duration_seconds",
+            "Recovery duration",
+            &snapshot.recovery_duration,
+        ),
+    ];
+    for (name, help, histogram) in histograms {
+        render_histogram(&mut out, name, help, histogram);
+    }
+
+    out
+}
+
+fn render_histogram(out: &mut String, name: &str, help: &str, histogram: &HistogramSnapshot) {
+    let _ = writeln!(out, "# HELP aerodb_{} {}", name, help);
+    let _ = writeln!(out, "# TYPE aerodb_{} histogram", name);
+    let mut cumulative = 0;
+    for (bound, count) in histogram.bounds_micros.iter().zip(&histogram.counts) {
+        cumulative += count;
+        let _ = writeln!(
+            out,
+            "aerodb_{}_bucket{{le=\"{}\"}} {}",
+            name,
+            seconds(*bound),
+            cumulative
+        );
+    }
+    let _ = writeln!(
+        out,
+        "aerodb_{}_bucket{{le=\"+Inf\"}} {}",
+        name, histogram.count
+    );
+    let _ = writeln!(out, "aerodb_{}_sum {}", name, seconds(histogram.sum_micros));
+    let _ = writeln!(out, "aerodb_{}_count {}", name, histogram.count);
+}
+
+/// Microseconds as decimal seconds, without trailing zeros
+fn seconds(micros: u64) -> String {
+    let whole = micros / 1_000_000;
+    let fraction = micros % 1_000_000;
+    if fraction == 0 {
+        return whole.to_string();
+    }
+    let fraction = format!("{:06}", fraction);
+    format!("{}.{}", whole, fraction.trim_end_matches('0'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observability::MetricsRegistry;
+    use std::time::Duration;
+
+    #[test]
+    fn test_seconds_formatting() {
+        assert_eq!(seconds(0), "0");
+        assert_eq!(seconds(100), "0.0001");
+        assert_eq!(seconds(1_500_000), "1.5");
+        assert_eq!(seconds(60_000_000), "60");
+    }
+
+    #[test]
+    fn test_render_counters_and_histograms() {
+        let registry = MetricsRegistry::new();
+        registry.increment_wal_fsyncs();
+        registry.increment_wal_fsyncs();
+        registry.set_documents(7);
+        registry.observe_query_latency(Duration::from_micros(80));
+        registry.observe_query_latency(Duration::from_millis(2));
+
+        let text = render_prometheus(&registry.snapshot());
+
+        assert!(
+            text.contains("# TYPE aerodb_wal_fsyncs_total counter\naerodb_wal_fsyncs_total 2\n")
+        );
+        assert!(text.contains("aerodb_documents 7\n"));
+        assert!(text.contains("aerodb_query_latency_seconds_bucket{le=\"0.0001\"} 1\n"));
+        assert!(text.contains("aerodb_query_latency_seconds_bucket{le=\"0.005\"} 2\n"));
+        assert!(text.contains("aerodb_query_latency_seconds_bucket{le=\"+Inf\"} 2\n"));
+        assert!(text.contains("aerodb_query_latency_seconds_sum 0.00208\n"));
+        assert!(text.contains("aerodb_recovery_duration_seconds_count 0\n"));
+    }
+
+    #[test]
+    fn test_render_is_deterministic() {
+        let registry = MetricsRegistry::new();
+        registry.increment_writes();
+        let snapshot = registry.snapshot();
+        assert_eq!(render_prometheus(&snapshot), render_prometheus(&snapshot));
+    }
+}
@@ -0,0 +1,444 @@
+//! Log sinks beyond stdout/stderr
+//!
+//! Per OBSERVABILITY.md, observability failure must never crash AeroDB:
+//! - A sink that fails to write is marked degraded and skipped silently
+//!   until a write succeeds again
+//! - Entering and leaving the degraded state is reported once on stderr
+//! - Sinks open lazily, so a bad path never fails boot
+//!
+//! Sinks are installed once at boot with `install_sinks`; every line the
+//! `Logger` emits is then also handed to each sink.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use super::events::Event;
+use super::logger::{Logger, Severity};
+
+/// Default size at which the log file rotates (100MB)
+pub const DEFAULT_LOG_FILE_MAX_BYTES: u64 = 104857600;
+
+/// Default number of rotated log files kept
+pub const DEFAULT_LOG_FILE_KEEP: usize = 5;
+
+/// Socket syslog and journald accept messages on
+pub const SYSLOG_SOCKET: &str = "/dev/log";
+
+/// Sinks every logged line is written to, after stdout/stderr
+static SINKS: RwLock<Vec<SinkSlot>> = RwLock::new(Vec::new());
+
+/// Destination for formatted log lines
+pub trait LogSink: Send + Sync {
+    /// Short name used when reporting failures, e.g. `file`
+    fn name(&self) -> &'static str;
+
+    /// Write one JSON log line (without trailing newline)
+    fn write_line(&self, severity: Severity, line: &str) -> io::Result<()>;
+}
+
+/// Rotating log file settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSinkConfig {
+    /// Active log file; rotated files get `.1`, `.2`, ... appended
+    pub path: PathBuf,
+    /// Rotate once the file reaches this size
+    pub max_bytes: u64,
+    /// Rotate once the file has been open this long (`None`: never)
+    pub max_age: Option<Duration>,
+    /// Rotated files kept; older ones are deleted
+    pub keep: usize,
+}
+
+impl FileSinkConfig {
+    /// Rotate `path` at the default size, keeping the default count
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            max_bytes: DEFAULT_LOG_FILE_MAX_BYTES,
+            max_age: None,
+            keep: DEFAULT_LOG_FILE_KEEP,
+        }
+    }
+}
+
+/// Sinks configured at boot (default: none)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogSinkConfig {
+    /// Rotating log file
+    pub file: Option<FileSinkConfig>,
+    /// Send every line to the local syslog/journald socket
+    pub syslog: bool,
+}
+
+impl LogSinkConfig {
+    /// Build the configured sinks
+    pub fn build(&self) -> Vec<Box<dyn LogSink>> {
+        let mut sinks: Vec<Box<dyn LogSink>> = Vec::new();
+        if let Some(file) = &self.file {
+            sinks.push(Box::new(RotatingFileSink::new(file.clone())));
+        }
+        if self.syslog {
+            sinks.push(Box::new(SyslogSink::new(SYSLOG_SOCKET)));
+        }
+        sinks
+    }
+}
+
+/// Replace the installed sinks with those in `config`
+pub fn install_sinks(config: &LogSinkConfig) {
+    let slots = config.build().into_iter().map(SinkSlot::new).collect();
+    if let Ok(mut sinks) = SINKS.write() {
+        *sinks = slots;
+    }
+}
+
+/// Hand `line` to every installed sink
+pub(super) fn dispatch(severity: Severity, line: &str) {
+    let Ok(sinks) = SINKS.read() else {
+        return;
+    };
+    for slot in sinks.iter() {
+        slot.write(severity, line);
+    }
+}
+
+/// An installed sink and whether its last write failed
+pub(super) struct SinkSlot {
+    sink: Box<dyn LogSink>,
+    degraded: AtomicBool,
+}
+
+impl SinkSlot {
+    pub(super) fn new(sink: Box<dyn LogSink>) -> Self {
+        Self {
+            sink,
+            degraded: AtomicBool::new(false),
+        }
+    }
+
+    /// Write `line`, reporting only changes between healthy and degraded
+    pub(super) fn write(&self, severity: Severity, line: &str) {
+        match self.sink.write_line(severity, line) {
+            Ok(()) => {
+                if self.degraded.swap(false, Ordering::Relaxed) {
+                    Logger::report(
+                        Severity::Info,
+                        Event::LogSinkRecovered,
+                        &[("sink", self.sink.name())],
+                    );
+                }
+            }
+            Err(e) => {
+                if !self.degraded.swap(true, Ordering::Relaxed) {
+                    Logger::report(
+                        Severity::Warn,
+                        Event::LogSinkDegraded,
+                        &[("sink", self.sink.name()), ("error", &e.to_string())],
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Log file rotated by size and age
+///
+/// On rotation `<path>.N` becomes `<path>.N+1`, `<path>` becomes
+/// `<path>.1`, and files beyond `keep` are deleted.
+pub struct RotatingFileSink {
+    config: FileSinkConfig,
+    state: Mutex<FileState>,
+}
+
+#[derive(Default)]
+struct FileState {
+    file: Option<File>,
+    size: u64,
+    opened_at: Option<Instant>,
+}
+
+impl RotatingFileSink {
+    /// Sink writing to `config.path`, opened on first write
+    pub fn new(config: FileSinkConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(FileState::default()),
+        }
+    }
+
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut name = self.config.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    /// Whether writing `incoming` more bytes would pass `max_bytes`
+    fn full(&self, state: &FileState, incoming: u64) -> bool {
+        state.size > 0 && state.size + incoming > self.config.max_bytes
+    }
+
+    /// Whether the open file has reached `max_age`
+    fn expired(&self, state: &FileState) -> bool {
+        match (self.config.max_age, state.opened_at) {
+            (Some(max_age), Some(opened_at)) => opened_at.elapsed() >= max_age,
+            _ => false,
+        }
+    }
+
+    fn rotate(&self, state: &mut FileState) -> io::Result<()> {
+        state.file = None;
+        if self.config.keep == 0 {
+            remove_if_exists(&self.config.path)?;
+        } else {
+            remove_if_exists(&self.rotated_path(self.config.keep))?;
+            for n in (1..self.config.keep).rev() {
+                rename_if_exists(&self.rotated_path(n), &self.rotated_path(n + 1))?;
+            }
+            rename_if_exists(&self.config.path, &self.rotated_path(1))?;
+        }
+        state.size = 0;
+        state.opened_at = None;
+        Ok(())
+    }
+
+    fn open(&self, state: &mut FileState) -> io::Result<()> {
+        if let Some(parent) = self.config.path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.config.path)?;
+        state.size = file.metadata()?.len();
+        state.opened_at = Some(Instant::now());
+        state.file = Some(file);
+        Ok(())
+    }
+}
+
+impl LogSink for RotatingFileSink {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn write_line(&self, _severity: Severity, line: &str) -> io::Result<()> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| io::Error::other("log file lock poisoned"))?;
+        let incoming = line.len() as u64 + 1;
+
+        if state.file.is_some() && (self.full(&state, incoming) || self.expired(&state)) {
+            self.rotate(&mut state)?;
+        }
+        if state.file.is_none() {
+            self.open(&mut state)?;
+            // A file left by an earlier run may already be full
+            if self.full(&state, incoming) {
+                self.rotate(&mut state)?;
+                self.open(&mut state)?;
+            }
+        }
+
+        let file = state.file.as_mut().expect("log file open");
+        let mut buffer = String::with_capacity(line.len() + 1);
+        buffer.push_str(line);
+        buffer.push('\n');
+        if let Err(e) = file.write_all(buffer.as_bytes()) {
+            // Reopen on the next write in case the file was moved away
+            state.file = None;
+            return Err(e);
+        }
+        state.size += incoming;
+        Ok(())
+    }
+}
+
+pub(super) fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+pub(super) fn rename_if_exists(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Local syslog over its datagram socket
+///
+/// journald listens on the same socket, so this covers both. Messages
+/// use the `daemon` facility and carry the JSON line as their body.
+pub struct SyslogSink {
+    socket_path: PathBuf,
+    #[cfg(unix)]
+    socket: Mutex<Option<std::os::unix::net::UnixDatagram>>,
+}
+
+/// `daemon` syslog facility
+const SYSLOG_FACILITY_DAEMON: u8 = 3;
+
+impl SyslogSink {
+    /// Sink sending to `socket_path`, connected on first write
+    pub fn new(socket_path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: socket_path.into(),
+            #[cfg(unix)]
+            socket: Mutex::new(None),
+        }
+    }
+
+    /// Syslog priority: facility * 8 + level
+    fn priority(severity: Severity) -> u8 {
+        let level = match severity {
+            Severity::Trace => 7,
+            Severity::Info => 6,
+            Severity::Warn => 4,
+            Severity::Error => 3,
+            Severity::Fatal => 2,
+        };
+        SYSLOG_FACILITY_DAEMON * 8 + level
+    }
+
+    /// Message sent for `line`
+    fn format(severity: Severity, line: &str) -> String {
+        format!(
+            "<{}>aerodb[{}]: {}",
+            Self::priority(severity),
+            std::process::id(),
+            line
+        )
+    }
+}
+
+impl LogSink for SyslogSink {
+    fn name(&self) -> &'static str {
+        "syslog"
+    }
+
+    #[cfg(unix)]
+    fn write_line(&self, severity: Severity, line: &str) -> io::Result<()> {
+        use std::os::unix::net::UnixDatagram;
+
+        let mut socket = self
+            .socket
+            .lock()
+            .map_err(|_| io::Error::other("syslog socket lock poisoned"))?;
+        if socket.is_none() {
+            let datagram = UnixDatagram::unbound()?;
+            datagram.connect(&self.socket_path)?;
+            *socket = Some(datagram);
+        }
+        let message = Self::format(severity, line);
+        let result = socket
+            .as_ref()
+            .expect("syslog socket connected")
+            .send(message.as_bytes());
+        if result.is_err() {
+            // Reconnect on the next write in case syslog restarted
+            *socket = None;
+        }
+        result.map(|_| ())
+    }
+
+    #[cfg(not(unix))]
+    fn write_line(&self, _severity: Severity, _line: &str) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("syslog socket {} requires unix", self.socket_path.display()),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn file_sink(dir: &TempDir, max_bytes: u64, keep: usize) -> (RotatingFileSink, PathBuf) {
+        let path = dir.path().join("logs").join("aerodb.log");
+        let sink = RotatingFileSink::new(FileSinkConfig {
+            path: path.clone(),
+            max_bytes,
+            max_age: None,
+            keep,
+        });
+        (sink, path)
+    }
+
+    #[test]
+    fn test_file_sink_rotates_by_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let (sink, path) = file_sink(&temp_dir, 16, 2);
+
+        for line in ["{\"n\":\"1111\"}", "{\"n\":\"2222\"}", "{\"n\":\"3333\"}"] {
+            sink.write_line(Severity::Info, line).unwrap();
+        }
+        sink.write_line(Severity::Info, "{\"n\":\"4444\"}").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"n\":\"4444\"}\n");
+        assert_eq!(
+            fs::read_to_string(sink.rotated_path(1)).unwrap(),
+            "{\"n\":\"3333\"}\n"
+        );
+        assert_eq!(
+            fs::read_to_string(sink.rotated_path(2)).unwrap(),
+            "{\"n\":\"2222\"}\n"
+        );
+        // keep = 2: the oldest file was deleted
+        assert!(!sink.rotated_path(3).exists());
+    }
+
+    #[test]
+    fn test_file_sink_rotates_by_age() {
+        let temp_dir = TempDir::new().unwrap();
+        let (mut sink, path) = file_sink(&temp_dir, u64::MAX, 1);
+        sink.config.max_age = Some(Duration::ZERO);
+
+        sink.write_line(Severity::Info, "first").unwrap();
+        sink.write_line(Severity::Info, "second").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second\n");
+        assert_eq!(fs::read_to_string(sink.rotated_path(1)).unwrap(), "first\n");
+    }
+
+    #[test]
+    fn test_failing_sink_degrades_and_recovers() {
+        let temp_dir = TempDir::new().unwrap();
+        // A directory where the log file should be: every open fails
+        let (sink, path) = file_sink(&temp_dir, u64::MAX, 1);
+        fs::create_dir_all(&path).unwrap();
+        let slot = SinkSlot::new(Box::new(sink));
+
+        slot.write(Severity::Info, "lost");
+        assert!(slot.degraded.load(Ordering::Relaxed));
+
+        fs::remove_dir(&path).unwrap();
+        slot.write(Severity::Info, "kept");
+        assert!(!slot.degraded.load(Ordering::Relaxed));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "kept\n");
+    }
+
+    #[test]
+    fn test_syslog_priority() {
+        assert_eq!(SyslogSink::priority(Severity::Info), 30);
+        assert_eq!(SyslogSink::priority(Severity::Fatal), 26);
+        assert!(SyslogSink::format(Severity::Warn, "{}").starts_with("<28>aerodb["));
+    }
+
+    #[test]
+    fn test_missing_syslog_socket_is_an_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let sink = SyslogSink::new(temp_dir.path().join("no-such-socket"));
+        assert!(sink.write_line(Severity::Info, "{}").is_err());
+    }
+}
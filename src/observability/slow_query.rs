@@ -0,0 +1,233 @@
+//! Slow query log
+//!
+//! Queries taking at least the configured threshold are recorded as
+//! structured JSON entries: the plan chosen, the predicates, the rows
+//! returned and a timing breakdown. Entries are appended to a dedicated
+//! rotating file, if configured, and the most recent are kept in memory for
+//! the DX API.
+//!
+//! A slow query log file that cannot be written degrades like any log sink;
+//! the query itself is never affected.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::executor::ExecutionStats;
+
+use super::logger::Severity;
+use super::sinks::{FileSinkConfig, RotatingFileSink, SinkSlot};
+
+/// Slow queries kept in memory for the DX API
+pub const SLOW_QUERIES_RETAINED: usize = 128;
+
+/// Slow query log settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlowQueryConfig {
+    /// Queries taking at least this long are recorded
+    pub threshold: Duration,
+    /// Dedicated rotating log file (`None`: memory only)
+    pub file: Option<FileSinkConfig>,
+}
+
+impl SlowQueryConfig {
+    /// Record queries taking at least `threshold`, in memory only
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            file: None,
+        }
+    }
+}
+
+/// How the planner chose to run a slow query
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SlowQueryPlan {
+    /// Scan type, e.g. `IndexedRange`
+    pub scan_type: String,
+    /// Index driving the scan
+    pub chosen_index: String,
+}
+
+/// Where a slow query spent its time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SlowQueryTiming {
+    /// Parsing and planning
+    pub planning_micros: u64,
+    /// Reading, validating and filtering documents
+    pub execution_micros: u64,
+    /// Whole query
+    pub total_micros: u64,
+}
+
+/// A query that crossed the threshold, as described by the caller
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SlowQuery {
+    /// Collection queried
+    pub collection: String,
+    /// Schema queried
+    pub schema_id: String,
+    /// Schema version queried
+    pub schema_version: String,
+    /// Chosen plan
+    pub plan: SlowQueryPlan,
+    /// Predicates as `{"field", "op", "value"}` objects
+    pub predicates: Vec<Value>,
+    /// Requested limit
+    pub limit: usize,
+    /// Rows returned
+    pub rows: u64,
+    /// Timing breakdown
+    pub timing: SlowQueryTiming,
+    /// Work done executing the query
+    pub stats: ExecutionStats,
+}
+
+/// A recorded slow query
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SlowQueryEntry {
+    /// Position in the log, starting at 1
+    pub seq: u64,
+    /// When the entry was recorded, in Unix milliseconds
+    pub ts_ms: u64,
+    /// The query
+    #[serde(flatten)]
+    pub query: SlowQuery,
+}
+
+/// Records queries taking at least the configured threshold
+pub struct SlowQueryLog {
+    threshold: Duration,
+    file: Option<SinkSlot>,
+    recent: Mutex<VecDeque<SlowQueryEntry>>,
+    next_seq: AtomicU64,
+}
+
+impl SlowQueryLog {
+    /// Log configured by `config`; its file opens on the first entry
+    pub fn new(config: &SlowQueryConfig) -> Self {
+        Self {
+            threshold: config.threshold,
+            file: config
+                .file
+                .clone()
+                .map(|file| SinkSlot::new(Box::new(RotatingFileSink::new(file)))),
+            recent: Mutex::new(VecDeque::with_capacity(SLOW_QUERIES_RETAINED)),
+            next_seq: AtomicU64::new(1),
+        }
+    }
+
+    /// Duration at which a query counts as slow
+    pub fn threshold(&self) -> Duration {
+        self.threshold
+    }
+
+    /// Whether a query taking `elapsed` should be recorded
+    pub fn is_slow(&self, elapsed: Duration) -> bool {
+        elapsed >= self.threshold
+    }
+
+    /// Record `query`, returning the entry written
+    pub fn record(&self, query: SlowQuery) -> SlowQueryEntry {
+        let entry = SlowQueryEntry {
+            seq: self.next_seq.fetch_add(1, Ordering::Relaxed),
+            ts_ms: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            query,
+        };
+
+        if let Some(file) = &self.file {
+            if let Ok(line) = serde_json::to_string(&entry) {
+                file.write(Severity::Warn, &line);
+            }
+        }
+        if let Ok(mut recent) = self.recent.lock() {
+            if recent.len() == SLOW_QUERIES_RETAINED {
+                recent.pop_front();
+            }
+            recent.push_back(entry.clone());
+        }
+        entry
+    }
+
+    /// Up to `limit` most recent entries, newest first
+    pub fn recent(&self, limit: usize) -> Vec<SlowQueryEntry> {
+        match self.recent.lock() {
+            Ok(recent) => recent.iter().rev().take(limit).cloned().collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn slow_query(rows: u64) -> SlowQuery {
+        SlowQuery {
+            collection: "users".to_string(),
+            schema_id: "users".to_string(),
+            schema_version: "v1".to_string(),
+            plan: SlowQueryPlan {
+                scan_type: "IndexedRange".to_string(),
+                chosen_index: "age".to_string(),
+            },
+            predicates: vec![serde_json::json!({"field": "age", "op": "gte", "value": 30})],
+            limit: 10,
+            rows,
+            timing: SlowQueryTiming {
+                planning_micros: 10,
+                execution_micros: 250_000,
+                total_micros: 250_010,
+            },
+            stats: ExecutionStats::default(),
+        }
+    }
+
+    #[test]
+    fn test_threshold() {
+        let log = SlowQueryLog::new(&SlowQueryConfig::new(Duration::from_millis(100)));
+        assert!(!log.is_slow(Duration::from_millis(99)));
+        assert!(log.is_slow(Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn test_entries_written_to_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("slow_queries.log");
+        let log = SlowQueryLog::new(&SlowQueryConfig {
+            threshold: Duration::ZERO,
+            file: Some(FileSinkConfig::new(&path)),
+        });
+
+        log.record(slow_query(3));
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let entry: Value = serde_json::from_str(contents.trim_end()).unwrap();
+        assert_eq!(entry["seq"], 1);
+        assert_eq!(entry["plan"]["chosen_index"], "age");
+        assert_eq!(entry["rows"], 3);
+        assert_eq!(entry["timing"]["execution_micros"], 250_000);
+    }
+
+    #[test]
+    fn test_recent_is_bounded_and_newest_first() {
+        let log = SlowQueryLog::new(&SlowQueryConfig::new(Duration::ZERO));
+        for rows in 0..(SLOW_QUERIES_RETAINED as u64 + 2) {
+            log.record(slow_query(rows));
+        }
+
+        let recent = log.recent(usize::MAX);
+        assert_eq!(recent.len(), SLOW_QUERIES_RETAINED);
+        assert_eq!(recent[0].query.rows, SLOW_QUERIES_RETAINED as u64 + 1);
+        assert_eq!(log.recent(1)[0].seq, SLOW_QUERIES_RETAINED as u64 + 2);
+    }
+}
@@ -0,0 +1,47 @@
+//! pgwire Configuration
+//!
+//! The listener trusts every client (no password exchange), so it binds
+//! loopback only, like the DX API.
+
+/// Configuration of the PostgreSQL wire-protocol listener
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgWireConfig {
+    /// Whether the listener is started
+    pub enabled: bool,
+    /// Port to listen on (5433, beside a local PostgreSQL on 5432)
+    pub port: u16,
+    /// Bind address (loopback only)
+    pub bind_address: String,
+    /// Connections served at once; further clients are refused
+    pub max_connections: usize,
+}
+
+impl Default for PgWireConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 5433,
+            bind_address: "127.0.0.1".to_string(),
+            max_connections: 16,
+        }
+    }
+}
+
+impl PgWireConfig {
+    /// Get the full bind address with port.
+    pub fn bind_addr(&self) -> String {
+        format!("{}:{}", self.bind_address, self.port)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_disabled_on_loopback() {
+        let config = PgWireConfig::default();
+        assert!(!config.enabled);
+        assert_eq!(config.bind_addr(), "127.0.0.1:5433");
+    }
+}
@@ -0,0 +1,26 @@
+//! # PostgreSQL Wire Protocol
+//!
+//! A read-only listener speaking the PostgreSQL frontend/backend protocol,
+//! so BI tools and `psql` can query AeroDB without a bespoke client.
+//!
+//! Only simple-protocol SELECT statements in a small SQL subset are served
+//! (see `sql`). Each statement translates to a `planner::Query` and runs
+//! through the same planner, bounds checks and read path as an API
+//! `query` request; anything the planner rejects is reported as a SQL
+//! error.
+//!
+//! ## Module Structure
+//!
+//! - `config` - Listener configuration
+//! - `sql` - SQL subset parser
+//! - `protocol` - Message framing
+//! - `server` - Listener and connection handling
+
+pub mod config;
+pub mod protocol;
+pub mod server;
+pub mod sql;
+
+pub use config::PgWireConfig;
+pub use server::{BackendError, PgWireServer, QueryBackend};
+pub use sql::{parse_select, Select, SqlError};
@@ -0,0 +1,290 @@
+//! Wire Protocol
+//!
+//! Framing of PostgreSQL frontend/backend protocol 3.0 messages: the
+//! untyped startup packet, typed frontend messages, and the backend
+//! messages the listener sends. Only the text result format is produced.
+
+use std::collections::HashMap;
+use std::io::{self, Read};
+
+/// Protocol version 3.0
+const PROTOCOL_VERSION: i32 = 196608;
+/// Startup codes that are requests rather than protocol versions
+const SSL_REQUEST: i32 = 80877103;
+const GSSENC_REQUEST: i32 = 80877104;
+const CANCEL_REQUEST: i32 = 80877102;
+
+/// Largest message accepted from a client
+pub const MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+
+/// Type OIDs of result columns
+pub const BOOL_OID: i32 = 16;
+pub const INT8_OID: i32 = 20;
+pub const TEXT_OID: i32 = 25;
+pub const JSON_OID: i32 = 114;
+pub const FLOAT8_OID: i32 = 701;
+
+/// First packet of a connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Startup {
+    /// Client asks for TLS (or GSSAPI encryption) before starting up
+    EncryptionRequest,
+    /// Client asks to cancel a query on another connection
+    CancelRequest,
+    /// Startup parameters (`user`, `database`, ...)
+    Params(HashMap<String, String>),
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn read_i32(reader: &mut impl Read) -> io::Result<i32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(i32::from_be_bytes(buf))
+}
+
+/// Body of a message whose length word (including itself) is `len`
+fn read_body(reader: &mut impl Read, len: i32) -> io::Result<Vec<u8>> {
+    let len = usize::try_from(len)
+        .ok()
+        .and_then(|len| len.checked_sub(4))
+        .filter(|len| *len <= MAX_MESSAGE_LEN)
+        .ok_or_else(|| invalid(format!("invalid message length {}", len)))?;
+    let mut body = vec![0u8; len];
+    reader.read_exact(&mut body)?;
+    Ok(body)
+}
+
+/// Read the untyped startup packet
+pub fn read_startup(reader: &mut impl Read) -> io::Result<Startup> {
+    let len = read_i32(reader)?;
+    let body = read_body(reader, len)?;
+    let code = body
+        .get(..4)
+        .map(|b| i32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| invalid("startup packet too short"))?;
+    match code {
+        SSL_REQUEST | GSSENC_REQUEST => Ok(Startup::EncryptionRequest),
+        CANCEL_REQUEST => Ok(Startup::CancelRequest),
+        PROTOCOL_VERSION => {
+            let mut fields = body[4..]
+                .split(|b| *b == 0)
+                .map(|f| String::from_utf8_lossy(f).into_owned());
+            let mut params = HashMap::new();
+            while let (Some(key), Some(value)) = (fields.next(), fields.next()) {
+                if key.is_empty() {
+                    break;
+                }
+                params.insert(key, value);
+            }
+            Ok(Startup::Params(params))
+        }
+        other => Err(invalid(format!(
+            "unsupported protocol version {}.{}",
+            other >> 16,
+            other & 0xffff
+        ))),
+    }
+}
+
+/// Read a typed frontend message, or `None` at end of stream
+pub fn read_message(reader: &mut impl Read) -> io::Result<Option<(u8, Vec<u8>)>> {
+    let mut tag = [0u8; 1];
+    match reader.read_exact(&mut tag) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = read_i32(reader)?;
+    Ok(Some((tag[0], read_body(reader, len)?)))
+}
+
+/// Query text of a simple Query message body
+pub fn query_text(body: &[u8]) -> io::Result<String> {
+    let text = body.strip_suffix(&[0]).unwrap_or(body);
+    String::from_utf8(text.to_vec()).map_err(|_| invalid("query is not valid UTF-8"))
+}
+
+/// Backend messages buffered for one write
+#[derive(Debug, Default)]
+pub struct Messages {
+    buf: Vec<u8>,
+}
+
+impl Messages {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Buffered bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Drop the buffered bytes once written
+    pub fn clear(&mut self) {
+        self.buf.clear();
+    }
+
+    fn message(&mut self, tag: u8, write_body: impl FnOnce(&mut Vec<u8>)) -> &mut Self {
+        self.buf.push(tag);
+        let start = self.buf.len();
+        self.buf.extend_from_slice(&[0; 4]);
+        write_body(&mut self.buf);
+        let len = (self.buf.len() - start) as i32;
+        self.buf[start..start + 4].copy_from_slice(&len.to_be_bytes());
+        self
+    }
+
+    fn cstr(buf: &mut Vec<u8>, s: &str) {
+        buf.extend(s.bytes().filter(|b| *b != 0));
+        buf.push(0);
+    }
+
+    /// AuthenticationOk
+    pub fn authentication_ok(&mut self) -> &mut Self {
+        self.message(b'R', |b| b.extend_from_slice(&0i32.to_be_bytes()))
+    }
+
+    /// ParameterStatus
+    pub fn parameter_status(&mut self, name: &str, value: &str) -> &mut Self {
+        self.message(b'S', |b| {
+            Self::cstr(b, name);
+            Self::cstr(b, value);
+        })
+    }
+
+    /// BackendKeyData
+    pub fn backend_key_data(&mut self, process_id: i32, secret: i32) -> &mut Self {
+        self.message(b'K', |b| {
+            b.extend_from_slice(&process_id.to_be_bytes());
+            b.extend_from_slice(&secret.to_be_bytes());
+        })
+    }
+
+    /// ReadyForQuery, idle outside a transaction
+    pub fn ready_for_query(&mut self) -> &mut Self {
+        self.message(b'Z', |b| b.push(b'I'))
+    }
+
+    /// RowDescription of text-format `columns` (name, type OID)
+    pub fn row_description(&mut self, columns: &[(String, i32)]) -> &mut Self {
+        self.message(b'T', |b| {
+            b.extend_from_slice(&(columns.len() as i16).to_be_bytes());
+            for (name, oid) in columns {
+                Self::cstr(b, name);
+                b.extend_from_slice(&0i32.to_be_bytes()); // table OID
+                b.extend_from_slice(&0i16.to_be_bytes()); // column number
+                b.extend_from_slice(&oid.to_be_bytes());
+                b.extend_from_slice(&(-1i16).to_be_bytes()); // type size
+                b.extend_from_slice(&(-1i32).to_be_bytes()); // type modifier
+                b.extend_from_slice(&0i16.to_be_bytes()); // text format
+            }
+        })
+    }
+
+    /// DataRow of text values, `None` for NULL
+    pub fn data_row(&mut self, values: &[Option<String>]) -> &mut Self {
+        self.message(b'D', |b| {
+            b.extend_from_slice(&(values.len() as i16).to_be_bytes());
+            for value in values {
+                match value {
+                    Some(text) => {
+                        b.extend_from_slice(&(text.len() as i32).to_be_bytes());
+                        b.extend_from_slice(text.as_bytes());
+                    }
+                    None => b.extend_from_slice(&(-1i32).to_be_bytes()),
+                }
+            }
+        })
+    }
+
+    /// CommandComplete
+    pub fn command_complete(&mut self, tag: &str) -> &mut Self {
+        self.message(b'C', |b| Self::cstr(b, tag))
+    }
+
+    /// EmptyQueryResponse
+    pub fn empty_query(&mut self) -> &mut Self {
+        self.message(b'I', |_| {})
+    }
+
+    /// ErrorResponse with severity ERROR
+    pub fn error(&mut self, sqlstate: &str, message: &str) -> &mut Self {
+        self.message(b'E', |b| {
+            for (field, value) in [(b'S', "ERROR"), (b'V', "ERROR"), (b'C', sqlstate)] {
+                b.push(field);
+                Self::cstr(b, value);
+            }
+            b.push(b'M');
+            Self::cstr(b, message);
+            b.push(0);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn startup_packet(code: i32, params: &[(&str, &str)]) -> Vec<u8> {
+        let mut body = code.to_be_bytes().to_vec();
+        for (key, value) in params {
+            body.extend_from_slice(key.as_bytes());
+            body.push(0);
+            body.extend_from_slice(value.as_bytes());
+            body.push(0);
+        }
+        body.push(0);
+        let mut packet = ((body.len() + 4) as i32).to_be_bytes().to_vec();
+        packet.extend(body);
+        packet
+    }
+
+    #[test]
+    fn test_read_startup() {
+        let packet = startup_packet(PROTOCOL_VERSION, &[("user", "bi"), ("database", "aero")]);
+        match read_startup(&mut packet.as_slice()).unwrap() {
+            Startup::Params(params) => {
+                assert_eq!(params["user"], "bi");
+                assert_eq!(params["database"], "aero");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let ssl = [0, 0, 0, 8, 0x04, 0xd2, 0x16, 0x2f];
+        assert_eq!(
+            read_startup(&mut ssl.as_slice()).unwrap(),
+            Startup::EncryptionRequest
+        );
+        assert!(read_startup(&mut startup_packet(131072, &[]).as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_message_framing() {
+        let mut messages = Messages::new();
+        messages.command_complete("SELECT 1");
+        assert_eq!(messages.as_bytes(), b"C\0\0\0\x0dSELECT 1\0");
+
+        let mut input = messages.as_bytes();
+        let (tag, body) = read_message(&mut input).unwrap().unwrap();
+        assert_eq!(tag, b'C');
+        assert_eq!(body, b"SELECT 1\0");
+        assert!(read_message(&mut input).unwrap().is_none());
+
+        let oversized = [b'Q', 0x7f, 0xff, 0xff, 0xff];
+        assert!(read_message(&mut oversized.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_data_row_null() {
+        let mut messages = Messages::new();
+        messages.data_row(&[Some("a".to_string()), None]);
+        assert_eq!(
+            messages.as_bytes(),
+            b"D\0\0\0\x0f\0\x02\0\0\0\x01a\xff\xff\xff\xff"
+        );
+    }
+}
@@ -0,0 +1,540 @@
+//! pgwire Server
+//!
+//! Accepts PostgreSQL clients on a TCP listener and answers simple-protocol
+//! SELECT statements by running the translated planner query on a
+//! `QueryBackend`. Each connection is served on its own thread; clients
+//! authenticate by trust, so the listener must only be reachable locally.
+//!
+//! Result columns are typed from the values returned: booleans as `bool`,
+//! integers as `int8`, other numbers as `float8`, objects and arrays as
+//! `json`, everything else (and mixed columns) as `text`.
+
+use std::io::{self, BufReader, BufWriter, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use serde_json::Value;
+
+use super::config::PgWireConfig;
+use super::protocol::{
+    query_text, read_message, read_startup, Messages, Startup, BOOL_OID, FLOAT8_OID, INT8_OID,
+    JSON_OID, TEXT_OID,
+};
+use super::sql::{parse_select, Select};
+use crate::observability::{Event, Logger, Severity};
+use crate::planner::Query;
+
+/// Server version reported to clients; drivers gate features on it
+const SERVER_VERSION: &str = "14.0 (AeroDB)";
+
+/// Rows written between flushes while streaming a result
+const FLUSH_ROWS: usize = 256;
+
+/// A query that failed in the backend
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendError {
+    /// AeroDB error code, e.g. `AERO_QUERY_UNBOUNDED`
+    pub code: String,
+    pub message: String,
+}
+
+impl BackendError {
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            message: message.into(),
+        }
+    }
+
+    /// SQLSTATE reported for the error
+    pub fn sqlstate(&self) -> &'static str {
+        match self.code.as_str() {
+            "AERO_UNKNOWN_SCHEMA" | "AERO_UNKNOWN_SCHEMA_VERSION" => "42P01",
+            "AERO_OVERLOADED" => "53000",
+            "AERO_MAINTENANCE_MODE" => "57P03",
+            "AERO_SNAPSHOT_TOO_OLD" => "72000",
+            code if code.starts_with("AERO_QUERY_") || code == "AERO_INVALID_REQUEST" => "42000",
+            _ => "XX000",
+        }
+    }
+}
+
+/// Runs planner queries for the listener
+pub trait QueryBackend: Send + Sync {
+    /// Documents matching `query`, in result order
+    fn query(&self, query: &Query) -> Result<Vec<Value>, BackendError>;
+}
+
+/// PostgreSQL wire-protocol listener
+pub struct PgWireServer {
+    config: PgWireConfig,
+    backend: Arc<dyn QueryBackend>,
+}
+
+impl PgWireServer {
+    pub fn new(config: PgWireConfig, backend: Arc<dyn QueryBackend>) -> Self {
+        Self { config, backend }
+    }
+
+    /// Bind the listener and serve connections on background threads
+    ///
+    /// Returns the bound address.
+    pub fn spawn(self) -> io::Result<SocketAddr> {
+        let listener = TcpListener::bind(self.config.bind_addr())?;
+        let addr = listener.local_addr()?;
+        let active = Arc::new(AtomicUsize::new(0));
+        let next_id = AtomicI32::new(1);
+        thread::Builder::new()
+            .name("aerodb-pgwire".to_string())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    let Ok(stream) = stream else { continue };
+                    let process_id = next_id.fetch_add(1, Ordering::Relaxed);
+                    if active.fetch_add(1, Ordering::SeqCst) >= self.config.max_connections {
+                        active.fetch_sub(1, Ordering::SeqCst);
+                        refuse(stream);
+                        continue;
+                    }
+                    let connection = Connection {
+                        backend: Arc::clone(&self.backend),
+                        process_id,
+                    };
+                    let finished = Arc::clone(&active);
+                    let spawned = thread::Builder::new()
+                        .name(format!("aerodb-pgwire-{}", process_id))
+                        .spawn(move || {
+                            if let Err(e) = connection.serve(stream) {
+                                Logger::log_stderr(
+                                    Severity::Warn,
+                                    Event::PgWireConnectionFailed.as_str(),
+                                    &[("error", &e.to_string())],
+                                );
+                            }
+                            finished.fetch_sub(1, Ordering::SeqCst);
+                        });
+                    if spawned.is_err() {
+                        active.fetch_sub(1, Ordering::SeqCst);
+                    }
+                }
+            })?;
+        Ok(addr)
+    }
+}
+
+/// Reject a client over the connection limit
+///
+/// The error is sent without waiting for the startup packet, so a client
+/// that never sends one cannot stall the accept loop.
+fn refuse(mut stream: TcpStream) {
+    let mut messages = Messages::new();
+    messages.error(
+        "53300",
+        "too many connections to the AeroDB pgwire listener",
+    );
+    let _ = stream.write_all(messages.as_bytes());
+}
+
+/// One client connection
+struct Connection {
+    backend: Arc<dyn QueryBackend>,
+    process_id: i32,
+}
+
+impl Connection {
+    fn serve(&self, stream: TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = BufWriter::new(stream);
+        let mut messages = Messages::new();
+
+        loop {
+            match read_startup(&mut reader)? {
+                Startup::EncryptionRequest => {
+                    // Encryption is not offered; the client continues in
+                    // plaintext or disconnects
+                    writer.write_all(b"N")?;
+                    writer.flush()?;
+                }
+                Startup::CancelRequest => return Ok(()),
+                Startup::Params(_) => break,
+            }
+        }
+
+        messages.authentication_ok();
+        for (name, value) in [
+            ("server_version", SERVER_VERSION),
+            ("server_encoding", "UTF8"),
+            ("client_encoding", "UTF8"),
+            ("DateStyle", "ISO, MDY"),
+            ("integer_datetimes", "on"),
+            ("standard_conforming_strings", "on"),
+        ] {
+            messages.parameter_status(name, value);
+        }
+        messages.backend_key_data(self.process_id, 0);
+        messages.ready_for_query();
+        self.send(&mut writer, &mut messages)?;
+
+        // After an error in the extended protocol, messages are discarded
+        // up to the next Sync
+        let mut discarding = false;
+        while let Some((tag, body)) = read_message(&mut reader)? {
+            match tag {
+                b'Q' => {
+                    let sql = query_text(&body)?;
+                    self.simple_query(&sql, &mut writer, &mut messages)?;
+                    messages.ready_for_query();
+                    self.send(&mut writer, &mut messages)?;
+                }
+                b'X' => return Ok(()),
+                b'S' => {
+                    discarding = false;
+                    messages.ready_for_query();
+                    self.send(&mut writer, &mut messages)?;
+                }
+                b'H' => writer.flush()?,
+                b'P' | b'B' | b'D' | b'E' | b'C' => {
+                    if !discarding {
+                        discarding = true;
+                        messages.error(
+                            "0A000",
+                            "the extended query protocol is not supported; use simple queries",
+                        );
+                        self.send(&mut writer, &mut messages)?;
+                    }
+                }
+                other => {
+                    messages.error(
+                        "08P01",
+                        &format!("unexpected message type '{}'", other as char),
+                    );
+                    self.send(&mut writer, &mut messages)?;
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn send(&self, writer: &mut BufWriter<TcpStream>, messages: &mut Messages) -> io::Result<()> {
+        writer.write_all(messages.as_bytes())?;
+        writer.flush()?;
+        messages.clear();
+        Ok(())
+    }
+
+    /// Answer one Query message, up to but excluding ReadyForQuery
+    fn simple_query(
+        &self,
+        sql: &str,
+        writer: &mut BufWriter<TcpStream>,
+        messages: &mut Messages,
+    ) -> io::Result<()> {
+        let statement = sql.trim().trim_end_matches(';').trim();
+        if statement.is_empty() {
+            messages.empty_query();
+            return Ok(());
+        }
+        // Drivers set session parameters on connect; none affect results
+        if statement
+            .split_whitespace()
+            .next()
+            .is_some_and(|word| word.eq_ignore_ascii_case("set"))
+        {
+            messages.command_complete("SET");
+            return Ok(());
+        }
+
+        let Select { columns, query } = match parse_select(sql) {
+            Ok(select) => select,
+            Err(e) => {
+                messages.error(e.sqlstate(), &e.to_string());
+                return Ok(());
+            }
+        };
+        let documents = match self.backend.query(&query) {
+            Ok(documents) => documents,
+            Err(e) => {
+                messages.error(e.sqlstate(), &format!("{}: {}", e.code, e.message));
+                return Ok(());
+            }
+        };
+
+        let columns = columns.unwrap_or_else(|| column_union(&documents));
+        let description: Vec<(String, i32)> = columns
+            .iter()
+            .map(|c| (c.clone(), column_type(&documents, c)))
+            .collect();
+        messages.row_description(&description);
+        for (i, document) in documents.iter().enumerate() {
+            let row: Vec<Option<String>> = columns
+                .iter()
+                .map(|c| document.get(c).and_then(text_value))
+                .collect();
+            messages.data_row(&row);
+            if (i + 1) % FLUSH_ROWS == 0 {
+                writer.write_all(messages.as_bytes())?;
+                messages.clear();
+            }
+        }
+        messages.command_complete(&format!("SELECT {}", documents.len()));
+        Ok(())
+    }
+}
+
+/// Fields of `documents` in first-seen order, for `SELECT *`
+fn column_union(documents: &[Value]) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+    for document in documents {
+        if let Some(fields) = document.as_object() {
+            for field in fields.keys() {
+                if !columns.contains(field) {
+                    columns.push(field.clone());
+                }
+            }
+        }
+    }
+    columns
+}
+
+/// Type OID of `column` across `documents`
+fn column_type(documents: &[Value], column: &str) -> i32 {
+    let mut oid = None;
+    for value in documents.iter().filter_map(|d| d.get(column)) {
+        let value_oid = match value {
+            Value::Null => continue,
+            Value::Bool(_) => BOOL_OID,
+            Value::Number(n) if n.is_i64() => INT8_OID,
+            Value::Number(_) => FLOAT8_OID,
+            Value::String(_) => TEXT_OID,
+            Value::Array(_) | Value::Object(_) => JSON_OID,
+        };
+        oid = match (oid, value_oid) {
+            (None, t) => Some(t),
+            (Some(a), b) if a == b => Some(a),
+            (Some(INT8_OID | FLOAT8_OID), INT8_OID | FLOAT8_OID) => Some(FLOAT8_OID),
+            _ => return TEXT_OID,
+        };
+    }
+    oid.unwrap_or(TEXT_OID)
+}
+
+/// Text format of a value, `None` for NULL
+fn text_value(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::Bool(b) => Some(if *b { "t" } else { "f" }.to_string()),
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Read;
+    use std::sync::Mutex;
+
+    use crate::planner::{Predicate, SortSpec};
+
+    /// Backend serving fixed documents and recording the queries it ran
+    struct FakeBackend {
+        documents: Vec<Value>,
+        queries: Mutex<Vec<Query>>,
+    }
+
+    impl QueryBackend for FakeBackend {
+        fn query(&self, query: &Query) -> Result<Vec<Value>, BackendError> {
+            self.queries.lock().unwrap().push(query.clone());
+            if query.schema_id == "missing" {
+                return Err(BackendError::new(
+                    "AERO_UNKNOWN_SCHEMA",
+                    "no schema missing",
+                ));
+            }
+            Ok(self.documents.clone())
+        }
+    }
+
+    fn spawn(documents: Vec<Value>) -> (SocketAddr, Arc<FakeBackend>) {
+        let backend = Arc::new(FakeBackend {
+            documents,
+            queries: Mutex::new(Vec::new()),
+        });
+        let config = PgWireConfig {
+            enabled: true,
+            port: 0,
+            ..PgWireConfig::default()
+        };
+        let addr = PgWireServer::new(config, backend.clone()).spawn().unwrap();
+        (addr, backend)
+    }
+
+    /// Minimal frontend: sends protocol messages, reads typed replies
+    struct Client {
+        stream: TcpStream,
+    }
+
+    impl Client {
+        fn connect(addr: SocketAddr) -> (Self, Vec<(u8, Vec<u8>)>) {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream
+                .write_all(&[0, 0, 0, 8, 0x04, 0xd2, 0x16, 0x2f])
+                .unwrap();
+            let mut reply = [0u8; 1];
+            stream.read_exact(&mut reply).unwrap();
+            assert_eq!(&reply, b"N");
+
+            let mut body = 196608i32.to_be_bytes().to_vec();
+            body.extend_from_slice(b"user\0bi\0\0");
+            let mut packet = ((body.len() + 4) as i32).to_be_bytes().to_vec();
+            packet.extend(body);
+            stream.write_all(&packet).unwrap();
+            let mut client = Self { stream };
+            let startup = client.until_ready();
+            (client, startup)
+        }
+
+        fn send(&mut self, tag: u8, body: &[u8]) {
+            let mut message = vec![tag];
+            message.extend_from_slice(&((body.len() + 4) as i32).to_be_bytes());
+            message.extend_from_slice(body);
+            self.stream.write_all(&message).unwrap();
+        }
+
+        fn query(&mut self, sql: &str) -> Vec<(u8, Vec<u8>)> {
+            self.send(b'Q', format!("{}\0", sql).as_bytes());
+            self.until_ready()
+        }
+
+        fn until_ready(&mut self) -> Vec<(u8, Vec<u8>)> {
+            let mut replies = Vec::new();
+            loop {
+                let (tag, body) = read_message(&mut self.stream).unwrap().unwrap();
+                replies.push((tag, body));
+                if tag == b'Z' {
+                    return replies;
+                }
+            }
+        }
+    }
+
+    fn tags(replies: &[(u8, Vec<u8>)]) -> String {
+        replies.iter().map(|(tag, _)| *tag as char).collect()
+    }
+
+    fn column_names(description: &[u8]) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut rest = &description[2..];
+        while let Some(end) = rest.iter().position(|b| *b == 0) {
+            names.push(String::from_utf8(rest[..end].to_vec()).unwrap());
+            rest = &rest[end + 19..];
+        }
+        names
+    }
+
+    fn row_values(row: &[u8]) -> Vec<Option<String>> {
+        let mut values = Vec::new();
+        let mut rest = &row[2..];
+        while rest.len() >= 4 {
+            let len = i32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]);
+            rest = &rest[4..];
+            if len < 0 {
+                values.push(None);
+            } else {
+                let (value, after) = rest.split_at(len as usize);
+                values.push(Some(String::from_utf8(value.to_vec()).unwrap()));
+                rest = after;
+            }
+        }
+        values
+    }
+
+    fn error_code(replies: &[(u8, Vec<u8>)]) -> String {
+        let (_, body) = replies.iter().find(|(tag, _)| *tag == b'E').unwrap();
+        let start = body.windows(1).position(|w| w == b"C").unwrap() + 1;
+        String::from_utf8(body[start..start + 5].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_select_streams_rows() {
+        let (addr, backend) = spawn(vec![
+            json!({"_id": "u1", "name": "Ada", "age": 36, "tags": ["x"]}),
+            json!({"_id": "u2", "name": "Grace", "age": 45.5, "active": true}),
+        ]);
+        let (mut client, startup) = Client::connect(addr);
+        assert_eq!(tags(&startup), "RSSSSSSKZ");
+
+        let replies =
+            client.query("SELECT * FROM v1.users WHERE age > 30 ORDER BY age DESC LIMIT 5");
+        assert_eq!(tags(&replies), "TDDCZ");
+
+        assert_eq!(
+            column_names(&replies[0].1),
+            vec!["_id", "age", "name", "tags", "active"]
+        );
+        let text = |s: &str| Some(s.to_string());
+        assert_eq!(
+            row_values(&replies[1].1),
+            vec![text("u1"), text("36"), text("Ada"), text("[\"x\"]"), None]
+        );
+        assert_eq!(
+            row_values(&replies[2].1),
+            vec![text("u2"), text("45.5"), text("Grace"), None, text("t")]
+        );
+        assert_eq!(replies[3].1, b"SELECT 2\0");
+
+        let queries = backend.queries.lock().unwrap();
+        assert_eq!(queries[0].schema_version.as_deref(), Some("v1"));
+        assert_eq!(queries[0].predicates, vec![Predicate::gt("age", json!(30))]);
+        assert_eq!(queries[0].sort, vec![SortSpec::desc("age")]);
+        assert_eq!(queries[0].limit, Some(5));
+    }
+
+    #[test]
+    fn test_errors_keep_connection_usable() {
+        let (addr, _) = spawn(vec![json!({"name": "Ada"})]);
+        let (mut client, _) = Client::connect(addr);
+
+        let replies = client.query("DELETE FROM v1.users");
+        assert_eq!(tags(&replies), "EZ");
+        assert_eq!(error_code(&replies), "0A000");
+
+        let replies = client.query("SELECT * FROM v1.missing LIMIT 1");
+        assert_eq!(error_code(&replies), "42P01");
+
+        // Extended protocol: one error, then silence until Sync
+        client.send(b'P', b"\0SELECT 1\0\0\0");
+        client.send(b'B', b"\0\0\0\0\0\0\0\0");
+        client.send(b'S', b"");
+        let replies = client.until_ready();
+        assert_eq!(tags(&replies), "EZ");
+
+        assert_eq!(tags(&client.query("SET extra_float_digits = 3")), "CZ");
+        assert_eq!(tags(&client.query("")), "IZ");
+        assert_eq!(
+            tags(&client.query("SELECT name FROM v1.users LIMIT 1")),
+            "TDCZ"
+        );
+        client.send(b'X', b"");
+    }
+
+    #[test]
+    fn test_column_types() {
+        let documents = vec![
+            json!({"n": 1, "f": 1, "s": "a", "mixed": 1, "b": true, "j": {"k": 1}}),
+            json!({"n": null, "f": 2.5, "s": "b", "mixed": "x"}),
+        ];
+        assert_eq!(column_type(&documents, "n"), INT8_OID);
+        assert_eq!(column_type(&documents, "f"), FLOAT8_OID);
+        assert_eq!(column_type(&documents, "s"), TEXT_OID);
+        assert_eq!(column_type(&documents, "mixed"), TEXT_OID);
+        assert_eq!(column_type(&documents, "b"), BOOL_OID);
+        assert_eq!(column_type(&documents, "j"), JSON_OID);
+        assert_eq!(column_type(&documents, "absent"), TEXT_OID);
+        assert_eq!(text_value(&json!(false)).as_deref(), Some("f"));
+        assert_eq!(text_value(&json!({"k": 1})).as_deref(), Some("{\"k\":1}"));
+    }
+}
@@ -0,0 +1,572 @@
+//! SQL subset
+//!
+//! Parses the one statement form the pgwire layer serves:
+//!
+//! ```text
+//! SELECT * | column [, column ...]
+//! FROM version.schema_id
+//! [WHERE condition [AND condition ...]]
+//! [ORDER BY column [ASC | DESC] [, ...]]
+//! LIMIT n
+//! ```
+//!
+//! A table is a schema, qualified by the schema version to read, so
+//! `v1.users` reads documents of schema `users` version `v1`. Conditions
+//! are `=`, `<>`/`!=`, `<`, `<=`, `>`, `>=` against a literal, `IN (...)`,
+//! `IS [NOT] NULL` and `LIKE 'prefix%'`, each mapping to one planner
+//! predicate. `LIMIT` is required: AeroDB rejects unbounded queries.
+//!
+//! Unquoted identifiers fold to lower case as in PostgreSQL; double-quote
+//! a field name to keep its case.
+
+use std::fmt;
+
+use serde_json::{Number, Value};
+
+use crate::planner::{Predicate, Query, SortSpec};
+
+/// Collection SQL queries read
+pub const COLLECTION: &str = "default";
+
+/// A parsed SELECT
+#[derive(Debug, Clone)]
+pub struct Select {
+    /// Selected columns, or `None` for `*`
+    pub columns: Option<Vec<String>>,
+    /// Planner query the statement translates to
+    pub query: Query,
+}
+
+/// SQL that cannot be served
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SqlError {
+    /// Not valid in the grammar above
+    Syntax(String),
+    /// Valid SQL outside the supported subset
+    Unsupported(String),
+}
+
+impl SqlError {
+    /// PostgreSQL SQLSTATE of the error
+    pub fn sqlstate(&self) -> &'static str {
+        match self {
+            SqlError::Syntax(_) => "42601",
+            SqlError::Unsupported(_) => "0A000",
+        }
+    }
+}
+
+impl fmt::Display for SqlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SqlError::Syntax(message) => write!(f, "syntax error: {}", message),
+            SqlError::Unsupported(message) => write!(f, "not supported: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SqlError {}
+
+type SqlResult<T> = Result<T, SqlError>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Identifier or keyword; `quoted` identifiers are never keywords
+    Ident {
+        name: String,
+        quoted: bool,
+    },
+    Str(String),
+    Number(Number),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: &[&str] = &[
+    "<>", "!=", "<=", ">=", "*", ",", "(", ")", ".", "=", "<", ">", ";",
+];
+
+fn tokenize(sql: &str) -> SqlResult<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = sql;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if c == '\'' || c == '"' {
+            let (text, after) = quoted(rest, c)?;
+            tokens.push(if c == '\'' {
+                Token::Str(text)
+            } else {
+                Token::Ident {
+                    name: text,
+                    quoted: true,
+                }
+            });
+            rest = after;
+        } else if c.is_ascii_digit()
+            || (c == '-' && rest[1..].starts_with(|d: char| d.is_ascii_digit()))
+        {
+            let end = rest[1..]
+                .find(|d: char| !(d.is_ascii_digit() || d == '.' || d == 'e' || d == 'E'))
+                .map_or(rest.len(), |i| i + 1);
+            tokens.push(Token::Number(number(&rest[..end])?));
+            rest = &rest[end..];
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest
+                .find(|d: char| !(d.is_alphanumeric() || d == '_' || d == '$'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident {
+                name: rest[..end].to_lowercase(),
+                quoted: false,
+            });
+            rest = &rest[end..];
+        } else if let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else {
+            return Err(SqlError::Syntax(format!("unexpected character '{}'", c)));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Text of the literal opening `input`, with doubled quotes unescaped
+fn quoted(input: &str, quote: char) -> SqlResult<(String, &str)> {
+    let mut text = String::new();
+    let mut chars = input.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        if c != quote {
+            text.push(c);
+        } else if chars.peek().is_some_and(|&(_, next)| next == quote) {
+            text.push(quote);
+            chars.next();
+        } else {
+            return Ok((text, &input[i + 1..]));
+        }
+    }
+    Err(SqlError::Syntax(format!("unterminated {} literal", quote)))
+}
+
+fn number(text: &str) -> SqlResult<Number> {
+    let invalid = || SqlError::Syntax(format!("invalid number {}", text));
+    if let Ok(n) = text.parse::<i64>() {
+        return Ok(n.into());
+    }
+    text.parse::<f64>()
+        .ok()
+        .and_then(Number::from_f64)
+        .ok_or_else(invalid)
+}
+
+/// Parse a SELECT in the supported subset
+pub fn parse_select(sql: &str) -> SqlResult<Select> {
+    let mut parser = Parser {
+        tokens: tokenize(sql)?,
+        pos: 0,
+    };
+    let select = parser.select()?;
+    parser.eat_symbol(";");
+    match parser.peek() {
+        None => Ok(select),
+        Some(token) => Err(SqlError::Syntax(format!(
+            "unexpected {} after statement",
+            describe(token)
+        ))),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident { name, quoted: false }) if name == keyword)
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> SqlResult<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.expected(&keyword.to_uppercase()))
+        }
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> SqlResult<()> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.expected(&format!("'{}'", symbol)))
+        }
+    }
+
+    fn expected(&self, what: &str) -> SqlError {
+        SqlError::Syntax(match self.peek() {
+            Some(token) => format!("expected {}, found {}", what, describe(token)),
+            None => format!("expected {}, found end of statement", what),
+        })
+    }
+
+    fn identifier(&mut self) -> SqlResult<String> {
+        match self.peek() {
+            Some(Token::Ident { name, quoted }) if *quoted || !is_reserved(name) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => Err(self.expected("an identifier")),
+        }
+    }
+
+    fn select(&mut self) -> SqlResult<Select> {
+        if !self.is_keyword("select") {
+            return Err(match self.peek() {
+                Some(Token::Ident {
+                    name,
+                    quoted: false,
+                }) => SqlError::Unsupported(format!(
+                    "{} statements; only SELECT is served",
+                    name.to_uppercase()
+                )),
+                _ => self.expected("SELECT"),
+            });
+        }
+        self.pos += 1;
+        if self.is_keyword("distinct") {
+            return Err(SqlError::Unsupported("SELECT DISTINCT".to_string()));
+        }
+
+        let columns = if self.eat_symbol("*") {
+            None
+        } else {
+            let mut columns = vec![self.column()?];
+            while self.eat_symbol(",") {
+                columns.push(self.column()?);
+            }
+            Some(columns)
+        };
+
+        self.expect_keyword("from")?;
+        let first = self.identifier()?;
+        let (schema_version, schema_id) = if self.eat_symbol(".") {
+            (first, self.identifier()?)
+        } else {
+            return Err(SqlError::Syntax(format!(
+                "qualify table {} with the schema version to read, e.g. v1.{}",
+                first, first
+            )));
+        };
+        if self.eat_symbol(",") || self.is_keyword("join") {
+            return Err(SqlError::Unsupported("joins".to_string()));
+        }
+
+        let mut query = Query::new(COLLECTION, schema_id).with_schema_version(schema_version);
+        if self.eat_keyword("where") {
+            query = query.with_predicate(self.condition()?);
+            while self.eat_keyword("and") {
+                query = query.with_predicate(self.condition()?);
+            }
+            if self.is_keyword("or") {
+                return Err(SqlError::Unsupported(
+                    "OR; conditions combine with AND only".to_string(),
+                ));
+            }
+        }
+        if self.is_keyword("group") {
+            return Err(SqlError::Unsupported("GROUP BY".to_string()));
+        }
+        if self.eat_keyword("order") {
+            self.expect_keyword("by")?;
+            loop {
+                let field = self.identifier()?;
+                let sort = if self.eat_keyword("desc") {
+                    SortSpec::desc(field)
+                } else {
+                    self.eat_keyword("asc");
+                    SortSpec::asc(field)
+                };
+                query = query.with_sort(sort);
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+        }
+        if !self.eat_keyword("limit") {
+            return Err(match self.peek() {
+                None | Some(Token::Symbol(";")) => SqlError::Unsupported(
+                    "queries without LIMIT; AeroDB rejects unbounded queries".to_string(),
+                ),
+                _ => self.expected("LIMIT"),
+            });
+        }
+        match self.next() {
+            Some(Token::Number(n)) if n.as_u64().is_some() => {
+                query = query.with_limit(n.as_u64().unwrap_or_default());
+            }
+            _ => {
+                self.pos -= 1;
+                return Err(self.expected("a non-negative integer LIMIT"));
+            }
+        }
+        if self.is_keyword("offset") {
+            return Err(SqlError::Unsupported("OFFSET".to_string()));
+        }
+
+        Ok(Select { columns, query })
+    }
+
+    fn column(&mut self) -> SqlResult<String> {
+        let column = self.identifier()?;
+        if self.eat_symbol("(") {
+            return Err(SqlError::Unsupported(format!("function {}()", column)));
+        }
+        Ok(column)
+    }
+
+    fn condition(&mut self) -> SqlResult<Predicate> {
+        let field = self.identifier()?;
+        if self.eat_keyword("is") {
+            let negated = self.eat_keyword("not");
+            self.expect_keyword("null")?;
+            return Ok(Predicate::exists(field, negated));
+        }
+        if self.eat_keyword("in") {
+            self.expect_symbol("(")?;
+            let mut values = vec![self.literal()?];
+            while self.eat_symbol(",") {
+                values.push(self.literal()?);
+            }
+            self.expect_symbol(")")?;
+            return Ok(Predicate::in_values(field, values));
+        }
+        if self.eat_keyword("like") {
+            return match self.next() {
+                Some(Token::Str(pattern)) => like(field, &pattern),
+                _ => {
+                    self.pos -= 1;
+                    Err(self.expected("a LIKE pattern string"))
+                }
+            };
+        }
+        if self.is_keyword("not") {
+            return Err(SqlError::Unsupported("NOT in conditions".to_string()));
+        }
+
+        let op = match self.next() {
+            Some(Token::Symbol(op)) if ["=", "<>", "!=", "<", "<=", ">", ">="].contains(&op) => op,
+            _ => {
+                self.pos -= 1;
+                return Err(self.expected("a comparison operator"));
+            }
+        };
+        let value = self.literal()?;
+        if value.is_null() {
+            return Err(SqlError::Syntax(format!(
+                "comparison with NULL never matches; use {} IS NULL",
+                field
+            )));
+        }
+        Ok(match op {
+            "=" => Predicate::eq(field, value),
+            "<>" | "!=" => Predicate::ne(field, value),
+            "<" => Predicate::lt(field, value),
+            "<=" => Predicate::lte(field, value),
+            ">" => Predicate::gt(field, value),
+            _ => Predicate::gte(field, value),
+        })
+    }
+
+    fn literal(&mut self) -> SqlResult<Value> {
+        let value = match self.peek() {
+            Some(Token::Str(s)) => Value::String(s.clone()),
+            Some(Token::Number(n)) => Value::Number(n.clone()),
+            Some(Token::Ident {
+                name,
+                quoted: false,
+            }) => match name.as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                "null" => Value::Null,
+                _ => return Err(self.expected("a literal")),
+            },
+            _ => return Err(self.expected("a literal")),
+        };
+        self.pos += 1;
+        Ok(value)
+    }
+}
+
+/// Predicate for `field LIKE pattern`: a pattern without wildcards is an
+/// equality, one ending in a single `%` is a prefix
+fn like(field: String, pattern: &str) -> SqlResult<Predicate> {
+    let (prefix, wildcard) = match pattern.strip_suffix('%') {
+        Some(prefix) => (prefix, true),
+        None => (pattern, false),
+    };
+    if prefix.contains(['%', '_', '\\']) {
+        return Err(SqlError::Unsupported(format!(
+            "LIKE pattern '{}'; only 'prefix%' patterns are served",
+            pattern
+        )));
+    }
+    Ok(if wildcard {
+        Predicate::prefix(field, prefix)
+    } else {
+        Predicate::eq(field, Value::String(prefix.to_string()))
+    })
+}
+
+fn is_reserved(word: &str) -> bool {
+    matches!(
+        word,
+        "select"
+            | "from"
+            | "where"
+            | "and"
+            | "or"
+            | "not"
+            | "order"
+            | "by"
+            | "limit"
+            | "offset"
+            | "group"
+            | "join"
+            | "is"
+            | "in"
+            | "like"
+            | "null"
+            | "true"
+            | "false"
+    )
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Ident { name, .. } => format!("'{}'", name),
+        Token::Str(s) => format!("string '{}'", s),
+        Token::Number(n) => format!("number {}", n),
+        Token::Symbol(s) => format!("'{}'", s),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::planner::FilterOp;
+    use serde_json::json;
+
+    #[test]
+    fn test_select_translates_to_query() {
+        let select = parse_select(
+            "SELECT name, \"createdAt\" FROM v1.users \
+             WHERE age >= 18 AND city IN ('Oslo', 'Rome') AND email IS NOT NULL \
+             AND name LIKE 'A%' AND score <> -1.5 \
+             ORDER BY age DESC, name LIMIT 10;",
+        )
+        .unwrap();
+
+        assert_eq!(
+            select.columns,
+            Some(vec!["name".to_string(), "createdAt".to_string()])
+        );
+        let query = select.query;
+        assert_eq!(query.schema_id, "users");
+        assert_eq!(query.schema_version.as_deref(), Some("v1"));
+        assert_eq!(query.limit, Some(10));
+        assert_eq!(
+            query.sort,
+            vec![SortSpec::desc("age"), SortSpec::asc("name")]
+        );
+        let ops: Vec<&FilterOp> = query.predicates.iter().map(|p| &p.op).collect();
+        assert_eq!(
+            ops,
+            vec![
+                &FilterOp::Gte(json!(18)),
+                &FilterOp::In(vec![json!("Oslo"), json!("Rome")]),
+                &FilterOp::Exists(true),
+                &FilterOp::Prefix("A".to_string()),
+                &FilterOp::Ne(json!(-1.5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_literals_and_identifiers() {
+        let select = parse_select(
+            "select * from V1.Users where \"Nick\" = 'O''Brien' and active = TRUE limit 1",
+        )
+        .unwrap();
+        assert_eq!(select.columns, None);
+        assert_eq!(select.query.schema_id, "users");
+        assert_eq!(
+            select.query.predicates[0],
+            Predicate::eq("Nick", json!("O'Brien"))
+        );
+        assert_eq!(
+            select.query.predicates[1],
+            Predicate::eq("active", json!(true))
+        );
+
+        let select = parse_select("SELECT * FROM v1.users WHERE name LIKE 'Ada' LIMIT 1").unwrap();
+        assert_eq!(
+            select.query.predicates[0],
+            Predicate::eq("name", json!("Ada"))
+        );
+    }
+
+    #[test]
+    fn test_outside_subset_rejected() {
+        let unsupported = [
+            "INSERT INTO v1.users VALUES (1)",
+            "SELECT * FROM v1.users",
+            "SELECT count(*) FROM v1.users LIMIT 1",
+            "SELECT * FROM v1.users WHERE a = 1 OR b = 2 LIMIT 1",
+            "SELECT * FROM v1.users, v1.orders LIMIT 1",
+            "SELECT * FROM v1.users WHERE name LIKE '%a' LIMIT 1",
+            "SELECT * FROM v1.users LIMIT 1 OFFSET 5",
+        ];
+        for sql in unsupported {
+            let err = parse_select(sql).unwrap_err();
+            assert_eq!(err.sqlstate(), "0A000", "{}: {}", sql, err);
+        }
+
+        let syntax = [
+            "SELECT * FROM users LIMIT 1",
+            "SELECT * FROM v1.users WHERE a = NULL LIMIT 1",
+            "SELECT * FROM v1.users WHERE name = 'open LIMIT 1",
+            "SELECT * FROM v1.users LIMIT -1",
+            "SELECT * FROM v1.users LIMIT 1 garbage",
+            "SELECT FROM v1.users LIMIT 1",
+        ];
+        for sql in syntax {
+            let err = parse_select(sql).unwrap_err();
+            assert_eq!(err.sqlstate(), "42601", "{}: {}", sql, err);
+        }
+    }
+}
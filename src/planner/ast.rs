@@ -17,6 +17,15 @@ pub enum FilterOp {
     Lte(serde_json::Value),
     /// Less than: field < value
     Lt(serde_json::Value),
+    /// Membership: field equals one of the values
+    In(Vec<serde_json::Value>),
+    /// Inequality: field != value (residual filter only, never drives a scan)
+    Ne(serde_json::Value),
+    /// Presence: field is present and non-null (`true`) or absent/null (`false`)
+    /// (residual filter only, never drives a scan)
+    Exists(bool),
+    /// String prefix: field starts with the prefix (planned as a range scan)
+    Prefix(String),
 }
 
 impl FilterOp {
@@ -25,14 +34,59 @@ impl FilterOp {
         matches!(self, FilterOp::Eq(_))
     }
 
-    /// Returns true if this is a range operation
+    /// Returns true if this is a range operation (including string prefix)
     pub fn is_range(&self) -> bool {
         matches!(
             self,
-            FilterOp::Gte(_) | FilterOp::Gt(_) | FilterOp::Lte(_) | FilterOp::Lt(_)
+            FilterOp::Gte(_)
+                | FilterOp::Gt(_)
+                | FilterOp::Lte(_)
+                | FilterOp::Lt(_)
+                | FilterOp::Prefix(_)
         )
     }
 
+    /// Returns true if this is a membership operation (multi-probe)
+    pub fn is_membership(&self) -> bool {
+        matches!(self, FilterOp::In(_))
+    }
+
+    /// Inclusive lower bound of a range operation
+    pub fn lower_bound(&self) -> Option<serde_json::Value> {
+        match self {
+            FilterOp::Gte(v) | FilterOp::Gt(v) => Some(v.clone()),
+            FilterOp::Prefix(p) => Some(serde_json::Value::String(p.clone())),
+            _ => None,
+        }
+    }
+
+    /// Inclusive upper bound of a range operation
+    ///
+    /// For a prefix this is the smallest string greater than every string
+    /// with that prefix; the residual filter drops it if it is matched.
+    pub fn upper_bound(&self) -> Option<serde_json::Value> {
+        match self {
+            FilterOp::Lte(v) | FilterOp::Lt(v) => Some(v.clone()),
+            FilterOp::Prefix(p) => prefix_successor(p).map(serde_json::Value::String),
+            _ => None,
+        }
+    }
+
+    /// Returns the operand for explain output
+    pub fn operand(&self) -> serde_json::Value {
+        match self {
+            FilterOp::Eq(v)
+            | FilterOp::Gte(v)
+            | FilterOp::Gt(v)
+            | FilterOp::Lte(v)
+            | FilterOp::Lt(v)
+            | FilterOp::Ne(v) => v.clone(),
+            FilterOp::In(values) => serde_json::Value::Array(values.clone()),
+            FilterOp::Exists(present) => serde_json::Value::Bool(*present),
+            FilterOp::Prefix(p) => serde_json::Value::String(p.clone()),
+        }
+    }
+
     /// Returns the operation name for explain output
     pub fn op_name(&self) -> &'static str {
         match self {
@@ -41,8 +95,29 @@ impl FilterOp {
             FilterOp::Gt(_) => "gt",
             FilterOp::Lte(_) => "lte",
             FilterOp::Lt(_) => "lt",
+            FilterOp::In(_) => "in",
+            FilterOp::Ne(_) => "ne",
+            FilterOp::Exists(_) => "exists",
+            FilterOp::Prefix(_) => "prefix",
+        }
+    }
+}
+
+/// Smallest string greater than every string starting with `prefix`
+///
+/// Increments the last character that can be incremented and drops the
+/// rest. Returns `None` when no such string exists (empty prefix, or every
+/// character is `char::MAX`), meaning the range has no upper bound.
+fn prefix_successor(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+    while let Some(last) = chars.pop() {
+        let next = (last as u32 + 1..=char::MAX as u32).find_map(char::from_u32);
+        if let Some(next) = next {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
         }
     }
+    None
 }
 
 /// A single predicate (field + operation)
@@ -95,6 +170,38 @@ impl Predicate {
         }
     }
 
+    /// Create a membership predicate (in)
+    pub fn in_values(field: impl Into<String>, values: Vec<serde_json::Value>) -> Self {
+        Self {
+            field: field.into(),
+            op: FilterOp::In(values),
+        }
+    }
+
+    /// Create an inequality predicate (ne)
+    pub fn ne(field: impl Into<String>, value: serde_json::Value) -> Self {
+        Self {
+            field: field.into(),
+            op: FilterOp::Ne(value),
+        }
+    }
+
+    /// Create a presence predicate (exists)
+    pub fn exists(field: impl Into<String>, present: bool) -> Self {
+        Self {
+            field: field.into(),
+            op: FilterOp::Exists(present),
+        }
+    }
+
+    /// Create a string prefix predicate
+    pub fn prefix(field: impl Into<String>, prefix: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            op: FilterOp::Prefix(prefix.into()),
+        }
+    }
+
     /// Returns true if this is an equality predicate
     pub fn is_equality(&self) -> bool {
         self.op.is_equality()
@@ -163,8 +270,9 @@ pub struct Query {
     pub schema_version: Option<String>,
     /// Filter predicates (all combined with AND)
     pub predicates: Vec<Predicate>,
-    /// Sort specification (optional, single field only in Phase 0)
-    pub sort: Option<SortSpec>,
+    /// Sort keys in priority order (empty = unsorted). Ties after the last
+    /// key are broken by `_id` ascending.
+    pub sort: Vec<SortSpec>,
     /// Limit (mandatory)
     pub limit: Option<u64>,
 }
@@ -177,7 +285,7 @@ impl Query {
             schema_id: schema_id.into(),
             schema_version: None,
             predicates: Vec::new(),
-            sort: None,
+            sort: Vec::new(),
             limit: None,
         }
     }
@@ -199,9 +307,9 @@ impl Query {
         self.with_predicate(Predicate::eq(field, value))
     }
 
-    /// Sets the sort specification
+    /// Appends a sort key (earlier keys take priority)
     pub fn with_sort(mut self, sort: SortSpec) -> Self {
-        self.sort = Some(sort);
+        self.sort.push(sort);
         self
     }
 
@@ -267,10 +375,43 @@ mod tests {
         assert!(!range_id.is_primary_key());
     }
 
+    #[test]
+    fn test_prefix_range_bounds() {
+        let op = FilterOp::Prefix("ab".to_string());
+        assert!(op.is_range());
+        assert_eq!(op.lower_bound(), Some(json!("ab")));
+        assert_eq!(op.upper_bound(), Some(json!("ac")));
+
+        let open = FilterOp::Prefix(format!("a{}", char::MAX));
+        assert_eq!(open.upper_bound(), Some(json!("b")));
+        assert_eq!(FilterOp::Prefix(String::new()).upper_bound(), None);
+    }
+
+    #[test]
+    fn test_residual_ops_do_not_drive_scans() {
+        for op in [FilterOp::Ne(json!(1)), FilterOp::Exists(true)] {
+            assert!(!op.is_equality());
+            assert!(!op.is_range());
+            assert!(!op.is_membership());
+        }
+        assert!(FilterOp::In(vec![json!(1)]).is_membership());
+    }
+
     #[test]
     fn test_sort_spec() {
         let asc = SortSpec::asc("created_at");
         assert_eq!(asc.direction, SortDirection::Asc);
         assert_eq!(asc.field, "created_at");
     }
+
+    #[test]
+    fn test_with_sort_appends_keys_in_order() {
+        let query = Query::new("users", "user")
+            .with_sort(SortSpec::asc("age"))
+            .with_sort(SortSpec::desc("name"));
+        assert_eq!(
+            query.sort,
+            vec![SortSpec::asc("age"), SortSpec::desc("name")]
+        );
+    }
 }
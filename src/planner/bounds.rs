@@ -4,7 +4,7 @@
 //! - Every filter predicate references indexed fields ONLY
 //! - Range predicates have explicit limit
 //! - Limit is mandatory and > 0
-//! - Sort field is indexed
+//! - Every sort field is indexed
 //! - No OR conditions
 //! - No functions or expressions
 
@@ -22,6 +22,8 @@ pub struct BoundednessProof {
     pub indexed_fields: Vec<String>,
     /// Whether primary key is used
     pub uses_pk: bool,
+    /// Sort fields proven to be indexed, in sort priority order
+    pub sort_fields: Vec<String>,
 }
 
 impl BoundednessProof {
@@ -31,6 +33,7 @@ impl BoundednessProof {
             max_scan: 1,
             indexed_fields: vec!["_id".to_string()],
             uses_pk: true,
+            sort_fields: Vec::new(),
         }
     }
 
@@ -40,8 +43,15 @@ impl BoundednessProof {
             max_scan: limit,
             indexed_fields: fields,
             uses_pk: false,
+            sort_fields: Vec::new(),
         }
     }
+
+    /// Record the sort fields proven to be indexed
+    pub fn with_sort_fields(mut self, fields: Vec<String>) -> Self {
+        self.sort_fields = fields;
+        self
+    }
 }
 
 /// Analyzes query boundedness.
@@ -72,12 +82,13 @@ impl<'a> BoundednessAnalyzer<'a> {
             }
         }
 
-        // 3. Check sort field is indexed (if present)
-        if let Some(sort) = &query.sort {
+        // 3. Check every sort field is indexed
+        for sort in &query.sort {
             if !self.is_indexed(&sort.field) {
                 return Err(PlannerError::sort_not_indexed(&sort.field));
             }
         }
+        let sort_fields: Vec<String> = query.sort.iter().map(|s| s.field.clone()).collect();
 
         // 4. Primary key lookup is special case
         if query.has_pk_filter() {
@@ -86,7 +97,7 @@ impl<'a> BoundednessAnalyzer<'a> {
                 // Still bounded, but semantically pk should return at most 1
                 // We allow limit > 1 but the scan is bounded at 1
             }
-            return Ok(BoundednessProof::pk_lookup());
+            return Ok(BoundednessProof::pk_lookup().with_sort_fields(sort_fields));
         }
 
         // 5. For range queries, limit is already checked above
@@ -94,7 +105,7 @@ impl<'a> BoundednessAnalyzer<'a> {
         let indexed_fields: Vec<String> =
             query.predicates.iter().map(|p| p.field.clone()).collect();
 
-        Ok(BoundednessProof::indexed_scan(limit, indexed_fields))
+        Ok(BoundednessProof::indexed_scan(limit, indexed_fields).with_sort_fields(sort_fields))
     }
 
     /// Checks if a field is indexed (_id is always indexed)
@@ -198,4 +209,22 @@ mod tests {
         assert!(!proof.uses_pk);
         assert_eq!(proof.max_scan, 100);
     }
+
+    #[test]
+    fn test_multi_field_sort_requires_every_field_indexed() {
+        let indexes = make_indexes(&["email", "age"]);
+        let analyzer = BoundednessAnalyzer::new(&indexes);
+
+        let query = Query::new("users", "users")
+            .with_schema_version("v1")
+            .with_predicate(Predicate::eq("email", json!("test@example.com")))
+            .with_sort(crate::planner::ast::SortSpec::asc("age"))
+            .with_limit(10);
+        let proof = analyzer.analyze(&query).unwrap();
+        assert_eq!(proof.sort_fields, vec!["age".to_string()]);
+
+        let query = query.with_sort(crate::planner::ast::SortSpec::desc("created_at"));
+        let err = analyzer.analyze(&query).unwrap_err();
+        assert_eq!(err.code().code(), "AERO_QUERY_SORT_NOT_INDEXED");
+    }
 }
@@ -0,0 +1,94 @@
+//! Cost model for choosing between indexed equality predicates
+//!
+//! When several equality predicates are indexed, the scan is driven by the
+//! one expected to return the fewest offsets, estimated from the index's
+//! cardinality statistics as entries per distinct key.
+//!
+//! Estimates are integers, so ranking is deterministic: same query and
+//! statistics → same plan. An index without statistics ranks after every
+//! index with them; ties are broken lexicographically by field name.
+
+use std::cmp::Ordering;
+
+use super::planner::IndexMetadata;
+
+/// Estimated cost of driving a scan from one index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexCost {
+    /// Indexed field
+    pub field: String,
+    /// Expected offsets returned (`None`: no statistics)
+    pub estimated_rows: Option<u64>,
+}
+
+impl IndexCost {
+    /// Human-readable estimate, e.g. `email: ~1 rows`
+    pub fn describe(&self) -> String {
+        match self.estimated_rows {
+            Some(rows) => format!("{}: ~{} rows", self.field, rows),
+            None => format!("{}: no statistics", self.field),
+        }
+    }
+
+    /// Cheaper estimates first, unknown last, then by field name
+    fn rank(&self, other: &Self) -> Ordering {
+        let key = |cost: &Self| (cost.estimated_rows.is_none(), cost.estimated_rows);
+        key(self)
+            .cmp(&key(other))
+            .then_with(|| self.field.cmp(&other.field))
+    }
+}
+
+/// Costs of equality scans on `fields`, cheapest first
+pub fn rank_equality_candidates(fields: &[&str], metadata: &IndexMetadata) -> Vec<IndexCost> {
+    let mut costs: Vec<IndexCost> = fields
+        .iter()
+        .map(|field| IndexCost {
+            field: field.to_string(),
+            estimated_rows: metadata
+                .statistics(field)
+                .map(|stats| stats.estimated_eq_rows()),
+        })
+        .collect();
+    costs.sort_by(IndexCost::rank);
+    costs.dedup_by(|a, b| a.field == b.field);
+    costs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::IndexStats;
+
+    fn stats(entries: u64, distinct_keys: u64) -> IndexStats {
+        IndexStats {
+            entries,
+            distinct_keys,
+        }
+    }
+
+    #[test]
+    fn test_most_selective_first_unknown_last() {
+        let metadata = IndexMetadata::with_indexes(["city", "email", "status"])
+            .with_statistics("status", stats(1000, 2))
+            .with_statistics("email", stats(1000, 1000));
+
+        let costs = rank_equality_candidates(&["status", "city", "email"], &metadata);
+        let described: Vec<String> = costs.iter().map(IndexCost::describe).collect();
+        assert_eq!(
+            described,
+            vec!["email: ~1 rows", "status: ~500 rows", "city: no statistics"]
+        );
+    }
+
+    #[test]
+    fn test_ties_broken_by_field_name() {
+        let metadata = IndexMetadata::with_indexes(["b", "a"])
+            .with_statistics("a", stats(10, 5))
+            .with_statistics("b", stats(10, 5));
+
+        let costs = rank_equality_candidates(&["b", "a", "b"], &metadata);
+        assert_eq!(costs.len(), 2);
+        assert_eq!(costs[0].field, "a");
+    }
+}
@@ -16,10 +16,16 @@ pub struct ExplainPlan {
     pub selected_index: Option<String>,
     /// Scan type description
     pub scan_type: Option<String>,
+    /// Composite index description (if a composite index was chosen)
+    pub composite_index: Option<String>,
+    /// Cost estimates of the indexed equality candidates, chosen first
+    pub index_costs: Vec<String>,
     /// List of predicates
     pub predicates: Vec<String>,
     /// Sort description
     pub sort: Option<String>,
+    /// When the sort spills to disk (if sorted and a threshold is set)
+    pub sort_spill: Option<String>,
     /// Limit
     pub limit: Option<u64>,
     /// Proven bounds
@@ -36,33 +42,37 @@ impl ExplainPlan {
         let predicates: Vec<String> = plan
             .predicates
             .iter()
-            .map(|p| {
-                format!(
-                    "{} {} {:?}",
-                    p.field,
-                    p.op.op_name(),
-                    match &p.op {
-                        super::ast::FilterOp::Eq(v) => v,
-                        super::ast::FilterOp::Gte(v) => v,
-                        super::ast::FilterOp::Gt(v) => v,
-                        super::ast::FilterOp::Lte(v) => v,
-                        super::ast::FilterOp::Lt(v) => v,
-                    }
-                )
-            })
+            .map(|p| format!("{} {} {:?}", p.field, p.op.op_name(), p.op.operand()))
             .collect();
 
-        let sort = plan
-            .sort
-            .as_ref()
-            .map(|s| format!("{} {}", s.field, s.direction.as_str()));
+        let sort = if plan.sort.is_empty() {
+            None
+        } else {
+            Some(
+                plan.sort
+                    .iter()
+                    .map(|s| format!("{} {}", s.field, s.direction.as_str()))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+        };
 
         Self {
             accepted: true,
             selected_index: Some(plan.chosen_index.clone()),
             scan_type: Some(plan.scan_type.as_str().to_string()),
+            composite_index: plan.composite.as_ref().map(|c| {
+                format!(
+                    "({}), {} of {} fields bound",
+                    c.fields.join(", "),
+                    c.bound_fields,
+                    c.fields.len()
+                )
+            }),
+            index_costs: plan.index_costs.iter().map(|c| c.describe()).collect(),
             predicates,
             sort,
+            sort_spill: None,
             limit: Some(plan.limit),
             max_scan: Some(plan.bounds_proof.max_scan),
             rejection_reason: None,
@@ -70,14 +80,29 @@ impl ExplainPlan {
         }
     }
 
+    /// Notes that a sorted plan spills sorted runs to disk past
+    /// `threshold` candidates
+    pub fn with_sort_spill_threshold(mut self, threshold: usize) -> Self {
+        if self.sort.is_some() {
+            self.sort_spill = Some(format!(
+                "sorted runs spill to disk past {} candidates",
+                threshold
+            ));
+        }
+        self
+    }
+
     /// Creates an explain plan from a planning error
     pub fn from_error(err: &PlannerError) -> Self {
         Self {
             accepted: false,
             selected_index: None,
             scan_type: None,
+            composite_index: None,
+            index_costs: Vec::new(),
             predicates: Vec::new(),
             sort: None,
+            sort_spill: None,
             limit: None,
             max_scan: None,
             rejection_reason: Some(err.message().to_string()),
@@ -98,6 +123,15 @@ impl fmt::Display for ExplainPlan {
             if let Some(scan) = &self.scan_type {
                 writeln!(f, "Scan Type: {}", scan)?;
             }
+            if let Some(composite) = &self.composite_index {
+                writeln!(f, "Composite Index: {}", composite)?;
+            }
+            if !self.index_costs.is_empty() {
+                writeln!(f, "Index Costs (chosen first):")?;
+                for cost in &self.index_costs {
+                    writeln!(f, "  - {}", cost)?;
+                }
+            }
             if !self.predicates.is_empty() {
                 writeln!(f, "Predicates:")?;
                 for pred in &self.predicates {
@@ -107,6 +141,9 @@ impl fmt::Display for ExplainPlan {
             if let Some(sort) = &self.sort {
                 writeln!(f, "Sort: {}", sort)?;
             }
+            if let Some(spill) = &self.sort_spill {
+                writeln!(f, "Sort Spill: {}", spill)?;
+            }
             if let Some(limit) = self.limit {
                 writeln!(f, "Limit: {}", limit)?;
             }
@@ -130,7 +167,8 @@ impl fmt::Display for ExplainPlan {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::planner::ast::{Predicate, Query};
+    use crate::index::IndexStats;
+    use crate::planner::ast::{Predicate, Query, SortSpec};
     use crate::planner::planner::{IndexMetadata, QueryPlanner, SchemaRegistry};
     use serde_json::json;
     use std::collections::HashSet;
@@ -170,6 +208,74 @@ mod tests {
         assert!(output.contains("email"));
     }
 
+    #[test]
+    fn test_explain_composite_plan() {
+        let registry = TestSchemaRegistry;
+        let indexes = IndexMetadata::new().with_composite_index(["city", "status", "age"]);
+        let planner = QueryPlanner::new(&registry, &indexes);
+
+        let query = Query::new("users", "users")
+            .with_schema_version("v1")
+            .with_predicate(Predicate::eq("city", json!("Paris")))
+            .with_predicate(Predicate::eq("status", json!("active")))
+            .with_limit(10);
+
+        let explain = ExplainPlan::from_plan(&planner.plan(&query).unwrap());
+
+        assert_eq!(explain.selected_index, Some("city,status,age".into()));
+        assert_eq!(explain.scan_type, Some("COMPOSITE_EQ".into()));
+        let output = format!("{}", explain);
+        assert!(output.contains("Composite Index: (city, status, age), 2 of 3 fields bound"));
+    }
+
+    #[test]
+    fn test_explain_records_cost_decision() {
+        let registry = TestSchemaRegistry;
+        let indexes = IndexMetadata::with_indexes(["status", "email"]).with_statistics(
+            "status",
+            IndexStats {
+                entries: 1000,
+                distinct_keys: 2,
+            },
+        );
+        let planner = QueryPlanner::new(&registry, &indexes);
+
+        let query = Query::new("users", "users")
+            .with_schema_version("v1")
+            .with_predicate(Predicate::eq("status", json!("active")))
+            .with_predicate(Predicate::eq("email", json!("test@example.com")))
+            .with_limit(10);
+
+        let explain = ExplainPlan::from_plan(&planner.plan(&query).unwrap());
+
+        assert_eq!(explain.selected_index, Some("status".into()));
+        let output = format!("{}", explain);
+        assert!(output.contains(
+            "Index Costs (chosen first):\n  - status: ~500 rows\n  - email: no statistics\n"
+        ));
+    }
+
+    #[test]
+    fn test_explain_sort_spill() {
+        let registry = TestSchemaRegistry;
+        let indexes = IndexMetadata::with_indexes(["age"]);
+        let planner = QueryPlanner::new(&registry, &indexes);
+
+        let query = Query::new("users", "users")
+            .with_schema_version("v1")
+            .with_predicate(Predicate::gte("age", json!(18)))
+            .with_limit(10);
+        let explain =
+            ExplainPlan::from_plan(&planner.plan(&query).unwrap()).with_sort_spill_threshold(1000);
+        assert_eq!(explain.sort_spill, None);
+
+        let query = query.with_sort(SortSpec::desc("age"));
+        let explain =
+            ExplainPlan::from_plan(&planner.plan(&query).unwrap()).with_sort_spill_threshold(1000);
+        let output = format!("{}", explain);
+        assert!(output.contains("Sort Spill: sorted runs spill to disk past 1000 candidates"));
+    }
+
     #[test]
     fn test_explain_rejected_plan() {
         let err = PlannerError::unindexed_field("name");
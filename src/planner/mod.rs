@@ -12,19 +12,23 @@
 //! # Index Selection Priority (strict order)
 //!
 //! 1. Primary key equality (_id)
-//! 2. Indexed equality predicate
+//! 2. Indexed equality predicate, the most selective per index statistics
 //! 3. Indexed range predicate with limit
 //!
 //! Ties broken lexicographically by field name.
 
 mod ast;
 mod bounds;
+mod cost;
 mod errors;
 mod explain;
 mod planner;
 
 pub use ast::{FilterOp, Predicate, Query, SortDirection, SortSpec};
 pub use bounds::BoundednessProof;
+pub use cost::IndexCost;
 pub use errors::{PlannerError, PlannerErrorCode, PlannerResult};
 pub use explain::ExplainPlan;
-pub use planner::{IndexMetadata, QueryPlan, QueryPlanner, ScanType, SchemaRegistry};
+pub use planner::{
+    CompositeScan, IndexMetadata, QueryPlan, QueryPlanner, ScanType, SchemaRegistry,
+};
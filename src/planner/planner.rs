@@ -4,22 +4,32 @@
 //!
 //! Index selection priority (strict order):
 //! 1. Primary key equality (_id)
-//! 2. Indexed equality predicate
-//! 3. Indexed range predicate with limit
+//! 2. Composite index with equality on at least two leading fields
+//! 3. Indexed equality predicate, the most selective per the cost model
+//! 4. Indexed membership (`$in`) predicate
+//! 5. Indexed range predicate with limit
 //!
 //! Ties broken lexicographically by field name.
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 
-use super::ast::{Predicate, Query, SortSpec};
+use serde_json::Value;
+
+use super::ast::{FilterOp, Predicate, Query, SortSpec};
 use super::bounds::{BoundednessAnalyzer, BoundednessProof};
+use super::cost::{rank_equality_candidates, IndexCost};
 use super::errors::{PlannerError, PlannerResult};
+use crate::index::IndexStats;
 
 /// Index metadata provided to the planner
 #[derive(Debug, Clone)]
 pub struct IndexMetadata {
     /// Set of indexed field names (excluding _id which is always indexed)
     pub indexed_fields: HashSet<String>,
+    /// Declared composite indexes, each an ordered list of fields
+    pub composite_indexes: Vec<Vec<String>>,
+    /// Cardinality statistics of single-field indexes, by field
+    pub statistics: BTreeMap<String, IndexStats>,
 }
 
 impl IndexMetadata {
@@ -27,6 +37,8 @@ impl IndexMetadata {
     pub fn new() -> Self {
         Self {
             indexed_fields: HashSet::new(),
+            composite_indexes: Vec::new(),
+            statistics: BTreeMap::new(),
         }
     }
 
@@ -34,13 +46,50 @@ impl IndexMetadata {
     pub fn with_indexes(fields: impl IntoIterator<Item = impl Into<String>>) -> Self {
         Self {
             indexed_fields: fields.into_iter().map(Into::into).collect(),
+            composite_indexes: Vec::new(),
+            statistics: BTreeMap::new(),
         }
     }
 
+    /// Declares a composite index over the given fields, in order.
+    ///
+    /// Declarations with fewer than two fields or duplicating an existing
+    /// declaration are ignored.
+    pub fn with_composite_index(
+        mut self,
+        fields: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        let fields: Vec<String> = fields.into_iter().map(Into::into).collect();
+        if fields.len() >= 2 && !self.composite_indexes.contains(&fields) {
+            self.composite_indexes.push(fields);
+        }
+        self
+    }
+
+    /// Records the cardinality of the index on `field`
+    pub fn with_statistics(mut self, field: impl Into<String>, stats: IndexStats) -> Self {
+        self.statistics.insert(field.into(), stats);
+        self
+    }
+
+    /// Cardinality of the index on `field`, if known
+    pub fn statistics(&self, field: &str) -> Option<&IndexStats> {
+        self.statistics.get(field)
+    }
+
     /// Checks if a field is indexed
     pub fn is_indexed(&self, field: &str) -> bool {
         field == "_id" || self.indexed_fields.contains(field)
     }
+
+    /// Fields covered by a single-field or composite index
+    pub fn covered_fields(&self) -> HashSet<String> {
+        self.indexed_fields
+            .iter()
+            .chain(self.composite_indexes.iter().flatten())
+            .cloned()
+            .collect()
+    }
 }
 
 impl Default for IndexMetadata {
@@ -54,8 +103,12 @@ impl Default for IndexMetadata {
 pub enum ScanType {
     /// Primary key equality lookup
     PrimaryKey,
+    /// Composite index scan on equality-bound leading fields
+    CompositeEquality,
     /// Indexed equality scan
     IndexedEquality,
+    /// Indexed equality scan probed once per `$in` value
+    IndexedIn,
     /// Indexed range scan with limit
     IndexedRange,
 }
@@ -64,12 +117,30 @@ impl ScanType {
     pub fn as_str(&self) -> &'static str {
         match self {
             ScanType::PrimaryKey => "PK_LOOKUP",
+            ScanType::CompositeEquality => "COMPOSITE_EQ",
             ScanType::IndexedEquality => "INDEX_EQ",
+            ScanType::IndexedIn => "INDEX_IN",
             ScanType::IndexedRange => "INDEX_RANGE",
         }
     }
 }
 
+/// Composite index chosen for a plan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompositeScan {
+    /// All fields of the composite index, in declaration order
+    pub fields: Vec<String>,
+    /// Number of leading fields bound by equality predicates
+    pub bound_fields: usize,
+}
+
+impl CompositeScan {
+    /// Index name as shown in plans (fields joined by ',')
+    pub fn name(&self) -> String {
+        self.fields.join(",")
+    }
+}
+
 /// Immutable query plan (no runtime state)
 #[derive(Debug, Clone)]
 pub struct QueryPlan {
@@ -79,20 +150,48 @@ pub struct QueryPlan {
     pub schema_id: String,
     /// Schema version
     pub schema_version: String,
-    /// Chosen index (field name)
+    /// Chosen index (field name, or composite index name)
     pub chosen_index: String,
     /// Scan type
     pub scan_type: ScanType,
+    /// Composite index details (set for `ScanType::CompositeEquality`)
+    pub composite: Option<CompositeScan>,
+    /// Indexed equality candidates weighed by the cost model, cheapest
+    /// (chosen) first (set for `ScanType::IndexedEquality`)
+    pub index_costs: Vec<IndexCost>,
     /// Filter predicates to apply
     pub predicates: Vec<Predicate>,
-    /// Sort specification (if any)
-    pub sort: Option<SortSpec>,
+    /// Sort keys in priority order (empty = unsorted)
+    pub sort: Vec<SortSpec>,
     /// Limit
     pub limit: u64,
     /// Boundedness proof
     pub bounds_proof: BoundednessProof,
 }
 
+impl QueryPlan {
+    /// Equality values for the bound leading fields of a composite scan.
+    ///
+    /// Empty unless the plan uses a composite index.
+    pub fn composite_prefix(&self) -> Vec<Value> {
+        let Some(composite) = &self.composite else {
+            return Vec::new();
+        };
+        composite.fields[..composite.bound_fields]
+            .iter()
+            .filter_map(|field| equality_value(&self.predicates, field).cloned())
+            .collect()
+    }
+}
+
+/// Value of the first equality predicate on `field`
+fn equality_value<'p>(predicates: &'p [Predicate], field: &str) -> Option<&'p Value> {
+    predicates.iter().find_map(|p| match &p.op {
+        FilterOp::Eq(v) if p.field == field => Some(v),
+        _ => None,
+    })
+}
+
 /// Schema registry trait for planner (read-only)
 pub trait SchemaRegistry {
     /// Check if schema exists
@@ -143,11 +242,16 @@ impl<'a, S: SchemaRegistry> QueryPlanner<'a, S> {
         }
 
         // 4. Prove boundedness BEFORE plan generation
-        let analyzer = BoundednessAnalyzer::new(&self.index_metadata.indexed_fields);
+        let covered_fields = self.index_metadata.covered_fields();
+        let analyzer = BoundednessAnalyzer::new(&covered_fields);
         let bounds_proof = analyzer.analyze(query)?;
 
         // 5. Select index using strict priority order
-        let (chosen_index, scan_type) = self.select_index(query)?;
+        let composite = self.select_composite(query);
+        let (chosen_index, scan_type, index_costs) = match &composite {
+            Some(c) => (c.name(), ScanType::CompositeEquality, Vec::new()),
+            None => self.select_index(query)?,
+        };
 
         // 6. Build immutable plan
         Ok(QueryPlan {
@@ -156,6 +260,8 @@ impl<'a, S: SchemaRegistry> QueryPlanner<'a, S> {
             schema_version: schema_version.clone(),
             chosen_index,
             scan_type,
+            composite,
+            index_costs,
             predicates: query.predicates.clone(),
             sort: query.sort.clone(),
             limit: query.limit.unwrap(), // Already validated in bounds
@@ -163,32 +269,83 @@ impl<'a, S: SchemaRegistry> QueryPlanner<'a, S> {
         })
     }
 
+    /// Selects a composite index whose leading fields are bound by equality.
+    ///
+    /// Only consulted after the primary key. A composite index qualifies when
+    /// at least its first two fields have equality predicates; the one with
+    /// the most bound fields wins, ties broken lexicographically by name.
+    fn select_composite(&self, query: &Query) -> Option<CompositeScan> {
+        if query.has_pk_filter() {
+            return None;
+        }
+
+        self.index_metadata
+            .composite_indexes
+            .iter()
+            .map(|fields| CompositeScan {
+                fields: fields.clone(),
+                bound_fields: fields
+                    .iter()
+                    .take_while(|f| equality_value(&query.predicates, f).is_some())
+                    .count(),
+            })
+            .filter(|c| c.bound_fields >= 2)
+            .min_by(|a, b| {
+                b.bound_fields
+                    .cmp(&a.bound_fields)
+                    .then_with(|| a.name().cmp(&b.name()))
+            })
+    }
+
     /// Selects index using strict priority order per QUERY.md §230-237.
     ///
-    /// Priority:
+    /// Priority (composite indexes, priority 2, are handled by
+    /// `select_composite`):
     /// 1. Primary key equality (_id)
-    /// 2. Indexed equality predicate
-    /// 3. Indexed range predicate with limit
+    /// 3. Indexed equality predicate, fewest estimated rows first
+    /// 4. Indexed membership (`$in`) predicate, one probe per value
+    /// 5. Indexed range (or `$prefix`) predicate with limit
     ///
-    /// Ties broken lexicographically.
-    fn select_index(&self, query: &Query) -> PlannerResult<(String, ScanType)> {
+    /// `$ne` and `$exists` never drive a scan; they are residual filters.
+    ///
+    /// Ties broken lexicographically. Returns the chosen index, the scan
+    /// type and, for indexed equality, the candidates' costs.
+    fn select_index(&self, query: &Query) -> PlannerResult<(String, ScanType, Vec<IndexCost>)> {
         // Priority 1: Primary key equality
         if query.has_pk_filter() {
-            return Ok(("_id".to_string(), ScanType::PrimaryKey));
+            return Ok(("_id".to_string(), ScanType::PrimaryKey, Vec::new()));
         }
 
         // Collect equality predicates on indexed fields
-        let mut eq_candidates: Vec<&str> = query
+        let eq_candidates: Vec<&str> = query
             .predicates
             .iter()
             .filter(|p| p.is_equality() && self.index_metadata.is_indexed(&p.field))
             .map(|p| p.field.as_str())
             .collect();
 
-        // Priority 2: Indexed equality (lexicographically smallest)
+        // Priority 3: Indexed equality (cheapest per the cost model)
         if !eq_candidates.is_empty() {
-            eq_candidates.sort();
-            return Ok((eq_candidates[0].to_string(), ScanType::IndexedEquality));
+            let costs = rank_equality_candidates(&eq_candidates, self.index_metadata);
+            return Ok((costs[0].field.clone(), ScanType::IndexedEquality, costs));
+        }
+
+        // Collect membership predicates on indexed fields
+        let mut in_candidates: Vec<&str> = query
+            .predicates
+            .iter()
+            .filter(|p| p.op.is_membership() && self.index_metadata.is_indexed(&p.field))
+            .map(|p| p.field.as_str())
+            .collect();
+
+        // Priority 4: Indexed membership (lexicographically smallest)
+        if !in_candidates.is_empty() {
+            in_candidates.sort();
+            return Ok((
+                in_candidates[0].to_string(),
+                ScanType::IndexedIn,
+                Vec::new(),
+            ));
         }
 
         // Collect range predicates on indexed fields
@@ -199,10 +356,14 @@ impl<'a, S: SchemaRegistry> QueryPlanner<'a, S> {
             .map(|p| p.field.as_str())
             .collect();
 
-        // Priority 3: Indexed range (lexicographically smallest)
+        // Priority 5: Indexed range (lexicographically smallest)
         if !range_candidates.is_empty() {
             range_candidates.sort();
-            return Ok((range_candidates[0].to_string(), ScanType::IndexedRange));
+            return Ok((
+                range_candidates[0].to_string(),
+                ScanType::IndexedRange,
+                Vec::new(),
+            ));
         }
 
         // No usable index found - should have been caught by bounds check
@@ -423,4 +584,117 @@ mod tests {
         // Should pick "alpha" (lexicographically smallest)
         assert_eq!(plan.chosen_index, "alpha");
     }
+
+    #[test]
+    fn test_most_selective_equality_index_chosen() {
+        let registry = TestSchemaRegistry::new();
+        let stats = |entries, distinct_keys| IndexStats {
+            entries,
+            distinct_keys,
+        };
+        let indexes = IndexMetadata::with_indexes(["country", "email"])
+            .with_statistics("country", stats(1000, 10))
+            .with_statistics("email", stats(1000, 1000));
+        let planner = QueryPlanner::new(&registry, &indexes);
+
+        // "country" sorts first but "email" is far more selective
+        let query = Query::new("users", "users")
+            .with_schema_version("v1")
+            .with_predicate(Predicate::eq("country", json!("FR")))
+            .with_predicate(Predicate::eq("email", json!("a@example.com")))
+            .with_limit(10);
+        let plan = planner.plan(&query).unwrap();
+        assert_eq!(plan.scan_type, ScanType::IndexedEquality);
+        assert_eq!(plan.chosen_index, "email");
+        assert_eq!(plan.index_costs.len(), 2);
+        assert_eq!(plan.index_costs[1].estimated_rows, Some(100));
+    }
+
+    #[test]
+    fn test_in_and_prefix_plans() {
+        let registry = TestSchemaRegistry::new();
+        let indexes = IndexMetadata::with_indexes(["email", "age"]);
+        let planner = QueryPlanner::new(&registry, &indexes);
+
+        let query = Query::new("users", "users")
+            .with_schema_version("v1")
+            .with_predicate(Predicate::prefix("email", "al"))
+            .with_predicate(Predicate::in_values("age", vec![json!(20), json!(30)]))
+            .with_limit(10);
+        let plan = planner.plan(&query).unwrap();
+        assert_eq!(plan.scan_type, ScanType::IndexedIn);
+        assert_eq!(plan.chosen_index, "age");
+
+        let query = Query::new("users", "users")
+            .with_schema_version("v1")
+            .with_predicate(Predicate::prefix("email", "al"))
+            .with_limit(10);
+        let plan = planner.plan(&query).unwrap();
+        assert_eq!(plan.scan_type, ScanType::IndexedRange);
+        assert_eq!(plan.chosen_index, "email");
+    }
+
+    #[test]
+    fn test_composite_index_on_leading_equalities() {
+        let registry = TestSchemaRegistry::new();
+        let indexes = IndexMetadata::with_indexes(["city"])
+            .with_composite_index(["city", "status", "age"])
+            .with_composite_index(["city", "age"]);
+        let planner = QueryPlanner::new(&registry, &indexes);
+
+        // Only (city, age) has both leading fields bound
+        let query = Query::new("users", "users")
+            .with_schema_version("v1")
+            .with_predicate(Predicate::eq("age", json!(30)))
+            .with_predicate(Predicate::eq("city", json!("Paris")))
+            .with_limit(10);
+        let plan = planner.plan(&query).unwrap();
+        assert_eq!(plan.scan_type, ScanType::CompositeEquality);
+        assert_eq!(plan.chosen_index, "city,age");
+        assert_eq!(plan.composite_prefix(), vec![json!("Paris"), json!(30)]);
+
+        // Only the leading field is bound: fall back to the single-field index
+        let query = Query::new("users", "users")
+            .with_schema_version("v1")
+            .with_predicate(Predicate::eq("city", json!("Paris")))
+            .with_predicate(Predicate::gt("age", json!(30)))
+            .with_limit(10);
+        let plan = planner.plan(&query).unwrap();
+        assert_eq!(plan.scan_type, ScanType::IndexedEquality);
+        assert!(plan.composite.is_none());
+    }
+
+    #[test]
+    fn test_composite_prefers_most_bound_fields() {
+        let registry = TestSchemaRegistry::new();
+        let indexes = IndexMetadata::new()
+            .with_composite_index(["a", "b"])
+            .with_composite_index(["a", "b", "c"]);
+        let planner = QueryPlanner::new(&registry, &indexes);
+
+        let query = Query::new("users", "users")
+            .with_schema_version("v1")
+            .with_predicate(Predicate::eq("a", json!(1)))
+            .with_predicate(Predicate::eq("b", json!(2)))
+            .with_predicate(Predicate::eq("c", json!(3)))
+            .with_limit(10);
+        let plan = planner.plan(&query).unwrap();
+        let composite = plan.composite.unwrap();
+        assert_eq!(composite.name(), "a,b,c");
+        assert_eq!(composite.bound_fields, 3);
+    }
+
+    #[test]
+    fn test_residual_only_query_rejected() {
+        let registry = TestSchemaRegistry::new();
+        let indexes = IndexMetadata::with_indexes(["email"]);
+        let planner = QueryPlanner::new(&registry, &indexes);
+
+        let query = Query::new("users", "users")
+            .with_schema_version("v1")
+            .with_predicate(Predicate::ne("email", json!("x@y.com")))
+            .with_predicate(Predicate::exists("email", true))
+            .with_limit(10);
+        assert!(planner.plan(&query).is_err());
+    }
 }
@@ -10,6 +10,12 @@
 //!
 //! Per PHASE6_INVARIANTS.md §P6-D2:
 //! After crash and recovery, authority state MUST be unambiguous.
+//!
+//! A second marker (`metadata/authority_epoch.marker`) holds the fencing
+//! epoch: a counter raised by every promotion and never removed. WAL is
+//! streamed under the Primary's epoch, and replicas refuse records from
+//! an epoch older than one they have seen, so a demoted Primary's late
+//! writes cannot be replicated.
 
 use serde::{Deserialize, Serialize};
 use std::fs::{self, File, OpenOptions};
@@ -22,6 +28,9 @@ use super::errors::{PromotionError, PromotionErrorKind, PromotionResult};
 /// Marker file name per PHASE6_ARCHITECTURE.md §4.2
 const MARKER_FILE_NAME: &str = "authority_transition.marker";
 
+/// Fencing epoch file name; unlike the transition marker it is never removed
+const EPOCH_FILE_NAME: &str = "authority_epoch.marker";
+
 /// Authority transition marker.
 ///
 /// Per PHASE6_ARCHITECTURE.md §4.2:
@@ -39,6 +48,10 @@ pub struct AuthorityMarker {
 
     /// Previous authority (for audit trail)
     pub previous_state: String,
+
+    /// Fencing epoch the new primary holds authority under
+    #[serde(default)]
+    pub epoch: u64,
 }
 
 impl AuthorityMarker {
@@ -51,9 +64,16 @@ impl AuthorityMarker {
                 .map(|d| d.as_secs() as i64)
                 .unwrap_or(0),
             previous_state: previous_state.to_string(),
+            epoch: 0,
         }
     }
 
+    /// Set the fencing epoch.
+    pub fn with_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
     /// Get the primary ID as UUID.
     pub fn get_primary_id(&self) -> Option<Uuid> {
         Uuid::parse_str(&self.new_primary_id).ok()
@@ -83,10 +103,25 @@ impl DurableMarker {
     /// # Arguments
     /// * `data_dir` - Base data directory
     pub fn new(data_dir: &Path) -> Self {
+        Self::named(data_dir, MARKER_FILE_NAME)
+    }
+
+    /// Create a manager for the fencing epoch marker.
+    ///
+    /// Same atomic write pattern; the marker is never removed, so the
+    /// epoch survives `remove` of the transition marker.
+    ///
+    /// # Arguments
+    /// * `data_dir` - Base data directory
+    pub fn epoch(data_dir: &Path) -> Self {
+        Self::named(data_dir, EPOCH_FILE_NAME)
+    }
+
+    fn named(data_dir: &Path, file_name: &str) -> Self {
         let metadata_dir = data_dir.join("metadata");
         Self {
-            marker_path: metadata_dir.join(MARKER_FILE_NAME),
-            temp_path: metadata_dir.join(format!("{}.tmp", MARKER_FILE_NAME)),
+            marker_path: metadata_dir.join(file_name),
+            temp_path: metadata_dir.join(format!("{}.tmp", file_name)),
         }
     }
 
@@ -329,4 +364,29 @@ mod tests {
 
         assert_eq!(read1, read2);
     }
+
+    #[test]
+    fn test_epoch_marker_is_separate() {
+        let tmp = TempDir::new().unwrap();
+        let dm = DurableMarker::new(tmp.path());
+        let epoch = DurableMarker::epoch(tmp.path());
+
+        let marker = AuthorityMarker::new(test_uuid(), "ReplicaActive").with_epoch(3);
+        dm.write_atomic(&marker).unwrap();
+        epoch.write_atomic(&marker).unwrap();
+
+        dm.remove().unwrap();
+        assert!(!dm.exists());
+        assert_eq!(epoch.read().unwrap().unwrap().epoch, 3);
+    }
+
+    #[test]
+    fn test_marker_without_epoch_reads_as_zero() {
+        // Markers written before fencing epochs existed
+        let marker: AuthorityMarker = serde_json::from_str(
+            r#"{"new_primary_id":"x","timestamp_secs":0,"previous_state":"ReplicaActive"}"#,
+        )
+        .unwrap();
+        assert_eq!(marker.epoch, 0);
+    }
 }
@@ -16,6 +16,10 @@
 //!
 //! Per PHASE6_ARCHITECTURE.md §4.2 (amended):
 //! Uses fsynced marker file for durable authority transition.
+//!
+//! Each applied transition also raises the durable fencing epoch. The
+//! promoted Primary streams WAL under the new epoch; replicas that have
+//! seen it refuse records from the demoted Primary's older epoch.
 
 use super::errors::{PromotionError, PromotionErrorKind, PromotionResult};
 use super::marker::{AuthorityMarker, DurableMarker};
@@ -81,6 +85,9 @@ pub struct AuthorityTransitionManager {
 
     /// Durable marker for crash-safe atomicity
     durable_marker: DurableMarker,
+
+    /// Durable fencing epoch, raised by every applied transition
+    epoch_marker: DurableMarker,
 }
 
 impl AuthorityTransitionManager {
@@ -93,6 +100,7 @@ impl AuthorityTransitionManager {
             transition_in_progress: false,
             promoting_replica_id: None,
             durable_marker: DurableMarker::new(data_dir),
+            epoch_marker: DurableMarker::epoch(data_dir),
         }
     }
 
@@ -179,9 +187,15 @@ impl AuthorityTransitionManager {
             )
         })?;
 
+        // Raise the fencing epoch before authority moves, so the new
+        // Primary never streams under the old Primary's epoch. A crash
+        // between the two writes only skips an epoch.
+        let epoch = self.fencing_epoch()? + 1;
+        let marker = AuthorityMarker::new(replica_id, "ReplicaActive").with_epoch(epoch);
+        self.epoch_marker.write_atomic(&marker)?;
+
         // Write durable marker - CRITICAL per P6-F2
         // This is the point of no return
-        let marker = AuthorityMarker::new(replica_id, "ReplicaActive");
         self.durable_marker.write_atomic(&marker)?;
 
         // Authority rebinding complete
@@ -261,6 +275,27 @@ impl AuthorityTransitionManager {
     pub fn has_durable_marker(&self) -> bool {
         self.durable_marker.exists()
     }
+
+    /// Current fencing epoch (0 before any promotion).
+    ///
+    /// A Primary streams WAL under this epoch; a replica fences its
+    /// `WalReceiver` with it on startup.
+    pub fn fencing_epoch(&self) -> PromotionResult<u64> {
+        Ok(self.epoch_marker.read()?.map_or(0, |marker| marker.epoch))
+    }
+
+    /// Durably record an epoch observed from a Primary's WAL stream.
+    ///
+    /// The epoch never decreases: an epoch at or below the current one is
+    /// ignored. Returns whether the epoch was raised.
+    pub fn observe_epoch(&self, primary_id: Uuid, epoch: u64) -> PromotionResult<bool> {
+        if epoch <= self.fencing_epoch()? {
+            return Ok(false);
+        }
+        let marker = AuthorityMarker::new(primary_id, "ReplicaActive").with_epoch(epoch);
+        self.epoch_marker.write_atomic(&marker)?;
+        Ok(true)
+    }
 }
 
 #[cfg(test)]
@@ -446,4 +481,33 @@ mod tests {
         assert!(result1.0); // Both should see committed
         assert_eq!(result1.1, Some(replica_id));
     }
+
+    #[test]
+    fn test_each_transition_raises_fencing_epoch() {
+        let (tmp, mut manager) = make_manager();
+        assert_eq!(manager.fencing_epoch().unwrap(), 0);
+
+        for expected in 1..=2 {
+            let replica_id = test_uuid();
+            let state = ReplicationState::ReplicaActive { replica_id };
+            manager.begin_transition(replica_id, &state).unwrap();
+            manager.apply_transition().unwrap();
+            manager.complete_transition().unwrap();
+
+            // The epoch outlives the transition marker and a restart
+            let restarted = AuthorityTransitionManager::new(tmp.path());
+            assert_eq!(restarted.fencing_epoch().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_observed_epoch_never_decreases() {
+        let (_tmp, manager) = make_manager();
+        let primary_id = test_uuid();
+
+        assert!(manager.observe_epoch(primary_id, 4).unwrap());
+        assert!(!manager.observe_epoch(primary_id, 2).unwrap());
+        assert!(!manager.observe_epoch(primary_id, 4).unwrap());
+        assert_eq!(manager.fencing_epoch().unwrap(), 4);
+    }
 }
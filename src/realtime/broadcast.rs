@@ -1,9 +1,13 @@
 //! # Broadcast Channels
 //!
 //! Pub/sub channels for user-generated messages.
+//!
+//! Messages are delivered to current subscribers only, unless the channel
+//! is durable: then they are also kept in the `EventLog` for its broadcast
+//! retention window, and subscribers that join late receive them.
 
 use std::collections::{HashMap, HashSet};
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 
 use chrono::{DateTime, Utc};
 use serde_json::Value;
@@ -11,6 +15,7 @@ use uuid::Uuid;
 
 use super::errors::{RealtimeError, RealtimeResult};
 use super::event::BroadcastEvent;
+use super::event_log::EventLog;
 
 /// Rate limit configuration
 #[derive(Debug, Clone)]
@@ -47,6 +52,9 @@ pub struct BroadcastChannel {
 
     /// Rate limit config
     rate_config: RateLimitConfig,
+
+    /// Log retaining messages (durable channels only)
+    event_log: Option<Arc<EventLog>>,
 }
 
 impl BroadcastChannel {
@@ -58,6 +66,26 @@ impl BroadcastChannel {
             subscribers: RwLock::new(HashSet::new()),
             rate_limits: RwLock::new(HashMap::new()),
             rate_config: RateLimitConfig::default(),
+            event_log: None,
+        }
+    }
+
+    /// Retain messages in `event_log` for late subscribers
+    pub fn durable(mut self, event_log: Arc<EventLog>) -> Self {
+        self.event_log = Some(event_log);
+        self
+    }
+
+    /// Whether messages are retained
+    pub fn is_durable(&self) -> bool {
+        self.event_log.is_some()
+    }
+
+    /// Retained messages after `since_sequence` (none unless durable)
+    pub fn missed_since(&self, since_sequence: u64) -> Vec<BroadcastEvent> {
+        match &self.event_log {
+            Some(log) => log.broadcasts_since(&self.name, since_sequence),
+            None => Vec::new(),
         }
     }
 
@@ -126,12 +154,11 @@ impl BroadcastChannel {
             ));
         }
 
-        Ok(BroadcastEvent::new(
-            self.name.clone(),
-            event,
-            payload,
-            sender_id,
-        ))
+        let event = BroadcastEvent::new(self.name.clone(), event, payload, sender_id);
+        Ok(match &self.event_log {
+            Some(log) => log.record_broadcast(event),
+            None => event,
+        })
     }
 
     /// Get all subscribers
@@ -153,6 +180,9 @@ impl BroadcastChannel {
 pub struct BroadcastRegistry {
     /// Channels by name
     channels: RwLock<HashMap<String, BroadcastChannel>>,
+
+    /// Log for durable channels
+    event_log: Option<Arc<EventLog>>,
 }
 
 impl BroadcastRegistry {
@@ -161,6 +191,30 @@ impl BroadcastRegistry {
         Self::default()
     }
 
+    /// Allow durable channels, retained in `event_log`
+    pub fn with_event_log(mut self, event_log: Arc<EventLog>) -> Self {
+        self.event_log = Some(event_log);
+        self
+    }
+
+    /// Get or create a durable channel
+    ///
+    /// An existing channel keeps its mode.
+    pub fn get_or_create_durable(&self, name: &str, is_public: bool) -> RealtimeResult<()> {
+        let event_log = self.event_log.clone().ok_or_else(|| {
+            RealtimeError::ConfigError("Durable broadcast requires an event log".to_string())
+        })?;
+        let mut channels = self
+            .channels
+            .write()
+            .map_err(|_| RealtimeError::Internal("Lock poisoned".into()))?;
+
+        channels.entry(name.to_string()).or_insert_with(|| {
+            BroadcastChannel::new(name.to_string(), is_public).durable(event_log)
+        });
+        Ok(())
+    }
+
     /// Get or create a channel
     pub fn get_or_create(&self, name: &str, is_public: bool) -> RealtimeResult<()> {
         let mut channels = self
@@ -192,6 +246,26 @@ impl BroadcastRegistry {
         }
     }
 
+    /// Subscribe to a channel, returning retained messages after
+    /// `since_sequence` (0 for all within the retention window)
+    pub fn subscribe_from(
+        &self,
+        channel_name: &str,
+        connection_id: &str,
+        since_sequence: u64,
+    ) -> RealtimeResult<Vec<BroadcastEvent>> {
+        let channels = self
+            .channels
+            .read()
+            .map_err(|_| RealtimeError::Internal("Lock poisoned".into()))?;
+
+        let channel = channels
+            .get(channel_name)
+            .ok_or_else(|| RealtimeError::ChannelNotFound(channel_name.to_string()))?;
+        channel.subscribe(connection_id)?;
+        Ok(channel.missed_since(since_sequence))
+    }
+
     /// Unsubscribe from a channel
     pub fn unsubscribe(&self, channel_name: &str, connection_id: &str) {
         if let Ok(channels) = self.channels.read() {
@@ -310,4 +384,50 @@ mod tests {
         assert_eq!(event.channel, "chat-room");
         assert_eq!(subscribers, vec!["conn-1"]);
     }
+
+    #[test]
+    fn test_durable_channel_replays_to_late_subscribers() {
+        let registry = BroadcastRegistry::new().with_event_log(Arc::new(EventLog::default()));
+        registry.get_or_create("ephemeral", true).unwrap();
+        registry.get_or_create_durable("durable", true).unwrap();
+
+        // Sent while no one is subscribed
+        for channel in ["ephemeral", "durable"] {
+            let (event, subscribers) = registry
+                .broadcast(
+                    channel,
+                    "msg".to_string(),
+                    json!({"text": "Hi"}),
+                    None,
+                    "conn-1",
+                )
+                .unwrap();
+            assert!(subscribers.is_empty());
+            assert_eq!(event.sequence.is_some(), channel == "durable");
+        }
+
+        assert!(registry
+            .subscribe_from("ephemeral", "conn-2", 0)
+            .unwrap()
+            .is_empty());
+        let missed = registry.subscribe_from("durable", "conn-2", 0).unwrap();
+        assert_eq!(missed.len(), 1);
+        assert_eq!(missed[0].payload, json!({"text": "Hi"}));
+
+        // Resuming after the last seen message replays nothing
+        let last = missed[0].sequence.unwrap();
+        assert!(registry
+            .subscribe_from("durable", "conn-3", last)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_durable_requires_event_log() {
+        let registry = BroadcastRegistry::new();
+        assert!(matches!(
+            registry.get_or_create_durable("durable", true),
+            Err(RealtimeError::ConfigError(_))
+        ));
+    }
 }
@@ -4,11 +4,20 @@
 //!
 //! ## Invariant: RT-D1
 //! Best-effort delivery. No guarantee of delivery or ordering.
+//!
+//! ## Backpressure
+//! Each connection has a bounded queue (`DispatcherConfig::queue_capacity`).
+//! Dispatch never blocks: when a slow consumer's queue is full the event is
+//! dropped for that connection, and under `OverflowPolicy::Disconnect` the
+//! connection is also closed so the client can reconnect and resume. Drops
+//! and disconnects are counted in `DispatcherStats`.
 
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
 
 use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
 
 use super::errors::{RealtimeError, RealtimeResult};
 use super::event::DatabaseEvent;
@@ -16,10 +25,50 @@ use super::subscription::{Subscription, SubscriptionRegistry};
 use crate::auth::rls::{RlsContext, RlsPolicy};
 
 /// Event sender for a connection
-pub type EventSender = mpsc::UnboundedSender<DatabaseEvent>;
+pub type EventSender = mpsc::Sender<DatabaseEvent>;
 
 /// Event receiver for a connection
-pub type EventReceiver = mpsc::UnboundedReceiver<DatabaseEvent>;
+///
+/// Yields `None` once the dispatcher has closed the connection.
+pub type EventReceiver = mpsc::Receiver<DatabaseEvent>;
+
+/// What happens to a connection whose queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drop the event for that connection and keep it open
+    DropEvent,
+    /// Drop the event and close the connection
+    Disconnect,
+}
+
+/// Dispatcher configuration
+#[derive(Debug, Clone)]
+pub struct DispatcherConfig {
+    /// Events queued per connection before the overflow policy applies
+    pub queue_capacity: usize,
+    /// Policy for connections whose queue is full
+    pub overflow: OverflowPolicy,
+}
+
+impl Default for DispatcherConfig {
+    fn default() -> Self {
+        Self {
+            queue_capacity: 1024,
+            overflow: OverflowPolicy::Disconnect,
+        }
+    }
+}
+
+/// Delivery counters since the dispatcher was created
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DispatcherStats {
+    /// Events queued for delivery
+    pub delivered: u64,
+    /// Events dropped because a connection's queue was full
+    pub dropped: u64,
+    /// Connections closed for falling behind
+    pub slow_disconnects: u64,
+}
 
 /// Connection info
 #[derive(Debug)]
@@ -45,6 +94,18 @@ pub struct Dispatcher {
 
     /// RLS policies by collection
     rls_policies: RwLock<HashMap<String, RlsPolicy>>,
+
+    /// Queue bound and overflow policy
+    config: DispatcherConfig,
+
+    /// Events queued for delivery
+    delivered: AtomicU64,
+
+    /// Events dropped on full queues
+    dropped: AtomicU64,
+
+    /// Connections closed for falling behind
+    slow_disconnects: AtomicU64,
 }
 
 impl Default for Dispatcher {
@@ -56,10 +117,28 @@ impl Default for Dispatcher {
 impl Dispatcher {
     /// Create a new dispatcher
     pub fn new(subscriptions: Arc<SubscriptionRegistry>) -> Self {
+        Self::with_config(subscriptions, DispatcherConfig::default())
+    }
+
+    /// Create a dispatcher with an explicit queue bound and overflow policy
+    pub fn with_config(subscriptions: Arc<SubscriptionRegistry>, config: DispatcherConfig) -> Self {
         Self {
             connections: RwLock::new(HashMap::new()),
             subscriptions,
             rls_policies: RwLock::new(HashMap::new()),
+            config,
+            delivered: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            slow_disconnects: AtomicU64::new(0),
+        }
+    }
+
+    /// Delivery counters
+    pub fn stats(&self) -> DispatcherStats {
+        DispatcherStats {
+            delivered: self.delivered.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            slow_disconnects: self.slow_disconnects.load(Ordering::Relaxed),
         }
     }
 
@@ -72,7 +151,7 @@ impl Dispatcher {
 
     /// Add a connection
     pub fn connect(&self, connection_id: String, rls_context: RlsContext) -> EventReceiver {
-        let (tx, rx) = mpsc::unbounded_channel();
+        let (tx, rx) = mpsc::channel(self.config.queue_capacity.max(1));
 
         let connection = Connection {
             id: connection_id.clone(),
@@ -114,6 +193,7 @@ impl Dispatcher {
             Ok(c) => c,
             Err(_) => return result,
         };
+        let mut slow = Vec::new();
 
         // Get RLS policy for this collection
         let rls_policy = self
@@ -133,18 +213,50 @@ impl Dispatcher {
             // Get connection
             if let Some(conn) = connections.get(&subscription.connection_id) {
                 // Send event (non-blocking)
-                match conn.sender.send(event.clone()) {
+                match conn.sender.try_send(event.clone()) {
                     Ok(_) => result.delivered += 1,
-                    Err(_) => result.failed += 1,
+                    Err(TrySendError::Full(_)) => {
+                        result.dropped += 1;
+                        if self.config.overflow == OverflowPolicy::Disconnect {
+                            slow.push(conn.id.clone());
+                        }
+                    }
+                    Err(TrySendError::Closed(_)) => result.failed += 1,
                 }
             } else {
                 result.failed += 1;
             }
         }
+        drop(connections);
 
+        // Closing the sender ends the receiver, which closes the socket
+        slow.dedup();
+        for connection_id in &slow {
+            self.disconnect(connection_id);
+        }
+
+        self.delivered
+            .fetch_add(result.delivered as u64, Ordering::Relaxed);
+        self.dropped
+            .fetch_add(result.dropped as u64, Ordering::Relaxed);
+        self.slow_disconnects
+            .fetch_add(slow.len() as u64, Ordering::Relaxed);
         result
     }
 
+    /// Check if an event passes the collection's RLS policy for `context`
+    ///
+    /// Used for events delivered outside `dispatch`, such as replay on
+    /// resume.
+    pub fn permits(&self, context: &RlsContext, event: &DatabaseEvent) -> bool {
+        let rls_policy = self
+            .rls_policies
+            .read()
+            .ok()
+            .and_then(|p| p.get(&event.collection).cloned());
+        self.check_rls(context, &rls_policy, event)
+    }
+
     /// Check if event passes RLS for a given context
     fn check_rls(
         &self,
@@ -201,6 +313,8 @@ pub struct DispatchResult {
     pub delivered: usize,
     /// Number of events filtered by RLS
     pub filtered: usize,
+    /// Number of events dropped on full queues
+    pub dropped: usize,
     /// Number of failed deliveries
     pub failed: usize,
 }
@@ -259,6 +373,79 @@ mod tests {
         assert_eq!(received.collection, "posts");
     }
 
+    fn post(sequence: u64) -> DatabaseEvent {
+        DatabaseEvent::insert(
+            sequence,
+            "posts".to_string(),
+            sequence.to_string(),
+            json!({}),
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_drops_events() {
+        let registry = Arc::new(SubscriptionRegistry::new());
+        let config = DispatcherConfig {
+            queue_capacity: 2,
+            overflow: OverflowPolicy::DropEvent,
+        };
+        let dispatcher = Dispatcher::with_config(Arc::clone(&registry), config);
+        let mut rx = dispatcher.connect("conn-1".to_string(), RlsContext::anonymous());
+        let sub = Subscription::new(
+            "conn-1".to_string(),
+            "posts".to_string(),
+            RlsContext::anonymous(),
+        );
+        registry.subscribe(sub).unwrap();
+
+        for sequence in 1..=3 {
+            dispatcher.dispatch(&post(sequence));
+        }
+        assert_eq!(
+            dispatcher.stats(),
+            DispatcherStats {
+                delivered: 2,
+                dropped: 1,
+                slow_disconnects: 0,
+            }
+        );
+
+        // The connection stays open and receives events once drained
+        assert_eq!(rx.recv().await.unwrap().sequence, 1);
+        assert_eq!(rx.recv().await.unwrap().sequence, 2);
+        assert_eq!(dispatcher.dispatch(&post(4)).delivered, 1);
+        assert_eq!(rx.recv().await.unwrap().sequence, 4);
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_disconnects_slow_consumer() {
+        let registry = Arc::new(SubscriptionRegistry::new());
+        let config = DispatcherConfig {
+            queue_capacity: 1,
+            overflow: OverflowPolicy::Disconnect,
+        };
+        let dispatcher = Dispatcher::with_config(Arc::clone(&registry), config);
+        let mut rx = dispatcher.connect("conn-1".to_string(), RlsContext::anonymous());
+        let sub = Subscription::new(
+            "conn-1".to_string(),
+            "posts".to_string(),
+            RlsContext::anonymous(),
+        );
+        registry.subscribe(sub).unwrap();
+
+        dispatcher.dispatch(&post(1));
+        let result = dispatcher.dispatch(&post(2));
+        assert_eq!(result.dropped, 1);
+        assert_eq!(dispatcher.stats().slow_disconnects, 1);
+        assert_eq!(dispatcher.connection_count(), 0);
+        assert!(registry.is_empty());
+
+        // Queued events drain, then the receiver ends
+        assert_eq!(rx.recv().await.unwrap().sequence, 1);
+        assert!(rx.recv().await.is_none());
+    }
+
     #[tokio::test]
     async fn test_rls_filtering() {
         let registry = Arc::new(SubscriptionRegistry::new());
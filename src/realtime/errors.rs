@@ -40,6 +40,10 @@ pub enum RealtimeError {
     #[error("Too many subscriptions (max: {0})")]
     TooManySubscriptions(usize),
 
+    /// Events after the resume cursor are no longer buffered
+    #[error("Resume gap: cannot resume from event {requested}, oldest buffered event is {oldest}")]
+    ResumeGap { requested: u64, oldest: u64 },
+
     // ==================
     // Authorization Errors
     // ==================
@@ -103,6 +107,7 @@ impl RealtimeError {
             RealtimeError::InvalidTopic(_) => 4000,
             RealtimeError::SubscriptionNotFound(_) => 4001,
             RealtimeError::TooManySubscriptions(_) => 4002,
+            RealtimeError::ResumeGap { .. } => 4005,
             RealtimeError::Unauthorized => 4003,
             RealtimeError::AuthenticationRequired => 4004,
             RealtimeError::ChannelNotFound(_) => 4010,
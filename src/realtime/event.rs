@@ -170,6 +170,10 @@ pub struct BroadcastEvent {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sender_id: Option<Uuid>,
 
+    /// Position in the event log (durable channels only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<u64>,
+
     /// Timestamp
     pub timestamp: DateTime<Utc>,
 }
@@ -182,6 +186,7 @@ impl BroadcastEvent {
             event,
             payload,
             sender_id,
+            sequence: None,
             timestamp: Utc::now(),
         }
     }
@@ -208,7 +213,7 @@ pub struct PresenceEvent {
     /// Channel name
     pub channel: String,
 
-    /// Event type (join, leave, sync)
+    /// Event type (join, leave, update, sync)
     pub event: PresenceEventType,
 
     /// User state(s)
@@ -224,6 +229,8 @@ pub struct PresenceEvent {
 pub enum PresenceEventType {
     Join,
     Leave,
+    /// A tracked user changed their state
+    Update,
     Sync,
 }
 
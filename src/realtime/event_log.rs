@@ -4,26 +4,48 @@
 //!
 //! ## Invariant: RT-E1
 //! Same WAL → Same events. Event generation is reproducible.
+//!
+//! ## Resuming
+//! Event sequence numbers are the cursor clients resume from. The log
+//! buffers the last `max_events` events; a client that reconnects with the
+//! last sequence it saw gets every later event it subscribes to. If some of
+//! those were already evicted, resuming fails with `ResumeGap` instead of
+//! silently skipping them, and the client must resynchronize by querying.
+//!
+//! ## Durable Broadcasts
+//! Messages on durable broadcast channels are kept for
+//! `broadcast_retention`, numbered by their own sequence, so subscribers
+//! that join late still receive what was sent within the window. Older
+//! messages are discarded silently: broadcasts are not change data.
 
 use std::collections::VecDeque;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::RwLock;
 
+use chrono::{Duration, Utc};
 use serde_json::Value;
 use uuid::Uuid;
 
-use super::event::{DatabaseEvent, EventType};
+use super::errors::{RealtimeError, RealtimeResult};
+use super::event::{BroadcastEvent, DatabaseEvent, EventType};
+use super::subscription::Subscription;
 
 /// Configuration for the event log
 #[derive(Debug, Clone)]
 pub struct EventLogConfig {
-    /// Maximum number of events to keep in memory
+    /// Maximum number of events to keep in memory (the resume window)
     pub max_events: usize,
+
+    /// How long durable broadcast messages are kept
+    pub broadcast_retention: Duration,
 }
 
 impl Default for EventLogConfig {
     fn default() -> Self {
-        Self { max_events: 10_000 }
+        Self {
+            max_events: 10_000,
+            broadcast_retention: Duration::minutes(5),
+        }
     }
 }
 
@@ -40,6 +62,12 @@ pub struct EventLog {
 
     /// Ring buffer of recent events
     events: RwLock<VecDeque<DatabaseEvent>>,
+
+    /// Next broadcast sequence number
+    next_broadcast_sequence: AtomicU64,
+
+    /// Retained durable broadcasts, oldest first
+    broadcasts: RwLock<VecDeque<BroadcastEvent>>,
 }
 
 impl Default for EventLog {
@@ -56,6 +84,8 @@ impl EventLog {
             config,
             next_sequence: AtomicU64::new(1),
             events: RwLock::new(VecDeque::with_capacity(capacity)),
+            next_broadcast_sequence: AtomicU64::new(1),
+            broadcasts: RwLock::new(VecDeque::new()),
         }
     }
 
@@ -150,6 +180,97 @@ impl EventLog {
         }
     }
 
+    /// Get the events since a given sequence that `subscription` receives
+    ///
+    /// Applies the subscription's collection, record, predicate and event
+    /// type filters server-side, so replay delivers exactly what live
+    /// dispatch would.
+    pub fn events_matching(
+        &self,
+        subscription: &Subscription,
+        since_sequence: u64,
+    ) -> Vec<DatabaseEvent> {
+        if let Ok(events) = self.events.read() {
+            events
+                .iter()
+                .filter(|e| e.sequence > since_sequence && subscription.matches(e))
+                .cloned()
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Events after `resume_from` that `subscription` receives
+    ///
+    /// `resume_from` is the sequence of the last event the client saw (0
+    /// for none).
+    ///
+    /// # Errors
+    ///
+    /// `ResumeGap` if any event after `resume_from` has been evicted from
+    /// the buffer, or `resume_from` was never issued by this log.
+    pub fn resume(
+        &self,
+        subscription: &Subscription,
+        resume_from: u64,
+    ) -> RealtimeResult<Vec<DatabaseEvent>> {
+        let events = self
+            .events
+            .read()
+            .map_err(|_| RealtimeError::Internal("Lock poisoned".into()))?;
+        let next = self.next_sequence();
+        let oldest = events.front().map_or(next, |e| e.sequence);
+        if resume_from.saturating_add(1) < oldest || resume_from >= next {
+            return Err(RealtimeError::ResumeGap {
+                requested: resume_from,
+                oldest,
+            });
+        }
+        Ok(events
+            .iter()
+            .filter(|e| e.sequence > resume_from && subscription.matches(e))
+            .cloned()
+            .collect())
+    }
+
+    /// Retain a durable broadcast, returning it with its sequence
+    ///
+    /// Expired broadcasts are discarded; at most `max_events` are kept.
+    pub fn record_broadcast(&self, mut event: BroadcastEvent) -> BroadcastEvent {
+        event.sequence = Some(self.next_broadcast_sequence.fetch_add(1, Ordering::SeqCst));
+        if let Ok(mut broadcasts) = self.broadcasts.write() {
+            self.expire_broadcasts(&mut broadcasts);
+            broadcasts.push_back(event.clone());
+            while broadcasts.len() > self.config.max_events {
+                broadcasts.pop_front();
+            }
+        }
+        event
+    }
+
+    /// Retained broadcasts on `channel` with a sequence above `since_sequence`
+    pub fn broadcasts_since(&self, channel: &str, since_sequence: u64) -> Vec<BroadcastEvent> {
+        if let Ok(mut broadcasts) = self.broadcasts.write() {
+            self.expire_broadcasts(&mut broadcasts);
+            broadcasts
+                .iter()
+                .filter(|e| e.channel == channel && e.sequence > Some(since_sequence))
+                .cloned()
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Drop broadcasts older than the retention window
+    fn expire_broadcasts(&self, broadcasts: &mut VecDeque<BroadcastEvent>) {
+        let cutoff = Utc::now() - self.config.broadcast_retention;
+        while broadcasts.front().is_some_and(|e| e.timestamp < cutoff) {
+            broadcasts.pop_front();
+        }
+    }
+
     /// Get the most recent events (up to limit)
     pub fn recent_events(&self, limit: usize) -> Vec<DatabaseEvent> {
         if let Ok(events) = self.events.read() {
@@ -260,9 +381,97 @@ mod tests {
         assert!(events.iter().all(|e| e.collection == "posts"));
     }
 
+    #[test]
+    fn test_events_matching_subscription() {
+        use crate::auth::rls::RlsContext;
+
+        let log = EventLog::default();
+        for (collection, id, status) in [
+            ("posts", "1", "draft"),
+            ("posts", "2", "published"),
+            ("comments", "2", "published"),
+            ("posts", "3", "published"),
+        ] {
+            log.record_insert(
+                collection.to_string(),
+                id.to_string(),
+                serde_json::json!({ "status": status }),
+                None,
+            );
+        }
+
+        let published = Subscription::new(
+            "conn-1".to_string(),
+            "posts".to_string(),
+            RlsContext::anonymous(),
+        )
+        .with_equality("status", serde_json::json!("published"));
+        let ids: Vec<String> = log
+            .events_matching(&published, 0)
+            .into_iter()
+            .map(|e| e.record_id)
+            .collect();
+        assert_eq!(ids, vec!["2", "3"]);
+
+        let one = published.with_record_id("3");
+        assert_eq!(log.events_matching(&one, 0).len(), 1);
+        assert!(log.events_matching(&one, 4).is_empty());
+    }
+
+    #[test]
+    fn test_resume_within_window_and_gap() {
+        use crate::auth::rls::RlsContext;
+
+        let log = EventLog::new(EventLogConfig {
+            max_events: 3,
+            ..EventLogConfig::default()
+        });
+        let sub = Subscription::new(
+            "conn-1".to_string(),
+            "posts".to_string(),
+            RlsContext::anonymous(),
+        );
+        assert!(log.resume(&sub, 0).unwrap().is_empty());
+
+        for i in 0..5 {
+            log.record_insert(
+                "posts".to_string(),
+                i.to_string(),
+                serde_json::json!({}),
+                None,
+            );
+        }
+
+        // Events 3..=5 are buffered: resuming after 2 or later loses nothing
+        let resumed: Vec<u64> = log
+            .resume(&sub, 2)
+            .unwrap()
+            .iter()
+            .map(|e| e.sequence)
+            .collect();
+        assert_eq!(resumed, vec![3, 4, 5]);
+        assert!(log.resume(&sub, 5).unwrap().is_empty());
+
+        // Event 2 was evicted, and 6 was never issued
+        assert!(matches!(
+            log.resume(&sub, 1),
+            Err(RealtimeError::ResumeGap {
+                requested: 1,
+                oldest: 3
+            })
+        ));
+        assert!(matches!(
+            log.resume(&sub, 6),
+            Err(RealtimeError::ResumeGap { .. })
+        ));
+    }
+
     #[test]
     fn test_ring_buffer_capacity() {
-        let log = EventLog::new(EventLogConfig { max_events: 5 });
+        let log = EventLog::new(EventLogConfig {
+            max_events: 5,
+            ..EventLogConfig::default()
+        });
 
         for i in 0..10 {
             log.record_insert(
@@ -310,6 +519,44 @@ mod tests {
         assert_eq!(delete.event_type, EventType::Delete);
     }
 
+    #[test]
+    fn test_broadcast_retention() {
+        let log = EventLog::default();
+        let mut stale =
+            BroadcastEvent::new("chat".to_string(), "msg".to_string(), Value::Null, None);
+        stale.timestamp = Utc::now() - Duration::minutes(10);
+        log.record_broadcast(stale);
+
+        let first = log.record_broadcast(BroadcastEvent::new(
+            "chat".to_string(),
+            "msg".to_string(),
+            serde_json::json!({"n": 1}),
+            None,
+        ));
+        log.record_broadcast(BroadcastEvent::new(
+            "other".to_string(),
+            "msg".to_string(),
+            Value::Null,
+            None,
+        ));
+        let second = log.record_broadcast(BroadcastEvent::new(
+            "chat".to_string(),
+            "msg".to_string(),
+            serde_json::json!({"n": 2}),
+            None,
+        ));
+        assert_eq!(first.sequence, Some(2));
+
+        // The stale message is gone; later ones are per channel
+        let missed = log.broadcasts_since("chat", 0);
+        let sequences: Vec<Option<u64>> = missed.iter().map(|e| e.sequence).collect();
+        assert_eq!(sequences, vec![first.sequence, second.sequence]);
+        assert_eq!(log.broadcasts_since("chat", 2).len(), 1);
+
+        // Broadcasts do not enter the database event stream
+        assert!(log.is_empty());
+    }
+
     #[test]
     fn test_deterministic_transformation() {
         // RT-E1: Same WAL → Same events
@@ -24,10 +24,10 @@ pub mod subscription;
 pub mod websocket;
 
 pub use broadcast::BroadcastChannel;
-pub use dispatcher::Dispatcher;
+pub use dispatcher::{Dispatcher, DispatcherConfig, DispatcherStats, OverflowPolicy};
 pub use errors::{RealtimeError, RealtimeResult};
 pub use event::{BroadcastEvent, DatabaseEvent, EventType};
 pub use event_log::EventLog;
-pub use presence::PresenceTracker;
+pub use presence::{PresenceChannels, PresenceRule, PresenceTracker};
 pub use subscription::{Subscription, SubscriptionFilter, SubscriptionRegistry};
 pub use websocket::{WebSocketConfig, WebSocketServer};
@@ -4,15 +4,22 @@
 //!
 //! ## Invariant: RT-P1
 //! Presence is eventually consistent, not immediately consistent.
+//!
+//! ## Authorization
+//! `PresenceChannels` gates joins with a per-channel `PresenceRule`.
+//! Anonymous connections never join; by default any authenticated user
+//! may, and a rule can instead require a JWT claim.
 
 use std::collections::HashMap;
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use uuid::Uuid;
 
+use crate::auth::rls::RlsContext;
+
 use super::errors::{RealtimeError, RealtimeResult};
 use super::event::{PresenceEvent, PresenceEventType};
 
@@ -137,6 +144,30 @@ impl PresenceTracker {
         })
     }
 
+    /// Replace a tracked user's state
+    pub fn update(&self, connection_id: &str, metadata: Value) -> RealtimeResult<PresenceEvent> {
+        let mut states = self
+            .states
+            .write()
+            .map_err(|_| RealtimeError::Internal("Lock poisoned".into()))?;
+
+        let state = states
+            .get_mut(connection_id)
+            .ok_or(RealtimeError::NotTracking)?;
+        state.metadata = metadata.clone();
+        state.heartbeat();
+
+        Ok(PresenceEvent {
+            channel: self.channel.clone(),
+            event: PresenceEventType::Update,
+            state: serde_json::json!({
+                "user_id": state.user_id.to_string(),
+                "metadata": metadata,
+            }),
+            timestamp: Utc::now(),
+        })
+    }
+
     /// Untrack a user (leave)
     pub fn untrack(&self, connection_id: &str) -> RealtimeResult<Option<PresenceEvent>> {
         let mut states = self
@@ -238,6 +269,167 @@ impl PresenceTracker {
             .map(|s| s.contains_key(connection_id))
             .unwrap_or(false)
     }
+
+    /// Connections currently tracked
+    pub fn connection_ids(&self) -> Vec<String> {
+        self.states
+            .read()
+            .map(|s| s.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Who may join a presence channel
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PresenceRule {
+    /// Any authenticated user
+    #[default]
+    Authenticated,
+
+    /// Users whose JWT carries `claim`, equal to one of `values` if any
+    Claim {
+        claim: String,
+        #[serde(default)]
+        values: Vec<Value>,
+    },
+}
+
+impl PresenceRule {
+    /// Whether `context` may join (the service role always may)
+    pub fn permits(&self, context: &RlsContext) -> bool {
+        if context.is_service_role {
+            return true;
+        }
+        if !context.is_authenticated {
+            return false;
+        }
+        match self {
+            PresenceRule::Authenticated => true,
+            PresenceRule::Claim { claim, values } => match context.claims.get(claim) {
+                Some(value) => values.is_empty() || values.contains(value),
+                None => false,
+            },
+        }
+    }
+}
+
+/// Presence trackers by channel, each created on its first join
+#[derive(Debug, Default)]
+pub struct PresenceChannels {
+    /// Configuration for new trackers
+    config: PresenceConfig,
+
+    /// Join rules by channel (`PresenceRule::default()` otherwise)
+    rules: RwLock<HashMap<String, PresenceRule>>,
+
+    /// Trackers by channel
+    trackers: RwLock<HashMap<String, Arc<PresenceTracker>>>,
+}
+
+impl PresenceChannels {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create with custom tracker config
+    pub fn with_config(config: PresenceConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    /// Set the rule for joining `channel`; existing members stay
+    pub fn set_rule(&self, channel: &str, rule: PresenceRule) {
+        if let Ok(mut rules) = self.rules.write() {
+            rules.insert(channel.to_string(), rule);
+        }
+    }
+
+    /// Rule for joining `channel`
+    pub fn rule(&self, channel: &str) -> PresenceRule {
+        self.rules
+            .read()
+            .ok()
+            .and_then(|rules| rules.get(channel).cloned())
+            .unwrap_or_default()
+    }
+
+    /// Join `channel` if `context` is permitted, returning the join diff
+    pub fn join(
+        &self,
+        channel: &str,
+        context: &RlsContext,
+        connection_id: &str,
+        metadata: Value,
+    ) -> RealtimeResult<PresenceEvent> {
+        let user_id = context
+            .user_id
+            .ok_or(RealtimeError::AuthenticationRequired)?;
+        if !self.rule(channel).permits(context) {
+            return Err(RealtimeError::Unauthorized);
+        }
+
+        let tracker = {
+            let mut trackers = self
+                .trackers
+                .write()
+                .map_err(|_| RealtimeError::Internal("Lock poisoned".into()))?;
+            Arc::clone(trackers.entry(channel.to_string()).or_insert_with(|| {
+                Arc::new(PresenceTracker::with_config(
+                    channel.to_string(),
+                    self.config.clone(),
+                ))
+            }))
+        };
+        tracker.track(user_id, connection_id.to_string(), metadata)
+    }
+
+    /// Replace a member's state, returning the update diff
+    pub fn update(
+        &self,
+        channel: &str,
+        connection_id: &str,
+        metadata: Value,
+    ) -> RealtimeResult<PresenceEvent> {
+        self.tracker(channel)
+            .ok_or(RealtimeError::NotTracking)?
+            .update(connection_id, metadata)
+    }
+
+    /// Leave `channel`, returning the leave diff if the connection was a member
+    pub fn leave(
+        &self,
+        channel: &str,
+        connection_id: &str,
+    ) -> RealtimeResult<Option<PresenceEvent>> {
+        match self.tracker(channel) {
+            Some(tracker) => tracker.untrack(connection_id),
+            None => Ok(None),
+        }
+    }
+
+    /// Leave every channel, e.g. on disconnect
+    pub fn leave_all(&self, connection_id: &str) -> Vec<PresenceEvent> {
+        let trackers: Vec<Arc<PresenceTracker>> = match self.trackers.read() {
+            Ok(trackers) => trackers.values().cloned().collect(),
+            Err(_) => return Vec::new(),
+        };
+        trackers
+            .iter()
+            .filter_map(|tracker| tracker.untrack(connection_id).ok().flatten())
+            .collect()
+    }
+
+    /// Tracker for `channel`, if anyone has joined it
+    pub fn tracker(&self, channel: &str) -> Option<Arc<PresenceTracker>> {
+        self.trackers
+            .read()
+            .ok()
+            .and_then(|trackers| trackers.get(channel).cloned())
+    }
 }
 
 #[cfg(test)]
@@ -317,6 +509,81 @@ mod tests {
         assert!(!state.is_stale(Duration::seconds(60)));
     }
 
+    #[test]
+    fn test_update_emits_diff() {
+        let tracker = PresenceTracker::new("lobby".to_string());
+        tracker
+            .track(
+                Uuid::new_v4(),
+                "conn-1".to_string(),
+                json!({"typing": false}),
+            )
+            .unwrap();
+
+        let event = tracker.update("conn-1", json!({"typing": true})).unwrap();
+        assert_eq!(event.event, PresenceEventType::Update);
+        assert_eq!(event.state["metadata"], json!({"typing": true}));
+        assert!(matches!(
+            tracker.update("conn-2", json!({})),
+            Err(RealtimeError::NotTracking)
+        ));
+    }
+
+    #[test]
+    fn test_join_requires_permitted_claim() {
+        let channels = PresenceChannels::new();
+        channels.set_rule(
+            "staff",
+            PresenceRule::Claim {
+                claim: "role".to_string(),
+                values: vec![json!("admin")],
+            },
+        );
+
+        // Anonymous connections never join
+        assert!(matches!(
+            channels.join("lobby", &RlsContext::anonymous(), "conn-0", json!({})),
+            Err(RealtimeError::AuthenticationRequired)
+        ));
+
+        // Any authenticated user joins a channel without a rule
+        let mut user = RlsContext::authenticated(Uuid::new_v4());
+        channels.join("lobby", &user, "conn-1", json!({})).unwrap();
+
+        // A missing or different claim is refused
+        assert!(matches!(
+            channels.join("staff", &user, "conn-1", json!({})),
+            Err(RealtimeError::Unauthorized)
+        ));
+        user.claims.insert("role".to_string(), json!("member"));
+        assert!(channels.join("staff", &user, "conn-1", json!({})).is_err());
+        assert!(channels.tracker("staff").is_none());
+
+        user.claims.insert("role".to_string(), json!("admin"));
+        let event = channels.join("staff", &user, "conn-1", json!({})).unwrap();
+        assert_eq!(event.event, PresenceEventType::Join);
+        assert_eq!(channels.tracker("staff").unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_leave_all() {
+        let channels = PresenceChannels::new();
+        let user = RlsContext::authenticated(Uuid::new_v4());
+        channels.join("a", &user, "conn-1", json!({})).unwrap();
+        channels.join("b", &user, "conn-1", json!({})).unwrap();
+        channels.join("b", &user, "conn-2", json!({})).unwrap();
+
+        let events = channels.leave_all("conn-1");
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| e.event == PresenceEventType::Leave));
+        assert_eq!(channels.tracker("a").unwrap().count(), 0);
+        assert_eq!(
+            channels.tracker("b").unwrap().connection_ids(),
+            vec!["conn-2".to_string()]
+        );
+        assert!(channels.leave("b", "conn-1").unwrap().is_none());
+    }
+
     #[test]
     fn test_is_tracked() {
         let tracker = PresenceTracker::new("lobby".to_string());
@@ -1,6 +1,12 @@
 //! # Subscription Management
 //!
 //! Client subscription registry and filtering.
+//!
+//! A subscription receives only the events of its collection that pass
+//! every server-side filter: event types, a single record id, equality
+//! predicates (`planner::Predicate`, evaluated like query filters) and
+//! comparison filters. Filters see the new data of inserts and updates and
+//! the old data of deletes.
 
 use std::collections::{HashMap, HashSet};
 use std::sync::RwLock;
@@ -11,6 +17,8 @@ use uuid::Uuid;
 use super::errors::{RealtimeError, RealtimeResult};
 use super::event::DatabaseEvent;
 use crate::auth::rls::RlsContext;
+use crate::executor::PredicateFilter;
+use crate::planner::Predicate;
 
 /// Filter operator for subscription predicates
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -110,6 +118,12 @@ pub struct Subscription {
     /// Event types to subscribe to (None = all)
     pub event_types: Option<HashSet<String>>,
 
+    /// Only events for this record (None = all records)
+    pub record_id: Option<String>,
+
+    /// Equality predicates the record data must satisfy
+    pub predicates: Vec<Predicate>,
+
     /// Filters to apply
     pub filters: Vec<SubscriptionFilter>,
 
@@ -129,6 +143,8 @@ impl Subscription {
             topic,
             collection,
             event_types: None,
+            record_id: None,
+            predicates: Vec::new(),
             filters: Vec::new(),
             rls_context,
         }
@@ -146,6 +162,18 @@ impl Subscription {
         self
     }
 
+    /// Only receive events for one record
+    pub fn with_record_id(mut self, record_id: impl Into<String>) -> Self {
+        self.record_id = Some(record_id.into());
+        self
+    }
+
+    /// Only receive events whose record has `field` equal to `value`
+    pub fn with_equality(mut self, field: impl Into<String>, value: serde_json::Value) -> Self {
+        self.predicates.push(Predicate::eq(field, value));
+        self
+    }
+
     /// Check if an event matches this subscription
     pub fn matches(&self, event: &DatabaseEvent) -> bool {
         // Check collection
@@ -160,6 +188,22 @@ impl Subscription {
             }
         }
 
+        // Check record
+        if let Some(ref record_id) = self.record_id {
+            if &event.record_id != record_id {
+                return false;
+            }
+        }
+
+        // Check equality predicates
+        if !self.predicates.is_empty() {
+            let data = event.new_data.as_ref().or(event.old_data.as_ref());
+            match data {
+                Some(data) if PredicateFilter::matches(data, &self.predicates) => {}
+                _ => return false,
+            }
+        }
+
         // Check filters
         for filter in &self.filters {
             if !filter.matches(event) {
@@ -446,6 +490,45 @@ mod tests {
         assert!(!sub.matches(&other_event));
     }
 
+    #[test]
+    fn test_subscription_record_and_equality_filters() {
+        let sub = Subscription::new("conn-1".to_string(), "posts".to_string(), create_test_rls())
+            .with_record_id("1")
+            .with_equality("status", json!("published"));
+
+        let published = |id: &str| {
+            DatabaseEvent::insert(
+                1,
+                "posts".to_string(),
+                id.to_string(),
+                json!({"status": "published"}),
+                None,
+            )
+        };
+        assert!(sub.matches(&published("1")));
+        assert!(!sub.matches(&published("2")));
+
+        let draft = DatabaseEvent::update(
+            2,
+            "posts".to_string(),
+            "1".to_string(),
+            json!({"status": "published"}),
+            json!({"status": "draft"}),
+            None,
+        );
+        assert!(!sub.matches(&draft));
+
+        // Deletes are matched on the data that was removed
+        let deleted = DatabaseEvent::delete(
+            3,
+            "posts".to_string(),
+            "1".to_string(),
+            json!({"status": "published"}),
+            None,
+        );
+        assert!(sub.matches(&deleted));
+    }
+
     #[test]
     fn test_registry_subscribe_unsubscribe() {
         let registry = SubscriptionRegistry::new();
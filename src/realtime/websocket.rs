@@ -19,7 +19,9 @@ use uuid::Uuid;
 
 use super::dispatcher::{Dispatcher, EventReceiver};
 use super::errors::{RealtimeError, RealtimeResult};
-use super::event::DatabaseEvent;
+use super::event::{DatabaseEvent, PresenceEvent};
+use super::event_log::EventLog;
+use super::presence::PresenceChannels;
 use super::subscription::{Subscription, SubscriptionFilter};
 use crate::auth::rls::RlsContext;
 
@@ -48,7 +50,10 @@ pub struct WebSocketConfig {
     /// Heartbeat interval in seconds
     pub heartbeat_interval_secs: u64,
 
-    /// Connection timeout in seconds
+    /// Close connections silent for longer than this (seconds)
+    ///
+    /// Each heartbeat sends a WebSocket ping; any frame from the client,
+    /// including the pong, counts as activity.
     pub connection_timeout_secs: u64,
 }
 
@@ -68,10 +73,22 @@ impl Default for WebSocketConfig {
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ClientMessage {
     /// Subscribe to a channel
+    ///
+    /// Only events passing every given filter are delivered.
     Subscribe {
         channel: String,
         #[serde(default)]
         filter: Option<SubscriptionFilter>,
+        /// Only events for this record
+        #[serde(default)]
+        record_id: Option<String>,
+        /// Field values the record must equal
+        #[serde(default)]
+        equals: serde_json::Map<String, serde_json::Value>,
+        /// Sequence of the last event seen before reconnecting; buffered
+        /// events after it are replayed first
+        #[serde(default)]
+        resume_from: Option<u64>,
     },
 
     /// Unsubscribe from a channel
@@ -85,6 +102,16 @@ pub enum ClientMessage {
 
     /// Authentication
     Auth { token: String },
+
+    /// Join a presence channel, or replace this connection's state in it
+    Track {
+        channel: String,
+        #[serde(default)]
+        state: serde_json::Value,
+    },
+
+    /// Leave a presence channel
+    Untrack { channel: String },
 }
 
 /// WebSocket message to client
@@ -117,8 +144,14 @@ pub enum ServerMessage {
 
     /// System message
     System { message: String },
+
+    /// Presence diff (join, leave, update) or full state (sync)
+    Presence { event: PresenceEvent },
 }
 
+/// Outgoing message channels by connection ID
+type ConnectionMap = RwLock<HashMap<String, mpsc::Sender<ServerMessage>>>;
+
 /// Connection state
 struct ConnectionState {
     id: String,
@@ -131,8 +164,10 @@ struct ConnectionState {
 pub struct WebSocketServer {
     config: WebSocketConfig,
     dispatcher: Arc<Dispatcher>,
+    event_log: Option<Arc<EventLog>>,
+    presence: Arc<PresenceChannels>,
     shutdown_tx: broadcast::Sender<()>,
-    connections: Arc<RwLock<HashMap<String, mpsc::Sender<ServerMessage>>>>,
+    connections: Arc<ConnectionMap>,
 }
 
 impl WebSocketServer {
@@ -143,11 +178,30 @@ impl WebSocketServer {
         Self {
             config,
             dispatcher,
+            event_log: None,
+            presence: Arc::new(PresenceChannels::new()),
             shutdown_tx,
             connections: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
+    /// Replay buffered events from `event_log` to subscribers that resume
+    pub fn with_event_log(mut self, event_log: Arc<EventLog>) -> Self {
+        self.event_log = Some(event_log);
+        self
+    }
+
+    /// Share presence channels, e.g. with the HTTP presence endpoint
+    pub fn with_presence(mut self, presence: Arc<PresenceChannels>) -> Self {
+        self.presence = presence;
+        self
+    }
+
+    /// Presence channels of this server
+    pub fn presence(&self) -> &Arc<PresenceChannels> {
+        &self.presence
+    }
+
     /// Start the WebSocket server
     pub async fn run(&self) -> RealtimeResult<()> {
         let addr: SocketAddr = self
@@ -170,6 +224,8 @@ impl WebSocketServer {
                     match accept_result {
                         Ok((stream, peer_addr)) => {
                             let dispatcher = Arc::clone(&self.dispatcher);
+                            let event_log = self.event_log.clone();
+                            let presence = Arc::clone(&self.presence);
                             let connections = Arc::clone(&self.connections);
                             let config = self.config.clone();
 
@@ -178,6 +234,8 @@ impl WebSocketServer {
                                     stream,
                                     peer_addr,
                                     dispatcher,
+                                    event_log,
+                                    presence,
                                     connections,
                                     config,
                                 ).await {
@@ -206,7 +264,9 @@ impl WebSocketServer {
         stream: TcpStream,
         peer_addr: SocketAddr,
         dispatcher: Arc<Dispatcher>,
-        connections: Arc<RwLock<HashMap<String, mpsc::Sender<ServerMessage>>>>,
+        event_log: Option<Arc<EventLog>>,
+        presence: Arc<PresenceChannels>,
+        connections: Arc<ConnectionMap>,
         config: WebSocketConfig,
     ) -> RealtimeResult<()> {
         let ws_stream = accept_async(stream).await.map_err(|e| {
@@ -246,11 +306,16 @@ impl WebSocketServer {
         // Heartbeat interval
         let heartbeat_interval = tokio::time::Duration::from_secs(config.heartbeat_interval_secs);
         let mut heartbeat_timer = tokio::time::interval(heartbeat_interval);
+        let connection_timeout = tokio::time::Duration::from_secs(config.connection_timeout_secs);
+        let mut last_seen = tokio::time::Instant::now();
 
         loop {
             tokio::select! {
                 // Handle incoming WebSocket messages
                 msg = ws_receiver.next() => {
+                    if let Some(Ok(_)) = msg {
+                        last_seen = tokio::time::Instant::now();
+                    }
                     match msg {
                         Some(Ok(Message::Text(text))) => {
                             match serde_json::from_str::<ClientMessage>(&text) {
@@ -262,13 +327,22 @@ impl WebSocketServer {
                                         &mut event_receiver,
                                         &mut subscribed_channels,
                                         &dispatcher,
+                                        event_log.as_deref(),
+                                        &presence,
+                                        &connections,
                                         &msg_tx,
                                     ).await {
                                         Ok(()) => {}
                                         Err(e) => {
+                                            let code = match e {
+                                                RealtimeError::ResumeGap { .. } => "RESUME_GAP",
+                                                RealtimeError::Unauthorized
+                                                | RealtimeError::AuthenticationRequired => "UNAUTHORIZED",
+                                                _ => "PROCESSING_ERROR",
+                                            };
                                             let err_msg = ServerMessage::Error {
                                                 message: e.to_string(),
-                                                code: "PROCESSING_ERROR".to_string(),
+                                                code: code.to_string(),
                                             };
                                             let _ = msg_tx.send(err_msg).await;
                                         }
@@ -332,17 +406,41 @@ impl WebSocketServer {
                         std::future::pending().await
                     }
                 } => {
-                    if let Some(event) = event {
-                        let event_msg = ServerMessage::Event {
-                            channel: event.collection.clone(),
-                            event,
-                        };
-                        let _ = msg_tx.send(event_msg).await;
+                    match event {
+                        Some(event) => {
+                            let event_msg = ServerMessage::Event {
+                                channel: event.collection.clone(),
+                                event,
+                            };
+                            let _ = msg_tx.send(event_msg).await;
+                        }
+                        None => {
+                            // The dispatcher dropped us for falling behind
+                            log_info!("Slow consumer disconnected: {}", connection_id);
+                            let err_msg = ServerMessage::Error {
+                                message: "Event queue overflowed; reconnect and resume".to_string(),
+                                code: "SLOW_CONSUMER".to_string(),
+                            };
+                            if let Ok(json) = serde_json::to_string(&err_msg) {
+                                let _ = ws_sender.send(Message::Text(json)).await;
+                            }
+                            let _ = ws_sender.send(Message::Close(None)).await;
+                            break;
+                        }
                     }
                 }
 
-                // Periodic heartbeat
+                // Periodic heartbeat and liveness check
                 _ = heartbeat_timer.tick() => {
+                    if last_seen.elapsed() > connection_timeout {
+                        log_info!("Connection timed out: {}", connection_id);
+                        let _ = ws_sender.send(Message::Close(None)).await;
+                        break;
+                    }
+                    if let Err(e) = ws_sender.send(Message::Ping(Vec::new())).await {
+                        log_error!("Failed to send ping: {}", e);
+                        break;
+                    }
                     let heartbeat = ServerMessage::Heartbeat {
                         ref_id: None,
                         server_time: chrono::Utc::now().timestamp(),
@@ -358,12 +456,16 @@ impl WebSocketServer {
             let mut conns = connections.write().await;
             conns.remove(&connection_id);
         }
+        for event in presence.leave_all(&connection_id) {
+            Self::publish_presence(&presence, &connections, event).await;
+        }
 
         log_info!("Connection {} cleaned up", connection_id);
         Ok(())
     }
 
     /// Process a client message
+    #[allow(clippy::too_many_arguments)]
     async fn process_client_message(
         connection_id: &str,
         message: ClientMessage,
@@ -371,24 +473,63 @@ impl WebSocketServer {
         event_receiver: &mut Option<EventReceiver>,
         subscribed_channels: &mut Vec<String>,
         dispatcher: &Arc<Dispatcher>,
+        event_log: Option<&EventLog>,
+        presence: &PresenceChannels,
+        connections: &ConnectionMap,
         msg_tx: &mpsc::Sender<ServerMessage>,
     ) -> RealtimeResult<()> {
         match message {
-            ClientMessage::Subscribe { channel, filter: _ } => {
+            ClientMessage::Subscribe {
+                channel,
+                filter,
+                record_id,
+                equals,
+                resume_from,
+            } => {
                 // Connect to dispatcher if not already
                 if event_receiver.is_none() {
                     let rx = dispatcher.connect(connection_id.to_string(), rls_context.clone());
                     *event_receiver = Some(rx);
                 }
 
-                // Subscribe to channel
-                let subscription = Subscription::new(
+                // Subscribe to channel, filtered server-side
+                let mut subscription = Subscription::new(
                     connection_id.to_string(),
                     channel.clone(),
                     rls_context.clone(),
                 );
+                if let Some(filter) = filter {
+                    subscription = subscription.with_filter(filter);
+                }
+                if let Some(record_id) = record_id {
+                    subscription = subscription.with_record_id(record_id);
+                }
+                for (field, value) in equals {
+                    subscription = subscription.with_equality(field, value);
+                }
 
+                // Registered before replay, so no event falls between the
+                // two; an event may arrive twice (dedupe by sequence)
                 dispatcher.subscriptions.register(subscription.clone())?;
+                let missed = match resume_from {
+                    Some(from) => {
+                        let replayed = event_log
+                            .ok_or_else(|| {
+                                RealtimeError::ConfigError(
+                                    "Resuming is not enabled on this server".to_string(),
+                                )
+                            })
+                            .and_then(|log| log.resume(&subscription, from));
+                        match replayed {
+                            Ok(events) => events,
+                            Err(e) => {
+                                dispatcher.subscriptions.unsubscribe(&subscription.id)?;
+                                return Err(e);
+                            }
+                        }
+                    }
+                    None => Vec::new(),
+                };
                 subscribed_channels.push(channel.clone());
 
                 let response = ServerMessage::Subscribed {
@@ -396,6 +537,16 @@ impl WebSocketServer {
                     subscription_id: subscription.id.to_string(),
                 };
                 let _ = msg_tx.send(response).await;
+                for event in missed {
+                    if dispatcher.permits(rls_context, &event) {
+                        let _ = msg_tx
+                            .send(ServerMessage::Event {
+                                channel: channel.clone(),
+                                event,
+                            })
+                            .await;
+                    }
+                }
             }
 
             ClientMessage::Unsubscribe { channel } => {
@@ -436,11 +587,71 @@ impl WebSocketServer {
                     }
                 }
             }
+
+            ClientMessage::Track { channel, state } => {
+                let joining = !presence
+                    .tracker(&channel)
+                    .is_some_and(|tracker| tracker.is_tracked(connection_id));
+                let diff = if joining {
+                    presence.join(&channel, rls_context, connection_id, state)?
+                } else {
+                    presence.update(&channel, connection_id, state)?
+                };
+
+                // A new member gets the full state, then everyone the diff
+                if joining {
+                    if let Some(tracker) = presence.tracker(&channel) {
+                        let _ = msg_tx
+                            .send(ServerMessage::Presence {
+                                event: tracker.sync()?,
+                            })
+                            .await;
+                    }
+                }
+                Self::publish_presence(presence, connections, diff).await;
+            }
+
+            ClientMessage::Untrack { channel } => {
+                if let Some(diff) = presence.leave(&channel, connection_id)? {
+                    let _ = msg_tx
+                        .send(ServerMessage::Presence {
+                            event: diff.clone(),
+                        })
+                        .await;
+                    Self::publish_presence(presence, connections, diff).await;
+                }
+            }
         }
 
         Ok(())
     }
 
+    /// Send a presence diff to every member of its channel
+    async fn publish_presence(
+        presence: &PresenceChannels,
+        connections: &ConnectionMap,
+        event: PresenceEvent,
+    ) {
+        let Some(tracker) = presence.tracker(&event.channel) else {
+            return;
+        };
+        let members = tracker.connection_ids();
+        let senders: Vec<mpsc::Sender<ServerMessage>> = {
+            let conns = connections.read().await;
+            members
+                .iter()
+                .filter_map(|id| conns.get(id).cloned())
+                .collect()
+        };
+        for sender in senders {
+            let _ = sender
+                .send(ServerMessage::Presence {
+                    event: event.clone(),
+                })
+                .await;
+        }
+    }
+
     /// Shutdown the server
     pub fn shutdown(&self) {
         let _ = self.shutdown_tx.send(());
@@ -469,7 +680,14 @@ fn validate_token(token: &str) -> RealtimeResult<RlsContext> {
         Ok(claims) => {
             // Extract user_id from claims
             match JwtManager::get_user_id(&claims) {
-                Ok(user_id) => Ok(RlsContext::authenticated(user_id)),
+                Ok(user_id) => {
+                    // Claims are kept for presence rules
+                    let mut context = RlsContext::authenticated(user_id);
+                    if let Ok(serde_json::Value::Object(map)) = serde_json::to_value(&claims) {
+                        context.claims = map.into_iter().collect();
+                    }
+                    Ok(context)
+                }
                 Err(_) => {
                     // Token is valid but has no user_id claim - allow anonymous authenticated
                     Ok(RlsContext::anonymous())
@@ -505,6 +723,35 @@ mod tests {
             }
             _ => panic!("Wrong message type"),
         }
+
+        let json = r#"{"type": "subscribe", "channel": "users", "record_id": "u1", "equals": {"role": "admin"}}"#;
+        match serde_json::from_str(json).unwrap() {
+            ClientMessage::Subscribe {
+                record_id, equals, ..
+            } => {
+                assert_eq!(record_id.as_deref(), Some("u1"));
+                assert_eq!(equals["role"], "admin");
+            }
+            _ => panic!("Wrong message type"),
+        }
+    }
+
+    #[test]
+    fn test_presence_message_parse() {
+        let json = r#"{"type": "track", "channel": "lobby", "state": {"status": "online"}}"#;
+        match serde_json::from_str(json).unwrap() {
+            ClientMessage::Track { channel, state } => {
+                assert_eq!(channel, "lobby");
+                assert_eq!(state["status"], "online");
+            }
+            _ => panic!("Wrong message type"),
+        }
+
+        let json = r#"{"type": "untrack", "channel": "lobby"}"#;
+        assert!(matches!(
+            serde_json::from_str(json).unwrap(),
+            ClientMessage::Untrack { .. }
+        ));
     }
 
     #[test]
@@ -4,14 +4,19 @@
 //! actual WAL, Storage, Index, and Schema types used in the system.
 
 use std::path::Path;
+use std::sync::Arc;
 
-use crate::index::IndexManager;
-use crate::schema::SchemaLoader;
-use crate::storage::{StorageReader, StorageWriter};
-use crate::wal::{WalReader, WalRecord};
+use serde_json::Value;
+
+use crate::index::{DocumentInfo, IndexError, IndexManager, IndexResult, RangeScan, StorageSeek};
+use crate::schema::{SchemaDdl, SchemaLoader};
+use crate::storage::{
+    CollectionKeyring, MasterKeyring, StorageFormat, StorageReader, StorageWriter,
+};
+use crate::wal::{TornTail, WalReader, WalRecord};
 
 use super::errors::{RecoveryError, RecoveryResult};
-use super::replay::{StorageApply, WalRead};
+use super::replay::{SchemaDdlApply, StorageApply, WalRead};
 use super::startup::IndexRebuild;
 use super::verifier::{SchemaCheck, StorageRecordInfo, StorageScan};
 
@@ -34,6 +39,31 @@ impl WalRead for WalReader {
             RecoveryError::recovery_failed(format!("Failed to reset WAL reader: {}", e))
         })
     }
+
+    fn wal_len(&self) -> u64 {
+        WalReader::file_size(self)
+    }
+
+    fn resume_at(&mut self, offset: u64, last_sequence: u64) -> RecoveryResult<bool> {
+        WalReader::resume_at(self, offset, last_sequence).map_err(|e| {
+            RecoveryError::recovery_failed(format!("Failed to resume WAL replay: {}", e))
+        })
+    }
+
+    fn truncate_torn_tail(&mut self) -> RecoveryResult<Option<TornTail>> {
+        WalReader::truncate_torn_tail(self).map_err(|e| {
+            RecoveryError::recovery_failed(format!("Failed to truncate torn WAL tail: {}", e))
+        })
+    }
+
+    fn truncate_at(&mut self, offset: u64) -> RecoveryResult<()> {
+        WalReader::truncate_at(self, offset).map_err(|e| {
+            RecoveryError::recovery_failed(format!(
+                "Failed to truncate WAL at offset {}: {}",
+                offset, e
+            ))
+        })
+    }
 }
 
 // ============================================================================
@@ -59,6 +89,27 @@ impl RecoveryStorage {
         Ok(Self { writer, reader })
     }
 
+    /// Create a recovery storage adapter over storage that may be encrypted
+    /// under `keys` (see `StorageWriter::open_with_keys`)
+    pub fn open_with_keys(data_dir: &Path, keys: &MasterKeyring) -> RecoveryResult<Self> {
+        let writer =
+            StorageWriter::open_with_keys(data_dir, StorageFormat::V1, keys).map_err(|e| {
+                RecoveryError::recovery_failed(format!("Failed to open storage writer: {}", e))
+            })?;
+        let reader = StorageReader::open_with_keys(writer.path(), keys).map_err(|e| {
+            RecoveryError::recovery_failed(format!("Failed to open storage reader: {}", e))
+        })?;
+        Ok(Self { writer, reader })
+    }
+
+    /// Attach per-collection keys so replayed bodies are sealed like live
+    /// writes.
+    pub fn with_keyring(mut self, keyring: Arc<CollectionKeyring>) -> Self {
+        self.writer.set_keyring(Arc::clone(&keyring));
+        self.reader.set_keyring(keyring);
+        self
+    }
+
     /// Consume the adapter and return the underlying writer and reader
     pub fn into_parts(self) -> (StorageWriter, StorageReader) {
         (self.writer, self.reader)
@@ -76,27 +127,219 @@ impl StorageApply for RecoveryStorage {
 
 impl StorageScan for RecoveryStorage {
     fn scan_next(&mut self) -> RecoveryResult<Option<StorageRecordInfo>> {
-        match self.reader.read_next() {
+        StorageScan::scan_next(&mut self.reader)
+    }
+
+    fn reset(&mut self) -> RecoveryResult<()> {
+        StorageScan::reset(&mut self.reader)
+    }
+
+    fn skip_next(&mut self) -> RecoveryResult<Option<u64>> {
+        StorageScan::skip_next(&mut self.reader)
+    }
+}
+
+// ============================================================================
+// StorageScan implementation for StorageReader
+// ============================================================================
+
+/// Verification scan over storage alone, without opening it for writes
+impl StorageScan for StorageReader {
+    fn scan_next(&mut self) -> RecoveryResult<Option<StorageRecordInfo>> {
+        match self.read_next() {
             Ok(Some(record)) => Ok(Some(StorageRecordInfo {
                 document_id: record.document_id.clone(),
                 schema_id: record.schema_id.clone(),
                 schema_version: record.schema_version.clone(),
-                offset: self.reader.current_offset(),
+                offset: self.current_offset(),
                 is_tombstone: record.is_tombstone,
             })),
             Ok(None) => Ok(None),
             Err(e) => Err(RecoveryError::storage_corruption(
-                self.reader.current_offset(),
+                self.current_offset(),
                 e.to_string(),
             )),
         }
     }
 
     fn reset(&mut self) -> RecoveryResult<()> {
-        self.reader.reset().map_err(|e| {
+        StorageReader::reset(self).map_err(|e| {
             RecoveryError::recovery_failed(format!("Failed to reset storage reader: {}", e))
         })
     }
+
+    fn skip_next(&mut self) -> RecoveryResult<Option<u64>> {
+        StorageReader::skip_next(self)
+            .map_err(|e| RecoveryError::storage_corruption(self.current_offset(), e.to_string()))
+    }
+}
+
+// ============================================================================
+// Index storage adapter (implements index StorageScan + StorageSeek)
+// ============================================================================
+
+/// Storage adapter for rebuilding and restoring indexes.
+///
+/// Scanned records keep their stored `collection:document_id` keys. Bodies
+/// of encrypted collections are opened with the keyring; a body whose key
+/// was erased is indexed as empty, so only its primary key is indexed.
+pub struct IndexStorage<'a> {
+    reader: &'a mut StorageReader,
+    keyring: Option<Arc<CollectionKeyring>>,
+}
+
+impl<'a> IndexStorage<'a> {
+    /// Create an adapter over a storage reader
+    pub fn new(reader: &'a mut StorageReader) -> Self {
+        Self {
+            reader,
+            keyring: None,
+        }
+    }
+
+    /// Open bodies of encrypted collections during scans
+    pub fn with_keyring(mut self, keyring: Arc<CollectionKeyring>) -> Self {
+        self.keyring = Some(keyring);
+        self
+    }
+}
+
+impl crate::index::StorageScan for IndexStorage<'_> {
+    fn scan_next(&mut self) -> IndexResult<Option<DocumentInfo>> {
+        scan_indexed_record(self.reader, self.keyring.as_deref())
+    }
+
+    fn reset(&mut self) -> IndexResult<()> {
+        self.reader
+            .reset()
+            .map_err(|e| IndexError::build_failed(e.to_string()))
+    }
+
+    fn current_offset(&self) -> u64 {
+        self.reader.current_offset()
+    }
+}
+
+impl StorageSeek for IndexStorage<'_> {
+    fn seek(&mut self, offset: u64) -> IndexResult<()> {
+        self.reader
+            .scan_from(offset)
+            .map_err(|e| IndexError::build_failed(e.to_string()))
+    }
+
+    fn read_body(&mut self, offset: u64) -> IndexResult<Value> {
+        let record = self
+            .reader
+            .read_at(offset)
+            .map_err(|e| IndexError::data_corruption(offset, e.to_string()))?;
+        serde_json::from_slice(&record.document_body)
+            .map_err(|e| IndexError::data_corruption(offset, e.to_string()))
+    }
+
+    fn storage_len(&mut self) -> IndexResult<u64> {
+        self.reader
+            .file_len()
+            .map_err(|e| IndexError::build_failed(e.to_string()))
+    }
+}
+
+impl RangeScan for IndexStorage<'_> {
+    type Range = IndexRangeStorage;
+
+    fn record_offsets(&mut self) -> IndexResult<Vec<u64>> {
+        crate::index::StorageScan::reset(self)?;
+        let mut offsets = Vec::new();
+        loop {
+            let offset = self.reader.current_offset();
+            match self.reader.skip_next() {
+                Ok(Some(offset)) => offsets.push(offset),
+                Ok(None) => return Ok(offsets),
+                Err(e) => return Err(IndexError::data_corruption(offset, e.to_string())),
+            }
+        }
+    }
+
+    fn open_range(&self, start: u64, end: u64) -> IndexResult<Self::Range> {
+        let mut reader = self
+            .reader
+            .reopen()
+            .map_err(|e| IndexError::build_failed(e.to_string()))?;
+        reader
+            .scan_from(start)
+            .map_err(|e| IndexError::build_failed(e.to_string()))?;
+        Ok(IndexRangeStorage {
+            reader,
+            start,
+            end,
+            keyring: self.keyring.clone(),
+        })
+    }
+}
+
+/// Scanner over one record range of storage, with its own file handle.
+///
+/// Opened by `IndexStorage` for parallel index rebuilds.
+pub struct IndexRangeStorage {
+    reader: StorageReader,
+    start: u64,
+    end: u64,
+    keyring: Option<Arc<CollectionKeyring>>,
+}
+
+impl crate::index::StorageScan for IndexRangeStorage {
+    fn scan_next(&mut self) -> IndexResult<Option<DocumentInfo>> {
+        if self.reader.current_offset() >= self.end {
+            return Ok(None);
+        }
+        scan_indexed_record(&mut self.reader, self.keyring.as_deref())
+    }
+
+    fn reset(&mut self) -> IndexResult<()> {
+        self.reader
+            .scan_from(self.start)
+            .map_err(|e| IndexError::build_failed(e.to_string()))
+    }
+
+    fn current_offset(&self) -> u64 {
+        self.reader.current_offset()
+    }
+}
+
+/// Read the next record for indexing, opening encrypted bodies
+fn scan_indexed_record(
+    reader: &mut StorageReader,
+    keyring: Option<&CollectionKeyring>,
+) -> IndexResult<Option<DocumentInfo>> {
+    let offset = reader.current_offset();
+    let mut record = match reader.read_next() {
+        Ok(Some(record)) => record,
+        Ok(None) => return Ok(None),
+        Err(e) => return Err(IndexError::data_corruption(offset, e.to_string())),
+    };
+
+    let body = if record.is_tombstone {
+        Value::Null
+    } else {
+        let opened = match keyring {
+            Some(keyring) => keyring.decrypt_record(&mut record).is_ok(),
+            None => true,
+        };
+        if opened {
+            serde_json::from_slice(&record.document_body)
+                .map_err(|e| IndexError::data_corruption(offset, e.to_string()))?
+        } else {
+            Value::Null
+        }
+    };
+
+    Ok(Some(DocumentInfo {
+        document_id: record.document_id,
+        schema_id: record.schema_id,
+        schema_version: record.schema_version,
+        is_tombstone: record.is_tombstone,
+        body,
+        offset,
+    }))
 }
 
 // ============================================================================
@@ -110,8 +353,8 @@ impl IndexRebuild for IndexManager {
         // after recovery completes. The actual rebuild is a no-op here because
         // we don't have storage access at this point.
         //
-        // The full rebuild happens via IndexManager::rebuild_from_storage(&mut S)
-        // which requires a StorageScan. This is called separately with the scanner.
+        // The full rebuild happens via RecoveryManager::recover_indexes, which
+        // requires storage access and is called separately with IndexStorage.
         Ok(())
     }
 }
@@ -130,6 +373,23 @@ impl SchemaCheck for SchemaLoader {
     }
 }
 
+// ============================================================================
+// SchemaDdlApply implementation for SchemaLoader
+// ============================================================================
+
+impl SchemaDdlApply for SchemaLoader {
+    fn apply_schema_ddl(&mut self, ddl: &SchemaDdl) -> RecoveryResult<()> {
+        self.apply_ddl(ddl).map_err(|e| {
+            RecoveryError::recovery_failed(format!(
+                "Failed to apply schema DDL for '{}' version '{}': {}",
+                ddl.schema_id(),
+                ddl.schema_version(),
+                e
+            ))
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -4,31 +4,41 @@
 //!
 //! # Startup Sequence (strict order)
 //!
-//! 1. Load schemas via schema loader
+//! 1. Load schemas via schema loader, then apply WAL schema DDL records
 //! 2. Open WAL reader
 //! 3. Open document storage
-//! 4. Replay WAL from offset 0 sequentially
+//! 4. Replay WAL from offset 0 sequentially, or resume from the position
+//!    recorded by an interrupted recovery
 //! 5. Apply each WAL record via storage.apply_wal_record
 //! 6. After replay completes, call index.rebuild_from_storage
-//! 7. Run consistency verification
-//! 8. Enter serving state
+//! 7. Run consistency verification at the configured level
+//! 8. Restore indexes from the latest index checkpoint, patched with the
+//!    records stored after it, or rebuild them from storage
+//! 9. Enter serving state
 //!
 //! # Invariants
 //!
 //! - R1: WAL is single source of truth for recovery
-//! - R2: Sequential replay from byte 0
+//! - R2: Sequential replay from byte 0, or from a recorded position whose
+//!   records are all durable in storage
 //! - K2: Halt-on-corruption policy
 
 mod adapters;
 mod errors;
+mod progress;
 mod replay;
 mod startup;
 mod verifier;
 
-pub use adapters::RecoveryStorage;
+pub use adapters::{IndexRangeStorage, IndexStorage, RecoveryStorage};
 pub use errors::{RecoveryError, RecoveryErrorCode, RecoveryResult};
-pub use replay::{ReplayStats, StorageApply, WalRead, WalReplayer};
-pub use startup::{IndexRebuild, RecoveryManager, RecoveryState};
+pub use progress::{
+    LoggedProgress, RecoveryProgress, ReplayPosition, ReplayProgress, ReplayTracker,
+    DEFAULT_REPORT_INTERVAL, REPLAY_POSITION_FILE,
+};
+pub use replay::{ReplayStats, SchemaDdlApply, StorageApply, WalRead, WalReplayer};
+pub use startup::{IndexRebuild, IndexRecoveryPath, RecoveryManager, RecoveryState};
 pub use verifier::{
-    ConsistencyVerifier, SchemaCheck, StorageRecordInfo, StorageScan, VerificationStats,
+    ConsistencyVerifier, SchemaCheck, StorageRecordInfo, StorageScan, VerificationLevel,
+    VerificationStats, DEFAULT_SAMPLE_PERCENT,
 };
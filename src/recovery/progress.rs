@@ -0,0 +1,279 @@
+//! Replay progress reporting and resumable replay
+//!
+//! A long WAL replay reports its progress every `interval` records through
+//! a `RecoveryProgress` sink; `LoggedProgress` emits `WAL_REPLAY_PROGRESS`
+//! observability events.
+//!
+//! At the same points replay durably records its position: the WAL offset
+//! and the statistics up to it. Positions are only recorded between
+//! transactions, and storage fsyncs every applied record, so every record
+//! before the position is durable in storage. An interrupted recovery then
+//! resumes from the position instead of byte 0. Replay is idempotent, so a
+//! position that no longer matches the WAL is discarded and replay starts
+//! over.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::observability::{Event, Logger};
+
+use super::errors::{RecoveryError, RecoveryResult};
+use super::replay::ReplayStats;
+
+/// Replay position filename, in the data directory
+pub const REPLAY_POSITION_FILE: &str = "replay_position";
+
+/// Records replayed between progress reports and position markers
+pub const DEFAULT_REPORT_INTERVAL: u64 = 10_000;
+
+/// Progress of a WAL replay
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayProgress {
+    /// Records replayed, including any before a resumed position
+    pub records_replayed: u64,
+    /// WAL offset reached
+    pub wal_offset: u64,
+    /// WAL length, or 0 if unknown
+    pub wal_len: u64,
+    /// Bytes replayed by this run
+    pub bytes_replayed: u64,
+    /// Time spent by this run
+    pub elapsed: Duration,
+}
+
+impl ReplayProgress {
+    /// Estimated time to replay the rest of the WAL at this run's rate.
+    ///
+    /// `None` until a byte has been replayed or if the WAL length is unknown.
+    pub fn eta(&self) -> Option<Duration> {
+        if self.bytes_replayed == 0 || self.wal_len == 0 {
+            return None;
+        }
+        let remaining = self.wal_len.saturating_sub(self.wal_offset);
+        let nanos = self.elapsed.as_nanos() * remaining as u128 / self.bytes_replayed as u128;
+        Some(Duration::from_nanos(nanos.min(u64::MAX as u128) as u64))
+    }
+}
+
+/// Receives replay progress reports
+pub trait RecoveryProgress: Send + Sync {
+    /// Called every report interval and once when replay completes
+    fn on_progress(&self, progress: &ReplayProgress);
+}
+
+/// Reports progress as `WAL_REPLAY_PROGRESS` observability events
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoggedProgress;
+
+impl RecoveryProgress for LoggedProgress {
+    fn on_progress(&self, progress: &ReplayProgress) {
+        let eta = progress
+            .eta()
+            .map(|eta| eta.as_millis().to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        Logger::info(
+            Event::RecoveryReplayProgress.as_str(),
+            &[
+                ("records", &progress.records_replayed.to_string()),
+                ("wal_offset", &progress.wal_offset.to_string()),
+                ("wal_len", &progress.wal_len.to_string()),
+                ("eta_ms", &eta),
+            ],
+        );
+    }
+}
+
+/// Durable replay position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayPosition {
+    /// WAL offset of the first record not yet replayed
+    pub wal_offset: u64,
+    /// Statistics of the records before `wal_offset`
+    pub stats: ReplayStats,
+}
+
+impl ReplayPosition {
+    /// Write the position to `path` atomically, with fsync
+    pub fn write_to(&self, path: &Path) -> RecoveryResult<()> {
+        let body = serde_json::to_vec(self).map_err(|e| {
+            RecoveryError::recovery_failed(format!("Serialize replay position: {}", e))
+        })?;
+        let io_err = |e: std::io::Error| {
+            RecoveryError::recovery_failed(format!("Write replay position: {}", e))
+        };
+
+        let tmp_path = path.with_extension("tmp");
+        let mut file = File::create(&tmp_path).map_err(io_err)?;
+        writeln!(file, "crc32:{:08x}", crc32fast::hash(&body)).map_err(io_err)?;
+        file.write_all(&body).map_err(io_err)?;
+        file.sync_all().map_err(io_err)?;
+        fs::rename(&tmp_path, path).map_err(io_err)?;
+        Ok(())
+    }
+
+    /// Read a position from `path`.
+    ///
+    /// Returns `None` if the file is missing, torn or fails its checksum.
+    pub fn read_from(path: &Path) -> Option<Self> {
+        let bytes = fs::read(path).ok()?;
+        let split = bytes.iter().position(|&b| b == b'\n')?;
+        let (line, body) = (&bytes[..split], &bytes[split + 1..]);
+        let expected = std::str::from_utf8(line)
+            .ok()?
+            .strip_prefix("crc32:")
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())?;
+        if crc32fast::hash(body) != expected {
+            return None;
+        }
+        serde_json::from_slice(body).ok()
+    }
+}
+
+/// Progress reporting and position markers for one replay
+pub struct ReplayTracker<'a> {
+    progress: &'a dyn RecoveryProgress,
+    position_path: Option<PathBuf>,
+    interval: u64,
+    started: Instant,
+    start_offset: u64,
+    wal_len: u64,
+    next_report: u64,
+}
+
+impl<'a> ReplayTracker<'a> {
+    /// Report to `progress` every `DEFAULT_REPORT_INTERVAL` records
+    pub fn new(progress: &'a dyn RecoveryProgress) -> Self {
+        Self {
+            progress,
+            position_path: None,
+            interval: DEFAULT_REPORT_INTERVAL,
+            started: Instant::now(),
+            start_offset: 0,
+            wal_len: 0,
+            next_report: DEFAULT_REPORT_INTERVAL,
+        }
+    }
+
+    /// Resume from and record positions in `path`
+    pub fn with_position_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.position_path = Some(path.into());
+        self
+    }
+
+    /// Report and record a position every `interval` records
+    pub fn with_interval(mut self, interval: u64) -> Self {
+        self.interval = interval.max(1);
+        self.next_report = self.interval;
+        self
+    }
+
+    /// The recorded position to resume from, if any
+    pub(super) fn saved_position(&self) -> Option<ReplayPosition> {
+        ReplayPosition::read_from(self.position_path.as_ref()?)
+    }
+
+    /// Start timing a replay of a `wal_len`-byte WAL from `stats`
+    pub(super) fn start(&mut self, stats: &ReplayStats, wal_len: u64) {
+        self.started = Instant::now();
+        self.start_offset = stats.final_offset;
+        self.wal_len = wal_len;
+        self.next_report = stats.records_replayed + self.interval;
+    }
+
+    /// Called between transactions with `wal_offset` at a record boundary.
+    ///
+    /// Reports progress and records the position once per interval.
+    pub(super) fn checkpoint(
+        &mut self,
+        stats: &ReplayStats,
+        wal_offset: u64,
+    ) -> RecoveryResult<()> {
+        if stats.records_replayed < self.next_report {
+            return Ok(());
+        }
+        self.next_report = stats.records_replayed + self.interval;
+
+        if let Some(path) = &self.position_path {
+            let mut stats = stats.clone();
+            stats.final_offset = wal_offset;
+            ReplayPosition { wal_offset, stats }.write_to(path)?;
+        }
+        self.report(stats, wal_offset);
+        Ok(())
+    }
+
+    /// Report progress
+    pub(super) fn report(&self, stats: &ReplayStats, wal_offset: u64) {
+        self.progress.on_progress(&ReplayProgress {
+            records_replayed: stats.records_replayed,
+            wal_offset,
+            wal_len: self.wal_len.max(wal_offset),
+            bytes_replayed: wal_offset.saturating_sub(self.start_offset),
+            elapsed: self.started.elapsed(),
+        });
+    }
+}
+
+/// Discards progress reports
+pub(super) struct NoProgress;
+
+impl RecoveryProgress for NoProgress {
+    fn on_progress(&self, _progress: &ReplayProgress) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_eta_scales_with_remaining_bytes() {
+        let progress = ReplayProgress {
+            records_replayed: 10,
+            wal_offset: 300,
+            wal_len: 1000,
+            bytes_replayed: 200,
+            elapsed: Duration::from_secs(2),
+        };
+        assert_eq!(progress.eta(), Some(Duration::from_secs(7)));
+
+        let unknown = ReplayProgress {
+            bytes_replayed: 0,
+            ..progress
+        };
+        assert_eq!(unknown.eta(), None);
+    }
+
+    #[test]
+    fn test_position_round_trip_and_corruption() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(REPLAY_POSITION_FILE);
+        assert!(ReplayPosition::read_from(&path).is_none());
+
+        let stats = ReplayStats {
+            records_replayed: 4,
+            final_sequence: 4,
+            final_offset: 400,
+            ..ReplayStats::default()
+        };
+        ReplayPosition {
+            wal_offset: 400,
+            stats,
+        }
+        .write_to(&path)
+        .unwrap();
+        let position = ReplayPosition::read_from(&path).unwrap();
+        assert_eq!(position.wal_offset, 400);
+        assert_eq!(position.stats.final_sequence, 4);
+
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 2;
+        bytes[last] ^= 0x01;
+        fs::write(&path, bytes).unwrap();
+        assert!(ReplayPosition::read_from(&path).is_none());
+    }
+}
@@ -7,10 +7,40 @@
 //! - Must read sequentially
 //! - Must validate checksum for every record
 //! - On ANY corruption: FATAL error, abort immediately
+//!
+//! The only exception is a torn final record when the reader runs with
+//! `TailRecovery::TruncateTornTail`: the tail is truncated, logged as
+//! `WAL_TAIL_TRUNCATED`, and replay completes with the records before it.
+//!
+//! # Transactions
+//!
+//! Operations between TXN_BEGIN and TXN_COMMIT are buffered and applied only
+//! when the matching TXN_COMMIT is read (all-or-nothing). A transaction still
+//! open at end of WAL was never acknowledged: it is discarded, the WAL is
+//! truncated back to its TXN_BEGIN, and `WAL_TXN_DISCARDED` is logged.
+//! Malformed transaction framing anywhere else is corruption.
+//!
+//! # Progress and resume
+//!
+//! `replay_tracked` reports progress and periodically records a durable
+//! replay position between transactions, so an interrupted recovery of a
+//! large WAL resumes there instead of byte 0 (see `progress`).
+//!
+//! # Schema DDL
+//!
+//! SCHEMA_DDL records are applied to the schema catalog before replay (see
+//! `replay_schema_ddl`); replay only counts them and never applies them to
+//! storage.
 
-use crate::wal::{RecordType, WalPayload, WalRecord};
+use serde::{Deserialize, Serialize};
+
+use crate::crash_point::{maybe_crash, points};
+use crate::observability::{Event, Logger};
+use crate::schema::SchemaDdl;
+use crate::wal::{RecordType, TornTail, TxnMarker, WalRecord};
 
 use super::errors::{RecoveryError, RecoveryResult};
+use super::progress::{NoProgress, ReplayTracker};
 
 /// Trait for applying WAL records to storage
 pub trait StorageApply {
@@ -18,6 +48,12 @@ pub trait StorageApply {
     fn apply_wal_record(&mut self, record: &WalRecord) -> RecoveryResult<()>;
 }
 
+/// Trait for applying schema DDL records to the schema catalog
+pub trait SchemaDdlApply {
+    /// Apply a schema change; must be idempotent
+    fn apply_schema_ddl(&mut self, ddl: &SchemaDdl) -> RecoveryResult<()>;
+}
+
 /// Trait for reading WAL records
 pub trait WalRead {
     /// Read the next WAL record
@@ -30,10 +66,43 @@ pub trait WalRead {
 
     /// Reset to beginning of WAL
     fn reset(&mut self) -> RecoveryResult<()>;
+
+    /// WAL length in bytes, or 0 if unknown
+    fn wal_len(&self) -> u64 {
+        0
+    }
+
+    /// Position the reader at `offset`, just after the record with
+    /// sequence number `last_sequence`.
+    ///
+    /// Returns `false`, with the reader reset, if the position does not
+    /// match the WAL or resuming is not supported.
+    fn resume_at(&mut self, offset: u64, last_sequence: u64) -> RecoveryResult<bool> {
+        let _ = (offset, last_sequence);
+        self.reset()?;
+        Ok(false)
+    }
+
+    /// Truncate a torn final record detected while reading.
+    ///
+    /// Returns `None` if the WAL ended cleanly.
+    fn truncate_torn_tail(&mut self) -> RecoveryResult<Option<TornTail>> {
+        Ok(None)
+    }
+
+    /// Truncate the WAL at a record boundary.
+    ///
+    /// Used to discard an uncommitted transaction at the end of the WAL.
+    fn truncate_at(&mut self, offset: u64) -> RecoveryResult<()> {
+        Err(RecoveryError::recovery_failed(format!(
+            "WAL truncation at offset {} not supported by this reader",
+            offset
+        )))
+    }
 }
 
 /// Statistics from WAL replay
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ReplayStats {
     /// Number of records replayed
     pub records_replayed: u64,
@@ -53,6 +122,29 @@ pub struct ReplayStats {
     pub final_offset: u64,
     /// Final sequence number
     pub final_sequence: u64,
+    /// Torn final record truncated during replay, if any
+    #[serde(skip)]
+    pub torn_tail: Option<TornTail>,
+    /// Number of committed transactions applied
+    pub txn_commits: u64,
+    /// Number of uncommitted transactions discarded at end of WAL
+    pub txns_discarded: u64,
+    /// Highest CommitId recorded by a TXN_COMMIT record (0 if none)
+    pub highest_commit_id: u64,
+    /// Number of schema DDL records seen
+    pub schema_ddl: u64,
+}
+
+/// Transaction buffered between TXN_BEGIN and TXN_COMMIT
+struct PendingTxn {
+    /// WAL offset of the TXN_BEGIN record
+    begin_offset: u64,
+    /// Decoded TXN_BEGIN marker
+    marker: TxnMarker,
+    /// Buffered operations
+    ops: Vec<WalRecord>,
+    /// Final sequence before the TXN_BEGIN record
+    prior_sequence: u64,
 }
 
 /// WAL replayer that processes WAL records sequentially
@@ -66,17 +158,47 @@ impl WalReplayer {
     /// 2. Reads each record sequentially
     /// 3. Validates checksum on every record
     /// 4. Applies each record to storage
-    /// 5. Aborts on any corruption
+    /// 5. Aborts on any corruption, except a torn final record the reader
+    ///    reports under `TailRecovery::TruncateTornTail`, which is truncated
     ///
     /// Replay is idempotent: same WAL replayed twice produces identical state.
     pub fn replay<W: WalRead, S: StorageApply>(
         wal: &mut W,
         storage: &mut S,
+    ) -> RecoveryResult<ReplayStats> {
+        Self::replay_tracked(wal, storage, &mut ReplayTracker::new(&NoProgress))
+    }
+
+    /// Replay like `replay`, reporting progress and recording positions
+    /// through `tracker`.
+    ///
+    /// Replay resumes from the tracker's recorded position if the WAL
+    /// confirms it, and starts at byte 0 otherwise. Returned statistics
+    /// include the records before a resumed position.
+    pub fn replay_tracked<W: WalRead, S: StorageApply>(
+        wal: &mut W,
+        storage: &mut S,
+        tracker: &mut ReplayTracker<'_>,
     ) -> RecoveryResult<ReplayStats> {
         // Reset to beginning of WAL
         wal.reset()?;
 
         let mut stats = ReplayStats::default();
+        if let Some(position) = tracker.saved_position() {
+            if wal.resume_at(position.wal_offset, position.stats.final_sequence)? {
+                Logger::info(
+                    Event::RecoveryReplayResumed.as_str(),
+                    &[
+                        ("wal_offset", &position.wal_offset.to_string()),
+                        ("records", &position.stats.records_replayed.to_string()),
+                    ],
+                );
+                stats = position.stats;
+            }
+        }
+        tracker.start(&stats, wal.wal_len());
+
+        let mut pending: Option<PendingTxn> = None;
 
         loop {
             let offset_before = wal.current_offset();
@@ -91,32 +213,182 @@ impl WalReplayer {
                 }
             };
 
-            // Apply to storage
-            storage.apply_wal_record(&record)?;
-
-            // Update stats based on record type
-            stats.records_replayed += 1;
+            let prior_sequence = stats.final_sequence;
             stats.final_sequence = record.sequence_number;
 
             match record.record_type {
-                RecordType::Insert => stats.inserts += 1,
-                RecordType::Update => stats.updates += 1,
-                RecordType::Delete => stats.deletes += 1,
-                RecordType::MvccCommit => stats.mvcc_commits += 1,
-                RecordType::MvccVersion => stats.mvcc_versions += 1,
-                RecordType::MvccGc => stats.mvcc_gc += 1,
+                RecordType::TxnBegin => {
+                    if pending.is_some() {
+                        return Err(RecoveryError::wal_corruption(
+                            offset_before,
+                            "TXN_BEGIN inside an open transaction",
+                        ));
+                    }
+                    pending = Some(PendingTxn {
+                        begin_offset: offset_before,
+                        marker: Self::decode_marker(&record, offset_before)?,
+                        ops: Vec::new(),
+                        prior_sequence,
+                    });
+                }
+                RecordType::TxnCommit => {
+                    let marker = Self::decode_marker(&record, offset_before)?;
+                    let txn = pending.take().ok_or_else(|| {
+                        RecoveryError::wal_corruption(
+                            offset_before,
+                            "TXN_COMMIT without matching TXN_BEGIN",
+                        )
+                    })?;
+                    if marker.txn_id != txn.marker.txn_id
+                        || marker.op_count as usize != txn.ops.len()
+                        || marker.op_count != txn.marker.op_count
+                    {
+                        return Err(RecoveryError::wal_corruption(
+                            offset_before,
+                            format!(
+                                "TXN_COMMIT for txn {} ({} ops) does not match open txn {} ({} ops)",
+                                marker.txn_id,
+                                marker.op_count,
+                                txn.marker.txn_id,
+                                txn.ops.len()
+                            ),
+                        ));
+                    }
+
+                    // Commit boundary reached: apply the whole transaction
+                    for op in &txn.ops {
+                        Self::apply(storage, op, &mut stats)?;
+                    }
+                    stats.records_replayed += 2; // TXN_BEGIN + TXN_COMMIT
+                    stats.txn_commits += 1;
+                    stats.highest_commit_id = stats.highest_commit_id.max(marker.commit_id);
+                }
+                RecordType::SchemaDdl => {
+                    if pending.is_some() {
+                        return Err(RecoveryError::wal_corruption(
+                            offset_before,
+                            "SCHEMA_DDL inside an open transaction",
+                        ));
+                    }
+                    stats.records_replayed += 1;
+                    stats.schema_ddl += 1;
+                }
+                _ => match pending.as_mut() {
+                    Some(txn) => txn.ops.push(record),
+                    None => Self::apply(storage, &record, &mut stats)?,
+                },
+            }
+
+            if pending.is_none() {
+                tracker.checkpoint(&stats, wal.current_offset())?;
             }
         }
 
+        maybe_crash(points::RECOVERY_BEFORE_TAIL_TRUNCATE);
+        if let Some(tail) = wal.truncate_torn_tail()? {
+            maybe_crash(points::RECOVERY_AFTER_TAIL_TRUNCATE);
+            Logger::warn(
+                Event::WalTailTruncated.as_str(),
+                &[
+                    ("offset", &tail.offset.to_string()),
+                    ("discarded_bytes", &tail.discarded_bytes.to_string()),
+                    ("reason", &tail.reason),
+                ],
+            );
+            stats.torn_tail = Some(tail);
+        }
+
+        // An open transaction at end of WAL was never acknowledged
+        if let Some(txn) = pending {
+            wal.truncate_at(txn.begin_offset)?;
+            Logger::warn(
+                Event::WalTxnDiscarded.as_str(),
+                &[
+                    ("txn_id", &txn.marker.txn_id.to_string()),
+                    ("offset", &txn.begin_offset.to_string()),
+                    ("buffered_ops", &txn.ops.len().to_string()),
+                ],
+            );
+            stats.txns_discarded += 1;
+            stats.final_sequence = txn.prior_sequence;
+        }
+
         stats.final_offset = wal.current_offset();
+        tracker.report(&stats, stats.final_offset);
 
         Ok(stats)
     }
+
+    /// Apply every SCHEMA_DDL record to the schema catalog, in WAL order.
+    ///
+    /// Runs before `replay` so documents written under runtime-created
+    /// schemas verify. Reading stops at the first unreadable record, which
+    /// `replay` then reports or truncates. Returns the number applied.
+    pub fn replay_schema_ddl<W: WalRead, C: SchemaDdlApply>(
+        wal: &mut W,
+        catalog: &mut C,
+    ) -> RecoveryResult<u64> {
+        wal.reset()?;
+
+        let mut applied = 0;
+        loop {
+            let offset = wal.current_offset();
+            let record = match wal.read_next() {
+                Ok(Some(record)) => record,
+                Ok(None) | Err(_) => break,
+            };
+            if record.record_type != RecordType::SchemaDdl {
+                continue;
+            }
+
+            let ddl = SchemaDdl::from_payload(&record.payload)
+                .map_err(|e| RecoveryError::wal_corruption(offset, e.message()))?;
+            catalog.apply_schema_ddl(&ddl)?;
+            applied += 1;
+        }
+
+        wal.reset()?;
+        Ok(applied)
+    }
+
+    /// Apply a single operation record to storage and count it.
+    fn apply<S: StorageApply>(
+        storage: &mut S,
+        record: &WalRecord,
+        stats: &mut ReplayStats,
+    ) -> RecoveryResult<()> {
+        storage.apply_wal_record(record)?;
+
+        stats.records_replayed += 1;
+        match record.record_type {
+            RecordType::Insert => stats.inserts += 1,
+            RecordType::Update => stats.updates += 1,
+            RecordType::Delete => stats.deletes += 1,
+            RecordType::MvccCommit => stats.mvcc_commits += 1,
+            RecordType::MvccVersion => stats.mvcc_versions += 1,
+            RecordType::MvccGc => stats.mvcc_gc += 1,
+            RecordType::TxnBegin | RecordType::TxnCommit | RecordType::SchemaDdl => {}
+        }
+        Ok(())
+    }
+
+    /// Decode the marker of a TXN_BEGIN / TXN_COMMIT record.
+    fn decode_marker(record: &WalRecord, offset: u64) -> RecoveryResult<TxnMarker> {
+        match record.txn_marker() {
+            Some(Ok(marker)) => Ok(marker),
+            Some(Err(e)) => Err(RecoveryError::wal_corruption(offset, e.to_string())),
+            None => Err(RecoveryError::wal_corruption(
+                offset,
+                "Expected transaction marker record",
+            )),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::wal::WalPayload;
 
     struct MockWal {
         records: Vec<WalRecord>,
@@ -169,6 +441,32 @@ mod tests {
             self.offset = 0;
             Ok(())
         }
+
+        fn wal_len(&self) -> u64 {
+            self.records.len() as u64 * 100
+        }
+
+        fn resume_at(&mut self, offset: u64, last_sequence: u64) -> RecoveryResult<bool> {
+            let position = (offset / 100) as usize;
+            let continues = match self.records.get(position) {
+                Some(record) => record.sequence_number == last_sequence + 1,
+                None => position == self.records.len(),
+            };
+            if !continues {
+                self.reset()?;
+                return Ok(false);
+            }
+            self.position = position;
+            self.offset = offset;
+            Ok(true)
+        }
+
+        fn truncate_at(&mut self, offset: u64) -> RecoveryResult<()> {
+            self.records.truncate((offset / 100) as usize);
+            self.position = self.records.len();
+            self.offset = offset;
+            Ok(())
+        }
     }
 
     struct MockStorage {
@@ -275,4 +573,163 @@ mod tests {
         assert_eq!(stats.records_replayed, 0);
         assert_eq!(storage.applied.len(), 0);
     }
+
+    #[test]
+    fn test_committed_txn_applies_all_ops() {
+        let records = vec![
+            make_insert_record(1, "user_1"),
+            WalRecord::txn_begin(2, 2),
+            make_insert_record(3, "user_2"),
+            make_delete_record(4, "user_1"),
+            WalRecord::txn_commit(5, 2, 2, 9),
+        ];
+
+        let mut wal = MockWal::new(records);
+        let mut storage = MockStorage::new();
+        let stats = WalReplayer::replay(&mut wal, &mut storage).unwrap();
+
+        assert_eq!(storage.applied.len(), 3);
+        assert_eq!(stats.records_replayed, 5);
+        assert_eq!(stats.txn_commits, 1);
+        assert_eq!(stats.txns_discarded, 0);
+        assert_eq!(stats.highest_commit_id, 9);
+        assert_eq!(stats.final_sequence, 5);
+    }
+
+    #[test]
+    fn test_open_txn_at_end_is_discarded() {
+        let records = vec![
+            make_insert_record(1, "user_1"),
+            WalRecord::txn_begin(2, 2),
+            make_insert_record(3, "user_2"),
+        ];
+
+        let mut wal = MockWal::new(records);
+        let mut storage = MockStorage::new();
+        let stats = WalReplayer::replay(&mut wal, &mut storage).unwrap();
+
+        // Only the record before TXN_BEGIN is applied
+        assert_eq!(storage.applied.len(), 1);
+        assert_eq!(stats.txns_discarded, 1);
+        assert_eq!(stats.final_sequence, 1);
+        assert_eq!(stats.final_offset, 100);
+        assert_eq!(wal.records.len(), 1);
+    }
+
+    #[test]
+    fn test_commit_without_begin_is_corruption() {
+        let records = vec![
+            make_insert_record(1, "user_1"),
+            WalRecord::txn_commit(2, 1, 0, 1),
+        ];
+
+        let mut wal = MockWal::new(records);
+        let mut storage = MockStorage::new();
+        let err = WalReplayer::replay(&mut wal, &mut storage).unwrap_err();
+        assert_eq!(err.code().code(), "AERO_WAL_CORRUPTION");
+    }
+
+    #[derive(Default)]
+    struct RecordedProgress {
+        reports: std::sync::Mutex<Vec<crate::recovery::ReplayProgress>>,
+    }
+
+    impl crate::recovery::RecoveryProgress for RecordedProgress {
+        fn on_progress(&self, progress: &crate::recovery::ReplayProgress) {
+            self.reports.lock().unwrap().push(progress.clone());
+        }
+    }
+
+    #[test]
+    fn test_interrupted_replay_resumes_from_position() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let position_file = temp_dir.path().join("replay_position");
+        let records = vec![
+            make_insert_record(1, "user_1"),
+            make_insert_record(2, "user_2"),
+            WalRecord::txn_begin(3, 1),
+            make_insert_record(4, "user_3"),
+            WalRecord::txn_commit(5, 3, 1, 1),
+            make_insert_record(6, "user_4"),
+            make_insert_record(7, "user_5"),
+        ];
+
+        // Interrupted by corruption at the last record
+        let progress = RecordedProgress::default();
+        let mut tracker = ReplayTracker::new(&progress)
+            .with_position_file(&position_file)
+            .with_interval(2);
+        let mut wal = MockWal::new(records.clone()).with_corruption_at(6);
+        let mut storage = MockStorage::new();
+        WalReplayer::replay_tracked(&mut wal, &mut storage, &mut tracker).unwrap_err();
+        let reports = progress.reports.lock().unwrap().clone();
+        // Positions are recorded between transactions only
+        let offsets: Vec<u64> = reports.iter().map(|p| p.wal_offset).collect();
+        assert_eq!(offsets, vec![200, 500]);
+        assert_eq!(reports[0].wal_len, 700);
+
+        // A position the WAL does not confirm is discarded
+        let mut renumbered = records.clone();
+        renumbered[5] = make_insert_record(9, "user_4");
+        let mut wal = MockWal::new(renumbered);
+        let mut storage = MockStorage::new();
+        let mut tracker = ReplayTracker::new(&progress).with_position_file(&position_file);
+        WalReplayer::replay_tracked(&mut wal, &mut storage, &mut tracker).unwrap();
+        assert_eq!(storage.applied.len(), 5);
+
+        // Resumes after the committed transaction
+        let progress = RecordedProgress::default();
+        let mut tracker = ReplayTracker::new(&progress)
+            .with_position_file(&position_file)
+            .with_interval(2);
+        let mut wal = MockWal::new(records);
+        let mut storage = MockStorage::new();
+        let stats = WalReplayer::replay_tracked(&mut wal, &mut storage, &mut tracker).unwrap();
+        let applied: Vec<u64> = storage.applied.iter().map(|r| r.sequence_number).collect();
+        assert_eq!(applied, vec![6, 7]);
+        assert_eq!(stats.records_replayed, 7);
+        assert_eq!(stats.inserts, 5);
+        assert_eq!(stats.txn_commits, 1);
+        assert_eq!(stats.final_sequence, 7);
+        let last = progress.reports.lock().unwrap().last().cloned().unwrap();
+        assert_eq!((last.records_replayed, last.wal_offset), (7, 700));
+        assert_eq!(last.bytes_replayed, 200);
+    }
+
+    #[derive(Default)]
+    struct MockCatalog {
+        applied: Vec<SchemaDdl>,
+    }
+
+    impl SchemaDdlApply for MockCatalog {
+        fn apply_schema_ddl(&mut self, ddl: &SchemaDdl) -> RecoveryResult<()> {
+            self.applied.push(ddl.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_schema_ddl_applied_to_catalog_not_storage() {
+        let ddl = SchemaDdl::Deprecate {
+            schema_id: "users".to_string(),
+            schema_version: "v1".to_string(),
+        };
+        let records = vec![
+            make_insert_record(1, "user_1"),
+            WalRecord::new(RecordType::SchemaDdl, 2, ddl.to_payload()),
+            make_insert_record(3, "user_2"),
+        ];
+
+        let mut wal = MockWal::new(records);
+        let mut catalog = MockCatalog::default();
+        let applied = WalReplayer::replay_schema_ddl(&mut wal, &mut catalog).unwrap();
+        assert_eq!(applied, 1);
+        assert_eq!(catalog.applied, vec![ddl]);
+
+        let mut storage = MockStorage::new();
+        let stats = WalReplayer::replay(&mut wal, &mut storage).unwrap();
+        assert_eq!(stats.records_replayed, 3);
+        assert_eq!(stats.schema_ddl, 1);
+        assert_eq!(storage.applied.len(), 2);
+    }
 }
@@ -4,21 +4,37 @@
 //!
 //! # Startup Sequence (strict order)
 //!
-//! 1. Load schemas via schema loader
+//! 1. Load schemas via schema loader, then apply WAL schema DDL records
 //! 2. Open WAL reader
 //! 3. Open document storage
-//! 4. Replay WAL from offset 0 sequentially
+//! 4. Replay WAL from offset 0 sequentially, or resume from the position
+//!    recorded by an interrupted recovery
 //! 5. Apply each WAL record via storage.apply_wal_record
 //! 6. After replay completes, call index.rebuild_from_storage
-//! 7. Run consistency verification
-//! 8. Enter serving state
+//! 7. Run consistency verification at the configured level
+//! 8. Restore indexes from the latest index checkpoint, patched with the
+//!    records stored after it, or rebuild them from storage
+//! 9. Enter serving state
 
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::checkpoint::{marker_path, CheckpointMarker};
+use crate::index::{
+    rebuild_parallel, IndexCheckpoint, IndexDeclarations, IndexError, IndexManager, RangeScan,
+    StorageSeek, StoredRecordIndexer, INDEX_CHECKPOINT_FILE,
+};
+use crate::snapshot::snapshot_path;
 
 use super::errors::{RecoveryError, RecoveryResult};
+use super::progress::{
+    LoggedProgress, RecoveryProgress, ReplayTracker, DEFAULT_REPORT_INTERVAL, REPLAY_POSITION_FILE,
+};
 use super::replay::{ReplayStats, StorageApply, WalRead, WalReplayer};
-use super::verifier::{ConsistencyVerifier, SchemaCheck, StorageScan, VerificationStats};
+use super::verifier::{
+    ConsistencyVerifier, SchemaCheck, StorageScan, VerificationLevel, VerificationStats,
+};
 
 /// Clean shutdown marker filename
 const CLEAN_SHUTDOWN_MARKER: &str = "clean_shutdown";
@@ -40,9 +56,30 @@ pub struct RecoveryState {
     pub was_clean_shutdown: bool,
 }
 
+/// How indexes were recovered at startup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexRecoveryPath {
+    /// Restored from the index checkpoint of a snapshot, then patched
+    Checkpoint {
+        /// Snapshot the checkpoint belongs to
+        snapshot_id: String,
+        /// Records stored after the checkpoint that were indexed
+        patched_records: u64,
+    },
+    /// Rebuilt by scanning all of storage
+    FullRebuild {
+        /// Why no checkpoint was used
+        reason: String,
+    },
+}
+
 /// Recovery Manager that orchestrates startup
 pub struct RecoveryManager {
     data_dir: PathBuf,
+    index_rebuild_threads: usize,
+    progress: Arc<dyn RecoveryProgress>,
+    report_interval: u64,
+    verification: VerificationLevel,
 }
 
 impl RecoveryManager {
@@ -50,9 +87,40 @@ impl RecoveryManager {
     pub fn new(data_dir: impl AsRef<Path>) -> Self {
         Self {
             data_dir: data_dir.as_ref().to_path_buf(),
+            index_rebuild_threads: 1,
+            progress: Arc::new(LoggedProgress),
+            report_interval: DEFAULT_REPORT_INTERVAL,
+            verification: VerificationLevel::Full,
         }
     }
 
+    /// Verify storage at `level` after replay (default `Full`)
+    pub fn with_verification(mut self, level: VerificationLevel) -> Self {
+        self.verification = level;
+        self
+    }
+
+    /// Report WAL replay progress to `progress` instead of the log
+    pub fn with_progress(mut self, progress: Arc<dyn RecoveryProgress>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Report progress and record the replay position every `records`
+    /// replayed records (default `DEFAULT_REPORT_INTERVAL`)
+    pub fn with_report_interval(mut self, records: u64) -> Self {
+        self.report_interval = records.max(1);
+        self
+    }
+
+    /// Rebuild indexes on up to `threads` worker threads (default 1).
+    ///
+    /// The rebuilt indexes are identical to a single-threaded rebuild.
+    pub fn with_index_rebuild_threads(mut self, threads: usize) -> Self {
+        self.index_rebuild_threads = threads.max(1);
+        self
+    }
+
     /// Returns the path to the clean shutdown marker
     fn marker_path(&self) -> PathBuf {
         self.data_dir.join(CLEAN_SHUTDOWN_MARKER)
@@ -63,6 +131,22 @@ impl RecoveryManager {
         self.marker_path().exists()
     }
 
+    /// Returns the path to the replay position marker
+    fn replay_position_path(&self) -> PathBuf {
+        self.data_dir.join(REPLAY_POSITION_FILE)
+    }
+
+    /// Remove the replay position once recovery has completed
+    fn remove_replay_position(&self) -> RecoveryResult<()> {
+        let path = self.replay_position_path();
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| {
+                RecoveryError::recovery_failed(format!("Failed to remove replay position: {}", e))
+            })?;
+        }
+        Ok(())
+    }
+
     /// Remove clean shutdown marker (called during startup)
     fn remove_shutdown_marker(&self) -> RecoveryResult<()> {
         let path = self.marker_path();
@@ -96,10 +180,11 @@ impl RecoveryManager {
     ///
     /// Steps (must be exact order):
     /// 1. Check for clean shutdown marker
-    /// 2. Replay WAL from offset 0
+    /// 2. Replay WAL from offset 0, or from the position recorded by an
+    ///    interrupted recovery, reporting progress
     /// 3. Rebuild indexes
-    /// 4. Verify consistency
-    /// 5. Remove shutdown marker
+    /// 4. Verify consistency at the configured level
+    /// 5. Remove shutdown marker and replay position
     ///
     /// Returns RecoveryState on success, FATAL error on any failure.
     pub fn recover<W, S, I, C>(
@@ -119,16 +204,21 @@ impl RecoveryManager {
         let was_clean_shutdown = self.was_clean_shutdown();
 
         // Step 2: Replay WAL (always replay in Phase 0, even after clean shutdown)
-        let replay_stats = WalReplayer::replay(wal, storage)?;
+        let mut tracker = ReplayTracker::new(self.progress.as_ref())
+            .with_position_file(self.replay_position_path())
+            .with_interval(self.report_interval);
+        let replay_stats = WalReplayer::replay_tracked(wal, storage, &mut tracker)?;
 
         // Step 3: Rebuild indexes from storage
         index.rebuild_from_storage()?;
 
         // Step 4: Verify consistency
-        let verification_stats = ConsistencyVerifier::verify(storage, schema_registry)?;
+        let verification_stats =
+            ConsistencyVerifier::verify_at(storage, schema_registry, self.verification)?;
 
-        // Step 5: Remove shutdown marker
+        // Step 5: Remove shutdown marker and replay position
         self.remove_shutdown_marker()?;
+        self.remove_replay_position()?;
 
         Ok(RecoveryState {
             replay_stats,
@@ -136,6 +226,104 @@ impl RecoveryManager {
             was_clean_shutdown,
         })
     }
+
+    /// Recover indexes after WAL replay.
+    ///
+    /// Restores the index checkpoint of the snapshot named by the
+    /// checkpoint marker and indexes the records stored after it. The
+    /// checkpoint is discarded in favour of a full rebuild from storage if it
+    /// is missing, fails its checksum, or no longer matches the marker, the
+    /// index declarations, `collection` or the storage length. `collection`
+    /// names the collection the manager's own entries belong to; other
+    /// collections are indexed in partitions.
+    ///
+    /// The full rebuild scans storage ranges in parallel when more than one
+    /// index rebuild thread is configured. Corruption found by the full
+    /// rebuild is FATAL.
+    pub fn recover_indexes<S: StorageSeek + RangeScan>(
+        &self,
+        index: &mut IndexManager,
+        collection: &str,
+        storage: &mut S,
+    ) -> RecoveryResult<IndexRecoveryPath> {
+        let reason = match self.restore_index_checkpoint(index, collection, storage) {
+            Ok(path) => return Ok(path),
+            Err(reason) => reason,
+        };
+
+        rebuild_parallel(index, collection, storage, self.index_rebuild_threads)
+            .map_err(index_failure)?;
+
+        Ok(IndexRecoveryPath::FullRebuild { reason })
+    }
+
+    /// Restore the latest index checkpoint, or explain why it is unusable
+    fn restore_index_checkpoint<S: StorageSeek>(
+        &self,
+        index: &mut IndexManager,
+        collection: &str,
+        storage: &mut S,
+    ) -> Result<IndexRecoveryPath, String> {
+        let marker_file = marker_path(&self.data_dir);
+        if !marker_file.exists() {
+            return Err("no checkpoint".to_string());
+        }
+        let marker = CheckpointMarker::read_from_file(&marker_file).map_err(|e| e.to_string())?;
+
+        let path = snapshot_path(&self.data_dir, &marker.snapshot_id).join(INDEX_CHECKPOINT_FILE);
+        if !path.exists() {
+            return Err(format!(
+                "snapshot {} has no index checkpoint",
+                marker.snapshot_id
+            ));
+        }
+        let checkpoint = IndexCheckpoint::read_from(&path).map_err(|e| e.to_string())?;
+
+        if checkpoint.snapshot_id != marker.snapshot_id {
+            return Err(format!(
+                "index checkpoint belongs to snapshot {}",
+                checkpoint.snapshot_id
+            ));
+        }
+        if checkpoint.collection != collection {
+            return Err(format!(
+                "index checkpoint was taken for collection '{}'",
+                checkpoint.collection
+            ));
+        }
+        if checkpoint.declarations != IndexDeclarations::of(index) {
+            return Err("index declarations changed".to_string());
+        }
+        let storage_len = storage.storage_len().map_err(|e| e.to_string())?;
+        if checkpoint.storage_end > storage_len {
+            return Err(format!(
+                "storage is shorter ({} bytes) than the index checkpoint ({} bytes)",
+                storage_len, checkpoint.storage_end
+            ));
+        }
+
+        let mut indexer = StoredRecordIndexer::new(index, collection);
+        indexer
+            .restore(&checkpoint, storage)
+            .map_err(|e| e.to_string())?;
+        storage
+            .seek(checkpoint.storage_end)
+            .map_err(|e| e.to_string())?;
+        let patched_records = indexer.scan(storage).map_err(|e| e.to_string())?;
+
+        Ok(IndexRecoveryPath::Checkpoint {
+            snapshot_id: checkpoint.snapshot_id,
+            patched_records,
+        })
+    }
+}
+
+/// Map an index rebuild failure to a FATAL recovery error
+fn index_failure(e: IndexError) -> RecoveryError {
+    match e.offset() {
+        Some(offset) => RecoveryError::storage_corruption(offset, e.message()),
+        None => RecoveryError::recovery_failed(e.message()),
+    }
 }
 
 #[cfg(test)]
@@ -351,6 +539,50 @@ mod tests {
         assert!(!manager.was_clean_shutdown()); // Marker removed
     }
 
+    #[test]
+    fn test_progress_reported_and_position_removed() {
+        use crate::recovery::{ReplayProgress, REPLAY_POSITION_FILE};
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        struct Reports(Mutex<Vec<ReplayProgress>>);
+
+        impl RecoveryProgress for Reports {
+            fn on_progress(&self, progress: &ReplayProgress) {
+                self.0.lock().unwrap().push(progress.clone());
+            }
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let reports = Arc::new(Reports::default());
+        let manager = RecoveryManager::new(temp_dir.path())
+            .with_progress(reports.clone())
+            .with_report_interval(2);
+
+        let records = (1..=5)
+            .map(|seq| make_insert_record(seq, &format!("user_{}", seq)))
+            .collect();
+        let mut wal = MockWal::new(records);
+        let mut storage = MockStorage::new();
+        let mut index = MockIndex::new();
+        let schema = MockSchemaRegistry::new();
+
+        manager
+            .recover(&mut wal, &mut storage, &mut index, &schema)
+            .unwrap();
+
+        // Two periodic reports and a final one
+        let records: Vec<u64> = reports
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|p| p.records_replayed)
+            .collect();
+        assert_eq!(records, vec![2, 4, 5]);
+        assert!(!temp_dir.path().join(REPLAY_POSITION_FILE).exists());
+    }
+
     #[test]
     fn test_replay_restores_documents() {
         let temp_dir = TempDir::new().unwrap();
@@ -6,6 +6,13 @@
 //! - Scan storage sequentially
 //! - Validate checksum on every record
 //! - Ensure no invalid schema references exist
+//!
+//! # Verification levels
+//!
+//! `VerificationLevel::Full` (the default) checks every record. Faster
+//! startups can trade coverage for time: `ManifestOnly` only walks record
+//! framing, `Sampled` fully checks a deterministic, seeded sample of records
+//! and walks the framing of the rest, and `None` skips verification.
 
 use super::errors::{RecoveryError, RecoveryResult};
 
@@ -41,6 +48,80 @@ pub trait StorageScan {
 
     /// Reset to beginning of storage
     fn reset(&mut self) -> RecoveryResult<()>;
+
+    /// Step over the next record, checking only that its framing fits the
+    /// file. Returns its offset, or None if at end.
+    fn skip_next(&mut self) -> RecoveryResult<Option<u64>> {
+        Ok(self.scan_next()?.map(|record| record.offset))
+    }
+}
+
+/// Percentage of records `VerificationLevel::Sampled` checks by default
+pub const DEFAULT_SAMPLE_PERCENT: u8 = 10;
+
+/// How thoroughly recovery verifies storage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerificationLevel {
+    /// No verification
+    None,
+    /// Walk record framing only; no checksum or schema checks
+    ManifestOnly,
+    /// Fully check a sample of records chosen deterministically from `seed`
+    Sampled {
+        /// Percentage of records checked (1-100)
+        percent: u8,
+        /// Sampling seed; the same seed checks the same records
+        seed: u64,
+    },
+    /// Check every record (default)
+    #[default]
+    Full,
+}
+
+impl VerificationLevel {
+    /// Parse a configuration name (`none`, `manifest-only`, `sampled`,
+    /// `full`). `sampled` uses `DEFAULT_SAMPLE_PERCENT` and seed 0.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(VerificationLevel::None),
+            "manifest-only" => Some(VerificationLevel::ManifestOnly),
+            "sampled" => Some(VerificationLevel::Sampled {
+                percent: DEFAULT_SAMPLE_PERCENT,
+                seed: 0,
+            }),
+            "full" => Some(VerificationLevel::Full),
+            _ => None,
+        }
+    }
+
+    /// Configuration name
+    pub fn name(self) -> &'static str {
+        match self {
+            VerificationLevel::None => "none",
+            VerificationLevel::ManifestOnly => "manifest-only",
+            VerificationLevel::Sampled { .. } => "sampled",
+            VerificationLevel::Full => "full",
+        }
+    }
+
+    /// Whether the record with ordinal `index` in storage is fully checked
+    fn checks(self, index: u64) -> bool {
+        match self {
+            VerificationLevel::None | VerificationLevel::ManifestOnly => false,
+            VerificationLevel::Sampled { percent, seed } => {
+                sample_hash(seed, index) % 100 < u64::from(percent)
+            }
+            VerificationLevel::Full => true,
+        }
+    }
+}
+
+/// SplitMix64 of the seed and record ordinal
+fn sample_hash(seed: u64, index: u64) -> u64 {
+    let mut z = seed ^ index.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
 }
 
 /// Verification statistics
@@ -48,10 +129,12 @@ pub trait StorageScan {
 pub struct VerificationStats {
     /// Number of records verified
     pub records_verified: u64,
-    /// Number of tombstones
+    /// Number of tombstones among verified records
     pub tombstones: u64,
-    /// Number of live documents
+    /// Number of live documents among verified records
     pub live_documents: u64,
+    /// Number of records whose framing was walked but not verified
+    pub records_skipped: u64,
 }
 
 /// Consistency verifier that checks storage integrity
@@ -70,22 +153,39 @@ impl ConsistencyVerifier {
         storage: &mut S,
         schema_registry: &C,
     ) -> RecoveryResult<VerificationStats> {
-        storage.reset()?;
+        Self::verify_at(storage, schema_registry, VerificationLevel::Full)
+    }
 
+    /// Verify storage consistency at `level`.
+    ///
+    /// Records `level` does not check still have their framing walked,
+    /// except at `VerificationLevel::None`, which reads nothing. Returns
+    /// FATAL error on any corruption or invalid reference found.
+    pub fn verify_at<S: StorageScan, C: SchemaCheck>(
+        storage: &mut S,
+        schema_registry: &C,
+        level: VerificationLevel,
+    ) -> RecoveryResult<VerificationStats> {
         let mut stats = VerificationStats::default();
+        if level == VerificationLevel::None {
+            return Ok(stats);
+        }
 
-        loop {
-            // Read next record (checksum validated by scanner)
-            let record = match storage.scan_next() {
-                Ok(Some(r)) => r,
-                Ok(None) => break, // End of storage
-                Err(e) => {
-                    // Storage corruption detected - abort immediately
-                    return Err(RecoveryError::storage_corruption(
-                        e.offset().unwrap_or(0),
-                        e.message(),
-                    ));
+        storage.reset()?;
+
+        for index in 0.. {
+            if !level.checks(index) {
+                match storage.skip_next().map_err(Self::corruption)? {
+                    Some(_) => stats.records_skipped += 1,
+                    None => break, // End of storage
                 }
+                continue;
+            }
+
+            // Read next record (checksum validated by scanner)
+            let record = match storage.scan_next().map_err(Self::corruption)? {
+                Some(r) => r,
+                None => break, // End of storage
             };
 
             stats.records_verified += 1;
@@ -115,6 +215,11 @@ impl ConsistencyVerifier {
 
         Ok(stats)
     }
+
+    /// Storage corruption detected - abort immediately
+    fn corruption(e: RecoveryError) -> RecoveryError {
+        RecoveryError::storage_corruption(e.offset().unwrap_or(0), e.message())
+    }
 }
 
 #[cfg(test)]
@@ -264,6 +369,65 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_verification_levels() {
+        let records: Vec<_> = (0..200)
+            .map(|i| make_record(&format!("user_{}", i), "users", "v1", i * 100))
+            .collect();
+        let schema = MockSchemaRegistry::new();
+        let verify = |level| {
+            let mut storage = MockStorage::new(records.clone());
+            ConsistencyVerifier::verify_at(&mut storage, &schema, level).unwrap()
+        };
+
+        let stats = verify(VerificationLevel::None);
+        assert_eq!((stats.records_verified, stats.records_skipped), (0, 0));
+
+        let stats = verify(VerificationLevel::ManifestOnly);
+        assert_eq!((stats.records_verified, stats.records_skipped), (0, 200));
+
+        let stats = verify(VerificationLevel::Full);
+        assert_eq!((stats.records_verified, stats.records_skipped), (200, 0));
+
+        let sampled = VerificationLevel::Sampled {
+            percent: 10,
+            seed: 7,
+        };
+        let stats = verify(sampled);
+        assert_eq!(stats.records_verified + stats.records_skipped, 200);
+        assert!((5..=40).contains(&stats.records_verified));
+        // The same seed checks the same records
+        assert_eq!(verify(sampled).records_verified, stats.records_verified);
+        let all = VerificationLevel::Sampled {
+            percent: 100,
+            seed: 7,
+        };
+        assert_eq!(verify(all).records_verified, 200);
+    }
+
+    #[test]
+    fn test_sampled_verification_is_reproducible() {
+        let level = VerificationLevel::Sampled {
+            percent: 20,
+            seed: 42,
+        };
+        let picked: Vec<u64> = (0..500).filter(|&i| level.checks(i)).collect();
+        let again: Vec<u64> = (0..500).filter(|&i| level.checks(i)).collect();
+        assert_eq!(picked, again);
+
+        let other_seed = VerificationLevel::Sampled {
+            percent: 20,
+            seed: 43,
+        };
+        let other: Vec<u64> = (0..500).filter(|&i| other_seed.checks(i)).collect();
+        assert_ne!(picked, other);
+
+        for name in ["none", "manifest-only", "sampled", "full"] {
+            assert_eq!(VerificationLevel::from_name(name).unwrap().name(), name);
+        }
+        assert!(VerificationLevel::from_name("partial").is_none());
+    }
+
     #[test]
     fn test_empty_storage() {
         let mut storage = MockStorage::new(vec![]);
@@ -0,0 +1,868 @@
+//! Replica Bootstrap over the Network
+//!
+//! A new Replica asks the Primary for a snapshot, installs it, then
+//! follows the Primary's WAL from the snapshot boundary on the same
+//! connection.
+//!
+//! Per REPLICATION_SNAPSHOT_TRANSFER.md §4, in order:
+//! 1. Replica sends `SnapshotRequest`, naming any partial download
+//! 2. Primary answers `SnapshotOffer`: snapshot id, commit boundary, WAL
+//!    sequence, and every file with its size and CRC32
+//! 3. Primary streams the files as `SnapshotChunk`s, then `SnapshotEnd`
+//! 4. Replica verifies every file and the manifest, then installs the
+//!    snapshot atomically (§5)
+//! 5. The WAL handshake resumes strictly after the boundary (§6)
+//!
+//! # Resuming
+//!
+//! Chunks are staged in `<target>.partial` next to the offer they belong
+//! to. A Replica that reconnects asks to resume from the bytes already
+//! staged; the Primary resumes if it still offers the same snapshot and
+//! restarts from zero otherwise. Staged bytes are installed only once
+//! every file verifies, so an interrupted transfer never leaves partial
+//! state in place (§9.1).
+//!
+//! Only unfiltered Replicas bootstrap this way: a snapshot holds every
+//! collection.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+
+use crc32fast::Hasher;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::config::ReplicationConfig;
+use super::errors::{ReplicationError, ReplicationErrorKind, ReplicationResult};
+use super::publication::PublicationManifest;
+use super::role::HaltReason;
+use super::snapshot_transfer::{
+    check_snapshot_eligibility, SnapshotInstallResult, SnapshotMetadata, SnapshotReceiver,
+};
+use super::transport::{
+    configure_stream, dial, protocol_violation, read_frame, reject, transport_io, write_frame,
+    Frame, PrimaryConnection, ReplicaConnection, TransportConfig, REPLICATION_PROTOCOL_VERSION,
+};
+use super::wal_receiver::WalReceiver;
+use super::wal_sender::WalPosition;
+use crate::mvcc::CommitId;
+use crate::snapshot::{compute_file_checksum, parse_checksum, SnapshotManifest};
+
+/// Offer kept in the staging directory while a download is in progress
+const OFFER_FILE: &str = "transfer.json";
+
+/// One file of a snapshot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotFile {
+    /// Path relative to the snapshot directory, `/`-separated
+    pub path: String,
+    /// Size in bytes
+    pub size: u64,
+    /// CRC32 of the contents
+    pub crc32: u32,
+}
+
+/// Download the Replica already holds part of
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotResume {
+    /// Snapshot being downloaded
+    pub snapshot_id: String,
+    /// Bytes staged so far
+    pub offset: u64,
+}
+
+/// Replica's request for a snapshot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotRequest {
+    /// Wire protocol version
+    pub protocol_version: u16,
+    /// Replica identity
+    pub replica_id: Uuid,
+    /// Partial download to continue, if any
+    pub resume: Option<SnapshotResume>,
+}
+
+/// Snapshot the Primary is about to stream
+///
+/// Files are streamed in order as one run of bytes; chunk offsets index
+/// into it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotOffer {
+    /// Snapshot id from its manifest
+    pub snapshot_id: String,
+    /// MVCC commit boundary
+    pub commit_boundary: u64,
+    /// WAL sequence at the boundary
+    pub wal_sequence: u64,
+    /// Files, in streaming order
+    pub files: Vec<SnapshotFile>,
+    /// Offset the first chunk starts at (non-zero when resuming)
+    pub start_offset: u64,
+}
+
+impl SnapshotOffer {
+    /// Total bytes across all files
+    pub fn total_bytes(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+
+    /// Checksum over every file's CRC32, in order
+    pub fn checksum(&self) -> u64 {
+        let mut hasher = Hasher::new();
+        for file in &self.files {
+            hasher.update(file.path.as_bytes());
+            hasher.update(&file.crc32.to_le_bytes());
+        }
+        hasher.finalize() as u64
+    }
+
+    /// Replication metadata for this snapshot
+    pub fn metadata(&self) -> SnapshotMetadata {
+        SnapshotMetadata::new(
+            CommitId::new(self.commit_boundary),
+            self.wal_sequence,
+            self.checksum(),
+            self.total_bytes(),
+        )
+    }
+
+    /// File containing `offset` and the offset within it
+    fn locate(&self, offset: u64) -> Option<(usize, u64)> {
+        let mut start = 0;
+        for (i, file) in self.files.iter().enumerate() {
+            if offset < start + file.size {
+                return Some((i, offset - start));
+            }
+            start += file.size;
+        }
+        None
+    }
+}
+
+/// Snapshot the Primary serves to bootstrapping Replicas
+#[derive(Debug, Clone)]
+pub struct SnapshotSource {
+    dir: PathBuf,
+    offer: SnapshotOffer,
+}
+
+impl SnapshotSource {
+    /// Serve the snapshot in `dir`, taken at WAL sequence `wal_sequence`
+    ///
+    /// # Errors
+    ///
+    /// The snapshot must have an MVCC commit boundary and pass the
+    /// eligibility checks of §3.
+    pub fn open(dir: &Path, wal_sequence: u64) -> ReplicationResult<Self> {
+        let manifest = SnapshotManifest::read_from_file(&dir.join("manifest.json"))
+            .map_err(|e| snapshot_error(format!("cannot read snapshot manifest: {}", e)))?;
+        let commit_boundary = manifest.commit_boundary().ok_or_else(|| {
+            ReplicationError::configuration_error(format!(
+                "snapshot {} has no commit boundary",
+                manifest.snapshot_id
+            ))
+        })?;
+
+        let mut paths = Vec::new();
+        collect_files(dir, dir, &mut paths)?;
+        paths.sort();
+        let mut files = Vec::with_capacity(paths.len());
+        for path in paths {
+            let full = dir.join(&path);
+            let size = fs::metadata(&full).map_err(|e| io_error(&full, e))?.len();
+            let crc32 = compute_file_checksum(&full).map_err(|e| snapshot_error(e.to_string()))?;
+            files.push(SnapshotFile { path, size, crc32 });
+        }
+
+        let offer = SnapshotOffer {
+            snapshot_id: manifest.snapshot_id,
+            commit_boundary,
+            wal_sequence,
+            files,
+            start_offset: 0,
+        };
+        let eligibility = check_snapshot_eligibility(&offer.metadata());
+        if !eligibility.is_eligible() {
+            return Err(ReplicationError::configuration_error(format!(
+                "snapshot not eligible: {:?}",
+                eligibility
+            )));
+        }
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            offer,
+        })
+    }
+
+    /// What this snapshot is offered as
+    pub fn offer(&self) -> &SnapshotOffer {
+        &self.offer
+    }
+
+    /// Stream the snapshot in answer to `request`
+    pub fn serve<S: Read + Write>(
+        &self,
+        stream: &mut S,
+        config: &TransportConfig,
+        request: &SnapshotRequest,
+    ) -> ReplicationResult<()> {
+        let start_offset = match &request.resume {
+            Some(resume)
+                if resume.snapshot_id == self.offer.snapshot_id
+                    && resume.offset <= self.offer.total_bytes() =>
+            {
+                resume.offset
+            }
+            _ => 0,
+        };
+        let offer = SnapshotOffer {
+            start_offset,
+            ..self.offer.clone()
+        };
+        write_frame(stream, &Frame::SnapshotOffer(offer))?;
+
+        let chunk_bytes = config.snapshot_chunk_bytes.max(1);
+        let mut file_start = 0;
+        for file in &self.offer.files {
+            let file_end = file_start + file.size;
+            if start_offset < file_end {
+                let path = self.dir.join(&file.path);
+                let mut reader = File::open(&path).map_err(|e| io_error(&path, e))?;
+                let mut offset = start_offset.max(file_start);
+                reader
+                    .seek(SeekFrom::Start(offset - file_start))
+                    .map_err(|e| io_error(&path, e))?;
+                while offset < file_end {
+                    let len = chunk_bytes.min((file_end - offset) as usize);
+                    let mut data = vec![0u8; len];
+                    reader
+                        .read_exact(&mut data)
+                        .map_err(|e| io_error(&path, e))?;
+                    write_frame(stream, &Frame::SnapshotChunk { offset, data })?;
+                    offset += len as u64;
+                }
+            }
+            file_start = file_end;
+        }
+        write_frame(stream, &Frame::SnapshotEnd)
+    }
+}
+
+impl PrimaryConnection<TcpStream> {
+    /// Accept the next replica, first serving `snapshot` if it asks for one
+    pub fn accept_with_bootstrap(
+        listener: &TcpListener,
+        config: TransportConfig,
+        primary_id: Uuid,
+        manifest: &PublicationManifest,
+        wal_end: WalPosition,
+        snapshot: &SnapshotSource,
+    ) -> ReplicationResult<Self> {
+        let (stream, _) = listener.accept().map_err(transport_io)?;
+        configure_stream(&stream, &config)?;
+        Self::handshake_with_bootstrap(stream, config, primary_id, manifest, wal_end, snapshot)
+    }
+}
+
+impl<S: Read + Write> PrimaryConnection<S> {
+    /// Like `handshake`, but a replica may first request `snapshot`
+    pub fn handshake_with_bootstrap(
+        mut stream: S,
+        config: TransportConfig,
+        primary_id: Uuid,
+        manifest: &PublicationManifest,
+        wal_end: WalPosition,
+        snapshot: &SnapshotSource,
+    ) -> ReplicationResult<Self> {
+        let request = match read_frame(&mut stream, config.max_frame_bytes)? {
+            Frame::SnapshotRequest(request) => request,
+            Frame::Hello(hello) => {
+                return Self::admit(stream, config, primary_id, manifest, wal_end, hello)
+            }
+            other => {
+                return Err(reject(
+                    &mut stream,
+                    HaltReason::ConfigurationError,
+                    protocol_violation("SnapshotRequest or Hello", &other),
+                ))
+            }
+        };
+        if request.protocol_version != REPLICATION_PROTOCOL_VERSION {
+            let e = ReplicationError::configuration_error(format!(
+                "protocol version {} is not supported (expected {})",
+                request.protocol_version, REPLICATION_PROTOCOL_VERSION
+            ));
+            return Err(reject(&mut stream, HaltReason::ConfigurationError, e));
+        }
+
+        snapshot.serve(&mut stream, &config, &request)?;
+        Self::handshake(stream, config, primary_id, manifest, wal_end)
+    }
+}
+
+impl ReplicaConnection<TcpStream> {
+    /// Install the Primary's snapshot into `target_dir`, then follow its WAL
+    ///
+    /// `target_dir` must not exist: existing state is never reused (§7.2).
+    /// A download interrupted earlier resumes from `<target_dir>.partial`.
+    pub fn bootstrap(
+        replication: &ReplicationConfig,
+        config: TransportConfig,
+        target_dir: &Path,
+        expected_primary: Option<Uuid>,
+    ) -> ReplicationResult<(SnapshotInstallResult, Self)> {
+        let (mut stream, replica_id) = dial(replication, &config)?;
+        let installed = fetch_snapshot(&mut stream, &config, replica_id, target_dir)?;
+        let receiver = WalReceiver::new(installed.wal_resume_position());
+        let connection = Self::handshake(stream, config, replica_id, receiver, expected_primary)?;
+        Ok((installed, connection))
+    }
+}
+
+/// Download, verify and install a snapshot into `target_dir`
+///
+/// # Errors
+///
+/// `Transport` if the connection drops; the staged bytes are kept for the
+/// next attempt. Any integrity failure discards them and sends the
+/// Primary `Reject`.
+pub fn fetch_snapshot<S: Read + Write>(
+    stream: &mut S,
+    config: &TransportConfig,
+    replica_id: Uuid,
+    target_dir: &Path,
+) -> ReplicationResult<SnapshotInstallResult> {
+    receive_snapshot(stream, config, replica_id, target_dir).map_err(|e| {
+        if e.kind == ReplicationErrorKind::SnapshotIntegrity {
+            reject(stream, HaltReason::SnapshotIntegrityFailure, e)
+        } else {
+            e
+        }
+    })
+}
+
+fn receive_snapshot<S: Read + Write>(
+    stream: &mut S,
+    config: &TransportConfig,
+    replica_id: Uuid,
+    target_dir: &Path,
+) -> ReplicationResult<SnapshotInstallResult> {
+    if target_dir.exists() {
+        return Err(ReplicationError::configuration_error(format!(
+            "{} already exists; discard existing replica state explicitly before bootstrapping",
+            target_dir.display()
+        )));
+    }
+    let staging = staging_dir(target_dir);
+    let staged = staged_download(&staging);
+
+    write_frame(
+        stream,
+        &Frame::SnapshotRequest(SnapshotRequest {
+            protocol_version: REPLICATION_PROTOCOL_VERSION,
+            replica_id,
+            resume: staged.as_ref().map(|(offer, offset)| SnapshotResume {
+                snapshot_id: offer.snapshot_id.clone(),
+                offset: *offset,
+            }),
+        }),
+    )?;
+
+    let offer = match read_frame(stream, config.max_frame_bytes)? {
+        Frame::SnapshotOffer(offer) => offer,
+        Frame::Reject { reason, message } => {
+            return Err(ReplicationError::halted(format!(
+                "primary rejected snapshot request ({:?}): {}",
+                reason, message
+            )))
+        }
+        other => return Err(protocol_violation("SnapshotOffer", &other)),
+    };
+    if let Some(file) = offer.files.iter().find(|f| !is_safe_path(&f.path)) {
+        return Err(snapshot_error(format!(
+            "unsafe snapshot path '{}'",
+            file.path
+        )));
+    }
+
+    // A different snapshot, or a restart, discards what was staged
+    let resumes = matches!(&staged, Some((prior, offset))
+        if prior.snapshot_id == offer.snapshot_id && *offset == offer.start_offset && offer.start_offset > 0);
+    if !resumes {
+        if offer.start_offset != 0 {
+            return Err(snapshot_error(format!(
+                "primary resumed at offset {} of a download not staged here",
+                offer.start_offset
+            )));
+        }
+        prepare_staging(&staging, &offer)?;
+    }
+
+    let mut receiver = SnapshotReceiver::new();
+    receiver.start_transfer(offer.metadata())?;
+    if offer.start_offset > 0 {
+        receiver.receive_bytes(offer.start_offset)?;
+    }
+
+    let mut offset = offer.start_offset;
+    loop {
+        match read_frame(stream, config.max_frame_bytes)? {
+            Frame::SnapshotChunk { offset: at, data } => {
+                if at != offset {
+                    return Err(discard(
+                        &staging,
+                        format!("snapshot chunk at offset {}, expected {}", at, offset),
+                    ));
+                }
+                write_chunk(&staging, &offer, offset, &data)
+                    .map_err(|e| discard(&staging, e.message))?;
+                offset += data.len() as u64;
+                receiver.receive_bytes(data.len() as u64)?;
+            }
+            Frame::SnapshotEnd => break,
+            Frame::Reject { reason, message } => {
+                return Err(ReplicationError::halted(format!(
+                    "primary halted snapshot transfer ({:?}): {}",
+                    reason, message
+                )))
+            }
+            other => {
+                let _ = fs::remove_dir_all(&staging);
+                return Err(protocol_violation("SnapshotChunk", &other));
+            }
+        }
+    }
+    if offset != offer.total_bytes() {
+        return Err(discard(
+            &staging,
+            format!(
+                "snapshot ended at {} of {} bytes",
+                offset,
+                offer.total_bytes()
+            ),
+        ));
+    }
+
+    verify_staged(&staging, &offer).map_err(|e| discard(&staging, e.message))?;
+    receiver
+        .validate()
+        .map_err(|e| discard(&staging, e.message))?;
+
+    // Atomic install: the staged directory becomes the snapshot
+    fs::remove_file(staging.join(OFFER_FILE)).map_err(|e| io_error(&staging, e))?;
+    fs::rename(&staging, target_dir).map_err(|e| io_error(target_dir, e))?;
+    if let Some(parent) = target_dir.parent() {
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    receiver.install()
+}
+
+/// Staging directory for a download into `target_dir`
+pub fn staging_dir(target_dir: &Path) -> PathBuf {
+    let mut name = target_dir.file_name().unwrap_or_default().to_os_string();
+    name.push(".partial");
+    target_dir.with_file_name(name)
+}
+
+/// The staged offer and how many of its bytes are on disk
+///
+/// Anything unreadable counts as nothing staged.
+fn staged_download(staging: &Path) -> Option<(SnapshotOffer, u64)> {
+    let json = fs::read_to_string(staging.join(OFFER_FILE)).ok()?;
+    let offer: SnapshotOffer = serde_json::from_str(&json).ok()?;
+    let mut offset = 0;
+    for file in &offer.files {
+        if !is_safe_path(&file.path) {
+            return None;
+        }
+        let len = fs::metadata(staging.join(&file.path)).map_or(0, |m| m.len());
+        if len > file.size {
+            return None;
+        }
+        offset += len;
+        if len < file.size {
+            break;
+        }
+    }
+    Some((offer, offset))
+}
+
+/// Empty the staging directory and record `offer` in it
+fn prepare_staging(staging: &Path, offer: &SnapshotOffer) -> ReplicationResult<()> {
+    if staging.exists() {
+        fs::remove_dir_all(staging).map_err(|e| io_error(staging, e))?;
+    }
+    fs::create_dir_all(staging).map_err(|e| io_error(staging, e))?;
+    let json = serde_json::to_vec(offer)
+        .map_err(|e| snapshot_error(format!("cannot record snapshot offer: {}", e)))?;
+    let path = staging.join(OFFER_FILE);
+    let mut file = File::create(&path).map_err(|e| io_error(&path, e))?;
+    file.write_all(&json).map_err(|e| io_error(&path, e))?;
+    file.sync_all().map_err(|e| io_error(&path, e))
+}
+
+/// Write a chunk, which must lie within one file
+fn write_chunk(
+    staging: &Path,
+    offer: &SnapshotOffer,
+    offset: u64,
+    data: &[u8],
+) -> ReplicationResult<()> {
+    let (index, within) = offer
+        .locate(offset)
+        .ok_or_else(|| snapshot_error(format!("snapshot chunk at {} is past the end", offset)))?;
+    let file = &offer.files[index];
+    if within + data.len() as u64 > file.size {
+        return Err(snapshot_error(format!(
+            "snapshot chunk at {} overruns {}",
+            offset, file.path
+        )));
+    }
+
+    let path = staging.join(&file.path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| io_error(parent, e))?;
+    }
+    let mut out = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(|e| io_error(&path, e))?;
+    out.seek(SeekFrom::Start(within))
+        .map_err(|e| io_error(&path, e))?;
+    out.write_all(data).map_err(|e| io_error(&path, e))
+}
+
+/// Check every file against the offer and the manifest, and fsync it
+///
+/// Per §5.2: checksums, manifest integrity, and commit boundary.
+fn verify_staged(staging: &Path, offer: &SnapshotOffer) -> ReplicationResult<()> {
+    for file in &offer.files {
+        let path = staging.join(&file.path);
+        let crc32 = compute_file_checksum(&path).map_err(|e| snapshot_error(e.to_string()))?;
+        if crc32 != file.crc32 {
+            return Err(snapshot_error(format!(
+                "checksum mismatch for {}: computed {:08x}, offered {:08x}",
+                file.path, crc32, file.crc32
+            )));
+        }
+        File::open(&path)
+            .and_then(|f| f.sync_all())
+            .map_err(|e| io_error(&path, e))?;
+    }
+
+    let manifest = SnapshotManifest::read_from_file(&staging.join("manifest.json"))
+        .map_err(|e| snapshot_error(format!("invalid snapshot manifest: {}", e)))?;
+    if manifest.snapshot_id != offer.snapshot_id
+        || manifest.commit_boundary() != Some(offer.commit_boundary)
+    {
+        return Err(snapshot_error(format!(
+            "manifest describes snapshot {} at boundary {:?}, offer was {} at {}",
+            manifest.snapshot_id,
+            manifest.commit_boundary(),
+            offer.snapshot_id,
+            offer.commit_boundary
+        )));
+    }
+    let expected = std::iter::once(("storage.dat".to_string(), &manifest.storage_checksum)).chain(
+        manifest
+            .schema_checksums
+            .iter()
+            .map(|(name, checksum)| (format!("schemas/{}", name), checksum)),
+    );
+    for (path, checksum) in expected {
+        let recorded = parse_checksum(checksum);
+        let offered = offer.files.iter().find(|f| f.path == path).map(|f| f.crc32);
+        if recorded.is_none() || recorded != offered {
+            return Err(snapshot_error(format!(
+                "{} does not match the snapshot manifest",
+                path
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Discard the staged download after an integrity failure
+fn discard(staging: &Path, message: impl Into<String>) -> ReplicationError {
+    let _ = fs::remove_dir_all(staging);
+    snapshot_error(message)
+}
+
+/// Relative paths under `root`, `/`-separated
+fn collect_files(root: &Path, dir: &Path, out: &mut Vec<String>) -> ReplicationResult<()> {
+    let entries = fs::read_dir(dir).map_err(|e| io_error(dir, e))?;
+    for entry in entries {
+        let path = entry.map_err(|e| io_error(dir, e))?.path();
+        if path.is_dir() {
+            collect_files(root, &path, out)?;
+        } else {
+            let relative = path.strip_prefix(root).expect("walked from root");
+            let parts: Vec<String> = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                .collect();
+            out.push(parts.join("/"));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `path` stays inside the snapshot directory
+fn is_safe_path(path: &str) -> bool {
+    !path.is_empty()
+        && path != OFFER_FILE
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+}
+
+fn snapshot_error(message: impl Into<String>) -> ReplicationError {
+    ReplicationError::snapshot_integrity_failed(message)
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> ReplicationError {
+    ReplicationError::transport(format!("snapshot I/O on {} failed: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replication::WalRecordEnvelope;
+    use crate::snapshot::format_checksum;
+    use crate::wal::{WalPayload, WalRecord};
+    use std::collections::HashMap;
+    use std::thread;
+    use tempfile::TempDir;
+
+    /// A snapshot directory with a manifest, storage and one schema
+    fn make_snapshot(dir: &Path) {
+        let storage = vec![7u8; 300];
+        let schema = br#"{"name": "users"}"#.to_vec();
+        fs::create_dir_all(dir.join("schemas")).unwrap();
+        fs::write(dir.join("storage.dat"), &storage).unwrap();
+        fs::write(dir.join("schemas/users_v1.json"), &schema).unwrap();
+        let crc = |data: &[u8]| {
+            let mut hasher = Hasher::new();
+            hasher.update(data);
+            format_checksum(hasher.finalize())
+        };
+        let manifest = SnapshotManifest::with_mvcc_boundary(
+            "20260101T000000Z",
+            "2026-01-01T00:00:00Z",
+            crc(&storage),
+            HashMap::from([("users_v1.json".to_string(), crc(&schema))]),
+            42,
+        );
+        manifest.write_to_file(&dir.join("manifest.json")).unwrap();
+    }
+
+    fn config() -> TransportConfig {
+        TransportConfig {
+            snapshot_chunk_bytes: 64,
+            ..TransportConfig::default()
+        }
+    }
+
+    fn record(sequence_number: u64) -> WalRecord {
+        WalRecord::insert(
+            sequence_number,
+            WalPayload::new("users", "doc1", "users", "v1", b"{}".to_vec()),
+        )
+    }
+
+    #[test]
+    fn test_bootstrap_then_wal_catch_up() {
+        let temp = TempDir::new().unwrap();
+        let source_dir = temp.path().join("primary-snapshot");
+        make_snapshot(&source_dir);
+        let source = SnapshotSource::open(&source_dir, 10).unwrap();
+        assert_eq!(source.offer().files.len(), 3);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let primary = thread::spawn(move || {
+            let mut conn = PrimaryConnection::accept_with_bootstrap(
+                &listener,
+                config(),
+                Uuid::new_v4(),
+                &PublicationManifest::new(),
+                WalPosition::new(12, 0),
+                &source,
+            )
+            .unwrap();
+            let sent = conn.send(&record(12)).unwrap().unwrap();
+            conn.wait_for_ack(conn.sender().current_position()).unwrap();
+            sent
+        });
+
+        let target = temp.path().join("replica-snapshot");
+        let replication = ReplicationConfig::replica(address, None);
+        let (installed, mut conn) =
+            ReplicaConnection::bootstrap(&replication, config(), &target, None).unwrap();
+        assert_eq!(installed.commit_boundary, CommitId::new(42));
+        assert_eq!(installed.wal_resume_sequence, 11);
+
+        // WAL resumes strictly after the snapshot boundary
+        let envelope: WalRecordEnvelope = conn.next_record().unwrap();
+        conn.ack(&envelope).unwrap();
+        assert_eq!(primary.join().unwrap(), WalPosition::new(11, 0));
+
+        for file in ["manifest.json", "storage.dat", "schemas/users_v1.json"] {
+            assert_eq!(
+                fs::read(target.join(file)).unwrap(),
+                fs::read(source_dir.join(file)).unwrap()
+            );
+        }
+        assert!(!target.join(OFFER_FILE).exists());
+        assert!(!staging_dir(&target).exists());
+    }
+
+    #[test]
+    fn test_interrupted_download_resumes() {
+        let temp = TempDir::new().unwrap();
+        let source_dir = temp.path().join("primary-snapshot");
+        make_snapshot(&source_dir);
+        let source = SnapshotSource::open(&source_dir, 10).unwrap();
+        let target = temp.path().join("replica-snapshot");
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let served = source.clone();
+        let primary = thread::spawn(move || {
+            // First connection drops after the offer and one chunk
+            let (mut stream, _) = listener.accept().unwrap();
+            let Frame::SnapshotRequest(request) = read_frame(&mut stream, usize::MAX).unwrap()
+            else {
+                panic!("expected snapshot request");
+            };
+            assert_eq!(request.resume, None);
+            write_frame(&mut stream, &Frame::SnapshotOffer(served.offer().clone())).unwrap();
+            let data = fs::read(source_dir.join(&served.offer().files[0].path)).unwrap();
+            let chunk = Frame::SnapshotChunk {
+                offset: 0,
+                data: data[..64].to_vec(),
+            };
+            write_frame(&mut stream, &chunk).unwrap();
+            drop(stream);
+
+            // Second connection resumes where the first stopped
+            let (mut stream, _) = listener.accept().unwrap();
+            let Frame::SnapshotRequest(request) = read_frame(&mut stream, usize::MAX).unwrap()
+            else {
+                panic!("expected snapshot request");
+            };
+            served.serve(&mut stream, &config(), &request).unwrap();
+            request.resume
+        });
+
+        let mut stream = TcpStream::connect(&address).unwrap();
+        let err = fetch_snapshot(&mut stream, &config(), Uuid::new_v4(), &target).unwrap_err();
+        assert_eq!(err.kind, ReplicationErrorKind::Transport);
+        assert!(staging_dir(&target).exists());
+        assert!(!target.exists());
+
+        let mut stream = TcpStream::connect(&address).unwrap();
+        let installed = fetch_snapshot(&mut stream, &config(), Uuid::new_v4(), &target).unwrap();
+        assert_eq!(installed.commit_boundary, CommitId::new(42));
+
+        let resume = primary.join().unwrap().unwrap();
+        assert_eq!(resume.offset, 64);
+        assert_eq!(resume.snapshot_id, "20260101T000000Z");
+        assert!(target.join("storage.dat").exists());
+    }
+
+    #[test]
+    fn test_corrupt_chunk_discards_download() {
+        let temp = TempDir::new().unwrap();
+        let source_dir = temp.path().join("primary-snapshot");
+        make_snapshot(&source_dir);
+        let source = SnapshotSource::open(&source_dir, 10).unwrap();
+
+        // The offer promises different contents than the files hold
+        let mut offer = source.offer().clone();
+        offer.files[0].crc32 ^= 1;
+        let mut wire = Vec::new();
+        write_frame(&mut wire, &Frame::SnapshotOffer(offer.clone())).unwrap();
+        let mut offset = 0;
+        for file in &offer.files {
+            let data = fs::read(source_dir.join(&file.path)).unwrap();
+            write_frame(&mut wire, &Frame::SnapshotChunk { offset, data }).unwrap();
+            offset += file.size;
+        }
+        write_frame(&mut wire, &Frame::SnapshotEnd).unwrap();
+
+        let target = temp.path().join("replica-snapshot");
+        let mut stream = Duplex {
+            input: std::io::Cursor::new(wire),
+            output: Vec::new(),
+        };
+        let err = fetch_snapshot(&mut stream, &config(), Uuid::new_v4(), &target).unwrap_err();
+        assert_eq!(err.kind, ReplicationErrorKind::SnapshotIntegrity);
+        assert!(!target.exists());
+        assert!(!staging_dir(&target).exists());
+
+        // The Primary is told why
+        let mut sent = stream.output.as_slice();
+        assert!(matches!(
+            read_frame(&mut sent, usize::MAX).unwrap(),
+            Frame::SnapshotRequest(_)
+        ));
+        assert!(matches!(
+            read_frame(&mut sent, usize::MAX).unwrap(),
+            Frame::Reject {
+                reason: HaltReason::SnapshotIntegrityFailure,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_existing_state_is_not_reused() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("replica-snapshot");
+        fs::create_dir_all(&target).unwrap();
+        let mut stream = Duplex {
+            input: std::io::Cursor::new(Vec::new()),
+            output: Vec::new(),
+        };
+        let err = fetch_snapshot(&mut stream, &config(), Uuid::new_v4(), &target).unwrap_err();
+        assert_eq!(err.kind, ReplicationErrorKind::ConfigurationError);
+        assert!(stream.output.is_empty());
+    }
+
+    #[test]
+    fn test_unsafe_paths_rejected() {
+        assert!(is_safe_path("schemas/users_v1.json"));
+        assert!(!is_safe_path("../escape"));
+        assert!(!is_safe_path("/etc/passwd"));
+        assert!(!is_safe_path(OFFER_FILE));
+    }
+
+    /// Scripted input with captured output
+    struct Duplex {
+        input: std::io::Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for Duplex {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for Duplex {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+}
@@ -46,8 +46,14 @@ pub struct ReplicationConfig {
     /// Per Stage 2+ requirements:
     /// - Required for replicas
     /// - Forbidden for primaries
-    /// - Not used in Stage 1 (networking deferred)
+    /// - Dialed by `ReplicaConnection::connect`
+    /// - For a cascading replica, this is its relay's address
     pub primary_address: Option<String>,
+
+    /// Whether this replica relays WAL to downstream replicas.
+    ///
+    /// Replicas only; a relay still never assigns CommitIds.
+    pub relay: bool,
 }
 
 impl ReplicationConfig {
@@ -63,6 +69,7 @@ impl ReplicationConfig {
             role,
             replica_id,
             primary_address,
+            relay: false,
         }
     }
 
@@ -75,6 +82,7 @@ impl ReplicationConfig {
             role: ReplicationRole::Primary,
             replica_id: None,
             primary_address: None,
+            relay: false,
         }
     }
 
@@ -85,6 +93,7 @@ impl ReplicationConfig {
             role: ReplicationRole::Primary,
             replica_id: None,
             primary_address: None,
+            relay: false,
         }
     }
 
@@ -97,14 +106,22 @@ impl ReplicationConfig {
             role: ReplicationRole::Replica,
             replica_id: Some(replica_id.unwrap_or_else(Uuid::new_v4)),
             primary_address: Some(primary_address),
+            relay: false,
         }
     }
 
+    /// Also relay WAL to downstream replicas.
+    pub fn with_relay(mut self) -> Self {
+        self.relay = true;
+        self
+    }
+
     /// Validate the configuration.
     ///
     /// Per PHASE5_IMPLEMENTATION_ORDER.md §Stage 1:
     /// - Replica requires primary_address
     /// - Primary forbids primary_address
+    /// - Only a replica may relay
     pub fn validate(&self) -> ReplicationResult<()> {
         if !self.enabled {
             // Disabled config is always valid
@@ -123,6 +140,11 @@ impl ReplicationConfig {
                         "Primary must not have replica_id configured",
                     ));
                 }
+                if self.relay {
+                    return Err(ReplicationError::configuration_error(
+                        "Primary must not be configured as a relay",
+                    ));
+                }
             }
             ReplicationRole::Replica => {
                 if self.primary_address.is_none() {
@@ -156,6 +178,11 @@ impl ReplicationConfig {
         self.enabled && self.role == ReplicationRole::Replica
     }
 
+    /// Check if this is a relaying replica configuration.
+    pub fn is_relay(&self) -> bool {
+        self.is_replica() && self.relay
+    }
+
     /// Get the replica ID if this is a replica.
     pub fn get_replica_id(&self) -> Option<Uuid> {
         if self.is_replica() {
@@ -216,6 +243,7 @@ mod tests {
             role: ReplicationRole::Replica,
             replica_id: Some(Uuid::new_v4()),
             primary_address: None,
+            relay: false,
         };
         let result = config.validate();
         assert!(result.is_err());
@@ -230,6 +258,7 @@ mod tests {
             role: ReplicationRole::Primary,
             replica_id: None,
             primary_address: Some("other:5432".to_string()),
+            relay: false,
         };
         let result = config.validate();
         assert!(result.is_err());
@@ -242,11 +271,25 @@ mod tests {
             role: ReplicationRole::Primary,
             replica_id: Some(Uuid::new_v4()),
             primary_address: None,
+            relay: false,
         };
         let result = config.validate();
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_only_replicas_relay() {
+        let relay = ReplicationConfig::replica("relay:5432".to_string(), None).with_relay();
+        assert!(relay.validate().is_ok());
+        assert!(relay.is_relay());
+
+        let config = ReplicationConfig {
+            relay: true,
+            ..ReplicationConfig::primary()
+        };
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_replica_auto_generates_uuid() {
         let config1 = ReplicationConfig::replica("primary:5432".to_string(), None);
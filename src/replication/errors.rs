@@ -48,6 +48,12 @@ pub enum ReplicationErrorKind {
 
     /// Configuration error
     ConfigurationError,
+
+    /// Transferred snapshot failed validation
+    SnapshotIntegrity,
+
+    /// Replication connection failed or timed out (reconnect to retry)
+    Transport,
 }
 
 impl ReplicationError {
@@ -109,6 +115,16 @@ impl ReplicationError {
         Self::new(ReplicationErrorKind::ConfigurationError, message)
     }
 
+    /// Create a snapshot integrity error.
+    pub fn snapshot_integrity_failed(message: impl Into<String>) -> Self {
+        Self::new(ReplicationErrorKind::SnapshotIntegrity, message)
+    }
+
+    /// Create a transport error.
+    pub fn transport(message: impl Into<String>) -> Self {
+        Self::new(ReplicationErrorKind::Transport, message)
+    }
+
     /// Check if this error is fatal (requires operator intervention).
     pub fn is_fatal(&self) -> bool {
         matches!(
@@ -148,5 +164,6 @@ mod tests {
     fn test_non_fatal_errors() {
         assert!(!ReplicationError::write_rejected("test").is_fatal());
         assert!(!ReplicationError::illegal_transition("test").is_fatal());
+        assert!(!ReplicationError::transport("test").is_fatal());
     }
 }
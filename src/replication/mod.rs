@@ -20,17 +20,23 @@
 //! # Phase 3 Optimizations
 //!
 //! - Fast Read: Pre-validated snapshot reuse on replicas (optional, disabled by default)
+//! - Publications: Per-subscriber collection filters, validated at handshake
 
 mod authority;
+mod bootstrap;
 mod compatibility;
 mod config;
 mod errors;
 mod failure_matrix;
 mod fast_read;
+mod publication;
 mod recovery;
+mod relay;
 mod replica_reads;
 mod role;
 mod snapshot_transfer;
+mod topology;
+mod transport;
 mod wal_receiver;
 mod wal_sender;
 
@@ -38,6 +44,10 @@ pub use authority::{
     check_commit_authority, check_dual_primary, check_write_admission, AuthorityCheck,
     WriteAdmission,
 };
+pub use bootstrap::{
+    fetch_snapshot, staging_dir, SnapshotFile, SnapshotOffer, SnapshotRequest, SnapshotResume,
+    SnapshotSource,
+};
 pub use compatibility::{
     CompatibilityAssertion, CompatibilityCheck, MvccCompatibility, Phase1Compatibility,
 };
@@ -48,12 +58,22 @@ pub use fast_read::{
     FastReadConfig, FastReadManager, FastReadResult, FastReadStats, ReplicaReadPath,
     ReplicaSafetyState, SafetyCheck, SafetyValidator, SafetyViolation,
 };
+pub use publication::{
+    Publication, PublicationManifest, PublishList, ReplicaHandshake, PUBLICATION_MANIFEST_FILE,
+    PUBLICATION_MANIFEST_VERSION,
+};
 pub use recovery::{PrimaryRecovery, RecoveryValidation, ReplicaRecovery, ReplicaResumeState};
+pub use relay::{RelayOutcome, WalRelay};
 pub use replica_reads::{ReadEligibility, ReplicaReadAdmission};
 pub use role::{HaltReason, ReplicationRole, ReplicationState};
 pub use snapshot_transfer::{
     check_snapshot_eligibility, SnapshotEligibility, SnapshotInstallResult, SnapshotMetadata,
     SnapshotReceiver, SnapshotTransferState,
 };
+pub use topology::ReplicationTopology;
+pub use transport::{
+    read_frame, write_frame, Frame, Hello, PrimaryConnection, ReplicaConnection, TransportConfig,
+    Welcome, REPLICATION_PROTOCOL_VERSION, WAL_FORMAT_VERSION,
+};
 pub use wal_receiver::{ReceiveResult, WalReceiver};
 pub use wal_sender::{WalPosition, WalRecordEnvelope, WalSender};
@@ -0,0 +1,398 @@
+//! Logical Replication Publications
+//!
+//! A publication restricts which collections a Primary streams to a given
+//! subscriber (replica or observer). Analytics followers that only need a
+//! subset of the data subscribe to a filtered publication.
+//!
+//! Per REPLICATION_LOG_FLOW.md §3.2, an unfiltered replica WAL is a prefix of
+//! the Primary WAL. A filtered subscriber's WAL is NOT such a prefix, so:
+//! - The filter is recorded in the publication manifest on the Primary
+//! - The subscriber must declare the publication it expects at handshake
+//! - Any mismatch is a configuration error, never silently accepted
+//!
+//! Positions on a filtered stream count published records only, so gap
+//! detection on the receiver remains valid for the published subset.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+use crc32fast::Hasher;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::errors::{ReplicationError, ReplicationResult};
+use crate::wal::WalRecord;
+
+/// Current publication manifest format version
+pub const PUBLICATION_MANIFEST_VERSION: u8 = 1;
+
+/// Publication manifest filename within the data directory
+pub const PUBLICATION_MANIFEST_FILE: &str = "publications.json";
+
+/// Set of collections published to a subscriber
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", content = "collections", rename_all = "snake_case")]
+pub enum PublishList {
+    /// Every collection is published (full physical replication)
+    All,
+    /// Only the listed collections are published
+    Collections(BTreeSet<String>),
+}
+
+/// Publication definition
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Publication {
+    /// Publication name
+    pub name: String,
+    /// Published collections
+    pub collections: PublishList,
+}
+
+impl Publication {
+    /// Create the default publication that streams every collection.
+    pub fn all() -> Self {
+        Self {
+            name: "all".to_string(),
+            collections: PublishList::All,
+        }
+    }
+
+    /// Create a publication restricted to the given collections.
+    pub fn for_collections<I, S>(name: impl Into<String>, collections: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            collections: PublishList::Collections(
+                collections.into_iter().map(Into::into).collect(),
+            ),
+        }
+    }
+
+    /// Check whether this publication filters out any collections.
+    pub fn is_filtered(&self) -> bool {
+        matches!(self.collections, PublishList::Collections(_))
+    }
+
+    /// Check whether a collection is published.
+    pub fn includes(&self, collection_id: &str) -> bool {
+        match &self.collections {
+            PublishList::All => true,
+            PublishList::Collections(set) => set.contains(collection_id),
+        }
+    }
+
+    /// Check whether a WAL record belongs to this publication.
+    ///
+    /// Non-document records (e.g. MVCC commit markers) carry no collection
+    /// and are published to every subscriber.
+    pub fn includes_record(&self, record: &WalRecord) -> bool {
+        let collection_id = &record.payload.collection_id;
+        collection_id.is_empty() || self.includes(collection_id)
+    }
+
+    /// Validate the publication definition.
+    ///
+    /// A filtered publication must name at least one collection, since an
+    /// empty filter would silently stream nothing.
+    pub fn validate(&self) -> ReplicationResult<()> {
+        if self.name.is_empty() {
+            return Err(ReplicationError::configuration_error(
+                "Publication name must not be empty",
+            ));
+        }
+        if let PublishList::Collections(set) = &self.collections {
+            if set.is_empty() {
+                return Err(ReplicationError::configuration_error(format!(
+                    "Publication '{}' must list at least one collection",
+                    self.name
+                )));
+            }
+            if set.iter().any(|c| c.is_empty()) {
+                return Err(ReplicationError::configuration_error(format!(
+                    "Publication '{}' contains an empty collection name",
+                    self.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Deterministic fingerprint of the publication filter.
+    ///
+    /// Collections are hashed in sorted order so the fingerprint is stable
+    /// across nodes and restarts.
+    pub fn fingerprint(&self) -> u32 {
+        let mut hasher = Hasher::new();
+        hasher.update(self.name.as_bytes());
+        hasher.update(&[0]);
+        match &self.collections {
+            PublishList::All => hasher.update(b"*"),
+            PublishList::Collections(set) => {
+                for collection in set {
+                    hasher.update(collection.as_bytes());
+                    hasher.update(&[0]);
+                }
+            }
+        }
+        hasher.finalize()
+    }
+}
+
+impl Default for Publication {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+/// Handshake sent by a subscriber when connecting to the Primary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicaHandshake {
+    /// Subscriber identity
+    pub replica_id: Uuid,
+    /// Publication the subscriber believes it is consuming
+    pub expected_publication: Publication,
+}
+
+impl ReplicaHandshake {
+    /// Create a new handshake.
+    pub fn new(replica_id: Uuid, expected_publication: Publication) -> Self {
+        Self {
+            replica_id,
+            expected_publication,
+        }
+    }
+}
+
+/// Publication manifest recorded on the Primary
+///
+/// Maps each subscriber to the publication it receives. Subscribers
+/// without an entry receive the full (unfiltered) stream.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicationManifest {
+    /// Manifest format version
+    pub format_version: u8,
+    /// Subscriber ID -> publication
+    pub subscribers: BTreeMap<Uuid, Publication>,
+}
+
+impl PublicationManifest {
+    /// Create an empty manifest.
+    pub fn new() -> Self {
+        Self {
+            format_version: PUBLICATION_MANIFEST_VERSION,
+            subscribers: BTreeMap::new(),
+        }
+    }
+
+    /// Assign a publication to a subscriber.
+    pub fn assign(&mut self, replica_id: Uuid, publication: Publication) -> ReplicationResult<()> {
+        publication.validate()?;
+        self.subscribers.insert(replica_id, publication);
+        Ok(())
+    }
+
+    /// Remove a subscriber's publication, reverting it to the full stream.
+    pub fn unassign(&mut self, replica_id: &Uuid) -> Option<Publication> {
+        self.subscribers.remove(replica_id)
+    }
+
+    /// Get the publication for a subscriber.
+    pub fn publication_for(&self, replica_id: &Uuid) -> Publication {
+        self.subscribers
+            .get(replica_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Validate a subscriber handshake against the recorded publication.
+    ///
+    /// The subscriber's expectation must match the recorded filter exactly.
+    /// A replica assuming it holds the full history while the Primary only
+    /// streams a subset (or vice versa) is rejected.
+    pub fn validate_handshake(
+        &self,
+        handshake: &ReplicaHandshake,
+    ) -> ReplicationResult<Publication> {
+        let recorded = self.publication_for(&handshake.replica_id);
+        if recorded.fingerprint() != handshake.expected_publication.fingerprint()
+            || recorded != handshake.expected_publication
+        {
+            return Err(ReplicationError::configuration_error(format!(
+                "Publication mismatch for replica {}: primary records '{}', replica expects '{}'",
+                handshake.replica_id, recorded.name, handshake.expected_publication.name
+            )));
+        }
+        Ok(recorded)
+    }
+
+    /// Load the manifest from a data directory.
+    ///
+    /// A missing manifest means no subscriber is filtered.
+    pub fn load(data_dir: &Path) -> ReplicationResult<Self> {
+        let path = data_dir.join(PUBLICATION_MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(Self::new());
+        }
+
+        let content = fs::read_to_string(&path).map_err(|e| {
+            ReplicationError::configuration_error(format!(
+                "Failed to read publication manifest {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let manifest: Self = serde_json::from_str(&content).map_err(|e| {
+            ReplicationError::configuration_error(format!(
+                "Failed to parse publication manifest {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        if manifest.format_version != PUBLICATION_MANIFEST_VERSION {
+            return Err(ReplicationError::configuration_error(format!(
+                "Unsupported publication manifest version {}",
+                manifest.format_version
+            )));
+        }
+        for publication in manifest.subscribers.values() {
+            publication.validate()?;
+        }
+
+        Ok(manifest)
+    }
+
+    /// Persist the manifest to a data directory.
+    ///
+    /// Writes to a temporary file, fsyncs, then renames into place.
+    pub fn persist(&self, data_dir: &Path) -> ReplicationResult<()> {
+        let path = data_dir.join(PUBLICATION_MANIFEST_FILE);
+        let tmp_path = data_dir.join(format!("{}.tmp", PUBLICATION_MANIFEST_FILE));
+
+        let json = serde_json::to_string_pretty(self).map_err(|e| {
+            ReplicationError::configuration_error(format!(
+                "Failed to serialize publication manifest: {}",
+                e
+            ))
+        })?;
+
+        let io_err = |e: std::io::Error| {
+            ReplicationError::configuration_error(format!(
+                "Failed to write publication manifest {}: {}",
+                path.display(),
+                e
+            ))
+        };
+
+        let mut file = File::create(&tmp_path).map_err(io_err)?;
+        file.write_all(json.as_bytes()).map_err(io_err)?;
+        file.sync_all().map_err(io_err)?;
+        fs::rename(&tmp_path, &path).map_err(io_err)?;
+
+        Ok(())
+    }
+}
+
+impl Default for PublicationManifest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_all_publication_includes_everything() {
+        let publication = Publication::all();
+        assert!(!publication.is_filtered());
+        assert!(publication.includes("users"));
+        assert!(publication.includes("orders"));
+    }
+
+    #[test]
+    fn test_filtered_publication() {
+        let publication = Publication::for_collections("analytics", ["orders", "events"]);
+        assert!(publication.is_filtered());
+        assert!(publication.includes("orders"));
+        assert!(!publication.includes("users"));
+    }
+
+    #[test]
+    fn test_empty_filter_rejected() {
+        let publication = Publication::for_collections("empty", Vec::<String>::new());
+        assert!(publication.validate().is_err());
+    }
+
+    #[test]
+    fn test_fingerprint_is_order_independent() {
+        let a = Publication::for_collections("p", ["a", "b"]);
+        let b = Publication::for_collections("p", ["b", "a"]);
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        assert_ne!(a.fingerprint(), Publication::all().fingerprint());
+    }
+
+    #[test]
+    fn test_handshake_mismatch_rejected() {
+        let replica_id = Uuid::new_v4();
+        let mut manifest = PublicationManifest::new();
+        manifest
+            .assign(
+                replica_id,
+                Publication::for_collections("analytics", ["orders"]),
+            )
+            .unwrap();
+
+        // Replica assumes full history
+        let handshake = ReplicaHandshake::new(replica_id, Publication::all());
+        assert!(manifest.validate_handshake(&handshake).is_err());
+
+        let handshake = ReplicaHandshake::new(
+            replica_id,
+            Publication::for_collections("analytics", ["orders"]),
+        );
+        assert!(manifest.validate_handshake(&handshake).is_ok());
+    }
+
+    #[test]
+    fn test_unknown_replica_gets_full_stream() {
+        let manifest = PublicationManifest::new();
+        let handshake = ReplicaHandshake::new(Uuid::new_v4(), Publication::all());
+        assert_eq!(
+            manifest.validate_handshake(&handshake).unwrap(),
+            Publication::all()
+        );
+    }
+
+    #[test]
+    fn test_manifest_persist_roundtrip() {
+        let temp = TempDir::new().unwrap();
+        let replica_id = Uuid::new_v4();
+
+        let mut manifest = PublicationManifest::new();
+        manifest
+            .assign(
+                replica_id,
+                Publication::for_collections("analytics", ["orders"]),
+            )
+            .unwrap();
+        manifest.persist(temp.path()).unwrap();
+
+        let loaded = PublicationManifest::load(temp.path()).unwrap();
+        assert_eq!(loaded, manifest);
+    }
+
+    #[test]
+    fn test_missing_manifest_is_empty() {
+        let temp = TempDir::new().unwrap();
+        let loaded = PublicationManifest::load(temp.path()).unwrap();
+        assert!(loaded.subscribers.is_empty());
+    }
+}
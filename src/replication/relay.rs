@@ -0,0 +1,430 @@
+//! Cascading Replication
+//!
+//! A relay is a replica that streams the WAL it has applied on to its own
+//! downstream replicas, as declared in the `ReplicationTopology`.
+//!
+//! A relay forwards history, it never creates it:
+//! - Records are forwarded unchanged, and only once the relay has applied
+//!   them (REPLICATION_LOG_FLOW.md §4.2)
+//! - Its `Welcome` names the true Primary, so every replica in the chain
+//!   checks its history against the one commit authority
+//! - Only replicas declared downstream of this relay are accepted; one
+//!   that is upstream of it would close a loop and is an authority
+//!   ambiguity
+//!
+//! A downstream that fails is dropped without affecting the relay or its
+//! other downstreams; it reconnects and resumes like any replica.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use uuid::Uuid;
+
+use super::config::ReplicationConfig;
+use super::errors::{ReplicationError, ReplicationResult};
+use super::publication::PublicationManifest;
+use super::role::HaltReason;
+use super::topology::ReplicationTopology;
+use super::transport::{
+    configure_stream, halt_reason_for, protocol_violation, read_frame, reject, transport_io, Frame,
+    PrimaryConnection, ReplicaConnection, TransportConfig,
+};
+use super::wal_receiver::WalReceiver;
+use super::wal_sender::{WalPosition, WalRecordEnvelope};
+
+/// Outcome of relaying one record
+#[derive(Debug)]
+pub struct RelayOutcome {
+    /// Record applied locally and forwarded
+    pub envelope: WalRecordEnvelope,
+    /// Downstreams dropped while forwarding it, with the reason
+    pub dropped: Vec<(Uuid, ReplicationError)>,
+}
+
+/// This node's place in a cascading topology
+#[derive(Debug, Clone)]
+pub struct WalRelay {
+    relay_id: Uuid,
+    topology: ReplicationTopology,
+}
+
+impl WalRelay {
+    /// Relay for the replica configured by `config`
+    ///
+    /// # Errors
+    ///
+    /// `config` must be a relaying replica, and `topology` must be valid
+    /// and declare it.
+    pub fn new(
+        config: &ReplicationConfig,
+        topology: ReplicationTopology,
+    ) -> ReplicationResult<Self> {
+        config.validate()?;
+        let Some(relay_id) = config.get_replica_id().filter(|_| config.is_relay()) else {
+            return Err(ReplicationError::configuration_error(
+                "Only a replica configured as a relay forwards WAL",
+            ));
+        };
+        topology.validate()?;
+        if topology.upstream_of(relay_id).is_none() {
+            return Err(ReplicationError::configuration_error(format!(
+                "relay {} is not declared in the replication topology",
+                relay_id
+            )));
+        }
+        Ok(Self { relay_id, topology })
+    }
+
+    /// This relay's replica id
+    pub fn relay_id(&self) -> Uuid {
+        self.relay_id
+    }
+
+    /// The Primary at the root of the topology
+    pub fn primary_id(&self) -> Uuid {
+        self.topology.primary_id()
+    }
+
+    /// Declared topology
+    pub fn topology(&self) -> &ReplicationTopology {
+        &self.topology
+    }
+
+    /// Connect to this relay's upstream, which must stream the true Primary's history
+    pub fn connect_upstream(
+        &self,
+        replication: &ReplicationConfig,
+        config: TransportConfig,
+        receiver: WalReceiver,
+    ) -> ReplicationResult<ReplicaConnection<TcpStream>> {
+        ReplicaConnection::connect(replication, config, receiver, Some(self.primary_id()))
+    }
+
+    /// Check that `replica_id` may stream from this relay
+    pub fn check_downstream(&self, replica_id: Uuid) -> ReplicationResult<()> {
+        if replica_id == self.relay_id || self.topology.chain(self.relay_id)?.contains(&replica_id)
+        {
+            return Err(ReplicationError::authority_ambiguity(format!(
+                "replication loop: {} is upstream of relay {}",
+                replica_id, self.relay_id
+            )));
+        }
+        if self.topology.upstream_of(replica_id) != Some(self.relay_id) {
+            return Err(ReplicationError::configuration_error(format!(
+                "replica {} is not declared downstream of relay {}",
+                replica_id, self.relay_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Accept the next downstream replica on `listener`
+    ///
+    /// `applied` is the relay's applied position: no downstream may
+    /// resume past it.
+    pub fn accept_downstream(
+        &self,
+        listener: &TcpListener,
+        config: TransportConfig,
+        manifest: &PublicationManifest,
+        applied: WalPosition,
+    ) -> ReplicationResult<PrimaryConnection<TcpStream>> {
+        let (stream, _) = listener.accept().map_err(transport_io)?;
+        configure_stream(&stream, &config)?;
+        self.handshake_downstream(stream, config, manifest, applied)
+    }
+
+    /// Validate a downstream replica's `Hello` and start streaming to it
+    pub fn handshake_downstream<S: Read + Write>(
+        &self,
+        mut stream: S,
+        config: TransportConfig,
+        manifest: &PublicationManifest,
+        applied: WalPosition,
+    ) -> ReplicationResult<PrimaryConnection<S>> {
+        let hello = match read_frame(&mut stream, config.max_frame_bytes)? {
+            Frame::Hello(hello) => hello,
+            other => {
+                return Err(reject(
+                    &mut stream,
+                    HaltReason::ConfigurationError,
+                    protocol_violation("Hello", &other),
+                ))
+            }
+        };
+        if let Err(e) = self.check_downstream(hello.replica_id) {
+            return Err(reject(&mut stream, halt_reason_for(&e), e));
+        }
+        PrimaryConnection::admit(stream, config, self.primary_id(), manifest, applied, hello)
+    }
+
+    /// Apply the next record from upstream, then forward it downstream
+    ///
+    /// Upstream failures are returned; downstream failures drop that
+    /// downstream and are reported in the outcome.
+    pub fn relay_next<U: Read + Write, D: Read + Write>(
+        &self,
+        upstream: &mut ReplicaConnection<U>,
+        downstreams: &mut Vec<PrimaryConnection<D>>,
+    ) -> ReplicationResult<RelayOutcome> {
+        let envelope = upstream.next_record()?;
+        upstream.ack(&envelope)?;
+
+        // Forward under the Primary's epoch, never the relay's own
+        let mut dropped = Vec::new();
+        downstreams.retain_mut(|downstream| {
+            downstream.set_epoch(envelope.epoch);
+            match downstream.send(&envelope.record) {
+                Ok(_) => true,
+                Err(e) => {
+                    dropped.push((downstream.replica_id(), e));
+                    false
+                }
+            }
+        });
+        Ok(RelayOutcome { envelope, dropped })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replication::{
+        write_frame, Hello, Publication, ReplicationErrorKind, REPLICATION_PROTOCOL_VERSION,
+        WAL_FORMAT_VERSION,
+    };
+    use crate::wal::{WalPayload, WalRecord};
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::thread;
+
+    fn record(sequence_number: u64) -> WalRecord {
+        WalRecord::insert(
+            sequence_number,
+            WalPayload::new("users", "doc1", "users", "v1", b"{}".to_vec()),
+        )
+    }
+
+    struct Cascade {
+        primary: Uuid,
+        relay: ReplicationConfig,
+        leaf: ReplicationConfig,
+        topology: ReplicationTopology,
+    }
+
+    fn cascade(relay_address: String, primary_address: String) -> Cascade {
+        let primary = Uuid::new_v4();
+        let relay = ReplicationConfig::replica(primary_address, None).with_relay();
+        let leaf = ReplicationConfig::replica(relay_address, None);
+        let relay_id = relay.replica_id.unwrap();
+        let topology = ReplicationTopology::new(primary)
+            .with_replica(relay_id, primary)
+            .with_replica(leaf.replica_id.unwrap(), relay_id);
+        Cascade {
+            primary,
+            relay,
+            leaf,
+            topology,
+        }
+    }
+
+    #[test]
+    fn test_relay_forwards_primary_history() {
+        let primary_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let relay_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let c = cascade(
+            relay_listener.local_addr().unwrap().to_string(),
+            primary_listener.local_addr().unwrap().to_string(),
+        );
+
+        let primary_id = c.primary;
+        let primary = thread::spawn(move || {
+            let mut conn = PrimaryConnection::accept(
+                &primary_listener,
+                TransportConfig::default(),
+                primary_id,
+                &PublicationManifest::new(),
+                WalPosition::genesis(),
+            )
+            .unwrap();
+            conn.set_epoch(2);
+            for seq in 1..=3 {
+                conn.send(&record(seq)).unwrap();
+            }
+            conn.wait_for_ack(conn.sender().current_position()).unwrap();
+        });
+
+        let relay = WalRelay::new(&c.relay, c.topology.clone()).unwrap();
+        let relay_config = c.relay.clone();
+        let relay_thread = thread::spawn(move || {
+            let mut upstream = relay
+                .connect_upstream(
+                    &relay_config,
+                    TransportConfig::default(),
+                    WalReceiver::new(WalPosition::genesis()),
+                )
+                .unwrap();
+            let mut downstreams = vec![relay
+                .accept_downstream(
+                    &relay_listener,
+                    TransportConfig::default(),
+                    &PublicationManifest::new(),
+                    WalPosition::genesis(),
+                )
+                .unwrap()];
+            for _ in 0..3 {
+                let outcome = relay.relay_next(&mut upstream, &mut downstreams).unwrap();
+                assert!(outcome.dropped.is_empty());
+            }
+            let leaf = &mut downstreams[0];
+            leaf.wait_for_ack(leaf.sender().current_position()).unwrap();
+        });
+
+        // The leaf expects the true Primary, not the relay
+        let mut leaf = ReplicaConnection::connect(
+            &c.leaf,
+            TransportConfig::default(),
+            WalReceiver::new(WalPosition::genesis()),
+            Some(c.primary),
+        )
+        .unwrap();
+        for seq in 1..=3 {
+            let envelope = leaf.next_record().unwrap();
+            assert_eq!(envelope.record, record(seq));
+            // Still under the Primary's fencing epoch
+            assert_eq!(envelope.epoch, 2);
+            leaf.ack(&envelope).unwrap();
+        }
+
+        primary.join().unwrap();
+        relay_thread.join().unwrap();
+    }
+
+    #[test]
+    fn test_downstream_loop_rejected() {
+        let c = cascade("relay:1".to_string(), "primary:1".to_string());
+        let relay = WalRelay::new(&c.relay, c.topology.clone()).unwrap();
+
+        // The primary, the relay itself, or an undeclared node may not attach
+        let err = relay.check_downstream(c.primary).unwrap_err();
+        assert_eq!(err.kind, ReplicationErrorKind::AuthorityAmbiguity);
+        let err = relay.check_downstream(relay.relay_id()).unwrap_err();
+        assert_eq!(err.kind, ReplicationErrorKind::AuthorityAmbiguity);
+        let err = relay.check_downstream(Uuid::new_v4()).unwrap_err();
+        assert_eq!(err.kind, ReplicationErrorKind::ConfigurationError);
+        assert!(relay.check_downstream(c.leaf.replica_id.unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_relay_requires_declaration() {
+        let c = cascade("relay:1".to_string(), "primary:1".to_string());
+
+        // A plain replica does not relay
+        let err = WalRelay::new(&c.leaf, c.topology.clone()).unwrap_err();
+        assert_eq!(err.kind, ReplicationErrorKind::ConfigurationError);
+
+        // A relay missing from the topology
+        let undeclared = ReplicationConfig::replica("primary:1".to_string(), None).with_relay();
+        assert!(WalRelay::new(&undeclared, c.topology.clone()).is_err());
+
+        // A relay never holds commit authority
+        let relay = WalRelay::new(&c.relay, c.topology).unwrap();
+        let err = relay
+            .topology()
+            .check_commit_authority(relay.relay_id())
+            .unwrap_err();
+        assert_eq!(err.kind, ReplicationErrorKind::CommitAuthorityViolation);
+    }
+
+    #[test]
+    fn test_dropped_downstream_does_not_stop_relay() {
+        let primary_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let c = cascade(
+            "relay:1".to_string(),
+            primary_listener.local_addr().unwrap().to_string(),
+        );
+        let relay = WalRelay::new(&c.relay, c.topology.clone()).unwrap();
+
+        let primary_id = c.primary;
+        let primary = thread::spawn(move || {
+            let mut conn = PrimaryConnection::accept(
+                &primary_listener,
+                TransportConfig::default(),
+                primary_id,
+                &PublicationManifest::new(),
+                WalPosition::genesis(),
+            )
+            .unwrap();
+            conn.send(&record(1)).unwrap();
+            conn.wait_for_ack(conn.sender().current_position()).unwrap();
+        });
+
+        // The leaf handshakes, then its connection breaks
+        let mut hello = Vec::new();
+        let leaf_id = c.leaf.replica_id.unwrap();
+        write_frame(
+            &mut hello,
+            &Frame::Hello(Hello {
+                protocol_version: REPLICATION_PROTOCOL_VERSION,
+                wal_format_version: WAL_FORMAT_VERSION,
+                replica_id: leaf_id,
+                publication: Publication::all(),
+                position: WalPosition::genesis(),
+            }),
+        )
+        .unwrap();
+        let broken = Rc::new(Cell::new(false));
+        let stream = Scripted {
+            input: std::io::Cursor::new(hello),
+            broken: broken.clone(),
+        };
+        let mut downstreams = vec![relay
+            .handshake_downstream(
+                stream,
+                TransportConfig::default(),
+                &PublicationManifest::new(),
+                WalPosition::genesis(),
+            )
+            .unwrap()];
+        broken.set(true);
+
+        let mut upstream = relay
+            .connect_upstream(
+                &c.relay,
+                TransportConfig::default(),
+                WalReceiver::new(WalPosition::genesis()),
+            )
+            .unwrap();
+        let outcome = relay.relay_next(&mut upstream, &mut downstreams).unwrap();
+        assert_eq!(outcome.envelope.record, record(1));
+        assert_eq!(outcome.dropped.len(), 1);
+        assert_eq!(outcome.dropped[0].0, leaf_id);
+        assert!(downstreams.is_empty());
+        primary.join().unwrap();
+    }
+
+    /// Scripted input; writes fail once `broken` is set
+    struct Scripted {
+        input: std::io::Cursor<Vec<u8>>,
+        broken: Rc<Cell<bool>>,
+    }
+
+    impl Read for Scripted {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for Scripted {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.broken.get() {
+                return Err(std::io::ErrorKind::BrokenPipe.into());
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+}
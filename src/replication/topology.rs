@@ -0,0 +1,190 @@
+//! Replication Topology
+//!
+//! Declares which node streams WAL to which. A replica's upstream is
+//! either the Primary or a relay: a replica that forwards the WAL it has
+//! applied to its own downstream replicas.
+//!
+//! Per REPLICATION_MODEL.md §6 authority is configured, never inferred,
+//! so the topology is declared up front and validated before any
+//! connection is made:
+//! - Exactly one root, the Primary, which has no upstream
+//! - Every upstream is a declared node
+//! - Following upstreams from any replica reaches the Primary (no loops)
+//!
+//! Per PHASE2_REPLICATION_INVARIANTS.md §2.2 only the Primary assigns
+//! CommitIds; relays forward history, they never create it.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::errors::{ReplicationError, ReplicationResult};
+
+/// Declared replication topology
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplicationTopology {
+    /// The Primary: sole commit authority and root of the tree
+    primary_id: Uuid,
+    /// Replica → the node it streams WAL from
+    upstreams: BTreeMap<Uuid, Uuid>,
+}
+
+impl ReplicationTopology {
+    /// Topology rooted at `primary_id`, with no replicas yet
+    pub fn new(primary_id: Uuid) -> Self {
+        Self {
+            primary_id,
+            upstreams: BTreeMap::new(),
+        }
+    }
+
+    /// Declare `replica_id` as streaming from `upstream_id`
+    pub fn with_replica(mut self, replica_id: Uuid, upstream_id: Uuid) -> Self {
+        self.upstreams.insert(replica_id, upstream_id);
+        self
+    }
+
+    /// The Primary
+    pub fn primary_id(&self) -> Uuid {
+        self.primary_id
+    }
+
+    /// The node `replica_id` streams from, if it is declared
+    pub fn upstream_of(&self, replica_id: Uuid) -> Option<Uuid> {
+        self.upstreams.get(&replica_id).copied()
+    }
+
+    /// Replicas streaming directly from `node_id`
+    pub fn downstream_of(&self, node_id: Uuid) -> Vec<Uuid> {
+        self.upstreams
+            .iter()
+            .filter(|(_, upstream)| **upstream == node_id)
+            .map(|(replica, _)| *replica)
+            .collect()
+    }
+
+    /// Whether `node_id` is a replica with downstream replicas
+    pub fn is_relay(&self, node_id: Uuid) -> bool {
+        self.upstreams.contains_key(&node_id) && !self.downstream_of(node_id).is_empty()
+    }
+
+    /// Upstreams of `replica_id`, nearest first, ending at the Primary
+    ///
+    /// # Errors
+    ///
+    /// An undeclared node is a configuration error; a loop is an
+    /// authority ambiguity, since no node in it can reach the Primary.
+    pub fn chain(&self, replica_id: Uuid) -> ReplicationResult<Vec<Uuid>> {
+        let mut chain = Vec::new();
+        let mut node = replica_id;
+        while node != self.primary_id {
+            let upstream = self.upstream_of(node).ok_or_else(|| {
+                ReplicationError::configuration_error(format!(
+                    "node {} is not declared in the replication topology",
+                    node
+                ))
+            })?;
+            if upstream == replica_id || chain.contains(&upstream) {
+                return Err(ReplicationError::authority_ambiguity(format!(
+                    "replication loop: {} streams from itself through {:?}",
+                    replica_id, chain
+                )));
+            }
+            chain.push(upstream);
+            node = upstream;
+        }
+        Ok(chain)
+    }
+
+    /// Validate the whole topology
+    pub fn validate(&self) -> ReplicationResult<()> {
+        if self.upstreams.contains_key(&self.primary_id) {
+            return Err(ReplicationError::authority_ambiguity(format!(
+                "primary {} is also declared as a replica",
+                self.primary_id
+            )));
+        }
+        for replica_id in self.upstreams.keys() {
+            self.chain(*replica_id)?;
+        }
+        Ok(())
+    }
+
+    /// Check that `node_id` may assign CommitIds
+    ///
+    /// Only the Primary may; relays and replicas never do.
+    pub fn check_commit_authority(&self, node_id: Uuid) -> ReplicationResult<()> {
+        if node_id == self.primary_id {
+            return Ok(());
+        }
+        Err(ReplicationError::commit_authority_violation(format!(
+            "only primary {} assigns CommitIds, not {}",
+            self.primary_id, node_id
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replication::ReplicationErrorKind;
+
+    #[test]
+    fn test_cascading_chain_reaches_primary() {
+        let (primary, relay, leaf) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let topology = ReplicationTopology::new(primary)
+            .with_replica(relay, primary)
+            .with_replica(leaf, relay);
+
+        assert!(topology.validate().is_ok());
+        assert_eq!(topology.chain(leaf).unwrap(), vec![relay, primary]);
+        assert_eq!(topology.downstream_of(relay), vec![leaf]);
+        assert!(topology.is_relay(relay));
+        assert!(!topology.is_relay(leaf));
+        assert!(!topology.is_relay(primary));
+    }
+
+    #[test]
+    fn test_loop_is_authority_ambiguity() {
+        let (primary, a, b) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let topology = ReplicationTopology::new(primary)
+            .with_replica(a, b)
+            .with_replica(b, a);
+
+        let err = topology.validate().unwrap_err();
+        assert_eq!(err.kind, ReplicationErrorKind::AuthorityAmbiguity);
+        assert!(err.message.contains("loop"));
+    }
+
+    #[test]
+    fn test_undeclared_upstream_rejected() {
+        let primary = Uuid::new_v4();
+        let topology =
+            ReplicationTopology::new(primary).with_replica(Uuid::new_v4(), Uuid::new_v4());
+
+        let err = topology.validate().unwrap_err();
+        assert_eq!(err.kind, ReplicationErrorKind::ConfigurationError);
+    }
+
+    #[test]
+    fn test_primary_cannot_be_a_replica() {
+        let (primary, relay) = (Uuid::new_v4(), Uuid::new_v4());
+        let topology = ReplicationTopology::new(primary)
+            .with_replica(relay, primary)
+            .with_replica(primary, relay);
+
+        let err = topology.validate().unwrap_err();
+        assert_eq!(err.kind, ReplicationErrorKind::AuthorityAmbiguity);
+    }
+
+    #[test]
+    fn test_only_primary_assigns_commit_ids() {
+        let (primary, relay) = (Uuid::new_v4(), Uuid::new_v4());
+        let topology = ReplicationTopology::new(primary).with_replica(relay, primary);
+
+        assert!(topology.check_commit_authority(primary).is_ok());
+        let err = topology.check_commit_authority(relay).unwrap_err();
+        assert_eq!(err.kind, ReplicationErrorKind::CommitAuthorityViolation);
+    }
+}
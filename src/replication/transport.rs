@@ -0,0 +1,1207 @@
+//! Replication Network Transport
+//!
+//! Streams WAL from a Primary's `WalSender` to a Replica's `WalReceiver`
+//! over TCP.
+//!
+//! # Framing
+//!
+//! Every message is one frame, laid out like a WAL record:
+//! - Frame Length (u32 LE) - total length including this field
+//! - Frame Type (u8)
+//! - Body (variable)
+//! - Checksum (u32 LE) - CRC32 of length, type and body
+//!
+//! A frame that fails its checksum is corruption and halts replication.
+//! WAL records travel inside `Record` frames in their own serialized
+//! form, so they carry their WAL checksum end to end.
+//!
+//! # Handshake
+//!
+//! 1. Replica sends `Hello`: protocol and WAL format versions, replica id,
+//!    expected publication, and the position it resumes from
+//! 2. Primary validates all of them and answers `Welcome` (its node id and
+//!    the start position) or `Reject`
+//!
+//! # Halt on Mismatch
+//!
+//! Per REPLICATION_MODEL.md there is no negotiation past the handshake and
+//! no automatic healing. Version, publication or node id mismatches, a
+//! replica ahead of the Primary, gaps, and checksum failures halt the
+//! connection: the detecting side sends `Reject` with the `HaltReason` so
+//! the peer halts too, and every later call fails.
+//!
+//! A new replica may instead open with `SnapshotRequest`; the Primary
+//! streams a snapshot (see `bootstrap`) and the handshake follows on the
+//! same connection.
+//!
+//! # Fencing
+//!
+//! Every `Record` carries the fencing epoch the Primary was promoted
+//! under. A replica that has seen a newer epoch refuses the record and
+//! halts with `AuthorityAmbiguity`, so a demoted Primary that keeps
+//! writing cannot replicate its late writes.
+//!
+//! # Heartbeats
+//!
+//! The Primary calls `heartbeat()` whenever it has been idle for
+//! `heartbeat_interval`; the Replica answers with its applied position.
+//! A peer silent for `peer_timeout` drops the connection with a
+//! (non-fatal) transport error. Heartbeats detect dead peers only; they
+//! never change authority.
+
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use crc32fast::Hasher;
+use uuid::Uuid;
+
+use super::bootstrap::{SnapshotOffer, SnapshotRequest};
+use super::config::ReplicationConfig;
+use super::errors::{ReplicationError, ReplicationErrorKind, ReplicationResult};
+use super::publication::{Publication, PublicationManifest, ReplicaHandshake};
+use super::role::HaltReason;
+use super::wal_receiver::{ReceiveResult, WalReceiver};
+use super::wal_sender::{WalPosition, WalRecordEnvelope, WalSender};
+use crate::wal::WalRecord;
+
+/// Wire protocol version spoken by this node
+pub const REPLICATION_PROTOCOL_VERSION: u16 = 2;
+
+/// WAL record encoding carried in `Record` frames
+pub const WAL_FORMAT_VERSION: u8 = 1;
+
+/// Frame header: length (4) + type (1)
+const FRAME_HEADER_SIZE: usize = 5;
+
+/// Frame trailer: checksum (4)
+const FRAME_CHECKSUM_SIZE: usize = 4;
+
+/// Transport timing and limits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransportConfig {
+    /// Idle time after which the Primary should send a heartbeat
+    pub heartbeat_interval: Duration,
+    /// Drop the connection after hearing nothing from the peer this long
+    pub peer_timeout: Duration,
+    /// Largest frame accepted; anything larger is corruption
+    pub max_frame_bytes: usize,
+    /// Snapshot bytes carried per `SnapshotChunk`
+    pub snapshot_chunk_bytes: usize,
+}
+
+impl Default for TransportConfig {
+    fn default() -> Self {
+        Self {
+            heartbeat_interval: Duration::from_secs(5),
+            peer_timeout: Duration::from_secs(30),
+            max_frame_bytes: 64 * 1024 * 1024,
+            snapshot_chunk_bytes: 1024 * 1024,
+        }
+    }
+}
+
+/// Replica's opening message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hello {
+    /// Wire protocol version
+    pub protocol_version: u16,
+    /// WAL format version
+    pub wal_format_version: u8,
+    /// Replica identity
+    pub replica_id: Uuid,
+    /// Publication the replica expects to consume
+    pub publication: Publication,
+    /// Position to resume streaming from
+    pub position: WalPosition,
+}
+
+/// Primary's acceptance of a `Hello`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Welcome {
+    /// Wire protocol version
+    pub protocol_version: u16,
+    /// WAL format version
+    pub wal_format_version: u8,
+    /// Primary identity
+    pub primary_id: Uuid,
+    /// Position streaming starts from
+    pub start: WalPosition,
+}
+
+/// One protocol message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    /// Replica → Primary: open a stream
+    Hello(Hello),
+    /// Primary → Replica: stream accepted
+    Welcome(Welcome),
+    /// Either way: the sender halted; the receiver must halt too
+    Reject { reason: HaltReason, message: String },
+    /// Primary → Replica: one WAL record
+    Record(WalRecordEnvelope),
+    /// Replica → Primary: records up to this position are durable
+    Ack(WalPosition),
+    /// Either way: liveness, with the sender's current position
+    Heartbeat(WalPosition),
+    /// Replica → Primary: send a snapshot before the handshake
+    SnapshotRequest(SnapshotRequest),
+    /// Primary → Replica: the snapshot about to be streamed
+    SnapshotOffer(SnapshotOffer),
+    /// Primary → Replica: snapshot bytes at an offset into its files
+    SnapshotChunk { offset: u64, data: Vec<u8> },
+    /// Primary → Replica: the snapshot has been sent in full
+    SnapshotEnd,
+}
+
+impl Frame {
+    fn type_byte(&self) -> u8 {
+        match self {
+            Frame::Hello(_) => 1,
+            Frame::Welcome(_) => 2,
+            Frame::Reject { .. } => 3,
+            Frame::Record(_) => 4,
+            Frame::Ack(_) => 5,
+            Frame::Heartbeat(_) => 6,
+            Frame::SnapshotRequest(_) => 7,
+            Frame::SnapshotOffer(_) => 8,
+            Frame::SnapshotChunk { .. } => 9,
+            Frame::SnapshotEnd => 10,
+        }
+    }
+
+    fn encode_body(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        match self {
+            Frame::Hello(hello) => {
+                let publication =
+                    serde_json::to_vec(&hello.publication).expect("publication serializes");
+                body.extend_from_slice(&hello.protocol_version.to_le_bytes());
+                body.push(hello.wal_format_version);
+                body.extend_from_slice(hello.replica_id.as_bytes());
+                put_position(&mut body, hello.position);
+                body.extend_from_slice(&(publication.len() as u32).to_le_bytes());
+                body.extend_from_slice(&publication);
+            }
+            Frame::Welcome(welcome) => {
+                body.extend_from_slice(&welcome.protocol_version.to_le_bytes());
+                body.push(welcome.wal_format_version);
+                body.extend_from_slice(welcome.primary_id.as_bytes());
+                put_position(&mut body, welcome.start);
+            }
+            Frame::Reject { reason, message } => {
+                body.push(halt_reason_code(*reason));
+                body.extend_from_slice(message.as_bytes());
+            }
+            Frame::Record(envelope) => {
+                put_position(&mut body, envelope.position);
+                body.extend_from_slice(&envelope.checksum.to_le_bytes());
+                body.extend_from_slice(&envelope.epoch.to_le_bytes());
+                body.extend_from_slice(&envelope.record.serialize());
+            }
+            Frame::Ack(position) | Frame::Heartbeat(position) => {
+                put_position(&mut body, *position);
+            }
+            Frame::SnapshotRequest(request) => {
+                body = serde_json::to_vec(request).expect("snapshot request serializes");
+            }
+            Frame::SnapshotOffer(offer) => {
+                body = serde_json::to_vec(offer).expect("snapshot offer serializes");
+            }
+            Frame::SnapshotChunk { offset, data } => {
+                body.extend_from_slice(&offset.to_le_bytes());
+                body.extend_from_slice(data);
+            }
+            Frame::SnapshotEnd => {}
+        }
+        body
+    }
+
+    fn decode(type_byte: u8, body: &[u8]) -> ReplicationResult<Self> {
+        let mut body = BodyReader { buf: body };
+        let frame = match type_byte {
+            1 => {
+                let protocol_version = body.u16()?;
+                let wal_format_version = body.u8()?;
+                let replica_id = body.uuid()?;
+                let position = body.position()?;
+                let len = body.u32()? as usize;
+                let publication = serde_json::from_slice(body.take(len)?)
+                    .map_err(|e| corrupt(format!("invalid publication in hello: {}", e)))?;
+                Frame::Hello(Hello {
+                    protocol_version,
+                    wal_format_version,
+                    replica_id,
+                    publication,
+                    position,
+                })
+            }
+            2 => Frame::Welcome(Welcome {
+                protocol_version: body.u16()?,
+                wal_format_version: body.u8()?,
+                primary_id: body.uuid()?,
+                start: body.position()?,
+            }),
+            3 => {
+                let reason = halt_reason_from_code(body.u8()?)?;
+                let message = String::from_utf8_lossy(body.rest()).into_owned();
+                Frame::Reject { reason, message }
+            }
+            4 => {
+                let position = body.position()?;
+                let checksum = body.u32()?;
+                let epoch = body.u64()?;
+                let (record, consumed) = WalRecord::deserialize(body.rest())
+                    .map_err(|e| corrupt(format!("invalid WAL record: {}", e)))?;
+                if consumed != body.buf.len() {
+                    return Err(corrupt("trailing bytes after WAL record"));
+                }
+                Frame::Record(WalRecordEnvelope {
+                    position,
+                    record,
+                    checksum,
+                    epoch,
+                })
+            }
+            5 => Frame::Ack(body.position()?),
+            6 => Frame::Heartbeat(body.position()?),
+            7 => Frame::SnapshotRequest(
+                serde_json::from_slice(body.rest())
+                    .map_err(|e| corrupt(format!("invalid snapshot request: {}", e)))?,
+            ),
+            8 => Frame::SnapshotOffer(
+                serde_json::from_slice(body.rest())
+                    .map_err(|e| corrupt(format!("invalid snapshot offer: {}", e)))?,
+            ),
+            9 => Frame::SnapshotChunk {
+                offset: body.u64()?,
+                data: body.rest().to_vec(),
+            },
+            10 => Frame::SnapshotEnd,
+            other => return Err(corrupt(format!("unknown frame type {}", other))),
+        };
+        Ok(frame)
+    }
+}
+
+/// Write one frame
+pub fn write_frame<W: Write>(writer: &mut W, frame: &Frame) -> ReplicationResult<()> {
+    let body = frame.encode_body();
+    let length = (FRAME_HEADER_SIZE + body.len() + FRAME_CHECKSUM_SIZE) as u32;
+
+    let mut buf = Vec::with_capacity(length as usize);
+    buf.extend_from_slice(&length.to_le_bytes());
+    buf.push(frame.type_byte());
+    buf.extend_from_slice(&body);
+    let mut hasher = Hasher::new();
+    hasher.update(&buf);
+    buf.extend_from_slice(&hasher.finalize().to_le_bytes());
+
+    writer.write_all(&buf).map_err(transport_io)?;
+    writer.flush().map_err(transport_io)
+}
+
+/// Read one frame, verifying its checksum
+///
+/// # Errors
+///
+/// `WalIntegrity` for a bad checksum, oversized or malformed frame;
+/// `Transport` if the connection fails or the peer times out.
+pub fn read_frame<R: Read>(reader: &mut R, max_frame_bytes: usize) -> ReplicationResult<Frame> {
+    let mut header = [0u8; FRAME_HEADER_SIZE];
+    reader.read_exact(&mut header).map_err(transport_io)?;
+    let length = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    if length < FRAME_HEADER_SIZE + FRAME_CHECKSUM_SIZE || length > max_frame_bytes {
+        return Err(corrupt(format!("invalid frame length {}", length)));
+    }
+
+    let mut rest = vec![0u8; length - FRAME_HEADER_SIZE];
+    reader.read_exact(&mut rest).map_err(transport_io)?;
+    let (body, checksum) = rest.split_at(rest.len() - FRAME_CHECKSUM_SIZE);
+    let stored = u32::from_le_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]);
+    let mut hasher = Hasher::new();
+    hasher.update(&header);
+    hasher.update(body);
+    let computed = hasher.finalize();
+    if computed != stored {
+        return Err(corrupt(format!(
+            "frame checksum mismatch: computed {:08x}, stored {:08x}",
+            computed, stored
+        )));
+    }
+
+    Frame::decode(header[4], body)
+}
+
+/// Primary end of a replication connection
+#[derive(Debug)]
+pub struct PrimaryConnection<S: Read + Write> {
+    stream: S,
+    config: TransportConfig,
+    replica_id: Uuid,
+    sender: WalSender,
+    halted: Option<HaltReason>,
+}
+
+impl PrimaryConnection<TcpStream> {
+    /// Accept the next replica on `listener` and perform the handshake
+    ///
+    /// See `handshake` for `manifest` and `wal_end`.
+    pub fn accept(
+        listener: &TcpListener,
+        config: TransportConfig,
+        primary_id: Uuid,
+        manifest: &PublicationManifest,
+        wal_end: WalPosition,
+    ) -> ReplicationResult<Self> {
+        let (stream, _) = listener.accept().map_err(transport_io)?;
+        configure_stream(&stream, &config)?;
+        Self::handshake(stream, config, primary_id, manifest, wal_end)
+    }
+}
+
+impl<S: Read + Write> PrimaryConnection<S> {
+    /// Validate a replica's `Hello` and start streaming
+    ///
+    /// The replica's publication must match `manifest`. `wal_end` is the
+    /// position after the last record the replica's publication covers
+    /// (positions count published records only); a replica resuming past
+    /// it holds history the Primary never wrote.
+    ///
+    /// # Errors
+    ///
+    /// On any mismatch the replica is sent `Reject` and the error is
+    /// returned; no connection is established.
+    pub fn handshake(
+        mut stream: S,
+        config: TransportConfig,
+        primary_id: Uuid,
+        manifest: &PublicationManifest,
+        wal_end: WalPosition,
+    ) -> ReplicationResult<Self> {
+        let hello = match read_frame(&mut stream, config.max_frame_bytes)? {
+            Frame::Hello(hello) => hello,
+            other => {
+                return Err(reject(
+                    &mut stream,
+                    HaltReason::ConfigurationError,
+                    protocol_violation("Hello", &other),
+                ))
+            }
+        };
+        Self::admit(stream, config, primary_id, manifest, wal_end, hello)
+    }
+
+    /// Validate an already-read `Hello` and start streaming
+    ///
+    /// `primary_id` is the Primary whose history is streamed: this node,
+    /// or the root Primary when relaying.
+    pub(super) fn admit(
+        mut stream: S,
+        config: TransportConfig,
+        primary_id: Uuid,
+        manifest: &PublicationManifest,
+        wal_end: WalPosition,
+        hello: Hello,
+    ) -> ReplicationResult<Self> {
+        let admitted = check_versions(hello.protocol_version, hello.wal_format_version)
+            .and_then(|()| {
+                if hello.replica_id == primary_id {
+                    return Err(ReplicationError::authority_ambiguity(format!(
+                        "replica id {} is the primary's own id",
+                        primary_id
+                    )));
+                }
+                manifest.validate_handshake(&ReplicaHandshake::new(
+                    hello.replica_id,
+                    hello.publication.clone(),
+                ))
+            })
+            .and_then(|publication| {
+                if hello.position > wal_end {
+                    return Err(ReplicationError::history_divergence(format!(
+                        "replica {} resumes from sequence {}, primary WAL ends at {}",
+                        hello.replica_id, hello.position.sequence, wal_end.sequence
+                    )));
+                }
+                Ok(publication)
+            });
+        let publication = match admitted {
+            Ok(publication) => publication,
+            Err(e) => return Err(reject(&mut stream, halt_reason_for(&e), e)),
+        };
+
+        let mut sender = WalSender::with_publication(hello.position, publication);
+        sender.start();
+        write_frame(
+            &mut stream,
+            &Frame::Welcome(Welcome {
+                protocol_version: REPLICATION_PROTOCOL_VERSION,
+                wal_format_version: WAL_FORMAT_VERSION,
+                primary_id,
+                start: hello.position,
+            }),
+        )?;
+
+        Ok(Self {
+            stream,
+            config,
+            replica_id: hello.replica_id,
+            sender,
+            halted: None,
+        })
+    }
+
+    /// Connected replica
+    pub fn replica_id(&self) -> Uuid {
+        self.replica_id
+    }
+
+    /// Sender tracking sent and acknowledged positions
+    pub fn sender(&self) -> &WalSender {
+        &self.sender
+    }
+
+    /// Stream later records under fencing epoch `epoch`
+    pub fn set_epoch(&mut self, epoch: u64) {
+        self.sender.set_epoch(epoch);
+    }
+
+    /// Why the connection halted, if it has
+    pub fn halt_reason(&self) -> Option<HaltReason> {
+        self.halted
+    }
+
+    /// Stream the next WAL record
+    ///
+    /// Returns the position it was sent at, or `None` if the replica's
+    /// publication excludes it.
+    pub fn send(&mut self, record: &WalRecord) -> ReplicationResult<Option<WalPosition>> {
+        self.check_halted()?;
+        let Some(envelope) = self.sender.prepare_published(record)? else {
+            return Ok(None);
+        };
+        let record_size = record.serialize().len() as u64;
+        write_frame(&mut self.stream, &Frame::Record(envelope.clone()))?;
+        self.sender.record_sent(record_size);
+        Ok(Some(envelope.position))
+    }
+
+    /// Tell the replica the Primary is alive
+    pub fn heartbeat(&mut self) -> ReplicationResult<()> {
+        self.check_halted()?;
+        write_frame(
+            &mut self.stream,
+            &Frame::Heartbeat(self.sender.current_position()),
+        )
+    }
+
+    /// Read one message from the replica, returning the acknowledged position
+    pub fn recv(&mut self) -> ReplicationResult<WalPosition> {
+        self.check_halted()?;
+        let frame = match read_frame(&mut self.stream, self.config.max_frame_bytes) {
+            Ok(frame) => frame,
+            Err(e) => return Err(self.fail(e)),
+        };
+        match frame {
+            Frame::Ack(position) => {
+                if let Err(e) = self.sender.handle_ack(position) {
+                    return Err(self.fail(e));
+                }
+            }
+            Frame::Heartbeat(position) => {
+                if position > self.sender.current_position() {
+                    let e = ReplicationError::history_divergence(format!(
+                        "replica reports sequence {}, primary has sent {}",
+                        position.sequence,
+                        self.sender.current_position().sequence
+                    ));
+                    return Err(self.fail(e));
+                }
+            }
+            Frame::Reject { reason, message } => {
+                self.halted = Some(reason);
+                return Err(ReplicationError::halted(format!(
+                    "replica halted replication: {}",
+                    message
+                )));
+            }
+            other => {
+                let e = protocol_violation("Ack or Heartbeat", &other);
+                return Err(self.fail(e));
+            }
+        }
+        Ok(self.sender.ack_position())
+    }
+
+    /// Read from the replica until it has acknowledged `position`
+    pub fn wait_for_ack(&mut self, position: WalPosition) -> ReplicationResult<()> {
+        while self.sender.ack_position() < position {
+            self.recv()?;
+        }
+        Ok(())
+    }
+
+    fn check_halted(&self) -> ReplicationResult<()> {
+        match self.halted {
+            Some(reason) => Err(ReplicationError::halted(format!(
+                "replication to {} halted: {:?}",
+                self.replica_id, reason
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Halt on a fatal error, telling the replica why
+    fn fail(&mut self, error: ReplicationError) -> ReplicationError {
+        if is_halting(&error) {
+            let reason = halt_reason_for(&error);
+            self.halted = Some(reason);
+            return reject(&mut self.stream, reason, error);
+        }
+        error
+    }
+}
+
+/// Replica end of a replication connection
+#[derive(Debug)]
+pub struct ReplicaConnection<S: Read + Write> {
+    stream: S,
+    config: TransportConfig,
+    primary_id: Uuid,
+    receiver: WalReceiver,
+    halted: Option<HaltReason>,
+}
+
+impl ReplicaConnection<TcpStream> {
+    /// Connect to the Primary named in `replication` and perform the handshake
+    pub fn connect(
+        replication: &ReplicationConfig,
+        config: TransportConfig,
+        receiver: WalReceiver,
+        expected_primary: Option<Uuid>,
+    ) -> ReplicationResult<Self> {
+        let (stream, replica_id) = dial(replication, &config)?;
+        Self::handshake(stream, config, replica_id, receiver, expected_primary)
+    }
+}
+
+impl<S: Read + Write> ReplicaConnection<S> {
+    /// Send `Hello` and wait for the Primary to accept it
+    ///
+    /// Streaming resumes from the receiver's applied position. If
+    /// `expected_primary` is given, a different Primary is an authority
+    /// ambiguity.
+    pub fn handshake(
+        mut stream: S,
+        config: TransportConfig,
+        replica_id: Uuid,
+        mut receiver: WalReceiver,
+        expected_primary: Option<Uuid>,
+    ) -> ReplicationResult<Self> {
+        let position = receiver.applied_position();
+        write_frame(
+            &mut stream,
+            &Frame::Hello(Hello {
+                protocol_version: REPLICATION_PROTOCOL_VERSION,
+                wal_format_version: WAL_FORMAT_VERSION,
+                replica_id,
+                publication: receiver.publication().clone(),
+                position,
+            }),
+        )?;
+
+        let welcome = match read_frame(&mut stream, config.max_frame_bytes)? {
+            Frame::Welcome(welcome) => welcome,
+            Frame::Reject { reason, message } => {
+                return Err(ReplicationError::halted(format!(
+                    "primary rejected handshake ({:?}): {}",
+                    reason, message
+                )))
+            }
+            other => {
+                return Err(reject(
+                    &mut stream,
+                    HaltReason::ConfigurationError,
+                    protocol_violation("Welcome", &other),
+                ))
+            }
+        };
+
+        let accepted = check_versions(welcome.protocol_version, welcome.wal_format_version)
+            .and_then(|()| match expected_primary {
+                Some(expected) if expected != welcome.primary_id => {
+                    Err(ReplicationError::authority_ambiguity(format!(
+                        "expected primary {}, connected to {}",
+                        expected, welcome.primary_id
+                    )))
+                }
+                _ => Ok(()),
+            })
+            .and_then(|()| {
+                if welcome.start != position {
+                    return Err(ReplicationError::history_divergence(format!(
+                        "primary starts at sequence {}, replica resumes from {}",
+                        welcome.start.sequence, position.sequence
+                    )));
+                }
+                Ok(())
+            });
+        if let Err(e) = accepted {
+            return Err(reject(&mut stream, halt_reason_for(&e), e));
+        }
+
+        receiver.start();
+        Ok(Self {
+            stream,
+            config,
+            primary_id: welcome.primary_id,
+            receiver,
+            halted: None,
+        })
+    }
+
+    /// Connected Primary
+    pub fn primary_id(&self) -> Uuid {
+        self.primary_id
+    }
+
+    /// Receiver tracking applied positions
+    pub fn receiver(&self) -> &WalReceiver {
+        &self.receiver
+    }
+
+    /// Why the connection halted, if it has
+    pub fn halt_reason(&self) -> Option<HaltReason> {
+        self.halted
+    }
+
+    /// Next record to append to the local WAL
+    ///
+    /// Answers heartbeats and skips duplicates. The caller appends the
+    /// record durably, then calls `ack`.
+    pub fn next_record(&mut self) -> ReplicationResult<WalRecordEnvelope> {
+        self.check_halted()?;
+        loop {
+            let frame = match read_frame(&mut self.stream, self.config.max_frame_bytes) {
+                Ok(frame) => frame,
+                Err(e) => return Err(self.fail(e)),
+            };
+            match frame {
+                Frame::Record(envelope) => match self.receiver.receive(&envelope) {
+                    ReceiveResult::Accepted => return Ok(envelope),
+                    ReceiveResult::Duplicate => continue,
+                    result => {
+                        let e = result
+                            .to_result()
+                            .expect_err("non-accepted records are errors");
+                        return Err(self.fail(e));
+                    }
+                },
+                Frame::Heartbeat(position) => {
+                    let applied = self.receiver.applied_position();
+                    if position < applied {
+                        let e = ReplicationError::history_divergence(format!(
+                            "primary reports sequence {}, replica has applied {}",
+                            position.sequence, applied.sequence
+                        ));
+                        return Err(self.fail(e));
+                    }
+                    write_frame(&mut self.stream, &Frame::Heartbeat(applied))?;
+                }
+                Frame::Reject { reason, message } => {
+                    self.halted = Some(reason);
+                    return Err(ReplicationError::halted(format!(
+                        "primary halted replication: {}",
+                        message
+                    )));
+                }
+                other => {
+                    let e = protocol_violation("Record or Heartbeat", &other);
+                    return Err(self.fail(e));
+                }
+            }
+        }
+    }
+
+    /// Acknowledge a record returned by `next_record` once it is durable
+    pub fn ack(&mut self, envelope: &WalRecordEnvelope) -> ReplicationResult<()> {
+        self.check_halted()?;
+        let record_size = envelope.record.serialize().len() as u64;
+        self.receiver.apply(envelope, record_size);
+        write_frame(
+            &mut self.stream,
+            &Frame::Ack(self.receiver.applied_position()),
+        )
+    }
+
+    fn check_halted(&self) -> ReplicationResult<()> {
+        match self.halted {
+            Some(reason) => Err(ReplicationError::halted(format!(
+                "replication from {} halted: {:?}",
+                self.primary_id, reason
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Halt on a fatal error, telling the Primary why
+    fn fail(&mut self, error: ReplicationError) -> ReplicationError {
+        if is_halting(&error) {
+            let reason = halt_reason_for(&error);
+            self.halted = Some(reason);
+            return reject(&mut self.stream, reason, error);
+        }
+        error
+    }
+}
+
+/// Open a connection to the Primary named in `replication`
+///
+/// Returns the stream and this replica's id.
+pub(super) fn dial(
+    replication: &ReplicationConfig,
+    config: &TransportConfig,
+) -> ReplicationResult<(TcpStream, Uuid)> {
+    replication.validate()?;
+    let (Some(address), Some(replica_id)) = (
+        replication.primary_address.as_deref(),
+        replication.get_replica_id(),
+    ) else {
+        return Err(ReplicationError::configuration_error(
+            "Only a replica connects to a primary",
+        ));
+    };
+    let stream = TcpStream::connect(address).map_err(transport_io)?;
+    configure_stream(&stream, config)?;
+    Ok((stream, replica_id))
+}
+
+/// Apply the configured read timeout and disable batching delays
+pub(super) fn configure_stream(
+    stream: &TcpStream,
+    config: &TransportConfig,
+) -> ReplicationResult<()> {
+    stream
+        .set_read_timeout(Some(config.peer_timeout))
+        .map_err(transport_io)?;
+    stream.set_nodelay(true).map_err(transport_io)
+}
+
+fn check_versions(protocol_version: u16, wal_format_version: u8) -> ReplicationResult<()> {
+    if protocol_version != REPLICATION_PROTOCOL_VERSION {
+        return Err(ReplicationError::configuration_error(format!(
+            "protocol version {} is not supported (expected {})",
+            protocol_version, REPLICATION_PROTOCOL_VERSION
+        )));
+    }
+    if wal_format_version != WAL_FORMAT_VERSION {
+        return Err(ReplicationError::configuration_error(format!(
+            "WAL format version {} is not supported (expected {})",
+            wal_format_version, WAL_FORMAT_VERSION
+        )));
+    }
+    Ok(())
+}
+
+/// Send `Reject` (best effort) and return `error`
+pub(super) fn reject<W: Write>(
+    stream: &mut W,
+    reason: HaltReason,
+    error: ReplicationError,
+) -> ReplicationError {
+    let _ = write_frame(
+        stream,
+        &Frame::Reject {
+            reason,
+            message: error.message.clone(),
+        },
+    );
+    error
+}
+
+/// Whether an error ends replication rather than just the connection
+fn is_halting(error: &ReplicationError) -> bool {
+    !matches!(error.kind, ReplicationErrorKind::Transport)
+}
+
+pub(super) fn halt_reason_for(error: &ReplicationError) -> HaltReason {
+    match error.kind {
+        ReplicationErrorKind::WalGap => HaltReason::WalGapDetected,
+        ReplicationErrorKind::WalIntegrity => HaltReason::WalCorruption,
+        ReplicationErrorKind::HistoryDivergence => HaltReason::HistoryDivergence,
+        ReplicationErrorKind::AuthorityAmbiguity => HaltReason::AuthorityAmbiguity,
+        ReplicationErrorKind::SnapshotIntegrity => HaltReason::SnapshotIntegrityFailure,
+        _ => HaltReason::ConfigurationError,
+    }
+}
+
+fn halt_reason_code(reason: HaltReason) -> u8 {
+    match reason {
+        HaltReason::WalGapDetected => 1,
+        HaltReason::HistoryDivergence => 2,
+        HaltReason::AuthorityAmbiguity => 3,
+        HaltReason::WalCorruption => 4,
+        HaltReason::SnapshotIntegrityFailure => 5,
+        HaltReason::ConfigurationError => 6,
+    }
+}
+
+fn halt_reason_from_code(code: u8) -> ReplicationResult<HaltReason> {
+    Ok(match code {
+        1 => HaltReason::WalGapDetected,
+        2 => HaltReason::HistoryDivergence,
+        3 => HaltReason::AuthorityAmbiguity,
+        4 => HaltReason::WalCorruption,
+        5 => HaltReason::SnapshotIntegrityFailure,
+        6 => HaltReason::ConfigurationError,
+        other => return Err(corrupt(format!("unknown halt reason {}", other))),
+    })
+}
+
+pub(super) fn protocol_violation(expected: &str, received: &Frame) -> ReplicationError {
+    ReplicationError::configuration_error(format!(
+        "protocol violation: expected {}, received frame type {}",
+        expected,
+        received.type_byte()
+    ))
+}
+
+fn corrupt(message: impl Into<String>) -> ReplicationError {
+    ReplicationError::wal_integrity_failed(message)
+}
+
+pub(super) fn transport_io(e: io::Error) -> ReplicationError {
+    ReplicationError::transport(format!("replication connection failed: {}", e))
+}
+
+fn put_position(buf: &mut Vec<u8>, position: WalPosition) {
+    buf.extend_from_slice(&position.sequence.to_le_bytes());
+    buf.extend_from_slice(&position.offset.to_le_bytes());
+}
+
+/// Cursor over a frame body; running out of bytes is corruption
+struct BodyReader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> BodyReader<'a> {
+    fn take(&mut self, len: usize) -> ReplicationResult<&'a [u8]> {
+        if self.buf.len() < len {
+            return Err(corrupt("frame body truncated"));
+        }
+        let (head, tail) = self.buf.split_at(len);
+        self.buf = tail;
+        Ok(head)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        self.buf
+    }
+
+    fn u8(&mut self) -> ReplicationResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> ReplicationResult<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> ReplicationResult<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().expect("4 bytes")))
+    }
+
+    fn u64(&mut self) -> ReplicationResult<u64> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().expect("8 bytes")))
+    }
+
+    fn uuid(&mut self) -> ReplicationResult<Uuid> {
+        let bytes = self.take(16)?;
+        Ok(Uuid::from_slice(bytes).expect("16 bytes"))
+    }
+
+    fn position(&mut self) -> ReplicationResult<WalPosition> {
+        Ok(WalPosition::new(self.u64()?, self.u64()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wal::WalPayload;
+    use std::thread;
+
+    fn record(sequence_number: u64, collection_id: &str) -> WalRecord {
+        WalRecord::insert(
+            sequence_number,
+            WalPayload::new(collection_id, "doc1", "schema1", "v1", b"{}".to_vec()),
+        )
+    }
+
+    /// Listener on an ephemeral port and its address
+    fn listen() -> (TcpListener, String) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        (listener, address)
+    }
+
+    fn replica_config(address: String) -> (ReplicationConfig, Uuid) {
+        let replica_id = Uuid::new_v4();
+        (
+            ReplicationConfig::replica(address, Some(replica_id)),
+            replica_id,
+        )
+    }
+
+    #[test]
+    fn test_frame_roundtrip_and_corruption() {
+        let frame = Frame::Record(WalRecordEnvelope::new(
+            WalPosition::new(3, 120),
+            record(4, "users"),
+        ));
+        let mut buf = Vec::new();
+        write_frame(&mut buf, &frame).unwrap();
+        assert_eq!(read_frame(&mut buf.as_slice(), usize::MAX).unwrap(), frame);
+
+        let last = buf.len() - 6;
+        buf[last] ^= 0xFF;
+        let err = read_frame(&mut buf.as_slice(), usize::MAX).unwrap_err();
+        assert_eq!(err.kind, ReplicationErrorKind::WalIntegrity);
+
+        // Oversized frames are refused before reading the body
+        let mut buf = Vec::new();
+        write_frame(&mut buf, &frame).unwrap();
+        assert!(read_frame(&mut buf.as_slice(), 16).is_err());
+    }
+
+    #[test]
+    fn test_stream_records_with_heartbeats_and_acks() {
+        let (listener, address) = listen();
+        let primary_id = Uuid::new_v4();
+        let primary = thread::spawn(move || {
+            let mut conn = PrimaryConnection::accept(
+                &listener,
+                TransportConfig::default(),
+                primary_id,
+                &PublicationManifest::new(),
+                WalPosition::genesis(),
+            )
+            .unwrap();
+            let mut last = WalPosition::genesis();
+            for sequence in 1..=3 {
+                last = conn.send(&record(sequence, "users")).unwrap().unwrap();
+            }
+            conn.heartbeat().unwrap();
+            let end = conn.sender().current_position();
+            conn.wait_for_ack(end).unwrap();
+            (last, conn.sender().ack_position())
+        });
+
+        let (config, _) = replica_config(address);
+        let mut conn = ReplicaConnection::connect(
+            &config,
+            TransportConfig::default(),
+            WalReceiver::from_genesis(),
+            Some(primary_id),
+        )
+        .unwrap();
+        assert_eq!(conn.primary_id(), primary_id);
+        for sequence in 0..3 {
+            let envelope = conn.next_record().unwrap();
+            assert_eq!(envelope.position.sequence, sequence);
+            assert_eq!(envelope.record.sequence_number, sequence + 1);
+            conn.ack(&envelope).unwrap();
+        }
+
+        let (last_sent, acked) = primary.join().unwrap();
+        assert_eq!(last_sent.sequence, 2);
+        assert_eq!(acked, conn.receiver().applied_position());
+        assert_eq!(acked.sequence, 3);
+    }
+
+    #[test]
+    fn test_replica_refuses_fenced_primary() {
+        let (listener, address) = listen();
+        let primary = thread::spawn(move || {
+            let mut conn = PrimaryConnection::accept(
+                &listener,
+                TransportConfig::default(),
+                Uuid::new_v4(),
+                &PublicationManifest::new(),
+                WalPosition::genesis(),
+            )
+            .unwrap();
+            // Demoted at epoch 1, still writing
+            conn.set_epoch(1);
+            conn.send(&record(1, "users")).unwrap();
+            let err = conn.recv().unwrap_err();
+            (err.kind, conn.halt_reason())
+        });
+
+        // The replica has already followed the Primary promoted at epoch 2
+        let mut receiver = WalReceiver::from_genesis();
+        receiver.fence(2);
+        let (config, _) = replica_config(address);
+        let mut conn =
+            ReplicaConnection::connect(&config, TransportConfig::default(), receiver, None)
+                .unwrap();
+
+        let err = conn.next_record().unwrap_err();
+        assert_eq!(err.kind, ReplicationErrorKind::AuthorityAmbiguity);
+        assert_eq!(conn.halt_reason(), Some(HaltReason::AuthorityAmbiguity));
+        assert_eq!(conn.receiver().applied_position(), WalPosition::genesis());
+        assert_eq!(
+            primary.join().unwrap(),
+            (
+                ReplicationErrorKind::Halted,
+                Some(HaltReason::AuthorityAmbiguity)
+            )
+        );
+    }
+
+    #[test]
+    fn test_publication_mismatch_halts_both_sides() {
+        let (listener, address) = listen();
+        let (config, replica_id) = replica_config(address);
+        let mut manifest = PublicationManifest::new();
+        manifest
+            .assign(
+                replica_id,
+                Publication::for_collections("analytics", ["orders"]),
+            )
+            .unwrap();
+
+        let primary = thread::spawn(move || {
+            PrimaryConnection::accept(
+                &listener,
+                TransportConfig::default(),
+                Uuid::new_v4(),
+                &manifest,
+                WalPosition::genesis(),
+            )
+            .unwrap_err()
+        });
+
+        // The replica assumes the full history
+        let err = ReplicaConnection::connect(
+            &config,
+            TransportConfig::default(),
+            WalReceiver::from_genesis(),
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err.kind, ReplicationErrorKind::Halted);
+        assert!(err.message.contains("ConfigurationError"));
+        assert_eq!(
+            primary.join().unwrap().kind,
+            ReplicationErrorKind::ConfigurationError
+        );
+    }
+
+    #[test]
+    fn test_version_and_position_mismatches_rejected() {
+        let (listener, address) = listen();
+        let primary = thread::spawn(move || {
+            let accept = || {
+                PrimaryConnection::accept(
+                    &listener,
+                    TransportConfig::default(),
+                    Uuid::new_v4(),
+                    &PublicationManifest::new(),
+                    WalPosition::new(5, 500),
+                )
+                .unwrap_err()
+                .kind
+            };
+            (accept(), accept())
+        });
+
+        // A replica from a newer protocol
+        let mut stream = TcpStream::connect(&address).unwrap();
+        let hello = Hello {
+            protocol_version: REPLICATION_PROTOCOL_VERSION + 1,
+            wal_format_version: WAL_FORMAT_VERSION,
+            replica_id: Uuid::new_v4(),
+            publication: Publication::all(),
+            position: WalPosition::genesis(),
+        };
+        write_frame(&mut stream, &Frame::Hello(hello.clone())).unwrap();
+        assert!(matches!(
+            read_frame(&mut stream, usize::MAX).unwrap(),
+            Frame::Reject {
+                reason: HaltReason::ConfigurationError,
+                ..
+            }
+        ));
+
+        // A replica ahead of the primary
+        let stream = TcpStream::connect(&address).unwrap();
+        let err = ReplicaConnection::handshake(
+            stream,
+            TransportConfig::default(),
+            hello.replica_id,
+            WalReceiver::new(WalPosition::new(9, 900)),
+            None,
+        )
+        .unwrap_err();
+        assert!(err.message.contains("HistoryDivergence"));
+
+        assert_eq!(
+            primary.join().unwrap(),
+            (
+                ReplicationErrorKind::ConfigurationError,
+                ReplicationErrorKind::HistoryDivergence
+            )
+        );
+    }
+
+    #[test]
+    fn test_gap_halts_replica_and_primary() {
+        let (listener, address) = listen();
+        let primary = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let Frame::Hello(hello) = read_frame(&mut stream, usize::MAX).unwrap() else {
+                panic!("expected hello");
+            };
+            let welcome = Welcome {
+                protocol_version: REPLICATION_PROTOCOL_VERSION,
+                wal_format_version: WAL_FORMAT_VERSION,
+                primary_id: Uuid::new_v4(),
+                start: hello.position,
+            };
+            write_frame(&mut stream, &Frame::Welcome(welcome)).unwrap();
+
+            // Skips sequence 0
+            let envelope = WalRecordEnvelope::new(WalPosition::new(1, 40), record(2, "users"));
+            write_frame(&mut stream, &Frame::Record(envelope)).unwrap();
+            read_frame(&mut stream, usize::MAX).unwrap()
+        });
+
+        let (config, _) = replica_config(address);
+        let mut conn = ReplicaConnection::connect(
+            &config,
+            TransportConfig::default(),
+            WalReceiver::from_genesis(),
+            None,
+        )
+        .unwrap();
+        let err = conn.next_record().unwrap_err();
+        assert_eq!(err.kind, ReplicationErrorKind::WalGap);
+        assert_eq!(conn.halt_reason(), Some(HaltReason::WalGapDetected));
+        assert_eq!(
+            conn.next_record().unwrap_err().kind,
+            ReplicationErrorKind::Halted
+        );
+
+        assert!(matches!(
+            primary.join().unwrap(),
+            Frame::Reject {
+                reason: HaltReason::WalGapDetected,
+                ..
+            }
+        ));
+    }
+}
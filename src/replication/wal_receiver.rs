@@ -9,8 +9,16 @@
 //! Per §3.2 Prefix Application Rule:
 //! - Replica_WAL == Prefix(Primary_WAL)
 //! - Replicas may lag, but must never skip, reorder, or invent
+//!
+//! A receiver bound to a filtered publication holds a subset of the
+//! Primary history and rejects records outside its publication.
+//!
+//! The receiver is fenced at the highest Primary epoch it has seen.
+//! Records from an older epoch come from a demoted Primary and are
+//! refused as an authority ambiguity.
 
 use super::errors::{ReplicationError, ReplicationResult};
+use super::publication::Publication;
 use super::role::{HaltReason, ReplicationState};
 use super::wal_sender::{WalPosition, WalRecordEnvelope};
 use crate::wal::WalRecord;
@@ -24,15 +32,29 @@ pub struct WalReceiver {
     expected_sequence: u64,
     /// Whether receiver is active
     active: bool,
+    /// Publication this receiver expects to consume
+    publication: Publication,
+    /// Lowest fencing epoch accepted
+    epoch: u64,
 }
 
 impl WalReceiver {
     /// Create a new WAL receiver starting from a position.
     pub fn new(start_position: WalPosition) -> Self {
+        Self::with_publication(start_position, Publication::all())
+    }
+
+    /// Create a new WAL receiver bound to a publication.
+    ///
+    /// The same publication must be presented at handshake so the Primary
+    /// can confirm both sides agree on the filter.
+    pub fn with_publication(start_position: WalPosition, publication: Publication) -> Self {
         Self {
             applied_position: start_position,
             expected_sequence: start_position.sequence,
             active: false,
+            publication,
+            epoch: 0,
         }
     }
 
@@ -51,6 +73,8 @@ impl WalReceiver {
             applied_position: position,
             expected_sequence: snapshot_commit_sequence + 1,
             active: false,
+            publication: Publication::all(),
+            epoch: 0,
         }
     }
 
@@ -69,6 +93,23 @@ impl WalReceiver {
         self.active
     }
 
+    /// Get the publication this receiver consumes.
+    pub fn publication(&self) -> &Publication {
+        &self.publication
+    }
+
+    /// Get the fencing epoch: records from older epochs are refused.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Refuse records from epochs older than `epoch`.
+    ///
+    /// The fence only rises; a lower epoch is ignored.
+    pub fn fence(&mut self, epoch: u64) {
+        self.epoch = self.epoch.max(epoch);
+    }
+
     /// Get last applied position.
     pub fn applied_position(&self) -> WalPosition {
         self.applied_position
@@ -93,6 +134,14 @@ impl WalReceiver {
             return ReceiveResult::NotActive;
         }
 
+        // A demoted Primary's late writes are never applied
+        if envelope.epoch < self.epoch {
+            return ReceiveResult::StaleEpoch {
+                fenced: self.epoch,
+                received: envelope.epoch,
+            };
+        }
+
         // Per §5.1: Check for gaps
         if envelope.position.sequence < self.expected_sequence {
             // Duplicate - already received
@@ -114,6 +163,13 @@ impl WalReceiver {
             return ReceiveResult::ChecksumInvalid;
         }
 
+        // Primary and replica must agree on the publication filter
+        if !self.publication.includes_record(&envelope.record) {
+            return ReceiveResult::Unpublished {
+                collection_id: envelope.record.payload.collection_id.clone(),
+            };
+        }
+
         ReceiveResult::Accepted
     }
 
@@ -121,9 +177,12 @@ impl WalReceiver {
     ///
     /// Per REPLICATION_LOG_FLOW.md §4.2:
     /// - Record is considered replicated only when durably appended
+    ///
+    /// Applying a record from a newer epoch raises the fence.
     pub fn apply(&mut self, envelope: &WalRecordEnvelope, record_size: u64) {
         self.applied_position = envelope.position.advance(record_size);
         self.expected_sequence = envelope.position.sequence + 1;
+        self.fence(envelope.epoch);
     }
 
     /// Check if a position is behind (would need catch-up).
@@ -154,6 +213,12 @@ pub enum ReceiveResult {
 
     /// Checksum validation failed - fatal per Stage 3
     ChecksumInvalid,
+
+    /// Record belongs to a collection outside the receiver's publication
+    Unpublished { collection_id: String },
+
+    /// Record comes from a Primary fenced off by a newer epoch
+    StaleEpoch { fenced: u64, received: u64 },
 }
 
 impl ReceiveResult {
@@ -172,9 +237,23 @@ impl ReceiveResult {
         matches!(self, Self::ChecksumInvalid)
     }
 
-    /// Check if result is fatal (gap or checksum failure).
+    /// Check if result is a publication mismatch (fatal).
+    pub fn is_unpublished(&self) -> bool {
+        matches!(self, Self::Unpublished { .. })
+    }
+
+    /// Check if result is a record from a fenced Primary (fatal).
+    pub fn is_stale_epoch(&self) -> bool {
+        matches!(self, Self::StaleEpoch { .. })
+    }
+
+    /// Check if result is fatal (gap, checksum failure, publication
+    /// mismatch, or stale epoch).
     pub fn is_fatal(&self) -> bool {
-        self.is_gap() || self.is_checksum_invalid()
+        self.is_gap()
+            || self.is_checksum_invalid()
+            || self.is_unpublished()
+            || self.is_stale_epoch()
     }
 
     /// Convert to halt reason.
@@ -182,6 +261,8 @@ impl ReceiveResult {
         match self {
             Self::GapDetected { .. } => Some(HaltReason::WalGapDetected),
             Self::ChecksumInvalid => Some(HaltReason::WalCorruption),
+            Self::Unpublished { .. } => Some(HaltReason::ConfigurationError),
+            Self::StaleEpoch { .. } => Some(HaltReason::AuthorityAmbiguity),
             _ => None,
         }
     }
@@ -201,6 +282,18 @@ impl ReceiveResult {
             Self::ChecksumInvalid => Err(ReplicationError::wal_integrity_failed(
                 "WAL record checksum validation failed",
             )),
+            Self::Unpublished { collection_id } => {
+                Err(ReplicationError::configuration_error(format!(
+                    "received record for unpublished collection '{}'",
+                    collection_id
+                )))
+            }
+            Self::StaleEpoch { fenced, received } => {
+                Err(ReplicationError::authority_ambiguity(format!(
+                    "record from fenced primary: epoch {} is older than {}",
+                    received, fenced
+                )))
+            }
         }
     }
 }
@@ -292,6 +385,62 @@ mod tests {
         assert!(result.to_result().is_err());
     }
 
+    #[test]
+    fn test_receiver_rejects_unpublished_collection() {
+        let mut receiver = WalReceiver::with_publication(
+            WalPosition::genesis(),
+            Publication::for_collections("analytics", ["orders"]),
+        );
+        receiver.start();
+
+        let envelope = WalRecordEnvelope::new(WalPosition::genesis(), create_test_record());
+
+        let result = receiver.receive(&envelope);
+        assert!(result.is_unpublished());
+        assert!(result.is_fatal());
+        assert_eq!(
+            result.to_halt_reason(),
+            Some(HaltReason::ConfigurationError)
+        );
+    }
+
+    #[test]
+    fn test_receiver_refuses_stale_epoch() {
+        let mut receiver = WalReceiver::from_genesis();
+        receiver.fence(2);
+        receiver.fence(1);
+        assert_eq!(receiver.epoch(), 2);
+        receiver.start();
+
+        let envelope =
+            WalRecordEnvelope::new(WalPosition::genesis(), create_test_record()).with_epoch(1);
+
+        let result = receiver.receive(&envelope);
+        assert!(result.is_stale_epoch());
+        assert!(result.is_fatal());
+        assert_eq!(
+            result.to_halt_reason(),
+            Some(HaltReason::AuthorityAmbiguity)
+        );
+    }
+
+    #[test]
+    fn test_newer_epoch_raises_fence() {
+        let mut receiver = WalReceiver::from_genesis();
+        receiver.start();
+
+        let promoted =
+            WalRecordEnvelope::new(WalPosition::genesis(), create_test_record()).with_epoch(3);
+        assert!(receiver.receive(&promoted).is_accepted());
+        receiver.apply(&promoted, 50);
+        assert_eq!(receiver.epoch(), 3);
+
+        // The demoted Primary's next write is refused
+        let late =
+            WalRecordEnvelope::new(WalPosition::new(1, 50), create_test_record()).with_epoch(2);
+        assert!(receiver.receive(&late).is_stale_epoch());
+    }
+
     fn create_test_record() -> WalRecord {
         use crate::wal::{RecordType, WalPayload};
         WalRecord {
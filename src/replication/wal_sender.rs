@@ -5,8 +5,17 @@
 //! - Records must be sent verbatim
 //! - Order must be preserved
 //! - No re-encoding, reordering, or inference
+//!
+//! A sender may be bound to a filtered publication, in which case records
+//! for unpublished collections are never emitted and positions count
+//! published records only.
+//!
+//! Every envelope carries the fencing epoch the Primary holds authority
+//! under (see `promotion::AuthorityTransitionManager`), so replicas can
+//! refuse records from a demoted Primary.
 
 use super::errors::{ReplicationError, ReplicationResult};
+use super::publication::Publication;
 use crate::wal::WalRecord;
 
 /// WAL position tracking
@@ -50,15 +59,26 @@ pub struct WalSender {
     ack_position: WalPosition,
     /// Whether sender is active
     active: bool,
+    /// Publication restricting which collections are streamed
+    publication: Publication,
+    /// Fencing epoch stamped on every envelope
+    epoch: u64,
 }
 
 impl WalSender {
     /// Create a new WAL sender starting from a position.
     pub fn new(start_position: WalPosition) -> Self {
+        Self::with_publication(start_position, Publication::all())
+    }
+
+    /// Create a new WAL sender bound to a publication.
+    pub fn with_publication(start_position: WalPosition, publication: Publication) -> Self {
         Self {
             current_position: start_position,
             ack_position: start_position,
             active: false,
+            publication,
+            epoch: 0,
         }
     }
 
@@ -92,11 +112,34 @@ impl WalSender {
         self.ack_position
     }
 
+    /// Get the publication this sender streams.
+    pub fn publication(&self) -> &Publication {
+        &self.publication
+    }
+
+    /// Get the fencing epoch records are sent under.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Set the fencing epoch records are sent under.
+    pub fn set_epoch(&mut self, epoch: u64) {
+        self.epoch = epoch;
+    }
+
+    /// Check whether a record is part of this sender's publication.
+    pub fn publishes(&self, record: &WalRecord) -> bool {
+        self.publication.includes_record(record)
+    }
+
     /// Prepare a record for sending.
     ///
     /// Per REPLICATION_LOG_FLOW.md §2.1:
     /// - WAL records are sent verbatim
     /// - No re-encoding allowed
+    ///
+    /// Records outside the publication are rejected; use
+    /// `prepare_published` to skip them instead.
     pub fn prepare_record(&self, record: &WalRecord) -> ReplicationResult<WalRecordEnvelope> {
         if !self.active {
             return Err(ReplicationError::configuration_error(
@@ -104,10 +147,28 @@ impl WalSender {
             ));
         }
 
-        Ok(WalRecordEnvelope::new(
-            self.current_position,
-            record.clone(),
-        ))
+        if !self.publishes(record) {
+            return Err(ReplicationError::configuration_error(format!(
+                "collection '{}' is not part of publication '{}'",
+                record.payload.collection_id, self.publication.name
+            )));
+        }
+
+        Ok(WalRecordEnvelope::new(self.current_position, record.clone()).with_epoch(self.epoch))
+    }
+
+    /// Prepare a record for sending, skipping unpublished collections.
+    ///
+    /// Returns `Ok(None)` for records filtered out by the publication. The
+    /// caller must not call `record_sent` for skipped records.
+    pub fn prepare_published(
+        &self,
+        record: &WalRecord,
+    ) -> ReplicationResult<Option<WalRecordEnvelope>> {
+        if self.active && !self.publishes(record) {
+            return Ok(None);
+        }
+        self.prepare_record(record).map(Some)
     }
 
     /// Mark a record as sent and advance position.
@@ -139,7 +200,7 @@ impl WalSender {
 /// Envelope for WAL record transmission
 ///
 /// Per Stage 3: Includes checksum for validation before application.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct WalRecordEnvelope {
     /// Position of this record
     pub position: WalPosition,
@@ -147,6 +208,8 @@ pub struct WalRecordEnvelope {
     pub record: WalRecord,
     /// CRC32 checksum of the record for validation
     pub checksum: u32,
+    /// Fencing epoch of the Primary that sent the record
+    pub epoch: u64,
 }
 
 impl WalRecordEnvelope {
@@ -159,9 +222,16 @@ impl WalRecordEnvelope {
             position,
             record,
             checksum,
+            epoch: 0,
         }
     }
 
+    /// Stamp the envelope with a fencing epoch.
+    pub fn with_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
     /// Validate the envelope's checksum.
     ///
     /// Per Stage 3: Must validate before application.
@@ -233,4 +303,50 @@ mod tests {
         // Cannot ack a position we haven't sent yet
         assert!(sender.handle_ack(WalPosition::new(10, 1000)).is_err());
     }
+
+    #[test]
+    fn test_filtered_sender_skips_unpublished() {
+        let mut sender = WalSender::with_publication(
+            WalPosition::genesis(),
+            Publication::for_collections("analytics", ["orders"]),
+        );
+        sender.start();
+
+        assert!(sender
+            .prepare_published(&create_test_record("users"))
+            .unwrap()
+            .is_none());
+        assert!(sender.prepare_record(&create_test_record("users")).is_err());
+
+        let envelope = sender
+            .prepare_published(&create_test_record("orders"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(envelope.position, WalPosition::genesis());
+    }
+
+    #[test]
+    fn test_envelopes_carry_sender_epoch() {
+        let mut sender = WalSender::from_genesis();
+        sender.start();
+        sender.set_epoch(7);
+
+        let envelope = sender.prepare_record(&create_test_record("users")).unwrap();
+        assert_eq!(envelope.epoch, 7);
+    }
+
+    fn create_test_record(collection_id: &str) -> WalRecord {
+        use crate::wal::{RecordType, WalPayload};
+        WalRecord {
+            sequence_number: 0,
+            record_type: RecordType::Insert,
+            payload: WalPayload {
+                collection_id: collection_id.to_string(),
+                document_id: "doc1".to_string(),
+                schema_id: "schema1".to_string(),
+                schema_version: "v1".to_string(),
+                document_body: vec![],
+            },
+        }
+    }
 }
@@ -0,0 +1,203 @@
+//! # Batch Operations
+//!
+//! Request and response types for `POST /rest/v1/{collection}/batch`.
+//!
+//! A batch is all-or-nothing: every operation is checked against the
+//! state left by the operations before it, and the batch is committed in
+//! one step only if all of them succeed.
+
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::errors::{RestError, RestResult};
+
+/// Maximum number of operations in one batch
+pub const MAX_BATCH_SIZE: usize = 1000;
+
+/// One operation of a batch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum BatchOp {
+    /// Insert a record
+    Insert { data: Value },
+    /// Merge `data` into the record with `id`
+    Update {
+        id: String,
+        data: Value,
+        /// ETag the record must still have, as in an If-Match header
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        if_match: Option<String>,
+    },
+    /// Delete the record with `id`
+    Delete {
+        id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        if_match: Option<String>,
+    },
+}
+
+/// Check a batch is non-empty and bounded
+pub fn validate_batch(ops: &[BatchOp]) -> RestResult<()> {
+    if ops.is_empty() {
+        return Err(RestError::InvalidBody(
+            "batch must contain at least one operation".to_string(),
+        ));
+    }
+    if ops.len() > MAX_BATCH_SIZE {
+        return Err(RestError::InvalidBody(format!(
+            "batch of {} operations exceeds maximum {}",
+            ops.len(),
+            MAX_BATCH_SIZE
+        )));
+    }
+    Ok(())
+}
+
+/// Outcome of one operation of a batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchItemResult {
+    /// HTTP status the operation would have had on its own
+    pub status: u16,
+    /// Problem type slug of the failure (see `RestError::problem_type`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Record as written (inserts and updates)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl BatchItemResult {
+    /// Record inserted
+    pub fn created(data: Value) -> Self {
+        Self::success(StatusCode::CREATED, Some(data))
+    }
+
+    /// Record updated
+    pub fn updated(data: Value) -> Self {
+        Self::success(StatusCode::OK, Some(data))
+    }
+
+    /// Record deleted
+    pub fn deleted() -> Self {
+        Self::success(StatusCode::OK, None)
+    }
+
+    fn success(status: StatusCode, data: Option<Value>) -> Self {
+        Self {
+            status: status.as_u16(),
+            code: None,
+            error: None,
+            data,
+        }
+    }
+
+    /// Operation failed with `err`
+    pub fn failed(err: &RestError) -> Self {
+        Self {
+            status: err.status_code().as_u16(),
+            code: Some(err.problem_type().to_string()),
+            error: Some(err.to_string()),
+            data: None,
+        }
+    }
+
+    /// Operation would have succeeded, but another one in the batch failed
+    fn aborted() -> Self {
+        Self {
+            status: StatusCode::FAILED_DEPENDENCY.as_u16(),
+            code: Some("batch-aborted".to_string()),
+            error: Some("Not applied: another operation in the batch failed".to_string()),
+            data: None,
+        }
+    }
+
+    fn is_success(&self) -> bool {
+        self.code.is_none()
+    }
+}
+
+/// Batch response: one result per operation, in request order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchResponse {
+    /// Whether the batch was applied
+    pub committed: bool,
+    pub results: Vec<BatchItemResult>,
+}
+
+impl BatchResponse {
+    /// Response for the per-operation outcomes of a staged batch
+    ///
+    /// If any operation failed, the batch is not committed and the
+    /// operations that succeeded are reported as aborted.
+    pub fn new(outcomes: Vec<RestResult<BatchItemResult>>) -> Self {
+        let committed = outcomes.iter().all(Result::is_ok);
+        let results = outcomes
+            .into_iter()
+            .map(|outcome| match outcome {
+                Ok(_) if !committed => BatchItemResult::aborted(),
+                Ok(result) => result,
+                Err(err) => BatchItemResult::failed(&err),
+            })
+            .collect();
+        Self { committed, results }
+    }
+
+    /// HTTP status of the whole batch: 200 if committed, otherwise the
+    /// status of the first failed operation
+    pub fn status_code(&self) -> StatusCode {
+        if self.committed {
+            return StatusCode::OK;
+        }
+        self.results
+            .iter()
+            .find(|r| !r.is_success() && r.status != StatusCode::FAILED_DEPENDENCY.as_u16())
+            .and_then(|r| StatusCode::from_u16(r.status).ok())
+            .unwrap_or(StatusCode::BAD_REQUEST)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_batch_ops() {
+        let ops: Vec<BatchOp> = serde_json::from_value(json!([
+            {"op": "insert", "data": {"name": "Ada"}},
+            {"op": "update", "id": "u1", "data": {"name": "Grace"}},
+            {"op": "delete", "id": "u2"},
+        ]))
+        .unwrap();
+        assert_eq!(
+            ops[2],
+            BatchOp::Delete {
+                id: "u2".to_string(),
+                if_match: None,
+            }
+        );
+
+        assert!(validate_batch(&ops).is_ok());
+        assert!(validate_batch(&[]).is_err());
+        assert!(serde_json::from_value::<BatchOp>(json!({"op": "upsert"})).is_err());
+    }
+
+    #[test]
+    fn test_failed_item_aborts_batch() {
+        let response = BatchResponse::new(vec![
+            Ok(BatchItemResult::created(json!({"id": "u1"}))),
+            Err(RestError::NotFound),
+        ]);
+        assert!(!response.committed);
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(response.results[0].status, 424);
+        assert_eq!(response.results[1].code.as_deref(), Some("not-found"));
+
+        let response = BatchResponse::new(vec![Ok(BatchItemResult::deleted())]);
+        assert!(response.committed);
+        assert_eq!(response.status_code(), StatusCode::OK);
+    }
+}
@@ -9,8 +9,11 @@ use std::sync::{Arc, RwLock};
 use serde_json::Value;
 use uuid::Uuid;
 
+use super::batch::{BatchItemResult, BatchOp, BatchResponse};
 use super::errors::{RestError, RestResult};
+use super::etag::check_if_match;
 use super::handler::RestHandler;
+use super::pagination::{paginate, sort_records};
 use super::parser::QueryParams;
 use super::response::{
     DeleteResponse, InsertResponse, ListResponse, SingleResponse, UpdateResponse,
@@ -50,13 +53,109 @@ impl<E: RlsEnforcer> DatabaseFacade<E> {
     }
 
     /// Update a collection
-    fn update_collection<F>(&self, name: &str, f: F)
+    fn update_collection<F, T>(&self, name: &str, f: F) -> T
     where
-        F: FnOnce(&mut CollectionData),
+        F: FnOnce(&mut CollectionData) -> T,
     {
         let mut collections = self.collections.write().unwrap();
         let collection = collections.entry(name.to_string()).or_default();
-        f(collection);
+        f(collection)
+    }
+
+    /// Insert `data` into `coll`
+    fn insert_into(
+        &self,
+        coll: &mut CollectionData,
+        collection: &str,
+        mut data: Value,
+        ctx: &RlsContext,
+    ) -> RestResult<Value> {
+        // Validate RLS for write
+        self.rls
+            .validate_write(collection, &data, ctx)
+            .map_err(RestError::Auth)?;
+
+        // Prepare document (inject owner_id if needed)
+        self.rls
+            .prepare_insert(collection, &mut data, ctx)
+            .map_err(|e| RestError::InvalidBody(e.to_string()))?;
+
+        // Generate ID if not present
+        let id = if let Some(id) = data.get("_id").and_then(|v| v.as_str()) {
+            id.to_string()
+        } else {
+            let id = Uuid::new_v4().to_string();
+            data.as_object_mut()
+                .ok_or_else(|| RestError::InvalidBody("record must be an object".to_string()))?
+                .insert("_id".to_string(), Value::String(id.clone()));
+            id
+        };
+
+        // Insert document
+        coll.documents.insert(id, data.clone());
+        Ok(data)
+    }
+
+    /// Merge `updates` into the document of `coll` with `id`
+    fn update_in(
+        &self,
+        coll: &mut CollectionData,
+        collection: &str,
+        id: &str,
+        updates: Value,
+        if_match: Option<&str>,
+        ctx: &RlsContext,
+    ) -> RestResult<Value> {
+        // Get existing document
+        let existing = coll.documents.get(id).ok_or(RestError::NotFound)?;
+
+        // Check RLS
+        let allowed = self.apply_rls_filter(collection, &[existing.clone()], ctx)?;
+        if allowed.is_empty() {
+            return Err(RestError::NotFound);
+        }
+        check_if_match(id, existing, if_match)?;
+
+        // Merge updates
+        let mut updated = existing.clone();
+        if let (Value::Object(base), Value::Object(patches)) = (&mut updated, updates) {
+            for (k, v) in patches {
+                base.insert(k, v);
+            }
+        }
+
+        // Validate updated document
+        self.rls
+            .validate_write(collection, &updated, ctx)
+            .map_err(RestError::Auth)?;
+
+        // Store updated document
+        coll.documents.insert(id.to_string(), updated.clone());
+        Ok(updated)
+    }
+
+    /// Remove the document with `id` from `coll`
+    fn delete_from(
+        &self,
+        coll: &mut CollectionData,
+        collection: &str,
+        id: &str,
+        if_match: Option<&str>,
+        ctx: &RlsContext,
+    ) -> RestResult<()> {
+        // Check if exists
+        let existing = coll.documents.get(id).ok_or(RestError::NotFound)?;
+
+        // Check RLS
+        let allowed = self.apply_rls_filter(collection, &[existing.clone()], ctx)?;
+        if allowed.is_empty() {
+            return Err(RestError::NotFound);
+        }
+        check_if_match(id, existing, if_match)?;
+
+        // Delete
+        coll.documents.remove(id);
+        Ok(())
     }
 
     /// Apply RLS filter to records
@@ -108,31 +207,6 @@ impl<E: RlsEnforcer> DatabaseFacade<E> {
             .collect()
     }
 
-    /// Apply ordering
-    fn apply_ordering(records: &mut [Value], params: &QueryParams) {
-        if let Some(order_by) = params.order.first() {
-            records.sort_by(|a, b| {
-                let va = a.get(&order_by.field);
-                let vb = b.get(&order_by.field);
-                let cmp = compare_json_values(va, vb);
-                if order_by.ascending {
-                    cmp
-                } else {
-                    cmp.reverse()
-                }
-            });
-        }
-    }
-
-    /// Apply pagination
-    fn apply_pagination(records: Vec<Value>, params: &QueryParams) -> Vec<Value> {
-        records
-            .into_iter()
-            .skip(params.offset)
-            .take(params.limit)
-            .collect()
-    }
-
     /// Select fields from records
     fn select_fields(records: Vec<Value>, params: &QueryParams) -> Vec<Value> {
         match &params.select {
@@ -177,10 +251,10 @@ impl<E: RlsEnforcer + Send + Sync> RestHandler for DatabaseFacade<E> {
 
         // Apply ordering
         let mut sorted = filtered;
-        Self::apply_ordering(&mut sorted, &params);
+        sort_records(&mut sorted, &params.sort_order());
 
         // Apply pagination
-        let paginated = Self::apply_pagination(sorted, &params);
+        let (paginated, next_cursor) = paginate(sorted, &params);
 
         // Select fields
         let selected = Self::select_fields(paginated, &params);
@@ -190,6 +264,7 @@ impl<E: RlsEnforcer + Send + Sync> RestHandler for DatabaseFacade<E> {
             count: total,
             limit: params.limit,
             offset: params.offset,
+            next_cursor,
         })
     }
 
@@ -217,35 +292,12 @@ impl<E: RlsEnforcer + Send + Sync> RestHandler for DatabaseFacade<E> {
     fn insert(
         &self,
         collection: &str,
-        mut data: Value,
+        data: Value,
         ctx: &RlsContext,
     ) -> RestResult<InsertResponse<Value>> {
-        // Validate RLS for write
-        self.rls
-            .validate_write(collection, &data, ctx)
-            .map_err(RestError::Auth)?;
-
-        // Prepare document (inject owner_id if needed)
-        self.rls
-            .prepare_insert(collection, &mut data, ctx)
-            .map_err(|e| RestError::InvalidBody(e.to_string()))?;
-
-        // Generate ID if not present
-        let id = if let Some(id) = data.get("_id").and_then(|v| v.as_str()) {
-            id.to_string()
-        } else {
-            let id = Uuid::new_v4().to_string();
-            data.as_object_mut()
-                .unwrap()
-                .insert("_id".to_string(), Value::String(id.clone()));
-            id
-        };
-
-        // Insert document
-        let result = data.clone();
-        self.update_collection(collection, |coll| {
-            coll.documents.insert(id, data);
-        });
+        let result = self.update_collection(collection, |coll| {
+            self.insert_into(coll, collection, data, ctx)
+        })?;
 
         Ok(InsertResponse {
             data: vec![result],
@@ -258,74 +310,60 @@ impl<E: RlsEnforcer + Send + Sync> RestHandler for DatabaseFacade<E> {
         collection: &str,
         id: &str,
         updates: Value,
+        if_match: Option<&str>,
         ctx: &RlsContext,
     ) -> RestResult<UpdateResponse<Value>> {
-        let coll = self.collection(collection);
-
-        // Get existing document
-        let existing = coll.documents.get(id).ok_or(RestError::NotFound)?;
-
-        // Check RLS
-        let allowed = self.apply_rls_filter(collection, &[existing.clone()], ctx)?;
-        if allowed.is_empty() {
-            return Err(RestError::NotFound);
-        }
-
-        // Merge updates
-        let mut updated = existing.clone();
-        if let (Value::Object(base), Value::Object(patches)) = (&mut updated, updates) {
-            for (k, v) in patches {
-                base.insert(k, v);
-            }
-        }
-
-        // Validate updated document
-        self.rls
-            .validate_write(collection, &updated, ctx)
-            .map_err(RestError::Auth)?;
-
-        // Store updated document
-        let result = updated.clone();
-        self.update_collection(collection, |coll| {
-            coll.documents.insert(id.to_string(), updated);
-        });
+        let result = self.update_collection(collection, |coll| {
+            self.update_in(coll, collection, id, updates, if_match, ctx)
+        })?;
 
         Ok(UpdateResponse { data: result })
     }
 
-    fn delete(&self, collection: &str, id: &str, ctx: &RlsContext) -> RestResult<DeleteResponse> {
-        let coll = self.collection(collection);
-
-        // Check if exists
-        let existing = coll.documents.get(id).ok_or(RestError::NotFound)?;
-
-        // Check RLS
-        let allowed = self.apply_rls_filter(collection, &[existing.clone()], ctx)?;
-        if allowed.is_empty() {
-            return Err(RestError::NotFound);
-        }
-
-        // Delete
+    fn delete(
+        &self,
+        collection: &str,
+        id: &str,
+        if_match: Option<&str>,
+        ctx: &RlsContext,
+    ) -> RestResult<DeleteResponse> {
         self.update_collection(collection, |coll| {
-            coll.documents.remove(id);
-        });
+            self.delete_from(coll, collection, id, if_match, ctx)
+        })?;
 
         Ok(DeleteResponse { deleted: true })
     }
-}
 
-/// Compare JSON values for sorting
-fn compare_json_values(a: Option<&Value>, b: Option<&Value>) -> std::cmp::Ordering {
-    match (a, b) {
-        (Some(Value::Number(a)), Some(Value::Number(b))) => a
-            .as_f64()
-            .unwrap_or(0.0)
-            .partial_cmp(&b.as_f64().unwrap_or(0.0))
-            .unwrap_or(std::cmp::Ordering::Equal),
-        (Some(Value::String(a)), Some(Value::String(b))) => a.cmp(b),
-        (Some(_), None) => std::cmp::Ordering::Less,
-        (None, Some(_)) => std::cmp::Ordering::Greater,
-        _ => std::cmp::Ordering::Equal,
+    fn batch(
+        &self,
+        collection: &str,
+        ops: Vec<BatchOp>,
+        ctx: &RlsContext,
+    ) -> RestResult<BatchResponse> {
+        let mut collections = self.collections.write().unwrap();
+
+        // Stage every op on a copy; the collection is replaced only if all succeed
+        let mut staged = collections.get(collection).cloned().unwrap_or_default();
+        let outcomes = ops
+            .into_iter()
+            .map(|op| match op {
+                BatchOp::Insert { data } => self
+                    .insert_into(&mut staged, collection, data, ctx)
+                    .map(BatchItemResult::created),
+                BatchOp::Update { id, data, if_match } => self
+                    .update_in(&mut staged, collection, &id, data, if_match.as_deref(), ctx)
+                    .map(BatchItemResult::updated),
+                BatchOp::Delete { id, if_match } => self
+                    .delete_from(&mut staged, collection, &id, if_match.as_deref(), ctx)
+                    .map(|()| BatchItemResult::deleted()),
+            })
+            .collect();
+
+        let response = BatchResponse::new(outcomes);
+        if response.committed {
+            collections.insert(collection.to_string(), staged);
+        }
+        Ok(response)
     }
 }
 
@@ -416,7 +454,7 @@ mod tests {
         db.insert("items", doc, &ctx).unwrap();
 
         let updates = json!({"name": "New", "count": 2});
-        let result = db.update("items", "test-id", updates, &ctx).unwrap();
+        let result = db.update("items", "test-id", updates, None, &ctx).unwrap();
         assert_eq!(result.data.get("name").unwrap(), "New");
         assert_eq!(result.data.get("count").unwrap(), 2);
     }
@@ -430,13 +468,60 @@ mod tests {
         let doc = json!({"_id": "delete-id", "name": "ToDelete"});
         db.insert("items", doc, &ctx).unwrap();
 
-        let result = db.delete("items", "delete-id", &ctx).unwrap();
+        let result = db.delete("items", "delete-id", None, &ctx).unwrap();
         assert!(result.deleted);
 
         // Verify deleted
         assert!(db.get("items", "delete-id", &ctx).is_err());
     }
 
+    #[test]
+    fn test_batch_rolls_back_on_failure() {
+        let db = create_facade();
+        let ctx = RlsContext::service_role();
+        db.insert("items", json!({"_id": "keep", "n": 1}), &ctx)
+            .unwrap();
+
+        let response = db
+            .batch(
+                "items",
+                vec![
+                    BatchOp::Update {
+                        id: "keep".to_string(),
+                        data: json!({"n": 2}),
+                        if_match: None,
+                    },
+                    BatchOp::Delete {
+                        id: "missing".to_string(),
+                        if_match: None,
+                    },
+                ],
+                &ctx,
+            )
+            .unwrap();
+        assert!(!response.committed);
+        assert_eq!(db.get("items", "keep", &ctx).unwrap().data["n"], 1);
+
+        let response = db
+            .batch(
+                "items",
+                vec![
+                    BatchOp::Insert {
+                        data: json!({"_id": "new"}),
+                    },
+                    BatchOp::Delete {
+                        id: "keep".to_string(),
+                        if_match: None,
+                    },
+                ],
+                &ctx,
+            )
+            .unwrap();
+        assert!(response.committed);
+        assert!(db.get("items", "new", &ctx).is_ok());
+        assert!(db.get("items", "keep", &ctx).is_err());
+    }
+
     #[test]
     fn test_pagination() {
         let db = create_facade();
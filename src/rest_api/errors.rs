@@ -2,10 +2,10 @@
 //!
 //! Error types for the REST API module.
 
-use axum::http::StatusCode;
+use axum::http::{header, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::auth::AuthError;
@@ -51,6 +51,10 @@ pub enum RestError {
     #[error("Limit {0} exceeds maximum {1}")]
     LimitExceeded(usize, usize),
 
+    /// If-Match does not match the record's current version
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
+
     // ==================
     // Auth Errors
     // ==================
@@ -58,6 +62,17 @@ pub enum RestError {
     #[error("{0}")]
     Auth(#[from] AuthError),
 
+    // ==================
+    // Replication
+    // ==================
+    /// Write sent to a node that is not the Primary (state, Primary URL)
+    #[error("Writes are not accepted in replication state {0}; send them to the primary")]
+    NotPrimary(String, Option<String>),
+
+    /// Replica cannot prove a read safe (reason, Primary URL)
+    #[error("Replica cannot serve reads: {0}")]
+    ReplicaUnavailable(String, Option<String>),
+
     // ==================
     // Server Errors (5xx)
     // ==================
@@ -68,6 +83,10 @@ pub enum RestError {
     /// Schema loading error
     #[error("Schema error: {0}")]
     SchemaError(String),
+
+    /// Operation not supported by this backend
+    #[error("Not supported: {0}")]
+    Unsupported(String),
 }
 
 impl RestError {
@@ -82,6 +101,9 @@ impl RestError {
             RestError::UnboundedQuery(_) => StatusCode::BAD_REQUEST,
             RestError::LimitExceeded(_, _) => StatusCode::BAD_REQUEST,
 
+            // 412 Precondition Failed
+            RestError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+
             // 401/403 from auth
             RestError::Auth(auth_err) => {
                 StatusCode::from_u16(auth_err.status_code()).unwrap_or(StatusCode::UNAUTHORIZED)
@@ -91,25 +113,81 @@ impl RestError {
             RestError::NotFound => StatusCode::NOT_FOUND,
             RestError::CollectionNotFound(_) => StatusCode::NOT_FOUND,
 
+            // 307 to the Primary when it is known, 503 otherwise
+            RestError::NotPrimary(_, Some(_)) => StatusCode::TEMPORARY_REDIRECT,
+            RestError::NotPrimary(_, None) => StatusCode::SERVICE_UNAVAILABLE,
+            RestError::ReplicaUnavailable(_, _) => StatusCode::SERVICE_UNAVAILABLE,
+
             // 500 Internal Server Error
             RestError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
             RestError::SchemaError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+
+            // 501 Not Implemented
+            RestError::Unsupported(_) => StatusCode::NOT_IMPLEMENTED,
+        }
+    }
+
+    /// Problem type slug, stable across message wording changes
+    pub fn problem_type(&self) -> &'static str {
+        match self {
+            RestError::InvalidQueryParam(_) => "invalid-query-param",
+            RestError::InvalidFilter(_) => "invalid-filter",
+            RestError::MissingParam(_) => "missing-param",
+            RestError::InvalidBody(_) => "invalid-body",
+            RestError::NotFound => "not-found",
+            RestError::CollectionNotFound(_) => "collection-not-found",
+            RestError::UnboundedQuery(_) => "unbounded-query",
+            RestError::LimitExceeded(_, _) => "limit-exceeded",
+            RestError::PreconditionFailed(_) => "precondition-failed",
+            RestError::Auth(_) => "auth",
+            RestError::NotPrimary(_, _) => "not-primary",
+            RestError::ReplicaUnavailable(_, _) => "replica-unavailable",
+            RestError::Internal(_) => "internal",
+            RestError::SchemaError(_) => "schema",
+            RestError::Unsupported(_) => "unsupported",
+        }
+    }
+
+    /// Primary URL to retry the request at, if known
+    pub fn redirect(&self) -> Option<&str> {
+        match self {
+            RestError::NotPrimary(_, primary) | RestError::ReplicaUnavailable(_, primary) => {
+                primary.as_deref()
+            }
+            _ => None,
         }
     }
 }
 
-/// Error response body
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-    pub code: u16,
+/// Media type of error responses (RFC 7807)
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Error response body: RFC 7807 problem details
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProblemDetails {
+    /// URI identifying the problem type, e.g. `urn:aerodb:problem:invalid-filter`
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    /// Short summary of the problem type
+    pub title: String,
+    /// HTTP status code
+    pub status: u16,
+    /// Explanation specific to this occurrence
+    pub detail: String,
+    /// Where to retry the request (replication redirects only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary: Option<String>,
 }
 
-impl From<RestError> for ErrorResponse {
+impl From<RestError> for ProblemDetails {
     fn from(err: RestError) -> Self {
+        let status = err.status_code();
         Self {
-            code: err.status_code().as_u16(),
-            error: err.to_string(),
+            problem_type: format!("urn:aerodb:problem:{}", err.problem_type()),
+            title: status.canonical_reason().unwrap_or("Error").to_string(),
+            status: status.as_u16(),
+            detail: err.to_string(),
+            primary: err.redirect().map(str::to_string),
         }
     }
 }
@@ -117,8 +195,16 @@ impl From<RestError> for ErrorResponse {
 impl IntoResponse for RestError {
     fn into_response(self) -> Response {
         let status = self.status_code();
-        let body = Json(ErrorResponse::from(self));
-        (status, body).into_response()
+        let location = match &self {
+            RestError::NotPrimary(_, Some(url)) => Some(url.clone()),
+            _ => None,
+        };
+        let body = Json(ProblemDetails::from(self));
+        let content_type = (header::CONTENT_TYPE, PROBLEM_JSON.to_string());
+        match location {
+            Some(url) => (status, [content_type, (header::LOCATION, url)], body).into_response(),
+            None => (status, [content_type], body).into_response(),
+        }
     }
 }
 
@@ -139,6 +225,23 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_error_is_problem_json() {
+        let response = RestError::InvalidFilter("age: in takes a list".to_string()).into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], PROBLEM_JSON);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem["type"], "urn:aerodb:problem:invalid-filter");
+        assert_eq!(problem["title"], "Bad Request");
+        assert_eq!(problem["status"], 400);
+        assert_eq!(problem["detail"], "Invalid filter: age: in takes a list");
+        assert!(problem.get("primary").is_none());
+    }
+
     #[test]
     fn test_auth_error_propagation() {
         let auth_err = AuthError::InvalidCredentials;
@@ -0,0 +1,71 @@
+//! # Entity Tags
+//!
+//! Record versions for conditional requests.
+//!
+//! A record's ETag is the CRC32 checksum of its JSON body, so any change
+//! to the record changes its ETag. `If-Match` on PATCH and DELETE makes
+//! the write conditional on the record still having that ETag.
+
+use serde_json::Value;
+
+use super::errors::{RestError, RestResult};
+use crate::storage::compute_checksum;
+
+/// Strong ETag of `record`, quoted as sent in the `ETag` header
+pub fn etag(record: &Value) -> String {
+    let body = serde_json::to_vec(record).unwrap_or_default();
+    format!("\"{:08x}\"", compute_checksum(&body))
+}
+
+/// Check `record` satisfies an `If-Match` header value
+///
+/// `*` matches any existing record; otherwise one of the listed ETags must
+/// equal the record's. Weak ETags (`W/"..."`) never match, as If-Match
+/// uses strong comparison.
+pub fn check_if_match(id: &str, record: &Value, if_match: Option<&str>) -> RestResult<()> {
+    let Some(if_match) = if_match else {
+        return Ok(());
+    };
+    let current = etag(record);
+    let matched = if_match
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag == current);
+    if matched {
+        Ok(())
+    } else {
+        Err(RestError::PreconditionFailed(format!(
+            "{} is at version {}, not {}",
+            id, current, if_match
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_etag_tracks_content() {
+        let record = json!({"id": "u1", "name": "Ada"});
+        assert_eq!(etag(&record), etag(&record.clone()));
+        assert_ne!(etag(&record), etag(&json!({"id": "u1", "name": "Grace"})));
+        assert!(etag(&record).starts_with('"'));
+    }
+
+    #[test]
+    fn test_if_match() {
+        let record = json!({"id": "u1"});
+        let current = etag(&record);
+
+        assert!(check_if_match("u1", &record, None).is_ok());
+        assert!(check_if_match("u1", &record, Some("*")).is_ok());
+        assert!(check_if_match("u1", &record, Some(&current)).is_ok());
+        assert!(check_if_match("u1", &record, Some(&format!("\"0\", {}", current))).is_ok());
+
+        let err = check_if_match("u1", &record, Some("\"00000000\"")).unwrap_err();
+        assert_eq!(err.status_code().as_u16(), 412);
+        assert!(check_if_match("u1", &record, Some(&format!("W/{}", current))).is_err());
+    }
+}
@@ -43,6 +43,14 @@ pub enum FilterOperator {
     /// Is null/not null
     #[serde(rename = "is")]
     Is,
+
+    /// String starts with value
+    #[serde(rename = "prefix")]
+    Prefix,
+
+    /// Present and non-null (`true`) or absent/null (`false`)
+    #[serde(rename = "exists")]
+    Exists,
 }
 
 impl FilterOperator {
@@ -58,6 +66,8 @@ impl FilterOperator {
             FilterOperator::Like => "like",
             FilterOperator::In => "in",
             FilterOperator::Is => "is",
+            FilterOperator::Prefix => "prefix",
+            FilterOperator::Exists => "exists",
         }
     }
 }
@@ -104,7 +114,13 @@ impl FilterExpr {
     pub fn matches(&self, doc: &Value) -> bool {
         let field_value = match doc.get(&self.field) {
             Some(v) => v,
-            None => return self.operator == FilterOperator::Is && self.value.is_null(),
+            None => {
+                return match self.operator {
+                    FilterOperator::Is => self.value.is_null(),
+                    FilterOperator::Exists => self.value == Value::Bool(false),
+                    _ => false,
+                }
+            }
         };
 
         match self.operator {
@@ -137,6 +153,11 @@ impl FilterExpr {
                     !field_value.is_null()
                 }
             }
+            FilterOperator::Prefix => match (field_value.as_str(), self.value.as_str()) {
+                (Some(field_str), Some(prefix)) => field_str.starts_with(prefix),
+                _ => false,
+            },
+            FilterOperator::Exists => field_value.is_null() != (self.value == Value::Bool(true)),
         }
     }
 }
@@ -273,6 +294,23 @@ mod tests {
         assert!(!filter.matches(&json!({"name": "Smith"})));
     }
 
+    #[test]
+    fn test_prefix_and_exists_filters() {
+        let prefix = FilterExpr::new("email", FilterOperator::Prefix, json!("ada@"));
+        assert!(prefix.matches(&json!({"email": "ada@example.com"})));
+        assert!(!prefix.matches(&json!({"email": "bob@example.com"})));
+        assert!(!prefix.matches(&json!({"email": 42})));
+
+        let present = FilterExpr::new("deleted_at", FilterOperator::Exists, json!(true));
+        let absent = FilterExpr::new("deleted_at", FilterOperator::Exists, json!(false));
+        for doc in [json!({}), json!({"deleted_at": null})] {
+            assert!(!present.matches(&doc));
+            assert!(absent.matches(&doc));
+        }
+        assert!(present.matches(&json!({"deleted_at": 1})));
+        assert!(!absent.matches(&json!({"deleted_at": 1})));
+    }
+
     #[test]
     fn test_filter_set() {
         let filters = FilterSet::new()
@@ -10,8 +10,11 @@ use uuid::Uuid;
 
 use crate::auth::rls::{RlsContext, RlsEnforcer};
 
+use super::batch::{BatchItemResult, BatchOp, BatchResponse};
 use super::errors::{RestError, RestResult};
+use super::etag::check_if_match;
 use super::filter::{FilterExpr, FilterSet};
+use super::pagination::{paginate, sort_records};
 use super::parser::QueryParams;
 use super::response::{
     DeleteResponse, InsertResponse, ListResponse, SingleResponse, UpdateResponse,
@@ -49,11 +52,29 @@ pub trait RestHandler: Send + Sync {
         collection: &str,
         id: &str,
         data: Value,
+        if_match: Option<&str>,
         ctx: &RlsContext,
     ) -> RestResult<UpdateResponse<Value>>;
 
     /// Delete a record
-    fn delete(&self, collection: &str, id: &str, ctx: &RlsContext) -> RestResult<DeleteResponse>;
+    ///
+    /// With `if_match`, updates and deletes fail with
+    /// `RestError::PreconditionFailed` unless the record's ETag matches.
+    fn delete(
+        &self,
+        collection: &str,
+        id: &str,
+        if_match: Option<&str>,
+        ctx: &RlsContext,
+    ) -> RestResult<DeleteResponse>;
+
+    /// Apply a batch of operations all-or-nothing, in one commit
+    fn batch(
+        &self,
+        collection: &str,
+        ops: Vec<BatchOp>,
+        ctx: &RlsContext,
+    ) -> RestResult<BatchResponse>;
 }
 
 /// In-memory REST handler for testing
@@ -114,43 +135,82 @@ impl<E: RlsEnforcer> InMemoryRestHandler<E> {
             .collect()
     }
 
-    /// Apply ordering
-    fn apply_ordering(records: &mut [Value], params: &QueryParams) {
-        if params.order.is_empty() {
-            return;
+    /// Insert `data` into `records`
+    fn insert_into(
+        &self,
+        records: &mut Vec<Value>,
+        collection: &str,
+        mut data: Value,
+        ctx: &RlsContext,
+    ) -> RestResult<Value> {
+        // Prepare insert (add owner field)
+        self.rls.prepare_insert(collection, &mut data, ctx)?;
+
+        // Add ID if not present
+        if data.get("id").is_none() {
+            if let Some(obj) = data.as_object_mut() {
+                obj.insert("id".to_string(), Value::String(Uuid::new_v4().to_string()));
+            }
         }
 
-        records.sort_by(|a, b| {
-            for order in &params.order {
-                let a_val = a.get(&order.field);
-                let b_val = b.get(&order.field);
+        // Validate write
+        self.rls.validate_write(collection, &data, ctx)?;
 
-                let cmp = match (a_val, b_val) {
-                    (Some(Value::Number(a)), Some(Value::Number(b))) => {
-                        let a = a.as_f64().unwrap_or(0.0);
-                        let b = b.as_f64().unwrap_or(0.0);
-                        a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
-                    }
-                    (Some(Value::String(a)), Some(Value::String(b))) => a.cmp(b),
-                    _ => std::cmp::Ordering::Equal,
-                };
+        records.push(data.clone());
+        Ok(data)
+    }
 
-                let cmp = if order.ascending { cmp } else { cmp.reverse() };
-                if cmp != std::cmp::Ordering::Equal {
-                    return cmp;
-                }
+    /// Merge `updates` into the record of `records` with `id`
+    fn update_in(
+        &self,
+        records: &mut [Value],
+        collection: &str,
+        id: &str,
+        updates: Value,
+        if_match: Option<&str>,
+        ctx: &RlsContext,
+    ) -> RestResult<Value> {
+        let record = records
+            .iter_mut()
+            .find(|r| r.get("id").and_then(|v| v.as_str()) == Some(id))
+            .ok_or(RestError::NotFound)?;
+
+        // Validate RLS
+        self.rls.validate_write(collection, record, ctx)?;
+        check_if_match(id, record, if_match)?;
+
+        // Apply updates
+        if let (Some(record_obj), Some(updates_obj)) = (record.as_object_mut(), updates.as_object())
+        {
+            for (key, value) in updates_obj {
+                record_obj.insert(key.clone(), value.clone());
             }
-            std::cmp::Ordering::Equal
-        });
+        }
+
+        Ok(record.clone())
     }
 
-    /// Apply pagination
-    fn apply_pagination(records: Vec<Value>, params: &QueryParams) -> Vec<Value> {
-        records
-            .into_iter()
-            .skip(params.offset)
-            .take(params.limit)
-            .collect()
+    /// Remove the record with `id` from `records`
+    fn delete_from(
+        &self,
+        records: &mut Vec<Value>,
+        collection: &str,
+        id: &str,
+        if_match: Option<&str>,
+        ctx: &RlsContext,
+    ) -> RestResult<()> {
+        // Find record and validate RLS
+        let idx = records
+            .iter()
+            .position(|r| r.get("id").and_then(|v| v.as_str()) == Some(id))
+            .ok_or(RestError::NotFound)?;
+
+        let record = &records[idx];
+        self.rls.validate_write(collection, record, ctx)?;
+        check_if_match(id, record, if_match)?;
+
+        records.remove(idx);
+        Ok(())
     }
 
     /// Select fields from records
@@ -198,15 +258,15 @@ impl<E: RlsEnforcer> RestHandler for InMemoryRestHandler<E> {
 
         // Apply ordering
         let mut records = records;
-        Self::apply_ordering(&mut records, &params);
+        sort_records(&mut records, &params.sort_order());
 
         // Apply pagination
-        let records = Self::apply_pagination(records, &params);
+        let (records, next_cursor) = paginate(records, &params);
 
         // Select fields
         let records = Self::select_fields(records, &params);
 
-        Ok(ListResponse::new(records, params.limit, params.offset))
+        Ok(ListResponse::new(records, params.limit, params.offset).with_next_cursor(next_cursor))
     }
 
     fn get(
@@ -237,31 +297,16 @@ impl<E: RlsEnforcer> RestHandler for InMemoryRestHandler<E> {
     fn insert(
         &self,
         collection: &str,
-        mut data: Value,
+        data: Value,
         ctx: &RlsContext,
     ) -> RestResult<InsertResponse<Value>> {
-        // Prepare insert (add owner field)
-        self.rls.prepare_insert(collection, &mut data, ctx)?;
-
-        // Add ID if not present
-        if data.get("id").is_none() {
-            if let Some(obj) = data.as_object_mut() {
-                obj.insert("id".to_string(), Value::String(Uuid::new_v4().to_string()));
-            }
-        }
-
-        // Validate write
-        self.rls.validate_write(collection, &data, ctx)?;
-
         let mut store = self
             .data
             .write()
             .map_err(|_| RestError::Internal("Lock poisoned".to_string()))?;
 
-        store
-            .entry(collection.to_string())
-            .or_default()
-            .push(data.clone());
+        let records = store.entry(collection.to_string()).or_default();
+        let data = self.insert_into(records, collection, data, ctx)?;
 
         Ok(InsertResponse::single(data))
     }
@@ -271,6 +316,7 @@ impl<E: RlsEnforcer> RestHandler for InMemoryRestHandler<E> {
         collection: &str,
         id: &str,
         updates: Value,
+        if_match: Option<&str>,
         ctx: &RlsContext,
     ) -> RestResult<UpdateResponse<Value>> {
         let mut store = self
@@ -281,27 +327,18 @@ impl<E: RlsEnforcer> RestHandler for InMemoryRestHandler<E> {
         let records = store
             .get_mut(collection)
             .ok_or(RestError::CollectionNotFound(collection.to_string()))?;
+        let record = self.update_in(records, collection, id, updates, if_match, ctx)?;
 
-        let record = records
-            .iter_mut()
-            .find(|r| r.get("id").and_then(|v| v.as_str()) == Some(id))
-            .ok_or(RestError::NotFound)?;
-
-        // Validate RLS
-        self.rls.validate_write(collection, record, ctx)?;
-
-        // Apply updates
-        if let (Some(record_obj), Some(updates_obj)) = (record.as_object_mut(), updates.as_object())
-        {
-            for (key, value) in updates_obj {
-                record_obj.insert(key.clone(), value.clone());
-            }
-        }
-
-        Ok(UpdateResponse::new(record.clone()))
+        Ok(UpdateResponse::new(record))
     }
 
-    fn delete(&self, collection: &str, id: &str, ctx: &RlsContext) -> RestResult<DeleteResponse> {
+    fn delete(
+        &self,
+        collection: &str,
+        id: &str,
+        if_match: Option<&str>,
+        ctx: &RlsContext,
+    ) -> RestResult<DeleteResponse> {
         let mut store = self
             .data
             .write()
@@ -310,19 +347,51 @@ impl<E: RlsEnforcer> RestHandler for InMemoryRestHandler<E> {
         let records = store
             .get_mut(collection)
             .ok_or(RestError::CollectionNotFound(collection.to_string()))?;
+        self.delete_from(records, collection, id, if_match, ctx)?;
 
-        // Find record and validate RLS
-        let idx = records
-            .iter()
-            .position(|r| r.get("id").and_then(|v| v.as_str()) == Some(id))
-            .ok_or(RestError::NotFound)?;
+        Ok(DeleteResponse::success())
+    }
 
-        let record = &records[idx];
-        self.rls.validate_write(collection, record, ctx)?;
+    fn batch(
+        &self,
+        collection: &str,
+        ops: Vec<BatchOp>,
+        ctx: &RlsContext,
+    ) -> RestResult<BatchResponse> {
+        let mut store = self
+            .data
+            .write()
+            .map_err(|_| RestError::Internal("Lock poisoned".to_string()))?;
 
-        records.remove(idx);
+        // Stage every op on a copy; the store is replaced only if all succeed
+        let mut staged = store.get(collection).cloned();
+        let not_found = || RestError::CollectionNotFound(collection.to_string());
+        let outcomes = ops
+            .into_iter()
+            .map(|op| match op {
+                BatchOp::Insert { data } => {
+                    let records = staged.get_or_insert_with(Vec::new);
+                    self.insert_into(records, collection, data, ctx)
+                        .map(BatchItemResult::created)
+                }
+                BatchOp::Update { id, data, if_match } => {
+                    let records = staged.as_mut().ok_or_else(not_found)?;
+                    self.update_in(records, collection, &id, data, if_match.as_deref(), ctx)
+                        .map(BatchItemResult::updated)
+                }
+                BatchOp::Delete { id, if_match } => {
+                    let records = staged.as_mut().ok_or_else(not_found)?;
+                    self.delete_from(records, collection, &id, if_match.as_deref(), ctx)
+                        .map(|()| BatchItemResult::deleted())
+                }
+            })
+            .collect();
 
-        Ok(DeleteResponse::success())
+        let response = BatchResponse::new(outcomes);
+        if let (true, Some(records)) = (response.committed, staged) {
+            store.insert(collection.to_string(), records);
+        }
+        Ok(response)
     }
 }
 
@@ -419,7 +488,7 @@ mod tests {
 
         // Update
         let updates = serde_json::json!({"title": "Updated"});
-        let result = handler.update("posts", id, updates, &ctx).unwrap();
+        let result = handler.update("posts", id, updates, None, &ctx).unwrap();
         assert_eq!(result.data["title"], "Updated");
     }
 
@@ -435,7 +504,7 @@ mod tests {
         let id = insert_result.data[0]["id"].as_str().unwrap();
 
         // Delete
-        let result = handler.delete("posts", id, &ctx).unwrap();
+        let result = handler.delete("posts", id, None, &ctx).unwrap();
         assert!(result.deleted);
 
         // Verify deleted
@@ -3,22 +3,29 @@
 //! Provides HTTP endpoints for CRUD operations on all collections,
 //! with RLS enforcement through the core pipeline.
 
+pub mod batch;
 pub mod database;
 pub mod errors;
+pub mod etag;
 pub mod filter;
 pub mod generator;
 pub mod handler;
+pub mod pagination;
 pub mod parser;
 pub mod pipeline_handler;
 pub mod response;
+pub mod routing;
 pub mod server;
 pub mod unified_api;
 
+pub use batch::{BatchItemResult, BatchOp, BatchResponse};
 pub use database::DatabaseFacade;
-pub use errors::{RestError, RestResult};
+pub use errors::{ProblemDetails, RestError, RestResult};
 pub use filter::{FilterExpr, FilterOperator};
 pub use handler::RestHandler;
+pub use pagination::Cursor;
 pub use parser::QueryParams;
 pub use pipeline_handler::PipelineRestHandler;
+pub use routing::{ReadRoute, ReadRouter};
 pub use server::RestServer;
 pub use unified_api::{OperationRequest, OperationResponse, UnifiedApiServer};
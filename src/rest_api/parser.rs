@@ -6,6 +6,7 @@ use std::collections::HashMap;
 
 use super::errors::{RestError, RestResult};
 use super::filter::{FilterExpr, FilterOperator};
+use super::pagination::Cursor;
 
 /// Maximum number of records that can be returned
 pub const MAX_LIMIT: usize = 1000;
@@ -30,6 +31,9 @@ pub struct QueryParams {
 
     /// Number of records to skip
     pub offset: usize,
+
+    /// Resume after the row a previous page ended on
+    pub cursor: Option<Cursor>,
 }
 
 impl Default for QueryParams {
@@ -40,12 +44,13 @@ impl Default for QueryParams {
             order: Vec::new(),
             limit: DEFAULT_LIMIT,
             offset: 0,
+            cursor: None,
         }
     }
 }
 
 /// Order by clause
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct OrderBy {
     pub field: String,
     pub ascending: bool,
@@ -54,13 +59,27 @@ pub struct OrderBy {
 impl QueryParams {
     /// Parse query parameters from a HashMap
     pub fn parse(params: &HashMap<String, String>) -> RestResult<Self> {
+        Self::from_pairs(params)
+    }
+
+    /// Parse query parameters in request order
+    ///
+    /// Unlike a map, pairs may repeat a field to apply several filters to
+    /// it, e.g. `age=gte.18&age=lt.65`.
+    pub fn from_pairs<I, K, V>(params: I) -> RestResult<Self>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
         let mut result = QueryParams {
             limit: DEFAULT_LIMIT,
             ..Default::default()
         };
 
         for (key, value) in params {
-            match key.as_str() {
+            let (key, value) = (key.as_ref(), value.as_ref());
+            match key {
                 "select" => {
                     result.select = Some(parse_select(value)?);
                 }
@@ -73,6 +92,9 @@ impl QueryParams {
                 "offset" => {
                     result.offset = parse_offset(value)?;
                 }
+                "cursor" => {
+                    result.cursor = Some(Cursor::decode(value)?);
+                }
                 _ => {
                     // Treat as filter
                     if let Some(filter) = parse_filter(key, value)? {
@@ -87,9 +109,35 @@ impl QueryParams {
             return Err(RestError::LimitExceeded(result.limit, MAX_LIMIT));
         }
 
+        if let Some(cursor) = &result.cursor {
+            if result.offset > 0 {
+                return Err(RestError::InvalidQueryParam(
+                    "cursor cannot be combined with offset".to_string(),
+                ));
+            }
+            if !cursor.matches_order(&result.sort_order()) {
+                return Err(RestError::InvalidQueryParam(
+                    "cursor was issued for a different order".to_string(),
+                ));
+            }
+        }
+
         Ok(result)
     }
 
+    /// Order clauses with `id` appended as a tiebreaker, so that every
+    /// list query, and every cursor over it, has one total order
+    pub fn sort_order(&self) -> Vec<OrderBy> {
+        let mut order = self.order.clone();
+        if !order.iter().any(|o| o.field == "id") {
+            order.push(OrderBy {
+                field: "id".to_string(),
+                ascending: true,
+            });
+        }
+        order
+    }
+
     /// Check if this query is bounded
     pub fn is_bounded(&self) -> bool {
         self.limit > 0 && self.limit <= MAX_LIMIT
@@ -177,7 +225,7 @@ fn parse_filter(field: &str, value: &str) -> RestResult<Option<FilterExpr>> {
 
         let op = match op_str {
             "eq" => FilterOperator::Eq,
-            "neq" => FilterOperator::Neq,
+            "neq" | "ne" => FilterOperator::Neq,
             "gt" => FilterOperator::Gt,
             "gte" => FilterOperator::Gte,
             "lt" => FilterOperator::Lt,
@@ -185,6 +233,8 @@ fn parse_filter(field: &str, value: &str) -> RestResult<Option<FilterExpr>> {
             "like" => FilterOperator::Like,
             "in" => FilterOperator::In,
             "is" => FilterOperator::Is,
+            "prefix" => FilterOperator::Prefix,
+            "exists" => FilterOperator::Exists,
             _ => {
                 // No known operator, treat as eq with the whole value
                 return Ok(Some(FilterExpr {
@@ -201,13 +251,51 @@ fn parse_filter(field: &str, value: &str) -> RestResult<Option<FilterExpr>> {
         (FilterOperator::Eq, value)
     };
 
+    let value = match operator {
+        FilterOperator::In => parse_filter_list(field, actual_value)?,
+        // A prefix is always matched as a string, even if it looks numeric
+        FilterOperator::Prefix => serde_json::Value::String(actual_value.to_string()),
+        FilterOperator::Exists => match actual_value {
+            "true" => serde_json::Value::Bool(true),
+            "false" => serde_json::Value::Bool(false),
+            _ => {
+                return Err(RestError::InvalidFilter(format!(
+                    "{}: exists takes true or false, got {}",
+                    field, actual_value
+                )))
+            }
+        },
+        _ => parse_filter_value(actual_value)?,
+    };
+
     Ok(Some(FilterExpr {
         field: field.to_string(),
         operator,
-        value: parse_filter_value(actual_value)?,
+        value,
     }))
 }
 
+/// Parse the `(a,b,c)` list of an `in` filter, typing each item like an
+/// `eq` value
+fn parse_filter_list(field: &str, value: &str) -> RestResult<serde_json::Value> {
+    let inner = value
+        .strip_prefix('(')
+        .and_then(|v| v.strip_suffix(')'))
+        .ok_or_else(|| {
+            RestError::InvalidFilter(format!(
+                "{}: in takes a list like (a,b), got {}",
+                field, value
+            ))
+        })?;
+    let items = inner
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(parse_filter_value)
+        .collect::<RestResult<Vec<_>>>()?;
+    Ok(serde_json::Value::Array(items))
+}
+
 /// Parse a filter value (handles lists for 'in' operator)
 fn parse_filter_value(value: &str) -> RestResult<serde_json::Value> {
     // Check for list syntax: (a,b,c)
@@ -300,6 +388,68 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_planner_operators() {
+        let filter = parse_filter("age", "in.(18,21)").unwrap().unwrap();
+        assert_eq!(filter.value, serde_json::json!([18, 21]));
+        assert!(matches!(
+            parse_filter("age", "in.18"),
+            Err(RestError::InvalidFilter(_))
+        ));
+
+        let filter = parse_filter("zip", "prefix.90").unwrap().unwrap();
+        assert_eq!(filter.operator, FilterOperator::Prefix);
+        assert_eq!(filter.value, serde_json::json!("90"));
+
+        let filter = parse_filter("deleted_at", "exists.false").unwrap().unwrap();
+        assert_eq!(filter.operator, FilterOperator::Exists);
+        assert!(parse_filter("deleted_at", "exists.maybe").is_err());
+
+        assert_eq!(
+            parse_filter("age", "ne.3").unwrap().unwrap().operator,
+            FilterOperator::Neq
+        );
+    }
+
+    #[test]
+    fn test_range_from_repeated_field() {
+        let query = QueryParams::from_pairs([("age", "gte.18"), ("age", "lt.65")]).unwrap();
+        let operators: Vec<_> = query.filters.iter().map(|f| f.operator).collect();
+        assert_eq!(operators, vec![FilterOperator::Gte, FilterOperator::Lt]);
+    }
+
+    #[test]
+    fn test_cursor_params() {
+        let order = vec![OrderBy {
+            field: "name".to_string(),
+            ascending: true,
+        }];
+        let cursor = Cursor::after_record(
+            &serde_json::json!({"id": "u1", "name": "Ada"}),
+            &QueryParams {
+                order,
+                ..Default::default()
+            }
+            .sort_order(),
+        )
+        .encode();
+
+        let query =
+            QueryParams::from_pairs([("order", "name.asc"), ("cursor", cursor.as_str())]).unwrap();
+        assert!(query.cursor.is_some());
+
+        // A cursor only continues the order it was issued for
+        assert!(
+            QueryParams::from_pairs([("order", "name.desc"), ("cursor", cursor.as_str())]).is_err()
+        );
+        assert!(QueryParams::from_pairs([
+            ("order", "name.asc"),
+            ("cursor", cursor.as_str()),
+            ("offset", "10")
+        ])
+        .is_err());
+    }
+
     #[test]
     fn test_full_query_params() {
         let mut params = HashMap::new();
@@ -12,8 +12,10 @@ use uuid::Uuid;
 use crate::auth::rls::RlsContext;
 use crate::core::{AuthContext, BridgeConfig, PipelineBridge, RequestContext};
 
+use super::batch::{BatchOp, BatchResponse};
 use super::errors::{RestError, RestResult};
 use super::filter::FilterSet;
+use super::pagination::{paginate, sort_records};
 use super::parser::QueryParams;
 use super::response::{
     DeleteResponse, InsertResponse, ListResponse, SingleResponse, UpdateResponse,
@@ -72,36 +74,6 @@ impl PipelineRestHandler {
             .collect()
     }
 
-    /// Apply ordering
-    fn apply_ordering(records: &mut [Value], params: &QueryParams) {
-        if params.order.is_empty() {
-            return;
-        }
-
-        records.sort_by(|a, b| {
-            for order in &params.order {
-                let a_val = a.get(&order.field);
-                let b_val = b.get(&order.field);
-
-                let cmp = match (a_val, b_val) {
-                    (Some(Value::Number(av)), Some(Value::Number(bv))) => {
-                        let a_f = av.as_f64().unwrap_or(0.0);
-                        let b_f = bv.as_f64().unwrap_or(0.0);
-                        a_f.partial_cmp(&b_f).unwrap_or(std::cmp::Ordering::Equal)
-                    }
-                    (Some(Value::String(av)), Some(Value::String(bv))) => av.cmp(bv),
-                    _ => std::cmp::Ordering::Equal,
-                };
-
-                let cmp = if order.ascending { cmp } else { cmp.reverse() };
-                if cmp != std::cmp::Ordering::Equal {
-                    return cmp;
-                }
-            }
-            std::cmp::Ordering::Equal
-        });
-    }
-
     /// Select fields from records
     fn select_fields(records: Vec<Value>, params: &QueryParams) -> Vec<Value> {
         match &params.select {
@@ -158,15 +130,15 @@ impl RestHandler for PipelineRestHandler {
         records = Self::apply_query_filters(&records, &params);
 
         // Apply ordering
-        Self::apply_ordering(&mut records, &params);
+        sort_records(&mut records, &params.sort_order());
 
         // Apply pagination
-        let records: Vec<Value> = records.into_iter().skip(offset).take(limit).collect();
+        let (records, next_cursor) = paginate(records, &params);
 
         // Select fields
         let records = Self::select_fields(records, &params);
 
-        Ok(ListResponse::new(records, limit, offset))
+        Ok(ListResponse::new(records, limit, offset).with_next_cursor(next_cursor))
     }
 
     fn get(
@@ -220,8 +192,10 @@ impl RestHandler for PipelineRestHandler {
         collection: &str,
         id: &str,
         data: Value,
+        if_match: Option<&str>,
         ctx: &RlsContext,
     ) -> RestResult<UpdateResponse<Value>> {
+        reject_precondition(if_match)?;
         let context = Self::to_request_context(ctx);
 
         // Execute update through pipeline
@@ -233,7 +207,14 @@ impl RestHandler for PipelineRestHandler {
         Ok(UpdateResponse::new(result))
     }
 
-    fn delete(&self, collection: &str, id: &str, ctx: &RlsContext) -> RestResult<DeleteResponse> {
+    fn delete(
+        &self,
+        collection: &str,
+        id: &str,
+        if_match: Option<&str>,
+        ctx: &RlsContext,
+    ) -> RestResult<DeleteResponse> {
+        reject_precondition(if_match)?;
         let context = Self::to_request_context(ctx);
 
         // Execute delete through pipeline
@@ -253,6 +234,31 @@ impl RestHandler for PipelineRestHandler {
             Err(RestError::NotFound)
         }
     }
+
+    fn batch(
+        &self,
+        _collection: &str,
+        _ops: Vec<BatchOp>,
+        _ctx: &RlsContext,
+    ) -> RestResult<BatchResponse> {
+        // The pipeline executes one operation per call; running a batch as
+        // separate calls could leave it half applied
+        Err(RestError::Unsupported(
+            "batches need an all-or-nothing backend; the pipeline applies one operation at a time"
+                .to_string(),
+        ))
+    }
+}
+
+/// The pipeline cannot check a precondition and write in one step; a
+/// separate read first would let a concurrent write slip in between
+fn reject_precondition(if_match: Option<&str>) -> RestResult<()> {
+    match if_match {
+        Some(_) => Err(RestError::Unsupported(
+            "If-Match needs a backend that checks and writes atomically".to_string(),
+        )),
+        None => Ok(()),
+    }
 }
 
 #[cfg(test)]
@@ -300,7 +306,7 @@ mod tests {
 
         // Update
         let updates = serde_json::json!({"name": "Robert"});
-        let result = handler.update("users", id, updates, &ctx).unwrap();
+        let result = handler.update("users", id, updates, None, &ctx).unwrap();
         assert_eq!(result.data["name"], "Robert");
     }
 
@@ -315,7 +321,7 @@ mod tests {
         let id = result.data[0].get("id").unwrap().as_str().unwrap();
 
         // Delete
-        let result = handler.delete("users", id, &ctx);
+        let result = handler.delete("users", id, None, &ctx);
         assert!(result.is_ok());
     }
 }
@@ -2,15 +2,18 @@
 //!
 //! Standard response types for REST API.
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// List response with pagination
-#[derive(Debug, Clone, Serialize)]
-pub struct ListResponse<T: Serialize> {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListResponse<T> {
     pub data: Vec<T>,
     pub count: usize,
     pub limit: usize,
     pub offset: usize,
+    /// Cursor of the next page, if there are more rows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
 impl<T: Serialize> ListResponse<T> {
@@ -21,13 +24,19 @@ impl<T: Serialize> ListResponse<T> {
             count,
             limit,
             offset,
+            next_cursor: None,
         }
     }
+
+    pub fn with_next_cursor(mut self, next_cursor: Option<String>) -> Self {
+        self.next_cursor = next_cursor;
+        self
+    }
 }
 
 /// Single record response
-#[derive(Debug, Clone, Serialize)]
-pub struct SingleResponse<T: Serialize> {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SingleResponse<T> {
     pub data: T,
 }
 
@@ -38,8 +47,8 @@ impl<T: Serialize> SingleResponse<T> {
 }
 
 /// Insert response with created records
-#[derive(Debug, Clone, Serialize)]
-pub struct InsertResponse<T: Serialize> {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InsertResponse<T> {
     pub data: Vec<T>,
     pub count: usize,
 }
@@ -59,8 +68,8 @@ impl<T: Serialize> InsertResponse<T> {
 }
 
 /// Update response
-#[derive(Debug, Clone, Serialize)]
-pub struct UpdateResponse<T: Serialize> {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateResponse<T> {
     pub data: T,
 }
 
@@ -71,7 +80,7 @@ impl<T: Serialize> UpdateResponse<T> {
 }
 
 /// Delete response
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeleteResponse {
     pub deleted: bool,
 }
@@ -83,7 +92,7 @@ impl DeleteResponse {
 }
 
 /// Count-only response (for HEAD requests)
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CountResponse {
     pub count: usize,
 }
@@ -107,6 +116,7 @@ mod tests {
         assert_eq!(json["count"], 2);
         assert_eq!(json["limit"], 20);
         assert_eq!(json["offset"], 0);
+        assert!(json.get("next_cursor").is_none());
     }
 
     #[test]
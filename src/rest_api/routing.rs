@@ -0,0 +1,217 @@
+//! # Replica Read Routing
+//!
+//! Decides whether this node may serve a REST request given its
+//! replication role.
+//!
+//! - Primary (or replication disabled): everything is served locally
+//! - Replica: reads are served only when `ReplicaReadAdmission` proves
+//!   them safe at the applied commit boundary (REPLICATION_READ_SEMANTICS.md
+//!   §4); writes are refused with a redirect to the Primary
+//! - Anything else (uninitialized, halted): reads and writes are refused
+//!
+//! Eligible replica reads go through `FastReadManager`, so the fast path
+//! is used whenever its safety preconditions hold.
+
+use std::sync::{Mutex, RwLock};
+
+use crate::mvcc::CommitId;
+use crate::replication::{
+    FastReadConfig, FastReadManager, FastReadStats, ReplicaReadAdmission, ReplicationState,
+};
+
+use super::errors::{RestError, RestResult};
+
+/// Where an admitted read is served from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadRoute {
+    /// This node accepts writes; reads see everything it has committed
+    Primary,
+    /// This node is a replica; reads see commits up to `boundary`
+    Replica {
+        /// Applied commit boundary the read is served at
+        boundary: CommitId,
+        /// Whether the fast path was used
+        fast_path: bool,
+    },
+}
+
+/// Routes REST reads and writes according to the replication role
+#[derive(Debug)]
+pub struct ReadRouter {
+    state: RwLock<ReplicationState>,
+    admission: RwLock<ReplicaReadAdmission>,
+    fast_read: Mutex<FastReadManager>,
+    /// Base URL of the Primary's HTTP API, for redirecting writes
+    primary_url: Option<String>,
+}
+
+impl ReadRouter {
+    /// Router for a node in `state`, with the fast path disabled
+    pub fn new(state: ReplicationState) -> Self {
+        Self {
+            state: RwLock::new(state),
+            admission: RwLock::new(ReplicaReadAdmission::default()),
+            fast_read: Mutex::new(FastReadManager::new(FastReadConfig::disabled())),
+            primary_url: None,
+        }
+    }
+
+    /// Redirect writes to the Primary at `url` (e.g. `http://primary:54321`)
+    pub fn with_primary_url(mut self, url: impl Into<String>) -> Self {
+        self.primary_url = Some(url.into().trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Serve eligible replica reads through the fast path
+    pub fn with_fast_read(mut self, config: FastReadConfig) -> Self {
+        self.fast_read = Mutex::new(FastReadManager::new(config));
+        self
+    }
+
+    /// Current replication state
+    pub fn state(&self) -> ReplicationState {
+        self.state.read().unwrap().clone()
+    }
+
+    /// Replace the replication state (role change or halt)
+    pub fn set_state(&self, state: ReplicationState) {
+        *self.state.write().unwrap() = state;
+    }
+
+    /// Record that WAL has been applied up to `boundary`
+    pub fn update_boundary(&self, boundary: CommitId) {
+        self.admission.write().unwrap().update_boundary(boundary);
+        self.fast_read
+            .lock()
+            .unwrap()
+            .update_durable_commit_id(boundary.value());
+    }
+
+    /// Update the admission state (WAL gap, snapshot, recovery)
+    pub fn update_admission(&self, update: impl FnOnce(&mut ReplicaReadAdmission)) {
+        update(&mut self.admission.write().unwrap());
+    }
+
+    /// Fast path statistics
+    pub fn fast_read_stats(&self) -> FastReadStats {
+        self.fast_read.lock().unwrap().stats().clone()
+    }
+
+    /// Admit a read, or explain why this node cannot serve it
+    ///
+    /// Replica reads use the latest safe boundary (§5.1), so they may lag
+    /// the Primary but never observe uncommitted or missing history.
+    pub fn admit_read(&self) -> RestResult<ReadRoute> {
+        let state = self.state.read().unwrap();
+        if state.can_write() {
+            return Ok(ReadRoute::Primary);
+        }
+
+        let admission = self.admission.read().unwrap();
+        let boundary = admission.safe_read_boundary();
+        admission
+            .check_eligibility(&state, boundary)
+            .to_result()
+            .map_err(|e| RestError::ReplicaUnavailable(e.message, self.primary_url.clone()))?;
+
+        let fast = self
+            .fast_read
+            .lock()
+            .unwrap()
+            .try_fast_read(boundary.value());
+        Ok(ReadRoute::Replica {
+            boundary,
+            fast_path: fast.fast_path_used,
+        })
+    }
+
+    /// Admit a write, redirecting it to the Primary when this node is not one
+    ///
+    /// `path` is the request path and query, appended to the Primary's URL.
+    pub fn admit_write(&self, path: &str) -> RestResult<()> {
+        let state = self.state.read().unwrap();
+        if state.can_write() {
+            return Ok(());
+        }
+        Err(RestError::NotPrimary(
+            state.state_name().to_string(),
+            self.primary_url
+                .as_ref()
+                .map(|url| format!("{}{}", url, path)),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replication::HaltReason;
+    use axum::http::StatusCode;
+    use uuid::Uuid;
+
+    fn replica() -> ReplicationState {
+        ReplicationState::ReplicaActive {
+            replica_id: Uuid::new_v4(),
+        }
+    }
+
+    #[test]
+    fn test_primary_serves_everything() {
+        let router = ReadRouter::new(ReplicationState::PrimaryActive);
+        assert_eq!(router.admit_read().unwrap(), ReadRoute::Primary);
+        assert!(router.admit_write("/rest/v1/users").is_ok());
+
+        let router = ReadRouter::new(ReplicationState::Disabled);
+        assert_eq!(router.admit_read().unwrap(), ReadRoute::Primary);
+        assert!(router.admit_write("/rest/v1/users").is_ok());
+    }
+
+    #[test]
+    fn test_replica_serves_reads_at_applied_boundary() {
+        let router = ReadRouter::new(replica()).with_fast_read(FastReadConfig::enabled());
+        router.update_boundary(CommitId::new(42));
+
+        assert_eq!(
+            router.admit_read().unwrap(),
+            ReadRoute::Replica {
+                boundary: CommitId::new(42),
+                fast_path: true,
+            }
+        );
+        assert_eq!(router.fast_read_stats().fast_path_hits, 1);
+    }
+
+    #[test]
+    fn test_replica_redirects_writes_to_primary() {
+        let router = ReadRouter::new(replica()).with_primary_url("http://primary:54321/");
+
+        let err = router.admit_write("/rest/v1/users?select=id").unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(
+            err.redirect(),
+            Some("http://primary:54321/rest/v1/users?select=id")
+        );
+
+        // Without a known Primary there is nowhere to redirect
+        let err = ReadRouter::new(replica()).admit_write("/rest/v1/users");
+        assert_eq!(
+            err.unwrap_err().status_code(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[test]
+    fn test_ineligible_replica_refuses_reads() {
+        let router = ReadRouter::new(replica()).with_primary_url("http://primary:54321");
+        router.update_admission(|admission| admission.mark_wal_gap());
+
+        let err = router.admit_read().unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(err.to_string().contains("WAL gap"));
+        assert_eq!(err.redirect(), Some("http://primary:54321"));
+
+        router.set_state(router.state().halt(HaltReason::WalGapDetected));
+        assert!(router.admit_read().is_err());
+        assert!(router.admit_write("/rest/v1/users").is_err());
+    }
+}
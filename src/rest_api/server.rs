@@ -2,12 +2,11 @@
 //!
 //! Axum-based HTTP server for REST endpoints.
 
-use std::collections::HashMap;
 use std::sync::Arc;
 
 use axum::{
-    extract::{Path, Query, State},
-    http::{HeaderMap, StatusCode},
+    extract::{OriginalUri, Path, Query, State},
+    http::{header, HeaderMap, HeaderName, StatusCode},
     routing::{delete, get, patch, post},
     Json, Router,
 };
@@ -16,17 +15,21 @@ use serde_json::Value;
 use crate::auth::jwt::{JwtConfig, JwtManager};
 use crate::auth::rls::RlsContext;
 
+use super::batch::{validate_batch, BatchOp, BatchResponse};
 use super::errors::{RestError, RestResult};
+use super::etag::etag;
 use super::handler::RestHandler;
 use super::parser::QueryParams;
 use super::response::{
     DeleteResponse, InsertResponse, ListResponse, SingleResponse, UpdateResponse,
 };
+use super::routing::ReadRouter;
 
 /// REST API server state
 pub struct RestServer<H: RestHandler> {
     handler: Arc<H>,
     jwt_manager: JwtManager,
+    routing: Option<Arc<ReadRouter>>,
 }
 
 impl<H: RestHandler + 'static> RestServer<H> {
@@ -34,19 +37,28 @@ impl<H: RestHandler + 'static> RestServer<H> {
         Self {
             handler: Arc::new(handler),
             jwt_manager: JwtManager::new(jwt_config),
+            routing: None,
         }
     }
 
+    /// Route requests by replication role: replicas serve eligible reads
+    /// and redirect writes to the Primary
+    pub fn with_read_routing(mut self, routing: Arc<ReadRouter>) -> Self {
+        self.routing = Some(routing);
+        self
+    }
+
     /// Build the Axum router
     pub fn router(self) -> Router {
         let state = Arc::new(self);
 
         Router::new()
-            .route("/rest/v1/{collection}", get(list_handler))
-            .route("/rest/v1/{collection}", post(insert_handler))
-            .route("/rest/v1/{collection}/{id}", get(get_handler))
-            .route("/rest/v1/{collection}/{id}", patch(update_handler))
-            .route("/rest/v1/{collection}/{id}", delete(delete_handler))
+            .route("/rest/v1/:collection", get(list_handler))
+            .route("/rest/v1/:collection", post(insert_handler))
+            .route("/rest/v1/:collection/batch", post(batch_handler))
+            .route("/rest/v1/:collection/:id", get(get_handler))
+            .route("/rest/v1/:collection/:id", patch(update_handler))
+            .route("/rest/v1/:collection/:id", delete(delete_handler))
             .with_state(state)
     }
 }
@@ -54,6 +66,25 @@ impl<H: RestHandler + 'static> RestServer<H> {
 /// Shared state type
 type ServerState<H> = Arc<RestServer<H>>;
 
+impl<H: RestHandler> RestServer<H> {
+    fn admit_read(&self) -> RestResult<()> {
+        if let Some(routing) = &self.routing {
+            routing.admit_read()?;
+        }
+        Ok(())
+    }
+
+    fn admit_write(&self, uri: &OriginalUri) -> RestResult<()> {
+        match &self.routing {
+            Some(routing) => {
+                let path = uri.path_and_query().map_or(uri.path(), |p| p.as_str());
+                routing.admit_write(path)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
 /// Extract RLS context from headers
 fn extract_context<H: RestHandler>(
     server: &RestServer<H>,
@@ -83,15 +114,33 @@ fn extract_context<H: RestHandler>(
     Ok(RlsContext::anonymous())
 }
 
+/// `If-Match` header value, if sent
+fn if_match(headers: &HeaderMap) -> RestResult<Option<&str>> {
+    headers
+        .get(header::IF_MATCH)
+        .map(|v| {
+            v.to_str().map_err(|_| {
+                RestError::PreconditionFailed("If-Match is not a valid ETag list".to_string())
+            })
+        })
+        .transpose()
+}
+
+/// `ETag` response header for `record`
+fn etag_header(record: &Value) -> [(HeaderName, String); 1] {
+    [(header::ETAG, etag(record))]
+}
+
 /// List records handler
 async fn list_handler<H: RestHandler + 'static>(
     State(server): State<ServerState<H>>,
     Path(collection): Path<String>,
-    Query(query): Query<HashMap<String, String>>,
+    Query(query): Query<Vec<(String, String)>>,
     headers: HeaderMap,
 ) -> Result<Json<ListResponse<Value>>, RestError> {
+    server.admit_read()?;
     let ctx = extract_context(&server, &headers)?;
-    let params = QueryParams::parse(&query)?;
+    let params = QueryParams::from_pairs(query)?;
 
     let result = server.handler.list(&collection, params, &ctx)?;
     Ok(Json(result))
@@ -102,48 +151,75 @@ async fn get_handler<H: RestHandler + 'static>(
     State(server): State<ServerState<H>>,
     Path((collection, id)): Path<(String, String)>,
     headers: HeaderMap,
-) -> Result<Json<SingleResponse<Value>>, RestError> {
+) -> Result<([(HeaderName, String); 1], Json<SingleResponse<Value>>), RestError> {
+    server.admit_read()?;
     let ctx = extract_context(&server, &headers)?;
 
     let result = server.handler.get(&collection, &id, &ctx)?;
-    Ok(Json(result))
+    Ok((etag_header(&result.data), Json(result)))
 }
 
 /// Insert record handler
 async fn insert_handler<H: RestHandler + 'static>(
     State(server): State<ServerState<H>>,
     Path(collection): Path<String>,
+    uri: OriginalUri,
     headers: HeaderMap,
     Json(body): Json<Value>,
 ) -> Result<(StatusCode, Json<InsertResponse<Value>>), RestError> {
+    server.admit_write(&uri)?;
     let ctx = extract_context(&server, &headers)?;
 
     let result = server.handler.insert(&collection, body, &ctx)?;
     Ok((StatusCode::CREATED, Json(result)))
 }
 
-/// Update record handler
+/// Batch handler: insert/update/delete records all-or-nothing
+async fn batch_handler<H: RestHandler + 'static>(
+    State(server): State<ServerState<H>>,
+    Path(collection): Path<String>,
+    uri: OriginalUri,
+    headers: HeaderMap,
+    Json(ops): Json<Vec<BatchOp>>,
+) -> Result<(StatusCode, Json<BatchResponse>), RestError> {
+    server.admit_write(&uri)?;
+    let ctx = extract_context(&server, &headers)?;
+    validate_batch(&ops)?;
+
+    let result = server.handler.batch(&collection, ops, &ctx)?;
+    Ok((result.status_code(), Json(result)))
+}
+
+/// Update record handler; honors `If-Match`
 async fn update_handler<H: RestHandler + 'static>(
     State(server): State<ServerState<H>>,
     Path((collection, id)): Path<(String, String)>,
+    uri: OriginalUri,
     headers: HeaderMap,
     Json(body): Json<Value>,
-) -> Result<Json<UpdateResponse<Value>>, RestError> {
+) -> Result<([(HeaderName, String); 1], Json<UpdateResponse<Value>>), RestError> {
+    server.admit_write(&uri)?;
     let ctx = extract_context(&server, &headers)?;
 
-    let result = server.handler.update(&collection, &id, body, &ctx)?;
-    Ok(Json(result))
+    let result = server
+        .handler
+        .update(&collection, &id, body, if_match(&headers)?, &ctx)?;
+    Ok((etag_header(&result.data), Json(result)))
 }
 
-/// Delete record handler
+/// Delete record handler; honors `If-Match`
 async fn delete_handler<H: RestHandler + 'static>(
     State(server): State<ServerState<H>>,
     Path((collection, id)): Path<(String, String)>,
+    uri: OriginalUri,
     headers: HeaderMap,
 ) -> Result<Json<DeleteResponse>, RestError> {
+    server.admit_write(&uri)?;
     let ctx = extract_context(&server, &headers)?;
 
-    let result = server.handler.delete(&collection, &id, &ctx)?;
+    let result = server
+        .handler
+        .delete(&collection, &id, if_match(&headers)?, &ctx)?;
     Ok(Json(result))
 }
 
@@ -152,6 +228,9 @@ mod tests {
     use super::super::handler::InMemoryRestHandler;
     use super::*;
     use crate::auth::rls::DefaultRlsEnforcer;
+    use crate::replication::ReplicationState;
+    use axum::response::IntoResponse;
+    use uuid::Uuid;
 
     fn create_test_server() -> RestServer<InMemoryRestHandler<DefaultRlsEnforcer>> {
         let handler = InMemoryRestHandler::new(DefaultRlsEnforcer::new());
@@ -164,4 +243,189 @@ mod tests {
         let _router = server.router();
         // Server creates successfully
     }
+
+    #[tokio::test]
+    async fn test_replica_serves_reads_and_redirects_writes() {
+        let routing = Arc::new(
+            ReadRouter::new(ReplicationState::ReplicaActive {
+                replica_id: Uuid::new_v4(),
+            })
+            .with_primary_url("http://primary:54321"),
+        );
+        let server = Arc::new(create_test_server().with_read_routing(routing));
+        let mut headers = HeaderMap::new();
+        headers.insert("apikey", "service_test".parse().unwrap());
+
+        let listed = list_handler(
+            State(Arc::clone(&server)),
+            Path("users".to_string()),
+            Query(vec![("limit".to_string(), "10".to_string())]),
+            headers.clone(),
+        )
+        .await;
+        assert!(listed.is_ok());
+
+        let uri = OriginalUri("/rest/v1/users".parse().unwrap());
+        let err = insert_handler(
+            State(server),
+            Path("users".to_string()),
+            uri,
+            headers,
+            Json(serde_json::json!({"name": "Ada"})),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(err.redirect(), Some("http://primary:54321/rest/v1/users"));
+
+        let response = err.into_response();
+        assert_eq!(
+            response.headers()["location"],
+            "http://primary:54321/rest/v1/users"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_is_all_or_nothing() {
+        let server = Arc::new(create_test_server());
+        let mut headers = HeaderMap::new();
+        headers.insert("apikey", "service_test".parse().unwrap());
+        let batch = |ops: Value| {
+            batch_handler(
+                State(Arc::clone(&server)),
+                Path("users".to_string()),
+                OriginalUri("/rest/v1/users/batch".parse().unwrap()),
+                headers.clone(),
+                Json(serde_json::from_value(ops).unwrap()),
+            )
+        };
+
+        let (status, Json(response)) = batch(serde_json::json!([
+            {"op": "insert", "data": {"id": "u1", "name": "Ada"}},
+            {"op": "insert", "data": {"id": "u2", "name": "Grace"}},
+            {"op": "update", "id": "u1", "data": {"name": "Ada L."}},
+            {"op": "delete", "id": "u2"},
+        ]))
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert!(response.committed);
+        let statuses: Vec<u16> = response.results.iter().map(|r| r.status).collect();
+        assert_eq!(statuses, vec![201, 201, 200, 200]);
+
+        // A missing record fails its item and leaves the insert unapplied
+        let (status, Json(response)) = batch(serde_json::json!([
+            {"op": "insert", "data": {"id": "u3"}},
+            {"op": "delete", "id": "missing"},
+        ]))
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(!response.committed);
+        assert_eq!(response.results[0].code.as_deref(), Some("batch-aborted"));
+        assert_eq!(response.results[1].code.as_deref(), Some("not-found"));
+
+        let ctx = RlsContext::service_role();
+        let users = server
+            .handler
+            .list("users", QueryParams::default(), &ctx)
+            .unwrap();
+        assert_eq!(users.count, 1);
+        assert_eq!(users.data[0]["name"], "Ada L.");
+
+        let err = batch(serde_json::json!([])).await.unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_if_match_guards_writes() {
+        let server = Arc::new(create_test_server());
+        let mut headers = HeaderMap::new();
+        headers.insert("apikey", "service_test".parse().unwrap());
+        let ctx = RlsContext::service_role();
+        server
+            .handler
+            .insert("users", serde_json::json!({"id": "u1", "n": 1}), &ctx)
+            .unwrap();
+        let path = || Path(("users".to_string(), "u1".to_string()));
+        let uri = || OriginalUri("/rest/v1/users/u1".parse().unwrap());
+
+        let (etag_v1, _) = get_handler(State(Arc::clone(&server)), path(), headers.clone())
+            .await
+            .unwrap();
+        let etag_v1 = etag_v1[0].1.clone();
+
+        let mut current = headers.clone();
+        current.insert(header::IF_MATCH, etag_v1.parse().unwrap());
+        let (etag_v2, _) = update_handler(
+            State(Arc::clone(&server)),
+            path(),
+            uri(),
+            current.clone(),
+            Json(serde_json::json!({"n": 2})),
+        )
+        .await
+        .unwrap();
+        assert_ne!(etag_v2[0].1, etag_v1);
+
+        // v1 is now stale
+        let err = update_handler(
+            State(Arc::clone(&server)),
+            path(),
+            uri(),
+            current.clone(),
+            Json(serde_json::json!({"n": 3})),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::PRECONDITION_FAILED);
+        let err = delete_handler(State(Arc::clone(&server)), path(), uri(), current)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::PRECONDITION_FAILED);
+
+        let record = server.handler.get("users", "u1", &ctx).unwrap();
+        assert_eq!(record.data["n"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_list_ranges_and_cursor_pages() {
+        let server = Arc::new(create_test_server());
+        let mut headers = HeaderMap::new();
+        headers.insert("apikey", "service_test".parse().unwrap());
+        let ctx = RlsContext::service_role();
+        for (id, age) in [("a", 15), ("b", 30), ("c", 45), ("d", 70), ("e", 20)] {
+            server
+                .handler
+                .insert("users", serde_json::json!({"id": id, "age": age}), &ctx)
+                .unwrap();
+        }
+
+        let list = |cursor: Option<String>| {
+            let mut query = vec![
+                ("age".to_string(), "gte.18".to_string()),
+                ("age".to_string(), "lt.65".to_string()),
+                ("order".to_string(), "age.desc".to_string()),
+                ("limit".to_string(), "2".to_string()),
+            ];
+            query.extend(cursor.map(|c| ("cursor".to_string(), c)));
+            list_handler(
+                State(Arc::clone(&server)),
+                Path("users".to_string()),
+                Query(query),
+                headers.clone(),
+            )
+        };
+
+        let first = list(None).await.unwrap().0;
+        assert_eq!(first.data[0]["id"], "c");
+        assert_eq!(first.data[1]["id"], "b");
+        let second = list(first.next_cursor).await.unwrap().0;
+        assert_eq!(second.data.len(), 1);
+        assert_eq!(second.data[0]["id"], "e");
+        assert_eq!(second.next_cursor, None);
+
+        let err = list(Some("garbage".to_string())).await.unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+    }
 }
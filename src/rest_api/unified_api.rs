@@ -8,7 +8,8 @@ use std::sync::Arc;
 
 use axum::{
     extract::State,
-    http::{HeaderMap, StatusCode},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     routing::post,
     Json, Router,
 };
@@ -27,6 +28,9 @@ use crate::functions::registry::FunctionRegistry;
 use crate::realtime::broadcast::BroadcastRegistry;
 use crate::realtime::subscription::SubscriptionRegistry;
 
+use super::errors::RestError;
+use super::routing::ReadRouter;
+
 /// Unified operation request
 #[derive(Debug, Deserialize)]
 pub struct OperationRequest {
@@ -61,6 +65,10 @@ pub struct ErrorInfo {
 
     /// HTTP status code
     pub status: u16,
+
+    /// Where to retry the operation (replication redirects only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary: Option<String>,
 }
 
 impl OperationResponse {
@@ -80,6 +88,7 @@ impl OperationResponse {
                 code: err.code().to_string(),
                 message: err.to_string(),
                 status: err.status_code(),
+                primary: None,
             }),
         }
     }
@@ -92,9 +101,24 @@ impl OperationResponse {
                 code: code.to_string(),
                 message,
                 status,
+                primary: None,
             }),
         }
     }
+
+    /// Replication routing refusal, naming the Primary when known
+    pub fn not_routable(err: &RestError) -> Self {
+        let code = match err {
+            RestError::NotPrimary(_, _) => "NOT_PRIMARY",
+            _ => "REPLICA_UNAVAILABLE",
+        };
+        let mut response =
+            Self::from_error_string(code, err.to_string(), err.status_code().as_u16());
+        if let Some(error) = &mut response.error {
+            error.primary = err.redirect().map(str::to_string);
+        }
+        response
+    }
 }
 
 /// Unified API server state
@@ -106,6 +130,7 @@ pub struct UnifiedApiServer {
     file_service: Arc<FileService<LocalBackend>>,
     subscription_registry: Arc<SubscriptionRegistry>,
     broadcast_registry: Arc<BroadcastRegistry>,
+    routing: Option<Arc<ReadRouter>>,
 }
 
 impl UnifiedApiServer {
@@ -120,9 +145,17 @@ impl UnifiedApiServer {
             file_service: Arc::new(FileService::new(backend)),
             subscription_registry: Arc::new(SubscriptionRegistry::new()),
             broadcast_registry: Arc::new(BroadcastRegistry::new()),
+            routing: None,
         }
     }
 
+    /// Route data operations by replication role: replicas serve eligible
+    /// reads and redirect writes to the Primary
+    pub fn with_read_routing(mut self, routing: Arc<ReadRouter>) -> Self {
+        self.routing = Some(routing);
+        self
+    }
+
     /// Create with default configuration
     pub fn with_defaults() -> Self {
         let bridge = PipelineBridge::new_in_memory(BridgeConfig::default());
@@ -150,7 +183,18 @@ async fn execute_operation(
     State(server): State<ServerState>,
     headers: HeaderMap,
     Json(request): Json<OperationRequest>,
-) -> (StatusCode, Json<OperationResponse>) {
+) -> Response {
+    if let Err(err) = admit(&server, &request.operation) {
+        let status = err.status_code();
+        let body = Json(OperationResponse::not_routable(&err));
+        return match err {
+            RestError::NotPrimary(_, Some(url)) => {
+                (status, [(header::LOCATION, url)], body).into_response()
+            }
+            _ => (status, body).into_response(),
+        };
+    }
+
     // Build request context from headers
     let ctx = match build_context(&server.jwt_manager, &headers) {
         Ok(ctx) => ctx,
@@ -164,23 +208,42 @@ async fn execute_operation(
                         code: "AUTH_FAILED".to_string(),
                         message: e.to_string(),
                         status: 401,
+                        primary: None,
                     }),
                 }),
-            );
+            )
+                .into_response();
         }
     };
 
     // Execute through pipeline
     match execute_via_bridge(&server, request.operation, ctx).await {
-        Ok(data) => (StatusCode::OK, Json(OperationResponse::success(data))),
+        Ok(data) => (StatusCode::OK, Json(OperationResponse::success(data))).into_response(),
         Err(err) => {
             let status = StatusCode::from_u16(err.status_code())
                 .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
-            (status, Json(OperationResponse::error(&err)))
+            (status, Json(OperationResponse::error(&err))).into_response()
         }
     }
 }
 
+/// Check a data operation against the replication role
+///
+/// Writes to a replica are redirected to the Primary, which accepts the
+/// same operation at the same path.
+fn admit(server: &UnifiedApiServer, operation: &Operation) -> Result<(), RestError> {
+    let Some(routing) = &server.routing else {
+        return Ok(());
+    };
+    if operation.is_data_write() {
+        routing.admit_write("/api/v1/operation")
+    } else if operation.is_data_read() {
+        routing.admit_read().map(|_| ())
+    } else {
+        Ok(())
+    }
+}
+
 /// Build request context from HTTP headers
 fn build_context(
     jwt_manager: &JwtManager,
@@ -290,7 +353,14 @@ async fn execute_via_bridge(
             // Create subscription object
             use crate::realtime::subscription::Subscription;
             let connection_id = Uuid::new_v4().to_string();
-            let subscription = Subscription::new(connection_id, sub_op.channel.clone(), rls_ctx);
+            let mut subscription =
+                Subscription::new(connection_id, sub_op.channel.clone(), rls_ctx);
+            // An object filter is a set of field equalities
+            if let Some(Value::Object(equals)) = &sub_op.filter {
+                for (field, value) in equals {
+                    subscription = subscription.with_equality(field.clone(), value.clone());
+                }
+            }
 
             match server.subscription_registry.subscribe(subscription) {
                 Ok(sub_id) => Ok(serde_json::json!({
@@ -402,6 +472,13 @@ async fn execute_via_bridge(
             "url": format!("/storage/buckets/{}/files/{}", file_op.bucket, file_op.path),
             "message": "Use the URL to download file content"
         })),
+
+        // Batches, transactions and catalog changes are API handler
+        // requests, not document operations
+        Operation::Command(command) => Err(CoreError::validation(format!(
+            "Command {} is not supported by the unified API",
+            command.command
+        ))),
     }
 }
 
@@ -459,4 +536,32 @@ mod tests {
         let server = UnifiedApiServer::with_defaults();
         let _router = server.router();
     }
+
+    #[test]
+    fn test_replica_redirects_write_operations() {
+        use crate::replication::ReplicationState;
+
+        let routing = ReadRouter::new(ReplicationState::ReplicaActive {
+            replica_id: Uuid::new_v4(),
+        })
+        .with_primary_url("http://primary:54321");
+        let server = UnifiedApiServer::with_defaults().with_read_routing(Arc::new(routing));
+
+        let read: OperationRequest =
+            serde_json::from_str(r#"{"op": "read", "collection": "users", "id": "123"}"#).unwrap();
+        assert!(admit(&server, &read.operation).is_ok());
+
+        let write: OperationRequest =
+            serde_json::from_str(r#"{"op": "delete", "collection": "users", "id": "123"}"#)
+                .unwrap();
+        let err = admit(&server, &write.operation).unwrap_err();
+        let resp = OperationResponse::not_routable(&err);
+        let error = resp.error.unwrap();
+        assert_eq!(error.code, "NOT_PRIMARY");
+        assert_eq!(error.status, 307);
+        assert_eq!(
+            error.primary.as_deref(),
+            Some("http://primary:54321/api/v1/operation")
+        );
+    }
 }
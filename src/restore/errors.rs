@@ -37,6 +37,8 @@ pub enum RestoreErrorCode {
     AeroRestoreCorruption,
     /// Invalid backup format
     AeroRestoreInvalidBackup,
+    /// Backup signature missing where required, or not verifying
+    AeroRestoreSignatureInvalid,
 }
 
 impl RestoreErrorCode {
@@ -47,6 +49,7 @@ impl RestoreErrorCode {
             RestoreErrorCode::AeroRestoreIo => "AERO_RESTORE_IO",
             RestoreErrorCode::AeroRestoreCorruption => "AERO_RESTORE_CORRUPTION",
             RestoreErrorCode::AeroRestoreInvalidBackup => "AERO_RESTORE_INVALID_BACKUP",
+            RestoreErrorCode::AeroRestoreSignatureInvalid => "AERO_RESTORE_SIGNATURE_INVALID",
         }
     }
 
@@ -123,6 +126,11 @@ impl RestoreError {
         )
     }
 
+    /// Creates a signature error
+    pub fn signature_invalid(message: impl Into<String>) -> Self {
+        Self::new(RestoreErrorCode::AeroRestoreSignatureInvalid, message, None)
+    }
+
     /// Returns the error code
     pub fn code(&self) -> RestoreErrorCode {
         self.code
@@ -191,6 +199,10 @@ mod tests {
             RestoreErrorCode::AeroRestoreInvalidBackup.as_str(),
             "AERO_RESTORE_INVALID_BACKUP"
         );
+        assert_eq!(
+            RestoreErrorCode::AeroRestoreSignatureInvalid.as_str(),
+            "AERO_RESTORE_SIGNATURE_INVALID"
+        );
     }
 
     #[test]
@@ -200,6 +212,7 @@ mod tests {
             RestoreErrorCode::AeroRestoreIo,
             RestoreErrorCode::AeroRestoreCorruption,
             RestoreErrorCode::AeroRestoreInvalidBackup,
+            RestoreErrorCode::AeroRestoreSignatureInvalid,
         ];
 
         for code in codes {
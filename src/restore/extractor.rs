@@ -11,6 +11,8 @@ use std::path::{Path, PathBuf};
 use tar::Archive;
 
 use super::errors::{RestoreError, RestoreResult};
+use crate::backup::{is_encrypted_archive, ArchiveDecryptor};
+use crate::storage::EncryptionKey;
 
 /// Create temp restore directory
 ///
@@ -53,16 +55,47 @@ pub fn create_temp_restore_dir(data_dir: &Path) -> RestoreResult<PathBuf> {
 /// Extract backup.tar to destination directory
 ///
 /// Per RESTORE.md §5: Extract backup.tar into temp directory
-pub fn extract_archive(archive_path: &Path, dest_dir: &Path) -> RestoreResult<()> {
-    let file = File::open(archive_path).map_err(|e| {
+///
+/// Encrypted archives are decrypted under `key` as they are unpacked; one
+/// that fails authentication is reported as corrupt.
+pub fn extract_archive(
+    archive_path: &Path,
+    dest_dir: &Path,
+    key: Option<&EncryptionKey>,
+) -> RestoreResult<()> {
+    let open_failed = |e| {
         RestoreError::io_error(
             format!("Failed to open backup archive: {}", archive_path.display()),
             e,
         )
-    })?;
+    };
+    let encrypted = is_encrypted_archive(archive_path).map_err(open_failed)?;
+    let file = File::open(archive_path).map_err(open_failed)?;
+    if encrypted {
+        let key = key.ok_or_else(|| {
+            RestoreError::invalid_backup(format!(
+                "Backup archive is encrypted and no decryption key was given: {}",
+                archive_path.display()
+            ))
+        })?;
+        let decryptor = ArchiveDecryptor::new(file, key).map_err(|e| {
+            RestoreError::corruption(format!(
+                "Failed to decrypt backup archive {}: {}",
+                archive_path.display(),
+                e
+            ))
+        })?;
+        return unpack(Archive::new(decryptor), archive_path, dest_dir);
+    }
 
-    let mut archive = Archive::new(file);
+    unpack(Archive::new(file), archive_path, dest_dir)
+}
 
+fn unpack(
+    mut archive: Archive<impl std::io::Read>,
+    archive_path: &Path,
+    dest_dir: &Path,
+) -> RestoreResult<()> {
     archive.unpack(dest_dir).map_err(|e| {
         RestoreError::invalid_backup_with_source(
             format!(
@@ -197,7 +230,7 @@ mod tests {
         let dest_dir = temp_dir.path().join("extracted");
         fs::create_dir_all(&dest_dir).unwrap();
 
-        let result = extract_archive(&archive_path, &dest_dir);
+        let result = extract_archive(&archive_path, &dest_dir, None);
         assert!(result.is_ok());
 
         // Verify extraction
@@ -214,7 +247,7 @@ mod tests {
         let dest_dir = temp_dir.path().join("extracted");
         fs::create_dir_all(&dest_dir).unwrap();
 
-        let result = extract_archive(&archive_path, &dest_dir);
+        let result = extract_archive(&archive_path, &dest_dir, None);
         assert!(result.is_err());
     }
 
@@ -32,6 +32,13 @@
 //! Restore does NOT replay WAL.
 //! Restore does NOT rebuild indexes.
 //! Restore prepares data for next `aerodb start`.
+//!
+//! # Protected Backups
+//!
+//! A `RestorePolicy` carries the backup decryption key and the trusted
+//! signing key, recorded out-of-band. The signature is verified before the
+//! archive is extracted, and a policy requiring signatures refuses
+//! unsigned archives.
 
 mod errors;
 mod extractor;
@@ -39,19 +46,54 @@ mod restorer;
 mod validator;
 
 pub use errors::{RestoreError, RestoreErrorCode, RestoreResult, Severity};
+pub use validator::{validate_snapshot_dir, validate_wal, verify_snapshot_checksums};
 
 use std::path::Path;
 
+use crate::backup::{BackupManifest, BackupVerifyKey};
+use crate::storage::EncryptionKey;
+
 use extractor::{
     cleanup_old_dir, cleanup_temp_dir, create_temp_restore_dir, extract_archive,
     get_old_data_dir_path,
 };
 use restorer::{atomic_replace, fsync_recursive, reorganize_extracted_files};
 use validator::{
-    validate_backup_manifest, validate_backup_structure, validate_preconditions, validate_snapshot,
-    validate_wal,
+    validate_backup_manifest, validate_backup_structure, validate_preconditions,
+    validate_signature, validate_snapshot,
 };
 
+/// Keys and signature requirements a restore runs under
+#[derive(Debug, Clone, Default)]
+pub struct RestorePolicy {
+    /// Key encrypted archives are decrypted with
+    pub decryption_key: Option<EncryptionKey>,
+    /// Trusted public key archive signatures must verify against
+    pub verify_key: Option<BackupVerifyKey>,
+    /// Refuse archives without a valid signature
+    pub require_signature: bool,
+}
+
+impl RestorePolicy {
+    /// Decrypt encrypted archives with `key`
+    pub fn with_decryption_key(mut self, key: EncryptionKey) -> Self {
+        self.decryption_key = Some(key);
+        self
+    }
+
+    /// Verify signatures against `key`
+    pub fn with_verify_key(mut self, key: BackupVerifyKey) -> Self {
+        self.verify_key = Some(key);
+        self
+    }
+
+    /// Refuse unsigned archives
+    pub fn requiring_signature(mut self) -> Self {
+        self.require_signature = true;
+        self
+    }
+}
+
 /// Restore manager for restoring from backup archives.
 ///
 /// This struct provides the public API for restore operations.
@@ -112,14 +154,33 @@ impl RestoreManager {
     /// - Spawn threads
     /// - Perform async IO
     pub fn restore_from_backup(data_dir: &Path, backup_path: &Path) -> Result<(), RestoreError> {
+        Self::restore_with_policy(data_dir, backup_path, &RestorePolicy::default())
+    }
+
+    /// Restore from a backup archive under `policy`.
+    ///
+    /// Identical to `restore_from_backup`, except that the archive's
+    /// signature is checked against `policy` before extraction and an
+    /// encrypted archive is decrypted with its key.
+    ///
+    /// # Errors
+    ///
+    /// As `restore_from_backup`, and `AERO_RESTORE_SIGNATURE_INVALID` if the
+    /// signature does not verify or is missing where required.
+    pub fn restore_with_policy(
+        data_dir: &Path,
+        backup_path: &Path,
+        policy: &RestorePolicy,
+    ) -> Result<(), RestoreError> {
         // Step 1: Validate preconditions
         validate_preconditions(data_dir, backup_path)?;
+        validate_signature(backup_path, policy)?;
 
         // Step 2: Create temp directory
         let temp_dir = create_temp_restore_dir(data_dir)?;
 
         // All remaining operations must clean up temp_dir on failure
-        let result = Self::restore_inner(data_dir, backup_path, &temp_dir);
+        let result = Self::restore_inner(data_dir, backup_path, &temp_dir, policy);
 
         if result.is_err() {
             // Clean up temp directory
@@ -138,13 +199,62 @@ impl RestoreManager {
         result
     }
 
+    /// Verify a backup archive without restoring it.
+    ///
+    /// Runs the validation steps of RESTORE.md §5 (extract, structure,
+    /// manifest, snapshot checksums, WAL) in a scratch directory next to
+    /// the archive, which is removed afterwards. No data directory is
+    /// touched, so AeroDB may be running.
+    ///
+    /// # Returns
+    ///
+    /// The backup manifest of a valid archive.
+    ///
+    /// # Errors
+    ///
+    /// Returns the `RestoreError` a restore from this archive would fail with.
+    pub fn verify_backup(backup_path: &Path) -> Result<BackupManifest, RestoreError> {
+        Self::verify_backup_with_policy(backup_path, &RestorePolicy::default())
+    }
+
+    /// Verify a backup archive under `policy` without restoring it.
+    ///
+    /// As `verify_backup`, with the signature checked and the archive
+    /// decrypted as `restore_with_policy` would.
+    pub fn verify_backup_with_policy(
+        backup_path: &Path,
+        policy: &RestorePolicy,
+    ) -> Result<BackupManifest, RestoreError> {
+        if !backup_path.is_file() {
+            return Err(RestoreError::failed(format!(
+                "Backup file does not exist: {}",
+                backup_path.display()
+            )));
+        }
+        validate_signature(backup_path, policy)?;
+
+        let scratch_dir = create_temp_restore_dir(backup_path)?;
+        let result = (|| {
+            extract_archive(backup_path, &scratch_dir, policy.decryption_key.as_ref())?;
+            validate_backup_structure(&scratch_dir)?;
+            let manifest = validate_backup_manifest(&scratch_dir)?;
+            validate_snapshot(&scratch_dir)?;
+            validate_wal(&scratch_dir)?;
+            Ok(manifest)
+        })();
+        cleanup_temp_dir(&scratch_dir);
+
+        result
+    }
+
     fn restore_inner(
         data_dir: &Path,
         backup_path: &Path,
         temp_dir: &Path,
+        policy: &RestorePolicy,
     ) -> Result<(), RestoreError> {
         // Step 3: Extract backup.tar
-        extract_archive(backup_path, temp_dir)?;
+        extract_archive(backup_path, temp_dir, policy.decryption_key.as_ref())?;
 
         // Step 4: Validate backup structure
         validate_backup_structure(temp_dir)?;
@@ -279,6 +389,24 @@ mod tests {
         assert!(data_dir.join("data").join("storage.dat").exists());
     }
 
+    #[test]
+    fn test_verify_backup_leaves_no_trace() {
+        let temp_dir = TempDir::new().unwrap();
+        let backup_path = temp_dir.path().join("backup.tar");
+        create_test_backup_archive(&backup_path);
+
+        let manifest = RestoreManager::verify_backup(&backup_path).unwrap();
+        assert_eq!(manifest.backup_id, "20260204T163000Z");
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+
+        // An empty archive fails exactly as a restore would
+        let builder = Builder::new(File::create(&backup_path).unwrap());
+        builder.into_inner().unwrap();
+        assert!(RestoreManager::verify_backup(&backup_path).is_err());
+        assert!(RestoreManager::verify_backup(&temp_dir.path().join("missing.tar")).is_err());
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
     #[test]
     fn test_restore_backup_not_found() {
         let temp_dir = TempDir::new().unwrap();
@@ -371,4 +499,34 @@ mod tests {
         let current_content = fs::read(data_dir.join("data").join("storage.dat")).unwrap();
         assert_eq!(original_content, current_content);
     }
+
+    #[test]
+    fn test_restore_refuses_unsigned_backup_when_required() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().join("data");
+        create_existing_data_dir(&data_dir);
+        let backup_path = temp_dir.path().join("backup.tar");
+        create_test_backup_archive(&backup_path);
+
+        let verify_key = crate::backup::BackupSigningKey::generate().verify_key();
+        for policy in [
+            RestorePolicy::default().requiring_signature(),
+            RestorePolicy::default()
+                .with_verify_key(verify_key)
+                .requiring_signature(),
+        ] {
+            let err =
+                RestoreManager::restore_with_policy(&data_dir, &backup_path, &policy).unwrap_err();
+            assert_eq!(err.code(), RestoreErrorCode::AeroRestoreSignatureInvalid);
+            assert!(!temp_dir.path().join("data.restore_tmp").exists());
+            assert_eq!(
+                fs::read(data_dir.join("data").join("storage.dat")).unwrap(),
+                b"old data"
+            );
+        }
+
+        // Unsigned archives are accepted when signing is not required
+        let policy = RestorePolicy::default().with_verify_key(verify_key);
+        RestoreManager::restore_with_policy(&data_dir, &backup_path, &policy).unwrap();
+    }
 }
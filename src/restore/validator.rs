@@ -5,16 +5,19 @@
 //! - Validate backup_manifest.json
 //! - Validate snapshot manifest and checksums
 //! - Validate WAL files
+//! - Verify the archive signature before anything is extracted
 //!
 //! Any validation failure aborts restore.
 
 use std::fs::{self, File};
-use std::io::Read;
+use std::io::{self, Read};
 use std::path::Path;
 
-use crate::backup::BackupManifest;
+use crate::backup::{verify_archive_signature, BackupManifest};
+use crate::snapshot::{compute_file_checksum, format_checksum, parse_checksum, SnapshotManifest};
 
 use super::errors::{RestoreError, RestoreResult};
+use super::RestorePolicy;
 
 /// Validate backup structure
 ///
@@ -87,14 +90,22 @@ pub fn validate_backup_manifest(restore_dir: &Path) -> RestoreResult<BackupManif
 /// - checksums correct (if present)
 /// - required files exist
 pub fn validate_snapshot(restore_dir: &Path) -> RestoreResult<()> {
-    let snapshot_dir = restore_dir.join("snapshot");
+    validate_snapshot_dir(&restore_dir.join("snapshot"))
+}
 
+/// Validate the layout of a snapshot directory
+///
+/// - manifest.json exists and is valid JSON
+/// - storage.dat exists
+/// - schemas, if present, is a directory
+pub fn validate_snapshot_dir(snapshot_dir: &Path) -> RestoreResult<()> {
     // Check manifest.json exists
     let manifest_path = snapshot_dir.join("manifest.json");
     if !manifest_path.exists() {
-        return Err(RestoreError::corruption(
-            "Missing snapshot manifest.json in backup",
-        ));
+        return Err(RestoreError::corruption(format!(
+            "Missing snapshot manifest.json in {}",
+            snapshot_dir.display()
+        )));
     }
 
     // Read and validate manifest is valid JSON
@@ -112,22 +123,58 @@ pub fn validate_snapshot(restore_dir: &Path) -> RestoreResult<()> {
     // Check storage.dat exists
     let storage_path = snapshot_dir.join("storage.dat");
     if !storage_path.exists() {
-        return Err(RestoreError::corruption(
-            "Missing storage.dat in backup snapshot",
-        ));
+        return Err(RestoreError::corruption(format!(
+            "Missing storage.dat in {}",
+            snapshot_dir.display()
+        )));
     }
 
     // Check schemas directory exists (optional, may be empty)
     let schemas_dir = snapshot_dir.join("schemas");
     if schemas_dir.exists() && !schemas_dir.is_dir() {
-        return Err(RestoreError::corruption(
-            "schemas in backup is not a directory",
-        ));
+        return Err(RestoreError::corruption(format!(
+            "schemas in {} is not a directory",
+            snapshot_dir.display()
+        )));
     }
 
     Ok(())
 }
 
+/// Verify the checksums a snapshot manifest records
+///
+/// Compares storage.dat and every listed schema file against the manifest.
+pub fn verify_snapshot_checksums(snapshot_dir: &Path) -> RestoreResult<SnapshotManifest> {
+    let manifest = SnapshotManifest::read_from_file(&snapshot_dir.join("manifest.json"))
+        .map_err(|e| RestoreError::corruption(format!("Invalid snapshot manifest: {}", e)))?;
+
+    let verify = |path: &Path, expected: &str| -> RestoreResult<()> {
+        let actual = compute_file_checksum(path)
+            .map_err(|e| RestoreError::corruption(format!("{}: {}", path.display(), e)))?;
+        if parse_checksum(expected) != Some(actual) {
+            return Err(RestoreError::corruption(format!(
+                "Checksum mismatch for {}: manifest {}, file {}",
+                path.display(),
+                expected,
+                format_checksum(actual)
+            )));
+        }
+        Ok(())
+    };
+
+    verify(
+        &snapshot_dir.join("storage.dat"),
+        &manifest.storage_checksum,
+    )?;
+    let mut schemas: Vec<_> = manifest.schema_checksums.iter().collect();
+    schemas.sort();
+    for (filename, checksum) in schemas {
+        verify(&snapshot_dir.join("schemas").join(filename), checksum)?;
+    }
+
+    Ok(manifest)
+}
+
 /// Validate WAL files within the backup
 ///
 /// Per RESTORE.md §5:
@@ -152,6 +199,46 @@ pub fn validate_wal(restore_dir: &Path) -> RestoreResult<()> {
     Ok(())
 }
 
+/// Verify the archive's signature as `policy` demands
+///
+/// With a verify key, a present signature must verify against it. Under
+/// `require_signature`, an unsigned archive is refused, and a policy
+/// without a verify key refuses every archive.
+pub fn validate_signature(backup_path: &Path, policy: &RestorePolicy) -> RestoreResult<()> {
+    let Some(key) = &policy.verify_key else {
+        if policy.require_signature {
+            return Err(RestoreError::signature_invalid(
+                "Restore policy requires signed backups but no verify key is configured",
+            ));
+        }
+        return Ok(());
+    };
+
+    match verify_archive_signature(backup_path, key) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            if policy.require_signature {
+                Err(RestoreError::signature_invalid(format!(
+                    "Backup archive is unsigned: {}",
+                    backup_path.display()
+                )))
+            } else {
+                Ok(())
+            }
+        }
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => Err(RestoreError::signature_invalid(
+            format!("{}: {}", backup_path.display(), e),
+        )),
+        Err(e) => Err(RestoreError::io_error(
+            format!(
+                "Failed to verify backup signature: {}",
+                backup_path.display()
+            ),
+            e,
+        )),
+    }
+}
+
 /// Check if AeroDB is currently running
 ///
 /// Per RESTORE.md §3: AeroDB must not be running
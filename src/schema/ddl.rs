@@ -0,0 +1,216 @@
+//! Schema DDL records
+//!
+//! Runtime schema changes are written to the WAL as `SCHEMA_DDL` records
+//! before they are applied, so they are durable and replayed at boot ahead
+//! of document recovery.
+//!
+//! Like transaction markers, DDL records carry an empty collection and are
+//! never applied to storage. The payload binds the schema id and version;
+//! the body is JSON:
+//! - `{"action": "create", "schema": {...}}`
+//! - `{"action": "deprecate"}`
+//! - `{"action": "create_collection", "collection": "..."}`, bound to the
+//!   collection's schema id with an empty version
+//! - `{"action": "drop_collection" | "truncate_collection", "collection": "..."}`,
+//!   with an empty binding
+//! - `{"action": "create_index", "field": "..."}`, with an empty binding
+//!
+//! Drop and truncate records follow the tombstones of every document they
+//! remove in the same WAL batch, so replaying the WAL removes the
+//! documents before the catalog forgets the collection.
+
+use serde_json::{json, Value};
+
+use crate::wal::WalPayload;
+
+use super::errors::{SchemaError, SchemaResult};
+use super::types::Schema;
+
+/// A schema catalog change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaDdl {
+    /// Register a new schema, or a new version of an existing one
+    Create(Schema),
+    /// Close a version to new writes; its documents stay readable
+    Deprecate {
+        schema_id: String,
+        schema_version: String,
+    },
+    /// Register a collection storing documents of one schema
+    CreateCollection {
+        collection: String,
+        schema_id: String,
+    },
+    /// Unregister a collection whose documents have been tombstoned
+    DropCollection { collection: String },
+    /// Record that every document of a collection was tombstoned
+    TruncateCollection { collection: String },
+    /// Declare a single-field secondary index
+    CreateIndex { field: String },
+}
+
+impl SchemaDdl {
+    /// Schema identifier the change applies to
+    pub fn schema_id(&self) -> &str {
+        match self {
+            SchemaDdl::Create(schema) => &schema.schema_id,
+            SchemaDdl::Deprecate { schema_id, .. } => schema_id,
+            SchemaDdl::CreateCollection { schema_id, .. } => schema_id,
+            SchemaDdl::DropCollection { .. }
+            | SchemaDdl::TruncateCollection { .. }
+            | SchemaDdl::CreateIndex { .. } => "",
+        }
+    }
+
+    /// Schema version the change applies to
+    pub fn schema_version(&self) -> &str {
+        match self {
+            SchemaDdl::Create(schema) => &schema.schema_version,
+            SchemaDdl::Deprecate { schema_version, .. } => schema_version,
+            SchemaDdl::CreateCollection { .. }
+            | SchemaDdl::DropCollection { .. }
+            | SchemaDdl::TruncateCollection { .. }
+            | SchemaDdl::CreateIndex { .. } => "",
+        }
+    }
+
+    /// Encode the change as a WAL payload
+    pub fn to_payload(&self) -> WalPayload {
+        let body = match self {
+            SchemaDdl::Create(schema) => json!({"action": "create", "schema": schema}),
+            SchemaDdl::Deprecate { .. } => json!({"action": "deprecate"}),
+            SchemaDdl::CreateCollection { collection, .. } => {
+                json!({"action": "create_collection", "collection": collection})
+            }
+            SchemaDdl::DropCollection { collection } => {
+                json!({"action": "drop_collection", "collection": collection})
+            }
+            SchemaDdl::TruncateCollection { collection } => {
+                json!({"action": "truncate_collection", "collection": collection})
+            }
+            SchemaDdl::CreateIndex { field } => json!({"action": "create_index", "field": field}),
+        };
+        WalPayload::new(
+            "",
+            "",
+            self.schema_id(),
+            self.schema_version(),
+            serde_json::to_vec(&body).expect("DDL body serializes"),
+        )
+    }
+
+    /// Decode a change from a WAL payload
+    pub fn from_payload(payload: &WalPayload) -> SchemaResult<Self> {
+        let malformed = |reason: String| {
+            SchemaError::malformed_schema(
+                format!("<wal:{}:{}>", payload.schema_id, payload.schema_version),
+                reason,
+            )
+        };
+
+        let body: Value = serde_json::from_slice(&payload.document_body)
+            .map_err(|e| malformed(format!("Invalid DDL body: {}", e)))?;
+
+        let collection = || {
+            body.get("collection")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| malformed("Missing collection".to_string()))
+        };
+
+        match body.get("action").and_then(Value::as_str) {
+            Some("create") => {
+                let schema = body
+                    .get("schema")
+                    .cloned()
+                    .ok_or_else(|| malformed("Missing schema".to_string()))?;
+                let schema: Schema = serde_json::from_value(schema)
+                    .map_err(|e| malformed(format!("Invalid schema: {}", e)))?;
+                if schema.key() != (payload.schema_id.as_str(), payload.schema_version.as_str()) {
+                    return Err(malformed(
+                        "Schema does not match the record's schema binding".to_string(),
+                    ));
+                }
+                Ok(SchemaDdl::Create(schema))
+            }
+            Some("deprecate") => Ok(SchemaDdl::Deprecate {
+                schema_id: payload.schema_id.clone(),
+                schema_version: payload.schema_version.clone(),
+            }),
+            Some("create_collection") => Ok(SchemaDdl::CreateCollection {
+                collection: collection()?,
+                schema_id: payload.schema_id.clone(),
+            }),
+            Some("drop_collection") => Ok(SchemaDdl::DropCollection {
+                collection: collection()?,
+            }),
+            Some("truncate_collection") => Ok(SchemaDdl::TruncateCollection {
+                collection: collection()?,
+            }),
+            Some("create_index") => {
+                let field = body
+                    .get("field")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| malformed("Missing field".to_string()))?;
+                Ok(SchemaDdl::CreateIndex {
+                    field: field.to_string(),
+                })
+            }
+            other => Err(malformed(format!("Unknown DDL action: {:?}", other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::FieldDef;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_ddl_payload_roundtrip() {
+        let mut fields = HashMap::new();
+        fields.insert("_id".to_string(), FieldDef::required_string());
+        let create = SchemaDdl::Create(Schema::new("users", "v1", fields));
+        let deprecate = SchemaDdl::Deprecate {
+            schema_id: "users".to_string(),
+            schema_version: "v1".to_string(),
+        };
+        let collection = SchemaDdl::CreateCollection {
+            collection: "admins".to_string(),
+            schema_id: "users".to_string(),
+        };
+
+        for ddl in [create, deprecate, collection] {
+            let payload = ddl.to_payload();
+            assert!(payload.collection_id.is_empty());
+            assert_eq!(payload.schema_id, "users");
+            assert_eq!(SchemaDdl::from_payload(&payload).unwrap(), ddl);
+        }
+
+        let drop = SchemaDdl::DropCollection {
+            collection: "admins".to_string(),
+        };
+        let truncate = SchemaDdl::TruncateCollection {
+            collection: "admins".to_string(),
+        };
+        let index = SchemaDdl::CreateIndex {
+            field: "age".to_string(),
+        };
+        for ddl in [drop, truncate, index] {
+            let payload = ddl.to_payload();
+            assert!(payload.schema_id.is_empty());
+            assert_eq!(SchemaDdl::from_payload(&payload).unwrap(), ddl);
+        }
+    }
+
+    #[test]
+    fn test_ddl_rejects_mismatched_binding() {
+        let mut fields = HashMap::new();
+        fields.insert("_id".to_string(), FieldDef::required_string());
+        let mut payload = SchemaDdl::Create(Schema::new("users", "v1", fields)).to_payload();
+        payload.schema_version = "v2".to_string();
+
+        assert!(SchemaDdl::from_payload(&payload).is_err());
+    }
+}
@@ -6,9 +6,14 @@
 //! - AERO_UNKNOWN_SCHEMA_VERSION (REJECT)
 //! - AERO_SCHEMA_VALIDATION_FAILED (REJECT)
 //! - AERO_SCHEMA_IMMUTABLE (REJECT)
+//! - AERO_SCHEMA_MIGRATION_INVALID (REJECT)
+//! - AERO_SCHEMA_DEPRECATED (REJECT)
+//! - AERO_UNKNOWN_COLLECTION (REJECT)
 
 use std::fmt;
 
+use serde::Serialize;
+
 /// Severity levels for schema errors
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Severity {
@@ -40,6 +45,12 @@ pub enum SchemaErrorCode {
     AeroSchemaValidationFailed,
     /// Attempt to modify existing schema
     AeroSchemaImmutable,
+    /// Migration between schema versions is not additive or not well-formed
+    AeroSchemaMigrationInvalid,
+    /// Write to a deprecated schema version
+    AeroSchemaDeprecated,
+    /// Collection not registered
+    AeroUnknownCollection,
     /// Schema missing during recovery (FATAL)
     AeroRecoverySchemaMissing,
 }
@@ -53,6 +64,9 @@ impl SchemaErrorCode {
             SchemaErrorCode::AeroUnknownSchemaVersion => "AERO_UNKNOWN_SCHEMA_VERSION",
             SchemaErrorCode::AeroSchemaValidationFailed => "AERO_SCHEMA_VALIDATION_FAILED",
             SchemaErrorCode::AeroSchemaImmutable => "AERO_SCHEMA_IMMUTABLE",
+            SchemaErrorCode::AeroSchemaMigrationInvalid => "AERO_SCHEMA_MIGRATION_INVALID",
+            SchemaErrorCode::AeroSchemaDeprecated => "AERO_SCHEMA_DEPRECATED",
+            SchemaErrorCode::AeroUnknownCollection => "AERO_UNKNOWN_COLLECTION",
             SchemaErrorCode::AeroRecoverySchemaMissing => "AERO_RECOVERY_SCHEMA_MISSING",
         }
     }
@@ -73,6 +87,9 @@ impl SchemaErrorCode {
             SchemaErrorCode::AeroUnknownSchemaVersion => "S3",
             SchemaErrorCode::AeroSchemaValidationFailed => "S2",
             SchemaErrorCode::AeroSchemaImmutable => "S4",
+            SchemaErrorCode::AeroSchemaMigrationInvalid => "S3",
+            SchemaErrorCode::AeroSchemaDeprecated => "S3",
+            SchemaErrorCode::AeroUnknownCollection => "S3",
             SchemaErrorCode::AeroRecoverySchemaMissing => "S3",
         }
     }
@@ -155,6 +172,68 @@ impl fmt::Display for ValidationDetails {
     }
 }
 
+/// One field-level violation, as reported to clients
+///
+/// `constraint` names the rule broken: `required`, `not_null`, `type` or
+/// `declared` (the field is not in the schema).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldViolation {
+    /// Field path (e.g., "address.city", "tags[2]")
+    pub path: String,
+    /// Expected type or condition
+    pub expected: String,
+    /// Rule the field breaks
+    pub constraint: &'static str,
+    /// JSON type found, or "missing"
+    pub actual: String,
+}
+
+impl FieldViolation {
+    fn new(
+        path: impl Into<String>,
+        expected: impl Into<String>,
+        constraint: &'static str,
+        actual: impl Into<String>,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            expected: expected.into(),
+            constraint,
+            actual: actual.into(),
+        }
+    }
+
+    pub fn missing_field(path: impl Into<String>, expected: impl Into<String>) -> Self {
+        Self::new(path, expected, "required", "missing")
+    }
+
+    pub fn undeclared_field(path: impl Into<String>, actual: impl Into<String>) -> Self {
+        Self::new(path, "no undeclared fields", "declared", actual)
+    }
+
+    pub fn null_value(path: impl Into<String>, expected: impl Into<String>) -> Self {
+        Self::new(path, expected, "not_null", "null")
+    }
+
+    pub fn type_mismatch(
+        path: impl Into<String>,
+        expected: impl Into<String>,
+        actual: impl Into<String>,
+    ) -> Self {
+        Self::new(path, expected, "type", actual)
+    }
+}
+
+impl fmt::Display for FieldViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "field '{}': expected {}, got {} ({})",
+            self.path, self.expected, self.actual, self.constraint
+        )
+    }
+}
+
 /// Schema error type with full context
 #[derive(Debug)]
 pub struct SchemaError {
@@ -166,8 +245,8 @@ pub struct SchemaError {
     schema_id: Option<String>,
     /// Schema version if applicable
     schema_version: Option<String>,
-    /// Validation details if applicable
-    details: Option<ValidationDetails>,
+    /// Validation details if applicable (boxed to keep results small)
+    details: Option<Box<ValidationDetails>>,
 }
 
 impl SchemaError {
@@ -220,7 +299,7 @@ impl SchemaError {
             message: format!("Document validation failed: {}", details),
             schema_id: Some(id),
             schema_version: Some(ver),
-            details: Some(details),
+            details: Some(Box::new(details)),
         }
     }
 
@@ -237,6 +316,76 @@ impl SchemaError {
         }
     }
 
+    /// Create an invalid migration error
+    pub fn invalid_migration(
+        schema_id: impl Into<String>,
+        from_version: impl Into<String>,
+        to_version: impl Into<String>,
+        reason: impl Into<String>,
+    ) -> Self {
+        let id = schema_id.into();
+        let from = from_version.into();
+        Self {
+            code: SchemaErrorCode::AeroSchemaMigrationInvalid,
+            message: format!(
+                "Invalid migration of schema '{}' from '{}' to '{}': {}",
+                id,
+                from,
+                to_version.into(),
+                reason.into()
+            ),
+            schema_id: Some(id),
+            schema_version: Some(from),
+            details: None,
+        }
+    }
+
+    /// Create a deprecated schema version error
+    pub fn schema_deprecated(schema_id: impl Into<String>, version: impl Into<String>) -> Self {
+        let id = schema_id.into();
+        let ver = version.into();
+        Self {
+            code: SchemaErrorCode::AeroSchemaDeprecated,
+            message: format!(
+                "Schema '{}' version '{}' is deprecated and accepts no new writes",
+                id, ver
+            ),
+            schema_id: Some(id),
+            schema_version: Some(ver),
+            details: None,
+        }
+    }
+
+    /// Create an unknown collection error
+    pub fn unknown_collection(collection: impl Into<String>) -> Self {
+        Self {
+            code: SchemaErrorCode::AeroUnknownCollection,
+            message: format!("Unknown collection: {}", collection.into()),
+            schema_id: None,
+            schema_version: None,
+            details: None,
+        }
+    }
+
+    /// Create an error for re-registering a collection under another schema
+    pub fn collection_immutable(
+        collection: impl Into<String>,
+        schema_id: impl Into<String>,
+    ) -> Self {
+        let id = schema_id.into();
+        Self {
+            code: SchemaErrorCode::AeroSchemaImmutable,
+            message: format!(
+                "Collection '{}' is already bound to schema '{}'",
+                collection.into(),
+                id
+            ),
+            schema_id: Some(id),
+            schema_version: None,
+            details: None,
+        }
+    }
+
     /// Create a recovery schema missing error (FATAL)
     pub fn recovery_schema_missing(
         schema_id: impl Into<String>,
@@ -296,7 +445,7 @@ impl SchemaError {
 
     /// Returns validation details if applicable
     pub fn details(&self) -> Option<&ValidationDetails> {
-        self.details.as_ref()
+        self.details.as_deref()
     }
 
     /// Returns whether this is a fatal error
@@ -4,13 +4,23 @@
 //! - Schemas stored at metadata/schemas/schema_<id>_<version>.json
 //! - One file per schema version
 //! - Missing schema files cause startup failure (FATAL)
+//!
+//! Registered collections and the schema each one stores are kept in
+//! metadata/collections.json; secondary index fields declared at runtime
+//! in metadata/indexes.json.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use serde::Serialize;
+use serde_json::Value;
+
+use super::ddl::SchemaDdl;
 use super::errors::{SchemaError, SchemaResult};
-use super::types::Schema;
+use super::migration::{FieldChange, SchemaMigration};
+use super::types::{FieldDef, Schema};
+use super::validator::SchemaValidator;
 
 /// Schema loader that reads schema files from disk and maintains an in-memory registry.
 pub struct SchemaLoader {
@@ -18,6 +28,16 @@ pub struct SchemaLoader {
     schema_dir: PathBuf,
     /// Loaded schemas indexed by (schema_id, schema_version)
     schemas: HashMap<(String, String), Schema>,
+    /// Registered migrations indexed by (schema_id, from_version)
+    migrations: HashMap<(String, String), SchemaMigration>,
+    /// File holding the collection registry
+    collections_path: PathBuf,
+    /// Registered collections: name -> schema id
+    collections: BTreeMap<String, String>,
+    /// File holding the declared index fields
+    indexes_path: PathBuf,
+    /// Secondary index fields declared at runtime
+    indexes: BTreeSet<String>,
 }
 
 impl SchemaLoader {
@@ -25,9 +45,15 @@ impl SchemaLoader {
     ///
     /// Schema files are expected at `<data_dir>/metadata/schemas/`.
     pub fn new(data_dir: &Path) -> Self {
+        let metadata_dir = data_dir.join("metadata");
         Self {
-            schema_dir: data_dir.join("metadata").join("schemas"),
+            schema_dir: metadata_dir.join("schemas"),
             schemas: HashMap::new(),
+            migrations: HashMap::new(),
+            collections_path: metadata_dir.join("collections.json"),
+            collections: BTreeMap::new(),
+            indexes_path: metadata_dir.join("indexes.json"),
+            indexes: BTreeSet::new(),
         }
     }
 
@@ -40,6 +66,13 @@ impl SchemaLoader {
     ///
     /// Per SCHEMA.md, missing or malformed schema files cause FATAL errors.
     pub fn load_all(&mut self) -> SchemaResult<()> {
+        if let Some(collections) = read_catalog_file(&self.collections_path)? {
+            self.collections = collections;
+        }
+        if let Some(indexes) = read_catalog_file(&self.indexes_path)? {
+            self.indexes = indexes;
+        }
+
         // Create directory if it doesn't exist
         if !self.schema_dir.exists() {
             fs::create_dir_all(&self.schema_dir).map_err(|e| {
@@ -154,12 +187,307 @@ impl SchemaLoader {
         self.schemas.len()
     }
 
+    /// Returns the schema id a registered collection stores.
+    pub fn collection_schema(&self, collection: &str) -> Option<&str> {
+        self.collections.get(collection).map(String::as_str)
+    }
+
+    /// Returns registered collections and their schema ids, in name order.
+    pub fn collections(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.collections
+            .iter()
+            .map(|(name, schema_id)| (name.as_str(), schema_id.as_str()))
+    }
+
+    /// Returns the secondary index fields declared at runtime, sorted.
+    pub fn index_fields(&self) -> impl Iterator<Item = &str> {
+        self.indexes.iter().map(String::as_str)
+    }
+
+    /// Registers a migration from one schema version to the next.
+    ///
+    /// Both versions must be registered. The migration must be additive:
+    /// replaying its changes over the source fields must yield exactly the
+    /// target fields with unchanged types, no field may become required
+    /// without a default, and defaults must validate. At most one migration
+    /// leaves each version, and chains may not loop.
+    ///
+    /// # Errors
+    ///
+    /// `AERO_UNKNOWN_SCHEMA_VERSION` if either version is missing;
+    /// `AERO_SCHEMA_MIGRATION_INVALID` otherwise.
+    pub fn register_migration(&mut self, migration: SchemaMigration) -> SchemaResult<()> {
+        let id = migration.schema_id.as_str();
+        let invalid = |reason: String| {
+            SchemaError::invalid_migration(
+                id,
+                &migration.from_version,
+                &migration.to_version,
+                reason,
+            )
+        };
+
+        let source = self
+            .get(id, &migration.from_version)
+            .ok_or_else(|| SchemaError::unknown_version(id, &migration.from_version))?;
+        let target = self
+            .get(id, &migration.to_version)
+            .ok_or_else(|| SchemaError::unknown_version(id, &migration.to_version))?;
+
+        let key = (id.to_string(), migration.from_version.clone());
+        if self.migrations.contains_key(&key) {
+            return Err(invalid(format!(
+                "a migration from '{}' is already registered",
+                migration.from_version
+            )));
+        }
+
+        // Replay the changes over the source field names
+        let mut fields: BTreeMap<&str, &FieldDef> = source
+            .fields
+            .iter()
+            .map(|(name, def)| (name.as_str(), def))
+            .collect();
+        for change in &migration.changes {
+            match change {
+                FieldChange::Rename { from, to } => {
+                    let def = fields.remove(from.as_str()).ok_or_else(|| {
+                        invalid(format!("renamed field '{}' does not exist", from))
+                    })?;
+                    if fields.insert(to, def).is_some() {
+                        return Err(invalid(format!("rename target '{}' already exists", to)));
+                    }
+                }
+                FieldChange::Add { field, default } => {
+                    if fields.contains_key(field.as_str()) {
+                        return Err(invalid(format!("added field '{}' already exists", field)));
+                    }
+                    let def = target.fields.get(field).ok_or_else(|| {
+                        invalid(format!("added field '{}' is not in the target", field))
+                    })?;
+                    match default {
+                        Some(value) => SchemaValidator::new(self)
+                            .validate_field_value(id, &migration.to_version, field, value)
+                            .map_err(|e| {
+                                invalid(format!("default for '{}': {}", field, e.message()))
+                            })?,
+                        None if def.required => {
+                            return Err(invalid(format!(
+                                "required field '{}' needs a default",
+                                field
+                            )))
+                        }
+                        None => {}
+                    }
+                    // Upgraded documents always carry a defaulted field
+                    fields.insert(field, def);
+                }
+            }
+        }
+
+        // The result must be exactly the target, with no retyping
+        let mut target_fields: Vec<_> = target.fields.iter().collect();
+        target_fields.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, def) in target_fields {
+            let Some(migrated) = fields.remove(name.as_str()) else {
+                return Err(invalid(format!(
+                    "field '{}' is not covered by the migration",
+                    name
+                )));
+            };
+            if migrated.field_type != def.field_type {
+                return Err(invalid(format!("field '{}' changes type", name)));
+            }
+            let defaulted = migration.changes.iter().any(
+                |c| matches!(c, FieldChange::Add { field, default: Some(_) } if field == name),
+            );
+            if def.required && !migrated.required && !defaulted {
+                return Err(invalid(format!("field '{}' becomes required", name)));
+            }
+        }
+        if let Some(dropped) = fields.keys().next() {
+            return Err(invalid(format!("field '{}' would be dropped", dropped)));
+        }
+
+        // Chains must not loop back to the source version
+        let mut version = migration.to_version.as_str();
+        while let Some(next) = self.migrations.get(&(id.to_string(), version.to_string())) {
+            if next.to_version == migration.from_version {
+                return Err(invalid("migration chain would loop".to_string()));
+            }
+            version = &next.to_version;
+        }
+        if migration.to_version == migration.from_version {
+            return Err(invalid("migration chain would loop".to_string()));
+        }
+
+        self.migrations.insert(key, migration);
+        Ok(())
+    }
+
+    /// Upgrades a document stored under `from_version` to `to_version` by
+    /// applying registered migrations in order.
+    ///
+    /// Returns `None` if no migration chain leads from one to the other.
+    pub fn upgrade_document(
+        &self,
+        schema_id: &str,
+        from_version: &str,
+        to_version: &str,
+        document: Value,
+    ) -> Option<Value> {
+        let mut version = from_version;
+        let mut document = document;
+        while version != to_version {
+            let migration = self
+                .migrations
+                .get(&(schema_id.to_string(), version.to_string()))?;
+            document = migration.apply(document);
+            version = &migration.to_version;
+        }
+        Some(document)
+    }
+
+    /// Checks a schema DDL change against the catalog without applying it.
+    ///
+    /// # Errors
+    ///
+    /// `AERO_SCHEMA_IMMUTABLE` if a created version already exists;
+    /// `AERO_UNKNOWN_SCHEMA_VERSION` if a deprecated version does not;
+    /// `AERO_UNKNOWN_SCHEMA` if a collection's schema is not registered;
+    /// `AERO_UNKNOWN_COLLECTION` if a dropped collection is not registered.
+    pub fn check_ddl(&self, ddl: &SchemaDdl) -> SchemaResult<()> {
+        let (id, version) = (ddl.schema_id(), ddl.schema_version());
+        match ddl {
+            SchemaDdl::CreateCollection { collection, .. } => {
+                match self.collection_schema(collection) {
+                    Some(bound) if bound != id => {
+                        Err(SchemaError::collection_immutable(collection, bound))
+                    }
+                    _ if !self.schema_id_exists(id) => Err(SchemaError::unknown_schema(id)),
+                    _ => Ok(()),
+                }
+            }
+            SchemaDdl::DropCollection { collection }
+                if self.collection_schema(collection).is_none() =>
+            {
+                Err(SchemaError::unknown_collection(collection))
+            }
+            SchemaDdl::Create(_) if self.exists(id, version) => {
+                Err(SchemaError::schema_immutable(id, version))
+            }
+            SchemaDdl::Deprecate { .. } if !self.exists(id, version) => {
+                Err(SchemaError::unknown_version(id, version))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Applies a schema DDL change: writes the schema file, then updates
+    /// the in-memory registry.
+    ///
+    /// Idempotent, so WAL replay may re-apply a change already on disk.
+    pub fn apply_ddl(&mut self, ddl: &SchemaDdl) -> SchemaResult<()> {
+        let (id, version) = (ddl.schema_id(), ddl.schema_version());
+        match ddl {
+            SchemaDdl::CreateCollection { collection, .. } => {
+                return self.apply_create_collection(collection, id);
+            }
+            SchemaDdl::DropCollection { collection } => {
+                if self.collection_schema(collection).is_none() {
+                    return Ok(());
+                }
+                let mut collections = self.collections.clone();
+                collections.remove(collection);
+                return self.write_collections(collections);
+            }
+            // Documents are removed by the tombstones ahead of the record
+            SchemaDdl::TruncateCollection { .. } => return Ok(()),
+            SchemaDdl::CreateIndex { field } => {
+                if self.indexes.contains(field) {
+                    return Ok(());
+                }
+                let mut indexes = self.indexes.clone();
+                indexes.insert(field.clone());
+                write_catalog_file(&self.indexes_path, &indexes)?;
+                self.indexes = indexes;
+                return Ok(());
+            }
+            SchemaDdl::Create(_) | SchemaDdl::Deprecate { .. } => {}
+        }
+        let key = (id.to_string(), version.to_string());
+        let path = self.schema_path(id, version);
+
+        let schema = match (ddl, self.schemas.get(&key)) {
+            (SchemaDdl::Create(schema), Some(existing)) => {
+                if existing.fields == schema.fields && existing.description == schema.description {
+                    return Ok(());
+                }
+                return Err(SchemaError::schema_immutable(id, version));
+            }
+            (SchemaDdl::Create(schema), None) => {
+                schema
+                    .validate_structure()
+                    .map_err(|e| SchemaError::malformed_schema(path.display().to_string(), e))?;
+                schema.clone()
+            }
+            (SchemaDdl::Deprecate { .. }, Some(existing)) => {
+                if existing.deprecated {
+                    return Ok(());
+                }
+                Schema {
+                    deprecated: true,
+                    ..existing.clone()
+                }
+            }
+            (SchemaDdl::Deprecate { .. }, None) => {
+                return Err(SchemaError::unknown_version(id, version));
+            }
+            (
+                SchemaDdl::CreateCollection { .. }
+                | SchemaDdl::DropCollection { .. }
+                | SchemaDdl::TruncateCollection { .. }
+                | SchemaDdl::CreateIndex { .. },
+                _,
+            ) => unreachable!("handled above"),
+        };
+
+        self.write_schema_file(&schema, &path)?;
+        self.schemas.insert(key, schema);
+        Ok(())
+    }
+
+    /// Registers a collection and rewrites the registry file.
+    fn apply_create_collection(&mut self, collection: &str, schema_id: &str) -> SchemaResult<()> {
+        match self.collection_schema(collection) {
+            Some(bound) if bound == schema_id => return Ok(()),
+            Some(bound) => return Err(SchemaError::collection_immutable(collection, bound)),
+            None => {}
+        }
+
+        let mut collections = self.collections.clone();
+        collections.insert(collection.to_string(), schema_id.to_string());
+        self.write_collections(collections)
+    }
+
+    /// Rewrites the registry file, then replaces the in-memory registry.
+    fn write_collections(&mut self, collections: BTreeMap<String, String>) -> SchemaResult<()> {
+        write_catalog_file(&self.collections_path, &collections)?;
+        self.collections = collections;
+        Ok(())
+    }
+
+    /// Path of the file holding one schema version
+    fn schema_path(&self, schema_id: &str, schema_version: &str) -> PathBuf {
+        self.schema_dir
+            .join(format!("schema_{}_{}.json", schema_id, schema_version))
+    }
+
     /// Saves a schema to disk.
     ///
     /// Creates the schema file at the standard location.
     pub fn save_schema(&self, schema: &Schema) -> SchemaResult<PathBuf> {
-        let filename = format!("schema_{}_{}.json", schema.schema_id, schema.schema_version);
-        let path = self.schema_dir.join(&filename);
+        let path = self.schema_path(&schema.schema_id, &schema.schema_version);
 
         // Check if file already exists (immutability)
         if path.exists() {
@@ -169,6 +497,13 @@ impl SchemaLoader {
             ));
         }
 
+        self.write_schema_file(schema, &path)?;
+
+        Ok(path)
+    }
+
+    /// Writes a schema file, replacing any existing file atomically.
+    fn write_schema_file(&self, schema: &Schema, path: &Path) -> SchemaResult<()> {
         // Ensure directory exists
         if !self.schema_dir.exists() {
             fs::create_dir_all(&self.schema_dir).map_err(|e| {
@@ -187,15 +522,46 @@ impl SchemaLoader {
             )
         })?;
 
-        fs::write(&path, content).map_err(|e| {
-            SchemaError::malformed_schema(
-                path.display().to_string(),
-                format!("Failed to write file: {}", e),
-            )
-        })?;
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, content)
+            .and_then(|_| fs::rename(&tmp_path, path))
+            .map_err(|e| {
+                SchemaError::malformed_schema(
+                    path.display().to_string(),
+                    format!("Failed to write file: {}", e),
+                )
+            })?;
 
-        Ok(path)
+        Ok(())
+    }
+}
+
+/// Reads a catalog file written by `write_catalog_file`, if it exists.
+fn read_catalog_file<T: serde::de::DeserializeOwned>(path: &Path) -> SchemaResult<Option<T>> {
+    if !path.exists() {
+        return Ok(None);
     }
+    let malformed =
+        |reason: String| SchemaError::malformed_schema(path.display().to_string(), reason);
+
+    let content =
+        fs::read_to_string(path).map_err(|e| malformed(format!("Failed to read file: {}", e)))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| malformed(format!("Invalid JSON: {}", e)))
+}
+
+/// Replaces a catalog file atomically.
+fn write_catalog_file(path: &Path, value: &impl Serialize) -> SchemaResult<()> {
+    let failed = |reason: String| SchemaError::malformed_schema(path.display().to_string(), reason);
+    let content = serde_json::to_string_pretty(value)
+        .map_err(|e| failed(format!("Failed to serialize: {}", e)))?;
+    let tmp_path = path.with_extension("json.tmp");
+    path.parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&tmp_path, content))
+        .and_then(|_| fs::rename(&tmp_path, path))
+        .map_err(|e| failed(format!("Failed to write file: {}", e)))
 }
 
 // Implement planner's SchemaRegistry trait
@@ -209,6 +575,19 @@ impl crate::planner::SchemaRegistry for SchemaLoader {
     }
 }
 
+// Implement executor's DocumentUpgrade trait
+impl crate::executor::DocumentUpgrade for SchemaLoader {
+    fn upgrade(
+        &self,
+        schema_id: &str,
+        from_version: &str,
+        to_version: &str,
+        body: Value,
+    ) -> Option<Value> {
+        self.upgrade_document(schema_id, from_version, to_version, body)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::types::FieldDef;
@@ -271,6 +650,194 @@ mod tests {
         assert!(!loader.exists("nonexistent", "v1"));
     }
 
+    fn versioned_loader(temp_dir: &TempDir) -> SchemaLoader {
+        let mut loader = SchemaLoader::new(temp_dir.path());
+        loader.register(sample_schema()).unwrap();
+
+        let mut fields = HashMap::new();
+        fields.insert("_id".into(), FieldDef::required_string());
+        fields.insert("full_name".into(), FieldDef::required_string());
+        fields.insert("active".into(), FieldDef::required_bool());
+        loader.register(Schema::new("users", "v2", fields)).unwrap();
+
+        let mut fields = HashMap::new();
+        fields.insert("_id".into(), FieldDef::required_string());
+        fields.insert("full_name".into(), FieldDef::required_string());
+        fields.insert("active".into(), FieldDef::required_bool());
+        fields.insert("age".into(), FieldDef::optional_int());
+        loader.register(Schema::new("users", "v3", fields)).unwrap();
+
+        loader
+    }
+
+    #[test]
+    fn test_migration_chain_upgrade() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut loader = versioned_loader(&temp_dir);
+
+        loader
+            .register_migration(
+                SchemaMigration::new("users", "v1", "v2")
+                    .rename_field("name", "full_name")
+                    .add_field_with_default("active", serde_json::json!(true)),
+            )
+            .unwrap();
+        loader
+            .register_migration(SchemaMigration::new("users", "v2", "v3").add_field("age"))
+            .unwrap();
+
+        let doc = serde_json::json!({"_id": "u1", "name": "Alice"});
+        let upgraded = loader.upgrade_document("users", "v1", "v3", doc.clone());
+        assert_eq!(
+            upgraded,
+            Some(serde_json::json!({"_id": "u1", "full_name": "Alice", "active": true}))
+        );
+
+        // Same version is served as-is; downgrades have no path
+        assert_eq!(
+            loader.upgrade_document("users", "v1", "v1", doc.clone()),
+            Some(doc.clone())
+        );
+        assert!(loader.upgrade_document("users", "v3", "v1", doc).is_none());
+    }
+
+    #[test]
+    fn test_migration_rejects_non_additive_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut loader = versioned_loader(&temp_dir);
+        let code = |result: SchemaResult<()>| result.unwrap_err().code().code();
+
+        // Required field added without a default
+        let result = loader.register_migration(
+            SchemaMigration::new("users", "v1", "v2")
+                .rename_field("name", "full_name")
+                .add_field("active"),
+        );
+        assert_eq!(code(result), "AERO_SCHEMA_MIGRATION_INVALID");
+
+        // Default of the wrong type
+        let result = loader.register_migration(
+            SchemaMigration::new("users", "v1", "v2")
+                .rename_field("name", "full_name")
+                .add_field_with_default("active", serde_json::json!("yes")),
+        );
+        assert_eq!(code(result), "AERO_SCHEMA_MIGRATION_INVALID");
+
+        // Dropping a field is not additive
+        let result = loader.register_migration(
+            SchemaMigration::new("users", "v1", "v2")
+                .add_field_with_default("full_name", serde_json::json!(""))
+                .add_field_with_default("active", serde_json::json!(true)),
+        );
+        assert_eq!(code(result), "AERO_SCHEMA_MIGRATION_INVALID");
+
+        // Unknown target version
+        let result = loader.register_migration(SchemaMigration::new("users", "v1", "v9"));
+        assert_eq!(code(result), "AERO_UNKNOWN_SCHEMA_VERSION");
+
+        // Nothing was registered
+        let doc = serde_json::json!({"_id": "u1", "name": "Alice"});
+        assert!(loader.upgrade_document("users", "v1", "v2", doc).is_none());
+    }
+
+    #[test]
+    fn test_apply_ddl_persists_and_is_idempotent() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut loader = SchemaLoader::new(temp_dir.path());
+
+        let create = SchemaDdl::Create(sample_schema());
+        let deprecate = SchemaDdl::Deprecate {
+            schema_id: "users".into(),
+            schema_version: "v1".into(),
+        };
+        loader.check_ddl(&create).unwrap();
+        assert!(loader.check_ddl(&deprecate).is_err());
+
+        // Replaying the same changes is a no-op
+        for ddl in [&create, &deprecate, &create, &deprecate] {
+            loader.apply_ddl(ddl).unwrap();
+        }
+        assert!(loader.get("users", "v1").unwrap().deprecated);
+        assert_eq!(
+            loader.check_ddl(&create).unwrap_err().code().code(),
+            "AERO_SCHEMA_IMMUTABLE"
+        );
+
+        // Both changes survive a reload from disk
+        let mut reloaded = SchemaLoader::new(temp_dir.path());
+        reloaded.load_all().unwrap();
+        assert!(reloaded.get("users", "v1").unwrap().deprecated);
+    }
+
+    #[test]
+    fn test_collection_registry_persists() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut loader = SchemaLoader::new(temp_dir.path());
+        loader
+            .apply_ddl(&SchemaDdl::Create(sample_schema()))
+            .unwrap();
+
+        let create = |schema_id: &str| SchemaDdl::CreateCollection {
+            collection: "admins".into(),
+            schema_id: schema_id.into(),
+        };
+        assert_eq!(
+            loader
+                .check_ddl(&create("orders"))
+                .unwrap_err()
+                .code()
+                .code(),
+            "AERO_UNKNOWN_SCHEMA"
+        );
+        loader.check_ddl(&create("users")).unwrap();
+        loader.apply_ddl(&create("users")).unwrap();
+        loader.apply_ddl(&create("users")).unwrap();
+        assert_eq!(
+            loader
+                .check_ddl(&create("orders"))
+                .unwrap_err()
+                .code()
+                .code(),
+            "AERO_SCHEMA_IMMUTABLE"
+        );
+
+        let mut reloaded = SchemaLoader::new(temp_dir.path());
+        reloaded.load_all().unwrap();
+        assert_eq!(reloaded.collection_schema("admins"), Some("users"));
+        assert_eq!(reloaded.collections().count(), 1);
+
+        // Dropping unregisters; replaying the drop is a no-op
+        let drop = SchemaDdl::DropCollection {
+            collection: "admins".into(),
+        };
+        reloaded.check_ddl(&drop).unwrap();
+        reloaded.apply_ddl(&drop).unwrap();
+        reloaded.apply_ddl(&drop).unwrap();
+        assert_eq!(
+            reloaded.check_ddl(&drop).unwrap_err().code().code(),
+            "AERO_UNKNOWN_COLLECTION"
+        );
+        let mut reloaded = SchemaLoader::new(temp_dir.path());
+        reloaded.load_all().unwrap();
+        assert_eq!(reloaded.collections().count(), 0);
+    }
+
+    #[test]
+    fn test_index_declarations_persist() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut loader = SchemaLoader::new(temp_dir.path());
+        let ddl = SchemaDdl::CreateIndex {
+            field: "age".into(),
+        };
+        loader.check_ddl(&ddl).unwrap();
+        loader.apply_ddl(&ddl).unwrap();
+        loader.apply_ddl(&ddl).unwrap();
+
+        let mut reloaded = SchemaLoader::new(temp_dir.path());
+        reloaded.load_all().unwrap();
+        assert_eq!(reloaded.index_fields().collect::<Vec<_>>(), vec!["age"]);
+    }
+
     #[test]
     fn test_load_empty_directory() {
         let temp_dir = TempDir::new().unwrap();
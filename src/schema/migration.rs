@@ -0,0 +1,130 @@
+//! Additive schema migrations
+//!
+//! A migration describes how documents written under `vN` of a schema are
+//! read under the next version `vN+1`. Only additive changes are allowed:
+//! fields may be added (optionally with a default) or renamed, never removed
+//! or retyped. Stored documents are never rewritten; they are upgraded
+//! lazily when read under a newer version.
+//!
+//! Upgrades are deterministic: changes apply in declaration order, and a
+//! chain of migrations applies in version order.
+
+use serde_json::Value;
+
+/// One additive change between consecutive schema versions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldChange {
+    /// New field; documents without it receive `default` if one is given
+    Add {
+        field: String,
+        default: Option<Value>,
+    },
+    /// Field renamed from `from` to `to`
+    Rename { from: String, to: String },
+}
+
+/// Changes from `from_version` to `to_version` of one schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaMigration {
+    /// Schema identifier
+    pub schema_id: String,
+    /// Version documents are stored under
+    pub from_version: String,
+    /// Version documents are served under
+    pub to_version: String,
+    /// Changes, applied in order
+    pub changes: Vec<FieldChange>,
+}
+
+impl SchemaMigration {
+    /// Creates an empty migration between two versions
+    pub fn new(
+        schema_id: impl Into<String>,
+        from_version: impl Into<String>,
+        to_version: impl Into<String>,
+    ) -> Self {
+        Self {
+            schema_id: schema_id.into(),
+            from_version: from_version.into(),
+            to_version: to_version.into(),
+            changes: Vec::new(),
+        }
+    }
+
+    /// Adds an optional field with no default
+    pub fn add_field(mut self, field: impl Into<String>) -> Self {
+        self.changes.push(FieldChange::Add {
+            field: field.into(),
+            default: None,
+        });
+        self
+    }
+
+    /// Adds a field filled with `default` in upgraded documents
+    pub fn add_field_with_default(mut self, field: impl Into<String>, default: Value) -> Self {
+        self.changes.push(FieldChange::Add {
+            field: field.into(),
+            default: Some(default),
+        });
+        self
+    }
+
+    /// Renames a field
+    pub fn rename_field(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.changes.push(FieldChange::Rename {
+            from: from.into(),
+            to: to.into(),
+        });
+        self
+    }
+
+    /// Upgrades a document body from `from_version` to `to_version`.
+    ///
+    /// Non-object bodies are returned unchanged.
+    pub fn apply(&self, mut document: Value) -> Value {
+        let Some(obj) = document.as_object_mut() else {
+            return document;
+        };
+
+        for change in &self.changes {
+            match change {
+                FieldChange::Add { field, default } => {
+                    if let Some(default) = default {
+                        obj.entry(field.clone()).or_insert_with(|| default.clone());
+                    }
+                }
+                FieldChange::Rename { from, to } => {
+                    if let Some(value) = obj.remove(from) {
+                        obj.insert(to.clone(), value);
+                    }
+                }
+            }
+        }
+
+        document
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply_add_and_rename() {
+        let migration = SchemaMigration::new("users", "v1", "v2")
+            .rename_field("name", "full_name")
+            .add_field_with_default("active", json!(true))
+            .add_field("nickname");
+
+        let upgraded = migration.apply(json!({"_id": "u1", "name": "Alice"}));
+        assert_eq!(
+            upgraded,
+            json!({"_id": "u1", "full_name": "Alice", "active": true})
+        );
+
+        // Existing values win over defaults
+        let upgraded = migration.apply(json!({"_id": "u2", "name": "Bob", "active": false}));
+        assert_eq!(upgraded["active"], json!(false));
+    }
+}
@@ -11,12 +11,16 @@
 //! - No nulls, defaults, or coercion
 //! - Deterministic validation
 
+mod ddl;
 mod errors;
 mod loader;
+mod migration;
 mod types;
 mod validator;
 
-pub use errors::{SchemaError, SchemaErrorCode, SchemaResult};
+pub use ddl::SchemaDdl;
+pub use errors::{FieldViolation, SchemaError, SchemaErrorCode, SchemaResult};
 pub use loader::SchemaLoader;
+pub use migration::{FieldChange, SchemaMigration};
 pub use types::{FieldDef, FieldType, Schema};
 pub use validator::SchemaValidator;
@@ -9,6 +9,7 @@
 //! - array: Homogeneous array with element type
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
 
 /// Supported field types as defined in SCHEMA.md §136-153
@@ -48,6 +49,28 @@ impl FieldType {
             FieldType::Array { .. } => "array",
         }
     }
+
+    /// Whether `value` is a valid non-null instance of this type
+    pub fn accepts(&self, value: &Value) -> bool {
+        match (self, value) {
+            (FieldType::String, Value::String(_)) => true,
+            (FieldType::Int, Value::Number(n)) => n.is_i64() || n.is_u64(),
+            (FieldType::Bool, Value::Bool(_)) => true,
+            (FieldType::Float, Value::Number(_)) => true,
+            (FieldType::Object { fields }, Value::Object(obj)) => {
+                obj.keys().all(|k| fields.contains_key(k))
+                    && fields.iter().all(|(name, def)| match obj.get(name) {
+                        None => !def.required,
+                        Some(Value::Null) => def.nullable,
+                        Some(v) => def.field_type.accepts(v),
+                    })
+            }
+            (FieldType::Array { element_type }, Value::Array(items)) => {
+                items.iter().all(|v| element_type.accepts(v))
+            }
+            _ => false,
+        }
+    }
 }
 
 /// Field definition as per SCHEMA.md §123-133
@@ -58,6 +81,18 @@ pub struct FieldDef {
     pub field_type: FieldType,
     /// Whether field must be present
     pub required: bool,
+    /// Whether values must be unique across documents (scalar fields only)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub unique: bool,
+    /// Whether an explicit null is accepted (non-strict schemas only)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub nullable: bool,
+    /// Value materialized when the field is absent (non-strict schemas only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+    /// Whether the value is encrypted before it reaches the WAL and storage
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub encrypted: bool,
 }
 
 impl FieldDef {
@@ -66,6 +101,10 @@ impl FieldDef {
         Self {
             field_type: FieldType::String,
             required: true,
+            unique: false,
+            nullable: false,
+            default: None,
+            encrypted: false,
         }
     }
 
@@ -74,6 +113,10 @@ impl FieldDef {
         Self {
             field_type: FieldType::String,
             required: false,
+            unique: false,
+            nullable: false,
+            default: None,
+            encrypted: false,
         }
     }
 
@@ -82,6 +125,10 @@ impl FieldDef {
         Self {
             field_type: FieldType::Int,
             required: true,
+            unique: false,
+            nullable: false,
+            default: None,
+            encrypted: false,
         }
     }
 
@@ -90,6 +137,10 @@ impl FieldDef {
         Self {
             field_type: FieldType::Int,
             required: false,
+            unique: false,
+            nullable: false,
+            default: None,
+            encrypted: false,
         }
     }
 
@@ -98,6 +149,10 @@ impl FieldDef {
         Self {
             field_type: FieldType::Bool,
             required: true,
+            unique: false,
+            nullable: false,
+            default: None,
+            encrypted: false,
         }
     }
 
@@ -106,6 +161,10 @@ impl FieldDef {
         Self {
             field_type: FieldType::Float,
             required: true,
+            unique: false,
+            nullable: false,
+            default: None,
+            encrypted: false,
         }
     }
 
@@ -114,6 +173,10 @@ impl FieldDef {
         Self {
             field_type: FieldType::Object { fields },
             required: true,
+            unique: false,
+            nullable: false,
+            default: None,
+            encrypted: false,
         }
     }
 
@@ -122,6 +185,10 @@ impl FieldDef {
         Self {
             field_type: FieldType::Object { fields },
             required: false,
+            unique: false,
+            nullable: false,
+            default: None,
+            encrypted: false,
         }
     }
 
@@ -132,6 +199,10 @@ impl FieldDef {
                 element_type: Box::new(element_type),
             },
             required: true,
+            unique: false,
+            nullable: false,
+            default: None,
+            encrypted: false,
         }
     }
 
@@ -142,8 +213,36 @@ impl FieldDef {
                 element_type: Box::new(element_type),
             },
             required: false,
+            unique: false,
+            nullable: false,
+            default: None,
+            encrypted: false,
         }
     }
+
+    /// Mark the field as unique across documents
+    pub fn unique(mut self) -> Self {
+        self.unique = true;
+        self
+    }
+
+    /// Accept explicit nulls for this field
+    pub fn nullable(mut self) -> Self {
+        self.nullable = true;
+        self
+    }
+
+    /// Materialize `value` when the field is absent from a written document
+    pub fn with_default(mut self, value: Value) -> Self {
+        self.default = Some(value);
+        self
+    }
+
+    /// Encrypt the field's value at rest
+    pub fn encrypted(mut self) -> Self {
+        self.encrypted = true;
+        self
+    }
 }
 
 /// Complete schema definition as per SCHEMA.md §93-119
@@ -158,6 +257,21 @@ pub struct Schema {
     pub description: Option<String>,
     /// Field definitions
     pub fields: HashMap<String, FieldDef>,
+    /// Deprecated versions stay readable but accept no new writes
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deprecated: bool,
+    /// Strict schemas forbid nullable fields and defaults (SCHEMA.md);
+    /// relaxing this is an explicit opt-in
+    #[serde(default = "default_strict", skip_serializing_if = "is_strict")]
+    pub strict: bool,
+}
+
+fn default_strict() -> bool {
+    true
+}
+
+fn is_strict(strict: &bool) -> bool {
+    *strict
 }
 
 impl Schema {
@@ -172,9 +286,39 @@ impl Schema {
             schema_version: schema_version.into(),
             description: None,
             fields,
+            deprecated: false,
+            strict: true,
         }
     }
 
+    /// Opt in to nullable fields and field defaults
+    pub fn non_strict(mut self) -> Self {
+        self.strict = false;
+        self
+    }
+
+    /// Returns the names of fields marked unique, sorted
+    pub fn unique_fields(&self) -> Vec<&str> {
+        let mut fields: Vec<&str> = self
+            .fields
+            .iter()
+            .filter(|(_, f)| f.unique)
+            .map(|(name, _)| name.as_str())
+            .collect();
+        fields.sort_unstable();
+        fields
+    }
+
+    /// Returns the dotted paths of encrypted fields, sorted
+    ///
+    /// Fields nested in an encrypted object are covered by its path.
+    pub fn encrypted_fields(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        collect_encrypted(&self.fields, "", &mut paths);
+        paths.sort_unstable();
+        paths
+    }
+
     /// Returns the unique key for this schema (id, version)
     pub fn key(&self) -> (&str, &str) {
         (&self.schema_id, &self.schema_version)
@@ -194,8 +338,98 @@ impl Schema {
             }
         }
 
-        Ok(())
+        // Unique is only defined for scalar fields
+        let mut fields: Vec<_> = self.fields.iter().collect();
+        fields.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, field) in fields {
+            if field.unique
+                && matches!(
+                    field.field_type,
+                    FieldType::Object { .. } | FieldType::Array { .. }
+                )
+            {
+                return Err(format!("Unique field '{}' must be a scalar", name));
+            }
+        }
+
+        // _id is always supplied by the client and never null
+        if let Some(id_field) = self.fields.get("_id") {
+            if id_field.nullable || id_field.default.is_some() {
+                return Err("'_id' field cannot be nullable or have a default".into());
+            }
+            // _id is bound into every encrypted value, so stays readable
+            if id_field.encrypted {
+                return Err("'_id' field cannot be encrypted".into());
+            }
+        }
+
+        // Unique constraints are enforced on stored values, which are
+        // ciphertext for encrypted fields
+        if let Some(name) = self
+            .unique_fields()
+            .into_iter()
+            .find(|name| self.fields[*name].encrypted)
+        {
+            return Err(format!("Unique field '{}' cannot be encrypted", name));
+        }
+
+        check_relaxed_fields(&self.fields, self.strict, "")
+    }
+}
+
+/// Collects paths of encrypted fields, recursing into plain nested objects
+fn collect_encrypted(fields: &HashMap<String, FieldDef>, prefix: &str, paths: &mut Vec<String>) {
+    for (name, field) in fields {
+        let path = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        if field.encrypted {
+            paths.push(path);
+        } else if let FieldType::Object { fields } = &field.field_type {
+            collect_encrypted(fields, &path, paths);
+        }
+    }
+}
+
+/// Checks nullable/default declarations, recursing into nested objects
+fn check_relaxed_fields(
+    fields: &HashMap<String, FieldDef>,
+    strict: bool,
+    prefix: &str,
+) -> Result<(), String> {
+    let mut sorted: Vec<_> = fields.iter().collect();
+    sorted.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, field) in sorted {
+        let path = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+
+        if strict && (field.nullable || field.default.is_some()) {
+            return Err(format!(
+                "Field '{}' is nullable or has a default, which requires a non-strict schema",
+                path
+            ));
+        }
+
+        if let Some(default) = &field.default {
+            if !field.field_type.accepts(default) {
+                return Err(format!(
+                    "Default for field '{}' is not a valid {}",
+                    path,
+                    field.field_type.type_name()
+                ));
+            }
+        }
+
+        if let FieldType::Object { fields } = &field.field_type {
+            check_relaxed_fields(fields, strict, &path)?;
+        }
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -237,6 +471,133 @@ mod tests {
         assert!(result.unwrap_err().contains("required"));
     }
 
+    #[test]
+    fn test_unique_fields() {
+        let mut fields = HashMap::new();
+        fields.insert("_id".into(), FieldDef::required_string());
+        fields.insert("email".into(), FieldDef::required_string().unique());
+        fields.insert("name".into(), FieldDef::required_string());
+
+        let schema = Schema::new("users", "v1", fields);
+        assert!(schema.validate_structure().is_ok());
+        assert_eq!(schema.unique_fields(), vec!["email"]);
+
+        // Round-trips through JSON; absent means not unique
+        let json = serde_json::to_string(&schema).unwrap();
+        let parsed: Schema = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, schema);
+        assert!(!json.contains("\"unique\":false"));
+
+        let mut fields = HashMap::new();
+        fields.insert("_id".into(), FieldDef::required_string());
+        fields.insert(
+            "tags".into(),
+            FieldDef::optional_array(FieldType::String).unique(),
+        );
+        let result = Schema::new("users", "v1", fields).validate_structure();
+        assert!(result.unwrap_err().contains("scalar"));
+    }
+
+    #[test]
+    fn test_nullable_and_default_require_non_strict() {
+        let mut fields = HashMap::new();
+        fields.insert("_id".into(), FieldDef::required_string());
+        fields.insert("nickname".into(), FieldDef::optional_string().nullable());
+        fields.insert(
+            "status".into(),
+            FieldDef::required_string().with_default(serde_json::json!("active")),
+        );
+
+        let strict = Schema::new("users", "v1", fields);
+        assert!(strict
+            .validate_structure()
+            .unwrap_err()
+            .contains("non-strict"));
+
+        let relaxed = strict.clone().non_strict();
+        assert!(relaxed.validate_structure().is_ok());
+
+        // Strict is the default and is omitted from JSON
+        let json = serde_json::to_string(&relaxed).unwrap();
+        assert!(json.contains("\"strict\":false"));
+        assert_eq!(serde_json::from_str::<Schema>(&json).unwrap(), relaxed);
+        let json = serde_json::to_string(&sample_schema()).unwrap();
+        assert!(!json.contains("strict"));
+        assert!(serde_json::from_str::<Schema>(&json).unwrap().strict);
+    }
+
+    #[test]
+    fn test_default_must_match_field_type() {
+        let mut fields = HashMap::new();
+        fields.insert("_id".into(), FieldDef::required_string());
+        fields.insert(
+            "age".into(),
+            FieldDef::optional_int().with_default(serde_json::json!("ten")),
+        );
+        let result = Schema::new("users", "v1", fields)
+            .non_strict()
+            .validate_structure();
+        assert!(result.unwrap_err().contains("'age'"));
+
+        let mut fields = HashMap::new();
+        fields.insert(
+            "_id".into(),
+            FieldDef::required_string().with_default(serde_json::json!("x")),
+        );
+        let result = Schema::new("users", "v1", fields)
+            .non_strict()
+            .validate_structure();
+        assert!(result.unwrap_err().contains("_id"));
+    }
+
+    #[test]
+    fn test_encrypted_fields() {
+        let mut address_fields = HashMap::new();
+        address_fields.insert("city".into(), FieldDef::required_string());
+        address_fields.insert("street".into(), FieldDef::required_string().encrypted());
+
+        let mut fields = HashMap::new();
+        fields.insert("_id".into(), FieldDef::required_string());
+        fields.insert("ssn".into(), FieldDef::required_string().encrypted());
+        fields.insert("address".into(), FieldDef::required_object(address_fields));
+        fields.insert(
+            "medical".into(),
+            FieldDef::optional_object(HashMap::from([(
+                "notes".to_string(),
+                FieldDef::optional_string().encrypted(),
+            )]))
+            .encrypted(),
+        );
+
+        let schema = Schema::new("patients", "v1", fields);
+        assert!(schema.validate_structure().is_ok());
+        assert_eq!(
+            schema.encrypted_fields(),
+            vec!["address.street", "medical", "ssn"]
+        );
+
+        // Round-trips through JSON; absent means not encrypted
+        let json = serde_json::to_string(&schema).unwrap();
+        assert_eq!(serde_json::from_str::<Schema>(&json).unwrap(), schema);
+        assert!(!serde_json::to_string(&sample_schema())
+            .unwrap()
+            .contains("encrypted"));
+
+        let mut fields = HashMap::new();
+        fields.insert("_id".into(), FieldDef::required_string().encrypted());
+        let result = Schema::new("users", "v1", fields).validate_structure();
+        assert!(result.unwrap_err().contains("'_id'"));
+
+        let mut fields = HashMap::new();
+        fields.insert("_id".into(), FieldDef::required_string());
+        fields.insert(
+            "email".into(),
+            FieldDef::required_string().unique().encrypted(),
+        );
+        let result = Schema::new("users", "v1", fields).validate_structure();
+        assert!(result.unwrap_err().contains("'email'"));
+    }
+
     #[test]
     fn test_nested_object_type() {
         let mut address_fields = HashMap::new();
@@ -14,18 +14,23 @@
 //! - Default values
 //! - Null values
 //! - Partial validation
+//!
+//! Non-strict schemas opt in to `nullable` fields and field `default`s.
+//! Defaults are materialized into the document by `prepare_document` before
+//! the WAL append, so stored documents are always complete and reads never
+//! compute values.
 
 use serde_json::Value;
 use std::collections::HashMap;
 
-use super::errors::{SchemaError, SchemaResult, ValidationDetails};
+use super::errors::{FieldViolation, SchemaError, SchemaResult, ValidationDetails};
 use super::loader::SchemaLoader;
 use super::types::{FieldDef, FieldType};
 
 /// Schema validator that enforces schema rules on documents.
 ///
 /// Validation occurs BEFORE WAL append (invariant S2).
-/// Validator does not mutate documents.
+/// Validation does not mutate documents; only `prepare_*` injects defaults.
 /// Validation is deterministic.
 pub struct SchemaValidator<'a> {
     loader: &'a SchemaLoader,
@@ -67,6 +72,11 @@ impl<'a> SchemaValidator<'a> {
             .get(schema_id, schema_version)
             .ok_or_else(|| SchemaError::unknown_version(schema_id, schema_version))?;
 
+        // Deprecated versions accept no new writes
+        if schema.deprecated {
+            return Err(SchemaError::schema_deprecated(schema_id, schema_version));
+        }
+
         // Document must be an object
         let doc_obj = document.as_object().ok_or_else(|| {
             SchemaError::validation_failed(
@@ -91,6 +101,47 @@ impl<'a> SchemaValidator<'a> {
         Ok(())
     }
 
+    /// Materializes declared defaults into `document`, then validates it.
+    ///
+    /// A field receives its default only when absent; an explicit null is
+    /// kept and checked against `nullable`. Strict schemas declare no
+    /// defaults, so this is plain validation for them.
+    pub fn prepare_document(
+        &self,
+        schema_id: &str,
+        schema_version: &str,
+        document: &mut Value,
+    ) -> SchemaResult<()> {
+        self.inject_defaults(schema_id, schema_version, document);
+        self.validate_document(schema_id, schema_version, document)
+    }
+
+    /// Materializes declared defaults into `document`, then validates it
+    /// as an update of `existing_id`.
+    pub fn prepare_update(
+        &self,
+        schema_id: &str,
+        schema_version: &str,
+        existing_id: &str,
+        document: &mut Value,
+    ) -> SchemaResult<()> {
+        self.inject_defaults(schema_id, schema_version, document);
+        self.validate_update(schema_id, schema_version, existing_id, document)
+    }
+
+    /// Injects defaults for absent fields; unknown schemas are left to
+    /// validation to report.
+    fn inject_defaults(&self, schema_id: &str, schema_version: &str, document: &mut Value) {
+        if let (Some(schema), Some(obj)) = (
+            self.loader.get(schema_id, schema_version),
+            document.as_object_mut(),
+        ) {
+            if !schema.strict {
+                inject_object_defaults(obj, &schema.fields);
+            }
+        }
+    }
+
     /// Validates a document for update, checking _id immutability.
     ///
     /// # Arguments
@@ -134,6 +185,90 @@ impl<'a> SchemaValidator<'a> {
         Ok(())
     }
 
+    /// Lists every field violation in `document`, rather than the first.
+    ///
+    /// Defaults are materialized into a copy first, so a field the write
+    /// would default is not reported missing. With `partial`, `document`
+    /// holds only the fields being set: absent fields are not missing.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SchemaError` for an unknown or deprecated schema version;
+    /// violations in the document itself are the `Ok` list.
+    pub fn violations(
+        &self,
+        schema_id: &str,
+        schema_version: &str,
+        document: &Value,
+        partial: bool,
+    ) -> SchemaResult<Vec<FieldViolation>> {
+        if !self.loader.schema_id_exists(schema_id) {
+            return Err(SchemaError::unknown_schema(schema_id));
+        }
+        let schema = self
+            .loader
+            .get(schema_id, schema_version)
+            .ok_or_else(|| SchemaError::unknown_version(schema_id, schema_version))?;
+        if schema.deprecated {
+            return Err(SchemaError::schema_deprecated(schema_id, schema_version));
+        }
+
+        let mut document = document.clone();
+        if !partial {
+            self.inject_defaults(schema_id, schema_version, &mut document);
+        }
+        let mut violations = Vec::new();
+        match document.as_object() {
+            Some(obj) => {
+                if !partial && !obj.contains_key("_id") && !schema.fields.contains_key("_id") {
+                    violations.push(FieldViolation::missing_field("_id", "string"));
+                }
+                collect_object(obj, &schema.fields, "", partial, &mut violations);
+            }
+            None => violations.push(FieldViolation::type_mismatch(
+                "$root",
+                "object",
+                json_type_name(&document),
+            )),
+        }
+        Ok(violations)
+    }
+
+    /// Validates a single top-level field value against its definition.
+    pub(crate) fn validate_field_value(
+        &self,
+        schema_id: &str,
+        schema_version: &str,
+        field: &str,
+        value: &Value,
+    ) -> SchemaResult<()> {
+        let schema = self
+            .loader
+            .get(schema_id, schema_version)
+            .ok_or_else(|| SchemaError::unknown_version(schema_id, schema_version))?;
+        let field_def = schema.fields.get(field).ok_or_else(|| {
+            SchemaError::validation_failed(
+                schema_id,
+                schema_version,
+                ValidationDetails::extra_field(field),
+            )
+        })?;
+        if value.is_null() {
+            return Err(SchemaError::validation_failed(
+                schema_id,
+                schema_version,
+                ValidationDetails::null_value(field),
+            ));
+        }
+        self.validate_value(
+            schema_id,
+            schema_version,
+            value,
+            &field_def.field_type,
+            field,
+        )
+    }
+
     /// Validates an object against field definitions.
     fn validate_object(
         &self,
@@ -161,7 +296,10 @@ impl<'a> SchemaValidator<'a> {
 
             match obj.get(field_name) {
                 Some(value) => {
-                    // Check for null (forbidden in Phase 0)
+                    // Null is forbidden unless the field opts in
+                    if value.is_null() && field_def.nullable {
+                        continue;
+                    }
                     if value.is_null() {
                         return Err(SchemaError::validation_failed(
                             schema_id,
@@ -294,6 +432,92 @@ impl<'a> SchemaValidator<'a> {
     }
 }
 
+/// Collects violations in `obj`, in field name order.
+fn collect_object(
+    obj: &serde_json::Map<String, Value>,
+    fields: &HashMap<String, FieldDef>,
+    path_prefix: &str,
+    partial: bool,
+    violations: &mut Vec<FieldViolation>,
+) {
+    let mut names: Vec<&String> = obj.keys().chain(fields.keys()).collect();
+    names.sort();
+    names.dedup();
+    for name in names {
+        let path = make_path(path_prefix, name);
+        match (obj.get(name), fields.get(name)) {
+            (Some(value), None) => violations.push(FieldViolation::undeclared_field(
+                path,
+                json_type_name(value),
+            )),
+            (Some(Value::Null), Some(def)) if !def.nullable => {
+                violations.push(FieldViolation::null_value(path, def.field_type.type_name()))
+            }
+            (Some(Value::Null), Some(_)) => {}
+            (Some(value), Some(def)) => collect_value(value, &def.field_type, &path, violations),
+            (None, Some(def)) if def.required && !partial => violations.push(
+                FieldViolation::missing_field(path, def.field_type.type_name()),
+            ),
+            (None, _) => {}
+        }
+    }
+}
+
+/// Collects violations in a non-null `value` of `field_type`.
+fn collect_value(
+    value: &Value,
+    field_type: &FieldType,
+    path: &str,
+    violations: &mut Vec<FieldViolation>,
+) {
+    match (field_type, value) {
+        (FieldType::Object { fields }, Value::Object(obj)) => {
+            collect_object(obj, fields, path, false, violations)
+        }
+        (FieldType::Array { element_type }, Value::Array(elements)) => {
+            for (i, element) in elements.iter().enumerate() {
+                let element_path = format!("{}[{}]", path, i);
+                if element.is_null() {
+                    violations.push(FieldViolation::null_value(
+                        element_path,
+                        element_type.type_name(),
+                    ));
+                } else {
+                    collect_value(element, element_type, &element_path, violations);
+                }
+            }
+        }
+        _ if !field_type.accepts(value) => violations.push(FieldViolation::type_mismatch(
+            path,
+            field_type.type_name(),
+            json_type_name(value),
+        )),
+        _ => {}
+    }
+}
+
+/// Inserts defaults for absent fields, recursing into present nested objects.
+fn inject_object_defaults(
+    obj: &mut serde_json::Map<String, Value>,
+    fields: &HashMap<String, FieldDef>,
+) {
+    for (name, def) in fields {
+        match obj.get_mut(name) {
+            None => {
+                if let Some(default) = &def.default {
+                    obj.insert(name.clone(), default.clone());
+                }
+            }
+            Some(Value::Object(inner)) => {
+                if let FieldType::Object { fields } = &def.field_type {
+                    inject_object_defaults(inner, fields);
+                }
+            }
+            Some(_) => {}
+        }
+    }
+}
+
 /// Returns the JSON type name for error messages.
 fn json_type_name(value: &Value) -> &'static str {
     match value {
@@ -338,6 +562,7 @@ fn type_error(
 
 #[cfg(test)]
 mod tests {
+    use super::super::ddl::SchemaDdl;
     use super::super::types::Schema;
     use super::*;
     use serde_json::json;
@@ -359,6 +584,70 @@ mod tests {
         (temp_dir, loader)
     }
 
+    #[test]
+    fn test_deprecated_version_rejects_writes() {
+        let (_temp_dir, mut loader) = setup_loader();
+        loader
+            .apply_ddl(&SchemaDdl::Deprecate {
+                schema_id: "users".into(),
+                schema_version: "v1".into(),
+            })
+            .unwrap();
+        let validator = SchemaValidator::new(&loader);
+
+        let doc = json!({"_id": "user_123", "name": "Alice", "active": true});
+        let err = validator
+            .validate_document("users", "v1", &doc)
+            .unwrap_err();
+        assert_eq!(err.code().code(), "AERO_SCHEMA_DEPRECATED");
+    }
+
+    #[test]
+    fn test_prepare_document_materializes_defaults() {
+        let (_temp_dir, mut loader) = setup_loader();
+        let mut address = HashMap::new();
+        address.insert(
+            "country".into(),
+            FieldDef::optional_string().with_default(json!("NZ")),
+        );
+        let mut fields = HashMap::new();
+        fields.insert("_id".into(), FieldDef::required_string());
+        fields.insert(
+            "status".into(),
+            FieldDef::required_string().with_default(json!("active")),
+        );
+        fields.insert("nickname".into(), FieldDef::optional_string().nullable());
+        fields.insert("address".into(), FieldDef::optional_object(address));
+        loader
+            .register(Schema::new("accounts", "v1", fields).non_strict())
+            .unwrap();
+        let validator = SchemaValidator::new(&loader);
+
+        // Validation alone does not fill in a missing required field
+        let mut doc = json!({"_id": "a1", "nickname": null, "address": {}});
+        assert!(validator.validate_document("accounts", "v1", &doc).is_err());
+
+        validator
+            .prepare_document("accounts", "v1", &mut doc)
+            .unwrap();
+        assert_eq!(
+            doc,
+            json!({"_id": "a1", "status": "active", "nickname": null, "address": {"country": "NZ"}})
+        );
+
+        // Explicit values are never overwritten; null stays forbidden
+        // on fields that are not nullable
+        let mut doc = json!({"_id": "a2", "status": "closed"});
+        validator
+            .prepare_document("accounts", "v1", &mut doc)
+            .unwrap();
+        assert_eq!(doc["status"], "closed");
+        let mut doc = json!({"_id": "a3", "status": null});
+        assert!(validator
+            .prepare_document("accounts", "v1", &mut doc)
+            .is_err());
+    }
+
     #[test]
     fn test_valid_document_passes() {
         let (_temp_dir, loader) = setup_loader();
@@ -644,4 +933,41 @@ mod tests {
         });
         assert!(validator.validate_document("scores", "v1", &doc).is_ok());
     }
+
+    #[test]
+    fn test_violations_lists_every_field() {
+        let (_temp_dir, loader) = setup_loader();
+        let validator = SchemaValidator::new(&loader);
+
+        let doc = json!({"name": 7, "age": null, "nickname": "Al"});
+        let violations = validator.violations("users", "v1", &doc, false).unwrap();
+        let found: Vec<(&str, &str, &str)> = violations
+            .iter()
+            .map(|v| (v.path.as_str(), v.expected.as_str(), v.constraint))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("_id", "string", "required"),
+                ("active", "bool", "required"),
+                ("age", "int", "not_null"),
+                ("name", "string", "type"),
+                ("nickname", "no undeclared fields", "declared"),
+            ]
+        );
+        assert_eq!(violations[3].actual, "int");
+
+        // Updates set only some fields
+        let updates = json!({"age": "old"});
+        let violations = validator.violations("users", "v1", &updates, true).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].path, "age");
+
+        let doc = json!({"_id": "u1", "name": "Alice", "active": true});
+        assert!(validator
+            .violations("users", "v1", &doc, false)
+            .unwrap()
+            .is_empty());
+        assert!(validator.violations("users", "v9", &doc, false).is_err());
+    }
 }
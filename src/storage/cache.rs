@@ -0,0 +1,190 @@
+//! Block cache for storage point lookups
+//!
+//! An LRU cache of records keyed by byte offset, shared between the
+//! StorageReader (which fills it from `read_at`) and the StorageWriter
+//! (which invalidates it). Records are cached exactly as read from disk:
+//! checksum-verified but still sealed, so encrypted bodies are opened on
+//! every hit and erasing a collection key takes effect immediately.
+//!
+//! Storage is append-only, so a cached offset only goes stale when the file
+//! tail is cut back (a failed batch) and later rewritten, or when the file is
+//! replaced. Every write invalidates entries at or beyond its offset, and
+//! `clear` drops everything.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use crate::observability::MetricsRegistry;
+
+use super::record::DocumentRecord;
+
+/// LRU cache of storage records keyed by offset
+pub struct BlockCache {
+    /// Maximum number of cached records
+    capacity: usize,
+    /// Entries and their recency
+    inner: Mutex<CacheInner>,
+    /// Hit/miss counters, when attached
+    metrics: Option<Arc<MetricsRegistry>>,
+}
+
+#[derive(Default)]
+struct CacheInner {
+    /// offset -> (record, last-use tick)
+    entries: BTreeMap<u64, (DocumentRecord, u64)>,
+    /// last-use tick -> offset, oldest first
+    recency: BTreeMap<u64, u64>,
+    /// Monotonic use counter
+    tick: u64,
+}
+
+impl BlockCache {
+    /// Creates a cache holding at most `capacity` records.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(CacheInner::default()),
+            metrics: None,
+        }
+    }
+
+    /// Reports hits and misses to the metrics registry.
+    pub fn with_metrics(mut self, metrics: Arc<MetricsRegistry>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Returns the number of cached records.
+    pub fn len(&self) -> usize {
+        self.inner.lock().expect("Lock poisoned").entries.len()
+    }
+
+    /// Returns whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Looks up the record at `offset`, marking it most recently used.
+    pub fn get(&self, offset: u64) -> Option<DocumentRecord> {
+        let mut inner = self.inner.lock().expect("Lock poisoned");
+        inner.tick += 1;
+        let tick = inner.tick;
+
+        let found = match inner.entries.get_mut(&offset) {
+            Some((record, last_use)) => {
+                let previous = std::mem::replace(last_use, tick);
+                Some((record.clone(), previous))
+            }
+            None => None,
+        };
+
+        match found {
+            Some((record, previous)) => {
+                inner.recency.remove(&previous);
+                inner.recency.insert(tick, offset);
+                if let Some(metrics) = &self.metrics {
+                    metrics.increment_storage_cache_hits();
+                }
+                Some(record)
+            }
+            None => {
+                if let Some(metrics) = &self.metrics {
+                    metrics.increment_storage_cache_misses();
+                }
+                None
+            }
+        }
+    }
+
+    /// Caches the record read at `offset`, evicting the least recently
+    /// used record when full.
+    pub fn insert(&self, offset: u64, record: DocumentRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut inner = self.inner.lock().expect("Lock poisoned");
+        inner.tick += 1;
+        let tick = inner.tick;
+
+        if let Some((_, previous)) = inner.entries.insert(offset, (record, tick)) {
+            inner.recency.remove(&previous);
+        }
+        inner.recency.insert(tick, offset);
+
+        while inner.entries.len() > self.capacity {
+            match inner.recency.pop_first() {
+                Some((_, oldest)) => {
+                    inner.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Drops every record at or beyond `offset`.
+    pub fn invalidate_from(&self, offset: u64) {
+        let mut inner = self.inner.lock().expect("Lock poisoned");
+        let stale = inner.entries.split_off(&offset);
+        for (_, (_, last_use)) in stale {
+            inner.recency.remove(&last_use);
+        }
+    }
+
+    /// Drops every record.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().expect("Lock poisoned");
+        inner.entries.clear();
+        inner.recency.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: &str) -> DocumentRecord {
+        DocumentRecord {
+            document_id: id.to_string(),
+            schema_id: "s".to_string(),
+            schema_version: "v1".to_string(),
+            is_tombstone: false,
+            document_body: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let metrics = Arc::new(MetricsRegistry::new());
+        let cache = BlockCache::new(2).with_metrics(Arc::clone(&metrics));
+
+        cache.insert(0, record("a"));
+        cache.insert(10, record("b"));
+        assert!(cache.get(0).is_some());
+        cache.insert(20, record("c"));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(10).is_none());
+        assert_eq!(cache.get(0).unwrap().document_id, "a");
+        assert_eq!(cache.get(20).unwrap().document_id, "c");
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.storage_cache_hits, 3);
+        assert_eq!(snapshot.storage_cache_misses, 1);
+    }
+
+    #[test]
+    fn test_invalidate_from_drops_tail() {
+        let cache = BlockCache::new(8);
+        cache.insert(0, record("a"));
+        cache.insert(10, record("b"));
+        cache.insert(20, record("c"));
+
+        cache.invalidate_from(10);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get(0).is_some());
+        assert!(cache.get(20).is_none());
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
@@ -5,10 +5,17 @@
 //! - Any checksum failure on read → operation abort
 //! - During recovery → startup abort
 //!
-//! Uses CRC32 (IEEE polynomial) for checksums.
+//! Uses CRC32 (IEEE polynomial) by default. Records written with another
+//! `ChecksumAlgorithm` record it in the high bits of the tombstone flag byte;
+//! legacy records carry zero there and decode as CRC32.
+
+use std::io;
 
 use crc32fast::Hasher;
 
+pub use crate::wal::ChecksumAlgorithm;
+use crate::wal::{decode_header_byte, encode_header_byte};
+
 /// Computes a CRC32 checksum over the provided data.
 ///
 /// This function is deterministic: the same input always produces the same output.
@@ -23,6 +30,17 @@ pub fn verify_checksum(data: &[u8], expected: u32) -> bool {
     compute_checksum(data) == expected
 }
 
+/// Encode the tombstone flag byte with the record's checksum algorithm.
+pub fn encode_flags(is_tombstone: bool, algorithm: ChecksumAlgorithm) -> u8 {
+    encode_header_byte(is_tombstone as u8, algorithm)
+}
+
+/// Decode the tombstone flag byte into the tombstone bit and algorithm.
+pub fn decode_flags(byte: u8) -> io::Result<(bool, ChecksumAlgorithm)> {
+    let (value, algorithm) = decode_header_byte(byte)?;
+    Ok((value & 1 != 0, algorithm))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -51,4 +69,16 @@ mod tests {
         assert!(verify_checksum(data, checksum));
         assert!(!verify_checksum(data, checksum ^ 1));
     }
+
+    #[test]
+    fn test_flags_roundtrip() {
+        assert_eq!(decode_flags(1).unwrap(), (true, ChecksumAlgorithm::Crc32));
+        assert_eq!(decode_flags(0).unwrap(), (false, ChecksumAlgorithm::Crc32));
+
+        let byte = encode_flags(true, ChecksumAlgorithm::Crc32c);
+        assert_eq!(
+            decode_flags(byte).unwrap(),
+            (true, ChecksumAlgorithm::Crc32c)
+        );
+    }
 }
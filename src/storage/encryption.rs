@@ -0,0 +1,854 @@
+//! Per-collection encryption scoping (bring-your-own-key per tenant)
+//!
+//! Each encrypted collection has its own random data key. The data key is
+//! never stored in the clear: it is wrapped by key material supplied by the
+//! tenant that owns the collection.
+//!
+//! Layout under the data directory:
+//! - `keys/collections.json` — collection → tenant, state, wrapped data key
+//! - `keys/tenants/<tenant_id>.key` — tenant key material (base64, 32 bytes)
+//!
+//! Deleting a tenant's key makes every data key it wraps unrecoverable, so
+//! the tenant's document bodies are cryptographically erased without
+//! rewriting storage. `erase_tenant` does this and records the collections
+//! as erased so boot does not expect their key. The WAL is not sealed under
+//! collection keys, so erasure is refused while the live WAL or an archived
+//! segment still holds a body of one of the tenant's collections: checkpoint
+//! and purge the archive first.
+//!
+//! At boot every collection marked encrypted MUST have its tenant key present
+//! and the key MUST unwrap the data key. Otherwise the keyring refuses to
+//! open and boot halts.
+//!
+//! # Scope
+//!
+//! - Only document bodies in storage are sealed; ids and schema metadata are
+//!   not. Tombstones carry no body.
+//! - Records written before a collection was marked encrypted stay readable
+//!   in the clear.
+//! - WAL records keep plaintext bodies until checkpoint truncates them.
+//!
+//! # Sealed body format
+//!
+//! ```text
+//! "AENC" | version (u8) | nonce (12) | ciphertext | tag (16)
+//! ```
+//!
+//! Bodies are sealed with AES-256-GCM under the data key; the magic and
+//! version are authenticated as associated data.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use rand::RngCore;
+use ring::aead::{Aad, Nonce, NONCE_LEN};
+use serde::{Deserialize, Serialize};
+
+use super::errors::{StorageError, StorageResult};
+use super::field_encryption::aead_key;
+use super::record::DocumentRecord;
+use crate::checkpoint::WAL_ARCHIVE_DIR;
+use crate::wal::WalReader;
+
+/// Key directory name within the data directory
+pub const KEYS_DIR: &str = "keys";
+
+/// Collection key registry file name within the key directory
+pub const COLLECTION_KEYS_FILE: &str = "collections.json";
+
+/// Tenant key directory name within the key directory
+pub const TENANT_KEYS_DIR: &str = "tenants";
+
+/// Key length in bytes (data keys and tenant keys)
+pub const KEY_LEN: usize = 32;
+
+const MAGIC: &[u8; 4] = b"AENC";
+const VERSION: u8 = 1;
+const TAG_LEN: usize = 16;
+const HEADER_LEN: usize = MAGIC.len() + 1 + NONCE_LEN;
+
+/// 256-bit key material
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey([u8; KEY_LEN]);
+
+impl EncryptionKey {
+    /// Create a key from raw bytes.
+    pub fn from_bytes(bytes: [u8; KEY_LEN]) -> Self {
+        Self(bytes)
+    }
+
+    /// Generate a random key.
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; KEY_LEN];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        Self(bytes)
+    }
+
+    /// Parse a base64-encoded key.
+    pub fn from_base64(encoded: &str) -> StorageResult<Self> {
+        let bytes = STANDARD.decode(encoded.trim()).map_err(|e| {
+            StorageError::key_unavailable(format!("Key is not valid base64: {}", e))
+        })?;
+        let bytes: [u8; KEY_LEN] = bytes.try_into().map_err(|b: Vec<u8>| {
+            StorageError::key_unavailable(format!("Key must be {} bytes, got {}", KEY_LEN, b.len()))
+        })?;
+        Ok(Self(bytes))
+    }
+
+    /// Encode the key as base64.
+    pub fn to_base64(&self) -> String {
+        STANDARD.encode(self.0)
+    }
+
+    /// Raw key bytes.
+    pub(crate) fn as_bytes(&self) -> &[u8; KEY_LEN] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EncryptionKey(<redacted>)")
+    }
+}
+
+/// Returns whether a body is in the sealed format.
+pub fn is_sealed(body: &[u8]) -> bool {
+    body.len() >= HEADER_LEN + TAG_LEN && body.starts_with(MAGIC)
+}
+
+/// Seal a body under a key.
+pub fn seal(key: &EncryptionKey, plaintext: &[u8]) -> Vec<u8> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let mut sealed = Vec::with_capacity(HEADER_LEN + plaintext.len() + TAG_LEN);
+    sealed.extend_from_slice(MAGIC);
+    sealed.push(VERSION);
+    sealed.extend_from_slice(&nonce);
+
+    let mut data = plaintext.to_vec();
+    aead_key(key)
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(&sealed[..=MAGIC.len()]),
+            &mut data,
+        )
+        .expect("AES-GCM sealing does not fail for in-memory bodies");
+    sealed.extend_from_slice(&data);
+    sealed
+}
+
+/// Open a sealed body.
+///
+/// # Errors
+///
+/// `AERO_DATA_CORRUPTION` if the body is malformed or fails authentication
+/// (tampered data or wrong key).
+pub fn open(key: &EncryptionKey, sealed: &[u8]) -> StorageResult<Vec<u8>> {
+    if !is_sealed(sealed) {
+        return Err(StorageError::data_corruption("Sealed body is malformed"));
+    }
+    if sealed[MAGIC.len()] != VERSION {
+        return Err(StorageError::data_corruption(format!(
+            "Unsupported sealed body version: {}",
+            sealed[MAGIC.len()]
+        )));
+    }
+
+    let nonce = Nonce::try_assume_unique_for_key(&sealed[MAGIC.len() + 1..HEADER_LEN])
+        .expect("Nonce length checked");
+    let mut data = sealed[HEADER_LEN..].to_vec();
+    let plaintext_len = aead_key(key)
+        .open_in_place(nonce, Aad::from(&sealed[..=MAGIC.len()]), &mut data)
+        .map_err(|_| StorageError::data_corruption("Sealed body failed authentication"))?
+        .len();
+    data.truncate(plaintext_len);
+    Ok(data)
+}
+
+/// Encryption state of a collection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectionKeyState {
+    /// Bodies are sealed; the tenant key must be present at boot
+    Encrypted,
+    /// The tenant key was deleted; bodies are unrecoverable
+    Erased,
+}
+
+/// Registry entry for an encrypted collection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectionKeyEntry {
+    /// Tenant whose key wraps the data key
+    pub tenant_id: String,
+    /// Encryption state
+    pub state: CollectionKeyState,
+    /// Data key sealed under the tenant key (base64)
+    pub wrapped_key: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Registry {
+    collections: BTreeMap<String, CollectionKeyEntry>,
+}
+
+#[derive(Debug, Default)]
+struct KeyringState {
+    registry: Registry,
+    /// Unwrapped data keys for collections in the `Encrypted` state
+    data_keys: BTreeMap<String, EncryptionKey>,
+}
+
+/// Per-collection data keys, shared by the storage writer and reader
+#[derive(Debug)]
+pub struct CollectionKeyring {
+    keys_dir: PathBuf,
+    state: RwLock<KeyringState>,
+}
+
+impl CollectionKeyring {
+    /// Open the keyring in `<data_dir>/keys` and unwrap every data key.
+    ///
+    /// A data directory without a registry has no encrypted collections.
+    ///
+    /// # Errors
+    ///
+    /// `AERO_ENCRYPTION_KEY_UNAVAILABLE` if a collection marked encrypted has
+    /// no tenant key, or the tenant key does not unwrap its data key.
+    pub fn open(data_dir: &Path) -> StorageResult<Self> {
+        let keys_dir = data_dir.join(KEYS_DIR);
+        let registry_path = keys_dir.join(COLLECTION_KEYS_FILE);
+
+        let registry: Registry = match fs::read(&registry_path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| {
+                StorageError::data_corruption(format!(
+                    "Invalid collection key registry {}: {}",
+                    registry_path.display(),
+                    e
+                ))
+            })?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Registry::default(),
+            Err(e) => {
+                return Err(StorageError::read_failed(
+                    format!(
+                        "Failed to read collection key registry: {}",
+                        registry_path.display()
+                    ),
+                    e,
+                ))
+            }
+        };
+
+        let keyring = Self {
+            keys_dir,
+            state: RwLock::new(KeyringState::default()),
+        };
+
+        let mut data_keys = BTreeMap::new();
+        for (collection, entry) in &registry.collections {
+            if entry.state == CollectionKeyState::Encrypted {
+                data_keys.insert(
+                    collection.clone(),
+                    keyring.unwrap_data_key(collection, entry)?,
+                );
+            }
+        }
+
+        *keyring.write_state() = KeyringState {
+            registry,
+            data_keys,
+        };
+        Ok(keyring)
+    }
+
+    /// Returns the path of a tenant's key file.
+    pub fn tenant_key_path(&self, tenant_id: &str) -> PathBuf {
+        self.keys_dir
+            .join(TENANT_KEYS_DIR)
+            .join(format!("{}.key", tenant_id))
+    }
+
+    /// Store tenant key material (bring-your-own-key).
+    ///
+    /// Overwriting an existing tenant key is rejected: it would orphan every
+    /// data key wrapped by the old one.
+    pub fn install_tenant_key(&self, tenant_id: &str, key: &EncryptionKey) -> StorageResult<()> {
+        validate_tenant_id(tenant_id)?;
+        let path = self.tenant_key_path(tenant_id);
+        if path.exists() {
+            return Err(StorageError::write_failed_no_source(format!(
+                "Tenant key already installed: {}",
+                tenant_id
+            )));
+        }
+        write_durable(&path, key.to_base64().as_bytes())
+    }
+
+    /// Mark a collection as encrypted under a tenant's key.
+    ///
+    /// Generates a fresh data key, wraps it with the tenant key and persists
+    /// the registry. Bodies written from now on are sealed.
+    ///
+    /// # Errors
+    ///
+    /// - `AERO_ENCRYPTION_KEY_UNAVAILABLE` if the tenant key is missing
+    /// - `AERO_STORAGE_WRITE_FAILED` if the collection already has an entry
+    pub fn enable_collection(&self, collection: &str, tenant_id: &str) -> StorageResult<()> {
+        validate_tenant_id(tenant_id)?;
+        let tenant_key = self.load_tenant_key(tenant_id)?;
+
+        let mut state = self.write_state();
+        if state.registry.collections.contains_key(collection) {
+            return Err(StorageError::write_failed_no_source(format!(
+                "Collection already has an encryption entry: {}",
+                collection
+            )));
+        }
+
+        let data_key = EncryptionKey::generate();
+        let entry = CollectionKeyEntry {
+            tenant_id: tenant_id.to_string(),
+            state: CollectionKeyState::Encrypted,
+            wrapped_key: STANDARD.encode(seal(&tenant_key, &data_key.0)),
+        };
+
+        let mut registry = Registry {
+            collections: state.registry.collections.clone(),
+        };
+        registry.collections.insert(collection.to_string(), entry);
+        self.persist(&registry)?;
+
+        state.registry = registry;
+        state.data_keys.insert(collection.to_string(), data_key);
+        Ok(())
+    }
+
+    /// Cryptographically erase a tenant's data.
+    ///
+    /// The registry is updated first (collections marked erased), then the
+    /// tenant key is deleted. A crash in between leaves an orphaned key file
+    /// that is no longer required at boot; rerunning completes the erase.
+    ///
+    /// Returns the collections that were erased.
+    ///
+    /// # Errors
+    ///
+    /// `AERO_STORAGE_WRITE_FAILED` if the live WAL or an archived WAL segment
+    /// still holds a body of one of the tenant's collections, or cannot be
+    /// scanned. Those bodies are not sealed under the data key, so deleting
+    /// the key would not erase them.
+    pub fn erase_tenant(&self, tenant_id: &str) -> StorageResult<Vec<String>> {
+        validate_tenant_id(tenant_id)?;
+        let mut state = self.write_state();
+
+        let collections: Vec<&str> = state
+            .registry
+            .collections
+            .iter()
+            .filter(|(_, entry)| entry.tenant_id == tenant_id)
+            .map(|(collection, _)| collection.as_str())
+            .collect();
+        self.ensure_wal_holds_no_bodies(tenant_id, &collections)?;
+
+        let mut registry = Registry {
+            collections: state.registry.collections.clone(),
+        };
+        let mut erased = Vec::new();
+        for (collection, entry) in registry.collections.iter_mut() {
+            if entry.tenant_id == tenant_id && entry.state == CollectionKeyState::Encrypted {
+                entry.state = CollectionKeyState::Erased;
+                erased.push(collection.clone());
+            }
+        }
+        self.persist(&registry)?;
+
+        let key_path = self.tenant_key_path(tenant_id);
+        match fs::remove_file(&key_path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(StorageError::write_failed(
+                    format!("Failed to delete tenant key: {}", key_path.display()),
+                    e,
+                ))
+            }
+        }
+        if let Some(parent) = key_path.parent() {
+            fsync_dir(parent).map_err(|e| {
+                StorageError::write_failed(
+                    format!("fsync key directory failed: {}", parent.display()),
+                    e,
+                )
+            })?;
+        }
+
+        for collection in &erased {
+            state.data_keys.remove(collection);
+        }
+        state.registry = registry;
+        Ok(erased)
+    }
+
+    /// Returns the registry entry for a collection, if any.
+    pub fn entry(&self, collection: &str) -> Option<CollectionKeyEntry> {
+        self.read_state()
+            .registry
+            .collections
+            .get(collection)
+            .cloned()
+    }
+
+    /// Whether bodies written to a collection are sealed.
+    pub fn is_encrypted(&self, collection: &str) -> bool {
+        self.read_state().data_keys.contains_key(collection)
+    }
+
+    /// Seal a body for a collection (unchanged if not encrypted).
+    pub fn encrypt_body(&self, collection: &str, body: &[u8]) -> StorageResult<Vec<u8>> {
+        let state = self.read_state();
+        match state.registry.collections.get(collection) {
+            None => Ok(body.to_vec()),
+            Some(entry) => match state.data_keys.get(collection) {
+                Some(key) => Ok(seal(key, body)),
+                None => Err(StorageError::key_unavailable(format!(
+                    "Collection {} was erased with tenant {}; writes are rejected",
+                    collection, entry.tenant_id
+                ))),
+            },
+        }
+    }
+
+    /// Open a body read from a collection.
+    ///
+    /// Bodies written before the collection was encrypted are returned as is.
+    pub fn decrypt_body(&self, collection: &str, body: &[u8]) -> StorageResult<Vec<u8>> {
+        let state = self.read_state();
+        let Some(entry) = state.registry.collections.get(collection) else {
+            return Ok(body.to_vec());
+        };
+        if !is_sealed(body) {
+            return Ok(body.to_vec());
+        }
+        match state.data_keys.get(collection) {
+            Some(key) => open(key, body),
+            None => Err(StorageError::key_unavailable(format!(
+                "Collection {} was erased with tenant {}",
+                collection, entry.tenant_id
+            ))),
+        }
+    }
+
+    /// Seal the body of a document record in place.
+    pub(crate) fn encrypt_record(&self, record: &mut DocumentRecord) -> StorageResult<()> {
+        if !record.is_tombstone {
+            record.document_body =
+                self.encrypt_body(record_collection(record), &record.document_body)?;
+        }
+        Ok(())
+    }
+
+    /// Open the body of a document record in place.
+    pub(crate) fn decrypt_record(&self, record: &mut DocumentRecord) -> StorageResult<()> {
+        if !record.is_tombstone {
+            record.document_body =
+                self.decrypt_body(record_collection(record), &record.document_body)?;
+        }
+        Ok(())
+    }
+
+    /// Refuse if a WAL file under the data directory holds a body of one of
+    /// `collections`.
+    fn ensure_wal_holds_no_bodies(
+        &self,
+        tenant_id: &str,
+        collections: &[&str],
+    ) -> StorageResult<()> {
+        if collections.is_empty() {
+            return Ok(());
+        }
+        let Some(data_dir) = self.keys_dir.parent() else {
+            return Ok(());
+        };
+
+        let mut wal_files = vec![data_dir.join("wal").join("wal.log")];
+        match fs::read_dir(data_dir.join(WAL_ARCHIVE_DIR)) {
+            Ok(entries) => {
+                for entry in entries {
+                    let path = entry
+                        .map_err(|e| {
+                            StorageError::read_failed("Failed to list archived WAL segments", e)
+                        })?
+                        .path();
+                    if path.extension().is_some_and(|ext| ext == "wal") {
+                        wal_files.push(path);
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(StorageError::read_failed(
+                    "Failed to list archived WAL segments",
+                    e,
+                ))
+            }
+        }
+
+        let refuse = |path: &Path, reason: String| {
+            StorageError::write_failed_no_source(format!(
+                "Cannot erase tenant {}: {} {}; checkpoint and purge archived WAL first",
+                tenant_id,
+                path.display(),
+                reason
+            ))
+        };
+        for path in wal_files {
+            if !path.exists() {
+                continue;
+            }
+            // An encrypted WAL is readable with the master key, which
+            // erasure does not delete, so it counts as unscannable
+            let mut reader = WalReader::open(&path)
+                .map_err(|e| refuse(&path, format!("cannot be scanned: {}", e)))?;
+            while let Some(record) = reader
+                .read_next()
+                .map_err(|e| refuse(&path, format!("cannot be scanned: {}", e)))?
+            {
+                let payload = &record.payload;
+                if !payload.document_body.is_empty()
+                    && collections.contains(&payload.collection_id.as_str())
+                {
+                    return Err(refuse(
+                        &path,
+                        format!("holds a body of collection {}", payload.collection_id),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn read_state(&self) -> RwLockReadGuard<'_, KeyringState> {
+        self.state.read().expect("Keyring lock poisoned")
+    }
+
+    fn write_state(&self) -> RwLockWriteGuard<'_, KeyringState> {
+        self.state.write().expect("Keyring lock poisoned")
+    }
+
+    fn load_tenant_key(&self, tenant_id: &str) -> StorageResult<EncryptionKey> {
+        let path = self.tenant_key_path(tenant_id);
+        let encoded = fs::read_to_string(&path).map_err(|e| {
+            StorageError::key_unavailable(format!(
+                "Tenant key for {} unavailable at {}: {}",
+                tenant_id,
+                path.display(),
+                e
+            ))
+        })?;
+        EncryptionKey::from_base64(&encoded)
+    }
+
+    fn unwrap_data_key(
+        &self,
+        collection: &str,
+        entry: &CollectionKeyEntry,
+    ) -> StorageResult<EncryptionKey> {
+        let tenant_key = self.load_tenant_key(&entry.tenant_id)?;
+        let wrapped = STANDARD.decode(&entry.wrapped_key).map_err(|e| {
+            StorageError::data_corruption(format!(
+                "Wrapped key for collection {} is not valid base64: {}",
+                collection, e
+            ))
+        })?;
+        let bytes = open(&tenant_key, &wrapped).map_err(|_| {
+            StorageError::key_unavailable(format!(
+                "Tenant key for {} does not unwrap the data key of collection {}",
+                entry.tenant_id, collection
+            ))
+        })?;
+        let bytes: [u8; KEY_LEN] = bytes.try_into().map_err(|_| {
+            StorageError::data_corruption(format!(
+                "Data key for collection {} has the wrong length",
+                collection
+            ))
+        })?;
+        Ok(EncryptionKey(bytes))
+    }
+
+    fn persist(&self, registry: &Registry) -> StorageResult<()> {
+        let bytes = serde_json::to_vec_pretty(registry).map_err(|e| {
+            StorageError::write_failed_no_source(format!(
+                "Failed to serialize collection key registry: {}",
+                e
+            ))
+        })?;
+        write_durable(&self.keys_dir.join(COLLECTION_KEYS_FILE), &bytes)
+    }
+}
+
+/// Collection part of a `collection:document` composite id.
+fn record_collection(record: &DocumentRecord) -> &str {
+    record
+        .document_id
+        .split_once(':')
+        .map(|(collection, _)| collection)
+        .unwrap_or("")
+}
+
+fn validate_tenant_id(tenant_id: &str) -> StorageResult<()> {
+    let valid = !tenant_id.is_empty()
+        && tenant_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(StorageError::write_failed_no_source(format!(
+            "Invalid tenant id: {:?}",
+            tenant_id
+        )))
+    }
+}
+
+/// Write a file atomically (temp file, fsync, rename, directory fsync).
+pub(super) fn write_durable(path: &Path, bytes: &[u8]) -> StorageResult<()> {
+    let dir = path.parent().expect("Key paths have a parent directory");
+    fs::create_dir_all(dir).map_err(|e| {
+        StorageError::write_failed(
+            format!("Failed to create key directory: {}", dir.display()),
+            e,
+        )
+    })?;
+
+    let tmp_path = path.with_extension("tmp");
+    let result = (|| {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        fsync_dir(dir)
+    })();
+    result.map_err(|e| StorageError::write_failed(format!("Failed to write {}", path.display()), e))
+}
+
+/// fsync a directory to ensure durability of renames and deletes.
+pub(super) fn fsync_dir(path: &Path) -> io::Result<()> {
+    OpenOptions::new().read(true).open(path)?.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn keyring_with_tenant(dir: &Path, tenant: &str) -> CollectionKeyring {
+        let keyring = CollectionKeyring::open(dir).unwrap();
+        keyring
+            .install_tenant_key(tenant, &EncryptionKey::generate())
+            .unwrap();
+        keyring
+    }
+
+    #[test]
+    fn test_seal_open_roundtrip() {
+        let key = EncryptionKey::generate();
+        let body = br#"{"_id": "doc1", "name": "Alice"}"#;
+
+        let sealed = seal(&key, body);
+        assert!(is_sealed(&sealed));
+        assert_ne!(&sealed[HEADER_LEN..HEADER_LEN + body.len()], body);
+        assert_eq!(open(&key, &sealed).unwrap(), body);
+    }
+
+    #[test]
+    fn test_open_rejects_tampering_and_wrong_key() {
+        let key = EncryptionKey::generate();
+        let mut sealed = seal(&key, b"secret body");
+
+        assert!(open(&EncryptionKey::generate(), &sealed).is_err());
+        sealed[HEADER_LEN] ^= 0x01;
+        let err = open(&key, &sealed).unwrap_err();
+        assert_eq!(err.code().code(), "AERO_DATA_CORRUPTION");
+    }
+
+    #[test]
+    fn test_collections_get_distinct_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let keyring = keyring_with_tenant(temp_dir.path(), "acme");
+        keyring.enable_collection("orders", "acme").unwrap();
+        keyring.enable_collection("invoices", "acme").unwrap();
+
+        let orders = keyring.entry("orders").unwrap();
+        let invoices = keyring.entry("invoices").unwrap();
+        assert_ne!(orders.wrapped_key, invoices.wrapped_key);
+
+        let sealed = keyring.encrypt_body("orders", b"{}").unwrap();
+        assert!(keyring.decrypt_body("invoices", &sealed).is_err());
+        assert_eq!(keyring.decrypt_body("orders", &sealed).unwrap(), b"{}");
+
+        // Unencrypted collections pass through
+        assert_eq!(keyring.encrypt_body("users", b"{}").unwrap(), b"{}");
+    }
+
+    #[test]
+    fn test_reopen_unwraps_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let sealed = {
+            let keyring = keyring_with_tenant(temp_dir.path(), "acme");
+            keyring.enable_collection("orders", "acme").unwrap();
+            keyring.encrypt_body("orders", b"body").unwrap()
+        };
+
+        let keyring = CollectionKeyring::open(temp_dir.path()).unwrap();
+        assert!(keyring.is_encrypted("orders"));
+        assert_eq!(keyring.decrypt_body("orders", &sealed).unwrap(), b"body");
+    }
+
+    #[test]
+    fn test_missing_tenant_key_fails_open() {
+        let temp_dir = TempDir::new().unwrap();
+        let key_path = {
+            let keyring = keyring_with_tenant(temp_dir.path(), "acme");
+            keyring.enable_collection("orders", "acme").unwrap();
+            keyring.tenant_key_path("acme")
+        };
+        fs::remove_file(key_path).unwrap();
+
+        let err = CollectionKeyring::open(temp_dir.path()).unwrap_err();
+        assert_eq!(err.code().code(), "AERO_ENCRYPTION_KEY_UNAVAILABLE");
+    }
+
+    #[test]
+    fn test_wrong_tenant_key_fails_open() {
+        let temp_dir = TempDir::new().unwrap();
+        let key_path = {
+            let keyring = keyring_with_tenant(temp_dir.path(), "acme");
+            keyring.enable_collection("orders", "acme").unwrap();
+            keyring.tenant_key_path("acme")
+        };
+        fs::write(key_path, EncryptionKey::generate().to_base64()).unwrap();
+
+        let err = CollectionKeyring::open(temp_dir.path()).unwrap_err();
+        assert_eq!(err.code().code(), "AERO_ENCRYPTION_KEY_UNAVAILABLE");
+    }
+
+    #[test]
+    fn test_erase_tenant_makes_data_unreadable() {
+        let temp_dir = TempDir::new().unwrap();
+        let keyring = keyring_with_tenant(temp_dir.path(), "acme");
+        keyring
+            .install_tenant_key("globex", &EncryptionKey::generate())
+            .unwrap();
+        keyring.enable_collection("orders", "acme").unwrap();
+        keyring.enable_collection("parts", "globex").unwrap();
+
+        let orders = keyring.encrypt_body("orders", b"acme data").unwrap();
+        let parts = keyring.encrypt_body("parts", b"globex data").unwrap();
+
+        assert_eq!(keyring.erase_tenant("acme").unwrap(), vec!["orders"]);
+        assert!(!keyring.tenant_key_path("acme").exists());
+
+        let err = keyring.decrypt_body("orders", &orders).unwrap_err();
+        assert_eq!(err.code().code(), "AERO_ENCRYPTION_KEY_UNAVAILABLE");
+        assert!(keyring.encrypt_body("orders", b"new").is_err());
+
+        // Boot no longer expects the erased tenant's key; other tenants unaffected
+        let reopened = CollectionKeyring::open(temp_dir.path()).unwrap();
+        assert_eq!(
+            reopened.entry("orders").unwrap().state,
+            CollectionKeyState::Erased
+        );
+        assert!(reopened.decrypt_body("orders", &orders).is_err());
+        assert_eq!(
+            reopened.decrypt_body("parts", &parts).unwrap(),
+            b"globex data"
+        );
+    }
+
+    #[test]
+    fn test_erase_refused_while_wal_holds_bodies() {
+        use crate::wal::{WalPayload, WalWriter};
+
+        let temp_dir = TempDir::new().unwrap();
+        let keyring = keyring_with_tenant(temp_dir.path(), "acme");
+        keyring.enable_collection("orders", "acme").unwrap();
+
+        let mut wal = WalWriter::open(temp_dir.path()).unwrap();
+        wal.append_insert(WalPayload::new(
+            "orders",
+            "o1",
+            "orders",
+            "v1",
+            b"plaintext".to_vec(),
+        ))
+        .unwrap();
+
+        let err = keyring.erase_tenant("acme").unwrap_err();
+        assert_eq!(err.code().code(), "AERO_STORAGE_WRITE_FAILED");
+        assert!(keyring.tenant_key_path("acme").exists());
+        assert!(keyring.is_encrypted("orders"));
+
+        // Checkpoint archived the segment: the copy still blocks erasure
+        let archive_dir = temp_dir.path().join(WAL_ARCHIVE_DIR);
+        fs::create_dir_all(&archive_dir).unwrap();
+        fs::copy(wal.path(), archive_dir.join("1-1.wal")).unwrap();
+        wal.truncate().unwrap();
+        assert!(keyring.erase_tenant("acme").is_err());
+
+        fs::remove_file(archive_dir.join("1-1.wal")).unwrap();
+        assert_eq!(keyring.erase_tenant("acme").unwrap(), vec!["orders"]);
+    }
+
+    #[test]
+    fn test_storage_seals_and_opens_bodies() {
+        use crate::storage::{StoragePayload, StorageReader, StorageWriter};
+        use std::sync::Arc;
+
+        let temp_dir = TempDir::new().unwrap();
+        let keyring = Arc::new(keyring_with_tenant(temp_dir.path(), "acme"));
+        keyring.enable_collection("orders", "acme").unwrap();
+
+        let body = br#"{"_id": "o1", "total": 42}"#.to_vec();
+        let offset = {
+            let mut writer = StorageWriter::open(temp_dir.path()).unwrap();
+            writer.set_keyring(Arc::clone(&keyring));
+            writer
+                .write(&StoragePayload::new(
+                    "orders",
+                    "o1",
+                    "orders",
+                    "v1",
+                    body.clone(),
+                ))
+                .unwrap()
+        };
+
+        // Bodies are sealed on disk
+        let mut raw = StorageReader::open_from_data_dir(temp_dir.path()).unwrap();
+        assert!(is_sealed(&raw.read_at(offset).unwrap().document_body));
+
+        let mut reader = StorageReader::open_from_data_dir(temp_dir.path()).unwrap();
+        reader.set_keyring(Arc::clone(&keyring));
+        assert_eq!(reader.read_at(offset).unwrap().document_body, body);
+
+        keyring.erase_tenant("acme").unwrap();
+        let err = reader.read_at(offset).unwrap_err();
+        assert_eq!(err.code().code(), "AERO_ENCRYPTION_KEY_UNAVAILABLE");
+    }
+
+    #[test]
+    fn test_enable_requires_tenant_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let keyring = CollectionKeyring::open(temp_dir.path()).unwrap();
+        let err = keyring.enable_collection("orders", "acme").unwrap_err();
+        assert_eq!(err.code().code(), "AERO_ENCRYPTION_KEY_UNAVAILABLE");
+        assert!(keyring.enable_collection("orders", "../acme").is_err());
+    }
+}
@@ -5,6 +5,8 @@
 //! - AERO_STORAGE_WRITE_FAILED (ERROR severity)
 //! - AERO_STORAGE_READ_FAILED (ERROR severity)
 //! - AERO_DATA_CORRUPTION (FATAL severity) - from CORRUPTION category
+//! - AERO_ENCRYPTION_KEY_UNAVAILABLE (ERROR severity)
+//! - AERO_STORAGE_FORMAT_UNSUPPORTED (FATAL severity)
 
 use std::fmt;
 use std::io;
@@ -38,6 +40,10 @@ pub enum StorageErrorCode {
     AeroStorageReadFailed,
     /// Data checksum failure (from CORRUPTION category)
     AeroDataCorruption,
+    /// Collection key missing, wrong, or erased
+    AeroEncryptionKeyUnavailable,
+    /// Storage file format this build cannot read
+    AeroStorageFormatUnsupported,
 }
 
 impl StorageErrorCode {
@@ -48,6 +54,8 @@ impl StorageErrorCode {
             StorageErrorCode::AeroStorageWriteFailed => "AERO_STORAGE_WRITE_FAILED",
             StorageErrorCode::AeroStorageReadFailed => "AERO_STORAGE_READ_FAILED",
             StorageErrorCode::AeroDataCorruption => "AERO_DATA_CORRUPTION",
+            StorageErrorCode::AeroEncryptionKeyUnavailable => "AERO_ENCRYPTION_KEY_UNAVAILABLE",
+            StorageErrorCode::AeroStorageFormatUnsupported => "AERO_STORAGE_FORMAT_UNSUPPORTED",
         }
     }
 
@@ -58,6 +66,8 @@ impl StorageErrorCode {
             StorageErrorCode::AeroStorageWriteFailed => Severity::Error,
             StorageErrorCode::AeroStorageReadFailed => Severity::Error,
             StorageErrorCode::AeroDataCorruption => Severity::Fatal,
+            StorageErrorCode::AeroEncryptionKeyUnavailable => Severity::Error,
+            StorageErrorCode::AeroStorageFormatUnsupported => Severity::Fatal,
         }
     }
 
@@ -68,6 +78,8 @@ impl StorageErrorCode {
             StorageErrorCode::AeroStorageWriteFailed => Some("D1"),
             StorageErrorCode::AeroStorageReadFailed => None,
             StorageErrorCode::AeroDataCorruption => Some("D2"),
+            StorageErrorCode::AeroEncryptionKeyUnavailable => None,
+            StorageErrorCode::AeroStorageFormatUnsupported => None,
         }
     }
 }
@@ -142,6 +154,26 @@ impl StorageError {
         }
     }
 
+    /// Create an encryption key unavailable error
+    pub fn key_unavailable(message: impl Into<String>) -> Self {
+        Self {
+            code: StorageErrorCode::AeroEncryptionKeyUnavailable,
+            message: message.into(),
+            details: None,
+            source: None,
+        }
+    }
+
+    /// Create an unsupported storage format error (FATAL)
+    pub fn format_unsupported(message: impl Into<String>) -> Self {
+        Self {
+            code: StorageErrorCode::AeroStorageFormatUnsupported,
+            message: message.into(),
+            details: None,
+            source: None,
+        }
+    }
+
     /// Create a data corruption error with byte offset context
     pub fn corruption_at_offset(offset: u64, reason: impl Into<String>) -> Self {
         Self {
@@ -245,6 +277,14 @@ mod tests {
             StorageErrorCode::AeroDataCorruption.code(),
             "AERO_DATA_CORRUPTION"
         );
+        assert_eq!(
+            StorageErrorCode::AeroEncryptionKeyUnavailable.code(),
+            "AERO_ENCRYPTION_KEY_UNAVAILABLE"
+        );
+        assert_eq!(
+            StorageErrorCode::AeroStorageFormatUnsupported.code(),
+            "AERO_STORAGE_FORMAT_UNSUPPORTED"
+        );
     }
 
     #[test]
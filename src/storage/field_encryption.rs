@@ -0,0 +1,559 @@
+//! Field-level encryption of document values
+//!
+//! Schemas mark fields `encrypted: true`. Before a document is serialized
+//! for the WAL and storage, the value of each such field is replaced by an
+//! envelope holding its AES-256-GCM ciphertext and the id of the key it was
+//! sealed under:
+//!
+//! ```json
+//! {"ssn": {"$encrypted": {"kid": "k2", "data": "<base64 nonce | ciphertext | tag>"}}}
+//! ```
+//!
+//! Reads open every envelope in a document, whatever schema version wrote
+//! it, using the key its id names. Keys come from a `KeyProvider`; rotating
+//! to a new active key leaves values sealed under older keys readable for
+//! as long as those keys are kept.
+//!
+//! The document id and field path are bound into each value as associated
+//! data, so a ciphertext copied to another field or document fails to open.
+//!
+//! `FieldKeyring` keeps keys under the data directory:
+//! - `keys/fields/<key_id>.key` — key material (base64, 32 bytes)
+//! - `keys/fields/active` — id of the key new values are sealed under
+//!
+//! Function secrets are sealed under a keyring of their own, kept the same
+//! way in `keys/secrets/` (`FieldKeyring::open_secrets`).
+//!
+//! # Scope
+//!
+//! - `_id` and unique fields cannot be encrypted
+//! - Indexes would hold the stored envelopes, so encrypted fields cannot be
+//!   indexed, and therefore not filtered on
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use rand::RngCore;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use serde_json::{json, Map, Value};
+
+use super::encryption::{write_durable, EncryptionKey, KEYS_DIR};
+use super::errors::{StorageError, StorageResult};
+
+/// Field key directory name within the key directory
+pub const FIELD_KEYS_DIR: &str = "fields";
+
+/// Function secret key directory name within the key directory
+pub const SECRET_KEYS_DIR: &str = "secrets";
+
+/// File naming the active field key, within the field key directory
+pub const ACTIVE_KEY_FILE: &str = "active";
+
+/// Envelope member marking an encrypted value
+pub const ENVELOPE_KEY: &str = "$encrypted";
+
+const AAD_PREFIX: &[u8] = b"aerodb-field:v1";
+
+/// Source of field encryption keys
+pub trait KeyProvider: Send + Sync {
+    /// Id of the key new values are sealed under, if any
+    fn active_key_id(&self) -> Option<String>;
+
+    /// Key material for `key_id`, if known
+    fn key(&self, key_id: &str) -> Option<EncryptionKey>;
+}
+
+#[derive(Debug, Default)]
+struct KeyringState {
+    keys: BTreeMap<String, EncryptionKey>,
+    active: Option<String>,
+}
+
+/// Field encryption keys, kept in memory or under the data directory
+#[derive(Debug)]
+pub struct FieldKeyring {
+    /// Key directory, or `None` to keep keys in memory
+    dir: Option<PathBuf>,
+    /// What the keys are for, in error messages
+    label: &'static str,
+    state: RwLock<KeyringState>,
+}
+
+impl Default for FieldKeyring {
+    fn default() -> Self {
+        Self::labelled("field")
+    }
+}
+
+impl FieldKeyring {
+    /// Keyring kept in memory
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Empty in-memory keyring whose errors name its keys `label` keys
+    pub(super) fn labelled(label: &'static str) -> Self {
+        Self {
+            dir: None,
+            label,
+            state: RwLock::default(),
+        }
+    }
+
+    /// Open the keyring in `<data_dir>/keys/fields`
+    ///
+    /// A data directory without field keys has none.
+    ///
+    /// # Errors
+    ///
+    /// `AERO_ENCRYPTION_KEY_UNAVAILABLE` if a key file is malformed or the
+    /// active key has no key file.
+    pub fn open(data_dir: &Path) -> StorageResult<Self> {
+        Self::open_in(data_dir.join(KEYS_DIR).join(FIELD_KEYS_DIR), "field")
+    }
+
+    /// Open the keyring sealing function secrets, in `<data_dir>/keys/secrets`
+    ///
+    /// # Errors
+    ///
+    /// As `open`.
+    pub fn open_secrets(data_dir: &Path) -> StorageResult<Self> {
+        Self::open_in(data_dir.join(KEYS_DIR).join(SECRET_KEYS_DIR), "secret")
+    }
+
+    /// Open a keyring kept in `dir`, see `open`
+    pub(super) fn open_in(dir: PathBuf, label: &'static str) -> StorageResult<Self> {
+        let mut state = KeyringState::default();
+
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self {
+                    dir: Some(dir),
+                    label,
+                    state: RwLock::new(state),
+                })
+            }
+            Err(e) => {
+                return Err(StorageError::read_failed(
+                    format!("Failed to read {} key directory: {}", label, dir.display()),
+                    e,
+                ))
+            }
+        };
+        for entry in entries {
+            let path = entry
+                .map_err(|e| {
+                    StorageError::read_failed(
+                        format!("Failed to read {} key directory: {}", label, dir.display()),
+                        e,
+                    )
+                })?
+                .path();
+            let key_id = match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) => match name.strip_suffix(".key") {
+                    Some(key_id) => key_id.to_string(),
+                    None => continue,
+                },
+                None => continue,
+            };
+            let key = EncryptionKey::from_base64(&read_key_file(&path)?).map_err(|e| {
+                StorageError::key_unavailable(format!(
+                    "Invalid {} key {}: {}",
+                    label,
+                    path.display(),
+                    e.message()
+                ))
+            })?;
+            state.keys.insert(key_id, key);
+        }
+
+        let active_path = dir.join(ACTIVE_KEY_FILE);
+        if active_path.exists() {
+            let active = read_key_file(&active_path)?.trim().to_string();
+            if !state.keys.contains_key(&active) {
+                return Err(StorageError::key_unavailable(format!(
+                    "Active {} key has no key file: {}",
+                    label, active
+                )));
+            }
+            state.active = Some(active);
+        }
+
+        Ok(Self {
+            dir: Some(dir),
+            label,
+            state: RwLock::new(state),
+        })
+    }
+
+    /// Store key material under `key_id` (bring-your-own-key)
+    ///
+    /// Replacing a key is rejected: values sealed under it would no longer
+    /// open.
+    pub fn install_key(&self, key_id: &str, key: &EncryptionKey) -> StorageResult<()> {
+        validate_key_id(key_id, self.label)?;
+        let mut state = self.state.write().unwrap_or_else(|e| e.into_inner());
+        if state.keys.contains_key(key_id) {
+            return Err(StorageError::write_failed_no_source(format!(
+                "Key already installed: {}",
+                key_id
+            )));
+        }
+        if let Some(dir) = &self.dir {
+            write_durable(
+                &dir.join(format!("{}.key", key_id)),
+                key.to_base64().as_bytes(),
+            )?;
+        }
+        state.keys.insert(key_id.to_string(), key.clone());
+        Ok(())
+    }
+
+    /// Seal new values under `key_id`
+    pub fn activate(&self, key_id: &str) -> StorageResult<()> {
+        let mut state = self.state.write().unwrap_or_else(|e| e.into_inner());
+        if !state.keys.contains_key(key_id) {
+            return Err(StorageError::key_unavailable(format!(
+                "Unknown {} key: {}",
+                self.label, key_id
+            )));
+        }
+        if let Some(dir) = &self.dir {
+            write_durable(&dir.join(ACTIVE_KEY_FILE), key_id.as_bytes())?;
+        }
+        state.active = Some(key_id.to_string());
+        Ok(())
+    }
+
+    /// Install a fresh random key under `key_id` and make it the active one
+    pub fn rotate(&self, key_id: &str) -> StorageResult<()> {
+        self.install_key(key_id, &EncryptionKey::generate())?;
+        self.activate(key_id)
+    }
+}
+
+impl KeyProvider for FieldKeyring {
+    fn active_key_id(&self) -> Option<String> {
+        let state = self.state.read().unwrap_or_else(|e| e.into_inner());
+        state.active.clone()
+    }
+
+    fn key(&self, key_id: &str) -> Option<EncryptionKey> {
+        let state = self.state.read().unwrap_or_else(|e| e.into_inner());
+        state.keys.get(key_id).cloned()
+    }
+}
+
+/// Seal the values at `paths` (dotted, e.g. `address.street`) in place
+///
+/// Absent and null values are left as they are.
+///
+/// # Errors
+///
+/// `AERO_ENCRYPTION_KEY_UNAVAILABLE` if there is a value to seal and the
+/// provider has no active key.
+pub fn encrypt_fields(
+    keys: &dyn KeyProvider,
+    document: &mut Value,
+    paths: &[String],
+) -> StorageResult<()> {
+    let doc_id = document_id(document);
+    let mut active: Option<(String, LessSafeKey)> = None;
+    for path in paths {
+        let Some(value) = lookup_mut(document, path) else {
+            continue;
+        };
+        if value.is_null() || is_envelope(value) {
+            continue;
+        }
+        if active.is_none() {
+            let key_id = keys
+                .active_key_id()
+                .ok_or_else(|| StorageError::key_unavailable("No active field encryption key"))?;
+            let key = keys.key(&key_id).ok_or_else(|| {
+                StorageError::key_unavailable(format!("Unknown field key: {}", key_id))
+            })?;
+            active = Some((key_id, aead_key(&key)));
+        }
+        let (key_id, key) = active.as_ref().expect("Active key loaded above");
+
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let mut data = serde_json::to_vec(value).expect("JSON values serialize");
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(associated_data(&doc_id, path)),
+            &mut data,
+        )
+        .map_err(|_| StorageError::write_failed_no_source("Field encryption failed"))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&data);
+        *value = json!({ENVELOPE_KEY: {"kid": key_id, "data": STANDARD.encode(sealed)}});
+    }
+    Ok(())
+}
+
+/// Open every sealed value in `document` in place
+///
+/// # Errors
+///
+/// - `AERO_ENCRYPTION_KEY_UNAVAILABLE` if a value's key is unknown
+/// - `AERO_DATA_CORRUPTION` if an envelope is malformed or fails
+///   authentication
+pub fn decrypt_fields(keys: &dyn KeyProvider, document: &mut Value) -> StorageResult<()> {
+    let doc_id = document_id(document);
+    match document {
+        Value::Object(fields) => open_object(keys, &doc_id, "", fields),
+        _ => Ok(()),
+    }
+}
+
+/// Whether `document` holds any sealed value
+pub fn has_encrypted_fields(document: &Value) -> bool {
+    match document {
+        Value::Object(fields) => fields
+            .values()
+            .any(|value| is_envelope(value) || has_encrypted_fields(value)),
+        _ => false,
+    }
+}
+
+fn open_object(
+    keys: &dyn KeyProvider,
+    doc_id: &str,
+    prefix: &str,
+    fields: &mut Map<String, Value>,
+) -> StorageResult<()> {
+    for (name, value) in fields.iter_mut() {
+        let path = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        if is_envelope(value) {
+            *value = open_value(keys, doc_id, &path, value)?;
+        } else if let Value::Object(nested) = value {
+            open_object(keys, doc_id, &path, nested)?;
+        }
+    }
+    Ok(())
+}
+
+fn open_value(
+    keys: &dyn KeyProvider,
+    doc_id: &str,
+    path: &str,
+    envelope: &Value,
+) -> StorageResult<Value> {
+    let malformed = || {
+        StorageError::corruption_for_document(
+            doc_id,
+            format!("Malformed encrypted field '{}'", path),
+        )
+    };
+    let sealed = &envelope[ENVELOPE_KEY];
+    let key_id = sealed["kid"].as_str().ok_or_else(malformed)?;
+    let mut data = sealed["data"]
+        .as_str()
+        .and_then(|data| STANDARD.decode(data).ok())
+        .filter(|data| data.len() > NONCE_LEN)
+        .ok_or_else(malformed)?;
+    let key = keys.key(key_id).ok_or_else(|| {
+        StorageError::key_unavailable(format!(
+            "Field key {} for '{}' is unavailable",
+            key_id, path
+        ))
+    })?;
+
+    let nonce = Nonce::try_assume_unique_for_key(&data[..NONCE_LEN]).map_err(|_| malformed())?;
+    let plaintext = aead_key(&key)
+        .open_in_place(
+            nonce,
+            Aad::from(associated_data(doc_id, path)),
+            &mut data[NONCE_LEN..],
+        )
+        .map_err(|_| {
+            StorageError::corruption_for_document(
+                doc_id,
+                format!("Encrypted field '{}' failed authentication", path),
+            )
+        })?;
+    serde_json::from_slice(plaintext).map_err(|_| malformed())
+}
+
+/// Whether `value` is a sealed value's envelope
+fn is_envelope(value: &Value) -> bool {
+    match value {
+        Value::Object(fields) => fields.len() == 1 && fields.contains_key(ENVELOPE_KEY),
+        _ => false,
+    }
+}
+
+/// Value at a dotted path, through nested objects only
+fn lookup_mut<'a>(document: &'a mut Value, path: &str) -> Option<&'a mut Value> {
+    path.split('.')
+        .try_fold(document, |value, name| value.as_object_mut()?.get_mut(name))
+}
+
+fn document_id(document: &Value) -> String {
+    document["_id"].as_str().unwrap_or_default().to_string()
+}
+
+fn associated_data(doc_id: &str, path: &str) -> Vec<u8> {
+    let mut aad = AAD_PREFIX.to_vec();
+    for part in [doc_id, path] {
+        aad.push(0);
+        aad.extend_from_slice(part.as_bytes());
+    }
+    aad
+}
+
+pub(super) fn aead_key(key: &EncryptionKey) -> LessSafeKey {
+    LessSafeKey::new(UnboundKey::new(&AES_256_GCM, key.as_bytes()).expect("Keys are 256 bits"))
+}
+
+fn read_key_file(path: &Path) -> StorageResult<String> {
+    fs::read_to_string(path).map_err(|e| {
+        StorageError::read_failed(format!("Failed to read key file: {}", path.display()), e)
+    })
+}
+
+/// Key ids end up in file names, so keep them to `[A-Za-z0-9_-]`
+fn validate_key_id(key_id: &str, label: &str) -> StorageResult<()> {
+    let valid = !key_id.is_empty()
+        && key_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(StorageError::write_failed_no_source(format!(
+            "Invalid {} key id: {:?}",
+            label, key_id
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::errors::StorageErrorCode;
+    use tempfile::TempDir;
+
+    fn keyring() -> FieldKeyring {
+        let keyring = FieldKeyring::new();
+        keyring.rotate("k1").unwrap();
+        keyring
+    }
+
+    fn paths(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn test_roundtrip_nested_and_typed_values() {
+        let keys = keyring();
+        let original = json!({
+            "_id": "u1",
+            "name": "Ada",
+            "ssn": "123-45-6789",
+            "salary": 120000,
+            "address": {"city": "London", "street": "1 Main St"},
+            "nickname": null
+        });
+        let mut doc = original.clone();
+        encrypt_fields(
+            &keys,
+            &mut doc,
+            &paths(&["ssn", "salary", "address.street", "nickname", "missing"]),
+        )
+        .unwrap();
+
+        assert_eq!(doc["name"], "Ada");
+        assert_eq!(doc["address"]["city"], "London");
+        assert_eq!(doc["ssn"][ENVELOPE_KEY]["kid"], "k1");
+        assert!(is_envelope(&doc["address"]["street"]));
+        assert!(doc["nickname"].is_null());
+        assert!(!serde_json::to_string(&doc).unwrap().contains("6789"));
+        assert!(has_encrypted_fields(&doc));
+
+        decrypt_fields(&keys, &mut doc).unwrap();
+        assert_eq!(doc, original);
+        assert!(!has_encrypted_fields(&doc));
+    }
+
+    #[test]
+    fn test_rotation_keeps_old_values_readable() {
+        let keys = keyring();
+        let mut old = json!({"_id": "u1", "ssn": "old"});
+        encrypt_fields(&keys, &mut old, &paths(&["ssn"])).unwrap();
+
+        keys.rotate("k2").unwrap();
+        let mut new = json!({"_id": "u2", "ssn": "new"});
+        encrypt_fields(&keys, &mut new, &paths(&["ssn"])).unwrap();
+        assert_eq!(new["ssn"][ENVELOPE_KEY]["kid"], "k2");
+
+        decrypt_fields(&keys, &mut old).unwrap();
+        decrypt_fields(&keys, &mut new).unwrap();
+        assert_eq!(old["ssn"], "old");
+        assert_eq!(new["ssn"], "new");
+    }
+
+    #[test]
+    fn test_missing_and_wrong_keys() {
+        let mut doc = json!({"_id": "u1", "ssn": "secret"});
+        let err = encrypt_fields(&FieldKeyring::new(), &mut doc, &paths(&["ssn"])).unwrap_err();
+        assert_eq!(err.code(), StorageErrorCode::AeroEncryptionKeyUnavailable);
+
+        encrypt_fields(&keyring(), &mut doc, &paths(&["ssn"])).unwrap();
+        // Another keyring's k1 is a different key
+        let err = decrypt_fields(&keyring(), &mut doc.clone()).unwrap_err();
+        assert_eq!(err.code(), StorageErrorCode::AeroDataCorruption);
+        let err = decrypt_fields(&FieldKeyring::new(), &mut doc).unwrap_err();
+        assert_eq!(err.code(), StorageErrorCode::AeroEncryptionKeyUnavailable);
+    }
+
+    #[test]
+    fn test_ciphertext_bound_to_document_and_field() {
+        let keys = keyring();
+        let mut doc = json!({"_id": "u1", "ssn": "secret", "pin": "1234"});
+        encrypt_fields(&keys, &mut doc, &paths(&["ssn", "pin"])).unwrap();
+
+        let mut swapped = doc.clone();
+        swapped["pin"] = doc["ssn"].clone();
+        assert!(decrypt_fields(&keys, &mut swapped).is_err());
+
+        let mut moved = doc.clone();
+        moved["_id"] = json!("u2");
+        assert!(decrypt_fields(&keys, &mut moved).is_err());
+    }
+
+    #[test]
+    fn test_keyring_survives_reopen() {
+        let dir = TempDir::new().unwrap();
+        let keys = FieldKeyring::open(dir.path()).unwrap();
+        assert!(keys.active_key_id().is_none());
+        keys.rotate("k1").unwrap();
+        keys.rotate("k2").unwrap();
+        assert!(keys.install_key("k1", &EncryptionKey::generate()).is_err());
+        assert!(keys
+            .install_key("../k3", &EncryptionKey::generate())
+            .is_err());
+        let mut doc = json!({"_id": "u1", "ssn": "secret"});
+        encrypt_fields(&keys, &mut doc, &paths(&["ssn"])).unwrap();
+        drop(keys);
+
+        let keys = FieldKeyring::open(dir.path()).unwrap();
+        assert_eq!(keys.active_key_id().as_deref(), Some("k2"));
+        assert!(keys.key("k1").is_some());
+        decrypt_fields(&keys, &mut doc).unwrap();
+        assert_eq!(doc["ssn"], "secret");
+    }
+}
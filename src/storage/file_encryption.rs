@@ -0,0 +1,439 @@
+//! Whole-file encryption of storage and the WAL
+//!
+//! With a master key active, new `documents.dat` and `wal.log` files are
+//! written encrypted. Each file gets its own random data key, kept at the
+//! start of the file wrapped (AES-256-GCM) under the master key:
+//!
+//! ```text
+//! magic "AEROENC1" | key id length u8 | master key id (32, zero padded) |
+//! wrapped data key (nonce 12 | key 32 | tag 16)
+//! ```
+//!
+//! Records follow as sealed frames, each under a fresh random nonce:
+//!
+//! ```text
+//! frame length u32 LE | nonce (12) | ciphertext of the plain record | tag (16)
+//! ```
+//!
+//! The frame length covers the whole frame, so record boundaries can be
+//! found without the key. The frame's file offset is bound in as associated
+//! data: a frame moved within or between files fails to open.
+//!
+//! Files describe their own keys, so snapshots and backups, which copy them
+//! byte for byte, stay encrypted and need only the master key to restore.
+//! An encrypted file is refused with `AERO_ENCRYPTION_KEY_UNAVAILABLE` when
+//! its master key is missing, rather than read as corrupt.
+//!
+//! `MasterKeyring` keeps master keys under the data directory like field
+//! keys:
+//! - `keys/master/<key_id>.key` — key material (base64, 32 bytes)
+//! - `keys/master/active` — id of the key new files are wrapped under
+//!
+//! # Rotation
+//!
+//! Rotating the master key changes the key new files are wrapped under; a
+//! checkpoint truncates the WAL into a new file. `StorageWriter::reencrypt`
+//! rewrites storage under a fresh data key, and a re-encrypting checkpoint
+//! (`CheckpointManager::create_reencrypting_checkpoint`) does so before its
+//! snapshot. Older master keys must be kept until no file needs them.
+//!
+//! Existing plain files stay plain until they are rewritten.
+
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use rand::RngCore;
+use ring::aead::{Aad, LessSafeKey, Nonce, NONCE_LEN};
+
+use super::encryption::{EncryptionKey, KEYS_DIR, KEY_LEN};
+use super::errors::{StorageError, StorageResult};
+use super::field_encryption::{aead_key, FieldKeyring, KeyProvider};
+
+/// Master key directory name within the key directory
+pub const MASTER_KEYS_DIR: &str = "master";
+
+/// Magic bytes opening an encrypted file
+pub const KEY_HEADER_MAGIC: &[u8; 8] = b"AEROENC1";
+
+/// Longest master key id a file header can name
+pub const MAX_MASTER_KEY_ID_LEN: usize = 32;
+
+const TAG_LEN: usize = 16;
+const WRAPPED_KEY_LEN: usize = NONCE_LEN + KEY_LEN + TAG_LEN;
+
+/// Size of the key header at the start of an encrypted file
+pub const KEY_HEADER_SIZE: u64 =
+    (KEY_HEADER_MAGIC.len() + 1 + MAX_MASTER_KEY_ID_LEN + WRAPPED_KEY_LEN) as u64;
+
+/// Bytes a sealed frame adds to the record it holds
+pub const FRAME_OVERHEAD: usize = 4 + NONCE_LEN + TAG_LEN;
+
+const WRAP_AAD: &[u8] = b"aerodb-file-key:v1";
+const FRAME_AAD: &[u8] = b"aerodb-file-frame:v1";
+
+/// Master keys wrapping the data keys of encrypted files
+#[derive(Debug)]
+pub struct MasterKeyring {
+    keys: FieldKeyring,
+}
+
+impl Default for MasterKeyring {
+    fn default() -> Self {
+        Self {
+            keys: FieldKeyring::labelled("master"),
+        }
+    }
+}
+
+impl MasterKeyring {
+    /// Keyring kept in memory
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open the keyring in `<data_dir>/keys/master`
+    ///
+    /// A data directory without master keys has none, and writes plain
+    /// files.
+    ///
+    /// # Errors
+    ///
+    /// `AERO_ENCRYPTION_KEY_UNAVAILABLE` if a key file is malformed or the
+    /// active key has no key file.
+    pub fn open(data_dir: &Path) -> StorageResult<Self> {
+        Ok(Self {
+            keys: FieldKeyring::open_in(data_dir.join(KEYS_DIR).join(MASTER_KEYS_DIR), "master")?,
+        })
+    }
+
+    /// Store master key material under `key_id` (bring-your-own-key)
+    pub fn install_key(&self, key_id: &str, key: &EncryptionKey) -> StorageResult<()> {
+        validate_master_key_id(key_id)?;
+        self.keys.install_key(key_id, key)
+    }
+
+    /// Wrap data keys of new files under `key_id`
+    pub fn activate(&self, key_id: &str) -> StorageResult<()> {
+        self.keys.activate(key_id)
+    }
+
+    /// Install a fresh random master key under `key_id` and activate it
+    pub fn rotate(&self, key_id: &str) -> StorageResult<()> {
+        self.install_key(key_id, &EncryptionKey::generate())?;
+        self.activate(key_id)
+    }
+
+    /// Id of the master key new files are wrapped under, if any
+    pub fn active_key_id(&self) -> Option<String> {
+        self.keys.active_key_id()
+    }
+
+    /// Whether new files are written encrypted
+    pub fn is_enabled(&self) -> bool {
+        self.active_key_id().is_some()
+    }
+
+    /// Generate a data key for a new file, wrapped under the active master
+    /// key
+    ///
+    /// # Errors
+    ///
+    /// `AERO_ENCRYPTION_KEY_UNAVAILABLE` if no master key is active.
+    pub fn new_file_key(&self) -> StorageResult<FileKey> {
+        let key_id = self
+            .active_key_id()
+            .ok_or_else(|| StorageError::key_unavailable("No active master key"))?;
+        let master = self.master_key(&key_id)?;
+        let data_key = EncryptionKey::generate();
+
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let mut wrapped = data_key.as_bytes().to_vec();
+        aead_key(&master)
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(wrap_aad(&key_id)),
+                &mut wrapped,
+            )
+            .map_err(|_| StorageError::write_failed_no_source("Data key wrapping failed"))?;
+
+        let mut header = KEY_HEADER_MAGIC.to_vec();
+        header.push(key_id.len() as u8);
+        let mut padded_id = [0u8; MAX_MASTER_KEY_ID_LEN];
+        padded_id[..key_id.len()].copy_from_slice(key_id.as_bytes());
+        header.extend_from_slice(&padded_id);
+        header.extend_from_slice(&nonce);
+        header.extend_from_slice(&wrapped);
+
+        Ok(FileKey {
+            key: aead_key(&data_key),
+            master_key_id: key_id,
+            header,
+        })
+    }
+
+    /// Unwrap the data key of a file from its key header
+    ///
+    /// # Errors
+    ///
+    /// - `AERO_ENCRYPTION_KEY_UNAVAILABLE` if the master key is unknown
+    /// - `AERO_DATA_CORRUPTION` if the header is malformed or fails
+    ///   authentication
+    pub fn open_file_key(&self, header: &[u8]) -> StorageResult<FileKey> {
+        let malformed = |reason: &str| StorageError::corruption_at_offset(0, reason.to_string());
+        if header.len() != KEY_HEADER_SIZE as usize || !header.starts_with(KEY_HEADER_MAGIC) {
+            return Err(malformed("Malformed encryption key header"));
+        }
+        let id_start = KEY_HEADER_MAGIC.len() + 1;
+        let id_len = header[KEY_HEADER_MAGIC.len()] as usize;
+        if id_len == 0 || id_len > MAX_MASTER_KEY_ID_LEN {
+            return Err(malformed(
+                "Malformed master key id in encryption key header",
+            ));
+        }
+        let key_id = std::str::from_utf8(&header[id_start..id_start + id_len])
+            .map_err(|_| malformed("Malformed master key id in encryption key header"))?
+            .to_string();
+        let master = self.master_key(&key_id)?;
+
+        let wrapped_start = id_start + MAX_MASTER_KEY_ID_LEN;
+        let nonce =
+            Nonce::try_assume_unique_for_key(&header[wrapped_start..wrapped_start + NONCE_LEN])
+                .map_err(|_| malformed("Malformed encryption key header"))?;
+        let mut wrapped = header[wrapped_start + NONCE_LEN..].to_vec();
+        let data_key = aead_key(&master)
+            .open_in_place(nonce, Aad::from(wrap_aad(&key_id)), &mut wrapped)
+            .map_err(|_| malformed("Data key failed authentication"))?;
+        let data_key: [u8; KEY_LEN] = data_key
+            .try_into()
+            .map_err(|_| malformed("Malformed encryption key header"))?;
+
+        Ok(FileKey {
+            key: aead_key(&EncryptionKey::from_bytes(data_key)),
+            master_key_id: key_id,
+            header: header.to_vec(),
+        })
+    }
+
+    fn master_key(&self, key_id: &str) -> StorageResult<EncryptionKey> {
+        self.keys.key(key_id).ok_or_else(|| {
+            StorageError::key_unavailable(format!("Master key {} is unavailable", key_id))
+        })
+    }
+}
+
+/// Data key of one encrypted file
+pub struct FileKey {
+    key: LessSafeKey,
+    master_key_id: String,
+    header: Vec<u8>,
+}
+
+impl FileKey {
+    /// Key header to write at the start of the file
+    pub fn header(&self) -> &[u8] {
+        &self.header
+    }
+
+    /// Id of the master key the data key is wrapped under
+    pub fn master_key_id(&self) -> &str {
+        &self.master_key_id
+    }
+
+    /// Seal a plain record into the frame written at `offset`
+    pub fn seal_frame(&self, offset: u64, record: &[u8]) -> Vec<u8> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let mut sealed = record.to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(frame_aad(offset)),
+                &mut sealed,
+            )
+            .expect("Records are far below the AES-GCM size limit");
+
+        let frame_len = (FRAME_OVERHEAD + record.len()) as u32;
+        let mut frame = frame_len.to_le_bytes().to_vec();
+        frame.extend_from_slice(&nonce);
+        frame.extend_from_slice(&sealed);
+        frame
+    }
+
+    /// Open the frame read at `offset`, length prefix included
+    ///
+    /// # Errors
+    ///
+    /// `AERO_DATA_CORRUPTION` if the frame is malformed or fails
+    /// authentication.
+    pub fn open_frame(&self, offset: u64, frame: &[u8]) -> StorageResult<Vec<u8>> {
+        if frame.len() < FRAME_OVERHEAD {
+            return Err(StorageError::corruption_at_offset(
+                offset,
+                format!("Encrypted frame of {} bytes is too short", frame.len()),
+            ));
+        }
+        let nonce = Nonce::try_assume_unique_for_key(&frame[4..4 + NONCE_LEN])
+            .expect("Nonce slice has nonce length");
+        let mut sealed = frame[4 + NONCE_LEN..].to_vec();
+        let len = self
+            .key
+            .open_in_place(nonce, Aad::from(frame_aad(offset)), &mut sealed)
+            .map_err(|_| {
+                StorageError::corruption_at_offset(offset, "Encrypted frame failed authentication")
+            })?
+            .len();
+        sealed.truncate(len);
+        Ok(sealed)
+    }
+}
+
+impl fmt::Debug for FileKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileKey")
+            .field("master_key_id", &self.master_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Read the key header at the start of `file`, if it is encrypted
+///
+/// Leaves the position just past the header of an encrypted file, and
+/// anywhere for a plain one.
+pub fn read_key_header<R: Read + Seek>(file: &mut R) -> io::Result<Option<Vec<u8>>> {
+    file.seek(SeekFrom::Start(0))?;
+    let mut header = vec![0u8; KEY_HEADER_SIZE as usize];
+    let mut filled = 0;
+    while filled < header.len() {
+        match file.read(&mut header[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    if filled < KEY_HEADER_MAGIC.len() || !header.starts_with(KEY_HEADER_MAGIC) {
+        return Ok(None);
+    }
+    if filled < header.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Truncated encryption key header",
+        ));
+    }
+    Ok(Some(header))
+}
+
+/// Error for an encrypted file opened without master keys
+pub(super) fn missing_keys(path: &Path) -> StorageError {
+    StorageError::key_unavailable(format!(
+        "{} is encrypted; open it with the master keyring",
+        path.display()
+    ))
+}
+
+fn wrap_aad(key_id: &str) -> Vec<u8> {
+    let mut aad = WRAP_AAD.to_vec();
+    aad.push(0);
+    aad.extend_from_slice(key_id.as_bytes());
+    aad
+}
+
+fn frame_aad(offset: u64) -> Vec<u8> {
+    let mut aad = FRAME_AAD.to_vec();
+    aad.extend_from_slice(&offset.to_le_bytes());
+    aad
+}
+
+/// Master key ids are recorded in file headers, so bound their length
+fn validate_master_key_id(key_id: &str) -> StorageResult<()> {
+    if key_id.len() > MAX_MASTER_KEY_ID_LEN {
+        return Err(StorageError::write_failed_no_source(format!(
+            "Master key id longer than {} bytes: {:?}",
+            MAX_MASTER_KEY_ID_LEN, key_id
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::errors::StorageErrorCode;
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    fn keyring() -> MasterKeyring {
+        let keys = MasterKeyring::new();
+        keys.rotate("m1").unwrap();
+        keys
+    }
+
+    #[test]
+    fn test_frames_round_trip_at_their_offset() {
+        let key = keyring().new_file_key().unwrap();
+        let frame = key.seal_frame(101, b"plain record");
+        assert_eq!(frame.len(), FRAME_OVERHEAD + 12);
+        assert_eq!(
+            u32::from_le_bytes(frame[..4].try_into().unwrap()) as usize,
+            frame.len()
+        );
+        assert_eq!(key.open_frame(101, &frame).unwrap(), b"plain record");
+
+        // A frame moved to another offset, or tampered with, does not open
+        assert!(key.open_frame(202, &frame).is_err());
+        let mut tampered = frame.clone();
+        tampered[20] ^= 1;
+        assert!(key.open_frame(101, &tampered).is_err());
+    }
+
+    #[test]
+    fn test_file_key_unwraps_from_header() {
+        let keys = keyring();
+        let key = keys.new_file_key().unwrap();
+        assert_eq!(key.header().len() as u64, KEY_HEADER_SIZE);
+        let frame = key.seal_frame(KEY_HEADER_SIZE, b"record");
+
+        let header = read_key_header(&mut Cursor::new(key.header().to_vec()))
+            .unwrap()
+            .unwrap();
+        let reopened = keys.open_file_key(&header).unwrap();
+        assert_eq!(reopened.master_key_id(), "m1");
+        assert_eq!(
+            reopened.open_frame(KEY_HEADER_SIZE, &frame).unwrap(),
+            b"record"
+        );
+
+        // Rotation wraps new files under the new key; old files still open
+        keys.rotate("m2").unwrap();
+        assert_eq!(keys.new_file_key().unwrap().master_key_id(), "m2");
+        assert!(keys.open_file_key(&header).is_ok());
+
+        // Without the master key the file is refused
+        let err = MasterKeyring::new().open_file_key(&header).unwrap_err();
+        assert_eq!(err.code(), StorageErrorCode::AeroEncryptionKeyUnavailable);
+
+        assert!(read_key_header(&mut Cursor::new(b"plain".to_vec()))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_keyring_persists_and_bounds_key_ids() {
+        let dir = TempDir::new().unwrap();
+        let header = {
+            let keys = MasterKeyring::open(dir.path()).unwrap();
+            assert!(!keys.is_enabled());
+            keys.rotate("m1").unwrap();
+            keys.new_file_key().unwrap().header().to_vec()
+        };
+
+        let keys = MasterKeyring::open(dir.path()).unwrap();
+        assert_eq!(keys.active_key_id().as_deref(), Some("m1"));
+        assert!(keys.open_file_key(&header).is_ok());
+        assert!(keys.rotate(&"k".repeat(MAX_MASTER_KEY_ID_LEN + 1)).is_err());
+        assert!(matches!(
+            MasterKeyring::new().new_file_key(),
+            Err(e) if e.code() == StorageErrorCode::AeroEncryptionKeyUnavailable
+        ));
+    }
+}
@@ -0,0 +1,235 @@
+//! Storage file format versions
+//!
+//! v1 files are a bare sequence of records starting at offset 0.
+//!
+//! v2 files start with a fixed file header so readers can tell formats
+//! apart explicitly instead of mis-parsing:
+//!
+//! ```text
+//! +------------------+
+//! | Magic            | (8 bytes, "AERODOCS")
+//! +------------------+
+//! | Format Version   | (u16 LE)
+//! +------------------+
+//! | Checksum Algo    | (u8, algorithm of the header checksum)
+//! +------------------+
+//! | Page Shift       | (u8, log2 of the page size)
+//! +------------------+
+//! | Header Checksum  | (u32 LE, over the preceding 12 bytes)
+//! +------------------+
+//! ```
+//!
+//! Each v2 record is framed by a per-record header (u32 LE record magic)
+//! followed by the v1 record bytes. A frame never straddles a page boundary
+//! unless it is larger than a page, in which case it starts on one; the gap
+//! before it is zero-filled. Readers skip to the next page when fewer than
+//! 4 bytes remain in the page or a zero stands where a record magic belongs.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+use super::checksum::ChecksumAlgorithm;
+use super::errors::{StorageError, StorageResult};
+
+/// Magic bytes opening a v2 storage file
+pub const FILE_MAGIC: [u8; 8] = *b"AERODOCS";
+
+/// Size of the v2 file header in bytes
+pub const FILE_HEADER_SIZE: u64 = 16;
+
+/// Per-record header opening every v2 record frame
+pub const RECORD_MAGIC: u32 = 0xAE0D_0C02;
+
+/// Size of the per-record header in bytes
+pub const RECORD_HEADER_SIZE: u64 = 4;
+
+/// Page size used for new v2 files
+pub const DEFAULT_PAGE_SIZE: u64 = 4096;
+
+const MIN_PAGE_SHIFT: u8 = 9;
+const MAX_PAGE_SHIFT: u8 = 24;
+
+/// On-disk storage file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StorageFormat {
+    /// Headerless, unaligned records
+    #[default]
+    V1,
+    /// File header plus framed, page-aligned records
+    V2,
+}
+
+impl StorageFormat {
+    /// Returns the format version number
+    pub fn version(self) -> u16 {
+        match self {
+            StorageFormat::V1 => 1,
+            StorageFormat::V2 => 2,
+        }
+    }
+
+    /// Convert from a format version number
+    pub fn from_version(version: u16) -> Option<Self> {
+        match version {
+            1 => Some(StorageFormat::V1),
+            2 => Some(StorageFormat::V2),
+            _ => None,
+        }
+    }
+}
+
+/// Decoded v2 file header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileHeader {
+    /// Algorithm of the header checksum
+    pub checksum_algorithm: ChecksumAlgorithm,
+    /// Page size records are aligned to
+    pub page_size: u64,
+}
+
+impl FileHeader {
+    /// Header for a new v2 file with the default page size
+    pub fn new(checksum_algorithm: ChecksumAlgorithm) -> Self {
+        Self {
+            checksum_algorithm,
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    /// Encode the header to its on-disk bytes
+    pub fn encode(&self) -> [u8; FILE_HEADER_SIZE as usize] {
+        let mut buf = [0u8; FILE_HEADER_SIZE as usize];
+        buf[0..8].copy_from_slice(&FILE_MAGIC);
+        buf[8..10].copy_from_slice(&StorageFormat::V2.version().to_le_bytes());
+        buf[10] = self.checksum_algorithm.as_u8();
+        buf[11] = self.page_size.trailing_zeros() as u8;
+        let checksum = self.checksum_algorithm.compute(&buf[0..12]);
+        buf[12..16].copy_from_slice(&checksum.to_le_bytes());
+        buf
+    }
+
+    /// Decode a header whose magic has already matched
+    ///
+    /// Returns AERO_STORAGE_FORMAT_UNSUPPORTED for an unknown format version
+    /// and AERO_DATA_CORRUPTION for a damaged header.
+    pub fn decode(buf: &[u8]) -> StorageResult<Self> {
+        if buf.len() < FILE_HEADER_SIZE as usize {
+            return Err(StorageError::corruption_at_offset(
+                0,
+                format!(
+                    "Truncated storage file header: {} of {} bytes",
+                    buf.len(),
+                    FILE_HEADER_SIZE
+                ),
+            ));
+        }
+
+        let version = u16::from_le_bytes([buf[8], buf[9]]);
+        if version != StorageFormat::V2.version() {
+            return Err(StorageError::format_unsupported(format!(
+                "Storage format version {} is not supported (expected 1 or 2)",
+                version
+            )));
+        }
+
+        let checksum_algorithm = ChecksumAlgorithm::from_u8(buf[10]).ok_or_else(|| {
+            StorageError::corruption_at_offset(
+                0,
+                format!("Unknown header checksum algorithm: {}", buf[10]),
+            )
+        })?;
+        let stored = u32::from_le_bytes([buf[12], buf[13], buf[14], buf[15]]);
+        if !checksum_algorithm.verify(&buf[0..12], stored) {
+            return Err(StorageError::corruption_at_offset(
+                0,
+                "Storage file header checksum mismatch",
+            ));
+        }
+
+        let page_shift = buf[11];
+        if !(MIN_PAGE_SHIFT..=MAX_PAGE_SHIFT).contains(&page_shift) {
+            return Err(StorageError::corruption_at_offset(
+                0,
+                format!("Invalid page shift in storage header: {}", page_shift),
+            ));
+        }
+
+        Ok(Self {
+            checksum_algorithm,
+            page_size: 1u64 << page_shift,
+        })
+    }
+
+    /// Detects the format of an open storage file
+    ///
+    /// Returns `None` for v1 (including empty files) and the decoded header
+    /// for v2. The file position is left unspecified.
+    pub fn detect(file: &mut File) -> StorageResult<Option<Self>> {
+        let mut buf = Vec::with_capacity(FILE_HEADER_SIZE as usize);
+        file.seek(SeekFrom::Start(0))
+            .and_then(|_| file.take(FILE_HEADER_SIZE).read_to_end(&mut buf))
+            .map_err(|e| StorageError::read_failed("Failed to read storage file header", e))?;
+
+        if buf.len() < FILE_MAGIC.len() || buf[0..8] != FILE_MAGIC {
+            return Ok(None);
+        }
+        Self::decode(&buf).map(Some)
+    }
+
+    /// Offset of the first record frame
+    pub fn data_start(&self) -> u64 {
+        FILE_HEADER_SIZE
+    }
+
+    /// Bytes left in the page containing `offset`
+    pub fn page_left(&self, offset: u64) -> u64 {
+        self.page_size - offset % self.page_size
+    }
+
+    /// Zero padding to write before a frame of `frame_len` bytes at `offset`
+    pub fn padding_before(&self, offset: u64, frame_len: u64) -> u64 {
+        let page_left = self.page_left(offset);
+        if offset.is_multiple_of(self.page_size) || frame_len <= page_left {
+            0
+        } else {
+            page_left
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_roundtrip() {
+        let header = FileHeader::new(ChecksumAlgorithm::Crc32c);
+        let bytes = header.encode();
+        assert_eq!(&bytes[0..8], b"AERODOCS");
+        assert_eq!(FileHeader::decode(&bytes).unwrap(), header);
+    }
+
+    #[test]
+    fn test_header_rejects_unknown_version_and_damage() {
+        let mut bytes = FileHeader::new(ChecksumAlgorithm::Crc32).encode();
+        bytes[8] = 3;
+        let err = FileHeader::decode(&bytes).unwrap_err();
+        assert_eq!(err.code().code(), "AERO_STORAGE_FORMAT_UNSUPPORTED");
+
+        let mut bytes = FileHeader::new(ChecksumAlgorithm::Crc32).encode();
+        bytes[11] ^= 0x01;
+        let err = FileHeader::decode(&bytes).unwrap_err();
+        assert_eq!(err.code().code(), "AERO_DATA_CORRUPTION");
+    }
+
+    #[test]
+    fn test_frames_never_straddle_pages() {
+        let header = FileHeader::new(ChecksumAlgorithm::Crc32);
+        assert_eq!(header.padding_before(16, 100), 0);
+        assert_eq!(header.padding_before(4000, 96), 0);
+        assert_eq!(header.padding_before(4000, 97), 96);
+        // Oversized frames start on a page boundary
+        assert_eq!(header.padding_before(4096, 10_000), 0);
+        assert_eq!(header.padding_before(100, 10_000), 3996);
+    }
+}
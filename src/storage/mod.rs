@@ -9,6 +9,12 @@
 //! - Checksum-verified on every read
 //! - Tombstones preserved forever (Phase 0)
 //! - Latest record wins for same document_id
+//! - Optional per-collection body encryption (`CollectionKeyring`)
+//! - Optional field-level encryption of schema-declared fields (`KeyProvider`)
+//! - Optional whole-file encryption of storage and the WAL (`MasterKeyring`)
+//! - Versioned file format, detected on open (`StorageFormat`)
+//! - Optional memory-mapped reads (`mmap` feature, `StorageReader::with_mmap`)
+//! - Optional offset-keyed LRU cache for point lookups (`BlockCache`)
 //! - WAL-driven (storage writes occur after WAL fsync)
 //!
 //! # Invariants Enforced
@@ -19,14 +25,29 @@
 //! - K2: Halt-on-corruption policy
 //! - C1: Full-document writes
 
+mod cache;
 mod checksum;
+mod encryption;
 mod errors;
+mod field_encryption;
+mod file_encryption;
+mod format;
 mod reader;
 mod record;
+mod source;
 mod writer;
 
-pub use checksum::compute_checksum;
-pub use errors::{StorageError, StorageResult};
+pub use cache::BlockCache;
+pub use checksum::{compute_checksum, ChecksumAlgorithm};
+pub use encryption::{CollectionKeyEntry, CollectionKeyState, CollectionKeyring, EncryptionKey};
+pub use errors::{StorageError, StorageErrorCode, StorageResult};
+pub use field_encryption::{
+    decrypt_fields, encrypt_fields, has_encrypted_fields, FieldKeyring, KeyProvider,
+};
+pub use file_encryption::{
+    read_key_header, FileKey, MasterKeyring, KEY_HEADER_SIZE, MASTER_KEYS_DIR,
+};
+pub use format::{FileHeader, StorageFormat};
 pub use reader::StorageReader;
 pub use record::{DocumentRecord, StoragePayload};
 pub use writer::StorageWriter;
@@ -7,13 +7,24 @@
 //! Per STORAGE.md §11:
 //! - Any checksum failure on read → operation abort
 //! - During recovery → startup abort
+//!
+//! The file format (v1 or v2) is detected on open. In v2 files the reader
+//! steps over page padding after every record, so `current_offset` always
+//! names the next record frame (or the end of the file). Encrypted files
+//! are opened with `open_with_keys`; plain `open` refuses them.
 
 use std::fs::File;
-use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use super::cache::BlockCache;
+use super::encryption::CollectionKeyring;
 use super::errors::{StorageError, StorageResult};
+use super::file_encryption::{missing_keys, read_key_header, FileKey, MasterKeyring};
+use super::format::{FileHeader, StorageFormat, RECORD_HEADER_SIZE, RECORD_MAGIC};
 use super::record::DocumentRecord;
+use super::source::ReadSource;
 
 /// Storage reader for sequential scans and primary key lookups.
 ///
@@ -21,17 +32,61 @@ use super::record::DocumentRecord;
 pub struct StorageReader {
     /// Path to the storage file
     storage_path: PathBuf,
-    /// Buffered reader
-    reader: BufReader<File>,
+    /// Buffered or memory-mapped byte source
+    reader: ReadSource,
     /// Current byte offset
     current_offset: u64,
     /// Total file size
     file_size: u64,
+    /// Per-collection keys used to open document bodies in `read_at`
+    keyring: Option<Arc<CollectionKeyring>>,
+    /// v2 file header; `None` for v1 and encrypted files
+    header: Option<FileHeader>,
+    /// Data key of an encrypted file
+    cipher: Option<Arc<FileKey>>,
+    /// Offset-keyed cache for `read_at`
+    cache: Option<Arc<BlockCache>>,
 }
 
 impl StorageReader {
     /// Opens the storage file for reading.
+    ///
+    /// Returns AERO_STORAGE_FORMAT_UNSUPPORTED if the file declares a format
+    /// version this build cannot read, and AERO_ENCRYPTION_KEY_UNAVAILABLE
+    /// if it is encrypted.
     pub fn open(storage_path: &Path) -> StorageResult<Self> {
+        Self::open_in(storage_path, None)
+    }
+
+    /// Opens the storage file, unwrapping its data key if it is encrypted.
+    ///
+    /// Returns AERO_ENCRYPTION_KEY_UNAVAILABLE if the file is encrypted
+    /// under a master key `keys` does not hold.
+    pub fn open_with_keys(storage_path: &Path, keys: &MasterKeyring) -> StorageResult<Self> {
+        Self::open_in(storage_path, Some(keys))
+    }
+
+    /// Opens another reader over the same file, sharing its data key.
+    pub fn reopen(&self) -> StorageResult<Self> {
+        let (file, file_size) = Self::open_file(&self.storage_path)?;
+        Self::from_file(&self.storage_path, file, file_size, self.cipher.clone())
+    }
+
+    fn open_in(storage_path: &Path, keys: Option<&MasterKeyring>) -> StorageResult<Self> {
+        let (mut file, file_size) = Self::open_file(storage_path)?;
+        let key_header = read_key_header(&mut file)
+            .map_err(|e| StorageError::read_failed("Failed to read storage key header", e))?;
+        let cipher = match key_header {
+            Some(key_header) => {
+                let keys = keys.ok_or_else(|| missing_keys(storage_path))?;
+                Some(Arc::new(keys.open_file_key(&key_header)?))
+            }
+            None => None,
+        };
+        Self::from_file(storage_path, file, file_size, cipher)
+    }
+
+    fn open_file(storage_path: &Path) -> StorageResult<(File, u64)> {
         let file = File::open(storage_path).map_err(|e| {
             if e.kind() == std::io::ErrorKind::NotFound {
                 StorageError::data_corruption(format!(
@@ -50,13 +105,33 @@ impl StorageReader {
             .metadata()
             .map_err(|e| StorageError::read_failed("Failed to read file metadata", e))?
             .len();
+        Ok((file, file_size))
+    }
 
-        Ok(Self {
+    fn from_file(
+        storage_path: &Path,
+        mut file: File,
+        file_size: u64,
+        cipher: Option<Arc<FileKey>>,
+    ) -> StorageResult<Self> {
+        let header = match cipher {
+            Some(_) => None,
+            None => FileHeader::detect(&mut file)?,
+        };
+
+        let mut reader = Self {
             storage_path: storage_path.to_path_buf(),
-            reader: BufReader::new(file),
+            reader: ReadSource::buffered(file),
             current_offset: 0,
             file_size,
-        })
+            keyring: None,
+            header,
+            cipher,
+            cache: None,
+        };
+        reader.seek_to(reader.data_start())?;
+        reader.skip_padding()?;
+        Ok(reader)
     }
 
     /// Opens storage from data directory.
@@ -70,6 +145,50 @@ impl StorageReader {
         &self.storage_path
     }
 
+    /// Serves reads from a memory mapping of the file.
+    ///
+    /// Records are still copied out and checksum-verified on every read.
+    /// Falls back to buffered file I/O on platforms without mmap.
+    #[cfg(feature = "mmap")]
+    pub fn with_mmap(mut self) -> StorageResult<Self> {
+        let file = self
+            .reader
+            .file()
+            .try_clone()
+            .map_err(|e| StorageError::read_failed("Failed to reopen storage file", e))?;
+        self.reader = ReadSource::mapped(file, self.file_size)
+            .map_err(|e| StorageError::read_failed("Failed to map storage file", e))?;
+        let offset = self.current_offset;
+        self.seek_to(offset)?;
+        Ok(self)
+    }
+
+    /// Returns whether reads are served from a memory mapping.
+    pub fn is_mapped(&self) -> bool {
+        self.reader.is_mapped()
+    }
+
+    /// Returns whether the file is encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    /// Returns the detected file format (v1 for encrypted files).
+    pub fn format(&self) -> StorageFormat {
+        match self.header {
+            Some(_) => StorageFormat::V2,
+            None => StorageFormat::V1,
+        }
+    }
+
+    /// Offset of the first record in the file.
+    fn data_start(&self) -> u64 {
+        match &self.cipher {
+            Some(cipher) => cipher.header().len() as u64,
+            None => self.header.as_ref().map_or(0, FileHeader::data_start),
+        }
+    }
+
     /// Returns the current read offset.
     pub fn current_offset(&self) -> u64 {
         self.current_offset
@@ -90,10 +209,15 @@ impl StorageReader {
     /// - `Ok(None)` if end of file
     /// - `Err(AERO_DATA_CORRUPTION)` if checksum fails (FATAL)
     pub fn read_next(&mut self) -> StorageResult<Option<DocumentRecord>> {
+        self.skip_padding()?;
         if self.current_offset >= self.file_size {
             return Ok(None);
         }
 
+        if self.header.is_some() {
+            self.read_record_header()?;
+        }
+
         let remaining = self.file_size - self.current_offset;
         const MIN_RECORD_SIZE: u64 = 4 + 4 + 4 + 4 + 1 + 4 + 4;
 
@@ -145,15 +269,124 @@ impl StorageReader {
             )
         })?;
 
-        // Parse and validate (includes checksum verification)
-        let (record, bytes_consumed) = DocumentRecord::deserialize(&record_buf)
-            .map_err(|e| StorageError::corruption_at_offset(self.current_offset, e.to_string()))?;
+        // Parse and validate (includes checksum verification); a sealed
+        // frame is opened first and consumed whole
+        let (record, bytes_consumed) = match &self.cipher {
+            Some(cipher) => {
+                let plain = cipher.open_frame(self.current_offset, &record_buf)?;
+                let (record, _) = DocumentRecord::deserialize(&plain).map_err(|e| {
+                    StorageError::corruption_at_offset(self.current_offset, e.to_string())
+                })?;
+                (record, record_buf.len())
+            }
+            None => DocumentRecord::deserialize(&record_buf).map_err(|e| {
+                StorageError::corruption_at_offset(self.current_offset, e.to_string())
+            })?,
+        };
 
         self.current_offset += bytes_consumed as u64;
+        self.skip_padding()?;
 
         Ok(Some(record))
     }
 
+    /// Steps over the next record without reading or validating its body.
+    ///
+    /// Returns the record's offset, or `None` at end of file. Finds record
+    /// boundaries cheaply; bodies are validated when they are read.
+    pub fn skip_next(&mut self) -> StorageResult<Option<u64>> {
+        self.skip_padding()?;
+        if self.current_offset >= self.file_size {
+            return Ok(None);
+        }
+        let offset = self.current_offset;
+
+        if self.header.is_some() {
+            self.read_record_header()?;
+        }
+
+        let mut len_buf = [0u8; 4];
+        self.reader.read_exact(&mut len_buf).map_err(|e| {
+            StorageError::corruption_at_offset(
+                self.current_offset,
+                format!("Failed to read record length: {}", e),
+            )
+        })?;
+        let record_length = u32::from_le_bytes(len_buf) as u64;
+        let remaining = self.file_size - self.current_offset;
+        if record_length < 4 || record_length > remaining {
+            return Err(StorageError::corruption_at_offset(
+                self.current_offset,
+                format!("Invalid record length: {}", record_length),
+            ));
+        }
+
+        self.seek_to(self.current_offset + record_length)?;
+        self.skip_padding()?;
+        Ok(Some(offset))
+    }
+
+    /// Consumes the per-record header of a v2 frame.
+    fn read_record_header(&mut self) -> StorageResult<()> {
+        if self.file_size - self.current_offset < RECORD_HEADER_SIZE {
+            return Err(StorageError::corruption_at_offset(
+                self.current_offset,
+                "Truncated storage: incomplete record header",
+            ));
+        }
+        let mut magic = [0u8; 4];
+        self.reader.read_exact(&mut magic).map_err(|e| {
+            StorageError::corruption_at_offset(
+                self.current_offset,
+                format!("Failed to read record header: {}", e),
+            )
+        })?;
+        if u32::from_le_bytes(magic) != RECORD_MAGIC {
+            return Err(StorageError::corruption_at_offset(
+                self.current_offset,
+                format!("Invalid record magic: {:#010x}", u32::from_le_bytes(magic)),
+            ));
+        }
+        self.current_offset += RECORD_HEADER_SIZE;
+        Ok(())
+    }
+
+    /// Steps over zero padding up to the next v2 record frame.
+    ///
+    /// No-op for v1 files and at the end of the file.
+    fn skip_padding(&mut self) -> StorageResult<()> {
+        let header = match self.header {
+            Some(header) => header,
+            None => return Ok(()),
+        };
+
+        while self.current_offset < self.file_size {
+            let page_left = header.page_left(self.current_offset);
+            if page_left >= RECORD_HEADER_SIZE {
+                // A torn trailing frame is reported by the next read
+                if self.file_size - self.current_offset < RECORD_HEADER_SIZE {
+                    return Ok(());
+                }
+                let mut magic = [0u8; 4];
+                self.reader.read_exact(&mut magic).map_err(|e| {
+                    StorageError::corruption_at_offset(
+                        self.current_offset,
+                        format!("Failed to read record header: {}", e),
+                    )
+                })?;
+                self.reader
+                    .seek_relative(-4)
+                    .map_err(|e| StorageError::read_failed("Failed to seek in storage file", e))?;
+                if magic != [0u8; 4] {
+                    return Ok(());
+                }
+            }
+            let next_page = self.current_offset + page_left;
+            self.seek_to(next_page.min(self.file_size))?;
+        }
+        Ok(())
+    }
+
     /// Reads all records from storage.
     ///
     /// Any corruption causes immediate failure.
@@ -179,13 +412,50 @@ impl StorageReader {
         Ok(())
     }
 
+    /// Opens document bodies of encrypted collections in `read_at`.
+    ///
+    /// Sequential scans (`read_next`, `read_all`) return bodies as stored.
+    pub fn set_keyring(&mut self, keyring: Arc<CollectionKeyring>) {
+        self.keyring = Some(keyring);
+    }
+
+    /// Serves `read_at` from the block cache, filling it on misses.
+    ///
+    /// Share the cache with the StorageWriter so writes invalidate it. A hit
+    /// leaves the sequential scan position unchanged.
+    pub fn set_cache(&mut self, cache: Arc<BlockCache>) {
+        self.cache = Some(cache);
+    }
+
     /// Reads a single record at the specified offset.
     ///
     /// Validates checksum. Returns AERO_DATA_CORRUPTION if invalid.
+    /// With a keyring set, returns AERO_ENCRYPTION_KEY_UNAVAILABLE if the
+    /// record's collection was erased.
     pub fn read_at(&mut self, offset: u64) -> StorageResult<DocumentRecord> {
-        self.seek_to(offset)?;
-        match self.read_next()? {
-            Some(record) => Ok(record),
+        let cached = self.cache.as_ref().and_then(|cache| cache.get(offset));
+        let record = match cached {
+            Some(record) => Some(record),
+            None => {
+                if offset >= self.file_size {
+                    // The record may have been appended after this reader was opened
+                    self.refresh_file_size()?;
+                }
+                self.seek_to(offset)?;
+                let record = self.read_next()?;
+                if let (Some(cache), Some(record)) = (&self.cache, &record) {
+                    cache.insert(offset, record.clone());
+                }
+                record
+            }
+        };
+        match record {
+            Some(mut record) => {
+                if let Some(keyring) = &self.keyring {
+                    keyring.decrypt_record(&mut record)?;
+                }
+                Ok(record)
+            }
             None => Err(StorageError::corruption_at_offset(
                 offset,
                 "No record at specified offset",
@@ -193,9 +463,42 @@ impl StorageReader {
         }
     }
 
+    /// Re-reads the file size so records appended by a writer become visible.
+    fn refresh_file_size(&mut self) -> StorageResult<()> {
+        self.file_size = self
+            .reader
+            .file()
+            .metadata()
+            .map_err(|e| StorageError::read_failed("Failed to read file metadata", e))?
+            .len();
+        self.reader
+            .extend_to(self.file_size)
+            .map_err(|e| StorageError::read_failed("Failed to map storage file", e))?;
+        Ok(())
+    }
+
     /// Resets reader to beginning of file.
+    ///
+    /// Records appended since the last reset are included in the next scan.
     pub fn reset(&mut self) -> StorageResult<()> {
-        self.seek_to(0)
+        self.refresh_file_size()?;
+        self.seek_to(self.data_start())?;
+        self.skip_padding()
+    }
+
+    /// Positions the sequential scan at the first record at or after `offset`.
+    ///
+    /// Records appended since the last reset are included.
+    pub fn scan_from(&mut self, offset: u64) -> StorageResult<()> {
+        self.refresh_file_size()?;
+        self.seek_to(offset.max(self.data_start()))?;
+        self.skip_padding()
+    }
+
+    /// Returns the file length, including records appended by a writer.
+    pub fn file_len(&mut self) -> StorageResult<u64> {
+        self.refresh_file_size()?;
+        Ok(self.file_size)
     }
 
     /// Finds the latest record for a document by sequential scan.
@@ -435,6 +738,47 @@ mod tests {
         assert_eq!(record.document_id, "test_collection:doc2");
     }
 
+    #[test]
+    fn test_read_at_sees_records_written_after_open() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut writer = StorageWriter::open(temp_dir.path()).unwrap();
+        let mut reader = StorageReader::open_from_data_dir(temp_dir.path()).unwrap();
+
+        let offset = writer.write(&create_test_payload("doc1")).unwrap();
+        let record = reader.read_at(offset).unwrap();
+        assert_eq!(record.document_id, "test_collection:doc1");
+    }
+
+    #[test]
+    fn test_read_at_uses_shared_block_cache() {
+        use super::super::cache::BlockCache;
+        use crate::observability::MetricsRegistry;
+
+        let temp_dir = TempDir::new().unwrap();
+        let metrics = Arc::new(MetricsRegistry::new());
+        let cache = Arc::new(BlockCache::new(16).with_metrics(Arc::clone(&metrics)));
+        let mut writer = StorageWriter::open(temp_dir.path()).unwrap();
+        writer.set_cache(Arc::clone(&cache));
+        let mut reader = StorageReader::open_from_data_dir(temp_dir.path()).unwrap();
+        reader.set_cache(Arc::clone(&cache));
+
+        let offset = writer.write(&create_test_payload("doc1")).unwrap();
+        for _ in 0..3 {
+            let record = reader.read_at(offset).unwrap();
+            assert_eq!(record.document_id, "test_collection:doc1");
+        }
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.storage_cache_misses, 1);
+        assert_eq!(snapshot.storage_cache_hits, 2);
+
+        // Writes drop entries at or beyond the end of the file only
+        cache.insert(writer.current_offset(), reader.read_at(offset).unwrap());
+        assert_eq!(cache.len(), 2);
+        writer.write(&create_test_payload("doc2")).unwrap();
+        assert_eq!(cache.len(), 1);
+        assert!(reader.read_at(offset).is_ok());
+    }
+
     #[test]
     fn test_tombstone_in_document_map() {
         let temp_dir = TempDir::new().unwrap();
@@ -454,4 +798,76 @@ mod tests {
         let record = map.get("test_collection:doc1").unwrap();
         assert!(record.is_tombstone);
     }
+
+    #[test]
+    fn test_detects_format_and_rejects_unknown_versions() {
+        use super::super::format::StorageFormat;
+
+        let temp_dir = TempDir::new().unwrap();
+        {
+            let mut writer = StorageWriter::open(temp_dir.path()).unwrap();
+            writer.write(&create_test_payload("doc1")).unwrap();
+        }
+        let reader = StorageReader::open_from_data_dir(temp_dir.path()).unwrap();
+        assert_eq!(reader.format(), StorageFormat::V1);
+
+        let v2_dir = TempDir::new().unwrap();
+        {
+            let mut writer =
+                StorageWriter::open_with_format(v2_dir.path(), StorageFormat::V2).unwrap();
+            writer.write(&create_test_payload("doc1")).unwrap();
+        }
+        let storage_path = v2_dir.path().join("data").join("documents.dat");
+        let mut reader = StorageReader::open(&storage_path).unwrap();
+        assert_eq!(reader.format(), StorageFormat::V2);
+        assert_eq!(reader.read_all().unwrap().len(), 1);
+
+        // A future format version is reported, not mis-parsed
+        let mut bytes = std::fs::read(&storage_path).unwrap();
+        bytes[8] = 9;
+        std::fs::write(&storage_path, &bytes).unwrap();
+        let err = StorageReader::open(&storage_path).err().unwrap();
+        assert_eq!(err.code().code(), "AERO_STORAGE_FORMAT_UNSUPPORTED");
+        assert!(err.is_fatal());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_mmap_reads_match_buffered_reads() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut writer = StorageWriter::open(temp_dir.path()).unwrap();
+        writer.write(&create_test_payload("doc1")).unwrap();
+        let offset2 = writer.write(&create_test_payload("doc2")).unwrap();
+
+        let mut reader = StorageReader::open_from_data_dir(temp_dir.path())
+            .unwrap()
+            .with_mmap()
+            .unwrap();
+        assert_eq!(reader.is_mapped(), cfg!(unix));
+        assert_eq!(reader.read_all().unwrap().len(), 2);
+        assert_eq!(
+            reader.read_at(offset2).unwrap().document_id,
+            "test_collection:doc2"
+        );
+
+        // Records appended after mapping are picked up by re-mapping
+        let offset3 = writer.write(&create_test_payload("doc3")).unwrap();
+        assert_eq!(
+            reader.read_at(offset3).unwrap().document_id,
+            "test_collection:doc3"
+        );
+
+        // Checksums are still verified on every mapped read
+        let storage_path = temp_dir.path().join("data").join("documents.dat");
+        let mut bytes = std::fs::read(&storage_path).unwrap();
+        let last = bytes.len() - 6;
+        bytes[last] ^= 0xFF;
+        std::fs::write(&storage_path, &bytes).unwrap();
+        let mut reader = StorageReader::open(&storage_path)
+            .unwrap()
+            .with_mmap()
+            .unwrap();
+        let err = reader.read_all().unwrap_err();
+        assert!(err.is_fatal());
+    }
 }
@@ -12,7 +12,7 @@
 //! +------------------+
 //! | Schema Version   | (length-prefixed string)
 //! +------------------+
-//! | Tombstone Flag   | (u8: 0 = live, 1 = deleted)
+//! | Flags            | (u8: bit 0 = tombstone, high bits = checksum algorithm)
 //! +------------------+
 //! | Document Payload | (length-prefixed bytes)
 //! +------------------+
@@ -20,10 +20,13 @@
 //! +------------------+
 //! ```
 //!
-//! Checksum covers all bytes except the checksum itself.
+//! Checksum covers all bytes except the checksum itself. Readers validate
+//! with the algorithm recorded in the flags byte.
 
 use std::io::{self, Read};
 
+use super::checksum::{decode_flags, encode_flags, ChecksumAlgorithm};
+
 /// Payload structure for a document to be stored.
 ///
 /// This contains all the metadata needed for a document record.
@@ -128,7 +131,7 @@ impl DocumentRecord {
 
     /// Serialize the record body (everything except length prefix and checksum).
     /// This is the data over which the checksum is computed.
-    fn serialize_body(&self) -> Vec<u8> {
+    fn serialize_body(&self, algorithm: ChecksumAlgorithm) -> Vec<u8> {
         let mut buf = Vec::new();
 
         // Document ID (length-prefixed)
@@ -143,8 +146,8 @@ impl DocumentRecord {
         buf.extend_from_slice(&(self.schema_version.len() as u32).to_le_bytes());
         buf.extend_from_slice(self.schema_version.as_bytes());
 
-        // Tombstone flag
-        buf.push(if self.is_tombstone { 1 } else { 0 });
+        // Flags: tombstone bit + checksum algorithm
+        buf.push(encode_flags(self.is_tombstone, algorithm));
 
         // Document body (length-prefixed)
         buf.extend_from_slice(&(self.document_body.len() as u32).to_le_bytes());
@@ -160,7 +163,12 @@ impl DocumentRecord {
     /// - Body (variable)
     /// - Checksum (u32 LE)
     pub fn serialize(&self) -> Vec<u8> {
-        let body = self.serialize_body();
+        self.serialize_with(ChecksumAlgorithm::default())
+    }
+
+    /// Serialize the complete record using the given checksum algorithm.
+    pub fn serialize_with(&self, algorithm: ChecksumAlgorithm) -> Vec<u8> {
+        let body = self.serialize_body(algorithm);
 
         // Record length = 4 (length) + body.len() + 4 (checksum)
         let record_length = (4 + body.len() + 4) as u32;
@@ -169,7 +177,7 @@ impl DocumentRecord {
         let mut checksum_data = Vec::with_capacity(4 + body.len());
         checksum_data.extend_from_slice(&record_length.to_le_bytes());
         checksum_data.extend_from_slice(&body);
-        let checksum = super::checksum::compute_checksum(&checksum_data);
+        let checksum = algorithm.compute(&checksum_data);
 
         // Build final record
         let mut record = Vec::with_capacity(record_length as usize);
@@ -223,8 +231,15 @@ impl DocumentRecord {
             data[checksum_offset + 3],
         ]);
 
+        // Verify checksum with the algorithm recorded in the flags byte.
+        // A corrupted body whose flags cannot be located falls back to CRC32
+        // and is reported as a checksum mismatch.
+        let algorithm = locate_flags(&data[4..checksum_offset])
+            .and_then(|byte| decode_flags(byte).ok())
+            .map(|(_, algorithm)| algorithm)
+            .unwrap_or_default();
         let checksum_data = &data[0..checksum_offset];
-        let computed_checksum = super::checksum::compute_checksum(checksum_data);
+        let computed_checksum = algorithm.compute(checksum_data);
 
         if computed_checksum != stored_checksum {
             return Err(io::Error::new(
@@ -267,9 +282,9 @@ impl DocumentRecord {
         let schema_id = read_string(&mut cursor)?;
         let schema_version = read_string(&mut cursor)?;
 
-        let mut tombstone_buf = [0u8; 1];
-        cursor.read_exact(&mut tombstone_buf)?;
-        let is_tombstone = tombstone_buf[0] != 0;
+        let mut flags_buf = [0u8; 1];
+        cursor.read_exact(&mut flags_buf)?;
+        let (is_tombstone, _) = decode_flags(flags_buf[0])?;
 
         let document_body = read_bytes(&mut cursor)?;
 
@@ -286,6 +301,20 @@ impl DocumentRecord {
     }
 }
 
+/// Locate the flags byte in a record body without allocating.
+///
+/// Skips the three length-prefixed strings that precede it. Returns `None`
+/// if a length prefix points past the end of the body.
+fn locate_flags(body: &[u8]) -> Option<u8> {
+    let mut offset = 0usize;
+    for _ in 0..3 {
+        let len_bytes = body.get(offset..offset + 4)?;
+        let len = u32::from_le_bytes([len_bytes[0], len_bytes[1], len_bytes[2], len_bytes[3]]);
+        offset = offset.checked_add(4)?.checked_add(len as usize)?;
+    }
+    body.get(offset).copied()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -385,4 +414,51 @@ mod tests {
         let record = DocumentRecord::from_payload(&payload);
         assert_eq!(record.document_id, "users:user_123");
     }
+
+    #[test]
+    fn test_roundtrip_with_each_algorithm() {
+        let record = DocumentRecord::from_payload(&sample_payload());
+        for algorithm in [
+            ChecksumAlgorithm::Crc32,
+            ChecksumAlgorithm::Crc32c,
+            ChecksumAlgorithm::XxHash64,
+        ] {
+            let serialized = record.serialize_with(algorithm);
+            let (recovered, consumed) = DocumentRecord::deserialize(&serialized).unwrap();
+            assert_eq!(recovered, record);
+            assert_eq!(consumed, serialized.len());
+        }
+    }
+
+    #[test]
+    fn test_default_serialization_is_legacy_crc32() {
+        let record = DocumentRecord::from_payload(&StoragePayload::tombstone(
+            "users",
+            "user_1",
+            "user_schema",
+            "v1",
+        ));
+        let serialized = record.serialize();
+
+        // Legacy flags byte: plain tombstone bit, no algorithm bits
+        let flags_offset = serialized.len() - 4 - 4 - 1;
+        assert_eq!(serialized[flags_offset], 1);
+        assert_eq!(
+            serialized[serialized.len() - 4..],
+            super::super::checksum::compute_checksum(&serialized[..serialized.len() - 4])
+                .to_le_bytes()
+        );
+    }
+
+    #[test]
+    fn test_corrupted_algorithm_bits_detected() {
+        let record = DocumentRecord::from_payload(&sample_payload());
+        let mut serialized = record.serialize_with(ChecksumAlgorithm::Crc32c);
+
+        let flags_offset = 4 + 4 + "users:user_123".len() + 4 + "user_schema".len() + 4 + 2;
+        serialized[flags_offset] ^= 0x60;
+
+        let err = DocumentRecord::deserialize(&serialized).unwrap_err();
+        assert!(err.to_string().contains("Checksum mismatch"));
+    }
 }
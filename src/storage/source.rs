@@ -0,0 +1,216 @@
+//! Byte sources behind StorageReader
+//!
+//! Reads go through buffered file I/O by default. With the `mmap` feature a
+//! reader can instead map the storage file and copy records straight out of
+//! the mapping. Either way the reader deserializes, and so checksums, every
+//! record it returns; the mapping only replaces the read syscalls.
+//!
+//! Platforms without mmap (and builds without the feature) always use
+//! buffered I/O.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+
+/// Where a StorageReader gets its bytes from
+pub(super) enum ReadSource {
+    /// Buffered file reads
+    Buffered(BufReader<File>),
+    /// Reads copied out of a read-only mapping of the file
+    #[cfg(all(feature = "mmap", unix))]
+    Mapped {
+        /// Kept open so the file can be re-mapped as it grows
+        file: File,
+        /// Mapping of the first `map.len()` bytes of the file
+        map: mapping::Mapping,
+        /// Current read position
+        pos: u64,
+    },
+}
+
+impl ReadSource {
+    /// Buffered reads over `file`
+    pub(super) fn buffered(file: File) -> Self {
+        ReadSource::Buffered(BufReader::new(file))
+    }
+
+    /// Mapped reads over `file`, or buffered reads where mmap is unavailable
+    #[cfg(feature = "mmap")]
+    pub(super) fn mapped(file: File, len: u64) -> io::Result<Self> {
+        #[cfg(unix)]
+        {
+            let map = mapping::Mapping::map(&file, len)?;
+            Ok(ReadSource::Mapped { file, map, pos: 0 })
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = len;
+            Ok(Self::buffered(file))
+        }
+    }
+
+    /// Whether reads are served from a mapping
+    pub(super) fn is_mapped(&self) -> bool {
+        match self {
+            ReadSource::Buffered(_) => false,
+            #[cfg(all(feature = "mmap", unix))]
+            ReadSource::Mapped { .. } => true,
+        }
+    }
+
+    /// The underlying file
+    pub(super) fn file(&self) -> &File {
+        match self {
+            ReadSource::Buffered(reader) => reader.get_ref(),
+            #[cfg(all(feature = "mmap", unix))]
+            ReadSource::Mapped { file, .. } => file,
+        }
+    }
+
+    /// Makes the first `len` bytes of the file readable.
+    ///
+    /// Buffered reads see the whole file already; a mapping is replaced
+    /// when the file has grown past it.
+    pub(super) fn extend_to(&mut self, len: u64) -> io::Result<()> {
+        match self {
+            ReadSource::Buffered(_) => {
+                let _ = len;
+                Ok(())
+            }
+            #[cfg(all(feature = "mmap", unix))]
+            ReadSource::Mapped { file, map, .. } => {
+                if len > map.len() as u64 {
+                    *map = mapping::Mapping::map(file, len)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Moves the read position by `offset` bytes.
+    pub(super) fn seek_relative(&mut self, offset: i64) -> io::Result<()> {
+        match self {
+            ReadSource::Buffered(reader) => reader.seek_relative(offset),
+            #[cfg(all(feature = "mmap", unix))]
+            ReadSource::Mapped { pos, .. } => {
+                *pos = pos.checked_add_signed(offset).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "Seek before start of file")
+                })?;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Read for ReadSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            ReadSource::Buffered(reader) => reader.read(buf),
+            #[cfg(all(feature = "mmap", unix))]
+            ReadSource::Mapped { map, pos, .. } => {
+                let bytes = map.as_slice();
+                let start = (*pos).min(bytes.len() as u64) as usize;
+                let n = buf.len().min(bytes.len() - start);
+                buf[..n].copy_from_slice(&bytes[start..start + n]);
+                *pos += n as u64;
+                Ok(n)
+            }
+        }
+    }
+}
+
+impl Seek for ReadSource {
+    fn seek(&mut self, target: SeekFrom) -> io::Result<u64> {
+        match self {
+            ReadSource::Buffered(reader) => reader.seek(target),
+            #[cfg(all(feature = "mmap", unix))]
+            ReadSource::Mapped { map, pos, .. } => {
+                let new_pos = match target {
+                    SeekFrom::Start(offset) => Some(offset),
+                    SeekFrom::End(offset) => (map.len() as u64).checked_add_signed(offset),
+                    SeekFrom::Current(offset) => pos.checked_add_signed(offset),
+                };
+                *pos = new_pos.ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "Seek before start of file")
+                })?;
+                Ok(*pos)
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "mmap", unix))]
+mod mapping {
+    use std::fs::File;
+    use std::io;
+    use std::os::unix::io::AsRawFd;
+
+    /// Read-only shared mapping of a file prefix
+    pub(in crate::storage) struct Mapping {
+        ptr: *mut libc::c_void,
+        len: usize,
+    }
+
+    // SAFETY: the mapping is read-only and owned; it is only unmapped on drop
+    unsafe impl Send for Mapping {}
+    unsafe impl Sync for Mapping {}
+
+    impl Mapping {
+        /// Maps the first `len` bytes of `file`
+        pub(in crate::storage) fn map(file: &File, len: u64) -> io::Result<Self> {
+            if len == 0 {
+                return Ok(Self {
+                    ptr: std::ptr::null_mut(),
+                    len: 0,
+                });
+            }
+            let len = usize::try_from(len).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput, "File too large to map")
+            })?;
+
+            // SAFETY: a fresh read-only mapping of an open file descriptor;
+            // the result is checked before use
+            let ptr = unsafe {
+                libc::mmap(
+                    std::ptr::null_mut(),
+                    len,
+                    libc::PROT_READ,
+                    libc::MAP_SHARED,
+                    file.as_raw_fd(),
+                    0,
+                )
+            };
+            if ptr == libc::MAP_FAILED {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self { ptr, len })
+        }
+
+        /// Length of the mapping in bytes
+        pub(in crate::storage) fn len(&self) -> usize {
+            self.len
+        }
+
+        /// The mapped bytes
+        ///
+        /// Storage is append-only, so mapped bytes never change underneath
+        /// the reader.
+        pub(in crate::storage) fn as_slice(&self) -> &[u8] {
+            if self.len == 0 {
+                return &[];
+            }
+            // SAFETY: ptr maps len readable bytes for the life of self
+            unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+        }
+    }
+
+    impl Drop for Mapping {
+        fn drop(&mut self) {
+            if self.len > 0 {
+                // SAFETY: ptr/len come from a successful mmap
+                unsafe {
+                    libc::munmap(self.ptr, self.len);
+                }
+            }
+        }
+    }
+}
@@ -6,13 +6,23 @@
 //! - Operation must not be acknowledged unless storage write completes
 //!
 //! The storage is append-only with no in-place updates (§6.1).
+//!
+//! New files are written in the requested `StorageFormat`; existing files
+//! keep the format they were created with. Opened with an active master
+//! key, new files are written encrypted instead (see `MasterKeyring`).
 
 use std::collections::HashMap;
 use std::fs::{self, File, OpenOptions};
-use std::io::Write;
+use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use super::cache::BlockCache;
+use super::checksum::ChecksumAlgorithm;
+use super::encryption::{fsync_dir, CollectionKeyring};
 use super::errors::{StorageError, StorageResult};
+use super::file_encryption::{missing_keys, read_key_header, FileKey, MasterKeyring};
+use super::format::{FileHeader, StorageFormat, RECORD_HEADER_SIZE, RECORD_MAGIC};
 use super::record::{DocumentRecord, StoragePayload};
 use crate::wal::WalRecord;
 
@@ -30,6 +40,16 @@ pub struct StorageWriter {
     /// In-memory index of document_id -> latest offset (for lookups)
     /// This is rebuilt on startup and maintained during writes
     document_offsets: HashMap<String, u64>,
+    /// Checksum algorithm for newly written records
+    checksum_algorithm: ChecksumAlgorithm,
+    /// Per-collection keys used to seal document bodies
+    keyring: Option<Arc<CollectionKeyring>>,
+    /// v2 file header; `None` for v1 and encrypted files
+    header: Option<FileHeader>,
+    /// Data key of an encrypted file
+    cipher: Option<FileKey>,
+    /// Reader cache invalidated by every write
+    cache: Option<Arc<BlockCache>>,
 }
 
 impl StorageWriter {
@@ -46,6 +66,40 @@ impl StorageWriter {
     ///
     /// Returns `StorageError::write_failed` if the file cannot be created or opened.
     pub fn open(data_dir: &Path) -> StorageResult<Self> {
+        Self::open_with_format(data_dir, StorageFormat::V1)
+    }
+
+    /// Opens or creates the storage file, writing new files as `format`.
+    ///
+    /// An existing non-empty file is appended to in its own format.
+    pub fn open_with_format(data_dir: &Path, format: StorageFormat) -> StorageResult<Self> {
+        Self::open_in(data_dir, format, None)
+    }
+
+    /// Opens or creates the storage file with whole-file encryption.
+    ///
+    /// With a master key active, a new (or empty) file is written encrypted
+    /// under a fresh data key, in sealed frames rather than `format`. An
+    /// existing file is appended to under its own data key, or stays plain
+    /// until `reencrypt` rewrites it.
+    ///
+    /// # Errors
+    ///
+    /// `AERO_ENCRYPTION_KEY_UNAVAILABLE` if the file is encrypted under a
+    /// master key `keys` does not hold.
+    pub fn open_with_keys(
+        data_dir: &Path,
+        format: StorageFormat,
+        keys: &MasterKeyring,
+    ) -> StorageResult<Self> {
+        Self::open_in(data_dir, format, Some(keys))
+    }
+
+    fn open_in(
+        data_dir: &Path,
+        format: StorageFormat,
+        keys: Option<&MasterKeyring>,
+    ) -> StorageResult<Self> {
         let data_subdir = data_dir.join("data");
         let storage_path = data_subdir.join("documents.dat");
 
@@ -60,7 +114,7 @@ impl StorageWriter {
         }
 
         // Open file for append
-        let file = OpenOptions::new()
+        let mut file = OpenOptions::new()
             .create(true)
             .read(true)
             .append(true)
@@ -72,24 +126,70 @@ impl StorageWriter {
                 )
             })?;
 
-        let current_offset = file
+        let mut current_offset = file
             .metadata()
             .map_err(|e| StorageError::write_failed("Failed to read file metadata", e))?
             .len();
 
+        // A new file gets its header before any record
+        let mut cipher = None;
+        let header = if current_offset == 0 && keys.is_some_and(MasterKeyring::is_enabled) {
+            let key = keys.expect("Checked above").new_file_key()?;
+            file.write_all(key.header())
+                .and_then(|_| file.sync_all())
+                .map_err(|e| StorageError::write_failed("Failed to write storage key header", e))?;
+            current_offset = key.header().len() as u64;
+            cipher = Some(key);
+            None
+        } else if current_offset == 0 {
+            match format {
+                StorageFormat::V1 => None,
+                StorageFormat::V2 => {
+                    let header = FileHeader::new(ChecksumAlgorithm::default());
+                    let bytes = header.encode();
+                    file.write_all(&bytes)
+                        .and_then(|_| file.sync_all())
+                        .map_err(|e| {
+                            StorageError::write_failed("Failed to write storage file header", e)
+                        })?;
+                    current_offset = bytes.len() as u64;
+                    Some(header)
+                }
+            }
+        } else {
+            let key_header = read_key_header(&mut file)
+                .map_err(|e| StorageError::read_failed("Failed to read storage key header", e))?;
+            match key_header {
+                Some(key_header) => {
+                    let keys = keys.ok_or_else(|| missing_keys(&storage_path))?;
+                    cipher = Some(keys.open_file_key(&key_header)?);
+                    None
+                }
+                None => FileHeader::detect(&mut file)?,
+            }
+        };
+
         // Build in-memory index by scanning existing records
-        let document_offsets = Self::build_offset_index(&storage_path)?;
+        let document_offsets = Self::build_offset_index(&storage_path, keys)?;
 
         Ok(Self {
             storage_path,
             file,
             current_offset,
             document_offsets,
+            checksum_algorithm: ChecksumAlgorithm::default(),
+            keyring: None,
+            header,
+            cipher,
+            cache: None,
         })
     }
 
     /// Builds the in-memory offset index by scanning the storage file.
-    fn build_offset_index(storage_path: &Path) -> StorageResult<HashMap<String, u64>> {
+    fn build_offset_index(
+        storage_path: &Path,
+        keys: Option<&MasterKeyring>,
+    ) -> StorageResult<HashMap<String, u64>> {
         use super::reader::StorageReader;
 
         let mut offsets = HashMap::new();
@@ -111,7 +211,10 @@ impl StorageWriter {
         }
 
         // Scan all records to build index
-        let mut reader = StorageReader::open(storage_path)?;
+        let mut reader = match keys {
+            Some(keys) => StorageReader::open_with_keys(storage_path, keys)?,
+            None => StorageReader::open(storage_path)?,
+        };
         loop {
             let offset = reader.current_offset();
             match reader.read_next() {
@@ -137,6 +240,84 @@ impl StorageWriter {
         self.current_offset
     }
 
+    /// Returns whether the file is encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    /// Returns the format records are written in (v1 for encrypted files).
+    pub fn format(&self) -> StorageFormat {
+        match self.header {
+            Some(_) => StorageFormat::V2,
+            None => StorageFormat::V1,
+        }
+    }
+
+    /// Encodes a record as written at `offset`.
+    ///
+    /// Returns the bytes to append and the offset of the record itself,
+    /// which in v2 files follows any page padding.
+    fn frame(&self, record: &DocumentRecord, offset: u64) -> (Vec<u8>, u64) {
+        let serialized = record.serialize_with(self.checksum_algorithm);
+        if let Some(cipher) = &self.cipher {
+            return (cipher.seal_frame(offset, &serialized), offset);
+        }
+        let header = match &self.header {
+            Some(header) => header,
+            None => return (serialized, offset),
+        };
+
+        let frame_len = RECORD_HEADER_SIZE + serialized.len() as u64;
+        let padding = header.padding_before(offset, frame_len);
+        let mut bytes = vec![0u8; padding as usize];
+        bytes.extend_from_slice(&RECORD_MAGIC.to_le_bytes());
+        bytes.extend_from_slice(&serialized);
+        (bytes, offset + padding)
+    }
+
+    /// Returns the checksum algorithm used for new records.
+    pub fn checksum_algorithm(&self) -> ChecksumAlgorithm {
+        self.checksum_algorithm
+    }
+
+    /// Sets the checksum algorithm used for new records.
+    ///
+    /// Existing records are still validated with the algorithm recorded in
+    /// their flags byte.
+    pub fn set_checksum_algorithm(&mut self, algorithm: ChecksumAlgorithm) {
+        self.checksum_algorithm = algorithm;
+    }
+
+    /// Seals document bodies of encrypted collections with the keyring.
+    pub fn set_keyring(&mut self, keyring: Arc<CollectionKeyring>) {
+        self.keyring = Some(keyring);
+    }
+
+    /// Invalidates the shared reader cache on every write.
+    ///
+    /// Entries at or beyond the write offset are dropped, so a file tail
+    /// cut back after a failed batch is never served from the cache.
+    pub fn set_cache(&mut self, cache: Arc<BlockCache>) {
+        self.cache = Some(cache);
+    }
+
+    /// Drops cached records at or beyond the current end of the file.
+    fn invalidate_cache(&self) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate_from(self.current_offset);
+        }
+    }
+
+    /// Builds the on-disk record for a payload, sealing the body if its
+    /// collection is encrypted.
+    fn record_for(&self, payload: &StoragePayload) -> StorageResult<DocumentRecord> {
+        let mut record = DocumentRecord::from_payload(payload);
+        if let Some(keyring) = &self.keyring {
+            keyring.encrypt_record(&mut record)?;
+        }
+        Ok(record)
+    }
+
     /// Returns the number of unique documents (excluding tombstones).
     pub fn document_count(&self) -> usize {
         self.document_offsets.len()
@@ -156,9 +337,9 @@ impl StorageWriter {
     ///
     /// Returns `AERO_STORAGE_WRITE_FAILED` if write or fsync fails.
     pub fn write(&mut self, payload: &StoragePayload) -> StorageResult<u64> {
-        let record = DocumentRecord::from_payload(payload);
-        let serialized = record.serialize();
-        let offset = self.current_offset;
+        let record = self.record_for(payload)?;
+        let (serialized, offset) = self.frame(&record, self.current_offset);
+        self.invalidate_cache();
 
         // Write to file
         self.file.write_all(&serialized).map_err(|e| {
@@ -189,6 +370,63 @@ impl StorageWriter {
         Ok(offset)
     }
 
+    /// Writes a batch of document records with a single fsync.
+    ///
+    /// If the write fails the storage file is cut back to its length before
+    /// the batch and no in-memory state changes.
+    ///
+    /// # Returns
+    ///
+    /// The byte offsets where the records were written, in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AERO_STORAGE_WRITE_FAILED` if write or fsync fails.
+    pub fn write_batch(&mut self, payloads: &[StoragePayload]) -> StorageResult<Vec<u64>> {
+        let mut buffer = Vec::new();
+        let mut entries = Vec::with_capacity(payloads.len());
+        for payload in payloads {
+            let record = self.record_for(payload)?;
+            let (framed, offset) = self.frame(&record, self.current_offset + buffer.len() as u64);
+            entries.push((record.document_id.clone(), offset));
+            buffer.extend_from_slice(&framed);
+        }
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.invalidate_cache();
+
+        if let Err(e) = self.file.write_all(&buffer) {
+            let _ = self.file.set_len(self.current_offset);
+            return Err(StorageError::write_failed(
+                format!("Failed to write batch of {} documents", entries.len()),
+                e,
+            ));
+        }
+
+        // One fsync for the whole batch - mandatory for durability
+        self.file.sync_all().map_err(|e| {
+            StorageError::write_failed(
+                format!(
+                    "fsync failed after writing batch of {} documents",
+                    entries.len()
+                ),
+                e,
+            )
+        })?;
+
+        self.current_offset += buffer.len() as u64;
+
+        // Update in-memory index (latest record wins)
+        Ok(entries
+            .into_iter()
+            .map(|(document_id, offset)| {
+                self.document_offsets.insert(document_id, offset);
+                offset
+            })
+            .collect())
+    }
+
     /// Writes a tombstone (DELETE) record.
     ///
     /// Tombstones are preserved forever in Phase 0.
@@ -231,6 +469,65 @@ impl StorageWriter {
     pub fn has_document(&self, composite_id: &str) -> bool {
         self.document_offsets.contains_key(composite_id)
     }
+
+    /// Rewrites the storage file under a fresh data key.
+    ///
+    /// Records are copied in file order into a new file wrapped under the
+    /// active master key, which then replaces the old file. Bodies sealed
+    /// by the collection keyring are copied as stored. Record offsets
+    /// change, so writers, readers and indexes over the old file must be
+    /// reopened or rebuilt.
+    ///
+    /// # Errors
+    ///
+    /// - `AERO_ENCRYPTION_KEY_UNAVAILABLE` if no master key is active, or
+    ///   the file is encrypted under a master key `keys` does not hold
+    /// - `AERO_STORAGE_WRITE_FAILED` if the new file cannot be written; the
+    ///   old file is left in place
+    pub fn reencrypt(storage_path: &Path, keys: &MasterKeyring) -> StorageResult<()> {
+        use super::reader::StorageReader;
+
+        let cipher = keys.new_file_key()?;
+        let mut reader = StorageReader::open_with_keys(storage_path, keys)?;
+        let tmp_path = storage_path.with_extension("dat.rekey");
+
+        let result = (|| {
+            let file = File::create(&tmp_path).map_err(|e| {
+                StorageError::write_failed(format!("Failed to create {}", tmp_path.display()), e)
+            })?;
+            let failed = |e| {
+                StorageError::write_failed(format!("Failed to write {}", tmp_path.display()), e)
+            };
+            let mut out = BufWriter::new(file);
+            out.write_all(cipher.header()).map_err(failed)?;
+            let mut offset = cipher.header().len() as u64;
+            while let Some(record) = reader.read_next()? {
+                let frame =
+                    cipher.seal_frame(offset, &record.serialize_with(ChecksumAlgorithm::default()));
+                out.write_all(&frame).map_err(failed)?;
+                offset += frame.len() as u64;
+            }
+            out.into_inner()
+                .map_err(|e| failed(e.into_error()))?
+                .sync_all()
+                .map_err(failed)?;
+
+            fs::rename(&tmp_path, storage_path).map_err(|e| {
+                StorageError::write_failed(
+                    format!("Failed to replace {}", storage_path.display()),
+                    e,
+                )
+            })?;
+            let dir = storage_path.parent().unwrap_or(Path::new("."));
+            fsync_dir(dir).map_err(|e| {
+                StorageError::write_failed(format!("Failed to fsync {}", dir.display()), e)
+            })
+        })();
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        result
+    }
 }
 
 #[cfg(test)]
@@ -413,6 +710,27 @@ mod tests {
         assert!(offset2 > offset1);
     }
 
+    #[test]
+    fn test_write_batch_offsets() {
+        use super::super::reader::StorageReader;
+
+        let temp_dir = TempDir::new().unwrap();
+
+        let mut writer = StorageWriter::open(temp_dir.path()).unwrap();
+        let first = writer.write(&create_test_payload("doc0")).unwrap();
+        let offsets = writer
+            .write_batch(&[create_test_payload("doc1"), create_test_payload("doc2")])
+            .unwrap();
+
+        assert_eq!(offsets.len(), 2);
+        assert!(offsets[0] > first && offsets[1] > offsets[0]);
+        assert_eq!(writer.document_count(), 3);
+
+        let mut reader = StorageReader::open(writer.path()).unwrap();
+        let record = reader.read_at(offsets[1]).unwrap();
+        assert_eq!(record.document_id, "test_collection:doc2");
+    }
+
     #[test]
     fn test_reopens_with_correct_state() {
         let temp_dir = TempDir::new().unwrap();
@@ -437,4 +755,114 @@ mod tests {
             writer.write(&create_test_payload("doc3")).unwrap();
         }
     }
+
+    #[test]
+    fn test_mixed_checksum_algorithms_reopen() {
+        let temp_dir = TempDir::new().unwrap();
+
+        {
+            let mut writer = StorageWriter::open(temp_dir.path()).unwrap();
+            writer.write(&create_test_payload("doc1")).unwrap();
+            writer.set_checksum_algorithm(ChecksumAlgorithm::XxHash64);
+            writer.write(&create_test_payload("doc2")).unwrap();
+        }
+
+        // Reopen validates each record with its recorded algorithm
+        let writer = StorageWriter::open(temp_dir.path()).unwrap();
+        assert!(writer.has_document("test_collection:doc1"));
+        assert!(writer.has_document("test_collection:doc2"));
+    }
+
+    #[test]
+    fn test_v2_records_are_page_aligned() {
+        use super::super::format::{DEFAULT_PAGE_SIZE, FILE_HEADER_SIZE};
+        use super::super::reader::StorageReader;
+
+        let temp_dir = TempDir::new().unwrap();
+        let payload = |id: &str, len: usize| {
+            StoragePayload::new("users", id, "schema", "v1", vec![b'x'; len])
+        };
+
+        let mut offsets = Vec::new();
+        {
+            let mut writer =
+                StorageWriter::open_with_format(temp_dir.path(), StorageFormat::V2).unwrap();
+            assert_eq!(writer.current_offset(), FILE_HEADER_SIZE);
+            offsets.push(writer.write(&payload("a", 3000)).unwrap());
+            offsets.push(writer.write(&payload("b", 3000)).unwrap());
+            offsets.extend(
+                writer
+                    .write_batch(&[payload("c", 10_000), payload("d", 10)])
+                    .unwrap(),
+            );
+        }
+        assert_eq!(offsets[0], FILE_HEADER_SIZE);
+        assert_eq!(offsets[1], DEFAULT_PAGE_SIZE);
+        assert_eq!(offsets[2], 2 * DEFAULT_PAGE_SIZE);
+
+        // Scans report the same offsets the writer returned
+        let mut reader = StorageReader::open_from_data_dir(temp_dir.path()).unwrap();
+        assert_eq!(reader.format(), StorageFormat::V2);
+        let mut scanned = Vec::new();
+        loop {
+            let offset = reader.current_offset();
+            match reader.read_next().unwrap() {
+                Some(_) => scanned.push(offset),
+                None => break,
+            }
+        }
+        assert_eq!(scanned, offsets);
+        assert_eq!(reader.read_at(offsets[3]).unwrap().document_id, "users:d");
+
+        // An existing file keeps its format regardless of the request
+        let writer = StorageWriter::open_with_format(temp_dir.path(), StorageFormat::V1).unwrap();
+        assert_eq!(writer.format(), StorageFormat::V2);
+        assert_eq!(writer.get_document_offset("users:c"), Some(offsets[2]));
+    }
+
+    #[test]
+    fn test_reencrypt_converts_plain_storage() {
+        use super::super::errors::StorageErrorCode;
+        use super::super::reader::StorageReader;
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = {
+            let mut writer = StorageWriter::open(temp_dir.path()).unwrap();
+            writer.write(&create_test_payload("secret_doc")).unwrap();
+            writer.write(&create_test_payload("doc2")).unwrap();
+            writer.path().to_path_buf()
+        };
+
+        let keys = MasterKeyring::new();
+        keys.rotate("m1").unwrap();
+        StorageWriter::reencrypt(&path, &keys).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        assert!(!bytes.windows(10).any(|w| w == b"secret_doc"));
+
+        let mut writer =
+            StorageWriter::open_with_keys(temp_dir.path(), StorageFormat::V1, &keys).unwrap();
+        assert!(writer.is_encrypted());
+        let offset = writer.write(&create_test_payload("doc3")).unwrap();
+        let mut reader = StorageReader::open_with_keys(&path, &keys).unwrap();
+        assert_eq!(reader.read_all().unwrap().len(), 3);
+        assert_eq!(
+            reader.read_at(offset).unwrap().document_id,
+            "test_collection:doc3"
+        );
+
+        let err = StorageWriter::open(temp_dir.path()).err().unwrap();
+        assert_eq!(err.code(), StorageErrorCode::AeroEncryptionKeyUnavailable);
+        let err = StorageReader::open(&path).err().unwrap();
+        assert_eq!(err.code(), StorageErrorCode::AeroEncryptionKeyUnavailable);
+
+        // After rotation the data key is wrapped by the new master key
+        keys.rotate("m2").unwrap();
+        StorageWriter::reencrypt(&path, &keys).unwrap();
+        let header = read_key_header(&mut File::open(&path).unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(keys.open_file_key(&header).unwrap().master_key_id(), "m2");
+        let mut reader = StorageReader::open_with_keys(&path, &keys).unwrap();
+        assert_eq!(reader.read_all().unwrap().len(), 3);
+    }
 }
@@ -5,10 +5,119 @@
 //! - Checksum covers record header, payload, and sequence number
 //! - Any checksum mismatch is corruption
 //!
-//! Uses CRC32 (IEEE polynomial) for checksums.
+//! The default algorithm is CRC32 (IEEE polynomial). CRC32C (Castagnoli,
+//! hardware-accelerated where available) and xxHash64 may be selected per
+//! writer. The algorithm is recorded in every record header so readers
+//! always validate with the algorithm the record was written with.
+//!
+//! # Header Encoding
+//!
+//! The algorithm identifier occupies the high bits of the record type byte.
+//! Legacy records carry zero in those bits, which decodes as CRC32, so
+//! existing files keep working unchanged.
+
+use std::fmt;
+use std::io;
 
 use crc32fast::Hasher;
 
+/// Bit offset of the algorithm identifier within a record header byte
+pub const ALGORITHM_SHIFT: u32 = 5;
+
+/// Mask of the record header byte bits that carry the payload value
+pub const ALGORITHM_VALUE_MASK: u8 = (1 << ALGORITHM_SHIFT) - 1;
+
+/// Checksum algorithm used to protect a record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[repr(u8)]
+pub enum ChecksumAlgorithm {
+    /// CRC32 (IEEE polynomial), the legacy default
+    #[default]
+    Crc32 = 0,
+    /// CRC32C (Castagnoli polynomial), SSE4.2-accelerated on x86_64
+    Crc32c = 1,
+    /// xxHash64, truncated to the low 32 bits
+    XxHash64 = 2,
+}
+
+impl ChecksumAlgorithm {
+    /// Convert from the on-disk identifier
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(ChecksumAlgorithm::Crc32),
+            1 => Some(ChecksumAlgorithm::Crc32c),
+            2 => Some(ChecksumAlgorithm::XxHash64),
+            _ => None,
+        }
+    }
+
+    /// Convert to the on-disk identifier
+    pub fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// Parse a configuration name (`crc32`, `crc32c`, `xxhash64`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "crc32" => Some(ChecksumAlgorithm::Crc32),
+            "crc32c" => Some(ChecksumAlgorithm::Crc32c),
+            "xxhash64" | "xxh64" => Some(ChecksumAlgorithm::XxHash64),
+            _ => None,
+        }
+    }
+
+    /// Configuration name of the algorithm
+    pub fn name(self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Crc32 => "crc32",
+            ChecksumAlgorithm::Crc32c => "crc32c",
+            ChecksumAlgorithm::XxHash64 => "xxhash64",
+        }
+    }
+
+    /// Compute a 32-bit checksum over the provided data.
+    pub fn compute(self, data: &[u8]) -> u32 {
+        match self {
+            ChecksumAlgorithm::Crc32 => compute_checksum(data),
+            ChecksumAlgorithm::Crc32c => crc32c(data),
+            ChecksumAlgorithm::XxHash64 => xxhash64(data, 0) as u32,
+        }
+    }
+
+    /// Verify data against an expected checksum.
+    pub fn verify(self, data: &[u8], expected: u32) -> bool {
+        self.compute(data) == expected
+    }
+}
+
+impl fmt::Display for ChecksumAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Combine a header value with the algorithm identifier.
+///
+/// `value` must fit in the low `ALGORITHM_SHIFT` bits.
+pub fn encode_header_byte(value: u8, algorithm: ChecksumAlgorithm) -> u8 {
+    debug_assert!(value <= ALGORITHM_VALUE_MASK);
+    (algorithm.as_u8() << ALGORITHM_SHIFT) | (value & ALGORITHM_VALUE_MASK)
+}
+
+/// Split a header byte into its value and algorithm identifier.
+///
+/// Returns an error for an unknown algorithm identifier.
+pub fn decode_header_byte(byte: u8) -> io::Result<(u8, ChecksumAlgorithm)> {
+    let id = byte >> ALGORITHM_SHIFT;
+    let algorithm = ChecksumAlgorithm::from_u8(id).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unknown checksum algorithm: {}", id),
+        )
+    })?;
+    Ok((byte & ALGORITHM_VALUE_MASK, algorithm))
+}
+
 /// Computes a CRC32 checksum over the provided data.
 ///
 /// This function is deterministic: the same input always produces the same output.
@@ -40,6 +149,155 @@ pub fn verify_checksum(data: &[u8], expected: u32) -> bool {
     compute_checksum(data) == expected
 }
 
+/// CRC32C (Castagnoli) lookup table for the software path
+const CRC32C_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x82F6_3B78
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Computes a CRC32C checksum, using SSE4.2 when the CPU supports it.
+fn crc32c(data: &[u8]) -> u32 {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if std::arch::is_x86_feature_detected!("sse4.2") {
+            // SAFETY: the sse4.2 feature was detected at runtime
+            return unsafe { crc32c_sse42(data) };
+        }
+    }
+    crc32c_software(data)
+}
+
+fn crc32c_software(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc = CRC32C_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse4.2")]
+unsafe fn crc32c_sse42(data: &[u8]) -> u32 {
+    use std::arch::x86_64::{_mm_crc32_u64, _mm_crc32_u8};
+
+    let mut crc = !0u64;
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let word = u64::from_le_bytes(chunk.try_into().unwrap());
+        crc = _mm_crc32_u64(crc, word);
+    }
+    let mut crc = crc as u32;
+    for &byte in chunks.remainder() {
+        crc = _mm_crc32_u8(crc, byte);
+    }
+    !crc
+}
+
+const XXH_PRIME64_1: u64 = 0x9E37_79B1_85EB_CA87;
+const XXH_PRIME64_2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const XXH_PRIME64_3: u64 = 0x1656_67B1_9E37_79F9;
+const XXH_PRIME64_4: u64 = 0x85EB_CA77_C2B2_AE63;
+const XXH_PRIME64_5: u64 = 0x27D4_EB2F_1656_67C5;
+
+fn xxh64_round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(XXH_PRIME64_2))
+        .rotate_left(31)
+        .wrapping_mul(XXH_PRIME64_1)
+}
+
+fn xxh64_merge_round(acc: u64, val: u64) -> u64 {
+    (acc ^ xxh64_round(0, val))
+        .wrapping_mul(XXH_PRIME64_1)
+        .wrapping_add(XXH_PRIME64_4)
+}
+
+fn read_u64(data: &[u8]) -> u64 {
+    u64::from_le_bytes(data[..8].try_into().unwrap())
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    u32::from_le_bytes(data[..4].try_into().unwrap())
+}
+
+/// Computes the 64-bit xxHash of the provided data.
+fn xxhash64(data: &[u8], seed: u64) -> u64 {
+    let len = data.len();
+    let mut rest = data;
+
+    let mut hash = if len >= 32 {
+        let mut v1 = seed.wrapping_add(XXH_PRIME64_1).wrapping_add(XXH_PRIME64_2);
+        let mut v2 = seed.wrapping_add(XXH_PRIME64_2);
+        let mut v3 = seed;
+        let mut v4 = seed.wrapping_sub(XXH_PRIME64_1);
+
+        while rest.len() >= 32 {
+            v1 = xxh64_round(v1, read_u64(rest));
+            v2 = xxh64_round(v2, read_u64(&rest[8..]));
+            v3 = xxh64_round(v3, read_u64(&rest[16..]));
+            v4 = xxh64_round(v4, read_u64(&rest[24..]));
+            rest = &rest[32..];
+        }
+
+        let mut acc = v1
+            .rotate_left(1)
+            .wrapping_add(v2.rotate_left(7))
+            .wrapping_add(v3.rotate_left(12))
+            .wrapping_add(v4.rotate_left(18));
+        acc = xxh64_merge_round(acc, v1);
+        acc = xxh64_merge_round(acc, v2);
+        acc = xxh64_merge_round(acc, v3);
+        xxh64_merge_round(acc, v4)
+    } else {
+        seed.wrapping_add(XXH_PRIME64_5)
+    };
+
+    hash = hash.wrapping_add(len as u64);
+
+    while rest.len() >= 8 {
+        hash ^= xxh64_round(0, read_u64(rest));
+        hash = hash
+            .rotate_left(27)
+            .wrapping_mul(XXH_PRIME64_1)
+            .wrapping_add(XXH_PRIME64_4);
+        rest = &rest[8..];
+    }
+
+    if rest.len() >= 4 {
+        hash ^= (read_u32(rest) as u64).wrapping_mul(XXH_PRIME64_1);
+        hash = hash
+            .rotate_left(23)
+            .wrapping_mul(XXH_PRIME64_2)
+            .wrapping_add(XXH_PRIME64_3);
+        rest = &rest[4..];
+    }
+
+    for &byte in rest {
+        hash ^= (byte as u64).wrapping_mul(XXH_PRIME64_5);
+        hash = hash.rotate_left(11).wrapping_mul(XXH_PRIME64_1);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(XXH_PRIME64_2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(XXH_PRIME64_3);
+    hash ^ (hash >> 32)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,4 +358,63 @@ mod tests {
         let checksum2 = compute_checksum(empty);
         assert_eq!(checksum1, checksum2);
     }
+
+    #[test]
+    fn test_crc32c_known_vector() {
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+        assert_eq!(crc32c_software(b"123456789"), 0xE306_9283);
+    }
+
+    #[test]
+    fn test_crc32c_hardware_matches_software() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 31) as u8).collect();
+        for len in [0, 1, 7, 8, 9, 63, 1000] {
+            assert_eq!(crc32c(&data[..len]), crc32c_software(&data[..len]));
+        }
+    }
+
+    #[test]
+    fn test_xxhash64_known_vectors() {
+        assert_eq!(xxhash64(b"", 0), 0xEF46_DB37_51D8_E999);
+        assert_eq!(xxhash64(b"abc", 0), 0x44BC_2CF5_AD77_0999);
+        assert_eq!(
+            xxhash64(b"Nobody inspects the spammish repetition", 0),
+            0xFBCE_A83C_8A37_8BF1
+        );
+    }
+
+    #[test]
+    fn test_algorithm_roundtrip() {
+        for algorithm in [
+            ChecksumAlgorithm::Crc32,
+            ChecksumAlgorithm::Crc32c,
+            ChecksumAlgorithm::XxHash64,
+        ] {
+            assert_eq!(
+                ChecksumAlgorithm::from_u8(algorithm.as_u8()),
+                Some(algorithm)
+            );
+            assert_eq!(
+                ChecksumAlgorithm::from_name(algorithm.name()),
+                Some(algorithm)
+            );
+            let checksum = algorithm.compute(b"payload");
+            assert!(algorithm.verify(b"payload", checksum));
+        }
+        assert_eq!(ChecksumAlgorithm::from_u8(7), None);
+    }
+
+    #[test]
+    fn test_legacy_header_byte_decodes_as_crc32() {
+        let (value, algorithm) = decode_header_byte(2).unwrap();
+        assert_eq!(value, 2);
+        assert_eq!(algorithm, ChecksumAlgorithm::Crc32);
+
+        let byte = encode_header_byte(4, ChecksumAlgorithm::XxHash64);
+        assert_eq!(
+            decode_header_byte(byte).unwrap(),
+            (4, ChecksumAlgorithm::XxHash64)
+        );
+        assert!(decode_header_byte(7 << ALGORITHM_SHIFT).is_err());
+    }
 }
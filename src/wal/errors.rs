@@ -4,10 +4,13 @@
 //! - AERO_WAL_APPEND_FAILED (ERROR severity)
 //! - AERO_WAL_FSYNC_FAILED (FATAL severity)
 //! - AERO_WAL_CORRUPTION (FATAL severity)
+//! - AERO_ENCRYPTION_KEY_UNAVAILABLE (ERROR severity)
 
 use std::fmt;
 use std::io;
 
+use crate::storage::{StorageError, StorageErrorCode};
+
 /// Severity levels for WAL errors as defined in ERRORS.md
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Severity {
@@ -35,6 +38,8 @@ pub enum WalErrorCode {
     AeroWalFsyncFailed,
     /// WAL checksum failure
     AeroWalCorruption,
+    /// Encrypted WAL opened without its master key
+    AeroEncryptionKeyUnavailable,
 }
 
 impl WalErrorCode {
@@ -44,6 +49,7 @@ impl WalErrorCode {
             WalErrorCode::AeroWalAppendFailed => "AERO_WAL_APPEND_FAILED",
             WalErrorCode::AeroWalFsyncFailed => "AERO_WAL_FSYNC_FAILED",
             WalErrorCode::AeroWalCorruption => "AERO_WAL_CORRUPTION",
+            WalErrorCode::AeroEncryptionKeyUnavailable => "AERO_ENCRYPTION_KEY_UNAVAILABLE",
         }
     }
 
@@ -53,6 +59,7 @@ impl WalErrorCode {
             WalErrorCode::AeroWalAppendFailed => Severity::Error,
             WalErrorCode::AeroWalFsyncFailed => Severity::Fatal,
             WalErrorCode::AeroWalCorruption => Severity::Fatal,
+            WalErrorCode::AeroEncryptionKeyUnavailable => Severity::Error,
         }
     }
 
@@ -62,6 +69,7 @@ impl WalErrorCode {
             WalErrorCode::AeroWalAppendFailed => Some("D1"),
             WalErrorCode::AeroWalFsyncFailed => Some("D1"),
             WalErrorCode::AeroWalCorruption => Some("K2"),
+            WalErrorCode::AeroEncryptionKeyUnavailable => None,
         }
     }
 }
@@ -136,6 +144,33 @@ impl WalError {
         }
     }
 
+    /// Create an error for an encrypted WAL whose key is unavailable
+    pub fn key_unavailable(message: impl Into<String>) -> Self {
+        Self {
+            code: WalErrorCode::AeroEncryptionKeyUnavailable,
+            message: message.into(),
+            details: None,
+            source: None,
+        }
+    }
+
+    /// Map an error unwrapping or opening an encrypted WAL
+    ///
+    /// A missing key stays a key error; anything else is corruption.
+    pub fn from_storage_error(err: StorageError) -> Self {
+        Self {
+            code: match err.code() {
+                StorageErrorCode::AeroEncryptionKeyUnavailable => {
+                    WalErrorCode::AeroEncryptionKeyUnavailable
+                }
+                _ => WalErrorCode::AeroWalCorruption,
+            },
+            message: err.message().to_string(),
+            details: err.details().map(str::to_string),
+            source: None,
+        }
+    }
+
     /// Returns the error code
     pub fn code(&self) -> WalErrorCode {
         self.code
@@ -165,6 +200,18 @@ impl WalError {
     pub fn is_fatal(&self) -> bool {
         self.severity() == Severity::Fatal
     }
+
+    /// Returns whether an append failed because the WAL device is full
+    ///
+    /// Such a failure is recoverable once space is freed, so callers may
+    /// keep serving reads instead of failing every request.
+    pub fn is_storage_full(&self) -> bool {
+        self.code == WalErrorCode::AeroWalAppendFailed
+            && self
+                .source
+                .as_ref()
+                .is_some_and(|e| e.kind() == io::ErrorKind::StorageFull)
+    }
 }
 
 impl fmt::Display for WalError {
@@ -215,6 +262,10 @@ mod tests {
             WalErrorCode::AeroWalCorruption.code(),
             "AERO_WAL_CORRUPTION"
         );
+        assert_eq!(
+            WalErrorCode::AeroEncryptionKeyUnavailable.code(),
+            "AERO_ENCRYPTION_KEY_UNAVAILABLE"
+        );
     }
 
     #[test]
@@ -251,6 +302,24 @@ mod tests {
         assert!(!err.is_fatal());
     }
 
+    #[test]
+    fn test_storage_full_is_detected_on_append_only() {
+        let full = || io::Error::from(io::ErrorKind::StorageFull);
+        assert!(WalError::append_failed("write failed", full()).is_storage_full());
+        assert!(!WalError::fsync_failed("fsync failed", full()).is_storage_full());
+        let other = io::Error::new(io::ErrorKind::Other, "disk error");
+        assert!(!WalError::append_failed("write failed", other).is_storage_full());
+    }
+
+    #[test]
+    fn test_storage_key_errors_keep_their_code() {
+        let err = WalError::from_storage_error(StorageError::key_unavailable("no master key"));
+        assert_eq!(err.code(), WalErrorCode::AeroEncryptionKeyUnavailable);
+        assert!(!err.is_fatal());
+        let err = WalError::from_storage_error(StorageError::data_corruption("bad tag"));
+        assert_eq!(err.code(), WalErrorCode::AeroWalCorruption);
+    }
+
     #[test]
     fn test_error_display_contains_required_fields() {
         let err = WalError::corruption_at_sequence(42, "checksum mismatch");
@@ -34,15 +34,15 @@ mod record;
 mod writer;
 
 pub use batching::{BatchWriteResult, WalBatch, WalBatchConfig, WalBatcher, WritePath};
-pub use checksum::compute_checksum;
+pub use checksum::{compute_checksum, decode_header_byte, encode_header_byte, ChecksumAlgorithm};
 pub use errors::{WalError, WalResult};
 pub use group_commit::{
     CommitGroup, CommitPath, GroupCommitConfig, GroupCommitManager, GroupCommitResult,
     PendingCommit, PendingCommitState,
 };
-pub use reader::WalReader;
+pub use reader::{TailRecovery, TornTail, WalReader};
 pub use record::{
     MvccCommitPayload, MvccCommitRecord, MvccVersionPayload, MvccVersionRecord, RecordType,
-    WalPayload, WalRecord,
+    TxnMarker, WalPayload, WalRecord,
 };
-pub use writer::WalWriter;
+pub use writer::{TxnAppend, WalWriter};
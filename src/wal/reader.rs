@@ -10,13 +10,69 @@
 //! - WAL records are replayed strictly in sequence number order
 //! - Replay always starts from the first record
 //! - Replay is single-threaded
+//!
+//! # Torn Tail Recovery
+//!
+//! A crash mid-append leaves a torn final record. With the default
+//! `TailRecovery::Strict` policy this halts like any other corruption.
+//! With `TailRecovery::TruncateTornTail` a damaged record that extends to
+//! end-of-file is reported as a `TornTail` and may be truncated; damage to
+//! any interior record still halts.
+//!
+//! # Encryption
+//!
+//! An encrypted WAL is opened with `open_with_keys`; plain `open` refuses
+//! it with `AERO_ENCRYPTION_KEY_UNAVAILABLE`. A sealed frame that fails
+//! authentication is corruption, torn if it ends the file.
 
-use std::fs::File;
+use std::fs::{File, OpenOptions};
 use std::io::{self, BufReader, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 
 use super::errors::{WalError, WalResult};
 use super::record::WalRecord;
+use super::writer::encrypted_without_keys;
+use crate::storage::{read_key_header, FileKey, MasterKeyring};
+
+/// Policy for a damaged final WAL record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TailRecovery {
+    /// Any corruption halts, including a torn final record (default)
+    #[default]
+    Strict,
+    /// A torn final record is truncated; interior corruption still halts
+    TruncateTornTail,
+}
+
+impl TailRecovery {
+    /// Parse a configuration name (`strict`, `truncate`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "strict" => Some(TailRecovery::Strict),
+            "truncate" => Some(TailRecovery::TruncateTornTail),
+            _ => None,
+        }
+    }
+
+    /// Configuration name of the policy
+    pub fn name(self) -> &'static str {
+        match self {
+            TailRecovery::Strict => "strict",
+            TailRecovery::TruncateTornTail => "truncate",
+        }
+    }
+}
+
+/// Torn final record detected at the WAL tail
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TornTail {
+    /// Offset of the first byte of the torn record (end of valid WAL)
+    pub offset: u64,
+    /// Number of bytes from `offset` to end of file
+    pub discarded_bytes: u64,
+    /// Why the record was rejected
+    pub reason: String,
+}
 
 /// WAL reader for sequential replay.
 ///
@@ -33,6 +89,14 @@ pub struct WalReader {
     file_size: u64,
     /// Last successfully read sequence number
     last_sequence: u64,
+    /// Policy for a damaged final record
+    tail_recovery: TailRecovery,
+    /// Torn tail detected by the last read (TruncateTornTail only)
+    torn_tail: Option<TornTail>,
+    /// Data key of an encrypted WAL
+    cipher: Option<FileKey>,
+    /// Offset of the first record (past the key header of an encrypted WAL)
+    data_start: u64,
 }
 
 impl WalReader {
@@ -44,9 +108,24 @@ impl WalReader {
     ///
     /// # Errors
     ///
-    /// Returns `WalError` if the file cannot be opened.
+    /// Returns `WalError` if the file cannot be opened, and
+    /// `AERO_ENCRYPTION_KEY_UNAVAILABLE` if it is encrypted.
     pub fn open(wal_path: &Path) -> WalResult<Self> {
-        let file = File::open(wal_path).map_err(|e| {
+        Self::open_in(wal_path, None)
+    }
+
+    /// Opens a WAL file, unwrapping its data key if it is encrypted.
+    ///
+    /// # Errors
+    ///
+    /// `AERO_ENCRYPTION_KEY_UNAVAILABLE` if the WAL is encrypted under a
+    /// master key `keys` does not hold.
+    pub fn open_with_keys(wal_path: &Path, keys: &MasterKeyring) -> WalResult<Self> {
+        Self::open_in(wal_path, Some(keys))
+    }
+
+    fn open_in(wal_path: &Path, keys: Option<&MasterKeyring>) -> WalResult<Self> {
+        let mut file = File::open(wal_path).map_err(|e| {
             if e.kind() == io::ErrorKind::NotFound {
                 WalError::corruption(format!("WAL file not found: {}", wal_path.display()))
             } else {
@@ -64,12 +143,162 @@ impl WalReader {
 
         let file_size = metadata.len();
 
+        let key_header = read_key_header(&mut file)
+            .map_err(|e| WalError::corruption(format!("Failed to read WAL key header: {}", e)))?;
+        let cipher = match key_header {
+            Some(key_header) => Some(
+                keys.ok_or_else(|| encrypted_without_keys(wal_path))?
+                    .open_file_key(&key_header)
+                    .map_err(WalError::from_storage_error)?,
+            ),
+            None => None,
+        };
+        let data_start = cipher
+            .as_ref()
+            .map_or(0, |cipher| cipher.header().len() as u64);
+        file.seek(SeekFrom::Start(data_start))
+            .map_err(|e| WalError::corruption(format!("Failed to seek in WAL: {}", e)))?;
+
         Ok(Self {
             wal_path: wal_path.to_path_buf(),
             reader: BufReader::new(file),
-            current_offset: 0,
+            current_offset: data_start,
             file_size,
             last_sequence: 0,
+            tail_recovery: TailRecovery::default(),
+            torn_tail: None,
+            cipher,
+            data_start,
+        })
+    }
+
+    /// Sets the policy for a damaged final record.
+    pub fn with_tail_recovery(mut self, tail_recovery: TailRecovery) -> Self {
+        self.tail_recovery = tail_recovery;
+        self
+    }
+
+    /// Returns the policy for a damaged final record.
+    pub fn tail_recovery(&self) -> TailRecovery {
+        self.tail_recovery
+    }
+
+    /// Returns the torn tail detected during reading, if any.
+    pub fn torn_tail(&self) -> Option<&TornTail> {
+        self.torn_tail.as_ref()
+    }
+
+    /// Truncates a detected torn tail from the WAL file.
+    ///
+    /// The file is cut at the end of the last valid record and fsynced.
+    /// Returns the truncated tail, or `None` if no torn tail was detected.
+    ///
+    /// # Errors
+    ///
+    /// - `AERO_WAL_APPEND_FAILED` if the file cannot be truncated
+    /// - `AERO_WAL_FSYNC_FAILED` if fsync fails (FATAL)
+    pub fn truncate_torn_tail(&mut self) -> WalResult<Option<TornTail>> {
+        let tail = match &self.torn_tail {
+            Some(tail) => tail.clone(),
+            None => return Ok(None),
+        };
+
+        self.truncate_at(tail.offset)?;
+        Ok(Some(tail))
+    }
+
+    /// Truncates the WAL file at `offset` and fsyncs it.
+    ///
+    /// Used by recovery to discard an unacknowledged tail (a torn record or
+    /// an uncommitted transaction). `offset` must be a record boundary.
+    ///
+    /// # Errors
+    ///
+    /// - `AERO_WAL_APPEND_FAILED` if the file cannot be truncated
+    /// - `AERO_WAL_FSYNC_FAILED` if fsync fails (FATAL)
+    pub fn truncate_at(&mut self, offset: u64) -> WalResult<()> {
+        let file = OpenOptions::new()
+            .write(true)
+            .open(&self.wal_path)
+            .map_err(|e| {
+                WalError::append_failed(
+                    format!(
+                        "Failed to open WAL for tail truncation: {}",
+                        self.wal_path.display()
+                    ),
+                    e,
+                )
+            })?;
+        file.set_len(offset).map_err(|e| {
+            WalError::append_failed(
+                format!("Failed to truncate WAL tail at offset {}", offset),
+                e,
+            )
+        })?;
+        file.sync_all().map_err(|e| {
+            WalError::fsync_failed(
+                format!("fsync failed after WAL tail truncation at {}", offset),
+                e,
+            )
+        })?;
+
+        self.file_size = offset;
+        if self.current_offset > offset {
+            self.current_offset = offset;
+            self.reader
+                .seek(SeekFrom::Start(offset))
+                .map_err(|e| WalError::append_failed("Failed to seek after truncation", e))?;
+        }
+        self.torn_tail = None;
+        Ok(())
+    }
+
+    /// Handles a damaged record at the current offset.
+    ///
+    /// A torn final record ends the WAL under `TruncateTornTail`; anything
+    /// else is corruption.
+    fn damaged_record(&mut self, at_tail: bool, err: WalError) -> WalResult<Option<WalRecord>> {
+        if at_tail && self.tail_recovery == TailRecovery::TruncateTornTail {
+            self.torn_tail = Some(TornTail {
+                offset: self.current_offset,
+                discarded_bytes: self.file_size - self.current_offset,
+                reason: err.message().to_string(),
+            });
+            return Ok(None);
+        }
+        Err(err)
+    }
+
+    /// Whether a whole record with a valid checksum starts anywhere at or
+    /// after `offset`.
+    ///
+    /// A torn final write leaves nothing valid behind it, whereas a
+    /// damaged header in the middle of the WAL is followed by the records
+    /// acknowledged after it. Unreadable input counts as nothing valid.
+    fn valid_record_after(&self, offset: u64) -> bool {
+        let mut rest = Vec::new();
+        let read = File::open(&self.wal_path).and_then(|mut file| {
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_to_end(&mut rest)
+        });
+        if read.is_err() {
+            return false;
+        }
+
+        (0..rest.len()).any(|start| {
+            let Some(len_bytes) = rest.get(start..start + 4) else {
+                return false;
+            };
+            let len = u32::from_le_bytes(len_bytes.try_into().unwrap_or_default()) as usize;
+            let Some(frame) = rest.get(start..start.saturating_add(len)) else {
+                return false;
+            };
+            match &self.cipher {
+                Some(cipher) => cipher
+                    .open_frame(offset + start as u64, frame)
+                    .is_ok_and(|plain| WalRecord::deserialize(&plain).is_ok()),
+                None => WalRecord::deserialize(frame).is_ok(),
+            }
         })
     }
 
@@ -117,8 +346,8 @@ impl WalReader {
     /// - File is truncated mid-record
     /// - Sequence numbers are not strictly increasing
     pub fn read_next(&mut self) -> WalResult<Option<WalRecord>> {
-        // Check if we've reached end of file
-        if self.current_offset >= self.file_size {
+        // Check if we've reached end of file (or a torn tail)
+        if self.current_offset >= self.file_size || self.torn_tail.is_some() {
             return Ok(None);
         }
 
@@ -127,13 +356,14 @@ impl WalReader {
         // Minimum record size check
         const MIN_RECORD_SIZE: u64 = 4 + 1 + 8 + 20 + 4; // len + type + seq + min_payload + checksum
         if remaining < MIN_RECORD_SIZE {
-            return Err(WalError::corruption_at_offset(
+            let err = WalError::corruption_at_offset(
                 self.current_offset,
                 format!(
                     "Truncated WAL: {} bytes remaining, minimum record size is {}",
                     remaining, MIN_RECORD_SIZE
                 ),
-            ));
+            );
+            return self.damaged_record(true, err);
         }
 
         // Read record length first
@@ -155,13 +385,17 @@ impl WalReader {
         }
 
         if record_length > remaining {
-            return Err(WalError::corruption_at_offset(
+            let err = WalError::corruption_at_offset(
                 self.current_offset,
                 format!(
                     "Record length {} exceeds remaining file size {}",
                     record_length, remaining
                 ),
-            ));
+            );
+            // A damaged length field on an interior record also overruns
+            // the file; it is a torn tail only if nothing valid follows
+            let at_tail = !self.valid_record_after(self.current_offset + 1);
+            return self.damaged_record(at_tail, err);
         }
 
         // Read the rest of the record
@@ -175,9 +409,28 @@ impl WalReader {
             )
         })?;
 
-        // Parse and validate record (includes checksum verification)
-        let (record, bytes_consumed) = WalRecord::deserialize(&record_buf)
-            .map_err(|e| WalError::corruption_at_offset(self.current_offset, e.to_string()))?;
+        // An encrypted frame is opened first and consumed whole
+        let (record_buf, frame_len) = match &self.cipher {
+            Some(cipher) => match cipher.open_frame(self.current_offset, &record_buf) {
+                Ok(plain) => (plain, Some(record_buf.len())),
+                Err(e) => {
+                    let err = WalError::corruption_at_offset(self.current_offset, e.message());
+                    return self.damaged_record(record_length == remaining, err);
+                }
+            },
+            None => (record_buf, None),
+        };
+
+        // Parse and validate record (includes checksum verification).
+        // Only a record that ends exactly at EOF can be a torn final write.
+        let (record, bytes_consumed) = match WalRecord::deserialize(&record_buf) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                let err = WalError::corruption_at_offset(self.current_offset, e.to_string());
+                return self.damaged_record(record_length == remaining, err);
+            }
+        };
+        let bytes_consumed = frame_len.unwrap_or(bytes_consumed);
 
         // Validate sequence number ordering
         if self.last_sequence > 0 && record.sequence_number != self.last_sequence + 1 {
@@ -237,16 +490,56 @@ impl WalReader {
     /// Resets the reader to the beginning of the WAL.
     pub fn reset(&mut self) -> WalResult<()> {
         self.reader
-            .seek(SeekFrom::Start(0))
+            .seek(SeekFrom::Start(self.data_start))
             .map_err(|e| WalError::corruption(format!("Failed to seek to start of WAL: {}", e)))?;
-        self.current_offset = 0;
+        self.current_offset = self.data_start;
         self.last_sequence = 0;
+        self.torn_tail = None;
+        Ok(())
+    }
+
+    /// Returns the WAL file size in bytes, as of opening.
+    pub fn file_size(&self) -> u64 {
+        self.file_size
+    }
+
+    /// Positions the reader at `offset`, just after the record with
+    /// sequence number `last_sequence`, to resume an interrupted replay.
+    ///
+    /// The record at `offset`, if any, is read to confirm it continues the
+    /// sequence. Returns `false`, with the reader reset, if it does not.
+    pub fn resume_at(&mut self, offset: u64, last_sequence: u64) -> WalResult<bool> {
+        if offset > self.file_size
+            || offset < self.data_start
+            || (offset == self.data_start) != (last_sequence == 0)
+        {
+            self.reset()?;
+            return Ok(false);
+        }
+
+        self.position_at(offset, last_sequence)?;
+        if self.read_next().is_err() {
+            self.reset()?;
+            return Ok(false);
+        }
+        self.position_at(offset, last_sequence)?;
+        Ok(true)
+    }
+
+    /// Moves the read position without validating it.
+    fn position_at(&mut self, offset: u64, last_sequence: u64) -> WalResult<()> {
+        self.reader
+            .seek(SeekFrom::Start(offset))
+            .map_err(|e| WalError::corruption(format!("Failed to seek in WAL: {}", e)))?;
+        self.current_offset = offset;
+        self.last_sequence = last_sequence;
+        self.torn_tail = None;
         Ok(())
     }
 
     /// Returns whether there are more records to read.
     pub fn has_more(&self) -> bool {
-        self.current_offset < self.file_size
+        self.current_offset < self.file_size && self.torn_tail.is_none()
     }
 }
 
@@ -545,6 +838,40 @@ mod tests {
         assert_eq!(record1.payload.document_id, record2.payload.document_id);
     }
 
+    #[test]
+    fn test_resume_at_validates_position() {
+        let temp_dir = TempDir::new().unwrap();
+
+        {
+            let mut writer = WalWriter::open(temp_dir.path()).unwrap();
+            for doc_id in ["doc1", "doc2", "doc3"] {
+                writer.append_insert(create_test_payload(doc_id)).unwrap();
+            }
+        }
+
+        let wal_path = temp_dir.path().join("wal").join("wal.log");
+        let mut reader = WalReader::open(&wal_path).unwrap();
+        reader.read_next().unwrap().unwrap();
+        let after_first = reader.current_offset();
+
+        // Resumes after the first record
+        reader.reset().unwrap();
+        assert!(reader.resume_at(after_first, 1).unwrap());
+        assert_eq!(reader.current_offset(), after_first);
+        let record = reader.read_next().unwrap().unwrap();
+        assert_eq!(record.payload.document_id, "doc2");
+
+        // Wrong sequence, mid-record offset and past-the-end are rejected
+        assert!(!reader.resume_at(after_first, 4).unwrap());
+        assert_eq!(reader.current_offset(), 0);
+        assert!(!reader.resume_at(after_first + 1, 1).unwrap());
+        assert!(!reader.resume_at(reader.file_size() + 1, 3).unwrap());
+
+        // The end of the WAL is a valid position
+        assert!(reader.resume_at(reader.file_size(), 3).unwrap());
+        assert!(reader.read_next().unwrap().is_none());
+    }
+
     #[test]
     fn test_replay_idempotency() {
         // Replaying the same WAL twice produces the same sequence of records
@@ -579,4 +906,99 @@ mod tests {
             assert_eq!(r1.payload, r2.payload);
         }
     }
+
+    fn write_records(temp_dir: &TempDir, count: usize) -> PathBuf {
+        let mut writer = WalWriter::open(temp_dir.path()).unwrap();
+        for i in 1..=count {
+            writer
+                .append_insert(create_test_payload(&format!("doc{}", i)))
+                .unwrap();
+        }
+        temp_dir.path().join("wal").join("wal.log")
+    }
+
+    #[test]
+    fn test_tail_recovery_names() {
+        assert_eq!(TailRecovery::default(), TailRecovery::Strict);
+        for mode in [TailRecovery::Strict, TailRecovery::TruncateTornTail] {
+            assert_eq!(TailRecovery::from_name(mode.name()), Some(mode));
+        }
+        assert_eq!(TailRecovery::from_name("repair"), None);
+    }
+
+    #[test]
+    fn test_torn_tail_strict_halts() {
+        let temp_dir = TempDir::new().unwrap();
+        let wal_path = write_records(&temp_dir, 2);
+        let len = std::fs::metadata(&wal_path).unwrap().len();
+        OpenOptions::new()
+            .write(true)
+            .open(&wal_path)
+            .unwrap()
+            .set_len(len - 3)
+            .unwrap();
+
+        let mut reader = WalReader::open(&wal_path).unwrap();
+        assert!(reader.read_next().unwrap().is_some());
+        assert!(reader.read_next().is_err());
+        assert!(reader.torn_tail().is_none());
+    }
+
+    #[test]
+    fn test_torn_tail_truncated() {
+        let temp_dir = TempDir::new().unwrap();
+        let wal_path = write_records(&temp_dir, 1);
+        let valid_len = std::fs::metadata(&wal_path).unwrap().len();
+        drop(write_records(&temp_dir, 1));
+        let full_len = std::fs::metadata(&wal_path).unwrap().len();
+        OpenOptions::new()
+            .write(true)
+            .open(&wal_path)
+            .unwrap()
+            .set_len(full_len - 7)
+            .unwrap();
+
+        let mut reader = WalReader::open(&wal_path)
+            .unwrap()
+            .with_tail_recovery(TailRecovery::TruncateTornTail);
+        assert_eq!(reader.read_all().unwrap().len(), 1);
+
+        let tail = reader.truncate_torn_tail().unwrap().unwrap();
+        assert_eq!(tail.offset, valid_len);
+        assert_eq!(tail.discarded_bytes, full_len - 7 - valid_len);
+        assert_eq!(std::fs::metadata(&wal_path).unwrap().len(), valid_len);
+    }
+
+    #[test]
+    fn test_torn_tail_checksum_failure_truncated() {
+        let temp_dir = TempDir::new().unwrap();
+        let wal_path = write_records(&temp_dir, 2);
+        let mut contents = std::fs::read(&wal_path).unwrap();
+        let last = contents.len() - 1;
+        contents[last] ^= 0xFF;
+        std::fs::write(&wal_path, contents).unwrap();
+
+        let mut reader = WalReader::open(&wal_path)
+            .unwrap()
+            .with_tail_recovery(TailRecovery::TruncateTornTail);
+        assert_eq!(reader.read_all().unwrap().len(), 1);
+        assert!(reader.torn_tail().is_some());
+    }
+
+    #[test]
+    fn test_interior_corruption_halts_in_truncate_mode() {
+        let temp_dir = TempDir::new().unwrap();
+        let wal_path = write_records(&temp_dir, 3);
+        let mut contents = std::fs::read(&wal_path).unwrap();
+        let mid = contents.len() / 2;
+        contents[mid] ^= 0xFF;
+        std::fs::write(&wal_path, contents).unwrap();
+
+        let mut reader = WalReader::open(&wal_path)
+            .unwrap()
+            .with_tail_recovery(TailRecovery::TruncateTornTail);
+        assert!(reader.read_all().is_err());
+        assert!(reader.torn_tail().is_none());
+        assert!(reader.truncate_torn_tail().unwrap().is_none());
+    }
 }
@@ -15,6 +15,8 @@
 
 use std::io::{self, Read, Write};
 
+use super::checksum::{decode_header_byte, encode_header_byte, ChecksumAlgorithm};
+
 /// WAL record types as defined in WAL.md §141-172
 /// Extended for MVCC per MVCC_WAL_INTERACTION.md
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -35,6 +37,12 @@ pub enum RecordType {
     /// MVCC garbage collection record
     /// Per MVCC_GC.md: GC events must be WAL-recorded for deterministic replay
     MvccGc = 5,
+    /// Start of a multi-operation transaction
+    TxnBegin = 6,
+    /// Commit of a multi-operation transaction (carries the CommitId)
+    TxnCommit = 7,
+    /// Schema catalog change (create or deprecate a schema version)
+    SchemaDdl = 8,
 }
 
 impl RecordType {
@@ -47,6 +55,9 @@ impl RecordType {
             3 => Some(RecordType::MvccCommit),
             4 => Some(RecordType::MvccVersion),
             5 => Some(RecordType::MvccGc),
+            6 => Some(RecordType::TxnBegin),
+            7 => Some(RecordType::TxnCommit),
+            8 => Some(RecordType::SchemaDdl),
             _ => None,
         }
     }
@@ -59,10 +70,78 @@ impl RecordType {
         )
     }
 
+    /// Returns true if this is a transaction boundary marker
+    pub fn is_txn_marker(self) -> bool {
+        matches!(self, RecordType::TxnBegin | RecordType::TxnCommit)
+    }
+
     /// Convert to u8
     pub fn as_u8(self) -> u8 {
         self as u8
     }
+
+    /// Record type name, as written in WAL.md
+    pub fn name(self) -> &'static str {
+        match self {
+            RecordType::Insert => "INSERT",
+            RecordType::Update => "UPDATE",
+            RecordType::Delete => "DELETE",
+            RecordType::MvccCommit => "MVCC_COMMIT",
+            RecordType::MvccVersion => "MVCC_VERSION",
+            RecordType::MvccGc => "MVCC_GC",
+            RecordType::TxnBegin => "TXN_BEGIN",
+            RecordType::TxnCommit => "TXN_COMMIT",
+            RecordType::SchemaDdl => "SCHEMA_DDL",
+        }
+    }
+}
+
+/// Transaction boundary marker carried by TxnBegin / TxnCommit records
+///
+/// Markers are ordinary WAL records with an empty collection (so they are
+/// never applied to storage and are published to every subscriber). The
+/// marker is encoded in the document body:
+/// - txn_id (u64 LE): sequence number of the TxnBegin record
+/// - op_count (u32 LE): number of operations in the transaction
+/// - commit_id (u64 LE): MVCC CommitId (0 on TxnBegin)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxnMarker {
+    /// Transaction identity (sequence number of the TxnBegin record)
+    pub txn_id: u64,
+    /// Number of operations between TxnBegin and TxnCommit
+    pub op_count: u32,
+    /// CommitId assigned at the commit boundary (0 on TxnBegin)
+    pub commit_id: u64,
+}
+
+impl TxnMarker {
+    /// Encoded marker size in bytes
+    const ENCODED_LEN: usize = 8 + 4 + 8;
+
+    /// Encode the marker as a WAL payload
+    pub fn to_payload(&self) -> WalPayload {
+        let mut body = Vec::with_capacity(Self::ENCODED_LEN);
+        body.extend_from_slice(&self.txn_id.to_le_bytes());
+        body.extend_from_slice(&self.op_count.to_le_bytes());
+        body.extend_from_slice(&self.commit_id.to_le_bytes());
+        WalPayload::new("", "", "", "", body)
+    }
+
+    /// Decode a marker from a WAL payload
+    pub fn from_payload(payload: &WalPayload) -> io::Result<Self> {
+        let body = &payload.document_body;
+        if body.len() != Self::ENCODED_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid transaction marker length: {}", body.len()),
+            ));
+        }
+        Ok(Self {
+            txn_id: u64::from_le_bytes(body[0..8].try_into().expect("8 bytes")),
+            op_count: u32::from_le_bytes(body[8..12].try_into().expect("4 bytes")),
+            commit_id: u64::from_le_bytes(body[12..20].try_into().expect("8 bytes")),
+        })
+    }
 }
 
 /// MVCC Commit payload per MVCC_WAL_INTERACTION.md
@@ -406,6 +485,37 @@ impl WalRecord {
         Self::new(RecordType::Delete, sequence_number, payload)
     }
 
+    /// Create a TXN_BEGIN record; the transaction ID is its sequence number
+    pub fn txn_begin(sequence_number: u64, op_count: u32) -> Self {
+        let marker = TxnMarker {
+            txn_id: sequence_number,
+            op_count,
+            commit_id: 0,
+        };
+        Self::new(RecordType::TxnBegin, sequence_number, marker.to_payload())
+    }
+
+    /// Create a TXN_COMMIT record
+    pub fn txn_commit(sequence_number: u64, txn_id: u64, op_count: u32, commit_id: u64) -> Self {
+        let marker = TxnMarker {
+            txn_id,
+            op_count,
+            commit_id,
+        };
+        Self::new(RecordType::TxnCommit, sequence_number, marker.to_payload())
+    }
+
+    /// Decode the transaction marker of a TXN_BEGIN / TXN_COMMIT record
+    ///
+    /// Returns `None` for non-marker records.
+    pub fn txn_marker(&self) -> Option<io::Result<TxnMarker>> {
+        if self.record_type.is_txn_marker() {
+            Some(TxnMarker::from_payload(&self.payload))
+        } else {
+            None
+        }
+    }
+
     /// Serialize the record body (everything except length prefix and checksum)
     /// This is the data over which the checksum is computed.
     ///
@@ -434,7 +544,16 @@ impl WalRecord {
     /// - Payload (variable)
     /// - Checksum (u32 LE)
     pub fn serialize(&self) -> Vec<u8> {
-        let body = self.serialize_body();
+        self.serialize_with(ChecksumAlgorithm::default())
+    }
+
+    /// Serialize the complete record using the given checksum algorithm.
+    ///
+    /// The algorithm identifier is recorded in the high bits of the record
+    /// type byte.
+    pub fn serialize_with(&self, algorithm: ChecksumAlgorithm) -> Vec<u8> {
+        let mut body = self.serialize_body();
+        body[0] = encode_header_byte(body[0], algorithm);
 
         // Record length = 4 (length field) + body.len() + 4 (checksum field)
         let record_length = (4 + body.len() + 4) as u32;
@@ -443,7 +562,7 @@ impl WalRecord {
         let mut checksum_data = Vec::with_capacity(4 + body.len());
         checksum_data.extend_from_slice(&record_length.to_le_bytes());
         checksum_data.extend_from_slice(&body);
-        let checksum = crate::wal::checksum::compute_checksum(&checksum_data);
+        let checksum = algorithm.compute(&checksum_data);
 
         // Build final record
         let mut record = Vec::with_capacity(record_length as usize);
@@ -499,9 +618,11 @@ impl WalRecord {
             data[checksum_offset + 3],
         ]);
 
-        // Verify checksum over everything except the checksum itself
+        // Verify checksum over everything except the checksum itself, using
+        // the algorithm recorded in the record header
+        let (record_type_byte, algorithm) = decode_header_byte(data[4])?;
         let checksum_data = &data[0..checksum_offset];
-        let computed_checksum = crate::wal::checksum::compute_checksum(checksum_data);
+        let computed_checksum = algorithm.compute(checksum_data);
 
         if computed_checksum != stored_checksum {
             return Err(io::Error::new(
@@ -514,7 +635,6 @@ impl WalRecord {
         }
 
         // Parse record body
-        let record_type_byte = data[4];
         let record_type = RecordType::from_u8(record_type_byte).ok_or_else(|| {
             io::Error::new(
                 io::ErrorKind::InvalidData,
@@ -586,13 +706,19 @@ impl MvccCommitRecord {
 
     /// Serialize the complete record to bytes with checksum
     pub fn serialize(&self) -> Vec<u8> {
-        let body = self.serialize_body();
+        self.serialize_with(ChecksumAlgorithm::default())
+    }
+
+    /// Serialize the complete record using the given checksum algorithm
+    pub fn serialize_with(&self, algorithm: ChecksumAlgorithm) -> Vec<u8> {
+        let mut body = self.serialize_body();
+        body[0] = encode_header_byte(body[0], algorithm);
         let record_length = (4 + body.len() + 4) as u32;
 
         let mut checksum_data = Vec::with_capacity(4 + body.len());
         checksum_data.extend_from_slice(&record_length.to_le_bytes());
         checksum_data.extend_from_slice(&body);
-        let checksum = crate::wal::checksum::compute_checksum(&checksum_data);
+        let checksum = algorithm.compute(&checksum_data);
 
         let mut record = Vec::with_capacity(record_length as usize);
         record.extend_from_slice(&record_length.to_le_bytes());
@@ -630,8 +756,9 @@ impl MvccCommitRecord {
             data[checksum_offset + 3],
         ]);
 
+        let (record_type_byte, algorithm) = decode_header_byte(data[4])?;
         let checksum_data = &data[0..checksum_offset];
-        let computed_checksum = crate::wal::checksum::compute_checksum(checksum_data);
+        let computed_checksum = algorithm.compute(checksum_data);
 
         if computed_checksum != stored_checksum {
             return Err(io::Error::new(
@@ -644,7 +771,6 @@ impl MvccCommitRecord {
         }
 
         // Verify record type
-        let record_type_byte = data[4];
         if record_type_byte != RecordType::MvccCommit.as_u8() {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
@@ -727,13 +853,19 @@ impl MvccVersionRecord {
 
     /// Serialize the complete record to bytes with checksum
     pub fn serialize(&self) -> Vec<u8> {
-        let body = self.serialize_body();
+        self.serialize_with(ChecksumAlgorithm::default())
+    }
+
+    /// Serialize the complete record using the given checksum algorithm
+    pub fn serialize_with(&self, algorithm: ChecksumAlgorithm) -> Vec<u8> {
+        let mut body = self.serialize_body();
+        body[0] = encode_header_byte(body[0], algorithm);
         let record_length = (4 + body.len() + 4) as u32;
 
         let mut checksum_data = Vec::with_capacity(4 + body.len());
         checksum_data.extend_from_slice(&record_length.to_le_bytes());
         checksum_data.extend_from_slice(&body);
-        let checksum = crate::wal::checksum::compute_checksum(&checksum_data);
+        let checksum = algorithm.compute(&checksum_data);
 
         let mut record = Vec::with_capacity(record_length as usize);
         record.extend_from_slice(&record_length.to_le_bytes());
@@ -771,8 +903,9 @@ impl MvccVersionRecord {
             data[checksum_offset + 3],
         ]);
 
+        let (record_type_byte, algorithm) = decode_header_byte(data[4])?;
         let checksum_data = &data[0..checksum_offset];
-        let computed_checksum = crate::wal::checksum::compute_checksum(checksum_data);
+        let computed_checksum = algorithm.compute(checksum_data);
 
         if computed_checksum != stored_checksum {
             return Err(io::Error::new(
@@ -785,7 +918,6 @@ impl MvccVersionRecord {
         }
 
         // Verify record type
-        let record_type_byte = data[4];
         if record_type_byte != RecordType::MvccVersion.as_u8() {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
@@ -835,11 +967,36 @@ mod tests {
 
     #[test]
     fn test_invalid_record_type() {
-        // 5 is now valid (MvccGc), so test 6 and 255
-        assert!(RecordType::from_u8(6).is_none());
+        // 6 and 7 are transaction markers, 8 is schema DDL, so test 9 and 255
+        assert_eq!(RecordType::from_u8(8), Some(RecordType::SchemaDdl));
+        assert!(RecordType::from_u8(9).is_none());
         assert!(RecordType::from_u8(255).is_none());
     }
 
+    #[test]
+    fn test_txn_marker_records_roundtrip() {
+        let begin = WalRecord::txn_begin(10, 3);
+        let commit = WalRecord::txn_commit(14, 10, 3, 7);
+
+        for record in [&begin, &commit] {
+            let (decoded, _) = WalRecord::deserialize(&record.serialize()).unwrap();
+            assert_eq!(&decoded, record);
+            assert!(decoded.record_type.is_txn_marker());
+            assert!(decoded.payload.collection_id.is_empty());
+        }
+
+        let marker = commit.txn_marker().unwrap().unwrap();
+        assert_eq!(marker.txn_id, 10);
+        assert_eq!(marker.op_count, 3);
+        assert_eq!(marker.commit_id, 7);
+        assert_eq!(begin.txn_marker().unwrap().unwrap().commit_id, 0);
+        assert!(
+            WalRecord::insert(1, WalPayload::tombstone("c", "d", "s", "v"))
+                .txn_marker()
+                .is_none()
+        );
+    }
+
     #[test]
     fn test_mvcc_commit_record_type() {
         assert_eq!(RecordType::MvccCommit.as_u8(), 3);
@@ -1060,4 +1217,51 @@ mod tests {
         assert_eq!(RecordType::from_u8(4), Some(RecordType::MvccVersion));
         assert!(RecordType::MvccVersion.is_mvcc_record());
     }
+
+    #[test]
+    fn test_record_roundtrip_with_each_algorithm() {
+        let record = WalRecord::insert(9, sample_payload());
+        for algorithm in [
+            ChecksumAlgorithm::Crc32,
+            ChecksumAlgorithm::Crc32c,
+            ChecksumAlgorithm::XxHash64,
+        ] {
+            let serialized = record.serialize_with(algorithm);
+            let (recovered, consumed) = WalRecord::deserialize(&serialized).unwrap();
+            assert_eq!(recovered, record);
+            assert_eq!(consumed, serialized.len());
+        }
+    }
+
+    #[test]
+    fn test_legacy_crc32_record_still_verifies() {
+        let record = WalRecord::update(3, sample_payload());
+        let serialized = record.serialize();
+
+        // Legacy layout: bare record type byte, CRC32 over len + body
+        assert_eq!(serialized[4], RecordType::Update.as_u8());
+        let checksum_offset = serialized.len() - 4;
+        assert_eq!(
+            serialized[checksum_offset..],
+            crate::wal::checksum::compute_checksum(&serialized[..checksum_offset]).to_le_bytes()
+        );
+        assert_eq!(WalRecord::deserialize(&serialized).unwrap().0, record);
+    }
+
+    #[test]
+    fn test_mvcc_records_with_crc32c() {
+        let commit = MvccCommitRecord::new(1, 42);
+        let serialized = commit.serialize_with(ChecksumAlgorithm::Crc32c);
+        assert_eq!(
+            MvccCommitRecord::deserialize(&serialized).unwrap().0,
+            commit
+        );
+
+        let version = MvccVersionRecord::new(2, 42, "users:1", b"{}".to_vec());
+        let serialized = version.serialize_with(ChecksumAlgorithm::XxHash64);
+        assert_eq!(
+            MvccVersionRecord::deserialize(&serialized).unwrap().0,
+            version
+        );
+    }
 }
@@ -2,16 +2,27 @@
 //!
 //! Per WAL.md §175-198:
 //! - Every WAL append is followed by fsync
-//! - No batching
-//! - No group commit
+//! - No group commit across requests
 //! - No async durability
 //!
+//! A single request may append a batch of records with one fsync
+//! (`append_batch`, `append_transaction`). No record of the batch is
+//! acknowledged before that fsync completes.
+//!
 //! Acknowledgment before fsync is forbidden.
+//!
+//! Opened with an active master key (`open_with_keys`), a new or truncated
+//! WAL is written encrypted under a fresh data key; see `MasterKeyring`.
 
 use std::fs::{self, File, OpenOptions};
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::observability::MetricsRegistry;
+use crate::storage::{read_key_header, FileKey, MasterKeyring};
 
+use super::checksum::ChecksumAlgorithm;
 use super::errors::{WalError, WalResult};
 use super::record::{RecordType, WalPayload, WalRecord};
 
@@ -29,6 +40,32 @@ pub struct WalWriter {
     file: File,
     /// Next sequence number to assign (starts at 1, never reused)
     next_sequence: u64,
+    /// Checksum algorithm for newly appended records
+    checksum_algorithm: ChecksumAlgorithm,
+    /// Highest CommitId recorded by a TXN_COMMIT record (0 if none)
+    last_commit_id: u64,
+    /// Registry counting appends and fsyncs, if any
+    metrics: Option<Arc<MetricsRegistry>>,
+    /// Master keys wrapping the data key of each new WAL file
+    keys: Option<Arc<MasterKeyring>>,
+    /// Data key of an encrypted WAL
+    cipher: Option<FileKey>,
+    /// Bytes the simulated device still accepts before failing with ENOSPC
+    #[cfg(test)]
+    space_left: Option<usize>,
+}
+
+/// Sequence numbers assigned to a committed transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxnAppend {
+    /// Transaction identity (sequence number of the TXN_BEGIN record)
+    pub txn_id: u64,
+    /// Sequence numbers of the operations, in order
+    pub op_sequences: Vec<u64>,
+    /// Sequence number of the TXN_COMMIT record
+    pub commit_sequence: u64,
+    /// CommitId recorded at the commit boundary
+    pub commit_id: u64,
 }
 
 impl WalWriter {
@@ -45,6 +82,24 @@ impl WalWriter {
     ///
     /// Returns `WalError::append_failed` if the file cannot be created or opened.
     pub fn open(data_dir: &Path) -> WalResult<Self> {
+        Self::open_in(data_dir, None)
+    }
+
+    /// Opens or creates the WAL with whole-file encryption.
+    ///
+    /// With a master key active, a new or empty WAL is written encrypted,
+    /// and so is every WAL `truncate` starts. An existing WAL is appended to
+    /// under its own data key, or stays plain until truncated.
+    ///
+    /// # Errors
+    ///
+    /// `AERO_ENCRYPTION_KEY_UNAVAILABLE` if the WAL is encrypted under a
+    /// master key `keys` does not hold.
+    pub fn open_with_keys(data_dir: &Path, keys: Arc<MasterKeyring>) -> WalResult<Self> {
+        Self::open_in(data_dir, Some(keys))
+    }
+
+    fn open_in(data_dir: &Path, keys: Option<Arc<MasterKeyring>>) -> WalResult<Self> {
         let wal_dir = data_dir.join("wal");
         let wal_path = wal_dir.join("wal.log");
 
@@ -59,8 +114,9 @@ impl WalWriter {
         }
 
         // Open file for append with exclusive write access
-        let file = OpenOptions::new()
+        let mut file = OpenOptions::new()
             .create(true)
+            .read(true)
             .append(true)
             .open(&wal_path)
             .map_err(|e| {
@@ -70,48 +126,108 @@ impl WalWriter {
                 )
             })?;
 
-        // Determine next sequence number by reading existing WAL
-        let next_sequence = Self::determine_next_sequence(&wal_path)?;
+        // A new WAL gets its key header before any record; an existing one
+        // is appended to under its own key
+        let len = file
+            .metadata()
+            .map_err(|e| WalError::append_failed("Failed to read WAL metadata", e))?
+            .len();
+        let cipher = if len == 0 {
+            match &keys {
+                Some(keys) if keys.is_enabled() => Some(Self::write_key_header(&mut file, keys)?),
+                _ => None,
+            }
+        } else {
+            let key_header = read_key_header(&mut file).map_err(|e| {
+                WalError::corruption(format!("Failed to read WAL key header: {}", e))
+            })?;
+            match key_header {
+                Some(key_header) => Some(
+                    keys.as_ref()
+                        .ok_or_else(|| encrypted_without_keys(&wal_path))?
+                        .open_file_key(&key_header)
+                        .map_err(WalError::from_storage_error)?,
+                ),
+                None => None,
+            }
+        };
+
+        // Determine next sequence number (and highest CommitId) by reading
+        // existing WAL
+        let (next_sequence, last_commit_id) =
+            Self::determine_next_sequence(&wal_path, keys.as_deref())?;
 
         Ok(Self {
             wal_path,
             file,
             next_sequence,
+            checksum_algorithm: ChecksumAlgorithm::default(),
+            last_commit_id,
+            metrics: None,
+            keys,
+            cipher,
+            #[cfg(test)]
+            space_left: None,
         })
     }
 
+    /// Writes the key header of a new WAL file under a fresh data key.
+    fn write_key_header(file: &mut File, keys: &MasterKeyring) -> WalResult<FileKey> {
+        let key = keys.new_file_key().map_err(WalError::from_storage_error)?;
+        file.write_all(key.header())
+            .map_err(|e| WalError::append_failed("Failed to write WAL key header", e))?;
+        file.sync_all()
+            .map_err(|e| WalError::fsync_failed("fsync failed after WAL key header", e))?;
+        Ok(key)
+    }
+
     /// Determines the next sequence number by scanning existing WAL.
     ///
-    /// Returns 1 if WAL is empty or does not exist.
-    fn determine_next_sequence(wal_path: &Path) -> WalResult<u64> {
+    /// Also returns the highest CommitId recorded by a TXN_COMMIT record.
+    /// Returns `(1, 0)` if WAL is empty or does not exist.
+    fn determine_next_sequence(
+        wal_path: &Path,
+        keys: Option<&MasterKeyring>,
+    ) -> WalResult<(u64, u64)> {
         use super::reader::WalReader;
 
         // If file doesn't exist or is empty, start at 1
         let metadata = match fs::metadata(wal_path) {
             Ok(m) => m,
-            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(1),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok((1, 0)),
             Err(e) => return Err(WalError::append_failed("Failed to read WAL metadata", e)),
         };
 
         if metadata.len() == 0 {
-            return Ok(1);
+            return Ok((1, 0));
         }
 
         // Read through WAL to find highest sequence number
-        let mut reader = WalReader::open(wal_path)?;
+        let mut reader = match keys {
+            Some(keys) => WalReader::open_with_keys(wal_path, keys)?,
+            None => WalReader::open(wal_path)?,
+        };
         let mut max_sequence = 0u64;
+        let mut max_commit_id = 0u64;
 
         loop {
             match reader.read_next() {
                 Ok(Some(record)) => {
                     max_sequence = max_sequence.max(record.sequence_number);
+                    if record.record_type == RecordType::TxnCommit {
+                        let marker = record.txn_marker().expect("TxnCommit is a marker");
+                        let marker = marker.map_err(|e| {
+                            WalError::corruption_at_sequence(record.sequence_number, e.to_string())
+                        })?;
+                        max_commit_id = max_commit_id.max(marker.commit_id);
+                    }
                 }
                 Ok(None) => break,
                 Err(e) => return Err(e),
             }
         }
 
-        Ok(max_sequence + 1)
+        Ok((max_sequence + 1, max_commit_id))
     }
 
     /// Returns the path to the WAL file.
@@ -119,6 +235,75 @@ impl WalWriter {
         &self.wal_path
     }
 
+    /// Returns whether the WAL file is encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    /// Returns the master keys new WAL files are encrypted with, if any.
+    pub fn master_keys(&self) -> Option<&Arc<MasterKeyring>> {
+        self.keys.as_ref()
+    }
+
+    /// Encodes records as appended at the end of the file.
+    ///
+    /// In an encrypted WAL each record is sealed at its file offset.
+    fn encode(&self, records: &[WalRecord]) -> WalResult<Vec<u8>> {
+        let mut buffer = Vec::new();
+        let start = match &self.cipher {
+            Some(_) => self
+                .file
+                .metadata()
+                .map_err(|e| WalError::append_failed("Failed to stat WAL before append", e))?
+                .len(),
+            None => 0,
+        };
+        for record in records {
+            let serialized = record.serialize_with(self.checksum_algorithm);
+            match &self.cipher {
+                Some(cipher) => buffer.extend_from_slice(
+                    &cipher.seal_frame(start + buffer.len() as u64, &serialized),
+                ),
+                None => buffer.extend_from_slice(&serialized),
+            }
+        }
+        Ok(buffer)
+    }
+
+    /// Returns the checksum algorithm used for new records.
+    pub fn checksum_algorithm(&self) -> ChecksumAlgorithm {
+        self.checksum_algorithm
+    }
+
+    /// Sets the checksum algorithm used for new records.
+    ///
+    /// Existing records keep the algorithm recorded in their header, so a
+    /// WAL may contain records protected by different algorithms.
+    pub fn set_checksum_algorithm(&mut self, algorithm: ChecksumAlgorithm) {
+        self.checksum_algorithm = algorithm;
+    }
+
+    /// Count appends, fsyncs and truncations in `metrics`
+    pub fn set_metrics(&mut self, metrics: Arc<MetricsRegistry>) {
+        self.metrics = Some(metrics);
+    }
+
+    /// Record `records` durable records of `bytes` total, and their fsync
+    fn record_durable(&self, records: u64, bytes: u64) {
+        if let Some(metrics) = &self.metrics {
+            metrics.add_wal_records(records);
+            metrics.add_wal_bytes(bytes);
+            metrics.increment_wal_fsyncs();
+        }
+    }
+
+    /// Returns the highest CommitId recorded by a TXN_COMMIT record.
+    ///
+    /// Derived from the WAL at open; 0 if no transaction has committed.
+    pub fn last_commit_id(&self) -> u64 {
+        self.last_commit_id
+    }
+
     /// Returns the next sequence number that will be assigned.
     pub fn next_sequence_number(&self) -> u64 {
         self.next_sequence
@@ -157,10 +342,10 @@ impl WalWriter {
     pub fn append(&mut self, record_type: RecordType, payload: WalPayload) -> WalResult<u64> {
         let sequence_number = self.next_sequence;
         let record = WalRecord::new(record_type, sequence_number, payload);
-        let serialized = record.serialize();
+        let serialized = self.encode(std::slice::from_ref(&record))?;
 
-        // Write to file
-        self.file.write_all(&serialized).map_err(|e| {
+        // Write to file; on failure cut the partial record back off
+        self.write_or_cut_back(&serialized).map_err(|e| {
             WalError::append_failed(
                 format!("Failed to write WAL record at sequence {}", sequence_number),
                 e,
@@ -180,10 +365,167 @@ impl WalWriter {
 
         // Only increment after successful fsync
         self.next_sequence += 1;
+        self.record_durable(1, serialized.len() as u64);
 
         Ok(sequence_number)
     }
 
+    /// Appends a batch of records with a single fsync.
+    ///
+    /// Records are assigned consecutive sequence numbers, written in order,
+    /// and made durable by one fsync. If the write fails the WAL is cut back
+    /// to its length before the batch, so no record of the batch survives.
+    ///
+    /// # Returns
+    ///
+    /// The sequence numbers assigned to the records, in order.
+    ///
+    /// # Errors
+    ///
+    /// - `AERO_WAL_APPEND_FAILED` if write fails
+    /// - `AERO_WAL_FSYNC_FAILED` if fsync fails (FATAL)
+    pub fn append_batch(&mut self, records: Vec<(RecordType, WalPayload)>) -> WalResult<Vec<u64>> {
+        let first_sequence = self.next_sequence;
+        let records: Vec<WalRecord> = records
+            .into_iter()
+            .enumerate()
+            .map(|(i, (record_type, payload))| {
+                WalRecord::new(record_type, first_sequence + i as u64, payload)
+            })
+            .collect();
+        let sequences = records.iter().map(|r| r.sequence_number).collect();
+        self.write_records(&records)?;
+        Ok(sequences)
+    }
+
+    /// Appends a multi-operation transaction with a single fsync.
+    ///
+    /// Writes TXN_BEGIN, every operation, then TXN_COMMIT carrying
+    /// `commit_id`. Recovery applies the operations only if the TXN_COMMIT
+    /// record is present, so a crash before the fsync completes leaves no
+    /// visible trace of the transaction.
+    ///
+    /// # Errors
+    ///
+    /// - `AERO_WAL_APPEND_FAILED` if write fails or `commit_id` does not
+    ///   follow the last recorded CommitId
+    /// - `AERO_WAL_FSYNC_FAILED` if fsync fails (FATAL)
+    pub fn append_transaction(
+        &mut self,
+        ops: Vec<(RecordType, WalPayload)>,
+        commit_id: u64,
+    ) -> WalResult<TxnAppend> {
+        if commit_id <= self.last_commit_id {
+            return Err(WalError::append_failed(
+                format!(
+                    "CommitId {} does not follow last recorded CommitId {}",
+                    commit_id, self.last_commit_id
+                ),
+                io::Error::new(io::ErrorKind::InvalidInput, "non-monotonic CommitId"),
+            ));
+        }
+
+        let op_count = ops.len() as u32;
+        let txn_id = self.next_sequence;
+        let mut records = Vec::with_capacity(ops.len() + 2);
+        records.push(WalRecord::txn_begin(txn_id, op_count));
+        for (i, (record_type, payload)) in ops.into_iter().enumerate() {
+            records.push(WalRecord::new(record_type, txn_id + 1 + i as u64, payload));
+        }
+        let commit_sequence = txn_id + 1 + op_count as u64;
+        records.push(WalRecord::txn_commit(
+            commit_sequence,
+            txn_id,
+            op_count,
+            commit_id,
+        ));
+
+        self.write_records(&records)?;
+        self.last_commit_id = commit_id;
+
+        Ok(TxnAppend {
+            txn_id,
+            op_sequences: (txn_id + 1..commit_sequence).collect(),
+            commit_sequence,
+            commit_id,
+        })
+    }
+
+    /// Writes pre-numbered records followed by a single fsync.
+    fn write_records(&mut self, records: &[WalRecord]) -> WalResult<()> {
+        let (first_sequence, last_sequence) = match (records.first(), records.last()) {
+            (Some(first), Some(last)) => (first.sequence_number, last.sequence_number),
+            _ => return Ok(()),
+        };
+
+        let buffer = self.encode(records)?;
+
+        // Write the whole batch; on failure cut the partial batch back off
+        if let Err(e) = self.write_or_cut_back(&buffer) {
+            return Err(WalError::append_failed(
+                format!(
+                    "Failed to write WAL batch at sequences {}..={}",
+                    first_sequence, last_sequence
+                ),
+                e,
+            ));
+        }
+
+        // One fsync for the whole batch - mandatory and FATAL if it fails
+        self.file.sync_all().map_err(|e| {
+            WalError::fsync_failed(
+                format!(
+                    "fsync failed after WAL batch append at sequences {}..={}",
+                    first_sequence, last_sequence
+                ),
+                e,
+            )
+        })?;
+
+        // Only advance after successful fsync
+        self.next_sequence = last_sequence + 1;
+        self.record_durable(records.len() as u64, buffer.len() as u64);
+
+        Ok(())
+    }
+
+    /// Writes `buffer` at the end of the WAL, or leaves the WAL as it was.
+    ///
+    /// A short write (e.g. the device filling up mid-record) would leave a
+    /// partial record that later appends land behind; recovery would then
+    /// find corruption in the middle of the log. The file is cut back to
+    /// its length before the write so the next append starts cleanly.
+    fn write_or_cut_back(&mut self, buffer: &[u8]) -> io::Result<()> {
+        let start_len = self.file.metadata()?.len();
+        let result = self.write_frames(buffer);
+        if result.is_err() {
+            let _ = self.file.set_len(start_len);
+        }
+        result
+    }
+
+    #[cfg(not(test))]
+    fn write_frames(&mut self, buffer: &[u8]) -> io::Result<()> {
+        self.file.write_all(buffer)
+    }
+
+    /// Writes as much of `buffer` as the simulated device has space for.
+    #[cfg(test)]
+    fn write_frames(&mut self, buffer: &[u8]) -> io::Result<()> {
+        match self.space_left {
+            Some(space) if space < buffer.len() => {
+                self.file.write_all(&buffer[..space])?;
+                self.space_left = Some(0);
+                Err(io::Error::from(io::ErrorKind::StorageFull))
+            }
+            Some(space) => {
+                self.space_left = Some(space - buffer.len());
+                self.file.write_all(buffer)
+            }
+            None => self.file.write_all(buffer),
+        }
+    }
+
     /// Appends an INSERT record.
     pub fn append_insert(&mut self, payload: WalPayload) -> WalResult<u64> {
         self.append(RecordType::Insert, payload)
@@ -206,7 +548,11 @@ impl WalWriter {
     pub fn fsync(&self) -> WalResult<()> {
         self.file
             .sync_all()
-            .map_err(|e| WalError::fsync_failed("Explicit WAL fsync failed", e))
+            .map_err(|e| WalError::fsync_failed("Explicit WAL fsync failed", e))?;
+        if let Some(metrics) = &self.metrics {
+            metrics.increment_wal_fsyncs();
+        }
+        Ok(())
     }
 
     /// Returns the WAL directory path.
@@ -245,8 +591,8 @@ impl WalWriter {
             })?;
         }
 
-        // Create new empty WAL file
-        let new_file = OpenOptions::new()
+        // Create new empty WAL file, under a fresh data key if encrypted
+        let mut new_file = OpenOptions::new()
             .create(true)
             .write(true)
             .truncate(true)
@@ -261,6 +607,11 @@ impl WalWriter {
                 )
             })?;
 
+        let cipher = match &self.keys {
+            Some(keys) if keys.is_enabled() => Some(Self::write_key_header(&mut new_file, keys)?),
+            _ => None,
+        };
+
         // fsync new file
         new_file.sync_all().map_err(|e| {
             WalError::fsync_failed(
@@ -303,12 +654,24 @@ impl WalWriter {
 
         // Update internal state
         self.file = file;
+        self.cipher = cipher;
         self.next_sequence = 1;
+        if let Some(metrics) = &self.metrics {
+            metrics.increment_wal_truncations();
+        }
 
         Ok(())
     }
 }
 
+/// Error for an encrypted WAL opened without master keys
+pub(super) fn encrypted_without_keys(wal_path: &Path) -> WalError {
+    WalError::key_unavailable(format!(
+        "{} is encrypted; open it with the master keyring",
+        wal_path.display()
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -383,6 +746,62 @@ mod tests {
         assert_eq!(seq3, 3);
     }
 
+    #[test]
+    fn test_append_batch_assigns_consecutive_sequences() {
+        let temp_dir = TempDir::new().unwrap();
+
+        {
+            let mut writer = WalWriter::open(temp_dir.path()).unwrap();
+            writer.append_insert(create_test_payload("doc0")).unwrap();
+            let sequences = writer
+                .append_batch(vec![
+                    (RecordType::Insert, create_test_payload("doc1")),
+                    (RecordType::Insert, create_test_payload("doc2")),
+                    (RecordType::Update, create_test_payload("doc1")),
+                ])
+                .unwrap();
+            assert_eq!(sequences, vec![2, 3, 4]);
+            assert_eq!(writer.next_sequence_number(), 5);
+            assert!(writer.append_batch(Vec::new()).unwrap().is_empty());
+        }
+
+        let writer = WalWriter::open(temp_dir.path()).unwrap();
+        assert_eq!(writer.next_sequence_number(), 5);
+    }
+
+    #[test]
+    fn test_append_transaction_brackets_ops() {
+        let temp_dir = TempDir::new().unwrap();
+
+        {
+            let mut writer = WalWriter::open(temp_dir.path()).unwrap();
+            writer.append_insert(create_test_payload("doc0")).unwrap();
+            let txn = writer
+                .append_transaction(
+                    vec![
+                        (RecordType::Insert, create_test_payload("doc1")),
+                        (RecordType::Update, create_test_payload("doc0")),
+                    ],
+                    7,
+                )
+                .unwrap();
+            assert_eq!(txn.txn_id, 2);
+            assert_eq!(txn.op_sequences, vec![3, 4]);
+            assert_eq!(txn.commit_sequence, 5);
+            assert_eq!(writer.last_commit_id(), 7);
+
+            // CommitIds must strictly increase
+            assert!(writer
+                .append_transaction(vec![(RecordType::Insert, create_test_payload("doc2"))], 7)
+                .is_err());
+            assert_eq!(writer.next_sequence_number(), 6);
+        }
+
+        let writer = WalWriter::open(temp_dir.path()).unwrap();
+        assert_eq!(writer.next_sequence_number(), 6);
+        assert_eq!(writer.last_commit_id(), 7);
+    }
+
     #[test]
     fn test_writer_reopens_with_correct_sequence() {
         let temp_dir = TempDir::new().unwrap();
@@ -405,6 +824,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_short_write_on_full_device_leaves_no_partial_record() {
+        use super::super::reader::WalReader;
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut writer = WalWriter::open(temp_dir.path()).unwrap();
+        writer.append_insert(create_test_payload("doc1")).unwrap();
+        let len_before = fs::metadata(writer.path()).unwrap().len();
+
+        // The device fills up partway through the next record
+        writer.space_left = Some(10);
+        let err = writer
+            .append_insert(create_test_payload("doc2"))
+            .unwrap_err();
+        assert!(err.is_storage_full());
+        assert_eq!(fs::metadata(writer.path()).unwrap().len(), len_before);
+
+        // Space is freed; the next append follows the last whole record
+        writer.space_left = None;
+        assert_eq!(
+            writer.append_insert(create_test_payload("doc3")).unwrap(),
+            2
+        );
+
+        let mut reader = WalReader::open(writer.path()).unwrap();
+        let records = reader.read_all().unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(reader.torn_tail().is_none());
+    }
+
     #[test]
     fn test_records_are_durable_after_append() {
         use super::super::reader::WalReader;
@@ -447,6 +896,31 @@ mod tests {
         assert!(writer.fsync().is_ok());
     }
 
+    #[test]
+    fn test_metrics_count_records_and_fsyncs() {
+        let temp_dir = TempDir::new().unwrap();
+        let metrics = Arc::new(MetricsRegistry::new());
+        let mut writer = WalWriter::open(temp_dir.path()).unwrap();
+        writer.set_metrics(Arc::clone(&metrics));
+
+        writer.append_insert(create_test_payload("doc1")).unwrap();
+        writer
+            .append_batch(vec![
+                (RecordType::Insert, create_test_payload("doc2")),
+                (RecordType::Insert, create_test_payload("doc3")),
+            ])
+            .unwrap();
+        writer.fsync().unwrap();
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.wal_records, 3);
+        assert_eq!(snapshot.wal_fsyncs, 3);
+        assert_eq!(
+            snapshot.wal_bytes,
+            fs::metadata(writer.path()).unwrap().len()
+        );
+    }
+
     #[test]
     fn test_wal_dir() {
         let temp_dir = TempDir::new().unwrap();
@@ -555,4 +1029,94 @@ mod tests {
             assert!(reader.read_next().unwrap().is_none());
         }
     }
+
+    #[test]
+    fn test_mixed_checksum_algorithms_replay() {
+        use super::super::reader::WalReader;
+
+        let temp_dir = TempDir::new().unwrap();
+
+        // Legacy CRC32 record, then switch algorithms mid-file
+        {
+            let mut writer = WalWriter::open(temp_dir.path()).unwrap();
+            writer.append_insert(create_test_payload("doc1")).unwrap();
+            writer.set_checksum_algorithm(ChecksumAlgorithm::Crc32c);
+            writer.append_insert(create_test_payload("doc2")).unwrap();
+            writer.set_checksum_algorithm(ChecksumAlgorithm::XxHash64);
+            writer.append_delete(create_test_payload("doc1")).unwrap();
+        }
+
+        let writer = WalWriter::open(temp_dir.path()).unwrap();
+        assert_eq!(writer.next_sequence_number(), 4);
+        assert_eq!(writer.checksum_algorithm(), ChecksumAlgorithm::Crc32);
+
+        let mut reader = WalReader::open(writer.path()).unwrap();
+        let records: Vec<_> = std::iter::from_fn(|| reader.read_next().unwrap()).collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[2].record_type, RecordType::Delete);
+    }
+
+    #[test]
+    fn test_encrypted_wal_round_trips_and_rekeys_on_truncate() {
+        use super::super::reader::WalReader;
+
+        let temp_dir = TempDir::new().unwrap();
+        let keys = Arc::new(MasterKeyring::open(temp_dir.path()).unwrap());
+        keys.rotate("m1").unwrap();
+
+        let mut writer = WalWriter::open_with_keys(temp_dir.path(), Arc::clone(&keys)).unwrap();
+        assert!(writer.is_encrypted());
+        writer
+            .append_insert(create_test_payload("secret_doc"))
+            .unwrap();
+        writer
+            .append_delete(create_test_payload("secret_doc"))
+            .unwrap();
+        let bytes = fs::read(writer.path()).unwrap();
+        assert!(!bytes.windows(10).any(|w| w == b"secret_doc"));
+
+        let mut reader = WalReader::open_with_keys(writer.path(), &keys).unwrap();
+        let records = reader.read_all().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].payload.document_id, "secret_doc");
+
+        // The WAL restarts under a data key wrapped by the rotated key
+        keys.rotate("m2").unwrap();
+        writer.truncate().unwrap();
+        writer.append_insert(create_test_payload("doc2")).unwrap();
+        drop(writer);
+
+        let writer = WalWriter::open_with_keys(temp_dir.path(), Arc::clone(&keys)).unwrap();
+        assert_eq!(writer.next_sequence_number(), 2);
+        let mut reader = WalReader::open_with_keys(writer.path(), &keys).unwrap();
+        let records = reader.read_all().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].payload.document_id, "doc2");
+    }
+
+    #[test]
+    fn test_encrypted_wal_refuses_to_open_without_keys() {
+        use super::super::errors::WalErrorCode;
+        use super::super::reader::WalReader;
+
+        let temp_dir = TempDir::new().unwrap();
+        let keys = Arc::new(MasterKeyring::open(temp_dir.path()).unwrap());
+        keys.rotate("m1").unwrap();
+        let wal_path = {
+            let mut writer = WalWriter::open_with_keys(temp_dir.path(), keys).unwrap();
+            writer.append_insert(create_test_payload("doc1")).unwrap();
+            writer.path().to_path_buf()
+        };
+
+        let err = WalWriter::open(temp_dir.path()).err().unwrap();
+        assert_eq!(err.code(), WalErrorCode::AeroEncryptionKeyUnavailable);
+        let err = WalReader::open(&wal_path).err().unwrap();
+        assert_eq!(err.code(), WalErrorCode::AeroEncryptionKeyUnavailable);
+
+        // A keyring without the wrapping key refuses as well
+        let other = MasterKeyring::new();
+        other.rotate("m9").unwrap();
+        let err = WalReader::open_with_keys(&wal_path, &other).err().unwrap();
+        assert_eq!(err.code(), WalErrorCode::AeroEncryptionKeyUnavailable);
+    }
 }
@@ -51,4 +51,12 @@ fn test_recovery_crash_points_defined() {
         points::RECOVERY_AFTER_INDEX_REBUILD,
         "recovery_after_index_rebuild"
     );
+    assert_eq!(
+        points::RECOVERY_BEFORE_TAIL_TRUNCATE,
+        "recovery_before_tail_truncate"
+    );
+    assert_eq!(
+        points::RECOVERY_AFTER_TAIL_TRUNCATE,
+        "recovery_after_tail_truncate"
+    );
 }
@@ -6,11 +6,13 @@
 //! - Partial replay prevention
 //!
 //! Per WAL.md, any corruption detected during recovery halts immediately
-//! with no partial replay and no repair attempts.
+//! with no partial replay and no repair attempts. The one opt-in exception is
+//! `TailRecovery::TruncateTornTail`, which truncates a torn final record.
+//! A transaction without its TXN_COMMIT is discarded as a whole.
 
 use aerodb::recovery::{RecoveryStorage, WalReplayer};
 use aerodb::storage::StorageReader;
-use aerodb::wal::{WalPayload, WalReader, WalWriter};
+use aerodb::wal::{RecordType, TailRecovery, WalPayload, WalReader, WalWriter};
 use std::fs;
 use tempfile::TempDir;
 
@@ -215,3 +217,287 @@ fn test_recovery_processes_in_order() {
 
     assert_eq!(records.len(), 3, "All records should be in storage");
 }
+
+// =============================================================================
+// Torn Tail Recovery Tests (crash mid-append)
+// =============================================================================
+
+/// Crash mid-append at every byte cut of the final record: the torn tail is
+/// truncated and replay completes with all earlier records.
+#[test]
+fn test_torn_tail_truncated_at_every_cut() {
+    let temp_dir = create_temp_data_dir();
+    let data_dir = temp_dir.path();
+    let wal_path = data_dir.join("wal/wal.log");
+
+    {
+        let mut writer = WalWriter::open(data_dir).unwrap();
+        writer.append_insert(create_test_payload("doc1")).unwrap();
+        writer.append_insert(create_test_payload("doc2")).unwrap();
+    }
+    let valid_len = fs::metadata(&wal_path).unwrap().len() as usize;
+    {
+        let mut writer = WalWriter::open(data_dir).unwrap();
+        writer.append_insert(create_test_payload("doc3")).unwrap();
+    }
+    let full = fs::read(&wal_path).unwrap();
+
+    for cut in valid_len + 1..full.len() {
+        fs::write(&wal_path, &full[..cut]).unwrap();
+
+        let stats = {
+            let mut wal = WalReader::open_from_data_dir(data_dir)
+                .unwrap()
+                .with_tail_recovery(TailRecovery::TruncateTornTail);
+            let mut storage = RecoveryStorage::open(data_dir).unwrap();
+            WalReplayer::replay(&mut wal, &mut storage).unwrap()
+        };
+
+        assert_eq!(stats.records_replayed, 2, "cut at byte {}", cut);
+        let tail = stats.torn_tail.expect("torn tail must be reported");
+        assert_eq!(tail.offset, valid_len as u64);
+        assert_eq!(tail.discarded_bytes, (cut - valid_len) as u64);
+        assert_eq!(fs::metadata(&wal_path).unwrap().len(), valid_len as u64);
+
+        // Crash after truncation (recovery_after_tail_truncate): the rerun
+        // finds a clean WAL and replays the same records
+        let rerun = {
+            let mut wal = WalReader::open_from_data_dir(data_dir)
+                .unwrap()
+                .with_tail_recovery(TailRecovery::TruncateTornTail);
+            let mut storage = RecoveryStorage::open(data_dir).unwrap();
+            WalReplayer::replay(&mut wal, &mut storage).unwrap()
+        };
+        assert_eq!(rerun.records_replayed, 2);
+        assert!(rerun.torn_tail.is_none());
+    }
+}
+
+/// Strict mode (default) halts on a torn tail and leaves the WAL untouched.
+#[test]
+fn test_torn_tail_strict_mode_halts() {
+    let temp_dir = create_temp_data_dir();
+    let data_dir = temp_dir.path();
+    let wal_path = data_dir.join("wal/wal.log");
+
+    {
+        let mut writer = WalWriter::open(data_dir).unwrap();
+        writer.append_insert(create_test_payload("doc1")).unwrap();
+        writer.append_insert(create_test_payload("doc2")).unwrap();
+    }
+    let contents = fs::read(&wal_path).unwrap();
+    fs::write(&wal_path, &contents[..contents.len() - 5]).unwrap();
+
+    let result = {
+        let mut wal = WalReader::open_from_data_dir(data_dir).unwrap();
+        let mut storage = RecoveryStorage::open(data_dir).unwrap();
+        WalReplayer::replay(&mut wal, &mut storage)
+    };
+
+    assert!(result.is_err(), "Strict mode must halt on torn tail");
+    assert_eq!(
+        fs::metadata(&wal_path).unwrap().len(),
+        (contents.len() - 5) as u64
+    );
+}
+
+/// Interior corruption halts even when torn-tail truncation is enabled.
+#[test]
+fn test_interior_corruption_halts_in_truncate_mode() {
+    let temp_dir = create_temp_data_dir();
+    let data_dir = temp_dir.path();
+    let wal_path = data_dir.join("wal/wal.log");
+
+    {
+        let mut writer = WalWriter::open(data_dir).unwrap();
+        for i in 1..=5 {
+            writer
+                .append_insert(create_test_payload(&format!("doc{}", i)))
+                .unwrap();
+        }
+    }
+    let mut contents = fs::read(&wal_path).unwrap();
+    let mid = contents.len() / 2;
+    contents[mid] ^= 0xFF;
+    fs::write(&wal_path, &contents).unwrap();
+
+    let result = {
+        let mut wal = WalReader::open_from_data_dir(data_dir)
+            .unwrap()
+            .with_tail_recovery(TailRecovery::TruncateTornTail);
+        let mut storage = RecoveryStorage::open(data_dir).unwrap();
+        WalReplayer::replay(&mut wal, &mut storage)
+    };
+
+    assert!(result.is_err(), "Interior corruption must halt recovery");
+    assert_eq!(fs::read(&wal_path).unwrap(), contents, "WAL must be intact");
+}
+
+/// A damaged length field on an interior record overruns the file like a
+/// torn tail, but the acknowledged records after it must not be dropped.
+#[test]
+fn test_interior_length_corruption_halts_in_truncate_mode() {
+    let temp_dir = create_temp_data_dir();
+    let data_dir = temp_dir.path();
+    let wal_path = data_dir.join("wal/wal.log");
+
+    {
+        let mut writer = WalWriter::open(data_dir).unwrap();
+        for i in 1..=5 {
+            writer
+                .append_insert(create_test_payload(&format!("doc{}", i)))
+                .unwrap();
+        }
+    }
+    let mut contents = fs::read(&wal_path).unwrap();
+    let first_len = u32::from_le_bytes(contents[0..4].try_into().unwrap()) as usize;
+    contents[first_len + 3] ^= 0x80;
+    fs::write(&wal_path, &contents).unwrap();
+
+    let result = {
+        let mut wal = WalReader::open_from_data_dir(data_dir)
+            .unwrap()
+            .with_tail_recovery(TailRecovery::TruncateTornTail);
+        let mut storage = RecoveryStorage::open(data_dir).unwrap();
+        WalReplayer::replay(&mut wal, &mut storage)
+    };
+
+    assert!(result.is_err(), "Interior corruption must halt recovery");
+    assert_eq!(fs::read(&wal_path).unwrap(), contents, "WAL must be intact");
+}
+
+/// After truncation, appends resume with the next sequence number.
+#[test]
+fn test_writer_resumes_after_tail_truncation() {
+    let temp_dir = create_temp_data_dir();
+    let data_dir = temp_dir.path();
+    let wal_path = data_dir.join("wal/wal.log");
+
+    {
+        let mut writer = WalWriter::open(data_dir).unwrap();
+        writer.append_insert(create_test_payload("doc1")).unwrap();
+        writer.append_insert(create_test_payload("doc2")).unwrap();
+    }
+    let contents = fs::read(&wal_path).unwrap();
+    fs::write(&wal_path, &contents[..contents.len() - 11]).unwrap();
+
+    {
+        let mut wal = WalReader::open_from_data_dir(data_dir)
+            .unwrap()
+            .with_tail_recovery(TailRecovery::TruncateTornTail);
+        let mut storage = RecoveryStorage::open(data_dir).unwrap();
+        WalReplayer::replay(&mut wal, &mut storage).unwrap();
+    }
+
+    let mut writer = WalWriter::open(data_dir).unwrap();
+    assert_eq!(writer.next_sequence_number(), 2);
+    writer.append_insert(create_test_payload("doc2b")).unwrap();
+    drop(writer);
+
+    let records = WalReader::open_from_data_dir(data_dir)
+        .unwrap()
+        .read_all()
+        .unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[1].payload.document_id, "doc2b");
+}
+
+// =============================================================================
+// Transaction Recovery Tests (all-or-nothing)
+// =============================================================================
+
+/// Crash at every byte cut inside a transaction: none of its ops are
+/// replayed and the WAL is truncated back to before TXN_BEGIN.
+#[test]
+fn test_crash_inside_txn_discards_all_ops() {
+    let temp_dir = create_temp_data_dir();
+    let data_dir = temp_dir.path();
+    let wal_path = data_dir.join("wal/wal.log");
+
+    {
+        let mut writer = WalWriter::open(data_dir).unwrap();
+        writer.append_insert(create_test_payload("doc1")).unwrap();
+    }
+    let pre_txn_len = fs::metadata(&wal_path).unwrap().len() as usize;
+    {
+        let mut writer = WalWriter::open(data_dir).unwrap();
+        writer
+            .append_transaction(
+                vec![
+                    (RecordType::Insert, create_test_payload("doc2")),
+                    (RecordType::Update, create_test_payload("doc1")),
+                ],
+                1,
+            )
+            .unwrap();
+    }
+    let full = fs::read(&wal_path).unwrap();
+
+    for cut in pre_txn_len + 1..full.len() {
+        fs::write(&wal_path, &full[..cut]).unwrap();
+
+        let stats = {
+            let mut wal = WalReader::open_from_data_dir(data_dir)
+                .unwrap()
+                .with_tail_recovery(TailRecovery::TruncateTornTail);
+            let mut storage = RecoveryStorage::open(data_dir).unwrap();
+            WalReplayer::replay(&mut wal, &mut storage).unwrap()
+        };
+
+        assert_eq!(stats.records_replayed, 1, "cut at byte {}", cut);
+        assert_eq!(stats.txn_commits, 0);
+        assert_eq!(stats.final_sequence, 1);
+        assert_eq!(
+            fs::metadata(&wal_path).unwrap().len(),
+            pre_txn_len as u64,
+            "cut at byte {}",
+            cut
+        );
+    }
+
+    // Writer resumes after the discarded transaction
+    let mut writer = WalWriter::open(data_dir).unwrap();
+    assert_eq!(writer.next_sequence_number(), 2);
+    assert_eq!(writer.last_commit_id(), 0);
+    writer.append_insert(create_test_payload("doc3")).unwrap();
+}
+
+/// A committed transaction replays fully and reports its CommitId.
+#[test]
+fn test_committed_txn_replays_fully() {
+    let temp_dir = create_temp_data_dir();
+    let data_dir = temp_dir.path();
+
+    {
+        let mut writer = WalWriter::open(data_dir).unwrap();
+        writer.append_insert(create_test_payload("doc1")).unwrap();
+        writer
+            .append_transaction(
+                vec![
+                    (RecordType::Insert, create_test_payload("doc2")),
+                    (RecordType::Insert, create_test_payload("doc3")),
+                ],
+                1,
+            )
+            .unwrap();
+        writer
+            .append_transaction(vec![(RecordType::Update, create_test_payload("doc2"))], 2)
+            .unwrap();
+    }
+
+    let stats = {
+        let mut wal = WalReader::open_from_data_dir(data_dir).unwrap();
+        let mut storage = RecoveryStorage::open(data_dir).unwrap();
+        WalReplayer::replay(&mut wal, &mut storage).unwrap()
+    };
+
+    assert_eq!(stats.inserts, 3);
+    assert_eq!(stats.updates, 1);
+    assert_eq!(stats.txn_commits, 2);
+    assert_eq!(stats.txns_discarded, 0);
+    assert_eq!(stats.highest_commit_id, 2);
+    assert_eq!(stats.final_sequence, 8);
+
+    let mut reader = StorageReader::open_from_data_dir(data_dir).unwrap();
+    assert_eq!(reader.read_all().unwrap().len(), 4);
+}